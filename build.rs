@@ -5,7 +5,10 @@ use poem_grpc_build::Config;
 fn main() -> Result<()> {
     Config::new()
         .file_descriptor_set_path("rustmailer.bin")
-        .compile(&["./protos/rustmailer.proto"], &["./protos"])?;
+        .compile(
+            &["./protos/rustmailer.proto", "./protos/rustmailer_v2.proto"],
+            &["./protos"],
+        )?;
 
     let output = Command::new("git")
         .args(&["rev-parse", "--short", "HEAD"])
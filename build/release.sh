@@ -1 +1 @@
-cargo build --release
\ No newline at end of file
+cargo build --release --features kafka
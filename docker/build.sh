@@ -8,7 +8,7 @@ pnpm run build || { echo "Frontend build failed"; exit 1; }
 # Step 1: Build Rust backend with cargo in project root
 echo "Step 1: Building Rust backend with cargo..."
 cd ../ || { echo "Failed to enter project root directory"; exit 1; }
-cargo build --release || { echo "Rust backend build failed"; exit 1; }
+cargo build --release --features kafka || { echo "Rust backend build failed"; exit 1; }
 
 # Step 2: Back to docker directory
 echo "Step 2: Returning to docker directory..."
@@ -10,7 +10,7 @@ use modules::{
     grpc::server::start_grpc_server,
     license::License,
     logger,
-    rest::start_http_server,
+    rest::{start_http_server, start_safe_mode_http_server},
     settings::cli::SETTINGS,
     tasks::{queue::RustMailerTaskQueue, PeriodicTasks},
     token::root::ensure_root_token,
@@ -18,10 +18,15 @@ use modules::{
 use tracing::{error, info};
 
 use crate::modules::{
-    cache::imap::manager::EnvelopeFlagsManager,
+    cache::{disk::encryption::CacheCipher, imap::manager::EnvelopeFlagsManager},
     common::signal::SignalManager,
-    database::{manager::DatabaseManager, snapshot::task::DatabaseSnapshotTask},
+    database::{
+        integrity::StartupIntegrityCheck, manager::DatabaseManager,
+        snapshot::task::DatabaseSnapshotTask,
+    },
+    fault::FaultInjector,
     metrics::MetricsService,
+    overview::memory::configure_allocator,
     settings::dir::DataDirManager,
 };
 
@@ -40,6 +45,7 @@ static LOGO: &str = r#"
 "#;
 #[tokio::main]
 async fn main() -> RustMailerResult<()> {
+    configure_allocator();
     logger::initialize_logging();
     info!("{}", LOGO);
     info!("Starting rustmailer-server");
@@ -53,6 +59,10 @@ async fn main() -> RustMailerResult<()> {
         return Err(error);
     }
 
+    if StartupIntegrityCheck::is_safe_mode() {
+        return start_safe_mode_http_server().await;
+    }
+
     start_server().await?;
     snapshot_after_shutdown_if_needed().await;
     Ok(())
@@ -63,12 +73,20 @@ async fn initialize() -> RustMailerResult<()> {
     // SETTINGS.validate()?;
     SignalManager::initialize().await?;
     DataDirManager::initialize().await?;
+    StartupIntegrityCheck::initialize().await?;
+    if StartupIntegrityCheck::is_safe_mode() {
+        return Ok(());
+    }
+    CacheCipher::initialize().await?;
     MetricsService::initialize().await?;
     DatabaseManager::initialize().await?;
     ensure_root_token().await?;
     License::initialize().await?;
     EnvelopeFlagsManager::initialize().await?;
     RustMailerTls::initialize().await?;
+    FaultInjector::initialize().await?;
+    #[cfg(feature = "test-harness")]
+    modules::harness::TestHarness::initialize().await?;
     EmailClientExecutors::initialize().await?;
     RustMailerTaskQueue::initialize().await?;
     PeriodicTasks::start_background_tasks();
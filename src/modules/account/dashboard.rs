@@ -0,0 +1,175 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modules::{
+        account::{
+            maintenance::MaintenanceWindow,
+            migration::AccountModel,
+            status::{AccountError, AccountRunningState},
+            sync_policy::folder_syncs,
+        },
+        context::executors::{PoolStats, RUST_MAIL_CONTEXT},
+        error::RustMailerResult,
+        scheduler::model::TaskStatus,
+        smtp::queue::rate::{usage, RateLimitKey, SendRateUsage},
+        tasks::{dead_letter::DeadLetter, queue::RustMailerTaskQueue},
+    },
+    utc_now,
+};
+
+/// Everything the account page shows, gathered in one call.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccountDashboard {
+    /// The account configuration.
+    pub account: AccountModel,
+    /// The sync state, absent until the account is first synced.
+    pub state: Option<AccountRunningState>,
+    /// The folders synced since startup, with the time of their last sync.
+    pub folder_syncs: Vec<FolderSync>,
+    /// The sync errors recorded since the last successful sync.
+    pub error_streak: ErrorStreak,
+    /// The emails of the account waiting in the send queue.
+    pub queue: QueueCounts,
+    /// Usage of the send rate limit of the account, if it has one.
+    pub send_rate: Option<SendRateUsage>,
+    /// The IMAP connection pool of the account, once it is in use.
+    pub imap_pool: Option<PoolStats>,
+    /// The SMTP connection pool of the account, once it is in use.
+    pub smtp_pool: Option<PoolStats>,
+    /// End (Unix epoch milliseconds) of the maintenance window in progress, if any.
+    pub maintenance_until: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct FolderSync {
+    /// The folder name.
+    pub folder: String,
+    /// Time (Unix epoch milliseconds) of the last sync of the folder.
+    pub last_synced_at: i64,
+}
+
+/// Consecutive sync errors, counted from the last successful full or incremental sync.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ErrorStreak {
+    /// Number of errors in the streak. At most the number of errors kept in the account
+    /// state.
+    pub count: usize,
+    /// Time (Unix epoch milliseconds) of the first error of the streak.
+    pub since: Option<i64>,
+    /// The most recent error of the streak.
+    pub last_error: Option<AccountError>,
+}
+
+impl ErrorStreak {
+    pub fn from_state(state: &AccountRunningState) -> Self {
+        let last_success = state
+            .last_full_sync_end
+            .max(state.last_incremental_sync_end)
+            .unwrap_or(i64::MIN);
+        let streak: Vec<&AccountError> = state
+            .errors
+            .iter()
+            .filter(|e| e.at > last_success)
+            .collect();
+        Self {
+            count: streak.len(),
+            since: streak.iter().map(|e| e.at).min(),
+            last_error: streak.iter().max_by_key(|e| e.at).map(|e| (*e).clone()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct QueueCounts {
+    /// Emails waiting to be sent, including retries and sends deferred by a rate limit.
+    pub scheduled: usize,
+    /// Emails being sent.
+    pub running: usize,
+    /// Email tasks that failed and are kept until the task cleanup.
+    pub failed: usize,
+    /// Emails and event hook deliveries of the account in the dead-letter queue.
+    pub dead_letters: usize,
+}
+
+impl AccountDashboard {
+    pub async fn get(account_id: u64) -> RustMailerResult<Self> {
+        let account = AccountModel::get(account_id).await?;
+        let state = AccountRunningState::get(account_id).await?;
+        let error_streak = state
+            .as_ref()
+            .map(ErrorStreak::from_state)
+            .unwrap_or_default();
+        let (imap_pool, smtp_pool) = RUST_MAIL_CONTEXT.pool_stats(account_id);
+        let send_rate = account
+            .send_rate_limit
+            .as_ref()
+            .map(|limit| usage(RateLimitKey::Account(account_id), limit, utc_now!()));
+        Ok(Self {
+            folder_syncs: folder_syncs(account_id)
+                .into_iter()
+                .map(|(folder, last_synced_at)| FolderSync {
+                    folder,
+                    last_synced_at,
+                })
+                .collect(),
+            queue: Self::queue_counts(account_id).await?,
+            maintenance_until: MaintenanceWindow::active_until(account_id).await?,
+            account,
+            state,
+            error_streak,
+            send_rate,
+            imap_pool,
+            smtp_pool,
+        })
+    }
+
+    async fn queue_counts(account_id: u64) -> RustMailerResult<QueueCounts> {
+        let tasks = RustMailerTaskQueue::get()?.list_all_email_tasks().await?;
+        let count = |status: TaskStatus| {
+            tasks
+                .iter()
+                .filter(|t| t.account_id == account_id && t.status == status)
+                .count()
+        };
+        Ok(QueueCounts {
+            scheduled: count(TaskStatus::Scheduled),
+            running: count(TaskStatus::Running),
+            failed: count(TaskStatus::Failed),
+            dead_letters: DeadLetter::count_for_account(account_id).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_errors_since_the_last_successful_sync() {
+        let mut state = AccountRunningState {
+            last_full_sync_end: Some(1_000),
+            last_incremental_sync_end: Some(3_000),
+            errors: [500, 2_000, 4_000, 5_000]
+                .into_iter()
+                .map(|at| AccountError::new(format!("error at {at}"), at))
+                .collect(),
+            ..Default::default()
+        };
+        let streak = ErrorStreak::from_state(&state);
+        assert_eq!(streak.count, 2);
+        assert_eq!(streak.since, Some(4_000));
+        assert_eq!(streak.last_error.unwrap().at, 5_000);
+
+        state.last_incremental_sync_end = Some(6_000);
+        assert_eq!(ErrorStreak::from_state(&state), ErrorStreak::default());
+
+        state.last_full_sync_end = None;
+        state.last_incremental_sync_end = None;
+        assert_eq!(ErrorStreak::from_state(&state).count, 4);
+    }
+}
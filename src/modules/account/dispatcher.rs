@@ -2,10 +2,17 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use crate::modules::account::status::AccountRunningState;
+use crate::modules::account::maintenance::MaintenanceWindow;
+use crate::modules::account::migration::AccountModel;
+use crate::modules::account::status::{AccountError, AccountRunningState};
+use crate::modules::hook::channel::{Event, EVENT_CHANNEL};
+use crate::modules::hook::events::payload::AccountSyncError;
+use crate::modules::hook::events::{EventPayload, EventType, RustMailerEvent};
+use crate::modules::hook::task::EventHookTask;
+use crate::utc_now;
 use std::sync::LazyLock;
 use tokio::sync::mpsc;
-use tracing::error;
+use tracing::{debug, error};
 
 pub static STATUS_DISPATCHER: LazyLock<ErrorDispatcher> = LazyLock::new(ErrorDispatcher::new);
 
@@ -19,8 +26,8 @@ impl ErrorDispatcher {
 
         tokio::spawn(async move {
             while let Some((account_id, error)) = rx.recv().await {
-                match AccountRunningState::append_error_message(account_id, error).await {
-                    Ok(()) => {}
+                match AccountRunningState::append_error_message(account_id, error.clone()).await {
+                    Ok(()) => notify_sync_error(account_id, error).await,
                     Err(error) => {
                         error!(
                             "Failed to append error for account: {}. Error: {:#?}",
@@ -43,3 +50,50 @@ impl ErrorDispatcher {
         }
     }
 }
+
+/// Notifies hooks watching `AccountSyncError`, with the recognized provider failure and its
+/// remediation, if any. Nothing is sent while the account is under maintenance.
+async fn notify_sync_error(account_id: u64, error: String) {
+    if MaintenanceWindow::is_under_maintenance(account_id).await {
+        debug!(
+            "Account {} is under maintenance, not reporting sync error: {}",
+            account_id, error
+        );
+        return;
+    }
+    match EventHookTask::is_watching_account_sync_error(account_id).await {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            error!(
+                "Failed to check event_watched for AccountSyncError of account {}: {:#?}",
+                account_id, e
+            );
+            return;
+        }
+    }
+    let account = match AccountModel::get(account_id).await {
+        Ok(account) => account,
+        Err(e) => {
+            error!("Failed to load account {}: {:#?}", account_id, e);
+            return;
+        }
+    };
+    let error = AccountError::new(error, utc_now!());
+    EVENT_CHANNEL
+        .queue(Event::new(
+            account.id,
+            &account.email,
+            RustMailerEvent::new(
+                EventType::AccountSyncError,
+                EventPayload::AccountSyncError(AccountSyncError {
+                    account_id: account.id,
+                    account_email: account.email.clone(),
+                    error: error.error,
+                    kind: error.kind.map(|k| k.to_string()),
+                    remediation: error.remediation,
+                }),
+            ),
+        ))
+        .await;
+}
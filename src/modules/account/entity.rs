@@ -139,6 +139,43 @@ impl SmtpConfig {
     }
 }
 
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct JmapConfig {
+    /// URL of the JMAP session resource, e.g. `https://api.fastmail.com/jmap/session`.
+    ///
+    /// The API, download and upload URLs of the account are discovered from it.
+    #[oai(validator(max_length = 2048))]
+    pub session_url: String,
+    /// Username for HTTP Basic authentication.
+    ///
+    /// If not set, the password is sent as a Bearer token, which is how API tokens
+    /// such as Fastmail API tokens are used.
+    pub username: Option<String>,
+    /// Authentication configuration.
+    ///
+    /// With `OAuth2`, the access token obtained through the OAuth2 authorization of the
+    /// account is sent as a Bearer token.
+    pub auth: AuthConfig,
+}
+
+impl JmapConfig {
+    pub fn try_encrypt_password(self) -> RustMailerResult<Self> {
+        Ok(Self {
+            session_url: self.session_url,
+            username: self.username,
+            auth: self.auth.encrypt()?,
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        match url::Url::parse(&self.session_url) {
+            Ok(url) if matches!(url.scheme(), "https" | "http") => {}
+            _ => return Err("session_url must be an absolute http(s) URL."),
+        }
+        self.auth.validate()
+    }
+}
+
 #[derive(Enum, Default, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum AuthType {
     /// Standard password authentication (PLAIN/LOGIN)
@@ -214,4 +251,6 @@ pub enum MailerType {
     GmailApi,
     /// Use Graph API
     GraphApi,
+    /// Use JMAP (RFC 8620/8621)
+    Jmap,
 }
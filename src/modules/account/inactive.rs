@@ -0,0 +1,323 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{fmt, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    modules::{
+        account::{
+            migration::AccountModel, payload::AccountUpdateRequest, status::AccountRunningState,
+        },
+        context::RustMailTask,
+        error::RustMailerResult,
+        hook::{
+            channel::{Event, EVENT_CHANNEL},
+            events::{payload::AccountAutoPaused, EventPayload, EventType, RustMailerEvent},
+            task::EventHookTask,
+        },
+        scheduler::periodic::PeriodicTask,
+        settings::cli::SETTINGS,
+        token::AccessToken,
+    },
+    utc_now,
+};
+
+const TASK_INTERVAL: Duration = Duration::from_secs(60 * 60); // every hour
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Fragments of account error logs that indicate the credentials no longer work.
+const AUTH_ERROR_MARKERS: [&str; 6] = [
+    "authenticate error",
+    "ImapAuthenticationFailed",
+    "MissingRefreshToken",
+    "OAuth2ItemDisabled",
+    "invalid_grant",
+    "AUTHENTICATIONFAILED",
+];
+
+/// Why an account was paused automatically.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum InactivityReason {
+    /// Authentication has been failing without a successful sync for too long.
+    AuthFailure,
+    /// No access token scoped to the account has been used for too long.
+    Unused,
+}
+
+impl fmt::Display for InactivityReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InactivityReason::AuthFailure => write!(f, "AuthFailure"),
+            InactivityReason::Unused => write!(f, "Unused"),
+        }
+    }
+}
+
+/// Thresholds, in days, after which an account is paused. `None` disables a rule.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AutoPausePolicy {
+    pub auth_failure_days: Option<u32>,
+    pub unused_days: Option<u32>,
+}
+
+impl AutoPausePolicy {
+    pub fn from_settings() -> Self {
+        Self {
+            auth_failure_days: SETTINGS.rustmailer_auto_pause_auth_failure_days,
+            unused_days: SETTINGS.rustmailer_auto_pause_unused_days,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.auth_failure_days.is_some() || self.unused_days.is_some()
+    }
+
+    /// Decides whether an enabled account should be paused, returning the reason and the
+    /// time (Unix epoch milliseconds) since which the account has been inactive.
+    ///
+    /// The account's `updated_at` resets both clocks, so a resumed account gets a full
+    /// grace period before it can be paused again.
+    pub fn evaluate(
+        &self,
+        account: &AccountModel,
+        state: Option<&AccountRunningState>,
+        last_token_use: Option<i64>,
+        now: i64,
+    ) -> Option<(InactivityReason, i64)> {
+        if !account.enabled {
+            return None;
+        }
+        if let (Some(days), Some(state)) = (self.auth_failure_days, state) {
+            if let Some(since) = auth_failing_since(state) {
+                let since = since.max(account.updated_at);
+                if now - since >= days as i64 * DAY_MS {
+                    return Some((InactivityReason::AuthFailure, since));
+                }
+            }
+        }
+        if let (Some(days), Some(last_use)) = (self.unused_days, last_token_use) {
+            let since = last_use.max(account.updated_at);
+            if now - since >= days as i64 * DAY_MS {
+                return Some((InactivityReason::Unused, since));
+            }
+        }
+        None
+    }
+}
+
+/// Returns `true` if an account error log entry is an authentication failure.
+pub fn is_auth_error(error: &str) -> bool {
+    AUTH_ERROR_MARKERS
+        .iter()
+        .any(|marker| error.contains(marker))
+}
+
+/// If the most recent error of the account is an authentication failure, returns the time
+/// of its last successful sync, i.e. since when the account has been failing.
+///
+/// Accounts that never completed a sync are considered failing since their first sync
+/// started.
+pub fn auth_failing_since(state: &AccountRunningState) -> Option<i64> {
+    let last_error = state.errors.last()?;
+    if !is_auth_error(&last_error.error) {
+        return None;
+    }
+    let last_success = state
+        .last_full_sync_end
+        .into_iter()
+        .chain(state.last_incremental_sync_end)
+        .max()
+        .unwrap_or(state.last_full_sync_start);
+    (last_error.at > last_success).then_some(last_success)
+}
+
+/// Returns the most recent use of any access token scoped to the account, or `None` if no
+/// such token exists. Accounts only reached through root tokens are never considered unused.
+pub fn last_token_use(tokens: &[AccessToken], account_id: u64) -> Option<i64> {
+    tokens
+        .iter()
+        .filter(|token| token.accounts.iter().any(|a| a.id == account_id))
+        .map(|token| token.last_access_at)
+        .max()
+}
+
+/// Pauses every enabled account matching the auto-pause policy and notifies hooks watching
+/// `AccountAutoPaused`. Paused accounts are resumed by enabling them again.
+pub async fn pause_inactive_accounts(policy: AutoPausePolicy) -> RustMailerResult<()> {
+    let tokens = AccessToken::list_all().await?;
+    let now = utc_now!();
+    for account in AccountModel::list_all().await? {
+        let state = AccountRunningState::get(account.id).await?;
+        let Some((reason, since)) = policy.evaluate(
+            &account,
+            state.as_ref(),
+            last_token_use(&tokens, account.id),
+            now,
+        ) else {
+            continue;
+        };
+
+        let request = AccountUpdateRequest {
+            enabled: Some(false),
+            ..Default::default()
+        };
+        if let Err(e) = AccountModel::update(account.id, request, false, None).await {
+            error!(
+                account_id = account.id,
+                error = %e,
+                "Failed to auto-pause inactive account"
+            );
+            continue;
+        }
+        warn!(
+            account_id = account.id,
+            reason = %reason,
+            inactive_since = since,
+            "Account paused automatically"
+        );
+        notify_paused(&account, reason, since).await;
+    }
+    Ok(())
+}
+
+async fn notify_paused(account: &AccountModel, reason: InactivityReason, since: i64) {
+    match EventHookTask::is_watching_account_auto_paused(account.id).await {
+        Ok(true) => {
+            EVENT_CHANNEL
+                .queue(Event::new(
+                    account.id,
+                    &account.email,
+                    RustMailerEvent::new(
+                        EventType::AccountAutoPaused,
+                        EventPayload::AccountAutoPaused(AccountAutoPaused {
+                            account_id: account.id,
+                            account_email: account.email.clone(),
+                            reason: reason.to_string(),
+                            inactive_since: since,
+                        }),
+                    ),
+                ))
+                .await;
+        }
+        Ok(false) => {}
+        Err(e) => {
+            error!(
+                account_id = account.id,
+                error = %e,
+                "Failed to check event_watched for AccountAutoPaused"
+            );
+        }
+    }
+}
+
+/// Periodically pauses accounts that keep failing authentication or are no longer used.
+pub struct InactiveAccountTask;
+
+impl RustMailTask for InactiveAccountTask {
+    fn start() {
+        let policy = AutoPausePolicy::from_settings();
+        if !policy.is_enabled() {
+            return;
+        }
+        info!(
+            auth_failure_days = ?policy.auth_failure_days,
+            unused_days = ?policy.unused_days,
+            "Inactive account auto-pause enabled"
+        );
+        let periodic_task = PeriodicTask::new("inactive-account-pauser");
+
+        let task =
+            move |_ctx: Option<u64>| Box::pin(async move { pause_inactive_accounts(policy).await });
+
+        periodic_task.start(task, None, TASK_INTERVAL, false, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::account::status::AccountError;
+
+    fn state(errors: &[(&str, i64)], last_sync_end: Option<i64>) -> AccountRunningState {
+        AccountRunningState {
+            account_id: 1,
+            last_full_sync_start: 100,
+            last_incremental_sync_end: last_sync_end,
+            errors: errors
+                .iter()
+                .map(|(error, at)| AccountError::new(error.to_string(), *at))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn account(updated_at: i64) -> AccountModel {
+        AccountModel {
+            id: 1,
+            enabled: true,
+            updated_at,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_consecutive_auth_failures() {
+        let auth = "imap client authenticate error: ImapAuthenticationFailed";
+        assert_eq!(
+            auth_failing_since(&state(&[(auth, 5000)], Some(1000))),
+            Some(1000)
+        );
+        // Never synced: failing since the first sync started.
+        assert_eq!(auth_failing_since(&state(&[(auth, 5000)], None)), Some(100));
+        // A successful sync after the failure.
+        assert_eq!(auth_failing_since(&state(&[(auth, 500)], Some(1000))), None);
+        // The most recent error is not an authentication failure.
+        assert_eq!(
+            auth_failing_since(&state(
+                &[(auth, 2000), ("imap client connect error", 3000)],
+                Some(1000)
+            )),
+            None
+        );
+        assert_eq!(auth_failing_since(&state(&[], Some(1000))), None);
+    }
+
+    #[test]
+    fn evaluates_the_policy() {
+        let policy = AutoPausePolicy {
+            auth_failure_days: Some(3),
+            unused_days: Some(30),
+        };
+        let now = 40 * DAY_MS;
+        let failing = state(&[("MissingRefreshToken", now - DAY_MS)], Some(DAY_MS));
+
+        assert_eq!(
+            policy.evaluate(&account(0), Some(&failing), None, now),
+            Some((InactivityReason::AuthFailure, DAY_MS))
+        );
+        // Resuming the account restarts the clock.
+        assert_eq!(
+            policy.evaluate(&account(now - DAY_MS), Some(&failing), None, now),
+            None
+        );
+        assert_eq!(
+            policy.evaluate(&account(0), None, Some(5 * DAY_MS), now),
+            Some((InactivityReason::Unused, 5 * DAY_MS))
+        );
+        assert_eq!(
+            policy.evaluate(&account(0), None, Some(20 * DAY_MS), now),
+            None
+        );
+        // Accounts without scoped tokens are never considered unused.
+        assert_eq!(policy.evaluate(&account(0), None, None, now), None);
+
+        let mut disabled = account(0);
+        disabled.enabled = false;
+        assert_eq!(policy.evaluate(&disabled, Some(&failing), None, now), None);
+        assert!(!AutoPausePolicy::default().is_enabled());
+    }
+}
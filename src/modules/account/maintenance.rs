@@ -0,0 +1,236 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::modules::account::migration::AccountModel;
+use crate::modules::common::auth::ClientContext;
+use crate::modules::database::batch::WriteBatch;
+use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::{delete_impl, insert_impl, list_all_impl, secondary_find_impl};
+use crate::modules::error::code::ErrorCode;
+use crate::modules::error::RustMailerResult;
+use crate::modules::overview::memory::check_metadata_capacity;
+use crate::{id, raise_error, utc_now};
+
+/// A planned maintenance period of an account's mail provider or of the network, during
+/// which failures are expected.
+///
+/// While a window is active, sync failures of the accounts it covers are still recorded in
+/// the account state but do not trigger `AccountSyncError` or `AccountAuthenticationFailed`
+/// events, and queued emails of these accounts are deferred until the window ends.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 23, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct MaintenanceWindow {
+    /// Unique identifier of the window.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// The account the window applies to. Global windows, applying to every account, have
+    /// no account.
+    pub account_id: Option<u64>,
+    /// Why the window was planned, e.g. "Provider datacenter migration".
+    pub reason: Option<String>,
+    /// Start of the window (Unix epoch milliseconds).
+    pub starts_at: i64,
+    /// End of the window (Unix epoch milliseconds).
+    pub ends_at: i64,
+    /// Timestamp of when the window was created (in Unix epoch milliseconds).
+    pub created_at: i64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct MaintenanceWindowCreateRequest {
+    /// The account the window applies to. Omit to create a global window, which requires
+    /// root permission.
+    pub account_id: Option<u64>,
+    /// Why the window is planned.
+    pub reason: Option<String>,
+    /// Start of the window (Unix epoch milliseconds). Defaults to now.
+    pub starts_at: Option<i64>,
+    /// End of the window (Unix epoch milliseconds). Must be in the future.
+    pub ends_at: i64,
+}
+
+impl MaintenanceWindow {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    pub async fn new(request: MaintenanceWindowCreateRequest) -> RustMailerResult<Self> {
+        if let Some(account_id) = request.account_id {
+            AccountModel::get(account_id).await?;
+        }
+        let now = utc_now!();
+        let window = Self {
+            id: id!(64),
+            account_id: request.account_id,
+            reason: request.reason.filter(|r| !r.trim().is_empty()),
+            starts_at: request.starts_at.unwrap_or(now),
+            ends_at: request.ends_at,
+            created_at: now,
+        };
+        window.validate(now)?;
+        Ok(window)
+    }
+
+    fn validate(&self, now: i64) -> RustMailerResult<()> {
+        if self.ends_at <= self.starts_at {
+            return Err(raise_error!(
+                "'ends_at' of a maintenance window must be after 'starts_at'".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if self.ends_at <= now {
+            return Err(raise_error!(
+                "'ends_at' of a maintenance window must be in the future".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        Ok(())
+    }
+
+    /// Global windows require root permission, account windows access to the account.
+    pub fn check_access(context: &ClientContext, account_id: Option<u64>) -> RustMailerResult<()> {
+        match account_id {
+            Some(account_id) => context.require_account_access(account_id),
+            None => context.require_root(),
+        }
+    }
+
+    /// Whether the window applies to `account_id`.
+    pub fn covers(&self, account_id: u64) -> bool {
+        self.account_id.is_none_or(|id| id == account_id)
+    }
+
+    /// Whether the window is in progress at `now`.
+    pub fn is_active(&self, now: i64) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+
+    pub async fn save(self) -> RustMailerResult<()> {
+        check_metadata_capacity()?;
+        insert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    pub async fn get(id: u64) -> RustMailerResult<MaintenanceWindow> {
+        secondary_find_impl(DB_MANAGER.meta_db(), MaintenanceWindowKey::id, id)
+            .await?
+            .ok_or_else(|| {
+                raise_error!(
+                    format!("Maintenance window id='{id}' not found."),
+                    ErrorCode::ResourceNotFound
+                )
+            })
+    }
+
+    /// Lists the windows applying to `account_id`, including global ones, or every window
+    /// if no account is given. Windows that ended are included.
+    pub async fn list(account_id: Option<u64>) -> RustMailerResult<Vec<MaintenanceWindow>> {
+        let windows: Vec<MaintenanceWindow> = list_all_impl(DB_MANAGER.meta_db()).await?;
+        Ok(windows
+            .into_iter()
+            .filter(|w| account_id.is_none_or(|id| w.covers(id)))
+            .collect())
+    }
+
+    /// The end of the maintenance of `account_id` in progress, if any. When active windows
+    /// overlap, the latest end is returned.
+    pub async fn active_until(account_id: u64) -> RustMailerResult<Option<i64>> {
+        let now = utc_now!();
+        Ok(Self::list(Some(account_id))
+            .await?
+            .iter()
+            .filter(|w| w.is_active(now))
+            .map(|w| w.ends_at)
+            .max())
+    }
+
+    /// Whether `account_id` is under maintenance. Errors are logged and treated as no
+    /// maintenance, so that failures keep being reported.
+    pub async fn is_under_maintenance(account_id: u64) -> bool {
+        match Self::active_until(account_id).await {
+            Ok(until) => until.is_some(),
+            Err(e) => {
+                error!(
+                    "Failed to check the maintenance windows of account {}: {:#?}",
+                    account_id, e
+                );
+                false
+            }
+        }
+    }
+
+    pub async fn remove(id: u64) -> RustMailerResult<()> {
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<MaintenanceWindow>(MaintenanceWindowKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!(
+                            "The maintenance window with id={id} that you want to delete was not found."
+                        ),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await
+    }
+
+    /// Adds the removal of all maintenance windows of an account to `batch`.
+    pub fn stage_remove_account_windows(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let windows: Vec<MaintenanceWindow> = rw
+                .scan()
+                .primary::<MaintenanceWindow>()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .all()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .filter_ok(|w| w.account_id == Some(account_id))
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(windows)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_to_accounts_while_active() {
+        let window = MaintenanceWindow {
+            account_id: Some(7),
+            starts_at: 1_000,
+            ends_at: 2_000,
+            ..Default::default()
+        };
+        assert!(window.covers(7));
+        assert!(!window.covers(8));
+        assert!(MaintenanceWindow {
+            account_id: None,
+            ..window.clone()
+        }
+        .covers(8));
+        assert!(!window.is_active(999));
+        assert!(window.is_active(1_000));
+        assert!(!window.is_active(2_000));
+
+        assert!(window.validate(1_500).is_ok());
+        assert!(window.validate(2_000).is_err());
+        assert!(MaintenanceWindow {
+            ends_at: 1_000,
+            ..window
+        }
+        .validate(0)
+        .is_err());
+    }
+}
@@ -15,14 +15,15 @@ use crate::{
     encrypt,
     modules::{
         account::{
-            entity::{Account, ImapConfig, MailerType, SmtpConfig},
+            entity::{Account, ImapConfig, JmapConfig, MailerType, SmtpConfig},
             since::DateSince,
             status::AccountRunningState,
+            sync_policy::{forget_folder_syncs, record_folder_sync, SyncPolicy},
         },
         cache::{
             imap::{
                 address::AddressEntity, mailbox::MailBox, manager::FLAGS_STATE_MAP,
-                migration::EmailEnvelopeV3, minimal::MinimalEnvelope, thread::EmailThread,
+                migration::EmailEnvelopeV7, minimal::MinimalEnvelope, thread::EmailThread,
             },
             vendor::{
                 gmail::sync::{
@@ -30,6 +31,10 @@ use crate::{
                     envelope::GmailEnvelope,
                     labels::{GmailCheckPoint, GmailLabels},
                 },
+                jmap::sync::{
+                    client::JmapClient, envelope::JmapEnvelope, mailboxes::JmapMailbox,
+                    state::JmapSyncState,
+                },
                 outlook::sync::{
                     delta::FolderDeltaLink, envelope::OutlookEnvelope, folders::OutlookFolder,
                 },
@@ -45,25 +50,44 @@ use crate::id;
 use crate::modules::account::payload::AccountCreateRequest;
 use crate::modules::account::payload::AccountUpdateRequest;
 use crate::modules::account::payload::MinimalAccount;
+use crate::modules::account::tags::normalize_tags;
 use crate::modules::cache::imap::task::SYNC_TASKS;
 use crate::modules::context::controller::SYNC_CONTROLLER;
 use crate::modules::context::executors::RUST_MAIL_CONTEXT;
 use crate::modules::database::count_by_unique_secondary_key_impl;
-use crate::modules::database::delete_impl;
+use crate::modules::database::batch::WriteBatch;
 use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::metrics::remove_account_metrics;
+use crate::modules::account::maintenance::MaintenanceWindow;
 use crate::modules::database::{
     paginate_query_primary_scan_all_impl, secondary_find_impl, update_impl,
+    versioned_update_impl, Versioned,
 };
 use crate::modules::error::code::ErrorCode;
+use crate::modules::hook::delivery::HookDelivery;
 use crate::modules::hook::entity::EventHooks;
 use crate::modules::license::License;
+use crate::modules::message::attachment_policy::AttachmentPolicy;
+use crate::modules::message::search::saved::SavedSearch;
 use crate::modules::oauth2::token::OAuth2AccessToken;
+use crate::modules::overview::memory::check_metadata_capacity;
 use crate::modules::rest::response::DataPage;
+use crate::modules::retention::entity::CleanupRule;
+use crate::modules::scheduler::recurring::entity::RecurringSend;
+use crate::modules::smtp::campaign::entity::Campaign;
+use crate::modules::smtp::loop_guard::LoopProtection;
+use crate::modules::smtp::queue::rate::SendRateLimit;
+use crate::modules::smtp::sent::SentCopyPolicy;
+use crate::modules::smtp::suppression::SuppressedAddress;
+use crate::modules::smtp::timeline::DeliveryEvent;
 use crate::modules::smtp::template::entity::EmailTemplate;
-use crate::modules::token::AccessToken;
+use crate::modules::smtp::template::partial::TemplatePartial;
+use crate::modules::tasks::dead_letter::DeadLetter;
+use crate::modules::token::{AccessToken, AccountInfo};
+use crate::modules::workspace::Workspace;
 use crate::raise_error;
 
-pub type AccountModel = AccountV3;
+pub type AccountModel = AccountV12;
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
 #[native_model(id = 5, version = 2, from = Account)]
@@ -213,142 +237,1131 @@ impl AccountV3 {
     fn pk(&self) -> String {
         format!("{}_{}", self.created_at, self.id)
     }
+}
 
-    pub fn minimal_sync(&self) -> bool {
-        self.minimal_sync.unwrap_or(false)
-    }
-
-    pub fn create(request: AccountCreateRequest) -> RustMailerResult<Self> {
-        Ok(Self {
-            id: id!(64),
-            email: request.email,
-            name: request.name,
-            imap: request
-                .imap
-                .map(|imap| imap.try_encrypt_password())
-                .transpose()?,
-            smtp: request
-                .smtp
-                .map(|smtp| smtp.try_encrypt_password())
-                .transpose()?,
-            enabled: request.enabled,
-            mailer_type: request.mailer_type,
-            minimal_sync: request.minimal_sync,
-            capabilities: None,
-            date_since: request.date_since,
-            dsn_capable: None,
-            sync_folders: vec![],
-            known_folders: BTreeSet::new(),
-            full_sync_interval_min: request.full_sync_interval_min,
-            incremental_sync_interval_sec: request.incremental_sync_interval_sec,
-            created_at: utc_now!(),
-            updated_at: utc_now!(),
-            use_proxy: request.use_proxy,
-            folder_limit: request.folder_limit,
-        })
-    }
-
-    pub async fn check_account_active(
-        account_id: u64,
-        imap_only: bool,
-    ) -> RustMailerResult<AccountModel> {
-        let account =
-            secondary_find_impl::<AccountModel>(DB_MANAGER.meta_db(), AccountV3Key::id, account_id)
-                .await?
-                .ok_or_else(|| {
-                    raise_error!(
-                        format!("Account id='{account_id}' not found"),
-                        ErrorCode::ResourceNotFound
-                    )
-                })?;
-
-        if !account.enabled {
-            return Err(raise_error!(
-                format!("Account id='{account_id}' is disabled"),
-                ErrorCode::AccountDisabled
-            ));
-        }
-
-        if imap_only && !matches!(account.mailer_type, MailerType::ImapSmtp) {
-            return Err(raise_error!(
-                format!(
-                    "Operation not allowed: account id='{account_id}' is of type '{:?}', but this action requires an IMAP/SMTP account",
-                    account.mailer_type
-                ),
-                ErrorCode::Incompatible
-            ));
-        }
-
-        Ok(account)
-    }
-
-    /// Fetches an `AccountEntity` by its `id`.
-    pub async fn get(account_id: u64) -> RustMailerResult<AccountModel> {
-        let result: AccountModel = Self::find(account_id).await?.ok_or_else(|| {
-            raise_error!(
-                format!("Account with ID '{account_id}' not found"),
-                ErrorCode::ResourceNotFound
-            )
-        })?;
-        Ok(result)
-    }
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 5, version = 4, from = AccountV3)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountV4 {
+    /// Unique account identifier
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// Represents the account activation status.
+    ///
+    /// If this value is `false`, all account-related resources will be unavailable
+    /// and any attempts to access them should return an error indicating the account
+    /// is inactive.
+    pub enabled: bool,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// Email address associated with this account
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Minimal sync mode flag
+    ///
+    /// When enabled (`true`), only the most essential metadata will be synchronized:
+    /// Recommended for:
+    /// - Extremely resource-constrained environments
+    /// - Accounts where only new message notification is needed
+    pub minimal_sync: Option<bool>,
+    /// IMAP Server-supported capability flags
+    pub capabilities: Option<Vec<String>>,
+    /// DSN (Delivery Status Notification) support flag
+    pub dsn_capable: Option<bool>,
+    /// Controls initial synchronization time range
+    ///
+    /// When dealing with large mailboxes, this restricts scanning to:
+    /// - Messages after specified starting point
+    /// - Or within sliding window
+    ///
+    /// ### Use Cases
+    /// - Event-driven systems (only sync recent actionable emails)
+    /// - First-time sync optimization for large accounts
+    /// - Reducing server load during resyncs
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync for this folder.  
+    /// If not set, sync all emails.  
+    /// otherwise sync up to `n` most recent emails (min 10).
+    pub folder_limit: Option<u32>,
+    /// Configuration for selective folder synchronization
+    ///
+    /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
+    /// Modified folders will be automatically synced on next update.
+    pub sync_folders: Vec<String>,
+    /// Full sync interval (minutes), default 30m
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s
+    pub incremental_sync_interval_sec: i64,
+    /// Tracks known mail folders and detects changes (creations/deletions)
+    pub known_folders: BTreeSet<String>,
+    /// Creation timestamp (UNIX epoch milliseconds)
+    pub created_at: i64,
+    /// Last update timestamp (UNIX epoch milliseconds)
+    pub updated_at: i64,
+    /// Optional proxy ID for establishing the connection to external APIs (e.g., Gmail, Outlook).
+    /// - If `None` or not provided, the client will connect directly to the API server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+}
 
-    pub async fn find(account_id: u64) -> RustMailerResult<Option<AccountModel>> {
-        secondary_find_impl::<AccountModel>(DB_MANAGER.meta_db(), AccountV3Key::id, account_id)
-            .await
+impl AccountV4 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
     }
+}
 
-    /// Saves the current `AccountEntity` by persisting it to storage.
-    pub async fn save(self) -> RustMailerResult<()> {
-        insert_impl(DB_MANAGER.meta_db(), self).await
-    }
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 5, version = 5, from = AccountV4)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountV5 {
+    /// Unique account identifier
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// Represents the account activation status.
+    ///
+    /// If this value is `false`, all account-related resources will be unavailable
+    /// and any attempts to access them should return an error indicating the account
+    /// is inactive.
+    pub enabled: bool,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// Email address associated with this account
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Minimal sync mode flag
+    ///
+    /// When enabled (`true`), only the most essential metadata will be synchronized:
+    /// Recommended for:
+    /// - Extremely resource-constrained environments
+    /// - Accounts where only new message notification is needed
+    pub minimal_sync: Option<bool>,
+    /// IMAP Server-supported capability flags
+    pub capabilities: Option<Vec<String>>,
+    /// DSN (Delivery Status Notification) support flag
+    pub dsn_capable: Option<bool>,
+    /// Controls initial synchronization time range
+    ///
+    /// When dealing with large mailboxes, this restricts scanning to:
+    /// - Messages after specified starting point
+    /// - Or within sliding window
+    ///
+    /// ### Use Cases
+    /// - Event-driven systems (only sync recent actionable emails)
+    /// - First-time sync optimization for large accounts
+    /// - Reducing server load during resyncs
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync for this folder.  
+    /// If not set, sync all emails.  
+    /// otherwise sync up to `n` most recent emails (min 10).
+    pub folder_limit: Option<u32>,
+    /// Configuration for selective folder synchronization
+    ///
+    /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
+    /// Modified folders will be automatically synced on next update.
+    pub sync_folders: Vec<String>,
+    /// Full sync interval (minutes), default 30m
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s
+    pub incremental_sync_interval_sec: i64,
+    /// Tracks known mail folders and detects changes (creations/deletions)
+    pub known_folders: BTreeSet<String>,
+    /// Creation timestamp (UNIX epoch milliseconds)
+    pub created_at: i64,
+    /// Last update timestamp (UNIX epoch milliseconds)
+    pub updated_at: i64,
+    /// Optional proxy ID for establishing the connection to external APIs (e.g., Gmail, Outlook).
+    /// - If `None` or not provided, the client will connect directly to the API server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    pub message_id_domain: Option<String>,
+}
 
-    pub async fn create_account(request: AccountCreateRequest) -> RustMailerResult<AccountModel> {
-        // Validate license limits before creating entity
-        if let Some(license) = License::get_current_license().await? {
-            let current_count = AccountV3::count().await?;
-            if let Some(max_accounts) = license.max_accounts {
-                if current_count >= max_accounts as usize {
-                    return Err(raise_error!(
-                        "Maximum account limit reached".into(),
-                        ErrorCode::LicenseAccountLimitReached
-                    ));
-                }
-            } 
-        }
-        let entity = request.create_entity()?;
-        entity.clone().save().await?;
-        SYNC_CONTROLLER
-            .trigger_start(entity.id, entity.email.clone())
-            .await;
-        Ok(entity)
+impl AccountV5 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
     }
+}
 
-    pub async fn update(
-        account_id: u64,
-        request: AccountUpdateRequest,
-        validate: bool,
-    ) -> RustMailerResult<()> {
-        if validate {
-            request.validate_update_request()?;
-        }
-
-        let account = AccountModel::get(account_id).await?;
-        let mut map = None;
-        if let Some(_) = &request.sync_folders {
-            if matches!(account.mailer_type, MailerType::GmailApi) {
-                map = Some(
-                    GmailClient::reverse_label_map(account_id, account.use_proxy, true).await?,
-                );
-            }
-        }
-        update_impl(
-            DB_MANAGER.meta_db(),
-            move |_| Ok(account),
-            move |current| Self::apply_update_fields(current, request, map),
-        )
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 5, version = 6, from = AccountV5)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountV6 {
+    /// Unique account identifier
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// Represents the account activation status.
+    ///
+    /// If this value is `false`, all account-related resources will be unavailable
+    /// and any attempts to access them should return an error indicating the account
+    /// is inactive.
+    pub enabled: bool,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// Email address associated with this account
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Minimal sync mode flag
+    ///
+    /// When enabled (`true`), only the most essential metadata will be synchronized:
+    /// Recommended for:
+    /// - Extremely resource-constrained environments
+    /// - Accounts where only new message notification is needed
+    pub minimal_sync: Option<bool>,
+    /// IMAP Server-supported capability flags
+    pub capabilities: Option<Vec<String>>,
+    /// DSN (Delivery Status Notification) support flag
+    pub dsn_capable: Option<bool>,
+    /// Controls initial synchronization time range
+    ///
+    /// When dealing with large mailboxes, this restricts scanning to:
+    /// - Messages after specified starting point
+    /// - Or within sliding window
+    ///
+    /// ### Use Cases
+    /// - Event-driven systems (only sync recent actionable emails)
+    /// - First-time sync optimization for large accounts
+    /// - Reducing server load during resyncs
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync for this folder.  
+    /// If not set, sync all emails.  
+    /// otherwise sync up to `n` most recent emails (min 10).
+    pub folder_limit: Option<u32>,
+    /// Configuration for selective folder synchronization
+    ///
+    /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
+    /// Modified folders will be automatically synced on next update.
+    pub sync_folders: Vec<String>,
+    /// Full sync interval (minutes), default 30m
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s
+    pub incremental_sync_interval_sec: i64,
+    /// Tracks known mail folders and detects changes (creations/deletions)
+    pub known_folders: BTreeSet<String>,
+    /// Creation timestamp (UNIX epoch milliseconds)
+    pub created_at: i64,
+    /// Last update timestamp (UNIX epoch milliseconds)
+    pub updated_at: i64,
+    /// Optional proxy ID for establishing the connection to external APIs (e.g., Gmail, Outlook).
+    /// - If `None` or not provided, the client will connect directly to the API server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    pub message_id_domain: Option<String>,
+    /// Free-form labels used to group and select accounts, such as `prod`,
+    /// `customer:acme` or `region:eu`.
+    pub tags: Vec<String>,
+}
+
+impl AccountV6 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 5, version = 7, from = AccountV6)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountV7 {
+    /// Unique account identifier
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// Represents the account activation status.
+    ///
+    /// If this value is `false`, all account-related resources will be unavailable
+    /// and any attempts to access them should return an error indicating the account
+    /// is inactive.
+    pub enabled: bool,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// Email address associated with this account
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Minimal sync mode flag
+    ///
+    /// When enabled (`true`), only the most essential metadata will be synchronized:
+    /// Recommended for:
+    /// - Extremely resource-constrained environments
+    /// - Accounts where only new message notification is needed
+    pub minimal_sync: Option<bool>,
+    /// IMAP Server-supported capability flags
+    pub capabilities: Option<Vec<String>>,
+    /// DSN (Delivery Status Notification) support flag
+    pub dsn_capable: Option<bool>,
+    /// Controls initial synchronization time range
+    ///
+    /// When dealing with large mailboxes, this restricts scanning to:
+    /// - Messages after specified starting point
+    /// - Or within sliding window
+    ///
+    /// ### Use Cases
+    /// - Event-driven systems (only sync recent actionable emails)
+    /// - First-time sync optimization for large accounts
+    /// - Reducing server load during resyncs
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync for this folder.  
+    /// If not set, sync all emails.  
+    /// otherwise sync up to `n` most recent emails (min 10).
+    pub folder_limit: Option<u32>,
+    /// Configuration for selective folder synchronization
+    ///
+    /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
+    /// Modified folders will be automatically synced on next update.
+    pub sync_folders: Vec<String>,
+    /// Full sync interval (minutes), default 30m
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s
+    pub incremental_sync_interval_sec: i64,
+    /// Tracks known mail folders and detects changes (creations/deletions)
+    pub known_folders: BTreeSet<String>,
+    /// Creation timestamp (UNIX epoch milliseconds)
+    pub created_at: i64,
+    /// Last update timestamp (UNIX epoch milliseconds)
+    pub updated_at: i64,
+    /// Optional proxy ID for establishing the connection to external APIs (e.g., Gmail, Outlook).
+    /// - If `None` or not provided, the client will connect directly to the API server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    pub message_id_domain: Option<String>,
+    /// Free-form labels used to group and select accounts, such as `prod`,
+    /// `customer:acme` or `region:eu`.
+    pub tags: Vec<String>,
+    /// How the copy of a sent email is stored when `save_to_sent` is requested, to avoid
+    /// duplicates with providers that save sent emails themselves.
+    ///
+    /// If not set, the email is always appended to the Sent folder.
+    pub sent_copy: Option<SentCopyPolicy>,
+}
+
+impl AccountV7 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 5, version = 8, from = AccountV7)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountV8 {
+    /// Unique account identifier
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// Represents the account activation status.
+    ///
+    /// If this value is `false`, all account-related resources will be unavailable
+    /// and any attempts to access them should return an error indicating the account
+    /// is inactive.
+    pub enabled: bool,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// Email address associated with this account
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Minimal sync mode flag
+    ///
+    /// When enabled (`true`), only the most essential metadata will be synchronized:
+    /// Recommended for:
+    /// - Extremely resource-constrained environments
+    /// - Accounts where only new message notification is needed
+    pub minimal_sync: Option<bool>,
+    /// IMAP Server-supported capability flags
+    pub capabilities: Option<Vec<String>>,
+    /// DSN (Delivery Status Notification) support flag
+    pub dsn_capable: Option<bool>,
+    /// Controls initial synchronization time range
+    ///
+    /// When dealing with large mailboxes, this restricts scanning to:
+    /// - Messages after specified starting point
+    /// - Or within sliding window
+    ///
+    /// ### Use Cases
+    /// - Event-driven systems (only sync recent actionable emails)
+    /// - First-time sync optimization for large accounts
+    /// - Reducing server load during resyncs
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync for this folder.  
+    /// If not set, sync all emails.  
+    /// otherwise sync up to `n` most recent emails (min 10).
+    pub folder_limit: Option<u32>,
+    /// Configuration for selective folder synchronization
+    ///
+    /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
+    /// Modified folders will be automatically synced on next update.
+    pub sync_folders: Vec<String>,
+    /// Full sync interval (minutes), default 30m
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s
+    pub incremental_sync_interval_sec: i64,
+    /// Tracks known mail folders and detects changes (creations/deletions)
+    pub known_folders: BTreeSet<String>,
+    /// Creation timestamp (UNIX epoch milliseconds)
+    pub created_at: i64,
+    /// Last update timestamp (UNIX epoch milliseconds)
+    pub updated_at: i64,
+    /// Optional proxy ID for establishing the connection to external APIs (e.g., Gmail, Outlook).
+    /// - If `None` or not provided, the client will connect directly to the API server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    pub message_id_domain: Option<String>,
+    /// Free-form labels used to group and select accounts, such as `prod`,
+    /// `customer:acme` or `region:eu`.
+    pub tags: Vec<String>,
+    /// How the copy of a sent email is stored when `save_to_sent` is requested, to avoid
+    /// duplicates with providers that save sent emails themselves.
+    ///
+    /// If not set, the email is always appended to the Sent folder.
+    pub sent_copy: Option<SentCopyPolicy>,
+    /// Screening of dangerous attachment types, such as executables, scripts and HTML files,
+    /// in outgoing and received emails.
+    ///
+    /// If not set, attachments are not screened.
+    pub attachment_policy: Option<AttachmentPolicy>,
+}
+
+impl AccountV8 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 5, version = 9, from = AccountV8)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountV9 {
+    /// Unique account identifier
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// JMAP server configuration, used by `Jmap` accounts
+    pub jmap: Option<JmapConfig>,
+    /// Represents the account activation status.
+    ///
+    /// If this value is `false`, all account-related resources will be unavailable
+    /// and any attempts to access them should return an error indicating the account
+    /// is inactive.
+    pub enabled: bool,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// Email address associated with this account
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Minimal sync mode flag
+    ///
+    /// When enabled (`true`), only the most essential metadata will be synchronized:
+    /// Recommended for:
+    /// - Extremely resource-constrained environments
+    /// - Accounts where only new message notification is needed
+    pub minimal_sync: Option<bool>,
+    /// IMAP Server-supported capability flags
+    pub capabilities: Option<Vec<String>>,
+    /// DSN (Delivery Status Notification) support flag
+    pub dsn_capable: Option<bool>,
+    /// Controls initial synchronization time range
+    ///
+    /// When dealing with large mailboxes, this restricts scanning to:
+    /// - Messages after specified starting point
+    /// - Or within sliding window
+    ///
+    /// ### Use Cases
+    /// - Event-driven systems (only sync recent actionable emails)
+    /// - First-time sync optimization for large accounts
+    /// - Reducing server load during resyncs
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync for this folder.  
+    /// If not set, sync all emails.  
+    /// otherwise sync up to `n` most recent emails (min 10).
+    pub folder_limit: Option<u32>,
+    /// Configuration for selective folder synchronization
+    ///
+    /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
+    /// Modified folders will be automatically synced on next update.
+    pub sync_folders: Vec<String>,
+    /// Full sync interval (minutes), default 30m
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s
+    pub incremental_sync_interval_sec: i64,
+    /// Tracks known mail folders and detects changes (creations/deletions)
+    pub known_folders: BTreeSet<String>,
+    /// Creation timestamp (UNIX epoch milliseconds)
+    pub created_at: i64,
+    /// Last update timestamp (UNIX epoch milliseconds)
+    pub updated_at: i64,
+    /// Optional proxy ID for establishing the connection to external APIs (e.g., Gmail, Outlook).
+    /// - If `None` or not provided, the client will connect directly to the API server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    pub message_id_domain: Option<String>,
+    /// Free-form labels used to group and select accounts, such as `prod`,
+    /// `customer:acme` or `region:eu`.
+    pub tags: Vec<String>,
+    /// How the copy of a sent email is stored when `save_to_sent` is requested, to avoid
+    /// duplicates with providers that save sent emails themselves.
+    ///
+    /// If not set, the email is always appended to the Sent folder.
+    pub sent_copy: Option<SentCopyPolicy>,
+    /// Screening of dangerous attachment types, such as executables, scripts and HTML files,
+    /// in outgoing and received emails.
+    ///
+    /// If not set, attachments are not screened.
+    pub attachment_policy: Option<AttachmentPolicy>,
+}
+
+impl AccountV9 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 5, version = 10, from = AccountV9)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountV10 {
+    /// Unique account identifier
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// JMAP server configuration, used by `Jmap` accounts
+    pub jmap: Option<JmapConfig>,
+    /// Represents the account activation status.
+    ///
+    /// If this value is `false`, all account-related resources will be unavailable
+    /// and any attempts to access them should return an error indicating the account
+    /// is inactive.
+    pub enabled: bool,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// Email address associated with this account
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Minimal sync mode flag
+    ///
+    /// When enabled (`true`), only the most essential metadata will be synchronized:
+    /// Recommended for:
+    /// - Extremely resource-constrained environments
+    /// - Accounts where only new message notification is needed
+    pub minimal_sync: Option<bool>,
+    /// IMAP Server-supported capability flags
+    pub capabilities: Option<Vec<String>>,
+    /// DSN (Delivery Status Notification) support flag
+    pub dsn_capable: Option<bool>,
+    /// Controls initial synchronization time range
+    ///
+    /// When dealing with large mailboxes, this restricts scanning to:
+    /// - Messages after specified starting point
+    /// - Or within sliding window
+    ///
+    /// ### Use Cases
+    /// - Event-driven systems (only sync recent actionable emails)
+    /// - First-time sync optimization for large accounts
+    /// - Reducing server load during resyncs
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync for this folder.  
+    /// If not set, sync all emails.  
+    /// otherwise sync up to `n` most recent emails (min 10).
+    pub folder_limit: Option<u32>,
+    /// Configuration for selective folder synchronization
+    ///
+    /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
+    /// Modified folders will be automatically synced on next update.
+    pub sync_folders: Vec<String>,
+    /// Full sync interval (minutes), default 30m
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s
+    pub incremental_sync_interval_sec: i64,
+    /// Tracks known mail folders and detects changes (creations/deletions)
+    pub known_folders: BTreeSet<String>,
+    /// Creation timestamp (UNIX epoch milliseconds)
+    pub created_at: i64,
+    /// Last update timestamp (UNIX epoch milliseconds)
+    pub updated_at: i64,
+    /// Optional proxy ID for establishing the connection to external APIs (e.g., Gmail, Outlook).
+    /// - If `None` or not provided, the client will connect directly to the API server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    pub message_id_domain: Option<String>,
+    /// Free-form labels used to group and select accounts, such as `prod`,
+    /// `customer:acme` or `region:eu`.
+    pub tags: Vec<String>,
+    /// How the copy of a sent email is stored when `save_to_sent` is requested, to avoid
+    /// duplicates with providers that save sent emails themselves.
+    ///
+    /// If not set, the email is always appended to the Sent folder.
+    pub sent_copy: Option<SentCopyPolicy>,
+    /// Screening of dangerous attachment types, such as executables, scripts and HTML files,
+    /// in outgoing and received emails.
+    ///
+    /// If not set, attachments are not screened.
+    pub attachment_policy: Option<AttachmentPolicy>,
+    /// Outbound send rate limit of the account, such as 100 emails per hour, to stay
+    /// within the sending limits of the provider.
+    ///
+    /// If not set, emails are sent as fast as the queue allows.
+    pub send_rate_limit: Option<SendRateLimit>,
+}
+
+impl AccountV10 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 5, version = 11, from = AccountV10)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountV11 {
+    /// Unique account identifier
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// JMAP server configuration, used by `Jmap` accounts
+    pub jmap: Option<JmapConfig>,
+    /// Represents the account activation status.
+    ///
+    /// If this value is `false`, all account-related resources will be unavailable
+    /// and any attempts to access them should return an error indicating the account
+    /// is inactive.
+    pub enabled: bool,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// Email address associated with this account
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Minimal sync mode flag
+    ///
+    /// When enabled (`true`), only the most essential metadata will be synchronized:
+    /// Recommended for:
+    /// - Extremely resource-constrained environments
+    /// - Accounts where only new message notification is needed
+    pub minimal_sync: Option<bool>,
+    /// IMAP Server-supported capability flags
+    pub capabilities: Option<Vec<String>>,
+    /// DSN (Delivery Status Notification) support flag
+    pub dsn_capable: Option<bool>,
+    /// Controls initial synchronization time range
+    ///
+    /// When dealing with large mailboxes, this restricts scanning to:
+    /// - Messages after specified starting point
+    /// - Or within sliding window
+    ///
+    /// ### Use Cases
+    /// - Event-driven systems (only sync recent actionable emails)
+    /// - First-time sync optimization for large accounts
+    /// - Reducing server load during resyncs
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync for this folder.  
+    /// If not set, sync all emails.  
+    /// otherwise sync up to `n` most recent emails (min 10).
+    pub folder_limit: Option<u32>,
+    /// Configuration for selective folder synchronization
+    ///
+    /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
+    /// Modified folders will be automatically synced on next update.
+    pub sync_folders: Vec<String>,
+    /// Full sync interval (minutes), default 30m
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s
+    pub incremental_sync_interval_sec: i64,
+    /// Tracks known mail folders and detects changes (creations/deletions)
+    pub known_folders: BTreeSet<String>,
+    /// Creation timestamp (UNIX epoch milliseconds)
+    pub created_at: i64,
+    /// Last update timestamp (UNIX epoch milliseconds)
+    pub updated_at: i64,
+    /// Optional proxy ID for establishing the connection to external APIs (e.g., Gmail, Outlook).
+    /// - If `None` or not provided, the client will connect directly to the API server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    pub message_id_domain: Option<String>,
+    /// Free-form labels used to group and select accounts, such as `prod`,
+    /// `customer:acme` or `region:eu`.
+    pub tags: Vec<String>,
+    /// How the copy of a sent email is stored when `save_to_sent` is requested, to avoid
+    /// duplicates with providers that save sent emails themselves.
+    ///
+    /// If not set, the email is always appended to the Sent folder.
+    pub sent_copy: Option<SentCopyPolicy>,
+    /// Screening of dangerous attachment types, such as executables, scripts and HTML files,
+    /// in outgoing and received emails.
+    ///
+    /// If not set, attachments are not screened.
+    pub attachment_policy: Option<AttachmentPolicy>,
+    /// Outbound send rate limit of the account, such as 100 emails per hour, to stay
+    /// within the sending limits of the provider.
+    ///
+    /// If not set, emails are sent as fast as the queue allows.
+    pub send_rate_limit: Option<SendRateLimit>,
+    /// Folder include/exclude patterns, maximum message age and per-folder sync
+    /// frequency applied by the synchronization of the account.
+    ///
+    /// If not set, the folders of `sync_folders` are synced within `date_since`.
+    pub sync_policy: Option<SyncPolicy>,
+}
+
+impl AccountV11 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 5, version = 12, from = AccountV11)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountV12 {
+    /// Unique account identifier
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// JMAP server configuration, used by `Jmap` accounts
+    pub jmap: Option<JmapConfig>,
+    /// Represents the account activation status.
+    ///
+    /// If this value is `false`, all account-related resources will be unavailable
+    /// and any attempts to access them should return an error indicating the account
+    /// is inactive.
+    pub enabled: bool,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// Email address associated with this account
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Minimal sync mode flag
+    ///
+    /// When enabled (`true`), only the most essential metadata will be synchronized:
+    /// Recommended for:
+    /// - Extremely resource-constrained environments
+    /// - Accounts where only new message notification is needed
+    pub minimal_sync: Option<bool>,
+    /// IMAP Server-supported capability flags
+    pub capabilities: Option<Vec<String>>,
+    /// DSN (Delivery Status Notification) support flag
+    pub dsn_capable: Option<bool>,
+    /// Controls initial synchronization time range
+    ///
+    /// When dealing with large mailboxes, this restricts scanning to:
+    /// - Messages after specified starting point
+    /// - Or within sliding window
+    ///
+    /// ### Use Cases
+    /// - Event-driven systems (only sync recent actionable emails)
+    /// - First-time sync optimization for large accounts
+    /// - Reducing server load during resyncs
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync for this folder.  
+    /// If not set, sync all emails.  
+    /// otherwise sync up to `n` most recent emails (min 10).
+    pub folder_limit: Option<u32>,
+    /// Configuration for selective folder synchronization
+    ///
+    /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
+    /// Modified folders will be automatically synced on next update.
+    pub sync_folders: Vec<String>,
+    /// Full sync interval (minutes), default 30m
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s
+    pub incremental_sync_interval_sec: i64,
+    /// Tracks known mail folders and detects changes (creations/deletions)
+    pub known_folders: BTreeSet<String>,
+    /// Creation timestamp (UNIX epoch milliseconds)
+    pub created_at: i64,
+    /// Last update timestamp (UNIX epoch milliseconds)
+    pub updated_at: i64,
+    /// Optional proxy ID for establishing the connection to external APIs (e.g., Gmail, Outlook).
+    /// - If `None` or not provided, the client will connect directly to the API server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    pub message_id_domain: Option<String>,
+    /// Free-form labels used to group and select accounts, such as `prod`,
+    /// `customer:acme` or `region:eu`.
+    pub tags: Vec<String>,
+    /// How the copy of a sent email is stored when `save_to_sent` is requested, to avoid
+    /// duplicates with providers that save sent emails themselves.
+    ///
+    /// If not set, the email is always appended to the Sent folder.
+    pub sent_copy: Option<SentCopyPolicy>,
+    /// Screening of dangerous attachment types, such as executables, scripts and HTML files,
+    /// in outgoing and received emails.
+    ///
+    /// If not set, attachments are not screened.
+    pub attachment_policy: Option<AttachmentPolicy>,
+    /// Outbound send rate limit of the account, such as 100 emails per hour, to stay
+    /// within the sending limits of the provider.
+    ///
+    /// If not set, emails are sent as fast as the queue allows.
+    pub send_rate_limit: Option<SendRateLimit>,
+    /// Folder include/exclude patterns, maximum message age and per-folder sync
+    /// frequency applied by the synchronization of the account.
+    ///
+    /// If not set, the folders of `sync_folders` are synced within `date_since`.
+    pub sync_policy: Option<SyncPolicy>,
+    /// The workspace the account belongs to, along with its templates and event hooks.
+    ///
+    /// If not set, the account is not part of any workspace.
+    pub workspace_id: Option<u64>,
+}
+
+impl Versioned for AccountV12 {
+    fn version(&self) -> i64 {
+        self.updated_at
+    }
+
+    fn set_version(&mut self, version: i64) {
+        self.updated_at = version;
+    }
+}
+
+impl AccountV12 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    pub fn minimal_sync(&self) -> bool {
+        self.minimal_sync.unwrap_or(false)
+    }
+
+    /// The start of the sync window, combining `date_since` with the maximum message age of
+    /// the sync policy.
+    pub fn sync_since(&self) -> RustMailerResult<Option<DateSince>> {
+        match &self.sync_policy {
+            Some(policy) => policy.effective_date_since(self.date_since.as_ref()),
+            None => Ok(self.date_since.clone()),
+        }
+    }
+
+    /// Whether the folder `name` is synced, `subscribed` telling whether it is selected by
+    /// `sync_folders`.
+    pub fn is_folder_synced(&self, name: &str, subscribed: bool) -> bool {
+        self.sync_policy
+            .as_ref()
+            .map_or(subscribed, |policy| policy.is_synced(name, subscribed))
+    }
+
+    /// Whether the folder `name` is due to be synced, according to the folder sync
+    /// intervals of the sync policy.
+    pub fn is_folder_due(&self, name: &str) -> bool {
+        self.sync_policy
+            .as_ref()
+            .is_none_or(|policy| policy.is_folder_due(self.id, name))
+    }
+
+    /// Records a sync of the folder `name`, for the folder sync intervals of the sync
+    /// policy and the account dashboard.
+    pub fn record_folder_sync(&self, name: &str) {
+        record_folder_sync(self.id, name);
+    }
+
+    pub fn create(request: AccountCreateRequest) -> RustMailerResult<Self> {
+        Ok(Self {
+            id: id!(64),
+            email: request.email,
+            name: request.name,
+            imap: request
+                .imap
+                .map(|imap| imap.try_encrypt_password())
+                .transpose()?,
+            smtp: request
+                .smtp
+                .map(|smtp| smtp.try_encrypt_password())
+                .transpose()?,
+            jmap: request
+                .jmap
+                .map(|jmap| jmap.try_encrypt_password())
+                .transpose()?,
+            enabled: request.enabled,
+            mailer_type: request.mailer_type,
+            minimal_sync: request.minimal_sync,
+            capabilities: None,
+            date_since: request.date_since,
+            dsn_capable: None,
+            sync_folders: vec![],
+            known_folders: BTreeSet::new(),
+            full_sync_interval_min: request.full_sync_interval_min,
+            incremental_sync_interval_sec: request.incremental_sync_interval_sec,
+            created_at: utc_now!(),
+            updated_at: utc_now!(),
+            use_proxy: request.use_proxy,
+            folder_limit: request.folder_limit,
+            loop_protection: request.loop_protection,
+            message_id_domain: request.message_id_domain,
+            tags: normalize_tags(request.tags.unwrap_or_default())?,
+            sent_copy: request.sent_copy,
+            attachment_policy: request.attachment_policy,
+            send_rate_limit: request.send_rate_limit,
+            sync_policy: request.sync_policy,
+            workspace_id: request.workspace_id,
+        })
+    }
+
+    pub async fn check_account_active(
+        account_id: u64,
+        imap_only: bool,
+    ) -> RustMailerResult<AccountModel> {
+        let account = secondary_find_impl::<AccountModel>(
+            DB_MANAGER.meta_db(),
+            AccountV12Key::id,
+            account_id,
+        )
+        .await?
+        .ok_or_else(|| {
+            raise_error!(
+                format!("Account id='{account_id}' not found"),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+
+        if !account.enabled {
+            return Err(raise_error!(
+                format!("Account id='{account_id}' is disabled"),
+                ErrorCode::AccountDisabled
+            ));
+        }
+
+        if imap_only && !matches!(account.mailer_type, MailerType::ImapSmtp) {
+            return Err(raise_error!(
+                format!(
+                    "Operation not allowed: account id='{account_id}' is of type '{:?}', but this action requires an IMAP/SMTP account",
+                    account.mailer_type
+                ),
+                ErrorCode::Incompatible
+            ));
+        }
+
+        Ok(account)
+    }
+
+    /// Fetches an `AccountEntity` by its `id`.
+    pub async fn get(account_id: u64) -> RustMailerResult<AccountModel> {
+        let result: AccountModel = Self::find(account_id).await?.ok_or_else(|| {
+            raise_error!(
+                format!("Account with ID '{account_id}' not found"),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+        Ok(result)
+    }
+
+    pub async fn find(account_id: u64) -> RustMailerResult<Option<AccountModel>> {
+        secondary_find_impl::<AccountModel>(DB_MANAGER.meta_db(), AccountV12Key::id, account_id)
+            .await
+    }
+
+    /// Saves the current `AccountEntity` by persisting it to storage.
+    pub async fn save(self) -> RustMailerResult<()> {
+        insert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    /// Creates an account and starts its synchronization.
+    ///
+    /// The access token `grant_to`, if any, is the token creating the account. It is granted
+    /// access to the new account in the same transaction, so that a failure cannot leave an
+    /// account its creator is unable to access, and a token scoped to a workspace creates
+    /// the account in its workspace.
+    pub async fn create_account(
+        mut request: AccountCreateRequest,
+        grant_to: Option<&AccessToken>,
+    ) -> RustMailerResult<AccountModel> {
+        check_metadata_capacity()?;
+        request.workspace_id = match grant_to {
+            None => request.workspace_id,
+            Some(token) if request.workspace_id.is_none_or(|id| token.workspace_id == Some(id)) => {
+                token.workspace_id
+            }
+            Some(_) => {
+                return Err(raise_error!(
+                    "Only root can create accounts in another workspace".into(),
+                    ErrorCode::PermissionDenied
+                ))
+            }
+        };
+        Workspace::check_exists(request.workspace_id).await?;
+        // Validate license limits before creating entity
+        if let Some(license) = License::get_current_license().await? {
+            let current_count = AccountV12::count().await?;
+            if let Some(max_accounts) = license.max_accounts {
+                if current_count >= max_accounts as usize {
+                    return Err(raise_error!(
+                        "Maximum account limit reached".into(),
+                        ErrorCode::LicenseAccountLimitReached
+                    ));
+                }
+            } 
+        }
+        let entity = request.create_entity()?;
+        let mut batch = WriteBatch::new().insert(entity.clone());
+        if let Some(token) = grant_to {
+            let account_info = AccountInfo {
+                id: entity.id,
+                email: entity.email.clone(),
+            };
+            batch = AccessToken::stage_grant_account_access(batch, &token.token, account_info);
+        }
+        batch.commit(DB_MANAGER.meta_db()).await?;
+        if entity.workspace_id.is_some() {
+            Workspace::invalidate_accounts();
+        }
+        SYNC_CONTROLLER
+            .trigger_start(entity.id, entity.email.clone())
+            .await;
+        Ok(entity)
+    }
+
+    /// Updates an account.
+    ///
+    /// If `expected_version` is set, the update is rejected with a `VersionConflict` error
+    /// when the account's `updated_at` no longer matches it.
+    pub async fn update(
+        account_id: u64,
+        request: AccountUpdateRequest,
+        validate: bool,
+        expected_version: Option<i64>,
+    ) -> RustMailerResult<()> {
+        if validate {
+            request.validate_update_request()?;
+        }
+
+        let account = AccountModel::get(account_id).await?;
+        let mut map = None;
+        if let Some(_) = &request.sync_folders {
+            match account.mailer_type {
+                MailerType::GmailApi => {
+                    map = Some(
+                        GmailClient::reverse_label_map(account_id, account.use_proxy, true).await?,
+                    );
+                }
+                MailerType::Jmap => {
+                    map = Some(JmapClient::reverse_mailbox_map(&account).await?);
+                }
+                _ => {}
+            }
+        }
+        let jmap_changed = request.jmap.is_some();
+        versioned_update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .secondary::<AccountModel>(AccountV12Key::id, account_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!("The account entity with id={account_id} that you want to modify was not found."),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            move |current| Self::apply_update_fields(current, request, map),
+            expected_version,
+        )
         .await?;
-
+        if jmap_changed {
+            JmapClient::forget_session(account_id);
+        }
         Ok(())
     }
 
@@ -357,7 +1370,7 @@ impl AccountV3 {
             enabled: Some(false),
             ..Default::default()
         };
-        Self::update(account_id, request, false).await?;
+        Self::update(account_id, request, false, None).await?;
         SYNC_TASKS.stop(account_id).await?;
         if let Err(error) = Self::cleanup_account_resources_sequential(account_id).await {
             tracing::error!(
@@ -370,25 +1383,45 @@ impl AccountV3 {
         Ok(())
     }
 
+    /// Removes the account together with every metadata entity referencing it, in a
+    /// single transaction.
     async fn delete_account(account_id: u64) -> RustMailerResult<()> {
-        delete_impl(DB_MANAGER.meta_db(), move|rw|{
-            rw.get().secondary::<AccountModel>(AccountV3Key::id, account_id).map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-            .ok_or_else(||raise_error!(format!("The account entity with id={account_id} that you want to delete was not found."), ErrorCode::ResourceNotFound))
-        }).await
+        let mut batch = WriteBatch::new();
+        batch = EmailTemplate::stage_remove_account_templates(batch, account_id);
+        batch = TemplatePartial::stage_remove_account_partials(batch, account_id);
+        batch = Campaign::stage_remove_account_campaigns(batch, account_id);
+        batch = RecurringSend::stage_remove_account_recurring_sends(batch, account_id);
+        batch = SavedSearch::stage_remove_account_searches(batch, account_id);
+        batch = CleanupRule::stage_remove_account_rules(batch, account_id);
+        batch = MaintenanceWindow::stage_remove_account_windows(batch, account_id);
+        batch = DeadLetter::stage_remove_account_dead_letters(batch, account_id);
+        batch = SuppressedAddress::stage_remove_account_suppressions(batch, account_id);
+        batch = DeliveryEvent::stage_remove_account_events(batch, account_id);
+        batch = HookDelivery::stage_remove_account_deliveries(batch, account_id);
+        batch = OAuth2AccessToken::stage_try_delete(batch, account_id);
+        batch = EventHooks::stage_try_delete(batch, account_id);
+        batch = AccessToken::stage_cleanup_account(batch, account_id);
+        batch = AccountRunningState::stage_delete(batch, account_id);
+        batch
+            .delete(move |rw| {
+                rw.get().secondary::<AccountModel>(AccountV12Key::id, account_id).map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(||raise_error!(format!("The account entity with id={account_id} that you want to delete was not found."), ErrorCode::ResourceNotFound))
+            })
+            .commit(DB_MANAGER.meta_db())
+            .await?;
+        Workspace::invalidate_accounts();
+        Ok(())
     }
 
     async fn cleanup_account_resources_sequential(account_id: u64) -> RustMailerResult<()> {
         let account = Self::get(account_id).await?;
-        EmailTemplate::remove_account_templates(account_id).await?;
-        OAuth2AccessToken::try_delete(account_id).await?;
-        EventHooks::try_delete(account_id).await?;
-        AccessToken::cleanup_account(account_id).await?;
-        AccountRunningState::delete(account_id).await?;
+        // Cached envelopes are removed first: if this fails, the (disabled) account and its
+        // metadata are still in place and the deletion can simply be retried.
         match account.mailer_type {
             MailerType::ImapSmtp => {
                 MailBox::clean(account_id).await?;
                 FLAGS_STATE_MAP.remove(&account.id);
-                EmailEnvelopeV3::clean_account(account.id).await?;
+                EmailEnvelopeV7::clean_account(account.id).await?;
                 MinimalEnvelope::clean_account(account.id).await?;
                 RUST_MAIL_CONTEXT.clean_account(account_id).await?;
             }
@@ -402,10 +1435,18 @@ impl AccountV3 {
                 OutlookEnvelope::clean_account(account.id).await?;
                 FolderDeltaLink::clean(account.id).await?;
             }
+            MailerType::Jmap => {
+                JmapMailbox::clean(account_id).await?;
+                JmapEnvelope::clean_account(account.id).await?;
+                JmapSyncState::clean(account.id).await?;
+                JmapClient::forget_session(account.id);
+            }
         }
         AddressEntity::clean_account(account.id).await?;
         EmailThread::clean_account(account.id).await?;
         Self::delete_account(account_id).await?;
+        remove_account_metrics(account_id);
+        forget_folder_syncs(account_id);
         info!("Sequential cleanup completed for account: {}", account_id);
         Ok(())
     }
@@ -415,7 +1456,7 @@ impl AccountV3 {
         sync_folders: Vec<String>,
     ) -> RustMailerResult<()> {
         update_impl(DB_MANAGER.meta_db(), move |rw| {
-            rw.get().secondary::<AccountModel>(AccountV3Key::id, account_id).map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            rw.get().secondary::<AccountModel>(AccountV12Key::id, account_id).map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
             .ok_or_else(|| raise_error!(format!("When trying to update account sync_folders, the corresponding record was not found. account_id={}", account_id), ErrorCode::ResourceNotFound))
         }, |current|{
             let mut updated = current.clone();
@@ -425,228 +1466,827 @@ impl AccountV3 {
         Ok(())
     }
 
-    pub async fn update_known_folders(
-        account_id: u64,
-        known_folders: BTreeSet<String>,
-    ) -> RustMailerResult<()> {
-        update_impl(DB_MANAGER.meta_db(), move |rw| {
-            rw.get().secondary::<AccountModel>(AccountV3Key::id, account_id).map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-            .ok_or_else(|| raise_error!(format!("When trying to update account known_folders, the corresponding record was not found. account_id={}", account_id), ErrorCode::ResourceNotFound))
-        }, |current|{
-            let mut updated = current.clone();
-            updated.known_folders = known_folders;
-            Ok(updated)
-        }).await?;
-        Ok(())
+    pub async fn update_known_folders(
+        account_id: u64,
+        known_folders: BTreeSet<String>,
+    ) -> RustMailerResult<()> {
+        update_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get().secondary::<AccountModel>(AccountV12Key::id, account_id).map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .ok_or_else(|| raise_error!(format!("When trying to update account known_folders, the corresponding record was not found. account_id={}", account_id), ErrorCode::ResourceNotFound))
+        }, |current|{
+            let mut updated = current.clone();
+            updated.known_folders = known_folders;
+            Ok(updated)
+        }).await?;
+        Ok(())
+    }
+
+    pub async fn update_capabilities(
+        account_id: u64,
+        capabilities: Vec<String>,
+    ) -> RustMailerResult<()> {
+        update_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get().secondary::<AccountModel>(AccountV12Key::id, account_id).map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .ok_or_else(|| raise_error!(format!("When trying to update account capabilities, the corresponding record was not found. account_id={}", account_id), ErrorCode::ResourceNotFound))
+        }, |current|{
+            let mut updated = current.clone();
+            updated.capabilities = Some(capabilities);
+            Ok(updated)
+        }).await?;
+        Ok(())
+    }
+
+    pub async fn update_dsn_capable(account_id: u64, dsn: bool) -> RustMailerResult<()> {
+        update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .secondary::<AccountModel>(AccountV12Key::id, account_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(format!(
+                            "When trying to update account dsn capabilities, the corresponding record was not found. account_id={}",
+                            account_id
+                        ), ErrorCode::ResourceNotFound)
+                    })
+            },
+            move |current| {
+                let mut updated = current.clone();
+                updated.dsn_capable = Some(dsn);
+                Ok(updated)
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Adds moving the account into the workspace `workspace_id`, or out of any workspace, to
+    /// `batch`. The caller invalidates the cached workspace accounts once it is committed.
+    pub fn stage_set_workspace(
+        batch: WriteBatch,
+        account_id: u64,
+        workspace_id: Option<u64>,
+    ) -> WriteBatch {
+        batch.update(
+            move |rw| {
+                rw.get()
+                    .secondary::<AccountModel>(AccountV12Key::id, account_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!("Account id='{account_id}' not found"),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            move |current| {
+                let mut updated = current.clone();
+                updated.workspace_id = workspace_id;
+                updated.updated_at = utc_now!();
+                Ok(updated)
+            },
+        )
+    }
+
+    /// Retrieves a list of all `AccountEntity` instances.
+    pub async fn list_all() -> RustMailerResult<Vec<AccountModel>> {
+        list_all_impl(DB_MANAGER.meta_db()).await
+    }
+
+    pub async fn minimal_list() -> RustMailerResult<Vec<MinimalAccount>> {
+        let result = list_all_impl(DB_MANAGER.meta_db())
+            .await?
+            .into_iter()
+            .filter(|a: &AccountModel| a.enabled)
+            .map(|account: AccountModel| MinimalAccount {
+                id: account.id,
+                email: account.email,
+                mailer_type: account.mailer_type,
+                tags: account.tags,
+            })
+            .collect::<Vec<MinimalAccount>>();
+        Ok(result)
+    }
+
+    pub async fn count() -> RustMailerResult<usize> {
+        count_by_unique_secondary_key_impl::<AccountModel>(DB_MANAGER.meta_db(), AccountV12Key::id)
+            .await
+    }
+
+    pub async fn paginate_list(
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<AccountModel>> {
+        paginate_query_primary_scan_all_impl(DB_MANAGER.meta_db(), page, page_size, desc)
+            .await
+            .map(DataPage::from)
+    }
+
+    // This method applies the updates from the request to the old account entity
+    fn apply_update_fields(
+        old: &AccountModel,
+        request: AccountUpdateRequest,
+        label_map: Option<AHashMap<String, String>>,
+    ) -> RustMailerResult<AccountModel> {
+        let mut new = old.clone();
+
+        if let Some(date_since) = request.date_since {
+            new.date_since = Some(date_since);
+        }
+
+        if let Some(folder_limit) = request.folder_limit {
+            new.folder_limit = Some(folder_limit);
+        }
+
+        if let Some(name) = &request.name {
+            new.name = Some(name.clone());
+        }
+
+        if let Some(imap) = &request.imap {
+            if let Some(current_imap) = &mut new.imap {
+                current_imap.host = imap.host.clone();
+                current_imap.port = imap.port.clone();
+                current_imap.encryption = imap.encryption.clone();
+                current_imap.auth.auth_type = imap.auth.auth_type.clone();
+                if let Some(password) = &imap.auth.password {
+                    let encrypted_password = encrypt!(password)?;
+                    current_imap.auth.password = Some(encrypted_password);
+                }
+                current_imap.use_proxy = imap.use_proxy;
+            }
+        }
+
+        if let Some(smtp) = &request.smtp {
+            if let Some(current_smtp) = &mut new.smtp {
+                current_smtp.host = smtp.host.clone();
+                current_smtp.port = smtp.port.clone();
+                current_smtp.encryption = smtp.encryption.clone();
+                current_smtp.auth.auth_type = smtp.auth.auth_type.clone();
+                if let Some(password) = &smtp.auth.password {
+                    let encrypted_password = encrypt!(password)?;
+                    current_smtp.auth.password = Some(encrypted_password);
+                }
+                current_smtp.use_proxy = smtp.use_proxy;
+            }
+        }
+
+        if let Some(jmap) = &request.jmap {
+            if let Some(current_jmap) = &mut new.jmap {
+                current_jmap.session_url = jmap.session_url.clone();
+                current_jmap.username = jmap.username.clone();
+                current_jmap.auth.auth_type = jmap.auth.auth_type.clone();
+                if let Some(password) = &jmap.auth.password {
+                    let encrypted_password = encrypt!(password)?;
+                    current_jmap.auth.password = Some(encrypted_password);
+                }
+            }
+        }
+
+        if let Some(folder_names) = request.sync_folders {
+            match label_map {
+                Some(label_map) => {
+                    let folder_ids: Vec<String> = folder_names
+                        .into_iter()
+                        .filter_map(|name| label_map.get(&name).cloned())
+                        .collect();
+                    new.sync_folders = folder_ids;
+                }
+                None => new.sync_folders = folder_names,
+            }
+        }
+
+        if let Some(use_proxy) = request.use_proxy {
+            new.use_proxy = Some(use_proxy);
+        }
+
+        if let Some(loop_protection) = request.loop_protection {
+            new.loop_protection = Some(loop_protection);
+        }
+
+        if let Some(message_id_domain) = request.message_id_domain {
+            new.message_id_domain = Some(message_id_domain);
+        }
+
+        if let Some(tags) = request.tags {
+            new.tags = normalize_tags(tags)?;
+        }
+
+        if let Some(sent_copy) = request.sent_copy {
+            new.sent_copy = Some(sent_copy);
+        }
+
+        if let Some(attachment_policy) = request.attachment_policy {
+            new.attachment_policy = Some(attachment_policy);
+        }
+        if let Some(send_rate_limit) = request.send_rate_limit {
+            new.send_rate_limit = Some(send_rate_limit);
+        }
+        if let Some(sync_policy) = request.sync_policy {
+            new.sync_policy = Some(sync_policy);
+        }
+
+        if let Some(full_sync_interval_min) = &request.full_sync_interval_min {
+            new.full_sync_interval_min = Some(*full_sync_interval_min);
+        }
+        if let Some(incremental_sync_interval_sec) = &request.incremental_sync_interval_sec {
+            new.incremental_sync_interval_sec = *incremental_sync_interval_sec;
+        }
+        if let Some(enabled) = request.enabled {
+            new.enabled = enabled;
+        }
+        new.updated_at = utc_now!();
+        Ok(new)
+    }
+}
+
+// Will never be used
+impl From<AccountV2> for Account {
+    fn from(value: AccountV2) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap.unwrap(),
+            smtp: value.smtp.unwrap(),
+            enabled: value.enabled,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync.unwrap(),
+            capabilities: value.capabilities.unwrap(),
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min.unwrap(),
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<Account> for AccountV2 {
+    fn from(value: Account) -> Self {
+        Self {
+            id: value.id,
+            imap: Some(value.imap),
+            smtp: Some(value.smtp),
+            enabled: value.enabled,
+            mailer_type: MailerType::ImapSmtp,
+            email: value.email,
+            name: value.name,
+            minimal_sync: Some(value.minimal_sync),
+            capabilities: Some(value.capabilities),
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: Some(value.full_sync_interval_min),
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: None,
+        }
+    }
+}
+
+impl From<AccountV2> for AccountV3 {
+    fn from(value: AccountV2) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: None,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+        }
     }
+}
 
-    pub async fn update_capabilities(
-        account_id: u64,
-        capabilities: Vec<String>,
-    ) -> RustMailerResult<()> {
-        update_impl(DB_MANAGER.meta_db(), move |rw| {
-            rw.get().secondary::<AccountModel>(AccountV3Key::id, account_id).map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-            .ok_or_else(|| raise_error!(format!("When trying to update account capabilities, the corresponding record was not found. account_id={}", account_id), ErrorCode::ResourceNotFound))
-        }, |current|{
-            let mut updated = current.clone();
-            updated.capabilities = Some(capabilities);
-            Ok(updated)
-        }).await?;
-        Ok(())
+impl From<AccountV3> for AccountV2 {
+    fn from(value: AccountV3) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+        }
     }
+}
 
-    pub async fn update_dsn_capable(account_id: u64, dsn: bool) -> RustMailerResult<()> {
-        update_impl(
-            DB_MANAGER.meta_db(),
-            move |rw| {
-                rw.get()
-                    .secondary::<AccountModel>(AccountV3Key::id, account_id)
-                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-                    .ok_or_else(|| {
-                        raise_error!(format!(
-                            "When trying to update account dsn capabilities, the corresponding record was not found. account_id={}",
-                            account_id
-                        ), ErrorCode::ResourceNotFound)
-                    })
-            },
-            move |current| {
-                let mut updated = current.clone();
-                updated.dsn_capable = Some(dsn);
-                Ok(updated)
-            },
-        )
-        .await?;
-        Ok(())
+impl From<AccountV3> for AccountV4 {
+    fn from(value: AccountV3) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: None,
+        }
     }
+}
 
-    /// Retrieves a list of all `AccountEntity` instances.
-    pub async fn list_all() -> RustMailerResult<Vec<AccountModel>> {
-        list_all_impl(DB_MANAGER.meta_db()).await
+impl From<AccountV4> for AccountV3 {
+    fn from(value: AccountV4) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+        }
     }
+}
 
-    pub async fn minimal_list() -> RustMailerResult<Vec<MinimalAccount>> {
-        let result = list_all_impl(DB_MANAGER.meta_db())
-            .await?
-            .into_iter()
-            .filter(|a: &AccountModel| a.enabled)
-            .map(|account: AccountModel| MinimalAccount {
-                id: account.id,
-                email: account.email,
-                mailer_type: account.mailer_type,
-            })
-            .collect::<Vec<MinimalAccount>>();
-        Ok(result)
+impl From<AccountV4> for AccountV5 {
+    fn from(value: AccountV4) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: None,
+        }
     }
+}
 
-    pub async fn count() -> RustMailerResult<usize> {
-        count_by_unique_secondary_key_impl::<AccountModel>(DB_MANAGER.meta_db(), AccountV3Key::id)
-            .await
+impl From<AccountV5> for AccountV4 {
+    fn from(value: AccountV5) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+        }
     }
+}
 
-    pub async fn paginate_list(
-        page: Option<u64>,
-        page_size: Option<u64>,
-        desc: Option<bool>,
-    ) -> RustMailerResult<DataPage<AccountModel>> {
-        paginate_query_primary_scan_all_impl(DB_MANAGER.meta_db(), page, page_size, desc)
-            .await
-            .map(DataPage::from)
+impl From<AccountV5> for AccountV6 {
+    fn from(value: AccountV5) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: Vec::new(),
+        }
     }
+}
 
-    // This method applies the updates from the request to the old account entity
-    fn apply_update_fields(
-        old: &AccountModel,
-        request: AccountUpdateRequest,
-        label_map: Option<AHashMap<String, String>>,
-    ) -> RustMailerResult<AccountModel> {
-        let mut new = old.clone();
-
-        if let Some(date_since) = request.date_since {
-            new.date_since = Some(date_since);
+impl From<AccountV6> for AccountV5 {
+    fn from(value: AccountV6) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
         }
+    }
+}
 
-        if let Some(folder_limit) = request.folder_limit {
-            new.folder_limit = Some(folder_limit);
+impl From<AccountV6> for AccountV7 {
+    fn from(value: AccountV6) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: None,
         }
+    }
+}
 
-        if let Some(name) = &request.name {
-            new.name = Some(name.clone());
+impl From<AccountV7> for AccountV6 {
+    fn from(value: AccountV7) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
         }
+    }
+}
 
-        if let Some(imap) = &request.imap {
-            if let Some(current_imap) = &mut new.imap {
-                current_imap.host = imap.host.clone();
-                current_imap.port = imap.port.clone();
-                current_imap.encryption = imap.encryption.clone();
-                current_imap.auth.auth_type = imap.auth.auth_type.clone();
-                if let Some(password) = &imap.auth.password {
-                    let encrypted_password = encrypt!(password)?;
-                    current_imap.auth.password = Some(encrypted_password);
-                }
-                current_imap.use_proxy = imap.use_proxy;
-            }
+impl From<AccountV7> for AccountV8 {
+    fn from(value: AccountV7) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
+            attachment_policy: None,
         }
+    }
+}
 
-        if let Some(smtp) = &request.smtp {
-            if let Some(current_smtp) = &mut new.smtp {
-                current_smtp.host = smtp.host.clone();
-                current_smtp.port = smtp.port.clone();
-                current_smtp.encryption = smtp.encryption.clone();
-                current_smtp.auth.auth_type = smtp.auth.auth_type.clone();
-                if let Some(password) = &smtp.auth.password {
-                    let encrypted_password = encrypt!(password)?;
-                    current_smtp.auth.password = Some(encrypted_password);
-                }
-                current_smtp.use_proxy = smtp.use_proxy;
-            }
+impl From<AccountV8> for AccountV7 {
+    fn from(value: AccountV8) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
         }
+    }
+}
 
-        if let Some(folder_names) = request.sync_folders {
-            match label_map {
-                Some(label_map) => {
-                    let folder_ids: Vec<String> = folder_names
-                        .into_iter()
-                        .filter_map(|name| label_map.get(&name).cloned())
-                        .collect();
-                    new.sync_folders = folder_ids;
-                }
-                None => new.sync_folders = folder_names,
-            }
+impl From<AccountV8> for AccountV9 {
+    fn from(value: AccountV8) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            jmap: None,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
+            attachment_policy: value.attachment_policy,
         }
+    }
+}
 
-        if let Some(use_proxy) = request.use_proxy {
-            new.use_proxy = Some(use_proxy);
+impl From<AccountV9> for AccountV8 {
+    fn from(value: AccountV9) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
+            attachment_policy: value.attachment_policy,
         }
+    }
+}
 
-        if let Some(full_sync_interval_min) = &request.full_sync_interval_min {
-            new.full_sync_interval_min = Some(*full_sync_interval_min);
-        }
-        if let Some(incremental_sync_interval_sec) = &request.incremental_sync_interval_sec {
-            new.incremental_sync_interval_sec = *incremental_sync_interval_sec;
+impl From<AccountV9> for AccountV10 {
+    fn from(value: AccountV9) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            jmap: value.jmap,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
+            attachment_policy: value.attachment_policy,
+            send_rate_limit: None,
         }
-        if let Some(enabled) = request.enabled {
-            new.enabled = enabled;
+    }
+}
+
+impl From<AccountV10> for AccountV9 {
+    fn from(value: AccountV10) -> Self {
+        Self {
+            id: value.id,
+            imap: value.imap,
+            smtp: value.smtp,
+            jmap: value.jmap,
+            enabled: value.enabled,
+            mailer_type: value.mailer_type,
+            email: value.email,
+            name: value.name,
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
+            dsn_capable: value.dsn_capable,
+            date_since: value.date_since,
+            folder_limit: value.folder_limit,
+            sync_folders: value.sync_folders,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            known_folders: value.known_folders,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
+            attachment_policy: value.attachment_policy,
         }
-        new.updated_at = utc_now!();
-        Ok(new)
     }
 }
 
-// Will never be used
-impl From<AccountV2> for Account {
-    fn from(value: AccountV2) -> Self {
+impl From<AccountV10> for AccountV11 {
+    fn from(value: AccountV10) -> Self {
         Self {
             id: value.id,
-            imap: value.imap.unwrap(),
-            smtp: value.smtp.unwrap(),
+            imap: value.imap,
+            smtp: value.smtp,
+            jmap: value.jmap,
             enabled: value.enabled,
+            mailer_type: value.mailer_type,
             email: value.email,
             name: value.name,
-            minimal_sync: value.minimal_sync.unwrap(),
-            capabilities: value.capabilities.unwrap(),
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
             dsn_capable: value.dsn_capable,
             date_since: value.date_since,
+            folder_limit: value.folder_limit,
             sync_folders: value.sync_folders,
-            full_sync_interval_min: value.full_sync_interval_min.unwrap(),
+            full_sync_interval_min: value.full_sync_interval_min,
             incremental_sync_interval_sec: value.incremental_sync_interval_sec,
             known_folders: value.known_folders,
             created_at: value.created_at,
             updated_at: value.updated_at,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
+            attachment_policy: value.attachment_policy,
+            send_rate_limit: value.send_rate_limit,
+            sync_policy: None,
         }
     }
 }
 
-impl From<Account> for AccountV2 {
-    fn from(value: Account) -> Self {
+impl From<AccountV11> for AccountV10 {
+    fn from(value: AccountV11) -> Self {
         Self {
             id: value.id,
-            imap: Some(value.imap),
-            smtp: Some(value.smtp),
+            imap: value.imap,
+            smtp: value.smtp,
+            jmap: value.jmap,
             enabled: value.enabled,
-            mailer_type: MailerType::ImapSmtp,
+            mailer_type: value.mailer_type,
             email: value.email,
             name: value.name,
-            minimal_sync: Some(value.minimal_sync),
-            capabilities: Some(value.capabilities),
+            minimal_sync: value.minimal_sync,
+            capabilities: value.capabilities,
             dsn_capable: value.dsn_capable,
             date_since: value.date_since,
+            folder_limit: value.folder_limit,
             sync_folders: value.sync_folders,
-            full_sync_interval_min: Some(value.full_sync_interval_min),
+            full_sync_interval_min: value.full_sync_interval_min,
             incremental_sync_interval_sec: value.incremental_sync_interval_sec,
             known_folders: value.known_folders,
             created_at: value.created_at,
             updated_at: value.updated_at,
-            use_proxy: None,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
+            attachment_policy: value.attachment_policy,
+            send_rate_limit: value.send_rate_limit,
         }
     }
 }
 
-impl From<AccountV2> for AccountV3 {
-    fn from(value: AccountV2) -> Self {
+impl From<AccountV11> for AccountV12 {
+    fn from(value: AccountV11) -> Self {
         Self {
             id: value.id,
             imap: value.imap,
             smtp: value.smtp,
+            jmap: value.jmap,
             enabled: value.enabled,
             mailer_type: value.mailer_type,
             email: value.email,
@@ -655,7 +2295,7 @@ impl From<AccountV2> for AccountV3 {
             capabilities: value.capabilities,
             dsn_capable: value.dsn_capable,
             date_since: value.date_since,
-            folder_limit: None,
+            folder_limit: value.folder_limit,
             sync_folders: value.sync_folders,
             full_sync_interval_min: value.full_sync_interval_min,
             incremental_sync_interval_sec: value.incremental_sync_interval_sec,
@@ -663,16 +2303,25 @@ impl From<AccountV2> for AccountV3 {
             created_at: value.created_at,
             updated_at: value.updated_at,
             use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
+            attachment_policy: value.attachment_policy,
+            send_rate_limit: value.send_rate_limit,
+            sync_policy: value.sync_policy,
+            workspace_id: None,
         }
     }
 }
 
-impl From<AccountV3> for AccountV2 {
-    fn from(value: AccountV3) -> Self {
+impl From<AccountV12> for AccountV11 {
+    fn from(value: AccountV12) -> Self {
         Self {
             id: value.id,
             imap: value.imap,
             smtp: value.smtp,
+            jmap: value.jmap,
             enabled: value.enabled,
             mailer_type: value.mailer_type,
             email: value.email,
@@ -681,6 +2330,7 @@ impl From<AccountV3> for AccountV2 {
             capabilities: value.capabilities,
             dsn_capable: value.dsn_capable,
             date_since: value.date_since,
+            folder_limit: value.folder_limit,
             sync_folders: value.sync_folders,
             full_sync_interval_min: value.full_sync_interval_min,
             incremental_sync_interval_sec: value.incremental_sync_interval_sec,
@@ -688,6 +2338,13 @@ impl From<AccountV3> for AccountV2 {
             created_at: value.created_at,
             updated_at: value.updated_at,
             use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy,
+            attachment_policy: value.attachment_policy,
+            send_rate_limit: value.send_rate_limit,
+            sync_policy: value.sync_policy,
         }
     }
 }
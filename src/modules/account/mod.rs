@@ -2,9 +2,15 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+pub mod dashboard;
 pub mod dispatcher;
 pub mod entity;
+pub mod inactive;
+pub mod maintenance;
 pub mod payload;
+pub mod profile;
 pub mod since;
 pub mod status;
+pub mod sync_policy;
+pub mod tags;
 pub mod migration;
@@ -4,11 +4,17 @@
 
 use std::collections::BTreeSet;
 
-use crate::modules::account::entity::{ImapConfig, MailerType, SmtpConfig};
+use crate::modules::account::entity::{ImapConfig, JmapConfig, MailerType, SmtpConfig};
 use crate::modules::account::migration::AccountModel;
 use crate::modules::account::since::DateSince;
+use crate::modules::account::sync_policy::SyncPolicy;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::error::RustMailerResult;
+use crate::modules::message::attachment_policy::AttachmentPolicy;
+use crate::modules::smtp::loop_guard::LoopProtection;
+use crate::modules::smtp::queue::rate::SendRateLimit;
+use crate::modules::smtp::sent::SentCopyPolicy;
+use crate::modules::smtp::util::validate_message_id_domain;
 use crate::modules::token::AccountInfo;
 use crate::{raise_error, validate_email};
 use poem_openapi::Object;
@@ -28,6 +34,8 @@ pub struct AccountCreateRequest {
     pub imap: Option<ImapConfig>,
     /// SMTP server configuration
     pub smtp: Option<SmtpConfig>,
+    /// JMAP server configuration, required when `mailer_type` is `Jmap`
+    pub jmap: Option<JmapConfig>,
     /// Represents the account activation status.
     ///
     /// If this value is `false`, all account-related resources will be unavailable
@@ -69,6 +77,33 @@ pub struct AccountCreateRequest {
     /// - If `None` or not provided, the client will connect directly to the API server.
     /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
     pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    #[oai(validator(max_length = 253, pattern = r"^[a-zA-Z0-9\-\.]+$"))]
+    pub message_id_domain: Option<String>,
+    /// Free-form labels used to group and select accounts, such as `prod`,
+    /// `customer:acme` or `region:eu`.
+    pub tags: Option<Vec<String>>,
+    /// How the copy of a sent email is stored when `save_to_sent` is requested.
+    /// If not set, the email is always appended to the Sent folder.
+    pub sent_copy: Option<SentCopyPolicy>,
+    /// Screening of dangerous attachment types in outgoing and received emails.
+    /// If not set, attachments are not screened.
+    pub attachment_policy: Option<AttachmentPolicy>,
+    /// Outbound send rate limit of the account, such as 100 emails per hour.
+    /// If not set, emails are sent as fast as the queue allows.
+    pub send_rate_limit: Option<SendRateLimit>,
+    /// Folder include/exclude patterns, maximum message age and per-folder sync frequency.
+    /// If not set, the folders of `sync_folders` are synced within `date_since`.
+    pub sync_policy: Option<SyncPolicy>,
+    /// The workspace to create the account in. Only root can choose it: accounts created
+    /// with an access token scoped to a workspace always belong to that workspace.
+    pub workspace_id: Option<u64>,
 }
 
 impl AccountCreateRequest {
@@ -76,6 +111,18 @@ impl AccountCreateRequest {
         if let Some(date_since) = self.date_since.as_ref() {
             date_since.validate()?;
         }
+        if let Some(domain) = self.message_id_domain.as_deref() {
+            validate_message_id_domain(domain)?;
+        }
+        if let Some(policy) = self.attachment_policy.as_ref() {
+            policy.validate()?;
+        }
+        if let Some(limit) = self.send_rate_limit.as_ref() {
+            limit.validate()?;
+        }
+        if let Some(policy) = self.sync_policy.as_ref() {
+            policy.validate()?;
+        }
         if matches!(self.mailer_type, MailerType::ImapSmtp) {
             if self.imap.is_none() || self.smtp.is_none() {
                 return Err(raise_error!(
@@ -96,6 +143,17 @@ impl AccountCreateRequest {
                 ));
             }
         }
+        if matches!(self.mailer_type, MailerType::Jmap) {
+            let jmap = self.jmap.as_ref().ok_or_else(|| {
+                raise_error!(
+                    "Invalid input: 'jmap' must be provided.".into(),
+                    ErrorCode::InvalidParameter
+                )
+            })?;
+            jmap.validate()
+                .map_err(|e| raise_error!(e.to_owned(), ErrorCode::InvalidParameter))?;
+            validate_email!(&self.email)?;
+        }
         Ok(AccountModel::create(self)?)
     }
 
@@ -126,6 +184,8 @@ pub struct AccountUpdateRequest {
     pub imap: Option<ImapConfig>,
     /// SMTP server configuration
     pub smtp: Option<SmtpConfig>,
+    /// JMAP server configuration
+    pub jmap: Option<JmapConfig>,
     /// Controls initial synchronization time range
     ///
     /// When dealing with large mailboxes, this restricts scanning to:
@@ -153,6 +213,9 @@ pub struct AccountUpdateRequest {
     ///   Since label names can be easily changed, the stable `labelId` is recorded here
     ///   instead of the label name.
     ///
+    /// - For JMAP accounts:
+    ///   Mailbox names are given here and the stable JMAP mailbox ids are recorded.
+    ///
     /// Defaults to standard folders (`INBOX`, `Sent`) if empty.
     /// Modified folders will be automatically synced on the next update.
     pub sync_folders: Option<Vec<String>>,
@@ -166,6 +229,27 @@ pub struct AccountUpdateRequest {
     /// - If `None` or not provided, the client will connect directly to the API server.
     /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID for API requests.
     pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    ///
+    /// If not set, no loop detection is performed for this account.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    ///
+    /// If not set, the domain of the sender address is used.
+    #[oai(validator(max_length = 253, pattern = r"^[a-zA-Z0-9\-\.]+$"))]
+    pub message_id_domain: Option<String>,
+    /// Free-form labels used to group and select accounts, such as `prod`,
+    /// `customer:acme` or `region:eu`. Replaces all existing tags of the account.
+    pub tags: Option<Vec<String>>,
+    /// How the copy of a sent email is stored when `save_to_sent` is requested.
+    pub sent_copy: Option<SentCopyPolicy>,
+    /// Screening of dangerous attachment types in outgoing and received emails.
+    pub attachment_policy: Option<AttachmentPolicy>,
+    /// Outbound send rate limit of the account, such as 100 emails per hour.
+    pub send_rate_limit: Option<SendRateLimit>,
+    /// Folder include/exclude patterns, maximum message age and per-folder sync frequency.
+    /// Replaces the current sync policy; excluded folders leave the cache on the next sync.
+    pub sync_policy: Option<SyncPolicy>,
 }
 
 impl AccountUpdateRequest {
@@ -173,6 +257,22 @@ impl AccountUpdateRequest {
         if let Some(date_since) = self.date_since.as_ref() {
             date_since.validate()?;
         }
+        if let Some(domain) = self.message_id_domain.as_deref() {
+            validate_message_id_domain(domain)?;
+        }
+        if let Some(policy) = self.attachment_policy.as_ref() {
+            policy.validate()?;
+        }
+        if let Some(limit) = self.send_rate_limit.as_ref() {
+            limit.validate()?;
+        }
+        if let Some(policy) = self.sync_policy.as_ref() {
+            policy.validate()?;
+        }
+        if let Some(jmap) = self.jmap.as_ref() {
+            jmap.validate()
+                .map_err(|e| raise_error!(e.to_owned(), ErrorCode::InvalidParameter))?;
+        }
 
         if let Some(mailboxes) = self.sync_folders.as_ref() {
             if mailboxes.is_empty() {
@@ -193,6 +293,7 @@ pub struct MinimalAccount {
     pub id: u64,
     pub email: String,
     pub mailer_type: MailerType,
+    pub tags: Vec<String>,
 }
 
 pub fn filter_accessible_accounts<'a>(
@@ -0,0 +1,529 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::account::entity::{ImapConfig, JmapConfig, MailerType, SmtpConfig};
+use crate::modules::account::migration::AccountModel;
+use crate::modules::account::payload::{AccountCreateRequest, AccountUpdateRequest};
+use crate::modules::account::since::DateSince;
+use crate::modules::account::sync_policy::SyncPolicy;
+use crate::modules::account::tags::normalize_tags;
+use crate::modules::common::auth::ClientContext;
+use crate::modules::common::paginated::paginate_vec;
+use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::{
+    delete_impl, insert_impl, list_all_impl, secondary_find_impl, update_impl,
+};
+use crate::modules::error::code::ErrorCode;
+use crate::modules::error::RustMailerResult;
+use crate::modules::message::attachment_policy::AttachmentPolicy;
+use crate::modules::overview::memory::check_metadata_capacity;
+use crate::modules::rest::response::DataPage;
+use crate::modules::retention::entity::CleanupRule;
+use crate::modules::retention::payload::CleanupRuleCreateRequest;
+use crate::modules::smtp::loop_guard::LoopProtection;
+use crate::modules::smtp::queue::rate::SendRateLimit;
+use crate::modules::smtp::sent::SentCopyPolicy;
+use crate::modules::token::AccessToken;
+use crate::{id, raise_error, utc_now};
+
+/// Full sync interval of accounts created from a profile that does not set one.
+const DEFAULT_FULL_SYNC_INTERVAL_MIN: i64 = 30;
+/// Incremental sync interval of accounts created from a profile that does not set one.
+const DEFAULT_INCREMENTAL_SYNC_INTERVAL_SEC: i64 = 60;
+
+/// A named set of account settings, such as the synced folders, sync schedule, retention
+/// rules and tags, used to provision accounts the same way and to keep them aligned.
+///
+/// Event hooks are bound to accounts through tags: a global hook with `account_tags`
+/// receives the events of every account created from, or re-applied with, a profile
+/// carrying these tags.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 28, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct AccountProfile {
+    /// Unique identifier of the profile.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Name of the profile, e.g. "Support mailboxes".
+    pub name: String,
+    /// Optional description of the profile.
+    pub description: Option<String>,
+    /// The settings given to accounts using the profile.
+    pub settings: AccountProfileSettings,
+    /// Timestamp of when the profile was created (in Unix epoch milliseconds).
+    pub created_at: i64,
+    /// Timestamp of when the profile was last updated (in Unix epoch milliseconds).
+    pub updated_at: i64,
+}
+
+/// The account settings of a profile. Settings left unset keep the defaults on account
+/// creation and are left unchanged when the profile is re-applied.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccountProfileSettings {
+    /// The folders (mailboxes or labels) to sync, by name.
+    pub sync_folders: Option<Vec<String>>,
+    /// Controls the initial synchronization time range.
+    pub date_since: Option<DateSince>,
+    /// Max emails to sync per folder.
+    #[oai(validator(minimum(value = "100")))]
+    pub folder_limit: Option<u32>,
+    /// Minimal sync mode flag. Only applied when creating accounts.
+    pub minimal_sync: Option<bool>,
+    /// Full sync interval (minutes), default 30m.
+    #[oai(validator(minimum(value = "10"), maximum(value = "10080")))]
+    pub full_sync_interval_min: Option<i64>,
+    /// Incremental sync interval (seconds), default 60s.
+    #[oai(validator(minimum(value = "10"), maximum(value = "3600")))]
+    pub incremental_sync_interval_sec: Option<i64>,
+    /// Optional proxy ID for connections to provider APIs.
+    pub use_proxy: Option<u64>,
+    /// Mail loop and auto-responder protection applied to outgoing emails.
+    pub loop_protection: Option<LoopProtection>,
+    /// Domain used for the right-hand side of generated `Message-ID` headers.
+    #[oai(validator(max_length = 253, pattern = r"^[a-zA-Z0-9\-\.]+$"))]
+    pub message_id_domain: Option<String>,
+    /// Tags added to the accounts. Tags the accounts already carry are kept.
+    pub tags: Option<Vec<String>>,
+    /// How the copy of a sent email is stored.
+    pub sent_copy: Option<SentCopyPolicy>,
+    /// Screening of dangerous attachment types.
+    pub attachment_policy: Option<AttachmentPolicy>,
+    /// Outbound send rate limit of each account.
+    pub send_rate_limit: Option<SendRateLimit>,
+    /// Folder include/exclude patterns, maximum message age and per-folder sync frequency.
+    pub sync_policy: Option<SyncPolicy>,
+    /// Cleanup rules created for the accounts, matched by name on re-apply so existing
+    /// rules are not duplicated. Rules are created disabled and must be dry run before
+    /// they can be enabled.
+    pub cleanup_rules: Option<Vec<CleanupRuleCreateRequest>>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccountProfileCreateRequest {
+    /// Name of the profile.
+    pub name: String,
+    /// Optional description of the profile.
+    pub description: Option<String>,
+    /// The settings given to accounts using the profile.
+    pub settings: AccountProfileSettings,
+}
+
+/// Changes to a profile. Fields left unset are unchanged; `settings` replaces all settings.
+/// Accounts already using the profile are not changed until it is re-applied.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccountProfileUpdateRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub settings: Option<AccountProfileSettings>,
+}
+
+/// The account-specific part of an account created from a profile.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ProfileAccountCreateRequest {
+    /// Email address of the account.
+    #[oai(validator(custom = "crate::modules::common::validator::EmailValidator"))]
+    pub email: String,
+    /// Display name for the account (optional)
+    pub name: Option<String>,
+    /// Method used to access and manage emails.
+    pub mailer_type: MailerType,
+    /// IMAP server configuration
+    pub imap: Option<ImapConfig>,
+    /// SMTP server configuration
+    pub smtp: Option<SmtpConfig>,
+    /// JMAP server configuration, required when `mailer_type` is `Jmap`
+    pub jmap: Option<JmapConfig>,
+    /// Whether the account starts enabled. Defaults to `true`.
+    pub enabled: Option<bool>,
+    /// Tags added to the tags of the profile.
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ProfileAccountCreateResult {
+    /// The created account.
+    pub account: AccountModel,
+    /// IDs of the cleanup rules created for the account.
+    pub cleanup_rules: Vec<u64>,
+    /// Settings of the profile that could not be applied. The account is created
+    /// regardless.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccountProfileApplyRequest {
+    /// The accounts to apply the profile to.
+    pub account_ids: Vec<u64>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccountProfileApplyResult {
+    /// Accounts the settings were applied to.
+    pub applied: Vec<u64>,
+    /// Accounts the profile could not be applied to, with the reason.
+    pub failed: Vec<AccountProfileApplyError>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccountProfileApplyError {
+    pub account_id: u64,
+    pub error: String,
+}
+
+impl AccountProfile {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    pub fn new(request: AccountProfileCreateRequest) -> RustMailerResult<Self> {
+        let now = utc_now!();
+        let profile = Self {
+            id: id!(64),
+            name: request.name,
+            description: request.description,
+            settings: request.settings,
+            created_at: now,
+            updated_at: now,
+        };
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    fn validate(&self) -> RustMailerResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(raise_error!(
+                "field: name is empty.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        self.settings
+            .update_request(&[])?
+            .validate_update_request()?;
+        if let Some(rules) = &self.settings.cleanup_rules {
+            for rule in rules {
+                if rule.name.trim().is_empty() {
+                    return Err(raise_error!(
+                        "Cleanup rules of a profile must have a name.".into(),
+                        ErrorCode::InvalidParameter
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn save(self) -> RustMailerResult<()> {
+        check_metadata_capacity()?;
+        insert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    pub async fn get(id: u64) -> RustMailerResult<AccountProfile> {
+        secondary_find_impl(DB_MANAGER.meta_db(), AccountProfileKey::id, id)
+            .await?
+            .ok_or_else(|| {
+                raise_error!(
+                    format!("Account profile id='{id}' not found."),
+                    ErrorCode::ResourceNotFound
+                )
+            })
+    }
+
+    pub async fn paginate_list(
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<AccountProfile>> {
+        let mut profiles: Vec<AccountProfile> = list_all_impl(DB_MANAGER.meta_db()).await?;
+        profiles.sort_by_key(|p| (p.created_at, p.id));
+        if desc.unwrap_or(false) {
+            profiles.reverse();
+        }
+        paginate_vec(&profiles, page, page_size).map(DataPage::from)
+    }
+
+    pub async fn update(
+        id: u64,
+        request: AccountProfileUpdateRequest,
+    ) -> RustMailerResult<AccountProfile> {
+        let updated = Self::get(id).await?.apply(request);
+        updated.validate()?;
+        let profile = updated.clone();
+        update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .secondary::<AccountProfile>(AccountProfileKey::id, id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!("The account profile with id={id} that you want to modify was not found."),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            move |_| Ok(profile),
+        )
+        .await?;
+        Ok(updated)
+    }
+
+    fn apply(mut self, request: AccountProfileUpdateRequest) -> Self {
+        if let Some(name) = request.name {
+            self.name = name;
+        }
+        if request.description.is_some() {
+            self.description = request.description;
+        }
+        if let Some(settings) = request.settings {
+            self.settings = settings;
+        }
+        self.updated_at = utc_now!();
+        self
+    }
+
+    pub async fn remove(id: u64) -> RustMailerResult<()> {
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<AccountProfile>(AccountProfileKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!("The account profile with id={id} that you want to delete was not found."),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await
+    }
+
+    /// Creates an account with the settings of the profile, then its synced folders and
+    /// cleanup rules. The access token `grant_to`, if any, is granted access to the account.
+    pub async fn create_account(
+        &self,
+        request: ProfileAccountCreateRequest,
+        grant_to: Option<&AccessToken>,
+    ) -> RustMailerResult<ProfileAccountCreateResult> {
+        let create_request = self.settings.create_request(request)?;
+        let account = AccountModel::create_account(create_request, grant_to).await?;
+
+        let mut warnings = Vec::new();
+        if let Some(sync_folders) = self.settings.sync_folders.clone() {
+            let request = AccountUpdateRequest {
+                sync_folders: Some(sync_folders),
+                ..Default::default()
+            };
+            if let Err(e) = AccountModel::update(account.id, request, true, None).await {
+                warnings.push(format!("Failed to set the synced folders: {e}"));
+            }
+        }
+        let (cleanup_rules, errors) = self.create_cleanup_rules(account.id).await;
+        warnings.extend(errors);
+        Ok(ProfileAccountCreateResult {
+            account: AccountModel::get(account.id).await?,
+            cleanup_rules,
+            warnings,
+        })
+    }
+
+    /// Applies the settings of the profile to each account accessible to `context`. Tags
+    /// are added to the existing ones and cleanup rules the account already has, by name,
+    /// are left unchanged.
+    pub async fn apply_to_accounts(
+        &self,
+        account_ids: Vec<u64>,
+        context: &ClientContext,
+    ) -> RustMailerResult<AccountProfileApplyResult> {
+        if account_ids.is_empty() {
+            return Err(raise_error!(
+                "At least one account is required.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let mut result = AccountProfileApplyResult::default();
+        for account_id in account_ids {
+            match self.apply_to_account(account_id, context).await {
+                Ok(()) => result.applied.push(account_id),
+                Err(e) => result.failed.push(AccountProfileApplyError {
+                    account_id,
+                    error: e.to_string(),
+                }),
+            }
+        }
+        Ok(result)
+    }
+
+    async fn apply_to_account(
+        &self,
+        account_id: u64,
+        context: &ClientContext,
+    ) -> RustMailerResult<()> {
+        context.require_account_access(account_id)?;
+        let account = AccountModel::get(account_id).await?;
+        let request = self.settings.update_request(&account.tags)?;
+        AccountModel::update(account_id, request, true, None).await?;
+        let (_, errors) = self.create_cleanup_rules(account_id).await;
+        if !errors.is_empty() {
+            return Err(raise_error!(errors.join("; "), ErrorCode::InvalidParameter));
+        }
+        Ok(())
+    }
+
+    /// Creates the cleanup rules of the profile the account does not have yet, returning
+    /// the IDs of the created rules and the rules that failed.
+    async fn create_cleanup_rules(&self, account_id: u64) -> (Vec<u64>, Vec<String>) {
+        let Some(rules) = &self.settings.cleanup_rules else {
+            return (Vec::new(), Vec::new());
+        };
+        let existing: Vec<String> = match CleanupRule::list_all().await {
+            Ok(existing) => existing
+                .into_iter()
+                .filter(|rule| rule.account_id == account_id)
+                .map(|rule| rule.name)
+                .collect(),
+            Err(e) => {
+                return (
+                    Vec::new(),
+                    vec![format!("Failed to list cleanup rules: {e}")],
+                )
+            }
+        };
+        let mut created = Vec::new();
+        let mut errors = Vec::new();
+        for request in rules {
+            if existing.contains(&request.name) {
+                continue;
+            }
+            let rule = match CleanupRule::new(account_id, request.clone()).await {
+                Ok(rule) => rule,
+                Err(e) => {
+                    errors.push(format!("Cleanup rule '{}': {e}", request.name));
+                    continue;
+                }
+            };
+            let id = rule.id;
+            match rule.save().await {
+                Ok(()) => created.push(id),
+                Err(e) => errors.push(format!("Cleanup rule '{}': {e}", request.name)),
+            }
+        }
+        (created, errors)
+    }
+}
+
+impl AccountProfileSettings {
+    /// The request creating an account with these settings. Synced folders and cleanup
+    /// rules are applied once the account exists.
+    pub fn create_request(
+        &self,
+        request: ProfileAccountCreateRequest,
+    ) -> RustMailerResult<AccountCreateRequest> {
+        let mut tags = self.tags.clone().unwrap_or_default();
+        tags.extend(request.tags.unwrap_or_default());
+        Ok(AccountCreateRequest {
+            email: request.email,
+            name: request.name,
+            imap: request.imap,
+            smtp: request.smtp,
+            jmap: request.jmap,
+            enabled: request.enabled.unwrap_or(true),
+            mailer_type: request.mailer_type,
+            date_since: self.date_since.clone(),
+            folder_limit: self.folder_limit,
+            minimal_sync: self.minimal_sync,
+            full_sync_interval_min: Some(
+                self.full_sync_interval_min
+                    .unwrap_or(DEFAULT_FULL_SYNC_INTERVAL_MIN),
+            ),
+            incremental_sync_interval_sec: self
+                .incremental_sync_interval_sec
+                .unwrap_or(DEFAULT_INCREMENTAL_SYNC_INTERVAL_SEC),
+            use_proxy: self.use_proxy,
+            loop_protection: self.loop_protection.clone(),
+            message_id_domain: self.message_id_domain.clone(),
+            tags: Some(normalize_tags(tags)?),
+            sent_copy: self.sent_copy,
+            attachment_policy: self.attachment_policy.clone(),
+            send_rate_limit: self.send_rate_limit.clone(),
+            sync_policy: self.sync_policy.clone(),
+            workspace_id: None,
+        })
+    }
+
+    /// The update applying these settings to an account carrying `current_tags`.
+    pub fn update_request(
+        &self,
+        current_tags: &[String],
+    ) -> RustMailerResult<AccountUpdateRequest> {
+        let tags = match &self.tags {
+            Some(tags) => {
+                let mut merged = current_tags.to_vec();
+                merged.extend(tags.iter().cloned());
+                Some(normalize_tags(merged)?)
+            }
+            None => None,
+        };
+        Ok(AccountUpdateRequest {
+            date_since: self.date_since.clone(),
+            folder_limit: self.folder_limit,
+            sync_folders: self.sync_folders.clone(),
+            full_sync_interval_min: self.full_sync_interval_min,
+            incremental_sync_interval_sec: self.incremental_sync_interval_sec,
+            use_proxy: self.use_proxy,
+            loop_protection: self.loop_protection.clone(),
+            message_id_domain: self.message_id_domain.clone(),
+            tags,
+            sent_copy: self.sent_copy,
+            attachment_policy: self.attachment_policy.clone(),
+            send_rate_limit: self.send_rate_limit.clone(),
+            sync_policy: self.sync_policy.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_account_requests_from_settings() {
+        let settings = AccountProfileSettings {
+            sync_folders: Some(vec!["INBOX".into(), "Support".into()]),
+            folder_limit: Some(1000),
+            tags: Some(vec!["support".into(), "region:eu".into()]),
+            ..Default::default()
+        };
+
+        let create = settings
+            .create_request(ProfileAccountCreateRequest {
+                email: "help@example.com".into(),
+                mailer_type: MailerType::ImapSmtp,
+                tags: Some(vec!["Customer:Acme".into()]),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(create.enabled);
+        assert_eq!(create.folder_limit, Some(1000));
+        assert_eq!(create.full_sync_interval_min, Some(30));
+        assert_eq!(create.incremental_sync_interval_sec, 60);
+        assert_eq!(
+            create.tags.unwrap(),
+            vec!["customer:acme", "region:eu", "support"]
+        );
+
+        let update = settings.update_request(&["vip".into()]).unwrap();
+        assert_eq!(update.sync_folders, settings.sync_folders);
+        assert_eq!(update.tags.unwrap(), vec!["region:eu", "support", "vip"]);
+        assert_eq!(update.enabled, None);
+        assert_eq!(update.full_sync_interval_min, None);
+
+        let untagged = AccountProfileSettings::default();
+        assert_eq!(untagged.update_request(&["vip".into()]).unwrap().tags, None);
+    }
+}
@@ -308,6 +308,33 @@ impl DateSince {
             ))
         }
     }
+
+    /// The JMAP `UTCDate` (e.g. `2024-11-19T00:00:00Z`) used in `Email/query` filters.
+    pub fn since_jmap_date(&self) -> RustMailerResult<String> {
+        self.since_outlook_date()
+    }
+
+    /// The first day of the range, in local time for relative dates.
+    pub fn naive_date(&self) -> RustMailerResult<NaiveDate> {
+        if let Some(r) = &self.relative {
+            Ok(r.compute_date()?.date_naive())
+        } else if let Some(f) = &self.fixed {
+            NaiveDate::parse_from_str(f, "%Y-%m-%d").map_err(|_| {
+                raise_error!(
+                    format!(
+                        "Invalid date format. Expected 'YYYY-MM-DD'. Example: '2024-11-19'. Provided: '{}'",
+                        f
+                    ),
+                    ErrorCode::InvalidParameter
+                )
+            })
+        } else {
+            Err(raise_error!(
+                "You must provide either a 'fixed' or 'relative' date.".to_string(),
+                ErrorCode::InvalidParameter
+            ))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -4,8 +4,11 @@
 
 use crate::{
     modules::{
-        database::{async_find_impl, delete_impl, manager::DB_MANAGER, update_impl, upsert_impl},
-        error::{code::ErrorCode, RustMailerResult},
+        database::{
+            async_find_impl, batch::WriteBatch, manager::DB_MANAGER, update_impl,
+            upsert_impl,
+        },
+        error::{code::ErrorCode, provider::ProviderErrorKind, RustMailerResult},
     },
     raise_error, utc_now,
 };
@@ -16,9 +19,35 @@ use serde::{Deserialize, Serialize};
 
 const ERROR_COUNT_PER_ACCOUNT: usize = 20;
 
-#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
 #[native_model(id = 13, version = 1)]
 #[native_db]
+pub struct AccountRunningStateV1 {
+    #[primary_key]
+    pub account_id: u64,
+    pub last_full_sync_start: i64,
+    pub last_full_sync_end: Option<i64>,
+    pub last_incremental_sync_start: i64,
+    pub last_incremental_sync_end: Option<i64>,
+    pub errors: Vec<AccountErrorV1>,
+    pub is_initial_sync_completed: bool,
+    pub initial_sync_folders: Vec<String>,
+    pub current_syncing_folder: Option<String>,
+    pub current_batch_number: Option<u32>,
+    pub current_total_batches: Option<u32>,
+    pub initial_sync_start_time: Option<i64>,
+    pub initial_sync_end_time: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct AccountErrorV1 {
+    pub error: String,
+    pub at: i64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 13, version = 2, from = AccountRunningStateV1)]
+#[native_db]
 pub struct AccountRunningState {
     #[primary_key]
     pub account_id: u64,
@@ -40,6 +69,22 @@ pub struct AccountRunningState {
 pub struct AccountError {
     pub error: String,
     pub at: i64,
+    /// The provider failure recognized in `error`, if any.
+    pub kind: Option<ProviderErrorKind>,
+    /// What the user should do to fix a recognized provider failure.
+    pub remediation: Option<String>,
+}
+
+impl AccountError {
+    pub fn new(error: String, at: i64) -> Self {
+        let kind = ProviderErrorKind::classify(&error);
+        Self {
+            error,
+            at,
+            kind,
+            remediation: kind.map(|k| k.remediation().into()),
+        }
+    }
 }
 
 impl AccountRunningState {
@@ -91,26 +136,15 @@ impl AccountRunningState {
         Ok(())
     }
 
-    pub async fn delete(account_id: u64) -> RustMailerResult<()> {
-        if Self::get(account_id).await?.is_none() {
-            return Ok(());
-        }
-
-        delete_impl(DB_MANAGER.meta_db(), move |rw| {
-            rw.get()
+    /// Adds the removal of the running state of an account, if any, to `batch`.
+    pub fn stage_delete(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let state = rw
+                .get()
                 .primary::<AccountRunningState>(account_id)
-                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-                .ok_or_else(|| {
-                    raise_error!(
-                        format!(
-                            "AccountRunningState '{}' not found during deletion process.",
-                            account_id
-                        ),
-                        ErrorCode::ResourceNotFound
-                    )
-                })
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(state.into_iter().collect())
         })
-        .await
     }
 
     pub async fn set_initial_sync_folders(
@@ -211,18 +245,64 @@ impl AccountRunningState {
     }
 
     pub fn append_error_log(&mut self, error: String) {
-        let new_error = AccountError {
-            error,
-            at: utc_now!(),
-        };
-
-        self.errors.push(new_error);
+        self.errors.push(AccountError::new(error, utc_now!()));
         if self.errors.len() > ERROR_COUNT_PER_ACCOUNT {
             self.errors.remove(0);
         }
     }
 }
 
+impl From<AccountRunningStateV1> for AccountRunningState {
+    fn from(value: AccountRunningStateV1) -> Self {
+        Self {
+            account_id: value.account_id,
+            last_full_sync_start: value.last_full_sync_start,
+            last_full_sync_end: value.last_full_sync_end,
+            last_incremental_sync_start: value.last_incremental_sync_start,
+            last_incremental_sync_end: value.last_incremental_sync_end,
+            errors: value
+                .errors
+                .into_iter()
+                .map(|e| AccountError::new(e.error, e.at))
+                .collect(),
+            is_initial_sync_completed: value.is_initial_sync_completed,
+            initial_sync_folders: value.initial_sync_folders,
+            current_syncing_folder: value.current_syncing_folder,
+            current_batch_number: value.current_batch_number,
+            current_total_batches: value.current_total_batches,
+            initial_sync_start_time: value.initial_sync_start_time,
+            initial_sync_end_time: value.initial_sync_end_time,
+        }
+    }
+}
+
+impl From<AccountRunningState> for AccountRunningStateV1 {
+    fn from(value: AccountRunningState) -> Self {
+        Self {
+            account_id: value.account_id,
+            last_full_sync_start: value.last_full_sync_start,
+            last_full_sync_end: value.last_full_sync_end,
+            last_incremental_sync_start: value.last_incremental_sync_start,
+            last_incremental_sync_end: value.last_incremental_sync_end,
+            errors: value
+                .errors
+                .into_iter()
+                .map(|e| AccountErrorV1 {
+                    error: e.error,
+                    at: e.at,
+                })
+                .collect(),
+            is_initial_sync_completed: value.is_initial_sync_completed,
+            initial_sync_folders: value.initial_sync_folders,
+            current_syncing_folder: value.current_syncing_folder,
+            current_batch_number: value.current_batch_number,
+            current_total_batches: value.current_total_batches,
+            initial_sync_start_time: value.initial_sync_start_time,
+            initial_sync_end_time: value.initial_sync_end_time,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -244,6 +324,18 @@ mod tests {
         assert_eq!(account_state.errors[0].error, "Error 1");
     }
 
+    #[test]
+    fn test_classifies_provider_errors() {
+        let mut account_state = AccountRunningState::default();
+        account_state.append_error_log("imap client authenticate error: invalid_grant".into());
+        account_state.append_error_log("imap client connect error: timed out".into());
+        let error = &account_state.errors[0];
+        assert_eq!(error.kind, Some(ProviderErrorKind::OAuth2Revoked));
+        assert!(error.remediation.is_some());
+        assert_eq!(account_state.errors[1].kind, None);
+        assert_eq!(account_state.errors[1].remediation, None);
+    }
+
     #[test]
     fn test_insert_multiple_errors() {
         let mut account_state = AccountRunningState {
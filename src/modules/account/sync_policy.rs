@@ -0,0 +1,327 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::sync::LazyLock;
+
+use dashmap::DashMap;
+use glob::{MatchOptions, Pattern};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modules::{
+        account::since::{DateSince, RelativeDate, Unit},
+        error::{code::ErrorCode, RustMailerResult},
+    },
+    raise_error, utc_now,
+};
+
+/// Maximum number of patterns in each list of a sync policy.
+const MAX_PATTERNS: usize = 100;
+/// Maximum length of a folder pattern.
+const MAX_PATTERN_LENGTH: usize = 255;
+/// Upper bound of `max_message_age_days`, about 50 years.
+const MAX_MESSAGE_AGE_DAYS: u32 = 18250;
+/// Upper bound of a folder sync interval, one week.
+const MAX_FOLDER_INTERVAL_SEC: u64 = 604800;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// Time (Unix epoch milliseconds) of the last sync of each folder,
+/// keyed by account and folder name.
+static LAST_FOLDER_SYNC: LazyLock<DashMap<(u64, String), i64>> = LazyLock::new(DashMap::new);
+
+/// Fine-grained control over what the synchronization of an account fetches.
+///
+/// Folder patterns are matched against folder names (label names for Gmail API accounts),
+/// ignoring case. `*` matches any sequence of characters, including the hierarchy
+/// delimiter, `?` any single character and `[...]` any character of a set, e.g.
+/// `Archive/*` or `[Gmail]/Spam`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SyncPolicy {
+    /// Folders synced in addition to `sync_folders`, such as `Projects/*`.
+    pub include: Vec<String>,
+    /// Folders never synced, even when listed in `sync_folders` or matched by `include`,
+    /// such as `Trash` or `Archive/*`. Cached emails of excluded folders are removed.
+    pub exclude: Vec<String>,
+    /// Only emails received within this many days are synced. Combined with `date_since`,
+    /// the later of both boundaries applies. Older emails leave the cache on the next full
+    /// sync.
+    #[oai(validator(minimum(value = "1"), maximum(value = "18250")))]
+    pub max_message_age_days: Option<u32>,
+    /// Sync frequency of individual folders, the first matching entry applying.
+    ///
+    /// A folder is synced at most once per interval, so rarely used folders can be checked
+    /// less often than the account's sync intervals. Intervals shorter than the sync
+    /// intervals of the account have no effect.
+    ///
+    /// **Note:** Only applies to IMAP and Graph API accounts; Gmail API and JMAP accounts
+    /// fetch the changes of all folders at once.
+    pub folder_intervals: Vec<FolderSyncInterval>,
+}
+
+/// The sync frequency of the folders matching a pattern.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct FolderSyncInterval {
+    /// Pattern of the folder names the interval applies to, e.g. `Archive/*`.
+    pub pattern: String,
+    /// Minimum time between two syncs of a matching folder, in seconds.
+    #[oai(validator(minimum(value = "1"), maximum(value = "604800")))]
+    pub interval_sec: u64,
+}
+
+impl SyncPolicy {
+    pub fn validate(&self) -> RustMailerResult<()> {
+        for (field, patterns) in [("include", &self.include), ("exclude", &self.exclude)] {
+            if patterns.len() > MAX_PATTERNS {
+                return Err(raise_error!(
+                    format!("'{field}' of a sync policy accepts at most {MAX_PATTERNS} patterns"),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+            for pattern in patterns {
+                validate_pattern(pattern)?;
+            }
+        }
+        if let Some(days) = self.max_message_age_days {
+            if days == 0 || days > MAX_MESSAGE_AGE_DAYS {
+                return Err(raise_error!(
+                    format!(
+                        "'max_message_age_days' of a sync policy must be between 1 and {MAX_MESSAGE_AGE_DAYS}"
+                    ),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+        if self.folder_intervals.len() > MAX_PATTERNS {
+            return Err(raise_error!(
+                format!(
+                    "'folder_intervals' of a sync policy accepts at most {MAX_PATTERNS} entries"
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        for interval in &self.folder_intervals {
+            validate_pattern(&interval.pattern)?;
+            if interval.interval_sec == 0 || interval.interval_sec > MAX_FOLDER_INTERVAL_SEC {
+                return Err(raise_error!(
+                    format!(
+                        "'interval_sec' of a folder sync interval must be between 1 and {MAX_FOLDER_INTERVAL_SEC}"
+                    ),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the folder `name` is synced, `subscribed` telling whether it is selected by
+    /// `sync_folders` (or the default folders).
+    pub fn is_synced(&self, name: &str, subscribed: bool) -> bool {
+        if any_matches(&self.exclude, name) {
+            return false;
+        }
+        subscribed || any_matches(&self.include, name)
+    }
+
+    /// The sync interval of the folder `name`, in seconds, if one applies.
+    pub fn folder_interval(&self, name: &str) -> Option<u64> {
+        self.folder_intervals
+            .iter()
+            .find(|interval| matches(&interval.pattern, name))
+            .map(|interval| interval.interval_sec)
+    }
+
+    /// The start of the sync window: the later of `date_since` and the maximum message age.
+    pub fn effective_date_since(
+        &self,
+        date_since: Option<&DateSince>,
+    ) -> RustMailerResult<Option<DateSince>> {
+        let max_age = self.max_message_age_days.map(|days| DateSince {
+            fixed: None,
+            relative: Some(RelativeDate {
+                unit: Unit::Days,
+                value: days,
+            }),
+        });
+        match (date_since, max_age) {
+            (Some(since), Some(max_age)) => {
+                if max_age.naive_date()? > since.naive_date()? {
+                    Ok(Some(max_age))
+                } else {
+                    Ok(Some(since.clone()))
+                }
+            }
+            (since, max_age) => Ok(since.cloned().or(max_age)),
+        }
+    }
+
+    /// Whether the folder `name` of the account is due to be synced, according to its
+    /// sync interval.
+    pub fn is_folder_due(&self, account_id: u64, name: &str) -> bool {
+        let Some(interval_sec) = self.folder_interval(name) else {
+            return true;
+        };
+        LAST_FOLDER_SYNC
+            .get(&(account_id, name.to_string()))
+            .is_none_or(|last| is_due(*last, interval_sec, utc_now!()))
+    }
+}
+
+/// Records a sync of the folder `name` of an account.
+pub fn record_folder_sync(account_id: u64, name: &str) {
+    LAST_FOLDER_SYNC.insert((account_id, name.to_string()), utc_now!());
+}
+
+/// The folders of an account synced since startup with the time of their last sync
+/// (Unix epoch milliseconds), sorted by name.
+pub fn folder_syncs(account_id: u64) -> Vec<(String, i64)> {
+    let mut syncs: Vec<(String, i64)> = LAST_FOLDER_SYNC
+        .iter()
+        .filter(|entry| entry.key().0 == account_id)
+        .map(|entry| (entry.key().1.clone(), *entry.value()))
+        .collect();
+    syncs.sort();
+    syncs
+}
+
+/// Forgets the folder syncs recorded for an account.
+pub fn forget_folder_syncs(account_id: u64) {
+    LAST_FOLDER_SYNC.retain(|(id, _), _| *id != account_id);
+}
+
+fn is_due(last_sync: i64, interval_sec: u64, now: i64) -> bool {
+    now - last_sync >= interval_sec as i64 * 1000
+}
+
+fn validate_pattern(pattern: &str) -> RustMailerResult<()> {
+    if pattern.trim().is_empty() || pattern.len() > MAX_PATTERN_LENGTH {
+        return Err(raise_error!(
+            format!("Folder patterns must be between 1 and {MAX_PATTERN_LENGTH} characters long"),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    Pattern::new(pattern).map_err(|e| {
+        raise_error!(
+            format!("Invalid folder pattern '{pattern}': {e}"),
+            ErrorCode::InvalidParameter
+        )
+    })?;
+    Ok(())
+}
+
+fn matches(pattern: &str, name: &str) -> bool {
+    Pattern::new(pattern)
+        .map(|p| p.matches_with(name, MATCH_OPTIONS))
+        .unwrap_or(false)
+}
+
+fn any_matches(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| matches(pattern, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> SyncPolicy {
+        SyncPolicy {
+            include: vec!["Projects/*".into()],
+            exclude: vec!["projects/old*".into(), "Trash".into()],
+            max_message_age_days: None,
+            folder_intervals: vec![
+                FolderSyncInterval {
+                    pattern: "Archive/*".into(),
+                    interval_sec: 3600,
+                },
+                FolderSyncInterval {
+                    pattern: "*".into(),
+                    interval_sec: 60,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn selects_folders() {
+        let policy = policy();
+        assert!(policy.is_synced("INBOX", true));
+        assert!(!policy.is_synced("Newsletters", false));
+        assert!(policy.is_synced("Projects/Apollo", false));
+        assert!(policy.is_synced("PROJECTS/Apollo/2025", false));
+        assert!(!policy.is_synced("Projects/Old-Apollo", false));
+        assert!(!policy.is_synced("Trash", true));
+        assert!(!policy.is_synced("trash", true));
+    }
+
+    #[test]
+    fn applies_the_first_matching_interval() {
+        let policy = policy();
+        assert_eq!(policy.folder_interval("Archive/2024"), Some(3600));
+        assert_eq!(policy.folder_interval("INBOX"), Some(60));
+        assert_eq!(SyncPolicy::default().folder_interval("INBOX"), None);
+        assert!(is_due(0, 60, 60_000));
+        assert!(!is_due(0, 60, 59_999));
+    }
+
+    #[test]
+    fn keeps_the_later_window_start() {
+        let policy = SyncPolicy {
+            max_message_age_days: Some(30),
+            ..Default::default()
+        };
+        let old = DateSince {
+            fixed: Some("2000-01-01".into()),
+            relative: None,
+        };
+        let since = policy.effective_date_since(Some(&old)).unwrap().unwrap();
+        assert_eq!(since.relative.map(|r| r.value), Some(30));
+
+        let recent = DateSince {
+            fixed: None,
+            relative: Some(RelativeDate {
+                unit: Unit::Days,
+                value: 7,
+            }),
+        };
+        assert_eq!(
+            policy.effective_date_since(Some(&recent)).unwrap(),
+            Some(recent.clone())
+        );
+        assert_eq!(
+            SyncPolicy::default()
+                .effective_date_since(Some(&recent))
+                .unwrap(),
+            Some(recent)
+        );
+        assert_eq!(
+            SyncPolicy::default().effective_date_since(None).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn validates_policies() {
+        assert!(policy().validate().is_ok());
+        let invalid = SyncPolicy {
+            exclude: vec!["[unclosed".into()],
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+        let empty = SyncPolicy {
+            include: vec![" ".into()],
+            ..Default::default()
+        };
+        assert!(empty.validate().is_err());
+        let zero = SyncPolicy {
+            max_message_age_days: Some(0),
+            ..Default::default()
+        };
+        assert!(zero.validate().is_err());
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeSet;
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modules::{
+        account::{migration::AccountModel, payload::AccountUpdateRequest},
+        common::auth::ClientContext,
+        error::{code::ErrorCode, RustMailerResult},
+    },
+    raise_error,
+};
+
+/// Maximum number of tags on a single account.
+pub const MAX_TAGS: usize = 32;
+/// Maximum length of a single tag.
+pub const MAX_TAG_LEN: usize = 64;
+
+/// Validates account tags and returns them lowercased, deduplicated and sorted.
+///
+/// Tags may contain ASCII letters, digits and `:`, `-`, `_`, `.`, `=` or `/`, so that
+/// `key:value` style labels such as `customer:acme` can be used.
+pub fn normalize_tags(tags: Vec<String>) -> RustMailerResult<Vec<String>> {
+    let mut normalized = BTreeSet::new();
+    for tag in tags {
+        let tag = tag.trim().to_ascii_lowercase();
+        if tag.is_empty() || tag.len() > MAX_TAG_LEN {
+            return Err(raise_error!(
+                format!("Tags must be between 1 and {MAX_TAG_LEN} characters long"),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if !tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '-' | '_' | '.' | '=' | '/'))
+        {
+            return Err(raise_error!(
+                format!("Invalid tag '{tag}': only letters, digits and ':-_.=/' are allowed"),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        normalized.insert(tag);
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(raise_error!(
+            format!("An account can have at most {MAX_TAGS} tags"),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    Ok(normalized.into_iter().collect())
+}
+
+/// Parses a comma-separated tag selector such as `prod,region:eu`.
+pub fn parse_tag_selector(selector: &str) -> Vec<String> {
+    selector
+        .split(',')
+        .map(|tag| tag.trim().to_ascii_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// Returns `true` if `tags` contains every tag of `selector`. An empty selector matches.
+pub fn has_all_tags(tags: &[String], selector: &[String]) -> bool {
+    selector
+        .iter()
+        .all(|wanted| tags.iter().any(|tag| tag.eq_ignore_ascii_case(wanted)))
+}
+
+/// Returns the ids of all accounts carrying every tag of `selector`.
+pub async fn account_ids_with_tags(selector: &[String]) -> RustMailerResult<BTreeSet<u64>> {
+    Ok(AccountModel::list_all()
+        .await?
+        .into_iter()
+        .filter(|account| has_all_tags(&account.tags, selector))
+        .map(|account| account.id)
+        .collect())
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccountBulkEnableRequest {
+    /// Accounts carrying all of these tags are updated. At least one tag is required.
+    pub tags: Vec<String>,
+    /// `false` pauses the matching accounts, `true` resumes them.
+    pub enabled: bool,
+    /// If `true`, only reports the matching accounts without changing them.
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccountBulkEnableResult {
+    /// IDs of the accounts matching the tags.
+    pub matched: Vec<u64>,
+    /// Number of accounts whose state was changed.
+    pub updated: u64,
+    /// Whether this was a dry run.
+    pub dry_run: bool,
+}
+
+impl AccountBulkEnableRequest {
+    /// Enables or disables all accounts accessible to the client that carry the tags.
+    pub async fn execute(
+        self,
+        context: &ClientContext,
+    ) -> RustMailerResult<AccountBulkEnableResult> {
+        let selector = parse_tag_selector(&self.tags.join(","));
+        if selector.is_empty() {
+            return Err(raise_error!(
+                "At least one tag is required to select accounts".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let accessible = context.accessible_accounts()?;
+        let accounts: Vec<AccountModel> = AccountModel::list_all()
+            .await?
+            .into_iter()
+            .filter(|account| has_all_tags(&account.tags, &selector))
+            .filter(|account| {
+                accessible.is_none_or(|allowed| allowed.iter().any(|a| a.id == account.id))
+            })
+            .collect();
+
+        let dry_run = self.dry_run.unwrap_or(false);
+        let mut updated = 0;
+        if !dry_run {
+            for account in accounts.iter().filter(|a| a.enabled != self.enabled) {
+                let request = AccountUpdateRequest {
+                    enabled: Some(self.enabled),
+                    ..Default::default()
+                };
+                AccountModel::update(account.id, request, false, None).await?;
+                updated += 1;
+            }
+        }
+        Ok(AccountBulkEnableResult {
+            matched: accounts.iter().map(|a| a.id).collect(),
+            updated,
+            dry_run,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_tags() {
+        let tags = normalize_tags(vec![
+            " Prod ".into(),
+            "customer:acme".into(),
+            "prod".into(),
+            "region:eu".into(),
+        ])
+        .unwrap();
+        assert_eq!(tags, vec!["customer:acme", "prod", "region:eu"]);
+
+        assert!(normalize_tags(vec!["".into()]).is_err());
+        assert!(normalize_tags(vec!["a,b".into()]).is_err());
+        assert!(normalize_tags(vec!["with space".into()]).is_err());
+        assert!(normalize_tags(vec!["x".repeat(MAX_TAG_LEN + 1)]).is_err());
+        assert!(normalize_tags((0..=MAX_TAGS).map(|i| format!("t{i}")).collect()).is_err());
+    }
+
+    #[test]
+    fn matches_tag_selectors() {
+        let tags = vec!["customer:acme".to_string(), "prod".to_string()];
+        assert!(has_all_tags(&tags, &parse_tag_selector("prod")));
+        assert!(has_all_tags(
+            &tags,
+            &parse_tag_selector(" PROD , customer:acme")
+        ));
+        assert!(!has_all_tags(&tags, &parse_tag_selector("prod,region:eu")));
+        assert!(has_all_tags(&tags, &parse_tag_selector(" , ")));
+        assert!(has_all_tags(&[], &[]));
+    }
+}
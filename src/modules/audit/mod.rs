@@ -0,0 +1,108 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    id,
+    modules::{
+        common::auth::ClientContext,
+        database::{insert_impl, manager::DB_MANAGER, paginate_query_primary_scan_all_impl},
+        error::RustMailerResult,
+        rest::response::DataPage,
+    },
+    utc_now,
+};
+
+/// A privileged operation, kept for later review.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 18, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct AuditEntry {
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Who performed the operation: `root`, the description of the access token used, or
+    /// `system` for operations of RustMailer itself.
+    pub actor: String,
+    /// Client IP address, when known.
+    pub ip_addr: Option<String>,
+    /// The operation, e.g. `imap_console`.
+    pub action: String,
+    /// The account the operation was performed on, if any.
+    pub account_id: Option<u64>,
+    /// Operation specific details, such as the command that was executed.
+    pub detail: String,
+    /// Whether the operation succeeded.
+    pub success: bool,
+    /// Timestamp (Unix epoch milliseconds) of the operation.
+    pub created_at: i64,
+}
+
+impl AuditEntry {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    pub fn new(
+        context: &ClientContext,
+        action: &str,
+        account_id: Option<u64>,
+        detail: String,
+        success: bool,
+    ) -> Self {
+        Self {
+            id: id!(64),
+            actor: Self::actor_of(context),
+            ip_addr: context.ip_addr.map(|ip| ip.to_string()),
+            action: action.to_string(),
+            account_id,
+            detail,
+            success,
+            created_at: utc_now!(),
+        }
+    }
+
+    /// An operation performed by RustMailer itself, such as a periodic task.
+    pub fn system(action: &str, account_id: Option<u64>, detail: String) -> Self {
+        Self {
+            id: id!(64),
+            actor: "system".to_string(),
+            ip_addr: None,
+            action: action.to_string(),
+            account_id,
+            detail,
+            success: true,
+            created_at: utc_now!(),
+        }
+    }
+
+    /// Who performs operations with `context`: `root`, or the description of the access
+    /// token used.
+    pub fn actor_of(context: &ClientContext) -> String {
+        match &context.access_token {
+            Some(token) if !context.is_root => format!(
+                "token:{}",
+                token.description.as_deref().unwrap_or("(no description)")
+            ),
+            _ => "root".to_string(),
+        }
+    }
+
+    pub async fn save(self) -> RustMailerResult<()> {
+        insert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    pub async fn paginate_list(
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<AuditEntry>> {
+        paginate_query_primary_scan_all_impl(DB_MANAGER.meta_db(), page, page_size, desc)
+            .await
+            .map(DataPage::from)
+    }
+}
@@ -15,6 +15,7 @@ use serde::{Deserialize, Serialize};
 
 pub mod entity;
 pub mod load;
+pub mod serve;
 #[cfg(test)]
 mod tests;
 
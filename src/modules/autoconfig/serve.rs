@@ -0,0 +1,262 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use tokio::sync::Mutex;
+
+use crate::modules::{
+    account::{
+        entity::{AuthType, Encryption, MailerType},
+        migration::AccountModel,
+    },
+    error::RustMailerResult,
+    smtp::mta::entity::Mta,
+};
+use crate::utc_now;
+
+/// How long the served mail domains are cached. The endpoints need no authentication, so
+/// the accounts are listed at most once per interval, however many requests arrive.
+const DOMAIN_CACHE_TTL_MS: i64 = 60_000;
+
+/// The client settings of each served mail domain, by lowercase domain, with the time they
+/// were loaded.
+static DOMAIN_SETTINGS: LazyLock<Mutex<Option<(i64, Arc<HashMap<String, ClientServerSettings>>)>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+/// The mail servers a client should use for an account, as served to mail clients by the
+/// autoconfig and Autodiscover endpoints.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClientServerSettings {
+    pub email: String,
+    pub imap: ClientServer,
+    pub smtp: Option<ClientServer>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClientServer {
+    pub host: String,
+    pub port: u16,
+    pub encryption: Encryption,
+    pub oauth2: bool,
+}
+
+impl ClientServerSettings {
+    /// Finds the settings a client should use for the given address. Settings are served
+    /// per domain: every address of a domain with an enabled IMAP/SMTP account gets the
+    /// same answer, so a request reveals whether the domain is served, but not whether an
+    /// account with the address exists.
+    pub async fn find(email: &str) -> RustMailerResult<Option<Self>> {
+        let email = email.trim();
+        let Some((_, domain)) = email.rsplit_once('@') else {
+            return Ok(None);
+        };
+        let domains = Self::served_domains().await?;
+        Ok(domains
+            .get(&domain.to_ascii_lowercase())
+            .map(|settings| Self {
+                email: email.to_string(),
+                ..settings.clone()
+            }))
+    }
+
+    /// The settings of each served domain, taken from the oldest enabled IMAP/SMTP account
+    /// of the domain. Accounts using a provider API have no servers a client could use and
+    /// are skipped.
+    async fn served_domains() -> RustMailerResult<Arc<HashMap<String, Self>>> {
+        let mut cached = DOMAIN_SETTINGS.lock().await;
+        let now = utc_now!();
+        if let Some((loaded_at, domains)) = cached.as_ref() {
+            if now - loaded_at < DOMAIN_CACHE_TTL_MS {
+                return Ok(domains.clone());
+            }
+        }
+        let mtas = Mta::list_all().await?;
+        let mut domains = HashMap::new();
+        for account in AccountModel::list_all().await? {
+            if !account.enabled || !matches!(account.mailer_type, MailerType::ImapSmtp) {
+                continue;
+            }
+            let Some((_, domain)) = account.email.rsplit_once('@') else {
+                continue;
+            };
+            let domain = domain.to_ascii_lowercase();
+            if domains.contains_key(&domain) {
+                continue;
+            }
+            if let Some(settings) = Self::from_account(account, &mtas) {
+                domains.insert(domain, settings);
+            }
+        }
+        let domains = Arc::new(domains);
+        *cached = Some((now, domains.clone()));
+        Ok(domains)
+    }
+
+    /// The settings of an account. Accounts without an SMTP server of their own send through
+    /// an MTA, which is then served instead: the MTA of the workspace of the account, or
+    /// otherwise a shared one.
+    pub fn from_account(account: AccountModel, mtas: &[Mta]) -> Option<Self> {
+        let imap = account.imap?;
+        let smtp = match account.smtp {
+            Some(smtp) => Some(ClientServer {
+                host: smtp.host,
+                port: smtp.port,
+                encryption: smtp.encryption,
+                oauth2: matches!(smtp.auth.auth_type, AuthType::OAuth2),
+            }),
+            None => mtas
+                .iter()
+                .filter(|mta| {
+                    mta.workspace_id.is_none() || mta.workspace_id == account.workspace_id
+                })
+                .min_by_key(|mta| (mta.workspace_id != account.workspace_id, mta.id))
+                .map(|mta| ClientServer {
+                    host: mta.server.host.clone(),
+                    port: mta.server.port,
+                    encryption: mta.server.encryption.clone(),
+                    oauth2: false,
+                }),
+        };
+        Some(Self {
+            imap: ClientServer {
+                host: imap.host,
+                port: imap.port,
+                encryption: imap.encryption,
+                oauth2: matches!(imap.auth.auth_type, AuthType::OAuth2),
+            },
+            smtp,
+            email: account.email,
+        })
+    }
+
+    /// Renders the settings in the Mozilla autoconfig format (`config-v1.1.xml`), used by
+    /// Thunderbird, K-9/Thunderbird for Android, Evolution and others.
+    pub fn to_autoconfig_xml(&self) -> String {
+        let domain = self
+            .email
+            .rsplit_once('@')
+            .map(|(_, domain)| domain)
+            .unwrap_or_default();
+        let server = |kind: &str, server: &ClientServer| {
+            let socket_type = match server.encryption {
+                Encryption::Ssl => "SSL",
+                Encryption::StartTls => "STARTTLS",
+                Encryption::None => "plain",
+            };
+            let authentication = if server.oauth2 {
+                "OAuth2"
+            } else {
+                "password-cleartext"
+            };
+            let element = if kind == "imap" {
+                "incomingServer"
+            } else {
+                "outgoingServer"
+            };
+            format!(
+                r#"    <{element} type="{kind}">
+      <hostname>{}</hostname>
+      <port>{}</port>
+      <socketType>{socket_type}</socketType>
+      <username>{}</username>
+      <authentication>{authentication}</authentication>
+    </{element}>
+"#,
+                escape_xml(&server.host),
+                server.port,
+                escape_xml(&self.email),
+            )
+        };
+        let mut servers = server("imap", &self.imap);
+        if let Some(smtp) = &self.smtp {
+            servers.push_str(&server("smtp", smtp));
+        }
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<clientConfig version="1.1">
+  <emailProvider id="{domain}">
+    <domain>{domain}</domain>
+    <displayName>{domain}</displayName>
+{servers}  </emailProvider>
+</clientConfig>
+"#,
+            domain = escape_xml(domain),
+        )
+    }
+
+    /// Renders the settings as a Microsoft Autodiscover (POX) response, used by Outlook
+    /// and the iOS and Android mail apps.
+    pub fn to_autodiscover_xml(&self) -> String {
+        let protocol = |kind: &str, server: &ClientServer| {
+            let (ssl, encryption) = match server.encryption {
+                Encryption::Ssl => ("on", "SSL"),
+                Encryption::StartTls => ("on", "TLS"),
+                Encryption::None => ("off", "None"),
+            };
+            format!(
+                r#"      <Protocol>
+        <Type>{kind}</Type>
+        <Server>{}</Server>
+        <Port>{}</Port>
+        <LoginName>{}</LoginName>
+        <DomainRequired>off</DomainRequired>
+        <SPA>off</SPA>
+        <SSL>{ssl}</SSL>
+        <Encryption>{encryption}</Encryption>
+        <AuthRequired>on</AuthRequired>
+      </Protocol>
+"#,
+                escape_xml(&server.host),
+                server.port,
+                escape_xml(&self.email),
+            )
+        };
+        let mut protocols = protocol("IMAP", &self.imap);
+        if let Some(smtp) = &self.smtp {
+            protocols.push_str(&protocol("SMTP", smtp));
+        }
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<Autodiscover xmlns="http://schemas.microsoft.com/exchange/autodiscover/responseschema/2006">
+  <Response xmlns="http://schemas.microsoft.com/exchange/autodiscover/outlook/responseschema/2006a">
+    <User>
+      <DisplayName>{}</DisplayName>
+    </User>
+    <Account>
+      <AccountType>email</AccountType>
+      <Action>settings</Action>
+{protocols}    </Account>
+  </Response>
+</Autodiscover>
+"#,
+            escape_xml(&self.email),
+        )
+    }
+}
+
+/// Extracts the address of an Autodiscover request, the content of its `EMailAddress`
+/// element.
+pub fn autodiscover_request_email(body: &str) -> Option<String> {
+    let start = body.find("<EMailAddress>")? + "<EMailAddress>".len();
+    let end = start + body[start..].find("</EMailAddress>")?;
+    let email = body[start..end].trim();
+    (!email.is_empty()).then(|| email.to_string())
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
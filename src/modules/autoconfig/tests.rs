@@ -7,3 +7,112 @@ async fn test() {
     let config = autoconfig::from_addr("test@163.com").await.unwrap();
     println!("{:#?}", config);
 }
+
+#[test]
+fn serves_account_settings_to_mail_clients() {
+    use crate::modules::account::entity::{
+        AuthConfig, AuthType, Encryption, ImapConfig, SmtpConfig,
+    };
+    use crate::modules::account::migration::AccountModel;
+    use crate::modules::autoconfig::serve::{autodiscover_request_email, ClientServerSettings};
+
+    let account = AccountModel {
+        email: "ops@example.com".into(),
+        name: Some("Ops & Support".into()),
+        imap: Some(ImapConfig {
+            host: "imap.example.com".into(),
+            port: 993,
+            encryption: Encryption::Ssl,
+            ..Default::default()
+        }),
+        smtp: Some(SmtpConfig {
+            host: "smtp.example.com".into(),
+            port: 587,
+            encryption: Encryption::StartTls,
+            auth: AuthConfig {
+                auth_type: AuthType::OAuth2,
+                password: None,
+            },
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let settings = ClientServerSettings::from_account(account, &[]).unwrap();
+
+    let autoconfig = settings.to_autoconfig_xml();
+    assert!(autoconfig.contains(r#"<emailProvider id="example.com">"#));
+    assert!(autoconfig.contains("<displayName>example.com</displayName>"));
+    assert!(!autoconfig.contains("Ops &amp; Support"));
+    assert!(autoconfig.contains("<hostname>imap.example.com</hostname>\n      <port>993</port>\n      <socketType>SSL</socketType>"));
+    assert!(autoconfig.contains("<socketType>STARTTLS</socketType>\n      <username>ops@example.com</username>\n      <authentication>OAuth2</authentication>"));
+
+    let autodiscover = settings.to_autodiscover_xml();
+    assert!(autodiscover.contains(
+        "<Type>IMAP</Type>\n        <Server>imap.example.com</Server>\n        <Port>993</Port>"
+    ));
+    assert!(autodiscover.contains("<Port>587</Port>\n        <LoginName>ops@example.com</LoginName>\n        <DomainRequired>off</DomainRequired>\n        <SPA>off</SPA>\n        <SSL>on</SSL>\n        <Encryption>TLS</Encryption>"));
+
+    let request = r#"<?xml version="1.0" encoding="utf-8"?>
+<Autodiscover xmlns="http://schemas.microsoft.com/exchange/autodiscover/outlook/requestschema/2006">
+  <Request>
+    <EMailAddress> ops@example.com </EMailAddress>
+    <AcceptableResponseSchema>http://schemas.microsoft.com/exchange/autodiscover/outlook/responseschema/2006a</AcceptableResponseSchema>
+  </Request>
+</Autodiscover>"#;
+    assert_eq!(
+        autodiscover_request_email(request).as_deref(),
+        Some("ops@example.com")
+    );
+    assert_eq!(autodiscover_request_email("<Request></Request>"), None);
+
+    let api_account = AccountModel::default();
+    assert!(ClientServerSettings::from_account(api_account, &[]).is_none());
+}
+
+#[test]
+fn serves_the_mta_of_accounts_without_smtp_server() {
+    use crate::modules::account::entity::{Encryption, ImapConfig};
+    use crate::modules::account::migration::AccountModel;
+    use crate::modules::autoconfig::serve::ClientServerSettings;
+    use crate::modules::smtp::mta::entity::{Mta, SmtpServerConfig};
+
+    let mta = |id: u64, host: &str, workspace_id: Option<u64>| Mta {
+        id,
+        server: SmtpServerConfig {
+            host: host.into(),
+            port: 465,
+            encryption: Encryption::Ssl,
+        },
+        workspace_id,
+        ..Default::default()
+    };
+    let mtas = vec![
+        mta(1, "shared.example.com", None),
+        mta(2, "other.example.com", Some(8)),
+        mta(3, "workspace.example.com", Some(7)),
+    ];
+    let account = AccountModel {
+        email: "ops@example.com".into(),
+        imap: Some(ImapConfig {
+            host: "imap.example.com".into(),
+            port: 993,
+            encryption: Encryption::Ssl,
+            ..Default::default()
+        }),
+        workspace_id: Some(7),
+        ..Default::default()
+    };
+
+    let settings = ClientServerSettings::from_account(account.clone(), &mtas).unwrap();
+    assert_eq!(settings.smtp.unwrap().host, "workspace.example.com");
+
+    let shared = AccountModel {
+        workspace_id: None,
+        ..account
+    };
+    let settings = ClientServerSettings::from_account(shared, &mtas).unwrap();
+    let smtp = settings.smtp.unwrap();
+    assert_eq!(smtp.host, "shared.example.com");
+    assert_eq!(smtp.port, 465);
+    assert!(!smtp.oauth2);
+}
@@ -0,0 +1,240 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+use crate::modules::bounce::parser::DeliveryStatus;
+
+/// Standardized category of a bounced email, derived from the status code and diagnostic
+/// of its delivery status notification.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Deserialize, Serialize, Enum)]
+pub enum BounceCategory {
+    /// Permanent failure, e.g. the mailbox or domain does not exist. Retrying will not help.
+    HardBounce,
+    /// Temporary failure, e.g. the server was unavailable or delivery is delayed.
+    SoftBounce,
+    /// The recipient's mailbox is over its storage quota.
+    MailboxFull,
+    /// Rejected as spam, or because of the sender's reputation or a blocklist listing.
+    SpamBlock,
+    /// Rejected by a policy of the receiving server, e.g. failed SPF/DKIM/DMARC checks,
+    /// a message size limit or a denied relay.
+    Policy,
+}
+
+/// Number of bounced recipients in each category.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct BounceStats {
+    /// Total number of bounced recipients.
+    pub total: u64,
+    pub hard_bounce: u64,
+    pub soft_bounce: u64,
+    pub mailbox_full: u64,
+    pub spam_block: u64,
+    pub policy: u64,
+}
+
+impl BounceStats {
+    pub fn record(&mut self, category: BounceCategory) {
+        self.total += 1;
+        match category {
+            BounceCategory::HardBounce => self.hard_bounce += 1,
+            BounceCategory::SoftBounce => self.soft_bounce += 1,
+            BounceCategory::MailboxFull => self.mailbox_full += 1,
+            BounceCategory::SpamBlock => self.spam_block += 1,
+            BounceCategory::Policy => self.policy += 1,
+        }
+    }
+}
+
+const MAILBOX_FULL_PHRASES: &[&str] = &[
+    "mailbox full",
+    "mailbox is full",
+    "over quota",
+    "overquota",
+    "quota exceeded",
+    "exceeded storage",
+    "insufficient storage",
+    "mailbox size limit",
+];
+
+const SPAM_BLOCK_PHRASES: &[&str] = &[
+    "spam",
+    "blocklist",
+    "blacklist",
+    "block list",
+    "black list",
+    "spamhaus",
+    "spamcop",
+    "barracuda",
+    "reputation",
+    "junk",
+    "unsolicited",
+    "rbl",
+    "dnsbl",
+];
+
+const POLICY_PHRASES: &[&str] = &[
+    "policy",
+    "dmarc",
+    "spf",
+    "dkim",
+    "not authorized",
+    "unauthenticated",
+    "relay denied",
+    "relaying denied",
+    "relay access denied",
+    "message too large",
+    "message size exceeds",
+    "too many recipients",
+];
+
+/// Classifies the delivery status notification of a recipient. Returns `None` if it does
+/// not report a bounce, e.g. for `delivered` or `relayed` notifications.
+pub fn classify(status: &DeliveryStatus) -> Option<BounceCategory> {
+    classify_bounce(
+        status.action.as_deref(),
+        status.status.as_deref(),
+        status.diagnostic_code.as_deref(),
+    )
+}
+
+/// Classifies a bounce from its DSN action, enhanced status code (e.g. `5.1.1`) and
+/// diagnostic code (e.g. `smtp; 550 5.1.1 User unknown`).
+pub fn classify_bounce(
+    action: Option<&str>,
+    status: Option<&str>,
+    diagnostic: Option<&str>,
+) -> Option<BounceCategory> {
+    let action = action.map(|a| a.trim().to_ascii_lowercase());
+    if matches!(
+        action.as_deref(),
+        Some("delivered" | "relayed" | "expanded")
+    ) {
+        return None;
+    }
+    let diagnostic = diagnostic.unwrap_or_default().to_lowercase();
+    // The enhanced status code of the notification, else one found in the diagnostic.
+    let code = status.and_then(parse_enhanced_status).or_else(|| {
+        diagnostic
+            .split_whitespace()
+            .find_map(parse_enhanced_status)
+    });
+    let reply_class = diagnostic
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() == 3)
+        .and_then(|part| part.chars().next());
+    let mentions = |phrases: &[&str]| phrases.iter().any(|p| diagnostic.contains(p));
+
+    if let Some((_, subject, detail)) = code {
+        match (subject, detail) {
+            (2, 2) => return Some(BounceCategory::MailboxFull),
+            (7, 1) if mentions(SPAM_BLOCK_PHRASES) => return Some(BounceCategory::SpamBlock),
+            (7, _) | (3, 4) | (2, 3) | (5, 3) => return Some(BounceCategory::Policy),
+            _ => {}
+        }
+    }
+    if mentions(MAILBOX_FULL_PHRASES) {
+        return Some(BounceCategory::MailboxFull);
+    }
+    if mentions(SPAM_BLOCK_PHRASES) {
+        return Some(BounceCategory::SpamBlock);
+    }
+    if mentions(POLICY_PHRASES) {
+        return Some(BounceCategory::Policy);
+    }
+
+    let class = code
+        .map(|(class, _, _)| class)
+        .or_else(|| reply_class.and_then(|c| c.to_digit(10)));
+    match (class, action.as_deref()) {
+        (Some(5), _) => Some(BounceCategory::HardBounce),
+        (Some(4), _) => Some(BounceCategory::SoftBounce),
+        (_, Some("failed")) => Some(BounceCategory::HardBounce),
+        (_, Some("delayed")) => Some(BounceCategory::SoftBounce),
+        _ => None,
+    }
+}
+
+/// Parses an enhanced status code (RFC 3463) of a failure: `4.x.x` or `5.x.x`.
+fn parse_enhanced_status(value: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = value.trim_matches(|c: char| !c.is_ascii_digit()).split('.');
+    let class = parts.next()?.parse().ok()?;
+    let subject = parts.next()?.parse().ok()?;
+    let detail = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !matches!(class, 4 | 5) {
+        return None;
+    }
+    Some((class, subject, detail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_bounces() {
+        assert_eq!(
+            classify_bounce(
+                Some("failed"),
+                Some("5.1.1"),
+                Some("smtp; 550 5.1.1 User unknown")
+            ),
+            Some(BounceCategory::HardBounce)
+        );
+        assert_eq!(
+            classify_bounce(Some("failed"), Some("5.2.2"), None),
+            Some(BounceCategory::MailboxFull)
+        );
+        assert_eq!(
+            classify_bounce(
+                Some("failed"),
+                None,
+                Some("smtp; 552 Requested action aborted: mailbox is full")
+            ),
+            Some(BounceCategory::MailboxFull)
+        );
+        assert_eq!(
+            classify_bounce(
+                Some("failed"),
+                Some("5.7.1"),
+                Some("smtp; 550 5.7.1 Service unavailable; client host blocked using Spamhaus")
+            ),
+            Some(BounceCategory::SpamBlock)
+        );
+        assert_eq!(
+            classify_bounce(
+                Some("failed"),
+                Some("5.7.26"),
+                Some("smtp; 550 5.7.26 Unauthenticated email is not accepted")
+            ),
+            Some(BounceCategory::Policy)
+        );
+        assert_eq!(
+            classify_bounce(Some("delayed"), Some("4.4.1"), Some("Connection timed out")),
+            Some(BounceCategory::SoftBounce)
+        );
+        assert_eq!(
+            classify_bounce(None, None, Some("smtp; 421 Try again later")),
+            Some(BounceCategory::SoftBounce)
+        );
+        assert_eq!(
+            classify_bounce(Some("delivered"), Some("2.0.0"), None),
+            None
+        );
+        assert_eq!(classify_bounce(None, None, None), None);
+    }
+
+    #[test]
+    fn counts_categories() {
+        let mut stats = BounceStats::default();
+        stats.record(BounceCategory::HardBounce);
+        stats.record(BounceCategory::SpamBlock);
+        stats.record(BounceCategory::HardBounce);
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.hard_bounce, 2);
+        assert_eq!(stats.spam_block, 1);
+    }
+}
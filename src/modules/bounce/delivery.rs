@@ -0,0 +1,204 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    modules::{
+        bounce::{
+            classify::{classify, BounceCategory},
+            parser::BounceReport,
+        },
+        database::manager::DB_MANAGER,
+        error::{code::ErrorCode, RustMailerResult},
+        scheduler::{nativedb::meta::NativeDbTaskStore, task::Task},
+        smtp::request::{task::SmtpTask, thread::normalize_message_id},
+    },
+    raise_error, utc_now,
+};
+
+/// The delivery outcome of a sent email for one recipient, as reported by a Delivery
+/// Status Notification (DSN) received in the sending account.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct RecipientDeliveryStatus {
+    /// The recipient the notification is about.
+    pub recipient: Option<String>,
+    /// The reported action: `failed`, `delayed`, `delivered`, `relayed` or `expanded`.
+    pub action: Option<String>,
+    /// The enhanced status code, e.g. `5.1.1`.
+    pub status: Option<String>,
+    /// The diagnostic returned by the remote server, if any.
+    pub diagnostic_code: Option<String>,
+    /// The remote MTA that reported the outcome.
+    pub remote_mta: Option<String>,
+    /// The category of the bounce, if the notification reports a failure.
+    pub category: Option<BounceCategory>,
+    /// The mailbox holding the notification.
+    pub mailbox_name: String,
+    /// The UID of the notification within `mailbox_name`.
+    pub uid: u32,
+    /// The Unix timestamp (milliseconds since epoch) when the notification was processed.
+    pub reported_at: i64,
+}
+
+/// Links a delivery status notification to the email task that sent the original message,
+/// recording the reported outcome on the task.
+///
+/// The task is matched by the envelope ID (`ENVID`) requested when sending, falling back to
+/// the Message-ID of the original message. Returns the ID of the linked task, if any.
+pub async fn link_delivery_report(
+    account_id: u64,
+    report: &BounceReport,
+    mailbox_name: &str,
+    uid: u32,
+) -> RustMailerResult<Option<u64>> {
+    let Some(delivery_status) = &report.delivery_status else {
+        return Ok(None);
+    };
+    let envelope_id = delivery_status.original_envelope_id.as_deref();
+    let message_id = report
+        .original_headers
+        .as_ref()
+        .and_then(|h| h.message_id.as_deref())
+        .or(delivery_status.original_message_id.as_deref());
+    if envelope_id.is_none() && message_id.is_none() {
+        return Ok(None);
+    }
+
+    let tasks = NativeDbTaskStore::list_all(DB_MANAGER.tasks_db(), SmtpTask::TASK_KEY).await?;
+    for meta in tasks {
+        let mut task: SmtpTask = serde_json::from_str(&meta.task_params)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        if !is_origin_of(&task, account_id, envelope_id, message_id) {
+            continue;
+        }
+
+        task.record_delivery_status(RecipientDeliveryStatus {
+            recipient: delivery_status.recipient.clone(),
+            action: delivery_status.action.clone(),
+            status: delivery_status.status.clone(),
+            diagnostic_code: delivery_status.diagnostic_code.clone(),
+            remote_mta: delivery_status.remote_mta.clone(),
+            category: classify(delivery_status),
+            mailbox_name: mailbox_name.to_string(),
+            uid,
+            reported_at: utc_now!(),
+        });
+        let task_params = serde_json::to_string(&task)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        NativeDbTaskStore::set_params(DB_MANAGER.tasks_db(), meta.id, task_params).await?;
+        info!(
+            "Account {}: Linked the delivery status notification {}/{} to email task {}.",
+            account_id, mailbox_name, uid, meta.id
+        );
+        return Ok(Some(meta.id));
+    }
+    Ok(None)
+}
+
+/// Whether the task sent the message a notification with the given envelope ID and
+/// original Message-ID is about.
+fn is_origin_of(
+    task: &SmtpTask,
+    account_id: u64,
+    envelope_id: Option<&str>,
+    message_id: Option<&str>,
+) -> bool {
+    if task.account_id != account_id {
+        return false;
+    }
+    let task_envelope_id = task
+        .control
+        .as_ref()
+        .and_then(|c| c.dsn.as_ref())
+        .and_then(|dsn| dsn.envid.as_deref());
+    if let (Some(expected), Some(actual)) = (task_envelope_id, envelope_id) {
+        return expected == actual;
+    }
+    message_id.is_some_and(|id| {
+        normalize_message_id(id).eq_ignore_ascii_case(normalize_message_id(&task.message_id))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::smtp::request::{DSNConfig, SendControl};
+
+    fn task(envid: Option<&str>) -> SmtpTask {
+        SmtpTask {
+            account_id: 1,
+            account_email: "sender@example.com".into(),
+            subject: None,
+            message_id: "<1.abc@example.com>".into(),
+            from: "sender@example.com".into(),
+            to: vec!["rcpt@example.com".into()],
+            cc: None,
+            bcc: None,
+            attachment_count: 0,
+            control: Some(SendControl {
+                dsn: Some(DSNConfig {
+                    envid: envid.map(Into::into),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            cache_key: "key".into(),
+            answer_email: None,
+            delivery_status: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matches_the_originating_task() {
+        let with_envid = task(Some("env-42"));
+        assert!(is_origin_of(&with_envid, 1, Some("env-42"), None));
+        assert!(!is_origin_of(
+            &with_envid,
+            1,
+            Some("env-43"),
+            Some("1.abc@example.com")
+        ));
+        assert!(!is_origin_of(&with_envid, 2, Some("env-42"), None));
+
+        let without_envid = task(None);
+        assert!(is_origin_of(
+            &without_envid,
+            1,
+            Some("env-42"),
+            Some("1.ABC@example.com")
+        ));
+        assert!(is_origin_of(
+            &without_envid,
+            1,
+            None,
+            Some("<1.abc@example.com>")
+        ));
+        assert!(!is_origin_of(
+            &without_envid,
+            1,
+            None,
+            Some("2.abc@example.com")
+        ));
+        assert!(!is_origin_of(&without_envid, 1, None, None));
+    }
+
+    #[test]
+    fn keeps_the_latest_status_per_recipient() {
+        let mut task = task(None);
+        let status = |recipient: &str, action: &str| RecipientDeliveryStatus {
+            recipient: Some(recipient.into()),
+            action: Some(action.into()),
+            ..Default::default()
+        };
+        task.record_delivery_status(status("a@example.com", "delayed"));
+        task.record_delivery_status(status("b@example.com", "delivered"));
+        task.record_delivery_status(status("A@example.com", "failed"));
+        assert_eq!(task.delivery_status.len(), 2);
+        assert_eq!(task.delivery_status[0].action.as_deref(), Some("failed"));
+        assert_eq!(task.delivery_status[1].action.as_deref(), Some("delivered"));
+    }
+}
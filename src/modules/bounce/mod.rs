@@ -2,6 +2,8 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+pub mod classify;
+pub mod delivery;
 pub mod detect;
 pub mod models;
 pub mod parser;
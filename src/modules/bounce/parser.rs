@@ -59,6 +59,8 @@ pub struct DeliveryStatus {
     pub original_message_id: Option<String>,
     /// Optional sender email address used by the Postfix system.
     pub postfix_sender: Option<String>,
+    /// Optional envelope ID (`ENVID`) given by the sender when the original email was sent.
+    pub original_envelope_id: Option<String>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
@@ -135,6 +137,7 @@ impl DeliveryStatus {
             && self.arrival_date.is_none()
             && self.original_message_id.is_none()
             && self.postfix_sender.is_none()
+            && self.original_envelope_id.is_none()
     }
 }
 
@@ -432,6 +435,7 @@ fn parse_delivery_status(bounce_details: &str) -> DeliveryStatus {
         ("x-postfix-queue-id", &mut result.postfix_queue_id),
         ("arrival-date", &mut result.arrival_date),
         ("x-original-message-id", &mut result.original_message_id),
+        ("original-envelope-id", &mut result.original_envelope_id),
     ] {
         if let Some(value) = details.get(key) {
             *field = Some(value.to_string());
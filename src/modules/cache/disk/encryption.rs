@@ -0,0 +1,272 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    io::{self, Cursor},
+    pin::Pin,
+    sync::LazyLock,
+    task::{Context, Poll},
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use tracing::info;
+
+use crate::{
+    modules::{
+        context::Initialize,
+        error::{code::ErrorCode, RustMailerResult},
+        settings::cli::SETTINGS,
+    },
+    raise_error,
+};
+
+/// Prefix of encrypted cache content. Cached emails, JSON and attachments never start
+/// with a NUL byte, so content written before encryption was enabled is told apart and
+/// still read as is.
+const MAGIC: &[u8; 8] = b"\0RMENC1\0";
+
+/// The key encrypting cache content, if `rustmailer_disk_cache_encryption_key` or
+/// `rustmailer_disk_cache_encryption_key_file` is configured.
+static CACHE_CIPHER: LazyLock<Result<Option<CacheCipher>, String>> =
+    LazyLock::new(CacheCipher::from_settings);
+
+/// AES-256-GCM encryption of disk cache content at rest.
+///
+/// Each item is sealed with a random nonce and its cache key as associated data, so
+/// content cannot be swapped between cache keys without failing decryption.
+pub struct CacheCipher {
+    key: LessSafeKey,
+}
+
+impl CacheCipher {
+    fn from_settings() -> Result<Option<Self>, String> {
+        let encoded = match (
+            &SETTINGS.rustmailer_disk_cache_encryption_key,
+            &SETTINGS.rustmailer_disk_cache_encryption_key_file,
+        ) {
+            (Some(_), Some(_)) => {
+                return Err(
+                    "Set only one of rustmailer_disk_cache_encryption_key and rustmailer_disk_cache_encryption_key_file"
+                        .into(),
+                )
+            }
+            (Some(key), None) => key.clone(),
+            (None, Some(path)) => std::fs::read_to_string(path).map_err(|e| {
+                format!(
+                    "Failed to read the disk cache encryption key file '{}': {}",
+                    path, e
+                )
+            })?,
+            (None, None) => return Ok(None),
+        };
+        Self::from_base64(encoded.trim()).map(Some)
+    }
+
+    /// Builds the cipher from a base64 encoded 256-bit key.
+    pub fn from_base64(encoded: &str) -> Result<Self, String> {
+        let key = general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|_| "The disk cache encryption key is not valid base64".to_string())?;
+        if key.len() != 32 {
+            return Err(format!(
+                "The disk cache encryption key must be 32 bytes (256 bits), got {} bytes",
+                key.len()
+            ));
+        }
+        let key = UnboundKey::new(&AES_256_GCM, &key)
+            .map_err(|_| "Invalid disk cache encryption key".to_string())?;
+        Ok(Self {
+            key: LessSafeKey::new(key),
+        })
+    }
+
+    /// The configured cipher, or `None` if cache encryption is disabled.
+    pub fn get() -> RustMailerResult<Option<&'static CacheCipher>> {
+        CACHE_CIPHER
+            .as_ref()
+            .map(Option::as_ref)
+            .map_err(|e| raise_error!(e.clone(), ErrorCode::MissingConfiguration))
+    }
+
+    /// Encrypts the content of `cache_key` into `MAGIC || nonce || ciphertext || tag`.
+    pub fn seal(&self, cache_key: &str, data: &[u8]) -> RustMailerResult<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| {
+            raise_error!(
+                "Failed to generate a nonce for cache encryption".into(),
+                ErrorCode::InternalError
+            )
+        })?;
+        let mut in_out = data.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(cache_key.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| {
+                raise_error!(
+                    format!("Failed to encrypt cache item {}", cache_key),
+                    ErrorCode::InternalError
+                )
+            })?;
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + in_out.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&in_out);
+        Ok(sealed)
+    }
+
+    /// Decrypts content produced by [`CacheCipher::seal`] for the same cache key.
+    pub fn open(&self, cache_key: &str, sealed: &[u8]) -> RustMailerResult<Vec<u8>> {
+        let failed = || {
+            raise_error!(
+                format!(
+                    "Failed to decrypt cache item {}; the encryption key may have changed or the content is corrupted",
+                    cache_key
+                ),
+                ErrorCode::InternalError
+            )
+        };
+        let body = sealed.strip_prefix(MAGIC.as_slice()).ok_or_else(failed)?;
+        if body.len() < NONCE_LEN {
+            return Err(failed());
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| failed())?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(cache_key.as_bytes()), &mut in_out)
+            .map_err(|_| failed())?;
+        let len = plaintext.len();
+        in_out.truncate(len);
+        Ok(in_out)
+    }
+}
+
+impl Initialize for CacheCipher {
+    /// Fails startup on an invalid key instead of writing customer content unencrypted.
+    async fn initialize() -> RustMailerResult<()> {
+        if Self::get()?.is_some() {
+            info!("Disk cache encryption at rest is enabled");
+        }
+        Ok(())
+    }
+}
+
+/// Reads the content of a cache item, decrypting it if it was written encrypted.
+pub enum CacheReader {
+    /// Unencrypted content streamed from disk. `header` holds the bytes already read to
+    /// check for encryption.
+    Plain {
+        header: Cursor<Vec<u8>>,
+        reader: cacache::Reader,
+    },
+    /// Decrypted content, held in memory.
+    Decrypted(Cursor<Vec<u8>>),
+}
+
+impl CacheReader {
+    pub async fn new(cache_key: &str, mut reader: cacache::Reader) -> RustMailerResult<Self> {
+        let mut header = Vec::with_capacity(MAGIC.len());
+        (&mut reader)
+            .take(MAGIC.len() as u64)
+            .read_to_end(&mut header)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        if header.as_slice() != MAGIC {
+            return Ok(Self::Plain {
+                header: Cursor::new(header),
+                reader,
+            });
+        }
+        let cipher = CacheCipher::get()?.ok_or_else(|| {
+            raise_error!(
+                format!(
+                    "Cache item {} is encrypted, but no disk cache encryption key is configured",
+                    cache_key
+                ),
+                ErrorCode::MissingConfiguration
+            )
+        })?;
+        let mut sealed = header;
+        reader
+            .read_to_end(&mut sealed)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        Ok(Self::Decrypted(Cursor::new(
+            cipher.open(cache_key, &sealed)?,
+        )))
+    }
+
+    /// Verifies the integrity of the content once it has been read. Decrypted content was
+    /// already authenticated when it was opened.
+    pub fn check(self) -> Result<(), cacache::Error> {
+        match self {
+            CacheReader::Plain { reader, .. } => reader.check().map(|_| ()),
+            CacheReader::Decrypted(_) => Ok(()),
+        }
+    }
+}
+
+impl AsyncRead for CacheReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            CacheReader::Plain { header, reader } => {
+                if (header.position() as usize) < header.get_ref().len() {
+                    Pin::new(header).poll_read(cx, buf)
+                } else {
+                    Pin::new(reader).poll_read(cx, buf)
+                }
+            }
+            CacheReader::Decrypted(content) => Pin::new(content).poll_read(cx, buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher() -> CacheCipher {
+        CacheCipher::from_base64(&general_purpose::STANDARD.encode([7u8; 32])).unwrap()
+    }
+
+    #[test]
+    fn seals_and_opens_content() {
+        let cipher = cipher();
+        let sealed = cipher
+            .seal("imap_raw_email_1", b"From: a@example.com")
+            .unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert!(!sealed
+            .windows(b"a@example.com".len())
+            .any(|w| w == b"a@example.com"));
+        assert_eq!(
+            cipher.open("imap_raw_email_1", &sealed).unwrap(),
+            b"From: a@example.com"
+        );
+
+        // The content is bound to its cache key and to the key it was sealed with.
+        assert!(cipher.open("imap_raw_email_2", &sealed).is_err());
+        let other = CacheCipher::from_base64(&general_purpose::STANDARD.encode([8u8; 32])).unwrap();
+        assert!(other.open("imap_raw_email_1", &sealed).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert!(CacheCipher::from_base64("not base64!").is_err());
+        assert!(CacheCipher::from_base64(&general_purpose::STANDARD.encode([7u8; 16])).is_err());
+    }
+}
@@ -9,13 +9,13 @@ use crate::{
             update_impl, upsert_impl,
         },
         error::{code::ErrorCode, RustMailerResult},
-        settings::dir::DATA_DIR_MANAGER,
+        settings::{cli::SETTINGS, dir::DATA_DIR_MANAGER},
     },
     raise_error, utc_now,
 };
 use native_db::*;
 use native_model::{native_model, Model};
-use poem_openapi::Object;
+use poem_openapi::{Enum, Object};
 use serde::{Deserialize, Serialize};
 use std::{
     path::{Path, PathBuf},
@@ -26,9 +26,16 @@ use sysinfo::Disks;
 use tokio::io::AsyncWriteExt;
 use tracing::{debug, error, info, warn};
 
+use encryption::{CacheCipher, CacheReader};
+use reconcile::ReconcileReport;
+use remote::{ObjectStorage, REMOTE_STORAGE};
+
+pub mod encryption;
+pub mod reconcile;
+pub mod remote;
 pub mod task;
 
-const DISK_USAGE_THRESHOLD: f64 = 85.0;
+pub const DISK_USAGE_THRESHOLD: f64 = 85.0;
 pub const ONE_WEEK_MS: i64 = 7 * 24 * 60 * 60 * 1000;
 const MAX_ITEMS_THRESHOLD: usize = 10000;
 
@@ -37,6 +44,136 @@ pub static DISK_CACHE: LazyLock<DiskCache> = LazyLock::new(DiskCache::init);
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
 #[native_model(id = 12, version = 1)]
 #[native_db]
+pub struct CacheItemV1 {
+    #[primary_key]
+    pub key: String,
+    pub size: u64,
+    pub pending: bool,
+    pub write_at: i64,
+    pub last_access_at: i64,
+}
+
+/// Where the content of a cache item is currently stored.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Enum)]
+pub enum CacheTier {
+    /// On local disk, in the cacache directory.
+    #[default]
+    Local,
+    /// Evicted from local disk to the S3-compatible object storage.
+    Remote,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+#[native_model(id = 12, version = 2, from = CacheItemV1)]
+#[native_db]
+pub struct CacheItemV2 {
+    #[primary_key]
+    pub key: String,
+    pub size: u64,
+    pub pending: bool,
+    pub write_at: i64,
+    pub last_access_at: i64,
+    pub tier: CacheTier,
+}
+
+impl From<CacheItemV1> for CacheItemV2 {
+    fn from(value: CacheItemV1) -> Self {
+        Self {
+            key: value.key,
+            size: value.size,
+            pending: value.pending,
+            write_at: value.write_at,
+            last_access_at: value.last_access_at,
+            tier: CacheTier::Local,
+        }
+    }
+}
+
+impl From<CacheItemV2> for CacheItemV1 {
+    fn from(value: CacheItemV2) -> Self {
+        Self {
+            key: value.key,
+            size: value.size,
+            pending: value.pending,
+            write_at: value.write_at,
+            last_access_at: value.last_access_at,
+        }
+    }
+}
+
+/// The feature a cache item belongs to. Each namespace has its own quota and eviction
+/// policy, so heavy use of one feature cannot evict the hot data of another.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize, Enum)]
+pub enum CacheNamespace {
+    /// Bodies of outgoing messages waiting to be sent.
+    Outgoing,
+    /// Decoded attachments and inline attachments.
+    Attachment,
+    /// Message content and raw messages fetched from the mail server.
+    #[default]
+    Content,
+}
+
+impl CacheNamespace {
+    pub const ALL: [CacheNamespace; 3] = [
+        CacheNamespace::Outgoing,
+        CacheNamespace::Attachment,
+        CacheNamespace::Content,
+    ];
+
+    /// Maximum local size of the namespace in bytes, if a quota is configured.
+    pub fn quota(&self) -> Option<u64> {
+        let mib = match self {
+            CacheNamespace::Outgoing => SETTINGS.rustmailer_disk_cache_outgoing_quota_mib,
+            CacheNamespace::Attachment => SETTINGS.rustmailer_disk_cache_attachment_quota_mib,
+            CacheNamespace::Content => SETTINGS.rustmailer_disk_cache_content_quota_mib,
+        };
+        mib.map(|mib| mib.saturating_mul(1024 * 1024))
+    }
+
+    /// Outgoing messages are each read once when sent, so the oldest are evicted first.
+    /// Fetched content and attachments are read repeatedly, so the least recently used
+    /// are evicted first.
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        match self {
+            CacheNamespace::Outgoing => EvictionPolicy::OldestWritten,
+            CacheNamespace::Attachment | CacheNamespace::Content => {
+                EvictionPolicy::LeastRecentlyUsed
+            }
+        }
+    }
+
+    /// Namespace of an item written before namespaces existed, derived from its key.
+    fn from_legacy_item(key: &str, pending: bool) -> Self {
+        if pending {
+            CacheNamespace::Outgoing
+        } else if key.contains("attachment_") {
+            CacheNamespace::Attachment
+        } else {
+            CacheNamespace::Content
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EvictionPolicy {
+    LeastRecentlyUsed,
+    OldestWritten,
+}
+
+impl EvictionPolicy {
+    /// Sorts `items` so that the first item is the first to be evicted.
+    pub fn sort(&self, items: &mut [CacheItem]) {
+        match self {
+            EvictionPolicy::LeastRecentlyUsed => items.sort_by_key(|item| item.last_access_at),
+            EvictionPolicy::OldestWritten => items.sort_by_key(|item| item.write_at),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+#[native_model(id = 12, version = 3, from = CacheItemV2)]
+#[native_db]
 pub struct CacheItem {
     #[primary_key]
     pub key: String,
@@ -44,16 +181,47 @@ pub struct CacheItem {
     pub pending: bool,
     pub write_at: i64,
     pub last_access_at: i64,
+    pub tier: CacheTier,
+    pub namespace: CacheNamespace,
+}
+
+impl From<CacheItemV2> for CacheItem {
+    fn from(value: CacheItemV2) -> Self {
+        Self {
+            namespace: CacheNamespace::from_legacy_item(&value.key, value.pending),
+            key: value.key,
+            size: value.size,
+            pending: value.pending,
+            write_at: value.write_at,
+            last_access_at: value.last_access_at,
+            tier: value.tier,
+        }
+    }
+}
+
+impl From<CacheItem> for CacheItemV2 {
+    fn from(value: CacheItem) -> Self {
+        Self {
+            key: value.key,
+            size: value.size,
+            pending: value.pending,
+            write_at: value.write_at,
+            last_access_at: value.last_access_at,
+            tier: value.tier,
+        }
+    }
 }
 
 impl CacheItem {
-    pub fn new(key: String, size: u64, pending: bool) -> Self {
+    pub fn new(key: String, size: u64, namespace: CacheNamespace) -> Self {
         Self {
             key,
             size,
-            pending,
+            pending: namespace == CacheNamespace::Outgoing,
             write_at: utc_now!(),
             last_access_at: utc_now!(),
+            tier: CacheTier::Local,
+            namespace,
         }
     }
 
@@ -94,9 +262,33 @@ impl CacheItem {
         Ok(())
     }
 
-    pub async fn check_exist(key: &str) -> RustMailerResult<bool> {
-        let item = async_find_impl::<CacheItem>(DB_MANAGER.meta_db(), key.to_string()).await?;
-        Ok(item.is_some())
+    pub async fn find(key: &str) -> RustMailerResult<Option<CacheItem>> {
+        async_find_impl::<CacheItem>(DB_MANAGER.meta_db(), key.to_string()).await
+    }
+
+    pub async fn set_tier(key: &str, tier: CacheTier) -> RustMailerResult<()> {
+        let key = key.to_string();
+        update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .primary::<CacheItem>(key.clone())
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!("The CacheItem with id={key} was not found."),
+                            ErrorCode::InternalError
+                        )
+                    })
+            },
+            move |current| {
+                let mut updated = current.clone();
+                updated.tier = tier;
+                Ok(updated)
+            },
+        )
+        .await?;
+        Ok(())
     }
 
     pub async fn delete(&self) -> RustMailerResult<()> {
@@ -153,13 +345,24 @@ impl DiskCache {
         }
     }
 
-    pub async fn put_cache(&self, key: &str, data: &[u8], pending: bool) -> RustMailerResult<()> {
+    /// Stores `data` under `key`, encrypted if disk cache encryption is enabled.
+    pub async fn put_cache(
+        &self,
+        key: &str,
+        data: &[u8],
+        namespace: CacheNamespace,
+    ) -> RustMailerResult<()> {
         let cache_dir = self.cache_dir.to_str().ok_or_else(|| {
             raise_error!(
                 "Failed to convert cache_dir to str".into(),
                 ErrorCode::InternalError
             )
         })?;
+        let sealed = match CacheCipher::get()? {
+            Some(cipher) => Some(cipher.seal(key, data)?),
+            None => None,
+        };
+        let data = sealed.as_deref().unwrap_or(data);
         let mut writer = cacache::Writer::create(cache_dir, key)
             .await
             .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
@@ -171,29 +374,45 @@ impl DiskCache {
             .commit()
             .await
             .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
-        let item = CacheItem::new(key.to_string(), data.len() as u64, pending);
+        let item = CacheItem::new(key.to_string(), data.len() as u64, namespace);
         item.save().await?;
         Ok(())
     }
 
-    pub async fn get_cache(&self, key: &str) -> RustMailerResult<Option<cacache::Reader>> {
-        if !CacheItem::check_exist(key).await? {
+    /// Opens the content stored under `key`, transparently decrypting encrypted items.
+    /// Items written before encryption was enabled are read as is.
+    pub async fn get_cache(&self, key: &str) -> RustMailerResult<Option<CacheReader>> {
+        let Some(item) = CacheItem::find(key).await? else {
             return Ok(None);
-        }
+        };
         let cache_dir_str = self.cache_dir.to_str().ok_or_else(|| {
             raise_error!(
                 "Failed to convert cache_dir to str".into(),
                 ErrorCode::InternalError
             )
         })?;
+        if item.tier == CacheTier::Remote && !self.restore_from_remote(cache_dir_str, &item).await?
+        {
+            return Ok(None);
+        }
         let reader = cacache::Reader::open(cache_dir_str, key)
             .await
             .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let reader = CacheReader::new(key, reader).await?;
         CacheItem::update_access(key).await?;
         Ok(Some(reader))
     }
 
     pub async fn clear(&self) -> RustMailerResult<()> {
+        if let Some(storage) = REMOTE_STORAGE.as_ref() {
+            for item in CacheItem::list().await? {
+                if item.tier == CacheTier::Remote {
+                    if let Err(e) = storage.delete(&item.key).await {
+                        warn!("Failed to delete remote cache item key={}: {}", item.key, e);
+                    }
+                }
+            }
+        }
         CacheItem::clear().await?;
         let cache_dir_str = match self.cache_dir.to_str() {
             Some(dir) => dir,
@@ -210,18 +429,39 @@ impl DiskCache {
             .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
     }
 
+    /// Verifies cached content and removes index items and content that no longer
+    /// match each other. See [`reconcile::reconcile`].
+    pub async fn reconcile(&self) -> RustMailerResult<ReconcileReport> {
+        reconcile::reconcile(&self.cache_dir).await
+    }
+
     pub async fn clean_cache_if_needed(&self) {
         let cache_items = match CacheItem::list().await {
             Ok(items) => {
-                let mut items = items;
-                items.sort_by_key(|item| item.last_access_at);
+                // Only items stored on local disk take up local space.
                 items
+                    .into_iter()
+                    .filter(|item| item.tier == CacheTier::Local)
+                    .collect::<Vec<CacheItem>>()
             }
             Err(e) => {
                 error!("Failed to fetch cache items: {}", e);
                 return;
             }
         };
+        // Convert cache_dir to str once and handle early return
+        let cache_dir_str = match self.cache_dir.to_str() {
+            Some(dir) => dir,
+            None => {
+                error!("Failed to convert cache_dir to string");
+                return;
+            }
+        };
+
+        // Enforce namespace quotas first, regardless of disk usage.
+        let mut cache_items = self.enforce_quotas(cache_dir_str, cache_items).await;
+        cache_items.sort_by_key(|item| item.last_access_at);
+
         // Early return if we can't get disk space
         let disk_space = match get_mount_disk_space(&self.cache_dir) {
             Some(space) => space,
@@ -240,24 +480,126 @@ impl DiskCache {
         }
         info!("Disk usage is {}%, initiating cache cleanup", current_usage);
 
-        // Convert cache_dir to str once and handle early return
-        let cache_dir_str = match self.cache_dir.to_str() {
-            Some(dir) => dir,
-            None => {
-                error!("Failed to convert cache_dir to string");
-                return;
-            }
-        };
         // Clean cache items
         let to_delete = select_items_to_delete(&cache_items, &disk_space);
-        for item in to_delete {
-            if let Err(e) = Self::remove_cache_item(cache_dir_str, &item).await {
+        Self::evict(cache_dir_str, to_delete).await;
+        let new_usage = calculate_disk_usage_percentage(&disk_space);
+        info!("Cache cleaned, disk usage reduced to {}%", new_usage);
+    }
+
+    /// Evicts items of namespaces that exceed their quota, following the eviction policy
+    /// of each namespace. Returns the items that were kept.
+    async fn enforce_quotas(&self, cache_dir: &str, cache_items: Vec<CacheItem>) -> Vec<CacheItem> {
+        let mut kept = Vec::with_capacity(cache_items.len());
+        for namespace in CacheNamespace::ALL {
+            let mut items: Vec<CacheItem> = cache_items
+                .iter()
+                .filter(|item| item.namespace == namespace)
+                .cloned()
+                .collect();
+            if let Some(quota) = namespace.quota() {
+                namespace.eviction_policy().sort(&mut items);
+                let to_evict = select_items_over_quota(&items, quota);
+                if !to_evict.is_empty() {
+                    info!(
+                        "Disk cache namespace {:?} exceeds its quota of {} bytes, evicting {} items",
+                        namespace,
+                        quota,
+                        to_evict.len()
+                    );
+                    items.retain(|item| !to_evict.iter().any(|e| e.key == item.key));
+                    Self::evict(cache_dir, to_evict).await;
+                }
+            }
+            kept.extend(items);
+        }
+        kept
+    }
+
+    /// Moves items to the remote tier if one is configured, otherwise removes them.
+    async fn evict(cache_dir: &str, items: Vec<CacheItem>) {
+        for item in items {
+            let result = match REMOTE_STORAGE.as_ref() {
+                Some(storage) => Self::move_to_remote(storage, cache_dir, &item).await,
+                None => Self::remove_cache_item(cache_dir, &item).await,
+            };
+            if let Err(e) = result {
                 error!("Cache item cleanup failed for key={}: {}", item.key, e);
                 continue; // Continue with next item instead of returning
             }
         }
-        let new_usage = calculate_disk_usage_percentage(&disk_space);
-        info!("Cache cleaned, disk usage reduced to {}%", new_usage);
+    }
+
+    /// Uploads the item to the remote tier, then frees its local copy.
+    async fn move_to_remote(
+        storage: &ObjectStorage,
+        cache_dir: &str,
+        item: &CacheItem,
+    ) -> Result<(), String> {
+        let data = cacache::read(cache_dir, &item.key)
+            .await
+            .map_err(|e| format!("Failed to read cache item from disk: {:?}", e))?;
+        storage
+            .put(&item.key, data)
+            .await
+            .map_err(|e| format!("Failed to upload cache item: {}", e))?;
+        // Mark the item as remote before removing the local copy, so that a failure in
+        // between leaves a readable item.
+        CacheItem::set_tier(&item.key, CacheTier::Remote)
+            .await
+            .map_err(|e| format!("Failed to update cache item tier: {}", e))?;
+        cacache::RemoveOpts::new()
+            .remove_fully(true)
+            .remove(cache_dir, &item.key)
+            .await
+            .map_err(|e| format!("Failed to remove cache item from disk: {:?}", e))
+    }
+
+    /// Fetches a remote item back to local disk. Returns `false` if the remote object is
+    /// gone and the item was not restored meanwhile, in which case the stale index entry is
+    /// removed.
+    async fn restore_from_remote(
+        &self,
+        cache_dir: &str,
+        item: &CacheItem,
+    ) -> RustMailerResult<bool> {
+        let storage = REMOTE_STORAGE.as_ref().ok_or_else(|| {
+            raise_error!(
+                format!(
+                    "Cache item {} is stored remotely, but no disk cache object storage is configured",
+                    item.key
+                ),
+                ErrorCode::MissingConfiguration
+            )
+        })?;
+        let Some(data) = storage.get(&item.key).await? else {
+            // A concurrent read may have restored the item and removed the remote object.
+            let local = cacache::metadata(cache_dir, &item.key)
+                .await
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .is_some();
+            return match CacheItem::find(&item.key).await? {
+                Some(current) if current.tier == CacheTier::Remote && !local => {
+                    warn!("Remote cache item key={} no longer exists", item.key);
+                    current.delete().await?;
+                    Ok(false)
+                }
+                Some(_) => Ok(true),
+                None => Ok(false),
+            };
+        };
+        cacache::write(cache_dir, &item.key, &data)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        CacheItem::set_tier(&item.key, CacheTier::Local).await?;
+        if let Err(e) = storage.delete(&item.key).await {
+            warn!(
+                "Failed to delete restored remote cache item key={}: {}",
+                item.key, e
+            );
+        }
+        debug!("Restored cache item key={} from remote storage", item.key);
+        Ok(true)
     }
 
     // Helper function to handle item removal
@@ -281,6 +623,29 @@ fn calculate_disk_usage_percentage(disk_space: &DiskSpace) -> f64 {
     (used_space as f64 / disk_space.total_space as f64) * 100.0
 }
 
+// Outgoing messages are kept for a week so that they can still be sent.
+fn is_protected(item: &CacheItem, now: i64) -> bool {
+    item.pending && now < item.write_at + ONE_WEEK_MS
+}
+
+// Helper function to select which items, in eviction order, to evict to bring a namespace under its quota
+fn select_items_over_quota(items: &[CacheItem], quota: u64) -> Vec<CacheItem> {
+    let now = utc_now!();
+    let mut used: u64 = items.iter().map(|item| item.size).sum();
+    let mut to_evict = Vec::new();
+    for item in items {
+        if used <= quota {
+            break;
+        }
+        if is_protected(item, now) {
+            continue;
+        }
+        used = used.saturating_sub(item.size);
+        to_evict.push(item.clone());
+    }
+    to_evict
+}
+
 // Helper function to select which cache items to delete to free space
 fn select_items_to_delete(cache_items: &[CacheItem], disk_space: &DiskSpace) -> Vec<CacheItem> {
     let now = utc_now!();
@@ -288,7 +653,7 @@ fn select_items_to_delete(cache_items: &[CacheItem], disk_space: &DiskSpace) ->
     let mut freed_space = 0;
     let total_items = cache_items.len();
     for item in cache_items {
-        if item.pending && now < item.write_at + ONE_WEEK_MS {
+        if is_protected(item, now) {
             continue;
         }
 
@@ -365,6 +730,8 @@ mod tests {
                 pending: false,
                 write_at: 100,
                 last_access_at: 300,
+                tier: CacheTier::Local,
+                namespace: CacheNamespace::Content,
             },
             CacheItem {
                 key: "b".to_string(),
@@ -372,6 +739,8 @@ mod tests {
                 pending: false,
                 write_at: 200,
                 last_access_at: 100,
+                tier: CacheTier::Local,
+                namespace: CacheNamespace::Content,
             },
             CacheItem {
                 key: "c".to_string(),
@@ -379,6 +748,8 @@ mod tests {
                 pending: true,
                 write_at: 300,
                 last_access_at: 200,
+                tier: CacheTier::Local,
+                namespace: CacheNamespace::Content,
             },
         ];
 
@@ -390,4 +761,48 @@ mod tests {
         items = items.drain(1..).collect();
         println!("{:#?}", items);
     }
+
+    #[test]
+    fn test_select_items_over_quota() {
+        let now = utc_now!();
+        let item = |key: &str, size: u64, pending: bool, last_access_at: i64| CacheItem {
+            key: key.to_string(),
+            size,
+            pending,
+            write_at: now,
+            last_access_at,
+            tier: CacheTier::Local,
+            namespace: CacheNamespace::Attachment,
+        };
+        let mut items = vec![
+            item("c", 30, false, 300),
+            item("a", 10, false, 100),
+            item("p", 50, true, 50),
+            item("b", 20, false, 200),
+        ];
+        EvictionPolicy::LeastRecentlyUsed.sort(&mut items);
+
+        // 110 bytes in use: evicting "a" and "b" brings the namespace under 85 bytes,
+        // the pending item is never evicted.
+        let evicted = select_items_over_quota(&items, 85);
+        let keys: Vec<&str> = evicted.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(keys, vec!["a", "b"]);
+        assert!(select_items_over_quota(&items, 110).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_namespace() {
+        assert_eq!(
+            CacheNamespace::from_legacy_item("abcdef", true),
+            CacheNamespace::Outgoing
+        );
+        assert_eq!(
+            CacheNamespace::from_legacy_item("gmail_inline_attachment_1_m_h", false),
+            CacheNamespace::Attachment
+        );
+        assert_eq!(
+            CacheNamespace::from_legacy_item("imap_raw_email_1_INBOX_2", false),
+            CacheNamespace::Content
+        );
+    }
 }
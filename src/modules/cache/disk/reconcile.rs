@@ -0,0 +1,208 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{path::Path, time::Instant};
+
+use ahash::{AHashMap, AHashSet};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    modules::{
+        cache::disk::{CacheItem, CacheTier},
+        database::{batch_delete_impl, manager::DB_MANAGER},
+        error::{code::ErrorCode, RustMailerResult},
+    },
+    raise_error, utc_now,
+};
+
+/// Entries written more recently than this are left alone, since `put_cache` writes the
+/// content before its index item and a reconciliation run may observe the gap.
+const GRACE_PERIOD_MS: i64 = 10 * 60 * 1000;
+
+/// Outcome of a disk cache reconciliation run.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ReconcileReport {
+    /// Cache items checked in the metadata index.
+    pub items_checked: usize,
+    /// Content entries checked in the cache directory.
+    pub entries_checked: usize,
+    /// Cache items removed because their content was missing from disk.
+    pub missing_content_removed: usize,
+    /// Entries removed, together with their cache item, because the content failed digest verification.
+    pub corrupted_removed: usize,
+    /// Content entries removed from disk because no local cache item referenced them.
+    pub orphaned_entries_removed: usize,
+    /// Cache items stored in the remote tier, which are not verified.
+    pub remote_items_skipped: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Result of scanning the cache directory against the metadata index.
+#[derive(Debug, Default)]
+struct LocalScan {
+    entries_checked: usize,
+    orphaned_entries_removed: usize,
+    /// Keys whose content failed verification; the entries were already removed from disk.
+    corrupted: Vec<String>,
+    /// Keys with verified local content.
+    verified: AHashSet<String>,
+}
+
+/// Verifies the digest of every entry in the cache directory, removing corrupted entries
+/// and entries that no local cache item refers to. Entries written after `cutoff`
+/// (UNIX epoch milliseconds) are skipped. Blocking.
+fn scan_local_entries(
+    cache_dir: &Path,
+    items: &AHashMap<String, CacheTier>,
+    cutoff: i64,
+) -> RustMailerResult<LocalScan> {
+    let mut scan = LocalScan::default();
+    let entries: Vec<cacache::Metadata> = cacache::list_sync(cache_dir)
+        .filter_map(|entry| match entry {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                warn!("Skipping unreadable disk cache index entry: {:#?}", e);
+                None
+            }
+        })
+        .collect();
+
+    for entry in entries {
+        scan.entries_checked += 1;
+        if entry.time > cutoff.max(0) as u128 {
+            scan.verified.insert(entry.key);
+            continue;
+        }
+        let valid = match items.get(&entry.key) {
+            // A remote item may still have a local copy if its removal failed during tiering.
+            None | Some(CacheTier::Remote) => {
+                remove_entry(cache_dir, &entry.key)?;
+                scan.orphaned_entries_removed += 1;
+                continue;
+            }
+            Some(CacheTier::Local) => cacache::read_hash_sync(cache_dir, &entry.integrity).is_ok(),
+        };
+        if valid {
+            scan.verified.insert(entry.key);
+        } else {
+            remove_entry(cache_dir, &entry.key)?;
+            scan.corrupted.push(entry.key);
+        }
+    }
+    Ok(scan)
+}
+
+fn remove_entry(cache_dir: &Path, key: &str) -> RustMailerResult<()> {
+    cacache::RemoveOpts::new()
+        .remove_fully(true)
+        .remove_sync(cache_dir, key)
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+}
+
+/// Brings the cache items in the metadata database and the content in the cache
+/// directory back in sync. Items without valid content and content without an item are
+/// both removed.
+pub async fn reconcile(cache_dir: &Path) -> RustMailerResult<ReconcileReport> {
+    let start = Instant::now();
+    let cutoff = utc_now!() - GRACE_PERIOD_MS;
+    let items = CacheItem::list().await?;
+    let tiers: AHashMap<String, CacheTier> = items
+        .iter()
+        .map(|item| (item.key.clone(), item.tier))
+        .collect();
+
+    let dir = cache_dir.to_path_buf();
+    let scan = tokio::task::spawn_blocking(move || scan_local_entries(&dir, &tiers, cutoff))
+        .await
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))??;
+
+    let remote_items_skipped = items
+        .iter()
+        .filter(|item| item.tier == CacheTier::Remote)
+        .count();
+    let missing: Vec<String> = items
+        .iter()
+        .filter(|item| {
+            item.tier == CacheTier::Local
+                && item.write_at <= cutoff
+                && !scan.verified.contains(&item.key)
+                && !scan.corrupted.contains(&item.key)
+        })
+        .map(|item| item.key.clone())
+        .collect();
+
+    let mut to_delete = missing.clone();
+    to_delete.extend(scan.corrupted.iter().cloned());
+    if !to_delete.is_empty() {
+        batch_delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            let mut found = Vec::with_capacity(to_delete.len());
+            for key in to_delete {
+                if let Some(item) = rw
+                    .get()
+                    .primary::<CacheItem>(key)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                {
+                    found.push(item);
+                }
+            }
+            Ok(found)
+        })
+        .await?;
+    }
+
+    let report = ReconcileReport {
+        items_checked: items.len(),
+        entries_checked: scan.entries_checked,
+        missing_content_removed: missing.len(),
+        corrupted_removed: scan.corrupted.len(),
+        orphaned_entries_removed: scan.orphaned_entries_removed,
+        remote_items_skipped,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    };
+    info!(
+        "Disk cache reconciled: {} items and {} entries checked, {} missing, {} corrupted, {} orphaned entries removed in {} ms.",
+        report.items_checked,
+        report.entries_checked,
+        report.missing_content_removed,
+        report.corrupted_removed,
+        report.orphaned_entries_removed,
+        report.elapsed_ms
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_local_entries_removes_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path();
+        cacache::write_sync(path, "indexed", b"hello").unwrap();
+        cacache::write_sync(path, "orphan", b"world").unwrap();
+        cacache::write_sync(path, "tiered", b"moved").unwrap();
+
+        let mut items = AHashMap::new();
+        items.insert("indexed".to_string(), CacheTier::Local);
+        items.insert("tiered".to_string(), CacheTier::Remote);
+
+        let scan = scan_local_entries(path, &items, i64::MAX).unwrap();
+        assert_eq!(scan.entries_checked, 3);
+        assert_eq!(scan.orphaned_entries_removed, 2);
+        assert!(scan.corrupted.is_empty());
+        assert!(scan.verified.contains("indexed"));
+        assert!(cacache::read_sync(path, "orphan").is_err());
+        assert!(cacache::read_sync(path, "tiered").is_err());
+        assert_eq!(cacache::read_sync(path, "indexed").unwrap(), b"hello");
+
+        // Entries written after the cutoff are not touched.
+        cacache::write_sync(path, "fresh", b"new").unwrap();
+        let scan = scan_local_entries(path, &items, 0).unwrap();
+        assert_eq!(scan.orphaned_entries_removed, 0);
+        assert!(cacache::read_sync(path, "fresh").is_ok());
+    }
+}
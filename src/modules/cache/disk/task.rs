@@ -6,7 +6,9 @@ use crate::modules::{
     cache::disk::DISK_CACHE, context::RustMailTask, scheduler::periodic::PeriodicTask,
 };
 use std::time::Duration;
+use tracing::error;
 const TASK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
 
 ///This task periodically cleans up the disk cache when storage usage exceeds a specified threshold to ensure efficient use of disk space.
 pub struct DiskCacheCleanTask;
@@ -25,3 +27,23 @@ impl RustMailTask for DiskCacheCleanTask {
         periodic_task.start(task, None, TASK_INTERVAL, false, false);
     }
 }
+
+///This task periodically verifies the disk cache and removes index items and files that no longer match each other.
+pub struct DiskCacheReconcileTask;
+
+impl RustMailTask for DiskCacheReconcileTask {
+    fn start() {
+        let periodic_task = PeriodicTask::new("disk-cache-reconciler");
+
+        let task = move |_: Option<u64>| {
+            Box::pin(async move {
+                if let Err(e) = DISK_CACHE.reconcile().await {
+                    error!("Disk cache reconciliation failed: {:#?}", e);
+                }
+                Ok(())
+            })
+        };
+
+        periodic_task.start(task, None, RECONCILE_INTERVAL, false, false);
+    }
+}
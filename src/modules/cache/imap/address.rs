@@ -14,9 +14,10 @@ use crate::{
     id,
     modules::{
         cache::{
-            imap::migration::EmailEnvelopeV3,
+            imap::migration::EmailEnvelopeV7,
             vendor::{
-                gmail::sync::envelope::GmailEnvelope, outlook::sync::envelope::OutlookEnvelope,
+                gmail::sync::envelope::GmailEnvelope, jmap::sync::envelope::JmapEnvelope,
+                outlook::sync::envelope::OutlookEnvelope,
             },
         },
         database::{batch_delete_impl, filter_by_secondary_key_impl, manager::DB_MANAGER},
@@ -196,7 +197,7 @@ impl AddressEntity {
         Ok(())
     }
 
-    pub fn extract(envelope: &EmailEnvelopeV3) -> Vec<AddressEntity> {
+    pub fn extract(envelope: &EmailEnvelopeV7) -> Vec<AddressEntity> {
         let from = envelope.from.as_ref().map(|f| f.address.clone()).flatten();
         let envelope_hash = envelope.create_envelope_id();
         let date = envelope.date.clone();
@@ -357,6 +358,82 @@ impl AddressEntity {
         let mailbox_id = envelope.folder_id;
         let mut entities = Vec::new();
 
+        match (&envelope.to, &envelope.cc) {
+            (None, None) => {
+                entities.push(AddressEntity {
+                    account_id,
+                    mailbox_id,
+                    id: id!(96),
+                    from,
+                    to: None,
+                    cc: None,
+                    envelope_hash,
+                    date,
+                    internal_date,
+                });
+            }
+            (None, Some(cc)) => {
+                entities.extend(cc.iter().map(|c| {
+                    let from = from.clone();
+                    AddressEntity {
+                        account_id,
+                        mailbox_id,
+                        id: id!(96),
+                        from,
+                        to: None,
+                        cc: c.address.clone(),
+                        envelope_hash,
+                        date,
+                        internal_date,
+                    }
+                }));
+            }
+            (Some(to), None) => {
+                entities.extend(to.iter().map(|t| {
+                    let from = from.clone();
+                    AddressEntity {
+                        account_id,
+                        mailbox_id,
+                        id: id!(96),
+                        from,
+                        to: t.address.clone(),
+                        cc: None,
+                        envelope_hash,
+                        date,
+                        internal_date,
+                    }
+                }));
+            }
+            (Some(to), Some(cc)) => {
+                entities.extend(to.iter().flat_map(|t| {
+                    let from = from.clone();
+                    cc.iter().map(move |c| AddressEntity {
+                        account_id,
+                        mailbox_id,
+                        id: id!(96),
+                        from: from.clone(),
+                        to: t.address.clone(),
+                        cc: c.address.clone(),
+                        envelope_hash,
+                        date,
+                        internal_date,
+                    })
+                }));
+            }
+        }
+
+        entities
+    }
+
+    pub fn extract4(envelope: &JmapEnvelope) -> Vec<AddressEntity> {
+        let from = envelope.from.as_ref().and_then(|f| f.address.clone());
+        let envelope_hash = envelope.create_envelope_id();
+        let date = envelope.date;
+        let internal_date = envelope.internal_date;
+        let account_id = envelope.account_id;
+        let mailbox_id = envelope.folder_id;
+        let mut entities = Vec::new();
+
         match (&envelope.to, &envelope.cc) {
             (None, None) => {
                 entities.push(AddressEntity {
@@ -0,0 +1,240 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use native_db::transaction::RwTransaction;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error};
+
+use crate::{
+    modules::{
+        cache::imap::{
+            mailbox::EnvelopeFlag,
+            manager::{FlagsHash, UID},
+            migration::{EmailEnvelopeV7, EmailEnvelopeV7Key},
+            minimal::MinimalEnvelope,
+        },
+        database::manager::DB_MANAGER,
+        error::{code::ErrorCode, RustMailerResult},
+        metrics::{
+            RUSTMAILER_FLAG_WRITE_COALESCED_SUBMISSIONS_TOTAL,
+            RUSTMAILER_FLAG_WRITE_TRANSACTIONS_TOTAL,
+        },
+        utils::envelope_hash,
+    },
+    raise_error,
+};
+
+/// Number of pending envelope flag updates at which a batch is written without waiting
+/// for more submissions.
+const MAX_BATCH_SIZE: usize = 2000;
+/// Maximum time a submission waits for other submissions to be coalesced with it.
+const MAX_BATCH_LATENCY: Duration = Duration::from_millis(50);
+
+static FLAG_WRITE_COALESCER: LazyLock<FlagWriteCoalescer> = LazyLock::new(FlagWriteCoalescer::new);
+
+/// A flag change for one cached envelope. `flags` is `None` for minimal-sync accounts,
+/// which only keep the flags hash.
+pub struct FlagUpdate {
+    pub account_id: u64,
+    pub mailbox_id: u64,
+    pub uid: UID,
+    pub flags: Option<Vec<EnvelopeFlag>>,
+    pub flags_hash: FlagsHash,
+}
+
+struct Submission {
+    updates: Vec<FlagUpdate>,
+    done: oneshot::Sender<RustMailerResult<()>>,
+}
+
+/// Coalesces envelope flag updates from concurrent sync runs and API calls into large
+/// write transactions on the envelope database.
+///
+/// Submissions are collected until `MAX_BATCH_SIZE` updates are pending or the oldest
+/// submission has waited `MAX_BATCH_LATENCY`, then written together. Repeated updates of
+/// the same envelope within a batch are collapsed into the latest one. Each batch is
+/// written in a single transaction, so a failing batch commits nothing. Callers are only
+/// released once their updates are committed. If the batch fails, each submission is
+/// retried in its own transaction, so that only the failing ones get an error.
+pub struct FlagWriteCoalescer {
+    sender: mpsc::UnboundedSender<Submission>,
+}
+
+impl FlagWriteCoalescer {
+    fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(Self::run(receiver));
+        Self { sender }
+    }
+
+    /// Writes `updates` as part of the next batch and waits for the batch to be committed.
+    pub async fn submit(updates: Vec<FlagUpdate>) -> RustMailerResult<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+        let (done, result) = oneshot::channel();
+        FLAG_WRITE_COALESCER
+            .sender
+            .send(Submission { updates, done })
+            .map_err(|_| {
+                raise_error!(
+                    "Envelope flag write coalescer is not running".into(),
+                    ErrorCode::InternalError
+                )
+            })?;
+        result.await.map_err(|_| {
+            raise_error!(
+                "Envelope flag write coalescer dropped the submission".into(),
+                ErrorCode::InternalError
+            )
+        })?
+    }
+
+    async fn run(mut receiver: mpsc::UnboundedReceiver<Submission>) {
+        while let Some(first) = receiver.recv().await {
+            let deadline = tokio::time::Instant::now() + MAX_BATCH_LATENCY;
+            let mut pending = first.updates.len();
+            let mut submissions = vec![first];
+            while pending < MAX_BATCH_SIZE {
+                match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                    Ok(Some(submission)) => {
+                        pending += submission.updates.len();
+                        submissions.push(submission);
+                    }
+                    _ => break,
+                }
+            }
+            Self::flush(submissions).await;
+        }
+    }
+
+    async fn flush(submissions: Vec<Submission>) {
+        let (updates, senders): (Vec<Vec<FlagUpdate>>, Vec<_>) = submissions
+            .into_iter()
+            .map(|submission| (submission.updates, submission.done))
+            .unzip();
+        RUSTMAILER_FLAG_WRITE_COALESCED_SUBMISSIONS_TOTAL.inc_by(senders.len() as u64);
+        let updates = Arc::new(updates);
+
+        let start = Instant::now();
+        let batch = updates.clone();
+        let result = write_blocking(move || {
+            let mut merged: AHashMap<(u64, u64, UID), &FlagUpdate> = AHashMap::new();
+            for update in batch.iter().flatten() {
+                merged.insert((update.account_id, update.mailbox_id, update.uid), update);
+            }
+            let merged: Vec<&FlagUpdate> = merged.into_values().collect();
+            write_updates(&merged).map(|skipped| (merged.len(), skipped))
+        })
+        .await;
+
+        match result {
+            Ok((total, skipped)) => {
+                debug!(
+                    "Wrote {} envelope flag updates from {} submissions in one transaction, {} skipped, {} ms.",
+                    total,
+                    senders.len(),
+                    skipped,
+                    start.elapsed().as_millis()
+                );
+                for sender in senders {
+                    let _ = sender.send(Ok(()));
+                }
+            }
+            Err(e) if senders.len() == 1 => {
+                error!("Failed to write envelope flag updates: {:#?}", e);
+                if let Some(sender) = senders.into_iter().next() {
+                    let _ = sender.send(Err(e));
+                }
+            }
+            Err(e) => {
+                error!(
+                    "Failed to write {} coalesced envelope flag submissions, retrying them one by one: {:#?}",
+                    senders.len(),
+                    e
+                );
+                for (index, sender) in senders.into_iter().enumerate() {
+                    let updates = updates.clone();
+                    let result = write_blocking(move || {
+                        let submission: Vec<&FlagUpdate> = updates[index].iter().collect();
+                        write_updates(&submission)
+                    })
+                    .await;
+                    if let Err(e) = &result {
+                        error!("Failed to write envelope flag updates: {:#?}", e);
+                    }
+                    let _ = sender.send(result.map(|_| ()));
+                }
+            }
+        }
+    }
+}
+
+/// Runs `write` on the blocking thread pool.
+async fn write_blocking<T: Send + 'static>(
+    write: impl FnOnce() -> RustMailerResult<T> + Send + 'static,
+) -> RustMailerResult<T> {
+    tokio::task::spawn_blocking(write)
+        .await
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+        .and_then(|r| r)
+}
+
+/// Writes `updates` in a single transaction, so that a failure commits none of them.
+/// Returns the number of updates skipped.
+fn write_updates(updates: &[&FlagUpdate]) -> RustMailerResult<usize> {
+    let rw = DB_MANAGER
+        .envelope_db()
+        .rw_transaction()
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    let mut skipped = 0usize;
+    for update in updates {
+        if !apply_update(&rw, update)? {
+            skipped += 1;
+        }
+    }
+    rw.commit()
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    RUSTMAILER_FLAG_WRITE_TRANSACTIONS_TOTAL.inc();
+    Ok(skipped)
+}
+
+/// Applies one update inside `rw`. Returns `false` if the envelope is no longer cached,
+/// e.g. because it was removed by a concurrent sync.
+fn apply_update(rw: &RwTransaction, update: &FlagUpdate) -> RustMailerResult<bool> {
+    let key = envelope_hash(update.account_id, update.mailbox_id, update.uid);
+    let Some(minimal) = rw
+        .get()
+        .primary::<MinimalEnvelope>(key)
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+    else {
+        return Ok(false);
+    };
+    let mut updated = minimal.clone();
+    updated.flags_hash = update.flags_hash;
+    rw.update(minimal, updated)
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+
+    if let Some(flags) = &update.flags {
+        let Some(envelope) = rw
+            .get()
+            .secondary::<EmailEnvelopeV7>(EmailEnvelopeV7Key::create_envelope_id, key)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+        else {
+            return Ok(false);
+        };
+        let mut updated = envelope.clone();
+        updated.flags = flags.clone();
+        updated.flags_hash = update.flags_hash;
+        rw.update(envelope, updated)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,252 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Instant;
+
+use ahash::AHashSet;
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    modules::{
+        account::migration::AccountModel,
+        cache::imap::{
+            mailbox::MailBox,
+            manager::EnvelopeFlagsManager,
+            sync::flow::{compress_uid_list, handle_minimal_sync_or_metadata_fetch},
+        },
+        context::executors::RUST_MAIL_CONTEXT,
+        envelope::extractor::parse_fetch_metadata,
+        error::RustMailerResult,
+    },
+    utc_now,
+};
+
+/// Maximum number of UIDs listed per discrepancy in a report.
+const MAX_REPORTED_UIDS: usize = 100;
+const REPAIR_BATCH_SIZE: usize = 1000;
+
+/// Result of comparing the local envelope cache of a mailbox with the IMAP server.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct MailboxIntegrityReport {
+    /// The decoded name of the mailbox.
+    pub mailbox_name: String,
+    /// Number of messages reported by the server (`EXISTS`).
+    pub remote_exists: u32,
+    /// Number of UIDs on the server that fall within the account sync window.
+    pub remote_count: usize,
+    /// Number of UIDs cached locally for this mailbox.
+    pub local_count: usize,
+    /// Lowest UID on the server within the sync window.
+    pub remote_min_uid: Option<u32>,
+    /// Highest UID on the server within the sync window.
+    pub remote_max_uid: Option<u32>,
+    /// Lowest UID in the local cache.
+    pub local_min_uid: Option<u32>,
+    /// Highest UID in the local cache.
+    pub local_max_uid: Option<u32>,
+    /// Number of UIDs present on the server but missing from the local cache.
+    pub missing_count: usize,
+    /// Up to 100 UIDs missing from the local cache, compressed as an IMAP sequence set.
+    pub missing_sample: Option<String>,
+    /// Number of locally cached UIDs that no longer exist on the server.
+    pub orphan_count: usize,
+    /// Up to 100 orphaned UIDs, compressed as an IMAP sequence set.
+    pub orphan_sample: Option<String>,
+    /// Whether the UIDVALIDITY stored locally differs from the one reported by the server.
+    pub uid_validity_mismatch: bool,
+    /// Whether the discrepancies of this mailbox have been repaired.
+    pub repaired: bool,
+    /// Error message if the mailbox could not be checked or repaired.
+    pub error: Option<String>,
+}
+
+impl MailboxIntegrityReport {
+    pub fn is_consistent(&self) -> bool {
+        self.error.is_none()
+            && self.missing_count == 0
+            && self.orphan_count == 0
+            && !self.uid_validity_mismatch
+    }
+}
+
+/// Summary of an envelope cache integrity check for one account.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct IntegrityReport {
+    /// The account that was checked.
+    pub account_id: u64,
+    /// Whether repairs were requested for this run.
+    pub repair: bool,
+    /// `true` if no discrepancies were found in any mailbox.
+    pub consistent: bool,
+    /// Per-mailbox results.
+    pub mailboxes: Vec<MailboxIntegrityReport>,
+    /// Time at which the check started (UNIX epoch milliseconds).
+    pub started_at: i64,
+    /// Duration of the check in milliseconds.
+    pub elapsed_ms: u64,
+}
+
+/// Compares the locally cached envelopes of every synchronized mailbox against the
+/// UIDs reported by the IMAP server.
+///
+/// When `repair` is set, missing UIDs are fetched and stored, and orphaned local
+/// envelopes are removed. Mailboxes whose UIDVALIDITY changed are only reported;
+/// they are handled by the regular sync or the single mailbox resync endpoint.
+pub async fn check_envelope_integrity(
+    account_id: u64,
+    repair: bool,
+) -> RustMailerResult<IntegrityReport> {
+    let account = AccountModel::check_account_active(account_id, true).await?;
+    let started_at = utc_now!();
+    let start_time = Instant::now();
+
+    let local_mailboxes = MailBox::list_all(account_id).await?;
+    let mut mailboxes = Vec::with_capacity(local_mailboxes.len());
+    for mailbox in &local_mailboxes {
+        let report = match check_mailbox(&account, mailbox, repair).await {
+            Ok(report) => report,
+            Err(e) => {
+                warn!(
+                    "Account {}: Integrity check of mailbox '{}' failed: {:#?}",
+                    account_id, mailbox.name, e
+                );
+                MailboxIntegrityReport {
+                    mailbox_name: mailbox.name.clone(),
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                }
+            }
+        };
+        mailboxes.push(report);
+    }
+
+    let consistent = mailboxes.iter().all(|m| m.is_consistent());
+    info!(
+        "Account {}: Envelope integrity check finished, {} mailboxes checked, consistent={}, repair={}.",
+        account_id,
+        mailboxes.len(),
+        consistent,
+        repair
+    );
+    Ok(IntegrityReport {
+        account_id,
+        repair,
+        consistent,
+        mailboxes,
+        started_at,
+        elapsed_ms: start_time.elapsed().as_millis() as u64,
+    })
+}
+
+async fn check_mailbox(
+    account: &AccountModel,
+    mailbox: &MailBox,
+    repair: bool,
+) -> RustMailerResult<MailboxIntegrityReport> {
+    let executor = RUST_MAIL_CONTEXT.imap(account.id).await?;
+    let encoded_name = mailbox.encoded_name();
+    let status = executor.examine_mailbox(&encoded_name).await?;
+
+    let query = match &account.sync_since()? {
+        Some(date_since) => format!("SINCE {}", date_since.since_date()?),
+        None => "ALL".to_string(),
+    };
+    let mut remote_uids: Vec<u32> = executor
+        .uid_search(&encoded_name, &query)
+        .await?
+        .into_iter()
+        .collect();
+    remote_uids.sort();
+    // Mirror the sync behaviour: only the most recent `folder_limit` UIDs are cached.
+    if let Some(limit) = account.folder_limit {
+        let limit = limit.max(100) as usize;
+        if remote_uids.len() > limit {
+            remote_uids = remote_uids.split_off(remote_uids.len() - limit);
+        }
+    }
+
+    let local_uids: AHashSet<u32> = EnvelopeFlagsManager::get_uid_map(account.id, mailbox.id, 0)
+        .into_keys()
+        .collect();
+    let (missing, orphans) = diff_uid_sets(&local_uids, &remote_uids);
+
+    let mut report = MailboxIntegrityReport {
+        mailbox_name: mailbox.name.clone(),
+        remote_exists: status.exists,
+        remote_count: remote_uids.len(),
+        local_count: local_uids.len(),
+        remote_min_uid: remote_uids.first().copied(),
+        remote_max_uid: remote_uids.last().copied(),
+        local_min_uid: local_uids.iter().min().copied(),
+        local_max_uid: local_uids.iter().max().copied(),
+        missing_count: missing.len(),
+        missing_sample: uid_sample(&missing),
+        orphan_count: orphans.len(),
+        orphan_sample: uid_sample(&orphans),
+        uid_validity_mismatch: mailbox.uid_validity.is_some()
+            && mailbox.uid_validity != status.uid_validity,
+        repaired: false,
+        error: None,
+    };
+
+    if !repair || report.uid_validity_mismatch || (missing.is_empty() && orphans.is_empty()) {
+        return Ok(report);
+    }
+
+    if !orphans.is_empty() {
+        info!(
+            "Account {}: Removing {} orphaned envelopes from mailbox '{}'.",
+            account.id,
+            orphans.len(),
+            mailbox.name
+        );
+        EnvelopeFlagsManager::clean_envelopes(account.id, mailbox.id, &orphans).await?;
+    }
+
+    if !missing.is_empty() {
+        info!(
+            "Account {}: Fetching {} missing envelopes for mailbox '{}'.",
+            account.id,
+            missing.len(),
+            mailbox.name
+        );
+        for chunk in missing.chunks(REPAIR_BATCH_SIZE) {
+            let fetches = executor
+                .uid_fetch_uid_and_flags(&compress_uid_list(chunk.to_vec()), &encoded_name)
+                .await?;
+            let uid_list: Vec<(u32, u64)> = parse_fetch_metadata(fetches, false)?
+                .into_iter()
+                .map(|(uid, (flags_hash, _))| (uid, flags_hash))
+                .collect();
+            let len = uid_list.len();
+            if len > 0 {
+                handle_minimal_sync_or_metadata_fetch(account, mailbox.id, mailbox, uid_list, len)
+                    .await?;
+            }
+        }
+    }
+    report.repaired = true;
+    Ok(report)
+}
+
+/// Returns `(missing, orphans)`: UIDs only present remotely and UIDs only present locally.
+pub fn diff_uid_sets(local: &AHashSet<u32>, remote: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let remote_set: AHashSet<u32> = remote.iter().copied().collect();
+    let mut missing: Vec<u32> = remote_set.difference(local).copied().collect();
+    let mut orphans: Vec<u32> = local.difference(&remote_set).copied().collect();
+    missing.sort();
+    orphans.sort();
+    (missing, orphans)
+}
+
+fn uid_sample(uids: &[u32]) -> Option<String> {
+    if uids.is_empty() {
+        return None;
+    }
+    Some(compress_uid_list(
+        uids.iter().take(MAX_REPORTED_UIDS).copied().collect(),
+    ))
+}
@@ -0,0 +1,100 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    LazyLock,
+};
+
+use dashmap::DashMap;
+use tracing::debug;
+
+use crate::{
+    modules::cache::imap::{mailbox::EnvelopeFlag, manager::UID},
+    utc_now,
+};
+
+/// How long a journal entry protects an API-initiated flag change from stale sync data.
+/// A sync batch that started before the change will always have finished by then.
+const JOURNAL_ENTRY_TTL_MS: i64 = 10 * 60 * 1000;
+
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Flag changes made through the API in one mailbox: uid -> (sequence, recorded_at).
+type MailboxJournal = DashMap<UID, (u64, i64)>;
+
+/// Per-mailbox journal of flag changes, keyed by (account_id, mailbox_id).
+static FLAG_CHANGE_JOURNAL: LazyLock<DashMap<(u64, u64), MailboxJournal>> =
+    LazyLock::new(DashMap::new);
+
+/// Sequences flag changes made through the API against flag state fetched by the sync.
+///
+/// A sync run takes a [`FlagChangeJournal::checkpoint`] before fetching remote flags and
+/// calls [`FlagChangeJournal::discard_stale`] before applying them. Any UID whose flags
+/// were changed through the API after the checkpoint is skipped, since the fetched state
+/// may predate that change. The next sync run picks up the authoritative server state.
+pub struct FlagChangeJournal;
+
+impl FlagChangeJournal {
+    /// Returns the current journal position.
+    pub fn checkpoint() -> u64 {
+        SEQUENCE.load(Ordering::SeqCst)
+    }
+
+    /// Records a flag change for the given UIDs. Must be called after the change has been
+    /// acknowledged by the server.
+    pub fn record(account_id: u64, mailbox_id: u64, uids: &[UID]) {
+        let seq = SEQUENCE.fetch_add(1, Ordering::SeqCst) + 1;
+        let now = utc_now!();
+        let entries = FLAG_CHANGE_JOURNAL
+            .entry((account_id, mailbox_id))
+            .or_default();
+        entries.retain(|_, (_, recorded_at)| now - *recorded_at < JOURNAL_ENTRY_TTL_MS);
+        for uid in uids {
+            entries.insert(*uid, (seq, now));
+        }
+    }
+
+    /// Removes flag updates for UIDs that were changed through the API after `checkpoint`.
+    pub fn discard_stale(
+        account_id: u64,
+        mailbox_id: u64,
+        checkpoint: u64,
+        updates: Vec<(UID, Vec<EnvelopeFlag>)>,
+    ) -> Vec<(UID, Vec<EnvelopeFlag>)> {
+        let Some(entries) = FLAG_CHANGE_JOURNAL.get(&(account_id, mailbox_id)) else {
+            return updates;
+        };
+        let total = updates.len();
+        let retained: Vec<_> = updates
+            .into_iter()
+            .filter(|(uid, _)| {
+                entries
+                    .get(uid)
+                    .is_none_or(|entry| entry.value().0 <= checkpoint)
+            })
+            .collect();
+        if retained.len() < total {
+            debug!(
+                "Account {}: Skipped {} stale flag update(s) in mailbox {} superseded by API changes.",
+                account_id,
+                total - retained.len(),
+                mailbox_id
+            );
+        }
+        retained
+    }
+
+    /// Number of UIDs currently tracked by the journal.
+    pub fn len() -> usize {
+        FLAG_CHANGE_JOURNAL
+            .iter()
+            .map(|entries| entries.len())
+            .sum()
+    }
+
+    pub fn clean_account(account_id: u64) {
+        FLAG_CHANGE_JOURNAL.retain(|(id, _), _| *id != account_id);
+    }
+}
@@ -11,18 +11,22 @@ use tracing::warn;
 
 use crate::modules::account::migration::AccountModel;
 use crate::modules::cache::imap::address::AddressEntity;
+use crate::modules::cache::imap::coalescer::{FlagUpdate, FlagWriteCoalescer};
 use crate::modules::cache::imap::flags_to_hash;
+use crate::modules::cache::imap::journal::FlagChangeJournal;
 use crate::modules::cache::imap::mailbox::EnvelopeFlag;
+use crate::modules::cache::imap::migration::EmailEnvelopeV7;
 use crate::modules::cache::imap::minimal::MinimalEnvelope;
 use crate::modules::cache::imap::thread::EmailThread;
-use crate::modules::cache::imap::migration::EmailEnvelopeV3;
 use crate::modules::context::Initialize;
 use crate::modules::error::RustMailerResult;
 use crate::modules::hook::channel::{Event, EVENT_CHANNEL};
 use crate::modules::hook::events::payload::EmailFlagsChanged;
 use crate::modules::hook::events::{EventPayload, EventType, RustMailerEvent};
 use crate::modules::hook::task::EventHookTask;
-use crate::modules::metrics::RUSTMAILER_MAIL_FLAG_CHANGE_TOTAL;
+use crate::modules::metrics::{
+    RUSTMAILER_ACCOUNT_MAIL_FLAG_CHANGE_TOTAL, RUSTMAILER_MAIL_FLAG_CHANGE_TOTAL,
+};
 
 /// Type aliases
 pub type UID = u32;
@@ -75,7 +79,8 @@ impl EnvelopeFlagsManager {
 
     pub async fn clean_account(account_id: u64) -> RustMailerResult<()> {
         FLAGS_STATE_MAP.remove(&account_id);
-        EmailEnvelopeV3::clean_account(account_id).await?;
+        FlagChangeJournal::clean_account(account_id);
+        EmailEnvelopeV7::clean_account(account_id).await?;
         MinimalEnvelope::clean_account(account_id).await?;
         AddressEntity::clean_account(account_id).await?;
         EmailThread::clean_account(account_id).await
@@ -99,7 +104,7 @@ impl EnvelopeFlagsManager {
                 FLAGS_STATE_MAP.remove(&account_id);
             }
         }
-        EmailEnvelopeV3::clean_envelopes(account_id, mailbox_id, to_delete_uid).await?;
+        EmailEnvelopeV7::clean_envelopes(account_id, mailbox_id, to_delete_uid).await?;
         MinimalEnvelope::clean_envelopes(account_id, mailbox_id, to_delete_uid).await?;
         AddressEntity::clean_envelopes(account_id, mailbox_id, to_delete_uid).await?;
         EmailThread::clean_envelopes(account_id, mailbox_id, to_delete_uid).await
@@ -110,12 +115,52 @@ impl EnvelopeFlagsManager {
         if let Some(mailbox_map) = FLAGS_STATE_MAP.get(&account_id) {
             mailbox_map.remove(&mailbox_id);
         }
-        EmailEnvelopeV3::clean_mailbox_envelopes(account_id, mailbox_id).await?;
+        EmailEnvelopeV7::clean_mailbox_envelopes(account_id, mailbox_id).await?;
         MinimalEnvelope::clean_mailbox_envelopes(account_id, mailbox_id).await?;
         AddressEntity::clean_mailbox_envelopes(account_id, mailbox_id).await?;
         EmailThread::clean_mailbox_envelopes(account_id, mailbox_id).await
     }
 
+    /// Moves all cached data of a mailbox to another mailbox id and name, e.g. after the
+    /// mailbox was renamed on the server, so its emails need not be downloaded again.
+    pub async fn move_mailbox(
+        account_id: u64,
+        from_mailbox_id: u64,
+        to_mailbox_id: u64,
+        to_mailbox_name: &str,
+    ) -> RustMailerResult<()> {
+        if let Some(mailbox_map) = FLAGS_STATE_MAP.get(&account_id) {
+            if let Some((_, uid_map)) = mailbox_map.remove(&from_mailbox_id) {
+                mailbox_map.insert(to_mailbox_id, uid_map);
+            }
+        }
+        EmailEnvelopeV7::move_mailbox_envelopes(
+            account_id,
+            from_mailbox_id,
+            to_mailbox_id,
+            to_mailbox_name.to_string(),
+        )
+        .await?;
+        // Minimal sync accounts only keep minimal envelopes.
+        MinimalEnvelope::move_mailbox_envelopes(account_id, from_mailbox_id, to_mailbox_id).await
+    }
+
+    /// Gives the cached emails of a mailbox new UIDs, keeping their local data. Cached
+    /// emails that are not remapped must be cleaned beforehand.
+    pub async fn remap_uids(
+        account_id: u64,
+        mailbox_id: u64,
+        remaps: Vec<(UID, UID, Vec<EnvelopeFlag>)>,
+    ) -> RustMailerResult<usize> {
+        if let Some(mailbox_map) = FLAGS_STATE_MAP.get(&account_id) {
+            mailbox_map.remove(&mailbox_id);
+        }
+        for (_, new_uid, flags) in &remaps {
+            Self::update_flag_change(account_id, mailbox_id, *new_uid, flags_to_hash(flags));
+        }
+        EmailEnvelopeV7::remap_uids(account_id, mailbox_id, remaps).await
+    }
+
     pub fn get_uid_map(account_id: u64, mailbox_id: u64, min_uid: UID) -> AHashMap<UID, FlagsHash> {
         let mut result = AHashMap::new();
         if let Some(mailboxes) = FLAGS_STATE_MAP.get(&account_id) {
@@ -137,11 +182,16 @@ impl EnvelopeFlagsManager {
         data: Vec<(u32, Vec<EnvelopeFlag>)>,
     ) -> RustMailerResult<()> {
         RUSTMAILER_MAIL_FLAG_CHANGE_TOTAL.inc_by(data.len() as u64);
+        RUSTMAILER_ACCOUNT_MAIL_FLAG_CHANGE_TOTAL
+            .with_label_values(&[&account.id.to_string()])
+            .inc_by(data.len() as u64);
+        let mut updates = Vec::with_capacity(data.len());
+        let mut hashes = Vec::with_capacity(data.len());
         for (uid, flags) in data {
             if !account.minimal_sync()
                 && EventHookTask::is_watching_email_flags_changed(account.id).await?
             {
-                if let Some(current) = EmailEnvelopeV3::find(account.id, mailbox_id, uid).await? {
+                if let Some(current) = EmailEnvelopeV7::find(account.id, mailbox_id, uid).await? {
                     let (added, removed) = Self::diff_envelope_flags(&current.flags, &flags);
                     EVENT_CHANNEL
                         .queue(Event::new(
@@ -171,11 +221,18 @@ impl EnvelopeFlagsManager {
             }
 
             let flags_hash = flags_to_hash(&flags);
-            if !account.minimal_sync() {
-                EmailEnvelopeV3::update_flags(account.id, mailbox_id, uid, &flags, flags_hash)
-                    .await?;
-            }
-            MinimalEnvelope::update_flags(account.id, mailbox_id, uid, flags_hash).await?;
+            hashes.push((uid, flags_hash));
+            updates.push(FlagUpdate {
+                account_id: account.id,
+                mailbox_id,
+                uid,
+                flags: (!account.minimal_sync()).then_some(flags),
+                flags_hash,
+            });
+        }
+
+        FlagWriteCoalescer::submit(updates).await?;
+        for (uid, flags_hash) in hashes {
             Self::update_flag_change(account.id, mailbox_id, uid, flags_hash);
         }
         Ok(())
@@ -191,6 +248,19 @@ impl EnvelopeFlagsManager {
         None
     }
 
+    pub fn count_uid_total() -> usize {
+        FLAGS_STATE_MAP
+            .iter()
+            .map(|account| {
+                account
+                    .value()
+                    .iter()
+                    .map(|mailbox| mailbox.value().len())
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
     pub fn count_account_uid_total(account_id: u64) -> usize {
         if let Some(mailboxes) = FLAGS_STATE_MAP.get(&account_id) {
             mailboxes.iter().map(|mailbox| mailbox.value().len()).sum()
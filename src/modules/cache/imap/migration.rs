@@ -2,34 +2,44 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use std::{collections::HashSet, sync::Arc, time::Instant};
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
 
 use itertools::Itertools;
 use native_db::*;
 use native_model::{native_model, Model};
 use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::info;
 
 use crate::{
     calculate_hash, id,
     modules::{
         cache::{
             imap::{
-                address::AddressEntity,
+                address::{AddressEntity, AddressEntityKey},
                 envelope::{EmailEnvelope, Received},
+                flags_to_hash,
                 mailbox::EnvelopeFlag,
                 manager::EnvelopeFlagsManager,
                 minimal::MinimalEnvelope,
                 thread::{EmailThread, EmailThreadKey},
             },
-            model::Envelope,
+            model::{Envelope, EnvelopeSortField},
         },
-        common::Addr,
+        common::{importance::Importance, paginated::paginate_vec, Addr},
         database::{
-            batch_delete_impl, filter_by_secondary_key_impl, manager::DB_MANAGER,
-            paginate_secondary_scan_impl, secondary_find_impl, update_impl, with_transaction,
+            batch_delete_impl, filter_by_secondary_key_impl, key::CompositeKey,
+            manager::DB_MANAGER, paginate_secondary_scan_impl, secondary_find_impl,
+            with_transaction,
         },
+        envelope::{authentication::AuthenticationResults, received::ReceivedChain},
         error::{code::ErrorCode, RustMailerResult},
         imap::section::{EmailBodyPart, ImapAttachment},
         rest::response::DataPage,
@@ -260,191 +270,972 @@ impl EmailEnvelopeV3 {
     pub fn create_envelope_id(&self) -> u64 {
         envelope_hash(self.account_id, self.mailbox_id, self.uid)
     }
+}
 
-    pub fn compute_thread_id(&self) -> u64 {
-        if self.in_reply_to.is_some() && self.references.as_ref().map_or(false, |r| !r.is_empty()) {
-            return calculate_hash!(&self.references.as_ref().unwrap()[0]);
-        }
-        if let Some(message_id) = self.message_id.as_ref() {
-            return calculate_hash!(message_id);
-        }
-        id!(128)
-    }
-
-    pub async fn find(
-        account_id: u64,
-        mailbox_id: u64,
-        uid: u32,
-    ) -> RustMailerResult<Option<EmailEnvelopeV3>> {
-        secondary_find_impl(
-            DB_MANAGER.envelope_db(),
-            EmailEnvelopeV3Key::create_envelope_id,
-            envelope_hash(account_id, mailbox_id, uid),
-        )
-        .await
-    }
-
-    pub async fn get_thread(account_id: u64, thread_id: u64) -> RustMailerResult<Vec<Envelope>> {
-        let envelopes = filter_by_secondary_key_impl::<EmailEnvelopeV3>(
-            DB_MANAGER.envelope_db(),
-            EmailEnvelopeV3Key::thread_id,
-            thread_id,
-        )
-        .await?;
-
-        let mut result = Vec::with_capacity(envelopes.len());
-        for e in envelopes {
-            if e.account_id == account_id {
-                result.push(e);
-            }
-        }
-        // Sort by internal_date in descending order
-        result.sort_by(|a, b| {
-            match (a.internal_date, b.internal_date) {
-                (Some(da), Some(db)) => db.cmp(&da),         // Descending
-                (Some(_), None) => std::cmp::Ordering::Less, // None is smaller
-                (None, Some(_)) => std::cmp::Ordering::Greater,
-                (None, None) => std::cmp::Ordering::Equal,
-            }
-        });
-
-        Ok(result.into_iter().map(Envelope::from).collect())
-    }
-
-    pub async fn get(envelope_id: u64) -> RustMailerResult<Option<EmailEnvelopeV3>> {
-        secondary_find_impl(
-            DB_MANAGER.envelope_db(),
-            EmailEnvelopeV3Key::create_envelope_id,
-            envelope_id,
-        )
-        .await
-    }
-
-    pub async fn save_envelopes(envelopes: Vec<EmailEnvelopeV3>) -> RustMailerResult<()> {
-        with_transaction(DB_MANAGER.envelope_db(), move |rw| {
-            for mut e in envelopes {
-                // --- Preprocessing ---
-                let minimal = MinimalEnvelope::from(&e);
-                EnvelopeFlagsManager::update_flag_change(
-                    e.account_id,
-                    e.mailbox_id,
-                    e.uid,
-                    e.flags_hash,
-                );
-                let address_entities = AddressEntity::extract(&e);
-                e.thread_id = e.compute_thread_id();
-
-                let thread = EmailThread::new(
-                    e.thread_id,
-                    e.create_envelope_id(),
-                    e.account_id,
-                    e.mailbox_id,
-                    e.internal_date,
-                    e.date,
-                );
-
-                // --- Store full & minimal envelope ---
-                rw.insert::<EmailEnvelopeV3>(e)
-                    .map_err(|err| raise_error!(format!("{:#?}", err), ErrorCode::InternalError))?;
-                rw.insert::<MinimalEnvelope>(minimal)
-                    .map_err(|err| raise_error!(format!("{:#?}", err), ErrorCode::InternalError))?;
-
-                // --- Thread upsert ---
-                match rw
-                    .get()
-                    .secondary::<EmailThread>(EmailThreadKey::thread_id, thread.thread_id)
-                    .map_err(|err| raise_error!(format!("{:#?}", err), ErrorCode::InternalError))?
-                {
-                    Some(current) => {
-                        // Only replace if current.internal_date is older than new internal_date
-                        if current.need_update(&thread) {
-                            rw.remove(current).map_err(|err| {
-                                raise_error!(format!("{:#?}", err), ErrorCode::InternalError)
-                            })?;
-                            rw.insert::<EmailThread>(thread).map_err(|err| {
-                                raise_error!(format!("{:#?}", err), ErrorCode::InternalError)
-                            })?;
-                        }
-                    }
-                    None => {
-                        rw.insert::<EmailThread>(thread).map_err(|err| {
-                            raise_error!(format!("{:#?}", err), ErrorCode::InternalError)
-                        })?;
-                    }
-                }
-
-                // --- Store address entities ---
-                for addr in address_entities {
-                    rw.insert::<AddressEntity>(addr).map_err(|err| {
-                        raise_error!(format!("{:#?}", err), ErrorCode::InternalError)
-                    })?;
-                }
-            }
-            Ok(())
-        })
-        .await
-    }
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 1, version = 4, from = EmailEnvelopeV3)]
+#[native_db(primary_key(pk -> String), secondary_key(create_envelope_id -> u64, unique))]
+pub struct EmailEnvelopeV4 {
+    /// The ID of the account owning the email.
+    #[secondary_key]
+    pub account_id: u64,
+    /// The unique identifier of the mailbox where the email is stored (e.g., `MailBox::id`).
+    /// Used for indexing to avoid updating indexes when mailboxes are renamed.
+    #[secondary_key]
+    pub mailbox_id: u64,
+    /// The decoded, human-readable name of the mailbox (e.g., "INBOX", "Sent").
+    pub mailbox_name: String,
+    /// The unique identifier (IMAP UID) of the email within the mailbox.
+    pub uid: u32,
+    /// The date and time the email was received by the server, as a Unix timestamp in milliseconds.
+    /// If `None`, the internal date is unavailable.
+    pub internal_date: Option<i64>,
+    /// The size of the email in bytes.
+    pub size: u32,
+    /// The flags associated with the email (e.g., `\Seen`, `\Answered`, `\Flagged`).
+    /// Represented as a list of `EnvelopeFlag` for standard or custom flags.
+    pub flags: Vec<EnvelopeFlag>,
+    /// A hash of the email's flags for efficient comparison or indexing.
+    pub flags_hash: u64,
+    /// The blind carbon copy (BCC) recipient(s) of the email, if any.
+    pub bcc: Option<Vec<Addr>>,
+    /// The carbon copy (CC) recipient(s) of the email, if any.
+    pub cc: Option<Vec<Addr>>,
+    /// The date the email was sent, as a Unix timestamp in milliseconds, if available.
+    pub date: Option<i64>,
+    /// The sender's address, including name and email, if available.
+    pub from: Option<Addr>,
+    /// The message ID of the email to which this email is a reply, if applicable.
+    pub in_reply_to: Option<String>,
+    /// The actual sender's address, if different from the `from` field.
+    pub sender: Option<Addr>,
+    /// The return address for undeliverable emails, if specified.
+    pub return_address: Option<String>,
+    /// The unique message ID of the email, typically used for threading.
+    pub message_id: Option<String>,
+    /// The subject of the email, if available.
+    pub subject: Option<String>,
+    /// The name of the thread this email belongs to, if applicable.
+    pub thread_name: Option<String>,
+    /// The identifier of the thread this email belongs to.
+    /// This is computed based on `in_reply_to` / `references` / `message_id`.
+    #[secondary_key]
+    pub thread_id: u64,
+    /// The MIME version of the email (e.g., "1.0"), if specified.
+    pub mime_version: Option<String>,
+    /// A list of message IDs referenced by this email, used for threading.
+    pub references: Option<Vec<String>>,
+    /// The address(es) to which replies should be sent, if specified.
+    pub reply_to: Option<Vec<Addr>>,
+    /// The primary recipient(s) of the email, if any.
+    pub to: Option<Vec<Addr>>,
+    /// A list of attachments included in the email, if any.
+    ///
+    /// Each `ImapAttachment` item contains metadata including the part ID and MIME type,
+    /// which indicates the exact location of the attachment in the raw message structure.
+    /// This allows the backend to directly fetch specific attachments without retrieving
+    /// the entire message content.
+    ///
+    /// This is particularly useful for accounts configured with minimal sync, where full
+    /// message bodies are not cached locally. By including this data in the API response,
+    /// the client can request to download only the required attachment via a follow-up
+    /// API call, improving both efficiency and user experience.
+    ///
+    /// Developers do not need to understand the internal IMAP part structure — this
+    /// metadata provides a clean abstraction for fetching specific attachments.
+    pub attachments: Option<Vec<ImapAttachment>>,
+    /// Metadata for the email's body parts (e.g., plain text, HTML), if available.
+    ///
+    /// Each `EmailBodyPart` contains detailed metadata (such as part ID, content type,
+    /// and charset) describing a portion of the email body. This enables precise access
+    /// to body content, such as plain text or HTML sections, without downloading the full
+    /// raw message from the server.
+    ///
+    /// This is especially helpful for lightweight clients or minimized-sync accounts that
+    /// do not cache full email content. The frontend can pass this metadata back to the
+    /// server to retrieve only the desired portion of the message (e.g., the HTML body),
+    /// which significantly reduces bandwidth and latency.
+    ///
+    /// By abstracting the complexity of MIME part navigation, developers can efficiently
+    /// retrieve specific parts of an email without handling the low-level IMAP structure.
+    pub body_meta: Option<Vec<EmailBodyPart>>,
+    /// Details about how the email was received, if available.
+    pub received: Option<Received>,
+    /// The `mid` field is reserved for potential integration with other backend models.
+    /// For instance, it can be used to store the email index or ID from external services like the Gmail API.
+    /// This ID could be used for reference or identification purposes in scenarios where an external service
+    /// provides an identifier for the email in question.
+    ///
+    /// This field is optional, meaning that it may be `None` if no external service identifier is available.
+    pub mid: Option<String>,
+    /// A list of labels applied to the message.
+    ///
+    /// Each element is a string representing a Gmail label name (e.g., "INBOX", "UNREAD").
+    /// This field reflects the current labels associated with the email.
+    ///
+    /// Note: This field is populated only for Gmail API accounts. For other account types, it will be empty.
+    pub labels: Vec<String>,
+    /// The importance of the email, read from its `Importance` or `X-Priority` header.
+    ///
+    /// `None` if the email carries no priority header.
+    pub importance: Option<Importance>,
+}
 
-    pub async fn list_messages_in_mailbox(
-        mailbox_id: u64,
-        page: u64,
-        page_size: u64,
-        desc: bool,
-    ) -> RustMailerResult<DataPage<EmailEnvelopeV3>> {
-        paginate_secondary_scan_impl(
-            DB_MANAGER.envelope_db(),
-            Some(page),
-            Some(page_size),
-            Some(desc),
-            EmailEnvelopeV3Key::mailbox_id,
-            mailbox_id,
+impl EmailEnvelopeV4 {
+    pub fn pk(&self) -> String {
+        format!(
+            "{}_{}",
+            self.internal_date.unwrap_or(utc_now!()),
+            envelope_hash(self.account_id, self.mailbox_id, self.uid)
         )
-        .await
-        .map(DataPage::from)
     }
 
-    pub async fn update_flags(
-        account_id: u64,
-        mailbox_id: u64,
-        uid: u32,
-        flags: &[EnvelopeFlag],
-        flags_hash: u64,
-    ) -> RustMailerResult<()> {
-        let flags = flags.to_vec();
-
-        update_impl(
-            DB_MANAGER.envelope_db(),
-            move |rw| {
-                rw.get()
-                    .secondary::<EmailEnvelopeV3>(
-                        EmailEnvelopeV3Key::create_envelope_id,
-                        envelope_hash(account_id, mailbox_id, uid),
-                    )
-                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-                    .ok_or_else(|| {
-                        raise_error!(
-                            "The EmailEnvelope that you want to modify was not found.".to_string(),
-                            ErrorCode::ResourceNotFound
-                        )
-                    })
-            },
-            move |current| {
-                let mut updated = current.clone();
-                updated.flags = flags;
-                updated.flags_hash = flags_hash;
-                Ok(updated)
-            },
-        )
-        .await
-        .map_err(|e| {
-            error!(
-                "Failed to update flags: account_id={}, mailbox_id={}, uid={}, error={:?}",
-                account_id, mailbox_id, uid, e
-            );
-            e
-        })?;
-
-        Ok(())
+    pub fn create_envelope_id(&self) -> u64 {
+        envelope_hash(self.account_id, self.mailbox_id, self.uid)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 1, version = 5, from = EmailEnvelopeV4)]
+#[native_db(primary_key(pk -> String), secondary_key(create_envelope_id -> u64, unique))]
+pub struct EmailEnvelopeV5 {
+    /// The ID of the account owning the email.
+    #[secondary_key]
+    pub account_id: u64,
+    /// The unique identifier of the mailbox where the email is stored (e.g., `MailBox::id`).
+    /// Used for indexing to avoid updating indexes when mailboxes are renamed.
+    #[secondary_key]
+    pub mailbox_id: u64,
+    /// The decoded, human-readable name of the mailbox (e.g., "INBOX", "Sent").
+    pub mailbox_name: String,
+    /// The unique identifier (IMAP UID) of the email within the mailbox.
+    pub uid: u32,
+    /// The date and time the email was received by the server, as a Unix timestamp in milliseconds.
+    /// If `None`, the internal date is unavailable.
+    pub internal_date: Option<i64>,
+    /// The size of the email in bytes.
+    pub size: u32,
+    /// The flags associated with the email (e.g., `\Seen`, `\Answered`, `\Flagged`).
+    /// Represented as a list of `EnvelopeFlag` for standard or custom flags.
+    pub flags: Vec<EnvelopeFlag>,
+    /// A hash of the email's flags for efficient comparison or indexing.
+    pub flags_hash: u64,
+    /// The blind carbon copy (BCC) recipient(s) of the email, if any.
+    pub bcc: Option<Vec<Addr>>,
+    /// The carbon copy (CC) recipient(s) of the email, if any.
+    pub cc: Option<Vec<Addr>>,
+    /// The date the email was sent, as a Unix timestamp in milliseconds, if available.
+    pub date: Option<i64>,
+    /// The sender's address, including name and email, if available.
+    pub from: Option<Addr>,
+    /// The message ID of the email to which this email is a reply, if applicable.
+    pub in_reply_to: Option<String>,
+    /// The actual sender's address, if different from the `from` field.
+    pub sender: Option<Addr>,
+    /// The return address for undeliverable emails, if specified.
+    pub return_address: Option<String>,
+    /// The unique message ID of the email, typically used for threading.
+    pub message_id: Option<String>,
+    /// The subject of the email, if available.
+    pub subject: Option<String>,
+    /// The name of the thread this email belongs to, if applicable.
+    pub thread_name: Option<String>,
+    /// The identifier of the thread this email belongs to.
+    /// This is computed based on `in_reply_to` / `references` / `message_id`.
+    #[secondary_key]
+    pub thread_id: u64,
+    /// The MIME version of the email (e.g., "1.0"), if specified.
+    pub mime_version: Option<String>,
+    /// A list of message IDs referenced by this email, used for threading.
+    pub references: Option<Vec<String>>,
+    /// The address(es) to which replies should be sent, if specified.
+    pub reply_to: Option<Vec<Addr>>,
+    /// The primary recipient(s) of the email, if any.
+    pub to: Option<Vec<Addr>>,
+    /// A list of attachments included in the email, if any.
+    ///
+    /// Each `ImapAttachment` item contains metadata including the part ID and MIME type,
+    /// which indicates the exact location of the attachment in the raw message structure.
+    /// This allows the backend to directly fetch specific attachments without retrieving
+    /// the entire message content.
+    ///
+    /// This is particularly useful for accounts configured with minimal sync, where full
+    /// message bodies are not cached locally. By including this data in the API response,
+    /// the client can request to download only the required attachment via a follow-up
+    /// API call, improving both efficiency and user experience.
+    ///
+    /// Developers do not need to understand the internal IMAP part structure — this
+    /// metadata provides a clean abstraction for fetching specific attachments.
+    pub attachments: Option<Vec<ImapAttachment>>,
+    /// Metadata for the email's body parts (e.g., plain text, HTML), if available.
+    ///
+    /// Each `EmailBodyPart` contains detailed metadata (such as part ID, content type,
+    /// and charset) describing a portion of the email body. This enables precise access
+    /// to body content, such as plain text or HTML sections, without downloading the full
+    /// raw message from the server.
+    ///
+    /// This is especially helpful for lightweight clients or minimized-sync accounts that
+    /// do not cache full email content. The frontend can pass this metadata back to the
+    /// server to retrieve only the desired portion of the message (e.g., the HTML body),
+    /// which significantly reduces bandwidth and latency.
+    ///
+    /// By abstracting the complexity of MIME part navigation, developers can efficiently
+    /// retrieve specific parts of an email without handling the low-level IMAP structure.
+    pub body_meta: Option<Vec<EmailBodyPart>>,
+    /// Details about how the email was received, if available.
+    pub received: Option<Received>,
+    /// The `mid` field is reserved for potential integration with other backend models.
+    /// For instance, it can be used to store the email index or ID from external services like the Gmail API.
+    /// This ID could be used for reference or identification purposes in scenarios where an external service
+    /// provides an identifier for the email in question.
+    ///
+    /// This field is optional, meaning that it may be `None` if no external service identifier is available.
+    pub mid: Option<String>,
+    /// A list of labels applied to the message.
+    ///
+    /// Each element is a string representing a Gmail label name (e.g., "INBOX", "UNREAD").
+    /// This field reflects the current labels associated with the email.
+    ///
+    /// Note: This field is populated only for Gmail API accounts. For other account types, it will be empty.
+    pub labels: Vec<String>,
+    /// The importance of the email, read from its `Importance` or `X-Priority` header.
+    ///
+    /// `None` if the email carries no priority header.
+    pub importance: Option<Importance>,
+    /// The hops of the email's `Received` headers, with the originating client IP and the
+    /// total transit time, if the email has any `Received` headers.
+    pub received_chain: Option<ReceivedChain>,
+}
+
+impl EmailEnvelopeV5 {
+    pub fn pk(&self) -> String {
+        format!(
+            "{}_{}",
+            self.internal_date.unwrap_or(utc_now!()),
+            envelope_hash(self.account_id, self.mailbox_id, self.uid)
+        )
+    }
+
+    pub fn create_envelope_id(&self) -> u64 {
+        envelope_hash(self.account_id, self.mailbox_id, self.uid)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 1, version = 6, from = EmailEnvelopeV5)]
+#[native_db(
+    primary_key(pk -> String),
+    secondary_key(create_envelope_id -> u64, unique),
+    secondary_key(mailbox_date_key -> String),
+    secondary_key(mailbox_size_key -> String)
+)]
+pub struct EmailEnvelopeV6 {
+    /// The ID of the account owning the email.
+    #[secondary_key]
+    pub account_id: u64,
+    /// The unique identifier of the mailbox where the email is stored (e.g., `MailBox::id`).
+    /// Used for indexing to avoid updating indexes when mailboxes are renamed.
+    #[secondary_key]
+    pub mailbox_id: u64,
+    /// The decoded, human-readable name of the mailbox (e.g., "INBOX", "Sent").
+    pub mailbox_name: String,
+    /// The unique identifier (IMAP UID) of the email within the mailbox.
+    pub uid: u32,
+    /// The date and time the email was received by the server, as a Unix timestamp in milliseconds.
+    /// If `None`, the internal date is unavailable.
+    pub internal_date: Option<i64>,
+    /// The size of the email in bytes.
+    pub size: u32,
+    /// The flags associated with the email (e.g., `\Seen`, `\Answered`, `\Flagged`).
+    /// Represented as a list of `EnvelopeFlag` for standard or custom flags.
+    pub flags: Vec<EnvelopeFlag>,
+    /// A hash of the email's flags for efficient comparison or indexing.
+    pub flags_hash: u64,
+    /// The blind carbon copy (BCC) recipient(s) of the email, if any.
+    pub bcc: Option<Vec<Addr>>,
+    /// The carbon copy (CC) recipient(s) of the email, if any.
+    pub cc: Option<Vec<Addr>>,
+    /// The date the email was sent, as a Unix timestamp in milliseconds, if available.
+    pub date: Option<i64>,
+    /// The sender's address, including name and email, if available.
+    pub from: Option<Addr>,
+    /// The message ID of the email to which this email is a reply, if applicable.
+    pub in_reply_to: Option<String>,
+    /// The actual sender's address, if different from the `from` field.
+    pub sender: Option<Addr>,
+    /// The return address for undeliverable emails, if specified.
+    pub return_address: Option<String>,
+    /// The unique message ID of the email, typically used for threading.
+    pub message_id: Option<String>,
+    /// The subject of the email, if available.
+    pub subject: Option<String>,
+    /// The name of the thread this email belongs to, if applicable.
+    pub thread_name: Option<String>,
+    /// The identifier of the thread this email belongs to.
+    /// This is computed based on `in_reply_to` / `references` / `message_id`.
+    #[secondary_key]
+    pub thread_id: u64,
+    /// The MIME version of the email (e.g., "1.0"), if specified.
+    pub mime_version: Option<String>,
+    /// A list of message IDs referenced by this email, used for threading.
+    pub references: Option<Vec<String>>,
+    /// The address(es) to which replies should be sent, if specified.
+    pub reply_to: Option<Vec<Addr>>,
+    /// The primary recipient(s) of the email, if any.
+    pub to: Option<Vec<Addr>>,
+    /// A list of attachments included in the email, if any.
+    ///
+    /// Each `ImapAttachment` item contains metadata including the part ID and MIME type,
+    /// which indicates the exact location of the attachment in the raw message structure.
+    /// This allows the backend to directly fetch specific attachments without retrieving
+    /// the entire message content.
+    ///
+    /// This is particularly useful for accounts configured with minimal sync, where full
+    /// message bodies are not cached locally. By including this data in the API response,
+    /// the client can request to download only the required attachment via a follow-up
+    /// API call, improving both efficiency and user experience.
+    ///
+    /// Developers do not need to understand the internal IMAP part structure — this
+    /// metadata provides a clean abstraction for fetching specific attachments.
+    pub attachments: Option<Vec<ImapAttachment>>,
+    /// Metadata for the email's body parts (e.g., plain text, HTML), if available.
+    ///
+    /// Each `EmailBodyPart` contains detailed metadata (such as part ID, content type,
+    /// and charset) describing a portion of the email body. This enables precise access
+    /// to body content, such as plain text or HTML sections, without downloading the full
+    /// raw message from the server.
+    ///
+    /// This is especially helpful for lightweight clients or minimized-sync accounts that
+    /// do not cache full email content. The frontend can pass this metadata back to the
+    /// server to retrieve only the desired portion of the message (e.g., the HTML body),
+    /// which significantly reduces bandwidth and latency.
+    ///
+    /// By abstracting the complexity of MIME part navigation, developers can efficiently
+    /// retrieve specific parts of an email without handling the low-level IMAP structure.
+    pub body_meta: Option<Vec<EmailBodyPart>>,
+    /// Details about how the email was received, if available.
+    pub received: Option<Received>,
+    /// The `mid` field is reserved for potential integration with other backend models.
+    /// For instance, it can be used to store the email index or ID from external services like the Gmail API.
+    /// This ID could be used for reference or identification purposes in scenarios where an external service
+    /// provides an identifier for the email in question.
+    ///
+    /// This field is optional, meaning that it may be `None` if no external service identifier is available.
+    pub mid: Option<String>,
+    /// A list of labels applied to the message.
+    ///
+    /// Each element is a string representing a Gmail label name (e.g., "INBOX", "UNREAD").
+    /// This field reflects the current labels associated with the email.
+    ///
+    /// Note: This field is populated only for Gmail API accounts. For other account types, it will be empty.
+    pub labels: Vec<String>,
+    /// The importance of the email, read from its `Importance` or `X-Priority` header.
+    ///
+    /// `None` if the email carries no priority header.
+    pub importance: Option<Importance>,
+    /// The hops of the email's `Received` headers, with the originating client IP and the
+    /// total transit time, if the email has any `Received` headers.
+    pub received_chain: Option<ReceivedChain>,
+    /// The SPF, DKIM and DMARC verdicts of the receiving server, read from the email's
+    /// `Authentication-Results` headers, if any.
+    pub authentication_results: Option<AuthenticationResults>,
+}
+
+impl EmailEnvelopeV6 {
+    pub fn pk(&self) -> String {
+        format!(
+            "{}_{}",
+            self.internal_date.unwrap_or(utc_now!()),
+            envelope_hash(self.account_id, self.mailbox_id, self.uid)
+        )
+    }
+
+    pub fn create_envelope_id(&self) -> u64 {
+        envelope_hash(self.account_id, self.mailbox_id, self.uid)
+    }
+
+    pub fn mailbox_date_key(&self) -> String {
+        mailbox_sort_key(self.mailbox_id, self.date.unwrap_or_default().max(0) as u64)
+    }
+
+    pub fn mailbox_size_key(&self) -> String {
+        mailbox_sort_key(self.mailbox_id, self.size as u64)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 1, version = 7, from = EmailEnvelopeV6)]
+#[native_db(
+    primary_key(pk -> String),
+    secondary_key(create_envelope_id -> u64, unique),
+    secondary_key(mailbox_date_key -> String),
+    secondary_key(mailbox_size_key -> String)
+)]
+pub struct EmailEnvelopeV7 {
+    /// The ID of the account owning the email.
+    #[secondary_key]
+    pub account_id: u64,
+    /// The unique identifier of the mailbox where the email is stored (e.g., `MailBox::id`).
+    /// Used for indexing to avoid updating indexes when mailboxes are renamed.
+    #[secondary_key]
+    pub mailbox_id: u64,
+    /// The decoded, human-readable name of the mailbox (e.g., "INBOX", "Sent").
+    pub mailbox_name: String,
+    /// The unique identifier (IMAP UID) of the email within the mailbox.
+    pub uid: u32,
+    /// The date and time the email was received by the server, as a Unix timestamp in milliseconds.
+    /// If `None`, the internal date is unavailable.
+    pub internal_date: Option<i64>,
+    /// The size of the email in bytes.
+    pub size: u32,
+    /// The flags associated with the email (e.g., `\Seen`, `\Answered`, `\Flagged`).
+    /// Represented as a list of `EnvelopeFlag` for standard or custom flags.
+    pub flags: Vec<EnvelopeFlag>,
+    /// A hash of the email's flags for efficient comparison or indexing.
+    pub flags_hash: u64,
+    /// The blind carbon copy (BCC) recipient(s) of the email, if any.
+    pub bcc: Option<Vec<Addr>>,
+    /// The carbon copy (CC) recipient(s) of the email, if any.
+    pub cc: Option<Vec<Addr>>,
+    /// The date the email was sent, as a Unix timestamp in milliseconds, if available.
+    pub date: Option<i64>,
+    /// The sender's address, including name and email, if available.
+    pub from: Option<Addr>,
+    /// The message ID of the email to which this email is a reply, if applicable.
+    pub in_reply_to: Option<String>,
+    /// The actual sender's address, if different from the `from` field.
+    pub sender: Option<Addr>,
+    /// The return address for undeliverable emails, if specified.
+    pub return_address: Option<String>,
+    /// The unique message ID of the email, typically used for threading.
+    pub message_id: Option<String>,
+    /// The subject of the email, if available.
+    pub subject: Option<String>,
+    /// The name of the thread this email belongs to, if applicable.
+    pub thread_name: Option<String>,
+    /// The identifier of the thread this email belongs to.
+    /// This is computed based on `in_reply_to` / `references` / `message_id`.
+    #[secondary_key]
+    pub thread_id: u64,
+    /// The MIME version of the email (e.g., "1.0"), if specified.
+    pub mime_version: Option<String>,
+    /// A list of message IDs referenced by this email, used for threading.
+    pub references: Option<Vec<String>>,
+    /// The address(es) to which replies should be sent, if specified.
+    pub reply_to: Option<Vec<Addr>>,
+    /// The primary recipient(s) of the email, if any.
+    pub to: Option<Vec<Addr>>,
+    /// A list of attachments included in the email, if any.
+    ///
+    /// Each `ImapAttachment` item contains metadata including the part ID and MIME type,
+    /// which indicates the exact location of the attachment in the raw message structure.
+    /// This allows the backend to directly fetch specific attachments without retrieving
+    /// the entire message content.
+    ///
+    /// This is particularly useful for accounts configured with minimal sync, where full
+    /// message bodies are not cached locally. By including this data in the API response,
+    /// the client can request to download only the required attachment via a follow-up
+    /// API call, improving both efficiency and user experience.
+    ///
+    /// Developers do not need to understand the internal IMAP part structure — this
+    /// metadata provides a clean abstraction for fetching specific attachments.
+    pub attachments: Option<Vec<ImapAttachment>>,
+    /// Metadata for the email's body parts (e.g., plain text, HTML), if available.
+    ///
+    /// Each `EmailBodyPart` contains detailed metadata (such as part ID, content type,
+    /// and charset) describing a portion of the email body. This enables precise access
+    /// to body content, such as plain text or HTML sections, without downloading the full
+    /// raw message from the server.
+    ///
+    /// This is especially helpful for lightweight clients or minimized-sync accounts that
+    /// do not cache full email content. The frontend can pass this metadata back to the
+    /// server to retrieve only the desired portion of the message (e.g., the HTML body),
+    /// which significantly reduces bandwidth and latency.
+    ///
+    /// By abstracting the complexity of MIME part navigation, developers can efficiently
+    /// retrieve specific parts of an email without handling the low-level IMAP structure.
+    pub body_meta: Option<Vec<EmailBodyPart>>,
+    /// Details about how the email was received, if available.
+    pub received: Option<Received>,
+    /// The `mid` field is reserved for potential integration with other backend models.
+    /// For instance, it can be used to store the email index or ID from external services like the Gmail API.
+    /// This ID could be used for reference or identification purposes in scenarios where an external service
+    /// provides an identifier for the email in question.
+    ///
+    /// This field is optional, meaning that it may be `None` if no external service identifier is available.
+    pub mid: Option<String>,
+    /// A list of labels applied to the message.
+    ///
+    /// Each element is a string representing a Gmail label name (e.g., "INBOX", "UNREAD").
+    /// This field reflects the current labels associated with the email.
+    ///
+    /// Note: This field is populated only for Gmail API accounts. For other account types, it will be empty.
+    pub labels: Vec<String>,
+    /// The importance of the email, read from its `Importance` or `X-Priority` header.
+    ///
+    /// `None` if the email carries no priority header.
+    pub importance: Option<Importance>,
+    /// The hops of the email's `Received` headers, with the originating client IP and the
+    /// total transit time, if the email has any `Received` headers.
+    pub received_chain: Option<ReceivedChain>,
+    /// The SPF, DKIM and DMARC verdicts of the receiving server, read from the email's
+    /// `Authentication-Results` headers, if any.
+    pub authentication_results: Option<AuthenticationResults>,
+    /// Why the headers of the email could not be fully parsed, if they could not.
+    ///
+    /// A partially parsed envelope holds the fields that could be recovered on a best-effort
+    /// basis, and keeps the raw header bytes in `raw_headers`, so the email stays visible.
+    pub parse_error: Option<String>,
+    /// The raw header bytes fetched from the server, kept only for partially parsed envelopes.
+    pub raw_headers: Option<Vec<u8>>,
+}
+
+impl EmailEnvelopeV7 {
+    pub fn pk(&self) -> String {
+        format!(
+            "{}_{}",
+            self.internal_date.unwrap_or(utc_now!()),
+            envelope_hash(self.account_id, self.mailbox_id, self.uid)
+        )
+    }
+
+    pub fn create_envelope_id(&self) -> u64 {
+        envelope_hash(self.account_id, self.mailbox_id, self.uid)
+    }
+
+    /// Orders the envelopes of a mailbox by their `Date` header. Envelopes without a date
+    /// (or dated before 1970) share the lowest key.
+    pub fn mailbox_date_key(&self) -> String {
+        mailbox_sort_key(self.mailbox_id, self.date.unwrap_or_default().max(0) as u64)
+    }
+
+    /// Orders the envelopes of a mailbox by size.
+    pub fn mailbox_size_key(&self) -> String {
+        mailbox_sort_key(self.mailbox_id, self.size as u64)
+    }
+
+    pub fn compute_thread_id(&self) -> u64 {
+        if self.in_reply_to.is_some() && self.references.as_ref().map_or(false, |r| !r.is_empty()) {
+            return calculate_hash!(&self.references.as_ref().unwrap()[0]);
+        }
+        if let Some(message_id) = self.message_id.as_ref() {
+            return calculate_hash!(message_id);
+        }
+        id!(128)
+    }
+
+    pub async fn find(
+        account_id: u64,
+        mailbox_id: u64,
+        uid: u32,
+    ) -> RustMailerResult<Option<EmailEnvelopeV7>> {
+        secondary_find_impl(
+            DB_MANAGER.envelope_db(),
+            EmailEnvelopeV7Key::create_envelope_id,
+            envelope_hash(account_id, mailbox_id, uid),
+        )
+        .await
+    }
+
+    pub async fn get_thread(account_id: u64, thread_id: u64) -> RustMailerResult<Vec<Envelope>> {
+        let envelopes = filter_by_secondary_key_impl::<EmailEnvelopeV7>(
+            DB_MANAGER.envelope_db(),
+            EmailEnvelopeV7Key::thread_id,
+            thread_id,
+        )
+        .await?;
+
+        let mut result = Vec::with_capacity(envelopes.len());
+        for e in envelopes {
+            if e.account_id == account_id {
+                result.push(e);
+            }
+        }
+        // Sort by internal_date in descending order
+        result.sort_by(|a, b| {
+            match (a.internal_date, b.internal_date) {
+                (Some(da), Some(db)) => db.cmp(&da),         // Descending
+                (Some(_), None) => std::cmp::Ordering::Less, // None is smaller
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            }
+        });
+
+        Ok(result.into_iter().map(Envelope::from).collect())
+    }
+
+    pub async fn get(envelope_id: u64) -> RustMailerResult<Option<EmailEnvelopeV7>> {
+        secondary_find_impl(
+            DB_MANAGER.envelope_db(),
+            EmailEnvelopeV7Key::create_envelope_id,
+            envelope_id,
+        )
+        .await
+    }
+
+    pub async fn save_envelopes(envelopes: Vec<EmailEnvelopeV7>) -> RustMailerResult<()> {
+        with_transaction(DB_MANAGER.envelope_db(), move |rw| {
+            for mut e in envelopes {
+                // --- Preprocessing ---
+                let minimal = MinimalEnvelope::from(&e);
+                EnvelopeFlagsManager::update_flag_change(
+                    e.account_id,
+                    e.mailbox_id,
+                    e.uid,
+                    e.flags_hash,
+                );
+                let address_entities = AddressEntity::extract(&e);
+                e.thread_id = e.compute_thread_id();
+
+                let thread = EmailThread::new(
+                    e.thread_id,
+                    e.create_envelope_id(),
+                    e.account_id,
+                    e.mailbox_id,
+                    e.internal_date,
+                    e.date,
+                );
+
+                // --- Store full & minimal envelope ---
+                rw.insert::<EmailEnvelopeV7>(e)
+                    .map_err(|err| raise_error!(format!("{:#?}", err), ErrorCode::InternalError))?;
+                rw.insert::<MinimalEnvelope>(minimal)
+                    .map_err(|err| raise_error!(format!("{:#?}", err), ErrorCode::InternalError))?;
+
+                // --- Thread upsert ---
+                match rw
+                    .get()
+                    .secondary::<EmailThread>(EmailThreadKey::thread_id, thread.thread_id)
+                    .map_err(|err| raise_error!(format!("{:#?}", err), ErrorCode::InternalError))?
+                {
+                    Some(current) => {
+                        // Only replace if current.internal_date is older than new internal_date
+                        if current.need_update(&thread) {
+                            rw.remove(current).map_err(|err| {
+                                raise_error!(format!("{:#?}", err), ErrorCode::InternalError)
+                            })?;
+                            rw.insert::<EmailThread>(thread).map_err(|err| {
+                                raise_error!(format!("{:#?}", err), ErrorCode::InternalError)
+                            })?;
+                        }
+                    }
+                    None => {
+                        rw.insert::<EmailThread>(thread).map_err(|err| {
+                            raise_error!(format!("{:#?}", err), ErrorCode::InternalError)
+                        })?;
+                    }
+                }
+
+                // --- Store address entities ---
+                for addr in address_entities {
+                    rw.insert::<AddressEntity>(addr).map_err(|err| {
+                        raise_error!(format!("{:#?}", err), ErrorCode::InternalError)
+                    })?;
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Moves the cached envelopes of a mailbox to another mailbox id and name, together
+    /// with their minimal envelopes, threads and address entities, keeping their UIDs.
+    ///
+    /// Returns the number of envelopes moved.
+    pub async fn move_mailbox_envelopes(
+        account_id: u64,
+        from_mailbox_id: u64,
+        to_mailbox_id: u64,
+        to_mailbox_name: String,
+    ) -> RustMailerResult<usize> {
+        const BATCH_SIZE: usize = 200;
+        let mut total_moved = 0usize;
+        let start_time = Instant::now();
+        loop {
+            let moved = Arc::new(AtomicUsize::new(0));
+            let batch_moved = moved.clone();
+            let to_mailbox_name = to_mailbox_name.clone();
+            with_transaction(DB_MANAGER.envelope_db(), move |rw| {
+                let to_move: Vec<EmailEnvelopeV7> = rw
+                    .scan()
+                    .secondary(EmailEnvelopeV7Key::mailbox_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .start_with(from_mailbox_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .filter_map(Result::ok)
+                    .filter(|e: &EmailEnvelopeV7| e.account_id == account_id)
+                    .take(BATCH_SIZE)
+                    .collect();
+                for envelope in to_move {
+                    let old_id = envelope.create_envelope_id();
+                    let mut moved_envelope = envelope.clone();
+                    moved_envelope.mailbox_id = to_mailbox_id;
+                    moved_envelope.mailbox_name = to_mailbox_name.clone();
+                    let new_id = moved_envelope.create_envelope_id();
+                    rw.remove(envelope)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                    rw.insert(moved_envelope)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+
+                    if let Some(minimal) = rw
+                        .get()
+                        .primary::<MinimalEnvelope>(old_id)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    {
+                        let mut moved_minimal = minimal.clone();
+                        moved_minimal.mailbox_id = to_mailbox_id;
+                        rw.remove(minimal).map_err(|e| {
+                            raise_error!(format!("{:#?}", e), ErrorCode::InternalError)
+                        })?;
+                        rw.insert(moved_minimal).map_err(|e| {
+                            raise_error!(format!("{:#?}", e), ErrorCode::InternalError)
+                        })?;
+                    }
+                    if let Some(thread) = rw
+                        .get()
+                        .secondary::<EmailThread>(EmailThreadKey::envelope_id, old_id)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    {
+                        let mut moved_thread = thread.clone();
+                        moved_thread.mailbox_id = to_mailbox_id;
+                        moved_thread.envelope_id = new_id;
+                        rw.remove(thread).map_err(|e| {
+                            raise_error!(format!("{:#?}", e), ErrorCode::InternalError)
+                        })?;
+                        rw.insert(moved_thread).map_err(|e| {
+                            raise_error!(format!("{:#?}", e), ErrorCode::InternalError)
+                        })?;
+                    }
+                    let addresses: Vec<AddressEntity> = rw
+                        .scan()
+                        .secondary(AddressEntityKey::envelope_hash)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                        .start_with(old_id)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                        .try_collect()
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                    for address in addresses {
+                        let mut moved_address = address.clone();
+                        moved_address.mailbox_id = to_mailbox_id;
+                        moved_address.envelope_hash = new_id;
+                        rw.remove(address).map_err(|e| {
+                            raise_error!(format!("{:#?}", e), ErrorCode::InternalError)
+                        })?;
+                        rw.insert(moved_address).map_err(|e| {
+                            raise_error!(format!("{:#?}", e), ErrorCode::InternalError)
+                        })?;
+                    }
+                    batch_moved.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(())
+            })
+            .await?;
+            let moved = moved.load(Ordering::Relaxed);
+            total_moved += moved;
+            if moved == 0 {
+                break;
+            }
+        }
+
+        info!(
+            "Finished moving envelopes from mailbox_id={} to mailbox_id={} account_id={} total_moved={} in {:?}",
+            from_mailbox_id,
+            to_mailbox_id,
+            account_id,
+            total_moved,
+            start_time.elapsed()
+        );
+        Ok(total_moved)
+    }
+
+    /// Gives cached envelopes of a mailbox new UIDs, e.g. after its UIDVALIDITY changed,
+    /// keeping their thread, address index and other local data. Each remap is
+    /// `(old_uid, new_uid, flags)`, replacing the flags with the ones on the server.
+    ///
+    /// Old and new UIDs of different emails may overlap, so all remaps are applied in a
+    /// single transaction: every envelope is removed before any is inserted again. Cached
+    /// envelopes that are not remapped must be removed beforehand.
+    pub async fn remap_uids(
+        account_id: u64,
+        mailbox_id: u64,
+        remaps: Vec<(u32, u32, Vec<EnvelopeFlag>)>,
+    ) -> RustMailerResult<usize> {
+        let remapped = Arc::new(AtomicUsize::new(0));
+        let total = remapped.clone();
+        with_transaction(DB_MANAGER.envelope_db(), move |rw| {
+            let mut staged = Vec::with_capacity(remaps.len());
+            for (old_uid, new_uid, flags) in remaps {
+                let old_id = envelope_hash(account_id, mailbox_id, old_uid);
+                let Some(envelope) = rw
+                    .get()
+                    .secondary::<EmailEnvelopeV7>(EmailEnvelopeV7Key::create_envelope_id, old_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                else {
+                    continue;
+                };
+                rw.remove(envelope.clone())
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                if let Some(minimal) = rw
+                    .get()
+                    .primary::<MinimalEnvelope>(old_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                {
+                    rw.remove(minimal)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                }
+                let thread = rw
+                    .get()
+                    .secondary::<EmailThread>(EmailThreadKey::envelope_id, old_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                if let Some(thread) = &thread {
+                    rw.remove(thread.clone())
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                }
+                let addresses: Vec<AddressEntity> = rw
+                    .scan()
+                    .secondary(AddressEntityKey::envelope_hash)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .start_with(old_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .try_collect()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                for address in &addresses {
+                    rw.remove(address.clone())
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                }
+
+                let mut envelope = envelope;
+                envelope.uid = new_uid;
+                envelope.flags_hash = flags_to_hash(&flags);
+                envelope.flags = flags;
+                staged.push((envelope, thread, addresses));
+            }
+
+            for (envelope, thread, addresses) in staged {
+                let new_id = envelope.create_envelope_id();
+                rw.insert(MinimalEnvelope::from(&envelope))
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                rw.insert(envelope)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                if let Some(mut thread) = thread {
+                    thread.envelope_id = new_id;
+                    rw.insert(thread)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                }
+                for mut address in addresses {
+                    address.envelope_hash = new_id;
+                    rw.insert(address)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                }
+                total.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(remapped.load(Ordering::Relaxed))
+    }
+
+    pub async fn list_messages_in_mailbox(
+        mailbox_id: u64,
+        page: u64,
+        page_size: u64,
+        desc: bool,
+    ) -> RustMailerResult<DataPage<EmailEnvelopeV7>> {
+        paginate_secondary_scan_impl(
+            DB_MANAGER.envelope_db(),
+            Some(page),
+            Some(page_size),
+            Some(desc),
+            EmailEnvelopeV7Key::mailbox_id,
+            mailbox_id,
+        )
+        .await
+        .map(DataPage::from)
+    }
+
+    /// Lists the cached messages of a mailbox ordered by `sort_by`, then by internal date.
+    ///
+    /// Internal date, date and size orders are read from indexes; sender and subject
+    /// orders load the whole mailbox and sort it in memory.
+    pub async fn list_messages_sorted(
+        mailbox_id: u64,
+        sort_by: EnvelopeSortField,
+        page: u64,
+        page_size: u64,
+        desc: bool,
+    ) -> RustMailerResult<DataPage<EmailEnvelopeV7>> {
+        let key = match sort_by {
+            EnvelopeSortField::InternalDate => {
+                return Self::list_messages_in_mailbox(mailbox_id, page, page_size, desc).await
+            }
+            EnvelopeSortField::Date => EmailEnvelopeV7Key::mailbox_date_key,
+            EnvelopeSortField::Size => EmailEnvelopeV7Key::mailbox_size_key,
+            EnvelopeSortField::From | EnvelopeSortField::Subject => {
+                let mut envelopes = filter_by_secondary_key_impl::<EmailEnvelopeV7>(
+                    DB_MANAGER.envelope_db(),
+                    EmailEnvelopeV7Key::mailbox_id,
+                    mailbox_id,
+                )
+                .await?;
+                envelopes.sort_by(|a, b| {
+                    let ordering = sort_by.compare(a, b);
+                    if desc {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                });
+                return paginate_vec(&envelopes, Some(page), Some(page_size)).map(DataPage::from);
+            }
+        };
+        // Keys are `"{mailbox_id}_{value}"`; the separator keeps mailbox 1 from matching 12.
+        let prefix = CompositeKey::new().segment(mailbox_id).segment("").build();
+        paginate_secondary_scan_impl(
+            DB_MANAGER.envelope_db(),
+            Some(page),
+            Some(page_size),
+            Some(desc),
+            key,
+            prefix,
+        )
+        .await
+        .map(DataPage::from)
+    }
+
+    /// Lists the cached messages of a mailbox, optionally keeping only those of the given
+    /// importance and ordering the most important first. Messages without a priority
+    /// header count as `Normal`.
+    pub async fn list_messages_by_importance(
+        mailbox_id: u64,
+        importance: Option<Importance>,
+        sort_by_importance: bool,
+        page: u64,
+        page_size: u64,
+        desc: bool,
+    ) -> RustMailerResult<DataPage<EmailEnvelopeV7>> {
+        let mut envelopes = filter_by_secondary_key_impl::<EmailEnvelopeV7>(
+            DB_MANAGER.envelope_db(),
+            EmailEnvelopeV7Key::mailbox_id,
+            mailbox_id,
+        )
+        .await?;
+        if let Some(importance) = importance {
+            envelopes.retain(|e| e.importance.unwrap_or_default() == importance);
+        }
+        envelopes.sort_by(|a, b| {
+            let by_date = if desc {
+                b.internal_date.cmp(&a.internal_date)
+            } else {
+                a.internal_date.cmp(&b.internal_date)
+            };
+            if sort_by_importance {
+                let rank = |e: &EmailEnvelopeV7| e.importance.unwrap_or_default().rank();
+                rank(a).cmp(&rank(b)).then(by_date)
+            } else {
+                by_date
+            }
+        });
+        paginate_vec(&envelopes, Some(page), Some(page_size)).map(DataPage::from)
     }
 
     pub async fn clean_mailbox_envelopes(account_id: u64, mailbox_id: u64) -> RustMailerResult<()> {
@@ -453,14 +1244,14 @@ impl EmailEnvelopeV3 {
         let start_time = Instant::now();
         loop {
             let deleted = batch_delete_impl(DB_MANAGER.envelope_db(), move |rw| {
-                let to_delete: Vec<EmailEnvelopeV3> = rw
+                let to_delete: Vec<EmailEnvelopeV7> = rw
                     .scan()
-                    .secondary(EmailEnvelopeV3Key::mailbox_id)
+                    .secondary(EmailEnvelopeV7Key::mailbox_id)
                     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
                     .start_with(mailbox_id)
                     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
                     .filter_map(Result::ok) // filter only Ok values
-                    .filter(|e: &EmailEnvelopeV3| e.account_id == account_id)
+                    .filter(|e: &EmailEnvelopeV7| e.account_id == account_id)
                     .take(BATCH_SIZE)
                     .collect();
                 Ok(to_delete)
@@ -496,14 +1287,14 @@ impl EmailEnvelopeV3 {
         loop {
             let to_delete_set = to_delete_set.clone();
             let deleted = batch_delete_impl(DB_MANAGER.envelope_db(), move |rw| {
-                let to_delete: Vec<EmailEnvelopeV3> = rw
+                let to_delete: Vec<EmailEnvelopeV7> = rw
                     .scan()
-                    .secondary(EmailEnvelopeV3Key::mailbox_id)
+                    .secondary(EmailEnvelopeV7Key::mailbox_id)
                     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
                     .start_with(mailbox_id)
                     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
                     .filter_map(Result::ok)
-                    .filter(|e: &EmailEnvelopeV3| {
+                    .filter(|e: &EmailEnvelopeV7| {
                         e.account_id == account_id && to_delete_set.contains(&e.uid)
                     })
                     .take(BATCH_SIZE)
@@ -533,9 +1324,9 @@ impl EmailEnvelopeV3 {
         let start_time = Instant::now();
         loop {
             let deleted = batch_delete_impl(DB_MANAGER.envelope_db(), move |rw| {
-                let to_delete: Vec<EmailEnvelopeV3> = rw
+                let to_delete: Vec<EmailEnvelopeV7> = rw
                     .scan()
-                    .secondary(EmailEnvelopeV3Key::account_id)
+                    .secondary(EmailEnvelopeV7Key::account_id)
                     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
                     .start_with(account_id)
                     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
@@ -562,6 +1353,15 @@ impl EmailEnvelopeV3 {
     }
 }
 
+/// Secondary key ordering the envelopes of a mailbox by a numeric value. The value is
+/// zero-padded so that string order matches numeric order.
+fn mailbox_sort_key(mailbox_id: u64, value: u64) -> String {
+    CompositeKey::new()
+        .segment(mailbox_id)
+        .segment(format!("{value:020}"))
+        .build()
+}
+
 impl From<EmailEnvelope> for EmailEnvelopeV2 {
     fn from(value: EmailEnvelope) -> Self {
         Self {
@@ -694,3 +1494,300 @@ impl From<EmailEnvelopeV3> for EmailEnvelopeV2 {
         }
     }
 }
+
+impl From<EmailEnvelopeV3> for EmailEnvelopeV4 {
+    fn from(value: EmailEnvelopeV3) -> Self {
+        Self {
+            account_id: value.account_id,
+            mailbox_id: value.mailbox_id,
+            mailbox_name: value.mailbox_name,
+            uid: value.uid,
+            internal_date: value.internal_date,
+            size: value.size,
+            flags: value.flags,
+            flags_hash: value.flags_hash,
+            bcc: value.bcc,
+            cc: value.cc,
+            date: value.date,
+            from: value.from,
+            in_reply_to: value.in_reply_to,
+            sender: value.sender,
+            return_address: value.return_address,
+            message_id: value.message_id,
+            subject: value.subject,
+            thread_name: value.thread_name,
+            thread_id: value.thread_id,
+            mime_version: value.mime_version,
+            references: value.references,
+            reply_to: value.reply_to,
+            to: value.to,
+            attachments: value.attachments,
+            body_meta: value.body_meta,
+            received: value.received,
+            mid: value.mid,
+            labels: value.labels,
+            importance: None,
+        }
+    }
+}
+
+impl From<EmailEnvelopeV4> for EmailEnvelopeV3 {
+    fn from(value: EmailEnvelopeV4) -> Self {
+        Self {
+            account_id: value.account_id,
+            mailbox_id: value.mailbox_id,
+            mailbox_name: value.mailbox_name,
+            uid: value.uid,
+            internal_date: value.internal_date,
+            size: value.size,
+            flags: value.flags,
+            flags_hash: value.flags_hash,
+            bcc: value.bcc,
+            cc: value.cc,
+            date: value.date,
+            from: value.from,
+            in_reply_to: value.in_reply_to,
+            sender: value.sender,
+            return_address: value.return_address,
+            message_id: value.message_id,
+            subject: value.subject,
+            thread_name: value.thread_name,
+            thread_id: value.thread_id,
+            mime_version: value.mime_version,
+            references: value.references,
+            reply_to: value.reply_to,
+            to: value.to,
+            attachments: value.attachments,
+            body_meta: value.body_meta,
+            received: value.received,
+            mid: value.mid,
+            labels: value.labels,
+        }
+    }
+}
+
+impl From<EmailEnvelopeV4> for EmailEnvelopeV5 {
+    fn from(value: EmailEnvelopeV4) -> Self {
+        Self {
+            account_id: value.account_id,
+            mailbox_id: value.mailbox_id,
+            mailbox_name: value.mailbox_name,
+            uid: value.uid,
+            internal_date: value.internal_date,
+            size: value.size,
+            flags: value.flags,
+            flags_hash: value.flags_hash,
+            bcc: value.bcc,
+            cc: value.cc,
+            date: value.date,
+            from: value.from,
+            in_reply_to: value.in_reply_to,
+            sender: value.sender,
+            return_address: value.return_address,
+            message_id: value.message_id,
+            subject: value.subject,
+            thread_name: value.thread_name,
+            thread_id: value.thread_id,
+            mime_version: value.mime_version,
+            references: value.references,
+            reply_to: value.reply_to,
+            to: value.to,
+            attachments: value.attachments,
+            body_meta: value.body_meta,
+            received: value.received,
+            mid: value.mid,
+            labels: value.labels,
+            importance: value.importance,
+            received_chain: None,
+        }
+    }
+}
+
+impl From<EmailEnvelopeV5> for EmailEnvelopeV4 {
+    fn from(value: EmailEnvelopeV5) -> Self {
+        Self {
+            account_id: value.account_id,
+            mailbox_id: value.mailbox_id,
+            mailbox_name: value.mailbox_name,
+            uid: value.uid,
+            internal_date: value.internal_date,
+            size: value.size,
+            flags: value.flags,
+            flags_hash: value.flags_hash,
+            bcc: value.bcc,
+            cc: value.cc,
+            date: value.date,
+            from: value.from,
+            in_reply_to: value.in_reply_to,
+            sender: value.sender,
+            return_address: value.return_address,
+            message_id: value.message_id,
+            subject: value.subject,
+            thread_name: value.thread_name,
+            thread_id: value.thread_id,
+            mime_version: value.mime_version,
+            references: value.references,
+            reply_to: value.reply_to,
+            to: value.to,
+            attachments: value.attachments,
+            body_meta: value.body_meta,
+            received: value.received,
+            mid: value.mid,
+            labels: value.labels,
+            importance: value.importance,
+        }
+    }
+}
+
+impl From<EmailEnvelopeV5> for EmailEnvelopeV6 {
+    fn from(value: EmailEnvelopeV5) -> Self {
+        Self {
+            account_id: value.account_id,
+            mailbox_id: value.mailbox_id,
+            mailbox_name: value.mailbox_name,
+            uid: value.uid,
+            internal_date: value.internal_date,
+            size: value.size,
+            flags: value.flags,
+            flags_hash: value.flags_hash,
+            bcc: value.bcc,
+            cc: value.cc,
+            date: value.date,
+            from: value.from,
+            in_reply_to: value.in_reply_to,
+            sender: value.sender,
+            return_address: value.return_address,
+            message_id: value.message_id,
+            subject: value.subject,
+            thread_name: value.thread_name,
+            thread_id: value.thread_id,
+            mime_version: value.mime_version,
+            references: value.references,
+            reply_to: value.reply_to,
+            to: value.to,
+            attachments: value.attachments,
+            body_meta: value.body_meta,
+            received: value.received,
+            mid: value.mid,
+            labels: value.labels,
+            importance: value.importance,
+            received_chain: value.received_chain,
+            authentication_results: None,
+        }
+    }
+}
+
+impl From<EmailEnvelopeV6> for EmailEnvelopeV5 {
+    fn from(value: EmailEnvelopeV6) -> Self {
+        Self {
+            account_id: value.account_id,
+            mailbox_id: value.mailbox_id,
+            mailbox_name: value.mailbox_name,
+            uid: value.uid,
+            internal_date: value.internal_date,
+            size: value.size,
+            flags: value.flags,
+            flags_hash: value.flags_hash,
+            bcc: value.bcc,
+            cc: value.cc,
+            date: value.date,
+            from: value.from,
+            in_reply_to: value.in_reply_to,
+            sender: value.sender,
+            return_address: value.return_address,
+            message_id: value.message_id,
+            subject: value.subject,
+            thread_name: value.thread_name,
+            thread_id: value.thread_id,
+            mime_version: value.mime_version,
+            references: value.references,
+            reply_to: value.reply_to,
+            to: value.to,
+            attachments: value.attachments,
+            body_meta: value.body_meta,
+            received: value.received,
+            mid: value.mid,
+            labels: value.labels,
+            importance: value.importance,
+            received_chain: value.received_chain,
+        }
+    }
+}
+
+impl From<EmailEnvelopeV6> for EmailEnvelopeV7 {
+    fn from(value: EmailEnvelopeV6) -> Self {
+        Self {
+            account_id: value.account_id,
+            mailbox_id: value.mailbox_id,
+            mailbox_name: value.mailbox_name,
+            uid: value.uid,
+            internal_date: value.internal_date,
+            size: value.size,
+            flags: value.flags,
+            flags_hash: value.flags_hash,
+            bcc: value.bcc,
+            cc: value.cc,
+            date: value.date,
+            from: value.from,
+            in_reply_to: value.in_reply_to,
+            sender: value.sender,
+            return_address: value.return_address,
+            message_id: value.message_id,
+            subject: value.subject,
+            thread_name: value.thread_name,
+            thread_id: value.thread_id,
+            mime_version: value.mime_version,
+            references: value.references,
+            reply_to: value.reply_to,
+            to: value.to,
+            attachments: value.attachments,
+            body_meta: value.body_meta,
+            received: value.received,
+            mid: value.mid,
+            labels: value.labels,
+            importance: value.importance,
+            received_chain: value.received_chain,
+            authentication_results: value.authentication_results,
+            parse_error: None,
+            raw_headers: None,
+        }
+    }
+}
+
+impl From<EmailEnvelopeV7> for EmailEnvelopeV6 {
+    fn from(value: EmailEnvelopeV7) -> Self {
+        Self {
+            account_id: value.account_id,
+            mailbox_id: value.mailbox_id,
+            mailbox_name: value.mailbox_name,
+            uid: value.uid,
+            internal_date: value.internal_date,
+            size: value.size,
+            flags: value.flags,
+            flags_hash: value.flags_hash,
+            bcc: value.bcc,
+            cc: value.cc,
+            date: value.date,
+            from: value.from,
+            in_reply_to: value.in_reply_to,
+            sender: value.sender,
+            return_address: value.return_address,
+            message_id: value.message_id,
+            subject: value.subject,
+            thread_name: value.thread_name,
+            thread_id: value.thread_id,
+            mime_version: value.mime_version,
+            references: value.references,
+            reply_to: value.reply_to,
+            to: value.to,
+            attachments: value.attachments,
+            body_meta: value.body_meta,
+            received: value.received,
+            mid: value.mid,
+            labels: value.labels,
+            importance: value.importance,
+            received_chain: value.received_chain,
+            authentication_results: value.authentication_results,
+        }
+    }
+}
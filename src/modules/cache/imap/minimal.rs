@@ -2,19 +2,26 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use std::{collections::HashSet, sync::Arc, time::Instant};
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
 
 use native_db::*;
 use native_model::{native_model, Model};
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::info;
 
 use crate::{
     modules::{
-        cache::imap::{manager::EnvelopeFlagsManager, migration::EmailEnvelopeV3},
+        cache::imap::{manager::EnvelopeFlagsManager, migration::EmailEnvelopeV7},
         database::{
             batch_delete_impl, batch_insert_impl, filter_by_secondary_key_impl,
-            manager::DB_MANAGER, update_impl,
+            manager::DB_MANAGER, with_transaction,
         },
         error::{code::ErrorCode, RustMailerResult},
         utils::envelope_hash,
@@ -67,6 +74,46 @@ impl MinimalEnvelope {
         Ok(())
     }
 
+    /// Moves the minimal envelopes of a mailbox to another mailbox id, keeping their UIDs.
+    pub async fn move_mailbox_envelopes(
+        account_id: u64,
+        from_mailbox_id: u64,
+        to_mailbox_id: u64,
+    ) -> RustMailerResult<()> {
+        const BATCH_SIZE: usize = 200;
+        loop {
+            let moved = Arc::new(AtomicUsize::new(0));
+            let batch_moved = moved.clone();
+            with_transaction(DB_MANAGER.envelope_db(), move |rw| {
+                let to_move: Vec<MinimalEnvelope> = rw
+                    .scan()
+                    .secondary(MinimalEnvelopeKey::mailbox_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .start_with(from_mailbox_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .filter_map(Result::ok)
+                    .filter(|e: &MinimalEnvelope| e.account_id == account_id)
+                    .take(BATCH_SIZE)
+                    .collect();
+                for envelope in to_move {
+                    let mut moved_envelope = envelope.clone();
+                    moved_envelope.mailbox_id = to_mailbox_id;
+                    rw.remove(envelope)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                    rw.insert(moved_envelope)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                    batch_moved.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(())
+            })
+            .await?;
+            if moved.load(Ordering::Relaxed) == 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn clean_mailbox_envelopes(account_id: u64, mailbox_id: u64) -> RustMailerResult<()> {
         const BATCH_SIZE: usize = 200;
         let mut total_deleted = 0usize;
@@ -147,44 +194,6 @@ impl MinimalEnvelope {
         Ok(())
     }
 
-    pub async fn update_flags(
-        account_id: u64,
-        mailbox_id: u64,
-        uid: u32,
-        flags_hash: u64,
-    ) -> RustMailerResult<()> {
-        update_impl(
-            DB_MANAGER.envelope_db(),
-            move |rw| {
-                rw.get()
-                    .primary::<MinimalEnvelope>(envelope_hash(account_id, mailbox_id, uid))
-                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-                    .ok_or_else(|| {
-                        raise_error!(
-                            "The MinimalEnvelope that you want to modify was not found."
-                                .to_string(),
-                            ErrorCode::ResourceNotFound
-                        )
-                    })
-            },
-            move |current| {
-                let mut updated = current.clone();
-                updated.flags_hash = flags_hash;
-                Ok(updated)
-            },
-        )
-        .await
-        .map_err(|e| {
-            error!(
-                "Failed to update flags: account_id={}, mailbox_id={}, uid={}, error={:?}",
-                account_id, mailbox_id, uid, e
-            );
-            e
-        })?;
-
-        Ok(())
-    }
-
     pub async fn clean_account(account_id: u64) -> RustMailerResult<()> {
         const BATCH_SIZE: usize = 200;
         let mut total_deleted = 0usize;
@@ -220,8 +229,8 @@ impl MinimalEnvelope {
     }
 }
 
-impl From<&EmailEnvelopeV3> for MinimalEnvelope {
-    fn from(value: &EmailEnvelopeV3) -> Self {
+impl From<&EmailEnvelopeV7> for MinimalEnvelope {
+    fn from(value: &EmailEnvelopeV7) -> Self {
         Self {
             account_id: value.account_id,
             mailbox_id: value.mailbox_id,
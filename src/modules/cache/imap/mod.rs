@@ -11,7 +11,10 @@ use crate::{
             imap::{
                 address::AddressEntity,
                 envelope::EmailEnvelope,
-                migration::{EmailEnvelopeV2, EmailEnvelopeV3},
+                migration::{
+                    EmailEnvelopeV2, EmailEnvelopeV3, EmailEnvelopeV4, EmailEnvelopeV5,
+                    EmailEnvelopeV6, EmailEnvelopeV7,
+                },
                 minimal::MinimalEnvelope,
                 thread::EmailThread,
             },
@@ -20,19 +23,25 @@ use crate::{
                     envelope::GmailEnvelope,
                     labels::{GmailCheckPoint, GmailLabels},
                 },
+                jmap::sync::{
+                    envelope::JmapEnvelope, mailboxes::JmapMailbox, state::JmapSyncState,
+                },
                 outlook::sync::{
                     delta::FolderDeltaLink, envelope::OutlookEnvelope, folders::OutlookFolder,
                 },
             },
         },
-        database::ModelsAdapter,
+        database::{migration::SchemaVersion, ModelsAdapter},
     },
 };
 use ahash::{AHashMap, AHashSet};
 use mailbox::{EmailFlag, EnvelopeFlag, MailBox};
 use native_db::Models;
 pub mod address;
+pub mod coalescer;
 pub mod envelope;
+pub mod integrity;
+pub mod journal;
 pub mod mailbox;
 pub mod manager;
 pub mod migration;
@@ -48,6 +57,10 @@ pub static ENVELOPE_MODELS: LazyLock<Models> = LazyLock::new(|| {
     adapter.register_model::<EmailEnvelope>();
     adapter.register_model::<EmailEnvelopeV2>();
     adapter.register_model::<EmailEnvelopeV3>();
+    adapter.register_model::<EmailEnvelopeV4>();
+    adapter.register_model::<EmailEnvelopeV5>();
+    adapter.register_model::<EmailEnvelopeV6>();
+    adapter.register_model::<EmailEnvelopeV7>();
     adapter.register_model::<MailBox>();
     adapter.register_model::<MinimalEnvelope>();
     adapter.register_model::<AddressEntity>();
@@ -58,6 +71,10 @@ pub static ENVELOPE_MODELS: LazyLock<Models> = LazyLock::new(|| {
     adapter.register_model::<OutlookFolder>();
     adapter.register_model::<FolderDeltaLink>();
     adapter.register_model::<OutlookEnvelope>();
+    adapter.register_model::<JmapMailbox>();
+    adapter.register_model::<JmapEnvelope>();
+    adapter.register_model::<JmapSyncState>();
+    adapter.register_model::<SchemaVersion>();
     adapter.models
 });
 
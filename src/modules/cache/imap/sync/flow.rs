@@ -5,16 +5,25 @@
 use crate::{
     modules::{
         account::{migration::AccountModel, since::DateSince, status::AccountRunningState},
-        bounce::parser::{extract_bounce_report, BounceReport},
+        bounce::{
+            classify::classify,
+            delivery::link_delivery_report,
+            parser::{extract_bounce_report, BounceReport},
+        },
         cache::{
             imap::{
                 diff, find_deleted_mailboxes, find_flag_updates, find_intersecting_mailboxes,
                 find_missing_mailboxes, find_missing_remote_uids,
+                journal::FlagChangeJournal,
                 mailbox::{EnvelopeFlag, MailBox},
                 manager::EnvelopeFlagsManager,
-                migration::EmailEnvelopeV3,
+                migration::EmailEnvelopeV7,
                 minimal::MinimalEnvelope,
-                sync::rebuild::{rebuild_mailbox_cache, rebuild_mailbox_cache_since_date},
+                sync::{
+                    rebuild::{rebuild_mailbox_cache, rebuild_mailbox_cache_since_date},
+                    rename::handle_renamed_mailboxes,
+                    uidvalidity::reconcile_uid_validity_change,
+                },
             },
             sync_type::SyncType,
             SEMAPHORE,
@@ -37,9 +46,13 @@ use crate::{
             },
             task::EventHookTask,
         },
-        message::content::{retrieve_email_content, FullMessageContent, MessageContentRequest},
-        metrics::RUSTMAILER_NEW_EMAIL_ARRIVAL_TOTAL,
+        message::{
+            attachment_policy::screen_inbound_envelopes,
+            content::{retrieve_email_content, FullMessageContent, MessageContentRequest},
+        },
+        metrics::{RUSTMAILER_ACCOUNT_NEW_EMAIL_ARRIVAL_TOTAL, RUSTMAILER_NEW_EMAIL_ARRIVAL_TOTAL},
         settings::cli::SETTINGS,
+        smtp::{suppression::SuppressedAddress, timeline::DeliveryEvent},
     },
     raise_error,
 };
@@ -125,7 +138,7 @@ pub async fn fetch_and_save_since_date(
                         } else {
                             let envelopes =
                                 extract_rich_envelopes(&fetches, account_id, &mailbox_name)?;
-                            EmailEnvelopeV3::save_envelopes(envelopes).await?;
+                            EmailEnvelopeV7::save_envelopes(envelopes).await?;
                         };
                         Ok(())
                     });
@@ -220,7 +233,7 @@ pub async fn fetch_and_save_full_mailbox(
                         } else {
                             let envelopes =
                                 extract_rich_envelopes(&fetches, account_id, &mailbox_name)?;
-                            EmailEnvelopeV3::save_envelopes(envelopes).await?;
+                            EmailEnvelopeV7::save_envelopes(envelopes).await?;
                         };
                         info!("Batch insertion completed for mailbox: {}, current page: {}, inserted count: {}", &mailbox_name, page, count);
                         Ok(count)
@@ -346,7 +359,7 @@ pub async fn reconcile_mailboxes(
                 }
                 info!(
                     "Account {}: Mailbox '{}' detected with changed uid_validity (local: {:#?}, remote: {:#?}). \
-                    The mailbox data may be invalid, resetting its envelopes and rebuilding the cache.",
+                    Reconciling its cached envelopes with the new UIDs.",
                     account_id, local_mailbox.name, &local_mailbox.uid_validity, &remote_mailbox.uid_validity
                 );
                 if EventHookTask::is_watching_uid_validity_change(account_id).await? {
@@ -365,21 +378,44 @@ pub async fn reconcile_mailboxes(
                         ))
                         .await;
                 }
-                match &account.date_since {
-                    Some(date_since) => {
-                        rebuild_mailbox_cache_since_date(
-                            account,
-                            local_mailbox.id,
-                            date_since,
-                            remote_mailbox,
-                        )
-                        .await?;
+                let reconciled = match reconcile_uid_validity_change(
+                    account,
+                    local_mailbox,
+                    remote_mailbox,
+                )
+                .await
+                {
+                    Ok(reconciled) => reconciled,
+                    Err(e) => {
+                        warn!(
+                            "Account {}: Failed to reconcile mailbox '{}' after its uid_validity changed: {}. Rebuilding its cache.",
+                            account_id, local_mailbox.name, e
+                        );
+                        false
                     }
-                    None => {
-                        rebuild_mailbox_cache(account, local_mailbox, remote_mailbox).await?;
+                };
+                if !reconciled {
+                    match &account.sync_since()? {
+                        Some(date_since) => {
+                            rebuild_mailbox_cache_since_date(
+                                account,
+                                local_mailbox.id,
+                                date_since,
+                                remote_mailbox,
+                            )
+                            .await?;
+                        }
+                        None => {
+                            rebuild_mailbox_cache(account, local_mailbox, remote_mailbox).await?;
+                        }
                     }
                 }
             } else {
+                // Folders with a sync interval are skipped until it elapses; their metadata
+                // is left untouched so the next sync picks up every change.
+                if !account.is_folder_due(&local_mailbox.name) {
+                    continue;
+                }
                 match sync_type {
                     SyncType::FullSync => {
                         perform_full_sync(account, local_mailbox, remote_mailbox).await?;
@@ -390,6 +426,7 @@ pub async fn reconcile_mailboxes(
                     }
                     SyncType::SkipSync => unreachable!(),
                 }
+                account.record_folder_sync(&local_mailbox.name);
             }
             mailboxes_to_update.push(remote_mailbox.clone());
         }
@@ -402,10 +439,10 @@ pub async fn reconcile_mailboxes(
         account.id,
         start_time.elapsed().as_secs()
     );
-    let deleted_mailboxes = find_deleted_mailboxes(local_mailboxes, remote_mailboxes);
-    let missing_mailboxes = find_missing_mailboxes(local_mailboxes, remote_mailboxes);
+    let mut deleted_mailboxes = find_deleted_mailboxes(local_mailboxes, remote_mailboxes);
+    let mut missing_mailboxes = find_missing_mailboxes(local_mailboxes, remote_mailboxes);
 
-    // handle_renamed_mailboxes(account, &mut deleted_mailboxes, &mut missing_mailboxes).await?;
+    handle_renamed_mailboxes(account, &mut deleted_mailboxes, &mut missing_mailboxes).await?;
     //delete local
     if !deleted_mailboxes.is_empty() {
         info!(
@@ -420,7 +457,7 @@ pub async fn reconcile_mailboxes(
         MailBox::batch_insert(&missing_mailboxes).await?;
         for mailbox in &missing_mailboxes {
             if mailbox.exists > 0 {
-                match &account.date_since {
+                match &account.sync_since()? {
                     Some(date_since) => {
                         match rebuild_mailbox_cache_since_date(
                             account, mailbox.id, date_since, &mailbox,
@@ -482,6 +519,7 @@ async fn sync_recent_envelope_flags(
     uid_next: u32,
 ) -> RustMailerResult<()> {
     let min_uid = (uid_next.saturating_sub(200)).max(1);
+    let checkpoint = FlagChangeJournal::checkpoint();
 
     let local_uid_flags = EnvelopeFlagsManager::get_uid_map(account.id, local_mailbox.id, min_uid);
 
@@ -491,7 +529,12 @@ async fn sync_recent_envelope_flags(
         .uid_fetch_uid_and_flags(uid_set.as_str(), &remote_mailbox.encoded_name())
         .await?;
     let remote_uid_flags = parse_fetch_metadata(fetches, false)?;
-    let update_flags = find_flag_updates(&local_uid_flags, remote_uid_flags);
+    let update_flags = FlagChangeJournal::discard_stale(
+        account.id,
+        local_mailbox.id,
+        checkpoint,
+        find_flag_updates(&local_uid_flags, remote_uid_flags),
+    );
     if !update_flags.is_empty() {
         info!(
             "Account {}: Mailbox '{}' incremental sync - {} message flags changed (UID range {}..*)",
@@ -567,7 +610,7 @@ async fn perform_incremental_sync(
                     "No maximum UID found in index for mailbox, assuming local cache is missing."
                 );
 
-                match &account.date_since {
+                match &account.sync_since()? {
                     Some(date_since) => {
                         fetch_and_save_since_date(
                             account,
@@ -606,6 +649,7 @@ async fn perform_full_sync(
         return Ok(());
     }
 
+    let checkpoint = FlagChangeJournal::checkpoint();
     let local_uid_flags_index = EnvelopeFlagsManager::get_uid_map(account.id, local_mailbox.id, 0);
 
     let (remote_uids_set, uids_with_updated_flags, new_uids_to_add) = diff_uids_and_flags(
@@ -613,7 +657,7 @@ async fn perform_full_sync(
         remote_mailbox,
         &local_uid_flags_index,
         remote_mailbox.exists,
-        &account.date_since,
+        &account.sync_since()?,
     )
     .await?;
 
@@ -645,6 +689,12 @@ async fn perform_full_sync(
     )
     .await?;
 
+    let uids_with_updated_flags = FlagChangeJournal::discard_stale(
+        account.id,
+        local_mailbox.id,
+        checkpoint,
+        uids_with_updated_flags,
+    );
     if !uids_with_updated_flags.is_empty() {
         info!("Account {}: Mailbox '{}' has {} envelopes with updated flags. Applying the changes locally.", &account.id, &local_mailbox.name, uids_with_updated_flags.len());
         EnvelopeFlagsManager::update_envelope_flags(
@@ -808,6 +858,9 @@ pub async fn fetch_and_store_new_envelopes_by_uid_list(
 
     let len = uid_list.len();
     RUSTMAILER_NEW_EMAIL_ARRIVAL_TOTAL.inc_by(len as u64);
+    RUSTMAILER_ACCOUNT_NEW_EMAIL_ARRIVAL_TOTAL
+        .with_label_values(&[&account.id.to_string()])
+        .inc_by(len as u64);
 
     let is_email_added_watched = EventHookTask::is_watching_email_add_event(account.id).await?;
     let is_bounce_watched = EventHookTask::bounce_watched(account.id).await?;
@@ -854,17 +907,16 @@ pub async fn fetch_and_store_new_envelopes_by_uid_list(
             .await?;
 
         // Store rich documents if not in minimal sync mode
-        let envelopes = extract_rich_envelopes(&fetches, account.id, &remote.name)?;
-        EmailEnvelopeV3::save_envelopes(envelopes).await?;
+        let mut envelopes = extract_rich_envelopes(&fetches, account.id, &remote.name)?;
+        let quarantined = screen_inbound_envelopes(account, remote, &mut envelopes).await;
+        EmailEnvelopeV7::save_envelopes(envelopes).await?;
 
-        // Process bounce reports if needed
-        if is_bounce_watched {
-            process_bounce_reports(&account, remote, &fetches).await?;
-        }
+        // Process bounce reports, linking delivery status notifications to their tasks
+        process_bounce_reports(account, remote, &fetches).await?;
 
         // Process email added events if needed
         if is_email_added_watched {
-            process_email_added_events(&account, remote, &fetches).await?;
+            process_email_added_events(account, remote, &fetches, &quarantined).await?;
         }
     }
 
@@ -880,8 +932,13 @@ async fn process_email_added_events(
     account: &AccountModel,
     remote: &MailBox,
     fetches: &[Fetch],
+    quarantined: &AHashSet<u32>,
 ) -> RustMailerResult<()> {
     for fetch in fetches {
+        // Quarantined emails have been moved out of this mailbox.
+        if fetch.uid.is_some_and(|uid| quarantined.contains(&uid)) {
+            continue;
+        }
         let envelope = extract_envelope(fetch, account.id, &remote.name)?;
         let thread_id = envelope.compute_thread_id();
         let message_content = match envelope.body_meta {
@@ -898,11 +955,7 @@ async fn process_email_added_events(
                 };
                 retrieve_email_content(account.id, request, true).await?
             }
-            None => FullMessageContent {
-                plain: None,
-                html: None,
-                attachments: None,
-            },
+            None => FullMessageContent::default(),
         };
         EVENT_CHANNEL
             .queue(Event::new(
@@ -932,6 +985,8 @@ async fn process_email_added_events(
                         reply_to: envelope.reply_to,
                         thread_id,
                         labels: vec![],
+                        received_chain: envelope.received_chain,
+                        authentication_results: envelope.authentication_results,
                     }),
                 ),
             ))
@@ -940,7 +995,7 @@ async fn process_email_added_events(
     Ok(())
 }
 
-async fn handle_minimal_sync_or_metadata_fetch(
+pub async fn handle_minimal_sync_or_metadata_fetch(
     account: &AccountModel,
     local_mailbox_id: u64,
     remote: &MailBox,
@@ -971,8 +1026,16 @@ async fn handle_minimal_sync_or_metadata_fetch(
             let fetches = executor
                 .uid_fetch_meta(&batch, &remote.encoded_name(), false)
                 .await?;
-            let envelopes = extract_rich_envelopes(&fetches, account.id, &remote.name)?;
-            EmailEnvelopeV3::save_envelopes(envelopes).await?;
+            let mut envelopes = extract_rich_envelopes(&fetches, account.id, &remote.name)?;
+            screen_inbound_envelopes(account, remote, &mut envelopes).await;
+            EmailEnvelopeV7::save_envelopes(envelopes).await?;
+            // Delivery status notifications are still linked to their tasks without hooks
+            if let Err(e) = process_bounce_reports(account, remote, &fetches).await {
+                warn!(
+                    "Account {}: Failed to process bounce reports in mailbox '{}': {:#?}",
+                    account.id, &remote.name, e
+                );
+            }
         }
 
         info!(
@@ -1012,12 +1075,29 @@ async fn process_bounce_reports(
 
         let report = extract_bounce_report(&message);
 
+        // Link delivery status notifications to the task that sent the original email
+        let task_id = match link_delivery_report(account.id, &report, &remote.name, uid).await {
+            Ok(task_id) => task_id,
+            Err(e) => {
+                warn!(
+                    "Account {}: Failed to link the delivery status notification {}/{} to its email task: {:#?}",
+                    account.id, &remote.name, uid, e
+                );
+                None
+            }
+        };
+
+        // Stop sending to addresses that hard bounced or complained
+        SuppressedAddress::record_report(account.id, &report, task_id).await;
+        // Add the reported outcome to the delivery timeline of the original email
+        DeliveryEvent::record_report(account.id, &report, task_id).await;
+
         // Process bounce event
         if EventHookTask::is_watching_email_bounce(account.id).await?
             && report.delivery_status.is_some()
             && report.original_headers.is_some()
         {
-            submit_bounce_event(account, remote, uid, &fetch, &message, &report).await?;
+            submit_bounce_event(account, remote, uid, &fetch, &message, &report, task_id).await?;
         }
 
         // Process feedback report event
@@ -1038,6 +1118,7 @@ async fn submit_bounce_event(
     fetch: &Fetch,
     message: &Message<'_>,
     report: &BounceReport,
+    task_id: Option<u64>,
 ) -> RustMailerResult<()> {
     EVENT_CHANNEL
         .queue(Event::new(
@@ -1059,6 +1140,8 @@ async fn submit_bounce_event(
                     to: message.to().map(|addr| AddrVec::from(addr).0),
                     original_headers: report.original_headers.clone(),
                     delivery_status: report.delivery_status.clone(),
+                    bounce_category: report.delivery_status.as_ref().and_then(classify),
+                    task_id,
                 }),
             ),
         ))
@@ -0,0 +1,259 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use async_imap::extensions::idle::IdleResponse;
+use dashmap::{DashMap, DashSet};
+use imap_proto::{MailboxDatum, Response};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::{
+    modules::{
+        account::{entity::MailerType, migration::AccountModel},
+        cache::imap::task::SYNC_TASKS,
+        common::signal::SIGNAL_MANAGER,
+        error::{code::ErrorCode, RustMailerResult},
+        imap::{capabilities::fetch_capabilities, manager::ImapConnectionManager},
+        metrics::RUSTMAILER_IMAP_IDLE_CONNECTIONS,
+        settings::cli::SETTINGS,
+    },
+    raise_error,
+};
+
+pub static IDLE_WATCHERS: LazyLock<IdleWatchers> = LazyLock::new(IdleWatchers::new);
+
+/// The mailbox watched over IDLE. A connection can only watch its selected mailbox, and new
+/// mail almost always lands in the inbox; other folders are still synced by polling.
+const IDLE_MAILBOX: &str = "INBOX";
+/// Servers may drop a connection that has been idle for 30 minutes (RFC 2177), so IDLE is
+/// re-issued a little before that.
+const IDLE_RENEW_INTERVAL: Duration = Duration::from_secs(29 * 60);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(300);
+
+struct IdleWatcher {
+    cancel_sender: oneshot::Sender<()>,
+    join_handle: JoinHandle<()>,
+}
+
+enum IdleOutcome {
+    /// IDLE cannot be used for this account; it is synced by polling only.
+    Unsupported,
+    /// The account is disabled; IDLE is retried later.
+    Paused,
+}
+
+/// Keeps one IDLE connection per IMAP account, next to its periodic sync task.
+///
+/// When the server reports a change in the watched mailbox, the sync task of the account is
+/// woken up and runs an incremental sync right away, which emits the usual email events.
+/// The periodic sync keeps running meanwhile, so the account falls back to polling whenever
+/// the server does not support IDLE or the IDLE connection is down.
+pub struct IdleWatchers {
+    watchers: DashMap<u64, IdleWatcher>,
+    pending: DashSet<u64>,
+}
+
+impl IdleWatchers {
+    fn new() -> Self {
+        Self {
+            watchers: DashMap::new(),
+            pending: DashSet::new(),
+        }
+    }
+
+    pub fn start(&self, account_id: u64) {
+        if !SETTINGS.rustmailer_imap_idle_enabled {
+            return;
+        }
+        let (cancel_sender, cancel_receiver) = oneshot::channel();
+        let join_handle = tokio::spawn(run(account_id, cancel_receiver));
+        // Dropping the sender of a previous watcher stops it.
+        self.watchers.insert(
+            account_id,
+            IdleWatcher {
+                cancel_sender,
+                join_handle,
+            },
+        );
+    }
+
+    pub async fn stop(&self, account_id: u64) {
+        if let Some((_, watcher)) = self.watchers.remove(&account_id) {
+            let _ = watcher.cancel_sender.send(());
+            let _ = watcher.join_handle.await;
+        }
+        self.pending.remove(&account_id);
+    }
+
+    /// Returns whether changes were reported over IDLE since the last call, clearing the flag.
+    pub fn take_pending(&self, account_id: u64) -> bool {
+        self.pending.remove(&account_id).is_some()
+    }
+
+    fn notify_changes(&self, account_id: u64) {
+        self.pending.insert(account_id);
+        SYNC_TASKS.wake(account_id);
+    }
+}
+
+async fn run(account_id: u64, mut cancel_receiver: oneshot::Receiver<()>) {
+    let mut shutdown = SIGNAL_MANAGER.subscribe();
+    let mut failures = 0u32;
+    loop {
+        let delay = tokio::select! {
+            outcome = watch(account_id, &mut failures) => match outcome {
+                Ok(IdleOutcome::Unsupported) => break,
+                Ok(IdleOutcome::Paused) => MAX_RECONNECT_DELAY,
+                Err(e) => {
+                    failures += 1;
+                    let delay = reconnect_delay(failures);
+                    warn!(
+                        "Account {}: IDLE connection failed, syncing by polling until it reconnects in {:?}: {:#?}",
+                        account_id, delay, e
+                    );
+                    delay
+                }
+            },
+            _ = &mut cancel_receiver => break,
+            _ = shutdown.recv() => break,
+        };
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = &mut cancel_receiver => break,
+            _ = shutdown.recv() => break,
+        }
+    }
+    debug!("Account {}: IDLE watcher stopped", account_id);
+}
+
+/// Watches the mailbox until the connection fails. Only returns `Ok` when IDLE cannot be
+/// used right now.
+async fn watch(account_id: u64, failures: &mut u32) -> RustMailerResult<IdleOutcome> {
+    let account = AccountModel::get(account_id).await?;
+    if !matches!(account.mailer_type, MailerType::ImapSmtp) {
+        return Ok(IdleOutcome::Unsupported);
+    }
+    if !account.enabled {
+        return Ok(IdleOutcome::Paused);
+    }
+
+    let mut session = ImapConnectionManager::new(account_id).build().await?;
+    let capabilities = fetch_capabilities(&mut session).await?;
+    if !capabilities.has_str("IDLE") {
+        info!(
+            "Account {}: IMAP server does not support IDLE, new emails are synced by polling",
+            account_id
+        );
+        let _ = session.logout().await;
+        return Ok(IdleOutcome::Unsupported);
+    }
+    session
+        .select(IDLE_MAILBOX)
+        .await
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+
+    *failures = 0;
+    let _connected = ConnectedGuard::new();
+    info!(
+        "Account {}: watching {} for changes over IMAP IDLE",
+        account_id, IDLE_MAILBOX
+    );
+    loop {
+        let mut handle = session.idle();
+        handle
+            .init()
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+        let renew_at = Instant::now() + IDLE_RENEW_INTERVAL;
+        loop {
+            // The stop source interrupts IDLE when dropped, so it must outlive the wait.
+            let (wait, _stop) =
+                handle.wait_with_timeout(renew_at.saturating_duration_since(Instant::now()));
+            match wait
+                .await
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?
+            {
+                IdleResponse::NewData(data) => {
+                    if reports_change(data.parsed()) {
+                        IDLE_WATCHERS.notify_changes(account_id);
+                    }
+                }
+                IdleResponse::Timeout | IdleResponse::ManualInterrupt => break,
+            }
+        }
+        session = handle
+            .done()
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+    }
+}
+
+/// Whether an untagged response received during IDLE means the mailbox changed: a new
+/// message count, an expunged message, or updated flags. Keepalives such as `* OK Still
+/// here` are ignored.
+fn reports_change(response: &Response) -> bool {
+    matches!(
+        response,
+        Response::MailboxData(MailboxDatum::Exists(_))
+            | Response::MailboxData(MailboxDatum::Recent(_))
+            | Response::Expunge(_)
+            | Response::Fetch(..)
+    )
+}
+
+/// Exponential backoff between reconnection attempts, from 5 seconds up to 5 minutes.
+fn reconnect_delay(failures: u32) -> Duration {
+    MIN_RECONNECT_DELAY
+        .saturating_mul(1 << failures.saturating_sub(1).min(6))
+        .min(MAX_RECONNECT_DELAY)
+}
+
+/// Counts the account in the IDLE connections gauge for as long as it is alive.
+struct ConnectedGuard;
+
+impl ConnectedGuard {
+    fn new() -> Self {
+        RUSTMAILER_IMAP_IDLE_CONNECTIONS.inc();
+        Self
+    }
+}
+
+impl Drop for ConnectedGuard {
+    fn drop(&mut self) {
+        RUSTMAILER_IMAP_IDLE_CONNECTIONS.dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imap_proto::parser::parse_response;
+
+    fn parsed(line: &[u8]) -> Response<'_> {
+        parse_response(line).unwrap().1
+    }
+
+    #[test]
+    fn detects_mailbox_changes() {
+        assert!(reports_change(&parsed(b"* 23 EXISTS\r\n")));
+        assert!(reports_change(&parsed(b"* 1 RECENT\r\n")));
+        assert!(reports_change(&parsed(b"* 4 EXPUNGE\r\n")));
+        assert!(reports_change(&parsed(b"* 7 FETCH (FLAGS (\\Seen))\r\n")));
+        assert!(!reports_change(&parsed(b"* OK Still here\r\n")));
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        assert_eq!(reconnect_delay(1), Duration::from_secs(5));
+        assert_eq!(reconnect_delay(2), Duration::from_secs(10));
+        assert_eq!(reconnect_delay(4), Duration::from_secs(40));
+        assert_eq!(reconnect_delay(7), Duration::from_secs(300));
+        assert_eq!(reconnect_delay(u32::MAX), Duration::from_secs(300));
+    }
+}
@@ -22,8 +22,11 @@ use sync_folders::get_sync_folders;
 use tracing::{debug, info};
 
 pub mod flow;
+pub mod idle;
 pub mod rebuild;
+pub mod rename;
 pub mod sync_folders;
+pub mod uidvalidity;
 
 static SYNC_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
@@ -57,7 +60,7 @@ pub async fn execute_imap_sync(account: &AccountModel) -> RustMailerResult<()> {
             remote_mailboxes.iter().map(|m| m.name.clone()).collect(),
         )
         .await?;
-        match &account.date_since {
+        match &account.sync_since()? {
             Some(date_since) => {
                 rebuild_cache_since_date(account, &remote_mailboxes, date_since).await?;
             }
@@ -0,0 +1,313 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use dashmap::DashMap;
+use tracing::{info, warn};
+
+use crate::{
+    encode_mailbox_name,
+    modules::{
+        account::migration::AccountModel,
+        cache::imap::{
+            mailbox::MailBox, manager::EnvelopeFlagsManager, migration::EmailEnvelopeV7,
+        },
+        context::executors::RUST_MAIL_CONTEXT,
+        error::RustMailerResult,
+    },
+};
+
+/// Number of cached UIDs compared with the server to confirm a rename.
+const SAMPLE_SIZE: usize = 8;
+
+/// Renames made through RustMailer, per account: old mailbox name -> new mailbox name.
+/// They are trusted by the next sync without sampling.
+static RENAME_HINTS: LazyLock<DashMap<u64, HashMap<String, String>>> = LazyLock::new(DashMap::new);
+
+/// Records a mailbox rename made through RustMailer, so the next sync moves the cached
+/// emails to the new name instead of downloading them again.
+pub fn record_rename(account_id: u64, from: &str, to: &str) {
+    RENAME_HINTS
+        .entry(account_id)
+        .or_default()
+        .insert(from.to_string(), to.to_string());
+}
+
+fn account_hints(account_id: u64) -> HashMap<String, String> {
+    RENAME_HINTS
+        .get(&account_id)
+        .map(|hints| hints.clone())
+        .unwrap_or_default()
+}
+
+/// Returns the name a mailbox was given by a recorded rename, including renames of one of
+/// its parents, which move the whole hierarchy.
+fn hinted_name(hints: &HashMap<String, String>, mailbox: &MailBox) -> Option<String> {
+    if let Some(to) = hints.get(&mailbox.name) {
+        return Some(to.clone());
+    }
+    let delimiter = mailbox.delimiter.as_deref()?;
+    hints.iter().find_map(|(from, to)| {
+        mailbox
+            .name
+            .strip_prefix(&format!("{from}{delimiter}"))
+            .map(|rest| format!("{to}{delimiter}{rest}"))
+    })
+}
+
+/// Picks the evenly spread UIDs compared with the server, newest first.
+pub fn sample_uids(mut uids: Vec<u32>, size: usize) -> Vec<u32> {
+    uids.sort_unstable_by(|a, b| b.cmp(a));
+    if uids.len() <= size {
+        return uids;
+    }
+    let step = uids.len() as f64 / size as f64;
+    (0..size)
+        .map(|i| uids[(i as f64 * step) as usize])
+        .collect()
+}
+
+/// Whether the sampled cached messages are still found under the same UIDs on the server.
+///
+/// A sampled UID matches if the server still has it and, when both sides know the
+/// Message-ID, with the same Message-ID. More than half of the sample must match, which
+/// tolerates messages deleted since the rename.
+pub fn sample_matches(
+    local: &[(u32, Option<String>)],
+    remote: &HashMap<u32, Option<String>>,
+) -> bool {
+    let matched = local
+        .iter()
+        .filter(|(uid, local_id)| match (local_id, remote.get(uid)) {
+            (_, None) => false,
+            (Some(local_id), Some(Some(remote_id))) => local_id == remote_id,
+            _ => true,
+        })
+        .count();
+    matched * 2 > local.len()
+}
+
+/// Whether `remote` is the cached mailbox `local` under a new name.
+///
+/// The UIDVALIDITY must be unchanged, otherwise the cached UIDs are meaningless anyway.
+/// Unless the rename was made through RustMailer, a sample of the cached messages must
+/// also be found under the same UIDs in the renamed mailbox.
+async fn is_same_mailbox(
+    account: &AccountModel,
+    local: &MailBox,
+    remote: &MailBox,
+    hinted: bool,
+) -> RustMailerResult<bool> {
+    if local.uid_validity.is_none() || local.uid_validity != remote.uid_validity {
+        return Ok(false);
+    }
+    if let (Some(local_next), Some(remote_next)) = (local.uid_next, remote.uid_next) {
+        if remote_next < local_next {
+            return Ok(false);
+        }
+    }
+    if hinted {
+        return Ok(true);
+    }
+    let uids: Vec<u32> = EnvelopeFlagsManager::get_uid_map(account.id, local.id, 0)
+        .into_keys()
+        .collect();
+    let sample = sample_uids(uids, SAMPLE_SIZE);
+    if sample.is_empty() {
+        // Nothing cached, so nothing to lose if this is a different mailbox.
+        return Ok(true);
+    }
+
+    let mut local_ids = Vec::with_capacity(sample.len());
+    for uid in &sample {
+        let message_id = if account.minimal_sync() {
+            None
+        } else {
+            EmailEnvelopeV7::find(account.id, local.id, *uid)
+                .await?
+                .and_then(|e| e.message_id)
+        };
+        local_ids.push((*uid, message_id));
+    }
+    let uid_set = sample
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let executor = RUST_MAIL_CONTEXT.imap(account.id).await?;
+    let remote_ids = executor
+        .uid_fetch_message_ids(&uid_set, &remote.encoded_name())
+        .await?;
+    Ok(sample_matches(&local_ids, &remote_ids))
+}
+
+/// Finds the new name of a subscribed folder that disappeared from the server, if it was
+/// renamed rather than deleted, so it stays subscribed.
+///
+/// Only checks the folders that appeared at the same time and share the cached
+/// UIDVALIDITY of the missing folder.
+pub async fn find_renamed_folder(
+    account: &AccountModel,
+    deleted_folder: &str,
+    new_folders: &[String],
+) -> RustMailerResult<Option<String>> {
+    let Ok(local) = MailBox::get(account.id, deleted_folder).await else {
+        return Ok(None);
+    };
+    if let Some(to) = hinted_name(&account_hints(account.id), &local) {
+        if new_folders.contains(&to) {
+            return Ok(Some(to));
+        }
+    }
+    if local.uid_validity.is_none() {
+        return Ok(None);
+    }
+    let executor = RUST_MAIL_CONTEXT.imap(account.id).await?;
+    for folder in new_folders {
+        let mailbox = match executor
+            .examine_mailbox(&encode_mailbox_name!(folder))
+            .await
+        {
+            Ok(mailbox) => mailbox,
+            Err(e) => {
+                warn!(
+                    "Account {}: Failed to examine mailbox '{}' while looking for renamed folders: {}",
+                    account.id, folder, e
+                );
+                continue;
+            }
+        };
+        if mailbox.uid_validity == local.uid_validity {
+            return Ok(Some(folder.clone()));
+        }
+    }
+    Ok(None)
+}
+
+/// Detects mailboxes renamed or moved on the server among those that disappeared
+/// (`deleted`) and appeared (`missing`) since the last sync, and moves their cached
+/// emails to the new mailbox instead of deleting and downloading them again.
+///
+/// Renamed mailboxes are removed from both lists; their new metadata is saved by the next
+/// sync, which then only fetches the changes made since the rename. Recorded renames are
+/// consumed by this check.
+pub async fn handle_renamed_mailboxes(
+    account: &AccountModel,
+    deleted: &mut Vec<MailBox>,
+    missing: &mut Vec<MailBox>,
+) -> RustMailerResult<()> {
+    let hints = RENAME_HINTS
+        .remove(&account.id)
+        .map(|(_, hints)| hints)
+        .unwrap_or_default();
+    let mut index = 0;
+    while index < deleted.len() && !missing.is_empty() {
+        let local = &deleted[index];
+        let hint = hinted_name(&hints, local);
+        let mut candidates: Vec<usize> = (0..missing.len())
+            .filter(|&i| missing[i].uid_validity == local.uid_validity)
+            .collect();
+        if let Some(hint) = &hint {
+            candidates.sort_by_key(|&i| missing[i].name != *hint);
+        }
+
+        let mut renamed_to = None;
+        for i in candidates {
+            let hinted = hint.as_deref() == Some(missing[i].name.as_str());
+            match is_same_mailbox(account, local, &missing[i], hinted).await {
+                Ok(true) => {
+                    renamed_to = Some(i);
+                    break;
+                }
+                Ok(false) => {}
+                Err(e) => warn!(
+                    "Account {}: Failed to check whether mailbox '{}' was renamed to '{}': {}",
+                    account.id, local.name, missing[i].name, e
+                ),
+            }
+        }
+
+        let Some(i) = renamed_to else {
+            index += 1;
+            continue;
+        };
+        let local = deleted.remove(index);
+        let remote = missing.remove(i);
+        info!(
+            "Account {}: Mailbox '{}' was renamed to '{}'. Moving its cached emails.",
+            account.id, local.name, remote.name
+        );
+        EnvelopeFlagsManager::move_mailbox(account.id, local.id, remote.id, &remote.name).await?;
+        // Keep the sync state of the cached mailbox, so the next sync picks up from it.
+        let renamed = MailBox {
+            id: remote.id,
+            name: remote.name,
+            delimiter: remote.delimiter,
+            attributes: remote.attributes,
+            ..local.clone()
+        };
+        MailBox::batch_insert(&[renamed]).await?;
+        MailBox::delete(local.id).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_evenly_newest_first() {
+        assert_eq!(sample_uids(vec![3, 1, 2], 8), vec![3, 2, 1]);
+        let sample = sample_uids((1..=100).collect(), 4);
+        assert_eq!(sample, vec![100, 75, 50, 25]);
+    }
+
+    #[test]
+    fn matches_samples_by_uid_and_message_id() {
+        let local = vec![
+            (10, Some("<a@x>".to_string())),
+            (11, Some("<b@x>".to_string())),
+            (12, None),
+        ];
+        let remote = HashMap::from([
+            (10, Some("<a@x>".to_string())),
+            (11, Some("<b@x>".to_string())),
+        ]);
+        assert!(sample_matches(&local, &remote));
+
+        // Same UIDs, different messages: another mailbox with the same UIDVALIDITY.
+        let other = HashMap::from([
+            (10, Some("<c@x>".to_string())),
+            (11, Some("<d@x>".to_string())),
+            (12, None),
+        ]);
+        assert!(!sample_matches(&local, &other));
+        assert!(!sample_matches(&local, &HashMap::new()));
+    }
+
+    #[test]
+    fn resolves_hints_for_child_mailboxes() {
+        let account_id = u64::MAX;
+        record_rename(account_id, "Projects", "Archive/Projects");
+        let hints = account_hints(account_id);
+        let child = MailBox {
+            name: "Projects/2024".into(),
+            delimiter: Some("/".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            hinted_name(&hints, &child).as_deref(),
+            Some("Archive/Projects/2024")
+        );
+        let unrelated = MailBox {
+            name: "Projects2".into(),
+            delimiter: Some("/".into()),
+            ..Default::default()
+        };
+        assert_eq!(hinted_name(&hints, &unrelated), None);
+        assert_eq!(hinted_name(&hints, &MailBox::default()), None);
+    }
+}
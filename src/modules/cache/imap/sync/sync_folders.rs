@@ -8,7 +8,10 @@ use crate::{
     decode_mailbox_name,
     modules::{
         account::migration::AccountModel,
-        cache::imap::mailbox::{AttributeEnum, MailBox},
+        cache::imap::{
+            mailbox::{AttributeEnum, MailBox},
+            sync::rename::find_renamed_folder,
+        },
         context::executors::RUST_MAIL_CONTEXT,
         error::{code::ErrorCode, RustMailerResult},
         hook::{
@@ -112,6 +115,26 @@ pub async fn get_sync_folders(account: &AccountModel) -> RustMailerResult<Vec<Ma
             ), ErrorCode::ImapUnexpectedResult));
         }
     }
+    if account.sync_policy.is_some() {
+        let subscribed: Vec<&str> = matched_mailboxes.iter().map(|n| n.name()).collect();
+        matched_mailboxes = mailboxes
+            .iter()
+            .filter(|(mailbox, name)| {
+                !is_noselect(mailbox)
+                    && account.is_folder_synced(&mailbox.name, subscribed.contains(&name.name()))
+            })
+            .map(|(_, name)| name)
+            .collect();
+        if matched_mailboxes.is_empty() {
+            return Err(raise_error!(
+                format!(
+                    "The sync policy of account {} excludes every mailbox.",
+                    &account.id
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+    }
     convert_names_to_mailboxes(account.id, matched_mailboxes).await
 }
 
@@ -135,17 +158,32 @@ pub async fn detect_mailbox_changes(
 
     // Handle deleted folders in sync_folders
     if !deleted_folders.is_empty() {
-        // Check if any deleted folders are in sync_folders
-        let remaining_sync_folders: Vec<String> = account
-            .sync_folders
-            .iter()
-            .filter(|folder| !deleted_folders.contains(folder))
-            .cloned()
-            .collect();
+        // Check if any deleted folders are in sync_folders. Folders that were renamed
+        // stay subscribed under their new name.
+        let mut remaining_sync_folders: Vec<String> = Vec::new();
+        let mut renamed_count = 0;
+        let mut removed_count = 0;
+        for folder in &account.sync_folders {
+            if !deleted_folders.contains(folder) {
+                remaining_sync_folders.push(folder.clone());
+                continue;
+            }
+            if let Some(new_name) = find_renamed_folder(account, folder, &new_folders).await? {
+                info!(
+                    "Account {}: Subscribed folder '{}' was renamed to '{}'",
+                    account.id, folder, new_name
+                );
+                if !remaining_sync_folders.contains(&new_name) {
+                    remaining_sync_folders.push(new_name);
+                }
+                renamed_count += 1;
+            } else {
+                removed_count += 1;
+            }
+        }
 
         // If sync_folders changed, update them
-        if remaining_sync_folders.len() != account.sync_folders.len() {
-            let removed_count = account.sync_folders.len() - remaining_sync_folders.len();
+        if renamed_count > 0 || removed_count > 0 {
             info!(
                 "Account {}: Removed {} deleted folders from sync_folders",
                 account.id, removed_count
@@ -0,0 +1,283 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::{HashMap, HashSet};
+
+use async_imap::types::{Fetch, Flag};
+use mail_parser::MessageParser;
+use tracing::info;
+
+use crate::modules::{
+    account::migration::AccountModel,
+    cache::imap::{
+        flags_to_hash,
+        mailbox::{EnvelopeFlag, MailBox},
+        manager::EnvelopeFlagsManager,
+        migration::{EmailEnvelopeV7, EmailEnvelopeV7Key},
+        sync::flow::{generate_uid_sequence_hashset, handle_minimal_sync_or_metadata_fetch},
+    },
+    context::executors::RUST_MAIL_CONTEXT,
+    database::{filter_by_secondary_key_impl, manager::DB_MANAGER},
+    error::RustMailerResult,
+};
+
+/// Number of UIDs whose identity is fetched per request.
+const IDENTITY_BATCH_SIZE: usize = 1000;
+
+/// What identifies a message independently of its UID.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MessageIdentity {
+    pub uid: u32,
+    pub message_id: Option<String>,
+    pub internal_date: Option<i64>,
+    pub size: u32,
+}
+
+impl From<&EmailEnvelopeV7> for MessageIdentity {
+    fn from(envelope: &EmailEnvelopeV7) -> Self {
+        Self {
+            uid: envelope.uid,
+            message_id: envelope.message_id.clone(),
+            internal_date: envelope.internal_date,
+            size: envelope.size,
+        }
+    }
+}
+
+/// Reads the identity and flags of a message fetched with `uid_fetch_identities`.
+fn parse_identity(fetch: &Fetch) -> Option<(MessageIdentity, Vec<EnvelopeFlag>)> {
+    let uid = fetch.uid?;
+    let message_id = fetch
+        .header()
+        .and_then(|header| MessageParser::default().parse_headers(header))
+        .and_then(|headers| headers.message_id().map(String::from));
+    let flags = fetch
+        .flags()
+        .filter(|f| !matches!(f, Flag::Recent))
+        .map(Into::into)
+        .collect();
+    let identity = MessageIdentity {
+        uid,
+        message_id,
+        internal_date: fetch.internal_date().map(|d| d.timestamp_millis()),
+        size: fetch.size.unwrap_or_default(),
+    };
+    Some((identity, flags))
+}
+
+/// Pairs cached messages with the messages found on the server after a UIDVALIDITY
+/// change, returning `(cached UID, new UID)` pairs sorted by cached UID.
+///
+/// Messages are matched by Message-ID. When several messages share a Message-ID, pairs
+/// with the same size, then the same internal date, are preferred. Messages without a
+/// Message-ID only match a message with the same internal date and size.
+pub fn match_identities(local: &[MessageIdentity], remote: &[MessageIdentity]) -> Vec<(u32, u32)> {
+    let mut by_message_id: HashMap<&str, Vec<&MessageIdentity>> = HashMap::new();
+    let mut by_date_and_size: HashMap<(i64, u32), Vec<&MessageIdentity>> = HashMap::new();
+    for r in remote {
+        match (&r.message_id, r.internal_date) {
+            (Some(message_id), _) => by_message_id.entry(message_id).or_default().push(r),
+            (None, Some(date)) => by_date_and_size.entry((date, r.size)).or_default().push(r),
+            (None, None) => {}
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for l in local {
+        let remote = match (&l.message_id, l.internal_date) {
+            (Some(message_id), _) => by_message_id.get(message_id.as_str()),
+            (None, Some(date)) => by_date_and_size.get(&(date, l.size)),
+            (None, None) => None,
+        };
+        for r in remote.into_iter().flatten() {
+            let score = (r.size == l.size, r.internal_date == l.internal_date);
+            candidates.push((score, l.uid, r.uid));
+        }
+    }
+    // Best pairs first; among equal pairs, the lowest UIDs first.
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let mut local_taken = HashSet::new();
+    let mut remote_taken = HashSet::new();
+    let mut matches = Vec::new();
+    for (_, local_uid, remote_uid) in candidates {
+        if !local_taken.contains(&local_uid) && !remote_taken.contains(&remote_uid) {
+            local_taken.insert(local_uid);
+            remote_taken.insert(remote_uid);
+            matches.push((local_uid, remote_uid));
+        }
+    }
+    matches.sort_unstable();
+    matches
+}
+
+/// Keeps the cached emails of a mailbox whose UIDVALIDITY changed, instead of downloading
+/// the whole mailbox again.
+///
+/// The cached envelopes are matched with the messages on the server by Message-ID, date
+/// and size, and given their new UIDs and current flags, which preserves their threads and
+/// other local data. Cached envelopes without a match are removed, and only messages
+/// without a cached envelope are fetched.
+///
+/// Returns `false` without changing anything if the cache cannot be reconciled, e.g. for
+/// minimal sync accounts, which do not cache Message-IDs; the mailbox must then be
+/// rebuilt.
+pub async fn reconcile_uid_validity_change(
+    account: &AccountModel,
+    local_mailbox: &MailBox,
+    remote_mailbox: &MailBox,
+) -> RustMailerResult<bool> {
+    if account.minimal_sync() {
+        return Ok(false);
+    }
+    let cached: Vec<MessageIdentity> = filter_by_secondary_key_impl::<EmailEnvelopeV7>(
+        DB_MANAGER.envelope_db(),
+        EmailEnvelopeV7Key::mailbox_id,
+        local_mailbox.id,
+    )
+    .await?
+    .iter()
+    .filter(|e| e.account_id == account.id)
+    .map(MessageIdentity::from)
+    .collect();
+    if cached.is_empty() {
+        return Ok(false);
+    }
+
+    let executor = RUST_MAIL_CONTEXT.imap(account.id).await?;
+    let encoded_name = remote_mailbox.encoded_name();
+    let query = match &account.sync_since()? {
+        Some(date_since) => format!("SINCE {}", date_since.since_date()?),
+        None => "ALL".to_string(),
+    };
+    let mut uids: Vec<u32> = executor
+        .uid_search(&encoded_name, &query)
+        .await?
+        .into_iter()
+        .collect();
+    uids.sort_unstable();
+
+    let mut remote = Vec::with_capacity(uids.len());
+    let mut remote_flags = HashMap::with_capacity(uids.len());
+    if !uids.is_empty() {
+        for batch in generate_uid_sequence_hashset(uids, IDENTITY_BATCH_SIZE, false) {
+            let fetches = executor.uid_fetch_identities(&batch, &encoded_name).await?;
+            for (identity, flags) in fetches.iter().filter_map(parse_identity) {
+                remote_flags.insert(identity.uid, flags);
+                remote.push(identity);
+            }
+        }
+    }
+
+    let matches = match_identities(&cached, &remote);
+    let matched_local: HashSet<u32> = matches.iter().map(|(old, _)| *old).collect();
+    let matched_remote: HashSet<u32> = matches.iter().map(|(_, new)| *new).collect();
+
+    let stale: Vec<u32> = cached
+        .iter()
+        .map(|c| c.uid)
+        .filter(|uid| !matched_local.contains(uid))
+        .collect();
+    if !stale.is_empty() {
+        EnvelopeFlagsManager::clean_envelopes(account.id, local_mailbox.id, &stale).await?;
+    }
+
+    let remaps = matches
+        .into_iter()
+        .map(|(old, new)| (old, new, remote_flags.remove(&new).unwrap_or_default()))
+        .collect();
+    let remapped = EnvelopeFlagsManager::remap_uids(account.id, local_mailbox.id, remaps).await?;
+
+    let new_messages: Vec<(u32, u64)> = remote
+        .iter()
+        .filter(|r| !matched_remote.contains(&r.uid))
+        .map(|r| {
+            let flags = remote_flags
+                .get(&r.uid)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            (r.uid, flags_to_hash(flags))
+        })
+        .collect();
+    let fetched = new_messages.len();
+    if fetched > 0 {
+        handle_minimal_sync_or_metadata_fetch(
+            account,
+            local_mailbox.id,
+            remote_mailbox,
+            new_messages,
+            fetched,
+        )
+        .await?;
+    }
+
+    info!(
+        "Account {}: Reconciled mailbox '{}' after its UIDVALIDITY changed: {} cached envelopes kept with new UIDs, {} removed, {} new messages fetched.",
+        account.id,
+        local_mailbox.name,
+        remapped,
+        stale.len(),
+        fetched
+    );
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(uid: u32, message_id: Option<&str>, date: i64, size: u32) -> MessageIdentity {
+        MessageIdentity {
+            uid,
+            message_id: message_id.map(String::from),
+            internal_date: Some(date),
+            size,
+        }
+    }
+
+    #[test]
+    fn matches_by_message_id() {
+        let local = vec![
+            identity(1, Some("<a@x>"), 100, 10),
+            identity(2, Some("<b@x>"), 200, 20),
+            identity(3, Some("<gone@x>"), 300, 30),
+        ];
+        let remote = vec![
+            identity(11, Some("<b@x>"), 200, 20),
+            identity(12, Some("<a@x>"), 100, 10),
+            identity(13, Some("<new@x>"), 400, 40),
+        ];
+        assert_eq!(match_identities(&local, &remote), vec![(1, 12), (2, 11)]);
+    }
+
+    #[test]
+    fn prefers_same_size_and_date_for_duplicate_message_ids() {
+        let local = vec![
+            identity(1, Some("<dup@x>"), 100, 10),
+            identity(2, Some("<dup@x>"), 200, 20),
+        ];
+        let remote = vec![
+            identity(7, Some("<dup@x>"), 200, 20),
+            identity(8, Some("<dup@x>"), 100, 10),
+        ];
+        assert_eq!(match_identities(&local, &remote), vec![(1, 8), (2, 7)]);
+
+        // Only one copy is left on the server.
+        let remote = vec![identity(9, Some("<dup@x>"), 200, 20)];
+        assert_eq!(match_identities(&local, &remote), vec![(2, 9)]);
+    }
+
+    #[test]
+    fn matches_messages_without_message_id_by_date_and_size() {
+        let local = vec![identity(1, None, 100, 10), identity(2, None, 200, 20)];
+        let remote = vec![identity(5, None, 100, 10), identity(6, None, 200, 21)];
+        assert_eq!(match_identities(&local, &remote), vec![(1, 5)]);
+
+        let undated = [MessageIdentity {
+            uid: 3,
+            ..Default::default()
+        }];
+        assert!(match_identities(&undated, &undated).is_empty());
+    }
+}
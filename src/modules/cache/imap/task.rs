@@ -4,7 +4,9 @@
 
 use crate::modules::account::entity::{AuthType, MailerType};
 use crate::modules::cache::imap::sync::execute_imap_sync;
+use crate::modules::cache::imap::sync::idle::IDLE_WATCHERS;
 use crate::modules::cache::vendor::gmail::sync::execute_gmail_sync;
+use crate::modules::cache::vendor::jmap::sync::execute_jmap_sync;
 use crate::modules::cache::vendor::outlook::sync::execute_outlook_sync;
 use crate::modules::oauth2::token::OAuth2AccessToken;
 use crate::modules::scheduler::periodic::TaskHandle;
@@ -16,7 +18,9 @@ use crate::modules::{
 use crate::utc_now;
 use dashmap::DashMap;
 use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
 use std::{sync::LazyLock, time::Duration};
+use tokio::sync::Notify;
 use tracing::{error, warn};
 
 static _DESCRIPTION: &str = "This task periodically synchronizes mailbox data for a specified account, ensuring that all local data is up-to-date.";
@@ -27,12 +31,22 @@ const WARN_INTERVAL_MS: i64 = 600_000;
 
 pub struct AccountSyncTask {
     tasks: DashMap<u64, TaskHandle>,
+    triggers: DashMap<u64, Arc<Notify>>,
 }
 
 impl AccountSyncTask {
     pub fn new() -> Self {
         Self {
             tasks: DashMap::new(),
+            triggers: DashMap::new(),
+        }
+    }
+
+    /// Runs the sync task of the account right away instead of waiting for the next cycle.
+    /// Wake-ups received while a sync is running are coalesced into a single extra run.
+    pub fn wake(&self, account_id: u64) {
+        if let Some(trigger) = self.triggers.get(&account_id) {
+            trigger.notify_one();
         }
     }
 
@@ -119,6 +133,28 @@ impl AccountSyncTask {
                                         )
                                     }
                                 }
+                                MailerType::Jmap => {
+                                    if let AuthType::OAuth2 = account.jmap.as_ref().expect("BUG: account.jmap is None, but this should never happen here").auth.auth_type {
+                                        if OAuth2AccessToken::get(account.id).await?.is_none() {
+                                            if utc_now!() % 300_000 == 0 {
+                                                warn!("Account {}: Sync aborted. OAuth2 authorization not completed. Please visit the rustmailer admin page to authorize this account.", account_id);
+                                            }
+                                            return Ok(());
+                                        }
+                                    }
+                                    if let Err(e) = execute_jmap_sync(&account).await {
+                                        STATUS_DISPATCHER
+                                            .append_error(
+                                                account_id,
+                                                format!("error in account sync task: {:#?}", e),
+                                            )
+                                            .await;
+                                        error!(
+                                            "Failed to synchronize mailbox data for '{}': {:?}",
+                                            account_id, e
+                                        )
+                                    }
+                                }
                             }
                         }
                     }
@@ -132,11 +168,23 @@ impl AccountSyncTask {
                 Ok(())
             })
         };
-        let handler = periodic_task.start(task, Some(account_id), TASK_INTERVAL, true, true);
+        let trigger = Arc::new(Notify::new());
+        let handler = periodic_task.start_with_trigger(
+            task,
+            Some(account_id),
+            TASK_INTERVAL,
+            true,
+            true,
+            trigger.clone(),
+        );
+        self.triggers.insert(account_id, trigger);
         self.tasks.insert(account_id, handler);
+        IDLE_WATCHERS.start(account_id);
     }
 
     pub async fn stop(&self, account_id: u64) -> RustMailerResult<()> {
+        IDLE_WATCHERS.stop(account_id).await;
+        self.triggers.remove(&account_id);
         if let Some((_, handler)) = self.tasks.remove(&account_id) {
             handler.cancel().await;
         } else {
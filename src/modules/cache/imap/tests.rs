@@ -82,3 +82,19 @@ async fn test1() {
         file_size as f64 / (1024.0 * 1024.0)
     );
 }
+
+#[test]
+fn test_diff_uid_sets() {
+    use crate::modules::cache::imap::integrity::diff_uid_sets;
+    use ahash::AHashSet;
+
+    let local: AHashSet<u32> = [1, 2, 3, 7, 9].into_iter().collect();
+    let remote = vec![2, 3, 4, 5, 9];
+    let (missing, orphans) = diff_uid_sets(&local, &remote);
+    assert_eq!(missing, vec![4, 5]);
+    assert_eq!(orphans, vec![1, 7]);
+
+    let (missing, orphans) = diff_uid_sets(&AHashSet::new(), &[]);
+    assert!(missing.is_empty());
+    assert!(orphans.is_empty());
+}
@@ -16,10 +16,11 @@ use crate::{
     modules::{
         account::migration::AccountModel,
         cache::{
-            imap::migration::EmailEnvelopeV3,
+            imap::migration::EmailEnvelopeV7,
             model::Envelope,
             vendor::{
                 gmail::sync::{client::GmailClient, envelope::GmailEnvelope},
+                jmap::sync::envelope::JmapEnvelope,
                 outlook::sync::envelope::OutlookEnvelope,
             },
         },
@@ -189,7 +190,7 @@ impl EmailThread {
         .await?;
 
         let fetch_tasks = threads.items.into_iter().map(|thread| async move {
-            EmailEnvelopeV3::get(thread.envelope_id)
+            EmailEnvelopeV7::get(thread.envelope_id)
                 .await?
                 .ok_or_else(|| {
                     raise_error!(
@@ -199,7 +200,7 @@ impl EmailThread {
                 })
         });
 
-        let results: RustMailerResult<Vec<EmailEnvelopeV3>> =
+        let results: RustMailerResult<Vec<EmailEnvelopeV7>> =
             join_all(fetch_tasks).await.into_iter().collect();
 
         let envelopes = results?;
@@ -296,6 +297,44 @@ impl EmailThread {
         })
     }
 
+    pub async fn list_threads_in_jmap_mailbox(
+        mailbox_id: u64,
+        page: u64,
+        page_size: u64,
+        desc: bool,
+    ) -> RustMailerResult<DataPage<Envelope>> {
+        let threads = paginate_secondary_scan_impl::<EmailThread>(
+            DB_MANAGER.envelope_db(),
+            Some(page),
+            Some(page_size),
+            Some(desc),
+            EmailThreadKey::mailbox_id,
+            mailbox_id,
+        )
+        .await?;
+
+        let fetch_tasks = threads.items.into_iter().map(|thread| async move {
+            JmapEnvelope::get(thread.envelope_id).await?.ok_or_else(|| {
+                raise_error!(
+                    format!("Envelope not found: {}", thread.envelope_id),
+                    ErrorCode::InternalError
+                )
+            })
+        });
+
+        let results: RustMailerResult<Vec<JmapEnvelope>> =
+            join_all(fetch_tasks).await.into_iter().collect();
+
+        let envelopes = results?.into_iter().map(|e| e.into()).collect();
+        Ok(DataPage {
+            current_page: threads.page,
+            page_size: threads.page_size,
+            total_items: threads.total_items,
+            items: envelopes,
+            total_pages: threads.total_pages,
+        })
+    }
+
     pub fn need_update(&self, new_thread: &EmailThread) -> bool {
         self.internal_date
             .map_or(true, |c| new_thread.internal_date.map_or(false, |n| n > c))
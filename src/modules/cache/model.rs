@@ -1,7 +1,9 @@
 // Copyright © 2025 rustmailer.com
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
-use poem_openapi::Object;
+use std::cmp::Ordering;
+
+use poem_openapi::{Enum, Object};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -10,9 +12,10 @@ use crate::{
         cache::imap::{
             envelope::Received,
             mailbox::{EmailFlag, EnvelopeFlag},
-            migration::EmailEnvelopeV3,
+            migration::EmailEnvelopeV7,
         },
-        common::Addr,
+        common::{importance::Importance, Addr},
+        envelope::{authentication::AuthenticationResults, received::ReceivedChain},
         imap::section::{EmailBodyPart, ImapAttachment},
     },
 };
@@ -26,6 +29,7 @@ pub struct Envelope {
     /// - **For IMAP accounts:** This is the **UID** (Unique Identifier) converted to a string.
     /// - **For Gmail API accounts:** This is the **Message ID (MID)** returned by the Gmail API.
     /// - **For Microsoft Graph API accounts:** This is the **ID** property (e.g., the base64-encoded EWS ID) of the message object.
+    /// - **For JMAP accounts:** This is the **Email id** assigned by the JMAP server.
     pub id: String,
     /// The ID of the account owning the email.
     pub account_id: u64,
@@ -119,10 +123,29 @@ pub struct Envelope {
     /// Each element is a string representing a Gmail label name (e.g., "INBOX", "UNREAD").
     /// This field reflects the current labels associated with the email.
     ///
-    /// **Note:** This field is populated only for Gmail API accounts. For other account types, it will be empty.
+    /// **Note:** Holds the labels of Gmail API accounts, the categories of Graph API accounts
+    /// and the keywords (e.g. `$flagged`) of JMAP accounts. For IMAP accounts, it will be empty.
     pub labels: Vec<String>,
 
     pub is_read: bool,
+    /// The importance of the email, read from its `Importance` or `X-Priority` header.
+    /// `None` if the email carries no priority header.
+    /// **Note:** Available only for IMAP accounts.
+    pub importance: Option<Importance>,
+    /// The hops of the email's `Received` headers, with the originating client IP and the
+    /// total transit time.
+    /// **Note:** Available only for IMAP accounts.
+    pub received_chain: Option<ReceivedChain>,
+    /// The SPF, DKIM and DMARC verdicts of the receiving server, read from the email's
+    /// `Authentication-Results` headers.
+    /// **Note:** Available only for IMAP accounts.
+    pub authentication_results: Option<AuthenticationResults>,
+    /// Set when the headers of the email could not be fully parsed, with the reason.
+    ///
+    /// The envelope then holds the fields recovered on a best-effort basis; the raw headers
+    /// can be read from the raw headers endpoint.
+    /// **Note:** Available only for IMAP accounts.
+    pub parse_error: Option<String>,
 }
 
 impl Envelope {
@@ -137,8 +160,60 @@ impl Envelope {
     }
 }
 
-impl From<EmailEnvelopeV3> for Envelope {
-    fn from(value: EmailEnvelopeV3) -> Self {
+/// The field a listing of cached envelopes is ordered by.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Enum)]
+pub enum EnvelopeSortField {
+    /// The date the server received the email.
+    #[default]
+    InternalDate,
+    /// The `Date` header of the email. Emails without one come first in ascending order.
+    Date,
+    /// The size of the email.
+    Size,
+    /// The display name of the sender, or their address if they have none, ignoring case.
+    From,
+    /// The subject of the email, ignoring case.
+    Subject,
+}
+
+impl EnvelopeSortField {
+    /// Compares two envelopes in ascending order of this field, then of internal date.
+    pub fn compare(&self, a: &EmailEnvelopeV7, b: &EmailEnvelopeV7) -> Ordering {
+        let by_field = match self {
+            Self::InternalDate => Ordering::Equal,
+            Self::Date => a.date.cmp(&b.date),
+            Self::Size => a.size.cmp(&b.size),
+            Self::From => sender_sort_key(a).cmp(&sender_sort_key(b)),
+            Self::Subject => subject_sort_key(a).cmp(&subject_sort_key(b)),
+        };
+        by_field.then(a.internal_date.cmp(&b.internal_date))
+    }
+}
+
+fn sender_sort_key(envelope: &EmailEnvelopeV7) -> String {
+    envelope
+        .from
+        .as_ref()
+        .and_then(|from| {
+            from.name
+                .as_deref()
+                .filter(|name| !name.trim().is_empty())
+                .or(from.address.as_deref())
+        })
+        .map(|value| value.trim().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn subject_sort_key(envelope: &EmailEnvelopeV7) -> String {
+    envelope
+        .subject
+        .as_deref()
+        .map(|subject| subject.trim().to_lowercase())
+        .unwrap_or_default()
+}
+
+impl From<EmailEnvelopeV7> for Envelope {
+    fn from(value: EmailEnvelopeV7) -> Self {
         Self {
             id: value.uid.to_string(),
             account_id: value.account_id,
@@ -171,6 +246,54 @@ impl From<EmailEnvelopeV3> for Envelope {
             body_meta: value.body_meta,
             received: value.received,
             labels: value.labels,
+            importance: value.importance,
+            received_chain: value.received_chain,
+            authentication_results: value.authentication_results,
+            parse_error: value.parse_error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(internal_date: i64, from: Option<Addr>, subject: Option<&str>) -> EmailEnvelopeV7 {
+        EmailEnvelopeV7 {
+            internal_date: Some(internal_date),
+            from,
+            subject: subject.map(String::from),
+            ..Default::default()
         }
     }
+
+    fn addr(name: Option<&str>, address: &str) -> Option<Addr> {
+        Some(Addr {
+            name: name.map(String::from),
+            address: Some(address.into()),
+        })
+    }
+
+    #[test]
+    fn compares_senders_by_name_then_address() {
+        let alice = envelope(1, addr(Some("alice"), "zed@x"), None);
+        let bob = envelope(2, addr(None, "Bob@x"), None);
+        let carol = envelope(3, addr(Some("Carol"), "a@x"), None);
+        let unknown = envelope(4, None, None);
+        let mut envelopes = [&carol, &unknown, &bob, &alice];
+        envelopes.sort_by(|a, b| EnvelopeSortField::From.compare(a, b));
+        let dates: Vec<_> = envelopes.iter().map(|e| e.internal_date).collect();
+        assert_eq!(dates, vec![Some(4), Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn compares_subjects_ignoring_case_then_internal_date() {
+        let later = envelope(2, None, Some("Report"));
+        let earlier = envelope(1, None, Some("report "));
+        let agenda = envelope(3, None, Some("agenda"));
+        let mut envelopes = [&later, &agenda, &earlier];
+        envelopes.sort_by(|a, b| EnvelopeSortField::Subject.compare(a, b));
+        let dates: Vec<_> = envelopes.iter().map(|e| e.internal_date).collect();
+        assert_eq!(dates, vec![Some(3), Some(1), Some(2)]);
+    }
 }
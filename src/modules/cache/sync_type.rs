@@ -5,6 +5,7 @@
 use crate::{
     modules::{
         account::{entity::MailerType, migration::AccountModel, status::AccountRunningState},
+        cache::imap::sync::idle::IDLE_WATCHERS,
         error::RustMailerResult,
     },
     utc_now,
@@ -35,6 +36,9 @@ pub async fn determine_sync_type(account: &AccountModel) -> RustMailerResult<Syn
 
             match account.mailer_type {
                 MailerType::ImapSmtp => {
+                    // Changes reported by the IDLE watcher are synced without waiting for
+                    // the incremental sync interval.
+                    let idle_changes = IDLE_WATCHERS.take_pending(account.id);
                     if is_time_for_full_sync(
                         now,
                         info.last_full_sync_start,
@@ -44,14 +48,14 @@ pub async fn determine_sync_type(account: &AccountModel) -> RustMailerResult<Syn
                     ) {
                         AccountRunningState::set_full_sync_start(account.id).await?;
                         SyncType::FullSync
-                    } else if incremental_sync {
+                    } else if incremental_sync || idle_changes {
                         AccountRunningState::set_incremental_sync_start(account.id).await?;
                         SyncType::IncrementalSync
                     } else {
                         SyncType::SkipSync
                     }
                 }
-                MailerType::GmailApi | MailerType::GraphApi => {
+                MailerType::GmailApi | MailerType::GraphApi | MailerType::Jmap => {
                     if incremental_sync {
                         AccountRunningState::set_incremental_sync_start(account.id).await?;
                         SyncType::IncrementalSync
@@ -7,13 +7,16 @@ use crate::{
         error::{code::ErrorCode, RustMailerError, RustMailerResult},
         license::cache::CachedLicense,
         settings::{cli::SETTINGS, system::SystemSetting},
-        token::{root::ROOT_TOKEN, AccessToken, AccessTokenScope, AccountInfo},
+        token::{
+            permission::ApiOperation, root::ROOT_TOKEN, AccessToken, AccessTokenScope, AccountInfo,
+        },
         utils::rate_limit::RATE_LIMITER_MANAGER,
     },
     raise_error,
 };
 use governor::clock::{Clock, QuantaClock};
 use http::Method;
+use itertools::Itertools;
 use poem::{
     web::{
         headers::{authorization::Bearer, Authorization, HeaderMapExt},
@@ -65,6 +68,11 @@ impl<E: Endpoint> Endpoint for ApiGuardEndpoint<E> {
                 })?;
         }
         let context = authorize_access(&req, None).await?;
+        context
+            .require_operation(&ApiOperation::classify(req.method(), req.uri().path()))
+            .map_err(|error| {
+                create_api_error_response(&error.to_string(), ErrorCode::PermissionDenied)
+            })?;
         req.set_data(Arc::new(context));
         self.ep.call(req).await
     }
@@ -100,6 +108,30 @@ impl ClientContext {
         }
     }
 
+    /// Requires the permission scopes of a fine-grained token to grant the operation.
+    pub fn require_operation(&self, operation: &ApiOperation) -> RustMailerResult<()> {
+        if !SETTINGS.rustmailer_enable_access_token || self.is_root {
+            return Ok(());
+        }
+        match &self.access_token {
+            Some(token) if !token.permits(operation) => {
+                let scopes = operation.granting_scopes();
+                Err(raise_error!(
+                    if scopes.is_empty() {
+                        "This operation requires a token with the 'Api' scope".into()
+                    } else {
+                        format!(
+                            "Token lacks a scope granting this operation, one of: {}",
+                            scopes.iter().map(|s| format!("{s:?}")).join(", ")
+                        )
+                    },
+                    ErrorCode::PermissionDenied
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub fn require_account_access(&self, account_id: u64) -> RustMailerResult<()> {
         if !SETTINGS.rustmailer_enable_access_token || self.is_root {
             return Ok(());
@@ -114,6 +146,57 @@ impl ClientContext {
         }
     }
 
+    /// The workspace the client is confined to. `None` for root and for tokens not scoped
+    /// to a workspace.
+    pub fn workspace_id(&self) -> Option<u64> {
+        if !SETTINGS.rustmailer_enable_access_token || self.is_root {
+            return None;
+        }
+        self.access_token
+            .as_ref()
+            .and_then(|token| token.workspace_id)
+    }
+
+    /// Requires access to a resource of the workspace `workspace_id`, or to a shared resource
+    /// outside any workspace if `None`. Tokens scoped to a workspace only reach the resources
+    /// of their workspace, other tokens only shared resources.
+    pub fn require_workspace_access(&self, workspace_id: Option<u64>) -> RustMailerResult<()> {
+        if !SETTINGS.rustmailer_enable_access_token
+            || self.is_root
+            || self
+                .access_token
+                .as_ref()
+                .is_some_and(|token| token.reaches_workspace(workspace_id))
+        {
+            return Ok(());
+        }
+        Err(raise_error!(
+            match workspace_id {
+                Some(id) =>
+                    format!("You do not have permission to access the resources of workspace {id}."),
+                None => "Tokens scoped to a workspace cannot access shared resources".into(),
+            },
+            ErrorCode::PermissionDenied
+        ))
+    }
+
+    /// Requires the right to manage a resource of the workspace `workspace_id`: root for
+    /// shared resources outside any workspace, root and the tokens scoped to the workspace
+    /// otherwise.
+    pub fn require_workspace_admin(&self, workspace_id: Option<u64>) -> RustMailerResult<()> {
+        match workspace_id {
+            Some(_) => self.require_workspace_access(workspace_id),
+            None => self.require_root(),
+        }
+    }
+
+    /// Whether a resource of the workspace `workspace_id` is visible to the client. Shared
+    /// resources outside any workspace are only visible to root and to tokens not scoped to
+    /// a workspace.
+    pub fn can_see_workspace(&self, workspace_id: Option<u64>) -> bool {
+        self.require_workspace_access(workspace_id).is_ok()
+    }
+
     pub fn accessible_accounts(&self) -> RustMailerResult<Option<&BTreeSet<AccountInfo>>> {
         if !SETTINGS.rustmailer_enable_access_token || self.is_root {
             Ok(None) // All accounts are accessible
@@ -177,8 +260,18 @@ pub async fn extract_client_context(req: &Request) -> Result<ClientContext> {
         // Validate and update access token
         let validated_token = AccessToken::try_update_access_timestamp(&token)
             .await
-            .map_err(|_| {
-                create_api_error_response("Invalid access token", ErrorCode::PermissionDenied)
+            .map_err(|error| match error {
+                RustMailerError::Generic {
+                    message,
+                    code: ErrorCode::PermissionDenied,
+                    ..
+                } => create_api_error_response(&message, ErrorCode::PermissionDenied),
+                _ => create_api_error_response("Invalid access token", ErrorCode::PermissionDenied),
+            })?
+            .resolve_workspace_accounts()
+            .await
+            .map_err(|error| {
+                create_api_error_response(&error.to_string(), ErrorCode::InternalError)
             })?;
 
         return Ok(ClientContext {
@@ -0,0 +1,301 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::borrow::Cow;
+
+use crate::{base64_encode, get_encoding, modules::imap::decoder::try_decode_string};
+
+/// Maximum length, in bytes, of a sanitized filename.
+const MAX_FILENAME_LEN: usize = 255;
+
+/// Maximum length of a single RFC 2231 parameter section, keeping header lines short.
+const MAX_SECTION_LEN: usize = 60;
+
+/// Characters that are removed from filenames, besides control characters.
+const FORBIDDEN_CHARS: [char; 7] = ['<', '>', ':', '"', '|', '?', '*'];
+
+/// Makes an attachment filename safe to use as a file name on disk.
+///
+/// Any directory part is dropped (both `/` and `\` are treated as separators), control and
+/// reserved characters are removed, and leading/trailing dots and whitespace are trimmed.
+/// Returns `None` if nothing usable is left.
+pub fn sanitize_filename(filename: &str) -> Option<String> {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let name: String = name
+        .chars()
+        .filter(|c| !c.is_control() && !FORBIDDEN_CHARS.contains(c))
+        .collect();
+    let name = name.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if name.is_empty() {
+        return None;
+    }
+    let mut end = name.len().min(MAX_FILENAME_LEN);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(name[..end].to_string())
+}
+
+/// Reads a (possibly RFC 2231 encoded) parameter such as `filename` from a MIME header's
+/// parameter list.
+///
+/// Extended (`filename*`) and continued (`filename*0*`, `filename*1`, ...) forms take
+/// precedence over the plain parameter, which may itself be an RFC 2047 encoded word.
+pub fn decode_param<K, V>(params: &[(K, V)], name: &str) -> Option<String>
+where
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut sections: Vec<(u32, bool, &str)> = Vec::new();
+    let mut plain = None;
+    for (key, value) in params {
+        let key = key.as_ref().trim();
+        let value = value.as_ref().trim();
+        if key.eq_ignore_ascii_case(name) {
+            plain = Some(value);
+            continue;
+        }
+        if key.len() <= name.len() || !key.is_char_boundary(name.len()) {
+            continue;
+        }
+        let (prefix, rest) = key.split_at(name.len());
+        if !prefix.eq_ignore_ascii_case(name) {
+            continue;
+        }
+        match rest {
+            "*" => sections.push((0, true, value)),
+            _ => {
+                let Some(rest) = rest.strip_prefix('*') else {
+                    continue;
+                };
+                let (index, encoded) = match rest.strip_suffix('*') {
+                    Some(index) => (index, true),
+                    None => (rest, false),
+                };
+                if let Ok(index) = index.parse::<u32>() {
+                    sections.push((index, encoded, value));
+                }
+            }
+        }
+    }
+
+    if sections.is_empty() {
+        return plain.map(|value| try_decode_string(unquote(value).as_ref()));
+    }
+    sections.sort_by_key(|(index, _, _)| *index);
+
+    let mut charset = None;
+    let mut bytes = Vec::new();
+    for (position, (_, encoded, value)) in sections.into_iter().enumerate() {
+        let value = unquote(value);
+        if !encoded {
+            bytes.extend_from_slice(value.as_bytes());
+            continue;
+        }
+        let mut value: &str = &value;
+        if position == 0 {
+            // charset'language'value
+            let mut parts = value.splitn(3, '\'');
+            if let (Some(cs), Some(_), Some(rest)) = (parts.next(), parts.next(), parts.next()) {
+                charset = Some(cs.to_string());
+                value = rest;
+            }
+        }
+        bytes.extend(percent_decode(value));
+    }
+
+    let decoded = match charset
+        .filter(|cs| !cs.is_empty())
+        .and_then(|cs| get_encoding!(cs))
+    {
+        Some(encoding) => encoding.decode(&bytes).0.into_owned(),
+        None => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+    Some(decoded)
+}
+
+/// Encodes a MIME header parameter, using RFC 2231 extended notation (with continuations
+/// for long values) when the value is not plain ASCII.
+///
+/// The returned string contains one or more `key=value` pairs, separated by `;` and
+/// folded onto separate header lines.
+pub fn encode_param(key: &str, value: &str) -> String {
+    if value.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+        let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+        return format!("{key}=\"{escaped}\"");
+    }
+
+    let encoded = format!("utf-8''{}", percent_encode(value));
+    if encoded.len() <= MAX_SECTION_LEN {
+        return format!("{key}*={encoded}");
+    }
+
+    let mut sections = Vec::new();
+    let mut rest = encoded.as_str();
+    while !rest.is_empty() {
+        let mut end = rest.len().min(MAX_SECTION_LEN);
+        // Never split a %XX escape across sections.
+        if let Some(pos) = rest[..end].rfind('%') {
+            if pos + 3 > end {
+                end = pos;
+            }
+        }
+        sections.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    sections
+        .iter()
+        .enumerate()
+        .map(|(index, section)| format!("{key}*{index}*={section}"))
+        .collect::<Vec<_>>()
+        .join(";\r\n ")
+}
+
+/// Encodes a value as a single RFC 2047 encoded word, for parameters such as the
+/// Content-Type `name` that many clients read without RFC 2231 support.
+pub fn encode_word(value: &str) -> Cow<'_, str> {
+    if value.is_ascii() {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!("=?utf-8?B?{}?=", base64_encode!(value.as_bytes())))
+    }
+}
+
+fn unquote(value: &str) -> Cow<'_, str> {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) if inner.contains('\\') => {
+            let mut unescaped = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                if c == '\\' {
+                    if let Some(next) = chars.next() {
+                        unescaped.push(next);
+                    }
+                } else {
+                    unescaped.push(c);
+                }
+            }
+            Cow::Owned(unescaped)
+        }
+        Some(inner) => Cow::Borrowed(inner),
+        None => Cow::Borrowed(value),
+    }
+}
+
+fn percent_decode(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(high), Some(low)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                decoded.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    decoded
+}
+
+/// Percent-encodes everything except RFC 5987 `attr-char`s.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len() * 3);
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_path_traversal() {
+        assert_eq!(
+            sanitize_filename("../../etc/passwd").as_deref(),
+            Some("passwd")
+        );
+        assert_eq!(
+            sanitize_filename("..\\..\\Windows\\win.ini").as_deref(),
+            Some("win.ini")
+        );
+        assert_eq!(
+            sanitize_filename("  report<1>?.pdf\r\n").as_deref(),
+            Some("report1.pdf")
+        );
+        assert_eq!(sanitize_filename(".."), None);
+        assert_eq!(sanitize_filename("dir/"), None);
+        assert_eq!(
+            sanitize_filename("报告/季度报告.xlsx").as_deref(),
+            Some("季度报告.xlsx")
+        );
+    }
+
+    #[test]
+    fn encodes_cjk_filenames() {
+        assert_eq!(encode_param("filename", "a.pdf"), "filename=\"a.pdf\"");
+        assert_eq!(
+            encode_param("filename", "报告.pdf"),
+            "filename*=utf-8''%E6%8A%A5%E5%91%8A.pdf"
+        );
+
+        let long = "日本語のファイル名テスト資料.docx";
+        let encoded = encode_param("filename", long);
+        assert!(encoded.starts_with("filename*0*=utf-8''"));
+        assert!(encoded.contains(";\r\n filename*1*="));
+        let params: Vec<(String, String)> = encoded
+            .split(";\r\n ")
+            .map(|p| {
+                let (k, v) = p.split_once('=').unwrap();
+                (k.to_string(), v.to_string())
+            })
+            .collect();
+        assert!(params.iter().all(|(k, v)| k.len() + v.len() < 76));
+        assert_eq!(decode_param(&params, "filename").as_deref(), Some(long));
+
+        assert_eq!(encode_word("报告.pdf"), "=?utf-8?B?5oql5ZGKLnBkZg==?=");
+    }
+
+    #[test]
+    fn decodes_rfc2231_and_rfc2047_params() {
+        let params = [("FILENAME*", "UTF-8''%E6%8A%A5%E5%91%8A.pdf")];
+        assert_eq!(
+            decode_param(&params, "filename").as_deref(),
+            Some("报告.pdf")
+        );
+
+        let params = [
+            ("name*1*", "%E5%91%8A.pdf"),
+            ("name*0*", "utf-8'zh'%E6%8A%A5"),
+            ("name", "fallback.pdf"),
+        ];
+        assert_eq!(decode_param(&params, "name").as_deref(), Some("报告.pdf"));
+
+        let params = [("name*0", "\"long \""), ("name*1", "name.txt")];
+        assert_eq!(
+            decode_param(&params, "name").as_deref(),
+            Some("long name.txt")
+        );
+
+        let params = [("name", "\"=?utf-8?B?5oql5ZGKLnBkZg==?=\"")];
+        assert_eq!(decode_param(&params, "name").as_deref(), Some("报告.pdf"));
+
+        let params = [("filename*", "gb2312''%B1%A8%B8%E6.pdf")];
+        assert_eq!(
+            decode_param(&params, "filename").as_deref(),
+            Some("报告.pdf")
+        );
+
+        assert_eq!(decode_param(&[("charset", "utf-8")], "name"), None);
+    }
+}
@@ -112,6 +112,37 @@ impl HttpClient {
         Ok(response)
     }
 
+    /// Fetches a JSON document with a `GET` request and the given headers.
+    ///
+    /// Error statuses are turned into errors.
+    pub async fn get_json(
+        &self,
+        url: &str,
+        headers: Option<&HashMap<String, String>>,
+    ) -> RustMailerResult<serde_json::Value> {
+        let mut builder = self.client.get(url).header(ACCEPT, "application/json");
+        for (key, value) in headers.into_iter().flatten() {
+            builder = builder.header(key, value);
+        }
+        let res = builder.send().await.map_err(|e| {
+            raise_error!(format!("Request failed: {:#?}", e), ErrorCode::NetworkError)
+        })?;
+        let status = res.status();
+        if !status.is_success() {
+            let text = res.text().await.unwrap_or_default();
+            return Err(raise_error!(
+                format!("GET {} failed with status {}: {}", url, status, text),
+                ErrorCode::ApiCallFailed
+            ));
+        }
+        res.json().await.map_err(|e| {
+            raise_error!(
+                format!("Failed to parse the response of {}: {:#?}", url, e),
+                ErrorCode::InternalError
+            )
+        })
+    }
+
     /// Wrapper around the Gmail API `GET` request to fetch data.
     pub async fn get(&self, url: &str, access_token: &str) -> RustMailerResult<serde_json::Value> {
         let mut attempt = 0;
@@ -482,4 +513,39 @@ impl HttpClient {
             ))
         }
     }
+
+    /// Sends a request with a caller-provided `Authorization` header value, for APIs that
+    /// also accept other schemes than Bearer tokens, such as JMAP with HTTP Basic credentials.
+    ///
+    /// Returns the response of a successful request; error statuses are turned into errors.
+    pub async fn send_with_authorization(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        authorization: &str,
+        body: Option<&serde_json::Value>,
+    ) -> RustMailerResult<reqwest::Response> {
+        let mut builder = self
+            .client
+            .request(method, url)
+            .header(AUTHORIZATION, authorization);
+        if let Some(body) = body {
+            builder = builder.header(CONTENT_TYPE, "application/json").json(body);
+        }
+        let res = builder.send().await.map_err(|e| {
+            raise_error!(format!("Request failed: {:#?}", e), ErrorCode::NetworkError)
+        })?;
+        if res.status().is_success() {
+            return Ok(res);
+        }
+        let status = res.status();
+        let text = res.text().await.unwrap_or_default();
+        Err(raise_error!(
+            format!(
+                "API call to {} failed with status {}: {}",
+                url, status, text
+            ),
+            ErrorCode::ApiCallFailed
+        ))
+    }
 }
@@ -0,0 +1,115 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use mail_parser::Message;
+use mail_send::mail_builder::{
+    headers::{raw::Raw, HeaderType},
+    MessageBuilder,
+};
+use poem_openapi::Enum;
+use serde::{Deserialize, Serialize};
+
+/// Headers carrying the importance of a message, in the order they are consulted.
+const IMPORTANCE_HEADERS: [&str; 4] = ["Importance", "X-Priority", "Priority", "X-MSMail-Priority"];
+
+/// The importance (priority) of an email, as signalled by its `Importance` and
+/// `X-Priority` headers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize, Enum)]
+pub enum Importance {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Importance {
+    /// Headers set on outgoing emails. Normal importance is the default and adds no headers.
+    pub fn headers(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Importance::High => &[("X-Priority", "1 (Highest)"), ("Importance", "high")],
+            Importance::Normal => &[],
+            Importance::Low => &[("X-Priority", "5 (Lowest)"), ("Importance", "low")],
+        }
+    }
+
+    /// Adds the priority headers to an outgoing message.
+    pub fn apply_headers(&self, builder: MessageBuilder<'static>) -> MessageBuilder<'static> {
+        self.headers().iter().fold(builder, |b, (name, value)| {
+            b.header(*name, HeaderType::Raw(Raw::new(*value)))
+        })
+    }
+
+    /// Sort rank, from the most to the least important.
+    pub fn rank(&self) -> u8 {
+        match self {
+            Importance::High => 0,
+            Importance::Normal => 1,
+            Importance::Low => 2,
+        }
+    }
+
+    /// Reads the importance of a parsed message, or `None` if it carries no priority header.
+    pub fn from_message(message: &Message) -> Option<Self> {
+        IMPORTANCE_HEADERS
+            .iter()
+            .filter_map(|name| message.header_raw(*name))
+            .find_map(Self::parse)
+    }
+
+    /// Parses a priority header value, either numeric (`X-Priority: 1 (Highest)`) or
+    /// textual (`Importance: high`, `Priority: urgent`, `X-MSMail-Priority: Low`).
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        if let Some(digit) = value.chars().next().and_then(|c| c.to_digit(10)) {
+            return match digit {
+                1 | 2 => Some(Importance::High),
+                3 => Some(Importance::Normal),
+                4 | 5 => Some(Importance::Low),
+                _ => None,
+            };
+        }
+        match value.split_whitespace().next()? {
+            "high" | "urgent" => Some(Importance::High),
+            "normal" => Some(Importance::Normal),
+            "low" | "non-urgent" => Some(Importance::Low),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mail_parser::MessageParser;
+
+    #[test]
+    fn parses_priority_values() {
+        assert_eq!(Importance::parse(" 1 (Highest)"), Some(Importance::High));
+        assert_eq!(Importance::parse("2"), Some(Importance::High));
+        assert_eq!(Importance::parse("3 (Normal)"), Some(Importance::Normal));
+        assert_eq!(Importance::parse("5 (Lowest)"), Some(Importance::Low));
+        assert_eq!(Importance::parse("High"), Some(Importance::High));
+        assert_eq!(Importance::parse("urgent"), Some(Importance::High));
+        assert_eq!(Importance::parse("non-urgent"), Some(Importance::Low));
+        assert_eq!(Importance::parse("whenever"), None);
+        assert_eq!(Importance::parse(""), None);
+    }
+
+    #[test]
+    fn reads_importance_from_headers() {
+        let parse = |raw: &str| {
+            let message = MessageParser::new().parse(raw.as_bytes()).unwrap();
+            Importance::from_message(&message)
+        };
+        assert_eq!(
+            parse("Subject: a\r\nX-Priority: 5 (Lowest)\r\n\r\nbody"),
+            Some(Importance::Low)
+        );
+        assert_eq!(
+            parse("Subject: a\r\nImportance: High\r\nX-Priority: 5\r\n\r\nbody"),
+            Some(Importance::High)
+        );
+        assert_eq!(parse("Subject: a\r\n\r\nbody"), None);
+    }
+}
@@ -42,6 +42,11 @@ where
         None
     }
 
+    /// Number of entries currently held, including expired ones not yet evicted.
+    pub async fn len(&self) -> usize {
+        self.store.read().await.len()
+    }
+
     /// Insert a new value into the cache.
     pub async fn set(&self, key: K, data: Arc<V>) {
         let mut store = self.store.write().await;
@@ -23,7 +23,9 @@ use tracing::error;
 
 pub mod auth;
 pub mod error;
+pub mod filename;
 pub mod http;
+pub mod importance;
 pub mod log;
 pub mod lru;
 pub mod paginated;
@@ -2,6 +2,7 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::context::guard::IMAP_REQUEST_GUARD;
 use crate::modules::context::Initialize;
 use crate::modules::error::code::ErrorCode;
 use crate::raise_error;
@@ -16,12 +17,32 @@ use crate::{
     utc_now,
 };
 use dashmap::DashMap;
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
 use std::sync::{Arc, LazyLock};
 use tracing::info;
 
 pub static RUST_MAIL_CONTEXT: LazyLock<EmailClientExecutors> =
     LazyLock::new(EmailClientExecutors::new);
 
+/// Connections of a pool.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct PoolStats {
+    /// Connections currently open, in use or idle.
+    pub connections: u32,
+    /// Open connections waiting to be used.
+    pub idle_connections: u32,
+}
+
+impl From<bb8::State> for PoolStats {
+    fn from(state: bb8::State) -> Self {
+        Self {
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+        }
+    }
+}
+
 pub struct EmailClientExecutors {
     start_at: i64,
     imap: DashMap<u64, Arc<ImapExecutor>>,
@@ -52,7 +73,7 @@ impl EmailClientExecutors {
         }
 
         let pool = build_imap_pool(account_id).await?;
-        let new_executor = Arc::new(ImapExecutor::new(pool));
+        let new_executor = Arc::new(ImapExecutor::new(account_id, pool));
 
         match self.imap.try_entry(account_id) {
             Some(dashmap::mapref::entry::Entry::Occupied(entry)) => Ok(entry.get().clone()),
@@ -77,6 +98,15 @@ impl EmailClientExecutors {
             .await
     }
 
+    /// Stats of the IMAP and SMTP pools of an account. A pool is only reported once it
+    /// was created by a first use; this never creates one.
+    pub fn pool_stats(&self, account_id: u64) -> (Option<PoolStats>, Option<PoolStats>) {
+        (
+            self.imap.get(&account_id).map(|e| e.pool_stats()),
+            self.smtp.get(&account_id).map(|e| e.pool_stats()),
+        )
+    }
+
     pub async fn clean_account(&self, account_id: u64) -> RustMailerResult<()> {
         if self.imap.remove(&account_id).is_some() {
             info!(account_id, "Closed IMAP pool for account");
@@ -85,6 +115,7 @@ impl EmailClientExecutors {
         if self.smtp.remove(&account_id).is_some() {
             info!(account_id, "Closed SMTP pool for account");
         }
+        IMAP_REQUEST_GUARD.remove(account_id);
 
         Ok(())
     }
@@ -0,0 +1,158 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{
+    modules::{
+        error::{code::ErrorCode, RustMailerResult},
+        metrics::{
+            RUSTMAILER_ACCOUNT_IMAP_REQUEST_CONTENDED_TOTAL,
+            RUSTMAILER_ACCOUNT_IMAP_REQUEST_REJECTED_TOTAL,
+            RUSTMAILER_ACCOUNT_IMAP_REQUEST_WAIT_SECONDS,
+        },
+        settings::cli::SETTINGS,
+    },
+    raise_error,
+};
+
+/// Limits IMAP-heavy API requests per account, leaving the rest of the account's IMAP pool
+/// to background sync.
+pub static IMAP_REQUEST_GUARD: LazyLock<AccountRequestGuard> = LazyLock::new(|| {
+    AccountRequestGuard::new(
+        SETTINGS.rustmailer_imap_request_concurrency as usize,
+        Duration::from_secs(SETTINGS.rustmailer_imap_request_queue_timeout_secs),
+    )
+});
+
+/// API requests that hold an IMAP connection for a long time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImapRequestKind {
+    RemoteList,
+    FullMessage,
+    Attachment,
+    MessageContent,
+}
+
+impl ImapRequestKind {
+    pub const ALL: [ImapRequestKind; 4] = [
+        ImapRequestKind::RemoteList,
+        ImapRequestKind::FullMessage,
+        ImapRequestKind::Attachment,
+        ImapRequestKind::MessageContent,
+    ];
+
+    /// Value of the `operation` label on the contention metrics.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImapRequestKind::RemoteList => "remote_list",
+            ImapRequestKind::FullMessage => "full_message",
+            ImapRequestKind::Attachment => "attachment",
+            ImapRequestKind::MessageContent => "message_content",
+        }
+    }
+}
+
+/// Per-account semaphores bounding concurrent requests, with a queue timeout.
+pub struct AccountRequestGuard {
+    limit: usize,
+    queue_timeout: Duration,
+    semaphores: DashMap<u64, Arc<Semaphore>>,
+}
+
+impl AccountRequestGuard {
+    pub fn new(limit: usize, queue_timeout: Duration) -> Self {
+        Self {
+            limit,
+            queue_timeout,
+            semaphores: DashMap::new(),
+        }
+    }
+
+    /// Waits for a free slot of the account. The slot is released when the returned permit
+    /// is dropped.
+    ///
+    /// Fails with [`ErrorCode::AccountBusy`] if no slot frees up within the queue timeout.
+    pub async fn acquire(
+        &self,
+        account_id: u64,
+        kind: ImapRequestKind,
+    ) -> RustMailerResult<OwnedSemaphorePermit> {
+        let semaphore = self
+            .semaphores
+            .entry(account_id)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.limit)))
+            .clone();
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            return Ok(permit);
+        }
+
+        let id = account_id.to_string();
+        let labels = [id.as_str(), kind.as_str()];
+        RUSTMAILER_ACCOUNT_IMAP_REQUEST_CONTENDED_TOTAL
+            .with_label_values(&labels)
+            .inc();
+        let start = Instant::now();
+        let result = tokio::time::timeout(self.queue_timeout, semaphore.acquire_owned()).await;
+        RUSTMAILER_ACCOUNT_IMAP_REQUEST_WAIT_SECONDS
+            .with_label_values(&labels)
+            .observe(start.elapsed().as_secs_f64());
+        match result {
+            Ok(Ok(permit)) => Ok(permit),
+            Ok(Err(e)) => Err(raise_error!(
+                format!("Failed to acquire semaphore: {e}"),
+                ErrorCode::InternalError
+            )),
+            Err(_) => {
+                RUSTMAILER_ACCOUNT_IMAP_REQUEST_REJECTED_TOTAL
+                    .with_label_values(&labels)
+                    .inc();
+                Err(raise_error!(
+                    format!(
+                        "Account {account_id} is busy: {} concurrent IMAP requests are already running, retry later",
+                        self.limit
+                    ),
+                    ErrorCode::AccountBusy
+                ))
+            }
+        }
+    }
+
+    /// Drops the semaphore of a removed account.
+    pub fn remove(&self, account_id: u64) {
+        self.semaphores.remove(&account_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::error::RustMailerError;
+
+    #[tokio::test]
+    async fn rejects_requests_beyond_the_limit() {
+        let guard = AccountRequestGuard::new(1, Duration::from_millis(50));
+        let permit = guard.acquire(1, ImapRequestKind::Attachment).await.unwrap();
+
+        let busy = guard.acquire(1, ImapRequestKind::Attachment).await;
+        assert!(matches!(
+            busy,
+            Err(RustMailerError::Generic {
+                code: ErrorCode::AccountBusy,
+                ..
+            })
+        ));
+        // Other accounts are not affected.
+        assert!(guard.acquire(2, ImapRequestKind::Attachment).await.is_ok());
+
+        drop(permit);
+        assert!(guard.acquire(1, ImapRequestKind::RemoteList).await.is_ok());
+    }
+}
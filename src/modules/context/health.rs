@@ -0,0 +1,348 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{sync::LazyLock, time::Instant};
+
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    modules::{
+        account::{
+            dashboard::ErrorStreak, entity::MailerType, migration::AccountModel,
+            status::AccountRunningState,
+        },
+        cache::disk::{get_mount_disk_space, DISK_USAGE_THRESHOLD},
+        context::executors::RUST_MAIL_CONTEXT,
+        metrics::{
+            EMAIL, HOOK, RUSTMAILER_EVENT_CHANNEL_DEPTH, RUSTMAILER_EVENT_CHANNEL_SPILLED_DEPTH,
+            RUSTMAILER_TASK_QUEUE_LENGTH,
+        },
+        scheduler::model::TaskStatus,
+        settings::{cli::SETTINGS, dir::DATA_DIR_MANAGER},
+        tasks::queue::RustMailerTaskQueue,
+    },
+    utc_now,
+};
+
+/// How long a computed status page is served before the checks run again.
+pub const STATUS_PAGE_TTL_SECS: i64 = 15;
+/// Accounts whose sync state is sampled for the IMAP connectivity check.
+const IMAP_SAMPLE_SIZE: usize = 50;
+/// Queued tasks of one type above which the queue is reported as degraded.
+const TASK_BACKLOG_THRESHOLD: i64 = 10_000;
+/// Metadata reads slower than this are reported as degraded.
+const SLOW_DATABASE_MS: u128 = 1_000;
+/// Disk usage of the cache volume, in percent, above which the cache is reported as down.
+const DISK_FULL_THRESHOLD: f64 = 98.0;
+
+static STATUS_PAGE_CACHE: LazyLock<Mutex<Option<StatusPage>>> = LazyLock::new(|| Mutex::new(None));
+
+/// Severity of a component or of the whole service, from best to worst.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Deserialize, Serialize, Enum,
+)]
+pub enum Severity {
+    /// Working normally.
+    #[default]
+    Operational,
+    /// Working, with errors or delays.
+    Degraded,
+    /// Not working.
+    Outage,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ComponentStatus {
+    /// The component: `database`, `task_queue`, `imap`, `event_hooks` or `disk_cache`.
+    pub name: String,
+    pub severity: Severity,
+    /// What was measured, e.g. `3 of 40 sampled accounts are failing to sync`.
+    pub message: String,
+}
+
+impl ComponentStatus {
+    fn new(name: &str, severity: Severity, message: String) -> Self {
+        Self {
+            name: name.into(),
+            severity,
+            message,
+        }
+    }
+}
+
+/// Component-level health of the service, for status pages and uptime monitors.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct StatusPage {
+    /// The worst severity of the components.
+    pub severity: Severity,
+    /// Time (Unix epoch milliseconds) the checks ran. Results are reused for
+    /// `STATUS_PAGE_TTL_SECS` seconds.
+    pub checked_at: i64,
+    /// The service uptime in milliseconds.
+    pub uptime_ms: i64,
+    /// The version of the RustMailer service currently running.
+    pub version: String,
+    pub components: Vec<ComponentStatus>,
+}
+
+/// The status page without measurements, for unauthenticated access.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct PublicStatusPage {
+    pub severity: Severity,
+    pub checked_at: i64,
+    pub components: Vec<PublicComponentStatus>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct PublicComponentStatus {
+    pub name: String,
+    pub severity: Severity,
+}
+
+impl StatusPage {
+    /// Returns the status page, running the checks again if the last result is older
+    /// than `STATUS_PAGE_TTL_SECS`.
+    pub async fn get() -> Self {
+        let mut cached = STATUS_PAGE_CACHE.lock().await;
+        if let Some(page) = cached.as_ref() {
+            if utc_now!() - page.checked_at < STATUS_PAGE_TTL_SECS * 1000 {
+                return page.clone();
+            }
+        }
+        let page = Self::check().await;
+        *cached = Some(page.clone());
+        page
+    }
+
+    async fn check() -> Self {
+        let started = Instant::now();
+        let accounts = AccountModel::list_all().await;
+        let database = match &accounts {
+            Ok(accounts) => database_status(accounts.len(), started.elapsed().as_millis()),
+            Err(e) => ComponentStatus::new(
+                "database",
+                Severity::Outage,
+                format!("Failed to read the metadata: {e}"),
+            ),
+        };
+        let imap = match accounts {
+            Ok(accounts) => imap_status(accounts).await,
+            Err(_) => ComponentStatus::new(
+                "imap",
+                Severity::Outage,
+                "Accounts could not be read from the metadata".into(),
+            ),
+        };
+        Self::from_components(vec![
+            database,
+            task_queue_status().await,
+            imap,
+            event_hooks_status(),
+            disk_cache_status(),
+        ])
+    }
+
+    pub fn from_components(components: Vec<ComponentStatus>) -> Self {
+        Self {
+            severity: components
+                .iter()
+                .map(|c| c.severity)
+                .max()
+                .unwrap_or_default(),
+            checked_at: utc_now!(),
+            uptime_ms: RUST_MAIL_CONTEXT.uptime_ms(),
+            version: env!("CARGO_PKG_VERSION").into(),
+            components,
+        }
+    }
+
+    pub fn to_public(&self) -> PublicStatusPage {
+        PublicStatusPage {
+            severity: self.severity,
+            checked_at: self.checked_at,
+            components: self
+                .components
+                .iter()
+                .map(|c| PublicComponentStatus {
+                    name: c.name.clone(),
+                    severity: c.severity,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn database_status(accounts: usize, elapsed_ms: u128) -> ComponentStatus {
+    let severity = if elapsed_ms > SLOW_DATABASE_MS {
+        Severity::Degraded
+    } else {
+        Severity::Operational
+    };
+    ComponentStatus::new(
+        "database",
+        severity,
+        format!("Read {accounts} accounts in {elapsed_ms} ms"),
+    )
+}
+
+async fn task_queue_status() -> ComponentStatus {
+    let running = match RustMailerTaskQueue::get() {
+        Ok(queue) => queue.list_email_tasks_by_status(TaskStatus::Running).await,
+        Err(e) => Err(e),
+    };
+    let running = match running {
+        Ok(running) => running.len(),
+        Err(e) => {
+            return ComponentStatus::new(
+                "task_queue",
+                Severity::Outage,
+                format!("Failed to read the task queue: {e}"),
+            )
+        }
+    };
+    let scheduled = RUSTMAILER_TASK_QUEUE_LENGTH
+        .with_label_values(&[EMAIL])
+        .get();
+    let severity = if scheduled > TASK_BACKLOG_THRESHOLD {
+        Severity::Degraded
+    } else {
+        Severity::Operational
+    };
+    ComponentStatus::new(
+        "task_queue",
+        severity,
+        format!("{scheduled} emails queued, {running} being sent"),
+    )
+}
+
+async fn imap_status(accounts: Vec<AccountModel>) -> ComponentStatus {
+    let mut sampled = 0;
+    let mut failing = 0;
+    for account in accounts
+        .into_iter()
+        .filter(|a| a.enabled && matches!(a.mailer_type, MailerType::ImapSmtp))
+        .take(IMAP_SAMPLE_SIZE)
+    {
+        match AccountRunningState::get(account.id).await {
+            Ok(Some(state)) => {
+                sampled += 1;
+                if ErrorStreak::from_state(&state).count > 0 {
+                    failing += 1;
+                }
+            }
+            // Not synced yet.
+            Ok(None) => {}
+            Err(e) => {
+                return ComponentStatus::new(
+                    "imap",
+                    Severity::Outage,
+                    format!(
+                        "Failed to read the sync state of account {}: {e}",
+                        account.id
+                    ),
+                )
+            }
+        }
+    }
+    ComponentStatus::new(
+        "imap",
+        imap_severity(sampled, failing),
+        format!("{failing} of {sampled} sampled accounts are failing to sync"),
+    )
+}
+
+/// Degraded when at least half of the sampled accounts are failing to sync. A few failing
+/// accounts usually have their own problems, such as a changed password.
+pub fn imap_severity(sampled: usize, failing: usize) -> Severity {
+    if sampled > 0 && failing * 2 >= sampled {
+        Severity::Degraded
+    } else {
+        Severity::Operational
+    }
+}
+
+fn event_hooks_status() -> ComponentStatus {
+    let queued = RUSTMAILER_EVENT_CHANNEL_DEPTH.get();
+    let spilled = RUSTMAILER_EVENT_CHANNEL_SPILLED_DEPTH.get();
+    let deliveries = RUSTMAILER_TASK_QUEUE_LENGTH
+        .with_label_values(&[HOOK])
+        .get();
+    let severity = if spilled > 0
+        || queued >= SETTINGS.rustmailer_event_channel_capacity as i64
+        || deliveries > TASK_BACKLOG_THRESHOLD
+    {
+        Severity::Degraded
+    } else {
+        Severity::Operational
+    };
+    ComponentStatus::new(
+        "event_hooks",
+        severity,
+        format!(
+            "{queued} events waiting for dispatch, {spilled} spilled to disk, {deliveries} deliveries queued"
+        ),
+    )
+}
+
+fn disk_cache_status() -> ComponentStatus {
+    match get_mount_disk_space(&DATA_DIR_MANAGER.disk_cache) {
+        Some(space) if space.total_space > 0 => {
+            let used = (space.total_space - space.available_space) as f64 * 100.0
+                / space.total_space as f64;
+            ComponentStatus::new(
+                "disk_cache",
+                disk_severity(used),
+                format!(
+                    "{:.1}% of the cache volume used, {} MB available",
+                    used,
+                    space.available_space / (1024 * 1024)
+                ),
+            )
+        }
+        _ => ComponentStatus::new(
+            "disk_cache",
+            Severity::Degraded,
+            "The volume of the cache directory could not be found".into(),
+        ),
+    }
+}
+
+/// Degraded from the usage at which the disk cache starts evicting items, down when the
+/// volume is about to be full.
+pub fn disk_severity(used_percent: f64) -> Severity {
+    if used_percent >= DISK_FULL_THRESHOLD {
+        Severity::Outage
+    } else if used_percent >= DISK_USAGE_THRESHOLD {
+        Severity::Degraded
+    } else {
+        Severity::Operational
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_worst_component_severity() {
+        let page = StatusPage::from_components(vec![
+            ComponentStatus::new("database", Severity::Operational, String::new()),
+            ComponentStatus::new("imap", imap_severity(40, 20), String::new()),
+            ComponentStatus::new("disk_cache", disk_severity(50.0), String::new()),
+        ]);
+        assert_eq!(page.severity, Severity::Degraded);
+        assert_eq!(page.to_public().components[1].severity, Severity::Degraded);
+
+        assert_eq!(imap_severity(0, 0), Severity::Operational);
+        assert_eq!(imap_severity(40, 3), Severity::Operational);
+        assert_eq!(disk_severity(DISK_USAGE_THRESHOLD), Severity::Degraded);
+        assert_eq!(disk_severity(99.0), Severity::Outage);
+        assert_eq!(
+            StatusPage::from_components(Vec::new()).severity,
+            Severity::Operational
+        );
+    }
+}
@@ -6,6 +6,9 @@ use crate::modules::error::RustMailerResult;
 
 pub mod controller;
 pub mod executors;
+pub mod guard;
+pub mod health;
+pub mod resources;
 pub mod status;
 
 pub trait Initialize {
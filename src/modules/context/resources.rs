@@ -0,0 +1,357 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    sync::{Arc, LazyLock},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+use tracing::{info, warn};
+
+use crate::{
+    modules::{
+        context::RustMailTask,
+        error::{code::ErrorCode, RustMailerResult},
+        metrics::{
+            RUSTMAILER_CONNECTION_ADMISSION_REJECTED_TOTAL, RUSTMAILER_MAX_FILE_DESCRIPTORS,
+            RUSTMAILER_OPEN_CONNECTIONS, RUSTMAILER_OPEN_FILE_DESCRIPTORS,
+        },
+        scheduler::periodic::PeriodicTask,
+        settings::cli::SETTINGS,
+    },
+    raise_error,
+};
+
+const FD_MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Caps the IMAP and SMTP connections open at the same time across all accounts.
+pub static CONNECTION_LIMITER: LazyLock<ConnectionLimiter> = LazyLock::new(|| {
+    ConnectionLimiter::new(
+        SETTINGS.rustmailer_max_imap_connections,
+        SETTINGS.rustmailer_max_smtp_connections,
+        Duration::from_secs(SETTINGS.rustmailer_connection_admission_timeout_secs),
+    )
+});
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConnectionKind {
+    Imap,
+    Smtp,
+}
+
+impl ConnectionKind {
+    /// Value of the `protocol` label on the connection metrics.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionKind::Imap => "imap",
+            ConnectionKind::Smtp => "smtp",
+        }
+    }
+}
+
+/// Global semaphores bounding open connections per protocol, with an admission timeout.
+/// A protocol without a limit is only counted.
+pub struct ConnectionLimiter {
+    imap: Option<Arc<Semaphore>>,
+    smtp: Option<Arc<Semaphore>>,
+    imap_limit: Option<u32>,
+    smtp_limit: Option<u32>,
+    admission_timeout: Duration,
+}
+
+impl ConnectionLimiter {
+    pub fn new(
+        imap_limit: Option<u32>,
+        smtp_limit: Option<u32>,
+        admission_timeout: Duration,
+    ) -> Self {
+        let semaphore = |limit: Option<u32>| limit.map(|l| Arc::new(Semaphore::new(l as usize)));
+        Self {
+            imap: semaphore(imap_limit),
+            smtp: semaphore(smtp_limit),
+            imap_limit,
+            smtp_limit,
+            admission_timeout,
+        }
+    }
+
+    pub fn limit(&self, kind: ConnectionKind) -> Option<u32> {
+        match kind {
+            ConnectionKind::Imap => self.imap_limit,
+            ConnectionKind::Smtp => self.smtp_limit,
+        }
+    }
+
+    /// Number of connections of `kind` currently open.
+    pub fn open(&self, kind: ConnectionKind) -> u64 {
+        RUSTMAILER_OPEN_CONNECTIONS
+            .with_label_values(&[kind.as_str()])
+            .get()
+            .max(0) as u64
+    }
+
+    /// Waits for a free connection slot. The slot is released when the returned permit,
+    /// which should live as long as the connection, is dropped.
+    ///
+    /// Fails with [`ErrorCode::ConnectionPoolTimeout`] if no slot frees up within the
+    /// admission timeout.
+    pub async fn acquire(&self, kind: ConnectionKind) -> RustMailerResult<ConnectionPermit> {
+        let semaphore = match kind {
+            ConnectionKind::Imap => &self.imap,
+            ConnectionKind::Smtp => &self.smtp,
+        };
+        let Some(semaphore) = semaphore else {
+            return Ok(ConnectionPermit::new(kind, None));
+        };
+        match tokio::time::timeout(self.admission_timeout, semaphore.clone().acquire_owned()).await
+        {
+            Ok(Ok(permit)) => Ok(ConnectionPermit::new(kind, Some(permit))),
+            Ok(Err(e)) => Err(raise_error!(
+                format!("Failed to acquire semaphore: {e}"),
+                ErrorCode::InternalError
+            )),
+            Err(_) => {
+                RUSTMAILER_CONNECTION_ADMISSION_REJECTED_TOTAL
+                    .with_label_values(&[kind.as_str()])
+                    .inc();
+                let limit = self.limit(kind).unwrap_or_default();
+                warn!(
+                    "Global {} connection limit of {} reached, a new connection was rejected",
+                    kind.as_str(),
+                    limit
+                );
+                Err(raise_error!(
+                    format!(
+                        "Too many open {} connections: the global limit of {} is reached, retry later",
+                        kind.as_str(),
+                        limit
+                    ),
+                    ErrorCode::ConnectionPoolTimeout
+                ))
+            }
+        }
+    }
+}
+
+/// A connection slot, counted in `rustmailer_open_connections` until dropped.
+#[derive(Debug)]
+pub struct ConnectionPermit {
+    kind: ConnectionKind,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl ConnectionPermit {
+    fn new(kind: ConnectionKind, permit: Option<OwnedSemaphorePermit>) -> Self {
+        RUSTMAILER_OPEN_CONNECTIONS
+            .with_label_values(&[kind.as_str()])
+            .inc();
+        Self {
+            kind,
+            _permit: permit,
+        }
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        RUSTMAILER_OPEN_CONNECTIONS
+            .with_label_values(&[self.kind.as_str()])
+            .dec();
+    }
+}
+
+/// A connection, or its stream, holding its slot until dropped.
+#[derive(Debug)]
+pub struct Admitted<T> {
+    inner: T,
+    _permit: ConnectionPermit,
+}
+
+impl<T> Admitted<T> {
+    pub fn new(inner: T, permit: ConnectionPermit) -> Self {
+        Self {
+            inner,
+            _permit: permit,
+        }
+    }
+}
+
+impl<T> Deref for Admitted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for Admitted<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Admitted<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Admitted<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+/// Process resource usage against its limits.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ResourceUsage {
+    /// File descriptors open by the process. Only reported on Linux.
+    pub open_file_descriptors: Option<u64>,
+    /// Soft limit on open file descriptors (RLIMIT_NOFILE). Only reported on Linux.
+    pub max_file_descriptors: Option<u64>,
+    /// Open IMAP connections, including pooled and IDLE connections.
+    pub imap_connections: u64,
+    /// Configured limit on open IMAP connections, if any.
+    pub max_imap_connections: Option<u32>,
+    /// Open SMTP connections.
+    pub smtp_connections: u64,
+    /// Configured limit on open SMTP connections, if any.
+    pub max_smtp_connections: Option<u32>,
+}
+
+impl ResourceUsage {
+    pub fn get() -> Self {
+        Self {
+            open_file_descriptors: open_file_descriptors(),
+            max_file_descriptors: max_file_descriptors(),
+            imap_connections: CONNECTION_LIMITER.open(ConnectionKind::Imap),
+            max_imap_connections: CONNECTION_LIMITER.limit(ConnectionKind::Imap),
+            smtp_connections: CONNECTION_LIMITER.open(ConnectionKind::Smtp),
+            max_smtp_connections: CONNECTION_LIMITER.limit(ConnectionKind::Smtp),
+        }
+    }
+}
+
+fn open_file_descriptors() -> Option<u64> {
+    std::fs::read_dir("/proc/self/fd")
+        .ok()
+        .map(|entries| entries.count() as u64)
+}
+
+fn max_file_descriptors() -> Option<u64> {
+    std::fs::read_to_string("/proc/self/limits")
+        .ok()
+        .and_then(|limits| parse_open_files_limit(&limits))
+}
+
+/// Parses the soft limit from the `Max open files` line of `/proc/<pid>/limits`.
+fn parse_open_files_limit(limits: &str) -> Option<u64> {
+    limits
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|soft| soft.parse().ok())
+}
+
+/// Updates the file descriptor gauges and warns when usage nears the limit.
+fn check_file_descriptors() {
+    let Some(open) = open_file_descriptors() else {
+        return;
+    };
+    RUSTMAILER_OPEN_FILE_DESCRIPTORS.set(open as i64);
+    let Some(max) = max_file_descriptors() else {
+        return;
+    };
+    RUSTMAILER_MAX_FILE_DESCRIPTORS.set(max as i64);
+    let threshold = max * SETTINGS.rustmailer_fd_warning_percent as u64 / 100;
+    if open >= threshold {
+        warn!(
+            "Process has {} of at most {} file descriptors open ({} IMAP and {} SMTP connections). Raise the ulimit or lower rustmailer_max_imap_connections/rustmailer_max_smtp_connections",
+            open,
+            max,
+            CONNECTION_LIMITER.open(ConnectionKind::Imap),
+            CONNECTION_LIMITER.open(ConnectionKind::Smtp)
+        );
+    }
+}
+
+pub struct FileDescriptorMonitorTask;
+
+impl RustMailTask for FileDescriptorMonitorTask {
+    fn start() {
+        if open_file_descriptors().is_none() {
+            info!("File descriptor usage is not available on this platform, monitoring disabled.");
+            return;
+        }
+        let periodic_task = PeriodicTask::new("file-descriptor-monitor");
+        let task = move |_: Option<u64>| {
+            Box::pin(async move {
+                check_file_descriptors();
+                Ok(())
+            })
+        };
+        periodic_task.start(task, None, FD_MONITOR_INTERVAL, false, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::error::RustMailerError;
+
+    #[tokio::test]
+    async fn rejects_connections_beyond_the_limit() {
+        let limiter = ConnectionLimiter::new(Some(1), None, Duration::from_millis(10));
+        let permit = limiter.acquire(ConnectionKind::Imap).await.unwrap();
+        let error = limiter.acquire(ConnectionKind::Imap).await.unwrap_err();
+        assert!(matches!(
+            error,
+            RustMailerError::Generic {
+                code: ErrorCode::ConnectionPoolTimeout,
+                ..
+            }
+        ));
+        // Unlimited protocols are only counted.
+        assert!(limiter.acquire(ConnectionKind::Smtp).await.is_ok());
+        drop(permit);
+        assert!(limiter.acquire(ConnectionKind::Imap).await.is_ok());
+    }
+
+    #[test]
+    fn parses_open_files_limit() {
+        let limits = "Limit                     Soft Limit           Hard Limit           Units     \n\
+                      Max processes             63704                63704                processes \n\
+                      Max open files            1024                 524288               files     \n";
+        assert_eq!(parse_open_files_limit(limits), Some(1024));
+        assert_eq!(
+            parse_open_files_limit("Max open files unlimited unlimited files"),
+            None
+        );
+    }
+}
@@ -3,6 +3,7 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::context::executors::RUST_MAIL_CONTEXT;
+use crate::modules::database::integrity::{IntegrityReport, StartupIntegrityCheck};
 use chrono::Local;
 use poem_openapi::Object;
 use serde::Deserialize;
@@ -20,6 +21,8 @@ pub struct RustMailerStatus {
     pub timezone: String,
     /// The version of the RustMailer service currently running.
     pub version: String,
+    /// The result of the startup integrity check of the metadata.
+    pub integrity: Option<IntegrityReport>,
 }
 
 impl RustMailerStatus {
@@ -30,6 +33,7 @@ impl RustMailerStatus {
                 .convert(Duration::from_millis(RUST_MAIL_CONTEXT.uptime_ms() as u64)),
             timezone: Local::now().offset().to_string(),
             version: env!("CARGO_PKG_VERSION").into(),
+            integrity: StartupIntegrityCheck::report(),
         }
     }
 }
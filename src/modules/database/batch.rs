@@ -0,0 +1,120 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::sync::Arc;
+
+use native_db::{transaction::RwTransaction, Database, ToInput};
+
+use crate::{
+    modules::{
+        database::with_transaction,
+        error::{code::ErrorCode, RustMailerResult},
+    },
+    raise_error,
+};
+
+type Operation = Box<dyn FnOnce(&RwTransaction) -> RustMailerResult<()> + Send + 'static>;
+
+/// Collects dependent writes to several entities of the same database and applies them
+/// in a single transaction, so that either all of them are committed or none is.
+///
+/// Operations run in the order they were added. Lookups passed to `update`/`delete*`
+/// are evaluated inside the transaction and therefore see the effects of earlier
+/// operations of the batch.
+#[derive(Default)]
+pub struct WriteBatch {
+    operations: Vec<Operation>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<T: ToInput + Send + 'static>(mut self, item: T) -> Self {
+        self.operations.push(Box::new(move |rw| {
+            rw.insert(item)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+        }));
+        self
+    }
+
+    /// Same semantics as [`super::update_impl`], as part of the batch.
+    pub fn update<T: ToInput + Clone + Send + 'static>(
+        mut self,
+        current: impl FnOnce(&RwTransaction) -> RustMailerResult<T> + Send + 'static,
+        updated: impl FnOnce(&T) -> RustMailerResult<T> + Send + 'static,
+    ) -> Self {
+        self.operations.push(Box::new(move |rw| {
+            let current_item = current(rw)?;
+            let updated_item = updated(&current_item)?;
+            rw.update(current_item, updated_item)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+        }));
+        self
+    }
+
+    /// Same semantics as [`super::batch_update_impl`], as part of the batch.
+    pub fn update_all<T: ToInput + Clone + Send + 'static>(
+        mut self,
+        filter: impl FnOnce(&RwTransaction) -> RustMailerResult<Vec<T>> + Send + 'static,
+        updated: impl FnOnce(&Vec<T>) -> RustMailerResult<Vec<(T, T)>> + Send + 'static,
+    ) -> Self {
+        self.operations.push(Box::new(move |rw| {
+            let targets = filter(rw)?;
+            for (old, new) in updated(&targets)? {
+                rw.update(old, new)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            }
+            Ok(())
+        }));
+        self
+    }
+
+    /// Same semantics as [`super::delete_impl`], as part of the batch.
+    pub fn delete<T: ToInput + Send + 'static>(
+        mut self,
+        delete: impl FnOnce(&RwTransaction) -> RustMailerResult<T> + Send + 'static,
+    ) -> Self {
+        self.operations.push(Box::new(move |rw| {
+            let to_delete = delete(rw)?;
+            rw.remove(to_delete)
+                .map(|_| ())
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+        }));
+        self
+    }
+
+    /// Same semantics as [`super::batch_delete_impl`], as part of the batch.
+    /// An empty result is not an error.
+    pub fn delete_all<T: ToInput + Send + 'static>(
+        mut self,
+        delete: impl FnOnce(&RwTransaction) -> RustMailerResult<Vec<T>> + Send + 'static,
+    ) -> Self {
+        self.operations.push(Box::new(move |rw| {
+            for item in delete(rw)? {
+                rw.remove(item)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            }
+            Ok(())
+        }));
+        self
+    }
+
+    /// Runs all operations in a single read-write transaction. If any of them fails,
+    /// the transaction is dropped without committing and nothing is written.
+    pub async fn commit(self, database: &Arc<Database<'static>>) -> RustMailerResult<()> {
+        if self.operations.is_empty() {
+            return Ok(());
+        }
+        let operations = self.operations;
+        with_transaction(database, move |rw| {
+            for operation in operations {
+                operation(rw)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use chrono::Local;
+use native_db::{transaction::RTransaction, Builder, Database, Models};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    modules::{
+        context::Initialize,
+        database::{
+            replica::{copy_database, copy_meta_tables, copy_task_tables},
+            META_MODELS,
+        },
+        error::{code::ErrorCode, RustMailerResult},
+        scheduler::nativedb::TASK_MODELS,
+        settings::{
+            cli::SETTINGS,
+            dir::{DATA_DIR_MANAGER, META_FILE, TASK_FILE},
+        },
+    },
+    raise_error, utc_now,
+};
+
+static INTEGRITY_REPORT: OnceLock<IntegrityReport> = OnceLock::new();
+
+type CopyTables = fn(&RTransaction, &Database<'static>) -> RustMailerResult<()>;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Enum)]
+pub enum IntegrityStatus {
+    /// All checked files were readable.
+    #[default]
+    Healthy,
+    /// A corrupted file was replaced by the latest valid snapshot or backup.
+    Recovered,
+    /// A corrupted file was found and not restored. Only the status endpoint is served.
+    SafeMode,
+}
+
+/// Outcome of the startup integrity check, exposed through the status endpoint.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct IntegrityReport {
+    pub status: IntegrityStatus,
+    /// Files verified at startup.
+    pub checked: Vec<String>,
+    /// The file that failed verification.
+    pub corrupted: Option<String>,
+    /// Why the file failed verification.
+    pub error: Option<String>,
+    /// The latest valid snapshot or backup found for the corrupted file.
+    pub recovery_candidate: Option<String>,
+    /// The snapshot or backup that was restored.
+    pub restored_from: Option<String>,
+    /// Where the corrupted file was moved to.
+    pub quarantined_to: Option<String>,
+    /// Time of the check (UNIX epoch milliseconds).
+    pub checked_at: i64,
+}
+
+/// A database file checked at startup, with the files it can be restored from.
+struct CheckTarget {
+    path: PathBuf,
+    models: &'static Models,
+    copy_tables: CopyTables,
+    /// Older snapshots or backups, newest first.
+    candidates: Vec<PathBuf>,
+    /// Where a candidate is copied to when restored.
+    restore_to: PathBuf,
+}
+
+/// Verifies the metadata database (or, in metadata memory mode, the latest metadata and
+/// task snapshots) before any database is opened.
+///
+/// If a file cannot be read, the latest valid snapshot or backup is restored when
+/// `rustmailer_integrity_auto_restore` is set. Otherwise RustMailer boots into safe mode,
+/// serving only the status endpoint so that operators can see what was found.
+pub struct StartupIntegrityCheck;
+
+impl Initialize for StartupIntegrityCheck {
+    async fn initialize() -> RustMailerResult<()> {
+        let report = tokio::task::spawn_blocking(Self::run)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))??;
+        match report.status {
+            IntegrityStatus::Healthy => info!("Startup integrity check passed."),
+            IntegrityStatus::Recovered => warn!(
+                "Startup integrity check recovered {:?} from {:?}; the corrupted file was moved to {:?}.",
+                report.corrupted, report.restored_from, report.quarantined_to
+            ),
+            IntegrityStatus::SafeMode => error!(
+                "Startup integrity check failed for {:?}: {:?}. Starting in safe mode. \
+                Latest valid snapshot or backup: {:?}. Restart with --rustmailer-integrity-auto-restore \
+                to restore it, or restore a backup manually.",
+                report.corrupted, report.error, report.recovery_candidate
+            ),
+        }
+        let _ = INTEGRITY_REPORT.set(report);
+        Ok(())
+    }
+}
+
+impl StartupIntegrityCheck {
+    pub fn report() -> Option<IntegrityReport> {
+        INTEGRITY_REPORT.get().cloned()
+    }
+
+    pub fn is_safe_mode() -> bool {
+        INTEGRITY_REPORT
+            .get()
+            .is_some_and(|r| r.status == IntegrityStatus::SafeMode)
+    }
+
+    fn run() -> RustMailerResult<IntegrityReport> {
+        let mut report = IntegrityReport {
+            checked_at: utc_now!(),
+            ..Default::default()
+        };
+        for target in Self::targets() {
+            report.checked.push(target.path.display().to_string());
+            let Err(e) = verify(&target.path, target.models, target.copy_tables) else {
+                continue;
+            };
+            warn!("{:?} failed verification: {:#?}", target.path, e);
+            report.corrupted = Some(target.path.display().to_string());
+            report.error = Some(e.to_string());
+
+            let candidate = target
+                .candidates
+                .iter()
+                .find(|c| verify(c, target.models, target.copy_tables).is_ok());
+            report.recovery_candidate = candidate.map(|c| c.display().to_string());
+
+            match candidate {
+                Some(candidate) if SETTINGS.rustmailer_integrity_auto_restore => {
+                    let quarantined = restore(&target.path, candidate, &target.restore_to)?;
+                    report.status = IntegrityStatus::Recovered;
+                    report.restored_from = Some(candidate.display().to_string());
+                    report.quarantined_to = Some(quarantined.display().to_string());
+                }
+                _ => {
+                    report.status = IntegrityStatus::SafeMode;
+                    return Ok(report);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    fn targets() -> Vec<CheckTarget> {
+        let mut targets = Vec::new();
+        if SETTINGS.rustmailer_metadata_memory_mode_enabled {
+            for (prefix, models, copy_tables) in [
+                (META_FILE, &*META_MODELS, copy_meta_tables as CopyTables),
+                (TASK_FILE, &*TASK_MODELS, copy_task_tables as CopyTables),
+            ] {
+                let mut snapshots = DATA_DIR_MANAGER.list_snapshots_for(prefix);
+                if snapshots.is_empty() {
+                    continue;
+                }
+                let latest = snapshots.remove(0);
+                // A restored snapshot is written under a new name so that it is the latest.
+                let restore_to = DATA_DIR_MANAGER.root_dir.join(format!(
+                    "{}.{}.snapshot",
+                    prefix,
+                    Local::now().format("%Y-%m-%d-%H-%M")
+                ));
+                targets.push(CheckTarget {
+                    path: latest,
+                    models,
+                    copy_tables,
+                    candidates: snapshots,
+                    restore_to,
+                });
+            }
+        } else if DATA_DIR_MANAGER.meta_db.exists() {
+            targets.push(CheckTarget {
+                path: DATA_DIR_MANAGER.meta_db.clone(),
+                models: &META_MODELS,
+                copy_tables: copy_meta_tables,
+                candidates: list_backups(),
+                restore_to: DATA_DIR_MANAGER.meta_db.clone(),
+            });
+        }
+        targets
+    }
+}
+
+/// Opens the database file and decodes every entity.
+fn verify(path: &Path, models: &'static Models, copy_tables: CopyTables) -> RustMailerResult<()> {
+    let database = Builder::new()
+        .open(models, path)
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    copy_database(&database, models, copy_tables)?;
+    Ok(())
+}
+
+/// Metadata backups written by the backup task, newest first.
+fn list_backups() -> Vec<PathBuf> {
+    let Some(backup_dir) = SETTINGS.rustmailer_backup_dir.as_ref() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(backup_dir) else {
+        return Vec::new();
+    };
+    let suffix = format!("_{}", META_FILE);
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with(&suffix))
+        })
+        .collect();
+    // Backup file names start with a sortable timestamp.
+    backups.sort_by(|a, b| b.file_name().cmp(&a.file_name()));
+    backups
+}
+
+/// Moves the corrupted file aside and copies `candidate` to `restore_to`. Returns the
+/// path the corrupted file was moved to.
+fn restore(corrupted: &Path, candidate: &Path, restore_to: &Path) -> RustMailerResult<PathBuf> {
+    let mut quarantined = corrupted.as_os_str().to_owned();
+    quarantined.push(format!(".corrupt-{}", Local::now().format("%Y%m%d_%H%M%S")));
+    let quarantined = PathBuf::from(quarantined);
+    std::fs::rename(corrupted, &quarantined).map_err(|e| {
+        raise_error!(
+            format!(
+                "Failed to move {:?} to {:?}: {:#?}",
+                corrupted, quarantined, e
+            ),
+            ErrorCode::InternalError
+        )
+    })?;
+    std::fs::copy(candidate, restore_to).map_err(|e| {
+        raise_error!(
+            format!(
+                "Failed to restore {:?} from {:?}: {:#?}",
+                restore_to, candidate, e
+            ),
+            ErrorCode::InternalError
+        )
+    })?;
+    Ok(quarantined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_detects_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let valid = dir.path().join("valid.db");
+        drop(Builder::new().create(&META_MODELS, &valid).unwrap());
+        assert!(verify(&valid, &META_MODELS, copy_meta_tables).is_ok());
+
+        let corrupted = dir.path().join("corrupted.db");
+        std::fs::write(&corrupted, b"definitely not a database").unwrap();
+        assert!(verify(&corrupted, &META_MODELS, copy_meta_tables).is_err());
+
+        let restore_to = dir.path().join("restored.db");
+        let quarantined = restore(&corrupted, &valid, &restore_to).unwrap();
+        assert!(quarantined.exists());
+        assert!(!corrupted.exists());
+        assert!(verify(&restore_to, &META_MODELS, copy_meta_tables).is_ok());
+    }
+}
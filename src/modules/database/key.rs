@@ -0,0 +1,52 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{fmt::Display, ops::Bound};
+
+/// Separator used between the segments of composite string keys.
+const SEPARATOR: char = '_';
+
+pub type KeyRange = (Bound<String>, Bound<String>);
+
+/// Builder for composite string keys such as `"{created_at}_{id}"` or
+/// `"{task_key}_{status}"`, and for range bounds over them.
+#[derive(Clone, Debug, Default)]
+pub struct CompositeKey {
+    key: String,
+}
+
+impl CompositeKey {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn segment(mut self, value: impl Display) -> Self {
+        if !self.key.is_empty() {
+            self.key.push(SEPARATOR);
+        }
+        self.key.push_str(&value.to_string());
+        self
+    }
+
+    pub fn build(self) -> String {
+        self.key
+    }
+}
+
+/// Range over keys whose first segment is a millisecond timestamp (e.g. the
+/// `"{created_at}_{id}"` primary keys), selecting `from <= timestamp < to`.
+///
+/// String ordering matches numeric ordering as long as all timestamps have the same
+/// number of digits, which holds for millisecond timestamps between 2001 and 2286.
+pub fn timestamp_key_range(from: Option<i64>, to: Option<i64>) -> KeyRange {
+    let start = match from {
+        Some(from) => Bound::Included(from.to_string()),
+        None => Bound::Unbounded,
+    };
+    let end = match to {
+        Some(to) => Bound::Excluded(to.to_string()),
+        None => Bound::Unbounded,
+    };
+    (start, end)
+}
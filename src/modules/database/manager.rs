@@ -2,11 +2,14 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use crate::modules::account::migration::AccountModel;
-use crate::modules::cache::imap::migration::EmailEnvelopeV3;
 use crate::modules::cache::imap::ENVELOPE_MODELS;
+use crate::modules::database::migration::{
+    SchemaVersion, ENVELOPE_MIGRATIONS, META_MIGRATIONS, TASK_MIGRATIONS,
+};
 use crate::modules::context::Initialize;
+use crate::modules::database::snapshot::journal::TaskJournal;
 use crate::modules::error::{code::ErrorCode, RustMailerError};
+use crate::modules::overview::memory::record_metadata_size;
 use crate::modules::scheduler::nativedb::TaskMetaEntity;
 use crate::modules::settings::cli::SETTINGS;
 use crate::modules::settings::dir::{DATA_DIR_MANAGER, META_FILE, TASK_FILE};
@@ -20,18 +23,8 @@ use tracing::{info, warn};
 
 pub static DB_MANAGER: LazyLock<DatabaseManager> = LazyLock::new(DatabaseManager::new);
 
-use crate::modules::{
-    account::status::AccountRunningState,
-    autoconfig::CachedMailSettings,
-    cache::disk::CacheItem,
-    database::{batch_insert_impl, list_all_impl},
-    hook::entity::EventHooks,
-    license::License,
-    oauth2::{entity::OAuth2, pending::OAuth2PendingEntity, token::OAuth2AccessToken},
-    overview::metrics::DailyMetrics,
-    settings::{proxy::Proxy, system::SystemSetting},
-    smtp::{mta::entity::Mta, template::entity::EmailTemplate},
-    token::AccessToken,
+use crate::modules::database::{
+    batch_insert_impl, for_each_legacy_meta_table, for_each_meta_table, list_all_impl,
 };
 
 /// Metadata database instance
@@ -87,13 +80,7 @@ impl DatabaseManager {
             .create(&META_MODELS, DATA_DIR_MANAGER.meta_db.clone())
             .map_err(Self::handle_database_error)?;
 
-        let rw = database
-            .rw_transaction()
-            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
-        rw.migrate::<AccountModel>()
-            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
-        rw.commit()
-            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        META_MIGRATIONS.apply(&database, SETTINGS.rustmailer_migration_dry_run)?;
 
         database
             .compact()
@@ -106,6 +93,9 @@ impl DatabaseManager {
         let snapshot = match lastest_snapshot {
             Some(snapshot) => {
                 info!("Found existing meta snapshot: {:?}", snapshot);
+                if let Ok(metadata) = std::fs::metadata(&snapshot) {
+                    record_metadata_size(metadata.len());
+                }
                 snapshot
             }
             None => {
@@ -120,12 +110,24 @@ impl DatabaseManager {
                 .create(&META_MODELS, snapshot)
                 .map_err(Self::handle_database_error)?,
         );
+        Self::copy_meta_tables(&database, &self.meta_db).await?;
+        // The snapshot may have been written by an older (or newer) release.
+        META_MIGRATIONS.apply(&self.meta_db, SETTINGS.rustmailer_migration_dry_run)?;
+
+        Ok(())
+    }
 
+    /// Copies every metadata table from `source` into `target`, including the tables of
+    /// earlier model versions, whose rows are only converted once the migrations run.
+    pub(crate) async fn copy_meta_tables(
+        source: &Arc<Database<'static>>,
+        target: &Arc<Database<'static>>,
+    ) -> RustMailerResult<()> {
         let mut join_set = tokio::task::JoinSet::new();
         macro_rules! spawn_migration_task {
             ($table:ty) => {
-                let db = Arc::clone(&database);
-                let mem_db = Arc::clone(&self.meta_db);
+                let db = Arc::clone(source);
+                let mem_db = Arc::clone(target);
                 join_set.spawn(async move {
                     let data = list_all_impl::<$table>(&db).await?;
                     batch_insert_impl(&mem_db, data).await
@@ -133,21 +135,8 @@ impl DatabaseManager {
             };
         }
 
-        spawn_migration_task!(AccessToken);
-        spawn_migration_task!(SystemSetting);
-        spawn_migration_task!(License);
-        spawn_migration_task!(CachedMailSettings);
-        spawn_migration_task!(AccountModel);
-        spawn_migration_task!(EmailTemplate);
-        spawn_migration_task!(Mta);
-        spawn_migration_task!(OAuth2);
-        spawn_migration_task!(OAuth2PendingEntity);
-        spawn_migration_task!(OAuth2AccessToken);
-        spawn_migration_task!(EventHooks);
-        spawn_migration_task!(CacheItem);
-        spawn_migration_task!(AccountRunningState);
-        spawn_migration_task!(DailyMetrics);
-        spawn_migration_task!(Proxy);
+        for_each_legacy_meta_table!(spawn_migration_task);
+        for_each_meta_table!(spawn_migration_task);
 
         while let Some(res) = join_set.join_next().await {
             match res {
@@ -160,7 +149,6 @@ impl DatabaseManager {
                 }
             }
         }
-
         Ok(())
     }
 
@@ -179,6 +167,7 @@ impl DatabaseManager {
             ) //default 128MB
             .create(&TASK_MODELS, DATA_DIR_MANAGER.task_db.clone())
             .map_err(Self::handle_database_error)?;
+        TASK_MIGRATIONS.apply(&database, SETTINGS.rustmailer_migration_dry_run)?;
         database
             .compact()
             .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
@@ -195,7 +184,8 @@ impl DatabaseManager {
             None => {
                 warn!("No task snapshot found in the data directory");
                 info!("Creating new task snapshot instance");
-                return Ok(());
+                // Changes made before the first snapshot are only in the journal.
+                return TaskJournal::replay(&self.tasks_db).await;
             }
         };
 
@@ -207,6 +197,10 @@ impl DatabaseManager {
 
         let data = list_all_impl::<TaskMetaEntity>(&database).await?;
         batch_insert_impl(&self.tasks_db, data).await?;
+        let data = list_all_impl::<SchemaVersion>(&database).await?;
+        batch_insert_impl(&self.tasks_db, data).await?;
+        TaskJournal::replay(&self.tasks_db).await?;
+        TASK_MIGRATIONS.apply(&self.tasks_db, SETTINGS.rustmailer_migration_dry_run)?;
 
         Ok(())
     }
@@ -227,13 +221,7 @@ impl DatabaseManager {
             .create(&ENVELOPE_MODELS, DATA_DIR_MANAGER.envelope_db.clone())
             .map_err(Self::handle_database_error)?;
 
-        let rw = database
-            .rw_transaction()
-            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
-        rw.migrate::<EmailEnvelopeV3>()
-            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
-        rw.commit()
-            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        ENVELOPE_MIGRATIONS.apply(&database, SETTINGS.rustmailer_migration_dry_run)?;
 
         database
             .compact()
@@ -274,6 +262,11 @@ impl Initialize for DatabaseManager {
             DB_MANAGER.load_meta_snapshot().await?;
             DB_MANAGER.load_task_snapshot().await?;
         }
+        if SETTINGS.rustmailer_migration_dry_run {
+            LazyLock::force(&DB_MANAGER);
+            info!("Migration dry run completed, no changes were written. Exiting.");
+            std::process::exit(0);
+        }
         Ok(())
     }
 }
@@ -0,0 +1,425 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Instant;
+
+use native_db::{transaction::RwTransaction, *};
+use native_model::{native_model, Model};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    modules::{
+        account::{migration::AccountModel, status::AccountRunningState},
+        cache::{disk::CacheItem, imap::migration::EmailEnvelopeV7},
+        error::{code::ErrorCode, RustMailerResult},
+        hook::entity::EventHooks,
+        overview::{metrics::DailyMetrics, rollup::MetricRollup},
+        smtp::{mta::entity::Mta, template::entity::EmailTemplate},
+        token::AccessToken,
+    },
+    raise_error, rustmailer_version, utc_now,
+};
+
+/// Schema version of a database file, stored inside the database itself.
+///
+/// The same model is registered in every database (metadata, tasks and envelopes); each
+/// database holds a single row keyed by its name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[native_model(id = 16, version = 1)]
+#[native_db]
+pub struct SchemaVersion {
+    #[primary_key]
+    pub database: String,
+    /// Version of the last migration applied to this database.
+    pub version: u32,
+    /// Version of RustMailer that last migrated this database.
+    pub rustmailer_version: String,
+    pub updated_at: i64,
+}
+
+/// A single ordered schema transform. Transforms run inside the migration transaction and
+/// must be idempotent, since databases created before the registry existed replay them.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub transform: fn(&RwTransaction) -> RustMailerResult<()>,
+}
+
+/// Ordered list of migrations for one database.
+pub struct MigrationRegistry {
+    pub database: &'static str,
+    pub migrations: &'static [Migration],
+}
+
+pub static META_MIGRATIONS: MigrationRegistry = MigrationRegistry {
+    database: "metadata",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "Upgrade accounts to the latest account model",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 2,
+            description: "Add storage tier to disk cache items",
+            transform: |rw| {
+                rw.migrate::<CacheItem>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 3,
+            description: "Add namespace to disk cache items",
+            transform: |rw| {
+                rw.migrate::<CacheItem>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 4,
+            description: "Add account to daily metrics and metric rollups",
+            transform: |rw| {
+                rw.migrate::<DailyMetrics>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                rw.migrate::<MetricRollup>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 5,
+            description: "Add loop protection settings to accounts",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 6,
+            description: "Add Message-ID domain to accounts",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 7,
+            description: "Add tags to accounts",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 8,
+            description: "Add account tag filters to event hooks",
+            transform: |rw| {
+                rw.migrate::<EventHooks>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 9,
+            description: "Add sent copy policy to accounts",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 10,
+            description: "Add attachment policy to accounts",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 11,
+            description: "Add signing secrets to event hooks",
+            transform: |rw| {
+                rw.migrate::<EventHooks>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 12,
+            description: "Add NATS subject templates to event hooks",
+            transform: |rw| {
+                rw.migrate::<EventHooks>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 13,
+            description: "Add JMAP configuration to accounts",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 14,
+            description: "Add send rate limits to accounts and MTAs",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                rw.migrate::<Mta>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 15,
+            description: "Classify provider failures in account error logs",
+            transform: |rw| {
+                rw.migrate::<AccountRunningState>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 16,
+            description: "Add sync policies to accounts",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 17,
+            description: "Add Kafka destinations to event hooks",
+            transform: |rw| {
+                rw.migrate::<EventHooks>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 18,
+            description: "Add Redis Streams destinations to event hooks",
+            transform: |rw| {
+                rw.migrate::<EventHooks>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 19,
+            description: "Add per-event-type VRL scripts to event hooks",
+            transform: |rw| {
+                rw.migrate::<EventHooks>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 20,
+            description:
+                "Add workspaces to accounts, templates, MTAs, event hooks and access tokens",
+            transform: |rw| {
+                rw.migrate::<AccountModel>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                rw.migrate::<EmailTemplate>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                rw.migrate::<Mta>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                rw.migrate::<EventHooks>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                rw.migrate::<AccessToken>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 21,
+            description: "Add expiry and rotation to access tokens",
+            transform: |rw| {
+                rw.migrate::<AccessToken>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+    ],
+};
+
+pub static TASK_MIGRATIONS: MigrationRegistry = MigrationRegistry {
+    database: "tasks",
+    migrations: &[],
+};
+
+pub static ENVELOPE_MIGRATIONS: MigrationRegistry = MigrationRegistry {
+    database: "envelope",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "Upgrade envelopes to the latest envelope model",
+            transform: |rw| {
+                rw.migrate::<EmailEnvelopeV7>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 2,
+            description: "Add importance to envelopes",
+            transform: |rw| {
+                rw.migrate::<EmailEnvelopeV7>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 3,
+            description: "Add received chain to envelopes",
+            transform: |rw| {
+                rw.migrate::<EmailEnvelopeV7>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 4,
+            description: "Add authentication results to envelopes",
+            transform: |rw| {
+                rw.migrate::<EmailEnvelopeV7>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 5,
+            description: "Index envelopes by date and size for sorted listings",
+            transform: |rw| {
+                rw.migrate::<EmailEnvelopeV7>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+        Migration {
+            version: 6,
+            description: "Keep the raw headers of partially parsed envelopes",
+            transform: |rw| {
+                rw.migrate::<EmailEnvelopeV7>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+            },
+        },
+    ],
+};
+
+impl MigrationRegistry {
+    /// The schema version this build of RustMailer writes.
+    pub fn latest_version(&self) -> u32 {
+        self.migrations.iter().map(|m| m.version).max().unwrap_or(0)
+    }
+
+    /// Refuses to continue if the database was written by a newer RustMailer, whose
+    /// schema this build cannot read safely.
+    pub fn check_compatible(&self, stored: u32) -> RustMailerResult<()> {
+        let latest = self.latest_version();
+        if stored > latest {
+            return Err(raise_error!(
+                format!(
+                    "The {} database has schema version {}, but this build of RustMailer only supports up to version {}. \
+                    It was written by a newer release; upgrade RustMailer or restore a backup taken before the upgrade.",
+                    self.database, stored, latest
+                ),
+                ErrorCode::InternalError
+            ));
+        }
+        Ok(())
+    }
+
+    /// Migrations newer than `stored`, in ascending version order.
+    pub fn pending(&self, stored: u32) -> Vec<&Migration> {
+        let mut pending: Vec<&Migration> = self
+            .migrations
+            .iter()
+            .filter(|m| m.version > stored)
+            .collect();
+        pending.sort_by_key(|m| m.version);
+        pending
+    }
+
+    /// Applies all pending migrations in one transaction and records the new schema
+    /// version. With `dry_run`, the transforms are executed but the transaction is rolled
+    /// back, so nothing is written.
+    pub fn apply(&self, database: &Database<'static>, dry_run: bool) -> RustMailerResult<()> {
+        let rw = database
+            .rw_transaction()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let current: Option<SchemaVersion> = rw
+            .get()
+            .primary(self.database.to_string())
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let stored = current.as_ref().map_or(0, |c| c.version);
+        self.check_compatible(stored)?;
+
+        let pending = self.pending(stored);
+        let latest = self.latest_version();
+        if pending.is_empty() && current.is_some() {
+            info!(
+                "[{}] Database schema is up to date (version {}).",
+                self.database, stored
+            );
+            return Ok(());
+        }
+
+        let total = pending.len();
+        info!(
+            "[{}] Migrating database schema from version {} to {} ({} pending migrations{}).",
+            self.database,
+            stored,
+            latest,
+            total,
+            if dry_run { ", dry run" } else { "" }
+        );
+        let start = Instant::now();
+        for (index, migration) in pending.into_iter().enumerate() {
+            let step_start = Instant::now();
+            info!(
+                "[{}] ({}/{}) Applying migration {}: {}",
+                self.database,
+                index + 1,
+                total,
+                migration.version,
+                migration.description
+            );
+            (migration.transform)(&rw).map_err(|e| {
+                warn!(
+                    "[{}] Migration {} failed, no changes were written: {:#?}",
+                    self.database, migration.version, e
+                );
+                e
+            })?;
+            info!(
+                "[{}] ({}/{}) Migration {} finished in {} ms.",
+                self.database,
+                index + 1,
+                total,
+                migration.version,
+                step_start.elapsed().as_millis()
+            );
+        }
+
+        rw.upsert(SchemaVersion {
+            database: self.database.to_string(),
+            version: latest,
+            rustmailer_version: rustmailer_version!().to_string(),
+            updated_at: utc_now!(),
+        })
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+
+        if dry_run {
+            // Dropping the transaction without committing discards every change.
+            drop(rw);
+            info!(
+                "[{}] Dry run: {} migrations would be applied, no changes were written.",
+                self.database, total
+            );
+            return Ok(());
+        }
+
+        rw.commit()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        info!(
+            "[{}] Database schema migrated to version {} in {} ms.",
+            self.database,
+            latest,
+            start.elapsed().as_millis()
+        );
+        Ok(())
+    }
+}
@@ -2,37 +2,105 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use crate::modules::account::migration::{AccountV2, AccountV3};
-use crate::modules::account::status::AccountRunningState;
-use crate::modules::autoconfig::CachedMailSettings;
-use crate::modules::cache::disk::CacheItem;
 use crate::modules::error::RustMailerResult;
-use crate::modules::hook::entity::EventHooks;
-use crate::modules::license::License;
-use crate::modules::oauth2::entity::OAuth2;
-use crate::modules::oauth2::pending::OAuth2PendingEntity;
-use crate::modules::oauth2::token::OAuth2AccessToken;
-use crate::modules::settings::proxy::Proxy;
-use crate::modules::settings::system::SystemSetting;
-use crate::modules::smtp::mta::entity::Mta;
-use crate::modules::smtp::template::entity::EmailTemplate;
-use crate::modules::token::AccessToken;
-use crate::modules::{account::entity::Account, overview::metrics::DailyMetrics};
-use crate::raise_error;
+use crate::{raise_error, utc_now};
 use db_type::{KeyOptions, ToKeyDefinition};
 use itertools::Itertools;
 use native_db::*;
 use serde::Serialize;
+use std::ops::RangeBounds;
 use std::sync::{Arc, LazyLock};
 use transaction::RwTransaction;
 
 use super::error::code::ErrorCode;
 pub mod backup;
+pub mod batch;
+pub mod integrity;
+pub mod key;
 pub mod manager;
+pub mod migration;
+pub mod replica;
 pub mod snapshot;
 #[cfg(test)]
 mod tests;
 
+/// Invokes `$apply!(Model)` with the current model of every metadata table. Loading the
+/// snapshot, copying the read replica and [`META_MODELS`] all walk this list, so a new
+/// table only needs to be added here.
+macro_rules! for_each_meta_table {
+    ($apply:ident) => {
+        $apply!(crate::modules::token::AccessToken);
+        $apply!(crate::modules::settings::system::SystemSetting);
+        $apply!(crate::modules::license::License);
+        $apply!(crate::modules::autoconfig::CachedMailSettings);
+        $apply!(crate::modules::account::migration::AccountModel);
+        $apply!(crate::modules::smtp::template::entity::EmailTemplate);
+        $apply!(crate::modules::smtp::mta::entity::Mta);
+        $apply!(crate::modules::oauth2::entity::OAuth2);
+        $apply!(crate::modules::oauth2::pending::OAuth2PendingEntity);
+        $apply!(crate::modules::oauth2::token::OAuth2AccessToken);
+        $apply!(crate::modules::hook::entity::EventHooks);
+        $apply!(crate::modules::cache::disk::CacheItem);
+        $apply!(crate::modules::account::status::AccountRunningState);
+        $apply!(crate::modules::overview::metrics::DailyMetrics);
+        $apply!(crate::modules::overview::rollup::MetricRollup);
+        $apply!(crate::modules::settings::proxy::Proxy);
+        $apply!(crate::modules::database::migration::SchemaVersion);
+        $apply!(crate::modules::audit::AuditEntry);
+        $apply!(crate::modules::rest::spec::ApiSpecSnapshot);
+        $apply!(crate::modules::smtp::campaign::entity::Campaign);
+        $apply!(crate::modules::tasks::dead_letter::DeadLetter);
+        $apply!(crate::modules::retention::entity::CleanupRule);
+        $apply!(crate::modules::account::maintenance::MaintenanceWindow);
+        $apply!(crate::modules::smtp::suppression::SuppressedAddress);
+        $apply!(crate::modules::smtp::template::partial::TemplatePartial);
+        $apply!(crate::modules::scheduler::recurring::entity::RecurringSend);
+        $apply!(crate::modules::message::search::saved::SavedSearch);
+        $apply!(crate::modules::account::profile::AccountProfile);
+        $apply!(crate::modules::smtp::timeline::DeliveryEvent);
+        $apply!(crate::modules::hook::delivery::HookDelivery);
+        $apply!(crate::modules::workspace::Workspace);
+    };
+}
+pub(crate) use for_each_meta_table;
+
+/// Invokes `$apply!(Model)` with every earlier version of a metadata model that is still
+/// registered so that old databases can be migrated. Restoring a snapshot into memory
+/// copies these tables as well, since their rows are only converted by the migrations.
+macro_rules! for_each_legacy_meta_table {
+    ($apply:ident) => {
+        $apply!(crate::modules::token::AccessTokenV1);
+        $apply!(crate::modules::token::AccessTokenV2);
+        $apply!(crate::modules::account::entity::Account);
+        $apply!(crate::modules::account::migration::AccountV2);
+        $apply!(crate::modules::account::migration::AccountV3);
+        $apply!(crate::modules::account::migration::AccountV4);
+        $apply!(crate::modules::account::migration::AccountV5);
+        $apply!(crate::modules::account::migration::AccountV6);
+        $apply!(crate::modules::account::migration::AccountV7);
+        $apply!(crate::modules::account::migration::AccountV8);
+        $apply!(crate::modules::account::migration::AccountV9);
+        $apply!(crate::modules::account::migration::AccountV10);
+        $apply!(crate::modules::account::migration::AccountV11);
+        $apply!(crate::modules::smtp::template::entity::EmailTemplateV1);
+        $apply!(crate::modules::smtp::mta::entity::MtaV1);
+        $apply!(crate::modules::smtp::mta::entity::MtaV2);
+        $apply!(crate::modules::hook::entity::EventHooksV1);
+        $apply!(crate::modules::hook::entity::EventHooksV2);
+        $apply!(crate::modules::hook::entity::EventHooksV3);
+        $apply!(crate::modules::hook::entity::EventHooksV4);
+        $apply!(crate::modules::hook::entity::EventHooksV5);
+        $apply!(crate::modules::hook::entity::EventHooksV6);
+        $apply!(crate::modules::hook::entity::EventHooksV7);
+        $apply!(crate::modules::cache::disk::CacheItemV1);
+        $apply!(crate::modules::cache::disk::CacheItemV2);
+        $apply!(crate::modules::account::status::AccountRunningStateV1);
+        $apply!(crate::modules::overview::metrics::DailyMetricsV1);
+        $apply!(crate::modules::overview::rollup::MetricRollupV1);
+    };
+}
+pub(crate) use for_each_legacy_meta_table;
+
 pub static META_MODELS: LazyLock<Models> = LazyLock::new(|| {
     let mut adapter = ModelsAdapter::new();
     adapter.register_metadata_models();
@@ -55,23 +123,13 @@ impl ModelsAdapter {
     }
 
     pub fn register_metadata_models(&mut self) {
-        self.register_model::<AccessToken>();
-        self.register_model::<SystemSetting>();
-        self.register_model::<License>();
-        self.register_model::<CachedMailSettings>();
-        self.register_model::<Account>();
-        self.register_model::<AccountV2>();
-        self.register_model::<AccountV3>();
-        self.register_model::<EmailTemplate>();
-        self.register_model::<Mta>();
-        self.register_model::<OAuth2>();
-        self.register_model::<OAuth2PendingEntity>();
-        self.register_model::<OAuth2AccessToken>();
-        self.register_model::<EventHooks>();
-        self.register_model::<CacheItem>();
-        self.register_model::<AccountRunningState>();
-        self.register_model::<DailyMetrics>();
-        self.register_model::<Proxy>();
+        macro_rules! register {
+            ($model:ty) => {
+                self.register_model::<$model>();
+            };
+        }
+        for_each_legacy_meta_table!(register);
+        for_each_meta_table!(register);
     }
 }
 
@@ -204,6 +262,52 @@ pub async fn update_impl<T: ToInput + Clone + std::fmt::Debug + Send + 'static>(
     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
 }
 
+/// Entities whose updates can be guarded by an optimistic concurrency check.
+pub trait Versioned {
+    /// The current version of the entity.
+    fn version(&self) -> i64;
+    /// Replaces the version of the entity.
+    fn set_version(&mut self, version: i64);
+}
+
+/// Same as [`update_impl`], but rejects the update with [`ErrorCode::VersionConflict`] if
+/// `expected_version` is set and no longer matches the stored entity. The version of the
+/// updated entity is always bumped, so that it strictly increases with every write.
+pub async fn versioned_update_impl<
+    T: Versioned + ToInput + Clone + std::fmt::Debug + Send + 'static,
+>(
+    database: &Arc<Database<'static>>,
+    current: impl FnOnce(&RwTransaction) -> RustMailerResult<T> + Send + 'static,
+    updated: impl FnOnce(&T) -> RustMailerResult<T> + Send + 'static,
+    expected_version: Option<i64>,
+) -> RustMailerResult<T> {
+    update_impl(
+        database,
+        move |rw| {
+            let item = current(rw)?;
+            if let Some(expected) = expected_version {
+                if item.version() != expected {
+                    return Err(raise_error!(
+                        format!(
+                            "The entity was modified concurrently: expected version {}, current version {}. Reload it and retry.",
+                            expected,
+                            item.version()
+                        ),
+                        ErrorCode::VersionConflict
+                    ));
+                }
+            }
+            Ok(item)
+        },
+        move |current| {
+            let mut item = updated(current)?;
+            item.set_version(utc_now!().max(current.version() + 1));
+            Ok(item)
+        },
+    )
+    .await
+}
+
 pub async fn batch_update_impl<T: ToInput + Clone + std::fmt::Debug + Send + 'static>(
     database: &Arc<Database<'static>>,
     filter: impl FnOnce(&RwTransaction) -> RustMailerResult<Vec<T>> + Send + 'static,
@@ -444,6 +548,175 @@ pub async fn filter_by_secondary_key_impl<T: ToInput + Clone + Send + 'static>(
     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
 }
 
+/// Like [`filter_by_secondary_key_impl`], but only keeps the entities matching `predicate`,
+/// so that scanning a large key does not load every entity into memory.
+pub async fn find_by_secondary_key_impl<T, F>(
+    database: &Arc<Database<'static>>,
+    key_def: impl ToKeyDefinition<KeyOptions> + Send + 'static,
+    start_with: impl ToKey + Send + 'static,
+    predicate: F,
+) -> RustMailerResult<Vec<T>>
+where
+    T: ToInput + Clone + Send + 'static,
+    F: Fn(&T) -> bool + Send + 'static,
+{
+    let db = database.clone();
+    tokio::task::spawn_blocking(move || {
+        let r_transaction = db
+            .r_transaction()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let mut entities = Vec::new();
+        for entity in r_transaction
+            .scan()
+            .secondary::<T>(key_def)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .start_with(start_with)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+        {
+            let entity =
+                entity.map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            if predicate(&entity) {
+                entities.push(entity);
+            }
+        }
+        Ok(entities)
+    })
+    .await
+    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+}
+
+/// Returns the entities whose secondary key falls within `range`, in key order.
+pub async fn range_by_secondary_key_impl<T, K>(
+    database: &Arc<Database<'static>>,
+    key_def: impl ToKeyDefinition<KeyOptions> + Send + 'static,
+    range: impl RangeBounds<K> + Send + 'static,
+) -> RustMailerResult<Vec<T>>
+where
+    T: ToInput + Clone + Send + 'static,
+    K: ToKey + Send + 'static,
+{
+    let db = database.clone();
+    tokio::task::spawn_blocking(move || {
+        let r_transaction = db
+            .r_transaction()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let entities: Vec<T> = r_transaction
+            .scan()
+            .secondary(key_def)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .range(range)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .try_collect()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        Ok(entities)
+    })
+    .await
+    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+}
+
+/// Scans the entities of a secondary key in primary key order, stopping at the first one
+/// failing `condition`, and returns at most `limit` of those matching `predicate`. With a
+/// primary key starting with a timestamp, this only reads the entities of the key older
+/// than a cutoff.
+pub async fn scan_secondary_key_while_impl<T, C, F>(
+    database: &Arc<Database<'static>>,
+    key_def: impl ToKeyDefinition<KeyOptions> + Send + 'static,
+    start_with: impl ToKey + Send + 'static,
+    condition: C,
+    predicate: F,
+    limit: usize,
+) -> RustMailerResult<Vec<T>>
+where
+    T: ToInput + Clone + Send + 'static,
+    C: Fn(&T) -> bool + Send + 'static,
+    F: Fn(&T) -> bool + Send + 'static,
+{
+    let db = database.clone();
+    tokio::task::spawn_blocking(move || {
+        let r_transaction = db
+            .r_transaction()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let mut entities = Vec::new();
+        for entity in r_transaction
+            .scan()
+            .secondary(key_def)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .start_with(start_with)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+        {
+            let entity: T =
+                entity.map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            if !condition(&entity) || entities.len() >= limit {
+                break;
+            }
+            if predicate(&entity) {
+                entities.push(entity);
+            }
+        }
+        Ok(entities)
+    })
+    .await
+    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+}
+
+/// Returns the entities whose primary key falls within `range`, in key order.
+pub async fn range_by_primary_key_impl<T, K>(
+    database: &Arc<Database<'static>>,
+    range: impl RangeBounds<K> + Send + 'static,
+) -> RustMailerResult<Vec<T>>
+where
+    T: ToInput + Clone + Send + 'static,
+    K: ToKey + Send + 'static,
+{
+    let db = database.clone();
+    tokio::task::spawn_blocking(move || {
+        let r_transaction = db
+            .r_transaction()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let entities: Vec<T> = r_transaction
+            .scan()
+            .primary()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .range(range)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .try_collect()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        Ok(entities)
+    })
+    .await
+    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+}
+
+/// Returns at most `limit` entities whose primary key falls within `range`, in key order.
+pub async fn range_by_primary_key_limited_impl<T, K>(
+    database: &Arc<Database<'static>>,
+    range: impl RangeBounds<K> + Send + 'static,
+    limit: usize,
+) -> RustMailerResult<Vec<T>>
+where
+    T: ToInput + Clone + Send + 'static,
+    K: ToKey + Send + 'static,
+{
+    let db = database.clone();
+    tokio::task::spawn_blocking(move || {
+        let r_transaction = db
+            .r_transaction()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let entities: Vec<T> = r_transaction
+            .scan()
+            .primary()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .range(range)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+            .take(limit)
+            .try_collect()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        Ok(entities)
+    })
+    .await
+    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+}
+
 pub async fn count_by_unique_secondary_key_impl<T: ToInput + Clone + Send + 'static>(
     database: &Arc<Database<'static>>,
     key_def: impl ToKeyDefinition<KeyOptions> + Send + 'static,
@@ -0,0 +1,201 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    sync::{Arc, LazyLock, RwLock},
+    time::{Duration, Instant},
+};
+
+use itertools::Itertools;
+use native_db::{transaction::RTransaction, Builder, Database, Models, ToInput};
+use tracing::{debug, info};
+
+use crate::{
+    modules::{
+        account::migration::AccountModel,
+        context::RustMailTask,
+        database::{
+            for_each_meta_table, manager::DB_MANAGER, migration::SchemaVersion, META_MODELS,
+        },
+        error::{code::ErrorCode, RustMailerError, RustMailerResult},
+        overview::{metrics::DailyMetrics, rollup::MetricRollup},
+        scheduler::{
+            nativedb::{TaskMetaEntity, TASK_MODELS},
+            periodic::PeriodicTask,
+        },
+        settings::cli::SETTINGS,
+    },
+    raise_error, utc_now,
+};
+
+pub static READ_REPLICA: LazyLock<ReadReplica> = LazyLock::new(ReadReplica::default);
+
+struct ReplicaState {
+    meta_db: Arc<Database<'static>>,
+    tasks_db: Arc<Database<'static>>,
+    refreshed_at: i64,
+}
+
+/// Point-in-time, in-memory copies of the task database and of the metadata tables used to
+/// serve expensive read-only queries (overview, analytics, dashboards).
+///
+/// Each copy is taken from a single read transaction, so it is consistent across tables.
+/// Queries against the replica never take locks on the primary databases, at the cost of
+/// being up to `rustmailer_read_replica_refresh_secs` stale. When the replica is disabled
+/// or has not been built yet, reads fall back to the primary databases.
+#[derive(Default)]
+pub struct ReadReplica {
+    state: RwLock<Option<ReplicaState>>,
+}
+
+impl ReadReplica {
+    pub fn meta_db(&self) -> Arc<Database<'static>> {
+        match self.state.read().unwrap().as_ref() {
+            Some(state) => state.meta_db.clone(),
+            None => DB_MANAGER.meta_db().clone(),
+        }
+    }
+
+    /// The metadata and task databases together with the time (UNIX epoch milliseconds) of
+    /// the copy they come from, `None` if reads are served by the primary databases. Read at
+    /// once, so that the three always match.
+    pub fn snapshot(&self) -> (Arc<Database<'static>>, Arc<Database<'static>>, Option<i64>) {
+        match self.state.read().unwrap().as_ref() {
+            Some(state) => (
+                state.meta_db.clone(),
+                state.tasks_db.clone(),
+                Some(state.refreshed_at),
+            ),
+            None => (
+                DB_MANAGER.meta_db().clone(),
+                DB_MANAGER.tasks_db().clone(),
+                None,
+            ),
+        }
+    }
+
+    /// Rebuilds both copies and swaps them in. Readers holding the previous handles keep
+    /// using them until they are done.
+    pub async fn refresh(&self) -> RustMailerResult<()> {
+        let start = Instant::now();
+        let (meta_db, tasks_db) = tokio::task::spawn_blocking(|| {
+            let meta_db =
+                copy_database(DB_MANAGER.meta_db(), &META_MODELS, copy_replica_meta_tables)?;
+            let tasks_db = copy_database(DB_MANAGER.tasks_db(), &TASK_MODELS, copy_task_tables)?;
+            Ok::<_, RustMailerError>((meta_db, tasks_db))
+        })
+        .await
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))??;
+
+        *self.state.write().unwrap() = Some(ReplicaState {
+            meta_db: Arc::new(meta_db),
+            tasks_db: Arc::new(tasks_db),
+            refreshed_at: utc_now!(),
+        });
+        debug!("Read replica refreshed in {} ms.", start.elapsed().as_millis());
+        Ok(())
+    }
+}
+
+/// Copies every table of `source` into a new in-memory database. Since every entity is
+/// decoded, this also verifies that `source` is readable.
+pub fn copy_database(
+    source: &Database<'static>,
+    models: &'static Models,
+    copy_tables: fn(&RTransaction, &Database<'static>) -> RustMailerResult<()>,
+) -> RustMailerResult<Database<'static>> {
+    let target = Builder::new()
+        .create_in_memory(models)
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    let r = source
+        .r_transaction()
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    copy_tables(&r, &target)?;
+    Ok(target)
+}
+
+fn copy_table<T: ToInput>(r: &RTransaction, target: &Database<'static>) -> RustMailerResult<()> {
+    copy_table_with::<T>(r, target, |entity| entity)
+}
+
+fn copy_table_with<T: ToInput>(
+    r: &RTransaction,
+    target: &Database<'static>,
+    map: impl Fn(T) -> T,
+) -> RustMailerResult<()> {
+    let entities: Vec<T> = r
+        .scan()
+        .primary()
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+        .all()
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+        .try_collect()
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    let rw = target
+        .rw_transaction()
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    for entity in entities {
+        rw.insert(map(entity))
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    }
+    rw.commit()
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+}
+
+pub fn copy_meta_tables(r: &RTransaction, target: &Database<'static>) -> RustMailerResult<()> {
+    macro_rules! copy {
+        ($table:ty) => {
+            copy_table::<$table>(r, target)?;
+        };
+    }
+    for_each_meta_table!(copy);
+    Ok(())
+}
+
+/// Copies the metadata tables read by analytics queries. Everything else, in particular
+/// access tokens, OAuth2 secrets and system settings, stays out of the replica, and account
+/// credentials are blanked.
+pub fn copy_replica_meta_tables(
+    r: &RTransaction,
+    target: &Database<'static>,
+) -> RustMailerResult<()> {
+    copy_table_with::<AccountModel>(r, target, |mut account| {
+        if let Some(imap) = account.imap.as_mut() {
+            imap.auth.password = None;
+        }
+        if let Some(smtp) = account.smtp.as_mut() {
+            smtp.auth.password = None;
+        }
+        if let Some(jmap) = account.jmap.as_mut() {
+            jmap.auth.password = None;
+        }
+        account
+    })?;
+    copy_table::<DailyMetrics>(r, target)?;
+    copy_table::<MetricRollup>(r, target)?;
+    copy_table::<SchemaVersion>(r, target)
+}
+
+pub fn copy_task_tables(r: &RTransaction, target: &Database<'static>) -> RustMailerResult<()> {
+    copy_table::<TaskMetaEntity>(r, target)?;
+    copy_table::<SchemaVersion>(r, target)
+}
+
+/// Periodically refreshes [`READ_REPLICA`] when `rustmailer_read_replica_refresh_secs` is set.
+pub struct ReadReplicaRefreshTask;
+
+impl RustMailTask for ReadReplicaRefreshTask {
+    fn start() {
+        let Some(interval) = SETTINGS.rustmailer_read_replica_refresh_secs else {
+            return;
+        };
+        info!(
+            "Read replica enabled, analytics queries are served from a copy refreshed every {} seconds.",
+            interval
+        );
+        let periodic_task = PeriodicTask::new("read-replica-refresh");
+        let task = move |_: Option<u64>| Box::pin(async move { READ_REPLICA.refresh().await });
+        periodic_task.start(task, None, Duration::from_secs(interval), false, true);
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+use std::time::Instant;
+
+use native_db::{transaction::RwTransaction, Database};
+use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+use tracing::{info, warn};
+
+use crate::{
+    modules::{
+        database::with_transaction,
+        error::{code::ErrorCode, RustMailerResult},
+        metrics::{
+            RUSTMAILER_TASK_JOURNAL_REPLAYED_RECORDS, RUSTMAILER_TASK_JOURNAL_REPLAY_DURATION,
+            RUSTMAILER_TASK_JOURNAL_WRITTEN_RECORDS_TOTAL,
+        },
+        scheduler::nativedb::{TaskMetaEntity, TaskMetaEntityKey},
+        settings::{
+            cli::SETTINGS,
+            dir::{DATA_DIR_MANAGER, TASK_FILE},
+        },
+    },
+    raise_error,
+};
+
+pub static TASK_JOURNAL: LazyLock<TaskJournal> = LazyLock::new(TaskJournal::new);
+
+/// One journaled change of the task queue.
+///
+/// Records carry the full state of the task rather than the change itself, so replaying
+/// a record that is already contained in the snapshot is harmless.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalRecord {
+    Upsert { task: Box<TaskMetaEntity> },
+    Delete { id: u64 },
+}
+
+struct JournalWriter {
+    generation: u64,
+    file: File,
+}
+
+impl JournalWriter {
+    fn open(generation: u64) -> RustMailerResult<Self> {
+        let path = DATA_DIR_MANAGER
+            .root_dir
+            .join(format!("{}.{}.journal", TASK_FILE, generation));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| {
+                raise_error!(
+                    format!("Failed to open task journal {:?}: {:#?}", path, e),
+                    ErrorCode::InternalError
+                )
+            })?;
+        Ok(Self { generation, file })
+    }
+}
+
+/// Write-ahead style journal of task queue changes in metadata memory mode.
+///
+/// Every committed change is appended to the current journal file. Each snapshot of the
+/// task queue starts a new file and deletes the older ones once it is written, so on startup
+/// only the changes made since the latest snapshot have to be replayed on top of it.
+pub struct TaskJournal {
+    writer: Mutex<Option<JournalWriter>>,
+}
+
+impl TaskJournal {
+    fn new() -> Self {
+        Self {
+            writer: Mutex::new(None),
+        }
+    }
+
+    pub fn enabled() -> bool {
+        SETTINGS.rustmailer_metadata_memory_mode_enabled && SETTINGS.rustmailer_task_journal_enabled
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<JournalWriter>> {
+        self.writer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Journals the current state of the given tasks, or their removal if they no longer
+    /// exist. Must be called after the change has been committed.
+    ///
+    /// A failure to write the journal never fails the change itself; it is only logged.
+    pub async fn record(database: &Arc<Database<'static>>, task_ids: Vec<u64>) {
+        if !Self::enabled() || task_ids.is_empty() {
+            return;
+        }
+        let db = database.clone();
+        match spawn_blocking(move || TASK_JOURNAL.append(&db, &task_ids)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Failed to write task journal: {:#?}", e),
+            Err(e) => warn!("Task journal write panicked: {:#?}", e),
+        }
+    }
+
+    fn append(&self, database: &Database<'static>, task_ids: &[u64]) -> RustMailerResult<()> {
+        // The tasks are read while holding the lock, so the journal keeps the commit order
+        // even when the same task is changed concurrently.
+        let mut slot = self.lock();
+        let r_transaction = database
+            .r_transaction()
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let mut lines = String::new();
+        for id in task_ids {
+            let task: Option<TaskMetaEntity> = r_transaction
+                .get()
+                .secondary(TaskMetaEntityKey::id, *id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            let record = match task {
+                Some(task) => JournalRecord::Upsert {
+                    task: Box::new(task),
+                },
+                None => JournalRecord::Delete { id: *id },
+            };
+            lines.push_str(
+                &serde_json::to_string(&record)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?,
+            );
+            lines.push('\n');
+        }
+
+        if slot.is_none() {
+            *slot = Some(JournalWriter::open(
+                Self::latest_generation().map_or(1, |g| g + 1),
+            )?);
+        }
+        let writer = slot.as_mut().expect("task journal writer was just opened");
+        writer
+            .file
+            .write_all(lines.as_bytes())
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        if SETTINGS.rustmailer_task_journal_fsync {
+            writer
+                .file
+                .sync_data()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        }
+        RUSTMAILER_TASK_JOURNAL_WRITTEN_RECORDS_TOTAL.inc_by(task_ids.len() as u64);
+        Ok(())
+    }
+
+    fn latest_generation() -> Option<u64> {
+        DATA_DIR_MANAGER
+            .list_journals_for(TASK_FILE)
+            .last()
+            .map(|(generation, _)| *generation)
+    }
+
+    /// Starts a new journal file, returning the generation of the previous one.
+    ///
+    /// Must be called right before a snapshot of the task queue is taken: everything journaled
+    /// up to the returned generation is contained in that snapshot.
+    pub fn rotate(&self) -> RustMailerResult<Option<u64>> {
+        if !Self::enabled() {
+            return Ok(None);
+        }
+        let mut slot = self.lock();
+        let previous = match slot.as_ref() {
+            Some(writer) => Some(writer.generation),
+            None => Self::latest_generation(),
+        };
+        *slot = Some(JournalWriter::open(previous.map_or(1, |g| g + 1))?);
+        Ok(previous)
+    }
+
+    /// Deletes the journal files up to and including `generation`, once a snapshot containing
+    /// their changes has been written.
+    pub fn prune(&self, generation: u64) {
+        for (_, path) in DATA_DIR_MANAGER
+            .list_journals_for(TASK_FILE)
+            .into_iter()
+            .take_while(|(g, _)| *g <= generation)
+        {
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!("Failed to delete task journal {:?}: {:#?}", path, e);
+            }
+        }
+    }
+
+    /// Replays the journaled changes on top of the task snapshot loaded at startup.
+    pub async fn replay(database: &Arc<Database<'static>>) -> RustMailerResult<()> {
+        if !Self::enabled() {
+            return Ok(());
+        }
+        let journals = DATA_DIR_MANAGER.list_journals_for(TASK_FILE);
+        if journals.is_empty() {
+            return Ok(());
+        }
+
+        let start = Instant::now();
+        let mut replayed = 0;
+        for (_, path) in journals {
+            let contents = tokio::fs::read(&path).await.map_err(|e| {
+                raise_error!(
+                    format!("Failed to read task journal {:?}: {:#?}", path, e),
+                    ErrorCode::InternalError
+                )
+            })?;
+            let (records, invalid_line) = parse_records(&String::from_utf8_lossy(&contents));
+            if let Some(line) = invalid_line {
+                // Most likely the last write before a crash; nothing after it can be trusted.
+                warn!(
+                    "Task journal {:?} is corrupted at line {}, ignoring the rest of the file",
+                    path, line
+                );
+            }
+            replayed += records.len();
+            with_transaction(database, move |rw| {
+                for record in records {
+                    apply(rw, record)?;
+                }
+                Ok(())
+            })
+            .await?;
+        }
+
+        let elapsed = start.elapsed();
+        RUSTMAILER_TASK_JOURNAL_REPLAY_DURATION.set(elapsed.as_secs_f64());
+        RUSTMAILER_TASK_JOURNAL_REPLAYED_RECORDS.set(replayed as i64);
+        info!(
+            "Replayed {} task journal records in {:#?}",
+            replayed, elapsed
+        );
+        Ok(())
+    }
+}
+
+/// Parses the records of a journal file, stopping at the first invalid line.
+///
+/// Returns the records and the 1-based number of the invalid line, if any.
+fn parse_records(contents: &str) -> (Vec<JournalRecord>, Option<usize>) {
+    let mut records = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            Err(_) => return (records, Some(index + 1)),
+        }
+    }
+    (records, None)
+}
+
+fn apply(rw: &RwTransaction, record: JournalRecord) -> RustMailerResult<()> {
+    match record {
+        JournalRecord::Upsert { task } => {
+            rw.upsert(*task)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        }
+        JournalRecord::Delete { id } => {
+            let task: Option<TaskMetaEntity> = rw
+                .get()
+                .secondary(TaskMetaEntityKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            if let Some(task) = task {
+                rw.remove(task)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::scheduler::model::TaskStatus;
+
+    fn task(id: u64, status: TaskStatus) -> TaskMetaEntity {
+        TaskMetaEntity {
+            id,
+            task_key: "send_email".into(),
+            queue_name: "emails".into(),
+            status,
+            created_at: 1_700_000_000_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let records = vec![
+            JournalRecord::Upsert {
+                task: Box::new(task(1, TaskStatus::Running)),
+            },
+            JournalRecord::Delete { id: 2 },
+        ];
+        let contents: String = records
+            .iter()
+            .map(|r| serde_json::to_string(r).unwrap() + "\n")
+            .collect();
+        assert!(contents.starts_with(r#"{"op":"upsert","task":{"#));
+        assert_eq!(parse_records(&contents), (records, None));
+    }
+
+    #[test]
+    fn stops_at_a_torn_write() {
+        let first = serde_json::to_string(&JournalRecord::Delete { id: 7 }).unwrap();
+        let second = serde_json::to_string(&JournalRecord::Upsert {
+            task: Box::new(task(8, TaskStatus::Scheduled)),
+        })
+        .unwrap();
+        let contents = format!("{first}\n\n{}", &second[..second.len() / 2]);
+
+        let (records, invalid_line) = parse_records(&contents);
+        assert_eq!(records, vec![JournalRecord::Delete { id: 7 }]);
+        assert_eq!(invalid_line, Some(3));
+    }
+}
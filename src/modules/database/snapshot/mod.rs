@@ -2,4 +2,5 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-pub mod task;
\ No newline at end of file
+pub mod journal;
+pub mod task;
@@ -3,7 +3,9 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::snapshot::journal::TASK_JOURNAL;
 use crate::modules::database::META_MODELS;
+use crate::modules::overview::memory::record_metadata_size;
 use crate::modules::scheduler::nativedb::TASK_MODELS;
 use crate::modules::settings::cli::SETTINGS;
 use crate::modules::settings::dir::{DATA_DIR_MANAGER, META_FILE, TASK_FILE};
@@ -16,8 +18,8 @@ use crate::{
     raise_error,
 };
 use chrono::Local;
-use native_db::Models;
-use std::sync::LazyLock;
+use native_db::{Database, Models};
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
 use tokio::join;
 use tokio::task::spawn_blocking;
@@ -63,8 +65,8 @@ impl DatabaseSnapshotTask {
 
     pub async fn snapshot() -> RustMailerResult<()> {
         let (meta_result, task_result) = join!(
-            Self::run_snapshot(META_FILE, &META_MODELS),
-            Self::run_snapshot(TASK_FILE, &TASK_MODELS)
+            Self::run_snapshot(META_FILE, DB_MANAGER.meta_db(), &META_MODELS),
+            Self::task_snapshot()
         );
         meta_result?;
         task_result?;
@@ -73,33 +75,60 @@ impl DatabaseSnapshotTask {
     }
 
     pub async fn block_snapshot() -> RustMailerResult<()> {
-        Self::run_snapshot(META_FILE, &META_MODELS).await?;
-        Self::run_snapshot(TASK_FILE, &TASK_MODELS).await
+        Self::run_snapshot(META_FILE, DB_MANAGER.meta_db(), &META_MODELS).await?;
+        Self::task_snapshot().await
     }
 
-    async fn run_snapshot(db_prefix: &str, models: &'static Models) -> RustMailerResult<()> {
+    /// Snapshots `tasks.db`, then drops the journal files whose changes it now contains.
+    async fn task_snapshot() -> RustMailerResult<()> {
+        let journaled = TASK_JOURNAL.rotate()?;
+        Self::run_snapshot(TASK_FILE, DB_MANAGER.tasks_db(), &TASK_MODELS).await?;
+        if let Some(generation) = journaled {
+            TASK_JOURNAL.prune(generation);
+        }
+        Ok(())
+    }
+
+    async fn run_snapshot(
+        db_prefix: &str,
+        database: &Arc<Database<'static>>,
+        models: &'static Models,
+    ) -> RustMailerResult<()> {
         let file_name = Self::generate_snapshot_filename(db_prefix);
         let file_path = DATA_DIR_MANAGER.root_dir.join(&file_name);
 
         info!("Starting snapshot for {} to {:?}", db_prefix, file_path);
 
-        spawn_blocking(move || DB_MANAGER.meta_db().snapshot(models, &file_path))
-            .await
-            .map_err(|join_err| {
-                error!("{} snapshot task panicked: {:?}", db_prefix, join_err);
-                raise_error!(
-                    format!("{} snapshot task panicked: {:?}", db_prefix, join_err),
-                    ErrorCode::InternalError
-                )
-            })?
-            .map_err(|e| {
-                error!("{} snapshot failed: {:?}", db_prefix, e);
-                raise_error!(
-                    format!("{} snapshot error: {:?}", db_prefix, e),
-                    ErrorCode::InternalError
-                )
-            })?;
+        let path = file_path.clone();
+        let database = database.clone();
+        // The snapshot is only complete once its database is closed, so the file size is read
+        // after dropping it.
+        let size = spawn_blocking(move || {
+            drop(database.snapshot(models, &path)?);
+            let size = std::fs::metadata(&path).map(|metadata| metadata.len()).ok();
+            Ok::<_, native_db::db_type::Error>(size)
+        })
+        .await
+        .map_err(|join_err| {
+            error!("{} snapshot task panicked: {:?}", db_prefix, join_err);
+            raise_error!(
+                format!("{} snapshot task panicked: {:?}", db_prefix, join_err),
+                ErrorCode::InternalError
+            )
+        })?
+        .map_err(|e| {
+            error!("{} snapshot failed: {:?}", db_prefix, e);
+            raise_error!(
+                format!("{} snapshot error: {:?}", db_prefix, e),
+                ErrorCode::InternalError
+            )
+        })?;
 
+        if db_prefix == META_FILE {
+            if let Some(size) = size {
+                record_metadata_size(size);
+            }
+        }
         info!("Completed snapshot for {}", db_prefix);
         Ok(())
     }
@@ -2,7 +2,7 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
 
 use crate::{
     id,
@@ -13,7 +13,12 @@ use crate::{
             imap::{mailbox::MailBox, ENVELOPE_MODELS},
             vendor::gmail::sync::envelope::GmailEnvelope,
         },
-        database::META_MODELS,
+        database::{
+            batch::WriteBatch,
+            migration::{Migration, MigrationRegistry},
+            META_MODELS,
+        },
+        error::{code::ErrorCode, RustMailerResult},
         hook::{
             entity::{EventHooks, HookType, HttpConfig, HttpMethod},
             events::EventType,
@@ -21,6 +26,7 @@ use crate::{
         },
         scheduler::nativedb::{TaskMetaEntity, TASK_MODELS},
     },
+    raise_error,
 };
 use itertools::Itertools;
 use native_db::Builder;
@@ -82,9 +88,15 @@ async fn test4() {
             custom_headers: BTreeMap::new(),
         }),
         nats: None,
+        kafka: None,
+        redis: None,
         vrl_script: None,
+        event_vrl_scripts: None,
         use_proxy: None,
         watched_events: vec![EventType::EmailSendingError],
+        account_tags: None,
+        workspace_id: None,
+        signing_secret: None,
     };
     let hook = EventHooks::new(request).await.unwrap();
     hook.save().await.unwrap();
@@ -103,6 +115,45 @@ async fn test5() {
     println!("{:#?}", account);
 }
 
+#[tokio::test]
+async fn test_write_batch_rolls_back_on_failure() {
+    let database = Arc::new(Builder::new().create_in_memory(&META_MODELS).unwrap());
+
+    let mut account = AccountModel::default();
+    account.id = id!(64);
+    let pk = format!("{}_{}", account.created_at, account.id);
+
+    // The second operation fails, so the insert must not be committed either.
+    let result = WriteBatch::new()
+        .insert(account.clone())
+        .delete(|_| -> RustMailerResult<AccountModel> {
+            Err(raise_error!("not found".into(), ErrorCode::ResourceNotFound))
+        })
+        .commit(&database)
+        .await;
+    assert!(result.is_err());
+    let found: Option<AccountModel> = database
+        .r_transaction()
+        .unwrap()
+        .get()
+        .primary(pk.clone())
+        .unwrap();
+    assert!(found.is_none());
+
+    WriteBatch::new()
+        .insert(account)
+        .commit(&database)
+        .await
+        .unwrap();
+    let found: Option<AccountModel> = database
+        .r_transaction()
+        .unwrap()
+        .get()
+        .primary(pk)
+        .unwrap();
+    assert!(found.is_some());
+}
+
 #[test]
 fn test6() {
     #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
@@ -187,3 +238,213 @@ fn test9() {
 
     println!("{:#?}", entities);
 }
+
+#[test]
+fn test_composite_key_ranges() {
+    use crate::modules::database::key::{timestamp_key_range, CompositeKey};
+    use std::ops::RangeBounds;
+
+    let key = CompositeKey::new().segment("smtp").segment(1u32).build();
+    assert_eq!(key, "smtp_1");
+
+    let range = timestamp_key_range(Some(1718000000000), Some(1718000000100));
+    assert!(range.contains(&"1718000000000_7".to_string()));
+    assert!(range.contains(&"1718000000099_7".to_string()));
+    assert!(!range.contains(&"1718000000100_7".to_string()));
+    assert!(!range.contains(&"1717999999999_7".to_string()));
+}
+
+#[test]
+fn test_migration_registry_order_and_downgrade_guard() {
+    static REGISTRY: MigrationRegistry = MigrationRegistry {
+        database: "test",
+        migrations: &[
+            Migration {
+                version: 3,
+                description: "third",
+                transform: |_| Ok(()),
+            },
+            Migration {
+                version: 1,
+                description: "first",
+                transform: |_| Ok(()),
+            },
+            Migration {
+                version: 2,
+                description: "second",
+                transform: |_| Ok(()),
+            },
+        ],
+    };
+
+    assert_eq!(REGISTRY.latest_version(), 3);
+    let pending: Vec<u32> = REGISTRY.pending(0).iter().map(|m| m.version).collect();
+    assert_eq!(pending, vec![1, 2, 3]);
+    let pending: Vec<u32> = REGISTRY.pending(1).iter().map(|m| m.version).collect();
+    assert_eq!(pending, vec![2, 3]);
+    assert!(REGISTRY.pending(3).is_empty());
+
+    assert!(REGISTRY.check_compatible(0).is_ok());
+    assert!(REGISTRY.check_compatible(3).is_ok());
+    assert!(REGISTRY.check_compatible(4).is_err());
+}
+
+#[test]
+fn test_replica_copies_every_meta_table() {
+    use crate::modules::database::replica::{copy_database, copy_meta_tables};
+    use crate::modules::message::search::saved::SavedSearch;
+    use crate::modules::workspace::Workspace;
+
+    let source = Builder::new().create_in_memory(&META_MODELS).unwrap();
+    let rw = source.rw_transaction().unwrap();
+    rw.insert(SavedSearch {
+        id: 1,
+        account_id: 2,
+        ..Default::default()
+    })
+    .unwrap();
+    rw.insert(Workspace {
+        id: 3,
+        ..Default::default()
+    })
+    .unwrap();
+    rw.commit().unwrap();
+
+    let replica = copy_database(&source, &META_MODELS, copy_meta_tables).unwrap();
+    let r = replica.r_transaction().unwrap();
+    let searches: Vec<SavedSearch> = r
+        .scan()
+        .primary()
+        .unwrap()
+        .all()
+        .unwrap()
+        .try_collect()
+        .unwrap();
+    let workspaces: Vec<Workspace> = r
+        .scan()
+        .primary()
+        .unwrap()
+        .all()
+        .unwrap()
+        .try_collect()
+        .unwrap();
+    assert_eq!(searches.len(), 1);
+    assert_eq!(workspaces.len(), 1);
+}
+
+#[test]
+fn test_replica_leaves_out_secrets() {
+    use crate::modules::account::entity::{AuthConfig, ImapConfig};
+    use crate::modules::database::replica::{copy_database, copy_replica_meta_tables};
+    use crate::modules::settings::system::SystemSetting;
+
+    let source = Builder::new().create_in_memory(&META_MODELS).unwrap();
+    let rw = source.rw_transaction().unwrap();
+    rw.insert(SystemSetting::new("root_token".into(), "secret".into()))
+        .unwrap();
+    rw.insert(AccountModel {
+        id: 1,
+        imap: Some(ImapConfig {
+            auth: AuthConfig {
+                password: Some("encrypted".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+    .unwrap();
+    rw.commit().unwrap();
+
+    let replica = copy_database(&source, &META_MODELS, copy_replica_meta_tables).unwrap();
+    let r = replica.r_transaction().unwrap();
+    let settings: Vec<SystemSetting> = r
+        .scan()
+        .primary()
+        .unwrap()
+        .all()
+        .unwrap()
+        .try_collect()
+        .unwrap();
+    let accounts: Vec<AccountModel> = r
+        .scan()
+        .primary()
+        .unwrap()
+        .all()
+        .unwrap()
+        .try_collect()
+        .unwrap();
+    assert!(settings.is_empty());
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].imap.as_ref().unwrap().auth.password, None);
+}
+
+#[tokio::test]
+async fn test_restore_snapshot_with_baseline_models() {
+    use crate::modules::account::entity::Account;
+    use crate::modules::database::{
+        insert_impl, list_all_impl, manager::DatabaseManager, migration::META_MIGRATIONS,
+    };
+    use crate::modules::hook::entity::EventHooksV1;
+    use crate::modules::smtp::mta::entity::{Mta, MtaV1};
+    use crate::modules::smtp::template::entity::{EmailTemplate, EmailTemplateV1};
+    use crate::modules::token::{AccessToken, AccessTokenV1};
+
+    // A snapshot written before the migration registry existed.
+    let snapshot = Arc::new(Builder::new().create_in_memory(&META_MODELS).unwrap());
+    let account = Account {
+        id: 1,
+        email: "baseline@example.com".into(),
+        ..Default::default()
+    };
+    insert_impl(&snapshot, account).await.unwrap();
+    let hook = EventHooksV1 {
+        id: 2,
+        account_id: Some(1),
+        ..Default::default()
+    };
+    insert_impl(&snapshot, hook).await.unwrap();
+    let mta = MtaV1 {
+        id: 3,
+        ..Default::default()
+    };
+    insert_impl(&snapshot, mta).await.unwrap();
+    let template = EmailTemplateV1 {
+        id: 4,
+        ..Default::default()
+    };
+    insert_impl(&snapshot, template).await.unwrap();
+    let token = AccessTokenV1 {
+        token: "baseline-token".into(),
+        accounts: Default::default(),
+        created_at: 0,
+        updated_at: 0,
+        description: None,
+        access_scopes: Default::default(),
+        last_access_at: 0,
+        acl: None,
+    };
+    insert_impl(&snapshot, token).await.unwrap();
+
+    let restored = Arc::new(Builder::new().create_in_memory(&META_MODELS).unwrap());
+    DatabaseManager::copy_meta_tables(&snapshot, &restored)
+        .await
+        .unwrap();
+    META_MIGRATIONS.apply(&restored, false).unwrap();
+
+    let accounts = list_all_impl::<AccountModel>(&restored).await.unwrap();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].email, "baseline@example.com");
+    let hooks = list_all_impl::<EventHooks>(&restored).await.unwrap();
+    assert_eq!(hooks.len(), 1);
+    assert_eq!(hooks[0].account_id, Some(1));
+    let mtas = list_all_impl::<Mta>(&restored).await.unwrap();
+    assert_eq!(mtas.len(), 1);
+    assert_eq!(mtas[0].id, 3);
+    let templates = list_all_impl::<EmailTemplate>(&restored).await.unwrap();
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0].id, 4);
+    let tokens = list_all_impl::<AccessToken>(&restored).await.unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].token, "baseline-token");
+}
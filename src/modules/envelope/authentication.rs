@@ -0,0 +1,321 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use mail_parser::Message;
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+pub const AUTHENTICATION_RESULTS: &str = "Authentication-Results";
+
+/// The result of an SPF, DKIM or DMARC check, as defined by RFC 8601.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Enum)]
+pub enum AuthVerdict {
+    Pass,
+    Fail,
+    SoftFail,
+    Neutral,
+    None,
+    TempError,
+    PermError,
+    Policy,
+}
+
+impl AuthVerdict {
+    /// Parses a result keyword, case-insensitively. `hardfail` is accepted as an alias of `fail`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "pass" => Some(AuthVerdict::Pass),
+            "fail" | "hardfail" => Some(AuthVerdict::Fail),
+            "softfail" => Some(AuthVerdict::SoftFail),
+            "neutral" => Some(AuthVerdict::Neutral),
+            "none" => Some(AuthVerdict::None),
+            "temperror" => Some(AuthVerdict::TempError),
+            "permerror" => Some(AuthVerdict::PermError),
+            "policy" => Some(AuthVerdict::Policy),
+            _ => None,
+        }
+    }
+
+    /// The result keyword as written in `Authentication-Results` headers.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthVerdict::Pass => "pass",
+            AuthVerdict::Fail => "fail",
+            AuthVerdict::SoftFail => "softfail",
+            AuthVerdict::Neutral => "neutral",
+            AuthVerdict::None => "none",
+            AuthVerdict::TempError => "temperror",
+            AuthVerdict::PermError => "permerror",
+            AuthVerdict::Policy => "policy",
+        }
+    }
+}
+
+/// The SPF check of the envelope sender.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SpfResult {
+    pub result: AuthVerdict,
+    /// The domain that was checked, taken from `smtp.mailfrom` or else `smtp.helo`.
+    pub domain: Option<String>,
+}
+
+/// The check of one DKIM signature.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct DkimResult {
+    pub result: AuthVerdict,
+    /// The signing domain (`header.d`, or the domain of `header.i`).
+    pub domain: Option<String>,
+    /// The selector of the signing key (`header.s`).
+    pub selector: Option<String>,
+}
+
+/// The DMARC evaluation of the `From` domain.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct DmarcResult {
+    pub result: AuthVerdict,
+    /// The `From` domain that was evaluated (`header.from`).
+    pub domain: Option<String>,
+    /// The published policy of the domain (e.g., "reject", "quarantine", "none"), if reported.
+    pub policy: Option<String>,
+}
+
+/// SPF, DKIM and DMARC verdicts read from the `Authentication-Results` headers of an email.
+///
+/// Only the headers added by the topmost authentication server are used: headers further
+/// down the message were added before the last hop and may have been forged by the sender.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AuthenticationResults {
+    /// The server that performed the checks (the authserv-id).
+    pub authserv_id: Option<String>,
+    pub spf: Option<SpfResult>,
+    /// One entry per DKIM signature checked.
+    pub dkim: Vec<DkimResult>,
+    pub dmarc: Option<DmarcResult>,
+}
+
+impl AuthenticationResults {
+    /// Reads the verdicts of a parsed message, or returns `None` if it carries no
+    /// `Authentication-Results` header with SPF, DKIM or DMARC results.
+    pub fn from_message(message: &Message) -> Option<Self> {
+        Self::parse(
+            message
+                .headers_raw()
+                .filter(|(name, _)| name.eq_ignore_ascii_case(AUTHENTICATION_RESULTS))
+                .map(|(_, value)| value),
+        )
+    }
+
+    /// Parses `Authentication-Results` header values, topmost first.
+    pub fn parse<'a>(headers: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        let mut results = Self::default();
+        for header in headers {
+            let mut segments = split_segments(header).into_iter();
+            let Some((authserv, _)) = segments.next() else {
+                continue;
+            };
+            let authserv_id = authserv.split_whitespace().next().map(str::to_lowercase);
+            match (&results.authserv_id, &authserv_id) {
+                (None, Some(_)) => results.authserv_id = authserv_id,
+                (Some(trusted), Some(id)) if trusted == id => {}
+                _ => continue,
+            }
+            for (segment, comment) in segments {
+                results.add(&segment, &comment);
+            }
+        }
+        (results.spf.is_some() || !results.dkim.is_empty() || results.dmarc.is_some())
+            .then_some(results)
+    }
+
+    /// Adds one `method=result property=value ...` result.
+    fn add(&mut self, segment: &str, comment: &str) {
+        let mut tokens = segment.split_whitespace();
+        let Some((method, result)) = tokens.next().and_then(|t| t.split_once('=')) else {
+            return;
+        };
+        let Some(result) = AuthVerdict::parse(result) else {
+            return;
+        };
+        let properties: Vec<(String, String)> = tokens
+            .filter_map(|t| t.split_once('='))
+            .map(|(k, v)| (k.to_ascii_lowercase(), v.trim_matches('"').to_string()))
+            .collect();
+        let property = |name: &str| {
+            properties
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.as_str())
+        };
+
+        // The method may carry a version, as in `dkim/1=pass`.
+        let method = method.split('/').next().unwrap_or_default();
+        match method.to_ascii_lowercase().as_str() {
+            "spf" if self.spf.is_none() => {
+                let domain = property("smtp.mailfrom")
+                    .or_else(|| property("smtp.helo"))
+                    .map(domain_of);
+                self.spf = Some(SpfResult { result, domain });
+            }
+            "dkim" => {
+                let domain = property("header.d")
+                    .or_else(|| property("header.i"))
+                    .map(domain_of);
+                let selector = property("header.s").map(str::to_string);
+                self.dkim.push(DkimResult {
+                    result,
+                    domain,
+                    selector,
+                });
+            }
+            "dmarc" if self.dmarc.is_none() => {
+                let domain = property("header.from").map(domain_of);
+                // Most servers report the published policy in a comment, as in `(p=REJECT)`.
+                let policy = comment
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .find_map(|t| t.strip_prefix("p="))
+                    .map(str::to_lowercase);
+                self.dmarc = Some(DmarcResult {
+                    result,
+                    domain,
+                    policy,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The lowercased domain of an address or domain, without any local part.
+fn domain_of(value: &str) -> String {
+    value
+        .rsplit_once('@')
+        .map_or(value, |(_, domain)| domain)
+        .trim_end_matches('.')
+        .to_lowercase()
+}
+
+/// Splits a header value on top-level `;`, returning each segment without comments, along
+/// with the text of its comments.
+fn split_segments(value: &str) -> Vec<(String, String)> {
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut comment = String::new();
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            escaped = false;
+            if depth > 0 {
+                comment.push(c);
+            } else {
+                segment.push(c);
+            }
+            continue;
+        }
+        match c {
+            '\\' if quoted || depth > 0 => escaped = true,
+            '"' if depth == 0 => {
+                quoted = !quoted;
+                segment.push(c);
+            }
+            '(' if !quoted => {
+                depth += 1;
+                // Comments separate tokens, as in `spf=pass(sender ok)`.
+                segment.push(' ');
+                comment.push(' ');
+            }
+            ')' if !quoted && depth > 0 => depth -= 1,
+            ';' if !quoted && depth == 0 => {
+                segments.push((std::mem::take(&mut segment), std::mem::take(&mut comment)));
+            }
+            c if depth > 0 => comment.push(c),
+            c => segment.push(c),
+        }
+    }
+    segments.push((segment, comment));
+    segments.retain(|(segment, _)| !segment.trim().is_empty());
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_gmail_style_results() {
+        let results = AuthenticationResults::parse([
+            "mx.google.com;\r\n       dkim=pass header.i=@example.com header.s=sel1 header.b=Abc123;\r\n       dkim=fail (body hash did not verify) header.d=esp.example.net header.s=s2;\r\n       spf=pass (google.com: domain of bounce@example.com designates 203.0.113.9 as permitted sender) smtp.mailfrom=bounce@example.com;\r\n       dmarc=pass (p=REJECT sp=NONE dis=NONE) header.from=Example.com",
+        ])
+        .unwrap();
+
+        assert_eq!(results.authserv_id.as_deref(), Some("mx.google.com"));
+        assert_eq!(
+            results.spf,
+            Some(SpfResult {
+                result: AuthVerdict::Pass,
+                domain: Some("example.com".into()),
+            })
+        );
+        assert_eq!(
+            results.dkim,
+            vec![
+                DkimResult {
+                    result: AuthVerdict::Pass,
+                    domain: Some("example.com".into()),
+                    selector: Some("sel1".into()),
+                },
+                DkimResult {
+                    result: AuthVerdict::Fail,
+                    domain: Some("esp.example.net".into()),
+                    selector: Some("s2".into()),
+                },
+            ]
+        );
+        assert_eq!(
+            results.dmarc,
+            Some(DmarcResult {
+                result: AuthVerdict::Pass,
+                domain: Some("example.com".into()),
+                policy: Some("reject".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn trusts_only_the_topmost_server() {
+        let results = AuthenticationResults::parse([
+            "mail.example.com; spf=softfail smtp.mailfrom=example.org",
+            "evil.example; dmarc=pass header.from=bank.example",
+            "Mail.Example.com; dmarc=fail action=none header.from=example.org",
+        ])
+        .unwrap();
+        assert_eq!(results.spf.unwrap().result, AuthVerdict::SoftFail);
+        let dmarc = results.dmarc.unwrap();
+        assert_eq!(dmarc.result, AuthVerdict::Fail);
+        assert_eq!(dmarc.domain.as_deref(), Some("example.org"));
+        assert_eq!(dmarc.policy, None);
+    }
+
+    #[test]
+    fn ignores_headers_without_results() {
+        assert_eq!(AuthenticationResults::parse(["mx.example.com; none"]), None);
+        assert_eq!(AuthenticationResults::parse(Vec::<&str>::new()), None);
+        assert_eq!(
+            AuthenticationResults::parse(["mx.example.com; arc=pass; spf=bogus"]),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_verdicts() {
+        assert_eq!(AuthVerdict::parse("PASS"), Some(AuthVerdict::Pass));
+        assert_eq!(AuthVerdict::parse("hardfail"), Some(AuthVerdict::Fail));
+        assert_eq!(
+            AuthVerdict::parse("temperror"),
+            Some(AuthVerdict::TempError)
+        );
+        assert_eq!(AuthVerdict::parse("bestguesspass"), None);
+    }
+}
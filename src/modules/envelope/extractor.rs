@@ -5,9 +5,12 @@
 use crate::id;
 use crate::modules::cache::imap::flags_to_hash;
 use crate::modules::cache::imap::mailbox::EnvelopeFlag;
+use crate::modules::cache::imap::migration::EmailEnvelopeV7;
 use crate::modules::cache::imap::minimal::MinimalEnvelope;
-use crate::modules::cache::imap::migration::EmailEnvelopeV3;
-use crate::modules::common::AddrVec;
+use crate::modules::common::importance::Importance;
+use crate::modules::common::{Addr, AddrVec};
+use crate::modules::envelope::authentication::AuthenticationResults;
+use crate::modules::envelope::received::ReceivedChain;
 use crate::modules::envelope::MinimalEnvelopeMeta;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::error::RustMailerResult;
@@ -15,106 +18,234 @@ use crate::modules::imap::section::SectionExtractor;
 use crate::modules::utils::mailbox_id;
 use crate::raise_error;
 use async_imap::types::{Fetch, Flag};
+use chrono::DateTime;
+use imap_proto::BodyStructure;
 use mail_parser::{Message, MessageParser};
+use tracing::warn;
 
+/// Extracts the envelope of a fetched email.
+///
+/// Emails whose headers or body structure cannot be fully parsed are not dropped: the
+/// envelope is built from whatever could be recovered, marked with a `parse_error`, and
+/// keeps the raw header bytes. Only a missing UID is an error.
 #[inline]
 pub fn extract_envelope(
     fetch: &Fetch,
     account_id: u64,
     mailbox_name: &str,
-) -> RustMailerResult<EmailEnvelopeV3> {
-    let attachments: Option<Vec<crate::modules::imap::section::ImapAttachment>> =
-        SectionExtractor::new(fetch.bodystructure().ok_or_else(|| {
-            raise_error!(
-                "No bodystructure available".into(),
-                ErrorCode::InternalError
-            )
-        })?)
-        .get_attachments();
-
-    let body = SectionExtractor::new(fetch.bodystructure().ok_or_else(|| {
-        raise_error!(
-            "No bodystructure available".into(),
-            ErrorCode::InternalError
-        )
-    })?)
-    .get_body_parts();
-
+) -> RustMailerResult<EmailEnvelopeV7> {
+    let uid = fetch
+        .uid
+        .ok_or_else(|| raise_error!("No uid available".into(), ErrorCode::InternalError))?;
     let flags: Vec<EnvelopeFlag> = fetch
         .flags()
         .filter(|f| !matches!(f, Flag::Recent))
         .map(Into::into)
         .collect();
+    let envelope = build_envelope(
+        FetchedParts {
+            account_id,
+            mailbox_name,
+            uid,
+            internal_date: fetch.internal_date().map(|d| d.timestamp_millis()),
+            size: fetch.size,
+            flags,
+            bodystructure: fetch.bodystructure(),
+        },
+        fetch.header(),
+    );
+    if let Some(error) = &envelope.parse_error {
+        warn!(
+            "Account {}: Email uid={} in '{}' was only partially parsed: {}",
+            account_id, uid, mailbox_name, error
+        );
+    }
+    Ok(envelope)
+}
 
-    let flags_hash = flags_to_hash(&flags);
-
-    let internal_date = fetch.internal_date().map(|d| d.timestamp_millis());
+/// The items of a `FETCH` response an envelope is built from, besides the headers.
+struct FetchedParts<'a> {
+    account_id: u64,
+    mailbox_name: &'a str,
+    uid: u32,
+    internal_date: Option<i64>,
+    size: Option<u32>,
+    flags: Vec<EnvelopeFlag>,
+    bodystructure: Option<&'a BodyStructure<'a>>,
+}
 
-    let uid = fetch
-        .uid
-        .ok_or_else(|| raise_error!("No uid available".into(), ErrorCode::InternalError))?;
-    let size = fetch
-        .size
-        .ok_or_else(|| raise_error!("No size available".into(), ErrorCode::InternalError))?;
+fn build_envelope(parts: FetchedParts<'_>, header: Option<&[u8]>) -> EmailEnvelopeV7 {
+    let mut problems = Vec::new();
+    let (attachments, body_meta) = match parts.bodystructure {
+        Some(structure) => {
+            let extractor = SectionExtractor::new(structure);
+            (extractor.get_attachments(), extractor.get_body_parts())
+        }
+        None => {
+            problems.push("No bodystructure available".to_string());
+            (None, None)
+        }
+    };
+    let size = parts.size.unwrap_or_else(|| {
+        problems.push("No size available".to_string());
+        0
+    });
+    let message = match header {
+        Some(header) => {
+            let message = MessageParser::new().parse(header);
+            if message.is_none() {
+                problems.push("Email header parse result is not available".to_string());
+            }
+            message
+        }
+        None => {
+            problems.push("No header available".to_string());
+            None
+        }
+    };
 
-    let header = fetch
-        .header()
-        .ok_or_else(|| raise_error!("No header available".into(), ErrorCode::InternalError))?;
-    let message = MessageParser::new().parse(header).ok_or_else(|| {
-        raise_error!(
-            "Email header parse result is not available".into(),
-            ErrorCode::InternalError
-        )
-    })?;
-
-    let envelope = EmailEnvelopeV3 {
-        account_id,
-        mailbox_id: mailbox_id(account_id, mailbox_name),
-        mailbox_name: mailbox_name.into(),
-        uid,
-        internal_date,
+    let flags_hash = flags_to_hash(&parts.flags);
+    let mut envelope = EmailEnvelopeV7 {
+        account_id: parts.account_id,
+        mailbox_id: mailbox_id(parts.account_id, parts.mailbox_name),
+        mailbox_name: parts.mailbox_name.into(),
+        uid: parts.uid,
+        internal_date: parts.internal_date,
         size,
-        flags,
+        flags: parts.flags,
         flags_hash,
-        bcc: message.bcc().map(|addr| AddrVec::from(addr).0),
-        cc: message.cc().map(|addr| AddrVec::from(addr).0),
-        date: message.date().map(|d| d.to_timestamp() * 1000),
-        from: message
-            .from()
-            .map(|addr| AddrVec::from(addr).0.first().cloned())
-            .flatten(),
-        in_reply_to: message.in_reply_to().as_text().map(String::from),
-        sender: message
-            .sender()
-            .map(|addr| AddrVec::from(addr).0.first().cloned())
-            .flatten(),
-        return_address: message.return_address().map(String::from),
-        message_id: message.message_id().map(String::from),
-        subject: message.subject().map(String::from),
-        mime_version: message.mime_version().as_text().map(String::from),
         thread_id: id!(64),
-        thread_name: message.thread_name().map(String::from),
-        references: extract_references(&message),
-        reply_to: message.reply_to().map(|addr| AddrVec::from(addr).0),
-        to: message.to().map(|addr| AddrVec::from(addr).0),
         attachments,
-        body_meta: body,
-        received: message.received().map(Into::into),
-        mid: None,
-        labels: vec![],
+        body_meta,
+        ..Default::default()
     };
+    if let Some(message) = &message {
+        apply_headers(&mut envelope, message);
+    }
+    if let Some(header) = header {
+        recover_raw_fields(&mut envelope, header, &mut problems);
+    }
+    if !problems.is_empty() {
+        envelope.parse_error = Some(problems.join("; "));
+        envelope.raw_headers = header.map(<[u8]>::to_vec);
+    }
+    envelope
+}
 
-    Ok(envelope)
+fn apply_headers(envelope: &mut EmailEnvelopeV7, message: &Message<'_>) {
+    envelope.bcc = message.bcc().map(|addr| AddrVec::from(addr).0);
+    envelope.cc = message.cc().map(|addr| AddrVec::from(addr).0);
+    envelope.date = message.date().map(|d| d.to_timestamp() * 1000);
+    envelope.from = message
+        .from()
+        .and_then(|addr| AddrVec::from(addr).0.first().cloned());
+    envelope.in_reply_to = message.in_reply_to().as_text().map(String::from);
+    envelope.sender = message
+        .sender()
+        .and_then(|addr| AddrVec::from(addr).0.first().cloned());
+    envelope.return_address = message.return_address().map(String::from);
+    envelope.message_id = message.message_id().map(String::from);
+    envelope.subject = message.subject().map(String::from);
+    envelope.mime_version = message.mime_version().as_text().map(String::from);
+    envelope.thread_name = message.thread_name().map(String::from);
+    envelope.references = extract_references(message);
+    envelope.reply_to = message.reply_to().map(|addr| AddrVec::from(addr).0);
+    envelope.to = message.to().map(|addr| AddrVec::from(addr).0);
+    envelope.received = message.received().map(Into::into);
+    envelope.importance = Importance::from_message(message);
+    envelope.received_chain = ReceivedChain::from_message(message);
+    envelope.authentication_results = AuthenticationResults::from_message(message);
+}
+
+/// Fills the fields the parser could not read from the raw value of their header, and
+/// records each such header as a parse problem.
+fn recover_raw_fields(envelope: &mut EmailEnvelopeV7, header: &[u8], problems: &mut Vec<String>) {
+    let raw = raw_header_fields(header);
+    let value = |name: &str| {
+        raw.iter()
+            .find(|(n, v)| n.eq_ignore_ascii_case(name) && !v.is_empty())
+            .map(|(_, v)| v.clone())
+    };
+
+    if envelope.subject.is_none() {
+        if let Some(subject) = value("Subject") {
+            envelope.subject = Some(subject);
+            problems.push("Unparseable Subject header".to_string());
+        }
+    }
+    if envelope.from.is_none() {
+        if let Some(from) = value("From") {
+            envelope.from = Some(raw_address(&from));
+            problems.push("Unparseable From header".to_string());
+        }
+    }
+    if envelope.message_id.is_none() {
+        if let Some(message_id) = value("Message-ID") {
+            envelope.message_id = Some(
+                message_id
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string(),
+            );
+            problems.push("Unparseable Message-ID header".to_string());
+        }
+    }
+    if envelope.date.is_none() {
+        if let Some(date) = value("Date") {
+            match DateTime::parse_from_rfc2822(&date) {
+                Ok(date) => envelope.date = Some(date.timestamp_millis()),
+                Err(_) => problems.push("Unparseable Date header".to_string()),
+            }
+        }
+    }
+}
+
+/// Splits raw header bytes into unfolded `(name, value)` pairs, decoding invalid UTF-8
+/// lossily and skipping lines that are not headers.
+fn raw_header_fields(header: &[u8]) -> Vec<(String, String)> {
+    let text = String::from_utf8_lossy(header);
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            if !name.is_empty() && !name.contains(' ') {
+                fields.push((name.to_string(), value.trim().to_string()));
+            }
+        }
+    }
+    fields
+}
+
+/// Best-effort address from a header value the address parser rejected: the text between
+/// angle brackets if there is any, otherwise the whole value.
+fn raw_address(value: &str) -> Addr {
+    match (value.find('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => {
+            let name = value[..start].trim().trim_matches('"').trim();
+            Addr {
+                name: (!name.is_empty()).then(|| name.to_string()),
+                address: Some(value[start + 1..end].trim().to_string()),
+            }
+        }
+        _ => Addr {
+            name: None,
+            address: Some(value.to_string()),
+        },
+    }
 }
 
 pub fn extract_minimal_envelope_meta(fetch: &Fetch) -> RustMailerResult<MinimalEnvelopeMeta> {
-    let attachments = SectionExtractor::new(fetch.bodystructure().ok_or_else(|| {
-        raise_error!(
-            "No bodystructure available".into(),
-            ErrorCode::InternalError
-        )
-    })?)
-    .get_attachments();
+    // A missing body structure only hides the attachments; the email can still be downloaded.
+    let attachments = fetch
+        .bodystructure()
+        .and_then(|structure| SectionExtractor::new(structure).get_attachments());
     let size = fetch
         .size
         .ok_or_else(|| raise_error!("No size available".into(), ErrorCode::InternalError))?;
@@ -127,7 +258,7 @@ pub fn extract_rich_envelopes(
     fetches: &Vec<Fetch>,
     account_id: u64,
     mailbox_name: &str,
-) -> RustMailerResult<Vec<EmailEnvelopeV3>> {
+) -> RustMailerResult<Vec<EmailEnvelopeV7>> {
     let mut envelopes = Vec::with_capacity(fetches.len());
     for fetch in fetches {
         let envelope = extract_envelope(fetch, account_id, mailbox_name)?;
@@ -208,3 +339,70 @@ fn extract_references(message: &Message<'_>) -> Option<Vec<String>> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(size: Option<u32>) -> FetchedParts<'static> {
+        FetchedParts {
+            account_id: 1,
+            mailbox_name: "INBOX",
+            uid: 7,
+            internal_date: Some(1_700_000_000_000),
+            size,
+            flags: vec![],
+            bodystructure: None,
+        }
+    }
+
+    #[test]
+    fn keeps_malformed_emails_visible() {
+        let header = b"From: Broken Sender <broken@example.com\r\n\
+Subject: Quarterly\r\n report\r\n\
+Message-ID: <abc@example.com>\r\n\
+Date: sometime last week\r\n\r\n";
+        let envelope = build_envelope(parts(Some(120)), Some(&header[..]));
+
+        assert_eq!(envelope.uid, 7);
+        assert_eq!(envelope.size, 120);
+        assert_eq!(envelope.subject.as_deref(), Some("Quarterly report"));
+        assert_eq!(envelope.message_id.as_deref(), Some("abc@example.com"));
+        assert_eq!(envelope.date, None);
+        let error = envelope.parse_error.unwrap();
+        assert!(error.contains("No bodystructure available"));
+        assert!(error.contains("Unparseable Date header"));
+        assert_eq!(envelope.raw_headers.as_deref(), Some(header.as_slice()));
+    }
+
+    #[test]
+    fn builds_envelopes_without_headers() {
+        let envelope = build_envelope(parts(None), None);
+        assert_eq!(envelope.size, 0);
+        assert_eq!(envelope.raw_headers, None);
+        assert_eq!(
+            envelope.parse_error.as_deref(),
+            Some("No bodystructure available; No size available; No header available")
+        );
+    }
+
+    #[test]
+    fn recovers_raw_fields() {
+        let fields = raw_header_fields(b"X-Test: a\r\n\tb\r\nnot a header\r\nTo: c\xff\r\n");
+        assert_eq!(
+            fields,
+            vec![
+                ("X-Test".to_string(), "a b".to_string()),
+                ("To".to_string(), "c\u{fffd}".to_string()),
+            ]
+        );
+
+        let addr = raw_address("\"Broken, Sender\" <broken@example.com>");
+        assert_eq!(addr.name.as_deref(), Some("Broken, Sender"));
+        assert_eq!(addr.address.as_deref(), Some("broken@example.com"));
+        assert_eq!(
+            raw_address("broken@@example.com").address.as_deref(),
+            Some("broken@@example.com")
+        );
+    }
+}
@@ -6,8 +6,10 @@ use crate::modules::cache::imap::sync::flow::compress_uid_list;
 use crate::modules::imap::section::ImapAttachment;
 use ahash::AHashSet;
 
+pub mod authentication;
 pub mod detect;
 pub mod extractor;
+pub mod received;
 
 pub fn generate_uid_set(uids: Vec<u32>) -> String {
     // Insert elements into HashSet to remove duplicates
@@ -0,0 +1,195 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::net::IpAddr;
+
+use mail_parser::{HeaderName, HeaderValue, Host, Message};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+/// One hop of an email's `Received` chain.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ReceivedHop {
+    /// The host that handed the email over, as announced in the `from` clause.
+    pub from: Option<String>,
+    /// The IP address the email was received from, as recorded by the receiving server.
+    pub from_ip: Option<String>,
+    /// The server that received the email.
+    pub by: Option<String>,
+    /// The protocol used to receive the email (e.g., "ESMTPS").
+    pub with: Option<String>,
+    /// When the server received the email, as a Unix timestamp in milliseconds.
+    pub date: Option<i64>,
+}
+
+/// The path an email took to reach the mailbox, parsed from all of its `Received` headers.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ReceivedChain {
+    /// The hops in transit order: the first hop is where the email entered the mail system,
+    /// the last one is the recipient's server.
+    pub hops: Vec<ReceivedHop>,
+    /// The IP address of the client the email originated from: the earliest public address
+    /// in the chain, or the earliest address if all of them are private.
+    ///
+    /// Only the hops added by the recipient's own servers can be trusted; earlier hops may
+    /// have been forged by the sender.
+    pub originating_ip: Option<String>,
+    /// The host name announced by the originating client, if any.
+    pub originating_host: Option<String>,
+    /// Time between the earliest and the latest hop, in milliseconds.
+    pub transit_time_ms: Option<i64>,
+}
+
+impl ReceivedChain {
+    /// Parses the `Received` headers of a message, or returns `None` if it has none.
+    pub fn from_message(message: &Message) -> Option<Self> {
+        let mut hops: Vec<(ReceivedHop, Option<IpAddr>)> = message
+            .header_values(HeaderName::Received)
+            .filter_map(|value| match value {
+                HeaderValue::Received(received) => {
+                    let from_ip = received.from_ip.or(match &received.from {
+                        Some(Host::IpAddr(ip)) => Some(*ip),
+                        _ => None,
+                    });
+                    let hop = ReceivedHop {
+                        from: received.from.as_ref().map(host_to_string),
+                        from_ip: from_ip.map(|ip| ip.to_string()),
+                        by: received.by.as_ref().map(host_to_string),
+                        with: received.with.as_ref().map(|p| p.to_string()),
+                        date: received.date.map(|d| d.to_timestamp() * 1000),
+                    };
+                    Some((hop, from_ip))
+                }
+                _ => None,
+            })
+            .collect();
+        if hops.is_empty() {
+            return None;
+        }
+        // Each server prepends its header, so the newest hop comes first.
+        hops.reverse();
+
+        let origin = hops
+            .iter()
+            .find(|(_, ip)| ip.is_some_and(is_public))
+            .or_else(|| hops.iter().find(|(_, ip)| ip.is_some()));
+        let originating_ip = origin.and_then(|(_, ip)| *ip).map(|ip| ip.to_string());
+        let originating_host = origin.and_then(|(hop, _)| {
+            hop.from
+                .clone()
+                .filter(|from| from.parse::<IpAddr>().is_err())
+        });
+
+        let dates: Vec<i64> = hops.iter().filter_map(|(hop, _)| hop.date).collect();
+        let transit_time_ms = match (dates.iter().min(), dates.iter().max()) {
+            (Some(first), Some(last)) if dates.len() > 1 => Some(last - first),
+            _ => None,
+        };
+
+        Some(Self {
+            hops: hops.into_iter().map(|(hop, _)| hop).collect(),
+            originating_ip,
+            originating_host,
+            transit_time_ms,
+        })
+    }
+}
+
+fn host_to_string(host: &Host) -> String {
+    match host {
+        Host::Name(name) => name.to_string(),
+        Host::IpAddr(ip) => ip.to_string(),
+    }
+}
+
+/// Returns `false` for loopback, private, link-local, shared (CGNAT) and unspecified
+/// addresses, which do not identify a client on the internet.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mail_parser::MessageParser;
+
+    fn chain(headers: &str) -> Option<ReceivedChain> {
+        let raw = format!("{headers}Subject: test\r\n\r\nbody\r\n");
+        let message = MessageParser::new().parse(raw.as_bytes()).unwrap();
+        ReceivedChain::from_message(&message)
+    }
+
+    #[test]
+    fn parses_the_received_chain() {
+        let chain = chain(
+            "Received: from mx.example.org (mx.example.org [203.0.113.9])\r\n\
+             \tby mail.example.com with ESMTPS id a1; Tue, 1 Jul 2025 10:00:05 +0000\r\n\
+             Received: from client.example.net (client.example.net [198.51.100.7])\r\n\
+             \tby mx.example.org with ESMTPSA id b2; Tue, 1 Jul 2025 10:00:00 +0000\r\n\
+             Received: from localhost (localhost [127.0.0.1])\r\n\
+             \tby client.example.net with SMTP id c3; Tue, 1 Jul 2025 09:59:58 +0000\r\n",
+        )
+        .unwrap();
+
+        assert_eq!(chain.hops.len(), 3);
+        assert_eq!(chain.hops[0].from_ip.as_deref(), Some("127.0.0.1"));
+        assert_eq!(chain.hops[2].by.as_deref(), Some("mail.example.com"));
+        assert_eq!(chain.originating_ip.as_deref(), Some("198.51.100.7"));
+        assert_eq!(
+            chain.originating_host.as_deref(),
+            Some("client.example.net")
+        );
+        assert_eq!(chain.transit_time_ms, Some(7000));
+    }
+
+    #[test]
+    fn handles_missing_and_private_chains() {
+        assert_eq!(chain(""), None);
+
+        let chain = chain(
+            "Received: from app01 (app01 [10.0.0.5]) by relay.internal with SMTP;\r\n\
+             \tTue, 1 Jul 2025 10:00:00 +0000\r\n",
+        )
+        .unwrap();
+        assert_eq!(chain.originating_ip.as_deref(), Some("10.0.0.5"));
+        assert_eq!(chain.transit_time_ms, None);
+    }
+
+    #[test]
+    fn classifies_public_addresses() {
+        for ip in ["8.8.8.8", "198.51.100.7", "2001:db8::1"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "10.1.2.3",
+            "192.168.1.1",
+            "100.64.0.1",
+            "::1",
+            "fd00::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+}
@@ -3,7 +3,7 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use poem::http::StatusCode;
-use poem_openapi::Enum;
+use poem_openapi::{Enum, Object};
 
 #[derive(Copy, Clone, Debug, Enum, Eq, PartialEq)]
 #[repr(u32)]
@@ -19,6 +19,8 @@ pub enum ErrorCode {
     PayloadTooLarge = 10070,
     RequestTimeout = 10080,
     MethodNotAllowed = 10090,
+    ContentPolicyViolation = 10100,
+    RangeNotSatisfiable = 10110,
 
     // Authentication and authorization errors (20000–20999)
     PermissionDenied = 20000,
@@ -33,6 +35,8 @@ pub enum ErrorCode {
     ResourceNotFound = 30000,
     AlreadyExists = 30010,
     TooManyRequest = 30020,
+    VersionConflict = 30030,
+    AccountBusy = 30040,
 
     // Network connection errors (40000–40999)
     NetworkError = 40000,
@@ -50,18 +54,172 @@ pub enum ErrorCode {
     AutoconfigFetchFailed = 50060,
     ApiCallFailed = 50070,
     GmailApiInvalidHistoryId = 50080,
+    MailLoopDetected = 50090,
+    RecipientSuppressed = 50100,
 
     // Message queue errors (60000–60999)
     NatsRequestFailed = 60000,
     NatsConnectionFailed = 60010,
     NatsCreateStreamFailed = 60020,
+    KafkaRequestFailed = 60100,
+    KafkaConnectionFailed = 60110,
+    RedisRequestFailed = 60200,
+    RedisConnectionFailed = 60210,
 
     // Internal system errors (70000–70999)
     InternalError = 70000,
     UnhandledPoemError = 70010,
 }
 
+/// An entry of the error catalog, describing one [`ErrorCode`].
+#[derive(Clone, Debug, Eq, PartialEq, Object)]
+pub struct ErrorCodeInfo {
+    /// Numeric code returned in the `code` field of error responses.
+    pub code: u32,
+    /// Stable name of the error code.
+    pub name: String,
+    /// Error category, derived from the code range.
+    pub category: String,
+    /// HTTP status of REST responses carrying this code.
+    pub http_status: u16,
+    /// Whether retrying the same request later may succeed.
+    pub retryable: bool,
+}
+
 impl ErrorCode {
+    /// Every error code, in ascending numeric order. New variants must be added here too.
+    pub const ALL: [ErrorCode; 48] = [
+        ErrorCode::InvalidParameter,
+        ErrorCode::VRLScriptSyntaxError,
+        ErrorCode::MissingConfiguration,
+        ErrorCode::Incompatible,
+        ErrorCode::ExceedsLimitation,
+        ErrorCode::EmlFileParseError,
+        ErrorCode::MissingContentLength,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::RequestTimeout,
+        ErrorCode::MethodNotAllowed,
+        ErrorCode::ContentPolicyViolation,
+        ErrorCode::RangeNotSatisfiable,
+        ErrorCode::PermissionDenied,
+        ErrorCode::AccountDisabled,
+        ErrorCode::LicenseAccountLimitReached,
+        ErrorCode::LicenseExpired,
+        ErrorCode::InvalidLicense,
+        ErrorCode::OAuth2ItemDisabled,
+        ErrorCode::MissingRefreshToken,
+        ErrorCode::ResourceNotFound,
+        ErrorCode::AlreadyExists,
+        ErrorCode::TooManyRequest,
+        ErrorCode::VersionConflict,
+        ErrorCode::AccountBusy,
+        ErrorCode::NetworkError,
+        ErrorCode::ConnectionTimeout,
+        ErrorCode::ConnectionPoolTimeout,
+        ErrorCode::HttpResponseError,
+        ErrorCode::ImapCommandFailed,
+        ErrorCode::ImapAuthenticationFailed,
+        ErrorCode::ImapUnexpectedResult,
+        ErrorCode::SmtpCommandFailed,
+        ErrorCode::SmtpConnectionFailed,
+        ErrorCode::MailBoxNotCached,
+        ErrorCode::AutoconfigFetchFailed,
+        ErrorCode::ApiCallFailed,
+        ErrorCode::GmailApiInvalidHistoryId,
+        ErrorCode::MailLoopDetected,
+        ErrorCode::RecipientSuppressed,
+        ErrorCode::NatsRequestFailed,
+        ErrorCode::NatsConnectionFailed,
+        ErrorCode::NatsCreateStreamFailed,
+        ErrorCode::KafkaRequestFailed,
+        ErrorCode::KafkaConnectionFailed,
+        ErrorCode::RedisRequestFailed,
+        ErrorCode::RedisConnectionFailed,
+        ErrorCode::InternalError,
+        ErrorCode::UnhandledPoemError,
+    ];
+
+    /// Describes every error code, for clients building their error handling.
+    pub fn catalog() -> Vec<ErrorCodeInfo> {
+        Self::ALL
+            .iter()
+            .map(|code| ErrorCodeInfo {
+                code: *code as u32,
+                name: format!("{:?}", code),
+                category: code.category().into(),
+                http_status: code.status().as_u16(),
+                retryable: code.retryable(),
+            })
+            .collect()
+    }
+
+    pub fn category(&self) -> &'static str {
+        match *self as u32 {
+            10000..=10999 => "Client",
+            20000..=20999 => "Authorization",
+            30000..=30999 => "Resource",
+            40000..=40999 => "Network",
+            50000..=50999 => "MailService",
+            60000..=60999 => "MessageQueue",
+            _ => "Internal",
+        }
+    }
+
+    /// Whether the error is usually transient, so the request may succeed when retried
+    /// later (with backoff). Other errors require changing the request or configuration.
+    pub fn retryable(&self) -> bool {
+        match self {
+            ErrorCode::RequestTimeout
+            | ErrorCode::TooManyRequest
+            | ErrorCode::VersionConflict
+            | ErrorCode::AccountBusy
+            | ErrorCode::NetworkError
+            | ErrorCode::ConnectionTimeout
+            | ErrorCode::ConnectionPoolTimeout
+            | ErrorCode::HttpResponseError
+            | ErrorCode::ImapCommandFailed
+            | ErrorCode::ImapUnexpectedResult
+            | ErrorCode::SmtpCommandFailed
+            | ErrorCode::SmtpConnectionFailed
+            | ErrorCode::MailBoxNotCached
+            | ErrorCode::AutoconfigFetchFailed
+            | ErrorCode::ApiCallFailed
+            | ErrorCode::NatsRequestFailed
+            | ErrorCode::NatsConnectionFailed
+            | ErrorCode::NatsCreateStreamFailed
+            | ErrorCode::KafkaRequestFailed
+            | ErrorCode::KafkaConnectionFailed
+            | ErrorCode::RedisRequestFailed
+            | ErrorCode::RedisConnectionFailed => true,
+            ErrorCode::InvalidParameter
+            | ErrorCode::VRLScriptSyntaxError
+            | ErrorCode::MissingConfiguration
+            | ErrorCode::Incompatible
+            | ErrorCode::ExceedsLimitation
+            | ErrorCode::EmlFileParseError
+            | ErrorCode::MissingContentLength
+            | ErrorCode::PayloadTooLarge
+            | ErrorCode::MethodNotAllowed
+            | ErrorCode::ContentPolicyViolation
+            | ErrorCode::RangeNotSatisfiable
+            | ErrorCode::PermissionDenied
+            | ErrorCode::AccountDisabled
+            | ErrorCode::LicenseAccountLimitReached
+            | ErrorCode::LicenseExpired
+            | ErrorCode::InvalidLicense
+            | ErrorCode::OAuth2ItemDisabled
+            | ErrorCode::MissingRefreshToken
+            | ErrorCode::ResourceNotFound
+            | ErrorCode::AlreadyExists
+            | ErrorCode::ImapAuthenticationFailed
+            | ErrorCode::GmailApiInvalidHistoryId
+            | ErrorCode::MailLoopDetected
+            | ErrorCode::RecipientSuppressed
+            | ErrorCode::InternalError
+            | ErrorCode::UnhandledPoemError => false,
+        }
+    }
+
     pub fn status(&self) -> StatusCode {
         match self {
             ErrorCode::InvalidParameter
@@ -78,10 +236,10 @@ impl ErrorCode {
             | ErrorCode::OAuth2ItemDisabled => StatusCode::FORBIDDEN,
             ErrorCode::ResourceNotFound => StatusCode::NOT_FOUND,
             ErrorCode::RequestTimeout => StatusCode::REQUEST_TIMEOUT,
-            ErrorCode::AlreadyExists => StatusCode::CONFLICT,
+            ErrorCode::AlreadyExists | ErrorCode::VersionConflict => StatusCode::CONFLICT,
             ErrorCode::MissingContentLength => StatusCode::LENGTH_REQUIRED,
             ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
-            ErrorCode::TooManyRequest => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::TooManyRequest | ErrorCode::AccountBusy => StatusCode::TOO_MANY_REQUESTS,
             ErrorCode::InternalError
             | ErrorCode::AutoconfigFetchFailed
             | ErrorCode::ImapCommandFailed
@@ -99,9 +257,35 @@ impl ErrorCode {
             | ErrorCode::ConnectionTimeout
             | ErrorCode::ConnectionPoolTimeout
             | ErrorCode::NatsConnectionFailed
+            | ErrorCode::KafkaRequestFailed
+            | ErrorCode::KafkaConnectionFailed
+            | ErrorCode::RedisRequestFailed
+            | ErrorCode::RedisConnectionFailed
             | ErrorCode::UnhandledPoemError
             | ErrorCode::SmtpConnectionFailed => StatusCode::INTERNAL_SERVER_ERROR,
             ErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            ErrorCode::RangeNotSatisfiable => StatusCode::RANGE_NOT_SATISFIABLE,
+            ErrorCode::MailLoopDetected
+            | ErrorCode::RecipientSuppressed
+            | ErrorCode::ContentPolicyViolation => StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_lists_every_code_once() {
+        let catalog = ErrorCode::catalog();
+        assert_eq!(catalog.len(), ErrorCode::ALL.len());
+        assert!(catalog.windows(2).all(|w| w[0].code < w[1].code));
+
+        let entry = catalog.iter().find(|e| e.code == 30020).unwrap();
+        assert_eq!(entry.name, "TooManyRequest");
+        assert_eq!(entry.category, "Resource");
+        assert_eq!(entry.http_status, 429);
+        assert!(entry.retryable);
+    }
+}
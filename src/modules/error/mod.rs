@@ -13,6 +13,7 @@ use snafu::{Location, Snafu};
 
 pub mod code;
 pub mod handler;
+pub mod provider;
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub))]
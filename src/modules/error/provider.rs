@@ -0,0 +1,223 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::fmt;
+
+use poem_openapi::Enum;
+use serde::{Deserialize, Serialize};
+
+/// Common mail provider failures, recognized from the raw protocol errors of IMAP, SMTP,
+/// Gmail API and Microsoft Graph so users get an actionable explanation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, Enum)]
+pub enum ProviderErrorKind {
+    /// The provider blocked the sign-in until the user logs in through its web interface.
+    WebLoginRequired,
+    /// The account uses 2-step verification and needs an app password for IMAP/SMTP.
+    AppPasswordRequired,
+    /// IMAP access is turned off in the mailbox settings.
+    ImapDisabled,
+    /// The provider no longer accepts password (basic) authentication.
+    BasicAuthDisabled,
+    /// A Microsoft Entra ID conditional access or MFA policy blocked the sign-in.
+    ConditionalAccessBlocked,
+    /// The OAuth2 grant expired or was revoked.
+    OAuth2Revoked,
+    /// The username or password was rejected.
+    InvalidCredentials,
+    /// The provider is throttling requests or connections.
+    RateLimited,
+    /// The mailbox is over its storage quota.
+    QuotaExceeded,
+}
+
+/// Lowercase fragments of raw errors identifying each kind. Checked in order, so the
+/// specific kinds come before the generic authentication failure.
+const PATTERNS: [(ProviderErrorKind, &[&str]); 9] = [
+    (
+        ProviderErrorKind::WebLoginRequired,
+        &[
+            "weblogin",
+            "[webalert",
+            "please log in via your web browser",
+            "web login required",
+        ],
+    ),
+    (
+        ProviderErrorKind::AppPasswordRequired,
+        &[
+            "application-specific password required",
+            "app password",
+            "invalidsecondfactor",
+            "534 5.7.9",
+        ],
+    ),
+    (
+        ProviderErrorKind::ImapDisabled,
+        &["not enabled for imap", "imap access is disabled"],
+    ),
+    (
+        ProviderErrorKind::BasicAuthDisabled,
+        &[
+            "basicauthblocked",
+            "basic authentication is disabled",
+            "smtpclientauthentication is disabled",
+            "5.7.139",
+        ],
+    ),
+    (
+        ProviderErrorKind::ConditionalAccessBlocked,
+        &[
+            "aadsts53000",
+            "aadsts53001",
+            "aadsts53003",
+            "aadsts50076",
+            "aadsts50079",
+            "conditional access",
+        ],
+    ),
+    (
+        ProviderErrorKind::OAuth2Revoked,
+        &[
+            "invalid_grant",
+            "aadsts70008",
+            "aadsts700082",
+            "token has been expired or revoked",
+            "missingrefreshtoken",
+            "oauth2itemdisabled",
+        ],
+    ),
+    (
+        ProviderErrorKind::RateLimited,
+        &[
+            "[throttled]",
+            "ratelimitexceeded",
+            "applicationthrottled",
+            "too many simultaneous connections",
+            "bandwidth limits",
+            "429 too many requests",
+            "421 4.7.0",
+            "454 4.7.0",
+        ],
+    ),
+    (
+        ProviderErrorKind::QuotaExceeded,
+        &["[overquota]", "quota exceeded", "552 5.2.2", "mailbox full"],
+    ),
+    (
+        ProviderErrorKind::InvalidCredentials,
+        &[
+            "authenticationfailed",
+            "invalid credentials",
+            "authenticate failed",
+            "login failed",
+            "535 5.7.8",
+            "535 5.7.3",
+            "imapauthenticationfailed",
+        ],
+    ),
+];
+
+impl ProviderErrorKind {
+    /// Recognizes the provider failure described by a raw error message, if any.
+    pub fn classify(error: &str) -> Option<Self> {
+        let error = error.to_lowercase();
+        PATTERNS
+            .iter()
+            .find(|(_, fragments)| fragments.iter().any(|f| error.contains(f)))
+            .map(|(kind, _)| *kind)
+    }
+
+    /// What the user should do to fix the failure.
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            ProviderErrorKind::WebLoginRequired => {
+                "The provider blocked the sign-in. Log in to the mailbox through the provider's \
+                website, complete any security check it asks for, then resume the account."
+            }
+            ProviderErrorKind::AppPasswordRequired => {
+                "The account uses 2-step verification. Create an app password in the provider's \
+                security settings and use it instead of the regular password."
+            }
+            ProviderErrorKind::ImapDisabled => {
+                "IMAP access is disabled for this mailbox. Enable IMAP in the mailbox settings \
+                of the provider."
+            }
+            ProviderErrorKind::BasicAuthDisabled => {
+                "The provider no longer accepts password authentication for this mailbox. \
+                Switch the account to OAuth2, or ask the administrator to enable authenticated \
+                IMAP/SMTP."
+            }
+            ProviderErrorKind::ConditionalAccessBlocked => {
+                "A conditional access or multi-factor authentication policy of the organization \
+                blocked the sign-in. Ask the Microsoft 365 administrator to allow RustMailer's \
+                OAuth2 app, then authorize the account again."
+            }
+            ProviderErrorKind::OAuth2Revoked => {
+                "The OAuth2 authorization expired or was revoked. Authorize the account again \
+                from the OAuth2 page."
+            }
+            ProviderErrorKind::InvalidCredentials => {
+                "The provider rejected the username or password. Check the credentials of the \
+                account and update them."
+            }
+            ProviderErrorKind::RateLimited => {
+                "The provider is throttling this account. RustMailer keeps retrying; reduce the \
+                number of connections or the sync frequency if it persists."
+            }
+            ProviderErrorKind::QuotaExceeded => {
+                "The mailbox is over its storage quota. Free up space or upgrade the storage \
+                plan of the mailbox."
+            }
+        }
+    }
+}
+
+impl fmt::Display for ProviderErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_provider_errors() {
+        let cases = [
+            (
+                "imap client authenticate error: Generic { message: \"[ALERT] Application-specific \
+                password required: https://support.google.com/accounts/answer/185833 (Failure)\" }",
+                ProviderErrorKind::AppPasswordRequired,
+            ),
+            (
+                "[WEBALERT https://accounts.google.com/signin/continue?...] Web login required.",
+                ProviderErrorKind::WebLoginRequired,
+            ),
+            (
+                "AADSTS53003: Access has been blocked by Conditional Access policies.",
+                ProviderErrorKind::ConditionalAccessBlocked,
+            ),
+            (
+                "{\"error\": \"invalid_grant\", \"error_description\": \"Token has been expired or revoked.\"}",
+                ProviderErrorKind::OAuth2Revoked,
+            ),
+            (
+                "[THROTTLED] Account exceeded command or bandwidth limits.",
+                ProviderErrorKind::RateLimited,
+            ),
+            (
+                "imap client authenticate error: [AUTHENTICATIONFAILED] Invalid credentials (Failure)",
+                ProviderErrorKind::InvalidCredentials,
+            ),
+        ];
+        for (error, kind) in cases {
+            assert_eq!(ProviderErrorKind::classify(error), Some(kind), "{error}");
+        }
+        assert_eq!(
+            ProviderErrorKind::classify("imap client connect error: connection refused"),
+            None
+        );
+    }
+}
@@ -0,0 +1,386 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Fault injection for resilience testing, enabled by `rustmailer_fault_injection_enabled`.
+//!
+//! Rules add latency, dropped connections or error responses to the IMAP, SMTP and event
+//! hook operations of specific accounts, so retries, circuit breakers and alerting can be
+//! exercised in staging. Rules are kept in memory and are lost on restart.
+
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use poem_openapi::{Enum, Object};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::modules::context::Initialize;
+use crate::modules::error::{code::ErrorCode, RustMailerError, RustMailerResult};
+use crate::modules::settings::cli::SETTINGS;
+use crate::{id, raise_error, utc_now};
+
+static FAULT_RULES: LazyLock<DashMap<u64, FaultRule>> = LazyLock::new(DashMap::new);
+
+/// The kind of operation a fault rule applies to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Deserialize, Serialize, Enum)]
+pub enum FaultTarget {
+    /// Checking out an IMAP session, before each IMAP command sequence.
+    Imap,
+    /// Sending a queued email over SMTP, through the account or an MTA.
+    Smtp,
+    /// Delivering an event to an event hook, over HTTP or NATS.
+    Hook,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum FaultKind {
+    /// Delays the operation by `latency_ms`, then lets it proceed.
+    Latency,
+    /// Fails the operation as if the connection had been dropped.
+    Disconnect,
+    /// Fails the operation with an error response of `status_code`.
+    ErrorResponse,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Object)]
+pub struct FaultRule {
+    pub id: u64,
+    pub target: FaultTarget,
+    /// The account whose operations are affected; all accounts if unset.
+    pub account_id: Option<u64>,
+    pub kind: FaultKind,
+    /// Delay added by a `Latency` rule, in milliseconds.
+    pub latency_ms: Option<u64>,
+    /// SMTP reply code or HTTP status returned by an `ErrorResponse` rule. IMAP operations
+    /// fail with a `NO` response whatever the code.
+    pub status_code: Option<u16>,
+    /// Share of matching operations affected, between 0 and 1.
+    pub probability: f64,
+    /// Number of injections after which the rule is removed; unlimited if unset.
+    pub max_hits: Option<u64>,
+    /// Number of injections so far.
+    pub hits: u64,
+    /// Time (UNIX epoch milliseconds) after which the rule is removed; never if unset.
+    pub expires_at: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Object)]
+pub struct FaultRuleRequest {
+    pub target: FaultTarget,
+    /// The account whose operations are affected; all accounts if unset.
+    pub account_id: Option<u64>,
+    pub kind: FaultKind,
+    /// Delay added by a `Latency` rule, in milliseconds. Required for `Latency` rules.
+    #[oai(validator(minimum(value = "1"), maximum(value = "600000")))]
+    pub latency_ms: Option<u64>,
+    /// Status of an `ErrorResponse` rule, from 400 to 599. Defaults to `451` for SMTP and
+    /// `503` for hooks.
+    #[oai(validator(minimum(value = "400"), maximum(value = "599")))]
+    pub status_code: Option<u16>,
+    /// Share of matching operations affected, between 0 and 1. Defaults to 1.
+    #[oai(validator(minimum(value = "0"), maximum(value = "1")))]
+    pub probability: Option<f64>,
+    /// Number of injections after which the rule is removed.
+    #[oai(validator(minimum(value = "1")))]
+    pub max_hits: Option<u64>,
+    /// Lifetime of the rule, in seconds.
+    #[oai(validator(minimum(value = "1")))]
+    pub ttl_seconds: Option<u64>,
+}
+
+impl FaultRule {
+    fn new(request: FaultRuleRequest) -> RustMailerResult<Self> {
+        let latency_ms = match request.kind {
+            FaultKind::Latency => Some(request.latency_ms.ok_or_else(|| {
+                raise_error!(
+                    "'latency_ms' is required for a Latency fault".into(),
+                    ErrorCode::InvalidParameter
+                )
+            })?),
+            _ => None,
+        };
+        let status_code = match (request.kind, request.target) {
+            (FaultKind::ErrorResponse, FaultTarget::Smtp) => {
+                Some(request.status_code.unwrap_or(451))
+            }
+            (FaultKind::ErrorResponse, FaultTarget::Hook) => {
+                Some(request.status_code.unwrap_or(503))
+            }
+            _ => None,
+        };
+        if status_code.is_some_and(|code| !(400..=599).contains(&code)) {
+            return Err(raise_error!(
+                "'status_code' of a fault must be between 400 and 599".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let probability = request.probability.unwrap_or(1.0);
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(raise_error!(
+                "'probability' of a fault must be between 0 and 1".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let created_at = utc_now!();
+        Ok(Self {
+            id: id!(64),
+            target: request.target,
+            account_id: request.account_id,
+            kind: request.kind,
+            latency_ms,
+            status_code,
+            probability,
+            max_hits: request.max_hits,
+            hits: 0,
+            expires_at: request
+                .ttl_seconds
+                .map(|ttl| created_at + ttl as i64 * 1000),
+            created_at,
+        })
+    }
+
+    fn applies_to(&self, target: FaultTarget, account_id: u64) -> bool {
+        self.target == target && self.account_id.is_none_or(|id| id == account_id)
+    }
+
+    fn is_spent(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+            || self.max_hits.is_some_and(|max| self.hits >= max)
+    }
+
+    /// The error a failing rule makes the operation return.
+    fn error(&self) -> Option<RustMailerError> {
+        let error = match (self.kind, self.target) {
+            (FaultKind::Latency, _) => return None,
+            (FaultKind::Disconnect, FaultTarget::Imap) => raise_error!(
+                "Connection to the IMAP server was closed (injected fault)".into(),
+                ErrorCode::NetworkError
+            ),
+            (FaultKind::Disconnect, FaultTarget::Smtp) => raise_error!(
+                "Connection to the SMTP server was closed (injected fault)".into(),
+                ErrorCode::SmtpConnectionFailed
+            ),
+            (FaultKind::Disconnect, FaultTarget::Hook) => raise_error!(
+                "Connection to the event hook was closed (injected fault)".into(),
+                ErrorCode::NetworkError
+            ),
+            (FaultKind::ErrorResponse, FaultTarget::Imap) => raise_error!(
+                "NO [UNAVAILABLE] Server unavailable (injected fault)".into(),
+                ErrorCode::ImapCommandFailed
+            ),
+            (FaultKind::ErrorResponse, FaultTarget::Smtp) => {
+                let code = self.status_code.unwrap_or(451);
+                raise_error!(
+                    format!(
+                        "Unexpected reply: {} {}.3.0 Requested action aborted (injected fault)",
+                        code,
+                        code / 100
+                    ),
+                    ErrorCode::SmtpCommandFailed
+                )
+            }
+            (FaultKind::ErrorResponse, FaultTarget::Hook) => {
+                let code = self.status_code.unwrap_or(503);
+                raise_error!(
+                    format!("Error response: {} - injected fault", code),
+                    ErrorCode::HttpResponseError
+                )
+            }
+        };
+        Some(error)
+    }
+}
+
+pub struct FaultInjector;
+
+impl FaultInjector {
+    fn ensure_enabled() -> RustMailerResult<()> {
+        if !SETTINGS.rustmailer_fault_injection_enabled {
+            return Err(raise_error!(
+                "Fault injection is disabled: set rustmailer_fault_injection_enabled to enable it"
+                    .into(),
+                ErrorCode::MissingConfiguration
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn list() -> RustMailerResult<Vec<FaultRule>> {
+        Self::ensure_enabled()?;
+        Self::prune();
+        let mut rules: Vec<FaultRule> = FAULT_RULES.iter().map(|r| r.value().clone()).collect();
+        rules.sort_by_key(|rule| rule.created_at);
+        Ok(rules)
+    }
+
+    pub fn add(request: FaultRuleRequest) -> RustMailerResult<FaultRule> {
+        Self::ensure_enabled()?;
+        let rule = FaultRule::new(request)?;
+        warn!(
+            "Fault injection rule {} added: {:?} {:?} faults for account {:?}",
+            rule.id, rule.target, rule.kind, rule.account_id
+        );
+        FAULT_RULES.insert(rule.id, rule.clone());
+        Ok(rule)
+    }
+
+    pub fn remove(id: u64) -> RustMailerResult<()> {
+        Self::ensure_enabled()?;
+        FAULT_RULES.remove(&id).map(|_| ()).ok_or_else(|| {
+            raise_error!(
+                format!("Fault injection rule {} not found", id),
+                ErrorCode::ResourceNotFound
+            )
+        })
+    }
+
+    pub fn clear() -> RustMailerResult<()> {
+        Self::ensure_enabled()?;
+        FAULT_RULES.clear();
+        Ok(())
+    }
+
+    /// Applies the rules matching an operation: waits for the latency they add, then
+    /// returns the error of the first failing one.
+    pub async fn inject(target: FaultTarget, account_id: u64) -> RustMailerResult<()> {
+        if !SETTINGS.rustmailer_fault_injection_enabled || FAULT_RULES.is_empty() {
+            return Ok(());
+        }
+        let (latency, error) = Self::fire(target, account_id);
+        if !latency.is_zero() {
+            debug!(
+                "Injecting {:?} of latency into {:?} operation of account {}",
+                latency, target, account_id
+            );
+            tokio::time::sleep(latency).await;
+        }
+        match error {
+            Some(error) => {
+                debug!(
+                    "Injecting failure into {:?} operation of account {}: {}",
+                    target, account_id, error
+                );
+                Err(error)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Counts a hit on every matching rule that fires, returning the total latency they
+    /// add and the error of the oldest failing one.
+    fn fire(target: FaultTarget, account_id: u64) -> (Duration, Option<RustMailerError>) {
+        let now = utc_now!();
+        let mut latency = 0;
+        let mut failure: Option<(i64, RustMailerError)> = None;
+        let mut rng = rand::rng();
+        for mut rule in FAULT_RULES.iter_mut() {
+            if !rule.applies_to(target, account_id) || rule.is_spent(now) {
+                continue;
+            }
+            if rule.probability < 1.0 && rng.random::<f64>() >= rule.probability {
+                continue;
+            }
+            rule.hits += 1;
+            latency += rule.latency_ms.unwrap_or(0);
+            if let Some(error) = rule.error() {
+                if failure.as_ref().is_none_or(|(at, _)| rule.created_at < *at) {
+                    failure = Some((rule.created_at, error));
+                }
+            }
+        }
+        Self::prune();
+        (Duration::from_millis(latency), failure.map(|(_, e)| e))
+    }
+
+    /// Removes expired rules and rules that reached their maximum number of hits.
+    fn prune() {
+        let now = utc_now!();
+        FAULT_RULES.retain(|_, rule| !rule.is_spent(now));
+    }
+}
+
+impl Initialize for FaultInjector {
+    async fn initialize() -> RustMailerResult<()> {
+        if SETTINGS.rustmailer_fault_injection_enabled {
+            warn!("Fault injection is enabled: do not use this instance in production");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(target: FaultTarget, kind: FaultKind) -> FaultRuleRequest {
+        FaultRuleRequest {
+            target,
+            account_id: Some(1),
+            kind,
+            latency_ms: None,
+            status_code: None,
+            probability: None,
+            max_hits: None,
+            ttl_seconds: None,
+        }
+    }
+
+    #[test]
+    fn builds_rules() {
+        assert!(FaultRule::new(request(FaultTarget::Imap, FaultKind::Latency)).is_err());
+        let rule = FaultRule::new(FaultRuleRequest {
+            latency_ms: Some(200),
+            ttl_seconds: Some(60),
+            ..request(FaultTarget::Imap, FaultKind::Latency)
+        })
+        .unwrap();
+        assert_eq!(rule.latency_ms, Some(200));
+        assert_eq!(rule.expires_at, Some(rule.created_at + 60_000));
+        assert!(rule.error().is_none());
+
+        let rule = FaultRule::new(request(FaultTarget::Smtp, FaultKind::ErrorResponse)).unwrap();
+        assert_eq!(rule.status_code, Some(451));
+        let error = rule.error().unwrap().to_string();
+        assert!(error.contains("451 4.3.0"), "{}", error);
+
+        let rule = FaultRule::new(FaultRuleRequest {
+            status_code: Some(500),
+            ..request(FaultTarget::Hook, FaultKind::ErrorResponse)
+        })
+        .unwrap();
+        assert!(rule.error().unwrap().to_string().contains("500"));
+        assert!(FaultRule::new(FaultRuleRequest {
+            probability: Some(1.5),
+            ..request(FaultTarget::Hook, FaultKind::Disconnect)
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn matches_and_spends_rules() {
+        let mut rule = FaultRule::new(FaultRuleRequest {
+            account_id: None,
+            max_hits: Some(2),
+            ..request(FaultTarget::Hook, FaultKind::Disconnect)
+        })
+        .unwrap();
+        assert!(rule.applies_to(FaultTarget::Hook, 7));
+        assert!(!rule.applies_to(FaultTarget::Smtp, 7));
+        let now = utc_now!();
+        assert!(!rule.is_spent(now));
+        rule.hits = 2;
+        assert!(rule.is_spent(now));
+
+        let rule = FaultRule::new(FaultRuleRequest {
+            ttl_seconds: Some(1),
+            ..request(FaultTarget::Imap, FaultKind::Disconnect)
+        })
+        .unwrap();
+        assert!(!rule.applies_to(FaultTarget::Imap, 2));
+        assert!(rule.is_spent(rule.created_at + 1000));
+    }
+}
@@ -39,3 +39,12 @@ pub fn require_root<T>(request: Request<T>) -> RustMailerResult<T> {
     context.require_root()?;
     Ok(inner)
 }
+
+/// Returns the context of the client that sent the request.
+pub fn client_context<T>(request: &Request<T>) -> RustMailerResult<Arc<ClientContext>> {
+    request
+        .extensions()
+        .get::<Arc<ClientContext>>()
+        .cloned()
+        .ok_or_else(|| raise_error!("Missing ClientContext".into(), ErrorCode::InternalError))
+}
@@ -37,8 +37,9 @@ impl From<RustMailerError> for Status {
             ErrorCode::ResourceNotFound => Code::NotFound,
             ErrorCode::RequestTimeout => Code::DeadlineExceeded,
             ErrorCode::AlreadyExists => Code::AlreadyExists,
+            ErrorCode::VersionConflict => Code::Aborted,
             ErrorCode::PayloadTooLarge => Code::ResourceExhausted,
-            ErrorCode::TooManyRequest => Code::ResourceExhausted,
+            ErrorCode::TooManyRequest | ErrorCode::AccountBusy => Code::ResourceExhausted,
             ErrorCode::InternalError
             | ErrorCode::AutoconfigFetchFailed
             | ErrorCode::ImapCommandFailed
@@ -56,9 +57,17 @@ impl From<RustMailerError> for Status {
             | ErrorCode::ConnectionTimeout
             | ErrorCode::ConnectionPoolTimeout
             | ErrorCode::NatsConnectionFailed
+            | ErrorCode::KafkaRequestFailed
+            | ErrorCode::KafkaConnectionFailed
+            | ErrorCode::RedisRequestFailed
+            | ErrorCode::RedisConnectionFailed
             | ErrorCode::UnhandledPoemError
             | ErrorCode::SmtpConnectionFailed => Code::Internal,
             ErrorCode::MethodNotAllowed => Code::Unimplemented,
+            ErrorCode::RangeNotSatisfiable => Code::OutOfRange,
+            ErrorCode::MailLoopDetected
+            | ErrorCode::RecipientSuppressed
+            | ErrorCode::ContentPolicyViolation => Code::FailedPrecondition,
         };
 
         let mut metadata = Metadata::new();
@@ -6,5 +6,6 @@ pub mod auth;
 pub mod error;
 pub mod server;
 pub mod service;
+pub mod version;
 #[cfg(test)]
 mod tests;
@@ -14,24 +14,34 @@ use crate::modules::common::timeout::Timeout;
 use crate::modules::common::tls::rustls_config;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::grpc::service::hook::RustMailerEventHooksService;
+use crate::modules::grpc::service::rustmailer_grpc::v2::SendMailServiceServer as SendMailServiceServerV2;
 use crate::modules::grpc::service::rustmailer_grpc::EventHooksServiceServer;
+use crate::modules::grpc::service::v2::send::RustMailerSendMailServiceV2;
+use crate::modules::grpc::version::GrpcDeprecation;
 use crate::modules::settings::cli::CompressionAlgorithm;
 use crate::modules::{
     error::RustMailerResult,
     grpc::service::{
         account::RustMailerAccountService,
         autoconfig::RustMailerAutoConfigService,
+        campaign::RustMailerCampaignService,
+        dead_letter::RustMailerDeadLetterService,
         mailbox::RustMailerMailboxService,
         message::RustMailerMessageService,
         mta::RustMailerMtaService,
         oauth2::RustMailerOAuth2Service,
+        recurring::RustMailerRecurringSendService,
+        retention::RustMailerRetentionService,
         rustmailer_grpc::{
-            AccountServiceServer, AutoConfigServiceServer, MailboxServiceServer,
-            MessageServiceServer, MtaServiceServer, OAuth2ServiceServer, SendMailServiceServer,
-            StatusServiceServer, TemplatesServiceServer, FILE_DESCRIPTOR_SET,
+            AccountServiceServer, AutoConfigServiceServer, CampaignServiceServer,
+            DeadLetterServiceServer, MailboxServiceServer, MessageServiceServer, MtaServiceServer,
+            OAuth2ServiceServer, RecurringSendServiceServer, RetentionServiceServer,
+            SendMailServiceServer, StatusServiceServer, SuppressionServiceServer,
+            TemplatesServiceServer, FILE_DESCRIPTOR_SET,
         },
         send::RustMailerSendMailService,
         status::RustMailerStatusService,
+        suppression::RustMailerSuppressionService,
         template::RustMailerTemplatesService,
     },
     settings::cli::SETTINGS,
@@ -124,7 +134,38 @@ pub async fn start_grpc_server() -> RustMailerResult<()> {
         SendMailServiceServer<RustMailerSendMailService>,
         RustMailerSendMailService
     );
+    route = add_service!(
+        route,
+        SendMailServiceServerV2<RustMailerSendMailServiceV2>,
+        RustMailerSendMailServiceV2
+    );
+    route = add_service!(
+        route,
+        SuppressionServiceServer<RustMailerSuppressionService>,
+        RustMailerSuppressionService
+    );
+    route = add_service!(
+        route,
+        CampaignServiceServer<RustMailerCampaignService>,
+        RustMailerCampaignService
+    );
+    route = add_service!(
+        route,
+        RecurringSendServiceServer<RustMailerRecurringSendService>,
+        RustMailerRecurringSendService
+    );
+    route = add_service!(
+        route,
+        DeadLetterServiceServer<RustMailerDeadLetterService>,
+        RustMailerDeadLetterService
+    );
+    route = add_service!(
+        route,
+        RetentionServiceServer<RustMailerRetentionService>,
+        RustMailerRetentionService
+    );
     let route = route
+        .with(GrpcDeprecation)
         .with(ApiGuard)
         .with(Timeout)
         .with(Tracing)
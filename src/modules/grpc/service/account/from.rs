@@ -4,13 +4,33 @@
 
 use crate::modules::{
     account::{
-        entity::{AuthConfig, AuthType, Encryption, ImapConfig, MailerType, SmtpConfig},
+        entity::{
+            AuthConfig, AuthType, Encryption, ImapConfig, JmapConfig, MailerType, SmtpConfig,
+        },
+        maintenance::{MaintenanceWindow, MaintenanceWindowCreateRequest},
         migration::AccountModel,
         payload::{AccountCreateRequest, AccountUpdateRequest, MinimalAccount},
+        profile::{
+            AccountProfile, AccountProfileApplyError, AccountProfileApplyResult,
+            AccountProfileCreateRequest, AccountProfileSettings, AccountProfileUpdateRequest,
+            ProfileAccountCreateRequest, ProfileAccountCreateResult,
+        },
         since::{DateSince, RelativeDate, Unit},
         status::{AccountError, AccountRunningState},
+        sync_policy::{FolderSyncInterval, SyncPolicy},
+        tags::{AccountBulkEnableRequest, AccountBulkEnableResult},
     },
     grpc::service::rustmailer_grpc,
+    message::attachment_policy::{
+        AttachmentCategory, AttachmentPolicy, InboundAttachmentAction, OutboundAttachmentAction,
+    },
+    rest::response::DataPage,
+    retention::payload::CleanupRuleCreateRequest,
+    smtp::{
+        loop_guard::{LoopAction, LoopProtection},
+        queue::rate::SendRateLimit,
+        sent::SentCopyPolicy,
+    },
 };
 
 impl TryFrom<i32> for Encryption {
@@ -146,6 +166,32 @@ impl From<SmtpConfig> for rustmailer_grpc::SmtpConfig {
     }
 }
 
+impl TryFrom<rustmailer_grpc::JmapConfig> for JmapConfig {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::JmapConfig) -> Result<Self, Self::Error> {
+        Ok(JmapConfig {
+            session_url: value.session_url,
+            username: value.username,
+            auth: AuthConfig::try_from(
+                value
+                    .auth
+                    .ok_or("AuthConfig is not set in JmapConfig, which is required")?,
+            )?,
+        })
+    }
+}
+
+impl From<JmapConfig> for rustmailer_grpc::JmapConfig {
+    fn from(value: JmapConfig) -> Self {
+        rustmailer_grpc::JmapConfig {
+            session_url: value.session_url,
+            username: value.username,
+            auth: Some(value.auth.into()),
+        }
+    }
+}
+
 impl TryFrom<i32> for Unit {
     type Error = &'static str;
 
@@ -217,6 +263,7 @@ impl TryFrom<rustmailer_grpc::Account> for AccountModel {
             id: value.id,
             imap: value.imap.map(|imap| imap.try_into()).transpose()?,
             smtp: value.smtp.map(|smtp| smtp.try_into()).transpose()?,
+            jmap: value.jmap.map(|jmap| jmap.try_into()).transpose()?,
             enabled: value.enabled,
             mailer_type: value.mailer_type.try_into()?,
             email: value.email,
@@ -237,6 +284,17 @@ impl TryFrom<rustmailer_grpc::Account> for AccountModel {
             updated_at: value.updated_at,
             use_proxy: value.use_proxy,
             folder_limit: value.folder_limit,
+            loop_protection: value
+                .loop_protection
+                .map(TryInto::try_into)
+                .transpose()?,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy.map(TryInto::try_into).transpose()?,
+            attachment_policy: value.attachment_policy.map(TryInto::try_into).transpose()?,
+            send_rate_limit: value.send_rate_limit.map(Into::into),
+            sync_policy: value.sync_policy.map(Into::into),
+            workspace_id: value.workspace_id,
         })
     }
 }
@@ -247,6 +305,7 @@ impl From<AccountModel> for rustmailer_grpc::Account {
             id: value.id,
             imap: value.imap.map(|imap| imap.into()),
             smtp: value.smtp.map(|smtp| smtp.into()),
+            jmap: value.jmap.map(|jmap| jmap.into()),
             enabled: value.enabled,
             mailer_type: value.mailer_type.into(),
             email: value.email,
@@ -263,6 +322,14 @@ impl From<AccountModel> for rustmailer_grpc::Account {
             updated_at: value.updated_at,
             use_proxy: value.use_proxy,
             folder_limit: value.folder_limit,
+            loop_protection: value.loop_protection.map(Into::into),
+            message_id_domain: value.message_id_domain,
+            tags: value.tags,
+            sent_copy: value.sent_copy.map(Into::into),
+            attachment_policy: value.attachment_policy.map(Into::into),
+            send_rate_limit: value.send_rate_limit.map(Into::into),
+            sync_policy: value.sync_policy.map(Into::into),
+            workspace_id: value.workspace_id,
         }
     }
 }
@@ -276,6 +343,7 @@ impl TryFrom<rustmailer_grpc::AccountCreateRequest> for AccountCreateRequest {
             name: value.name,
             imap: value.imap.map(|imap| imap.try_into()).transpose()?,
             smtp: value.smtp.map(|smtp| smtp.try_into()).transpose()?,
+            jmap: value.jmap.map(|jmap| jmap.try_into()).transpose()?,
             enabled: value.enabled,
             mailer_type: value.mailer_type.try_into()?,
             date_since: value.date_since.map(|ds| ds.try_into()).transpose()?,
@@ -284,6 +352,17 @@ impl TryFrom<rustmailer_grpc::AccountCreateRequest> for AccountCreateRequest {
             incremental_sync_interval_sec: value.incremental_sync_interval_sec,
             use_proxy: value.use_proxy,
             folder_limit: value.folder_limit,
+            loop_protection: value
+                .loop_protection
+                .map(TryInto::try_into)
+                .transpose()?,
+            message_id_domain: value.message_id_domain,
+            tags: (!value.tags.is_empty()).then_some(value.tags),
+            sent_copy: value.sent_copy.map(TryInto::try_into).transpose()?,
+            attachment_policy: value.attachment_policy.map(TryInto::try_into).transpose()?,
+            send_rate_limit: value.send_rate_limit.map(Into::into),
+            sync_policy: value.sync_policy.map(Into::into),
+            workspace_id: value.workspace_id,
         })
     }
 }
@@ -301,8 +380,19 @@ impl TryFrom<rustmailer_grpc::AccountUpdateRequest> for AccountUpdateRequest {
             incremental_sync_interval_sec: value.incremental_sync_interval_sec,
             imap: value.imap.map(|imap| imap.try_into()).transpose()?,
             smtp: value.smtp.map(|smtp| smtp.try_into()).transpose()?,
+            jmap: value.jmap.map(|jmap| jmap.try_into()).transpose()?,
             use_proxy: value.use_proxy,
             folder_limit: value.folder_limit,
+            loop_protection: value
+                .loop_protection
+                .map(TryInto::try_into)
+                .transpose()?,
+            message_id_domain: value.message_id_domain,
+            tags: value.tags.map(|list| list.tags),
+            sent_copy: value.sent_copy.map(TryInto::try_into).transpose()?,
+            attachment_policy: value.attachment_policy.map(TryInto::try_into).transpose()?,
+            send_rate_limit: value.send_rate_limit.map(Into::into),
+            sync_policy: value.sync_policy.map(Into::into),
         })
     }
 }
@@ -332,6 +422,8 @@ impl From<AccountError> for rustmailer_grpc::AccountError {
         Self {
             error: value.error,
             at: value.at,
+            kind: value.kind.map(|k| k.to_string()),
+            remediation: value.remediation,
         }
     }
 }
@@ -342,6 +434,7 @@ impl From<MinimalAccount> for rustmailer_grpc::MinimalAccount {
             id: value.id,
             email: value.email,
             mailer_type: value.mailer_type.into(),
+            tags: value.tags,
         }
     }
 }
@@ -354,6 +447,7 @@ impl TryFrom<i32> for MailerType {
             0 => Ok(MailerType::ImapSmtp),
             1 => Ok(MailerType::GmailApi),
             2 => Ok(MailerType::GraphApi),
+            3 => Ok(MailerType::Jmap),
             _ => Err("Invalid value for Unit"),
         }
     }
@@ -365,6 +459,453 @@ impl From<MailerType> for i32 {
             MailerType::ImapSmtp => 0,
             MailerType::GmailApi => 1,
             MailerType::GraphApi => 2,
+            MailerType::Jmap => 3,
+        }
+    }
+}
+
+impl TryFrom<i32> for LoopAction {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(LoopAction::Block),
+            1 => Ok(LoopAction::Flag),
+            _ => Err("Invalid value for LoopAction"),
+        }
+    }
+}
+
+impl From<LoopAction> for i32 {
+    fn from(value: LoopAction) -> Self {
+        match value {
+            LoopAction::Block => 0,
+            LoopAction::Flag => 1,
+        }
+    }
+}
+
+impl TryFrom<i32> for SentCopyPolicy {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SentCopyPolicy::Always),
+            1 => Ok(SentCopyPolicy::Auto),
+            2 => Ok(SentCopyPolicy::CheckSent),
+            _ => Err("Invalid value for SentCopyPolicy"),
+        }
+    }
+}
+
+impl From<SentCopyPolicy> for i32 {
+    fn from(value: SentCopyPolicy) -> Self {
+        match value {
+            SentCopyPolicy::Always => 0,
+            SentCopyPolicy::Auto => 1,
+            SentCopyPolicy::CheckSent => 2,
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::LoopProtection> for LoopProtection {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::LoopProtection) -> Result<Self, Self::Error> {
+        Ok(Self {
+            action: value.action.try_into()?,
+            check_managed_recipients: value.check_managed_recipients,
+            max_identical_subjects: value.max_identical_subjects,
+            window_minutes: value.window_minutes,
+        })
+    }
+}
+
+impl From<LoopProtection> for rustmailer_grpc::LoopProtection {
+    fn from(value: LoopProtection) -> Self {
+        Self {
+            action: value.action.into(),
+            check_managed_recipients: value.check_managed_recipients,
+            max_identical_subjects: value.max_identical_subjects,
+            window_minutes: value.window_minutes,
+        }
+    }
+}
+
+impl TryFrom<i32> for OutboundAttachmentAction {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(OutboundAttachmentAction::Reject),
+            1 => Ok(OutboundAttachmentAction::Strip),
+            2 => Ok(OutboundAttachmentAction::Flag),
+            _ => Err("Invalid value for OutboundAttachmentAction"),
+        }
+    }
+}
+
+impl From<OutboundAttachmentAction> for i32 {
+    fn from(value: OutboundAttachmentAction) -> Self {
+        match value {
+            OutboundAttachmentAction::Reject => 0,
+            OutboundAttachmentAction::Strip => 1,
+            OutboundAttachmentAction::Flag => 2,
+        }
+    }
+}
+
+impl TryFrom<i32> for InboundAttachmentAction {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(InboundAttachmentAction::Tag),
+            1 => Ok(InboundAttachmentAction::Quarantine),
+            _ => Err("Invalid value for InboundAttachmentAction"),
+        }
+    }
+}
+
+impl From<InboundAttachmentAction> for i32 {
+    fn from(value: InboundAttachmentAction) -> Self {
+        match value {
+            InboundAttachmentAction::Tag => 0,
+            InboundAttachmentAction::Quarantine => 1,
+        }
+    }
+}
+
+impl TryFrom<i32> for AttachmentCategory {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AttachmentCategory::Executable),
+            1 => Ok(AttachmentCategory::Script),
+            2 => Ok(AttachmentCategory::MacroDocument),
+            3 => Ok(AttachmentCategory::Html),
+            4 => Ok(AttachmentCategory::Blocked),
+            _ => Err("Invalid value for AttachmentCategory"),
+        }
+    }
+}
+
+impl From<AttachmentCategory> for i32 {
+    fn from(value: AttachmentCategory) -> Self {
+        match value {
+            AttachmentCategory::Executable => 0,
+            AttachmentCategory::Script => 1,
+            AttachmentCategory::MacroDocument => 2,
+            AttachmentCategory::Html => 3,
+            AttachmentCategory::Blocked => 4,
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::AttachmentPolicy> for AttachmentPolicy {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::AttachmentPolicy) -> Result<Self, Self::Error> {
+        Ok(Self {
+            outbound: value.outbound.map(TryInto::try_into).transpose()?,
+            inbound: value.inbound.map(TryInto::try_into).transpose()?,
+            categories: value
+                .categories
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            blocked_extensions: value.blocked_extensions,
+            allowed_extensions: value.allowed_extensions,
+            quarantine_mailbox: value.quarantine_mailbox,
+        })
+    }
+}
+
+impl From<AttachmentPolicy> for rustmailer_grpc::AttachmentPolicy {
+    fn from(value: AttachmentPolicy) -> Self {
+        Self {
+            outbound: value.outbound.map(Into::into),
+            inbound: value.inbound.map(Into::into),
+            categories: value.categories.into_iter().map(Into::into).collect(),
+            blocked_extensions: value.blocked_extensions,
+            allowed_extensions: value.allowed_extensions,
+            quarantine_mailbox: value.quarantine_mailbox,
+        }
+    }
+}
+
+impl From<rustmailer_grpc::SendRateLimit> for SendRateLimit {
+    fn from(value: rustmailer_grpc::SendRateLimit) -> Self {
+        Self {
+            max_messages: value.max_messages,
+            period_minutes: value.period_minutes,
+            burst: value.burst,
+        }
+    }
+}
+
+impl From<SendRateLimit> for rustmailer_grpc::SendRateLimit {
+    fn from(value: SendRateLimit) -> Self {
+        Self {
+            max_messages: value.max_messages,
+            period_minutes: value.period_minutes,
+            burst: value.burst,
+        }
+    }
+}
+
+impl From<rustmailer_grpc::FolderSyncInterval> for FolderSyncInterval {
+    fn from(value: rustmailer_grpc::FolderSyncInterval) -> Self {
+        Self {
+            pattern: value.pattern,
+            interval_sec: value.interval_sec,
+        }
+    }
+}
+
+impl From<FolderSyncInterval> for rustmailer_grpc::FolderSyncInterval {
+    fn from(value: FolderSyncInterval) -> Self {
+        Self {
+            pattern: value.pattern,
+            interval_sec: value.interval_sec,
+        }
+    }
+}
+
+impl From<rustmailer_grpc::SyncPolicy> for SyncPolicy {
+    fn from(value: rustmailer_grpc::SyncPolicy) -> Self {
+        Self {
+            include: value.include,
+            exclude: value.exclude,
+            max_message_age_days: value.max_message_age_days,
+            folder_intervals: value.folder_intervals.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<SyncPolicy> for rustmailer_grpc::SyncPolicy {
+    fn from(value: SyncPolicy) -> Self {
+        Self {
+            include: value.include,
+            exclude: value.exclude,
+            max_message_age_days: value.max_message_age_days,
+            folder_intervals: value.folder_intervals.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<rustmailer_grpc::SetAccountsEnabledRequest> for AccountBulkEnableRequest {
+    fn from(value: rustmailer_grpc::SetAccountsEnabledRequest) -> Self {
+        Self {
+            tags: value.tags,
+            enabled: value.enabled,
+            dry_run: value.dry_run,
+        }
+    }
+}
+
+impl From<AccountBulkEnableResult> for rustmailer_grpc::SetAccountsEnabledResponse {
+    fn from(value: AccountBulkEnableResult) -> Self {
+        Self {
+            matched: value.matched,
+            updated: value.updated,
+            dry_run: value.dry_run,
+        }
+    }
+}
+
+impl From<MaintenanceWindow> for rustmailer_grpc::MaintenanceWindow {
+    fn from(value: MaintenanceWindow) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            reason: value.reason,
+            starts_at: value.starts_at,
+            ends_at: value.ends_at,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<rustmailer_grpc::MaintenanceWindowCreateRequest> for MaintenanceWindowCreateRequest {
+    fn from(value: rustmailer_grpc::MaintenanceWindowCreateRequest) -> Self {
+        Self {
+            account_id: value.account_id,
+            reason: value.reason,
+            starts_at: value.starts_at,
+            ends_at: value.ends_at,
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::AccountProfileSettings> for AccountProfileSettings {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::AccountProfileSettings) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sync_folders: (!value.sync_folders.is_empty()).then_some(value.sync_folders),
+            date_since: value.date_since.map(TryInto::try_into).transpose()?,
+            folder_limit: value.folder_limit,
+            minimal_sync: value.minimal_sync,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            use_proxy: value.use_proxy,
+            loop_protection: value
+                .loop_protection
+                .map(TryInto::try_into)
+                .transpose()?,
+            message_id_domain: value.message_id_domain,
+            tags: (!value.tags.is_empty()).then_some(value.tags),
+            sent_copy: value.sent_copy.map(TryInto::try_into).transpose()?,
+            attachment_policy: value.attachment_policy.map(TryInto::try_into).transpose()?,
+            send_rate_limit: value.send_rate_limit.map(Into::into),
+            sync_policy: value.sync_policy.map(Into::into),
+            cleanup_rules: (!value.cleanup_rules.is_empty()).then(|| {
+                value
+                    .cleanup_rules
+                    .into_iter()
+                    .map(|rule| CleanupRuleCreateRequest {
+                        name: rule.name,
+                        mailbox: rule.mailbox,
+                        query: rule.query,
+                        older_than_days: rule.older_than_days,
+                        interval_hours: rule.interval_hours,
+                        max_deletions_per_run: rule.max_deletions_per_run,
+                    })
+                    .collect()
+            }),
+        })
+    }
+}
+
+impl From<AccountProfileSettings> for rustmailer_grpc::AccountProfileSettings {
+    fn from(value: AccountProfileSettings) -> Self {
+        Self {
+            sync_folders: value.sync_folders.unwrap_or_default(),
+            date_since: value.date_since.map(Into::into),
+            folder_limit: value.folder_limit,
+            minimal_sync: value.minimal_sync,
+            full_sync_interval_min: value.full_sync_interval_min,
+            incremental_sync_interval_sec: value.incremental_sync_interval_sec,
+            use_proxy: value.use_proxy,
+            loop_protection: value.loop_protection.map(Into::into),
+            message_id_domain: value.message_id_domain,
+            tags: value.tags.unwrap_or_default(),
+            sent_copy: value.sent_copy.map(Into::into),
+            attachment_policy: value.attachment_policy.map(Into::into),
+            send_rate_limit: value.send_rate_limit.map(Into::into),
+            sync_policy: value.sync_policy.map(Into::into),
+            cleanup_rules: value
+                .cleanup_rules
+                .unwrap_or_default()
+                .into_iter()
+                .map(|rule| rustmailer_grpc::ProfileCleanupRule {
+                    name: rule.name,
+                    mailbox: rule.mailbox,
+                    query: rule.query,
+                    older_than_days: rule.older_than_days,
+                    interval_hours: rule.interval_hours,
+                    max_deletions_per_run: rule.max_deletions_per_run,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<AccountProfile> for rustmailer_grpc::AccountProfile {
+    fn from(value: AccountProfile) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            description: value.description,
+            settings: Some(value.settings.into()),
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<DataPage<AccountProfile>> for rustmailer_grpc::PagedAccountProfile {
+    fn from(value: DataPage<AccountProfile>) -> Self {
+        Self {
+            current_page: value.current_page,
+            page_size: value.page_size,
+            total_items: value.total_items,
+            items: value.items.into_iter().map(Into::into).collect(),
+            total_pages: value.total_pages,
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::AccountProfileCreateRequest> for AccountProfileCreateRequest {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::AccountProfileCreateRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: value.name,
+            description: value.description,
+            settings: value
+                .settings
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl TryFrom<rustmailer_grpc::UpdateAccountProfileRequest> for AccountProfileUpdateRequest {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::UpdateAccountProfileRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: value.name,
+            description: value.description,
+            settings: value.settings.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl TryFrom<rustmailer_grpc::CreateAccountFromProfileRequest> for ProfileAccountCreateRequest {
+    type Error = &'static str;
+
+    fn try_from(
+        value: rustmailer_grpc::CreateAccountFromProfileRequest,
+    ) -> Result<Self, Self::Error> {
+        Ok(Self {
+            email: value.email,
+            name: value.name,
+            mailer_type: value.mailer_type.try_into()?,
+            imap: value.imap.map(TryInto::try_into).transpose()?,
+            smtp: value.smtp.map(TryInto::try_into).transpose()?,
+            jmap: value.jmap.map(TryInto::try_into).transpose()?,
+            enabled: value.enabled,
+            tags: (!value.tags.is_empty()).then_some(value.tags),
+        })
+    }
+}
+
+impl From<ProfileAccountCreateResult> for rustmailer_grpc::CreateAccountFromProfileResponse {
+    fn from(value: ProfileAccountCreateResult) -> Self {
+        Self {
+            account: Some(value.account.into()),
+            cleanup_rules: value.cleanup_rules,
+            warnings: value.warnings,
+        }
+    }
+}
+
+impl From<AccountProfileApplyResult> for rustmailer_grpc::ApplyAccountProfileResponse {
+    fn from(value: AccountProfileApplyResult) -> Self {
+        Self {
+            applied: value.applied,
+            failed: value
+                .failed
+                .into_iter()
+                .map(|AccountProfileApplyError { account_id, error }| {
+                    rustmailer_grpc::AccountProfileApplyError { account_id, error }
+                })
+                .collect(),
         }
     }
 }
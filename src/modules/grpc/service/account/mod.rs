@@ -2,25 +2,37 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::account::maintenance::{
+    MaintenanceWindow as RustMailerMaintenanceWindow,
+    MaintenanceWindowCreateRequest as RustMailerMaintenanceWindowCreateRequest,
+};
 use crate::modules::account::migration::AccountModel as RustMailerAccount;
 use crate::modules::account::payload::filter_accessible_accounts;
 use crate::modules::account::payload::AccountCreateRequest as RustMailerAccountCreateRequest;
 use crate::modules::account::payload::AccountUpdateRequest as RustMailerAccountUpdateRequest;
+use crate::modules::account::profile::{
+    AccountProfile as RustMailerAccountProfile,
+    AccountProfileCreateRequest as RustMailerAccountProfileCreateRequest,
+    AccountProfileUpdateRequest, ProfileAccountCreateRequest,
+};
 use crate::modules::account::status::AccountRunningState as RustMailerAccountRunningState;
+use crate::modules::account::tags::AccountBulkEnableRequest;
 use crate::modules::common::auth::ClientContext;
 use crate::modules::common::paginated::paginate_vec;
-use crate::modules::context::controller::SYNC_CONTROLLER;
 use crate::modules::error::code::ErrorCode;
-use crate::modules::grpc::auth::require_account_access;
+use crate::modules::grpc::auth::{client_context, require_account_access, require_root};
 use crate::modules::grpc::service::rustmailer_grpc::AccountService;
 use crate::modules::grpc::service::rustmailer_grpc::ListMinimalAccountsResponse;
 use crate::modules::grpc::service::rustmailer_grpc::{
-    Account, AccountCreateRequest, AccountId, AccountRunningState, AccountUpdateRequest, Empty,
-    PagedAccount, PaginateRequest,
+    Account, AccountCreateRequest, AccountId, AccountProfile, AccountProfileCreateRequest,
+    AccountProfileId, AccountRunningState, AccountUpdateRequest, ApplyAccountProfileRequest,
+    ApplyAccountProfileResponse, CreateAccountFromProfileRequest, CreateAccountFromProfileResponse,
+    Empty, ListMaintenanceWindowsRequest, ListMaintenanceWindowsResponse, MaintenanceWindow,
+    MaintenanceWindowCreateRequest, MaintenanceWindowRef, PagedAccount, PagedAccountProfile,
+    PaginateRequest, SetAccountsEnabledRequest, SetAccountsEnabledResponse,
+    UpdateAccountProfileRequest,
 };
 use crate::modules::rest::response::DataPage;
-use crate::modules::token::AccessToken;
-use crate::modules::token::AccountInfo;
 use crate::raise_error;
 use poem_grpc::{Request, Response, Status};
 use std::collections::BTreeSet;
@@ -58,21 +70,8 @@ impl AccountService for RustMailerAccountService {
 
         let request = RustMailerAccountCreateRequest::try_from(req)
             .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
-        let entity = request.create_entity()?;
-        entity.clone().save().await?;
-
-        if let Some(access_token) = &context.access_token {
-            let account_info = AccountInfo {
-                id: entity.id.clone(),
-                email: entity.email.clone(),
-            };
-
-            AccessToken::grant_account_access(&access_token.token, account_info).await?;
-        }
-
-        SYNC_CONTROLLER
-            .trigger_start(entity.id.clone(), entity.email.clone())
-            .await;
+        let grant_to = context.access_token.as_ref();
+        let entity = RustMailerAccount::create_account(request, grant_to).await?;
         Ok(Response::new(entity.into()))
     }
 
@@ -82,11 +81,12 @@ impl AccountService for RustMailerAccountService {
     ) -> Result<Response<Empty>, Status> {
         let req = require_account_access(request, |r| r.account_id)?;
         let account_id = req.account_id;
+        let expected_version = req.expected_updated_at;
         let request = RustMailerAccountUpdateRequest::try_from(req)
             .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
         request.validate_update_request()?;
 
-        RustMailerAccount::update(account_id, request, false).await?;
+        RustMailerAccount::update(account_id, request, false, expected_version).await?;
         Ok(Response::new(Empty::default()))
     }
 
@@ -179,4 +179,131 @@ impl AccountService for RustMailerAccountService {
             accounts: result.into_iter().map(Into::into).collect(),
         }))
     }
+
+    async fn set_accounts_enabled(
+        &self,
+        request: Request<SetAccountsEnabledRequest>,
+    ) -> Result<Response<SetAccountsEnabledResponse>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let request: AccountBulkEnableRequest = req.into();
+        let result = request.execute(context).await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn list_maintenance_windows(
+        &self,
+        request: Request<ListMaintenanceWindowsRequest>,
+    ) -> Result<Response<ListMaintenanceWindowsResponse>, Status> {
+        let context = client_context(&request)?;
+        let req = request.into_inner();
+        RustMailerMaintenanceWindow::check_access(&context, req.account_id)?;
+        let windows = RustMailerMaintenanceWindow::list(req.account_id).await?;
+        Ok(Response::new(ListMaintenanceWindowsResponse {
+            windows: windows.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn create_maintenance_window(
+        &self,
+        request: Request<MaintenanceWindowCreateRequest>,
+    ) -> Result<Response<MaintenanceWindow>, Status> {
+        let context = client_context(&request)?;
+        let request: RustMailerMaintenanceWindowCreateRequest = request.into_inner().into();
+        RustMailerMaintenanceWindow::check_access(&context, request.account_id)?;
+        let window = RustMailerMaintenanceWindow::new(request).await?;
+        window.clone().save().await?;
+        Ok(Response::new(window.into()))
+    }
+
+    async fn remove_maintenance_window(
+        &self,
+        request: Request<MaintenanceWindowRef>,
+    ) -> Result<Response<Empty>, Status> {
+        let context = client_context(&request)?;
+        let id = request.into_inner().id;
+        let window = RustMailerMaintenanceWindow::get(id).await?;
+        RustMailerMaintenanceWindow::check_access(&context, window.account_id)?;
+        RustMailerMaintenanceWindow::remove(id).await?;
+        Ok(Response::new(Empty::default()))
+    }
+
+    async fn create_account_profile(
+        &self,
+        request: Request<AccountProfileCreateRequest>,
+    ) -> Result<Response<AccountProfile>, Status> {
+        let req = require_root(request)?;
+        let request = RustMailerAccountProfileCreateRequest::try_from(req)
+            .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let profile = RustMailerAccountProfile::new(request)?;
+        profile.clone().save().await?;
+        Ok(Response::new(profile.into()))
+    }
+
+    async fn list_account_profiles(
+        &self,
+        request: Request<PaginateRequest>,
+    ) -> Result<Response<PagedAccountProfile>, Status> {
+        let req = request.into_inner();
+        let data =
+            RustMailerAccountProfile::paginate_list(req.page, req.page_size, req.desc).await?;
+        Ok(Response::new(data.into()))
+    }
+
+    async fn get_account_profile(
+        &self,
+        request: Request<AccountProfileId>,
+    ) -> Result<Response<AccountProfile>, Status> {
+        let profile = RustMailerAccountProfile::get(request.into_inner().id).await?;
+        Ok(Response::new(profile.into()))
+    }
+
+    async fn update_account_profile(
+        &self,
+        request: Request<UpdateAccountProfileRequest>,
+    ) -> Result<Response<AccountProfile>, Status> {
+        let req = require_root(request)?;
+        let id = req.id;
+        let request = AccountProfileUpdateRequest::try_from(req)
+            .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let profile = RustMailerAccountProfile::update(id, request).await?;
+        Ok(Response::new(profile.into()))
+    }
+
+    async fn remove_account_profile(
+        &self,
+        request: Request<AccountProfileId>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = require_root(request)?;
+        RustMailerAccountProfile::remove(req.id).await?;
+        Ok(Response::new(Empty::default()))
+    }
+
+    async fn create_account_from_profile(
+        &self,
+        request: Request<CreateAccountFromProfileRequest>,
+    ) -> Result<Response<CreateAccountFromProfileResponse>, Status> {
+        let context = client_context(&request)?;
+        let req = request.into_inner();
+        let profile = RustMailerAccountProfile::get(req.profile_id).await?;
+        let request = ProfileAccountCreateRequest::try_from(req)
+            .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let grant_to = context.access_token.as_ref();
+        let result = profile.create_account(request, grant_to).await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn apply_account_profile(
+        &self,
+        request: Request<ApplyAccountProfileRequest>,
+    ) -> Result<Response<ApplyAccountProfileResponse>, Status> {
+        let context = client_context(&request)?;
+        let req = request.into_inner();
+        let profile = RustMailerAccountProfile::get(req.profile_id).await?;
+        let result = profile.apply_to_accounts(req.account_ids, &context).await?;
+        Ok(Response::new(result.into()))
+    }
 }
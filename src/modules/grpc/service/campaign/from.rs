@@ -0,0 +1,119 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::{
+    bounce::classify::BounceStats,
+    grpc::service::rustmailer_grpc,
+    rest::response::DataPage,
+    smtp::campaign::{
+        entity::{Campaign, CampaignFailure},
+        payload::{CampaignCreateRequest, CampaignProgress, CampaignRecipient},
+    },
+    utils::{json_value_to_prost_value, prost_value_to_json_value},
+};
+
+impl TryFrom<rustmailer_grpc::CampaignCreateRequest> for CampaignCreateRequest {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::CampaignCreateRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: value.name,
+            template_id: value.template_id,
+            from: value.from.map(Into::into),
+            recipients: value
+                .recipients
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()?,
+            messages_per_minute: value.messages_per_minute,
+            start_at: value.start_at,
+            send_control: value.send_control.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl TryFrom<rustmailer_grpc::CampaignRecipient> for CampaignRecipient {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::CampaignRecipient) -> Result<Self, Self::Error> {
+        Ok(Self {
+            to: value.to.ok_or("campaign recipient is missing 'to'")?.into(),
+            variables: value.variables.map(prost_value_to_json_value),
+        })
+    }
+}
+
+impl From<Campaign> for rustmailer_grpc::Campaign {
+    fn from(value: Campaign) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            name: value.name,
+            template_id: value.template_id,
+            mta: value.mta,
+            messages_per_minute: value.messages_per_minute,
+            total_recipients: value.total_recipients,
+            queued: value.queued,
+            failures: value.failures.into_iter().map(Into::into).collect(),
+            first_send_at: value.first_send_at,
+            last_send_at: value.last_send_at,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<CampaignFailure> for rustmailer_grpc::CampaignFailure {
+    fn from(value: CampaignFailure) -> Self {
+        Self {
+            recipient: value.recipient,
+            error: value.error,
+        }
+    }
+}
+
+impl From<DataPage<Campaign>> for rustmailer_grpc::PagedCampaign {
+    fn from(value: DataPage<Campaign>) -> Self {
+        Self {
+            current_page: value.current_page,
+            page_size: value.page_size,
+            total_items: value.total_items,
+            items: value.items.into_iter().map(Into::into).collect(),
+            total_pages: value.total_pages,
+        }
+    }
+}
+
+impl From<CampaignProgress> for rustmailer_grpc::CampaignProgress {
+    fn from(value: CampaignProgress) -> Self {
+        Self {
+            scheduled: value.scheduled,
+            running: value.running,
+            success: value.success,
+            failed: value.failed,
+            stopped: value.stopped,
+        }
+    }
+}
+
+impl From<BounceStats> for rustmailer_grpc::CampaignBounceStats {
+    fn from(value: BounceStats) -> Self {
+        Self {
+            total: value.total,
+            hard_bounce: value.hard_bounce,
+            soft_bounce: value.soft_bounce,
+            mailbox_full: value.mailbox_full,
+            spam_block: value.spam_block,
+            policy: value.policy,
+        }
+    }
+}
+
+impl From<CampaignRecipient> for rustmailer_grpc::CampaignRecipient {
+    fn from(value: CampaignRecipient) -> Self {
+        Self {
+            to: Some(value.to.into()),
+            variables: value.variables.map(json_value_to_prost_value),
+        }
+    }
+}
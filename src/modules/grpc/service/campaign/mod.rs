@@ -0,0 +1,117 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::sync::Arc;
+
+use crate::modules::common::auth::ClientContext;
+use crate::modules::error::code::ErrorCode;
+use crate::modules::grpc::auth::require_account_access;
+use crate::modules::grpc::service::rustmailer_grpc::{
+    BulkEmailTaskResult, Campaign, CampaignBounceStats, CampaignProgress, CampaignRef,
+    CampaignService, CreateCampaignRequest, Empty, ListCampaignsRequest, PagedCampaign,
+};
+use crate::modules::smtp::campaign::entity::Campaign as RustMailerCampaign;
+use crate::modules::smtp::campaign::send::{
+    campaign_bounce_stats, campaign_progress, cancel_campaign, launch_campaign,
+};
+use crate::raise_error;
+use poem_grpc::{Request, Response, Status};
+
+pub mod from;
+
+#[derive(Default)]
+pub struct RustMailerCampaignService;
+
+impl CampaignService for RustMailerCampaignService {
+    async fn create_campaign(
+        &self,
+        request: Request<CreateCampaignRequest>,
+    ) -> Result<Response<Campaign>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let campaign_request = req
+            .request
+            .ok_or_else(|| {
+                raise_error!(
+                    "Missing campaign request".into(),
+                    ErrorCode::InvalidParameter
+                )
+            })?
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let campaign = launch_campaign(req.account_id, campaign_request).await?;
+        Ok(Response::new(campaign.into()))
+    }
+
+    async fn list_campaigns(
+        &self,
+        request: Request<ListCampaignsRequest>,
+    ) -> Result<Response<PagedCampaign>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let result = RustMailerCampaign::paginate_list_account(
+            req.account_id,
+            req.page,
+            req.page_size,
+            req.desc,
+        )
+        .await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn get_campaign(
+        &self,
+        request: Request<CampaignRef>,
+    ) -> Result<Response<Campaign>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let campaign = RustMailerCampaign::get(req.account_id, req.id).await?;
+        Ok(Response::new(campaign.into()))
+    }
+
+    async fn get_campaign_progress(
+        &self,
+        request: Request<CampaignRef>,
+    ) -> Result<Response<CampaignProgress>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let campaign = RustMailerCampaign::get(req.account_id, req.id).await?;
+        let progress = campaign_progress(&campaign).await?;
+        Ok(Response::new(progress.into()))
+    }
+
+    async fn get_campaign_bounce_stats(
+        &self,
+        request: Request<CampaignRef>,
+    ) -> Result<Response<CampaignBounceStats>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let campaign = RustMailerCampaign::get(req.account_id, req.id).await?;
+        let stats = campaign_bounce_stats(&campaign).await?;
+        Ok(Response::new(stats.into()))
+    }
+
+    async fn cancel_campaign(
+        &self,
+        request: Request<CampaignRef>,
+    ) -> Result<Response<BulkEmailTaskResult>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+
+        // Get ClientContext from cloned extensions
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        context.require_account_access(req.account_id)?;
+
+        let campaign = RustMailerCampaign::get(req.account_id, req.id).await?;
+        let result = cancel_campaign(context, &campaign).await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn remove_campaign(
+        &self,
+        request: Request<CampaignRef>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let campaign = RustMailerCampaign::get(req.account_id, req.id).await?;
+        RustMailerCampaign::remove(campaign.id).await?;
+        Ok(Response::new(Empty::default()))
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::{
+    grpc::service::rustmailer_grpc,
+    rest::response::DataPage,
+    tasks::dead_letter::{
+        DeadLetter, DeadLetterDetail, DeadLetterFilter, DeadLetterKind, DeadLetterPurgeResult,
+        DeadLetterRequeueResult,
+    },
+};
+
+impl From<DeadLetterKind> for i32 {
+    fn from(value: DeadLetterKind) -> Self {
+        match value {
+            DeadLetterKind::Email => 0,
+            DeadLetterKind::Hook => 1,
+        }
+    }
+}
+
+impl TryFrom<i32> for DeadLetterKind {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(DeadLetterKind::Email),
+            1 => Ok(DeadLetterKind::Hook),
+            _ => Err("Invalid value for DeadLetterKind"),
+        }
+    }
+}
+
+impl From<DeadLetter> for rustmailer_grpc::DeadLetter {
+    fn from(value: DeadLetter) -> Self {
+        Self {
+            id: value.id,
+            kind: value.kind.into(),
+            account_id: value.account_id,
+            account_email: value.account_email,
+            error: value.error,
+            retry_count: value.retry_count.map(|c| c as u64),
+            last_duration_ms: value.last_duration_ms.map(|d| d as u64),
+            task_created_at: value.task_created_at,
+            failed_at: value.failed_at,
+        }
+    }
+}
+
+impl From<DeadLetterDetail> for rustmailer_grpc::DeadLetterDetail {
+    fn from(value: DeadLetterDetail) -> Self {
+        Self {
+            dead_letter: Some(value.dead_letter.into()),
+            email_task: value.email_task.map(Into::into),
+            hook_task: value.hook_task.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::DeadLetterFilter> for DeadLetterFilter {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::DeadLetterFilter) -> Result<Self, Self::Error> {
+        Ok(Self {
+            kind: value.kind.map(TryInto::try_into).transpose()?,
+            account_ids: (!value.account_ids.is_empty()).then_some(value.account_ids),
+            failed_before: value.failed_before,
+        })
+    }
+}
+
+impl From<DeadLetterRequeueResult> for rustmailer_grpc::DeadLetterRequeueResult {
+    fn from(value: DeadLetterRequeueResult) -> Self {
+        Self {
+            task_id: value.task_id,
+        }
+    }
+}
+
+impl From<DeadLetterPurgeResult> for rustmailer_grpc::DeadLetterPurgeResult {
+    fn from(value: DeadLetterPurgeResult) -> Self {
+        Self {
+            purged: value.purged,
+        }
+    }
+}
+
+impl From<DataPage<DeadLetter>> for rustmailer_grpc::PagedDeadLetter {
+    fn from(value: DataPage<DeadLetter>) -> Self {
+        Self {
+            current_page: value.current_page,
+            page_size: value.page_size,
+            total_items: value.total_items,
+            items: value.items.into_iter().map(Into::into).collect(),
+            total_pages: value.total_pages,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::common::auth::ClientContext;
+use crate::modules::error::code::ErrorCode;
+use crate::modules::grpc::auth::client_context;
+use crate::modules::grpc::service::rustmailer_grpc::{
+    DeadLetterDetail, DeadLetterFilter, DeadLetterPurgeResult, DeadLetterRef,
+    DeadLetterRequeueResult, DeadLetterService, Empty, ListDeadLettersRequest, PagedDeadLetter,
+};
+use crate::modules::tasks::dead_letter::{
+    DeadLetter as RustMailerDeadLetter, DeadLetterFilter as RustMailerDeadLetterFilter,
+    DeadLetterKind,
+};
+use crate::raise_error;
+use poem_grpc::{Request, Response, Status};
+
+pub mod from;
+
+#[derive(Default)]
+pub struct RustMailerDeadLetterService;
+
+/// Loads a dead letter, checking that the caller can access its account.
+async fn accessible_dead_letter(
+    context: &ClientContext,
+    id: u64,
+) -> Result<RustMailerDeadLetter, Status> {
+    let letter = RustMailerDeadLetter::get(id).await?;
+    context.require_account_access(letter.account_id)?;
+    Ok(letter)
+}
+
+impl DeadLetterService for RustMailerDeadLetterService {
+    async fn list_dead_letters(
+        &self,
+        request: Request<ListDeadLettersRequest>,
+    ) -> Result<Response<PagedDeadLetter>, Status> {
+        let context = client_context(&request)?;
+        let req = request.into_inner();
+        let filter = RustMailerDeadLetterFilter {
+            kind: req
+                .kind
+                .map(DeadLetterKind::try_from)
+                .transpose()
+                .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?,
+            account_ids: req.account_id.map(|id| vec![id]),
+            failed_before: None,
+        };
+        let result = RustMailerDeadLetter::paginate_list(
+            &context,
+            &filter,
+            req.page,
+            req.page_size,
+            req.desc,
+        )
+        .await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn get_dead_letter(
+        &self,
+        request: Request<DeadLetterRef>,
+    ) -> Result<Response<DeadLetterDetail>, Status> {
+        let context = client_context(&request)?;
+        let letter = accessible_dead_letter(&context, request.into_inner().id).await?;
+        Ok(Response::new(letter.detail()?.into()))
+    }
+
+    async fn requeue_dead_letter(
+        &self,
+        request: Request<DeadLetterRef>,
+    ) -> Result<Response<DeadLetterRequeueResult>, Status> {
+        let context = client_context(&request)?;
+        let letter = accessible_dead_letter(&context, request.into_inner().id).await?;
+        let result = RustMailerDeadLetter::requeue(letter.id).await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn remove_dead_letter(
+        &self,
+        request: Request<DeadLetterRef>,
+    ) -> Result<Response<Empty>, Status> {
+        let context = client_context(&request)?;
+        let letter = accessible_dead_letter(&context, request.into_inner().id).await?;
+        RustMailerDeadLetter::remove(letter.id).await?;
+        Ok(Response::new(Empty::default()))
+    }
+
+    async fn purge_dead_letters(
+        &self,
+        request: Request<DeadLetterFilter>,
+    ) -> Result<Response<DeadLetterPurgeResult>, Status> {
+        let context = client_context(&request)?;
+        let filter: RustMailerDeadLetterFilter = request
+            .into_inner()
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let result = RustMailerDeadLetter::purge(&context, &filter).await?;
+        Ok(Response::new(result.into()))
+    }
+}
@@ -5,15 +5,24 @@
 use crate::modules::{
     grpc::service::rustmailer_grpc::{self},
     hook::{
-        entity::{EventHooks, HookType, HttpConfig, HttpMethod},
+        delivery::{HookDelivery, ReplayedDelivery},
+        entity::{EventHooks, EventVrlScript, HookType, HttpConfig, HttpMethod},
         events::EventType,
+        kafka::{KafkaConfig, KafkaSaslMechanism, KafkaSecurityProtocol, KafkaTopicMode},
         nats::{NatsAuthType, NatsConfig},
-        payload::{EventhookCreateRequest, EventhookUpdateRequest},
+        payload::{EventhookCreateRequest, EventhookUpdateRequest, RotatedHookSecret},
+        redis::RedisConfig,
+        signing::HookSigningSecret,
+        simulate::{EventSimulationRequest, SimulatedEvent},
+        stream::LiveEvent,
         task::SendEventHookTask,
-        vrl::payload::{ResolveResult, VrlScriptTestRequest},
+        vrl::payload::{
+            ResolveResult, VrlSandboxCase, VrlSandboxRequest, VrlSandboxResult,
+            VrlScriptTestRequest,
+        },
     },
     rest::response::DataPage,
-    utils::json_value_to_prost_value,
+    utils::{json_value_to_prost_value, prost_value_to_json_value},
 };
 
 impl From<EventHooks> for rustmailer_grpc::EventHooks {
@@ -29,13 +38,64 @@ impl From<EventHooks> for rustmailer_grpc::EventHooks {
             hook_type: value.hook_type.into(),
             http: value.http.map(Into::into),
             nats: value.nats.map(Into::into),
+            kafka: value.kafka.map(Into::into),
+            redis: value.redis.map(Into::into),
             vrl_script: value.vrl_script,
+            event_vrl_scripts: value
+                .event_vrl_scripts
+                .into_iter()
+                .map(Into::into)
+                .collect(),
             call_count: value.call_count,
             success_count: value.success_count,
             failure_count: value.failure_count,
             last_error: value.last_error,
             watched_events: value.watched_events.into_iter().map(|e| e.into()).collect(),
             global: value.global as u32,
+            account_tags: value.account_tags.unwrap_or_default(),
+            workspace_id: value.workspace_id,
+            signing_secrets: value.signing_secrets.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<EventVrlScript> for rustmailer_grpc::EventVrlScript {
+    fn from(value: EventVrlScript) -> Self {
+        Self {
+            event_type: value.event_type.into(),
+            script: value.script,
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::EventVrlScript> for EventVrlScript {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::EventVrlScript) -> Result<Self, Self::Error> {
+        Ok(Self {
+            event_type: EventType::try_from(value.event_type)?,
+            script: value.script,
+        })
+    }
+}
+
+impl From<HookSigningSecret> for rustmailer_grpc::HookSigningSecret {
+    fn from(value: HookSigningSecret) -> Self {
+        Self {
+            key_id: value.key_id,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+        }
+    }
+}
+
+impl From<RotatedHookSecret> for rustmailer_grpc::RotatedEventHookSecret {
+    fn from(value: RotatedHookSecret) -> Self {
+        Self {
+            key_id: value.key_id,
+            secret: value.secret,
+            previous_key_id: value.previous_key_id,
+            previous_expires_at: value.previous_expires_at,
         }
     }
 }
@@ -45,6 +105,8 @@ impl From<HookType> for i32 {
         match value {
             HookType::Http => 0,
             HookType::Nats => 1,
+            HookType::Kafka => 2,
+            HookType::Redis => 3,
         }
     }
 }
@@ -89,6 +151,68 @@ impl From<NatsConfig> for rustmailer_grpc::NatsConfig {
             password: value.password,
             stream_name: value.stream_name,
             namespace: value.namespace,
+            subject_template: value.subject_template,
+        }
+    }
+}
+
+impl From<KafkaSecurityProtocol> for i32 {
+    fn from(value: KafkaSecurityProtocol) -> Self {
+        match value {
+            KafkaSecurityProtocol::Plaintext => 0,
+            KafkaSecurityProtocol::Ssl => 1,
+            KafkaSecurityProtocol::SaslPlaintext => 2,
+            KafkaSecurityProtocol::SaslSsl => 3,
+        }
+    }
+}
+
+impl From<KafkaSaslMechanism> for i32 {
+    fn from(value: KafkaSaslMechanism) -> Self {
+        match value {
+            KafkaSaslMechanism::Plain => 0,
+            KafkaSaslMechanism::ScramSha256 => 1,
+            KafkaSaslMechanism::ScramSha512 => 2,
+        }
+    }
+}
+
+impl From<KafkaTopicMode> for i32 {
+    fn from(value: KafkaTopicMode) -> Self {
+        match value {
+            KafkaTopicMode::Single => 0,
+            KafkaTopicMode::PerEventType => 1,
+        }
+    }
+}
+
+impl From<KafkaConfig> for rustmailer_grpc::KafkaConfig {
+    fn from(value: KafkaConfig) -> Self {
+        Self {
+            brokers: value.brokers,
+            security_protocol: value.security_protocol.into(),
+            sasl_mechanism: value.sasl_mechanism.into(),
+            username: value.username,
+            password: value.password,
+            ca_certificate: value.ca_certificate,
+            topic_mode: value.topic_mode.into(),
+            topic: value.topic,
+        }
+    }
+}
+
+impl From<RedisConfig> for rustmailer_grpc::RedisConfig {
+    fn from(value: RedisConfig) -> Self {
+        Self {
+            host: value.host,
+            port: value.port as u32,
+            use_tls: value.use_tls,
+            username: value.username,
+            password: value.password,
+            database: value.database as u32,
+            stream_key: value.stream_key,
+            max_len: value.max_len,
+            approximate_trimming: value.approximate_trimming,
         }
     }
 }
@@ -108,6 +232,14 @@ impl From<EventType> for i32 {
             EventType::EmailFeedBackReport => 9,
             EventType::EmailOpened => 10,
             EventType::EmailLinkClicked => 11,
+            EventType::EmailLoopDetected => 12,
+            EventType::AccountAutoPaused => 13,
+            EventType::AttachmentPolicyTriggered => 14,
+            EventType::AccountSyncError => 15,
+            EventType::AccountAuthenticationFailed => 16,
+            EventType::AccessTokenCreated => 17,
+            EventType::AccessTokenRotated => 18,
+            EventType::AccessTokenExpiring => 19,
         }
     }
 }
@@ -123,13 +255,27 @@ impl TryFrom<rustmailer_grpc::CreateEventHookRequest> for EventhookCreateRequest
             hook_type: value.hook_type.try_into()?,
             http: value.http.map(HttpConfig::try_from).transpose()?,
             nats: value.nats.map(NatsConfig::try_from).transpose()?,
+            kafka: value.kafka.map(KafkaConfig::try_from).transpose()?,
+            redis: value.redis.map(Into::into),
             vrl_script: value.vrl_script,
+            event_vrl_scripts: (!value.event_vrl_scripts.is_empty())
+                .then(|| {
+                    value
+                        .event_vrl_scripts
+                        .into_iter()
+                        .map(EventVrlScript::try_from)
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()?,
             watched_events: value
                 .watched_events
                 .into_iter()
                 .map(EventType::try_from)
                 .collect::<Result<Vec<_>, _>>()?,
             use_proxy: value.use_proxy,
+            account_tags: (!value.account_tags.is_empty()).then_some(value.account_tags),
+            workspace_id: value.workspace_id,
+            signing_secret: value.signing_secret,
         })
     }
 }
@@ -141,6 +287,8 @@ impl TryFrom<i32> for HookType {
         match value {
             0 => Ok(HookType::Http),
             1 => Ok(HookType::Nats),
+            2 => Ok(HookType::Kafka),
+            3 => Ok(HookType::Redis),
             _ => Err("Invalid value for HookType"),
         }
     }
@@ -183,6 +331,7 @@ impl TryFrom<rustmailer_grpc::NatsConfig> for NatsConfig {
             password: value.password,
             stream_name: value.stream_name,
             namespace: value.namespace,
+            subject_template: value.subject_template,
         })
     }
 }
@@ -200,6 +349,78 @@ impl TryFrom<i32> for NatsAuthType {
     }
 }
 
+impl TryFrom<rustmailer_grpc::KafkaConfig> for KafkaConfig {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::KafkaConfig) -> Result<Self, Self::Error> {
+        Ok(Self {
+            brokers: value.brokers,
+            security_protocol: value.security_protocol.try_into()?,
+            sasl_mechanism: value.sasl_mechanism.try_into()?,
+            username: value.username,
+            password: value.password,
+            ca_certificate: value.ca_certificate,
+            topic_mode: value.topic_mode.try_into()?,
+            topic: value.topic,
+        })
+    }
+}
+
+impl TryFrom<i32> for KafkaSecurityProtocol {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(KafkaSecurityProtocol::Plaintext),
+            1 => Ok(KafkaSecurityProtocol::Ssl),
+            2 => Ok(KafkaSecurityProtocol::SaslPlaintext),
+            3 => Ok(KafkaSecurityProtocol::SaslSsl),
+            _ => Err("Invalid value for KafkaSecurityProtocol"),
+        }
+    }
+}
+
+impl TryFrom<i32> for KafkaSaslMechanism {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(KafkaSaslMechanism::Plain),
+            1 => Ok(KafkaSaslMechanism::ScramSha256),
+            2 => Ok(KafkaSaslMechanism::ScramSha512),
+            _ => Err("Invalid value for KafkaSaslMechanism"),
+        }
+    }
+}
+
+impl TryFrom<i32> for KafkaTopicMode {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(KafkaTopicMode::Single),
+            1 => Ok(KafkaTopicMode::PerEventType),
+            _ => Err("Invalid value for KafkaTopicMode"),
+        }
+    }
+}
+
+impl From<rustmailer_grpc::RedisConfig> for RedisConfig {
+    fn from(value: rustmailer_grpc::RedisConfig) -> Self {
+        Self {
+            host: value.host,
+            port: value.port as u16,
+            use_tls: value.use_tls,
+            username: value.username,
+            password: value.password,
+            database: value.database as u16,
+            stream_key: value.stream_key,
+            max_len: value.max_len,
+            approximate_trimming: value.approximate_trimming,
+        }
+    }
+}
+
 impl TryFrom<i32> for EventType {
     type Error = &'static str;
 
@@ -217,6 +438,14 @@ impl TryFrom<i32> for EventType {
             9 => Ok(EventType::EmailFeedBackReport),
             10 => Ok(EventType::EmailOpened),
             11 => Ok(EventType::EmailLinkClicked),
+            12 => Ok(EventType::EmailLoopDetected),
+            13 => Ok(EventType::AccountAutoPaused),
+            14 => Ok(EventType::AttachmentPolicyTriggered),
+            15 => Ok(EventType::AccountSyncError),
+            16 => Ok(EventType::AccountAuthenticationFailed),
+            17 => Ok(EventType::AccessTokenCreated),
+            18 => Ok(EventType::AccessTokenRotated),
+            19 => Ok(EventType::AccessTokenExpiring),
             _ => Err("Invalid value for EventType"),
         }
     }
@@ -231,7 +460,18 @@ impl TryFrom<rustmailer_grpc::UpdateEventhookRequest> for EventhookUpdateRequest
             enabled: value.enabled,
             http: value.http.map(HttpConfig::try_from).transpose()?,
             nats: value.nats.map(NatsConfig::try_from).transpose()?,
+            kafka: value.kafka.map(KafkaConfig::try_from).transpose()?,
+            redis: value.redis.map(Into::into),
             vrl_script: value.vrl_script,
+            event_vrl_scripts: value
+                .event_vrl_scripts
+                .map(|list| {
+                    list.scripts
+                        .into_iter()
+                        .map(EventVrlScript::try_from)
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()?,
             watched_events: {
                 if value.watched_events.is_empty() {
                     None
@@ -246,6 +486,7 @@ impl TryFrom<rustmailer_grpc::UpdateEventhookRequest> for EventhookUpdateRequest
                 }
             },
             use_proxy: value.use_proxy,
+            account_tags: value.account_tags.map(|list| list.tags),
         })
     }
 }
@@ -288,6 +529,73 @@ impl From<SendEventHookTask> for rustmailer_grpc::EventHookTask {
             account_id: value.account_id,
             event: Some(json_value_to_prost_value(value.event)),
             event_type: value.event_type.into(),
+            test: value.test,
+        }
+    }
+}
+
+impl From<HookDelivery> for rustmailer_grpc::EventHookDelivery {
+    fn from(value: HookDelivery) -> Self {
+        let event = serde_json::from_str(&value.event).unwrap_or(serde_json::Value::Null);
+        Self {
+            id: value.id,
+            hook_id: value.hook_id,
+            account_id: value.account_id,
+            account_email: value.account_email,
+            global: value.global,
+            task_id: value.task_id,
+            event_type: value.event_type.into(),
+            destination: value.destination.into(),
+            attempt: value.attempt,
+            success: value.success,
+            status_code: value.status_code.map(u32::from),
+            latency_ms: value.latency_ms,
+            response: value.response,
+            error: value.error,
+            event: Some(json_value_to_prost_value(event)),
+            test: value.test,
+            replay_of: value.replay_of,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<DataPage<HookDelivery>> for rustmailer_grpc::PagedEventHookDeliveries {
+    fn from(value: DataPage<HookDelivery>) -> Self {
+        Self {
+            current_page: value.current_page,
+            page_size: value.page_size,
+            total_items: value.total_items,
+            items: value.items.into_iter().map(Into::into).collect(),
+            total_pages: value.total_pages,
+        }
+    }
+}
+
+impl From<ReplayedDelivery> for rustmailer_grpc::ReplayedEventHookDelivery {
+    fn from(value: ReplayedDelivery) -> Self {
+        Self {
+            task_id: value.task_id,
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::SimulateEventRequest> for EventSimulationRequest {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::SimulateEventRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            event_type: EventType::try_from(value.event_type)?,
+            overrides: value.overrides.map(prost_value_to_json_value),
+        })
+    }
+}
+
+impl From<SimulatedEvent> for rustmailer_grpc::SimulatedEvent {
+    fn from(value: SimulatedEvent) -> Self {
+        Self {
+            task_id: value.task_id,
+            event: Some(json_value_to_prost_value(value.event)),
         }
     }
 }
@@ -309,3 +617,61 @@ impl From<ResolveResult> for rustmailer_grpc::ResolveResult {
         }
     }
 }
+
+impl TryFrom<rustmailer_grpc::VrlSandboxRequest> for VrlSandboxRequest {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::VrlSandboxRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            program: value.program,
+            event_types: (!value.event_types.is_empty())
+                .then(|| {
+                    value
+                        .event_types
+                        .into_iter()
+                        .map(EventType::try_from)
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()?,
+            sample: value.sample,
+        })
+    }
+}
+
+impl From<VrlSandboxCase> for rustmailer_grpc::VrlSandboxCase {
+    fn from(value: VrlSandboxCase) -> Self {
+        Self {
+            event_type: value.event_type.map(Into::into),
+            result: value.result.map(json_value_to_prost_value),
+            dropped: value.dropped,
+            error: value.error,
+        }
+    }
+}
+
+impl From<VrlSandboxResult> for rustmailer_grpc::VrlSandboxResult {
+    fn from(value: VrlSandboxResult) -> Self {
+        Self {
+            compile_error: value.compile_error,
+            cases: value.cases.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&LiveEvent> for rustmailer_grpc::RustMailerEvent {
+    fn from(value: &LiveEvent) -> Self {
+        let event = &value.event;
+        let payload = serde_json::to_value(&event.payload).unwrap_or(serde_json::Value::Null);
+        Self {
+            schema_version: event.schema_version,
+            event_id: event.event_id,
+            event_type: event.event_type.clone().into(),
+            instance_url: event.instance_url.clone(),
+            timestamp: event.timestamp,
+            payload: Some(json_value_to_prost_value(payload)),
+            test: event.test,
+            account_id: value.account_id,
+            account_email: value.account_email.clone(),
+        }
+    }
+}
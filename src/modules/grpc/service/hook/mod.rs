@@ -4,19 +4,31 @@
 
 use std::{collections::BTreeSet, sync::Arc};
 
-use poem_grpc::{Request, Response, Status};
+use futures::StreamExt;
+use poem_grpc::{Request, Response, Status, Streaming};
 
 use crate::{
     modules::{
         common::{auth::ClientContext, paginated::paginate_vec},
         error::code::ErrorCode,
         grpc::service::rustmailer_grpc::{
-            CreateEventHookRequest, Empty, EventHookTask, EventHooks, EventHooksService,
-            GetEventHookRequest, GetTaskRequest, ListEventHookRequest, ListTasksRequest,
-            PagedEventHookTask, PagedEventHooks, RemoveEventHookRequest, RemoveTaskRequest,
-            ResolveResult, UpdateEventhookRequest, VrlScriptTestRequest,
+            CreateEventHookRequest, Empty, EventHookDelivery, EventHookTask, EventHooks,
+            EventHooksService, EventStreamRequest, GetEventHookDeliveryRequest,
+            GetEventHookRequest, GetTaskRequest, ListEventHookDeliveriesRequest,
+            ListEventHookRequest, ListTasksRequest, PagedEventHookDeliveries, PagedEventHookTask,
+            PagedEventHooks, RemoveEventHookRequest, RemoveTaskRequest, ReplayedEventHookDelivery,
+            ResolveResult, RotateEventHookSecretRequest, RotatedEventHookSecret, RustMailerEvent,
+            SimulateEventRequest, SimulatedEvent, UpdateEventhookRequest, VrlSandboxRequest,
+            VrlSandboxResult, VrlScriptTestRequest,
+        },
+        hook::{
+            delivery::HookDelivery,
+            events::EVENT_EXAMPLES,
+            payload::{EventhookCreateRequest, RotateHookSecretRequest},
+            simulate::simulate_event,
+            stream::{EventFilter, EVENT_SUBSCRIBERS},
+            vrl::{resolve_vrl_input, run_vrl_sandbox},
         },
-        hook::{events::EVENT_EXAMPLES, vrl::resolve_vrl_input},
         rest::response::DataPage,
         scheduler::model::TaskStatus,
         tasks::queue::RustMailerTaskQueue,
@@ -26,6 +38,7 @@ use crate::{
 };
 
 use crate::modules::hook::entity::EventHooks as RustMailerEventHooks;
+use crate::modules::hook::events::EventType as RustMailerEventType;
 use crate::modules::hook::task::SendEventHookTask as RustMailerQueuedEventHookTask;
 
 mod from;
@@ -57,7 +70,7 @@ impl EventHooksService for RustMailerEventHooksService {
                 context.require_account_access(account_id)?;
             }
             None => {
-                context.require_root()?;
+                context.require_workspace_admin(result.workspace_id)?;
             }
         }
 
@@ -87,7 +100,7 @@ impl EventHooksService for RustMailerEventHooksService {
                 context.require_account_access(account_id)?;
             }
             None => {
-                context.require_root()?;
+                context.require_workspace_admin(result.workspace_id)?;
             }
         }
         RustMailerEventHooks::delete(result.id).await?;
@@ -105,19 +118,19 @@ impl EventHooksService for RustMailerEventHooksService {
         let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
             raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
         })?;
+        let mut req: EventhookCreateRequest = req
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
         match req.account_id {
             Some(account_id) => {
                 context.require_account_access(account_id)?;
             }
             None => {
-                context.require_root()?;
+                req.workspace_id = req.workspace_id.or(context.workspace_id());
+                context.require_workspace_admin(req.workspace_id)?;
             }
         }
-        let entity =
-            RustMailerEventHooks::new(req.try_into().map_err(|e: &'static str| {
-                raise_error!(e.to_string(), ErrorCode::InvalidParameter)
-            })?)
-            .await?;
+        let entity = RustMailerEventHooks::new(req).await?;
         entity.clone().save().await?;
         Ok(Response::new(entity.into()))
     }
@@ -148,7 +161,7 @@ impl EventHooksService for RustMailerEventHooksService {
                 context.require_account_access(account_id)?;
             }
             None => {
-                context.require_root()?;
+                context.require_workspace_admin(hook.workspace_id)?;
             }
         }
         RustMailerEventHooks::update(
@@ -161,6 +174,123 @@ impl EventHooksService for RustMailerEventHooksService {
         Ok(Response::new(Empty::default()))
     }
 
+    async fn rotate_event_hook_secret(
+        &self,
+        request: Request<RotateEventHookSecretRequest>,
+    ) -> Result<Response<RotatedEventHookSecret>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+
+        // Get ClientContext from cloned extensions
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+
+        let hook = RustMailerEventHooks::get_by_id(req.id)
+            .await?
+            .ok_or_else(|| {
+                raise_error!("event hook not found".into(), ErrorCode::ResourceNotFound)
+            })?;
+
+        match hook.account_id {
+            Some(account_id) => {
+                context.require_account_access(account_id)?;
+            }
+            None => {
+                context.require_workspace_admin(hook.workspace_id)?;
+            }
+        }
+        let rotated = RustMailerEventHooks::rotate_secret(
+            hook.id,
+            RotateHookSecretRequest {
+                overlap_seconds: req.overlap_seconds,
+            },
+        )
+        .await?;
+        Ok(Response::new(rotated.into()))
+    }
+
+    async fn simulate_event(
+        &self,
+        request: Request<SimulateEventRequest>,
+    ) -> Result<Response<SimulatedEvent>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+
+        // Get ClientContext from cloned extensions
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+
+        let hook = RustMailerEventHooks::get_by_id(req.id)
+            .await?
+            .ok_or_else(|| {
+                raise_error!("event hook not found".into(), ErrorCode::ResourceNotFound)
+            })?;
+
+        match hook.account_id {
+            Some(account_id) => {
+                context.require_account_access(account_id)?;
+            }
+            None => {
+                context.require_workspace_admin(hook.workspace_id)?;
+            }
+        }
+        let request = req
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let simulated = simulate_event(&hook, request).await?;
+        Ok(Response::new(simulated.into()))
+    }
+
+    async fn list_event_hook_deliveries(
+        &self,
+        request: Request<ListEventHookDeliveriesRequest>,
+    ) -> Result<Response<PagedEventHookDeliveries>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+
+        // Get ClientContext from cloned extensions
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+
+        let result = HookDelivery::list(context, req.id, req.page, req.page_size, req.desc).await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn get_event_hook_delivery(
+        &self,
+        request: Request<GetEventHookDeliveryRequest>,
+    ) -> Result<Response<EventHookDelivery>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+
+        // Get ClientContext from cloned extensions
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+
+        let delivery = HookDelivery::get(context, req.id).await?;
+        Ok(Response::new(delivery.into()))
+    }
+
+    async fn replay_event_hook_delivery(
+        &self,
+        request: Request<GetEventHookDeliveryRequest>,
+    ) -> Result<Response<ReplayedEventHookDelivery>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+
+        // Get ClientContext from cloned extensions
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+
+        let replayed = HookDelivery::replay(context, req.id).await?;
+        Ok(Response::new(replayed.into()))
+    }
+
     async fn list_event_hook(
         &self,
         request: Request<ListEventHookRequest>,
@@ -197,6 +327,45 @@ impl EventHooksService for RustMailerEventHooksService {
         Ok(Response::new(result.into()))
     }
 
+    async fn vrl_script_sandbox(
+        &self,
+        request: Request<VrlSandboxRequest>,
+    ) -> Result<Response<VrlSandboxResult>, Status> {
+        let req = request.into_inner();
+        let request = req
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let result = run_vrl_sandbox(request).await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn event_stream(
+        &self,
+        request: Request<EventStreamRequest>,
+    ) -> Result<Response<Streaming<RustMailerEvent>>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+
+        // Get ClientContext from cloned extensions
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+
+        let event_types = req
+            .event_types
+            .into_iter()
+            .map(RustMailerEventType::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let filter = EventFilter::for_context(context, event_types, req.account_ids)?;
+        let events = EVENT_SUBSCRIBERS
+            .subscribe(filter)
+            .into_stream()
+            .map(|event| RustMailerEvent::from(event.as_ref()))
+            .map(Ok);
+        Ok(Response::new(Streaming::new(events)))
+    }
+
     async fn list_event_hook_tasks(
         &self,
         request: Request<ListTasksRequest>,
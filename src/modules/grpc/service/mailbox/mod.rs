@@ -5,14 +5,15 @@
 use crate::modules::grpc::auth::require_account_access;
 use crate::modules::grpc::service::rustmailer_grpc::{
     CreateMailboxRequest, DeleteMailboxRequest, Empty, ListMailboxesRequest, ListMailboxesResponse,
-    ListSubscribedRequest, MailboxService, MailboxUpdateRequest, SubscribeRequest,
-    UnsubscribeRequest,
+    ListSubscribedRequest, MailboxService, MailboxUpdateRequest, ResyncMailboxRequest,
+    SubscribeRequest, UnsubscribeRequest,
 };
 use crate::modules::mailbox::{
     create::create_mailbox,
     delete::delete_mailbox,
     list::{get_account_mailboxes, list_subscribed_mailboxes},
     rename::update_mailbox,
+    resync::resync_mailbox,
     subscribe::{subscribe_mailbox, unsubscribe_mailbox},
 };
 use poem_grpc::{Request, Response, Status};
@@ -89,4 +90,13 @@ impl MailboxService for RustMailerMailboxService {
         update_mailbox(req.account_id, req.into()).await?;
         Ok(Response::new(Empty::default()))
     }
+
+    async fn resync_mailbox(
+        &self,
+        request: Request<ResyncMailboxRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        resync_mailbox(req.account_id, &req.mailbox_name).await?;
+        Ok(Response::new(Empty::default()))
+    }
 }
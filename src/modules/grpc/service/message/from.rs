@@ -8,21 +8,31 @@ use crate::modules::{
             envelope::Received,
             mailbox::{EmailFlag, EnvelopeFlag},
         },
-        model::Envelope,
+        model::{Envelope, EnvelopeSortField},
     },
-    common::Addr,
+    common::{importance::Importance, Addr},
+    envelope::{
+        authentication::{AuthVerdict, AuthenticationResults, DkimResult, DmarcResult, SpfResult},
+        received::{ReceivedChain, ReceivedHop},
+    },
+    error::{RustMailerError, RustMailerResult},
     grpc::service::rustmailer_grpc::{self},
     imap::section::{EmailBodyPart, Encoding, ImapAttachment, Param, PartType, SegmentPath},
     message::{
         append::AppendReplyToDraftRequest,
         attachment::AttachmentRequest,
+        charset::ContentCharset,
         content::{AttachmentInfo, FullMessageContent, MessageContentRequest, PlainText},
+        conversation::ThreadScope,
         delete::MessageDeleteRequest,
         flag::{FlagAction, FlagMessageRequest},
+        headers::RawHeaders,
         search::payload::{
             Condition, Conditions, Logic, MessageSearch, MessageSearchRequest, Operator,
-            UnifiedSearchRequest,
+            SavedSearchCreateRequest, SavedSearchUpdateRequest, UnifiedSearchRequest,
         },
+        search::query::parse_unified_search,
+        search::saved::SavedSearch,
         transfer::MailboxTransferRequest,
     },
     rest::response::{CursorDataPage, DataPage},
@@ -221,6 +231,10 @@ impl From<Envelope> for rustmailer_grpc::EmailEnvelope {
                 .collect(),
             received: value.received.map(Into::into),
             labels: value.labels,
+            importance: value.importance.map(Into::into),
+            received_chain: value.received_chain.map(Into::into),
+            authentication_results: value.authentication_results.map(Into::into),
+            parse_error: value.parse_error,
         }
     }
 }
@@ -264,6 +278,19 @@ impl TryFrom<i32> for Encoding {
     }
 }
 
+impl TryFrom<i32> for ThreadScope {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ThreadScope::Thread),
+            1 => Ok(ThreadScope::Account),
+            2 => Ok(ThreadScope::AllAccounts),
+            _ => Err("Invalid value for ThreadScope"),
+        }
+    }
+}
+
 impl From<ImapAttachment> for rustmailer_grpc::ImapAttachment {
     fn from(value: ImapAttachment) -> Self {
         Self {
@@ -385,6 +412,84 @@ impl From<Received> for rustmailer_grpc::Received {
     }
 }
 
+impl From<ReceivedHop> for rustmailer_grpc::ReceivedHop {
+    fn from(value: ReceivedHop) -> Self {
+        Self {
+            from: value.from,
+            from_ip: value.from_ip,
+            by: value.by,
+            with: value.with,
+            date: value.date,
+        }
+    }
+}
+
+impl From<ReceivedChain> for rustmailer_grpc::ReceivedChain {
+    fn from(value: ReceivedChain) -> Self {
+        Self {
+            hops: value.hops.into_iter().map(Into::into).collect(),
+            originating_ip: value.originating_ip,
+            originating_host: value.originating_host,
+            transit_time_ms: value.transit_time_ms,
+        }
+    }
+}
+
+impl From<AuthVerdict> for i32 {
+    fn from(value: AuthVerdict) -> Self {
+        match value {
+            AuthVerdict::Pass => 0,
+            AuthVerdict::Fail => 1,
+            AuthVerdict::SoftFail => 2,
+            AuthVerdict::Neutral => 3,
+            AuthVerdict::None => 4,
+            AuthVerdict::TempError => 5,
+            AuthVerdict::PermError => 6,
+            AuthVerdict::Policy => 7,
+        }
+    }
+}
+
+impl From<SpfResult> for rustmailer_grpc::SpfResult {
+    fn from(value: SpfResult) -> Self {
+        Self {
+            result: value.result.into(),
+            domain: value.domain,
+        }
+    }
+}
+
+impl From<DkimResult> for rustmailer_grpc::DkimResult {
+    fn from(value: DkimResult) -> Self {
+        Self {
+            result: value.result.into(),
+            domain: value.domain,
+            selector: value.selector,
+        }
+    }
+}
+
+impl From<DmarcResult> for rustmailer_grpc::DmarcResult {
+    fn from(value: DmarcResult) -> Self {
+        Self {
+            result: value.result.into(),
+            domain: value.domain,
+            policy: value.policy,
+        }
+    }
+}
+
+impl From<AuthenticationResults> for rustmailer_grpc::AuthenticationResults {
+    fn from(value: AuthenticationResults) -> Self {
+        Self {
+            authserv_id: value.authserv_id,
+            spf: value.spf.map(Into::into),
+            dkim: value.dkim.into_iter().map(Into::into).collect(),
+            dmarc: value.dmarc.map(Into::into),
+        }
+    }
+}
+
 impl TryFrom<rustmailer_grpc::FetchMessageContentRequest> for MessageContentRequest {
     type Error = &'static str;
 
@@ -426,6 +531,18 @@ impl From<FullMessageContent> for rustmailer_grpc::MessageContentResponse {
                 .into_iter()
                 .map(Into::into)
                 .collect(),
+            plain_charset: value.plain_charset.map(Into::into),
+            html_charset: value.html_charset.map(Into::into),
+        }
+    }
+}
+
+impl From<ContentCharset> for rustmailer_grpc::ContentCharset {
+    fn from(value: ContentCharset) -> Self {
+        Self {
+            declared: value.declared,
+            charset: value.charset,
+            detected: value.detected,
         }
     }
 }
@@ -479,6 +596,8 @@ impl TryFrom<rustmailer_grpc::FetchMessageAttachmentRequest> for AttachmentReque
             attachment: value.attachment.map(|a| a.try_into()).transpose()?,
             attachment_info: value.attachment_info.map(|a| a.into()),
             filename: value.filename,
+            offset: value.offset,
+            length: value.length,
         })
     }
 }
@@ -511,6 +630,87 @@ impl From<rustmailer_grpc::UnifiedSearchRequest> for UnifiedSearchRequest {
     }
 }
 
+impl TryFrom<rustmailer_grpc::SavedSearchCriteria> for UnifiedSearchRequest {
+    type Error = RustMailerError;
+
+    fn try_from(value: rustmailer_grpc::SavedSearchCriteria) -> RustMailerResult<Self> {
+        match value.query.as_deref() {
+            Some(query) => parse_unified_search(query),
+            None => Ok(Self {
+                accounts: Some(value.accounts).filter(|v| !v.is_empty()),
+                email: value.email,
+                after: value.after,
+                before: value.before,
+            }),
+        }
+    }
+}
+
+impl From<UnifiedSearchRequest> for rustmailer_grpc::SavedSearchCriteria {
+    fn from(value: UnifiedSearchRequest) -> Self {
+        Self {
+            accounts: value.accounts.unwrap_or_default(),
+            email: value.email,
+            after: value.after,
+            before: value.before,
+            query: None,
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::SavedSearchCreateRequest> for SavedSearchCreateRequest {
+    type Error = RustMailerError;
+
+    fn try_from(value: rustmailer_grpc::SavedSearchCreateRequest) -> RustMailerResult<Self> {
+        Ok(Self {
+            name: value.name,
+            description: value.description,
+            search: value.search.unwrap_or_default().try_into()?,
+            within_days: value.within_days,
+        })
+    }
+}
+
+impl TryFrom<rustmailer_grpc::UpdateSavedSearchRequest> for SavedSearchUpdateRequest {
+    type Error = RustMailerError;
+
+    fn try_from(value: rustmailer_grpc::UpdateSavedSearchRequest) -> RustMailerResult<Self> {
+        Ok(Self {
+            name: value.name,
+            description: value.description,
+            search: value.search.map(TryInto::try_into).transpose()?,
+            within_days: value.within_days,
+        })
+    }
+}
+
+impl From<SavedSearch> for rustmailer_grpc::SavedSearch {
+    fn from(value: SavedSearch) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            name: value.name,
+            description: value.description,
+            search: Some(value.search.into()),
+            within_days: value.within_days,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<DataPage<SavedSearch>> for rustmailer_grpc::PagedSavedSearch {
+    fn from(value: DataPage<SavedSearch>) -> Self {
+        Self {
+            current_page: value.current_page,
+            page_size: value.page_size,
+            total_items: value.total_items,
+            items: value.items.into_iter().map(Into::into).collect(),
+            total_pages: value.total_pages,
+        }
+    }
+}
+
 impl TryFrom<rustmailer_grpc::Logic> for Logic {
     type Error = &'static str;
 
@@ -589,6 +789,9 @@ impl TryFrom<i32> for Conditions {
             31 => Ok(Conditions::Unkeyword),
             32 => Ok(Conditions::Unseen),
             33 => Ok(Conditions::GmailSeacrch),
+            34 => Ok(Conditions::Spf),
+            35 => Ok(Conditions::Dkim),
+            36 => Ok(Conditions::Dmarc),
             _ => Err("Invalid value for Conditions"),
         }
     }
@@ -605,14 +808,66 @@ impl TryFrom<rustmailer_grpc::MessageSearchRequest> for MessageSearchRequest {
     }
 }
 
-impl From<rustmailer_grpc::AppendReplyToDraftRequest> for AppendReplyToDraftRequest {
-    fn from(value: rustmailer_grpc::AppendReplyToDraftRequest) -> Self {
-        Self {
+impl TryFrom<rustmailer_grpc::AppendReplyToDraftRequest> for AppendReplyToDraftRequest {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::AppendReplyToDraftRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
             mailbox_name: value.mailbox_name,
             id: value.id,
             preview: value.preview,
             text: value.text,
             html: value.html,
+            subject_prefixes: value.subject_prefixes.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl TryFrom<i32> for Importance {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Importance::Normal),
+            1 => Ok(Importance::High),
+            2 => Ok(Importance::Low),
+            _ => Err("Invalid value for Importance"),
+        }
+    }
+}
+
+impl From<Importance> for i32 {
+    fn from(value: Importance) -> Self {
+        match value {
+            Importance::Normal => 0,
+            Importance::High => 1,
+            Importance::Low => 2,
+        }
+    }
+}
+
+impl TryFrom<i32> for EnvelopeSortField {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EnvelopeSortField::InternalDate),
+            1 => Ok(EnvelopeSortField::Date),
+            2 => Ok(EnvelopeSortField::Size),
+            3 => Ok(EnvelopeSortField::From),
+            4 => Ok(EnvelopeSortField::Subject),
+            _ => Err("Invalid value for EnvelopeSortField"),
+        }
+    }
+}
+
+impl From<RawHeaders> for rustmailer_grpc::RawHeaders {
+    fn from(value: RawHeaders) -> Self {
+        Self {
+            headers: value.headers,
+            headers_base64: value.headers_base64,
+            parse_error: value.parse_error,
+            cached: value.cached,
         }
     }
 }
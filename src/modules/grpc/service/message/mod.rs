@@ -4,38 +4,52 @@
 
 use std::sync::Arc;
 
+use crate::modules::cache::disk::encryption::CacheReader;
+use crate::modules::cache::model::EnvelopeSortField;
 use crate::modules::common::auth::ClientContext;
+use crate::modules::common::importance::Importance;
 use crate::modules::error::code::ErrorCode;
-use crate::modules::grpc::auth::require_account_access;
+use crate::modules::error::RustMailerError;
+use crate::modules::grpc::auth::{client_context, require_account_access};
 use crate::modules::grpc::service::rustmailer_grpc::{
-    AppendReplyToDraftRequest, ByteResponse, CursorDataPage, EmailEnvelopeList,
-    GetThreadMessagesRequest, ListThreadsRequest, MessageContentResponse, PagedMessages,
-    UnifiedSearchRequest,
+    AppendReplyToDraftRequest, ByteChunk, ByteResponse, CursorDataPage, EmailEnvelopeList,
+    GetThreadMessagesRequest, ListSavedSearchesRequest, ListThreadsRequest, MessageContentResponse,
+    PagedMessages, PagedSavedSearch, RunSavedSearchRequest, SavedSearch, SavedSearchCreateRequest,
+    SavedSearchId, UnifiedSearchRequest, UpdateSavedSearchRequest,
 };
 use crate::modules::grpc::service::rustmailer_grpc::{
     Empty, FetchMessageAttachmentRequest, FetchMessageContentRequest, FetchRawMessageRequest,
     FlagMessageRequest, ListMessagesRequest, MailboxTransferRequest, MessageDeleteRequest,
-    MessageSearchRequest, MessageService,
+    MessageSearchRequest, MessageService, RawHeaders, StreamEnvelopesRequest,
 };
 use crate::modules::message::append::AppendReplyToDraftRequest as RustMailerAppendReplyToDraftRequest;
 use crate::modules::message::attachment::retrieve_email_attachment;
 use crate::modules::message::content::retrieve_email_content;
+use crate::modules::message::conversation::{get_conversation, ThreadScope};
 use crate::modules::message::delete::move_to_trash;
 use crate::modules::message::flag::modify_flags;
 use crate::modules::message::flag::FlagMessageRequest as RustMailerFlagMessageRequest;
 use crate::modules::message::full::retrieve_raw_email;
+use crate::modules::message::headers::retrieve_raw_headers;
 use crate::modules::message::list::{
-    get_thread_messages, list_messages_in_mailbox, list_threads_in_mailbox,
+    list_messages_in_mailbox, list_threads_in_mailbox, stream_cached_envelopes,
+    DEFAULT_STREAM_CHUNK_SIZE,
 };
 use crate::modules::message::search::payload::MessageSearchRequest as RustMailerMessageSearchRequest;
 use crate::modules::message::search::payload::UnifiedSearchRequest as RustMailerUnifiedSearchRequest;
+use crate::modules::message::search::query::parse_unified_search;
+use crate::modules::message::search::saved::SavedSearch as RustMailerSavedSearch;
 use crate::modules::message::transfer::{transfer_messages, MessageTransfer};
 use crate::raise_error;
-use poem_grpc::{Request, Response, Status};
+use futures::{future, stream, Stream, TryStreamExt};
+use poem_grpc::{Request, Response, Status, Streaming};
 use tokio::io::AsyncReadExt;
 
 pub mod from;
 
+/// Size of the chunks of streamed attachments and raw messages.
+const BYTE_CHUNK_SIZE: u64 = 64 * 1024;
+
 #[derive(Default)]
 pub struct RustMailerMessageService;
 
@@ -95,6 +109,15 @@ impl MessageService for RustMailerMessageService {
             req.page_size,
             req.remote,
             req.desc,
+            req.importance
+                .map(Importance::try_from)
+                .transpose()
+                .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?,
+            req.sort_by_importance.unwrap_or(false),
+            req.sort_by
+                .map(EnvelopeSortField::try_from)
+                .transpose()
+                .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?,
         )
         .await?;
 
@@ -122,7 +145,7 @@ impl MessageService for RustMailerMessageService {
         request: Request<FetchMessageAttachmentRequest>,
     ) -> Result<Response<ByteResponse>, Status> {
         let req = require_account_access(request, |r| r.account_id)?;
-        let (mut reader, _) = retrieve_email_attachment(
+        let (mut reader, _, _) = retrieve_email_attachment(
             req.account_id,
             req.try_into().map_err(|e: &'static str| {
                 raise_error!(e.to_string(), ErrorCode::InvalidParameter)
@@ -138,6 +161,24 @@ impl MessageService for RustMailerMessageService {
         Ok(Response::new(ByteResponse { data: buffer }))
     }
 
+    async fn fetch_message_attachment_stream(
+        &self,
+        request: Request<FetchMessageAttachmentRequest>,
+    ) -> Result<Response<Streaming<ByteChunk>>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let (reader, filename, range) = retrieve_email_attachment(
+            req.account_id,
+            req.try_into().map_err(|e: &'static str| {
+                raise_error!(e.to_string(), ErrorCode::InvalidParameter)
+            })?,
+        )
+        .await?;
+        let start = range.map_or(0, |range| range.start);
+        Ok(Response::new(Streaming::new(stream_chunks(
+            reader, filename, start,
+        ))))
+    }
+
     async fn fetch_raw_message(
         &self,
         request: Request<FetchRawMessageRequest>,
@@ -155,6 +196,28 @@ impl MessageService for RustMailerMessageService {
         Ok(Response::new(ByteResponse { data: buffer }))
     }
 
+    async fn fetch_raw_message_stream(
+        &self,
+        request: Request<FetchRawMessageRequest>,
+    ) -> Result<Response<Streaming<ByteChunk>>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let reader =
+            retrieve_raw_email(req.account_id, req.mailbox_name.as_deref(), &req.id).await?;
+        Ok(Response::new(Streaming::new(stream_chunks(
+            reader, None, 0,
+        ))))
+    }
+
+    async fn fetch_raw_headers(
+        &self,
+        request: Request<FetchRawMessageRequest>,
+    ) -> Result<Response<RawHeaders>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let headers =
+            retrieve_raw_headers(req.account_id, req.mailbox_name.as_deref(), &req.id).await?;
+        Ok(Response::new(headers.into()))
+    }
+
     async fn message_search(
         &self,
         request: Request<MessageSearchRequest>,
@@ -164,9 +227,15 @@ impl MessageService for RustMailerMessageService {
         let next_page_token = req.next_page_token.clone();
         let page_size = req.page_size;
         let desc = req.desc;
-        let request: RustMailerMessageSearchRequest = req
-            .try_into()
-            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let request = match req.query.as_deref() {
+            Some(query) => {
+                RustMailerMessageSearchRequest::from_query(account_id, query, req.mailbox_name)
+                    .await?
+            }
+            None => req.try_into().map_err(|e: &'static str| {
+                raise_error!(e.to_string(), ErrorCode::InvalidParameter)
+            })?,
+        };
 
         let result = request
             .search_impl(
@@ -194,28 +263,37 @@ impl MessageService for RustMailerMessageService {
         let page_size = req.page_size;
         let desc = req.desc;
 
-        let mut request: RustMailerUnifiedSearchRequest = req.into();
-
-        match &mut request.accounts {
-            Some(accounts) => {
-                for &account_id in accounts.iter() {
-                    context.require_account_access(account_id)?;
-                }
-            }
-            None => {
-                if !context.is_root {
-                    // Inject accessible accounts if not specified
-                    if let Some(accessible) = context.accessible_accounts()? {
-                        let account_ids = accessible.iter().map(|a| a.id).collect::<Vec<u64>>();
-                        request.accounts = Some(account_ids);
-                    }
-                }
-            }
-        }
+        let mut request: RustMailerUnifiedSearchRequest = match req.query.as_deref() {
+            Some(query) => parse_unified_search(query)?,
+            None => req.into(),
+        };
+        request.restrict_to(context)?;
         let result = request.search(page, page_size, desc).await?;
         Ok(Response::new(result.into()))
     }
 
+    async fn stream_envelopes(
+        &self,
+        request: Request<StreamEnvelopesRequest>,
+    ) -> Result<Response<Streaming<EmailEnvelopeList>>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+
+        let chunks = stream_cached_envelopes(
+            req.account_id,
+            req.mailbox_name,
+            req.since,
+            req.chunk_size.unwrap_or(DEFAULT_STREAM_CHUNK_SIZE),
+        )
+        .await?;
+        let chunks = chunks
+            .try_filter(|items| future::ready(!items.is_empty()))
+            .map_ok(|items| EmailEnvelopeList {
+                items: items.into_iter().map(Into::into).collect(),
+            })
+            .map_err(Status::from);
+        Ok(Response::new(Streaming::new(chunks)))
+    }
+
     async fn list_threads(
         &self,
         request: Request<ListThreadsRequest>,
@@ -237,8 +315,15 @@ impl MessageService for RustMailerMessageService {
         &self,
         request: Request<GetThreadMessagesRequest>,
     ) -> Result<Response<EmailEnvelopeList>, Status> {
+        let context = client_context(&request)?;
         let req = require_account_access(request, |r| r.account_id)?;
-        let envelopes = get_thread_messages(req.account_id, req.thread_id).await?;
+        let scope = req
+            .scope
+            .map(ThreadScope::try_from)
+            .transpose()
+            .map_err(|e| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?
+            .unwrap_or_default();
+        let envelopes = get_conversation(&context, req.account_id, req.thread_id, scope).await?;
 
         Ok(Response::new(EmailEnvelopeList {
             items: envelopes.into_iter().map(|e| e.into()).collect(),
@@ -251,8 +336,140 @@ impl MessageService for RustMailerMessageService {
     ) -> Result<Response<Empty>, Status> {
         let req = require_account_access(request, |r| r.account_id)?;
         let account_id = req.account_id;
-        let request: RustMailerAppendReplyToDraftRequest = req.into();
+        let request: RustMailerAppendReplyToDraftRequest = req
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
         request.append_reply_to_draft(account_id).await?;
         Ok(Response::new(Empty::default()))
     }
+
+    async fn create_saved_search(
+        &self,
+        request: Request<SavedSearchCreateRequest>,
+    ) -> Result<Response<SavedSearch>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let saved = RustMailerSavedSearch::new(req.account_id, req.try_into()?)?;
+        saved.clone().save().await?;
+        Ok(Response::new(saved.into()))
+    }
+
+    async fn list_saved_searches(
+        &self,
+        request: Request<ListSavedSearchesRequest>,
+    ) -> Result<Response<PagedSavedSearch>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let result =
+            RustMailerSavedSearch::paginate_list(req.account_id, req.page, req.page_size, req.desc)
+                .await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn get_saved_search(
+        &self,
+        request: Request<SavedSearchId>,
+    ) -> Result<Response<SavedSearch>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let saved = RustMailerSavedSearch::get(req.account_id, req.id).await?;
+        Ok(Response::new(saved.into()))
+    }
+
+    async fn update_saved_search(
+        &self,
+        request: Request<UpdateSavedSearchRequest>,
+    ) -> Result<Response<SavedSearch>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let (account_id, id) = (req.account_id, req.id);
+        let updated = RustMailerSavedSearch::update(account_id, id, req.try_into()?).await?;
+        Ok(Response::new(updated.into()))
+    }
+
+    async fn remove_saved_search(
+        &self,
+        request: Request<SavedSearchId>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let saved = RustMailerSavedSearch::get(req.account_id, req.id).await?;
+        RustMailerSavedSearch::remove(saved.id).await?;
+        Ok(Response::new(Empty::default()))
+    }
+
+    async fn run_saved_search(
+        &self,
+        request: Request<RunSavedSearchRequest>,
+    ) -> Result<Response<PagedMessages>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let saved = RustMailerSavedSearch::get(req.account_id, req.id).await?;
+        let result = saved.run(req.page, req.page_size, req.desc).await?;
+        Ok(Response::new(result.into()))
+    }
+}
+
+/// Streams the content of `reader` in chunks, setting `filename` on the first one.
+/// Chunk offsets start at `start`, the offset of the content within the whole file.
+///
+/// At least one chunk is sent, so that empty content still carries the file name.
+fn stream_chunks(
+    reader: CacheReader,
+    filename: Option<String>,
+    start: u64,
+) -> impl Stream<Item = Result<ByteChunk, Status>> + Send + 'static {
+    stream::try_unfold(Some((reader, start, filename)), move |state| async move {
+        let Some((mut reader, offset, filename)) = state else {
+            return Ok(None);
+        };
+        let mut data = Vec::with_capacity(BYTE_CHUNK_SIZE as usize);
+        (&mut reader)
+            .take(BYTE_CHUNK_SIZE)
+            .read_to_end(&mut data)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        if data.is_empty() && offset > start {
+            return Ok(None);
+        }
+        let len = data.len() as u64;
+        // A short chunk is the last one.
+        let next = (len == BYTE_CHUNK_SIZE).then_some((reader, offset + len, None));
+        Ok(Some((
+            ByteChunk {
+                data,
+                offset,
+                filename,
+            },
+            next,
+        )))
+    })
+    .map_err(|e: RustMailerError| Status::from(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    async fn chunks(len: usize) -> Vec<ByteChunk> {
+        let content: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let reader = CacheReader::Decrypted(Cursor::new(content));
+        stream_chunks(reader, Some("a.bin".into()), 0)
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn streams_content_in_chunks() {
+        let size = BYTE_CHUNK_SIZE as usize;
+        let result = chunks(2 * size + 10).await;
+        let offsets: Vec<u64> = result.iter().map(|c| c.offset).collect();
+        assert_eq!(offsets, vec![0, size as u64, 2 * size as u64]);
+        assert_eq!(result[2].data.len(), 10);
+        assert_eq!(result[0].filename.as_deref(), Some("a.bin"));
+        assert_eq!(result[1].filename, None);
+
+        assert_eq!(chunks(size).await.len(), 1);
+        let empty = chunks(0).await;
+        assert_eq!(empty.len(), 1);
+        assert!(empty[0].data.is_empty());
+        assert_eq!(empty[0].filename.as_deref(), Some("a.bin"));
+    }
 }
@@ -4,17 +4,27 @@
 
 pub mod account;
 pub mod autoconfig;
+pub mod campaign;
+pub mod dead_letter;
 pub mod hook;
 pub mod mailbox;
 pub mod message;
 pub mod mta;
 pub mod oauth2;
+pub mod recurring;
+pub mod retention;
 pub mod send;
 pub mod status;
+pub mod suppression;
 pub mod template;
+pub mod v2;
 
 pub mod rustmailer_grpc {
     poem_grpc::include_proto!("rustmailer.grpc");
     pub const FILE_DESCRIPTOR_SET: &[u8] =
         poem_grpc::include_file_descriptor_set!("rustmailer.bin");
+
+    pub mod v2 {
+        poem_grpc::include_proto!("rustmailer.grpc.v2");
+    }
 }
@@ -66,6 +66,8 @@ impl TryFrom<rustmailer_grpc::MtaCreateRequest> for MTACreateRequest {
             server: value.server.ok_or("field 'server' missing")?.try_into()?,
             dsn_capable: value.dsn_capable,
             use_proxy: value.use_proxy,
+            send_rate_limit: value.send_rate_limit.map(Into::into),
+            workspace_id: value.workspace_id,
         })
     }
 }
@@ -80,6 +82,7 @@ impl TryFrom<rustmailer_grpc::MtaUpdateRequest> for MTAUpdateRequest {
             server: value.server.map(SmtpServerConfig::try_from).transpose()?,
             dsn_capable: value.dsn_capable,
             use_proxy: value.use_proxy,
+            send_rate_limit: value.send_rate_limit.map(Into::into),
         })
     }
 }
@@ -96,6 +99,8 @@ impl From<Mta> for rustmailer_grpc::Mta {
             updated_at: value.updated_at,
             last_access_at: value.last_access_at,
             use_proxy: value.use_proxy,
+            send_rate_limit: value.send_rate_limit.map(Into::into),
+            workspace_id: value.workspace_id,
         }
     }
 }
@@ -2,6 +2,9 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use std::sync::Arc;
+
+use crate::modules::common::auth::ClientContext;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::grpc::auth::require_root;
 use crate::modules::grpc::service::rustmailer_grpc::SendTestEmailRequest;
@@ -10,6 +13,7 @@ use crate::modules::grpc::service::rustmailer_grpc::{
     MtaUpdateRequest, PagedMta,
 };
 use crate::modules::smtp::mta::entity::Mta as RustMailerMta;
+use crate::modules::smtp::mta::payload::MTACreateRequest;
 use crate::modules::smtp::mta::send::send_test_email;
 use crate::raise_error;
 use poem_grpc::{Request, Response, Status};
@@ -21,9 +25,14 @@ pub struct RustMailerMtaService;
 
 impl MtaService for RustMailerMtaService {
     async fn get_mta(&self, request: Request<GetMtaRequest>) -> Result<Response<Mta>, Status> {
+        let extensions = request.extensions().clone();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
         let mta = RustMailerMta::get(request.into_inner().id)
             .await?
             .ok_or_else(|| raise_error!("mta not found".into(), ErrorCode::ResourceNotFound))?;
+        context.require_workspace_access(mta.workspace_id)?;
         Ok(Response::new(mta.into()))
     }
 
@@ -31,7 +40,12 @@ impl MtaService for RustMailerMtaService {
         &self,
         request: Request<DeleteMtaRequest>,
     ) -> Result<Response<Empty>, Status> {
-        let req = require_root(request)?;
+        let extensions = request.extensions().clone();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let req = request.into_inner();
+        context.require_workspace_admin(RustMailerMta::workspace_of(req.id).await?)?;
         RustMailerMta::delete(req.id).await?;
         Ok(Response::new(Empty::default()))
     }
@@ -40,10 +54,17 @@ impl MtaService for RustMailerMtaService {
         &self,
         request: Request<MtaCreateRequest>,
     ) -> Result<Response<Empty>, Status> {
-        let req = require_root(request)?;
-        let entity = RustMailerMta::new(req.try_into().map_err(|e: &'static str| {
-            raise_error!(e.to_string(), ErrorCode::InvalidParameter)
-        })?)?;
+        let extensions = request.extensions().clone();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let mut req: MTACreateRequest = request
+            .into_inner()
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        req.workspace_id = req.workspace_id.or(context.workspace_id());
+        context.require_workspace_admin(req.workspace_id)?;
+        let entity = RustMailerMta::new(req)?;
         entity.save().await?;
         Ok(Response::new(Empty::default()))
     }
@@ -52,7 +73,12 @@ impl MtaService for RustMailerMtaService {
         &self,
         request: Request<MtaUpdateRequest>,
     ) -> Result<Response<Empty>, Status> {
-        let req = require_root(request)?;
+        let extensions = request.extensions().clone();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let req = request.into_inner();
+        context.require_workspace_admin(RustMailerMta::workspace_of(req.id).await?)?;
         RustMailerMta::update(
             req.id,
             req.try_into().map_err(|e: &'static str| {
@@ -67,9 +93,18 @@ impl MtaService for RustMailerMtaService {
         &self,
         request: Request<ListMtaRequest>,
     ) -> Result<Response<PagedMta>, Status> {
+        let extensions = request.extensions().clone();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
         let request = request.into_inner();
-        let result =
-            RustMailerMta::paginate_list(request.page, request.page_size, request.desc).await?;
+        let result = RustMailerMta::paginate_list_visible(
+            context,
+            request.page,
+            request.page_size,
+            request.desc,
+        )
+        .await?;
         Ok(Response::new(result.into()))
     }
 
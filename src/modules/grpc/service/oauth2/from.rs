@@ -6,6 +6,7 @@ use crate::modules::{
     grpc::service::rustmailer_grpc::{self, PagedOAuth2},
     oauth2::{
         entity::{OAuth2, OAuth2CreateRequest, OAuth2UpdateRequest},
+        pending::OAuth2PendingFlow,
         token::{ExternalOAuth2Request, OAuth2AccessToken},
     },
     rest::response::DataPage,
@@ -105,3 +106,14 @@ impl From<rustmailer_grpc::ExternalOAuth2Request> for ExternalOAuth2Request {
         }
     }
 }
+
+impl From<OAuth2PendingFlow> for rustmailer_grpc::OAuth2PendingFlow {
+    fn from(value: OAuth2PendingFlow) -> Self {
+        Self {
+            oauth2_id: value.oauth2_id,
+            account_id: value.account_id,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+        }
+    }
+}
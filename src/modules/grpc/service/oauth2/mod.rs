@@ -6,11 +6,12 @@ use crate::modules::error::code::ErrorCode;
 use crate::modules::grpc::auth::{require_account_access, require_root};
 use crate::modules::grpc::service::rustmailer_grpc::{
     AuthorizeUrlRequest, AuthorizeUrlResponse, DeleteOAuth2Request, Empty, ExternalOAuth2Request,
-    GetOAuth2Request, GetOAuth2TokensRequest, ListOAuth2Request, OAuth2, OAuth2AccessToken,
-    OAuth2CreateRequest, OAuth2Service, PagedOAuth2, UpdateOAuth2Request,
+    GetOAuth2Request, GetOAuth2TokensRequest, ListOAuth2PendingFlowsRequest,
+    ListOAuth2PendingFlowsResponse, ListOAuth2Request, OAuth2, OAuth2AccessToken,
+    OAuth2CreateRequest, OAuth2Service, PagedOAuth2, ReauthorizeUrlRequest, UpdateOAuth2Request,
 };
 use crate::modules::oauth2::{
-    entity::OAuth2 as RustMailerOAuth2, flow::OAuth2Flow,
+    entity::OAuth2 as RustMailerOAuth2, flow::OAuth2Flow, pending::OAuth2PendingEntity,
     token::OAuth2AccessToken as RustMailerOAuth2AccessToken,
 };
 use crate::raise_error;
@@ -83,6 +84,15 @@ impl OAuth2Service for RustMailerOAuth2Service {
         Ok(Response::new(AuthorizeUrlResponse { url }))
     }
 
+    async fn create_reauthorize_url(
+        &self,
+        request: Request<ReauthorizeUrlRequest>,
+    ) -> Result<Response<AuthorizeUrlResponse>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let url = OAuth2Flow::reauthorize_url(req.account_id).await?;
+        Ok(Response::new(AuthorizeUrlResponse { url }))
+    }
+
     async fn get_o_auth2_tokens(
         &self,
         request: Request<GetOAuth2TokensRequest>,
@@ -108,4 +118,15 @@ impl OAuth2Service for RustMailerOAuth2Service {
             .await?;
         Ok(Response::new(Empty::default()))
     }
+
+    async fn list_o_auth2_pending_flows(
+        &self,
+        request: Request<ListOAuth2PendingFlowsRequest>,
+    ) -> Result<Response<ListOAuth2PendingFlowsResponse>, Status> {
+        let req = require_root(request)?;
+        let flows = OAuth2PendingEntity::list(req.account_id).await?;
+        Ok(Response::new(ListOAuth2PendingFlowsResponse {
+            flows: flows.into_iter().map(Into::into).collect(),
+        }))
+    }
 }
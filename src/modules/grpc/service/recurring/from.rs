@@ -0,0 +1,136 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::{
+    grpc::service::rustmailer_grpc,
+    rest::response::DataPage,
+    scheduler::recurring::{
+        entity::{RecurringSend, RecurringSendRun},
+        payload::{RecipientSource, RecurringSendCreateRequest, RecurringSendUpdateRequest},
+    },
+};
+
+impl TryFrom<rustmailer_grpc::RecipientSource> for RecipientSource {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::RecipientSource) -> Result<Self, Self::Error> {
+        Ok(Self {
+            recipients: (!value.recipients.is_empty())
+                .then(|| {
+                    value
+                        .recipients
+                        .into_iter()
+                        .map(TryInto::try_into)
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()?,
+            url: value.url,
+            headers: (!value.headers.is_empty()).then(|| value.headers.into_iter().collect()),
+        })
+    }
+}
+
+impl From<RecipientSource> for rustmailer_grpc::RecipientSource {
+    fn from(value: RecipientSource) -> Self {
+        Self {
+            recipients: value
+                .recipients
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            url: value.url,
+            headers: value.headers.unwrap_or_default().into_iter().collect(),
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::RecurringSendCreateRequest> for RecurringSendCreateRequest {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::RecurringSendCreateRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: value.name,
+            description: value.description,
+            cron: value.cron,
+            timezone: value.timezone,
+            template_id: value.template_id,
+            from: value.from.map(Into::into),
+            recipients: value
+                .recipients
+                .ok_or("recurring send is missing 'recipients'")?
+                .try_into()?,
+            messages_per_minute: value.messages_per_minute,
+            send_control: value.send_control.map(TryInto::try_into).transpose()?,
+            enabled: value.enabled,
+        })
+    }
+}
+
+impl TryFrom<rustmailer_grpc::UpdateRecurringSendRequest> for RecurringSendUpdateRequest {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::UpdateRecurringSendRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: value.name,
+            description: value.description,
+            cron: value.cron,
+            timezone: value.timezone,
+            template_id: value.template_id,
+            from: value.from.map(Into::into),
+            recipients: value.recipients.map(TryInto::try_into).transpose()?,
+            messages_per_minute: value.messages_per_minute,
+            send_control: value.send_control.map(TryInto::try_into).transpose()?,
+            enabled: value.enabled,
+        })
+    }
+}
+
+impl From<RecurringSendRun> for rustmailer_grpc::RecurringSendRun {
+    fn from(value: RecurringSendRun) -> Self {
+        Self {
+            started_at: value.started_at,
+            campaign_id: value.campaign_id,
+            queued: value.queued,
+            failed_recipients: value.failed_recipients,
+            error: value.error,
+        }
+    }
+}
+
+impl From<RecurringSend> for rustmailer_grpc::RecurringSend {
+    fn from(value: RecurringSend) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            name: value.name,
+            description: value.description,
+            cron: value.cron,
+            timezone: value.timezone,
+            template_id: value.template_id,
+            from: value.from.map(Into::into),
+            recipients: Some(value.recipients.into()),
+            messages_per_minute: value.messages_per_minute,
+            send_control: value.send_control.map(Into::into),
+            enabled: value.enabled,
+            next_run_at: value.next_run_at,
+            last_run: value.last_run.map(Into::into),
+            run_count: value.run_count,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<DataPage<RecurringSend>> for rustmailer_grpc::PagedRecurringSend {
+    fn from(value: DataPage<RecurringSend>) -> Self {
+        Self {
+            current_page: value.current_page,
+            page_size: value.page_size,
+            total_items: value.total_items,
+            items: value.items.into_iter().map(Into::into).collect(),
+            total_pages: value.total_pages,
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::error::code::ErrorCode;
+use crate::modules::grpc::auth::require_account_access;
+use crate::modules::grpc::service::rustmailer_grpc::{
+    Campaign, CreateRecurringSendRequest, Empty, ListRecurringSendsRequest, PagedRecurringSend,
+    RecurringSend, RecurringSendRef, RecurringSendService, UpdateRecurringSendRequest,
+};
+use crate::modules::scheduler::recurring::entity::RecurringSend as RustMailerRecurringSend;
+use crate::modules::scheduler::recurring::run::run_recurring_send;
+use crate::raise_error;
+use poem_grpc::{Request, Response, Status};
+
+pub mod from;
+
+#[derive(Default)]
+pub struct RustMailerRecurringSendService;
+
+impl RecurringSendService for RustMailerRecurringSendService {
+    async fn create_recurring_send(
+        &self,
+        request: Request<CreateRecurringSendRequest>,
+    ) -> Result<Response<RecurringSend>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let create_request = req
+            .request
+            .ok_or_else(|| {
+                raise_error!(
+                    "Missing recurring send request".into(),
+                    ErrorCode::InvalidParameter
+                )
+            })?
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let send = RustMailerRecurringSend::new(req.account_id, create_request).await?;
+        send.clone().save().await?;
+        Ok(Response::new(send.into()))
+    }
+
+    async fn list_recurring_sends(
+        &self,
+        request: Request<ListRecurringSendsRequest>,
+    ) -> Result<Response<PagedRecurringSend>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let result = RustMailerRecurringSend::paginate_list_account(
+            req.account_id,
+            req.page,
+            req.page_size,
+            req.desc,
+        )
+        .await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn get_recurring_send(
+        &self,
+        request: Request<RecurringSendRef>,
+    ) -> Result<Response<RecurringSend>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let send = RustMailerRecurringSend::get(req.account_id, req.id).await?;
+        Ok(Response::new(send.into()))
+    }
+
+    async fn update_recurring_send(
+        &self,
+        request: Request<UpdateRecurringSendRequest>,
+    ) -> Result<Response<RecurringSend>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let (account_id, id) = (req.account_id, req.id);
+        let update_request = req
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        let send = RustMailerRecurringSend::update(account_id, id, update_request).await?;
+        Ok(Response::new(send.into()))
+    }
+
+    async fn run_recurring_send(
+        &self,
+        request: Request<RecurringSendRef>,
+    ) -> Result<Response<Campaign>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let send = RustMailerRecurringSend::get(req.account_id, req.id).await?;
+        let campaign = run_recurring_send(&send).await?;
+        Ok(Response::new(campaign.into()))
+    }
+
+    async fn remove_recurring_send(
+        &self,
+        request: Request<RecurringSendRef>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let send = RustMailerRecurringSend::get(req.account_id, req.id).await?;
+        RustMailerRecurringSend::remove(send.id).await?;
+        Ok(Response::new(Empty::default()))
+    }
+}
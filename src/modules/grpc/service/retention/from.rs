@@ -0,0 +1,89 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::{
+    grpc::service::rustmailer_grpc,
+    rest::response::DataPage,
+    retention::{
+        entity::CleanupRule,
+        payload::{CleanupDryRunReport, CleanupRuleCreateRequest, CleanupRuleUpdateRequest},
+    },
+};
+
+impl From<rustmailer_grpc::CreateCleanupRuleRequest> for CleanupRuleCreateRequest {
+    fn from(value: rustmailer_grpc::CreateCleanupRuleRequest) -> Self {
+        Self {
+            name: value.name,
+            mailbox: value.mailbox,
+            query: value.query,
+            older_than_days: value.older_than_days,
+            interval_hours: value.interval_hours,
+            max_deletions_per_run: value.max_deletions_per_run,
+        }
+    }
+}
+
+impl From<rustmailer_grpc::UpdateCleanupRuleRequest> for CleanupRuleUpdateRequest {
+    fn from(value: rustmailer_grpc::UpdateCleanupRuleRequest) -> Self {
+        Self {
+            name: value.name,
+            mailbox: value.mailbox,
+            query: value.query,
+            older_than_days: value.older_than_days,
+            interval_hours: value.interval_hours,
+            max_deletions_per_run: value.max_deletions_per_run,
+            enabled: value.enabled,
+        }
+    }
+}
+
+impl From<CleanupRule> for rustmailer_grpc::CleanupRule {
+    fn from(value: CleanupRule) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            name: value.name,
+            mailbox: value.mailbox,
+            query: value.query,
+            older_than_days: value.older_than_days,
+            interval_hours: value.interval_hours,
+            max_deletions_per_run: value.max_deletions_per_run,
+            enabled: value.enabled,
+            dry_run_at: value.dry_run_at,
+            dry_run_matches: value.dry_run_matches,
+            last_run_at: value.last_run_at,
+            last_run_deleted: value.last_run_deleted,
+            last_error: value.last_error,
+            total_deleted: value.total_deleted,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<DataPage<CleanupRule>> for rustmailer_grpc::PagedCleanupRule {
+    fn from(value: DataPage<CleanupRule>) -> Self {
+        Self {
+            current_page: value.current_page,
+            page_size: value.page_size,
+            total_items: value.total_items,
+            items: value.items.into_iter().map(Into::into).collect(),
+            total_pages: value.total_pages,
+        }
+    }
+}
+
+impl From<CleanupDryRunReport> for rustmailer_grpc::CleanupDryRunReport {
+    fn from(value: CleanupDryRunReport) -> Self {
+        Self {
+            rule_id: value.rule_id,
+            search: value.search,
+            matched: value.matched,
+            would_delete: value.would_delete,
+            capped: value.capped,
+            samples: value.samples.into_iter().map(Into::into).collect(),
+            ran_at: value.ran_at,
+        }
+    }
+}
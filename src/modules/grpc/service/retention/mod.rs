@@ -0,0 +1,82 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::grpc::auth::require_account_access;
+use crate::modules::grpc::service::rustmailer_grpc::{
+    CleanupDryRunReport, CleanupRule, CleanupRuleRef, CreateCleanupRuleRequest, Empty,
+    ListCleanupRulesRequest, PagedCleanupRule, RetentionService, UpdateCleanupRuleRequest,
+};
+use crate::modules::retention::entity::CleanupRule as RustMailerCleanupRule;
+use poem_grpc::{Request, Response, Status};
+
+pub mod from;
+
+#[derive(Default)]
+pub struct RustMailerRetentionService;
+
+impl RetentionService for RustMailerRetentionService {
+    async fn create_cleanup_rule(
+        &self,
+        request: Request<CreateCleanupRuleRequest>,
+    ) -> Result<Response<CleanupRule>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let account_id = req.account_id;
+        let rule = RustMailerCleanupRule::new(account_id, req.into()).await?;
+        rule.clone().save().await?;
+        Ok(Response::new(rule.into()))
+    }
+
+    async fn list_cleanup_rules(
+        &self,
+        request: Request<ListCleanupRulesRequest>,
+    ) -> Result<Response<PagedCleanupRule>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let result = RustMailerCleanupRule::paginate_list_account(
+            req.account_id,
+            req.page,
+            req.page_size,
+            req.desc,
+        )
+        .await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn get_cleanup_rule(
+        &self,
+        request: Request<CleanupRuleRef>,
+    ) -> Result<Response<CleanupRule>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let rule = RustMailerCleanupRule::get(req.account_id, req.id).await?;
+        Ok(Response::new(rule.into()))
+    }
+
+    async fn update_cleanup_rule(
+        &self,
+        request: Request<UpdateCleanupRuleRequest>,
+    ) -> Result<Response<CleanupRule>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let (account_id, id) = (req.account_id, req.id);
+        let rule = RustMailerCleanupRule::update(account_id, id, req.into()).await?;
+        Ok(Response::new(rule.into()))
+    }
+
+    async fn remove_cleanup_rule(
+        &self,
+        request: Request<CleanupRuleRef>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let rule = RustMailerCleanupRule::get(req.account_id, req.id).await?;
+        RustMailerCleanupRule::remove(rule.id).await?;
+        Ok(Response::new(Empty::default()))
+    }
+
+    async fn dry_run_cleanup_rule(
+        &self,
+        request: Request<CleanupRuleRef>,
+    ) -> Result<Response<CleanupDryRunReport>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let report = RustMailerCleanupRule::dry_run(req.account_id, req.id).await?;
+        Ok(Response::new(report.into()))
+    }
+}
@@ -3,20 +3,29 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::{
-    common::Addr,
+    bounce::{classify::BounceCategory, delivery::RecipientDeliveryStatus},
+    cache::imap::mailbox::EnvelopeFlag,
+    common::{importance::Importance, Addr},
     grpc::service::rustmailer_grpc,
     rest::response::DataPage,
     scheduler::model::TaskStatus,
     smtp::{
-        queue::message::SendEmailTask,
+        lint::{ContentLintReport, LintRule, LintSeverity, LintWarning},
+        queue::{
+            bulk::{BulkTaskRequest, BulkTaskResult, EmailTaskFilter},
+            message::SendEmailTask,
+        },
         request::{
             forward::ForwardEmailRequest,
             headers::{HeaderValue, Raw, Text, Url},
             new::{Recipient, SendEmailRequest},
             reply::ReplyEmailRequest,
             AttachmentPayload, AttachmentRef, DSNConfig, EmailAddress, MailAttachment,
-            MailEnvelope, NotifyOption, Retry, ReturnContent, SendControl, Strategy,
+            MailEnvelope, NotifyOption, QueuedEmail, Retry, ReturnContent, SendControl,
+            SendEmailResponse, Strategy,
         },
+        subject::{SubjectLocale, SubjectPrefixes},
+        timeline::{DeliveryEvent, DeliveryEventKind, MessageTimeline, RecipientTimeline},
     },
     utils::prost_value_to_json_value,
 };
@@ -32,6 +41,7 @@ impl TryFrom<rustmailer_grpc::SendEmailRequest> for SendEmailRequest {
             subject: value.subject,
             text: value.text,
             html: value.html,
+            markdown: value.markdown,
             preview: value.preview,
             eml: value.eml,
             template_id: value.template_id,
@@ -55,6 +65,7 @@ impl TryFrom<rustmailer_grpc::SendEmailRequest> for SendEmailRequest {
                 }
             },
             send_control: value.send_control.map(|c| c.try_into()).transpose()?,
+            importance: value.importance.map(Importance::try_from).transpose()?,
         })
     }
 }
@@ -98,6 +109,7 @@ impl TryFrom<rustmailer_grpc::ReplyEmailRequest> for ReplyEmailRequest {
             include_original: value.include_original,
             include_all_attachments: value.include_all_attachments,
             send_control: { value.send_control.map(|c| c.try_into()).transpose()? },
+            importance: value.importance.map(Importance::try_from).transpose()?,
         })
     }
 }
@@ -140,6 +152,7 @@ impl TryFrom<rustmailer_grpc::ForwardEmailRequest> for ForwardEmailRequest {
             include_original: value.include_original,
             include_all_attachments: value.include_all_attachments,
             send_control: { value.send_control.map(|c| c.try_into()).transpose()? },
+            importance: value.importance.map(Importance::try_from).transpose()?,
         })
     }
 }
@@ -152,17 +165,131 @@ impl TryFrom<rustmailer_grpc::SendControl> for SendControl {
             envelope: value.envelope.map(Into::into),
             save_to_sent: value.save_to_sent,
             sent_folder: value.sent_folder,
+            sent_flags: if value.sent_flags.is_empty() {
+                None
+            } else {
+                Some(
+                    value
+                        .sent_flags
+                        .into_iter()
+                        .map(EnvelopeFlag::try_from)
+                        .collect::<Result<Vec<_>, _>>()?,
+                )
+            },
+            sent_date: value.sent_date,
             dry_run: value.dry_run,
+            compose_only: value.compose_only,
             send_at: value.send_at,
             retry_policy: value.retry_policy.map(Retry::try_from).transpose()?,
             mta: value.mta,
             dsn: value.dsn.map(DSNConfig::try_from).transpose()?,
             campaign_id: value.campaign_id,
             enable_tracking: value.enable_tracking,
+            local_send_time: value.local_send_time,
+            default_timezone: value.default_timezone,
+            subject_prefixes: value
+                .subject_prefixes
+                .map(SubjectPrefixes::try_from)
+                .transpose()?,
+            enforce_content_policy: value.enforce_content_policy,
+            ignore_suppression_list: value.ignore_suppression_list,
+        })
+    }
+}
+
+impl From<SendControl> for rustmailer_grpc::SendControl {
+    fn from(value: SendControl) -> Self {
+        Self {
+            envelope: value.envelope.map(Into::into),
+            save_to_sent: value.save_to_sent,
+            sent_folder: value.sent_folder,
+            sent_flags: value
+                .sent_flags
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            sent_date: value.sent_date,
+            dry_run: value.dry_run,
+            compose_only: value.compose_only,
+            send_at: value.send_at,
+            retry_policy: value.retry_policy.map(Into::into),
+            mta: value.mta,
+            dsn: value.dsn.map(Into::into),
+            campaign_id: value.campaign_id,
+            enable_tracking: value.enable_tracking,
+            local_send_time: value.local_send_time,
+            default_timezone: value.default_timezone,
+            subject_prefixes: value.subject_prefixes.map(Into::into),
+            enforce_content_policy: value.enforce_content_policy,
+            ignore_suppression_list: value.ignore_suppression_list,
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::SubjectPrefixes> for SubjectPrefixes {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::SubjectPrefixes) -> Result<Self, Self::Error> {
+        Ok(Self {
+            locale: value.locale.map(SubjectLocale::try_from).transpose()?,
+            reply: value.reply,
+            forward: value.forward,
         })
     }
 }
 
+impl From<SubjectPrefixes> for rustmailer_grpc::SubjectPrefixes {
+    fn from(value: SubjectPrefixes) -> Self {
+        Self {
+            locale: value.locale.map(Into::into),
+            reply: value.reply,
+            forward: value.forward,
+        }
+    }
+}
+
+impl TryFrom<i32> for SubjectLocale {
+    type Error = &'static str;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SubjectLocale::English),
+            1 => Ok(SubjectLocale::German),
+            2 => Ok(SubjectLocale::Dutch),
+            3 => Ok(SubjectLocale::Swedish),
+            4 => Ok(SubjectLocale::Danish),
+            5 => Ok(SubjectLocale::Norwegian),
+            6 => Ok(SubjectLocale::Finnish),
+            7 => Ok(SubjectLocale::French),
+            8 => Ok(SubjectLocale::Spanish),
+            9 => Ok(SubjectLocale::Italian),
+            10 => Ok(SubjectLocale::Portuguese),
+            11 => Ok(SubjectLocale::Polish),
+            _ => Err("Invalid value for SubjectLocale"),
+        }
+    }
+}
+
+impl From<SubjectLocale> for i32 {
+    fn from(value: SubjectLocale) -> Self {
+        match value {
+            SubjectLocale::English => 0,
+            SubjectLocale::German => 1,
+            SubjectLocale::Dutch => 2,
+            SubjectLocale::Swedish => 3,
+            SubjectLocale::Danish => 4,
+            SubjectLocale::Norwegian => 5,
+            SubjectLocale::Finnish => 6,
+            SubjectLocale::French => 7,
+            SubjectLocale::Spanish => 8,
+            SubjectLocale::Italian => 9,
+            SubjectLocale::Portuguese => 10,
+            SubjectLocale::Polish => 11,
+        }
+    }
+}
+
 impl TryFrom<rustmailer_grpc::DsnConfig> for DSNConfig {
     type Error = &'static str;
 
@@ -267,6 +394,16 @@ impl TryFrom<rustmailer_grpc::Retry> for Retry {
     }
 }
 
+impl From<Retry> for rustmailer_grpc::Retry {
+    fn from(value: Retry) -> Self {
+        Self {
+            strategy: value.strategy.into(),
+            seconds: value.seconds,
+            max_retries: value.max_retries,
+        }
+    }
+}
+
 impl TryFrom<i32> for Strategy {
     type Error = &'static str;
 
@@ -279,6 +416,15 @@ impl TryFrom<i32> for Strategy {
     }
 }
 
+impl From<Strategy> for i32 {
+    fn from(value: Strategy) -> Self {
+        match value {
+            Strategy::Linear => 0,
+            Strategy::Exponential => 1,
+        }
+    }
+}
+
 impl From<rustmailer_grpc::EmailAddress> for EmailAddress {
     fn from(value: rustmailer_grpc::EmailAddress) -> Self {
         Self {
@@ -288,6 +434,15 @@ impl From<rustmailer_grpc::EmailAddress> for EmailAddress {
     }
 }
 
+impl From<EmailAddress> for rustmailer_grpc::EmailAddress {
+    fn from(value: EmailAddress) -> Self {
+        Self {
+            name: value.name,
+            address: value.address,
+        }
+    }
+}
+
 impl From<rustmailer_grpc::Recipient> for Recipient {
     fn from(value: rustmailer_grpc::Recipient) -> Self {
         Self {
@@ -298,6 +453,8 @@ impl From<rustmailer_grpc::Recipient> for Recipient {
                 .then(|| value.reply_to.into_iter().map(Into::into).collect()),
             template_params: value.template_params.map(prost_value_to_json_value),
             send_at: value.send_at,
+            timezone: value.timezone,
+            in_reply_to: value.in_reply_to,
         }
     }
 }
@@ -430,6 +587,35 @@ impl From<SendEmailTask> for rustmailer_grpc::EmailTask {
             reply: value.reply,
             mailbox: value.mailbox,
             uid: value.uid,
+            delivery_status: value.delivery_status.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<RecipientDeliveryStatus> for rustmailer_grpc::RecipientDeliveryStatus {
+    fn from(value: RecipientDeliveryStatus) -> Self {
+        Self {
+            recipient: value.recipient,
+            action: value.action,
+            status: value.status,
+            diagnostic_code: value.diagnostic_code,
+            remote_mta: value.remote_mta,
+            mailbox_name: value.mailbox_name,
+            uid: value.uid,
+            reported_at: value.reported_at,
+            category: value.category.map(Into::into),
+        }
+    }
+}
+
+impl From<BounceCategory> for i32 {
+    fn from(value: BounceCategory) -> Self {
+        match value {
+            BounceCategory::HardBounce => 0,
+            BounceCategory::SoftBounce => 1,
+            BounceCategory::MailboxFull => 2,
+            BounceCategory::SpamBlock => 3,
+            BounceCategory::Policy => 4,
         }
     }
 }
@@ -474,3 +660,172 @@ impl From<DataPage<SendEmailTask>> for rustmailer_grpc::PagedEmailTask {
         }
     }
 }
+
+impl From<QueuedEmail> for rustmailer_grpc::QueuedEmail {
+    fn from(value: QueuedEmail) -> Self {
+        Self {
+            message_id: value.message_id,
+            task_id: value.task_id,
+            scheduled_at: value.scheduled_at,
+            queue_position: value.queue_position,
+            estimated_send_at: value.estimated_send_at,
+            timezone: value.timezone,
+            in_reply_to: value.in_reply_to,
+            raw: value.raw,
+            suppressed_recipients: value.suppressed_recipients.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<SendEmailResponse> for rustmailer_grpc::SendEmailResponse {
+    fn from(value: SendEmailResponse) -> Self {
+        Self {
+            messages: value.messages.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<rustmailer_grpc::EmailTaskFilter> for EmailTaskFilter {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::EmailTaskFilter) -> Result<Self, Self::Error> {
+        Ok(Self {
+            account_ids: (!value.account_ids.is_empty()).then_some(value.account_ids),
+            account_tags: (!value.account_tags.is_empty()).then_some(value.account_tags),
+            campaign_id: value.campaign_id,
+            statuses: if value.statuses.is_empty() {
+                None
+            } else {
+                Some(
+                    value
+                        .statuses
+                        .into_iter()
+                        .map(TaskStatus::try_from)
+                        .collect::<Result<Vec<_>, _>>()?,
+                )
+            },
+            created_after: value.created_after,
+            created_before: value.created_before,
+        })
+    }
+}
+
+impl TryFrom<rustmailer_grpc::BulkEmailTaskRequest> for BulkTaskRequest {
+    type Error = &'static str;
+
+    fn try_from(value: rustmailer_grpc::BulkEmailTaskRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            filter: value
+                .filter
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+            dry_run: value.dry_run,
+            send_at: value.send_at,
+        })
+    }
+}
+
+impl From<BulkTaskResult> for rustmailer_grpc::BulkEmailTaskResult {
+    fn from(value: BulkTaskResult) -> Self {
+        Self {
+            matched: value.matched,
+            updated: value.updated,
+            dry_run: value.dry_run,
+        }
+    }
+}
+
+impl From<LintSeverity> for i32 {
+    fn from(value: LintSeverity) -> Self {
+        match value {
+            LintSeverity::Warning => 0,
+            LintSeverity::Error => 1,
+        }
+    }
+}
+
+impl From<LintRule> for i32 {
+    fn from(value: LintRule) -> Self {
+        match value {
+            LintRule::MissingTextAlternative => 0,
+            LintRule::ImageOnlyBody => 1,
+            LintRule::ExcessiveLinks => 2,
+            LintRule::BlockedPhrase => 3,
+            LintRule::MissingUnsubscribe => 4,
+            LintRule::BrokenTemplateVariable => 5,
+        }
+    }
+}
+
+impl From<LintWarning> for rustmailer_grpc::LintWarning {
+    fn from(value: LintWarning) -> Self {
+        Self {
+            rule: value.rule.into(),
+            severity: value.severity.into(),
+            message: value.message,
+            recipient_index: value.recipient_index,
+        }
+    }
+}
+
+impl From<ContentLintReport> for rustmailer_grpc::ContentLintReport {
+    fn from(value: ContentLintReport) -> Self {
+        Self {
+            passed: value.passed,
+            warnings: value.warnings.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<DeliveryEventKind> for i32 {
+    fn from(value: DeliveryEventKind) -> Self {
+        match value {
+            DeliveryEventKind::Queued => 0,
+            DeliveryEventKind::Sent => 1,
+            DeliveryEventKind::Retrying => 2,
+            DeliveryEventKind::Failed => 3,
+            DeliveryEventKind::Delivered => 4,
+            DeliveryEventKind::Delayed => 5,
+            DeliveryEventKind::Bounced => 6,
+            DeliveryEventKind::Opened => 7,
+            DeliveryEventKind::Clicked => 8,
+            DeliveryEventKind::Complained => 9,
+        }
+    }
+}
+
+impl From<DeliveryEvent> for rustmailer_grpc::DeliveryEvent {
+    fn from(value: DeliveryEvent) -> Self {
+        Self {
+            id: value.id,
+            message_id: value.message_id,
+            account_id: value.account_id,
+            recipient: value.recipient,
+            kind: value.kind.into(),
+            task_id: value.task_id,
+            campaign_id: value.campaign_id,
+            detail: value.detail,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<RecipientTimeline> for rustmailer_grpc::RecipientTimeline {
+    fn from(value: RecipientTimeline) -> Self {
+        Self {
+            recipient: value.recipient,
+            status: value.status.into(),
+            events: value.events.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<MessageTimeline> for rustmailer_grpc::MessageTimeline {
+    fn from(value: MessageTimeline) -> Self {
+        Self {
+            message_id: value.message_id,
+            recipients: value.recipients.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -2,21 +2,29 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::account::migration::AccountModel;
 use crate::modules::common::auth::ClientContext;
 use crate::modules::common::paginated::paginate_vec;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::grpc::auth::require_account_access;
 use crate::modules::rest::response::DataPage;
 use crate::modules::scheduler::model::TaskStatus;
+use crate::modules::smtp::lint::lint_request;
+use crate::modules::smtp::queue::bulk::{
+    run_bulk_task_action, run_task_action, BulkTaskAction, BulkTaskRequest,
+};
 use crate::modules::smtp::queue::message::SendEmailTask as RustMailerQueuedEmailTask;
 use crate::modules::smtp::request::forward::ForwardEmailRequest as RustMailerForwardEmailRequest;
 use crate::modules::smtp::request::new::SendEmailRequest as RustMailerSendEmailRequest;
 use crate::modules::smtp::request::reply::ReplyEmailRequest as RustMailerReplyEmailRequest;
+use crate::modules::smtp::timeline::DeliveryEvent;
 use crate::modules::tasks::queue::RustMailerTaskQueue;
 use crate::modules::{
     grpc::service::rustmailer_grpc::{
-        EmailTask, Empty, ForwardMailRequest, GetTaskRequest, ListTasksRequest, PagedEmailTask,
-        RemoveTaskRequest, ReplyMailRequest, SendMailService, SendNewMailRequest,
+        BulkEmailTaskRequest, BulkEmailTaskResult, ContentLintReport, EmailTask,
+        EmailTaskActionRequest, Empty, ForwardMailRequest, GetMessageTimelineRequest,
+        GetTaskRequest, ListTasksRequest, MessageTimeline, PagedEmailTask, RemoveTaskRequest,
+        ReplyMailRequest, SendEmailResponse, SendMailService, SendNewMailRequest,
     },
     smtp::request::builder::EmailBuilder,
 };
@@ -34,7 +42,7 @@ impl SendMailService for RustMailerSendMailService {
     async fn send_new_mail(
         &self,
         request: Request<SendNewMailRequest>,
-    ) -> Result<Response<Empty>, Status> {
+    ) -> Result<Response<SendEmailResponse>, Status> {
         let req = require_account_access(request, |r| r.account_id)?;
         let email_request: RustMailerSendEmailRequest = req
             .request
@@ -47,14 +55,14 @@ impl SendMailService for RustMailerSendMailService {
             .try_into()
             .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
 
-        email_request.build(req.account_id).await?;
-        Ok(Response::new(Empty {}))
+        let response = email_request.build(req.account_id).await?;
+        Ok(Response::new(response.into()))
     }
 
     async fn reply_mail(
         &self,
         request: Request<ReplyMailRequest>,
-    ) -> Result<Response<Empty>, Status> {
+    ) -> Result<Response<SendEmailResponse>, Status> {
         let req = require_account_access(request, |r| r.account_id)?;
         let email_request: RustMailerReplyEmailRequest = req
             .request
@@ -66,14 +74,14 @@ impl SendMailService for RustMailerSendMailService {
             })?
             .try_into()
             .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
-        email_request.build(req.account_id).await?;
-        Ok(Response::new(Empty::default()))
+        let response = email_request.build(req.account_id).await?;
+        Ok(Response::new(response.into()))
     }
 
     async fn forward_mail(
         &self,
         request: Request<ForwardMailRequest>,
-    ) -> Result<Response<Empty>, Status> {
+    ) -> Result<Response<SendEmailResponse>, Status> {
         let req = require_account_access(request, |r| r.account_id)?;
         let email_request: RustMailerForwardEmailRequest = req
             .request
@@ -85,8 +93,8 @@ impl SendMailService for RustMailerSendMailService {
             })?
             .try_into()
             .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
-        email_request.build(req.account_id).await?;
-        Ok(Response::new(Empty::default()))
+        let response = email_request.build(req.account_id).await?;
+        Ok(Response::new(response.into()))
     }
 
     async fn list_email_tasks(
@@ -199,4 +207,146 @@ impl SendMailService for RustMailerSendMailService {
         send_queue.remove_task(req.id).await?;
         Ok(Response::new(Empty::default()))
     }
+
+    async fn cancel_email_tasks(
+        &self,
+        request: Request<BulkEmailTaskRequest>,
+    ) -> Result<Response<BulkEmailTaskResult>, Status> {
+        bulk_task_action(request, BulkTaskAction::Cancel).await
+    }
+
+    async fn retry_email_tasks(
+        &self,
+        request: Request<BulkEmailTaskRequest>,
+    ) -> Result<Response<BulkEmailTaskResult>, Status> {
+        bulk_task_action(request, BulkTaskAction::Retry).await
+    }
+
+    async fn pause_email_tasks(
+        &self,
+        request: Request<BulkEmailTaskRequest>,
+    ) -> Result<Response<BulkEmailTaskResult>, Status> {
+        bulk_task_action(request, BulkTaskAction::Pause).await
+    }
+
+    async fn resume_email_tasks(
+        &self,
+        request: Request<BulkEmailTaskRequest>,
+    ) -> Result<Response<BulkEmailTaskResult>, Status> {
+        bulk_task_action(request, BulkTaskAction::Resume).await
+    }
+
+    async fn reschedule_email_tasks(
+        &self,
+        request: Request<BulkEmailTaskRequest>,
+    ) -> Result<Response<BulkEmailTaskResult>, Status> {
+        bulk_task_action(request, BulkTaskAction::Reschedule).await
+    }
+
+    async fn pause_email_task(
+        &self,
+        request: Request<EmailTaskActionRequest>,
+    ) -> Result<Response<EmailTask>, Status> {
+        task_action(request, BulkTaskAction::Pause).await
+    }
+
+    async fn resume_email_task(
+        &self,
+        request: Request<EmailTaskActionRequest>,
+    ) -> Result<Response<EmailTask>, Status> {
+        task_action(request, BulkTaskAction::Resume).await
+    }
+
+    async fn reschedule_email_task(
+        &self,
+        request: Request<EmailTaskActionRequest>,
+    ) -> Result<Response<EmailTask>, Status> {
+        task_action(request, BulkTaskAction::Reschedule).await
+    }
+
+    async fn lint_mail(
+        &self,
+        request: Request<SendNewMailRequest>,
+    ) -> Result<Response<ContentLintReport>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let email_request: RustMailerSendEmailRequest = req
+            .request
+            .ok_or_else(|| {
+                raise_error!(
+                    "'SendEmailRequest' must be set".into(),
+                    ErrorCode::InvalidParameter
+                )
+            })?
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        email_request.validate().await?;
+        let account = AccountModel::get(req.account_id).await?;
+        let report = lint_request(&account, &email_request).await?;
+        Ok(Response::new(report.into()))
+    }
+
+    async fn compose_mail(
+        &self,
+        request: Request<SendNewMailRequest>,
+    ) -> Result<Response<SendEmailResponse>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let mut email_request: RustMailerSendEmailRequest = req
+            .request
+            .ok_or_else(|| {
+                raise_error!(
+                    "'SendEmailRequest' must be set".into(),
+                    ErrorCode::InvalidParameter
+                )
+            })?
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        email_request
+            .send_control
+            .get_or_insert_with(Default::default)
+            .compose_only = Some(true);
+        let response = email_request.build(req.account_id).await?;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn get_message_timeline(
+        &self,
+        request: Request<GetMessageTimelineRequest>,
+    ) -> Result<Response<MessageTimeline>, Status> {
+        let extensions = request.extensions().clone();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let req = request.into_inner();
+        let timeline = DeliveryEvent::timeline(context, &req.message_id).await?;
+        Ok(Response::new(timeline.into()))
+    }
+}
+
+async fn bulk_task_action(
+    request: Request<BulkEmailTaskRequest>,
+    action: BulkTaskAction,
+) -> Result<Response<BulkEmailTaskResult>, Status> {
+    let extensions = request.extensions().clone();
+    let context = extensions
+        .get::<Arc<ClientContext>>()
+        .ok_or_else(|| raise_error!("Missing ClientContext".into(), ErrorCode::InternalError))?;
+    let request: BulkTaskRequest = request
+        .into_inner()
+        .try_into()
+        .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+    let result = run_bulk_task_action(context, action, &request).await?;
+    Ok(Response::new(result.into()))
+}
+
+async fn task_action(
+    request: Request<EmailTaskActionRequest>,
+    action: BulkTaskAction,
+) -> Result<Response<EmailTask>, Status> {
+    let extensions = request.extensions().clone();
+    let context = extensions
+        .get::<Arc<ClientContext>>()
+        .ok_or_else(|| raise_error!("Missing ClientContext".into(), ErrorCode::InternalError))?;
+    let req = request.into_inner();
+    let task = run_task_action(context, req.id, action, req.send_at).await?;
+    Ok(Response::new(task.into()))
 }
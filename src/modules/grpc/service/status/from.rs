@@ -3,7 +3,10 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::{
-    context::status::RustMailerStatus,
+    context::{
+        health::{ComponentStatus, Severity, StatusPage},
+        status::RustMailerStatus,
+    },
     grpc::service::rustmailer_grpc,
     version::{LicenseCheckResult, Notifications, Release, ReleaseNotification},
 };
@@ -57,3 +60,35 @@ impl From<Release> for rustmailer_grpc::Release {
         }
     }
 }
+
+impl From<Severity> for i32 {
+    fn from(value: Severity) -> Self {
+        match value {
+            Severity::Operational => rustmailer_grpc::Severity::Operational as i32,
+            Severity::Degraded => rustmailer_grpc::Severity::Degraded as i32,
+            Severity::Outage => rustmailer_grpc::Severity::Outage as i32,
+        }
+    }
+}
+
+impl From<ComponentStatus> for rustmailer_grpc::ComponentStatus {
+    fn from(value: ComponentStatus) -> Self {
+        Self {
+            name: value.name,
+            severity: value.severity.into(),
+            message: value.message,
+        }
+    }
+}
+
+impl From<StatusPage> for rustmailer_grpc::StatusPage {
+    fn from(value: StatusPage) -> Self {
+        Self {
+            severity: value.severity.into(),
+            checked_at: value.checked_at,
+            uptime_ms: value.uptime_ms,
+            version: value.version,
+            components: value.components.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -2,8 +2,10 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::context::health::StatusPage as RustMailerStatusPage;
+use crate::modules::grpc::auth::require_root;
 use crate::modules::grpc::service::rustmailer_grpc::{
-    Empty, Notifications, ServerStatus, StatusService,
+    Empty, Notifications, ServerStatus, StatusPage, StatusService,
 };
 use crate::modules::{context::status::RustMailerStatus, version::fetch_notifications};
 use poem_grpc::{Request, Response, Status};
@@ -25,4 +27,12 @@ impl StatusService for RustMailerStatusService {
         let notifications = fetch_notifications().await?;
         Ok(Response::new(notifications.into()))
     }
+
+    async fn get_status_page(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<StatusPage>, Status> {
+        require_root(request)?;
+        Ok(Response::new(RustMailerStatusPage::get().await.into()))
+    }
 }
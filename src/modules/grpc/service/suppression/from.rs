@@ -0,0 +1,45 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::{
+    grpc::service::rustmailer_grpc,
+    rest::response::DataPage,
+    smtp::suppression::{SuppressedAddress, SuppressionSource},
+};
+
+impl From<SuppressionSource> for i32 {
+    fn from(value: SuppressionSource) -> Self {
+        match value {
+            SuppressionSource::Bounce => 0,
+            SuppressionSource::Complaint => 1,
+            SuppressionSource::Manual => 2,
+        }
+    }
+}
+
+impl From<SuppressedAddress> for rustmailer_grpc::SuppressedAddress {
+    fn from(value: SuppressedAddress) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            address: value.address,
+            source: value.source.into(),
+            reason: value.reason,
+            task_id: value.task_id,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<DataPage<SuppressedAddress>> for rustmailer_grpc::PagedSuppressedAddress {
+    fn from(value: DataPage<SuppressedAddress>) -> Self {
+        Self {
+            current_page: value.current_page,
+            page_size: value.page_size,
+            total_items: value.total_items,
+            items: value.items.into_iter().map(Into::into).collect(),
+            total_pages: value.total_pages,
+        }
+    }
+}
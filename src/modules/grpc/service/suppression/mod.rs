@@ -0,0 +1,67 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::sync::Arc;
+
+use crate::modules::common::auth::ClientContext;
+use crate::modules::error::code::ErrorCode;
+use crate::modules::grpc::auth::require_account_access;
+use crate::modules::grpc::service::rustmailer_grpc::{
+    CreateSuppressionRequest, Empty, ListSuppressionsRequest, PagedSuppressedAddress,
+    RemoveSuppressionRequest, SuppressedAddress, SuppressionService,
+};
+use crate::modules::smtp::suppression::{
+    SuppressedAddress as RustMailerSuppressedAddress, SuppressionRequest,
+};
+use crate::raise_error;
+use poem_grpc::{Request, Response, Status};
+
+pub mod from;
+
+#[derive(Default)]
+pub struct RustMailerSuppressionService;
+
+impl SuppressionService for RustMailerSuppressionService {
+    async fn list_suppressions(
+        &self,
+        request: Request<ListSuppressionsRequest>,
+    ) -> Result<Response<PagedSuppressedAddress>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let result =
+            RustMailerSuppressedAddress::paginate_list(req.account_id, req.page, req.page_size)
+                .await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn create_suppression(
+        &self,
+        request: Request<CreateSuppressionRequest>,
+    ) -> Result<Response<SuppressedAddress>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let entry = RustMailerSuppressedAddress::create(
+            req.account_id,
+            SuppressionRequest {
+                address: req.address,
+                reason: req.reason,
+            },
+        )
+        .await?;
+        Ok(Response::new(entry.into()))
+    }
+
+    async fn remove_suppression(
+        &self,
+        request: Request<RemoveSuppressionRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let entry = RustMailerSuppressedAddress::get(req.id).await?;
+        context.require_account_access(entry.account_id)?;
+        RustMailerSuppressedAddress::remove(req.id).await?;
+        Ok(Response::new(Empty::default()))
+    }
+}
@@ -7,7 +7,12 @@ use crate::modules::{
     rest::response::DataPage,
     smtp::template::{
         entity::{EmailTemplate, MessageFormat},
-        payload::{TemplateCreateRequest, TemplateSentTestRequest, TemplateUpdateRequest},
+        partial::TemplatePartial,
+        payload::{
+            SeedSendResult, TemplateCreateRequest, TemplatePartialCreateRequest,
+            TemplatePartialUpdateRequest, TemplateSeedTestReport, TemplateSeedTestRequest,
+            TemplateSentTestRequest, TemplateUpdateRequest,
+        },
     },
     token::AccountInfo,
     utils::prost_value_to_json_value,
@@ -45,6 +50,7 @@ impl From<EmailTemplate> for rustmailer_grpc::EmailTemplate {
             created_at: value.created_at,
             updated_at: value.updated_at,
             last_access_at: value.last_access_at,
+            workspace_id: value.workspace_id,
         }
     }
 }
@@ -54,6 +60,7 @@ impl From<MessageFormat> for i32 {
         match value {
             MessageFormat::Markdown => 1,
             MessageFormat::Html => 0,
+            MessageFormat::Mjml => 2,
         }
     }
 }
@@ -65,6 +72,7 @@ impl TryFrom<rustmailer_grpc::EmailTemplateCreateRequest> for TemplateCreateRequ
         Ok(Self {
             description: value.description,
             account_id: value.account_id,
+            workspace_id: value.workspace_id,
             subject: value.subject,
             preview: value.preview,
             text: value.text,
@@ -81,6 +89,7 @@ impl TryFrom<i32> for MessageFormat {
         match value {
             0 => Ok(MessageFormat::Html),
             1 => Ok(MessageFormat::Markdown),
+            2 => Ok(MessageFormat::Mjml),
             _ => Err("Invalid value for MessageFormat"),
         }
     }
@@ -122,3 +131,83 @@ impl From<rustmailer_grpc::TemplateSentTestRequest> for TemplateSentTestRequest
         }
     }
 }
+
+impl From<rustmailer_grpc::TemplateSeedTestRequest> for TemplateSeedTestRequest {
+    fn from(value: rustmailer_grpc::TemplateSeedTestRequest) -> Self {
+        Self {
+            account_id: value.account_id,
+            recipients: (!value.recipients.is_empty()).then_some(value.recipients),
+            template_params: value.template_params.map(prost_value_to_json_value),
+        }
+    }
+}
+
+impl From<SeedSendResult> for rustmailer_grpc::SeedSendResult {
+    fn from(value: SeedSendResult) -> Self {
+        Self {
+            recipient: value.recipient,
+            provider: value.provider,
+            success: value.success,
+            error: value.error,
+            message_id: value.message_id,
+            duration_ms: value.duration_ms,
+        }
+    }
+}
+
+impl From<TemplateSeedTestReport> for rustmailer_grpc::TemplateSeedTestReport {
+    fn from(value: TemplateSeedTestReport) -> Self {
+        Self {
+            subject: value.subject,
+            sent: value.sent as u64,
+            failed: value.failed as u64,
+            results: value.results.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<TemplatePartial> for rustmailer_grpc::TemplatePartial {
+    fn from(value: TemplatePartial) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            description: value.description,
+            account: value.account.map(Into::into),
+            content: value.content,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<rustmailer_grpc::TemplatePartialCreateRequest> for TemplatePartialCreateRequest {
+    fn from(value: rustmailer_grpc::TemplatePartialCreateRequest) -> Self {
+        Self {
+            account_id: value.account_id,
+            name: value.name,
+            description: value.description,
+            content: value.content,
+        }
+    }
+}
+
+impl From<rustmailer_grpc::UpdateTemplatePartialRequest> for TemplatePartialUpdateRequest {
+    fn from(value: rustmailer_grpc::UpdateTemplatePartialRequest) -> Self {
+        Self {
+            description: value.description,
+            content: value.content,
+        }
+    }
+}
+
+impl From<DataPage<TemplatePartial>> for rustmailer_grpc::PagedTemplatePartial {
+    fn from(value: DataPage<TemplatePartial>) -> Self {
+        Self {
+            current_page: value.current_page,
+            page_size: value.page_size,
+            total_items: value.total_items,
+            items: value.items.into_iter().map(Into::into).collect(),
+            total_pages: value.total_pages,
+        }
+    }
+}
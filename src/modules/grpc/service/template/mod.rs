@@ -8,13 +8,17 @@ use crate::modules::common::auth::ClientContext;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::grpc::auth::{require_account_access, require_root};
 use crate::modules::grpc::service::rustmailer_grpc::{
-    DeleteAccountTemplatesRequest, DeleteTemplateRequest, EmailTemplate,
-    EmailTemplateCreateRequest, Empty, GetTemplateRequest, ListAccountTemplatesRequest,
-    ListTemplatesRequest, PagedEmailTemplate, TemplateSentTestRequest, TemplatesService,
-    UpdateTemplateRequest,
+    DeleteAccountTemplatesRequest, DeleteTemplatePartialRequest, DeleteTemplateRequest,
+    EmailTemplate, EmailTemplateCreateRequest, Empty, GetTemplatePartialRequest,
+    GetTemplateRequest, ListAccountTemplatesRequest, ListTemplatesRequest, PagedEmailTemplate,
+    PagedTemplatePartial, TemplatePartial, TemplatePartialCreateRequest, TemplateSeedTestReport,
+    TemplateSeedTestRequest, TemplateSentTestRequest, TemplatesService,
+    UpdateTemplatePartialRequest, UpdateTemplateRequest,
 };
 use crate::modules::smtp::template::entity::EmailTemplate as RustMailerEmailTemplate;
-use crate::modules::smtp::template::send::send_template_test_email;
+use crate::modules::smtp::template::partial::TemplatePartial as RustMailerTemplatePartial;
+use crate::modules::smtp::template::payload::TemplateCreateRequest;
+use crate::modules::smtp::template::send::{send_template_seed_test, send_template_test_email};
 use crate::raise_error;
 use poem_grpc::{Request, Response, Status};
 
@@ -35,9 +39,7 @@ impl TemplatesService for RustMailerTemplatesService {
             raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
         })?;
         let result = RustMailerEmailTemplate::get(req.id).await?;
-        if let Some(account_info) = &result.account {
-            context.require_account_access(account_info.id)?;
-        }
+        result.check_access(context)?;
         Ok(Response::new(result.into()))
     }
 
@@ -53,10 +55,9 @@ impl TemplatesService for RustMailerTemplatesService {
             raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
         })?;
 
-        let result = RustMailerEmailTemplate::get(req.id).await?;
-        if let Some(account_info) = &result.account {
-            context.require_account_access(account_info.id)?;
-        }
+        RustMailerEmailTemplate::get(req.id)
+            .await?
+            .check_access(context)?;
 
         RustMailerEmailTemplate::remove(req.id).await?;
         Ok(Response::new(Empty::default()))
@@ -66,12 +67,22 @@ impl TemplatesService for RustMailerTemplatesService {
         &self,
         request: Request<EmailTemplateCreateRequest>,
     ) -> Result<Response<Empty>, Status> {
+        let extensions = request.extensions().clone();
         let request = request.into_inner();
-        let template =
-            RustMailerEmailTemplate::new(request.try_into().map_err(|e: &'static str| {
-                raise_error!(e.to_string(), ErrorCode::InvalidParameter)
-            })?)
-            .await?;
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let mut request: TemplateCreateRequest = request
+            .try_into()
+            .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))?;
+        match request.account_id {
+            Some(account_id) => context.require_account_access(account_id)?,
+            None => {
+                request.workspace_id = request.workspace_id.or(context.workspace_id());
+                context.require_workspace_access(request.workspace_id)?;
+            }
+        }
+        let template = RustMailerEmailTemplate::new(request).await?;
         template.save().await?;
         Ok(Response::new(Empty::default()))
     }
@@ -88,16 +99,17 @@ impl TemplatesService for RustMailerTemplatesService {
             raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
         })?;
 
-        let result = RustMailerEmailTemplate::get(req.id).await?;
-        if let Some(account_info) = &result.account {
-            context.require_account_access(account_info.id)?;
-        }
+        RustMailerEmailTemplate::get(req.id)
+            .await?
+            .check_access(context)?;
 
+        let expected_version = req.expected_updated_at;
         RustMailerEmailTemplate::update(
             req.id,
             req.try_into().map_err(|e: &'static str| {
                 raise_error!(e.to_string(), ErrorCode::InvalidParameter)
             })?,
+            expected_version,
         )
         .await?;
         Ok(Response::new(Empty::default()))
@@ -145,4 +157,97 @@ impl TemplatesService for RustMailerTemplatesService {
         send_template_test_email(req.template_id, req.into()).await?;
         Ok(Response::new(Empty::default()))
     }
+
+    async fn send_seed_test(
+        &self,
+        request: Request<TemplateSeedTestRequest>,
+    ) -> Result<Response<TemplateSeedTestReport>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let report = send_template_seed_test(req.template_id, req.into()).await?;
+        Ok(Response::new(report.into()))
+    }
+
+    async fn create_template_partial(
+        &self,
+        request: Request<TemplatePartialCreateRequest>,
+    ) -> Result<Response<TemplatePartial>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        RustMailerTemplatePartial::check_access(context, req.account_id)?;
+        let partial = RustMailerTemplatePartial::new(req.into()).await?;
+        partial.clone().save().await?;
+        Ok(Response::new(partial.into()))
+    }
+
+    async fn get_template_partial(
+        &self,
+        request: Request<GetTemplatePartialRequest>,
+    ) -> Result<Response<TemplatePartial>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let partial = RustMailerTemplatePartial::get(req.id).await?;
+        RustMailerTemplatePartial::check_access(context, partial.account_id())?;
+        Ok(Response::new(partial.into()))
+    }
+
+    async fn update_template_partial(
+        &self,
+        request: Request<UpdateTemplatePartialRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let partial = RustMailerTemplatePartial::get(req.id).await?;
+        RustMailerTemplatePartial::check_access(context, partial.account_id())?;
+        RustMailerTemplatePartial::update(req.id, req.into()).await?;
+        Ok(Response::new(Empty::default()))
+    }
+
+    async fn remove_template_partial(
+        &self,
+        request: Request<DeleteTemplatePartialRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+        let partial = RustMailerTemplatePartial::get(req.id).await?;
+        RustMailerTemplatePartial::check_access(context, partial.account_id())?;
+        RustMailerTemplatePartial::remove(req.id).await?;
+        Ok(Response::new(Empty::default()))
+    }
+
+    async fn list_template_partials(
+        &self,
+        request: Request<ListTemplatesRequest>,
+    ) -> Result<Response<PagedTemplatePartial>, Status> {
+        let req = require_root(request)?;
+        let result =
+            RustMailerTemplatePartial::paginate_list(req.page, req.page_size, req.desc).await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn list_account_template_partials(
+        &self,
+        request: Request<ListAccountTemplatesRequest>,
+    ) -> Result<Response<PagedTemplatePartial>, Status> {
+        let req = require_account_access(request, |r| r.account_id)?;
+        let result = RustMailerTemplatePartial::paginate_list_account(
+            req.account_id,
+            req.page,
+            req.page_size,
+            req.desc,
+        )
+        .await?;
+        Ok(Response::new(result.into()))
+    }
 }
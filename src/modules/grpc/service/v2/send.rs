@@ -0,0 +1,184 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{ops::Bound, sync::Arc};
+
+use futures::{stream, StreamExt};
+use poem_grpc::{Request, Response, Status, Streaming};
+
+use crate::{
+    modules::{
+        common::auth::ClientContext,
+        error::{code::ErrorCode, RustMailerResult},
+        grpc::service::{
+            rustmailer_grpc::{
+                v2::{
+                    EmailTaskPage, ListEmailTasksRequest, SendMailService, StreamEmailTasksRequest,
+                },
+                BulkEmailTaskRequest, BulkEmailTaskResult, EmailTask, Empty, ForwardMailRequest,
+                GetTaskRequest, RemoveTaskRequest, ReplyMailRequest, SendEmailResponse,
+                SendMailService as SendMailServiceV1, SendNewMailRequest,
+            },
+            send::RustMailerSendMailService,
+        },
+        scheduler::{model::TaskStatus, nativedb::TaskMetaEntity},
+        smtp::queue::message::SendEmailTask,
+        tasks::export::{TaskExport, TaskExportKind},
+    },
+    raise_error,
+};
+
+const DEFAULT_PAGE_SIZE: u32 = 100;
+const MAX_PAGE_SIZE: u32 = 1000;
+/// Number of tasks read per round trip while streaming.
+const STREAM_BATCH_SIZE: usize = 500;
+
+#[derive(Default)]
+pub struct RustMailerSendMailServiceV2;
+
+impl SendMailService for RustMailerSendMailServiceV2 {
+    async fn send_new_mail(
+        &self,
+        request: Request<SendNewMailRequest>,
+    ) -> Result<Response<SendEmailResponse>, Status> {
+        RustMailerSendMailService.send_new_mail(request).await
+    }
+
+    async fn reply_mail(
+        &self,
+        request: Request<ReplyMailRequest>,
+    ) -> Result<Response<SendEmailResponse>, Status> {
+        RustMailerSendMailService.reply_mail(request).await
+    }
+
+    async fn forward_mail(
+        &self,
+        request: Request<ForwardMailRequest>,
+    ) -> Result<Response<SendEmailResponse>, Status> {
+        RustMailerSendMailService.forward_mail(request).await
+    }
+
+    async fn list_email_tasks(
+        &self,
+        request: Request<ListEmailTasksRequest>,
+    ) -> Result<Response<EmailTaskPage>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+
+        let page_size = req.page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+        if page_size == 0 || page_size > MAX_PAGE_SIZE {
+            return Err(raise_error!(
+                format!("'page_size' must be between 1 and {MAX_PAGE_SIZE}"),
+                ErrorCode::InvalidParameter
+            )
+            .into());
+        }
+        let export = TaskExport::new(
+            context,
+            TaskExportKind::Email,
+            req.created_after,
+            req.created_before,
+            status(req.status)?,
+            None,
+        )?;
+        let cursor = match req.cursor {
+            Some(cursor) => Bound::Excluded(cursor),
+            None => export.start(),
+        };
+        let (tasks, next) = export.next_page(cursor, page_size as usize).await?;
+        Ok(Response::new(EmailTaskPage {
+            items: to_email_tasks(&tasks)?,
+            next_cursor: match next {
+                Some(Bound::Excluded(cursor)) => Some(cursor),
+                _ => None,
+            },
+        }))
+    }
+
+    async fn stream_email_tasks(
+        &self,
+        request: Request<StreamEmailTasksRequest>,
+    ) -> Result<Response<Streaming<EmailTask>>, Status> {
+        let extensions = request.extensions().clone();
+        let req = request.into_inner();
+        let context = extensions.get::<Arc<ClientContext>>().ok_or_else(|| {
+            raise_error!("Missing ClientContext".into(), ErrorCode::InternalError)
+        })?;
+
+        let export = Arc::new(TaskExport::new(
+            context,
+            TaskExportKind::Email,
+            req.created_after,
+            req.created_before,
+            status(req.status)?,
+            None,
+        )?);
+        let pages = stream::try_unfold(Some(export.start()), move |cursor| {
+            let export = export.clone();
+            async move {
+                let Some(cursor) = cursor else {
+                    return Ok(None);
+                };
+                let (tasks, next) = export.next_page(cursor, STREAM_BATCH_SIZE).await?;
+                if tasks.is_empty() {
+                    return Ok(None);
+                }
+                Ok(Some((to_email_tasks(&tasks)?, next)))
+            }
+        });
+        let tasks = pages.flat_map(|page: RustMailerResult<Vec<EmailTask>>| {
+            let items: Vec<Result<EmailTask, Status>> = match page {
+                Ok(tasks) => tasks.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e.into())],
+            };
+            stream::iter(items)
+        });
+        Ok(Response::new(Streaming::new(tasks)))
+    }
+
+    async fn get_email_task(
+        &self,
+        request: Request<GetTaskRequest>,
+    ) -> Result<Response<EmailTask>, Status> {
+        RustMailerSendMailService.get_email_task(request).await
+    }
+
+    async fn remove_email_task(
+        &self,
+        request: Request<RemoveTaskRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        RustMailerSendMailService.remove_email_task(request).await
+    }
+
+    async fn cancel_email_tasks(
+        &self,
+        request: Request<BulkEmailTaskRequest>,
+    ) -> Result<Response<BulkEmailTaskResult>, Status> {
+        RustMailerSendMailService.cancel_email_tasks(request).await
+    }
+
+    async fn retry_email_tasks(
+        &self,
+        request: Request<BulkEmailTaskRequest>,
+    ) -> Result<Response<BulkEmailTaskResult>, Status> {
+        RustMailerSendMailService.retry_email_tasks(request).await
+    }
+}
+
+fn status(status: Option<i32>) -> RustMailerResult<Option<TaskStatus>> {
+    status
+        .map(TaskStatus::try_from)
+        .transpose()
+        .map_err(|e: &'static str| raise_error!(e.to_string(), ErrorCode::InvalidParameter))
+}
+
+fn to_email_tasks(tasks: &[TaskMetaEntity]) -> RustMailerResult<Vec<EmailTask>> {
+    tasks
+        .iter()
+        .map(|task| SendEmailTask::try_from(task).map(Into::into))
+        .collect()
+}
@@ -41,6 +41,9 @@ async fn test1() {
         page_size: 10,
         remote: false,
         desc: true,
+        importance: None,
+        sort_by_importance: None,
+        sort_by: None,
     };
 
     let mut request = poem_grpc::Request::new(request);
@@ -104,6 +107,7 @@ async fn test3() {
         page: 1,
         page_size: 15,
         desc: true,
+        query: None,
     };
 
     let mut request = poem_grpc::Request::new(request);
@@ -160,6 +164,7 @@ async fn test5() {
     let request = GetThreadMessagesRequest {
         account_id: 6606017263301165,
         thread_id: 1572863359614161,
+        scope: None,
     };
 
     let mut request = poem_grpc::Request::new(request);
@@ -191,6 +196,7 @@ async fn test6() {
         text: Some("hello world.".into()),
         html: None,
         draft_folder_path: Some("[Gmail]/Drafts".into()),
+        subject_prefixes: None,
     };
 
     let mut request = poem_grpc::Request::new(request);
@@ -251,6 +257,7 @@ async fn test8() {
         html: None,
         draft_folder_path: None,
         id: "1970d297da3c2dd2".into(),
+        subject_prefixes: None,
     };
 
     let mut request = poem_grpc::Request::new(request);
@@ -0,0 +1,77 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use http::HeaderValue;
+use poem::{Endpoint, IntoResponse, Middleware, Request, Response, Result};
+
+/// Response metadata set on calls to v1 services that have a v2 counterpart, holding the
+/// fully qualified name of the replacement service.
+pub const DEPRECATION_HEADER: &str = "x-rustmailer-deprecated";
+
+/// v1 services superseded by a v2 service, with their replacement.
+const SUPERSEDED_SERVICES: [(&str, &str); 1] = [(
+    "rustmailer.grpc.SendMailService",
+    "rustmailer.grpc.v2.SendMailService",
+)];
+
+/// Returns the replacement of the service called at `path` (`/{package}.{service}/{method}`),
+/// if the service is deprecated.
+pub fn replacement_service(path: &str) -> Option<&'static str> {
+    let service = path.strip_prefix('/')?.split('/').next()?;
+    SUPERSEDED_SERVICES
+        .iter()
+        .find(|(v1, _)| *v1 == service)
+        .map(|(_, v2)| *v2)
+}
+
+/// Adds the deprecation metadata to responses of superseded v1 services.
+pub struct GrpcDeprecation;
+
+impl<E: Endpoint> Middleware<E> for GrpcDeprecation {
+    type Output = GrpcDeprecationEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        GrpcDeprecationEndpoint { ep }
+    }
+}
+
+pub struct GrpcDeprecationEndpoint<E> {
+    ep: E,
+}
+
+impl<E: Endpoint> Endpoint for GrpcDeprecationEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let replacement = replacement_service(req.uri().path());
+        let mut response = self.ep.call(req).await?.into_response();
+        if let Some(replacement) = replacement {
+            response
+                .headers_mut()
+                .insert(DEPRECATION_HEADER, HeaderValue::from_static(replacement));
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_replacement_of_superseded_services() {
+        assert_eq!(
+            replacement_service("/rustmailer.grpc.SendMailService/ListEmailTasks"),
+            Some("rustmailer.grpc.v2.SendMailService")
+        );
+        assert_eq!(
+            replacement_service("/rustmailer.grpc.v2.SendMailService/ListEmailTasks"),
+            None
+        );
+        assert_eq!(
+            replacement_service("/rustmailer.grpc.AccountService/ListAccounts"),
+            None
+        );
+    }
+}
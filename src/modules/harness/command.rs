@@ -0,0 +1,242 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+/// An argument of an IMAP command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    /// An atom, including bracketed section specs like `BODY.PEEK[HEADER.FIELDS (To)]<0.10>`.
+    Atom(String),
+    /// A quoted string or literal.
+    String(Vec<u8>),
+    List(Vec<Token>),
+}
+
+impl Token {
+    /// The token as text, if it is an atom or a string.
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Token::Atom(atom) => Some(atom.clone()),
+            Token::String(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+            Token::List(_) => None,
+        }
+    }
+
+    /// The texts of a parenthesized list, or of a single atom or string.
+    pub fn texts(&self) -> Vec<String> {
+        match self {
+            Token::List(tokens) => tokens.iter().filter_map(Token::text).collect(),
+            token => token.text().into_iter().collect(),
+        }
+    }
+}
+
+/// Splits a command, without its final CRLF, into tokens.
+pub fn tokenize(input: &[u8]) -> Result<Vec<Token>, String> {
+    Tokenizer { input, position: 0 }.tokens(false)
+}
+
+struct Tokenizer<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Tokenizer<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn tokens(&mut self, nested: bool) -> Result<Vec<Token>, String> {
+        let mut tokens = Vec::new();
+        loop {
+            while self.peek() == Some(b' ') {
+                self.position += 1;
+            }
+            match self.peek() {
+                None if nested => return Err("Unterminated list".into()),
+                None => return Ok(tokens),
+                Some(b')') if nested => {
+                    self.position += 1;
+                    return Ok(tokens);
+                }
+                Some(b')') => return Err("Unexpected ')'".into()),
+                Some(b'(') => {
+                    self.position += 1;
+                    tokens.push(Token::List(self.tokens(true)?));
+                }
+                Some(b'"') => tokens.push(self.quoted()?),
+                Some(b'{') => tokens.push(self.literal()?),
+                Some(_) => tokens.push(self.atom()),
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<Token, String> {
+        self.position += 1;
+        let mut value = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err("Unterminated quoted string".into()),
+                Some(b'"') => {
+                    self.position += 1;
+                    return Ok(Token::String(value));
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    value.push(self.peek().ok_or("Unterminated quoted string")?);
+                }
+                Some(byte) => value.push(byte),
+            }
+            self.position += 1;
+        }
+    }
+
+    fn literal(&mut self) -> Result<Token, String> {
+        let rest = &self.input[self.position..];
+        let close = rest
+            .iter()
+            .position(|b| *b == b'}')
+            .ok_or("Invalid literal")?;
+        let spec = std::str::from_utf8(&rest[1..close]).map_err(|_| "Invalid literal")?;
+        let length: usize = spec
+            .trim_end_matches('+')
+            .parse()
+            .map_err(|_| "Invalid literal length")?;
+        let start = self.position + close + 1;
+        let start = if self.input[start..].starts_with(b"\r\n") {
+            start + 2
+        } else {
+            return Err("Literal must be followed by CRLF".into());
+        };
+        let value = self
+            .input
+            .get(start..start + length)
+            .ok_or("Literal is shorter than announced")?;
+        self.position = start + length;
+        Ok(Token::String(value.to_vec()))
+    }
+
+    /// Reads an atom. Brackets may hold spaces and parentheses, as in section specs.
+    fn atom(&mut self) -> Token {
+        let start = self.position;
+        let mut depth = 0;
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'[' => depth += 1,
+                b']' if depth > 0 => depth -= 1,
+                b' ' | b'(' | b')' | b'"' | b'\r' | b'\n' if depth == 0 => break,
+                _ => {}
+            }
+            self.position += 1;
+        }
+        Token::Atom(String::from_utf8_lossy(&self.input[start..self.position]).into_owned())
+    }
+}
+
+/// The length of the literal a command line ends with, and whether the client waits for a
+/// continuation before sending it (`{n}`, as opposed to `{n+}`).
+pub fn literal_length(line: &[u8]) -> Option<(usize, bool)> {
+    let line = line
+        .strip_suffix(b"\r\n")
+        .or_else(|| line.strip_suffix(b"\n"))?;
+    let line = line.strip_suffix(b"}")?;
+    let open = line.iter().rposition(|b| *b == b'{')?;
+    let spec = std::str::from_utf8(&line[open + 1..]).ok()?;
+    match spec.strip_suffix('+') {
+        Some(length) => Some((length.parse().ok()?, false)),
+        None => Some((spec.parse().ok()?, true)),
+    }
+}
+
+/// A set of message sequence numbers or UIDs, e.g. `1:4,7,9:*`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SequenceSet(Vec<(Bound, Bound)>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Bound {
+    Number(u32),
+    /// `*`: the largest number in use.
+    Last,
+}
+
+impl Bound {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "*" => Some(Bound::Last),
+            _ => value.parse().ok().map(Bound::Number),
+        }
+    }
+
+    fn resolve(self, last: u32) -> u32 {
+        match self {
+            Bound::Number(n) => n,
+            Bound::Last => last,
+        }
+    }
+}
+
+impl SequenceSet {
+    pub fn parse(value: &str) -> Option<Self> {
+        value
+            .split(',')
+            .map(|range| match range.split_once(':') {
+                Some((start, end)) => Some((Bound::parse(start)?, Bound::parse(end)?)),
+                None => Bound::parse(range).map(|bound| (bound, bound)),
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(Self)
+    }
+
+    /// Whether the set holds `n`, given the largest number in use.
+    pub fn contains(&self, n: u32, last: u32) -> bool {
+        self.0.iter().any(|(start, end)| {
+            let (start, end) = (start.resolve(last), end.resolve(last));
+            (start.min(end)..=start.max(end)).contains(&n)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_commands() {
+        let tokens =
+            tokenize(b"a1 UID FETCH 1:* (UID BODY.PEEK[HEADER.FIELDS (To Cc)]<0.10>) \"a \\\"b\\\"\" {3}\r\nxyz")
+                .unwrap();
+        assert_eq!(
+            tokens,
+            [
+                Token::Atom("a1".into()),
+                Token::Atom("UID".into()),
+                Token::Atom("FETCH".into()),
+                Token::Atom("1:*".into()),
+                Token::List(vec![
+                    Token::Atom("UID".into()),
+                    Token::Atom("BODY.PEEK[HEADER.FIELDS (To Cc)]<0.10>".into()),
+                ]),
+                Token::String(b"a \"b\"".to_vec()),
+                Token::String(b"xyz".to_vec()),
+            ]
+        );
+        assert!(tokenize(b"a1 FETCH (UID").is_err());
+        assert_eq!(literal_length(b"a APPEND INBOX {12}\r\n"), Some((12, true)));
+        assert_eq!(
+            literal_length(b"a APPEND INBOX {12+}\r\n"),
+            Some((12, false))
+        );
+        assert_eq!(literal_length(b"a NOOP\r\n"), None);
+    }
+
+    #[test]
+    fn matches_sequence_sets() {
+        let set = SequenceSet::parse("2:4,9,12:*").unwrap();
+        assert!(set.contains(3, 20));
+        assert!(set.contains(9, 20));
+        assert!(!set.contains(10, 20));
+        assert!(set.contains(20, 20));
+        assert!(SequenceSet::parse("*:5").unwrap().contains(6, 7));
+        assert!(SequenceSet::parse("1,x").is_none());
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::path::{Path, PathBuf};
+
+use mail_parser::MessageParser;
+use serde::{Deserialize, Serialize};
+
+/// Internal date of seeded messages without a usable `Date` header: 2025-01-01T00:00:00Z.
+const DEFAULT_INTERNAL_DATE: i64 = 1_735_689_600_000;
+
+/// Initial state of the in-memory servers, read from the JSON file set with
+/// `rustmailer_test_harness_fixture`, e.g.
+///
+/// ```json
+/// {
+///   "accounts": [{
+///     "login": "alice@example.com",
+///     "mailboxes": [
+///       {"name": "INBOX", "messages": [{"file": "welcome.eml", "flags": ["\\Seen"]}]},
+///       {"name": "Sent", "attributes": ["\\Sent"]}
+///     ],
+///     "imap": [{"type": "Disconnect", "after_commands": 3, "times": 1}],
+///     "smtp": [{"type": "Greylist", "attempts": 1}]
+///   }]
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HarnessFixture {
+    #[serde(default)]
+    pub accounts: Vec<AccountFixture>,
+    /// Directory the `file` paths of messages are relative to: the directory of the fixture.
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+impl HarnessFixture {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            format!(
+                "Failed to read the test harness fixture '{}': {}",
+                path.display(),
+                e
+            )
+        })?;
+        let mut fixture: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid test harness fixture '{}': {}", path.display(), e))?;
+        fixture.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(fixture)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AccountFixture {
+    /// Name the account logs in with: the email address of an account, or the username of
+    /// an MTA.
+    pub login: String,
+    /// Mailboxes of the account. Without any, the account gets INBOX, Sent, Drafts and Trash.
+    #[serde(default)]
+    pub mailboxes: Vec<MailboxFixture>,
+    /// Scripted behaviors of the IMAP server for this login.
+    #[serde(default)]
+    pub imap: Vec<ScriptedBehavior>,
+    /// Scripted behaviors of the SMTP server for this login.
+    #[serde(default)]
+    pub smtp: Vec<ScriptedBehavior>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MailboxFixture {
+    /// Name of the mailbox as sent over IMAP (modified UTF-7), with `/` as the hierarchy
+    /// delimiter.
+    pub name: String,
+    /// Attributes returned by LIST, e.g. `\Sent` or `\Trash`.
+    #[serde(default)]
+    pub attributes: Vec<String>,
+    /// UIDVALIDITY of the mailbox, 1 by default.
+    pub uid_validity: Option<u32>,
+    /// Messages of the mailbox, given UIDs from 1 in order.
+    #[serde(default)]
+    pub messages: Vec<MessageFixture>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MessageFixture {
+    /// The RFC 5322 message.
+    pub raw: Option<String>,
+    /// Path of an `.eml` file holding the message, relative to the fixture. Alternative to
+    /// `raw`.
+    pub file: Option<PathBuf>,
+    /// Flags of the message, e.g. `\Seen` or `$Important`.
+    #[serde(default)]
+    pub flags: Vec<String>,
+    /// Internal date (in milliseconds) of the message. Defaults to its `Date` header.
+    pub internal_date: Option<i64>,
+}
+
+impl MessageFixture {
+    pub fn content(&self, base_dir: &Path) -> Result<Vec<u8>, String> {
+        match (&self.raw, &self.file) {
+            (Some(raw), None) => Ok(raw.clone().into_bytes()),
+            (None, Some(file)) => {
+                let path = base_dir.join(file);
+                std::fs::read(&path).map_err(|e| {
+                    format!(
+                        "Failed to read the fixture message '{}': {}",
+                        path.display(),
+                        e
+                    )
+                })
+            }
+            _ => Err("Fixture messages need exactly one of 'raw' and 'file'".into()),
+        }
+    }
+}
+
+/// The internal date of a message without an explicit one: its `Date` header, or a fixed
+/// date so runs stay deterministic.
+pub fn default_internal_date(raw: &[u8]) -> i64 {
+    MessageParser::default()
+        .parse_headers(raw)
+        .and_then(|headers| headers.date().map(|date| date.to_timestamp() * 1000))
+        .unwrap_or(DEFAULT_INTERNAL_DATE)
+}
+
+/// Server behavior scripted for a login, to exercise the error handling of workflows.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ScriptedBehavior {
+    /// Rejects the first `times` logins with an authentication failure.
+    RejectLogin { times: u32 },
+    /// Drops the connection without a response once `after_commands` commands were handled
+    /// after login, on each of the first `times` connections.
+    Disconnect { after_commands: u32, times: u32 },
+    /// Delays every response by `millis` milliseconds.
+    Latency { millis: u64 },
+    /// SMTP only. Answers `451 4.7.1` to a recipient the first `attempts` times it is used
+    /// with the same sender, as greylisting servers do.
+    Greylist { attempts: u32 },
+    /// SMTP only. Permanently rejects the recipient with `550 5.1.1`.
+    RejectRecipient { address: String },
+}
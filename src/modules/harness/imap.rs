@@ -0,0 +1,1234 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::io;
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::DateTime;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tracing::debug;
+
+use crate::modules::harness::command::{literal_length, tokenize, SequenceSet, Token};
+use crate::modules::harness::mime::{write_string, Entity, Section, SectionKind};
+use crate::modules::harness::search::{Position, SearchKey};
+use crate::modules::harness::store::{
+    Mailstore, Protocol, SessionScript, StubAccount, StubMailbox, StubMessage,
+};
+use crate::modules::harness::{sasl_user, PIPE_CAPACITY};
+use crate::modules::imap::append::format_internal_date;
+use crate::utc_now;
+
+const CAPABILITIES: &str =
+    "IMAP4rev1 LITERAL+ IDLE UIDPLUS MOVE ID ENABLE NAMESPACE UNSELECT AUTH=PLAIN AUTH=XOAUTH2";
+const SYSTEM_FLAGS: &str = "\\Answered \\Flagged \\Deleted \\Seen \\Draft";
+const DELIMITER: char = '/';
+const INTERNAL_DATE_FORMAT: &str = "%d-%b-%Y %H:%M:%S %z";
+
+/// Opens a connection to a new session of the in-memory IMAP server.
+pub fn connect(mailstore: Arc<Mailstore>) -> DuplexStream {
+    let (client, server) = tokio::io::duplex(PIPE_CAPACITY);
+    tokio::spawn(async move {
+        if let Err(e) = Session::new(mailstore, server).run().await {
+            debug!("In-memory IMAP session failed: {:#?}", e);
+        }
+    });
+    client
+}
+
+/// How a command ends: the status of its tagged response, or the end of the connection.
+enum Completion {
+    Ok(String),
+    No(String),
+    Bad(String),
+    Logout,
+    Closed,
+}
+
+#[derive(Clone)]
+struct Selected {
+    name: String,
+    read_only: bool,
+    /// Number of messages the client was last told about.
+    exists: usize,
+}
+
+struct Session {
+    mailstore: Arc<Mailstore>,
+    stream: BufReader<DuplexStream>,
+    /// Bytes of a line not fully received yet.
+    pending: Vec<u8>,
+    login: Option<String>,
+    script: SessionScript,
+    /// Commands handled since login.
+    commands: u32,
+    selected: Option<Selected>,
+}
+
+impl Session {
+    fn new(mailstore: Arc<Mailstore>, stream: DuplexStream) -> Self {
+        Self {
+            mailstore,
+            stream: BufReader::new(stream),
+            pending: Vec::new(),
+            login: None,
+            script: SessionScript::default(),
+            commands: 0,
+            selected: None,
+        }
+    }
+
+    async fn run(mut self) -> io::Result<()> {
+        self.send(format!(
+            "* OK [CAPABILITY {}] RustMailer test harness ready\r\n",
+            CAPABILITIES
+        ))
+        .await?;
+        while let Some(command) = self.read_command().await? {
+            let command = command
+                .strip_suffix(b"\r\n")
+                .or_else(|| command.strip_suffix(b"\n"))
+                .unwrap_or(&command);
+            let mut tokens = match tokenize(command) {
+                Ok(tokens) => tokens.into_iter(),
+                Err(e) => {
+                    self.send(format!("* BAD {}\r\n", e)).await?;
+                    continue;
+                }
+            };
+            let (Some(Token::Atom(tag)), Some(name)) =
+                (tokens.next(), tokens.next().and_then(|t| t.text()))
+            else {
+                self.send("* BAD Missing command\r\n").await?;
+                continue;
+            };
+            let args: Vec<Token> = tokens.collect();
+
+            if self.login.is_some() {
+                self.commands += 1;
+                if self
+                    .script
+                    .disconnect_after
+                    .is_some_and(|limit| self.commands > limit)
+                {
+                    debug!("In-memory IMAP server drops the connection as scripted");
+                    return Ok(());
+                }
+                if let Some(latency) = self.script.latency {
+                    tokio::time::sleep(latency).await;
+                }
+            }
+
+            match self.handle(&name.to_ascii_uppercase(), &args).await? {
+                Completion::Ok(text) => self.send(format!("{} OK {}\r\n", tag, text)).await?,
+                Completion::No(text) => self.send(format!("{} NO {}\r\n", tag, text)).await?,
+                Completion::Bad(text) => self.send(format!("{} BAD {}\r\n", tag, text)).await?,
+                Completion::Logout => {
+                    self.send(format!(
+                        "* BYE Logging out\r\n{} OK LOGOUT completed\r\n",
+                        tag
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                Completion::Closed => return Ok(()),
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle(&mut self, name: &str, args: &[Token]) -> io::Result<Completion> {
+        match name {
+            "CAPABILITY" => {
+                self.send(format!("* CAPABILITY {}\r\n", CAPABILITIES))
+                    .await?;
+                return Ok(Completion::Ok("CAPABILITY completed".into()));
+            }
+            "NOOP" | "CHECK" => {
+                self.report_changes().await?;
+                return Ok(Completion::Ok(format!("{} completed", name)));
+            }
+            "LOGOUT" => return Ok(Completion::Logout),
+            "ID" => {
+                self.send("* ID (\"name\" \"RustMailer test harness\")\r\n")
+                    .await?;
+                return Ok(Completion::Ok("ID completed".into()));
+            }
+            "ENABLE" => {
+                self.send("* ENABLED\r\n").await?;
+                return Ok(Completion::Ok("ENABLE completed".into()));
+            }
+            "LOGIN" => return Ok(self.login(args.first().and_then(Token::text))),
+            "AUTHENTICATE" => return self.authenticate(args).await,
+            _ => {}
+        }
+        if self.login.is_none() {
+            return Ok(Completion::No("Not authenticated".into()));
+        }
+        match name {
+            "NAMESPACE" => {
+                self.send(format!(
+                    "* NAMESPACE ((\"\" \"{}\")) NIL NIL\r\n",
+                    DELIMITER
+                ))
+                .await?;
+                Ok(Completion::Ok("NAMESPACE completed".into()))
+            }
+            "LIST" => self.list(args, false).await,
+            "LSUB" => self.list(args, true).await,
+            "SELECT" => self.select(args, false).await,
+            "EXAMINE" => self.select(args, true).await,
+            "STATUS" => self.status(args).await,
+            "CREATE" | "DELETE" | "RENAME" | "SUBSCRIBE" | "UNSUBSCRIBE" => {
+                Ok(self.manage(name, args))
+            }
+            "APPEND" => Ok(self.append(args)),
+            "IDLE" => self.idle().await,
+            "UID" => match args.first().and_then(Token::text) {
+                Some(command) => {
+                    self.handle_selected(&command.to_ascii_uppercase(), &args[1..], true)
+                        .await
+                }
+                None => Ok(Completion::Bad("Missing UID command".into())),
+            },
+            _ => self.handle_selected(name, args, false).await,
+        }
+    }
+
+    /// Handles the commands that need a selected mailbox.
+    async fn handle_selected(
+        &mut self,
+        name: &str,
+        args: &[Token],
+        uid: bool,
+    ) -> io::Result<Completion> {
+        const COMMANDS: &[&str] = &[
+            "FETCH", "STORE", "SEARCH", "COPY", "MOVE", "EXPUNGE", "CLOSE", "UNSELECT",
+        ];
+        if !COMMANDS.contains(&name) || (uid && matches!(name, "CLOSE" | "UNSELECT")) {
+            return Ok(Completion::Bad(format!("Unknown command {}", name)));
+        }
+        let Some(selected) = self.selected.clone() else {
+            return Ok(Completion::No("No mailbox selected".into()));
+        };
+        match name {
+            "FETCH" => self.fetch(&selected, args, uid).await,
+            "STORE" => self.store(&selected, args, uid).await,
+            "SEARCH" => self.search(&selected, args, uid).await,
+            "COPY" => self.copy(&selected, args, uid, false).await,
+            "MOVE" => self.copy(&selected, args, uid, true).await,
+            "EXPUNGE" => self.expunge(&selected, args, uid).await,
+            "CLOSE" => {
+                // CLOSE expunges silently.
+                if !selected.read_only {
+                    self.with_mailbox(&selected.name, |mailbox| {
+                        mailbox.messages.retain(|m| !m.has_flag("\\Deleted"))
+                    });
+                    self.mailstore.notify_changes();
+                }
+                self.selected = None;
+                Ok(Completion::Ok("CLOSE completed".into()))
+            }
+            _ => {
+                self.selected = None;
+                Ok(Completion::Ok("UNSELECT completed".into()))
+            }
+        }
+    }
+
+    fn login(&mut self, user: Option<String>) -> Completion {
+        let Some(user) = user.filter(|user| !user.is_empty()) else {
+            return Completion::Bad("Missing user name".into());
+        };
+        let script = self.mailstore.login(Protocol::Imap, &user);
+        if script.rejected {
+            return Completion::No("[AUTHENTICATIONFAILED] Invalid credentials".into());
+        }
+        self.login = Some(user);
+        self.script = script;
+        self.commands = 0;
+        Completion::Ok("Logged in".into())
+    }
+
+    async fn authenticate(&mut self, args: &[Token]) -> io::Result<Completion> {
+        let mechanism = args
+            .first()
+            .and_then(Token::text)
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        if mechanism != "PLAIN" && mechanism != "XOAUTH2" {
+            return Ok(Completion::No(
+                "Unsupported authentication mechanism".into(),
+            ));
+        }
+        let response = match args.get(1).and_then(Token::text) {
+            Some(initial) => initial,
+            None => {
+                self.send("+ \r\n").await?;
+                let Some(line) = self.read_line().await? else {
+                    return Ok(Completion::Closed);
+                };
+                String::from_utf8_lossy(&line).into_owned()
+            }
+        };
+        let user = STANDARD
+            .decode(response.trim())
+            .ok()
+            .and_then(|response| sasl_user(&mechanism, &response));
+        Ok(self.login(user))
+    }
+
+    async fn list(&mut self, args: &[Token], subscribed_only: bool) -> io::Result<Completion> {
+        let command = if subscribed_only { "LSUB" } else { "LIST" };
+        let (Some(reference), Some(pattern)) = (
+            args.first().and_then(Token::text),
+            args.get(1).and_then(Token::text),
+        ) else {
+            return Ok(Completion::Bad(format!("Invalid {} arguments", command)));
+        };
+        if pattern.is_empty() {
+            self.send(format!(
+                "* {} (\\Noselect) \"{}\" \"\"\r\n",
+                command, DELIMITER
+            ))
+            .await?;
+            return Ok(Completion::Ok(format!("{} completed", command)));
+        }
+        let pattern = format!("{}{}", reference, pattern);
+        let response = self.with_account(|account| {
+            let mut out = Vec::new();
+            for mailbox in &account.mailboxes {
+                if (subscribed_only && !mailbox.subscribed)
+                    || !wildcard_match(pattern.as_bytes(), mailbox.name.as_bytes())
+                {
+                    continue;
+                }
+                let prefix = format!("{}{}", mailbox.name, DELIMITER);
+                let children = account
+                    .mailboxes
+                    .iter()
+                    .any(|m| m.name.starts_with(&prefix));
+                let mut attributes = mailbox.attributes.clone();
+                attributes.push(
+                    if children {
+                        "\\HasChildren"
+                    } else {
+                        "\\HasNoChildren"
+                    }
+                    .into(),
+                );
+                out.extend_from_slice(
+                    format!(
+                        "* {} ({}) \"{}\" ",
+                        command,
+                        attributes.join(" "),
+                        DELIMITER
+                    )
+                    .as_bytes(),
+                );
+                write_string(&mut out, mailbox.name.as_bytes());
+                out.extend_from_slice(b"\r\n");
+            }
+            out
+        });
+        self.send(response).await?;
+        Ok(Completion::Ok(format!("{} completed", command)))
+    }
+
+    async fn select(&mut self, args: &[Token], read_only: bool) -> io::Result<Completion> {
+        let Some(name) = args.first().and_then(Token::text) else {
+            return Ok(Completion::Bad("Missing mailbox name".into()));
+        };
+        self.selected = None;
+        let Some((name, exists, first_unseen, uid_validity, uid_next)) =
+            self.with_account(|account| {
+                account.mailbox(&name).map(|mailbox| {
+                    (
+                        mailbox.name.clone(),
+                        mailbox.messages.len(),
+                        mailbox.messages.iter().position(|m| !m.has_flag("\\Seen")),
+                        mailbox.uid_validity,
+                        mailbox.uid_next,
+                    )
+                })
+            })
+        else {
+            return Ok(Completion::No(
+                "[NONEXISTENT] Mailbox does not exist".into(),
+            ));
+        };
+        let mut response = format!("* FLAGS ({})\r\n", SYSTEM_FLAGS);
+        if read_only {
+            response.push_str("* OK [PERMANENTFLAGS ()] Read-only mailbox\r\n");
+        } else {
+            response.push_str(&format!(
+                "* OK [PERMANENTFLAGS ({} \\*)] Flags permitted\r\n",
+                SYSTEM_FLAGS
+            ));
+        }
+        response.push_str(&format!("* {} EXISTS\r\n* 0 RECENT\r\n", exists));
+        if let Some(index) = first_unseen {
+            response.push_str(&format!("* OK [UNSEEN {}] First unseen\r\n", index + 1));
+        }
+        response.push_str(&format!(
+            "* OK [UIDVALIDITY {}] UIDs valid\r\n* OK [UIDNEXT {}] Predicted next UID\r\n",
+            uid_validity, uid_next
+        ));
+        self.send(response).await?;
+        self.selected = Some(Selected {
+            name,
+            read_only,
+            exists,
+        });
+        Ok(Completion::Ok(
+            if read_only {
+                "[READ-ONLY] EXAMINE completed"
+            } else {
+                "[READ-WRITE] SELECT completed"
+            }
+            .into(),
+        ))
+    }
+
+    async fn status(&mut self, args: &[Token]) -> io::Result<Completion> {
+        let (Some(name), Some(items)) = (args.first().and_then(Token::text), args.get(1)) else {
+            return Ok(Completion::Bad("Invalid STATUS arguments".into()));
+        };
+        let values = self.with_account(|account| {
+            account.mailbox(&name).map(|mailbox| {
+                items
+                    .texts()
+                    .iter()
+                    .filter_map(|item| {
+                        let item = item.to_ascii_uppercase();
+                        let value = match item.as_str() {
+                            "MESSAGES" => mailbox.messages.len() as u32,
+                            "RECENT" => 0,
+                            "UIDNEXT" => mailbox.uid_next,
+                            "UIDVALIDITY" => mailbox.uid_validity,
+                            "UNSEEN" => mailbox.unseen() as u32,
+                            _ => return None,
+                        };
+                        Some(format!("{} {}", item, value))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+        });
+        let Some(values) = values else {
+            return Ok(Completion::No(
+                "[NONEXISTENT] Mailbox does not exist".into(),
+            ));
+        };
+        let mut response = b"* STATUS ".to_vec();
+        write_string(&mut response, name.as_bytes());
+        response.extend_from_slice(format!(" ({})\r\n", values).as_bytes());
+        self.send(response).await?;
+        Ok(Completion::Ok("STATUS completed".into()))
+    }
+
+    /// Handles CREATE, DELETE, RENAME, SUBSCRIBE and UNSUBSCRIBE.
+    fn manage(&self, command: &str, args: &[Token]) -> Completion {
+        let Some(name) = args.first().and_then(Token::text) else {
+            return Completion::Bad("Missing mailbox name".into());
+        };
+        let name = name.trim_end_matches(DELIMITER).to_string();
+        let target = args.get(1).and_then(Token::text);
+        let result = self.with_account(|account| -> Result<(), &'static str> {
+            let exists = account.mailbox(&name).is_some();
+            match command {
+                "CREATE" if exists => Err("[ALREADYEXISTS] Mailbox already exists"),
+                "CREATE" => {
+                    account
+                        .mailboxes
+                        .push(StubMailbox::new(name.clone(), Vec::new(), 1));
+                    Ok(())
+                }
+                _ if !exists => Err("[NONEXISTENT] Mailbox does not exist"),
+                "DELETE" | "RENAME" if name.eq_ignore_ascii_case("INBOX") => {
+                    Err("INBOX cannot be deleted or renamed")
+                }
+                "DELETE" => {
+                    account.mailboxes.retain(|m| m.name != name);
+                    Ok(())
+                }
+                "RENAME" => {
+                    let target = target.as_deref().ok_or("Missing new mailbox name")?;
+                    if account.mailbox(target).is_some() {
+                        return Err("[ALREADYEXISTS] Mailbox already exists");
+                    }
+                    // Children move along with the mailbox.
+                    let prefix = format!("{}{}", name, DELIMITER);
+                    for mailbox in &mut account.mailboxes {
+                        let renamed = if mailbox.name == name {
+                            Some(target.to_string())
+                        } else {
+                            mailbox
+                                .name
+                                .strip_prefix(&prefix)
+                                .map(|rest| format!("{}{}{}", target, DELIMITER, rest))
+                        };
+                        if let Some(renamed) = renamed {
+                            mailbox.name = renamed;
+                        }
+                    }
+                    Ok(())
+                }
+                _ => {
+                    if let Some(mailbox) = account.mailbox_mut(&name) {
+                        mailbox.subscribed = command == "SUBSCRIBE";
+                    }
+                    Ok(())
+                }
+            }
+        });
+        match result {
+            Ok(()) => Completion::Ok(format!("{} completed", command)),
+            Err(e) => Completion::No(e.into()),
+        }
+    }
+
+    fn append(&self, args: &[Token]) -> Completion {
+        let Some(name) = args.first().and_then(Token::text) else {
+            return Completion::Bad("Missing mailbox name".into());
+        };
+        let Some((Token::String(raw), options)) = args[1..].split_last() else {
+            return Completion::Bad("Missing message literal".into());
+        };
+        let mut flags = Vec::new();
+        let mut internal_date = None;
+        for option in options {
+            match option {
+                Token::String(date) => {
+                    internal_date = DateTime::parse_from_str(
+                        String::from_utf8_lossy(date).trim(),
+                        INTERNAL_DATE_FORMAT,
+                    )
+                    .ok()
+                    .map(|date| date.timestamp_millis())
+                }
+                flag_list => flags.extend(flag_list.texts()),
+            }
+        }
+        let internal_date = internal_date.unwrap_or_else(|| utc_now!());
+        let appended = self.with_account(|account| {
+            account.mailbox_mut(&name).map(|mailbox| {
+                (
+                    mailbox.uid_validity,
+                    mailbox.append(raw.clone(), flags, internal_date),
+                )
+            })
+        });
+        let Some((uid_validity, uid)) = appended else {
+            return Completion::No("[TRYCREATE] Mailbox does not exist".into());
+        };
+        self.mailstore.notify_changes();
+        Completion::Ok(format!(
+            "[APPENDUID {} {}] APPEND completed",
+            uid_validity, uid
+        ))
+    }
+
+    async fn fetch(
+        &mut self,
+        selected: &Selected,
+        args: &[Token],
+        uid: bool,
+    ) -> io::Result<Completion> {
+        let (Some(set), Some(items)) = (MessageSet::parse(args.first(), uid), args.get(1)) else {
+            return Ok(Completion::Bad("Invalid FETCH arguments".into()));
+        };
+        let mut items = match FetchItem::parse(items) {
+            Ok(items) => items,
+            Err(e) => return Ok(Completion::Bad(e)),
+        };
+        if uid && !items.contains(&FetchItem::Uid) {
+            items.insert(0, FetchItem::Uid);
+        }
+        let marks_seen = !selected.read_only && items.iter().any(FetchItem::marks_seen);
+        if marks_seen && !items.contains(&FetchItem::Flags) {
+            items.push(FetchItem::Flags);
+        }
+        let response = self.with_mailbox(&selected.name, |mailbox| {
+            let mut out = Vec::new();
+            for index in set.indexes(mailbox) {
+                let message = &mut mailbox.messages[index];
+                if marks_seen && !message.has_flag("\\Seen") {
+                    message.flags.push("\\Seen".into());
+                }
+                write_fetch(&mut out, index + 1, message, &items);
+            }
+            out
+        });
+        let Some(response) = response else {
+            return Ok(Completion::No("Mailbox no longer exists".into()));
+        };
+        self.send(response).await?;
+        Ok(Completion::Ok("FETCH completed".into()))
+    }
+
+    async fn store(
+        &mut self,
+        selected: &Selected,
+        args: &[Token],
+        uid: bool,
+    ) -> io::Result<Completion> {
+        let (Some(set), Some(item)) = (
+            MessageSet::parse(args.first(), uid),
+            args.get(1).and_then(Token::text),
+        ) else {
+            return Ok(Completion::Bad("Invalid STORE arguments".into()));
+        };
+        let item = item.to_ascii_uppercase();
+        let silent = item.ends_with(".SILENT");
+        let mode = item.trim_end_matches(".SILENT");
+        if !matches!(mode, "FLAGS" | "+FLAGS" | "-FLAGS") {
+            return Ok(Completion::Bad(format!("Unknown STORE item {}", item)));
+        }
+        if selected.read_only {
+            return Ok(Completion::No("Mailbox is read-only".into()));
+        }
+        let flags: Vec<String> = args[2..].iter().flat_map(Token::texts).collect();
+        let items: &[FetchItem] = if uid {
+            &[FetchItem::Uid, FetchItem::Flags]
+        } else {
+            &[FetchItem::Flags]
+        };
+        let response = self.with_mailbox(&selected.name, |mailbox| {
+            let mut out = Vec::new();
+            for index in set.indexes(mailbox) {
+                let message = &mut mailbox.messages[index];
+                match mode {
+                    "FLAGS" => message.flags = flags.clone(),
+                    "+FLAGS" => {
+                        for flag in &flags {
+                            if !message.has_flag(flag) {
+                                message.flags.push(flag.clone());
+                            }
+                        }
+                    }
+                    _ => message
+                        .flags
+                        .retain(|f| !flags.iter().any(|flag| flag.eq_ignore_ascii_case(f))),
+                }
+                if !silent {
+                    write_fetch(&mut out, index + 1, message, items);
+                }
+            }
+            out
+        });
+        let Some(response) = response else {
+            return Ok(Completion::No("Mailbox no longer exists".into()));
+        };
+        self.send(response).await?;
+        Ok(Completion::Ok("STORE completed".into()))
+    }
+
+    async fn search(
+        &mut self,
+        selected: &Selected,
+        args: &[Token],
+        uid: bool,
+    ) -> io::Result<Completion> {
+        let key = match SearchKey::parse(args) {
+            Ok(key) => key,
+            Err(e) => return Ok(Completion::Bad(e)),
+        };
+        let numbers = self.with_mailbox(&selected.name, |mailbox| {
+            let last_uid = mailbox.messages.last().map_or(0, |m| m.uid);
+            let last_sequence = mailbox.messages.len() as u32;
+            mailbox
+                .messages
+                .iter()
+                .enumerate()
+                .filter_map(|(index, message)| {
+                    let sequence = index as u32 + 1;
+                    let position = Position {
+                        sequence,
+                        last_sequence,
+                        last_uid,
+                    };
+                    key.matches(message, &position).then_some(if uid {
+                        message.uid
+                    } else {
+                        sequence
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        let Some(numbers) = numbers else {
+            return Ok(Completion::No("Mailbox no longer exists".into()));
+        };
+        let mut response = String::from("* SEARCH");
+        for number in numbers {
+            response.push_str(&format!(" {}", number));
+        }
+        response.push_str("\r\n");
+        self.send(response).await?;
+        Ok(Completion::Ok("SEARCH completed".into()))
+    }
+
+    /// Handles COPY, and MOVE when `remove` is set.
+    async fn copy(
+        &mut self,
+        selected: &Selected,
+        args: &[Token],
+        uid: bool,
+        remove: bool,
+    ) -> io::Result<Completion> {
+        let command = if remove { "MOVE" } else { "COPY" };
+        let (Some(set), Some(target)) = (
+            MessageSet::parse(args.first(), uid),
+            args.get(1).and_then(Token::text),
+        ) else {
+            return Ok(Completion::Bad(format!("Invalid {} arguments", command)));
+        };
+        if remove && selected.read_only {
+            return Ok(Completion::No("Mailbox is read-only".into()));
+        }
+        let result = self.with_account(|account| -> Result<_, &'static str> {
+            let source = account
+                .mailbox(&selected.name)
+                .ok_or("Mailbox no longer exists")?;
+            let indexes = set.indexes(source);
+            let messages: Vec<StubMessage> = indexes
+                .iter()
+                .map(|i| source.messages[*i].clone())
+                .collect();
+            let destination = account
+                .mailbox_mut(&target)
+                .ok_or("[TRYCREATE] Mailbox does not exist")?;
+            let copied: Vec<u32> = messages
+                .iter()
+                .map(|m| destination.append(m.raw.clone(), m.flags.clone(), m.internal_date))
+                .collect();
+            let uid_validity = destination.uid_validity;
+            if remove {
+                if let Some(source) = account.mailbox_mut(&selected.name) {
+                    for index in indexes.iter().rev() {
+                        source.messages.remove(*index);
+                    }
+                }
+            }
+            let uids = messages.iter().map(|m| m.uid).collect::<Vec<_>>();
+            Ok((uid_validity, uids, copied, indexes))
+        });
+        let (uid_validity, uids, copied, indexes) = match result {
+            Ok(result) => result,
+            Err(e) => return Ok(Completion::No(e.into())),
+        };
+        self.mailstore.notify_changes();
+        let code = if copied.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "[COPYUID {} {} {}] ",
+                uid_validity,
+                join_numbers(&uids),
+                join_numbers(&copied)
+            )
+        };
+        if !remove {
+            return Ok(Completion::Ok(format!("{}COPY completed", code)));
+        }
+        self.send(format!("* OK {}Moved\r\n", code)).await?;
+        self.send_expunged(&indexes).await?;
+        Ok(Completion::Ok("MOVE completed".into()))
+    }
+
+    async fn expunge(
+        &mut self,
+        selected: &Selected,
+        args: &[Token],
+        uid: bool,
+    ) -> io::Result<Completion> {
+        if selected.read_only {
+            return Ok(Completion::No("Mailbox is read-only".into()));
+        }
+        let set = match (uid, MessageSet::parse(args.first(), uid)) {
+            (false, _) => None,
+            (true, Some(set)) => Some(set),
+            (true, None) => return Ok(Completion::Bad("Invalid UID EXPUNGE arguments".into())),
+        };
+        let removed = self.with_mailbox(&selected.name, |mailbox| {
+            let candidates = match &set {
+                Some(set) => set.indexes(mailbox),
+                None => (0..mailbox.messages.len()).collect(),
+            };
+            let removed: Vec<usize> = candidates
+                .into_iter()
+                .filter(|index| mailbox.messages[*index].has_flag("\\Deleted"))
+                .collect();
+            for index in removed.iter().rev() {
+                mailbox.messages.remove(*index);
+            }
+            removed
+        });
+        let Some(removed) = removed else {
+            return Ok(Completion::No("Mailbox no longer exists".into()));
+        };
+        if !removed.is_empty() {
+            self.mailstore.notify_changes();
+        }
+        self.send_expunged(&removed).await?;
+        Ok(Completion::Ok("EXPUNGE completed".into()))
+    }
+
+    /// Reports removed messages, given their indexes before removal in ascending order.
+    async fn send_expunged(&mut self, removed: &[usize]) -> io::Result<()> {
+        let mut response = String::new();
+        for index in removed.iter().rev() {
+            response.push_str(&format!("* {} EXPUNGE\r\n", index + 1));
+        }
+        if let Some(selected) = self.selected.as_mut() {
+            selected.exists = selected.exists.saturating_sub(removed.len());
+        }
+        self.send(response).await
+    }
+
+    async fn idle(&mut self) -> io::Result<Completion> {
+        self.send("+ idling\r\n").await?;
+        let mailstore = self.mailstore.clone();
+        loop {
+            let changed = mailstore.changes().notified();
+            let line = tokio::select! {
+                line = self.read_line() => Some(line?),
+                _ = changed => None,
+            };
+            match line {
+                None => self.report_changes().await?,
+                Some(None) => return Ok(Completion::Closed),
+                Some(Some(line)) if line.trim_ascii().eq_ignore_ascii_case(b"DONE") => {
+                    return Ok(Completion::Ok("IDLE terminated".into()))
+                }
+                Some(Some(_)) => return Ok(Completion::Bad("Expected DONE".into())),
+            }
+        }
+    }
+
+    /// Tells the client how many messages the selected mailbox holds, if that changed.
+    ///
+    /// Messages removed by other sessions are reported with EXISTS too, as the server does
+    /// not track which ones they were.
+    async fn report_changes(&mut self) -> io::Result<()> {
+        let Some(selected) = self.selected.clone() else {
+            return Ok(());
+        };
+        let exists = self.with_mailbox(&selected.name, |mailbox| mailbox.messages.len());
+        match exists {
+            Some(exists) if exists != selected.exists => {
+                if let Some(selected) = self.selected.as_mut() {
+                    selected.exists = exists;
+                }
+                self.send(format!("* {} EXISTS\r\n", exists)).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn with_account<R>(&self, f: impl FnOnce(&mut StubAccount) -> R) -> R {
+        self.mailstore
+            .with_account(self.login.as_deref().unwrap_or_default(), f)
+    }
+
+    /// Runs `f` on a mailbox of the login, or returns `None` if it does not exist.
+    fn with_mailbox<R>(&self, name: &str, f: impl FnOnce(&mut StubMailbox) -> R) -> Option<R> {
+        self.with_account(|account| account.mailbox_mut(name).map(f))
+    }
+
+    async fn send(&mut self, data: impl AsRef<[u8]>) -> io::Result<()> {
+        self.stream.write_all(data.as_ref()).await?;
+        self.stream.flush().await
+    }
+
+    /// Reads a line, or returns `None` once the client closed the connection. Cancel safe:
+    /// a partial line is kept for the next call.
+    async fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.stream.read_until(b'\n', &mut self.pending).await?;
+        if !self.pending.ends_with(b"\n") {
+            return Ok(None);
+        }
+        Ok(Some(std::mem::take(&mut self.pending)))
+    }
+
+    /// Reads a command, including the literals it carries.
+    async fn read_command(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut command = Vec::new();
+        loop {
+            let Some(line) = self.read_line().await? else {
+                return Ok(None);
+            };
+            command.extend_from_slice(&line);
+            let Some((length, synchronizing)) = literal_length(&line) else {
+                return Ok(Some(command));
+            };
+            if synchronizing {
+                self.send("+ Ready for literal data\r\n").await?;
+            }
+            let mut literal = vec![0; length];
+            self.stream.read_exact(&mut literal).await?;
+            command.extend_from_slice(&literal);
+        }
+    }
+}
+
+/// The messages a command refers to, by sequence number or UID.
+struct MessageSet {
+    set: SequenceSet,
+    uid: bool,
+}
+
+impl MessageSet {
+    fn parse(token: Option<&Token>, uid: bool) -> Option<Self> {
+        let set = SequenceSet::parse(&token?.text()?)?;
+        Some(Self { set, uid })
+    }
+
+    /// Indexes of the messages of the mailbox in the set, in ascending order.
+    fn indexes(&self, mailbox: &StubMailbox) -> Vec<usize> {
+        let last_uid = mailbox.messages.last().map_or(0, |m| m.uid);
+        let last_sequence = mailbox.messages.len() as u32;
+        mailbox
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(index, message)| {
+                if self.uid {
+                    self.set.contains(message.uid, last_uid)
+                } else {
+                    self.set.contains(*index as u32 + 1, last_sequence)
+                }
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum FetchItem {
+    Uid,
+    Flags,
+    InternalDate,
+    Size,
+    Envelope,
+    Body,
+    BodyStructure,
+    Rfc822,
+    Rfc822Header,
+    Rfc822Text,
+    Section {
+        /// Name of the item in responses, e.g. `BODY[1.MIME]`.
+        label: String,
+        section: Section,
+        partial: Option<(usize, usize)>,
+        peek: bool,
+    },
+}
+
+impl FetchItem {
+    fn parse(token: &Token) -> Result<Vec<Self>, String> {
+        let mut items = Vec::new();
+        for name in token.texts() {
+            match name.to_ascii_uppercase().as_str() {
+                "ALL" => {
+                    items.extend([Self::Flags, Self::InternalDate, Self::Size, Self::Envelope])
+                }
+                "FAST" => items.extend([Self::Flags, Self::InternalDate, Self::Size]),
+                "FULL" => items.extend([
+                    Self::Flags,
+                    Self::InternalDate,
+                    Self::Size,
+                    Self::Envelope,
+                    Self::Body,
+                ]),
+                "UID" => items.push(Self::Uid),
+                "FLAGS" => items.push(Self::Flags),
+                "INTERNALDATE" => items.push(Self::InternalDate),
+                "RFC822.SIZE" => items.push(Self::Size),
+                "ENVELOPE" => items.push(Self::Envelope),
+                "BODY" => items.push(Self::Body),
+                "BODYSTRUCTURE" => items.push(Self::BodyStructure),
+                "RFC822" => items.push(Self::Rfc822),
+                "RFC822.HEADER" => items.push(Self::Rfc822Header),
+                "RFC822.TEXT" => items.push(Self::Rfc822Text),
+                _ => items.push(
+                    Self::parse_section(&name)
+                        .ok_or_else(|| format!("Unknown fetch item {}", name))?,
+                ),
+            }
+        }
+        Ok(items)
+    }
+
+    /// Parses `BODY[section]<origin.length>` and `BODY.PEEK[...]`.
+    fn parse_section(name: &str) -> Option<Self> {
+        let upper = name.to_ascii_uppercase();
+        let peek = upper.starts_with("BODY.PEEK[");
+        if !peek && !upper.starts_with("BODY[") {
+            return None;
+        }
+        let spec = &name[name.find('[')? + 1..name.rfind(']')?];
+        let partial = match &name[name.rfind(']')? + 1..] {
+            "" => None,
+            partial => {
+                let (origin, length) = partial
+                    .strip_prefix('<')?
+                    .strip_suffix('>')?
+                    .split_once('.')?;
+                Some((origin.parse().ok()?, length.parse().ok()?))
+            }
+        };
+        Some(Self::Section {
+            label: format!("BODY[{}]", spec),
+            section: Section::parse(spec)?,
+            partial,
+            peek,
+        })
+    }
+
+    /// Whether fetching the item sets `\Seen`.
+    fn marks_seen(&self) -> bool {
+        matches!(
+            self,
+            Self::Rfc822 | Self::Rfc822Text | Self::Section { peek: false, .. }
+        )
+    }
+}
+
+fn write_fetch(out: &mut Vec<u8>, sequence: usize, message: &StubMessage, items: &[FetchItem]) {
+    let entity = Entity::parse(&message.raw);
+    let section = |kind: SectionKind| {
+        entity
+            .section(&Section {
+                path: Vec::new(),
+                kind,
+            })
+            .unwrap_or_default()
+    };
+    out.extend_from_slice(format!("* {} FETCH (", sequence).as_bytes());
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push(b' ');
+        }
+        match item {
+            FetchItem::Uid => out.extend_from_slice(format!("UID {}", message.uid).as_bytes()),
+            FetchItem::Flags => {
+                out.extend_from_slice(format!("FLAGS ({})", message.flags.join(" ")).as_bytes())
+            }
+            FetchItem::InternalDate => {
+                out.extend_from_slice(b"INTERNALDATE ");
+                let date = format_internal_date(message.internal_date).unwrap_or_default();
+                write_string(out, date.as_bytes());
+            }
+            FetchItem::Size => {
+                out.extend_from_slice(format!("RFC822.SIZE {}", message.raw.len()).as_bytes())
+            }
+            FetchItem::Envelope => {
+                out.extend_from_slice(b"ENVELOPE ");
+                entity.write_envelope(out);
+            }
+            FetchItem::Body => {
+                out.extend_from_slice(b"BODY ");
+                entity.write_structure(out, false);
+            }
+            FetchItem::BodyStructure => {
+                out.extend_from_slice(b"BODYSTRUCTURE ");
+                entity.write_structure(out, true);
+            }
+            FetchItem::Rfc822 => write_literal(out, "RFC822", &message.raw),
+            FetchItem::Rfc822Header => {
+                write_literal(out, "RFC822.HEADER", &section(SectionKind::Header))
+            }
+            FetchItem::Rfc822Text => write_literal(out, "RFC822.TEXT", &section(SectionKind::Text)),
+            FetchItem::Section {
+                label,
+                section,
+                partial,
+                ..
+            } => {
+                let content = entity.section(section).unwrap_or_default();
+                match partial {
+                    None => write_literal(out, label, &content),
+                    Some((origin, length)) => {
+                        let start = (*origin).min(content.len());
+                        let end = start.saturating_add(*length).min(content.len());
+                        write_literal(out, &format!("{}<{}>", label, origin), &content[start..end])
+                    }
+                }
+            }
+        }
+    }
+    out.extend_from_slice(b")\r\n");
+}
+
+fn write_literal(out: &mut Vec<u8>, label: &str, value: &[u8]) {
+    out.extend_from_slice(format!("{} {{{}}}\r\n", label, value.len()).as_bytes());
+    out.extend_from_slice(value);
+}
+
+fn join_numbers(numbers: &[u32]) -> String {
+    numbers
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Matches a LIST pattern: `*` matches anything, `%` anything but the hierarchy delimiter.
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| wildcard_match(rest, &name[i..])),
+        Some((b'%', rest)) => (0..=name.len())
+            .take_while(|i| !name[..*i].contains(&(DELIMITER as u8)))
+            .any(|i| wildcard_match(rest, &name[i..])),
+        Some((c, rest)) => name.first() == Some(c) && wildcard_match(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_imap::Client;
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::modules::harness::fixture::{
+        AccountFixture, HarnessFixture, MailboxFixture, MessageFixture, ScriptedBehavior,
+    };
+
+    const LOGIN: &str = "alice@example.com";
+
+    fn mailstore(imap: Vec<ScriptedBehavior>) -> Arc<Mailstore> {
+        let fixture = HarnessFixture {
+            accounts: vec![AccountFixture {
+                login: LOGIN.into(),
+                mailboxes: vec![
+                    MailboxFixture {
+                        name: "INBOX".into(),
+                        messages: vec![MessageFixture {
+                            raw: Some(
+                                "From: Bob <bob@example.com>\r\nSubject: Hello\r\nMessage-ID: <1@example.com>\r\n\r\nHi Alice\r\n"
+                                    .into(),
+                            ),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    },
+                    MailboxFixture {
+                        name: "Sent".into(),
+                        attributes: vec!["\\Sent".into()],
+                        ..Default::default()
+                    },
+                    MailboxFixture {
+                        name: "Trash".into(),
+                        attributes: vec!["\\Trash".into()],
+                        ..Default::default()
+                    },
+                ],
+                imap,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        Arc::new(Mailstore::from_fixture(fixture).unwrap())
+    }
+
+    #[test]
+    fn matches_list_patterns() {
+        assert!(wildcard_match(b"*", b"Work/Reports"));
+        assert!(wildcard_match(b"Work/%", b"Work/Reports"));
+        assert!(!wildcard_match(b"%", b"Work/Reports"));
+        assert!(wildcard_match(b"INBOX", b"INBOX"));
+    }
+
+    #[tokio::test]
+    async fn serves_seeded_mailboxes() {
+        let store = mailstore(Vec::new());
+        let mut client = Client::new(connect(store.clone()));
+        client.read_response().await.unwrap().unwrap();
+        let mut session = client
+            .login(LOGIN, "secret")
+            .await
+            .map_err(|(e, _)| e)
+            .unwrap();
+
+        let names: Vec<_> = session
+            .list(Some(""), Some("*"))
+            .await
+            .unwrap()
+            .map_ok(|name| name.name().to_string())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(names, ["INBOX", "Sent", "Trash"]);
+
+        let mailbox = session.select("INBOX").await.unwrap();
+        assert_eq!(mailbox.exists, 1);
+        assert_eq!(mailbox.uid_next, Some(2));
+
+        let fetches: Vec<_> = session
+            .uid_fetch(
+                "1:*",
+                "(UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (Subject)] BODY[TEXT])",
+            )
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(fetches.len(), 1);
+        let fetch = &fetches[0];
+        assert_eq!(fetch.uid, Some(1));
+        assert_eq!(fetch.header(), Some(&b"Subject: Hello\r\n\r\n"[..]));
+        assert_eq!(fetch.text(), Some(&b"Hi Alice\r\n"[..]));
+        let envelope = fetch.envelope().unwrap();
+        assert_eq!(envelope.subject.as_deref(), Some(&b"Hello"[..]));
+
+        let unseen = session.uid_search("UNSEEN").await.unwrap();
+        assert!(unseen.is_empty());
+
+        session
+            .append(
+                "Sent",
+                Some("\\Seen"),
+                None,
+                b"Subject: Reply\r\n\r\nHi Bob\r\n",
+            )
+            .await
+            .unwrap();
+        let status = session.status("Sent", "(MESSAGES UIDNEXT)").await.unwrap();
+        assert_eq!(status.exists, 1);
+
+        session.uid_mv("1", "Trash").await.unwrap();
+        store.with_account(LOGIN, |account| {
+            assert!(account.mailbox("INBOX").unwrap().messages.is_empty());
+            assert_eq!(account.mailbox("Trash").unwrap().messages[0].uid, 1);
+        });
+        session.logout().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn follows_scripted_behaviors() {
+        let store = mailstore(vec![
+            ScriptedBehavior::RejectLogin { times: 1 },
+            ScriptedBehavior::Disconnect {
+                after_commands: 1,
+                times: 1,
+            },
+        ]);
+        let mut client = Client::new(connect(store.clone()));
+        client.read_response().await.unwrap().unwrap();
+        assert!(client.login(LOGIN, "secret").await.is_err());
+
+        let mut client = Client::new(connect(store.clone()));
+        client.read_response().await.unwrap().unwrap();
+        let mut session = client
+            .login(LOGIN, "secret")
+            .await
+            .map_err(|(e, _)| e)
+            .unwrap();
+        session.noop().await.unwrap();
+        assert!(session.noop().await.is_err());
+    }
+}
@@ -0,0 +1,638 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::borrow::Cow;
+
+/// A MIME entity of a stored message, keeping the byte ranges IMAP sections refer to.
+pub struct Entity<'a> {
+    /// The header block followed by the body.
+    raw: &'a [u8],
+    /// The header block, including the blank line ending it.
+    header: &'a [u8],
+    body: &'a [u8],
+    content_type: ContentType,
+    /// Children of a multipart entity.
+    parts: Vec<Entity<'a>>,
+    /// The message encapsulated in a `message/rfc822` entity.
+    message: Option<Box<Entity<'a>>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ContentType {
+    ty: String,
+    subtype: String,
+    params: Vec<(String, String)>,
+}
+
+impl ContentType {
+    fn new(ty: &str, subtype: &str) -> Self {
+        Self {
+            ty: ty.into(),
+            subtype: subtype.into(),
+            params: Vec::new(),
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let (value, params) = parse_params(value);
+        let (ty, subtype) = value.split_once('/')?;
+        Some(Self {
+            ty: ty.trim().to_lowercase(),
+            subtype: subtype.trim().to_lowercase(),
+            params,
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn is(&self, ty: &str, subtype: &str) -> bool {
+        self.ty == ty && self.subtype == subtype
+    }
+}
+
+/// What a `BODY[...]` fetch item refers to, e.g. `1.2.HEADER.FIELDS (Subject)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    pub path: Vec<usize>,
+    pub kind: SectionKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SectionKind {
+    Full,
+    Header,
+    HeaderFields(Vec<String>),
+    HeaderFieldsNot(Vec<String>),
+    Text,
+    Mime,
+}
+
+impl Section {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut path = Vec::new();
+        let mut rest = spec.trim();
+        loop {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if end == 0 {
+                break;
+            }
+            path.push(rest[..end].parse().ok().filter(|n| *n > 0)?);
+            rest = &rest[end..];
+            match rest.strip_prefix('.') {
+                Some(stripped) => rest = stripped,
+                None => break,
+            }
+        }
+        let upper = rest.to_ascii_uppercase();
+        let names = || -> Option<Vec<String>> {
+            let list = rest[rest.find('(')? + 1..rest.rfind(')')?].to_string();
+            Some(list.split_whitespace().map(unquote).collect())
+        };
+        let kind = match upper.as_str() {
+            "" => SectionKind::Full,
+            "HEADER" => SectionKind::Header,
+            "TEXT" => SectionKind::Text,
+            "MIME" if !path.is_empty() => SectionKind::Mime,
+            _ if upper.starts_with("HEADER.FIELDS.NOT") => SectionKind::HeaderFieldsNot(names()?),
+            _ if upper.starts_with("HEADER.FIELDS") => SectionKind::HeaderFields(names()?),
+            _ => return None,
+        };
+        Some(Self { path, kind })
+    }
+}
+
+impl<'a> Entity<'a> {
+    pub fn parse(raw: &'a [u8]) -> Self {
+        Self::parse_entity(raw, ContentType::new("text", "plain"))
+    }
+
+    fn parse_entity(raw: &'a [u8], default_type: ContentType) -> Self {
+        let (header, body) = split_header(raw);
+        let content_type = field(header, "Content-Type")
+            .and_then(|value| ContentType::parse(&value))
+            .unwrap_or(default_type);
+        let mut entity = Self {
+            raw,
+            header,
+            body,
+            content_type,
+            parts: Vec::new(),
+            message: None,
+        };
+        if entity.content_type.ty == "multipart" {
+            let child_type = if entity.content_type.subtype == "digest" {
+                ContentType::new("message", "rfc822")
+            } else {
+                ContentType::new("text", "plain")
+            };
+            if let Some(boundary) = entity.content_type.param("boundary") {
+                entity.parts = split_multipart(body, boundary)
+                    .into_iter()
+                    .map(|part| Self::parse_entity(part, child_type.clone()))
+                    .collect();
+            }
+        } else if entity.content_type.is("message", "rfc822") {
+            entity.message = Some(Box::new(Self::parse(body)));
+        }
+        entity
+    }
+
+    pub fn field(&self, name: &str) -> Option<String> {
+        field(self.header, name)
+    }
+
+    /// The entity a part number refers to, resolved from this entity as a message.
+    fn part(&self, path: &[usize]) -> Option<&Entity<'a>> {
+        let Some((first, rest)) = path.split_first() else {
+            return Some(self);
+        };
+        let entity = if self.content_type.ty == "multipart" {
+            self.parts.get(first - 1)?
+        } else if *first == 1 {
+            self
+        } else {
+            return None;
+        };
+        entity.descend(rest)
+    }
+
+    fn descend(&self, path: &[usize]) -> Option<&Entity<'a>> {
+        let Some((first, rest)) = path.split_first() else {
+            return Some(self);
+        };
+        if self.content_type.ty == "multipart" {
+            self.parts.get(first - 1)?.descend(rest)
+        } else {
+            self.message.as_ref()?.part(path)
+        }
+    }
+
+    /// The content of a section, or `None` if the message has no such section.
+    pub fn section(&self, section: &Section) -> Option<Cow<'a, [u8]>> {
+        let entity = self.part(&section.path)?;
+        let message = if section.path.is_empty() {
+            Some(self)
+        } else {
+            entity.message.as_deref()
+        };
+        Some(match &section.kind {
+            SectionKind::Full if section.path.is_empty() => Cow::Borrowed(self.raw),
+            SectionKind::Full => Cow::Borrowed(entity.body),
+            SectionKind::Header => Cow::Borrowed(message?.header),
+            SectionKind::HeaderFields(names) => {
+                Cow::Owned(filter_fields(message?.header, names, true))
+            }
+            SectionKind::HeaderFieldsNot(names) => {
+                Cow::Owned(filter_fields(message?.header, names, false))
+            }
+            SectionKind::Text => Cow::Borrowed(message?.body),
+            SectionKind::Mime => Cow::Borrowed(entity.header),
+        })
+    }
+
+    pub fn body(&self) -> &'a [u8] {
+        self.body
+    }
+
+    /// Writes the `BODYSTRUCTURE` of the entity, or its `BODY` without extension data.
+    pub fn write_structure(&self, out: &mut Vec<u8>, extensible: bool) {
+        out.push(b'(');
+        if self.content_type.ty == "multipart" && !self.parts.is_empty() {
+            for part in &self.parts {
+                part.write_structure(out, extensible);
+            }
+            out.push(b' ');
+            write_string(
+                out,
+                self.content_type.subtype.to_ascii_uppercase().as_bytes(),
+            );
+            if extensible {
+                out.push(b' ');
+                self.write_params(out);
+                out.push(b' ');
+                self.write_extension(out);
+            }
+            out.push(b')');
+            return;
+        }
+        let content_type = if self.content_type.ty == "multipart" {
+            // Multiparts without parts cannot be described, so they are shown as text.
+            ContentType::new("text", "plain")
+        } else {
+            self.content_type.clone()
+        };
+        write_string(out, content_type.ty.to_ascii_uppercase().as_bytes());
+        out.push(b' ');
+        write_string(out, content_type.subtype.to_ascii_uppercase().as_bytes());
+        out.push(b' ');
+        self.write_params(out);
+        out.push(b' ');
+        write_nstring(out, self.field("Content-ID").as_deref());
+        out.push(b' ');
+        write_nstring(out, self.field("Content-Description").as_deref());
+        out.push(b' ');
+        let encoding = self
+            .field("Content-Transfer-Encoding")
+            .map(|encoding| encoding.to_ascii_uppercase())
+            .unwrap_or_else(|| "7BIT".into());
+        write_string(out, encoding.as_bytes());
+        out.extend_from_slice(format!(" {}", self.body.len()).as_bytes());
+        let lines = self.body.iter().filter(|b| **b == b'\n').count();
+        if let Some(message) = &self.message {
+            out.push(b' ');
+            message.write_envelope(out);
+            out.push(b' ');
+            message.write_structure(out, extensible);
+            out.extend_from_slice(format!(" {}", lines).as_bytes());
+        } else if content_type.ty == "text" {
+            out.extend_from_slice(format!(" {}", lines).as_bytes());
+        }
+        if extensible {
+            out.push(b' ');
+            write_nstring(out, self.field("Content-MD5").as_deref());
+            out.push(b' ');
+            self.write_extension(out);
+        }
+        out.push(b')');
+    }
+
+    fn write_params(&self, out: &mut Vec<u8>) {
+        if self.content_type.params.is_empty() {
+            out.extend_from_slice(b"NIL");
+            return;
+        }
+        write_param_list(out, &self.content_type.params);
+    }
+
+    /// Writes the disposition, language and location of the entity.
+    fn write_extension(&self, out: &mut Vec<u8>) {
+        match self.field("Content-Disposition") {
+            Some(value) => {
+                let (disposition, params) = parse_params(&value);
+                out.push(b'(');
+                write_string(out, disposition.to_lowercase().as_bytes());
+                out.push(b' ');
+                if params.is_empty() {
+                    out.extend_from_slice(b"NIL");
+                } else {
+                    write_param_list(out, &params);
+                }
+                out.push(b')');
+            }
+            None => out.extend_from_slice(b"NIL"),
+        }
+        out.push(b' ');
+        write_nstring(out, self.field("Content-Language").as_deref());
+        out.push(b' ');
+        write_nstring(out, self.field("Content-Location").as_deref());
+    }
+
+    /// Writes the `ENVELOPE` of the entity as a message.
+    pub fn write_envelope(&self, out: &mut Vec<u8>) {
+        let from = self.field("From");
+        out.push(b'(');
+        write_nstring(out, self.field("Date").as_deref());
+        out.push(b' ');
+        write_nstring(out, self.field("Subject").as_deref());
+        for (name, fallback) in [
+            ("From", None),
+            ("Sender", from.as_deref()),
+            ("Reply-To", from.as_deref()),
+            ("To", None),
+            ("Cc", None),
+            ("Bcc", None),
+        ] {
+            out.push(b' ');
+            write_addresses(out, self.field(name).as_deref().or(fallback));
+        }
+        out.push(b' ');
+        write_nstring(out, self.field("In-Reply-To").as_deref());
+        out.push(b' ');
+        write_nstring(out, self.field("Message-ID").as_deref());
+        out.push(b')');
+    }
+}
+
+/// Splits a message into its header block, including the blank line ending it, and body.
+fn split_header(raw: &[u8]) -> (&[u8], &[u8]) {
+    if raw.starts_with(b"\r\n") {
+        return raw.split_at(2);
+    }
+    if raw.starts_with(b"\n") {
+        return raw.split_at(1);
+    }
+    let crlf = raw.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4);
+    let lf = raw.windows(2).position(|w| w == b"\n\n").map(|i| i + 2);
+    let end = match (crlf, lf) {
+        (Some(crlf), Some(lf)) => crlf.min(lf),
+        (end, None) | (None, end) => end.unwrap_or(raw.len()),
+    };
+    raw.split_at(end)
+}
+
+/// The raw header fields: their name and lines, including continuation lines.
+fn raw_fields(header: &[u8]) -> Vec<(String, &[u8])> {
+    // Name, start and end of each field.
+    let mut fields: Vec<(String, usize, usize)> = Vec::new();
+    let mut start = 0;
+    while start < header.len() {
+        let end = header[start..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or(header.len(), |i| start + i + 1);
+        let line = &header[start..end];
+        if line == b"\r\n" || line == b"\n" {
+            break;
+        }
+        match (line.first(), fields.last_mut()) {
+            (Some(b' ' | b'\t'), Some((_, _, field_end))) => *field_end = end,
+            _ => {
+                if let Some(colon) = line.iter().position(|b| *b == b':') {
+                    let name = String::from_utf8_lossy(&line[..colon]).trim().to_string();
+                    fields.push((name, start, end));
+                }
+            }
+        }
+        start = end;
+    }
+    fields
+        .into_iter()
+        .map(|(name, start, end)| (name, &header[start..end]))
+        .collect()
+}
+
+/// The unfolded value of the first header field with the name.
+fn field(header: &[u8], name: &str) -> Option<String> {
+    raw_fields(header)
+        .into_iter()
+        .find(|(field_name, _)| field_name.eq_ignore_ascii_case(name))
+        .map(|(_, line)| {
+            let value = &line[line.iter().position(|b| *b == b':').unwrap_or(0) + 1..];
+            String::from_utf8_lossy(value)
+                .replace(['\r', '\n'], "")
+                .trim()
+                .to_string()
+        })
+}
+
+/// The header fields with (or without) the given names, followed by a blank line.
+fn filter_fields(header: &[u8], names: &[String], include: bool) -> Vec<u8> {
+    let mut out: Vec<u8> = raw_fields(header)
+        .into_iter()
+        .filter(|(name, _)| names.iter().any(|n| n.eq_ignore_ascii_case(name)) == include)
+        .flat_map(|(_, line)| line.iter().copied())
+        .collect();
+    out.extend_from_slice(b"\r\n");
+    out
+}
+
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut pos = 0;
+    while pos < body.len() {
+        let line_end = body[pos..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or(body.len(), |i| pos + i + 1);
+        let line = &body[pos..line_end];
+        if let Some(rest) = line.strip_prefix(delimiter.as_bytes()) {
+            let closing = rest.starts_with(b"--");
+            if closing || rest.iter().all(u8::is_ascii_whitespace) {
+                if let Some(start) = start {
+                    // The line break before a delimiter belongs to the delimiter.
+                    let end = if body[..pos].ends_with(b"\r\n") {
+                        pos - 2
+                    } else if body[..pos].ends_with(b"\n") {
+                        pos - 1
+                    } else {
+                        pos
+                    };
+                    parts.push(&body[start..end.max(start)]);
+                }
+                if closing {
+                    return parts;
+                }
+                start = Some(line_end);
+            }
+        }
+        pos = line_end;
+    }
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+/// Splits a `value; key=value` header value into the value and its parameters.
+fn parse_params(value: &str) -> (&str, Vec<(String, String)>) {
+    let mut items = split_outside_quotes(value, b';').into_iter();
+    let value = items.next().unwrap_or_default().trim();
+    let params = items
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((key.trim().to_lowercase(), unquote(value.trim())))
+        })
+        .collect();
+    (value, params)
+}
+
+/// Splits on `separator` outside of quoted strings, angle brackets and comments.
+fn split_outside_quotes(value: &str, separator: u8) -> Vec<&str> {
+    let mut items = Vec::new();
+    let (mut start, mut quoted, mut escaped, mut depth) = (0, false, false, 0i32);
+    for (i, b) in value.bytes().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if quoted => escaped = true,
+            b'"' => quoted = !quoted,
+            b'<' | b'(' if !quoted => depth += 1,
+            b'>' | b')' if !quoted => depth -= 1,
+            _ if b == separator && !quoted && depth <= 0 => {
+                items.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
+
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => {
+            let mut out = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => out.extend(chars.next()),
+                    c => out.push(c),
+                }
+            }
+            out
+        }
+        None => value.to_string(),
+    }
+}
+
+fn write_param_list(out: &mut Vec<u8>, params: &[(String, String)]) {
+    out.push(b'(');
+    for (i, (key, value)) in params.iter().enumerate() {
+        if i > 0 {
+            out.push(b' ');
+        }
+        write_string(out, key.to_ascii_uppercase().as_bytes());
+        out.push(b' ');
+        write_string(out, value.as_bytes());
+    }
+    out.push(b')');
+}
+
+fn write_addresses(out: &mut Vec<u8>, value: Option<&str>) {
+    let addresses: Vec<(Option<String>, String)> = value
+        .map(|value| {
+            split_outside_quotes(value, b',')
+                .into_iter()
+                .filter_map(parse_address)
+                .collect()
+        })
+        .unwrap_or_default();
+    if addresses.is_empty() {
+        out.extend_from_slice(b"NIL");
+        return;
+    }
+    out.push(b'(');
+    for (name, address) in addresses {
+        let (mailbox, host) = match address.rsplit_once('@') {
+            Some((mailbox, host)) => (mailbox, Some(host)),
+            None => (address.as_str(), None),
+        };
+        out.push(b'(');
+        write_nstring(out, name.as_deref());
+        out.extend_from_slice(b" NIL ");
+        write_string(out, mailbox.as_bytes());
+        out.push(b' ');
+        write_nstring(out, host);
+        out.push(b')');
+    }
+    out.push(b')');
+}
+
+/// Parses `"Name" <mailbox@host>` or `mailbox@host` into its display name and address.
+fn parse_address(value: &str) -> Option<(Option<String>, String)> {
+    let value = value.trim();
+    match (value.rfind('<'), value.rfind('>')) {
+        (Some(open), Some(close)) if open < close => {
+            let name = unquote(value[..open].trim());
+            let address = value[open + 1..close].trim().to_string();
+            Some(((!name.is_empty()).then_some(name), address))
+        }
+        _ if !value.is_empty() => Some((None, value.to_string())),
+        _ => None,
+    }
+}
+
+fn write_nstring(out: &mut Vec<u8>, value: Option<&str>) {
+    match value {
+        Some(value) => write_string(out, value.as_bytes()),
+        None => out.extend_from_slice(b"NIL"),
+    }
+}
+
+/// Writes an IMAP string: quoted when possible, as a literal otherwise.
+pub fn write_string(out: &mut Vec<u8>, value: &[u8]) {
+    if value
+        .iter()
+        .all(|b| b.is_ascii() && *b != b'\r' && *b != b'\n' && *b != 0)
+    {
+        out.push(b'"');
+        for b in value {
+            if matches!(b, b'"' | b'\\') {
+                out.push(b'\\');
+            }
+            out.push(*b);
+        }
+        out.push(b'"');
+    } else {
+        out.extend_from_slice(format!("{{{}}}\r\n", value.len()).as_bytes());
+        out.extend_from_slice(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &[u8] = b"From: \"Doe, Jane\" <jane@example.com>\r\n\
+To: bob@example.com, Carol <carol@example.org>\r\n\
+Subject: Quarterly\r\n report\r\n\
+Message-ID: <q3@example.com>\r\n\
+Content-Type: multipart/mixed; boundary=\"outer\"\r\n\
+\r\n\
+preamble\r\n\
+--outer\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+\r\n\
+See attached.\r\n\
+--outer\r\n\
+Content-Type: application/pdf; name=\"q3.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+Content-Disposition: attachment; filename=\"q3.pdf\"\r\n\
+\r\n\
+JVBERi0=\r\n\
+--outer--\r\n";
+
+    #[test]
+    fn resolves_sections() {
+        let entity = Entity::parse(MESSAGE);
+        let section = |spec: &str| {
+            entity
+                .section(&Section::parse(spec).unwrap())
+                .map(|content| String::from_utf8(content.into_owned()).unwrap())
+        };
+        assert_eq!(section("1").as_deref(), Some("See attached."));
+        assert_eq!(section("2").as_deref(), Some("JVBERi0="));
+        assert!(section("2.MIME")
+            .unwrap()
+            .starts_with("Content-Type: application/pdf"));
+        assert_eq!(
+            section("HEADER.FIELDS (Subject Message-ID)").as_deref(),
+            Some("Subject: Quarterly\r\n report\r\nMessage-ID: <q3@example.com>\r\n\r\n")
+        );
+        assert_eq!(section("").unwrap().len(), MESSAGE.len());
+        assert_eq!(section("3"), None);
+        assert_eq!(entity.field("Subject").as_deref(), Some("Quarterly report"));
+    }
+
+    #[test]
+    fn writes_structures_and_envelopes() {
+        let entity = Entity::parse(MESSAGE);
+        let mut structure = Vec::new();
+        entity.write_structure(&mut structure, true);
+        assert_eq!(
+            String::from_utf8(structure).unwrap(),
+            "((\"TEXT\" \"PLAIN\" (\"CHARSET\" \"utf-8\") NIL NIL \"7BIT\" 13 0 NIL NIL NIL NIL)\
+(\"APPLICATION\" \"PDF\" (\"NAME\" \"q3.pdf\") NIL NIL \"BASE64\" 8 NIL (\"attachment\" (\"FILENAME\" \"q3.pdf\")) NIL NIL) \
+\"MIXED\" (\"BOUNDARY\" \"outer\") NIL NIL NIL)"
+        );
+
+        let mut envelope = Vec::new();
+        entity.write_envelope(&mut envelope);
+        assert_eq!(
+            String::from_utf8(envelope).unwrap(),
+            "(NIL \"Quarterly report\" ((\"Doe, Jane\" NIL \"jane\" \"example.com\")) \
+((\"Doe, Jane\" NIL \"jane\" \"example.com\")) ((\"Doe, Jane\" NIL \"jane\" \"example.com\")) \
+((NIL NIL \"bob\" \"example.com\")(\"Carol\" NIL \"carol\" \"example.org\")) NIL NIL NIL \"<q3@example.com>\")"
+        );
+    }
+}
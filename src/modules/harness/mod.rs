@@ -0,0 +1,94 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! End-to-end test mode, built with the `test-harness` feature.
+//!
+//! When `rustmailer_test_harness_fixture` is set, IMAP and SMTP connections are served by
+//! deterministic in-memory servers instead of the network. Their mailboxes are seeded from
+//! the fixture, which can also script server behaviors (rejected logins, dropped
+//! connections, latency, greylisting), so sync, send and hook workflows can be tested
+//! without real mail servers.
+
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+
+use tracing::warn;
+
+use crate::modules::context::Initialize;
+use crate::modules::error::{code::ErrorCode, RustMailerResult};
+use crate::modules::harness::fixture::HarnessFixture;
+use crate::modules::harness::store::Mailstore;
+use crate::modules::settings::cli::SETTINGS;
+use crate::raise_error;
+
+mod command;
+pub mod fixture;
+pub mod imap;
+mod mime;
+pub mod payload;
+mod search;
+pub mod smtp;
+pub mod store;
+
+/// Capacity of the in-memory pipe between a client and a server, in each direction.
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+static TEST_HARNESS: LazyLock<Result<Option<Arc<Mailstore>>, String>> =
+    LazyLock::new(TestHarness::from_settings);
+
+pub struct TestHarness;
+
+impl TestHarness {
+    fn from_settings() -> Result<Option<Arc<Mailstore>>, String> {
+        let Some(path) = &SETTINGS.rustmailer_test_harness_fixture else {
+            return Ok(None);
+        };
+        let fixture = HarnessFixture::load(Path::new(path))?;
+        Mailstore::from_fixture(fixture).map(|store| Some(Arc::new(store)))
+    }
+
+    /// The state of the in-memory servers, or `None` if the test harness is disabled.
+    pub fn get() -> RustMailerResult<Option<Arc<Mailstore>>> {
+        TEST_HARNESS
+            .as_ref()
+            .map(Option::clone)
+            .map_err(|e| raise_error!(e.clone(), ErrorCode::MissingConfiguration))
+    }
+
+    /// The state of the in-memory servers, failing if the test harness is disabled.
+    pub fn mailstore() -> RustMailerResult<Arc<Mailstore>> {
+        Self::get()?.ok_or_else(|| {
+            raise_error!(
+                "The test harness is disabled: set rustmailer_test_harness_fixture to enable it"
+                    .into(),
+                ErrorCode::MissingConfiguration
+            )
+        })
+    }
+}
+
+impl Initialize for TestHarness {
+    /// Fails startup on an invalid fixture, so tests do not run against real servers.
+    async fn initialize() -> RustMailerResult<()> {
+        if Self::get()?.is_some() {
+            warn!("Test harness is enabled: IMAP and SMTP connections are served in memory");
+        }
+        Ok(())
+    }
+}
+
+/// The user name of a SASL `PLAIN` or `XOAUTH2` response.
+fn sasl_user(mechanism: &str, response: &[u8]) -> Option<String> {
+    let response = String::from_utf8_lossy(response);
+    let user = match mechanism {
+        // authzid NUL authcid NUL passwd
+        "PLAIN" => response.split('\0').nth(1),
+        // user=... ^A auth=Bearer ... ^A ^A
+        "XOAUTH2" => response
+            .split('\x01')
+            .find_map(|field| field.strip_prefix("user=")),
+        _ => None,
+    };
+    user.map(String::from)
+}
@@ -0,0 +1,43 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+/// A message accepted by the in-memory SMTP server.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Object)]
+pub struct CapturedMessage {
+    /// Login the message was sent with: the account email, or the username of the MTA.
+    pub login: String,
+    /// Envelope sender (`MAIL FROM`).
+    pub mail_from: String,
+    /// Envelope recipients (`RCPT TO`) the message was accepted for.
+    pub recipients: Vec<String>,
+    /// Subject of the message, if any.
+    pub subject: Option<String>,
+    /// Message-ID of the message, without angle brackets.
+    pub message_id: Option<String>,
+    /// The message as received, base64 encoded.
+    pub raw: String,
+    /// Timestamp (in milliseconds) the message was received at.
+    pub received_at: i64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Object)]
+pub struct InboundMessageRequest {
+    /// Login of the mailbox owner: the email address of the account.
+    pub login: String,
+    /// Mailbox to add the message to, INBOX by default.
+    pub mailbox: Option<String>,
+    /// The RFC 5322 message, base64 encoded.
+    pub raw: String,
+    /// Flags of the message, e.g. `\Seen`.
+    pub flags: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Object)]
+pub struct InboundMessage {
+    /// UID of the message in the mailbox.
+    pub uid: u32,
+}
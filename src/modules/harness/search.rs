@@ -0,0 +1,236 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::iter::Peekable;
+use std::slice::Iter;
+
+use chrono::{DateTime, NaiveDate};
+use mail_parser::MessageParser;
+
+use crate::modules::harness::command::{SequenceSet, Token};
+use crate::modules::harness::mime::Entity;
+use crate::modules::harness::store::StubMessage;
+
+/// A search key of a `SEARCH` command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchKey {
+    All,
+    /// Messages with (`true`) or without (`false`) the flag.
+    Flag(String, bool),
+    /// Compares the internal date, or the `Date` header for the `SENT*` keys.
+    Date {
+        sent: bool,
+        comparison: DateComparison,
+        date: NaiveDate,
+    },
+    /// Case-insensitive substring match of a header field. An empty value matches any
+    /// message with the field.
+    Header(String, String),
+    Body(String),
+    Text(String),
+    Larger(usize),
+    Smaller(usize),
+    Uid(SequenceSet),
+    Sequence(SequenceSet),
+    Not(Box<SearchKey>),
+    Or(Box<SearchKey>, Box<SearchKey>),
+    And(Vec<SearchKey>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateComparison {
+    Before,
+    On,
+    Since,
+}
+
+/// Where a message stands in the selected mailbox.
+pub struct Position {
+    pub sequence: u32,
+    pub last_sequence: u32,
+    pub last_uid: u32,
+}
+
+impl SearchKey {
+    /// Parses the arguments of a `SEARCH` command, ignoring a leading `CHARSET`.
+    pub fn parse(tokens: &[Token]) -> Result<Self, String> {
+        let mut tokens = tokens.iter().peekable();
+        if matches!(tokens.peek(), Some(Token::Atom(atom)) if atom.eq_ignore_ascii_case("CHARSET"))
+        {
+            tokens.next();
+            tokens.next();
+        }
+        let mut keys = Vec::new();
+        while tokens.peek().is_some() {
+            keys.push(Self::parse_key(&mut tokens)?);
+        }
+        Ok(SearchKey::And(keys))
+    }
+
+    fn parse_key(tokens: &mut Peekable<Iter<Token>>) -> Result<Self, String> {
+        let atom = match tokens.next() {
+            Some(Token::List(keys)) => return Self::parse(keys),
+            Some(Token::Atom(atom)) => atom.to_ascii_uppercase(),
+            Some(token) => return Err(format!("Unexpected search argument {:?}", token)),
+            None => return Err("Missing search key".into()),
+        };
+        let mut argument = || {
+            tokens
+                .next()
+                .and_then(Token::text)
+                .ok_or_else(|| format!("Missing argument of {}", atom))
+        };
+        let flag = |name: &str, set: bool| SearchKey::Flag(name.into(), set);
+        let key = match atom.as_str() {
+            "ALL" => SearchKey::All,
+            "ANSWERED" => flag("\\Answered", true),
+            "UNANSWERED" => flag("\\Answered", false),
+            "DELETED" => flag("\\Deleted", true),
+            "UNDELETED" => flag("\\Deleted", false),
+            "DRAFT" => flag("\\Draft", true),
+            "UNDRAFT" => flag("\\Draft", false),
+            "FLAGGED" => flag("\\Flagged", true),
+            "UNFLAGGED" => flag("\\Flagged", false),
+            "SEEN" => flag("\\Seen", true),
+            "UNSEEN" => flag("\\Seen", false),
+            // The server does not track \Recent, so no message is recent.
+            "RECENT" => flag("\\Recent", true),
+            "OLD" => flag("\\Recent", false),
+            "NEW" => SearchKey::And(vec![flag("\\Recent", true), flag("\\Seen", false)]),
+            "KEYWORD" => SearchKey::Flag(argument()?, true),
+            "UNKEYWORD" => SearchKey::Flag(argument()?, false),
+            "BEFORE" | "ON" | "SINCE" | "SENTBEFORE" | "SENTON" | "SENTSINCE" => {
+                let value = argument()?;
+                let date = NaiveDate::parse_from_str(&value, "%d-%b-%Y")
+                    .map_err(|_| format!("Invalid search date '{}'", value))?;
+                let comparison = match atom.trim_start_matches("SENT") {
+                    "BEFORE" => DateComparison::Before,
+                    "ON" => DateComparison::On,
+                    _ => DateComparison::Since,
+                };
+                SearchKey::Date {
+                    sent: atom.starts_with("SENT"),
+                    comparison,
+                    date,
+                }
+            }
+            "FROM" | "TO" | "CC" | "BCC" | "SUBJECT" => {
+                SearchKey::Header(atom.clone(), argument()?)
+            }
+            "HEADER" => SearchKey::Header(argument()?, argument()?),
+            "BODY" => SearchKey::Body(argument()?),
+            "TEXT" => SearchKey::Text(argument()?),
+            "LARGER" | "SMALLER" => {
+                let size = argument()?
+                    .parse()
+                    .map_err(|_| format!("Invalid size for {}", atom))?;
+                if atom == "LARGER" {
+                    SearchKey::Larger(size)
+                } else {
+                    SearchKey::Smaller(size)
+                }
+            }
+            "UID" => {
+                let set = argument()?;
+                SearchKey::Uid(
+                    SequenceSet::parse(&set).ok_or_else(|| format!("Invalid UID set '{}'", set))?,
+                )
+            }
+            "NOT" => SearchKey::Not(Box::new(Self::parse_key(tokens)?)),
+            "OR" => SearchKey::Or(
+                Box::new(Self::parse_key(tokens)?),
+                Box::new(Self::parse_key(tokens)?),
+            ),
+            _ => SearchKey::Sequence(
+                SequenceSet::parse(&atom)
+                    .ok_or_else(|| format!("Unknown search key '{}'", atom))?,
+            ),
+        };
+        Ok(key)
+    }
+
+    pub fn matches(&self, message: &StubMessage, position: &Position) -> bool {
+        match self {
+            SearchKey::All => true,
+            SearchKey::Flag(flag, set) => message.has_flag(flag) == *set,
+            SearchKey::Date {
+                sent,
+                comparison,
+                date,
+            } => {
+                let timestamp = if *sent {
+                    MessageParser::default()
+                        .parse_headers(&message.raw)
+                        .and_then(|headers| headers.date().map(|d| d.to_timestamp() * 1000))
+                } else {
+                    Some(message.internal_date)
+                };
+                let Some(day) = timestamp
+                    .and_then(DateTime::from_timestamp_millis)
+                    .map(|time| time.date_naive())
+                else {
+                    return false;
+                };
+                match comparison {
+                    DateComparison::Before => day < *date,
+                    DateComparison::On => day == *date,
+                    DateComparison::Since => day >= *date,
+                }
+            }
+            SearchKey::Header(name, value) => Entity::parse(&message.raw)
+                .field(name)
+                .is_some_and(|field| contains(field.as_bytes(), value)),
+            SearchKey::Body(value) => contains(Entity::parse(&message.raw).body(), value),
+            SearchKey::Text(value) => contains(&message.raw, value),
+            SearchKey::Larger(size) => message.raw.len() > *size,
+            SearchKey::Smaller(size) => message.raw.len() < *size,
+            SearchKey::Uid(set) => set.contains(message.uid, position.last_uid),
+            SearchKey::Sequence(set) => set.contains(position.sequence, position.last_sequence),
+            SearchKey::Not(key) => !key.matches(message, position),
+            SearchKey::Or(a, b) => a.matches(message, position) || b.matches(message, position),
+            SearchKey::And(keys) => keys.iter().all(|key| key.matches(message, position)),
+        }
+    }
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    String::from_utf8_lossy(haystack)
+        .to_lowercase()
+        .contains(&needle.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::modules::harness::command::tokenize;
+
+    use super::*;
+
+    #[test]
+    fn evaluates_search_keys() {
+        let message = StubMessage {
+            uid: 7,
+            flags: vec!["\\Seen".into()],
+            internal_date: 1_756_720_800_000,
+            raw: b"From: Alice <alice@example.com>\r\nSubject: Quarterly report\r\nDate: Sun, 31 Aug 2025 22:00:00 +0000\r\n\r\nNumbers inside\r\n".to_vec(),
+        };
+        let position = Position {
+            sequence: 2,
+            last_sequence: 3,
+            last_uid: 9,
+        };
+        let matches = |query: &str| {
+            let key = SearchKey::parse(&tokenize(query.as_bytes()).unwrap()).unwrap();
+            key.matches(&message, &position)
+        };
+        assert!(matches(
+            "CHARSET UTF-8 SEEN FROM alice SUBJECT \"quarterly\""
+        ));
+        assert!(matches("UNDELETED SINCE 1-Sep-2025 SENTBEFORE 1-Sep-2025"));
+        assert!(matches("OR UNSEEN (BODY numbers UID 5:*)"));
+        assert!(matches("NOT 1,3 LARGER 10 HEADER Subject \"\""));
+        assert!(!matches("TEXT invoice"));
+        assert!(!matches("HEADER To alice"));
+        assert!(SearchKey::parse(&tokenize(b"SINCE yesterday").unwrap()).is_err());
+    }
+}
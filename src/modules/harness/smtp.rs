@@ -0,0 +1,330 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::io;
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tracing::debug;
+
+use crate::modules::harness::store::{Mailstore, Protocol, RecipientCheck, SessionScript};
+use crate::modules::harness::{sasl_user, PIPE_CAPACITY};
+
+const HOSTNAME: &str = "harness.rustmailer.test";
+
+/// Opens a connection to a new session of the in-memory SMTP server.
+pub fn connect(mailstore: Arc<Mailstore>) -> DuplexStream {
+    let (client, server) = tokio::io::duplex(PIPE_CAPACITY);
+    tokio::spawn(async move {
+        if let Err(e) = Session::new(mailstore, server).run().await {
+            debug!("In-memory SMTP session failed: {:#?}", e);
+        }
+    });
+    client
+}
+
+struct Session {
+    mailstore: Arc<Mailstore>,
+    stream: BufReader<DuplexStream>,
+    login: Option<String>,
+    script: SessionScript,
+    /// Commands handled since login.
+    commands: u32,
+    mail_from: Option<String>,
+    recipients: Vec<String>,
+}
+
+impl Session {
+    fn new(mailstore: Arc<Mailstore>, stream: DuplexStream) -> Self {
+        Self {
+            mailstore,
+            stream: BufReader::new(stream),
+            login: None,
+            script: SessionScript::default(),
+            commands: 0,
+            mail_from: None,
+            recipients: Vec::new(),
+        }
+    }
+
+    async fn run(mut self) -> io::Result<()> {
+        self.send(&format!("220 {} ESMTP RustMailer test harness", HOSTNAME))
+            .await?;
+        while let Some(line) = self.read_line().await? {
+            let line = line.trim_end();
+            let (verb, argument) = line.split_once(' ').unwrap_or((line, ""));
+            let verb = verb.to_ascii_uppercase();
+
+            if self.login.is_some() {
+                self.commands += 1;
+                if self
+                    .script
+                    .disconnect_after
+                    .is_some_and(|limit| self.commands > limit)
+                {
+                    debug!("In-memory SMTP server drops the connection as scripted");
+                    return Ok(());
+                }
+                if let Some(latency) = self.script.latency {
+                    tokio::time::sleep(latency).await;
+                }
+            }
+
+            match verb.as_str() {
+                "EHLO" => {
+                    self.send(&format!(
+                        "250-{}\r\n250-8BITMIME\r\n250-SMTPUTF8\r\n250-SIZE 52428800\r\n250-AUTH PLAIN LOGIN XOAUTH2\r\n250 ENHANCEDSTATUSCODES",
+                        HOSTNAME
+                    ))
+                    .await?
+                }
+                "HELO" => self.send(&format!("250 {}", HOSTNAME)).await?,
+                "AUTH" => {
+                    if !self.authenticate(argument).await? {
+                        return Ok(());
+                    }
+                }
+                "MAIL" => self.mail_from(argument).await?,
+                "RCPT" => self.rcpt_to(argument).await?,
+                "DATA" => {
+                    if !self.data().await? {
+                        return Ok(());
+                    }
+                }
+                "RSET" => {
+                    self.reset();
+                    self.send("250 2.0.0 OK").await?
+                }
+                "NOOP" => self.send("250 2.0.0 OK").await?,
+                "VRFY" => self.send("252 2.5.0 Cannot verify the user").await?,
+                "QUIT" => {
+                    self.send("221 2.0.0 Bye").await?;
+                    return Ok(());
+                }
+                _ => self.send("500 5.5.2 Command not recognized").await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles AUTH. Returns `false` if the client closed the connection.
+    async fn authenticate(&mut self, argument: &str) -> io::Result<bool> {
+        let (mechanism, initial) = argument.split_once(' ').unwrap_or((argument, ""));
+        let mechanism = mechanism.to_ascii_uppercase();
+        let user = match mechanism.as_str() {
+            "PLAIN" | "XOAUTH2" => {
+                let response = match initial {
+                    "" => match self.challenge("").await? {
+                        Some(response) => response,
+                        None => return Ok(false),
+                    },
+                    initial => initial.to_string(),
+                };
+                decode(&response).and_then(|response| sasl_user(&mechanism, &response))
+            }
+            "LOGIN" => {
+                // "Username:" and "Password:", base64 encoded.
+                let Some(user) = self.challenge("VXNlcm5hbWU6").await? else {
+                    return Ok(false);
+                };
+                if self.challenge("UGFzc3dvcmQ6").await?.is_none() {
+                    return Ok(false);
+                }
+                decode(&user).map(|user| String::from_utf8_lossy(&user).into_owned())
+            }
+            _ => {
+                self.send("504 5.5.4 Unrecognized authentication type")
+                    .await?;
+                return Ok(true);
+            }
+        };
+        let Some(user) = user.filter(|user| !user.is_empty()) else {
+            self.send("501 5.5.2 Invalid authentication response")
+                .await?;
+            return Ok(true);
+        };
+        let script = self.mailstore.login(Protocol::Smtp, &user);
+        if script.rejected {
+            self.send("535 5.7.8 Authentication credentials invalid")
+                .await?;
+            return Ok(true);
+        }
+        self.login = Some(user);
+        self.script = script;
+        self.commands = 0;
+        self.send("235 2.7.0 Authentication successful").await?;
+        Ok(true)
+    }
+
+    /// Sends a `334` challenge and reads the response.
+    async fn challenge(&mut self, challenge: &str) -> io::Result<Option<String>> {
+        self.send(&format!("334 {}", challenge)).await?;
+        Ok(self.read_line().await?.map(|line| line.trim().to_string()))
+    }
+
+    async fn mail_from(&mut self, argument: &str) -> io::Result<()> {
+        if self.login.is_none() {
+            return self.send("530 5.7.0 Authentication required").await;
+        }
+        let Some(address) = path(argument, "FROM:") else {
+            return self.send("501 5.5.4 Invalid MAIL FROM").await;
+        };
+        self.reset();
+        self.mail_from = Some(address);
+        self.send("250 2.1.0 OK").await
+    }
+
+    async fn rcpt_to(&mut self, argument: &str) -> io::Result<()> {
+        let (Some(login), Some(mail_from)) = (&self.login, &self.mail_from) else {
+            return self.send("503 5.5.1 MAIL first").await;
+        };
+        let Some(recipient) = path(argument, "TO:").filter(|r| !r.is_empty()) else {
+            return self.send("501 5.5.4 Invalid RCPT TO").await;
+        };
+        match self.mailstore.check_recipient(login, mail_from, &recipient) {
+            RecipientCheck::Accepted => {
+                self.recipients.push(recipient);
+                self.send("250 2.1.5 OK").await
+            }
+            RecipientCheck::Greylisted => {
+                self.send("451 4.7.1 Greylisted, please try again later")
+                    .await
+            }
+            RecipientCheck::Rejected => self.send("550 5.1.1 Mailbox unavailable").await,
+        }
+    }
+
+    /// Handles DATA. Returns `false` if the client closed the connection.
+    async fn data(&mut self) -> io::Result<bool> {
+        if self.recipients.is_empty() {
+            self.send("554 5.5.1 No valid recipients").await?;
+            return Ok(true);
+        }
+        self.send("354 End data with <CR><LF>.<CR><LF>").await?;
+        let mut raw = Vec::new();
+        loop {
+            let mut line = Vec::new();
+            if self.stream.read_until(b'\n', &mut line).await? == 0 {
+                return Ok(false);
+            }
+            if line == b".\r\n" || line == b".\n" {
+                break;
+            }
+            // Undo dot-stuffing.
+            let line = line.strip_prefix(b".").unwrap_or(&line);
+            raw.extend_from_slice(line);
+        }
+        let login = self.login.clone().unwrap_or_default();
+        let mail_from = self.mail_from.clone().unwrap_or_default();
+        let recipients = std::mem::take(&mut self.recipients);
+        self.mailstore.receive(&login, &mail_from, recipients, raw);
+        self.reset();
+        self.send("250 2.0.0 OK queued").await?;
+        Ok(true)
+    }
+
+    fn reset(&mut self) {
+        self.mail_from = None;
+        self.recipients.clear();
+    }
+
+    async fn send(&mut self, reply: &str) -> io::Result<()> {
+        self.stream.write_all(reply.as_bytes()).await?;
+        self.stream.write_all(b"\r\n").await?;
+        self.stream.flush().await
+    }
+
+    async fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = Vec::new();
+        if self.stream.read_until(b'\n', &mut line).await? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+    }
+}
+
+/// The address of a `FROM:<address>` or `TO:<address>` argument, without parameters.
+fn path(argument: &str, prefix: &str) -> Option<String> {
+    let argument = argument.trim();
+    if !argument
+        .get(..prefix.len())
+        .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+    {
+        return None;
+    }
+    let path = argument[prefix.len()..].trim_start();
+    let address = match path.strip_prefix('<') {
+        Some(rest) => &rest[..rest.find('>')?],
+        None => path.split_whitespace().next().unwrap_or_default(),
+    };
+    Some(address.to_string())
+}
+
+fn decode(response: &str) -> Option<Vec<u8>> {
+    STANDARD.decode(response.trim()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use mail_send::smtp::message::Message;
+    use mail_send::{Credentials, SmtpClient};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::modules::harness::fixture::{AccountFixture, HarnessFixture, ScriptedBehavior};
+
+    #[test]
+    fn parses_paths() {
+        assert_eq!(
+            path("FROM:<alice@example.com> BODY=8BITMIME", "FROM:").as_deref(),
+            Some("alice@example.com")
+        );
+        assert_eq!(path("to: <>", "TO:").as_deref(), Some(""));
+        assert_eq!(path("FROM:<alice@example.com>", "TO:"), None);
+    }
+
+    #[tokio::test]
+    async fn accepts_messages_and_greylists() {
+        let fixture = HarnessFixture {
+            accounts: vec![AccountFixture {
+                login: "alice@example.com".into(),
+                smtp: vec![ScriptedBehavior::Greylist { attempts: 1 }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let store = Arc::new(Mailstore::from_fixture(fixture).unwrap());
+        let mut client = SmtpClient {
+            stream: connect(store.clone()),
+            timeout: Duration::from_secs(5),
+        };
+        client.read().await.unwrap();
+        let capabilities = client.capabilities("localhost", false).await.unwrap();
+        client
+            .authenticate(
+                &Credentials::new("alice@example.com", "secret"),
+                &capabilities,
+            )
+            .await
+            .unwrap();
+
+        let message = Message::empty()
+            .from("alice@example.com")
+            .to("bob@example.com")
+            .body(&b"Subject: Hi\r\n\r\n.Leading dot\r\n"[..]);
+        assert!(client.send(message.clone()).await.is_err());
+        client.rset().await.unwrap();
+        client.send(message).await.unwrap();
+
+        let outbox = store.outbox();
+        assert_eq!(outbox.len(), 1);
+        assert_eq!(outbox[0].recipients, ["bob@example.com"]);
+        assert_eq!(outbox[0].subject.as_deref(), Some("Hi"));
+        assert_eq!(
+            crate::base64_decode!(&outbox[0].raw),
+            b"Subject: Hi\r\n\r\n.Leading dot\r\n"
+        );
+    }
+}
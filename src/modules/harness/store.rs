@@ -0,0 +1,476 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use mail_parser::MessageParser;
+use tokio::sync::Notify;
+
+use crate::modules::harness::fixture::{
+    default_internal_date, AccountFixture, HarnessFixture, ScriptedBehavior,
+};
+use crate::modules::harness::payload::CapturedMessage;
+use crate::{base64_encode, utc_now};
+
+/// Mailboxes of logins the fixture gives none, with their LIST attribute.
+const DEFAULT_MAILBOXES: &[(&str, Option<&str>)] = &[
+    ("INBOX", None),
+    ("Sent", Some("\\Sent")),
+    ("Drafts", Some("\\Drafts")),
+    ("Trash", Some("\\Trash")),
+];
+
+#[derive(Clone, Debug)]
+pub struct StubMessage {
+    pub uid: u32,
+    pub flags: Vec<String>,
+    /// Internal date in milliseconds.
+    pub internal_date: i64,
+    pub raw: Vec<u8>,
+}
+
+impl StubMessage {
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f.eq_ignore_ascii_case(flag))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StubMailbox {
+    pub name: String,
+    pub attributes: Vec<String>,
+    pub subscribed: bool,
+    pub uid_validity: u32,
+    pub uid_next: u32,
+    pub messages: Vec<StubMessage>,
+}
+
+impl StubMailbox {
+    pub fn new(name: impl Into<String>, attributes: Vec<String>, uid_validity: u32) -> Self {
+        Self {
+            name: name.into(),
+            attributes,
+            subscribed: true,
+            uid_validity,
+            uid_next: 1,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Adds a message and returns its UID.
+    pub fn append(&mut self, raw: Vec<u8>, flags: Vec<String>, internal_date: i64) -> u32 {
+        let uid = self.uid_next;
+        self.uid_next += 1;
+        self.messages.push(StubMessage {
+            uid,
+            flags,
+            internal_date,
+            raw,
+        });
+        uid
+    }
+
+    pub fn unseen(&self) -> usize {
+        self.messages
+            .iter()
+            .filter(|m| !m.has_flag("\\Seen"))
+            .count()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    Imap,
+    Smtp,
+}
+
+/// How a server treats a session, scripted by the behaviors of its login.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionScript {
+    pub rejected: bool,
+    /// Number of commands handled after login before the connection is dropped.
+    pub disconnect_after: Option<u32>,
+    pub latency: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecipientCheck {
+    Accepted,
+    Greylisted,
+    Rejected,
+}
+
+#[derive(Debug, Default)]
+struct LoginCounters {
+    attempts: u32,
+    sessions: u32,
+}
+
+#[derive(Debug)]
+pub struct StubAccount {
+    pub mailboxes: Vec<StubMailbox>,
+    imap: Vec<ScriptedBehavior>,
+    smtp: Vec<ScriptedBehavior>,
+    imap_logins: LoginCounters,
+    smtp_logins: LoginCounters,
+    /// Times each (sender, recipient) pair was tried, for greylisting.
+    greylist: HashMap<(String, String), u32>,
+}
+
+impl Default for StubAccount {
+    fn default() -> Self {
+        Self {
+            mailboxes: DEFAULT_MAILBOXES
+                .iter()
+                .map(|(name, attribute)| {
+                    StubMailbox::new(*name, attribute.iter().map(|a| a.to_string()).collect(), 1)
+                })
+                .collect(),
+            imap: Vec::new(),
+            smtp: Vec::new(),
+            imap_logins: LoginCounters::default(),
+            smtp_logins: LoginCounters::default(),
+            greylist: HashMap::new(),
+        }
+    }
+}
+
+impl StubAccount {
+    fn from_fixture(fixture: &AccountFixture, base_dir: &std::path::Path) -> Result<Self, String> {
+        let mut account = Self {
+            imap: fixture.imap.clone(),
+            smtp: fixture.smtp.clone(),
+            ..Default::default()
+        };
+        if fixture.mailboxes.is_empty() {
+            return Ok(account);
+        }
+        account.mailboxes = fixture
+            .mailboxes
+            .iter()
+            .map(|mailbox| {
+                let mut stub = StubMailbox::new(
+                    mailbox.name.clone(),
+                    mailbox.attributes.clone(),
+                    mailbox.uid_validity.unwrap_or(1),
+                );
+                for message in &mailbox.messages {
+                    let raw = message.content(base_dir)?;
+                    let internal_date = message
+                        .internal_date
+                        .unwrap_or_else(|| default_internal_date(&raw));
+                    stub.append(raw, message.flags.clone(), internal_date);
+                }
+                Ok(stub)
+            })
+            .collect::<Result<_, String>>()?;
+        if account.mailbox("INBOX").is_none() {
+            account
+                .mailboxes
+                .insert(0, StubMailbox::new("INBOX", Vec::new(), 1));
+        }
+        Ok(account)
+    }
+
+    /// Finds a mailbox by name. INBOX is case-insensitive.
+    pub fn mailbox(&self, name: &str) -> Option<&StubMailbox> {
+        self.mailboxes.iter().find(|m| same_mailbox(&m.name, name))
+    }
+
+    pub fn mailbox_mut(&mut self, name: &str) -> Option<&mut StubMailbox> {
+        self.mailboxes
+            .iter_mut()
+            .find(|m| same_mailbox(&m.name, name))
+    }
+
+    fn login(&mut self, protocol: Protocol) -> SessionScript {
+        let (behaviors, counters) = match protocol {
+            Protocol::Imap => (&self.imap, &mut self.imap_logins),
+            Protocol::Smtp => (&self.smtp, &mut self.smtp_logins),
+        };
+        counters.attempts += 1;
+        let rejected = behaviors.iter().any(|behavior| {
+            matches!(behavior, ScriptedBehavior::RejectLogin { times } if counters.attempts <= *times)
+        });
+        if rejected {
+            return SessionScript {
+                rejected,
+                ..Default::default()
+            };
+        }
+        counters.sessions += 1;
+        let sessions = counters.sessions;
+        SessionScript {
+            rejected,
+            disconnect_after: behaviors.iter().find_map(|behavior| match behavior {
+                ScriptedBehavior::Disconnect {
+                    after_commands,
+                    times,
+                } if sessions <= *times => Some(*after_commands),
+                _ => None,
+            }),
+            latency: behaviors.iter().find_map(|behavior| match behavior {
+                ScriptedBehavior::Latency { millis } => Some(Duration::from_millis(*millis)),
+                _ => None,
+            }),
+        }
+    }
+
+    fn check_recipient(&mut self, sender: &str, recipient: &str) -> RecipientCheck {
+        let rejected = self.smtp.iter().any(|behavior| {
+            matches!(behavior, ScriptedBehavior::RejectRecipient { address } if address.eq_ignore_ascii_case(recipient))
+        });
+        if rejected {
+            return RecipientCheck::Rejected;
+        }
+        let Some(attempts) = self.smtp.iter().find_map(|behavior| match behavior {
+            ScriptedBehavior::Greylist { attempts } => Some(*attempts),
+            _ => None,
+        }) else {
+            return RecipientCheck::Accepted;
+        };
+        let tries = self
+            .greylist
+            .entry((sender.to_lowercase(), recipient.to_lowercase()))
+            .or_default();
+        *tries += 1;
+        if *tries <= attempts {
+            RecipientCheck::Greylisted
+        } else {
+            RecipientCheck::Accepted
+        }
+    }
+}
+
+fn same_mailbox(a: &str, b: &str) -> bool {
+    a == b || (a.eq_ignore_ascii_case("INBOX") && b.eq_ignore_ascii_case("INBOX"))
+}
+
+/// State of the in-memory servers: the mailboxes of each login, and the messages sent.
+///
+/// Logins missing from the fixture get default mailboxes on first use, so any account
+/// can connect.
+pub struct Mailstore {
+    fixture: HarnessFixture,
+    accounts: Mutex<HashMap<String, StubAccount>>,
+    outbox: Mutex<Vec<CapturedMessage>>,
+    changes: Notify,
+}
+
+impl Mailstore {
+    pub fn from_fixture(fixture: HarnessFixture) -> Result<Self, String> {
+        let accounts = Self::load_accounts(&fixture)?;
+        Ok(Self {
+            fixture,
+            accounts: Mutex::new(accounts),
+            outbox: Mutex::new(Vec::new()),
+            changes: Notify::new(),
+        })
+    }
+
+    fn load_accounts(fixture: &HarnessFixture) -> Result<HashMap<String, StubAccount>, String> {
+        fixture
+            .accounts
+            .iter()
+            .map(|account| {
+                Ok((
+                    account.login.to_lowercase(),
+                    StubAccount::from_fixture(account, &fixture.base_dir)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Restores the mailboxes and behaviors of the fixture and empties the outbox.
+    pub fn reset(&self) -> Result<(), String> {
+        let accounts = Self::load_accounts(&self.fixture)?;
+        *lock(&self.accounts) = accounts;
+        lock(&self.outbox).clear();
+        self.changes.notify_waiters();
+        Ok(())
+    }
+
+    pub fn with_account<R>(&self, login: &str, f: impl FnOnce(&mut StubAccount) -> R) -> R {
+        let mut accounts = lock(&self.accounts);
+        f(accounts.entry(login.to_lowercase()).or_default())
+    }
+
+    /// Whether the login is known, from the fixture or an earlier connection.
+    pub fn has_account(&self, login: &str) -> bool {
+        lock(&self.accounts).contains_key(&login.to_lowercase())
+    }
+
+    pub fn login(&self, protocol: Protocol, login: &str) -> SessionScript {
+        self.with_account(login, |account| account.login(protocol))
+    }
+
+    pub fn check_recipient(&self, login: &str, sender: &str, recipient: &str) -> RecipientCheck {
+        self.with_account(login, |account| account.check_recipient(sender, recipient))
+    }
+
+    /// Records a message accepted over SMTP, and delivers it to the INBOX of the recipients
+    /// that are logins of the store.
+    pub fn receive(&self, login: &str, mail_from: &str, recipients: Vec<String>, raw: Vec<u8>) {
+        let headers = MessageParser::default().parse_headers(&raw);
+        let captured = CapturedMessage {
+            login: login.to_string(),
+            mail_from: mail_from.to_string(),
+            subject: headers.as_ref().and_then(|h| h.subject().map(String::from)),
+            message_id: headers
+                .as_ref()
+                .and_then(|h| h.message_id().map(String::from)),
+            raw: base64_encode!(&raw),
+            received_at: utc_now!(),
+            recipients,
+        };
+        {
+            let mut accounts = lock(&self.accounts);
+            for recipient in &captured.recipients {
+                if let Some(inbox) = accounts
+                    .get_mut(&recipient.to_lowercase())
+                    .and_then(|account| account.mailbox_mut("INBOX"))
+                {
+                    inbox.append(raw.clone(), Vec::new(), captured.received_at);
+                }
+            }
+        }
+        lock(&self.outbox).push(captured);
+        self.changes.notify_waiters();
+    }
+
+    /// Adds a message to a mailbox of the login, as if it had been delivered.
+    pub fn inject(
+        &self,
+        login: &str,
+        mailbox: &str,
+        raw: Vec<u8>,
+        flags: Vec<String>,
+    ) -> Result<u32, String> {
+        let uid = self.with_account(login, |account| {
+            account
+                .mailbox_mut(mailbox)
+                .map(|stub| stub.append(raw, flags, utc_now!()))
+                .ok_or_else(|| format!("Mailbox '{}' of '{}' does not exist", mailbox, login))
+        })?;
+        self.changes.notify_waiters();
+        Ok(uid)
+    }
+
+    pub fn outbox(&self) -> Vec<CapturedMessage> {
+        lock(&self.outbox).clone()
+    }
+
+    /// Wakes the sessions waiting in IDLE after messages were added or removed.
+    pub fn notify_changes(&self) {
+        self.changes.notify_waiters();
+    }
+
+    pub fn changes(&self) -> &Notify {
+        &self.changes
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::harness::fixture::{MailboxFixture, MessageFixture};
+
+    fn fixture(behaviors: Vec<ScriptedBehavior>) -> HarnessFixture {
+        HarnessFixture {
+            accounts: vec![AccountFixture {
+                login: "Alice@example.com".into(),
+                mailboxes: vec![MailboxFixture {
+                    name: "Archive".into(),
+                    messages: vec![MessageFixture {
+                        raw: Some(
+                            "Subject: Hello\r\nDate: Mon, 1 Sep 2025 10:00:00 +0000\r\n\r\nHi\r\n"
+                                .into(),
+                        ),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                imap: behaviors.clone(),
+                smtp: behaviors,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn seeds_mailboxes_from_the_fixture() {
+        let store = Mailstore::from_fixture(fixture(Vec::new())).unwrap();
+        store.with_account("alice@example.com", |account| {
+            let names: Vec<_> = account.mailboxes.iter().map(|m| m.name.as_str()).collect();
+            assert_eq!(names, ["INBOX", "Archive"]);
+            let archive = account.mailbox("Archive").unwrap();
+            assert_eq!(archive.messages[0].uid, 1);
+            assert_eq!(archive.messages[0].internal_date, 1_756_720_800_000);
+            assert_eq!(archive.uid_next, 2);
+        });
+        assert!(!store.has_account("bob@example.com"));
+        store.with_account("bob@example.com", |account| {
+            assert!(account.mailbox("inbox").is_some());
+            assert_eq!(account.mailboxes.len(), DEFAULT_MAILBOXES.len());
+        });
+    }
+
+    #[test]
+    fn scripts_sessions() {
+        let store = Mailstore::from_fixture(fixture(vec![
+            ScriptedBehavior::RejectLogin { times: 1 },
+            ScriptedBehavior::Disconnect {
+                after_commands: 2,
+                times: 1,
+            },
+            ScriptedBehavior::Greylist { attempts: 1 },
+            ScriptedBehavior::RejectRecipient {
+                address: "gone@example.com".into(),
+            },
+        ]))
+        .unwrap();
+        let login = "alice@example.com";
+        assert!(store.login(Protocol::Imap, login).rejected);
+        assert_eq!(store.login(Protocol::Imap, login).disconnect_after, Some(2));
+        assert_eq!(store.login(Protocol::Imap, login), SessionScript::default());
+        assert!(store.login(Protocol::Smtp, login).rejected);
+
+        let check = |recipient| store.check_recipient(login, "alice@example.com", recipient);
+        assert_eq!(check("bob@example.com"), RecipientCheck::Greylisted);
+        assert_eq!(check("BOB@example.com"), RecipientCheck::Accepted);
+        assert_eq!(check("gone@example.com"), RecipientCheck::Rejected);
+    }
+
+    #[test]
+    fn delivers_sent_messages_to_local_recipients() {
+        let store = Mailstore::from_fixture(fixture(Vec::new())).unwrap();
+        let raw = b"Subject: Report\r\nMessage-ID: <1@example.com>\r\n\r\nBody\r\n".to_vec();
+        store.receive(
+            "bob@example.com",
+            "bob@example.com",
+            vec!["alice@example.com".into(), "carol@example.org".into()],
+            raw,
+        );
+        let outbox = store.outbox();
+        assert_eq!(outbox.len(), 1);
+        assert_eq!(outbox[0].subject.as_deref(), Some("Report"));
+        assert_eq!(outbox[0].message_id.as_deref(), Some("1@example.com"));
+        store.with_account("alice@example.com", |account| {
+            assert_eq!(account.mailbox("INBOX").unwrap().messages.len(), 1);
+        });
+        assert!(!store.has_account("carol@example.org"));
+
+        store.reset().unwrap();
+        assert!(store.outbox().is_empty());
+        store.with_account("alice@example.com", |account| {
+            assert!(account.mailbox("INBOX").unwrap().messages.is_empty());
+        });
+    }
+}
@@ -2,20 +2,49 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use std::{sync::LazyLock, time::Duration};
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex, MutexGuard},
+    time::Duration,
+};
 
-use tokio::{sync::mpsc, time::Instant};
-use tracing::error;
+use serde::{Deserialize, Serialize};
+use tokio::{io::AsyncBufReadExt, sync::Notify, time::Instant};
+use tracing::{error, info, warn};
 
-use crate::modules::{
-    error::RustMailerResult,
-    hook::{events::RustMailerEvent, task::EventHookTask},
-    tasks::queue::RustMailerTaskQueue,
+use crate::{
+    modules::{
+        error::{code::ErrorCode, RustMailerError, RustMailerResult},
+        hook::{
+            events::{EventType, RustMailerEvent},
+            stream::EVENT_SUBSCRIBERS,
+            task::EventHookTask,
+        },
+        metrics::{
+            RUSTMAILER_EVENT_CHANNEL_DEPTH, RUSTMAILER_EVENT_CHANNEL_DROPPED_TOTAL,
+            RUSTMAILER_EVENT_CHANNEL_SPILLED_DEPTH, RUSTMAILER_EVENT_CHANNEL_SPILLED_TOTAL,
+        },
+        settings::{
+            cli::{EventOverflowPolicy, SETTINGS},
+            dir::DATA_DIR_MANAGER,
+        },
+        tasks::queue::RustMailerTaskQueue,
+    },
+    raise_error,
 };
 
 pub static EVENT_CHANNEL: LazyLock<EventChannel> = LazyLock::new(EventChannel::new);
 
 const BATCH_SIZE: usize = 50;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(3);
+/// Events spilled while the channel is full, one JSON document per line.
+const SPILL_FILE: &str = "events.spill";
+/// A spill file being dispatched. Only left over if the process stopped halfway through, in
+/// which case its events are dispatched again on startup.
+const DRAINING_FILE: &str = "events.spill.draining";
 
 #[derive(Debug)]
 pub struct Event {
@@ -34,87 +63,421 @@ impl Event {
     }
 }
 
-pub struct EventChannel {
-    sender: mpsc::Sender<Event>,
+/// An event waiting to be dispatched, serialized up front so it can be spilled to disk and
+/// read back as is.
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingEvent {
+    account_id: u64,
+    account_email: String,
+    event_type: EventType,
+    event: serde_json::Value,
 }
 
-impl EventChannel {
-    pub async fn queue(&self, event: Event) {
-        if let Err(e) = self.sender.send(event).await {
-            error!("Failed to queue event. Channel error: {:#?}", e);
+impl TryFrom<Event> for PendingEvent {
+    type Error = RustMailerError;
+
+    fn try_from(value: Event) -> Result<Self, Self::Error> {
+        Ok(Self {
+            account_id: value.account_id,
+            account_email: value.account_email,
+            event_type: value.event.event_type.clone(),
+            event: value.event.to_json_value()?,
+        })
+    }
+}
+
+struct QueueState {
+    events: VecDeque<PendingEvent>,
+    /// Number of events in the spill file. With the spill policy, new events are spilled too
+    /// while it is not empty, so they are dispatched after the ones already on disk.
+    spilled: usize,
+    spill_file: Option<File>,
+}
+
+/// Bounded queue between the producers of events and the dispatcher that turns them into
+/// event hook tasks.
+///
+/// Once `capacity` events are waiting, the overflow policy decides what happens to new
+/// ones: producers wait for room, the oldest event is dropped, or events are spilled to disk.
+struct EventQueue {
+    state: Mutex<QueueState>,
+    capacity: usize,
+    policy: EventOverflowPolicy,
+    spill_path: PathBuf,
+    draining_path: PathBuf,
+    /// Notified when an event is queued.
+    queued: Notify,
+    /// Notified when the dispatcher takes events out of the queue.
+    dequeued: Notify,
+}
+
+impl EventQueue {
+    fn new(capacity: usize, policy: EventOverflowPolicy, dir: &Path) -> Self {
+        let spill_path = dir.join(SPILL_FILE);
+        let draining_path = dir.join(DRAINING_FILE);
+        let spilled = count_lines(&spill_path);
+        let on_disk = spilled + count_lines(&draining_path);
+        if on_disk > 0 {
+            info!(
+                "Found {} spilled events from a previous run, they will be dispatched first",
+                on_disk
+            );
+        }
+        RUSTMAILER_EVENT_CHANNEL_SPILLED_DEPTH.set(on_disk as i64);
+        Self {
+            state: Mutex::new(QueueState {
+                events: VecDeque::with_capacity(capacity.min(BATCH_SIZE * 4)),
+                spilled,
+                spill_file: None,
+            }),
+            capacity,
+            policy,
+            spill_path,
+            draining_path,
+            queued: Notify::new(),
+            dequeued: Notify::new(),
         }
     }
 
-    pub fn new() -> Self {
-        let (sender, mut receiver) = mpsc::channel::<Event>(1000);
-        let instance = EventChannel { sender };
-        let mut buffer: Vec<Event> = Vec::with_capacity(BATCH_SIZE);
-        let mut last_flush_time = Instant::now();
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
 
-        tokio::spawn({
-            async move {
-                loop {
-                    tokio::select! {
-                        maybe_event = receiver.recv() => {
-                            match maybe_event {
-                                Some(e) => buffer.push(e),
-                                None => break, // channel closed
-                            }
-                        }
-                        _ = tokio::time::sleep(Duration::from_secs(3)) => {
-                        }
-                    }
+    async fn push(&self, mut event: PendingEvent) {
+        loop {
+            // Created before the queue is checked, so room made in between is not missed.
+            let room = self.dequeued.notified();
+            match self.offer(event) {
+                Ok(()) => {
+                    self.queued.notify_one();
+                    return;
+                }
+                Err(rejected) => event = rejected,
+            }
+            room.await;
+        }
+    }
 
-                    let should_flush = buffer.len() >= BATCH_SIZE
-                        || last_flush_time.elapsed() >= Duration::from_secs(3);
-                    if should_flush && !buffer.is_empty() {
-                        match Self::handle(&buffer).await {
-                            Ok(_) => tracing::debug!(
-                                "Successfully processed batch of {} messages",
-                                buffer.len()
-                            ),
-                            Err(e) => tracing::error!("Error processing batch: {:?}", e),
-                        }
-                        buffer.clear();
-                        last_flush_time = Instant::now();
-                    }
+    /// Queues the event, applying the overflow policy if the queue is full. Gives the event
+    /// back if the producer has to wait for room.
+    fn offer(&self, event: PendingEvent) -> Result<(), PendingEvent> {
+        let mut state = self.lock();
+        let keep_spilling = self.policy == EventOverflowPolicy::Spill && state.spilled > 0;
+        if !keep_spilling && state.events.len() < self.capacity {
+            state.events.push_back(event);
+            RUSTMAILER_EVENT_CHANNEL_DEPTH.set(state.events.len() as i64);
+            return Ok(());
+        }
+        match self.policy {
+            EventOverflowPolicy::Block => Err(event),
+            EventOverflowPolicy::DropOldest => {
+                if let Some(dropped) = state.events.pop_front() {
+                    RUSTMAILER_EVENT_CHANNEL_DROPPED_TOTAL.inc();
+                    warn!(
+                        "Event channel is full, dropped the oldest event ({}) of account {}",
+                        dropped.event_type, dropped.account_id
+                    );
+                }
+                state.events.push_back(event);
+                Ok(())
+            }
+            EventOverflowPolicy::Spill => match self.spill(&mut state, &event) {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    // Rather wait than lose the event.
+                    error!(
+                        "Failed to spill event to {:?}, waiting for room instead: {:#?}",
+                        self.spill_path, e
+                    );
+                    Err(event)
+                }
+            },
+        }
+    }
+
+    fn spill(&self, state: &mut QueueState, event: &PendingEvent) -> RustMailerResult<()> {
+        let mut line = serde_json::to_vec(event)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        line.push(b'\n');
+        if state.spill_file.is_none() {
+            state.spill_file = Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.spill_path)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?,
+            );
+        }
+        state
+            .spill_file
+            .as_mut()
+            .expect("spill file was just opened")
+            .write_all(&line)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        state.spilled += 1;
+        RUSTMAILER_EVENT_CHANNEL_SPILLED_TOTAL.inc();
+        RUSTMAILER_EVENT_CHANNEL_SPILLED_DEPTH.inc();
+        Ok(())
+    }
+
+    /// Moves queued events to `buffer` until it holds `BATCH_SIZE` events. Returns whether
+    /// events are left in the queue.
+    fn take(&self, buffer: &mut Vec<PendingEvent>) -> bool {
+        let mut state = self.lock();
+        let count = BATCH_SIZE
+            .saturating_sub(buffer.len())
+            .min(state.events.len());
+        buffer.extend(state.events.drain(..count));
+        RUSTMAILER_EVENT_CHANNEL_DEPTH.set(state.events.len() as i64);
+        let remaining = !state.events.is_empty();
+        drop(state);
+        if count > 0 {
+            self.dequeued.notify_waiters();
+        }
+        remaining
+    }
+
+    /// Returns the spill file to dispatch, if any, renaming the current spill file first so
+    /// new events can be spilled meanwhile.
+    fn next_spill_file(&self) -> Option<PathBuf> {
+        if self.draining_path.exists() {
+            return Some(self.draining_path.clone());
+        }
+        let mut state = self.lock();
+        if state.spilled == 0 && !self.spill_path.exists() {
+            return None;
+        }
+        state.spill_file = None;
+        if let Err(e) = std::fs::rename(&self.spill_path, &self.draining_path) {
+            error!(
+                "Failed to move spill file {:?} for dispatch: {:#?}",
+                self.spill_path, e
+            );
+            return None;
+        }
+        state.spilled = 0;
+        Some(self.draining_path.clone())
+    }
+
+    async fn dispatch(self: Arc<Self>) {
+        let mut buffer: Vec<PendingEvent> = Vec::with_capacity(BATCH_SIZE);
+        let mut last_flush_time = Instant::now();
+        let mut remaining = false;
+        loop {
+            if !remaining {
+                tokio::select! {
+                    _ = self.queued.notified() => {}
+                    _ = tokio::time::sleep(FLUSH_INTERVAL) => {}
                 }
+            }
+            remaining = self.take(&mut buffer);
 
-                if !buffer.is_empty() {
-                    if let Err(e) = Self::handle(&buffer).await {
-                        tracing::error!("Error processing final batch: {:?}", e);
+            if buffer.len() < BATCH_SIZE && last_flush_time.elapsed() < FLUSH_INTERVAL {
+                continue;
+            }
+            if !buffer.is_empty() {
+                match handle(&buffer).await {
+                    Ok(_) => {
+                        tracing::debug!("Successfully processed batch of {} messages", buffer.len())
                     }
+                    Err(e) => tracing::error!("Error processing batch: {:?}", e),
                 }
+                buffer.clear();
             }
-        });
+            last_flush_time = Instant::now();
 
-        instance
+            // Spilled events are newer than the queued ones, so they go once the queue is empty.
+            if !remaining {
+                if let Some(path) = self.next_spill_file() {
+                    drain_spill_file(&path).await;
+                }
+            }
+        }
+    }
+}
+
+pub struct EventChannel {
+    queue: Arc<EventQueue>,
+}
+
+impl EventChannel {
+    pub async fn queue(&self, event: Event) {
+        EVENT_SUBSCRIBERS.publish(event.account_id, &event.account_email, &event.event);
+        match PendingEvent::try_from(event) {
+            Ok(event) => self.queue.push(event).await,
+            Err(e) => error!("Failed to queue event. Serialization error: {:#?}", e),
+        }
+    }
+
+    pub fn new() -> Self {
+        let queue = Arc::new(EventQueue::new(
+            SETTINGS.rustmailer_event_channel_capacity as usize,
+            SETTINGS.rustmailer_event_channel_overflow_policy,
+            &DATA_DIR_MANAGER.root_dir,
+        ));
+        tokio::spawn(queue.clone().dispatch());
+        EventChannel { queue }
+    }
+}
+
+async fn handle(events: &[PendingEvent]) -> RustMailerResult<()> {
+    let mut all_tasks = Vec::new();
+
+    for event in events {
+        let hooks = EventHookTask::get_matching_hooks(event.account_id, &event.event_type).await?;
+
+        for h in hooks {
+            all_tasks.push(EventHookTask {
+                event_hook_id: h.id,
+                account_id: event.account_id,
+                account_email: event.account_email.clone(),
+                event_type: event.event_type.clone(),
+                event: event.event.clone(),
+                test: false,
+                replay_of: None,
+            });
+        }
     }
 
-    pub async fn handle(events: &[Event]) -> RustMailerResult<()> {
-        let mut all_tasks = Vec::new();
+    let task_queue = RustMailerTaskQueue::get()?;
+    for chunk in all_tasks.chunks(BATCH_SIZE) {
+        task_queue.submit_tasks(chunk, None).await?;
+    }
 
-        for event in events {
-            let hooks =
-                EventHookTask::get_matching_hooks(event.account_id, &event.event.event_type)
-                    .await?;
+    Ok(())
+}
 
-            for h in hooks {
-                all_tasks.push(EventHookTask {
-                    event_hook_id: h.id,
-                    account_id: event.account_id,
-                    account_email: event.account_email.clone(),
-                    event_type: event.event.event_type.clone(),
-                    event: event.event.to_json_value().unwrap(),
-                });
+/// Dispatches the events of a spill file in batches, then deletes it.
+async fn drain_spill_file(path: &Path) {
+    let file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Failed to open spill file {:?}: {:#?}", path, e);
+            return;
+        }
+    };
+    let mut lines = tokio::io::BufReader::new(file).lines();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut total = 0usize;
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                error!("Failed to read spill file {:?}: {:#?}", path, e);
+                break;
             }
+        };
+        RUSTMAILER_EVENT_CHANNEL_SPILLED_DEPTH.dec();
+        match serde_json::from_str::<PendingEvent>(&line) {
+            Ok(event) => batch.push(event),
+            Err(e) => warn!("Skipping invalid line in spill file {:?}: {:#?}", path, e),
+        }
+        if batch.len() >= BATCH_SIZE {
+            total += dispatch_spilled(&mut batch).await;
         }
+    }
+    total += dispatch_spilled(&mut batch).await;
+    if let Err(e) = tokio::fs::remove_file(path).await {
+        error!("Failed to delete spill file {:?}: {:#?}", path, e);
+    }
+    info!("Dispatched {} spilled events", total);
+}
+
+async fn dispatch_spilled(batch: &mut Vec<PendingEvent>) -> usize {
+    if batch.is_empty() {
+        return 0;
+    }
+    if let Err(e) = handle(batch).await {
+        tracing::error!("Error processing spilled batch: {:?}", e);
+    }
+    let count = batch.len();
+    batch.clear();
+    count
+}
+
+fn count_lines(path: &Path) -> usize {
+    File::open(path)
+        .map(|file| BufReader::new(file).lines().count())
+        .unwrap_or(0)
+}
 
-        let task_queue = RustMailerTaskQueue::get()?;
-        for chunk in all_tasks.chunks(BATCH_SIZE) {
-            task_queue.submit_tasks(chunk, None).await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    fn event(n: u64) -> PendingEvent {
+        PendingEvent {
+            account_id: n,
+            account_email: "test@example.com".into(),
+            event_type: EventType::EmailAddedToFolder,
+            event: serde_json::json!({ "n": n }),
         }
+    }
 
-        Ok(())
+    fn queued(queue: &EventQueue) -> Vec<u64> {
+        queue.lock().events.iter().map(|e| e.account_id).collect()
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_keeps_the_newest_events() {
+        let dir = tempdir().unwrap();
+        let queue = EventQueue::new(2, EventOverflowPolicy::DropOldest, dir.path());
+        for n in 1..=3 {
+            queue.push(event(n)).await;
+        }
+        assert_eq!(queued(&queue), vec![2, 3]);
+    }
+
+    #[tokio::test]
+    async fn spill_keeps_the_order_of_events() {
+        let dir = tempdir().unwrap();
+        let queue = EventQueue::new(1, EventOverflowPolicy::Spill, dir.path());
+        for n in 1..=3 {
+            queue.push(event(n)).await;
+        }
+        let mut buffer = Vec::new();
+        assert!(!queue.take(&mut buffer));
+        // The queue has room again, but older events are still on disk.
+        queue.push(event(4)).await;
+        assert_eq!(queued(&queue), Vec::<u64>::new());
+
+        let path = queue.next_spill_file().unwrap();
+        let mut contents = String::new();
+        File::open(&path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let spilled: Vec<u64> = contents
+            .lines()
+            .map(|l| serde_json::from_str::<PendingEvent>(l).unwrap().account_id)
+            .collect();
+        assert_eq!(spilled, vec![2, 3, 4]);
+
+        queue.push(event(5)).await;
+        assert_eq!(queued(&queue), vec![5]);
+        // A restart picks up the file that was being dispatched.
+        let restarted = EventQueue::new(1, EventOverflowPolicy::Spill, dir.path());
+        assert_eq!(restarted.next_spill_file(), Some(path));
+    }
+
+    #[tokio::test]
+    async fn block_waits_for_room() {
+        let dir = tempdir().unwrap();
+        let queue = Arc::new(EventQueue::new(1, EventOverflowPolicy::Block, dir.path()));
+        queue.push(event(1)).await;
+        let producer = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.push(event(2)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!producer.is_finished());
+
+        let mut buffer = Vec::new();
+        queue.take(&mut buffer);
+        producer.await.unwrap();
+        assert_eq!(queued(&queue), vec![2]);
     }
 }
@@ -0,0 +1,328 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::{
+    id,
+    modules::{
+        common::auth::ClientContext,
+        database::{
+            batch::WriteBatch, batch_delete_impl, insert_impl, key::timestamp_key_range,
+            manager::DB_MANAGER, paginate_secondary_scan_impl, secondary_find_impl,
+        },
+        error::{code::ErrorCode, RustMailerResult},
+        hook::{
+            entity::{EventHooks, HookType},
+            events::EventType,
+            task::EventHookTask,
+        },
+        rest::response::DataPage,
+        settings::cli::SETTINGS,
+        tasks::queue::RustMailerTaskQueue,
+    },
+    raise_error, utc_now,
+};
+
+pub mod task;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// Maximum number of characters of a response body kept in the log.
+pub const RESPONSE_SNIPPET_LEN: usize = 1024;
+
+/// What is known about a delivery attempt once it ends, filled in while dispatching.
+#[derive(Clone, Debug, Default)]
+pub struct DeliveryAttempt {
+    /// The attempt number, starting at 1. 0 if the hook task could not be read.
+    pub attempt: u32,
+    /// The status code of the response, for HTTP hooks.
+    pub status_code: Option<u16>,
+    /// The beginning of the response body, for HTTP hooks.
+    pub response: Option<String>,
+}
+
+impl DeliveryAttempt {
+    pub fn set_response(&mut self, status_code: u16, body: &str) {
+        self.status_code = Some(status_code);
+        self.response =
+            (!body.is_empty()).then(|| body.chars().take(RESPONSE_SNIPPET_LEN).collect());
+    }
+}
+
+/// The outcome of one dispatch of an event to a hook.
+///
+/// Deliveries are kept for `rustmailer_hook_delivery_log_retention_days` days.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 30, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct HookDelivery {
+    /// Unique identifier of the delivery.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// The hook the event was dispatched to.
+    #[secondary_key]
+    pub hook_id: u64,
+    /// The account the event is about.
+    #[secondary_key]
+    pub account_id: u64,
+    /// Email address of the account the event is about.
+    pub account_email: String,
+    /// Whether the hook is a global hook.
+    pub global: bool,
+    /// The hook task that dispatched the event.
+    pub task_id: u64,
+    pub event_type: EventType,
+    /// The kind of destination of the hook at the time of the delivery.
+    pub destination: HookType,
+    /// The attempt number of the hook task, starting at 1.
+    pub attempt: u32,
+    /// Whether the event was delivered.
+    pub success: bool,
+    /// The status code of the response, for HTTP hooks.
+    pub status_code: Option<u16>,
+    /// Time taken by the attempt, in milliseconds.
+    pub latency_ms: u64,
+    /// The first 1024 characters of the response body, for HTTP hooks.
+    pub response: Option<String>,
+    /// The error of a failed attempt.
+    pub error: Option<String>,
+    /// The dispatched event as JSON, before the VRL script of the hook is applied.
+    pub event: String,
+    /// Whether the event was synthesized by the simulation endpoint.
+    pub test: bool,
+    /// The delivery this event was replayed from, if any.
+    pub replay_of: Option<u64>,
+    /// Time (Unix epoch milliseconds) the attempt ended.
+    pub created_at: i64,
+}
+
+/// An event queued again for delivery to its hook.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ReplayedDelivery {
+    /// ID of the hook task delivering the event, usable with the hook task endpoints.
+    pub task_id: u64,
+}
+
+impl HookDelivery {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    /// Starts the log entry of a delivery attempt, before the task hands its event over.
+    pub fn start(task: &EventHookTask, task_id: u64, hook: &EventHooks) -> Self {
+        Self {
+            id: id!(64),
+            hook_id: hook.id,
+            account_id: task.account_id,
+            account_email: task.account_email.clone(),
+            global: hook.account_id.is_none(),
+            task_id,
+            event_type: task.event_type.clone(),
+            destination: hook.hook_type.clone(),
+            event: task.event.to_string(),
+            test: task.test,
+            replay_of: task.replay_of,
+            ..Default::default()
+        }
+    }
+
+    /// Completes the entry with the outcome of the attempt and saves it. Failures are only
+    /// logged, so that dispatching is not affected.
+    pub async fn finish(
+        mut self,
+        attempt: DeliveryAttempt,
+        elapsed: Duration,
+        error: Option<String>,
+    ) {
+        self.attempt = attempt.attempt;
+        self.status_code = attempt.status_code;
+        self.response = attempt.response;
+        self.latency_ms = elapsed.as_millis() as u64;
+        self.success = error.is_none();
+        self.error = error;
+        self.created_at = utc_now!();
+        let hook_id = self.hook_id;
+        if let Err(e) = insert_impl(DB_MANAGER.meta_db(), self).await {
+            warn!("Hook {}: Failed to record hook delivery: {:#?}", hook_id, e);
+        }
+    }
+
+    async fn require_access(&self, context: &ClientContext) -> RustMailerResult<()> {
+        if self.global {
+            Self::require_global_hook_access(context, self.hook_id).await
+        } else {
+            context.require_account_access(self.account_id)
+        }
+    }
+
+    /// The deliveries of a global hook are readable by the clients managing the hook, and
+    /// only by root once it is deleted.
+    async fn require_global_hook_access(
+        context: &ClientContext,
+        hook_id: u64,
+    ) -> RustMailerResult<()> {
+        match EventHooks::get_by_id(hook_id).await? {
+            Some(hook) => context.require_workspace_admin(hook.workspace_id),
+            None => context.require_root(),
+        }
+    }
+
+    pub async fn get(context: &ClientContext, id: u64) -> RustMailerResult<HookDelivery> {
+        let delivery: HookDelivery =
+            secondary_find_impl(DB_MANAGER.meta_db(), HookDeliveryKey::id, id)
+                .await?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!("Hook delivery with id={id} not found."),
+                        ErrorCode::ResourceNotFound
+                    )
+                })?;
+        delivery.require_access(context).await?;
+        Ok(delivery)
+    }
+
+    /// The deliveries of a hook, oldest first unless `desc` is set.
+    pub async fn list(
+        context: &ClientContext,
+        hook_id: u64,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<HookDelivery>> {
+        // The deliveries of a deleted hook stay readable by root until they expire.
+        match EventHooks::get_by_id(hook_id).await? {
+            Some(hook) => match hook.account_id {
+                Some(account_id) => context.require_account_access(account_id)?,
+                None => context.require_workspace_admin(hook.workspace_id)?,
+            },
+            None => context.require_root()?,
+        }
+        paginate_secondary_scan_impl(
+            DB_MANAGER.meta_db(),
+            page,
+            page_size,
+            desc,
+            HookDeliveryKey::hook_id,
+            hook_id,
+        )
+        .await
+        .map(DataPage::from)
+    }
+
+    /// Queues the event of a delivery again for delivery to its hook. The event goes through
+    /// the current configuration of the hook, whether or not it is enabled.
+    pub async fn replay(context: &ClientContext, id: u64) -> RustMailerResult<ReplayedDelivery> {
+        let delivery = Self::get(context, id).await?;
+        let hook = EventHooks::get_by_id(delivery.hook_id)
+            .await?
+            .ok_or_else(|| {
+                raise_error!(
+                    format!(
+                        "The event hook with id={} no longer exists.",
+                        delivery.hook_id
+                    ),
+                    ErrorCode::ResourceNotFound
+                )
+            })?;
+        let event = serde_json::from_str(&delivery.event)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        let task = EventHookTask {
+            event_hook_id: hook.id,
+            account_id: delivery.account_id,
+            account_email: delivery.account_email,
+            event_type: delivery.event_type,
+            event,
+            test: delivery.test,
+            replay_of: Some(delivery.id),
+        };
+        let meta = RustMailerTaskQueue::get()?.submit_task(task, None).await?;
+        Ok(ReplayedDelivery { task_id: meta.id })
+    }
+
+    /// Removes the deliveries older than `rustmailer_hook_delivery_log_retention_days`,
+    /// returning the number of deliveries removed.
+    pub async fn clean() -> RustMailerResult<usize> {
+        let cutoff =
+            utc_now!() - SETTINGS.rustmailer_hook_delivery_log_retention_days as i64 * DAY_MS;
+        batch_delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            let expired: Vec<HookDelivery> = rw
+                .scan()
+                .primary()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .range(timestamp_key_range(None, Some(cutoff)))
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(expired)
+        })
+        .await
+    }
+
+    pub fn stage_remove_account_deliveries(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let deliveries: Vec<HookDelivery> = rw
+                .scan()
+                .secondary::<HookDelivery>(HookDeliveryKey::account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(deliveries)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_response_snippets() {
+        let mut attempt = DeliveryAttempt::default();
+        attempt.set_response(502, &"é".repeat(RESPONSE_SNIPPET_LEN + 10));
+        assert_eq!(attempt.status_code, Some(502));
+        assert_eq!(
+            attempt.response.as_ref().unwrap().chars().count(),
+            RESPONSE_SNIPPET_LEN
+        );
+
+        attempt.set_response(204, "");
+        assert_eq!(attempt.status_code, Some(204));
+        assert!(attempt.response.is_none());
+    }
+
+    #[test]
+    fn starts_entries_from_hook_tasks() {
+        let task = EventHookTask {
+            event_hook_id: 3,
+            account_id: 7,
+            account_email: "user@example.com".into(),
+            event_type: EventType::EmailBounce,
+            event: serde_json::json!({"event_id": "abc"}),
+            test: false,
+            replay_of: Some(11),
+        };
+        let hook = EventHooks {
+            id: 3,
+            hook_type: HookType::Nats,
+            ..Default::default()
+        };
+        let delivery = HookDelivery::start(&task, 42, &hook);
+        assert!(delivery.global);
+        assert_eq!(delivery.task_id, 42);
+        assert_eq!(delivery.destination, HookType::Nats);
+        assert_eq!(delivery.replay_of, Some(11));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&delivery.event).unwrap(),
+            task.event
+        );
+    }
+}
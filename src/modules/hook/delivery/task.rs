@@ -0,0 +1,34 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Duration;
+
+use tracing::info;
+
+use crate::modules::{
+    context::RustMailTask, hook::delivery::HookDelivery, scheduler::periodic::PeriodicTask,
+};
+
+const TASK_INTERVAL: Duration = Duration::from_secs(60 * 60); // every hour
+
+/// Removes the hook deliveries past their retention.
+pub struct HookDeliveryCleanTask;
+
+impl RustMailTask for HookDeliveryCleanTask {
+    fn start() {
+        let periodic_task = PeriodicTask::new("hook-delivery-log-cleaner");
+
+        let task = move |_ctx: Option<u64>| {
+            Box::pin(async move {
+                let removed = HookDelivery::clean().await?;
+                if removed > 0 {
+                    info!("Removed {} expired hook deliveries", removed);
+                }
+                Ok(())
+            })
+        };
+
+        periodic_task.start(task, None, TASK_INTERVAL, false, false);
+    }
+}
@@ -2,8 +2,9 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use crate::id;
 use crate::modules::account::migration::AccountModel;
+use crate::modules::account::tags::{has_all_tags, normalize_tags};
+use crate::modules::database::batch::WriteBatch;
 use crate::modules::database::manager::DB_MANAGER;
 use crate::modules::database::{
     delete_impl, filter_by_secondary_key_impl, paginate_query_primary_scan_all_impl,
@@ -11,11 +12,21 @@ use crate::modules::database::{
 };
 use crate::modules::error::code::ErrorCode;
 use crate::modules::hook::events::EventType;
-use crate::modules::hook::nats::NatsConfig;
+use crate::modules::hook::kafka::{self, KafkaConfig};
+use crate::modules::hook::nats::{NatsConfig, NatsConfigV1};
 use crate::modules::hook::payload::apply_update;
-use crate::modules::hook::payload::{EventhookCreateRequest, EventhookUpdateRequest};
+use crate::modules::hook::payload::{
+    EventhookCreateRequest, EventhookUpdateRequest, RotateHookSecretRequest, RotatedHookSecret,
+};
+use crate::modules::hook::redis::{self, RedisConfig};
+use crate::modules::hook::signing::{
+    rotate_secrets, HookSigningSecret, DEFAULT_SECRET_OVERLAP_SECS, MAX_SECRET_OVERLAP_SECS,
+    MIN_SECRET_LEN,
+};
 use crate::modules::hook::vrl::compile_vrl_script;
 use crate::modules::rest::response::DataPage;
+use crate::modules::workspace::Workspace;
+use crate::{encrypt, generate_token, id};
 use crate::{
     modules::database::insert_impl, modules::error::RustMailerResult, raise_error, utc_now,
 };
@@ -25,7 +36,7 @@ use native_model::{native_model, Model};
 use poem_openapi::types::Type;
 use poem_openapi::{Enum, Object};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 use url::Url;
 
@@ -45,6 +56,10 @@ pub enum HookType {
     Http,
     ///using NATS messaging system for event delivery
     Nats,
+    ///using a Kafka producer for event delivery
+    Kafka,
+    ///using Redis Streams for event delivery
+    Redis,
 }
 
 impl HookType {
@@ -52,6 +67,8 @@ impl HookType {
         match self {
             HookType::Http => "http",
             HookType::Nats => "nats",
+            HookType::Kafka => "kafka",
+            HookType::Redis => "redis",
         }
     }
 }
@@ -75,10 +92,72 @@ pub struct HttpConfig {
     pub custom_headers: BTreeMap<String, String>,
 }
 
+/// A VRL script applied to the events of one type instead of the hook's `vrl_script`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct EventVrlScript {
+    /// The type of the events the script applies to.
+    pub event_type: EventType,
+    /// The VRL script. Events it turns into `null` are not delivered.
+    pub script: String,
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
 #[native_model(id = 11, version = 1)]
 #[native_db(primary_key(pk -> String))]
-pub struct EventHooks {
+pub struct EventHooksV1 {
+    /// The unique identifier of the event hook
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Unique identifier of the account associated with the hook.
+    #[secondary_key(unique, optional)]
+    pub account_id: Option<u64>,
+    /// Email address of the account associated with the hook.
+    pub email: Option<String>,
+    /// Optional description providing additional context about the hook.
+    pub description: Option<String>,
+    /// Timestamp (in milliseconds) when the hook was created.
+    pub created_at: i64,
+    /// Timestamp (in milliseconds) when the hook was last updated.
+    pub updated_at: i64,
+    /// Indicates whether the hook is global and applies to all accounts. 1: true, 0: false
+    #[secondary_key]
+    pub global: u8,
+    /// Indicates whether the hook is currently active and processing events.
+    pub enabled: bool,
+    /// The type of hook (e.g., HTTP or NATS).
+    pub hook_type: HookType,
+    /// Optional HTTP configuration for HTTP-based hook.
+    pub http: Option<HttpConfig>,
+    /// Optional NATS configuration for NATS-based hook.
+    pub nats: Option<NatsConfigV1>,
+    /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
+    pub vrl_script: Option<String>,
+    /// Total number of times the hook has been triggered.
+    pub call_count: u64,
+    /// Number of times the hook has been successfully executed.
+    pub success_count: u64,
+    /// Number of times the hook execution has failed.
+    pub failure_count: u64,
+    /// Details of the last error encountered during hook execution, if any.
+    pub last_error: Option<String>,
+    /// List of event types the hook is configured to monitor.
+    pub watched_events: Vec<EventType>,
+    /// Optional proxy ID for establishing the connection.
+    /// - If `None` or not provided, the client will connect directly to the webhook server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
+    pub use_proxy: Option<u64>,
+}
+
+impl EventHooksV1 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+#[native_model(id = 11, version = 2, from = EventHooksV1)]
+#[native_db(primary_key(pk -> String))]
+pub struct EventHooksV2 {
     /// The unique identifier of the event hook
     #[secondary_key(unique)]
     pub id: u64,
@@ -103,7 +182,183 @@ pub struct EventHooks {
     /// Optional HTTP configuration for HTTP-based hook.
     pub http: Option<HttpConfig>,
     /// Optional NATS configuration for NATS-based hook.
+    pub nats: Option<NatsConfigV1>,
+    /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
+    pub vrl_script: Option<String>,
+    /// Total number of times the hook has been triggered.
+    pub call_count: u64,
+    /// Number of times the hook has been successfully executed.
+    pub success_count: u64,
+    /// Number of times the hook execution has failed.
+    pub failure_count: u64,
+    /// Details of the last error encountered during hook execution, if any.
+    pub last_error: Option<String>,
+    /// List of event types the hook is configured to monitor.
+    pub watched_events: Vec<EventType>,
+    /// Optional proxy ID for establishing the connection.
+    /// - If `None` or not provided, the client will connect directly to the webhook server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
+    pub use_proxy: Option<u64>,
+    /// Only for global hooks: limits the hook to accounts carrying all of these tags.
+    /// If `None`, the hook applies to all accounts.
+    pub account_tags: Option<Vec<String>>,
+}
+
+impl EventHooksV2 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+#[native_model(id = 11, version = 3, from = EventHooksV2)]
+#[native_db(primary_key(pk -> String))]
+pub struct EventHooksV3 {
+    /// The unique identifier of the event hook
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Unique identifier of the account associated with the hook.
+    #[secondary_key(unique, optional)]
+    pub account_id: Option<u64>,
+    /// Email address of the account associated with the hook.
+    pub email: Option<String>,
+    /// Optional description providing additional context about the hook.
+    pub description: Option<String>,
+    /// Timestamp (in milliseconds) when the hook was created.
+    pub created_at: i64,
+    /// Timestamp (in milliseconds) when the hook was last updated.
+    pub updated_at: i64,
+    /// Indicates whether the hook is global and applies to all accounts. 1: true, 0: false
+    #[secondary_key]
+    pub global: u8,
+    /// Indicates whether the hook is currently active and processing events.
+    pub enabled: bool,
+    /// The type of hook (e.g., HTTP or NATS).
+    pub hook_type: HookType,
+    /// Optional HTTP configuration for HTTP-based hook.
+    pub http: Option<HttpConfig>,
+    /// Optional NATS configuration for NATS-based hook.
+    pub nats: Option<NatsConfigV1>,
+    /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
+    pub vrl_script: Option<String>,
+    /// Total number of times the hook has been triggered.
+    pub call_count: u64,
+    /// Number of times the hook has been successfully executed.
+    pub success_count: u64,
+    /// Number of times the hook execution has failed.
+    pub failure_count: u64,
+    /// Details of the last error encountered during hook execution, if any.
+    pub last_error: Option<String>,
+    /// List of event types the hook is configured to monitor.
+    pub watched_events: Vec<EventType>,
+    /// Optional proxy ID for establishing the connection.
+    /// - If `None` or not provided, the client will connect directly to the webhook server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
+    pub use_proxy: Option<u64>,
+    /// Only for global hooks: limits the hook to accounts carrying all of these tags.
+    /// If `None`, the hook applies to all accounts.
+    pub account_tags: Option<Vec<String>>,
+    /// Secrets used to sign the payloads delivered by the hook, current one first.
+    /// A rotated-out secret is kept until its overlap period ends.
+    pub signing_secrets: Vec<HookSigningSecret>,
+}
+
+impl EventHooksV3 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+#[native_model(id = 11, version = 4, from = EventHooksV3)]
+#[native_db(primary_key(pk -> String))]
+pub struct EventHooksV4 {
+    /// The unique identifier of the event hook
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Unique identifier of the account associated with the hook.
+    #[secondary_key(unique, optional)]
+    pub account_id: Option<u64>,
+    /// Email address of the account associated with the hook.
+    pub email: Option<String>,
+    /// Optional description providing additional context about the hook.
+    pub description: Option<String>,
+    /// Timestamp (in milliseconds) when the hook was created.
+    pub created_at: i64,
+    /// Timestamp (in milliseconds) when the hook was last updated.
+    pub updated_at: i64,
+    /// Indicates whether the hook is global and applies to all accounts. 1: true, 0: false
+    #[secondary_key]
+    pub global: u8,
+    /// Indicates whether the hook is currently active and processing events.
+    pub enabled: bool,
+    /// The type of hook (e.g., HTTP or NATS).
+    pub hook_type: HookType,
+    /// Optional HTTP configuration for HTTP-based hook.
+    pub http: Option<HttpConfig>,
+    /// Optional NATS configuration for NATS-based hook.
+    pub nats: Option<NatsConfig>,
+    /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
+    pub vrl_script: Option<String>,
+    /// Total number of times the hook has been triggered.
+    pub call_count: u64,
+    /// Number of times the hook has been successfully executed.
+    pub success_count: u64,
+    /// Number of times the hook execution has failed.
+    pub failure_count: u64,
+    /// Details of the last error encountered during hook execution, if any.
+    pub last_error: Option<String>,
+    /// List of event types the hook is configured to monitor.
+    pub watched_events: Vec<EventType>,
+    /// Optional proxy ID for establishing the connection.
+    /// - If `None` or not provided, the client will connect directly to the webhook server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
+    pub use_proxy: Option<u64>,
+    /// Only for global hooks: limits the hook to accounts carrying all of these tags.
+    /// If `None`, the hook applies to all accounts.
+    pub account_tags: Option<Vec<String>>,
+    /// Secrets used to sign the payloads delivered by the hook, current one first.
+    /// A rotated-out secret is kept until its overlap period ends.
+    pub signing_secrets: Vec<HookSigningSecret>,
+}
+
+impl EventHooksV4 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+#[native_model(id = 11, version = 5, from = EventHooksV4)]
+#[native_db(primary_key(pk -> String))]
+pub struct EventHooksV5 {
+    /// The unique identifier of the event hook
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Unique identifier of the account associated with the hook.
+    #[secondary_key(unique, optional)]
+    pub account_id: Option<u64>,
+    /// Email address of the account associated with the hook.
+    pub email: Option<String>,
+    /// Optional description providing additional context about the hook.
+    pub description: Option<String>,
+    /// Timestamp (in milliseconds) when the hook was created.
+    pub created_at: i64,
+    /// Timestamp (in milliseconds) when the hook was last updated.
+    pub updated_at: i64,
+    /// Indicates whether the hook is global and applies to all accounts. 1: true, 0: false
+    #[secondary_key]
+    pub global: u8,
+    /// Indicates whether the hook is currently active and processing events.
+    pub enabled: bool,
+    /// The type of hook (e.g., HTTP, NATS or Kafka).
+    pub hook_type: HookType,
+    /// Optional HTTP configuration for HTTP-based hook.
+    pub http: Option<HttpConfig>,
+    /// Optional NATS configuration for NATS-based hook.
     pub nats: Option<NatsConfig>,
+    /// Optional Kafka configuration for Kafka-based hook.
+    pub kafka: Option<KafkaConfig>,
     /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
     pub vrl_script: Option<String>,
     /// Total number of times the hook has been triggered.
@@ -120,6 +375,208 @@ pub struct EventHooks {
     /// - If `None` or not provided, the client will connect directly to the webhook server.
     /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
     pub use_proxy: Option<u64>,
+    /// Only for global hooks: limits the hook to accounts carrying all of these tags.
+    /// If `None`, the hook applies to all accounts.
+    pub account_tags: Option<Vec<String>>,
+    /// Secrets used to sign the payloads delivered by the hook, current one first.
+    /// A rotated-out secret is kept until its overlap period ends.
+    pub signing_secrets: Vec<HookSigningSecret>,
+}
+
+impl EventHooksV5 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+#[native_model(id = 11, version = 6, from = EventHooksV5)]
+#[native_db(primary_key(pk -> String))]
+pub struct EventHooksV6 {
+    /// The unique identifier of the event hook
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Unique identifier of the account associated with the hook.
+    #[secondary_key(unique, optional)]
+    pub account_id: Option<u64>,
+    /// Email address of the account associated with the hook.
+    pub email: Option<String>,
+    /// Optional description providing additional context about the hook.
+    pub description: Option<String>,
+    /// Timestamp (in milliseconds) when the hook was created.
+    pub created_at: i64,
+    /// Timestamp (in milliseconds) when the hook was last updated.
+    pub updated_at: i64,
+    /// Indicates whether the hook is global and applies to all accounts. 1: true, 0: false
+    #[secondary_key]
+    pub global: u8,
+    /// Indicates whether the hook is currently active and processing events.
+    pub enabled: bool,
+    /// The type of hook (e.g., HTTP, NATS, Kafka or Redis Streams).
+    pub hook_type: HookType,
+    /// Optional HTTP configuration for HTTP-based hook.
+    pub http: Option<HttpConfig>,
+    /// Optional NATS configuration for NATS-based hook.
+    pub nats: Option<NatsConfig>,
+    /// Optional Kafka configuration for Kafka-based hook.
+    pub kafka: Option<KafkaConfig>,
+    /// Optional Redis configuration for Redis Streams-based hook.
+    pub redis: Option<RedisConfig>,
+    /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
+    pub vrl_script: Option<String>,
+    /// Total number of times the hook has been triggered.
+    pub call_count: u64,
+    /// Number of times the hook has been successfully executed.
+    pub success_count: u64,
+    /// Number of times the hook execution has failed.
+    pub failure_count: u64,
+    /// Details of the last error encountered during hook execution, if any.
+    pub last_error: Option<String>,
+    /// List of event types the hook is configured to monitor.
+    pub watched_events: Vec<EventType>,
+    /// Optional proxy ID for establishing the connection.
+    /// - If `None` or not provided, the client will connect directly to the webhook server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
+    pub use_proxy: Option<u64>,
+    /// Only for global hooks: limits the hook to accounts carrying all of these tags.
+    /// If `None`, the hook applies to all accounts.
+    pub account_tags: Option<Vec<String>>,
+    /// Secrets used to sign the payloads delivered by the hook, current one first.
+    /// A rotated-out secret is kept until its overlap period ends.
+    pub signing_secrets: Vec<HookSigningSecret>,
+}
+
+impl EventHooksV6 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+#[native_model(id = 11, version = 7, from = EventHooksV6)]
+#[native_db(primary_key(pk -> String))]
+pub struct EventHooksV7 {
+    /// The unique identifier of the event hook
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Unique identifier of the account associated with the hook.
+    #[secondary_key(unique, optional)]
+    pub account_id: Option<u64>,
+    /// Email address of the account associated with the hook.
+    pub email: Option<String>,
+    /// Optional description providing additional context about the hook.
+    pub description: Option<String>,
+    /// Timestamp (in milliseconds) when the hook was created.
+    pub created_at: i64,
+    /// Timestamp (in milliseconds) when the hook was last updated.
+    pub updated_at: i64,
+    /// Indicates whether the hook is global and applies to all accounts. 1: true, 0: false
+    #[secondary_key]
+    pub global: u8,
+    /// Indicates whether the hook is currently active and processing events.
+    pub enabled: bool,
+    /// The type of hook (e.g., HTTP, NATS, Kafka or Redis Streams).
+    pub hook_type: HookType,
+    /// Optional HTTP configuration for HTTP-based hook.
+    pub http: Option<HttpConfig>,
+    /// Optional NATS configuration for NATS-based hook.
+    pub nats: Option<NatsConfig>,
+    /// Optional Kafka configuration for Kafka-based hook.
+    pub kafka: Option<KafkaConfig>,
+    /// Optional Redis configuration for Redis Streams-based hook.
+    pub redis: Option<RedisConfig>,
+    /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
+    pub vrl_script: Option<String>,
+    /// VRL scripts applied to the events of their type instead of `vrl_script`.
+    pub event_vrl_scripts: Vec<EventVrlScript>,
+    /// Total number of times the hook has been triggered.
+    pub call_count: u64,
+    /// Number of times the hook has been successfully executed.
+    pub success_count: u64,
+    /// Number of times the hook execution has failed.
+    pub failure_count: u64,
+    /// Details of the last error encountered during hook execution, if any.
+    pub last_error: Option<String>,
+    /// List of event types the hook is configured to monitor.
+    pub watched_events: Vec<EventType>,
+    /// Optional proxy ID for establishing the connection.
+    /// - If `None` or not provided, the client will connect directly to the webhook server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
+    pub use_proxy: Option<u64>,
+    /// Only for global hooks: limits the hook to accounts carrying all of these tags.
+    /// If `None`, the hook applies to all accounts.
+    pub account_tags: Option<Vec<String>>,
+    /// Secrets used to sign the payloads delivered by the hook, current one first.
+    /// A rotated-out secret is kept until its overlap period ends.
+    pub signing_secrets: Vec<HookSigningSecret>,
+}
+
+impl EventHooksV7 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+#[native_model(id = 11, version = 8, from = EventHooksV7)]
+#[native_db(primary_key(pk -> String))]
+pub struct EventHooks {
+    /// The unique identifier of the event hook
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Unique identifier of the account associated with the hook.
+    #[secondary_key(unique, optional)]
+    pub account_id: Option<u64>,
+    /// Email address of the account associated with the hook.
+    pub email: Option<String>,
+    /// Optional description providing additional context about the hook.
+    pub description: Option<String>,
+    /// Timestamp (in milliseconds) when the hook was created.
+    pub created_at: i64,
+    /// Timestamp (in milliseconds) when the hook was last updated.
+    pub updated_at: i64,
+    /// Indicates whether the hook is global and applies to all accounts. 1: true, 0: false
+    #[secondary_key]
+    pub global: u8,
+    /// Indicates whether the hook is currently active and processing events.
+    pub enabled: bool,
+    /// The type of hook (e.g., HTTP, NATS, Kafka or Redis Streams).
+    pub hook_type: HookType,
+    /// Optional HTTP configuration for HTTP-based hook.
+    pub http: Option<HttpConfig>,
+    /// Optional NATS configuration for NATS-based hook.
+    pub nats: Option<NatsConfig>,
+    /// Optional Kafka configuration for Kafka-based hook.
+    pub kafka: Option<KafkaConfig>,
+    /// Optional Redis configuration for Redis Streams-based hook.
+    pub redis: Option<RedisConfig>,
+    /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
+    pub vrl_script: Option<String>,
+    /// VRL scripts applied to the events of their type instead of `vrl_script`.
+    pub event_vrl_scripts: Vec<EventVrlScript>,
+    /// Total number of times the hook has been triggered.
+    pub call_count: u64,
+    /// Number of times the hook has been successfully executed.
+    pub success_count: u64,
+    /// Number of times the hook execution has failed.
+    pub failure_count: u64,
+    /// Details of the last error encountered during hook execution, if any.
+    pub last_error: Option<String>,
+    /// List of event types the hook is configured to monitor.
+    pub watched_events: Vec<EventType>,
+    /// Optional proxy ID for establishing the connection.
+    /// - If `None` or not provided, the client will connect directly to the webhook server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
+    pub use_proxy: Option<u64>,
+    /// Only for global hooks: limits the hook to accounts carrying all of these tags.
+    /// If `None`, the hook applies to all accounts.
+    pub account_tags: Option<Vec<String>>,
+    /// Only for global hooks: the workspace whose accounts the hook applies to.
+    /// If `None`, the hook applies to the accounts of the whole instance.
+    pub workspace_id: Option<u64>,
+    /// Secrets used to sign the payloads delivered by the hook, current one first.
+    /// A rotated-out secret is kept until its overlap period ends.
+    pub signing_secrets: Vec<HookSigningSecret>,
 }
 
 impl EventHooks {
@@ -133,6 +590,24 @@ impl EventHooks {
         } else {
             (None, 1)
         };
+        let signing_secrets = match request.signing_secret {
+            Some(secret) if secret.len() < MIN_SECRET_LEN => {
+                return Err(raise_error!(
+                    format!(
+                        "'signing_secret' must be at least {} characters long",
+                        MIN_SECRET_LEN
+                    ),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+            Some(secret) => vec![HookSigningSecret {
+                key_id: generate_token!(64),
+                secret: encrypt!(&secret)?,
+                created_at: utc_now!(),
+                expires_at: None,
+            }],
+            None => Vec::new(),
+        };
         Ok(Self {
             id: id!(64),
             account_id: request.account_id,
@@ -145,13 +620,23 @@ impl EventHooks {
             hook_type: request.hook_type,
             http: request.http,
             nats: request.nats,
+            kafka: request.kafka,
+            redis: request.redis,
             vrl_script: request.vrl_script,
+            event_vrl_scripts: request.event_vrl_scripts.unwrap_or_default(),
             call_count: 0,
             success_count: 0,
             failure_count: 0,
             last_error: None,
             watched_events: request.watched_events,
             use_proxy: request.use_proxy,
+            account_tags: request
+                .account_tags
+                .map(normalize_tags)
+                .transpose()?
+                .filter(|tags| !tags.is_empty()),
+            workspace_id: request.workspace_id,
+            signing_secrets,
         })
     }
 
@@ -171,6 +656,16 @@ impl EventHooks {
         insert_impl(DB_MANAGER.meta_db(), self).await
     }
 
+    /// The VRL script applied to events of the given type: the script of the type if the
+    /// hook has one, otherwise `vrl_script`.
+    pub fn vrl_script_for(&self, event_type: &EventType) -> Option<&str> {
+        self.event_vrl_scripts
+            .iter()
+            .find(|s| &s.event_type == event_type)
+            .map(|s| s.script.as_str())
+            .or(self.vrl_script.as_deref())
+    }
+
     /// Get a specific Webhook entity by its ID
     pub async fn get_by_id(id: u64) -> RustMailerResult<Option<EventHooks>> {
         secondary_find_impl(DB_MANAGER.meta_db(), EventHooksKey::id, id).await
@@ -185,6 +680,18 @@ impl EventHooks {
         .await
     }
 
+    /// Whether a global hook applies to an account with the given tags.
+    pub fn matches_account_tags(&self, tags: &[String]) -> bool {
+        self.account_tags
+            .as_ref()
+            .is_none_or(|selector| has_all_tags(tags, selector))
+    }
+
+    /// Whether a global hook applies to an account of the workspace `workspace_id`.
+    pub fn matches_workspace(&self, workspace_id: Option<u64>) -> bool {
+        self.workspace_id.is_none() || self.workspace_id == workspace_id
+    }
+
     pub async fn global_hooks() -> RustMailerResult<Vec<EventHooks>> {
         filter_by_secondary_key_impl(DB_MANAGER.meta_db(), EventHooksKey::global, 1u8).await
     }
@@ -207,28 +714,30 @@ impl EventHooks {
         .await
     }
 
-    pub async fn try_delete(account_id: u64) -> RustMailerResult<()> {
-        if Self::get_by_account_id(account_id).await?.is_none() {
-            return Ok(());
-        }
-        delete_impl(DB_MANAGER.meta_db(), move |rw| {
-            rw.get()
+    /// Adds the removal of the event hook of an account, if any, to `batch`.
+    pub fn stage_try_delete(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let hook = rw
+                .get()
                 .secondary::<EventHooks>(EventHooksKey::account_id, Some(account_id))
-                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-                .ok_or_else(|| {
-                    raise_error!(
-                        format!(
-                            "The event hook with id={account_id} that you want to delete was not found."
-                        ),
-                        ErrorCode::ResourceNotFound
-                    )
-                })
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(hook.into_iter().collect())
         })
-        .await?;
-        Ok(())
     }
 
-    pub async fn update(id: u64, request: EventhookUpdateRequest) -> RustMailerResult<()> {
+    pub async fn update(id: u64, mut request: EventhookUpdateRequest) -> RustMailerResult<()> {
+        request.account_tags = request.account_tags.map(normalize_tags).transpose()?;
+        if let Some(nats) = &request.nats {
+            nats.validate()?;
+        }
+        if let Some(kafka) = &request.kafka {
+            kafka::ensure_supported()?;
+            kafka.validate()?;
+        }
+        if let Some(redis) = &request.redis {
+            redis::ensure_supported()?;
+            redis.validate()?;
+        }
         update_impl(
             DB_MANAGER.meta_db(),
             move |rw| {
@@ -237,12 +746,81 @@ impl EventHooks {
                     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
                     .ok_or_else(|| {raise_error!(format!("The event hook entity with id={} that you want to modify was not found.",id), ErrorCode::ResourceNotFound)})
             },
-            |current| Ok(apply_update(current, request)),
+            |current| {
+                if current.account_id.is_some() && request.account_tags.is_some() {
+                    return Err(raise_error!(
+                        "'account_tags' can only be set on global event hooks".into(),
+                        ErrorCode::InvalidParameter
+                    ));
+                }
+                Ok(apply_update(current, request))
+            },
         )
         .await?;
         Ok(())
     }
 
+    /// Replaces the signing secret of the hook with a new one, keeping the current secret
+    /// valid for the requested overlap.
+    pub async fn rotate_secret(
+        id: u64,
+        request: RotateHookSecretRequest,
+    ) -> RustMailerResult<RotatedHookSecret> {
+        let overlap_seconds = request
+            .overlap_seconds
+            .unwrap_or(DEFAULT_SECRET_OVERLAP_SECS);
+        if overlap_seconds > MAX_SECRET_OVERLAP_SECS {
+            return Err(raise_error!(
+                format!(
+                    "'overlap_seconds' must not exceed {} seconds",
+                    MAX_SECRET_OVERLAP_SECS
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let overlap_ms = overlap_seconds as i64 * 1000;
+        let now = utc_now!();
+        let secret = format!("whsec_{}", generate_token!(256));
+        let new = HookSigningSecret {
+            key_id: generate_token!(64),
+            secret: encrypt!(&secret)?,
+            created_at: now,
+            expires_at: None,
+        };
+        let key_id = new.key_id.clone();
+
+        let previous = update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .secondary::<EventHooks>(EventHooksKey::id, id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {raise_error!(format!("The event hook entity with id={} that you want to modify was not found.",id), ErrorCode::ResourceNotFound)})
+            },
+            move |current| {
+                let mut updated = current.clone();
+                updated.signing_secrets =
+                    rotate_secrets(&current.signing_secrets, new, overlap_ms, now);
+                updated.updated_at = now;
+                Ok(updated)
+            },
+        )
+        .await?;
+
+        let previous_key_id = previous
+            .signing_secrets
+            .into_iter()
+            .find(|s| s.expires_at.is_none())
+            .filter(|_| overlap_ms > 0)
+            .map(|s| s.key_id);
+        Ok(RotatedHookSecret {
+            key_id,
+            secret,
+            previous_expires_at: previous_key_id.as_ref().map(|_| now + overlap_ms),
+            previous_key_id,
+        })
+    }
+
     pub async fn internal_update(
         id: u64,
         request: InternalEventHookUpdateRequest,
@@ -262,6 +840,7 @@ impl EventHooks {
     }
 
     async fn validate(&self) -> RustMailerResult<()> {
+        Workspace::check_exists(self.workspace_id).await?;
         if let Some(account_id) = self.account_id {
             if AccountModel::get(account_id).await?.is_none() {
                 return Err(raise_error!(
@@ -270,6 +849,20 @@ impl EventHooks {
                 ));
             }
 
+            if self.account_tags.is_some() {
+                return Err(raise_error!(
+                    "'account_tags' can only be set on global event hooks".into(),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+
+            if self.workspace_id.is_some() {
+                return Err(raise_error!(
+                    "'workspace_id' can only be set on global event hooks: the hook of an account belongs to the workspace of the account".into(),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+
             if Self::get_by_account_id(account_id).await?.is_some() {
                 return Err(raise_error!(
                     "Account already has an EventHook".into(),
@@ -295,11 +888,38 @@ impl EventHooks {
                     ));
                 }
             }
+            HookType::Kafka => {
+                if self.kafka.is_none() {
+                    return Err(raise_error!(
+                        "when event hook type is `Kafka`, field `kafka` must be configured".into(),
+                        ErrorCode::InvalidParameter
+                    ));
+                }
+            }
+            HookType::Redis => {
+                if self.redis.is_none() {
+                    return Err(raise_error!(
+                        "when event hook type is `Redis`, field `redis` must be configured".into(),
+                        ErrorCode::InvalidParameter
+                    ));
+                }
+            }
         }
 
-        if self.http.is_some() && self.nats.is_some() {
+        let destinations = [
+            self.http.is_some(),
+            self.nats.is_some(),
+            self.kafka.is_some(),
+            self.redis.is_some(),
+        ];
+        if destinations
+            .into_iter()
+            .filter(|configured| *configured)
+            .count()
+            > 1
+        {
             return Err(raise_error!(
-                "Do not configure both http and nats".into(),
+                "Configure only one of http, nats, kafka and redis".into(),
                 ErrorCode::InvalidParameter
             ));
         }
@@ -333,6 +953,16 @@ impl EventHooks {
             nats.validate()?;
         }
 
+        if let Some(kafka) = &self.kafka {
+            kafka::ensure_supported()?;
+            kafka.validate()?;
+        }
+
+        if let Some(redis) = &self.redis {
+            redis::ensure_supported()?;
+            redis.validate()?;
+        }
+
         if self.watched_events.is_empty() {
             return Err(raise_error!(
                 "Please select at least one event to watch".into(),
@@ -343,6 +973,407 @@ impl EventHooks {
         if let Some(vrl_script) = &self.vrl_script {
             compile_vrl_script(vrl_script)?;
         }
+
+        let mut scripted = HashSet::new();
+        for script in &self.event_vrl_scripts {
+            if !scripted.insert(&script.event_type) {
+                return Err(raise_error!(
+                    format!("Duplicate VRL script for event type {}", script.event_type),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+            compile_vrl_script(&script.script)?;
+        }
         Ok(())
     }
 }
+
+impl From<EventHooksV1> for EventHooksV2 {
+    fn from(value: EventHooksV1) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: None,
+        }
+    }
+}
+
+impl From<EventHooksV2> for EventHooksV1 {
+    fn from(value: EventHooksV2) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+        }
+    }
+}
+
+impl From<EventHooksV2> for EventHooksV3 {
+    fn from(value: EventHooksV2) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: Vec::new(),
+        }
+    }
+}
+
+impl From<EventHooksV3> for EventHooksV2 {
+    fn from(value: EventHooksV3) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+        }
+    }
+}
+
+impl From<EventHooksV3> for EventHooksV4 {
+    fn from(value: EventHooksV3) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats.map(Into::into),
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
+
+impl From<EventHooksV4> for EventHooksV3 {
+    fn from(value: EventHooksV4) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats.map(Into::into),
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
+
+impl From<EventHooksV4> for EventHooksV5 {
+    fn from(value: EventHooksV4) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            kafka: None,
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
+
+impl From<EventHooksV5> for EventHooksV4 {
+    fn from(value: EventHooksV5) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
+
+impl From<EventHooksV5> for EventHooksV6 {
+    fn from(value: EventHooksV5) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            kafka: value.kafka,
+            redis: None,
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
+
+impl From<EventHooksV6> for EventHooksV5 {
+    fn from(value: EventHooksV6) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            kafka: value.kafka,
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
+
+impl From<EventHooksV6> for EventHooksV7 {
+    fn from(value: EventHooksV6) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            kafka: value.kafka,
+            redis: value.redis,
+            vrl_script: value.vrl_script,
+            event_vrl_scripts: Vec::new(),
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
+
+impl From<EventHooksV7> for EventHooksV6 {
+    fn from(value: EventHooksV7) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            kafka: value.kafka,
+            redis: value.redis,
+            vrl_script: value.vrl_script,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
+
+impl From<EventHooksV7> for EventHooks {
+    fn from(value: EventHooksV7) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            kafka: value.kafka,
+            redis: value.redis,
+            vrl_script: value.vrl_script,
+            event_vrl_scripts: value.event_vrl_scripts,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            workspace_id: None,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
+
+impl From<EventHooks> for EventHooksV7 {
+    fn from(value: EventHooks) -> Self {
+        Self {
+            id: value.id,
+            account_id: value.account_id,
+            email: value.email,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            global: value.global,
+            enabled: value.enabled,
+            hook_type: value.hook_type,
+            http: value.http,
+            nats: value.nats,
+            kafka: value.kafka,
+            redis: value.redis,
+            vrl_script: value.vrl_script,
+            event_vrl_scripts: value.event_vrl_scripts,
+            call_count: value.call_count,
+            success_count: value.success_count,
+            failure_count: value.failure_count,
+            last_error: value.last_error,
+            watched_events: value.watched_events,
+            use_proxy: value.use_proxy,
+            account_tags: value.account_tags,
+            signing_secrets: value.signing_secrets,
+        }
+    }
+}
@@ -15,11 +15,25 @@ use serde::{Deserialize, Serialize};
 use crate::{
     generate_token, id,
     modules::{
-        bounce::parser::{DeliveryStatus, FeedbackReport, RawEmailHeaders},
+        bounce::{
+            classify::BounceCategory,
+            parser::{DeliveryStatus, FeedbackReport, RawEmailHeaders},
+        },
         cache::imap::mailbox::{EmailFlag, EnvelopeFlag},
         common::Addr,
-        error::{code::ErrorCode, RustMailerResult},
-        hook::events::payload::{EmailLinkClicked, EmailOpened},
+        envelope::{
+            authentication::{
+                AuthVerdict, AuthenticationResults, DkimResult, DmarcResult, SpfResult,
+            },
+            received::{ReceivedChain, ReceivedHop},
+        },
+        error::{code::ErrorCode, provider::ProviderErrorKind, RustMailerResult},
+        hook::events::payload::{
+            AccessTokenCreated, AccessTokenExpiring, AccessTokenRotated,
+            AccountAuthenticationFailed, AccountAutoPaused, AccountSyncError,
+            AttachmentPolicyTriggered, DangerousAttachment, EmailLinkClicked, EmailLoopDetected,
+            EmailOpened,
+        },
         message::content::{FullMessageContent, PlainText},
         settings::cli::SETTINGS,
     },
@@ -33,8 +47,16 @@ mod tests;
 pub static EVENT_EXAMPLES: LazyLock<serde_json::Value> =
     LazyLock::new(RustMailerEvent::generate_event_examples);
 
+/// Version of the event schema: the envelope and the payloads of all event types.
+///
+/// Bumped whenever a release changes events in a way that may break consumers, so they can
+/// tell which parser to use. Also sent in the `X-RustMailer-Schema-Version` header.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct RustMailerEvent {
+    /// Version of the event schema, see [`EVENT_SCHEMA_VERSION`].
+    pub schema_version: u32,
     /// Unique identifier for the event.
     pub event_id: u64,
     /// Type of event that triggered the webhook (e.g., email added, sent, or bounced).
@@ -45,16 +67,22 @@ pub struct RustMailerEvent {
     pub timestamp: i64,
     /// Payload containing detailed data associated with the event.
     pub payload: EventPayload,
+    /// Whether the event was simulated for integration testing rather than caused by mail.
+    /// Only serialized when set.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub test: bool,
 }
 
 impl RustMailerEvent {
     pub fn new(event_type: EventType, payload: EventPayload) -> Self {
         Self {
+            schema_version: EVENT_SCHEMA_VERSION,
             event_id: id!(96),
             event_type,
             instance_url: SETTINGS.rustmailer_public_url.clone(),
             timestamp: utc_now!(),
             payload,
+            test: false,
         }
     }
 
@@ -91,6 +119,22 @@ pub enum EventType {
     EmailOpened,
     /// Event triggered when a link in an email is clicked by the recipient.
     EmailLinkClicked,
+    /// Event triggered when an outgoing email is detected as a potential mail loop, whether it was blocked or only flagged.
+    EmailLoopDetected,
+    /// Event triggered when an account's sync is paused automatically because it kept failing authentication or was no longer used.
+    AccountAutoPaused,
+    /// Event triggered when an incoming or outgoing email carries an attachment type blocked by the account's attachment policy.
+    AttachmentPolicyTriggered,
+    /// Event triggered when an account's sync or connection fails, with the recognized provider failure and its remediation, if any.
+    AccountSyncError,
+    /// Event triggered when an account's OAuth2 access token repeatedly fails to refresh, e.g. because the refresh token was revoked, and the account must be authorized again.
+    AccountAuthenticationFailed,
+    /// Event triggered when an API access token is created. Instance-wide, so only delivered to global hooks.
+    AccessTokenCreated,
+    /// Event triggered when an API access token is rotated, replaced by a new token while it stays valid for a grace period. Only delivered to global hooks.
+    AccessTokenRotated,
+    /// Event triggered once when an API access token is about to expire. Only delivered to global hooks.
+    AccessTokenExpiring,
 }
 
 impl fmt::Display for EventType {
@@ -108,6 +152,14 @@ impl fmt::Display for EventType {
             EventType::EmailFeedBackReport => write!(f, "EmailFeedBackReport"),
             EventType::EmailOpened => write!(f, "EmailOpened"),
             EventType::EmailLinkClicked => write!(f, "EmailLinkClicked"),
+            EventType::EmailLoopDetected => write!(f, "EmailLoopDetected"),
+            EventType::AccountAutoPaused => write!(f, "AccountAutoPaused"),
+            EventType::AttachmentPolicyTriggered => write!(f, "AttachmentPolicyTriggered"),
+            EventType::AccountSyncError => write!(f, "AccountSyncError"),
+            EventType::AccountAuthenticationFailed => write!(f, "AccountAuthenticationFailed"),
+            EventType::AccessTokenCreated => write!(f, "AccessTokenCreated"),
+            EventType::AccessTokenRotated => write!(f, "AccessTokenRotated"),
+            EventType::AccessTokenExpiring => write!(f, "AccessTokenExpiring"),
         }
     }
 }
@@ -127,6 +179,14 @@ pub enum EventPayload {
     EmailFeedBackReport(EmailFeedBackReport),
     EmailOpened(EmailOpened),
     EmailLinkClicked(EmailLinkClicked),
+    EmailLoopDetected(EmailLoopDetected),
+    AccountAutoPaused(AccountAutoPaused),
+    AttachmentPolicyTriggered(AttachmentPolicyTriggered),
+    AccountSyncError(AccountSyncError),
+    AccountAuthenticationFailed(AccountAuthenticationFailed),
+    AccessTokenCreated(AccessTokenCreated),
+    AccessTokenRotated(AccessTokenRotated),
+    AccessTokenExpiring(AccessTokenExpiring),
 }
 
 impl RustMailerEvent {
@@ -148,11 +208,13 @@ impl RustMailerEvent {
                 map.insert(
                     EventType::$variant,
                     RustMailerEvent {
+                        schema_version: EVENT_SCHEMA_VERSION,
                         event_id: id!(96),
                         event_type: EventType::$variant,
                         instance_url: instance_url.clone(),
                         timestamp,
                         payload: EventPayload::$variant($payload),
+                        test: false,
                     },
                 );
             };
@@ -182,13 +244,52 @@ impl RustMailerEvent {
                         truncated: false,
                     }),
                     html: Some(String::from("<p>Welcome to use rustmailer!</p>")),
-                    attachments: None
+                    attachments: None,
+                    ..Default::default()
                 },
                 thread_name: Some("Meeting Thread".into()),
                 thread_id: id!(64),
                 reply_to: Some(vec![addr("reply@example.com")]),
                 to: Some(vec![addr("recipient@example.com")]),
-                labels: vec![]
+                labels: vec![],
+                received_chain: Some(ReceivedChain {
+                    hops: vec![
+                        ReceivedHop {
+                            from: Some("client.example.net".into()),
+                            from_ip: Some("198.51.100.7".into()),
+                            by: Some("mx.example.org".into()),
+                            with: Some("ESMTPSA".into()),
+                            date: Some(timestamp - 2000),
+                        },
+                        ReceivedHop {
+                            from: Some("mx.example.org".into()),
+                            from_ip: Some("203.0.113.9".into()),
+                            by: Some("mail.server.com".into()),
+                            with: Some("ESMTPS".into()),
+                            date: Some(timestamp),
+                        },
+                    ],
+                    originating_ip: Some("198.51.100.7".into()),
+                    originating_host: Some("client.example.net".into()),
+                    transit_time_ms: Some(2000),
+                }),
+                authentication_results: Some(AuthenticationResults {
+                    authserv_id: Some("mail.server.com".into()),
+                    spf: Some(SpfResult {
+                        result: AuthVerdict::Pass,
+                        domain: Some("example.com".into()),
+                    }),
+                    dkim: vec![DkimResult {
+                        result: AuthVerdict::Pass,
+                        domain: Some("example.com".into()),
+                        selector: Some("selector1".into()),
+                    }],
+                    dmarc: Some(DmarcResult {
+                        result: AuthVerdict::Pass,
+                        domain: Some("example.com".into()),
+                        policy: Some("reject".into()),
+                    }),
+                })
             }
         );
 
@@ -310,7 +411,10 @@ impl RustMailerEvent {
                     arrival_date: Some("2023-05-15T14:32:18Z".into()),
                     original_message_id: Some("<123456789@example.org>".into()),
                     postfix_sender: Some("sender@example.org".into()),
-                })
+                    original_envelope_id: Some("env-20231011-0001".into()),
+                }),
+                bounce_category: Some(BounceCategory::HardBounce),
+                task_id: Some(id!(64)),
             }
         );
 
@@ -383,6 +487,113 @@ impl RustMailerEvent {
             }
         );
 
+        insert_event!(
+            EmailLoopDetected,
+            EmailLoopDetected {
+                account_id: id!(64),
+                account_email: account_email.clone(),
+                from: account_email.clone(),
+                to: vec!["alias@example.com".into()],
+                subject: Some("Automatic reply: Meeting Notes".into()),
+                message_id: "<msg404@server.com>".into(),
+                reason: "Recipient 'alias@example.com' is an account managed by this instance"
+                    .into(),
+                blocked: true,
+            }
+        );
+
+        insert_event!(
+            AccountAutoPaused,
+            AccountAutoPaused {
+                account_id: id!(64),
+                account_email: account_email.clone(),
+                reason: "AuthFailure".into(),
+                inactive_since: timestamp - 7 * 24 * 60 * 60 * 1000,
+            }
+        );
+
+        insert_event!(
+            AttachmentPolicyTriggered,
+            AttachmentPolicyTriggered {
+                account_id: id!(64),
+                account_email: account_email.clone(),
+                direction: "Inbound".into(),
+                action: "Quarantine".into(),
+                mailbox_name: Some("INBOX".into()),
+                uid: Some(4521),
+                message_id: Some("<msg505@server.com>".into()),
+                subject: Some("Invoice #2025-118".into()),
+                attachments: vec![DangerousAttachment {
+                    filename: Some("invoice.pdf.html".into()),
+                    content_type: "text/html".into(),
+                    category: "Html".into(),
+                }],
+            }
+        );
+
+        insert_event!(
+            AccountSyncError,
+            AccountSyncError {
+                account_id: id!(64),
+                account_email: account_email.clone(),
+                error: "imap client authenticate error: [ALERT] Application-specific password \
+                    required: https://support.google.com/accounts/answer/185833 (Failure)"
+                    .into(),
+                kind: Some(ProviderErrorKind::AppPasswordRequired.to_string()),
+                remediation: Some(ProviderErrorKind::AppPasswordRequired.remediation().into()),
+            }
+        );
+
+        insert_event!(
+            AccountAuthenticationFailed,
+            AccountAuthenticationFailed {
+                account_id: id!(64),
+                account_email: account_email.clone(),
+                oauth2_id: id!(64),
+                error: "Failed to retrieve refresh token response: Server returned error \
+                    response: invalid_grant: Token has been expired or revoked."
+                    .into(),
+                consecutive_failures: 3,
+                failing_since: timestamp - 2 * 60 * 1000,
+            }
+        );
+
+        insert_event!(
+            AccessTokenCreated,
+            AccessTokenCreated {
+                token_prefix: "Xk3r9Lq2".into(),
+                description: Some("Reporting dashboard".into()),
+                access_scopes: vec!["ReadOnly".into()],
+                workspace_id: None,
+                expires_at: Some(timestamp + 90 * 24 * 60 * 60 * 1000),
+                actor: "root".into(),
+            }
+        );
+
+        insert_event!(
+            AccessTokenRotated,
+            AccessTokenRotated {
+                token_prefix: "Xk3r9Lq2".into(),
+                new_token_prefix: "p8Vt2mZa".into(),
+                description: Some("Reporting dashboard".into()),
+                workspace_id: None,
+                grace_period_ends_at: timestamp + 24 * 60 * 60 * 1000,
+                expires_at: Some(timestamp + 90 * 24 * 60 * 60 * 1000),
+                actor: "root".into(),
+            }
+        );
+
+        insert_event!(
+            AccessTokenExpiring,
+            AccessTokenExpiring {
+                token_prefix: "Xk3r9Lq2".into(),
+                description: Some("Reporting dashboard".into()),
+                workspace_id: None,
+                expires_at: timestamp + 7 * 24 * 60 * 60 * 1000,
+                replaced_by_prefix: None,
+            }
+        );
+
         serde_json::to_value(map).unwrap()
     }
 }
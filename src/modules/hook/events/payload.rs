@@ -3,8 +3,12 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::{
-    bounce::parser::{DeliveryStatus, FeedbackReport, RawEmailHeaders},
+    bounce::{
+        classify::BounceCategory,
+        parser::{DeliveryStatus, FeedbackReport, RawEmailHeaders},
+    },
     common::Addr,
+    envelope::{authentication::AuthenticationResults, received::ReceivedChain},
     message::content::FullMessageContent,
 };
 use serde::{Deserialize, Serialize};
@@ -61,6 +65,14 @@ pub struct EmailAddedToFolder {
     ///
     /// Note: This field is populated only for Gmail API accounts. For other account types, it will be empty.
     pub labels: Vec<String>,
+    /// The hops of the email's `Received` headers, with the originating client IP and the total transit time.
+    ///
+    /// Note: This field is populated only for IMAP accounts.
+    pub received_chain: Option<ReceivedChain>,
+    /// The SPF, DKIM and DMARC verdicts of the receiving server, read from the email's `Authentication-Results` headers.
+    ///
+    /// Note: This field is populated only for IMAP accounts.
+    pub authentication_results: Option<AuthenticationResults>,
 }
 
 // #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -220,6 +232,11 @@ pub struct EmailBounce {
     pub original_headers: Option<RawEmailHeaders>,
     /// Optional delivery status information for the bounced email.
     pub delivery_status: Option<DeliveryStatus>,
+    /// Category of the bounce derived from the delivery status, if it reports a failure.
+    pub bounce_category: Option<BounceCategory>,
+    /// ID of the email task that sent the original email, when it was sent through
+    /// RustMailer and could be matched by envelope ID or Message-ID.
+    pub task_id: Option<u64>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -279,3 +296,160 @@ pub struct EmailLinkClicked {
     /// The user agent string of the client used to click the link.
     pub user_agent: String,
 }
+
+/// Represents an event triggered when an outgoing email is detected as a potential mail loop.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct EmailLoopDetected {
+    /// Unique identifier of the account associated with the email.
+    pub account_id: u64,
+    /// Email address of the account associated with the email.
+    pub account_email: String,
+    /// Sender email address of the email.
+    pub from: String,
+    /// List of recipient email addresses of the email.
+    pub to: Vec<String>,
+    /// Optional subject line of the email.
+    pub subject: Option<String>,
+    /// Unique message ID of the email.
+    pub message_id: String,
+    /// Why the email was considered part of a loop.
+    pub reason: String,
+    /// Whether the email was blocked (`true`) or sent anyway and only flagged (`false`).
+    pub blocked: bool,
+}
+
+/// Represents an event triggered when an account's sync is paused automatically.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AccountAutoPaused {
+    /// Unique identifier of the paused account.
+    pub account_id: u64,
+    /// Email address of the paused account.
+    pub account_email: String,
+    /// Why the account was paused: `AuthFailure` or `Unused`.
+    pub reason: String,
+    /// Since when the account has been failing or unused (Unix epoch milliseconds).
+    pub inactive_since: i64,
+}
+
+/// Represents an event triggered when an email with a dangerous attachment is handled by the
+/// account's attachment policy.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AttachmentPolicyTriggered {
+    /// Unique identifier of the account associated with the email.
+    pub account_id: u64,
+    /// Email address of the account associated with the email.
+    pub account_email: String,
+    /// `Inbound` for a received email, `Outbound` for an email being sent.
+    pub direction: String,
+    /// Action taken: `Tag` or `Quarantine` for received emails, `Reject`, `Strip` or `Flag`
+    /// for outgoing emails.
+    pub action: String,
+    /// Mailbox the email arrived in, for received emails.
+    pub mailbox_name: Option<String>,
+    /// UID of the email in `mailbox_name`, for received emails.
+    pub uid: Option<u32>,
+    /// Optional unique message ID of the email.
+    pub message_id: Option<String>,
+    /// Optional subject line of the email.
+    pub subject: Option<String>,
+    /// The attachments that matched the policy.
+    pub attachments: Vec<DangerousAttachment>,
+}
+
+/// An attachment that matched an account's attachment policy.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DangerousAttachment {
+    /// Optional file name of the attachment.
+    pub filename: Option<String>,
+    /// MIME type of the attachment.
+    pub content_type: String,
+    /// Matched category: `Executable`, `Script`, `MacroDocument`, `Html` or `Blocked`.
+    pub category: String,
+}
+
+/// Represents an event triggered when an account's sync or connection fails.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AccountSyncError {
+    /// Unique identifier of the account.
+    pub account_id: u64,
+    /// Email address of the account.
+    pub account_email: String,
+    /// The raw error reported by the mail provider or RustMailer.
+    pub error: String,
+    /// The recognized provider failure, e.g. `AppPasswordRequired` or `RateLimited`, if any.
+    pub kind: Option<String>,
+    /// What the user should do to fix a recognized provider failure.
+    pub remediation: Option<String>,
+}
+
+/// Represents an event triggered when the OAuth2 access token of an account keeps failing to
+/// refresh, e.g. because the refresh token was revoked. The account stops syncing until it is
+/// authorized again.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AccountAuthenticationFailed {
+    /// Unique identifier of the account.
+    pub account_id: u64,
+    /// Email address of the account.
+    pub account_email: String,
+    /// The ID of the OAuth2 configuration the account was authorized with.
+    pub oauth2_id: u64,
+    /// The error returned by the last refresh attempt.
+    pub error: String,
+    /// Number of consecutive failed refresh attempts.
+    pub consecutive_failures: u32,
+    /// When the first of these failures occurred (Unix epoch milliseconds).
+    pub failing_since: i64,
+}
+
+/// Represents an event triggered when an API access token is created. The token itself is
+/// never included, only its first characters.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AccessTokenCreated {
+    /// The first characters of the token, enough to identify it.
+    pub token_prefix: String,
+    /// Description of the token, if any.
+    pub description: Option<String>,
+    /// Scopes granted to the token.
+    pub access_scopes: Vec<String>,
+    /// The workspace the token is scoped to, if any.
+    pub workspace_id: Option<u64>,
+    /// When the token expires (Unix epoch milliseconds), if it does.
+    pub expires_at: Option<i64>,
+    /// Who created the token: `root`, or the description of the access token used.
+    pub actor: String,
+}
+
+/// Represents an event triggered when an API access token is rotated. The old token keeps
+/// working until the end of its grace period.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AccessTokenRotated {
+    /// The first characters of the rotated token.
+    pub token_prefix: String,
+    /// The first characters of the token replacing it.
+    pub new_token_prefix: String,
+    /// Description of the token, if any.
+    pub description: Option<String>,
+    /// The workspace the token is scoped to, if any.
+    pub workspace_id: Option<u64>,
+    /// When the rotated token stops working (Unix epoch milliseconds).
+    pub grace_period_ends_at: i64,
+    /// When the new token expires (Unix epoch milliseconds), if it does.
+    pub expires_at: Option<i64>,
+    /// Who rotated the token: `root`, or the description of the access token used.
+    pub actor: String,
+}
+
+/// Represents an event triggered once when an API access token is about to expire.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AccessTokenExpiring {
+    /// The first characters of the token.
+    pub token_prefix: String,
+    /// Description of the token, if any.
+    pub description: Option<String>,
+    /// The workspace the token is scoped to, if any.
+    pub workspace_id: Option<u64>,
+    /// When the token expires (Unix epoch milliseconds).
+    pub expires_at: i64,
+    /// The first characters of the token that replaced it, if it was rotated.
+    pub replaced_by_prefix: Option<String>,
+}
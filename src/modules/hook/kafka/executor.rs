@@ -0,0 +1,119 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::error::code::ErrorCode;
+use crate::modules::error::RustMailerResult;
+use crate::modules::hook::events::EventType;
+use crate::modules::hook::kafka::{KafkaConfig, DELIVERY_TIMEOUT_MS};
+use crate::raise_error;
+use dashmap::DashMap;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tracing::{debug, error};
+
+pub static KAFKA_EXECUTORS: LazyLock<KafkaProducerExecutors> =
+    LazyLock::new(KafkaProducerExecutors::new);
+
+/// Producers shared by the hooks with the same configuration. A producer multiplexes
+/// every in-flight message over its own broker connections, so no pool is needed.
+pub struct KafkaProducerExecutors {
+    kafka: DashMap<KafkaConfig, Arc<KafkaExecutor>>,
+}
+
+impl KafkaProducerExecutors {
+    pub fn new() -> Self {
+        Self {
+            kafka: DashMap::new(),
+        }
+    }
+
+    pub async fn get(&self, config: &KafkaConfig) -> RustMailerResult<Arc<KafkaExecutor>> {
+        if let Some(executor) = self.kafka.get(config) {
+            return Ok(executor.value().clone());
+        }
+
+        let executor = Arc::new(KafkaExecutor::new(config.clone())?);
+
+        match self.kafka.try_entry(config.clone()) {
+            Some(dashmap::mapref::entry::Entry::Occupied(entry)) => Ok(entry.get().clone()),
+            Some(dashmap::mapref::entry::Entry::Vacant(entry)) => {
+                entry.insert(executor.clone());
+                Ok(executor)
+            }
+            None => Err(raise_error!(
+                "DashMap locked".into(),
+                ErrorCode::InternalError
+            )),
+        }
+    }
+}
+
+pub struct KafkaExecutor {
+    config: KafkaConfig,
+    producer: FutureProducer,
+}
+
+impl KafkaExecutor {
+    pub fn new(config: KafkaConfig) -> RustMailerResult<Self> {
+        let producer = config.client_config().create().map_err(|e| {
+            raise_error!(
+                format!(
+                    "Failed to create Kafka producer for brokers {}. Error: {}",
+                    config.brokers.join(","),
+                    e
+                ),
+                ErrorCode::KafkaConnectionFailed
+            )
+        })?;
+        Ok(Self { config, producer })
+    }
+
+    /// Publishes the event and waits until the brokers acknowledged it. Events are keyed
+    /// by account, so the events of an account keep their order within a partition.
+    pub async fn publish(
+        &self,
+        task_info: Option<HashMap<String, String>>,
+        account_id: u64,
+        event_type: EventType,
+        payload: serde_json::Value,
+    ) -> RustMailerResult<()> {
+        let topic = self.config.topic(&event_type);
+        let key = account_id.to_string();
+        let body = payload.to_string();
+
+        let mut headers = OwnedHeaders::new();
+        if let Some(task_info) = task_info {
+            for (key, value) in &task_info {
+                headers = headers.insert(Header {
+                    key,
+                    value: Some(value),
+                });
+            }
+        }
+        let record = FutureRecord::to(&topic)
+            .key(&key)
+            .payload(&body)
+            .headers(headers);
+
+        let delivery = self
+            .producer
+            .send(record, Duration::from_millis(DELIVERY_TIMEOUT_MS))
+            .await
+            .map_err(|(e, _)| {
+                error!(
+                    "Failed to publish event to Kafka topic '{}': {:?}",
+                    topic, e
+                );
+                raise_error!(format!("{:#?}", e), ErrorCode::KafkaRequestFailed)
+            })?;
+        debug!(
+            "Successfully published event: {} to Kafka topic '{}' ({:?})",
+            event_type, topic, delivery
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+#[cfg(any(feature = "kafka", test))]
+use crate::modules::hook::events::EventType;
+use crate::{
+    modules::error::{code::ErrorCode, RustMailerResult},
+    raise_error,
+};
+use poem_openapi::{Enum, Object};
+#[cfg(feature = "kafka")]
+use rdkafka::ClientConfig;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "kafka")]
+pub mod executor;
+
+/// Longest topic name accepted by Kafka brokers.
+const MAX_TOPIC_LEN: usize = 249;
+/// Longest topic prefix accepted in [`KafkaTopicMode::PerEventType`], leaving room for the
+/// `.{event_type}` suffix.
+const MAX_TOPIC_PREFIX_LEN: usize = 200;
+/// How long the producer waits for a message to be acknowledged before reporting a failure.
+#[cfg(feature = "kafka")]
+pub const DELIVERY_TIMEOUT_MS: u64 = 30_000;
+
+/// Kafka destinations can only be used by binaries built with the `kafka` feature.
+pub fn ensure_supported() -> RustMailerResult<()> {
+    if cfg!(feature = "kafka") {
+        Ok(())
+    } else {
+        Err(raise_error!(
+            "Kafka destinations are not supported by this build of RustMailer, rebuild it with the `kafka` feature".into(),
+            ErrorCode::InvalidParameter
+        ))
+    }
+}
+
+#[derive(Enum, Default, Hash, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum KafkaSecurityProtocol {
+    /// Unauthenticated, unencrypted connections.
+    #[default]
+    Plaintext,
+    /// TLS encrypted connections without SASL authentication.
+    Ssl,
+    /// SASL authentication over unencrypted connections.
+    SaslPlaintext,
+    /// SASL authentication over TLS encrypted connections.
+    SaslSsl,
+}
+
+impl KafkaSecurityProtocol {
+    #[cfg(feature = "kafka")]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KafkaSecurityProtocol::Plaintext => "plaintext",
+            KafkaSecurityProtocol::Ssl => "ssl",
+            KafkaSecurityProtocol::SaslPlaintext => "sasl_plaintext",
+            KafkaSecurityProtocol::SaslSsl => "sasl_ssl",
+        }
+    }
+
+    fn uses_sasl(&self) -> bool {
+        matches!(
+            self,
+            KafkaSecurityProtocol::SaslPlaintext | KafkaSecurityProtocol::SaslSsl
+        )
+    }
+
+    #[cfg(feature = "kafka")]
+    fn uses_tls(&self) -> bool {
+        matches!(
+            self,
+            KafkaSecurityProtocol::Ssl | KafkaSecurityProtocol::SaslSsl
+        )
+    }
+}
+
+#[derive(Enum, Default, Hash, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum KafkaSaslMechanism {
+    #[default]
+    Plain,
+    ScramSha256,
+    ScramSha512,
+}
+
+impl KafkaSaslMechanism {
+    #[cfg(feature = "kafka")]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KafkaSaslMechanism::Plain => "PLAIN",
+            KafkaSaslMechanism::ScramSha256 => "SCRAM-SHA-256",
+            KafkaSaslMechanism::ScramSha512 => "SCRAM-SHA-512",
+        }
+    }
+}
+
+#[derive(Enum, Default, Hash, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum KafkaTopicMode {
+    /// Every event is published to `topic`.
+    #[default]
+    Single,
+    /// Each event type is published to its own topic, `{topic}.{event_type}`,
+    /// e.g. `rustmailer.EmailAddedToFolder`.
+    PerEventType,
+}
+
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct KafkaConfig {
+    /// Bootstrap brokers of the Kafka cluster, as `host:port`.
+    #[oai(validator(min_items = 1))]
+    pub brokers: Vec<String>,
+    /// The protocol used to communicate with the brokers.
+    pub security_protocol: KafkaSecurityProtocol,
+    /// The SASL mechanism used when the security protocol is `SaslPlaintext` or `SaslSsl`.
+    pub sasl_mechanism: KafkaSaslMechanism,
+    /// Optional username for SASL authentication.
+    pub username: Option<String>,
+    /// Optional password for SASL authentication.
+    pub password: Option<String>,
+    /// Optional PEM encoded CA certificate used to verify the brokers when TLS is enabled.
+    /// If omitted, the system trust store is used.
+    pub ca_certificate: Option<String>,
+    /// Whether events go to a single topic or to one topic per event type.
+    pub topic_mode: KafkaTopicMode,
+    /// The topic events are published to in `Single` mode, or the prefix of the
+    /// per-event-type topics in `PerEventType` mode.
+    pub topic: String,
+}
+
+impl KafkaConfig {
+    pub fn validate(&self) -> RustMailerResult<()> {
+        if self.brokers.is_empty() {
+            return Err(raise_error!(
+                "At least one Kafka broker is required".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        for broker in &self.brokers {
+            let valid = broker
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+            if !valid {
+                return Err(raise_error!(
+                    format!("Invalid Kafka broker '{}': expected 'host:port'", broker),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+
+        let max_len = match self.topic_mode {
+            KafkaTopicMode::Single => MAX_TOPIC_LEN,
+            KafkaTopicMode::PerEventType => MAX_TOPIC_PREFIX_LEN,
+        };
+        if self.topic.is_empty()
+            || self.topic.len() > max_len
+            || !self
+                .topic
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        {
+            return Err(raise_error!(
+                format!("Invalid topic: topic must be 1 to {} characters long and can only contain letters, numbers, '.', '_' and '-'.", max_len),
+                ErrorCode::InvalidParameter
+            ));
+        }
+
+        if self.security_protocol.uses_sasl()
+            && (self.username.is_none() || self.password.is_none())
+        {
+            return Err(raise_error!(
+                "username and password are required when the security protocol uses SASL".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        Ok(())
+    }
+
+    /// The topic an event of the given type is published to.
+    #[cfg(any(feature = "kafka", test))]
+    pub fn topic(&self, event_type: &EventType) -> String {
+        match self.topic_mode {
+            KafkaTopicMode::Single => self.topic.clone(),
+            KafkaTopicMode::PerEventType => format!("{}.{}", self.topic, event_type),
+        }
+    }
+
+    /// The librdkafka configuration of the producer.
+    #[cfg(feature = "kafka")]
+    pub fn client_config(&self) -> ClientConfig {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", self.brokers.join(","))
+            .set("client.id", "rustmailer")
+            .set("security.protocol", self.security_protocol.as_str())
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .set("message.timeout.ms", DELIVERY_TIMEOUT_MS.to_string());
+
+        if self.security_protocol.uses_sasl() {
+            config.set("sasl.mechanism", self.sasl_mechanism.as_str());
+            if let Some(username) = &self.username {
+                config.set("sasl.username", username);
+            }
+            if let Some(password) = &self.password {
+                config.set("sasl.password", password);
+            }
+        }
+        if self.security_protocol.uses_tls() {
+            if let Some(ca_certificate) = &self.ca_certificate {
+                config.set("ssl.ca.pem", ca_certificate);
+            }
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(topic_mode: KafkaTopicMode, topic: &str) -> KafkaConfig {
+        KafkaConfig {
+            brokers: vec!["kafka-1:9092".into(), "kafka-2:9092".into()],
+            topic_mode,
+            topic: topic.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_topics() {
+        let single = config(KafkaTopicMode::Single, "mail-events");
+        assert_eq!(single.topic(&EventType::EmailBounce), "mail-events");
+        let per_event = config(KafkaTopicMode::PerEventType, "rustmailer");
+        assert_eq!(
+            per_event.topic(&EventType::EmailAddedToFolder),
+            "rustmailer.EmailAddedToFolder"
+        );
+    }
+
+    #[test]
+    fn validates_brokers_topics_and_sasl() {
+        assert!(config(KafkaTopicMode::Single, "mail-events")
+            .validate()
+            .is_ok());
+        for topic in ["", "mail events", "mail/events", &"t".repeat(250)] {
+            assert!(config(KafkaTopicMode::Single, topic).validate().is_err());
+        }
+        assert!(config(KafkaTopicMode::PerEventType, &"t".repeat(201))
+            .validate()
+            .is_err());
+
+        let mut broker = config(KafkaTopicMode::Single, "events");
+        broker.brokers = vec!["kafka-1".into()];
+        assert!(broker.validate().is_err());
+        broker.brokers = Vec::new();
+        assert!(broker.validate().is_err());
+
+        let mut sasl = config(KafkaTopicMode::Single, "events");
+        sasl.security_protocol = KafkaSecurityProtocol::SaslSsl;
+        sasl.username = Some("rustmailer".into());
+        assert!(sasl.validate().is_err());
+        sasl.password = Some("secret".into());
+        assert!(sasl.validate().is_ok());
+    }
+}
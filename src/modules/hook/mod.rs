@@ -3,10 +3,16 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 pub mod channel;
+pub mod delivery;
 pub mod entity;
 pub mod events;
+pub mod kafka;
 pub mod nats;
 pub mod payload;
+pub mod redis;
+pub mod signing;
+pub mod simulate;
+pub mod stream;
 pub mod task;
 #[cfg(test)]
 mod tests;
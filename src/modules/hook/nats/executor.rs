@@ -90,10 +90,11 @@ impl NatsExecutor {
     pub async fn publish(
         &self,
         task_info: Option<HashMap<String, String>>,
+        account_id: u64,
         event_type: EventType,
         payload: serde_json::Value,
     ) -> RustMailerResult<()> {
-        let topic = format!("{}.{}", self.config.namespace, event_type);
+        let topic = self.config.subject(account_id, &event_type);
 
         let mut headers = async_nats::HeaderMap::new();
         if let Some(task_info) = task_info {
@@ -3,7 +3,10 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::{
-    modules::error::{code::ErrorCode, RustMailerResult},
+    modules::{
+        error::{code::ErrorCode, RustMailerResult},
+        hook::events::EventType,
+    },
     raise_error,
 };
 use async_nats::jetstream::{self};
@@ -14,6 +17,11 @@ use serde::{Deserialize, Serialize};
 pub mod executor;
 pub mod pool;
 
+/// Subject used when a hook has no subject template, e.g. `rustmailer.EmailAddedToFolder`.
+pub const DEFAULT_SUBJECT_TEMPLATE: &str = "{namespace}.{event_type}";
+/// Placeholders that can be used in a subject template.
+pub const SUBJECT_PLACEHOLDERS: [&str; 3] = ["{namespace}", "{account_id}", "{event_type}"];
+
 #[derive(Debug)]
 pub struct NatsConnectionManager {
     config: NatsConfig,
@@ -59,6 +67,63 @@ pub struct NatsConfig {
     pub stream_name: String,
     /// The namespace or subject prefix used for organizing messages in the NATS server.
     pub namespace: String,
+    /// Optional template of the subject events are published under, built from dot-separated
+    /// tokens and the placeholders `{namespace}`, `{account_id}` and `{event_type}`,
+    /// e.g. `{namespace}.{account_id}.{event_type}`. The subject must be covered by the
+    /// subject filter of the stream. Defaults to `{namespace}.{event_type}`.
+    pub subject_template: Option<String>,
+}
+
+/// Layout of [`NatsConfig`] stored by event hooks before subject templates were added.
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct NatsConfigV1 {
+    /// The hostname or IP address of the NATS server.
+    pub host: String,
+    /// The port number on which the NATS server is listening.
+    pub port: u16,
+    /// The authentication type used to connect to the NATS server.
+    pub auth_type: NatsAuthType,
+    /// Optional token for token-based authentication with the NATS server.
+    pub token: Option<String>,
+    /// Optional username for user-based authentication with the NATS server.
+    pub username: Option<String>,
+    /// Optional password for user-based authentication with the NATS server.
+    pub password: Option<String>,
+    /// The name of the NATS stream to which messages are published.
+    pub stream_name: String,
+    /// The namespace or subject prefix used for organizing messages in the NATS server.
+    pub namespace: String,
+}
+
+impl From<NatsConfigV1> for NatsConfig {
+    fn from(value: NatsConfigV1) -> Self {
+        Self {
+            host: value.host,
+            port: value.port,
+            auth_type: value.auth_type,
+            token: value.token,
+            username: value.username,
+            password: value.password,
+            stream_name: value.stream_name,
+            namespace: value.namespace,
+            subject_template: None,
+        }
+    }
+}
+
+impl From<NatsConfig> for NatsConfigV1 {
+    fn from(value: NatsConfig) -> Self {
+        Self {
+            host: value.host,
+            port: value.port,
+            auth_type: value.auth_type,
+            token: value.token,
+            username: value.username,
+            password: value.password,
+            stream_name: value.stream_name,
+            namespace: value.namespace,
+        }
+    }
 }
 
 impl NatsConfig {
@@ -69,6 +134,10 @@ impl NatsConfig {
             return Err(raise_error!("Invalid namespace: namespace can only contain letters, numbers, and underscores, and must start with a letter.".into(), ErrorCode::InvalidParameter));
         }
 
+        if let Some(template) = &self.subject_template {
+            self.validate_subject_template(template, &[self.stream_subject_filter()])?;
+        }
+
         match self.auth_type {
             NatsAuthType::None => {}
             NatsAuthType::Password => {
@@ -92,6 +161,75 @@ impl NatsConfig {
         Ok(())
     }
 
+    /// The subject filter of the stream created for the hook.
+    pub fn stream_subject_filter(&self) -> String {
+        format!("{}.>", self.namespace)
+    }
+
+    /// The subject an event of the given account is published under.
+    pub fn subject(&self, account_id: u64, event_type: &EventType) -> String {
+        self.subject_template
+            .as_deref()
+            .unwrap_or(DEFAULT_SUBJECT_TEMPLATE)
+            .replace("{namespace}", &self.namespace)
+            .replace("{account_id}", &account_id.to_string())
+            .replace("{event_type}", &event_type.to_string())
+    }
+
+    /// Checks that `template` is a valid subject template and that every subject it can
+    /// produce is captured by one of the `stream_subjects` filters, so no event is published
+    /// to a subject the stream does not store.
+    pub fn validate_subject_template(
+        &self,
+        template: &str,
+        stream_subjects: &[String],
+    ) -> RustMailerResult<()> {
+        let invalid = |reason: String| {
+            raise_error!(
+                format!("Invalid subject template '{}': {}", template, reason),
+                ErrorCode::InvalidParameter
+            )
+        };
+        let template = template.replace("{namespace}", &self.namespace);
+        // Tokens holding a placeholder vary from one event to another, like a `*` wildcard.
+        let mut pattern = Vec::new();
+        for token in template.split('.') {
+            if token.is_empty() {
+                return Err(invalid("tokens must not be empty".into()));
+            }
+            if token.contains(|c: char| c.is_whitespace() || c == '*' || c == '>') {
+                return Err(invalid(
+                    "tokens must not contain whitespace or the wildcards '*' and '>'".into(),
+                ));
+            }
+            let literal = SUBJECT_PLACEHOLDERS
+                .iter()
+                .fold(token.to_string(), |rest, placeholder| {
+                    rest.replace(placeholder, "")
+                });
+            if literal.contains(['{', '}']) {
+                return Err(invalid(format!(
+                    "unknown placeholder in '{}', expected one of {}",
+                    token,
+                    SUBJECT_PLACEHOLDERS.join(", ")
+                )));
+            }
+            pattern.push((literal.len() == token.len()).then_some(token));
+        }
+
+        if !stream_subjects
+            .iter()
+            .any(|filter| filter_covers(filter, &pattern))
+        {
+            return Err(invalid(format!(
+                "subjects are not captured by the subject filter of stream '{}' ({})",
+                self.stream_name,
+                stream_subjects.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
     pub async fn create_producer(&self) -> RustMailerResult<async_nats::jetstream::Context> {
         let nats_url = format!("nats://{}:{}", &self.host, &self.port);
 
@@ -158,10 +296,10 @@ impl NatsConfig {
 
         let jetstream = jetstream::new(client);
 
-        jetstream
+        let stream = jetstream
             .create_stream(jetstream::stream::Config {
                 name: self.stream_name.to_string(),
-                subjects: vec![format!("{}.>", self.namespace)],
+                subjects: vec![self.stream_subject_filter()],
                 ..Default::default()
             })
             .await
@@ -175,6 +313,90 @@ impl NatsConfig {
                 )
             })?;
 
+        if let Some(template) = &self.subject_template {
+            self.validate_subject_template(template, &stream.cached_info().config.subjects)?;
+        }
+
         Ok(jetstream)
     }
 }
+
+/// Whether the NATS subject `filter` matches every subject of `pattern`, whose tokens are
+/// either literal or, for `None`, any single token.
+fn filter_covers(filter: &str, pattern: &[Option<&str>]) -> bool {
+    let filter: Vec<&str> = filter.split('.').collect();
+    for (index, token) in filter.iter().enumerate() {
+        if *token == ">" {
+            return pattern.len() > index;
+        }
+        match (*token, pattern.get(index)) {
+            (_, None) => return false,
+            ("*", Some(_)) => {}
+            (token, Some(Some(literal))) if token == *literal => {}
+            _ => return false,
+        }
+    }
+    filter.len() == pattern.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(subject_template: Option<&str>) -> NatsConfig {
+        NatsConfig {
+            host: "127.0.0.1".into(),
+            port: 4222,
+            stream_name: "events".into(),
+            namespace: "rustmailer".into(),
+            subject_template: subject_template.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_subjects() {
+        assert_eq!(
+            config(None).subject(42, &EventType::EmailAddedToFolder),
+            "rustmailer.EmailAddedToFolder"
+        );
+        assert_eq!(
+            config(Some("{namespace}.acct_{account_id}.{event_type}"))
+                .subject(42, &EventType::EmailFlagsChanged),
+            "rustmailer.acct_42.EmailFlagsChanged"
+        );
+    }
+
+    #[test]
+    fn validates_templates_against_the_stream_filter() {
+        for template in [
+            "{namespace}.{account_id}.{event_type}",
+            "rustmailer.{event_type}",
+            "{namespace}.mail.{event_type}.{account_id}",
+        ] {
+            assert!(config(Some(template)).validate().is_ok(), "{template}");
+        }
+        for template in [
+            "{event_type}.{namespace}",
+            "{namespace}",
+            "other.{event_type}",
+            "{namespace}..{event_type}",
+            "{namespace}.*",
+            "{namespace}.{account}",
+            "{namespace}.{event type}",
+        ] {
+            assert!(config(Some(template)).validate().is_err(), "{template}");
+        }
+    }
+
+    #[test]
+    fn matches_nats_wildcards() {
+        let pattern = [Some("rustmailer"), None, Some("EmailBounce")];
+        assert!(filter_covers("rustmailer.>", &pattern));
+        assert!(filter_covers("rustmailer.*.EmailBounce", &pattern));
+        assert!(filter_covers("*.*.*", &pattern));
+        assert!(!filter_covers("rustmailer.42.>", &pattern));
+        assert!(!filter_covers("rustmailer.*", &pattern));
+        assert!(!filter_covers("rustmailer.*.EmailBounce.>", &pattern));
+    }
+}
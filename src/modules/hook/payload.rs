@@ -2,8 +2,10 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use crate::modules::hook::entity::HookType;
+use crate::modules::hook::entity::{EventVrlScript, HookType};
 use crate::modules::hook::events::EventType;
+use crate::modules::hook::kafka::KafkaConfig;
+use crate::modules::hook::redis::RedisConfig;
 use crate::modules::hook::{entity::HttpConfig, nats::NatsConfig};
 use crate::{modules::hook::entity::EventHooks, utc_now};
 use poem_openapi::Object;
@@ -17,20 +19,35 @@ pub struct EventhookCreateRequest {
     pub description: Option<String>,
     /// Indicates whether the hook is active and processing events upon creation.
     pub enabled: bool,
-    /// The type of hook (e.g., HTTP or NATS).
+    /// The type of hook (e.g., HTTP, NATS, Kafka or Redis Streams).
     pub hook_type: HookType,
     /// Optional HTTP configuration for HTTP-based hook.
     pub http: Option<HttpConfig>,
     /// Optional NATS configuration for NATS-based hook.
     pub nats: Option<NatsConfig>,
+    /// Optional Kafka configuration for Kafka-based hook.
+    pub kafka: Option<KafkaConfig>,
+    /// Optional Redis configuration for Redis Streams-based hook.
+    pub redis: Option<RedisConfig>,
     /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
     pub vrl_script: Option<String>,
+    /// Optional VRL scripts applied to the events of their type instead of `vrl_script`.
+    pub event_vrl_scripts: Option<Vec<EventVrlScript>>,
     /// List of event types the hook is configured to monitor.
     pub watched_events: Vec<EventType>,
     /// Optional proxy ID for establishing the connection.
     /// - If `None` or not provided, the client will connect directly to the webhook server.
     /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
     pub use_proxy: Option<u64>,
+    /// Only for global hooks: limits the hook to accounts carrying all of these tags.
+    pub account_tags: Option<Vec<String>>,
+    /// Only for global hooks: limits the hook to the accounts of a workspace. Defaults to the
+    /// workspace of the access token.
+    pub workspace_id: Option<u64>,
+    /// Optional secret used to sign the delivered payloads, at least 16 characters.
+    /// If omitted, payloads are unsigned until a secret is generated with `rotate-secret`.
+    #[oai(validator(min_length = 16))]
+    pub signing_secret: Option<String>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
@@ -43,14 +60,45 @@ pub struct EventhookUpdateRequest {
     pub http: Option<HttpConfig>,
     /// Optional NATS configuration for NATS-based hook.
     pub nats: Option<NatsConfig>,
+    /// Optional Kafka configuration for Kafka-based hook.
+    pub kafka: Option<KafkaConfig>,
+    /// Optional Redis configuration for Redis Streams-based hook.
+    pub redis: Option<RedisConfig>,
     /// Optional VRL (Vector Remap Language) script for customizing the hook payload.
     pub vrl_script: Option<String>,
+    /// Replaces the VRL scripts applied to the events of their type instead of `vrl_script`.
+    /// An empty list removes them.
+    pub event_vrl_scripts: Option<Vec<EventVrlScript>>,
     /// List of event types the hook is configured to monitor.
     pub watched_events: Option<Vec<EventType>>,
     /// Optional proxy ID for establishing the connection.
     /// - If `None` or not provided, the client will connect directly to the webhook server.
     /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
     pub use_proxy: Option<u64>,
+    /// Only for global hooks: replaces the account tags the hook is limited to.
+    /// An empty list removes the limit.
+    pub account_tags: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct RotateHookSecretRequest {
+    /// How long, in seconds, the current secret keeps signing payloads alongside the new one,
+    /// so receivers can switch keys without rejecting deliveries. Defaults to 24 hours;
+    /// 0 retires the current secret immediately. At most 30 days.
+    pub overlap_seconds: Option<u64>,
+}
+
+/// The result of a signing secret rotation. The secret is only ever returned here.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct RotatedHookSecret {
+    /// Identifier of the new secret.
+    pub key_id: String,
+    /// The new secret. Store it now: it cannot be retrieved again.
+    pub secret: String,
+    /// Identifier of the previous secret, if it is still used during the overlap.
+    pub previous_key_id: Option<String>,
+    /// Timestamp (in milliseconds) when the previous secret stops being used.
+    pub previous_expires_at: Option<i64>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -80,10 +128,22 @@ pub fn apply_update(old: &EventHooks, request: EventhookUpdateRequest) -> EventH
         new.nats = Some(nats);
     }
 
+    if let Some(kafka) = request.kafka {
+        new.kafka = Some(kafka);
+    }
+
+    if let Some(redis) = request.redis {
+        new.redis = Some(redis);
+    }
+
     if let Some(vrl_script) = request.vrl_script {
         new.vrl_script = Some(vrl_script);
     }
 
+    if let Some(event_vrl_scripts) = request.event_vrl_scripts {
+        new.event_vrl_scripts = event_vrl_scripts;
+    }
+
     if let Some(use_proxy) = request.use_proxy {
         new.use_proxy = Some(use_proxy)
     }
@@ -92,6 +152,10 @@ pub fn apply_update(old: &EventHooks, request: EventhookUpdateRequest) -> EventH
         new.watched_events = watched_events;
     }
 
+    if let Some(account_tags) = request.account_tags {
+        new.account_tags = (!account_tags.is_empty()).then_some(account_tags);
+    }
+
     new.updated_at = utc_now!();
 
     new
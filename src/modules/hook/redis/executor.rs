@@ -0,0 +1,111 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::error::code::ErrorCode;
+use crate::modules::hook::events::EventType;
+use crate::modules::hook::redis::RedisConfig;
+use crate::{
+    modules::{error::RustMailerResult, hook::redis::RedisConnectionManager},
+    raise_error,
+};
+use bb8::Pool;
+use dashmap::DashMap;
+use redis::streams::StreamMaxlen;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+use tracing::{debug, error};
+
+use super::pool::build_redis_pool;
+
+pub static REDIS_EXECUTORS: LazyLock<RedisStreamExecutors> =
+    LazyLock::new(RedisStreamExecutors::new);
+
+pub struct RedisStreamExecutors {
+    redis: DashMap<RedisConfig, Arc<RedisExecutor>>,
+}
+
+impl RedisStreamExecutors {
+    pub fn new() -> Self {
+        Self {
+            redis: DashMap::new(),
+        }
+    }
+
+    pub async fn get(&self, config: &RedisConfig) -> RustMailerResult<Arc<RedisExecutor>> {
+        if let Some(executor) = self.redis.get(config) {
+            return Ok(executor.value().clone());
+        }
+
+        let pool = build_redis_pool(config).await?;
+        let executor = Arc::new(RedisExecutor::new(config.clone(), pool));
+
+        match self.redis.try_entry(config.clone()) {
+            Some(dashmap::mapref::entry::Entry::Occupied(entry)) => Ok(entry.get().clone()),
+            Some(dashmap::mapref::entry::Entry::Vacant(entry)) => {
+                entry.insert(executor.clone());
+                Ok(executor)
+            }
+            None => Err(raise_error!(
+                "DashMap locked".into(),
+                ErrorCode::InternalError
+            )),
+        }
+    }
+}
+
+pub struct RedisExecutor {
+    config: RedisConfig,
+    pool: Pool<RedisConnectionManager>,
+}
+
+impl RedisExecutor {
+    pub fn new(config: RedisConfig, pool: Pool<RedisConnectionManager>) -> Self {
+        Self { config, pool }
+    }
+
+    /// Appends the event to its stream with `XADD`, trimming the stream to `max_len`
+    /// entries if configured. The task headers are stored as extra entry fields.
+    pub async fn publish(
+        &self,
+        task_info: Option<HashMap<String, String>>,
+        account_id: u64,
+        event_type: EventType,
+        payload: serde_json::Value,
+    ) -> RustMailerResult<()> {
+        let key = self.config.stream_key(account_id, &event_type);
+
+        let mut fields = vec![
+            ("event_type".to_string(), event_type.to_string()),
+            ("account_id".to_string(), account_id.to_string()),
+            ("payload".to_string(), payload.to_string()),
+        ];
+        if let Some(task_info) = task_info {
+            fields.extend(task_info);
+        }
+
+        let mut command = redis::cmd("XADD");
+        command.arg(&key);
+        if let Some(max_len) = self.config.max_len {
+            let max_len = max_len as usize;
+            command.arg(if self.config.approximate_trimming {
+                StreamMaxlen::Approx(max_len)
+            } else {
+                StreamMaxlen::Equals(max_len)
+            });
+        }
+        command.arg("*").arg(&fields);
+
+        let mut conn = self.pool.get().await?;
+        let result = command.query_async::<String>(&mut *conn).await;
+        let entry_id = result.map_err(|e| {
+            error!("Failed to append event to Redis stream '{}': {:?}", key, e);
+            raise_error!(format!("{:#?}", e), ErrorCode::RedisRequestFailed)
+        })?;
+        debug!(
+            "Successfully published event: {} to Redis stream '{}' as entry {}",
+            event_type, key, entry_id
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::{
+    modules::{
+        error::{code::ErrorCode, RustMailerResult},
+        hook::events::EventType,
+    },
+    raise_error,
+};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+#[cfg(feature = "redis")]
+pub mod executor;
+#[cfg(feature = "redis")]
+pub mod pool;
+
+/// Placeholders that can be used in a stream key.
+pub const STREAM_KEY_PLACEHOLDERS: [&str; 2] = ["{account_id}", "{event_type}"];
+
+/// Redis destinations can only be used by binaries built with the `redis` feature.
+pub fn ensure_supported() -> RustMailerResult<()> {
+    if cfg!(feature = "redis") {
+        Ok(())
+    } else {
+        Err(raise_error!(
+            "Redis destinations are not supported by this build of RustMailer, rebuild it with the `redis` feature".into(),
+            ErrorCode::InvalidParameter
+        ))
+    }
+}
+
+#[cfg(feature = "redis")]
+#[derive(Debug)]
+pub struct RedisConnectionManager {
+    client: redis::Client,
+}
+
+#[cfg(feature = "redis")]
+impl RedisConnectionManager {
+    pub fn new(config: &RedisConfig) -> RustMailerResult<Self> {
+        Ok(Self {
+            client: config.create_client()?,
+        })
+    }
+
+    pub async fn build(&self) -> RustMailerResult<redis::aio::MultiplexedConnection> {
+        self.client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|error| {
+                raise_error!(
+                    format!("Failed to connect to Redis server. Error: {}", error),
+                    ErrorCode::RedisConnectionFailed
+                )
+            })
+    }
+}
+
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct RedisConfig {
+    /// The hostname or IP address of the Redis server.
+    #[oai(validator(max_length = 253, pattern = r"^[a-zA-Z0-9\-\.]+$"))]
+    pub host: String,
+    /// The port number on which the Redis server is listening.
+    #[oai(validator(minimum(value = "1"), maximum(value = "65535")))]
+    pub port: u16,
+    /// Whether to connect to the Redis server over TLS.
+    pub use_tls: bool,
+    /// Optional username for ACL authentication with the Redis server.
+    pub username: Option<String>,
+    /// Optional password for authentication with the Redis server.
+    pub password: Option<String>,
+    /// The logical database to select after connecting.
+    pub database: u16,
+    /// Key of the stream events are appended to. May contain the placeholders
+    /// `{account_id}` and `{event_type}`, e.g. `rustmailer:{event_type}`.
+    #[oai(validator(min_length = 1, max_length = 256))]
+    pub stream_key: String,
+    /// Optional maximum number of entries kept in the stream. Older entries are trimmed
+    /// whenever an event is appended. If `None`, the stream is never trimmed.
+    #[oai(validator(minimum(value = "1")))]
+    pub max_len: Option<u64>,
+    /// Whether trimming may keep a few more entries than `max_len`, which lets Redis trim
+    /// whole nodes at once and is much cheaper. Ignored when `max_len` is not set.
+    pub approximate_trimming: bool,
+}
+
+impl RedisConfig {
+    pub fn validate(&self) -> RustMailerResult<()> {
+        if self.stream_key.trim().is_empty() {
+            return Err(raise_error!(
+                "Invalid stream key: stream key must not be empty.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let literal = STREAM_KEY_PLACEHOLDERS
+            .iter()
+            .fold(self.stream_key.clone(), |rest, placeholder| {
+                rest.replace(placeholder, "")
+            });
+        if literal.contains(['{', '}']) {
+            return Err(raise_error!(
+                format!(
+                    "Invalid stream key '{}': unknown placeholder, expected one of {}",
+                    self.stream_key,
+                    STREAM_KEY_PLACEHOLDERS.join(", ")
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+
+        if self.username.is_some() && self.password.is_none() {
+            return Err(raise_error!(
+                "password is required when username is set".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+
+        if self.max_len == Some(0) {
+            return Err(raise_error!(
+                "max_len must be greater than 0".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        Ok(())
+    }
+
+    /// The key of the stream an event of the given account is appended to.
+    pub fn stream_key(&self, account_id: u64, event_type: &EventType) -> String {
+        self.stream_key
+            .replace("{account_id}", &account_id.to_string())
+            .replace("{event_type}", &event_type.to_string())
+    }
+
+    /// The connection URL of the server, without credentials.
+    #[cfg(feature = "redis")]
+    fn url(&self) -> String {
+        let scheme = if self.use_tls { "rediss" } else { "redis" };
+        format!("{}://{}:{}/{}", scheme, self.host, self.port, self.database)
+    }
+
+    #[cfg(feature = "redis")]
+    pub fn create_client(&self) -> RustMailerResult<redis::Client> {
+        let invalid = |error: String| {
+            raise_error!(
+                format!(
+                    "Invalid Redis server address {}. Error: {}",
+                    self.url(),
+                    error
+                ),
+                ErrorCode::InvalidParameter
+            )
+        };
+        let mut url = Url::parse(&self.url()).map_err(|e| invalid(e.to_string()))?;
+        // Setting the credentials on the URL takes care of percent-encoding them.
+        if let Some(username) = &self.username {
+            url.set_username(username)
+                .map_err(|_| invalid("invalid username".into()))?;
+        }
+        if let Some(password) = &self.password {
+            url.set_password(Some(password))
+                .map_err(|_| invalid("invalid password".into()))?;
+        }
+        redis::Client::open(url.as_str()).map_err(|e| invalid(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(stream_key: &str) -> RedisConfig {
+        RedisConfig {
+            host: "127.0.0.1".into(),
+            port: 6379,
+            stream_key: stream_key.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_stream_keys() {
+        assert_eq!(
+            config("rustmailer:events").stream_key(42, &EventType::EmailBounce),
+            "rustmailer:events"
+        );
+        assert_eq!(
+            config("rustmailer:{account_id}:{event_type}")
+                .stream_key(42, &EventType::EmailFlagsChanged),
+            "rustmailer:42:EmailFlagsChanged"
+        );
+    }
+
+    #[test]
+    fn validates_configs() {
+        assert!(config("rustmailer:{event_type}").validate().is_ok());
+        assert!(config(" ").validate().is_err());
+        assert!(config("rustmailer:{account}").validate().is_err());
+
+        let mut trimmed = config("events");
+        trimmed.max_len = Some(0);
+        assert!(trimmed.validate().is_err());
+        trimmed.max_len = Some(10_000);
+        assert!(trimmed.validate().is_ok());
+
+        let mut acl = config("events");
+        acl.username = Some("rustmailer".into());
+        assert!(acl.validate().is_err());
+        acl.password = Some("p@ss/word".into());
+        assert!(acl.validate().is_ok());
+        #[cfg(feature = "redis")]
+        assert!(acl.create_client().is_ok());
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::Object;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+
+use crate::{decrypt, modules::error::RustMailerResult};
+
+/// HMAC-SHA256 signatures of the delivered payload, per active secret: a `v1=<hex>` entry
+/// over the payload, and a `v2=<hex>` entry over `<timestamp>.<payload>`, which receivers
+/// should prefer as it also authenticates `X-RustMailer-Timestamp`.
+pub const SIGNATURE_HEADER: &str = "X-RustMailer-Signature";
+/// Time (Unix epoch seconds) the payload was signed at. Receivers reject deliveries whose
+/// timestamp is too old to protect against replays.
+pub const TIMESTAMP_HEADER: &str = "X-RustMailer-Timestamp";
+/// Identifier of the current signing secret of the hook.
+pub const SIGNATURE_KEY_ID_HEADER: &str = "X-RustMailer-Signature-Key-Id";
+/// Schema version of the event the payload was built from.
+pub const SCHEMA_VERSION_HEADER: &str = "X-RustMailer-Schema-Version";
+/// Present on deliveries of simulated events, which receivers should not act upon.
+pub const TEST_EVENT_HEADER: &str = "X-RustMailer-Test";
+
+pub const DEFAULT_SECRET_OVERLAP_SECS: u64 = 24 * 60 * 60;
+pub const MAX_SECRET_OVERLAP_SECS: u64 = 30 * 24 * 60 * 60;
+/// Minimum length of a signing secret chosen by the user.
+pub const MIN_SECRET_LEN: usize = 16;
+
+/// A secret used to sign the payloads delivered by an event hook.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct HookSigningSecret {
+    /// Identifier of the secret, sent in the `X-RustMailer-Signature-Key-Id` header while
+    /// the secret is current.
+    pub key_id: String,
+    /// The secret, encrypted at rest. Never returned by the API.
+    #[oai(skip)]
+    pub secret: String,
+    /// Timestamp (in milliseconds) when the secret was created.
+    pub created_at: i64,
+    /// Timestamp (in milliseconds) when a rotated-out secret stops being used.
+    /// `None` for the current secret.
+    pub expires_at: Option<i64>,
+}
+
+impl HookSigningSecret {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// Makes `new` the current secret. The previous current secret keeps signing payloads
+/// until `now + overlap_ms`, or is dropped right away if `overlap_ms` is 0; secrets rotated
+/// out before it are dropped, so at most two secrets are ever active.
+pub fn rotate_secrets(
+    secrets: &[HookSigningSecret],
+    new: HookSigningSecret,
+    overlap_ms: i64,
+    now: i64,
+) -> Vec<HookSigningSecret> {
+    let mut rotated = vec![new];
+    if overlap_ms > 0 {
+        if let Some(current) = secrets.iter().find(|s| s.expires_at.is_none()) {
+            rotated.push(HookSigningSecret {
+                expires_at: Some(now + overlap_ms),
+                ..current.clone()
+            });
+        }
+    }
+    rotated
+}
+
+/// Computes the signature headers of a payload, or none if the hook has no active secret.
+pub fn signature_headers(
+    secrets: &[HookSigningSecret],
+    body: &[u8],
+    now: i64,
+) -> RustMailerResult<Vec<(String, String)>> {
+    let active: Vec<&HookSigningSecret> = secrets.iter().filter(|s| s.is_active(now)).collect();
+    if active.is_empty() {
+        return Ok(Vec::new());
+    }
+    let timestamp = (now / 1000).to_string();
+    let mut signatures = Vec::with_capacity(active.len() * 2);
+    for secret in &active {
+        let secret = decrypt!(&secret.secret)?;
+        signatures.push(format!("v1={}", sign(&secret, body)));
+        let timestamped = sign_timestamped(&secret, &timestamp, body);
+        signatures.push(format!("v2={timestamped}"));
+    }
+    let mut headers = vec![
+        (SIGNATURE_HEADER.to_string(), signatures.join(",")),
+        (TIMESTAMP_HEADER.to_string(), timestamp),
+    ];
+    if let Some(current) = active.iter().find(|s| s.expires_at.is_none()) {
+        headers.push((SIGNATURE_KEY_ID_HEADER.to_string(), current.key_id.clone()));
+    }
+    Ok(headers)
+}
+
+/// Hex-encoded HMAC-SHA256 of `body` keyed with `secret`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    hex::encode(hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()), body).as_ref())
+}
+
+/// Hex-encoded HMAC-SHA256 of `<timestamp>.<body>` keyed with `secret`.
+pub fn sign_timestamped(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut context = hmac::Context::with_key(&key);
+    context.update(timestamp.as_bytes());
+    context.update(b".");
+    context.update(body);
+    hex::encode(context.sign().as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret(key_id: &str, expires_at: Option<i64>) -> HookSigningSecret {
+        HookSigningSecret {
+            key_id: key_id.into(),
+            secret: format!("encrypted-{key_id}"),
+            created_at: 0,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn timestamped_signatures_cover_the_timestamp() {
+        let signature = sign_timestamped("secret", "1700000000", b"{}");
+        assert_eq!(signature, sign("secret", b"1700000000.{}"));
+        assert_ne!(signature, sign_timestamped("secret", "1700000001", b"{}"));
+    }
+
+    #[test]
+    fn rotation_keeps_the_previous_secret_during_the_overlap() {
+        let secrets = vec![secret("b", None), secret("a", Some(500))];
+        let rotated = rotate_secrets(&secrets, secret("c", None), 1_000, 100);
+        assert_eq!(rotated, vec![secret("c", None), secret("b", Some(1_100))]);
+
+        let rotated = rotate_secrets(&secrets, secret("c", None), 0, 100);
+        assert_eq!(rotated, vec![secret("c", None)]);
+
+        let rotated = rotate_secrets(&[], secret("a", None), 1_000, 100);
+        assert_eq!(rotated, vec![secret("a", None)]);
+    }
+
+    #[test]
+    fn expired_secrets_are_inactive() {
+        assert!(secret("a", None).is_active(100));
+        assert!(secret("a", Some(101)).is_active(100));
+        assert!(!secret("a", Some(100)).is_active(100));
+        assert_eq!(
+            signature_headers(&[secret("a", Some(50))], b"{}", 100).unwrap(),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    id,
+    modules::{
+        error::{code::ErrorCode, RustMailerResult},
+        hook::{
+            entity::EventHooks,
+            events::{EventType, EVENT_EXAMPLES},
+            task::EventHookTask,
+        },
+        settings::cli::SETTINGS,
+        tasks::queue::RustMailerTaskQueue,
+    },
+    raise_error, utc_now,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Object)]
+pub struct EventSimulationRequest {
+    /// The type of event to simulate.
+    pub event_type: EventType,
+    /// Fields of the example payload to replace, applied as a JSON merge patch (RFC 7396),
+    /// e.g. `{"subject": "Invoice #42", "bcc": null}`.
+    pub overrides: Option<Value>,
+}
+
+/// A simulated event queued for delivery to a hook.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Object)]
+pub struct SimulatedEvent {
+    /// ID of the hook task delivering the event, usable with the hook task endpoints.
+    pub task_id: u64,
+    /// The event as queued, before the VRL script of the hook is applied.
+    pub event: Value,
+}
+
+/// Synthesizes an event from the examples and queues it for delivery to the hook.
+///
+/// The event goes through the same pipeline as real events (VRL script, signing, headers
+/// and retries), whether or not the hook watches its type or is enabled. It carries
+/// `"test": true`, and its delivery the `X-RustMailer-Test` header.
+pub async fn simulate_event(
+    hook: &EventHooks,
+    request: EventSimulationRequest,
+) -> RustMailerResult<SimulatedEvent> {
+    let event = synthesize_event(hook, &request)?;
+    let payload = &event["payload"];
+    let account_id = payload["account_id"]
+        .as_u64()
+        .or(hook.account_id)
+        .unwrap_or_default();
+    let account_email = payload["account_email"]
+        .as_str()
+        .map(String::from)
+        .or_else(|| hook.email.clone())
+        .unwrap_or_default();
+
+    let task = EventHookTask {
+        event_hook_id: hook.id,
+        account_id,
+        account_email,
+        event_type: request.event_type,
+        event: event.clone(),
+        test: true,
+        replay_of: None,
+    };
+    let meta = RustMailerTaskQueue::get()?.submit_task(task, None).await?;
+    Ok(SimulatedEvent {
+        task_id: meta.id,
+        event,
+    })
+}
+
+fn synthesize_event(
+    hook: &EventHooks,
+    request: &EventSimulationRequest,
+) -> RustMailerResult<Value> {
+    let mut event = EVENT_EXAMPLES
+        .get(request.event_type.to_string())
+        .cloned()
+        .ok_or_else(|| {
+            raise_error!(
+                format!("No example available for event type {}", request.event_type),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+    event["event_id"] = id!(96).into();
+    event["timestamp"] = utc_now!().into();
+    event["instance_url"] = SETTINGS.rustmailer_public_url.clone().into();
+    event["test"] = true.into();
+
+    let payload = &mut event["payload"];
+    // Events delivered to an account hook are about the account of the hook.
+    if let (Some(account_id), Some(email)) = (hook.account_id, &hook.email) {
+        if payload.get("account_id").is_some() {
+            payload["account_id"] = account_id.into();
+        }
+        if payload.get("account_email").is_some() {
+            payload["account_email"] = email.clone().into();
+        }
+    }
+    if let Some(overrides) = &request.overrides {
+        if !overrides.is_object() {
+            return Err(raise_error!(
+                "'overrides' must be a JSON object".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        merge_patch(payload, overrides);
+    }
+    Ok(event)
+}
+
+/// Applies a JSON merge patch (RFC 7396): objects are merged recursively, `null` removes
+/// a field and any other value replaces it.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let target = target
+        .as_object_mut()
+        .expect("target was just made an object");
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn applies_merge_patches() {
+        let mut payload = json!({
+            "subject": "Meeting Notes",
+            "bcc": ["a@example.com"],
+            "message": {"plain": {"content": "Hello", "truncated": false}, "html": "<p>Hello</p>"}
+        });
+        merge_patch(
+            &mut payload,
+            &json!({"subject": "Invoice #42", "bcc": null, "message": {"html": null, "plain": {"content": "Hi"}}}),
+        );
+        assert_eq!(
+            payload,
+            json!({
+                "subject": "Invoice #42",
+                "message": {"plain": {"content": "Hi", "truncated": false}}
+            })
+        );
+    }
+
+    #[test]
+    fn synthesizes_test_events_for_the_hook_account() {
+        let hook = EventHooks {
+            id: 1,
+            account_id: Some(42),
+            email: Some("owner@example.com".into()),
+            ..Default::default()
+        };
+        let request = EventSimulationRequest {
+            event_type: EventType::EmailBounce,
+            overrides: Some(json!({"subject": "Undeliverable: Invoice #42"})),
+        };
+        let event = synthesize_event(&hook, &request).unwrap();
+        assert_eq!(event["event_type"], "EmailBounce");
+        assert_eq!(event["test"], true);
+        assert_eq!(event["payload"]["account_id"], 42);
+        assert_eq!(event["payload"]["account_email"], "owner@example.com");
+        assert_eq!(event["payload"]["subject"], "Undeliverable: Invoice #42");
+
+        let invalid = EventSimulationRequest {
+            event_type: EventType::EmailBounce,
+            overrides: Some(json!(["subject"])),
+        };
+        assert!(synthesize_event(&hook, &invalid).is_err());
+    }
+}
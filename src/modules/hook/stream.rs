@@ -0,0 +1,305 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    collections::{BTreeSet, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, LazyLock, Mutex,
+    },
+    time::Duration,
+};
+
+use dashmap::DashMap;
+use futures::{stream, Stream};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::modules::{
+    common::auth::ClientContext,
+    error::RustMailerResult,
+    hook::events::{EventType, RustMailerEvent},
+};
+
+/// Subscriptions of the clients streaming events, fed by the event channel.
+pub static EVENT_SUBSCRIBERS: LazyLock<EventSubscribers> = LazyLock::new(EventSubscribers::new);
+
+/// Number of events a subscriber may fall behind before it misses some.
+const SUBSCRIBER_BUFFER: usize = 1024;
+/// Number of recent events kept for subscribers resuming after a disconnection.
+const REPLAY_BUFFER: usize = 1024;
+
+/// An event as queued on the event channel, with the account it is about.
+#[derive(Clone, Debug, Serialize)]
+pub struct LiveEvent {
+    pub account_id: u64,
+    pub account_email: String,
+    pub event: RustMailerEvent,
+}
+
+/// The events a subscriber receives.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EventFilter {
+    /// Event types to receive. All types if empty.
+    pub event_types: HashSet<EventType>,
+    /// Accounts whose events to receive. All accounts if `None`.
+    pub account_ids: Option<BTreeSet<u64>>,
+}
+
+impl EventFilter {
+    /// A filter for the requested event types and accounts, limited to the accounts
+    /// accessible to `context`. No requested account means all accessible accounts.
+    pub fn for_context(
+        context: &ClientContext,
+        event_types: Vec<EventType>,
+        account_ids: Vec<u64>,
+    ) -> RustMailerResult<Self> {
+        for account_id in &account_ids {
+            context.require_account_access(*account_id)?;
+        }
+        let account_ids = match (account_ids.is_empty(), context.accessible_accounts()?) {
+            (false, _) => Some(account_ids.into_iter().collect()),
+            (true, Some(accessible)) => Some(accessible.iter().map(|a| a.id).collect()),
+            (true, None) => None,
+        };
+        Ok(Self {
+            event_types: event_types.into_iter().collect(),
+            account_ids,
+        })
+    }
+
+    pub fn matches(&self, account_id: u64, event_type: &EventType) -> bool {
+        (self.event_types.is_empty() || self.event_types.contains(event_type))
+            && self
+                .account_ids
+                .as_ref()
+                .is_none_or(|ids| ids.contains(&account_id))
+    }
+}
+
+pub struct EventSubscribers {
+    sender: broadcast::Sender<Arc<LiveEvent>>,
+    filters: Arc<DashMap<u64, EventFilter>>,
+    next_id: AtomicU64,
+    /// The most recently published events, oldest first.
+    recent: Mutex<VecDeque<Arc<LiveEvent>>>,
+}
+
+impl EventSubscribers {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(SUBSCRIBER_BUFFER);
+        Self {
+            sender,
+            filters: Arc::new(DashMap::new()),
+            next_id: AtomicU64::new(1),
+            recent: Mutex::new(VecDeque::with_capacity(REPLAY_BUFFER)),
+        }
+    }
+
+    pub fn subscribe(&self, filter: EventFilter) -> EventSubscription {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.filters.insert(id, filter.clone());
+        EventSubscription {
+            id,
+            filter,
+            receiver: self.sender.subscribe(),
+            filters: self.filters.clone(),
+            linger: None,
+        }
+    }
+
+    /// Subscribes, also returning the recent events matching the filter that were published
+    /// after the event with ID `last_event_id`. Nothing is returned if that event is no longer
+    /// among the recent ones, e.g. after a restart.
+    ///
+    /// Every event is either returned or received by the subscription, never both.
+    pub fn subscribe_since(
+        &self,
+        filter: EventFilter,
+        last_event_id: Option<u64>,
+    ) -> (Vec<Arc<LiveEvent>>, EventSubscription) {
+        // Publishing holds the lock while sending, so no event slips in between.
+        let recent = self.recent.lock().unwrap();
+        let missed = last_event_id
+            .and_then(|last| recent.iter().position(|e| e.event.event_id == last))
+            .map(|position| {
+                recent
+                    .iter()
+                    .skip(position + 1)
+                    .filter(|e| filter.matches(e.account_id, &e.event.event_type))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        (missed, self.subscribe(filter))
+    }
+
+    /// Whether a subscriber receives events of this type for the account. Producers that
+    /// only build events watched by a hook build them for subscribers too.
+    pub fn watching(&self, account_id: u64, event_type: &EventType) -> bool {
+        self.filters
+            .iter()
+            .any(|filter| filter.matches(account_id, event_type))
+    }
+
+    /// Hands an event to the subscribers. Cheap when there are none.
+    pub fn publish(&self, account_id: u64, account_email: &str, event: &RustMailerEvent) {
+        if !self.watching(account_id, &event.event_type) {
+            return;
+        }
+        let event = Arc::new(LiveEvent {
+            account_id,
+            account_email: account_email.into(),
+            event: event.clone(),
+        });
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == REPLAY_BUFFER {
+            recent.pop_front();
+        }
+        recent.push_back(event.clone());
+        // Sending only fails when the last subscriber went away in the meantime.
+        let _ = self.sender.send(event);
+    }
+}
+
+/// The events received by a streaming client. Dropping it ends the subscription.
+pub struct EventSubscription {
+    id: u64,
+    filter: EventFilter,
+    receiver: broadcast::Receiver<Arc<LiveEvent>>,
+    filters: Arc<DashMap<u64, EventFilter>>,
+    linger: Option<Duration>,
+}
+
+impl EventSubscription {
+    /// Keeps the events of the filter produced for `duration` after the subscription is
+    /// dropped, so that a client reconnecting within it can resume where it left off.
+    pub fn linger(mut self, duration: Duration) -> Self {
+        self.linger = Some(duration);
+        self
+    }
+
+    /// The next event matching the filter. Events missed because the subscriber fell too
+    /// far behind are skipped.
+    pub async fn next(&mut self) -> Option<Arc<LiveEvent>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event)
+                    if self
+                        .filter
+                        .matches(event.account_id, &event.event.event_type) =>
+                {
+                    return Some(event)
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!(
+                        "Event subscription {} fell behind and missed {} events",
+                        self.id, missed
+                    );
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Arc<LiveEvent>> + Send {
+        stream::unfold(self, |mut subscription| async move {
+            subscription.next().await.map(|event| (event, subscription))
+        })
+    }
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        let (id, filters) = (self.id, self.filters.clone());
+        match (self.linger, tokio::runtime::Handle::try_current()) {
+            (Some(linger), Ok(handle)) => {
+                handle.spawn(async move {
+                    tokio::time::sleep(linger).await;
+                    filters.remove(&id);
+                });
+            }
+            _ => {
+                filters.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::hook::events::{payload::MailboxDeletion, EventPayload};
+
+    fn event(event_type: EventType) -> RustMailerEvent {
+        RustMailerEvent::new(
+            event_type,
+            EventPayload::MailboxDeletion(MailboxDeletion {
+                account_id: 1,
+                account_email: "user@example.com".into(),
+                mailbox_names: vec!["Archive".into()],
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn delivers_matching_events_to_subscribers() {
+        let subscribers = EventSubscribers::new();
+        assert!(!subscribers.watching(1, &EventType::MailboxDeletion));
+
+        let mut subscription = subscribers.subscribe(EventFilter {
+            event_types: HashSet::from([EventType::MailboxDeletion]),
+            account_ids: Some(BTreeSet::from([1])),
+        });
+        assert!(subscribers.watching(1, &EventType::MailboxDeletion));
+        assert!(!subscribers.watching(2, &EventType::MailboxDeletion));
+        assert!(!subscribers.watching(1, &EventType::EmailOpened));
+
+        subscribers.publish(2, "other@example.com", &event(EventType::MailboxDeletion));
+        subscribers.publish(1, "user@example.com", &event(EventType::EmailOpened));
+        subscribers.publish(1, "user@example.com", &event(EventType::MailboxDeletion));
+        let received = subscription.next().await.unwrap();
+        assert_eq!(received.account_id, 1);
+        assert_eq!(received.event.event_type, EventType::MailboxDeletion);
+
+        drop(subscription);
+        assert!(!subscribers.watching(1, &EventType::MailboxDeletion));
+    }
+
+    #[tokio::test]
+    async fn resumes_after_the_last_received_event() {
+        let subscribers = EventSubscribers::new();
+        let filter = EventFilter {
+            event_types: HashSet::from([EventType::MailboxDeletion]),
+            account_ids: Some(BTreeSet::from([1])),
+        };
+        let subscription = subscribers.subscribe(filter.clone());
+        let published: Vec<_> = (0..3)
+            .map(|_| {
+                let event = event(EventType::MailboxDeletion);
+                subscribers.publish(1, "user@example.com", &event);
+                event.event_id
+            })
+            .collect();
+        subscribers.publish(2, "other@example.com", &event(EventType::MailboxDeletion));
+        drop(subscription);
+
+        let (missed, mut subscription) =
+            subscribers.subscribe_since(filter.clone(), Some(published[0]));
+        let missed: Vec<_> = missed.iter().map(|e| e.event.event_id).collect();
+        assert_eq!(missed, published[1..]);
+
+        let live = event(EventType::MailboxDeletion);
+        subscribers.publish(1, "user@example.com", &live);
+        assert_eq!(
+            subscription.next().await.unwrap().event.event_id,
+            live.event_id
+        );
+
+        let (missed, _subscription) = subscribers.subscribe_since(filter, Some(u64::MAX));
+        assert!(missed.is_empty());
+    }
+}
@@ -5,10 +5,19 @@
 use std::collections::HashMap;
 use std::time::Instant;
 
+use crate::modules::account::migration::AccountModel;
 use crate::modules::common::http::HttpClient;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::error::RustMailerError;
+use crate::modules::fault::{FaultInjector, FaultTarget};
+use crate::modules::hook::delivery::{DeliveryAttempt, HookDelivery};
 use crate::modules::hook::entity::EventHooks;
+use crate::modules::hook::kafka;
+use crate::modules::hook::redis;
+use crate::modules::hook::signing::{
+    signature_headers, HookSigningSecret, SCHEMA_VERSION_HEADER, TEST_EVENT_HEADER,
+};
+use crate::modules::hook::stream::EVENT_SUBSCRIBERS;
 use crate::modules::hook::vrl::payload::VrlScriptTestRequest;
 use crate::modules::hook::vrl::resolve_vrl_input;
 use crate::modules::metrics::{
@@ -47,19 +56,27 @@ pub struct EventHookTask {
     pub account_email: String,
     pub event_type: EventType,
     pub event: serde_json::Value,
+    /// Set for events synthesized by the simulation endpoint.
+    #[serde(default)]
+    pub test: bool,
+    /// The hook delivery this event is replayed from, if any.
+    #[serde(default)]
+    pub replay_of: Option<u64>,
 }
 
 impl EventHookTask {
     async fn event_watched(account_id: u64, event_type: EventType) -> RustMailerResult<bool> {
+        if EVENT_SUBSCRIBERS.watching(account_id, &event_type) {
+            return Ok(true);
+        }
         let account_hook = EventHooks::get_by_account_id(account_id)
             .await?
             .map_or(false, |hook| {
                 hook.enabled && hook.watched_events.contains(&event_type)
             });
-        let global_hook = EventHooks::global_hooks()
+        let global_hook = !Self::global_hooks_for(account_id, &[event_type])
             .await?
-            .iter()
-            .any(|hook| hook.enabled && hook.watched_events.contains(&event_type));
+            .is_empty();
 
         Ok(account_hook || global_hook)
     }
@@ -114,8 +131,45 @@ impl EventHookTask {
         EventHookTask::event_watched(account_id, EventType::EmailLinkClicked).await
     }
 
+    pub async fn is_watching_email_loop_detected(account_id: u64) -> RustMailerResult<bool> {
+        EventHookTask::event_watched(account_id, EventType::EmailLoopDetected).await
+    }
+
+    pub async fn is_watching_account_auto_paused(account_id: u64) -> RustMailerResult<bool> {
+        EventHookTask::event_watched(account_id, EventType::AccountAutoPaused).await
+    }
+
+    pub async fn is_watching_attachment_policy_triggered(
+        account_id: u64,
+    ) -> RustMailerResult<bool> {
+        EventHookTask::event_watched(account_id, EventType::AttachmentPolicyTriggered).await
+    }
+
+    pub async fn is_watching_account_sync_error(account_id: u64) -> RustMailerResult<bool> {
+        EventHookTask::event_watched(account_id, EventType::AccountSyncError).await
+    }
+
+    pub async fn is_watching_account_authentication_failed(
+        account_id: u64,
+    ) -> RustMailerResult<bool> {
+        EventHookTask::event_watched(account_id, EventType::AccountAuthenticationFailed).await
+    }
+
+    /// Whether an instance-wide event, about no account, is watched. Such events are queued
+    /// with the account ID `0` and only reach global hooks outside any workspace and
+    /// subscribers not limited to accounts.
+    pub async fn is_watching_instance_event(event_type: EventType) -> RustMailerResult<bool> {
+        EventHookTask::event_watched(0, event_type).await
+    }
+
     pub async fn bounce_watched(account_id: u64) -> RustMailerResult<bool> {
         let target_events = &[EventType::EmailBounce, EventType::EmailFeedBackReport];
+        if target_events
+            .iter()
+            .any(|e| EVENT_SUBSCRIBERS.watching(account_id, e))
+        {
+            return Ok(true);
+        }
 
         let account_hook = EventHooks::get_by_account_id(account_id)
             .await?
@@ -125,16 +179,41 @@ impl EventHookTask {
                         .iter()
                         .any(|e| hook.watched_events.contains(e))
             });
-        let global_hook = EventHooks::global_hooks().await?.iter().any(|hook| {
-            hook.enabled
-                && target_events
-                    .iter()
-                    .any(|e| hook.watched_events.contains(e))
-        });
+        let global_hook = !Self::global_hooks_for(account_id, target_events)
+            .await?
+            .is_empty();
 
         Ok(account_hook || global_hook)
     }
 
+    /// Enabled global hooks watching any of `events` that apply to the account.
+    async fn global_hooks_for(
+        account_id: u64,
+        events: &[EventType],
+    ) -> RustMailerResult<Vec<EventHooks>> {
+        let hooks: Vec<EventHooks> = EventHooks::global_hooks()
+            .await?
+            .into_iter()
+            .filter(|hook| hook.enabled && events.iter().any(|e| hook.watched_events.contains(e)))
+            .collect();
+        // The account is only looked up when a hook is limited to tagged accounts or to the
+        // accounts of a workspace.
+        if hooks
+            .iter()
+            .all(|hook| hook.account_tags.is_none() && hook.workspace_id.is_none())
+        {
+            return Ok(hooks);
+        }
+        let (tags, workspace_id) = AccountModel::find(account_id)
+            .await?
+            .map(|account| (account.tags, account.workspace_id))
+            .unwrap_or_default();
+        Ok(hooks
+            .into_iter()
+            .filter(|hook| hook.matches_account_tags(&tags) && hook.matches_workspace(workspace_id))
+            .collect())
+    }
+
     pub async fn get_matching_hooks(
         account_id: u64,
         event_type: &EventType,
@@ -143,11 +222,8 @@ impl EventHookTask {
             .await?
             .filter(|hook| hook.enabled && hook.watched_events.contains(event_type));
 
-        let global_hooks = EventHooks::global_hooks()
-            .await?
-            .into_iter()
-            .filter(|hook| hook.enabled && hook.watched_events.contains(event_type))
-            .collect::<Vec<_>>();
+        let global_hooks =
+            Self::global_hooks_for(account_id, std::slice::from_ref(event_type)).await?;
 
         let mut result = Vec::new();
         if let Some(hook) = account_hook {
@@ -218,9 +294,20 @@ impl Task for EventHookTask {
                 },
             )
             .await?;
+            let delivery = HookDelivery::start(&self, task_id, &event_hook);
+            let mut attempt = DeliveryAttempt::default();
             let start = Instant::now();
 
-            match send_event(task_id, self.event, self.event_type, event_hook).await {
+            match send_event(
+                task_id,
+                self.account_id,
+                self.event,
+                self.event_type,
+                event_hook,
+                &mut attempt,
+            )
+            .await
+            {
                 Ok(()) => {
                     let update = InternalEventHookUpdateRequest {
                         increase_success_count: Some(true),
@@ -233,6 +320,7 @@ impl Task for EventHookTask {
                     RUSTMAILER_EVENT_DISPATCH_DURATION_SECONDS_BY_TYPE_STATUS_AND_DESTINATION
                         .with_label_values(&[SUCCESS, destination])
                         .observe(elapsed.as_secs_f64());
+                    delivery.finish(attempt, elapsed, None).await;
                     EventHooks::internal_update(self.event_hook_id, update).await?;
                     Ok(())
                 }
@@ -245,6 +333,9 @@ impl Task for EventHookTask {
                     RUSTMAILER_EVENT_DISPATCH_DURATION_SECONDS_BY_TYPE_STATUS_AND_DESTINATION
                         .with_label_values(&[FAILURE, destination])
                         .observe(elapsed.as_secs_f64());
+                    delivery
+                        .finish(attempt, elapsed, Some(error_msg.clone()))
+                        .await;
                     let update = InternalEventHookUpdateRequest {
                         increase_failure_count: Some(true),
                         last_error: Some(error_msg.clone()),
@@ -284,14 +375,25 @@ async fn process_payload(
 
 async fn send_event(
     task_id: u64,
+    account_id: u64,
     event: serde_json::Value,
     event_type: EventType,
     event_hook: EventHooks,
+    attempt: &mut DeliveryAttempt,
 ) -> RustMailerResult<()> {
-    let task = RustMailerTaskQueue::get()?
-        .get_hook_task(task_id)
-        .await?
-        .map(|t| t.headers());
+    let hook_task = RustMailerTaskQueue::get()?.get_hook_task(task_id).await?;
+    attempt.attempt = hook_task
+        .as_ref()
+        .map_or(0, |t| t.retry_count.unwrap_or_default() as u32 + 1);
+    FaultInjector::inject(FaultTarget::Hook, account_id).await?;
+    let mut headers = hook_task.map(|t| t.headers()).unwrap_or_default();
+    // Events queued before schema versioning was introduced carry no version.
+    let schema_version = event
+        .get("schema_version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(1);
+    headers.insert(SCHEMA_VERSION_HEADER.into(), schema_version.to_string());
+    let vrl_script = event_hook.vrl_script_for(&event_type).map(String::from);
     match event_hook.hook_type {
         HookType::Http => {
             let http_config = event_hook.http.ok_or_else(|| {
@@ -301,23 +403,24 @@ async fn send_event(
                 )
             })?;
 
-            let headers = (!http_config.custom_headers.is_empty())
+            let custom_headers = (!http_config.custom_headers.is_empty())
                 .then(|| http_config.custom_headers.into_iter().collect());
-            let payload = process_payload(event, event_hook.vrl_script).await?;
+            let payload = process_payload(event, vrl_script).await?;
 
             if payload != serde_json::Value::Null {
+                sign_payload(&mut headers, &event_hook.signing_secrets, &payload)?;
                 let client = HttpClient::new(event_hook.use_proxy).await?;
                 let response = client
                     .send_json_request(
-                        task,
+                        Some(headers),
                         http_config.http_method,
                         &http_config.target_url,
                         &payload,
-                        headers,
+                        custom_headers,
                     )
                     .await?;
 
-                handle_response(response).await?;
+                handle_response(response, attempt).await?;
             }
             Ok(())
         }
@@ -330,27 +433,93 @@ async fn send_event(
             })?;
 
             let executor = NATS_EXECUTORS.get(&nats_config).await?;
-            let payload = process_payload(event, event_hook.vrl_script).await?;
+            let payload = process_payload(event, vrl_script).await?;
+
+            if payload != serde_json::Value::Null {
+                sign_payload(&mut headers, &event_hook.signing_secrets, &payload)?;
+                executor
+                    .publish(Some(headers), account_id, event_type, payload)
+                    .await?;
+            }
+            Ok(())
+        }
+        #[cfg(feature = "kafka")]
+        HookType::Kafka => {
+            let kafka_config = event_hook.kafka.ok_or_else(|| {
+                raise_error!(
+                    "Missing Kafka config in event hook".into(),
+                    ErrorCode::MissingConfiguration
+                )
+            })?;
+
+            let executor = kafka::executor::KAFKA_EXECUTORS.get(&kafka_config).await?;
+            let payload = process_payload(event, vrl_script).await?;
+
+            if payload != serde_json::Value::Null {
+                sign_payload(&mut headers, &event_hook.signing_secrets, &payload)?;
+                executor
+                    .publish(Some(headers), account_id, event_type, payload)
+                    .await?;
+            }
+            Ok(())
+        }
+        #[cfg(not(feature = "kafka"))]
+        HookType::Kafka => kafka::ensure_supported(),
+        #[cfg(feature = "redis")]
+        HookType::Redis => {
+            let redis_config = event_hook.redis.ok_or_else(|| {
+                raise_error!(
+                    "Missing Redis config in event hook".into(),
+                    ErrorCode::MissingConfiguration
+                )
+            })?;
+
+            let executor = redis::executor::REDIS_EXECUTORS.get(&redis_config).await?;
+            let payload = process_payload(event, vrl_script).await?;
 
             if payload != serde_json::Value::Null {
-                executor.publish(task, event_type, payload).await?;
+                sign_payload(&mut headers, &event_hook.signing_secrets, &payload)?;
+                executor
+                    .publish(Some(headers), account_id, event_type, payload)
+                    .await?;
             }
             Ok(())
         }
+        #[cfg(not(feature = "redis"))]
+        HookType::Redis => redis::ensure_supported(),
+    }
+}
+
+/// Adds the signature headers of the hook, computed over the payload as serialized on the wire.
+fn sign_payload(
+    headers: &mut HashMap<String, String>,
+    secrets: &[HookSigningSecret],
+    payload: &serde_json::Value,
+) -> RustMailerResult<()> {
+    if secrets.is_empty() {
+        return Ok(());
     }
+    let body = serde_json::to_vec(payload)
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    headers.extend(signature_headers(secrets, &body, utc_now!())?);
+    Ok(())
 }
 
-async fn handle_response(response: reqwest::Response) -> RustMailerResult<()> {
-    if !response.status().is_success() {
-        let status = response.status();
-        let url = response.url().clone();
-        let headers = response.headers().clone();
+async fn handle_response(
+    response: reqwest::Response,
+    attempt: &mut DeliveryAttempt,
+) -> RustMailerResult<()> {
+    let status = response.status();
+    let url = response.url().clone();
+    let headers = response.headers().clone();
 
-        let body = match response.text().await {
-            Ok(text) => text,
-            Err(e) => format!("<failed to read body: {}>", e),
-        };
+    let body = match response.text().await {
+        Ok(text) => text,
+        Err(e) => format!("<failed to read body: {}>", e),
+    };
+    attempt.set_response(status.as_u16(), &body);
 
+    if !status.is_success() {
         // Log detailed error information
         tracing::error!(
             status = %status,
@@ -383,6 +552,7 @@ pub struct SendEventHookTask {
     pub account_email: String,
     pub event: serde_json::Value,
     pub event_type: EventType,
+    pub test: bool,
 }
 
 impl SendEventHookTask {
@@ -398,6 +568,9 @@ impl SendEventHookTask {
         if let Some(retry_count) = self.retry_count {
             headers.insert("X-Task-Retry-Count".into(), retry_count.to_string());
         }
+        if self.test {
+            headers.insert(TEST_EVENT_HEADER.into(), "true".into());
+        }
 
         headers
     }
@@ -424,6 +597,7 @@ impl TryFrom<&TaskMetaEntity> for SendEventHookTask {
             account_email: event_hook_task.account_email,
             event: event_hook_task.event,
             event_type: event_hook_task.event_type,
+            test: event_hook_task.test,
         })
     }
 }
@@ -14,8 +14,13 @@ use crate::{
     modules::{
         common::Addr,
         hook::{
-            events::{payload::MailboxDeletion, EventPayload, EventType, RustMailerEvent},
+            entity::{EventHooks, EventVrlScript},
+            events::{
+                payload::MailboxDeletion, EventPayload, EventType, RustMailerEvent,
+                EVENT_SCHEMA_VERSION,
+            },
             nats::{executor::NATS_EXECUTORS, NatsAuthType, NatsConfig},
+            vrl::{payload::VrlSandboxRequest, run_vrl_sandbox},
         },
     },
     utc_now,
@@ -180,6 +185,54 @@ fn test_handle_missing_regex_match() {
     assert_eq!(result, value!(null));
 }
 
+#[test]
+fn test_vrl_script_for_event_type() {
+    let mut hook = EventHooks {
+        vrl_script: Some(".default = true".into()),
+        event_vrl_scripts: vec![EventVrlScript {
+            event_type: EventType::EmailBounce,
+            script: ".bounce = true".into(),
+        }],
+        ..Default::default()
+    };
+    assert_eq!(
+        hook.vrl_script_for(&EventType::EmailBounce),
+        Some(".bounce = true")
+    );
+    assert_eq!(
+        hook.vrl_script_for(&EventType::EmailOpened),
+        Some(".default = true")
+    );
+    hook.vrl_script = None;
+    assert_eq!(hook.vrl_script_for(&EventType::EmailOpened), None);
+}
+
+#[tokio::test]
+async fn test_vrl_sandbox() {
+    let request = VrlSandboxRequest {
+        program: r#"if .event_type == "EmailBounce" { . = null } else { .checked = true }"#.into(),
+        event_types: Some(vec![EventType::EmailBounce, EventType::EmailOpened]),
+        sample: Some("not json".into()),
+    };
+    let result = run_vrl_sandbox(request).await.unwrap();
+    assert!(result.compile_error.is_none());
+    assert_eq!(result.cases.len(), 3);
+    assert_eq!(result.cases[0].event_type, Some(EventType::EmailBounce));
+    assert!(result.cases[0].dropped);
+    assert!(!result.cases[1].dropped);
+    assert_eq!(result.cases[1].result.as_ref().unwrap()["checked"], true);
+    assert!(result.cases[2].event_type.is_none());
+    assert!(result.cases[2].error.is_some());
+
+    let invalid = VrlSandboxRequest {
+        program: ".foo = ".into(),
+        ..Default::default()
+    };
+    let result = run_vrl_sandbox(invalid).await.unwrap();
+    assert!(result.compile_error.is_some());
+    assert!(result.cases.is_empty());
+}
+
 #[tokio::test]
 async fn test_create_jetstream_producer_and_send_message() {
     let config = NatsConfig {
@@ -191,6 +244,7 @@ async fn test_create_jetstream_producer_and_send_message() {
         password: None,
         stream_name: "test_stream".to_string(),
         namespace: "test.ns".to_string(),
+        subject_template: None,
     };
 
     let nats = NATS_EXECUTORS.get(&config).await.unwrap();
@@ -200,22 +254,26 @@ async fn test_create_jetstream_producer_and_send_message() {
         address: Some(email.to_string()),
     };
 
+    let account_id = id!(64);
     let payload = MailboxDeletion {
-        account_id: id!(64),
+        account_id,
         account_email: "test@example.com".into(),
         mailbox_names: vec!["test_mailbox".into()],
     };
 
     let event = RustMailerEvent {
+        schema_version: EVENT_SCHEMA_VERSION,
         event_id: id!(96),
         event_type: EventType::MailboxDeletion,
         instance_url: "http://localhost:15630".into(),
         timestamp: utc_now!(),
         payload: EventPayload::MailboxDeletion(payload),
+        test: false,
     };
 
     nats.publish(
         None,
+        account_id,
         EventType::MailboxDeletion,
         event.to_json_value().unwrap(),
     )
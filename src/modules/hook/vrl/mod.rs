@@ -3,10 +3,16 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::{
-    modules::error::{code::ErrorCode, RustMailerResult},
+    modules::{
+        error::{code::ErrorCode, RustMailerResult},
+        hook::events::{EventType, EVENT_EXAMPLES},
+    },
     raise_error,
 };
-use payload::{Outcome, ResolveResult, VrlScriptTestRequest};
+use payload::{
+    Outcome, ResolveResult, VrlSandboxCase, VrlSandboxRequest, VrlSandboxResult,
+    VrlScriptTestRequest,
+};
 use std::collections::BTreeMap;
 use vrl::{
     compiler::{compile_with_state, runtime::Runtime, CompileConfig, TargetValueRef},
@@ -89,21 +95,88 @@ fn resolve(input: VrlScriptTestRequest) -> RustMailerResult<Outcome> {
     }
 }
 
-pub fn compile_vrl_script(vrl_script: &str) -> RustMailerResult<()> {
+/// Runs a script against the event examples and the sample event of the request, the way
+/// hooks run it on the events they deliver.
+pub async fn run_vrl_sandbox(request: VrlSandboxRequest) -> RustMailerResult<VrlSandboxResult> {
+    if let Some(error) = compile_diagnostics(&request.program) {
+        return Ok(VrlSandboxResult {
+            compile_error: Some(error),
+            cases: Vec::new(),
+        });
+    }
+
+    let mut inputs: Vec<(Option<EventType>, String)> = match request.event_types {
+        Some(event_types) => event_types
+            .into_iter()
+            .map(|event_type| {
+                let example = EVENT_EXAMPLES.get(event_type.to_string()).ok_or_else(|| {
+                    raise_error!(
+                        format!("No example available for event type {}", event_type),
+                        ErrorCode::ResourceNotFound
+                    )
+                })?;
+                Ok((Some(event_type), example.to_string()))
+            })
+            .collect::<RustMailerResult<_>>()?,
+        None => EVENT_EXAMPLES
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, example)| {
+                serde_json::from_value(serde_json::Value::String(name.clone()))
+                    .ok()
+                    .map(|event_type| (Some(event_type), example.to_string()))
+            })
+            .collect(),
+    };
+    inputs.extend(request.sample.map(|sample| (None, sample)));
+
+    let mut cases = Vec::with_capacity(inputs.len());
+    for (event_type, event) in inputs {
+        let test = VrlScriptTestRequest {
+            program: request.program.clone(),
+            event: Some(event),
+        };
+        // An invalid sample only fails its own case.
+        let case = match resolve_vrl_input(test).await {
+            Ok(resolved) => VrlSandboxCase {
+                event_type,
+                dropped: resolved.result == Some(serde_json::Value::Null),
+                result: resolved.result,
+                error: resolved.error,
+            },
+            Err(e) => VrlSandboxCase {
+                event_type,
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        };
+        cases.push(case);
+    }
+    Ok(VrlSandboxResult {
+        compile_error: None,
+        cases,
+    })
+}
+
+fn compile_diagnostics(vrl_script: &str) -> Option<String> {
     let functions = vrl::stdlib::all();
     let state = TypeState::default();
     let config = CompileConfig::default();
-    match compile_with_state(vrl_script, &functions, &state, config) {
-        Ok(_) => Ok(()),
-        Err(diagnostics) => {
-            let msg = Formatter::new(&vrl_script, diagnostics).to_string();
-            Err(raise_error!(
-                format!(
-                    "VRL script contains syntax errors. Please fix before submission: {}",
-                    msg
-                ),
-                ErrorCode::VRLScriptSyntaxError
-            ))
-        }
+    compile_with_state(vrl_script, &functions, &state, config)
+        .err()
+        .map(|diagnostics| Formatter::new(vrl_script, diagnostics).to_string())
+}
+
+pub fn compile_vrl_script(vrl_script: &str) -> RustMailerResult<()> {
+    match compile_diagnostics(vrl_script) {
+        None => Ok(()),
+        Some(msg) => Err(raise_error!(
+            format!(
+                "VRL script contains syntax errors. Please fix before submission: {}",
+                msg
+            ),
+            ErrorCode::VRLScriptSyntaxError
+        )),
     }
 }
@@ -2,6 +2,7 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::hook::events::EventType;
 use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
 use vrl::core::Value;
@@ -28,3 +29,37 @@ pub struct ResolveResult {
     /// An error message describing the failure, if the operation was not successful.
     pub error: Option<String>,
 }
+
+/// A script to try against the event examples and an optional sample event, without
+/// saving it to a hook.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct VrlSandboxRequest {
+    /// The VRL script program to be tested.
+    pub program: String,
+    /// Optional. Limits the run to the examples of these event types. If `None`, the script
+    /// runs against the example of every event type.
+    pub event_types: Option<Vec<EventType>>,
+    /// Optional event data (JSON string), such as a real event, to also run the script on.
+    pub sample: Option<String>,
+}
+
+/// The outcome of a sandboxed script on one event.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct VrlSandboxCase {
+    /// The event type of the example, or `None` for the sample event.
+    pub event_type: Option<EventType>,
+    /// The transformed event, if the script succeeded.
+    pub result: Option<serde_json::Value>,
+    /// Whether the script turned the event into `null`, in which case it is not delivered.
+    pub dropped: bool,
+    /// An error message describing the failure, if the script failed on this event.
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct VrlSandboxResult {
+    /// The compiler diagnostics if the script does not compile, in which case it is not run.
+    pub compile_error: Option<String>,
+    /// The outcome of the script on each example, followed by the sample event if provided.
+    pub cases: Vec<VrlSandboxCase>,
+}
@@ -0,0 +1,69 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use chrono::DateTime;
+use mail_parser::MessageParser;
+
+use crate::modules::{cache::imap::mailbox::EnvelopeFlag, error::RustMailerResult};
+
+/// Formats a Unix timestamp (milliseconds) as an IMAP `date-time`, used as the internal
+/// date of an APPEND, e.g. `05-Mar-2025 14:02:11 +0000`.
+pub fn format_internal_date(timestamp_ms: i64) -> Option<String> {
+    DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|date| date.format("%d-%b-%Y %H:%M:%S +0000").to_string())
+}
+
+/// Reads the `Date` header of a raw message as a Unix timestamp in milliseconds.
+pub fn message_date(body: &[u8]) -> Option<i64> {
+    MessageParser::default()
+        .parse_headers(body)
+        .and_then(|headers| headers.date().map(|date| date.to_timestamp() * 1000))
+}
+
+/// Joins flags into the space-separated list of an APPEND flag parenthesized list.
+/// Returns `None` if there are no flags.
+pub fn format_append_flags(flags: &[EnvelopeFlag]) -> RustMailerResult<Option<String>> {
+    if flags.is_empty() {
+        return Ok(None);
+    }
+    let flags = flags
+        .iter()
+        .map(EnvelopeFlag::to_imap_string)
+        .collect::<RustMailerResult<Vec<_>>>()?;
+    Ok(Some(flags.join(" ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::cache::imap::mailbox::EmailFlag;
+
+    #[test]
+    fn formats_internal_date() {
+        assert_eq!(
+            format_internal_date(1_741_183_331_000).as_deref(),
+            Some("05-Mar-2025 14:02:11 +0000")
+        );
+    }
+
+    #[test]
+    fn reads_message_date() {
+        let body = b"Date: Wed, 05 Mar 2025 15:02:11 +0100\r\nSubject: hi\r\n\r\nbody";
+        assert_eq!(message_date(body), Some(1_741_183_331_000));
+        assert_eq!(message_date(b"Subject: hi\r\n\r\nbody"), None);
+    }
+
+    #[test]
+    fn formats_append_flags() {
+        assert_eq!(format_append_flags(&[]).unwrap(), None);
+        let flags = [
+            EnvelopeFlag::new(EmailFlag::Seen, None),
+            EnvelopeFlag::new(EmailFlag::Flagged, None),
+        ];
+        assert_eq!(
+            format_append_flags(&flags).unwrap().as_deref(),
+            Some("\\Seen \\Flagged")
+        );
+    }
+}
@@ -3,6 +3,9 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::account::entity::Encryption;
+use crate::modules::context::resources::{
+    Admitted, ConnectionKind, ConnectionPermit, CONNECTION_LIMITER,
+};
 use crate::modules::error::code::ErrorCode;
 use crate::modules::error::RustMailerResult;
 use crate::modules::imap::session::SessionStream;
@@ -48,7 +51,9 @@ fn alpn(port: u16) -> &'static [&'static str] {
 }
 
 impl Client {
-    fn new(stream: Box<dyn SessionStream>) -> Self {
+    /// The stream holds `permit` for as long as the connection stays open.
+    fn new(stream: Box<dyn SessionStream>, permit: ConnectionPermit) -> Self {
+        let stream: Box<dyn SessionStream> = Box::new(Admitted::new(stream, permit));
         Self {
             inner: ImapClient::new(stream),
         }
@@ -80,6 +85,29 @@ impl Client {
         Ok(session)
     }
 
+    /// Connects to the in-memory IMAP server of the test harness.
+    #[cfg(feature = "test-harness")]
+    pub async fn in_memory(
+        mailstore: std::sync::Arc<crate::modules::harness::store::Mailstore>,
+    ) -> RustMailerResult<Self> {
+        let permit = CONNECTION_LIMITER.acquire(ConnectionKind::Imap).await?;
+        let session_stream: Box<dyn SessionStream> =
+            Box::new(crate::modules::harness::imap::connect(mailstore));
+        let mut client = Client::new(session_stream, permit);
+        // Read and validate the greeting response
+        let _greeting = client
+            .read_response()
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?
+            .ok_or_else(|| {
+                raise_error!(
+                    "failed to read greeting".into(),
+                    ErrorCode::ImapCommandFailed
+                )
+            })?;
+        Ok(client)
+    }
+
     pub async fn connection(
         domain: String,
         encryption: Encryption,
@@ -88,15 +116,18 @@ impl Client {
     ) -> RustMailerResult<Self> {
         let domain = &domain;
         let resolved_addr = Self::resolve_to_socket_addr(domain, port)?;
+        let permit = CONNECTION_LIMITER.acquire(ConnectionKind::Imap).await?;
         debug!("Attempting IMAP connection to {domain} ({resolved_addr}).");
         match encryption {
             Encryption::Ssl => {
-                Self::establish_secure_connection(resolved_addr, domain, use_proxy).await
+                Self::establish_secure_connection(resolved_addr, domain, use_proxy, permit).await
             }
             Encryption::StartTls => {
-                Self::establish_starttls_connection(resolved_addr, domain, use_proxy).await
+                Self::establish_starttls_connection(resolved_addr, domain, use_proxy, permit).await
+            }
+            Encryption::None => {
+                Self::establish_insecure_connection(resolved_addr, use_proxy, permit).await
             }
-            Encryption::None => Self::establish_insecure_connection(resolved_addr, use_proxy).await,
         }
     }
 
@@ -104,6 +135,7 @@ impl Client {
         address: SocketAddr,
         server_hostname: &str,
         use_proxy: Option<u64>,
+        permit: ConnectionPermit,
     ) -> RustMailerResult<Self> {
         // Establish the TLS connection with the specified parameters
         let tls_stream =
@@ -115,7 +147,7 @@ impl Client {
         // Create a SessionStream trait object for further communication
         let session_stream = Box::new(buffered_stream);
         // Initialize the client with the session stream
-        let mut client = Client::new(session_stream);
+        let mut client = Client::new(session_stream, permit);
         // Read and validate the greeting response
         let _greeting = client
             .read_response()
@@ -135,6 +167,7 @@ impl Client {
     async fn establish_insecure_connection(
         address: SocketAddr,
         use_proxy: Option<u64>,
+        permit: ConnectionPermit,
     ) -> RustMailerResult<Self> {
         // Establish the TCP connection without encryption
         let tcp_stream = establish_tcp_connection_with_timeout(address, use_proxy).await?;
@@ -144,7 +177,7 @@ impl Client {
         // Create a SessionStream trait object for further communication
         let session_stream: Box<dyn SessionStream> = Box::new(buffered_stream);
         // Initialize the client with the session stream
-        let mut client = Client::new(session_stream);
+        let mut client = Client::new(session_stream, permit);
 
         // Read and validate the greeting response
         let _greeting = client
@@ -166,6 +199,7 @@ impl Client {
         address: SocketAddr,
         server_hostname: &str,
         use_proxy: Option<u64>,
+        permit: ConnectionPermit,
     ) -> RustMailerResult<Self> {
         // Establish the initial TCP connection
         let tcp_stream = establish_tcp_connection_with_timeout(address, use_proxy).await?;
@@ -209,7 +243,7 @@ impl Client {
         // Create a SessionStream trait object for further communication
         let session_stream: Box<dyn SessionStream> = Box::new(buffered_stream);
         // Initialize the client with the session stream
-        let client = Client::new(session_stream);
+        let client = Client::new(session_stream, permit);
         // Return the established client
         Ok(client)
     }
@@ -0,0 +1,230 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Instant;
+
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    encode_mailbox_name,
+    modules::{
+        account::migration::AccountModel,
+        context::executors::RUST_MAIL_CONTEXT,
+        error::{code::ErrorCode, RustMailerResult},
+    },
+    raise_error,
+};
+
+/// Data items accepted by the console's `STATUS` command.
+const STATUS_ITEMS: [&str; 5] = ["MESSAGES", "RECENT", "UIDNEXT", "UIDVALIDITY", "UNSEEN"];
+
+/// The raw IMAP commands the console is allowed to run. Only read-only commands are exposed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum ImapConsoleCommand {
+    #[default]
+    Capability,
+    Status,
+    Examine,
+    /// `UID SEARCH`, run after opening the mailbox read-only.
+    UidSearch,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ImapConsoleRequest {
+    pub command: ImapConsoleCommand,
+    /// Mailbox name (decoded). Required for `STATUS`, `EXAMINE` and `UID SEARCH`.
+    pub mailbox: Option<String>,
+    /// Command arguments: the data items for `STATUS` (defaults to all supported items),
+    /// or the search criteria for `UID SEARCH` (defaults to `ALL`).
+    pub arguments: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ImapConsoleResponse {
+    /// The raw command sent to the server, without the tag.
+    pub command: String,
+    /// The raw server response, including the tagged completion line.
+    pub response: String,
+    /// Time spent executing the command, in milliseconds.
+    pub elapsed_ms: u64,
+}
+
+impl ImapConsoleRequest {
+    /// Builds the raw command, returning it along with the mailbox to examine beforehand.
+    pub fn build(&self) -> RustMailerResult<(String, Option<String>)> {
+        let arguments = self
+            .arguments
+            .as_deref()
+            .map(str::trim)
+            .filter(|a| !a.is_empty());
+        if let Some(arguments) = arguments {
+            if arguments.contains(['\r', '\n', '{']) {
+                return Err(raise_error!(
+                    "Arguments must not contain line breaks or literals".into(),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+
+        match self.command {
+            ImapConsoleCommand::Capability => Ok(("CAPABILITY".into(), None)),
+            ImapConsoleCommand::Status => {
+                let items = match arguments {
+                    Some(arguments) => {
+                        let items: Vec<String> = arguments
+                            .trim_start_matches('(')
+                            .trim_end_matches(')')
+                            .split_whitespace()
+                            .map(str::to_ascii_uppercase)
+                            .collect();
+                        if let Some(item) =
+                            items.iter().find(|i| !STATUS_ITEMS.contains(&i.as_str()))
+                        {
+                            return Err(raise_error!(
+                                format!(
+                                    "Unsupported STATUS item '{item}', expected one of {}",
+                                    STATUS_ITEMS.join(", ")
+                                ),
+                                ErrorCode::InvalidParameter
+                            ));
+                        }
+                        items.join(" ")
+                    }
+                    None => STATUS_ITEMS.join(" "),
+                };
+                Ok((format!("STATUS {} ({items})", self.quoted_mailbox()?), None))
+            }
+            ImapConsoleCommand::Examine => {
+                Ok((format!("EXAMINE {}", self.quoted_mailbox()?), None))
+            }
+            ImapConsoleCommand::UidSearch => {
+                let mailbox = encode_mailbox_name!(self.mailbox()?);
+                let criteria = arguments.unwrap_or("ALL");
+                Ok((format!("UID SEARCH {criteria}"), Some(mailbox)))
+            }
+        }
+    }
+
+    fn mailbox(&self) -> RustMailerResult<&str> {
+        self.mailbox
+            .as_deref()
+            .filter(|m| !m.trim().is_empty())
+            .ok_or_else(|| {
+                raise_error!(
+                    format!("A mailbox is required for {:?}", self.command),
+                    ErrorCode::InvalidParameter
+                )
+            })
+    }
+
+    fn quoted_mailbox(&self) -> RustMailerResult<String> {
+        let mailbox = self.mailbox()?;
+        if mailbox.contains(['\r', '\n']) {
+            return Err(raise_error!(
+                "Mailbox name must not contain line breaks".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let encoded = encode_mailbox_name!(mailbox);
+        Ok(format!(
+            "\"{}\"",
+            encoded.replace('\\', "\\\\").replace('"', "\\\"")
+        ))
+    }
+}
+
+/// Runs a whitelisted raw IMAP command on a pooled connection of the account.
+pub async fn run_console_command(
+    account_id: u64,
+    request: &ImapConsoleRequest,
+) -> RustMailerResult<ImapConsoleResponse> {
+    AccountModel::check_account_active(account_id, true).await?;
+    let (command, examine) = request.build()?;
+    let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
+    let start = Instant::now();
+    let response = executor
+        .run_raw_command(examine.as_deref(), &command)
+        .await?;
+    Ok(ImapConsoleResponse {
+        command,
+        response: String::from_utf8_lossy(&response).into_owned(),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(
+        command: ImapConsoleCommand,
+        mailbox: Option<&str>,
+        arguments: Option<&str>,
+    ) -> ImapConsoleRequest {
+        ImapConsoleRequest {
+            command,
+            mailbox: mailbox.map(Into::into),
+            arguments: arguments.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn builds_whitelisted_commands() {
+        let (command, examine) = request(ImapConsoleCommand::Capability, None, None)
+            .build()
+            .unwrap();
+        assert_eq!(command, "CAPABILITY");
+        assert!(examine.is_none());
+
+        let (command, _) = request(
+            ImapConsoleCommand::Status,
+            Some("INBOX"),
+            Some("(uidnext unseen)"),
+        )
+        .build()
+        .unwrap();
+        assert_eq!(command, "STATUS \"INBOX\" (UIDNEXT UNSEEN)");
+
+        let (command, _) = request(ImapConsoleCommand::Examine, Some("a\"b"), None)
+            .build()
+            .unwrap();
+        assert_eq!(command, "EXAMINE \"a\\\"b\"");
+
+        let (command, examine) = request(ImapConsoleCommand::UidSearch, Some("INBOX"), None)
+            .build()
+            .unwrap();
+        assert_eq!(command, "UID SEARCH ALL");
+        assert_eq!(examine.as_deref(), Some("INBOX"));
+    }
+
+    #[test]
+    fn rejects_unsafe_input() {
+        assert!(request(ImapConsoleCommand::Status, None, None)
+            .build()
+            .is_err());
+        assert!(
+            request(ImapConsoleCommand::Status, Some("INBOX"), Some("SIZE"))
+                .build()
+                .is_err()
+        );
+        assert!(request(
+            ImapConsoleCommand::UidSearch,
+            Some("INBOX"),
+            Some("ALL\r\nA1 DELETE x")
+        )
+        .build()
+        .is_err());
+        assert!(request(
+            ImapConsoleCommand::UidSearch,
+            Some("INBOX"),
+            Some("TEXT {5}")
+        )
+        .build()
+        .is_err());
+        assert!(request(ImapConsoleCommand::Examine, Some("IN\nBOX"), None)
+            .build()
+            .is_err());
+    }
+}
@@ -3,18 +3,20 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::cache::imap::mailbox::EnvelopeFlag;
+use crate::modules::context::executors::PoolStats;
 use crate::modules::error::code::ErrorCode;
+use crate::modules::fault::{FaultInjector, FaultTarget};
 use crate::modules::{error::RustMailerResult, imap::manager::ImapConnectionManager};
 use crate::{encode_mailbox_name, raise_error};
 use async_imap::types::{Fetch, Mailbox, Name};
-use bb8::Pool;
+use bb8::{Pool, PooledConnection};
 use futures::{StreamExt, TryStreamExt};
 use mail_parser::MessageParser;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use tracing::{debug, info};
 
 /// The IMAP query to fetch email metadata including headers and body structure.
-const RICH_METADATA_QUERY: &str = "(UID BODYSTRUCTURE RFC822.SIZE INTERNALDATE FLAGS BODY.PEEK[HEADER.FIELDS (BCC CC Date From In-Reply-To Sender Return-Path Message-ID Subject MIME-Version References Reply-To To Received)])";
+const RICH_METADATA_QUERY: &str = "(UID BODYSTRUCTURE RFC822.SIZE INTERNALDATE FLAGS BODY.PEEK[HEADER.FIELDS (BCC CC Date From In-Reply-To Sender Return-Path Message-ID Subject MIME-Version References Reply-To To Received Authentication-Results)])";
 
 const MINIMAL_METADATA_QUERY: &str = "(UID FLAGS)";
 
@@ -28,17 +30,30 @@ const BODY_FETCH_COMMAND: &str = "(BODY.PEEK[])";
 
 const HEADER_MESSAGE_ID_QUERY: &str = "(UID BODY.PEEK[HEADER.FIELDS (Message-ID)])";
 
+const IDENTITY_QUERY: &str =
+    "(UID FLAGS RFC822.SIZE INTERNALDATE BODY.PEEK[HEADER.FIELDS (Message-ID)])";
+
 pub struct ImapExecutor {
+    account_id: u64,
     pool: Pool<ImapConnectionManager>,
 }
 
 impl ImapExecutor {
-    pub fn new(pool: Pool<ImapConnectionManager>) -> Self {
-        Self { pool }
+    pub fn new(account_id: u64, pool: Pool<ImapConnectionManager>) -> Self {
+        Self { account_id, pool }
+    }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.state().into()
+    }
+
+    async fn session(&self) -> RustMailerResult<PooledConnection<'_, ImapConnectionManager>> {
+        FaultInjector::inject(FaultTarget::Imap, self.account_id).await?;
+        Ok(self.pool.get().await?)
     }
 
     pub async fn list_all_mailboxes(&self) -> RustMailerResult<Vec<Name>> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         let list = session
             .list(Some(""), Some("*"))
             .await
@@ -51,7 +66,7 @@ impl ImapExecutor {
     }
 
     pub async fn list_all_subscribed_mailboxes(&self) -> RustMailerResult<Vec<Name>> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         let list = session
             .lsub(Some(""), Some("*"))
             .await
@@ -64,7 +79,7 @@ impl ImapExecutor {
     }
 
     pub async fn create_mailbox(&self, mailbox_name: &str) -> RustMailerResult<()> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .create(mailbox_name)
             .await
@@ -73,7 +88,7 @@ impl ImapExecutor {
     }
 
     pub async fn examine_mailbox(&self, mailbox_name: &str) -> RustMailerResult<Mailbox> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .examine(mailbox_name)
             .await
@@ -81,7 +96,7 @@ impl ImapExecutor {
     }
 
     pub async fn expunge_mailbox(&self, mailbox_name: &str) -> RustMailerResult<()> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .select(mailbox_name)
             .await
@@ -94,7 +109,7 @@ impl ImapExecutor {
     }
 
     pub async fn delete_mailbox(&self, mailbox_name: &str) -> RustMailerResult<()> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .delete(mailbox_name)
             .await
@@ -103,7 +118,7 @@ impl ImapExecutor {
     }
 
     pub async fn rename_mailbox(&self, from: &str, to: &str) -> RustMailerResult<()> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .rename(from, to)
             .await
@@ -112,7 +127,7 @@ impl ImapExecutor {
     }
 
     pub async fn subscribe_mailbox(&self, mailbox_name: &str) -> RustMailerResult<()> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .subscribe(mailbox_name)
             .await
@@ -121,7 +136,7 @@ impl ImapExecutor {
     }
 
     pub async fn unsubscribe_mailbox(&self, mailbox_name: &str) -> RustMailerResult<()> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .unsubscribe(mailbox_name)
             .await
@@ -138,7 +153,7 @@ impl ImapExecutor {
         assert!(start_uid > 0, "start_uid must be greater than 0");
         let uid_set = format!("{}:*", start_uid);
 
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .examine(mailbox_name)
             .await
@@ -173,7 +188,7 @@ impl ImapExecutor {
         target_message_id: &str,
         mailbox_name: &str,
     ) -> RustMailerResult<u32> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .examine(mailbox_name)
             .await
@@ -253,7 +268,7 @@ impl ImapExecutor {
         assert!(page > 0, "Page number must be greater than 0");
         assert!(page_size > 0, "Page size must be greater than 0");
 
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         let total = session
             .examine(mailbox_name)
             .await
@@ -317,7 +332,7 @@ impl ImapExecutor {
     ) -> RustMailerResult<Vec<Fetch>> {
         assert!(page > 0, "Page number must be greater than 0");
         assert!(page_size > 0, "Page size must be greater than 0");
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         let total = session
             .examine(mailbox_name)
             .await
@@ -377,7 +392,7 @@ impl ImapExecutor {
         mailbox_name: &str,
     ) -> RustMailerResult<Vec<Fetch>> {
         debug!("Fetching UID batch: '{}'", uid_set);
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .examine(mailbox_name)
             .await
@@ -398,7 +413,7 @@ impl ImapExecutor {
         uid_set: &str,
         mailbox_name: &str,
     ) -> RustMailerResult<Vec<Fetch>> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .examine(mailbox_name)
             .await
@@ -414,13 +429,66 @@ impl ImapExecutor {
         Ok(result)
     }
 
+    /// Fetches the Message-ID header of the given UIDs. UIDs that no longer exist are
+    /// missing from the result; messages without a Message-ID map to `None`.
+    pub async fn uid_fetch_message_ids(
+        &self,
+        uid_set: &str,
+        mailbox_name: &str,
+    ) -> RustMailerResult<HashMap<u32, Option<String>>> {
+        let mut session = self.session().await?;
+        session
+            .examine(mailbox_name)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+        let fetches = session
+            .uid_fetch(uid_set, HEADER_MESSAGE_ID_QUERY)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?
+            .try_collect::<Vec<Fetch>>()
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+        Ok(fetches
+            .iter()
+            .filter_map(|fetch| {
+                let message_id = fetch
+                    .header()
+                    .and_then(|header| MessageParser::default().parse_headers(header))
+                    .and_then(|headers| headers.message_id().map(String::from));
+                fetch.uid.map(|uid| (uid, message_id))
+            })
+            .collect())
+    }
+
+    /// Fetches what identifies the given messages independently of their UIDs: the
+    /// Message-ID header, internal date and size, along with their flags.
+    pub async fn uid_fetch_identities(
+        &self,
+        uid_set: &str,
+        mailbox_name: &str,
+    ) -> RustMailerResult<Vec<Fetch>> {
+        let mut session = self.session().await?;
+        session
+            .examine(mailbox_name)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+        let fetches = session
+            .uid_fetch(uid_set, IDENTITY_QUERY)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?
+            .try_collect::<Vec<Fetch>>()
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+        Ok(fetches)
+    }
+
     pub async fn uid_fetch_meta(
         &self,
         uid_set: &str,
         mailbox_name: &str,
         minimal: bool,
     ) -> RustMailerResult<Vec<Fetch>> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .examine(mailbox_name)
             .await
@@ -447,7 +515,7 @@ impl ImapExecutor {
         internaldate: Option<&str>,
         content: impl AsRef<[u8]>,
     ) -> RustMailerResult<()> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .append(mailbox_name, flags, internaldate, content)
             .await
@@ -459,7 +527,7 @@ impl ImapExecutor {
         uid: &str,
         mailbox_name: &str,
     ) -> RustMailerResult<Option<Fetch>> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .examine(mailbox_name)
             .await
@@ -481,7 +549,7 @@ impl ImapExecutor {
         mailbox_name: &str,
         path: &str,
     ) -> RustMailerResult<Vec<Fetch>> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .examine(mailbox_name)
             .await
@@ -497,12 +565,41 @@ impl ImapExecutor {
         Ok(result)
     }
 
+    /// Fetches `length` bytes of a body part, starting at `origin`, without fetching the
+    /// rest of the part.
+    pub async fn uid_fetch_partial_part(
+        &self,
+        uid: &str,
+        mailbox_name: &str,
+        path: &str,
+        origin: u64,
+        length: u64,
+    ) -> RustMailerResult<Vec<Fetch>> {
+        let mut session = self.session().await?;
+        session
+            .examine(mailbox_name)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+        let list = session
+            .uid_fetch(
+                uid,
+                &format!("(UID BODY.PEEK[{}]<{}.{}>)", path, origin, length),
+            )
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+        let result = list
+            .try_collect::<Vec<Fetch>>()
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+        Ok(result)
+    }
+
     // pub async fn uid_expunge_envelopes(
     //     &self,
     //     uid_set: &str,
     //     mailbox_name: &str,
     // ) -> RustMailerResult<()> {
-    //     let mut session = self.pool.get().await?;
+    //     let mut session = self.session().await?;
     //     session.examine(mailbox_name).await.map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapOperationFailed))?;
     //     let _ = session.uid_expunge(uid_set).await.map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapOperationFailed))?;
     //     Ok(())
@@ -514,7 +611,7 @@ impl ImapExecutor {
         from: &str,
         to: &str,
     ) -> RustMailerResult<()> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .select(from)
             .await
@@ -532,7 +629,7 @@ impl ImapExecutor {
         from: &str,
         to: &str,
     ) -> RustMailerResult<()> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .select(from)
             .await
@@ -550,7 +647,7 @@ impl ImapExecutor {
         mailbox_name: &str,
         query: &str,
     ) -> RustMailerResult<Vec<Fetch>> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .select(mailbox_name)
             .await
@@ -649,7 +746,7 @@ impl ImapExecutor {
         mailbox_name: &str,
         query: &str,
     ) -> RustMailerResult<HashSet<u32>> {
-        let mut session = self.pool.get().await?;
+        let mut session = self.session().await?;
         session
             .examine(mailbox_name)
             .await
@@ -660,4 +757,56 @@ impl ImapExecutor {
             .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
         Ok(result)
     }
+
+    /// Runs an untagged command on a pooled session and returns the raw response,
+    /// including the tagged completion line. With `examine`, the mailbox is opened
+    /// read-only first. A mailbox opened by the command is closed again before the
+    /// session returns to the pool.
+    pub async fn run_raw_command(
+        &self,
+        examine: Option<&str>,
+        command: &str,
+    ) -> RustMailerResult<Vec<u8>> {
+        let mut session = self.session().await?;
+        let selects = examine.is_some()
+            || command
+                .get(..8)
+                .is_some_and(|verb| verb.eq_ignore_ascii_case("EXAMINE "));
+        let result = async {
+            if let Some(mailbox_name) = examine {
+                session
+                    .examine(mailbox_name)
+                    .await
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+            }
+            let id = session
+                .run_command(command)
+                .await
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?;
+            let mut raw = Vec::new();
+            loop {
+                let response = session
+                    .read_response()
+                    .await
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::ImapCommandFailed))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            "Connection closed before the command completed".into(),
+                            ErrorCode::ImapCommandFailed
+                        )
+                    })?;
+                raw.extend_from_slice(response.borrow_owner());
+                if response.request_id() == Some(&id) {
+                    return Ok(raw);
+                }
+            }
+        }
+        .await;
+        // CLOSE does not expunge a mailbox opened read-only. If it fails, the session is
+        // logged out, so that the pool drops it when the NOOP check on check out fails.
+        if selects && session.close().await.is_err() {
+            let _ = session.logout().await;
+        }
+        result
+    }
 }
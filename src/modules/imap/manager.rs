@@ -34,6 +34,10 @@ impl ImapConnectionManager {
     }
 
     async fn create_client(&self, account: &AccountModel) -> RustMailerResult<Client> {
+        #[cfg(feature = "test-harness")]
+        if let Some(mailstore) = crate::modules::harness::TestHarness::get()? {
+            return Client::in_memory(mailstore).await;
+        }
         let imap = account
             .imap
             .clone()
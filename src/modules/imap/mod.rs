@@ -2,8 +2,10 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+pub mod append;
 pub mod capabilities;
 pub mod client;
+pub mod console;
 pub mod executor;
 pub mod flags;
 pub mod manager;
@@ -2,14 +2,16 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use crate::{generate_token, modules::imap::decoder::try_decode_string};
+use crate::{
+    generate_token,
+    modules::common::filename::{decode_param, sanitize_filename},
+};
 use async_imap::{
     imap_proto::{
         BodyContentCommon, BodyContentSinglePart, BodyStructure, ContentEncoding, SectionPath,
     },
     types::Fetch,
 };
-use imap_proto::ContentType;
 use mail_parser::decoders::{
     base64::base64_decode_stream, quoted_printable::quoted_printable_decode,
 };
@@ -91,6 +93,15 @@ impl EmailBodyPart {
     pub fn decode(&self, fetch: &Fetch) -> Option<Vec<u8>> {
         decode_impl(fetch, &self.transfer_encoding, &self.path)
     }
+
+    /// The charset declared in the `Content-Type` parameters of the part, if any.
+    pub fn charset(&self) -> Option<&str> {
+        self.params
+            .as_ref()?
+            .iter()
+            .find(|p| p.key.eq_ignore_ascii_case("charset"))
+            .map(|p| p.value.as_str())
+    }
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
@@ -175,6 +186,55 @@ impl ImapAttachment {
         let encoded_data = fetch.section(&self.path.clone().section_path())?;
         Some(encoded_data.to_vec())
     }
+
+    /// Decodes a partial fetch of the start of the attachment, dropping the trailing bytes
+    /// that cannot be decoded without the rest of the content.
+    pub fn decode_prefix(&self, fetch: &Fetch) -> Option<Vec<u8>> {
+        let encoded_data = fetch.section(&self.path.clone().section_path())?;
+        decode_prefix(encoded_data, &self.transfer_encoding)
+    }
+
+    /// The number of encoded bytes to fetch so that at least `decoded_length` bytes of the
+    /// attachment can be decoded, assuming base64 lines of at least 60 characters.
+    pub fn encoded_prefix_length(&self, decoded_length: u64) -> u64 {
+        match self.transfer_encoding {
+            Encoding::None => decoded_length,
+            Encoding::Base64 => {
+                let chars = decoded_length.div_ceil(3) * 4;
+                chars + (chars / 60 + 1) * 2
+            }
+            // Every byte may be escaped, and soft line breaks are inserted every 76 characters.
+            Encoding::QuotedPrintable => {
+                let chars = decoded_length * 3;
+                chars + (chars / 73 + 1) * 3
+            }
+        }
+    }
+}
+
+fn decode_prefix(encoded_data: &[u8], transfer_encoding: &Encoding) -> Option<Vec<u8>> {
+    match transfer_encoding {
+        Encoding::Base64 => {
+            let mut data: Vec<u8> = encoded_data
+                .iter()
+                .copied()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect();
+            // Only complete 4-character groups can be decoded.
+            data.truncate(data.len() / 4 * 4);
+            base64_decode_stream(data.iter(), data.len(), u8::MAX)
+        }
+        Encoding::QuotedPrintable => {
+            // Drop an escape sequence or soft line break cut by the end of the prefix.
+            let len = encoded_data.len();
+            let end = encoded_data[len.saturating_sub(2)..]
+                .iter()
+                .position(|b| *b == b'=')
+                .map_or(len, |p| len.saturating_sub(2) + p);
+            quoted_printable_decode(&encoded_data[..end])
+        }
+        Encoding::None => Some(encoded_data.to_vec()),
+    }
 }
 
 fn decode_impl(fetch: &Fetch, transfer_encoding: &Encoding, path: &SegmentPath) -> Option<Vec<u8>> {
@@ -337,19 +397,23 @@ impl<'a> SectionExtractor<'a> {
         Self::recursive_parse_body(self.structure, SegmentPath::new(Vec::new()))
     }
 
-    /// Retrieves the file name from the content disposition if it exists.
-    fn get_file_name(disposition: &ContentType<'a>) -> Option<String> {
-        disposition
-            .params
-            .as_ref()?
-            .iter()
-            .find_map(|(key, value)| {
-                if key.eq_ignore_ascii_case("name") {
-                    Some(try_decode_string(value.trim()))
-                } else {
-                    None
-                }
+    /// Retrieves the file name of an attachment, preferring the Content-Disposition
+    /// `filename` parameter over the Content-Type `name` parameter. RFC 2231 and RFC 2047
+    /// encoded names are decoded, and the result is sanitized.
+    fn get_file_name(common: &BodyContentCommon<'a>) -> Option<String> {
+        common
+            .disposition
+            .as_ref()
+            .and_then(|disposition| disposition.params.as_deref())
+            .and_then(|params| decode_param(params, "filename"))
+            .or_else(|| {
+                common
+                    .ty
+                    .params
+                    .as_deref()
+                    .and_then(|params| decode_param(params, "name"))
             })
+            .and_then(|name| sanitize_filename(&name))
     }
 
     /// Parses a single attachment from the body content.
@@ -369,7 +433,7 @@ impl<'a> SectionExtractor<'a> {
 
                 Some(ImapAttachment::new(
                     segment,
-                    Self::get_file_name(&common.ty),
+                    Self::get_file_name(common),
                     other.octets as usize,
                     common.ty.subtype.to_string(),
                     attachment_encoding,
@@ -492,3 +556,33 @@ impl<'a> SectionExtractor<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_the_start_of_encoded_content() {
+        // "Hello, world!" split over two base64 lines, cut in the second line.
+        let base64 = b"SGVsbG8s\r\nIHdvcmxk";
+        assert_eq!(
+            decode_prefix(base64, &Encoding::Base64).unwrap(),
+            b"Hello, world"
+        );
+        assert_eq!(
+            decode_prefix(&base64[..12], &Encoding::Base64).unwrap(),
+            b"Hello,"
+        );
+
+        let qp = b"caf=C3=A9 au lait=\r\n for=C3=A9";
+        assert_eq!(
+            decode_prefix(&qp[..8], &Encoding::QuotedPrintable).unwrap(),
+            b"caf\xC3"
+        );
+        assert_eq!(
+            decode_prefix(&qp[..19], &Encoding::QuotedPrintable).unwrap(),
+            "café au lait".as_bytes()
+        );
+        assert_eq!(decode_prefix(b"plain", &Encoding::None).unwrap(), b"plain");
+    }
+}
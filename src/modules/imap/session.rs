@@ -2,6 +2,7 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::context::resources::Admitted;
 use std::pin::Pin;
 use tokio::io::{AsyncRead, AsyncWrite, BufWriter};
 use tokio_io_timeout::TimeoutStream;
@@ -28,6 +29,13 @@ impl<T: SessionStream> SessionStream for BufWriter<T> {
     //     self.get_mut().set_read_timeout(timeout);
     // }
 }
+/// Streams holding a slot of the global IMAP connection limit.
+impl<T: SessionStream> SessionStream for Admitted<T> {}
+
+/// Connections to the in-memory IMAP server of the test harness.
+#[cfg(feature = "test-harness")]
+impl SessionStream for tokio::io::DuplexStream {}
+
 impl<T: AsyncRead + AsyncWrite + Send + Sync + std::fmt::Debug> SessionStream
     for Pin<Box<TimeoutStream<T>>>
 {
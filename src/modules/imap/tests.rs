@@ -54,3 +54,41 @@ async fn test1() {
         println!("{}", part.is_multipart());
     }
 }
+
+#[test]
+fn extracts_decoded_and_sanitized_attachment_names() {
+    use crate::modules::imap::section::SectionExtractor;
+    use imap_proto::{parser::parse_response, AttributeValue, Response};
+
+    let response = b"* 1 FETCH (BODYSTRUCTURE ((\"TEXT\" \"PLAIN\" (\"CHARSET\" \"utf-8\") NIL NIL \"7BIT\" 5 1 NIL NIL NIL NIL)\
+(\"APPLICATION\" \"PDF\" (\"NAME\" \"=?utf-8?B?5oql5ZGKLnBkZg==?=\") NIL NIL \"BASE64\" 100 NIL (\"ATTACHMENT\" (\"FILENAME*\" \"utf-8''%E5%AD%A3%E5%BA%A6%E6%8A%A5%E5%91%8A.pdf\")) NIL NIL)\
+(\"APPLICATION\" \"PDF\" (\"NAME\" \"=?utf-8?B?5oql5ZGKLnBkZg==?=\") NIL NIL \"BASE64\" 100 NIL (\"ATTACHMENT\" NIL) NIL NIL)\
+(\"APPLICATION\" \"OCTET-STREAM\" (\"NAME\" \"../../evil.sh\") NIL NIL \"BASE64\" 10 NIL (\"ATTACHMENT\" NIL) NIL NIL) \
+\"MIXED\" (\"BOUNDARY\" \"b\") NIL NIL NIL))\r\n";
+    let (_, parsed) = parse_response(response).unwrap();
+    let Response::Fetch(_, attributes) = parsed else {
+        panic!("unexpected response");
+    };
+    let structure = attributes
+        .iter()
+        .find_map(|a| match a {
+            AttributeValue::BodyStructure(structure) => Some(structure),
+            _ => None,
+        })
+        .unwrap();
+
+    let names: Vec<Option<String>> = SectionExtractor::new(structure)
+        .get_attachments()
+        .unwrap()
+        .into_iter()
+        .map(|a| a.filename)
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            Some("季度报告.pdf".to_string()),
+            Some("报告.pdf".to_string()),
+            Some("evil.sh".to_string()),
+        ]
+    );
+}
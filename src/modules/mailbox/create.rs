@@ -10,8 +10,9 @@ use crate::{
         account::{entity::MailerType, migration::AccountModel},
         cache::vendor::{gmail::sync::client::GmailClient, outlook::sync::client::OutlookClient},
         context::executors::RUST_MAIL_CONTEXT,
-        error::RustMailerResult,
+        error::{code::ErrorCode, RustMailerResult},
     },
+    raise_error,
 };
 
 /// Represents the color settings for a mailbox/label in RustMailer.
@@ -98,6 +99,10 @@ pub async fn create_mailbox(
             GmailClient::create_label(account_id, account.use_proxy, request).await?;
             Ok(())
         }
+        MailerType::Jmap => Err(raise_error!(
+            "Creating mailboxes is not supported for JMAP accounts.".into(),
+            ErrorCode::Incompatible
+        )),
         MailerType::GraphApi => {
             OutlookClient::create_folder(
                 account_id,
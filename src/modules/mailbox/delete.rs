@@ -35,6 +35,10 @@ pub async fn delete_mailbox(account_id: u64, mailbox_name: &str) -> RustMailerRe
             })?;
             GmailClient::delete_label(account_id, account.use_proxy, label_id).await
         }
+        MailerType::Jmap => Err(raise_error!(
+            "Deleting mailboxes is not supported for JMAP accounts.".into(),
+            ErrorCode::Incompatible
+        )),
         MailerType::GraphApi => {
             let mailboxes = OutlookClient::list_mailfolders(account_id, account.use_proxy).await?;
             let target_folder = mailboxes
@@ -10,6 +10,8 @@ use crate::modules::cache::imap::mailbox::{Attribute, AttributeEnum, MailBox};
 use crate::modules::cache::vendor::gmail::model::labels::LabelDetail;
 use crate::modules::cache::vendor::gmail::sync::client::GmailClient;
 use crate::modules::cache::vendor::gmail::sync::labels::GmailLabels;
+use crate::modules::cache::vendor::jmap::sync::client::JmapClient;
+use crate::modules::cache::vendor::jmap::sync::mailboxes::JmapMailbox;
 use crate::modules::cache::vendor::outlook::sync::client::OutlookClient;
 use crate::modules::cache::vendor::outlook::sync::folders::OutlookFolder;
 use crate::modules::context::executors::RUST_MAIL_CONTEXT;
@@ -51,6 +53,17 @@ pub async fn get_account_mailboxes(
             let folders = OutlookFolder::list_all(account_id).await?;
             Ok(folders.into_iter().map(Into::into).collect())
         }
+        (MailerType::Jmap, true) => {
+            let mailboxes = JmapClient::list_mailboxes(&account).await?;
+            Ok(mailboxes
+                .into_iter()
+                .map(|m| JmapMailbox::new(account_id, m).into())
+                .collect())
+        }
+        (MailerType::Jmap, false) => {
+            let mailboxes = JmapMailbox::list_all(account_id).await?;
+            Ok(mailboxes.into_iter().map(Into::into).collect())
+        }
     }
 }
 
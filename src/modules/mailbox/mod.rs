@@ -6,4 +6,6 @@ pub mod create;
 pub mod delete;
 pub mod list;
 pub mod rename;
+pub mod resync;
 pub mod subscribe;
+pub mod usage;
@@ -6,7 +6,10 @@ use crate::{
     encode_mailbox_name,
     modules::{
         account::{entity::MailerType, migration::AccountModel},
-        cache::vendor::{gmail::sync::client::GmailClient, outlook::sync::client::OutlookClient},
+        cache::{
+            imap::sync::rename::record_rename,
+            vendor::{gmail::sync::client::GmailClient, outlook::sync::client::OutlookClient},
+        },
         context::executors::RUST_MAIL_CONTEXT,
         error::{code::ErrorCode, RustMailerResult},
         mailbox::create::LabelColor,
@@ -48,20 +51,23 @@ pub async fn update_mailbox(
     let account = AccountModel::check_account_active(account_id, false).await?;
     match account.mailer_type {
         MailerType::ImapSmtp => {
-            if payload.new_name.is_none() {
+            let Some(new_name) = payload.new_name else {
                 return Err(raise_error!(
                     "The `new_name` field is required when updating a mailbox.".into(),
                     ErrorCode::InvalidParameter
                 ));
-            }
+            };
 
             let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
             executor
                 .rename_mailbox(
                     encode_mailbox_name!(&payload.current_name).as_str(),
-                    encode_mailbox_name!(&payload.new_name.unwrap()).as_str(),
+                    encode_mailbox_name!(&new_name).as_str(),
                 )
-                .await
+                .await?;
+            // Let the next sync move the cached emails instead of downloading them again.
+            record_rename(account_id, &payload.current_name, &new_name);
+            Ok(())
         }
         MailerType::GmailApi => {
             if payload.new_name.is_none() && payload.label_color.is_none() {
@@ -84,6 +90,10 @@ pub async fn update_mailbox(
             })?;
             GmailClient::update_label(account_id, account.use_proxy, label_id, &payload).await
         }
+        MailerType::Jmap => Err(raise_error!(
+            "Updating mailboxes is not supported for JMAP accounts.".into(),
+            ErrorCode::Incompatible
+        )),
         MailerType::GraphApi => {
             if payload.new_name.is_none() {
                 return Err(raise_error!(
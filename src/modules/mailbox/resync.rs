@@ -0,0 +1,264 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Instant;
+
+use tracing::{error, info};
+
+use crate::{
+    encode_mailbox_name,
+    modules::{
+        account::{dispatcher::STATUS_DISPATCHER, entity::MailerType, migration::AccountModel},
+        cache::{
+            imap::{
+                address::AddressEntity,
+                mailbox::MailBox,
+                sync::rebuild::{rebuild_mailbox_cache, rebuild_mailbox_cache_since_date},
+                thread::EmailThread,
+            },
+            vendor::{
+                gmail::sync::{
+                    client::GmailClient,
+                    envelope::GmailEnvelope,
+                    flow::{fetch_and_save_full_label, fetch_and_save_since_date},
+                    labels::GmailLabels,
+                },
+                jmap::sync::{
+                    client::JmapClient, envelope::JmapEnvelope, flow::fetch_and_save_mailbox,
+                    mailboxes::JmapMailbox,
+                },
+                outlook::sync::{
+                    client::OutlookClient,
+                    delta::FolderDeltaLink,
+                    envelope::OutlookEnvelope,
+                    flow::{
+                        fetch_and_save_full_folder,
+                        fetch_and_save_since_date as fetch_and_save_folder_since_date,
+                    },
+                    folders::OutlookFolder,
+                },
+            },
+        },
+        context::executors::RUST_MAIL_CONTEXT,
+        error::{code::ErrorCode, RustMailerError, RustMailerResult},
+        mailbox::list::convert_names_to_mailboxes,
+    },
+    raise_error,
+};
+
+/// Triggers a full resynchronization of a single cached mailbox (IMAP folder,
+/// Gmail label or Graph API folder).
+///
+/// The mailbox must already be part of the local cache. Its cached envelopes are
+/// discarded and re-fetched from the server, while every other mailbox of the
+/// account is left untouched. Validation happens synchronously; the actual
+/// rebuild runs in the background and failures are reported through the
+/// account running state.
+pub async fn resync_mailbox(account_id: u64, mailbox_name: &str) -> RustMailerResult<()> {
+    let account = AccountModel::check_account_active(account_id, false).await?;
+    let mailbox_name = mailbox_name.trim().to_string();
+    if mailbox_name.is_empty() {
+        return Err(raise_error!(
+            "The mailbox name must not be empty.".into(),
+            ErrorCode::InvalidParameter
+        ));
+    }
+
+    match account.mailer_type {
+        MailerType::ImapSmtp => {
+            MailBox::get(account_id, &mailbox_name)
+                .await
+                .map_err(|_| mailbox_not_cached(account_id, &mailbox_name))?;
+        }
+        MailerType::GmailApi => {
+            GmailLabels::get_by_name(account_id, &mailbox_name).await?;
+        }
+        MailerType::GraphApi => {
+            OutlookFolder::get_by_name(account_id, &mailbox_name).await?;
+        }
+        MailerType::Jmap => {
+            JmapMailbox::get_by_name(account_id, &mailbox_name).await?;
+        }
+    }
+
+    tokio::spawn(async move {
+        let start_time = Instant::now();
+        let result = match account.mailer_type {
+            MailerType::ImapSmtp => resync_imap_mailbox(&account, &mailbox_name).await,
+            MailerType::GmailApi => resync_gmail_label(&account, &mailbox_name).await,
+            MailerType::GraphApi => resync_outlook_folder(&account, &mailbox_name).await,
+            MailerType::Jmap => resync_jmap_mailbox(&account, &mailbox_name).await,
+        };
+        match result {
+            Ok(()) => info!(
+                "Account {}: Mailbox '{}' resync completed. {} seconds elapsed.",
+                account.id,
+                mailbox_name,
+                start_time.elapsed().as_secs()
+            ),
+            Err(e) => {
+                error!(
+                    "Account {}: Failed to resync mailbox '{}': {:#?}",
+                    account.id, mailbox_name, e
+                );
+                STATUS_DISPATCHER
+                    .append_error(
+                        account.id,
+                        format!("error in mailbox '{}' resync: {:#?}", mailbox_name, e),
+                    )
+                    .await;
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn resync_imap_mailbox(account: &AccountModel, mailbox_name: &str) -> RustMailerResult<()> {
+    let local = MailBox::get(account.id, mailbox_name).await?;
+    let executor = RUST_MAIL_CONTEXT.imap(account.id).await?;
+    let encoded_name = encode_mailbox_name!(mailbox_name);
+    let names = executor.list_all_mailboxes().await?;
+    let name = names
+        .iter()
+        .find(|n| n.name() == encoded_name)
+        .ok_or_else(|| {
+            raise_error!(
+                format!(
+                    "Mailbox '{}' no longer exists on the IMAP server for account {}",
+                    mailbox_name, account.id
+                ),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+    let remote = convert_names_to_mailboxes(account.id, [name])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            raise_error!(
+                format!("Mailbox '{}' is not selectable", mailbox_name),
+                ErrorCode::ImapUnexpectedResult
+            )
+        })?;
+
+    info!(
+        "Account {}: Resyncing mailbox '{}' (local exists: {}, remote exists: {}, local uid_validity: {:?}, remote uid_validity: {:?}).",
+        account.id, mailbox_name, local.exists, remote.exists, local.uid_validity, remote.uid_validity
+    );
+    match &account.sync_since()? {
+        Some(date_since) => {
+            rebuild_mailbox_cache_since_date(account, local.id, date_since, &remote).await?
+        }
+        None => rebuild_mailbox_cache(account, &local, &remote).await?,
+    }
+    // As in `reconcile_mailboxes`, the mailbox metadata is only updated once its
+    // envelopes have been rebuilt successfully.
+    MailBox::batch_upsert(&[remote]).await
+}
+
+async fn resync_gmail_label(account: &AccountModel, label_name: &str) -> RustMailerResult<()> {
+    let local = GmailLabels::get_by_name(account.id, label_name).await?;
+    let detail = GmailClient::get_label(account.id, account.use_proxy, &local.label_id).await?;
+    let mut remote: GmailLabels = detail.into();
+    remote.id = local.id;
+    remote.account_id = account.id;
+
+    GmailEnvelope::clean_label_envelopes(account.id, local.id).await?;
+    AddressEntity::clean_mailbox_envelopes(account.id, local.id).await?;
+    EmailThread::clean_mailbox_envelopes(account.id, local.id).await?;
+
+    if remote.exists > 0 {
+        let (inserted, _) = match &account.sync_since()? {
+            Some(date_since) => {
+                let date = date_since.since_gmail_date()?;
+                fetch_and_save_since_date(account, date.as_str(), &remote, false).await?
+            }
+            None => fetch_and_save_full_label(account, &remote, remote.exists, false).await?,
+        };
+        info!(
+            "Account {}: Label '{}' resynced, {} messages inserted.",
+            account.id, label_name, inserted
+        );
+    }
+    GmailLabels::upsert(remote).await
+}
+
+async fn resync_outlook_folder(account: &AccountModel, folder_name: &str) -> RustMailerResult<()> {
+    let local = OutlookFolder::get_by_name(account.id, folder_name).await?;
+    let folder = OutlookClient::get_folder(account.id, account.use_proxy, &local.folder_id).await?;
+    let mut remote: OutlookFolder = folder.try_into()?;
+    remote.id = local.id;
+    remote.account_id = account.id;
+    // Keep the full path name used by the local cache instead of the display name.
+    remote.name = local.name.clone();
+
+    OutlookEnvelope::clean_folder_envelopes(account.id, local.id).await?;
+    AddressEntity::clean_mailbox_envelopes(account.id, local.id).await?;
+    EmailThread::clean_mailbox_envelopes(account.id, local.id).await?;
+
+    if remote.exists > 0 {
+        let inserted = match &account.sync_since()? {
+            Some(date_since) => {
+                let date = date_since.since_outlook_date()?;
+                fetch_and_save_folder_since_date(account, date.as_str(), &remote, false).await?
+            }
+            None => fetch_and_save_full_folder(account, &remote, remote.exists, false).await?,
+        };
+        info!(
+            "Account {}: Folder '{}' resynced, {} messages inserted.",
+            account.id, folder_name, inserted
+        );
+    }
+    let delta_link =
+        OutlookClient::get_delta_link(account.id, account.use_proxy, &remote.folder_id).await?;
+    FolderDeltaLink::upsert(account.id, &remote.folder_id, &delta_link).await?;
+    OutlookFolder::upsert(remote).await
+}
+
+async fn resync_jmap_mailbox(account: &AccountModel, mailbox_name: &str) -> RustMailerResult<()> {
+    let local = JmapMailbox::get_by_name(account.id, mailbox_name).await?;
+    let mailbox = JmapClient::list_mailboxes(account)
+        .await?
+        .into_iter()
+        .find(|m| m.id == local.mailbox_id)
+        .ok_or_else(|| {
+            raise_error!(
+                format!(
+                    "Mailbox '{}' no longer exists on the JMAP server for account {}",
+                    mailbox_name, account.id
+                ),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+    let remote = JmapMailbox::new(account.id, mailbox);
+
+    JmapEnvelope::clean_folder_envelopes(account.id, local.id).await?;
+    AddressEntity::clean_mailbox_envelopes(account.id, local.id).await?;
+    EmailThread::clean_mailbox_envelopes(account.id, local.id).await?;
+
+    if remote.exists > 0 {
+        let date = account
+            .sync_since()?
+            .map(|d| d.since_jmap_date())
+            .transpose()?;
+        let inserted = fetch_and_save_mailbox(account, &remote, date.as_deref(), false).await?;
+        info!(
+            "Account {}: Mailbox '{}' resynced, {} messages inserted.",
+            account.id, mailbox_name, inserted
+        );
+    }
+    // Changes made meanwhile are applied again by the next incremental sync, which
+    // tolerates envelopes that are already up to date.
+    JmapMailbox::upsert(remote).await
+}
+
+fn mailbox_not_cached(account_id: u64, mailbox_name: &str) -> RustMailerError {
+    raise_error!(
+        format!(
+            "Mailbox '{}' is not part of the local cache for account {}. Only synchronized mailboxes can be resynced.",
+            mailbox_name, account_id
+        ),
+        ErrorCode::MailBoxNotCached
+    )
+}
@@ -0,0 +1,513 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{cmp::Reverse, collections::HashMap, time::Instant};
+
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    modules::{
+        account::migration::AccountModel,
+        cache::imap::{
+            mailbox::{AttributeEnum, MailBox},
+            migration::{EmailEnvelopeV7, EmailEnvelopeV7Key},
+        },
+        context::executors::RUST_MAIL_CONTEXT,
+        database::{filter_by_secondary_key_impl, manager::DB_MANAGER},
+        error::{code::ErrorCode, RustMailerResult},
+    },
+    raise_error, utc_now,
+};
+
+/// Default number of senders listed in a storage usage report.
+const DEFAULT_TOP_SENDERS: usize = 10;
+const MAX_TOP_SENDERS: usize = 100;
+/// Trash and junk mailboxes above this size are recommended for emptying.
+const DISPOSABLE_MAILBOX_THRESHOLD: u64 = 10 * 1024 * 1024;
+/// Senders using at least this share of the cached storage are recommended for review.
+const SENDER_SHARE_THRESHOLD: f64 = 0.1;
+/// Messages at least this large are counted as large messages.
+const LARGE_MESSAGE_SIZE: u32 = 10 * 1024 * 1024;
+
+/// Storage used by the cached messages of one mailbox.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct MailboxUsage {
+    /// The decoded name of the mailbox.
+    pub mailbox_name: String,
+    /// Number of cached messages.
+    pub messages: u64,
+    /// Total size of the cached messages, in bytes.
+    pub bytes: u64,
+    /// Number of cached messages of at least 10 MiB.
+    pub large_messages: u64,
+    /// Total size of the cached messages of at least 10 MiB, in bytes.
+    pub large_message_bytes: u64,
+    /// Number of messages on the server: the last synchronized `EXISTS` count, or the
+    /// live `STATUS` count when a refresh was requested.
+    pub server_messages: Option<u32>,
+    /// Total size of the mailbox on the server, in bytes. Only reported on refresh, by
+    /// servers supporting the `STATUS=SIZE` extension (RFC 8438).
+    pub server_bytes: Option<u64>,
+    /// Error message if the live refresh of this mailbox failed.
+    pub error: Option<String>,
+}
+
+/// Storage used by the cached messages of one sender.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SenderUsage {
+    /// The sender address, lowercased. Falls back to the display name for messages
+    /// without a sender address.
+    pub sender: String,
+    /// The display name most recently seen for this sender.
+    pub name: Option<String>,
+    /// Number of cached messages from this sender.
+    pub messages: u64,
+    /// Total size of the cached messages from this sender, in bytes.
+    pub bytes: u64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum StorageRecommendationKind {
+    /// Empty a trash or junk mailbox.
+    EmptyMailbox,
+    /// Review or delete the messages of a sender using a large share of the storage.
+    ReviewSender,
+    /// Remove or archive large messages of a mailbox.
+    RemoveLargeMessages,
+}
+
+/// A cleanup action that would free a significant amount of storage.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct StorageRecommendation {
+    pub kind: StorageRecommendationKind,
+    /// The mailbox name or sender the recommendation applies to.
+    pub target: String,
+    /// Number of messages affected.
+    pub messages: u64,
+    /// Storage that would be freed, in bytes.
+    pub bytes: u64,
+    /// Human-readable description of the recommendation.
+    pub description: String,
+}
+
+/// Storage usage of an account, computed from the cached envelope sizes.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct StorageUsageReport {
+    pub account_id: u64,
+    /// Number of cached messages in all mailboxes.
+    pub total_messages: u64,
+    /// Total size of the cached messages, in bytes.
+    pub total_bytes: u64,
+    /// Per-mailbox usage, largest first.
+    pub mailboxes: Vec<MailboxUsage>,
+    /// The senders using the most storage, largest first.
+    pub top_senders: Vec<SenderUsage>,
+    /// Suggested cleanup actions, largest savings first.
+    pub recommendations: Vec<StorageRecommendation>,
+    /// Whether mailbox counts were refreshed from the server with `STATUS`.
+    pub refreshed: bool,
+    /// Time at which the report was generated (UNIX epoch milliseconds).
+    pub generated_at: i64,
+    /// Time spent computing the report, in milliseconds.
+    pub elapsed_ms: u64,
+}
+
+#[derive(Default)]
+struct SenderTotals {
+    name: Option<String>,
+    messages: u64,
+    bytes: u64,
+}
+
+/// Accumulates cached envelopes into mailbox and sender totals.
+#[derive(Default)]
+struct UsageAggregator {
+    senders: HashMap<String, SenderTotals>,
+}
+
+impl UsageAggregator {
+    fn add_mailbox(&mut self, mailbox: &MailBox, envelopes: &[EmailEnvelopeV7]) -> MailboxUsage {
+        let mut usage = MailboxUsage {
+            mailbox_name: mailbox.name.clone(),
+            server_messages: Some(mailbox.exists),
+            ..Default::default()
+        };
+        for envelope in envelopes {
+            let size = envelope.size as u64;
+            usage.messages += 1;
+            usage.bytes += size;
+            if envelope.size >= LARGE_MESSAGE_SIZE {
+                usage.large_messages += 1;
+                usage.large_message_bytes += size;
+            }
+
+            let Some(sender) = sender_key(envelope) else {
+                continue;
+            };
+            let totals = self.senders.entry(sender).or_default();
+            totals.messages += 1;
+            totals.bytes += size;
+            if let Some(name) = envelope
+                .from
+                .as_ref()
+                .and_then(|f| f.name.as_ref())
+                .filter(|n| !n.trim().is_empty())
+            {
+                totals.name = Some(name.clone());
+            }
+        }
+        usage
+    }
+
+    fn top_senders(self, top: usize) -> Vec<SenderUsage> {
+        let mut senders: Vec<SenderUsage> = self
+            .senders
+            .into_iter()
+            .map(|(sender, totals)| SenderUsage {
+                sender,
+                name: totals.name,
+                messages: totals.messages,
+                bytes: totals.bytes,
+            })
+            .collect();
+        senders.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.sender.cmp(&b.sender)));
+        senders.truncate(top);
+        senders
+    }
+}
+
+fn sender_key(envelope: &EmailEnvelopeV7) -> Option<String> {
+    let from = envelope.from.as_ref()?;
+    from.address
+        .as_deref()
+        .or(from.name.as_deref())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Computes the storage usage of an IMAP account per mailbox and per sender from the
+/// sizes of the cached envelopes.
+///
+/// Only messages within the account's sync window are cached, so the totals may be
+/// lower than the usage reported by the server. With `refresh`, the message count of
+/// every mailbox (and its size, if the server supports `STATUS=SIZE`) is fetched
+/// live from the server with `STATUS`.
+pub async fn storage_usage(
+    account_id: u64,
+    top: Option<usize>,
+    refresh: bool,
+) -> RustMailerResult<StorageUsageReport> {
+    let account = AccountModel::check_account_active(account_id, true).await?;
+    if account.minimal_sync() {
+        return Err(raise_error!(
+            format!(
+                "Account id='{account_id}' uses minimal sync, which does not cache message sizes"
+            ),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    let top = top.unwrap_or(DEFAULT_TOP_SENDERS).clamp(1, MAX_TOP_SENDERS);
+    let generated_at = utc_now!();
+    let start_time = Instant::now();
+
+    let status_size = account
+        .capabilities
+        .as_ref()
+        .is_some_and(|c| c.iter().any(|c| c.eq_ignore_ascii_case("STATUS=SIZE")));
+    let local_mailboxes = MailBox::list_all(account_id).await?;
+    let mut aggregator = UsageAggregator::default();
+    let mut mailboxes = Vec::with_capacity(local_mailboxes.len());
+    for mailbox in &local_mailboxes {
+        let envelopes: Vec<EmailEnvelopeV7> = filter_by_secondary_key_impl(
+            DB_MANAGER.envelope_db(),
+            EmailEnvelopeV7Key::mailbox_id,
+            mailbox.id,
+        )
+        .await?
+        .into_iter()
+        .filter(|e: &EmailEnvelopeV7| e.account_id == account_id)
+        .collect();
+        let mut usage = aggregator.add_mailbox(mailbox, &envelopes);
+        if refresh {
+            match fetch_mailbox_status(account_id, mailbox, status_size).await {
+                Ok((messages, bytes)) => {
+                    usage.server_messages = messages.or(usage.server_messages);
+                    usage.server_bytes = bytes;
+                }
+                Err(e) => {
+                    warn!(
+                        "Account {}: Failed to refresh the status of mailbox '{}': {:#?}",
+                        account_id, mailbox.name, e
+                    );
+                    usage.error = Some(e.to_string());
+                }
+            }
+        }
+        mailboxes.push((mailbox, usage));
+    }
+
+    let total_messages = mailboxes.iter().map(|(_, u)| u.messages).sum();
+    let total_bytes = mailboxes.iter().map(|(_, u)| u.bytes).sum();
+    let top_senders = aggregator.top_senders(top);
+    let recommendations = recommend(&mailboxes, &top_senders, total_bytes);
+    let mut mailboxes: Vec<MailboxUsage> = mailboxes.into_iter().map(|(_, u)| u).collect();
+    mailboxes.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then_with(|| a.mailbox_name.cmp(&b.mailbox_name))
+    });
+
+    Ok(StorageUsageReport {
+        account_id,
+        total_messages,
+        total_bytes,
+        mailboxes,
+        top_senders,
+        recommendations,
+        refreshed: refresh,
+        generated_at,
+        elapsed_ms: start_time.elapsed().as_millis() as u64,
+    })
+}
+
+async fn fetch_mailbox_status(
+    account_id: u64,
+    mailbox: &MailBox,
+    with_size: bool,
+) -> RustMailerResult<(Option<u32>, Option<u64>)> {
+    let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
+    let items = if with_size {
+        "MESSAGES SIZE"
+    } else {
+        "MESSAGES"
+    };
+    let command = format!("STATUS \"{}\" ({})", mailbox.encoded_name(), items);
+    let response = executor.run_raw_command(None, &command).await?;
+    let response = String::from_utf8_lossy(&response);
+    parse_status_response(&response).ok_or_else(|| {
+        raise_error!(
+            format!("Unexpected STATUS response: {}", response.trim()),
+            ErrorCode::ImapUnexpectedResult
+        )
+    })
+}
+
+/// Extracts the `MESSAGES` and `SIZE` items from the untagged `STATUS` response.
+fn parse_status_response(response: &str) -> Option<(Option<u32>, Option<u64>)> {
+    let line = response
+        .lines()
+        .find(|l| l.to_ascii_uppercase().starts_with("* STATUS "))?;
+    let items = &line[line.rfind('(')? + 1..line.rfind(')')?];
+    let mut messages = None;
+    let mut size = None;
+    let mut tokens = items.split_whitespace();
+    while let (Some(name), Some(value)) = (tokens.next(), tokens.next()) {
+        match name.to_ascii_uppercase().as_str() {
+            "MESSAGES" => messages = value.parse().ok(),
+            "SIZE" => size = value.parse().ok(),
+            _ => {}
+        }
+    }
+    Some((messages, size))
+}
+
+fn recommend(
+    mailboxes: &[(&MailBox, MailboxUsage)],
+    top_senders: &[SenderUsage],
+    total_bytes: u64,
+) -> Vec<StorageRecommendation> {
+    let mut recommendations = Vec::new();
+    for (mailbox, usage) in mailboxes {
+        let disposable = mailbox
+            .attributes
+            .iter()
+            .any(|a| matches!(a.attr, AttributeEnum::Trash | AttributeEnum::Junk));
+        // Prefer the server size, which also covers messages outside the sync window.
+        let bytes = usage.server_bytes.unwrap_or(usage.bytes);
+        if disposable && bytes >= DISPOSABLE_MAILBOX_THRESHOLD {
+            let messages = usage
+                .server_messages
+                .map(u64::from)
+                .unwrap_or(usage.messages);
+            recommendations.push(StorageRecommendation {
+                kind: StorageRecommendationKind::EmptyMailbox,
+                target: usage.mailbox_name.clone(),
+                messages,
+                bytes,
+                description: format!(
+                    "Empty '{}' to free {} bytes in {} messages",
+                    usage.mailbox_name, bytes, messages
+                ),
+            });
+        } else if !disposable && usage.large_messages > 0 {
+            recommendations.push(StorageRecommendation {
+                kind: StorageRecommendationKind::RemoveLargeMessages,
+                target: usage.mailbox_name.clone(),
+                messages: usage.large_messages,
+                bytes: usage.large_message_bytes,
+                description: format!(
+                    "'{}' holds {} messages of 10 MiB or more; archive or remove them, or their attachments",
+                    usage.mailbox_name, usage.large_messages
+                ),
+            });
+        }
+    }
+    if total_bytes > 0 {
+        for sender in top_senders {
+            let share = sender.bytes as f64 / total_bytes as f64;
+            if share >= SENDER_SHARE_THRESHOLD {
+                recommendations.push(StorageRecommendation {
+                    kind: StorageRecommendationKind::ReviewSender,
+                    target: sender.sender.clone(),
+                    messages: sender.messages,
+                    bytes: sender.bytes,
+                    description: format!(
+                        "Messages from {} use {:.0}% of the cached storage ({} bytes in {} messages)",
+                        sender.sender,
+                        share * 100.0,
+                        sender.bytes,
+                        sender.messages
+                    ),
+                });
+            }
+        }
+    }
+    recommendations.sort_by_key(|r| Reverse(r.bytes));
+    recommendations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::{cache::imap::mailbox::Attribute, common::Addr};
+
+    fn envelope(from: Option<(&str, &str)>, size: u32) -> EmailEnvelopeV7 {
+        EmailEnvelopeV7 {
+            from: from.map(|(name, address)| Addr {
+                name: Some(name.to_string()).filter(|n| !n.is_empty()),
+                address: Some(address.to_string()).filter(|a| !a.is_empty()),
+            }),
+            size,
+            ..Default::default()
+        }
+    }
+
+    fn mailbox(name: &str, attr: Option<AttributeEnum>) -> MailBox {
+        MailBox {
+            name: name.into(),
+            attributes: attr.map(|a| Attribute::new(a, None)).into_iter().collect(),
+            exists: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn aggregates_mailboxes_and_senders() {
+        let mut aggregator = UsageAggregator::default();
+        let inbox = aggregator.add_mailbox(
+            &mailbox("INBOX", None),
+            &[
+                envelope(Some(("Alice", "Alice@Example.com")), 100),
+                envelope(Some(("", "bob@example.com")), 50),
+                envelope(None, 10),
+            ],
+        );
+        assert_eq!(inbox.messages, 3);
+        assert_eq!(inbox.bytes, 160);
+        assert_eq!(inbox.server_messages, Some(3));
+
+        aggregator.add_mailbox(
+            &mailbox("Archive", None),
+            &[
+                envelope(Some(("", "alice@example.com")), 30),
+                envelope(Some(("Newsletter", "")), 80),
+            ],
+        );
+        let senders = aggregator.top_senders(2);
+        assert_eq!(senders.len(), 2);
+        assert_eq!(senders[0].sender, "alice@example.com");
+        assert_eq!(senders[0].name.as_deref(), Some("Alice"));
+        assert_eq!((senders[0].messages, senders[0].bytes), (2, 130));
+        assert_eq!(senders[1].sender, "newsletter");
+    }
+
+    #[test]
+    fn parses_status_responses() {
+        assert_eq!(
+            parse_status_response(
+                "* STATUS \"INBOX\" (MESSAGES 231 SIZE 44292)\r\nA0001 OK STATUS completed\r\n"
+            ),
+            Some((Some(231), Some(44292)))
+        );
+        assert_eq!(
+            parse_status_response("* STATUS Trash (MESSAGES 2)\r\nA2 OK done\r\n"),
+            Some((Some(2), None))
+        );
+        assert_eq!(
+            parse_status_response("A3 NO [NONEXISTENT] Unknown mailbox\r\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn recommends_cleanups() {
+        let trash = mailbox("Trash", Some(AttributeEnum::Trash));
+        let inbox = mailbox("INBOX", None);
+        let mailboxes = vec![
+            (
+                &trash,
+                MailboxUsage {
+                    mailbox_name: "Trash".into(),
+                    messages: 1,
+                    bytes: 1024,
+                    server_messages: Some(40),
+                    server_bytes: Some(DISPOSABLE_MAILBOX_THRESHOLD * 2),
+                    ..Default::default()
+                },
+            ),
+            (
+                &inbox,
+                MailboxUsage {
+                    mailbox_name: "INBOX".into(),
+                    messages: 10,
+                    bytes: DISPOSABLE_MAILBOX_THRESHOLD * 3,
+                    large_messages: 2,
+                    large_message_bytes: LARGE_MESSAGE_SIZE as u64 * 2,
+                    ..Default::default()
+                },
+            ),
+        ];
+        let senders = vec![
+            SenderUsage {
+                sender: "big@example.com".into(),
+                messages: 5,
+                bytes: DISPOSABLE_MAILBOX_THRESHOLD,
+                ..Default::default()
+            },
+            SenderUsage {
+                sender: "small@example.com".into(),
+                messages: 1,
+                bytes: 10,
+                ..Default::default()
+            },
+        ];
+        let recommendations = recommend(&mailboxes, &senders, DISPOSABLE_MAILBOX_THRESHOLD * 3);
+        let kinds: Vec<_> = recommendations
+            .iter()
+            .map(|r| (r.kind, r.target.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (StorageRecommendationKind::EmptyMailbox, "Trash"),
+                (StorageRecommendationKind::RemoveLargeMessages, "INBOX"),
+                (StorageRecommendationKind::ReviewSender, "big@example.com"),
+            ]
+        );
+        assert_eq!(recommendations[0].messages, 40);
+    }
+}
@@ -31,7 +31,8 @@ use crate::{
                 reply::{apply_references, apply_references2},
                 EmailHandler,
             },
-            util::generate_message_id,
+            subject::{reply_subject, SubjectPrefixes},
+            util::generate_account_message_id,
         },
     },
     raise_error,
@@ -66,10 +67,18 @@ pub struct AppendReplyToDraftRequest {
     /// This field is optional and can be used to provide HTML content.
     #[oai(validator(min_length = "1", max_length = "50000"))]
     pub html: Option<String>,
+    /// Subject prefixes used for the reply subject, including locale presets such as
+    /// `SV:` or `AW:`. Ignored for Graph API accounts, whose server sets the subject.
+    pub subject_prefixes: Option<SubjectPrefixes>,
 }
 
 impl AppendReplyToDraftRequest {
     fn validate(&self, gmail_or_graph: bool) -> RustMailerResult<()> {
+        if let Some(prefixes) = &self.subject_prefixes {
+            prefixes
+                .validate()
+                .map_err(|e| raise_error!(e, ErrorCode::InvalidParameter))?;
+        }
         if !gmail_or_graph {
             // IMAP account: uid and mailbox_name required
             if self.id.parse::<u32>().is_err() {
@@ -115,6 +124,10 @@ impl AppendReplyToDraftRequest {
                 )
                 .await
             }
+            MailerType::Jmap => Err(raise_error!(
+                "Creating reply drafts is not supported for JMAP accounts.".into(),
+                ErrorCode::Incompatible
+            )),
         }
     }
 
@@ -163,12 +176,12 @@ impl AppendReplyToDraftRequest {
                 })?,
         };
 
-        let subject = format!("Re: {}", envelope.subject.as_deref().unwrap_or(""));
+        let subject = reply_subject(envelope.subject.as_deref(), self.subject_prefixes.as_ref());
         let mut builder = MessageBuilder::new()
             .from(from)
             .to(Address::from(to.clone()))
             .subject(subject);
-        let message_id = generate_message_id();
+        let message_id = generate_account_message_id(account, &account.email);
         builder = builder.message_id(message_id.clone());
         builder = apply_references(builder, &envelope)?;
         builder = self.apply_content(builder)?;
@@ -183,7 +196,7 @@ impl AppendReplyToDraftRequest {
         executor
             .append(
                 drafts_mailbox.encoded_name().as_str(),
-                None,
+                Some("\\Seen \\Draft"),
                 None,
                 message.body,
             )
@@ -228,7 +241,7 @@ impl AppendReplyToDraftRequest {
                 })?,
         };
 
-        let subject = format!("Re: {}", envelope.subject.as_deref().unwrap_or(""));
+        let subject = reply_subject(envelope.subject.as_deref(), self.subject_prefixes.as_ref());
         let mut builder = MessageBuilder::new()
             .from(from)
             .to(Address::from(to.clone()))
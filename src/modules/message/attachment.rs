@@ -6,20 +6,24 @@ use crate::base64_decode_url_safe;
 use crate::modules::account::entity::MailerType;
 use crate::modules::cache::vendor::gmail::model::messages::PartBody;
 use crate::modules::cache::vendor::gmail::sync::client::GmailClient;
+use crate::modules::cache::vendor::jmap::sync::client::JmapClient;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::message::content::{AttachmentInfo, FullMessageContent};
 use crate::modules::message::get_minimal_meta;
 use crate::{
     encode_mailbox_name,
     modules::account::migration::AccountModel,
-    modules::cache::disk::DISK_CACHE,
+    modules::cache::disk::{encryption::CacheReader, CacheNamespace, DISK_CACHE},
     modules::context::executors::RUST_MAIL_CONTEXT,
+    modules::context::guard::{ImapRequestKind, IMAP_REQUEST_GUARD},
     modules::error::RustMailerResult,
     modules::imap::section::{ImapAttachment, SegmentPath},
     raise_error,
 };
 use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use tokio::io::AsyncReadExt;
 
 const MAX_ATTACHMENT_SIZE: usize = 52_428_800; // 50MB
 
@@ -38,16 +42,139 @@ pub struct AttachmentRequest {
     ///   that can be parsed back to a `u32`.
     /// - For Gmail API accounts, this is the message ID (`mid`) returned by the API.
     pub id: String,
-    /// Gmail API only: attachment info used to fetch it via Gmail API.
+    /// Gmail API and JMAP only: attachment info used to fetch it via the API.
     /// Not used for IMAP accounts.
     pub attachment_info: Option<AttachmentInfo>,
     /// Optional: The original filename of the attachment, if available.  
     /// - Gmail API only.  
     pub filename: Option<String>,
+    /// Optional: Offset of the first byte to return, e.g. to resume an interrupted
+    /// download. Defaults to the start of the attachment.
+    pub offset: Option<u64>,
+    /// Optional: Maximum number of bytes to return, e.g. to preview the start of a large
+    /// attachment. Defaults to the rest of the attachment.
+    ///
+    /// For IMAP accounts, ranges ending within the attachment are fetched partially from
+    /// the server, so the start of attachments exceeding the size limit can be previewed.
+    #[oai(validator(minimum(value = "1")))]
+    pub length: Option<u64>,
+}
+
+/// A byte range of the decoded content of an attachment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ByteRange {
+    /// Offset of the first byte.
+    pub offset: u64,
+    /// Maximum number of bytes, the rest of the attachment if not set.
+    pub length: Option<u64>,
+}
+
+impl ByteRange {
+    /// Parses the value of an HTTP `Range` header. Only single ranges with a start offset
+    /// are supported, such as `bytes=1024-` or `bytes=0-65535`.
+    pub fn parse_header(value: &str) -> RustMailerResult<Self> {
+        let invalid = || {
+            raise_error!(
+                format!(
+                    "Unsupported Range header '{}': expected 'bytes=<start>-[<end>]'",
+                    value
+                ),
+                ErrorCode::InvalidParameter
+            )
+        };
+        let spec = value
+            .trim()
+            .strip_prefix("bytes=")
+            .filter(|spec| !spec.contains(','))
+            .ok_or_else(invalid)?;
+        let (start, end) = spec.split_once('-').ok_or_else(invalid)?;
+        let offset = start.trim().parse::<u64>().map_err(|_| invalid())?;
+        let length = match end.trim() {
+            "" => None,
+            end => {
+                let end = end.parse::<u64>().map_err(|_| invalid())?;
+                if end < offset {
+                    return Err(invalid());
+                }
+                Some(end - offset + 1)
+            }
+        };
+        Ok(Self { offset, length })
+    }
+
+    /// Selects the range from the start of the decoded content, `total` being the size of
+    /// the whole attachment when known.
+    pub fn select(
+        &self,
+        mut data: Vec<u8>,
+        total: Option<u64>,
+    ) -> RustMailerResult<(Vec<u8>, ContentRange)> {
+        let available = data.len() as u64;
+        if self.offset >= available {
+            return Err(raise_error!(
+                format!(
+                    "Requested range starts at byte {}, beyond the end of the attachment",
+                    self.offset
+                ),
+                ErrorCode::RangeNotSatisfiable
+            ));
+        }
+        let end = self.length.map_or(available, |length| {
+            self.offset.saturating_add(length).min(available)
+        });
+        data.truncate(end as usize);
+        data.drain(..self.offset as usize);
+        let range = ContentRange {
+            start: self.offset,
+            length: end - self.offset,
+            total,
+        };
+        Ok((data, range))
+    }
+}
+
+/// The part of an attachment returned for a ranged request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ContentRange {
+    /// Offset of the first byte returned.
+    pub start: u64,
+    /// Number of bytes returned.
+    pub length: u64,
+    /// Size of the whole attachment, unknown when only its start was fetched.
+    pub total: Option<u64>,
+}
+
+impl ContentRange {
+    /// The value of the HTTP `Content-Range` header.
+    pub fn header(&self) -> String {
+        let total = self
+            .total
+            .map_or_else(|| "*".to_string(), |total| total.to_string());
+        format!(
+            "bytes {}-{}/{}",
+            self.start,
+            self.start + self.length - 1,
+            total
+        )
+    }
 }
 
 impl AttachmentRequest {
+    /// The requested byte range, if any.
+    pub fn range(&self) -> Option<ByteRange> {
+        (self.offset.is_some() || self.length.is_some()).then(|| ByteRange {
+            offset: self.offset.unwrap_or(0),
+            length: self.length,
+        })
+    }
+
     pub fn validate(&self, account: &AccountModel) -> RustMailerResult<()> {
+        if self.length == Some(0) {
+            return Err(raise_error!(
+                "`length` must be at least 1.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
         match account.mailer_type {
             MailerType::ImapSmtp => {
                 if self.mailbox.is_none() || self.attachment.is_none() {
@@ -74,6 +201,14 @@ impl AttachmentRequest {
                 }
             }
             MailerType::GraphApi => todo!(),
+            MailerType::Jmap => {
+                if self.attachment_info.is_none() {
+                    return Err(raise_error!(
+                        "Current account type is `JMAP`. Downloading attachments requires `attachment_info`.".into(),
+                        ErrorCode::InvalidParameter
+                    ));
+                }
+            }
         }
         Ok(())
     }
@@ -103,6 +238,10 @@ pub fn inline_attachment_diskcache_key(
     )
 }
 
+fn jmap_attachment_diskcache_key(account_id: u64, blob_id: &str) -> String {
+    format!("jmap_attachment_{}_{}", account_id, blob_id)
+}
+
 pub fn gmail_attachment_diskcache_key(
     account_id: u64,
     mid: &str,
@@ -129,12 +268,17 @@ pub fn gmail_inline_attachment_diskcache_key(
     )
 }
 
+/// Retrieves an attachment, along with its filename when known.
+///
+/// When the request selects a byte range, only that range is returned, described by the
+/// returned [`ContentRange`].
 pub async fn retrieve_email_attachment(
     account_id: u64,
     request: AttachmentRequest,
-) -> RustMailerResult<(cacache::Reader, Option<String>)> {
+) -> RustMailerResult<(CacheReader, Option<String>, Option<ContentRange>)> {
     let account = AccountModel::check_account_active(account_id, false).await?;
     request.validate(&account)?;
+    let range = request.range();
     match account.mailer_type {
         MailerType::ImapSmtp => {
             let mut attachment = request.attachment.ok_or_else(|| {
@@ -158,8 +302,14 @@ pub async fn retrieve_email_attachment(
                 )
             })?;
             let filename = attachment.filename.take();
+            if let Some(range) = range {
+                let (reader, range) =
+                    retrieve_imap_attachment_range(account_id, attachment, mailbox, uid, range)
+                        .await?;
+                return Ok((reader, filename, Some(range)));
+            }
             let reader = retrieve_imap_attachment(account_id, attachment, mailbox, uid).await?;
-            Ok((reader, filename))
+            Ok((reader, filename, None))
         }
         MailerType::GmailApi => {
             let attachment_info = request.attachment_info.as_ref().ok_or_else(|| {
@@ -170,9 +320,23 @@ pub async fn retrieve_email_attachment(
             })?;
             let filename = request.filename;
             let reader = retrieve_gmail_attachment(&account, &request.id, &attachment_info).await?;
-            Ok((reader, filename))
+            let (reader, range) = read_range(reader, range).await?;
+            Ok((reader, filename, range))
         }
         MailerType::GraphApi => todo!(),
+        MailerType::Jmap => {
+            let attachment_info = request.attachment_info.as_ref().ok_or_else(|| {
+                raise_error!(
+                    "`attachment_info` is required when retrieving attachments for JMAP accounts."
+                        .into(),
+                    ErrorCode::InvalidParameter
+                )
+            })?;
+            let filename = request.filename;
+            let reader = retrieve_jmap_attachment(&account, attachment_info).await?;
+            let (reader, range) = read_range(reader, range).await?;
+            Ok((reader, filename, range))
+        }
     }
 }
 
@@ -181,7 +345,7 @@ async fn retrieve_imap_attachment(
     attachment: ImapAttachment,
     mailbox: String,
     uid: u32,
-) -> RustMailerResult<cacache::Reader> {
+) -> RustMailerResult<CacheReader> {
     if attachment.size >= MAX_ATTACHMENT_SIZE {
         return Err(raise_error!(
             format!(
@@ -224,6 +388,9 @@ async fn retrieve_imap_attachment(
         ), ErrorCode::ExceedsLimitation));
     }
 
+    let _permit = IMAP_REQUEST_GUARD
+        .acquire(account_id, ImapRequestKind::Attachment)
+        .await?;
     let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
     // Fetch the attachment from the server
     let result = executor
@@ -248,7 +415,9 @@ async fn retrieve_imap_attachment(
         )
     })?;
     // Cache the result and return it
-    DISK_CACHE.put_cache(&cache_key, &decoded, false).await?;
+    DISK_CACHE
+        .put_cache(&cache_key, &decoded, CacheNamespace::Attachment)
+        .await?;
 
     // Cache the original inline attachment for replace cid with attachment content
     if attachment.inline {
@@ -261,7 +430,7 @@ async fn retrieve_imap_attachment(
         let inline_cache_key =
             inline_attachment_diskcache_key(account_id, &mailbox, uid, attachment.path.clone());
         DISK_CACHE
-            .put_cache(&inline_cache_key, &encoded, false)
+            .put_cache(&inline_cache_key, &encoded, CacheNamespace::Attachment)
             .await?;
     }
 
@@ -271,11 +440,122 @@ async fn retrieve_imap_attachment(
         .ok_or_else(|| raise_error!("Unexpected cache miss".into(), ErrorCode::InternalError))
 }
 
+/// Restricts fully retrieved content to the requested range, if any.
+pub async fn read_range(
+    reader: CacheReader,
+    range: Option<ByteRange>,
+) -> RustMailerResult<(CacheReader, Option<ContentRange>)> {
+    match range {
+        Some(range) => {
+            let (reader, range) = select_range(reader, range).await?;
+            Ok((reader, Some(range)))
+        }
+        None => Ok((reader, None)),
+    }
+}
+
+async fn select_range(
+    mut reader: CacheReader,
+    range: ByteRange,
+) -> RustMailerResult<(CacheReader, ContentRange)> {
+    let mut data = Vec::new();
+    reader
+        .read_to_end(&mut data)
+        .await
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    let total = data.len() as u64;
+    let (data, range) = range.select(data, Some(total))?;
+    Ok((CacheReader::Decrypted(Cursor::new(data)), range))
+}
+
+/// Retrieves a byte range of an IMAP attachment.
+///
+/// Cached attachments are sliced. Otherwise, a range ending within the attachment only
+/// fetches the start of the part with a partial `BODY.PEEK[<section>]<origin.length>`
+/// fetch, which is not cached. Open-ended ranges fetch the whole attachment.
+async fn retrieve_imap_attachment_range(
+    account_id: u64,
+    attachment: ImapAttachment,
+    mailbox: String,
+    uid: u32,
+    range: ByteRange,
+) -> RustMailerResult<(CacheReader, ContentRange)> {
+    let cache_key = attachment_diskcache_key(account_id, &mailbox, uid, attachment.path.clone());
+    if let Some(reader) = DISK_CACHE.get_cache(&cache_key).await? {
+        return select_range(reader, range).await;
+    }
+
+    if let Some(length) = range.length {
+        let end = range.offset.saturating_add(length);
+        if end >= MAX_ATTACHMENT_SIZE as u64 {
+            return Err(raise_error!(
+                format!(
+                    "Requested range ends at byte {}, beyond the maximum allowed size of {} bytes",
+                    end, MAX_ATTACHMENT_SIZE
+                ),
+                ErrorCode::ExceedsLimitation
+            ));
+        }
+        let encoded_length = attachment.encoded_prefix_length(end);
+        // Otherwise the range covers the whole part anyway.
+        if encoded_length < attachment.size as u64 {
+            let data = fetch_imap_attachment_prefix(
+                account_id,
+                &attachment,
+                &mailbox,
+                uid,
+                encoded_length,
+            )
+            .await?;
+            let (data, range) = range.select(data, None)?;
+            return Ok((CacheReader::Decrypted(Cursor::new(data)), range));
+        }
+    }
+
+    let reader = retrieve_imap_attachment(account_id, attachment, mailbox, uid).await?;
+    select_range(reader, range).await
+}
+
+/// Fetches and decodes the first `encoded_length` bytes of an IMAP attachment.
+async fn fetch_imap_attachment_prefix(
+    account_id: u64,
+    attachment: &ImapAttachment,
+    mailbox: &str,
+    uid: u32,
+    encoded_length: u64,
+) -> RustMailerResult<Vec<u8>> {
+    let _permit = IMAP_REQUEST_GUARD
+        .acquire(account_id, ImapRequestKind::Attachment)
+        .await?;
+    let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
+    let result = executor
+        .uid_fetch_partial_part(
+            &uid.to_string(),
+            &encode_mailbox_name!(mailbox),
+            &attachment.path.to_string(),
+            0,
+            encoded_length,
+        )
+        .await?;
+    let target = result.iter().find(|f| f.uid == Some(uid)).ok_or_else(|| {
+        raise_error!(
+            "Failed to fetch attachment from server".into(),
+            ErrorCode::InternalError
+        )
+    })?;
+    attachment.decode_prefix(target).ok_or_else(|| {
+        raise_error!(
+            "Failed to parse attachment content from result".into(),
+            ErrorCode::InternalError
+        )
+    })
+}
+
 async fn retrieve_gmail_attachment(
     account: &AccountModel,
     mid: &str,
     attachment_info: &AttachmentInfo,
-) -> RustMailerResult<cacache::Reader> {
+) -> RustMailerResult<CacheReader> {
     let cache_key = gmail_attachment_diskcache_key(account.id, mid, attachment_info);
     if let Some(reader) = DISK_CACHE.get_cache(&cache_key).await? {
         return Ok(reader);
@@ -327,13 +607,19 @@ async fn retrieve_gmail_attachment(
                     ErrorCode::InternalError
                 )
             })?;
-            DISK_CACHE.put_cache(&cache_key, &decoded, false).await?;
+            DISK_CACHE
+        .put_cache(&cache_key, &decoded, CacheNamespace::Attachment)
+        .await?;
             //Inline attachments directly cache the Base64-encoded content.
             if attachment.inline {
                 let inline_cache_key =
                     gmail_inline_attachment_diskcache_key(account.id, mid, attachment_info);
                 DISK_CACHE
-                    .put_cache(&inline_cache_key, data.as_bytes(), false)
+                    .put_cache(
+                        &inline_cache_key,
+                        data.as_bytes(),
+                        CacheNamespace::Attachment,
+                    )
                     .await?;
             }
             DISK_CACHE.get_cache(&cache_key).await?.ok_or_else(|| {
@@ -346,3 +632,90 @@ async fn retrieve_gmail_attachment(
         )),
     }
 }
+
+/// JMAP attachments are blobs, addressed by the blob id carried in `attachment_info.id`.
+/// Blobs are immutable, so the blob id alone identifies the cached content.
+async fn retrieve_jmap_attachment(
+    account: &AccountModel,
+    attachment_info: &AttachmentInfo,
+) -> RustMailerResult<CacheReader> {
+    let cache_key = jmap_attachment_diskcache_key(account.id, &attachment_info.id);
+    if let Some(reader) = DISK_CACHE.get_cache(&cache_key).await? {
+        return Ok(reader);
+    }
+    if attachment_info.size as usize >= MAX_ATTACHMENT_SIZE {
+        return Err(raise_error!(
+            format!(
+                "Attachment size {} bytes exceeds the maximum allowed size of {} bytes",
+                attachment_info.size, MAX_ATTACHMENT_SIZE
+            ),
+            ErrorCode::ExceedsLimitation
+        ));
+    }
+    let data = JmapClient::download_blob(
+        account,
+        &attachment_info.id,
+        &attachment_info.filename,
+        &attachment_info.file_type,
+    )
+    .await?;
+    DISK_CACHE
+        .put_cache(&cache_key, &data, CacheNamespace::Attachment)
+        .await?;
+    DISK_CACHE
+        .get_cache(&cache_key)
+        .await?
+        .ok_or_else(|| raise_error!("Unexpected cache miss".into(), ErrorCode::InternalError))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_range_headers() {
+        assert_eq!(
+            ByteRange::parse_header("bytes=1024-").unwrap(),
+            ByteRange {
+                offset: 1024,
+                length: None
+            }
+        );
+        assert_eq!(
+            ByteRange::parse_header("bytes=0-65535").unwrap(),
+            ByteRange {
+                offset: 0,
+                length: Some(65536)
+            }
+        );
+        assert!(ByteRange::parse_header("bytes=-500").is_err());
+        assert!(ByteRange::parse_header("bytes=0-1,4-5").is_err());
+        assert!(ByteRange::parse_header("bytes=10-5").is_err());
+        assert!(ByteRange::parse_header("items=0-5").is_err());
+    }
+
+    #[test]
+    fn selects_ranges() {
+        let range = ByteRange {
+            offset: 2,
+            length: Some(3),
+        };
+        let (data, content_range) = range.select(b"abcdefgh".to_vec(), Some(8)).unwrap();
+        assert_eq!(data, b"cde");
+        assert_eq!(content_range.header(), "bytes 2-4/8");
+
+        let rest = ByteRange {
+            offset: 6,
+            length: Some(100),
+        };
+        let (data, content_range) = rest.select(b"abcdefgh".to_vec(), None).unwrap();
+        assert_eq!(data, b"gh");
+        assert_eq!(content_range.header(), "bytes 6-7/*");
+
+        let beyond = ByteRange {
+            offset: 8,
+            length: None,
+        };
+        assert!(beyond.select(b"abcdefgh".to_vec(), Some(8)).is_err());
+    }
+}
@@ -0,0 +1,725 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::borrow::Cow;
+
+use ahash::AHashSet;
+use mail_parser::{MessageParser, MimeHeaders};
+use mail_send::mail_builder::{
+    mime::{BodyPart, MimePart},
+    MessageBuilder,
+};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::{
+    encode_mailbox_name,
+    modules::{
+        account::migration::AccountModel,
+        cache::imap::{
+            flags_to_hash,
+            mailbox::{EmailFlag, EnvelopeFlag, MailBox},
+            migration::EmailEnvelopeV7,
+        },
+        context::executors::RUST_MAIL_CONTEXT,
+        envelope::generate_uid_set,
+        error::{code::ErrorCode, RustMailerResult},
+        hook::{
+            channel::{Event, EVENT_CHANNEL},
+            events::{
+                payload::{AttachmentPolicyTriggered, DangerousAttachment},
+                EventPayload, EventType, RustMailerEvent,
+            },
+            task::EventHookTask,
+        },
+    },
+    raise_error,
+};
+
+/// IMAP keyword set on received emails that carry a dangerous attachment.
+pub const DANGEROUS_ATTACHMENT_KEYWORD: &str = "$DangerousAttachment";
+
+/// Mailbox quarantined emails are moved to when the policy does not name one.
+pub const DEFAULT_QUARANTINE_MAILBOX: &str = "Quarantine";
+
+const MAX_EXTENSIONS: usize = 100;
+const MAX_EXTENSION_LEN: usize = 16;
+
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "exe", "com", "pif", "scr", "msi", "msp", "msix", "appx", "dll", "cpl", "bat", "cmd", "lnk",
+    "reg", "inf", "msc", "gadget", "jar", "apk", "app", "dmg", "iso", "img", "vhd", "vhdx",
+];
+const EXECUTABLE_TYPES: &[&str] = &[
+    "application/x-msdownload",
+    "application/x-msdos-program",
+    "application/x-dosexec",
+    "application/x-executable",
+    "application/vnd.microsoft.portable-executable",
+    "application/x-msi",
+    "application/x-ms-installer",
+    "application/x-ms-shortcut",
+    "application/java-archive",
+    "application/x-iso9660-image",
+    "application/x-apple-diskimage",
+];
+const SCRIPT_EXTENSIONS: &[&str] = &[
+    "js", "jse", "mjs", "vbs", "vbe", "wsf", "wsh", "wsc", "sct", "ps1", "psm1", "psd1", "hta",
+];
+const SCRIPT_TYPES: &[&str] = &[
+    "application/javascript",
+    "application/x-javascript",
+    "application/ecmascript",
+    "text/javascript",
+    "text/vbscript",
+    "application/hta",
+];
+const MACRO_DOCUMENT_EXTENSIONS: &[&str] = &[
+    "docm", "dotm", "xlsm", "xltm", "xlsb", "xlam", "pptm", "potm", "ppam", "ppsm", "sldm",
+];
+const HTML_EXTENSIONS: &[&str] = &["html", "htm", "shtml", "xhtml", "mht", "mhtml", "svg"];
+const HTML_TYPES: &[&str] = &["text/html", "application/xhtml+xml", "image/svg+xml"];
+
+/// Kind of dangerous attachment recognized by an [`AttachmentPolicy`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Deserialize, Serialize, Enum)]
+pub enum AttachmentCategory {
+    /// Executables, installers, shortcuts and disk images, such as `.exe`, `.msi`, `.lnk`
+    /// or `.iso`. Outgoing attachments are also recognized by their content.
+    Executable,
+    /// Scripts run by the operating system, such as `.js`, `.vbs`, `.ps1` or `.hta`.
+    Script,
+    /// Office documents that can carry macros, such as `.docm` or `.xlsm`.
+    MacroDocument,
+    /// HTML and SVG files, commonly used for HTML smuggling and phishing pages.
+    Html,
+    /// An extension listed in `blocked_extensions`.
+    Blocked,
+}
+
+impl AttachmentCategory {
+    const BUILT_IN: [AttachmentCategory; 4] = [
+        AttachmentCategory::Executable,
+        AttachmentCategory::Script,
+        AttachmentCategory::MacroDocument,
+        AttachmentCategory::Html,
+    ];
+
+    /// Returns `true` if an attachment with this extension, lowercased MIME type and content
+    /// (when available) belongs to the category.
+    fn matches(
+        &self,
+        extension: Option<&str>,
+        content_type: &str,
+        contents: Option<&[u8]>,
+    ) -> bool {
+        let (extensions, types) = match self {
+            AttachmentCategory::Executable => (EXECUTABLE_EXTENSIONS, EXECUTABLE_TYPES),
+            AttachmentCategory::Script => (SCRIPT_EXTENSIONS, SCRIPT_TYPES),
+            AttachmentCategory::MacroDocument => (MACRO_DOCUMENT_EXTENSIONS, &[][..]),
+            AttachmentCategory::Html => (HTML_EXTENSIONS, HTML_TYPES),
+            AttachmentCategory::Blocked => return false,
+        };
+        if extension.is_some_and(|e| extensions.contains(&e)) || types.contains(&content_type) {
+            return true;
+        }
+        match self {
+            AttachmentCategory::Executable => contents.is_some_and(is_executable),
+            AttachmentCategory::MacroDocument => content_type.contains("macroenabled"),
+            AttachmentCategory::Html => contents.is_some_and(looks_like_html),
+            _ => false,
+        }
+    }
+}
+
+/// What happens to an outgoing email with a dangerous attachment.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum OutboundAttachmentAction {
+    /// The email is rejected with a `ContentPolicyViolation` error.
+    #[default]
+    Reject,
+    /// The dangerous attachments are removed and a note listing them is appended to the body.
+    Strip,
+    /// The email is sent unchanged; only an `AttachmentPolicyTriggered` event is emitted.
+    Flag,
+}
+
+/// What happens to a received email with a dangerous attachment.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum InboundAttachmentAction {
+    /// The email is tagged with the `$DangerousAttachment` keyword and left in place.
+    #[default]
+    Tag,
+    /// The email is tagged and moved to the quarantine mailbox.
+    Quarantine,
+}
+
+/// Per-account policy for dangerous attachment types, such as executables, scripts,
+/// macro-enabled documents and HTML files.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AttachmentPolicy {
+    /// Action taken on outgoing emails. If not set, outgoing emails are not screened.
+    pub outbound: Option<OutboundAttachmentAction>,
+    /// Action taken on emails arriving in synced mailboxes. If not set, received emails are
+    /// not screened.
+    ///
+    /// Only IMAP accounts that are not in minimal sync mode are screened, and only for emails
+    /// that arrive after the initial sync.
+    pub inbound: Option<InboundAttachmentAction>,
+    /// Built-in categories to screen for. If empty, all of them are screened.
+    pub categories: Vec<AttachmentCategory>,
+    /// Additional file extensions treated as dangerous, such as "zip".
+    pub blocked_extensions: Vec<String>,
+    /// File extensions never treated as dangerous, overriding the built-in categories.
+    pub allowed_extensions: Vec<String>,
+    /// Mailbox quarantined emails are moved to, created if missing. Defaults to `Quarantine`.
+    pub quarantine_mailbox: Option<String>,
+}
+
+impl AttachmentPolicy {
+    pub fn validate(&self) -> RustMailerResult<()> {
+        for (field, extensions) in [
+            ("blocked_extensions", &self.blocked_extensions),
+            ("allowed_extensions", &self.allowed_extensions),
+        ] {
+            if extensions.len() > MAX_EXTENSIONS {
+                return Err(raise_error!(
+                    format!("'{field}' cannot contain more than {MAX_EXTENSIONS} extensions."),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+            if let Some(invalid) = extensions.iter().find(|e| {
+                let e = normalize_extension(e);
+                e.is_empty()
+                    || e.len() > MAX_EXTENSION_LEN
+                    || !e.chars().all(|c| c.is_ascii_alphanumeric())
+            }) {
+                return Err(raise_error!(
+                    format!("Invalid file extension '{invalid}' in '{field}'."),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+        if self
+            .quarantine_mailbox
+            .as_deref()
+            .is_some_and(|m| m.trim().is_empty())
+        {
+            return Err(raise_error!(
+                "'quarantine_mailbox' cannot be empty.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn quarantine_mailbox(&self) -> &str {
+        self.quarantine_mailbox
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or(DEFAULT_QUARANTINE_MAILBOX)
+    }
+
+    /// Classifies an attachment by its file name, MIME type and, when available, its decoded
+    /// content. Returns `None` if the attachment is not dangerous under this policy.
+    pub fn classify(
+        &self,
+        filename: Option<&str>,
+        content_type: &str,
+        contents: Option<&[u8]>,
+    ) -> Option<AttachmentCategory> {
+        let extension = filename.and_then(file_extension);
+        if let Some(extension) = extension.as_deref() {
+            let listed = |list: &[String]| list.iter().any(|e| normalize_extension(e) == extension);
+            if listed(&self.allowed_extensions) {
+                return None;
+            }
+            if listed(&self.blocked_extensions) {
+                return Some(AttachmentCategory::Blocked);
+            }
+        }
+        let content_type = content_type.to_ascii_lowercase();
+        let content_type = content_type.split(';').next().unwrap_or_default().trim();
+        AttachmentCategory::BUILT_IN
+            .into_iter()
+            .filter(|c| self.categories.is_empty() || self.categories.contains(c))
+            .find(|c| c.matches(extension.as_deref(), content_type, contents))
+    }
+
+    /// Classifies one attachment part of an outgoing email.
+    fn inspect_part(&self, part: &MimePart<'_>) -> Option<DangerousAttachment> {
+        let mut raw = Vec::new();
+        part.clone().write_part(&mut raw).ok()?;
+        let message = MessageParser::new().parse(&raw)?;
+        let root = message.root_part();
+        let filename = root.attachment_name();
+        let content_type = root
+            .content_type()
+            .map(|ct| match ct.subtype() {
+                Some(subtype) => format!("{}/{}", ct.ctype(), subtype),
+                None => ct.ctype().to_string(),
+            })
+            .unwrap_or_else(|| "application/octet-stream".into());
+        let category = self.classify(filename, &content_type, Some(root.contents()))?;
+        Some(DangerousAttachment {
+            filename: filename.map(Into::into),
+            content_type,
+            category: format!("{category:?}"),
+        })
+    }
+
+    /// Classifies the attachments of a received email.
+    fn inspect_envelope(&self, envelope: &EmailEnvelopeV7) -> Vec<DangerousAttachment> {
+        envelope
+            .attachments
+            .iter()
+            .flatten()
+            // Unnamed inline parts are embedded images or alternative bodies, not files.
+            .filter(|a| !(a.inline && a.filename.is_none()))
+            .filter_map(|a| {
+                let category = self.classify(a.filename.as_deref(), &a.file_type, None)?;
+                Some(DangerousAttachment {
+                    filename: a.filename.clone(),
+                    content_type: a.file_type.clone(),
+                    category: format!("{category:?}"),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Screens the attachments of an outgoing email against the account's attachment policy.
+///
+/// Depending on the outbound action, fails with a `ContentPolicyViolation` error, removes the
+/// dangerous attachments and notes their removal in the body, or lets the email through
+/// unchanged. Returns the number of removed attachments. Unless `dry_run` is set, an
+/// `AttachmentPolicyTriggered` event is emitted to watching hooks.
+pub async fn screen_outbound_attachments(
+    account: &AccountModel,
+    builder: &mut MessageBuilder<'_>,
+    subject: Option<&str>,
+    message_id: &str,
+    dry_run: bool,
+) -> RustMailerResult<usize> {
+    let Some(policy) = &account.attachment_policy else {
+        return Ok(0);
+    };
+    let Some(action) = policy.outbound else {
+        return Ok(0);
+    };
+
+    let (indices, attachments): (Vec<usize>, Vec<DangerousAttachment>) = builder
+        .attachments
+        .iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(i, part)| policy.inspect_part(part).map(|a| (i, a)))
+        .unzip();
+    if attachments.is_empty() {
+        return Ok(0);
+    }
+
+    let names = attachment_names(&attachments);
+    warn!(
+        account_id = account.id,
+        message_id = message_id,
+        action = ?action,
+        "Outgoing email has dangerous attachments: {}",
+        names
+    );
+    if !dry_run {
+        emit_event(
+            account,
+            AttachmentPolicyTriggered {
+                account_id: account.id,
+                account_email: account.email.clone(),
+                direction: "Outbound".into(),
+                action: format!("{action:?}"),
+                mailbox_name: None,
+                uid: None,
+                message_id: Some(message_id.to_string()),
+                subject: subject.map(Into::into),
+                attachments: attachments.clone(),
+            },
+        )
+        .await;
+    }
+
+    match action {
+        OutboundAttachmentAction::Reject => Err(raise_error!(
+            format!("Email not sent: attachments not allowed by the account's attachment policy: {names}"),
+            ErrorCode::ContentPolicyViolation
+        )),
+        OutboundAttachmentAction::Strip => {
+            builder.attachments = builder
+                .attachments
+                .take()
+                .map(|parts| {
+                    parts
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(i, _)| !indices.contains(i))
+                        .map(|(_, part)| part)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|parts| !parts.is_empty());
+            annotate_body(builder, &names, attachments.len());
+            Ok(attachments.len())
+        }
+        OutboundAttachmentAction::Flag => Ok(0),
+    }
+}
+
+/// Screens newly fetched envelopes of an IMAP mailbox against the account's attachment policy.
+///
+/// Emails with a dangerous attachment are tagged with the [`DANGEROUS_ATTACHMENT_KEYWORD`]
+/// keyword, both on the server and in `envelopes`. With the `Quarantine` action they are then
+/// moved to the quarantine mailbox and removed from `envelopes`, and their UIDs are returned so
+/// that callers can skip them. An `AttachmentPolicyTriggered` event is emitted for each email.
+///
+/// Failures are logged and do not fail the sync; the affected emails are left in place.
+pub async fn screen_inbound_envelopes(
+    account: &AccountModel,
+    mailbox: &MailBox,
+    envelopes: &mut Vec<EmailEnvelopeV7>,
+) -> AHashSet<u32> {
+    let mut quarantined = AHashSet::new();
+    let Some(policy) = &account.attachment_policy else {
+        return quarantined;
+    };
+    let Some(action) = policy.inbound else {
+        return quarantined;
+    };
+    let quarantine_mailbox = policy.quarantine_mailbox();
+    if account.minimal_sync() || mailbox.name == quarantine_mailbox {
+        return quarantined;
+    }
+
+    let keyword = EnvelopeFlag::new(
+        EmailFlag::Custom,
+        Some(DANGEROUS_ATTACHMENT_KEYWORD.to_string()),
+    );
+    let flagged: Vec<(u32, Vec<DangerousAttachment>)> = envelopes
+        .iter()
+        .filter(|e| !e.flags.contains(&keyword))
+        .map(|e| (e.uid, policy.inspect_envelope(e)))
+        .filter(|(_, attachments)| !attachments.is_empty())
+        .collect();
+    if flagged.is_empty() {
+        return quarantined;
+    }
+    let uid_set = generate_uid_set(flagged.iter().map(|(uid, _)| *uid).collect());
+
+    let executor = match RUST_MAIL_CONTEXT.imap(account.id).await {
+        Ok(executor) => executor,
+        Err(e) => {
+            error!(account_id = account.id, error = %e, "Failed to screen attachments of new emails");
+            return quarantined;
+        }
+    };
+    if let Err(e) = executor
+        .uid_set_flags(
+            &uid_set,
+            &mailbox.name,
+            Some(vec![keyword.clone()]),
+            None,
+            None,
+        )
+        .await
+    {
+        error!(
+            account_id = account.id,
+            mailbox = %mailbox.name,
+            error = %e,
+            "Failed to tag emails with dangerous attachments"
+        );
+        return quarantined;
+    }
+    for envelope in envelopes
+        .iter_mut()
+        .filter(|e| flagged.iter().any(|(uid, _)| *uid == e.uid))
+    {
+        envelope.flags.push(keyword.clone());
+        envelope.flags_hash = flags_to_hash(&envelope.flags);
+    }
+
+    let mut taken = InboundAttachmentAction::Tag;
+    if action == InboundAttachmentAction::Quarantine {
+        let target = encode_mailbox_name!(quarantine_mailbox);
+        let mut moved = executor
+            .uid_move_envelopes(&uid_set, &mailbox.encoded_name(), &target)
+            .await;
+        if moved.is_err() {
+            // The quarantine mailbox may not exist yet.
+            moved = match executor.create_mailbox(&target).await {
+                Ok(()) => {
+                    executor
+                        .uid_move_envelopes(&uid_set, &mailbox.encoded_name(), &target)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+        }
+        match moved {
+            Ok(()) => {
+                taken = InboundAttachmentAction::Quarantine;
+                quarantined = flagged.iter().map(|(uid, _)| *uid).collect();
+            }
+            Err(e) => error!(
+                account_id = account.id,
+                mailbox = %mailbox.name,
+                error = %e,
+                "Failed to quarantine emails with dangerous attachments, leaving them tagged in place"
+            ),
+        }
+    }
+
+    for (uid, attachments) in flagged {
+        let envelope = envelopes.iter().find(|e| e.uid == uid);
+        warn!(
+            account_id = account.id,
+            mailbox = %mailbox.name,
+            uid = uid,
+            action = ?taken,
+            "Received email has dangerous attachments: {}",
+            attachment_names(&attachments)
+        );
+        emit_event(
+            account,
+            AttachmentPolicyTriggered {
+                account_id: account.id,
+                account_email: account.email.clone(),
+                direction: "Inbound".into(),
+                action: format!("{taken:?}"),
+                mailbox_name: Some(mailbox.name.clone()),
+                uid: Some(uid),
+                message_id: envelope.and_then(|e| e.message_id.clone()),
+                subject: envelope.and_then(|e| e.subject.clone()),
+                attachments,
+            },
+        )
+        .await;
+    }
+    envelopes.retain(|e| !quarantined.contains(&e.uid));
+    quarantined
+}
+
+async fn emit_event(account: &AccountModel, payload: AttachmentPolicyTriggered) {
+    match EventHookTask::is_watching_attachment_policy_triggered(account.id).await {
+        Ok(true) => {
+            EVENT_CHANNEL
+                .queue(Event::new(
+                    account.id,
+                    &account.email,
+                    RustMailerEvent::new(
+                        EventType::AttachmentPolicyTriggered,
+                        EventPayload::AttachmentPolicyTriggered(payload),
+                    ),
+                ))
+                .await;
+        }
+        Ok(false) => {}
+        Err(e) => {
+            error!(
+                account_id = account.id,
+                error = %e,
+                "Failed to check event_watched for AttachmentPolicyTriggered"
+            );
+        }
+    }
+}
+
+fn attachment_names(attachments: &[DangerousAttachment]) -> String {
+    attachments
+        .iter()
+        .map(|a| a.filename.as_deref().unwrap_or("(unnamed)"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Appends a note about the removed attachments to the text and HTML bodies.
+fn annotate_body(builder: &mut MessageBuilder<'_>, names: &str, count: usize) {
+    let note = format!("{count} attachment(s) removed by the sender's security policy: {names}");
+    if let Some(BodyPart::Text(text)) = builder.text_body.as_mut().map(|p| &mut p.contents) {
+        *text = Cow::Owned(format!("{text}\n\n[{note}]"));
+    }
+    if let Some(BodyPart::Text(html)) = builder.html_body.as_mut().map(|p| &mut p.contents) {
+        *html = Cow::Owned(append_html_note(html, &note));
+    }
+}
+
+fn append_html_note(html: &str, note: &str) -> String {
+    let note = format!(
+        "<p style=\"color:#666;font-size:12px\">{}</p>",
+        html_escape::encode_text(note)
+    );
+    match html.to_ascii_lowercase().rfind("</body>") {
+        Some(end) => format!("{}{}{}", &html[..end], note, &html[end..]),
+        None => format!("{html}{note}"),
+    }
+}
+
+/// The lowercased extension of a file name.
+fn file_extension(filename: &str) -> Option<String> {
+    // Windows ignores trailing dots and spaces, so `invoice.exe.` still runs as an executable.
+    let name = filename.trim_end_matches(['.', ' ']);
+    let (_, extension) = name.rsplit_once('.')?;
+    (!extension.is_empty()).then(|| extension.to_ascii_lowercase())
+}
+
+fn normalize_extension(extension: &str) -> String {
+    extension
+        .trim()
+        .trim_start_matches('.')
+        .to_ascii_lowercase()
+}
+
+/// Returns `true` for Windows (PE) and ELF executables.
+fn is_executable(contents: &[u8]) -> bool {
+    if contents.starts_with(b"\x7fELF") {
+        return true;
+    }
+    if contents.len() < 64 || !contents.starts_with(b"MZ") {
+        return false;
+    }
+    let offset = u32::from_le_bytes([contents[60], contents[61], contents[62], contents[63]]);
+    let offset = offset as usize;
+    contents.get(offset..offset.saturating_add(4)) == Some(b"PE\0\0")
+}
+
+fn looks_like_html(contents: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&contents[..contents.len().min(512)]).to_lowercase();
+    let head = head.trim_start_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    head.starts_with("<!doctype html") || head.starts_with("<html")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pe_header() -> Vec<u8> {
+        let mut exe = vec![0u8; 128];
+        exe[..2].copy_from_slice(b"MZ");
+        exe[60] = 64;
+        exe[64..68].copy_from_slice(b"PE\0\0");
+        exe
+    }
+
+    #[test]
+    fn classifies_by_extension_and_type() {
+        let policy = AttachmentPolicy::default();
+        let classify =
+            |name: &str, content_type: &str| policy.classify(Some(name), content_type, None);
+
+        assert_eq!(
+            classify("setup.EXE", "application/octet-stream"),
+            Some(AttachmentCategory::Executable)
+        );
+        assert_eq!(
+            classify("invoice.pdf.exe. ", "application/pdf"),
+            Some(AttachmentCategory::Executable)
+        );
+        assert_eq!(
+            classify("run.js", "text/plain"),
+            Some(AttachmentCategory::Script)
+        );
+        assert_eq!(
+            classify("budget.xlsm", "application/octet-stream"),
+            Some(AttachmentCategory::MacroDocument)
+        );
+        assert_eq!(
+            classify(
+                "budget.bin",
+                "application/vnd.ms-excel.sheet.macroEnabled.12"
+            ),
+            Some(AttachmentCategory::MacroDocument)
+        );
+        assert_eq!(
+            classify("payment.htm", "application/octet-stream"),
+            Some(AttachmentCategory::Html)
+        );
+        assert_eq!(
+            classify("notice", "text/html; charset=utf-8"),
+            Some(AttachmentCategory::Html)
+        );
+        assert_eq!(classify("report.pdf", "application/pdf"), None);
+        assert_eq!(classify("photo.jpg", "image/jpeg"), None);
+    }
+
+    #[test]
+    fn sniffs_outgoing_content() {
+        let policy = AttachmentPolicy::default();
+        assert_eq!(
+            policy.classify(Some("report.pdf"), "application/pdf", Some(&pe_header())),
+            Some(AttachmentCategory::Executable)
+        );
+        assert_eq!(
+            policy.classify(
+                Some("notes.txt"),
+                "text/plain",
+                Some(b"\xef\xbb\xbf  <!DOCTYPE html><html><script>")
+            ),
+            Some(AttachmentCategory::Html)
+        );
+        assert_eq!(
+            policy.classify(
+                Some("notes.txt"),
+                "text/plain",
+                Some(b"MZ Holdings annual report")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn applies_custom_lists_and_categories() {
+        let policy = AttachmentPolicy {
+            categories: vec![AttachmentCategory::Executable],
+            blocked_extensions: vec![".ZIP".into()],
+            allowed_extensions: vec!["msi".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.classify(Some("archive.zip"), "application/zip", None),
+            Some(AttachmentCategory::Blocked)
+        );
+        assert_eq!(
+            policy.classify(Some("agent.msi"), "application/x-msi", None),
+            None
+        );
+        assert_eq!(policy.classify(Some("page.html"), "text/html", None), None);
+        assert_eq!(
+            policy.classify(Some("tool.exe"), "application/octet-stream", None),
+            Some(AttachmentCategory::Executable)
+        );
+    }
+
+    #[test]
+    fn validates_extensions() {
+        let policy = |blocked: &str| AttachmentPolicy {
+            blocked_extensions: vec![blocked.into()],
+            ..Default::default()
+        };
+        assert!(policy(".7z").validate().is_ok());
+        assert!(policy("tar.gz").validate().is_err());
+        assert!(policy(" ").validate().is_err());
+        assert!(AttachmentPolicy {
+            quarantine_mailbox: Some("  ".into()),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn notes_removed_attachments_in_html() {
+        assert_eq!(
+            append_html_note("<html><BODY>Hi</BODY></html>", "removed: <a>.exe"),
+            "<html><BODY>Hi<p style=\"color:#666;font-size:12px\">removed: &lt;a&gt;.exe</p></BODY></html>"
+        );
+        assert_eq!(
+            append_html_note("<p>Hi</p>", "x"),
+            "<p>Hi</p><p style=\"color:#666;font-size:12px\">x</p>"
+        );
+    }
+}
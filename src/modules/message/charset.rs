@@ -0,0 +1,141 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+/// The charset a text body part was decoded with.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ContentCharset {
+    /// The charset declared by the `Content-Type` of the part, if any.
+    pub declared: Option<String>,
+    /// The charset the content was decoded with, e.g. `UTF-8`, `ISO-2022-JP`, `Shift_JIS`,
+    /// `GBK`, `Big5` or `KOI8-R`.
+    pub charset: String,
+    /// Whether `charset` was detected from the content, because the declared charset was
+    /// missing, unknown or did not match the content.
+    pub detected: bool,
+}
+
+/// Decodes a text body part into a string.
+///
+/// The declared charset is used when the content is valid in it. Otherwise the charset is
+/// detected from the content, which also overrides single-byte declarations (such as
+/// `ISO-8859-1`, which any content is valid in) when the detection is confident.
+pub fn decode_text(data: &[u8], declared: Option<&str>) -> (String, ContentCharset) {
+    let declared = declared
+        .map(|charset| charset.trim().trim_matches('"').to_string())
+        .filter(|charset| !charset.is_empty());
+    let declared_encoding = declared
+        .as_deref()
+        .and_then(|charset| Encoding::for_label(charset.as_bytes()));
+    let result = |text: String, encoding: &'static Encoding, detected: bool| {
+        let charset = ContentCharset {
+            declared: declared.clone(),
+            charset: encoding.name().into(),
+            detected,
+        };
+        (text, charset)
+    };
+
+    // ASCII content reads the same in every ASCII-compatible charset.
+    if data.is_ascii() && declared_encoding.is_none_or(|e| e.is_ascii_compatible()) {
+        let text = String::from_utf8_lossy(data).into_owned();
+        return result(text, declared_encoding.unwrap_or(UTF_8), false);
+    }
+
+    if let Some(encoding) = declared_encoding {
+        if let Some(text) = encoding.decode_without_bom_handling_and_without_replacement(data) {
+            if !encoding.is_single_byte() {
+                return result(text.into_owned(), encoding, false);
+            }
+            let (guess, confident) = detect(data);
+            if confident && guess != encoding {
+                if let Some(text) = guess.decode_without_bom_handling_and_without_replacement(data)
+                {
+                    return result(text.into_owned(), guess, true);
+                }
+            }
+            return result(text.into_owned(), encoding, false);
+        }
+    }
+
+    let (guess, _) = detect(data);
+    let (text, _) = guess.decode_without_bom_handling(data);
+    result(text.into_owned(), guess, true)
+}
+
+/// Extracts the `charset` parameter of a `Content-Type` header value.
+pub fn charset_param(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Guesses the charset of the content, and whether the guess is reliable.
+fn detect(data: &[u8]) -> (&'static Encoding, bool) {
+    let mut detector = EncodingDetector::new();
+    detector.feed(data, true);
+    detector.guess_assess(None, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{BIG5, GBK, ISO_2022_JP, KOI8_R};
+
+    use super::*;
+
+    fn encode(encoding: &'static Encoding, text: &str) -> Vec<u8> {
+        encoding.encode(text).0.into_owned()
+    }
+
+    #[test]
+    fn decodes_declared_charsets() {
+        let japanese = "会議の議事録を添付しますので、ご確認ください。";
+        let (text, charset) = decode_text(&encode(ISO_2022_JP, japanese), Some("iso-2022-jp"));
+        assert_eq!(text, japanese);
+        assert_eq!(charset.charset, "ISO-2022-JP");
+        assert!(!charset.detected);
+
+        let russian = "Добрый день! Высылаю вам отчёт за прошлый месяц.";
+        let (text, charset) = decode_text(&encode(KOI8_R, russian), Some("\"KOI8-R\""));
+        assert_eq!(text, russian);
+        assert_eq!(charset.declared.as_deref(), Some("KOI8-R"));
+
+        let (text, charset) = decode_text(b"Hello", None);
+        assert_eq!(text, "Hello");
+        assert_eq!(charset.charset, "UTF-8");
+        assert!(!charset.detected);
+    }
+
+    #[test]
+    fn detects_wrongly_declared_charsets() {
+        let chinese = "这是一封测试邮件，用于检查中文编码的自动识别功能是否正常工作。我们希望系统能够正确地显示邮件的内容。";
+        let (text, charset) = decode_text(&encode(GBK, chinese), Some("utf-8"));
+        assert_eq!(text, chinese);
+        assert_eq!(charset.charset, "GBK");
+        assert!(charset.detected);
+
+        let traditional = "這是一封測試郵件，用於檢查繁體中文編碼的自動識別功能是否正常運作。我們希望系統能夠正確地顯示郵件的內容。";
+        let (text, charset) = decode_text(&encode(BIG5, traditional), None);
+        assert_eq!(text, traditional);
+        assert_eq!(charset.charset, "Big5");
+        assert!(charset.detected);
+    }
+
+    #[test]
+    fn extracts_the_charset_parameter() {
+        assert_eq!(
+            charset_param("text/plain; format=flowed; Charset=\"ISO-2022-JP\""),
+            Some("ISO-2022-JP")
+        );
+        assert_eq!(charset_param("text/html;charset=gb2312"), Some("gb2312"));
+        assert_eq!(charset_param("text/plain"), None);
+    }
+}
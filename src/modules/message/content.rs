@@ -6,32 +6,35 @@ use crate::modules::account::entity::MailerType;
 use crate::modules::account::migration::AccountModel;
 use crate::modules::cache::vendor::gmail::model::messages::PartBody;
 use crate::modules::cache::vendor::gmail::sync::client::GmailClient;
+use crate::modules::cache::vendor::jmap::sync::client::JmapClient;
 use crate::modules::cache::vendor::outlook::model::{Attachment, Message};
 use crate::modules::cache::vendor::outlook::sync::client::OutlookClient;
+use crate::modules::common::filename::sanitize_filename;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::error::RustMailerError;
 use crate::modules::imap::section::Encoding;
 use crate::modules::message::attachment::inline_attachment_diskcache_key;
+use crate::modules::message::charset::{decode_text, ContentCharset};
 use crate::{base64_decode_url_safe, base64_encode, calculate_hash};
 use crate::{
     encode_mailbox_name,
     modules::{
-        cache::disk::DISK_CACHE,
-        context::executors::RUST_MAIL_CONTEXT,
+        cache::disk::{encryption::CacheReader, CacheNamespace, DISK_CACHE},
+        context::{
+            executors::RUST_MAIL_CONTEXT,
+            guard::{ImapRequestKind, IMAP_REQUEST_GUARD},
+        },
         error::RustMailerResult,
         imap::section::{EmailBodyPart, ImapAttachment, PartType, SegmentPath},
     },
     raise_error,
 };
 
-use cacache::Reader;
 use mime_guess::from_ext;
 use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
 use tokio::io::AsyncReadExt;
 
-const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
-
 /// Request for fetching the html/plain content of a specific email message.
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
 pub struct MessageContentRequest {
@@ -81,10 +84,10 @@ impl MessageContentRequest {
                     ));
                 }
             }
-            MailerType::GmailApi | MailerType::GraphApi => {
+            MailerType::GmailApi | MailerType::GraphApi | MailerType::Jmap => {
                 if self.mailbox.is_some() {
                     return Err(raise_error!(
-                        "`mailbox` must not be set for Gmail/Graph API accounts.".into(),
+                        "`mailbox` must not be set for Gmail/Graph API/JMAP accounts.".into(),
                         ErrorCode::InvalidParameter
                     ));
                 }
@@ -164,6 +167,12 @@ pub struct FullMessageContent {
     /// - **IMAP accounts**: Always `None`, since attachment metadata is already
     ///   included in the envelope.
     pub attachments: Option<Vec<AttachmentInfo>>,
+    /// The charset the plain text was decoded with, set when it was transcoded from the
+    /// raw MIME part (IMAP and Gmail API accounts).
+    pub plain_charset: Option<ContentCharset>,
+    /// The charset the HTML was decoded with, set when it was transcoded from the raw MIME
+    /// part (IMAP and Gmail API accounts).
+    pub html_charset: Option<ContentCharset>,
 }
 
 impl FullMessageContent {
@@ -215,7 +224,7 @@ fn outlook_content_diskcache_key(account_id: u64, mid: &str) -> String {
     format!("outlook_content_{}_{}", account_id, mid)
 }
 
-async fn read_string_from_reader(reader: &mut Reader) -> RustMailerResult<Option<String>> {
+async fn read_string_from_reader(reader: &mut CacheReader) -> RustMailerResult<Option<String>> {
     let mut buffer = Vec::new();
     if let Err(_) = reader.read_to_end(&mut buffer).await {
         return Ok(None);
@@ -227,50 +236,27 @@ async fn read_string_from_reader(reader: &mut Reader) -> RustMailerResult<Option
     }
 }
 
-async fn read_text_from_reader(
-    reader: &mut Reader,
-    max_length: Option<usize>,
-    actual_size: usize,
-) -> RustMailerResult<PlainText> {
-    let length_to_read = match max_length {
-        Some(max) => max.min(actual_size).min(MAX_BODY_SIZE),
-        None => actual_size,
-    };
-    let mut buffer = vec![0u8; length_to_read];
-    let bytes_read = reader
-        .read(&mut buffer)
-        .await
-        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
-    let truncated = bytes_read < actual_size;
-    let content = match std::str::from_utf8(&buffer[..bytes_read]) {
-        Ok(valid_str) => valid_str.into(),
-        Err(_) => "???".into(),
-    };
-    Ok(PlainText { content, truncated })
-}
-
-async fn read_html_from_reader(
-    reader: &mut Reader,
-    actual_size: usize,
-) -> RustMailerResult<String> {
-    let mut buffer = vec![0u8; actual_size];
-    let bytes_read = reader
-        .read(&mut buffer)
+async fn read_bytes_from_reader(reader: &mut CacheReader) -> RustMailerResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    reader
+        .read_to_end(&mut buffer)
         .await
         .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
-    let content = match std::str::from_utf8(&buffer[..bytes_read]) {
-        Ok(valid_str) => valid_str.into(),
-        Err(_) => "???".into(),
-    };
-    Ok(content)
+    Ok(buffer)
 }
 
-fn to_string(data: &[u8]) -> RustMailerResult<String> {
-    let content = match std::str::from_utf8(data) {
-        Ok(valid_str) => valid_str.into(),
-        Err(_) => "???".into(),
-    };
-    Ok(content)
+/// Truncates the text to at most `max` bytes, at a character boundary. Returns whether
+/// the text was truncated.
+fn truncate_text(text: &mut String, max: usize) -> bool {
+    if text.len() <= max {
+        return false;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    true
 }
 
 async fn replace_inline_attachments(
@@ -368,7 +354,7 @@ async fn replace_inline_attachments(
                     })?;
 
                     DISK_CACHE
-                        .put_cache(&inline_cache_key, &encoded, false)
+                        .put_cache(&inline_cache_key, &encoded, CacheNamespace::Attachment)
                         .await?;
                     String::from_utf8_lossy(&encoded).into_owned()
                 }
@@ -434,6 +420,9 @@ pub async fn retrieve_email_content(
             retrieve_outlook_message_content(account_id, request.id, request.max_length, skip_cache)
                 .await
         }
+        MailerType::Jmap => {
+            retrieve_jmap_message_content(&account, &request.id, request.max_length).await
+        }
     }
 }
 
@@ -446,140 +435,65 @@ async fn retrieve_imap_message_content(
     inline: Option<Vec<ImapAttachment>>,
     skip_cache: bool,
 ) -> RustMailerResult<FullMessageContent> {
-    let mut plain: Option<PlainText> = None;
-    let mut html: Option<String> = None;
+    let mut content = FullMessageContent::default();
 
     // Find Plain part
     if let Some(part) = sections.iter().find(|p| p.part_type == PartType::Plain) {
-        let content = if skip_cache {
-            // Skip cache and fetch directly
-            let decoded_content =
-                fetch_mail_part_from_imap(account_id, uid, &mailbox, part).await?;
-            let mut decoded_content = to_string(&decoded_content)?;
-
-            // Handle max_length truncation
-            if matches!(max_length, Some(max) if decoded_content.len() > max) {
-                decoded_content.truncate(max_length.unwrap());
-                PlainText {
-                    content: decoded_content,
-                    truncated: true,
-                }
-            } else {
-                PlainText {
-                    content: decoded_content,
-                    truncated: false,
-                }
-            }
-        } else {
-            // Try cache first
-            let cache_key =
-                email_content_diskcache_key(account_id, &mailbox, uid, part.path.clone());
-
-            if let Some(mut reader) = DISK_CACHE.get_cache(&cache_key).await? {
-                read_text_from_reader(&mut reader, max_length, part.size).await?
-            } else {
-                // Fetch from IMAP if not in cache
-                let decoded_content =
-                    fetch_mail_part_from_imap(account_id, uid, &mailbox, part).await?;
-                // Cache the decoded content
-                DISK_CACHE
-                    .put_cache(&cache_key, decoded_content.as_slice(), false)
-                    .await?;
-
-                let mut decoded_content = to_string(&decoded_content)?;
-
-                // Handle max_length truncation
-                if matches!(max_length, Some(max) if decoded_content.len() > max) {
-                    decoded_content.truncate(max_length.unwrap());
-                    PlainText {
-                        content: decoded_content,
-                        truncated: true,
-                    }
-                } else {
-                    PlainText {
-                        content: decoded_content,
-                        truncated: false,
-                    }
-                }
-            }
-        };
-        plain = Some(content);
+        let data = load_imap_part(account_id, uid, &mailbox, part, skip_cache).await?;
+        let (mut text, charset) = decode_text(&data, part.charset());
+        // Handle max_length truncation
+        let truncated = max_length.is_some_and(|max| truncate_text(&mut text, max));
+        content.plain = Some(PlainText {
+            content: text,
+            truncated,
+        });
+        content.plain_charset = Some(charset);
     }
 
     // Find HTML part
     if let Some(part) = sections.iter().find(|p| p.part_type == PartType::Html) {
-        let content = if skip_cache {
-            // Skip cache and fetch directly
-            let decoded_content =
-                fetch_mail_part_from_imap(account_id, uid, &mailbox, part).await?;
-            let mut decoded_content = to_string(&decoded_content)?;
-
-            // Handle inline attachments
-            if let Some(inline) = &inline {
-                replace_inline_attachments(
-                    account_id,
-                    &mailbox,
-                    uid,
-                    &mut decoded_content,
-                    inline,
-                    skip_cache,
-                )
+        let data = load_imap_part(account_id, uid, &mailbox, part, skip_cache).await?;
+        let (mut html, charset) = decode_text(&data, part.charset());
+        // Handle inline attachments
+        if let Some(inline) = &inline {
+            replace_inline_attachments(account_id, &mailbox, uid, &mut html, inline, skip_cache)
                 .await?;
-            }
-            decoded_content
-        } else {
-            // Try cache first
-            let cache_key =
-                email_content_diskcache_key(account_id, &mailbox, uid, part.path.clone());
-
-            if let Some(mut reader) = DISK_CACHE.get_cache(&cache_key).await? {
-                let mut content = read_html_from_reader(&mut reader, part.size).await?;
-                if let Some(inline) = &inline {
-                    replace_inline_attachments(
-                        account_id,
-                        &mailbox,
-                        uid,
-                        &mut content,
-                        inline,
-                        skip_cache,
-                    )
-                    .await?;
-                }
-                content
-            } else {
-                // Fetch from IMAP if not in cache
-                let decoded_content =
-                    fetch_mail_part_from_imap(account_id, uid, &mailbox, part).await?;
-                // Cache the decoded content
-                DISK_CACHE
-                    .put_cache(&cache_key, decoded_content.as_slice(), false)
-                    .await?;
-
-                let mut decoded_content = to_string(&decoded_content)?;
-
-                // Handle inline attachments
-                if let Some(inline) = &inline {
-                    replace_inline_attachments(
-                        account_id,
-                        &mailbox,
-                        uid,
-                        &mut decoded_content,
-                        inline,
-                        skip_cache,
-                    )
-                    .await?;
-                }
-                decoded_content
-            }
-        };
-        html = Some(content);
+        }
+        content.html = Some(html);
+        content.html_charset = Some(charset);
     }
 
-    Ok(FullMessageContent {
-        plain,
-        html,
-        attachments: None,
-    })
+    Ok(content)
+}
+
+/// Loads the transfer-decoded bytes of a body part, still in the charset of the part,
+/// trying the disk cache first.
+async fn load_imap_part(
+    account_id: u64,
+    uid: u32,
+    mailbox: &str,
+    part: &EmailBodyPart,
+    skip_cache: bool,
+) -> RustMailerResult<Vec<u8>> {
+    if skip_cache {
+        // Skip cache and fetch directly
+        return fetch_mail_part_from_imap(account_id, uid, mailbox, part).await;
+    }
+    let cache_key = email_content_diskcache_key(account_id, mailbox, uid, part.path.clone());
+    if let Some(mut reader) = DISK_CACHE.get_cache(&cache_key).await? {
+        return read_bytes_from_reader(&mut reader).await;
+    }
+    // Fetch from IMAP if not in cache
+    let decoded_content = fetch_mail_part_from_imap(account_id, uid, mailbox, part).await?;
+    // Cache the decoded content
+    DISK_CACHE
+        .put_cache(
+            &cache_key,
+            decoded_content.as_slice(),
+            CacheNamespace::Content,
+        )
+        .await?;
+    Ok(decoded_content)
 }
 
 async fn gmail_embed_inline_attachments(
@@ -630,7 +544,7 @@ async fn gmail_fetch_and_cache(
     if let Some(max_len) = max_length {
         if let Some(plain) = &mut message_content.plain {
             if plain.content.len() > max_len {
-                plain.content.truncate(max_len);
+                truncate_text(&mut plain.content, max_len);
                 plain.truncated = true;
             } else {
                 plain.truncated = false;
@@ -651,7 +565,7 @@ async fn gmail_fetch_and_cache(
         )
     })?;
     DISK_CACHE
-        .put_cache(cache_key, json.as_bytes(), false)
+        .put_cache(cache_key, json.as_bytes(), CacheNamespace::Content)
         .await?;
 
     Ok(message_content)
@@ -684,7 +598,7 @@ async fn retrieve_gmail_message_content(
             if let Some(max_len) = max_length {
                 if let Some(plain) = &mut message.plain {
                     if plain.content.len() > max_len {
-                        plain.content.truncate(max_len);
+                        truncate_text(&mut plain.content, max_len);
                         plain.truncated = true;
                     } else {
                         plain.truncated = false;
@@ -703,6 +617,9 @@ async fn fetch_mail_part_from_imap(
     mailbox: &str,
     part: &EmailBodyPart,
 ) -> RustMailerResult<Vec<u8>> {
+    let _permit = IMAP_REQUEST_GUARD
+        .acquire(account_id, ImapRequestKind::MessageContent)
+        .await?;
     let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
 
     let result = executor
@@ -761,7 +678,7 @@ async fn retrieve_outlook_message_content(
             if let Some(max_len) = max_length {
                 if let Some(plain) = &mut message.plain {
                     if plain.content.len() > max_len {
-                        plain.content.truncate(max_len);
+                        truncate_text(&mut plain.content, max_len);
                         plain.truncated = true;
                     } else {
                         plain.truncated = false;
@@ -774,6 +691,27 @@ async fn retrieve_outlook_message_content(
     outlook_fetch_and_cache(account_id, account.use_proxy, &mid, &cache_key, max_length).await
 }
 
+/// JMAP servers return decoded body values directly, so the content is not cached.
+async fn retrieve_jmap_message_content(
+    account: &AccountModel,
+    mid: &str,
+    max_length: Option<usize>,
+) -> RustMailerResult<FullMessageContent> {
+    let email = JmapClient::get_email(account, mid, true).await?;
+    let mut message_content: FullMessageContent = email.into();
+    if let Some(max_len) = max_length {
+        if let Some(plain) = &mut message_content.plain {
+            if plain.content.len() > max_len {
+                truncate_text(&mut plain.content, max_len);
+                plain.truncated = true;
+            } else {
+                plain.truncated = false;
+            }
+        }
+    }
+    Ok(message_content)
+}
+
 async fn outlook_fetch_and_cache(
     account_id: u64,
     use_proxy: Option<u64>,
@@ -787,7 +725,7 @@ async fn outlook_fetch_and_cache(
     if let Some(max_len) = max_length {
         if let Some(plain) = &mut message_content.plain {
             if plain.content.len() > max_len {
-                plain.content.truncate(max_len);
+                truncate_text(&mut plain.content, max_len);
                 plain.truncated = true;
             } else {
                 plain.truncated = false;
@@ -808,7 +746,7 @@ async fn outlook_fetch_and_cache(
         )
     })?;
     DISK_CACHE
-        .put_cache(cache_key, json.as_bytes(), false)
+        .put_cache(cache_key, json.as_bytes(), CacheNamespace::Content)
         .await?;
 
     Ok(message_content)
@@ -874,6 +812,7 @@ impl TryFrom<Message> for FullMessageContent {
             plain,
             html,
             attachments,
+            ..Default::default()
         })
     }
 }
@@ -887,7 +826,11 @@ impl From<Attachment> for AttachmentInfo {
             transfer_encoding: None,
             content_id: value.content_id,
             inline: value.is_inline.unwrap_or(false),
-            filename: value.name.unwrap_or_else(|| "unknown".to_string()),
+            filename: value
+                .name
+                .as_deref()
+                .and_then(sanitize_filename)
+                .unwrap_or_else(|| "unknown".to_string()),
             id: value.id,
             size: value.size.unwrap_or(0),
         }
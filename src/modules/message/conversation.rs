@@ -0,0 +1,269 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap, HashSet},
+};
+
+use poem_openapi::Enum;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::account::entity::MailerType;
+use crate::modules::account::migration::AccountModel;
+use crate::modules::cache::imap::address::AddressEntity;
+use crate::modules::cache::imap::migration::EmailEnvelopeV7;
+use crate::modules::cache::model::Envelope;
+use crate::modules::cache::vendor::gmail::sync::client::GmailClient;
+use crate::modules::cache::vendor::gmail::sync::envelope::GmailEnvelope;
+use crate::modules::cache::vendor::jmap::sync::envelope::JmapEnvelope;
+use crate::modules::cache::vendor::outlook::sync::envelope::OutlookEnvelope;
+use crate::modules::common::auth::ClientContext;
+use crate::modules::error::RustMailerResult;
+use crate::modules::message::list::get_thread_messages;
+
+/// Rounds of participant lookups. Each round follows the addresses of the messages
+/// linked in the previous one.
+const MAX_LOOKUP_ROUNDS: usize = 3;
+/// Cached envelopes examined at most per conversation.
+const MAX_CANDIDATES: usize = 2_000;
+/// Only messages dated within this many days of the thread are examined.
+const CONVERSATION_WINDOW_DAYS: i64 = 180;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Which messages make up a thread.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum ThreadScope {
+    /// The thread as computed by the sync of the account: by `References` for IMAP
+    /// accounts, or the thread of the provider for API accounts.
+    #[default]
+    Thread,
+    /// The thread together with the messages of every mailbox of the account linked to it
+    /// by `Message-ID`, `In-Reply-To` or `References`, so that replies in the Sent folder
+    /// are included even when they do not carry the full `References` chain.
+    Account,
+    /// Like `Account`, across all accounts accessible to the client, e.g. to follow a
+    /// conversation between two managed accounts.
+    AllAccounts,
+}
+
+/// Returns the messages of a thread, linked across mailboxes and accounts according to
+/// `scope`, newest first.
+///
+/// Linked messages are looked up among the cached messages exchanged with the participants
+/// of the thread, within `CONVERSATION_WINDOW_DAYS` days of it.
+pub async fn get_conversation(
+    context: &ClientContext,
+    account_id: u64,
+    thread_id: u64,
+    scope: ThreadScope,
+) -> RustMailerResult<Vec<Envelope>> {
+    let thread = get_thread_messages(account_id, thread_id).await?;
+    if scope == ThreadScope::Thread || thread.is_empty() {
+        return Ok(thread);
+    }
+
+    let accounts: Vec<AccountModel> = match scope {
+        ThreadScope::AllAccounts => {
+            let accessible = context.accessible_accounts()?;
+            AccountModel::list_all()
+                .await?
+                .into_iter()
+                .filter(|a| a.enabled && !a.minimal_sync())
+                .filter(|a| accessible.is_none_or(|set| set.iter().any(|i| i.id == a.id)))
+                .collect()
+        }
+        _ => vec![AccountModel::get(account_id).await?],
+    };
+    let own_addresses: HashSet<String> = accounts.iter().map(|a| a.email.to_lowercase()).collect();
+    let accounts: HashMap<u64, AccountModel> = accounts.into_iter().map(|a| (a.id, a)).collect();
+
+    let dates = thread.iter().filter_map(|e| e.internal_date.or(e.date));
+    let (Some(first), Some(last)) = (dates.clone().min(), dates.max()) else {
+        return Ok(thread);
+    };
+    let window = CONVERSATION_WINDOW_DAYS * DAY_MS;
+    let (after, before) = (first - window, last + window);
+
+    let mut conversation = thread;
+    let mut looked_up: HashSet<String> = HashSet::new();
+    let mut examined: HashSet<(u64, u64)> = HashSet::new();
+    for _ in 0..MAX_LOOKUP_ROUNDS {
+        let addresses: Vec<String> = lookup_addresses(&conversation, &own_addresses)
+            .into_iter()
+            .filter(|address| looked_up.insert(address.to_lowercase()))
+            .collect();
+        if addresses.is_empty() {
+            break;
+        }
+
+        let mut candidates = Vec::new();
+        for address in addresses {
+            let entities = AddressEntity::from(&address)
+                .await?
+                .into_iter()
+                .chain(AddressEntity::to(&address).await?)
+                .chain(AddressEntity::cc(&address).await?);
+            for entity in entities {
+                let ts = entity.internal_date.or(entity.date).unwrap_or_default();
+                if ts < after || ts > before || examined.len() >= MAX_CANDIDATES {
+                    continue;
+                }
+                let Some(account) = accounts.get(&entity.account_id) else {
+                    continue;
+                };
+                if !examined.insert((entity.account_id, entity.envelope_hash)) {
+                    continue;
+                }
+                if let Some(envelope) = load_envelope(account, entity.envelope_hash).await? {
+                    candidates.push(envelope);
+                }
+            }
+        }
+
+        let linked = conversation.len();
+        conversation = link_conversation(conversation, candidates);
+        if conversation.len() == linked {
+            break;
+        }
+    }
+    Ok(conversation)
+}
+
+async fn load_envelope(
+    account: &AccountModel,
+    envelope_hash: u64,
+) -> RustMailerResult<Option<Envelope>> {
+    Ok(match account.mailer_type {
+        MailerType::ImapSmtp => EmailEnvelopeV7::get(envelope_hash).await?.map(Into::into),
+        MailerType::GmailApi => match GmailEnvelope::get(envelope_hash).await? {
+            Some(envelope) => {
+                let map = GmailClient::label_map(account.id, account.use_proxy).await?;
+                Some(envelope.into_envelope(&map))
+            }
+            None => None,
+        },
+        MailerType::GraphApi => OutlookEnvelope::get(envelope_hash).await?.map(Into::into),
+        MailerType::Jmap => JmapEnvelope::get(envelope_hash).await?.map(Into::into),
+    })
+}
+
+/// The addresses to look up linked messages by: the participants of the conversation
+/// other than the accounts themselves, whose messages would be scanned entirely. Messages
+/// exchanged only between the accounts are looked up by the account addresses.
+fn lookup_addresses(conversation: &[Envelope], own_addresses: &HashSet<String>) -> Vec<String> {
+    let participants: BTreeSet<String> = conversation
+        .iter()
+        .flat_map(|e| {
+            e.from
+                .iter()
+                .chain(e.to.iter().flatten())
+                .chain(e.cc.iter().flatten())
+        })
+        .filter_map(|addr| addr.address.clone())
+        .collect();
+    let external: Vec<String> = participants
+        .iter()
+        .filter(|address| !own_addresses.contains(&address.to_lowercase()))
+        .cloned()
+        .collect();
+    if external.is_empty() {
+        participants.into_iter().collect()
+    } else {
+        external
+    }
+}
+
+/// Adds to `conversation` the candidates linked to it by `Message-ID`, `In-Reply-To` or
+/// `References`, directly or through other linked candidates, and returns the messages
+/// newest first.
+pub fn link_conversation(conversation: Vec<Envelope>, candidates: Vec<Envelope>) -> Vec<Envelope> {
+    let key = |e: &Envelope| (e.account_id, e.mailbox_id, e.id.clone());
+    let mut seen: HashSet<(u64, u64, String)> = conversation.iter().map(key).collect();
+    let mut ids: HashSet<String> = conversation.iter().flat_map(message_ids).collect();
+    let mut linked = conversation;
+    let mut remaining: Vec<Envelope> = candidates
+        .into_iter()
+        .filter(|e| seen.insert(key(e)))
+        .collect();
+
+    loop {
+        let (matched, rest): (Vec<Envelope>, Vec<Envelope>) = remaining
+            .into_iter()
+            .partition(|e| message_ids(e).iter().any(|id| ids.contains(id)));
+        remaining = rest;
+        if matched.is_empty() {
+            break;
+        }
+        for envelope in matched {
+            ids.extend(message_ids(&envelope));
+            linked.push(envelope);
+        }
+    }
+
+    linked.sort_by_key(|e| Reverse(e.internal_date));
+    linked
+}
+
+/// The Message-IDs an envelope is identified by or refers to, without angle brackets.
+fn message_ids(envelope: &Envelope) -> Vec<String> {
+    envelope
+        .message_id
+        .iter()
+        .chain(envelope.in_reply_to.iter())
+        .chain(envelope.references.iter().flatten())
+        .map(|id| {
+            id.trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string()
+        })
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(
+        mailbox_id: u64,
+        id: &str,
+        internal_date: i64,
+        message_id: &str,
+        in_reply_to: Option<&str>,
+    ) -> Envelope {
+        Envelope {
+            id: id.into(),
+            account_id: 1,
+            mailbox_id,
+            internal_date: Some(internal_date),
+            message_id: Some(message_id.into()),
+            in_reply_to: in_reply_to.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn links_messages_across_mailboxes_by_headers() {
+        let inbox = 1;
+        let sent = 2;
+        let question = envelope(inbox, "10", 100, "<q@example.com>", None);
+        // A reply without References is not in the thread of the question.
+        let reply = envelope(sent, "7", 200, "<r@example.com>", Some("<q@example.com>"));
+        let follow_up = envelope(inbox, "11", 300, "f@example.com", Some("r@example.com"));
+        let unrelated = envelope(inbox, "12", 400, "<u@example.com>", None);
+
+        let linked = link_conversation(
+            vec![question.clone()],
+            vec![
+                follow_up.clone(),
+                unrelated,
+                reply.clone(),
+                question.clone(),
+            ],
+        );
+        assert_eq!(linked, vec![follow_up, reply, question]);
+    }
+}
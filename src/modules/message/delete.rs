@@ -68,6 +68,10 @@ pub async fn move_to_trash(
         }
         MailerType::GmailApi => gmail_move_to_trash(&account, &request.ids).await,
         MailerType::GraphApi => outlook_move_to_trash(&account, &request.ids).await,
+        MailerType::Jmap => Err(raise_error!(
+            "Deleting messages is not supported for JMAP accounts.".into(),
+            ErrorCode::Incompatible
+        )),
     }
 }
 
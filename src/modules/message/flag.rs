@@ -5,7 +5,12 @@
 use crate::{
     modules::{
         account::migration::AccountModel,
-        cache::imap::mailbox::EnvelopeFlag,
+        cache::imap::{
+            journal::FlagChangeJournal,
+            mailbox::{EnvelopeFlag, MailBox},
+            manager::EnvelopeFlagsManager,
+            migration::EmailEnvelopeV7,
+        },
         context::executors::RUST_MAIL_CONTEXT,
         envelope::generate_uid_set,
         error::{code::ErrorCode, RustMailerResult},
@@ -78,10 +83,32 @@ impl FlagAction {
         validate_field(&self.overwrite)?;
         Ok(())
     }
+
+    /// Computes the flags resulting from applying this action to `current`.
+    ///
+    /// Mirrors the order used when storing the flags on the server: `overwrite` is
+    /// exclusive, otherwise `add` is applied before `remove`.
+    pub fn apply(&self, current: &[EnvelopeFlag]) -> Vec<EnvelopeFlag> {
+        if let Some(overwrite) = &self.overwrite {
+            return overwrite.clone();
+        }
+        let mut flags = current.to_vec();
+        if let Some(add) = &self.add {
+            for flag in add {
+                if !flags.contains(flag) {
+                    flags.push(flag.clone());
+                }
+            }
+        }
+        if let Some(remove) = &self.remove {
+            flags.retain(|f| !remove.contains(f));
+        }
+        flags
+    }
 }
 
 pub async fn modify_flags(account_id: u64, request: FlagMessageRequest) -> RustMailerResult<()> {
-    AccountModel::check_account_active(account_id, true).await?;
+    let account = AccountModel::check_account_active(account_id, true).await?;
     request.validate()?;
 
     let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
@@ -91,10 +118,72 @@ pub async fn modify_flags(account_id: u64, request: FlagMessageRequest) -> RustM
         .uid_set_flags(
             &uid_set,
             &request.mailbox,
-            request.action.add,
-            request.action.remove,
-            request.action.overwrite,
+            request.action.add.clone(),
+            request.action.remove.clone(),
+            request.action.overwrite.clone(),
         )
         .await?;
+    apply_local_flag_change(&account, &request).await
+}
+
+/// Applies a flag change already acknowledged by the server to the local cache and
+/// records it in the flag change journal, so that an in-flight sync batch does not
+/// overwrite it with flags fetched before the change.
+async fn apply_local_flag_change(
+    account: &AccountModel,
+    request: &FlagMessageRequest,
+) -> RustMailerResult<()> {
+    // Only synchronized mailboxes have local state to update.
+    let Ok(mailbox) = MailBox::get(account.id, &request.mailbox).await else {
+        return Ok(());
+    };
+    FlagChangeJournal::record(account.id, mailbox.id, &request.uids);
+    // Minimal sync only keeps a hash of the flags, the next sync run refreshes it.
+    if account.minimal_sync() {
+        return Ok(());
+    }
+
+    let mut updates = Vec::with_capacity(request.uids.len());
+    for uid in &request.uids {
+        if let Some(envelope) = EmailEnvelopeV7::find(account.id, mailbox.id, *uid).await? {
+            let flags = request.action.apply(&envelope.flags);
+            if flags != envelope.flags {
+                updates.push((*uid, flags));
+            }
+        }
+    }
+    if !updates.is_empty() {
+        EnvelopeFlagsManager::update_envelope_flags(account, mailbox.id, updates).await?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::cache::imap::mailbox::EmailFlag;
+
+    #[test]
+    fn test_flag_action_apply() {
+        let seen = EnvelopeFlag::new(EmailFlag::Seen, None);
+        let flagged = EnvelopeFlag::new(EmailFlag::Flagged, None);
+        let answered = EnvelopeFlag::new(EmailFlag::Answered, None);
+
+        let action = FlagAction {
+            add: Some(vec![flagged.clone(), seen.clone()]),
+            remove: Some(vec![answered.clone()]),
+            overwrite: None,
+        };
+        assert_eq!(
+            action.apply(&[seen.clone(), answered.clone()]),
+            vec![seen.clone(), flagged.clone()]
+        );
+
+        let action = FlagAction {
+            add: Some(vec![flagged.clone()]),
+            remove: None,
+            overwrite: Some(vec![answered.clone()]),
+        };
+        assert_eq!(action.apply(&[seen]), vec![answered]);
+    }
+}
@@ -7,10 +7,16 @@ use crate::{
     modules::{
         account::{entity::MailerType, migration::AccountModel},
         cache::{
-            disk::DISK_CACHE,
-            vendor::{gmail::sync::client::GmailClient, outlook::sync::client::OutlookClient},
+            disk::{encryption::CacheReader, CacheNamespace, DISK_CACHE},
+            vendor::{
+                gmail::sync::client::GmailClient, jmap::sync::client::JmapClient,
+                outlook::sync::client::OutlookClient,
+            },
+        },
+        context::{
+            executors::RUST_MAIL_CONTEXT,
+            guard::{ImapRequestKind, IMAP_REQUEST_GUARD},
         },
-        context::executors::RUST_MAIL_CONTEXT,
         error::{code::ErrorCode, RustMailerResult},
     },
     raise_error,
@@ -42,11 +48,15 @@ fn outlook_raw_email_diskcache_key(account_id: u64, mid: &str) -> String {
     format!("outlook_raw_email_{}_{}", account_id, mid)
 }
 
+fn jmap_raw_email_diskcache_key(account_id: u64, mid: &str) -> String {
+    format!("jmap_raw_email_{}_{}", account_id, mid)
+}
+
 pub async fn retrieve_raw_email(
     account_id: u64,
     mailbox: Option<&str>,
     id: &str,
-) -> RustMailerResult<cacache::Reader> {
+) -> RustMailerResult<CacheReader> {
     let account = AccountModel::check_account_active(account_id, false).await?;
     match account.mailer_type {
         MailerType::ImapSmtp => {
@@ -66,6 +76,7 @@ pub async fn retrieve_raw_email(
         }
         MailerType::GmailApi => retrieve_gmail_raw_email(&account, id).await,
         MailerType::GraphApi => retrieve_outlook_raw_email(&account, id).await,
+        MailerType::Jmap => retrieve_jmap_raw_email(&account, id).await,
     }
 }
 
@@ -73,7 +84,7 @@ async fn retrieve_imap_raw_email(
     account_id: u64,
     mailbox: &str,
     uid: u32,
-) -> RustMailerResult<cacache::Reader> {
+) -> RustMailerResult<CacheReader> {
     let meta = get_minimal_meta(account_id, mailbox, uid).await?;
     if meta.size > MAX_EMAIL_TOTAL_SIZE {
         return Err(raise_error!(format!(
@@ -90,6 +101,9 @@ async fn retrieve_imap_raw_email(
         return Ok(reader);
     }
 
+    let _permit = IMAP_REQUEST_GUARD
+        .acquire(account_id, ImapRequestKind::FullMessage)
+        .await?;
     let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
     let fetch = executor
         .uid_fetch_full_message(uid.to_string().as_str(), mailbox)
@@ -111,7 +125,9 @@ async fn retrieve_imap_raw_email(
         )
     })?;
 
-    DISK_CACHE.put_cache(&cache_key, body, false).await?;
+    DISK_CACHE
+        .put_cache(&cache_key, body, CacheNamespace::Content)
+        .await?;
     DISK_CACHE
         .get_cache(&cache_key)
         .await?
@@ -121,7 +137,7 @@ async fn retrieve_imap_raw_email(
 async fn retrieve_gmail_raw_email(
     account: &AccountModel,
     mid: &str,
-) -> RustMailerResult<cacache::Reader> {
+) -> RustMailerResult<CacheReader> {
     let meta = GmailClient::get_message(account.id, account.use_proxy, mid).await?;
     if meta.size_estimate > MAX_EMAIL_TOTAL_SIZE {
         return Err(raise_error!(
@@ -149,7 +165,9 @@ async fn retrieve_gmail_raw_email(
         )
     })?;
 
-    DISK_CACHE.put_cache(&cache_key, &data, false).await?;
+    DISK_CACHE
+        .put_cache(&cache_key, &data, CacheNamespace::Content)
+        .await?;
     DISK_CACHE
         .get_cache(&cache_key)
         .await?
@@ -159,13 +177,44 @@ async fn retrieve_gmail_raw_email(
 async fn retrieve_outlook_raw_email(
     account: &AccountModel,
     mid: &str,
-) -> RustMailerResult<cacache::Reader> {
+) -> RustMailerResult<CacheReader> {
     let cache_key = outlook_raw_email_diskcache_key(account.id, mid);
     if let Some(reader) = DISK_CACHE.get_cache(&cache_key).await? {
         return Ok(reader);
     }
     let data = OutlookClient::get_raw_message(account.id, account.use_proxy, mid).await?;
-    DISK_CACHE.put_cache(&cache_key, &data, false).await?;
+    DISK_CACHE
+        .put_cache(&cache_key, &data, CacheNamespace::Content)
+        .await?;
+    DISK_CACHE
+        .get_cache(&cache_key)
+        .await?
+        .ok_or_else(|| raise_error!("Unexpected cache miss".into(), ErrorCode::InternalError))
+}
+
+async fn retrieve_jmap_raw_email(
+    account: &AccountModel,
+    mid: &str,
+) -> RustMailerResult<CacheReader> {
+    let cache_key = jmap_raw_email_diskcache_key(account.id, mid);
+    if let Some(reader) = DISK_CACHE.get_cache(&cache_key).await? {
+        return Ok(reader);
+    }
+    let email = JmapClient::get_email(account, mid, false).await?;
+    if email.size > MAX_EMAIL_TOTAL_SIZE {
+        return Err(raise_error!(
+            format!(
+                "Message size {} bytes exceeds maximum allowed size of {} bytes (mid: {})",
+                email.size, MAX_EMAIL_TOTAL_SIZE, mid
+            ),
+            ErrorCode::ExceedsLimitation
+        ));
+    }
+    let data =
+        JmapClient::download_blob(account, &email.blob_id, "message.eml", "message/rfc822").await?;
+    DISK_CACHE
+        .put_cache(&cache_key, &data, CacheNamespace::Content)
+        .await?;
     DISK_CACHE
         .get_cache(&cache_key)
         .await?
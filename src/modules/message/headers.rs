@@ -0,0 +1,166 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use base64::{engine::general_purpose, Engine as _};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    encode_mailbox_name,
+    modules::{
+        account::{entity::MailerType, migration::AccountModel},
+        cache::{disk::encryption::CacheReader, imap::migration::EmailEnvelopeV7},
+        context::executors::RUST_MAIL_CONTEXT,
+        error::{code::ErrorCode, RustMailerResult},
+        message::full::retrieve_raw_email,
+        utils::mailbox_id,
+    },
+    raise_error,
+};
+
+/// Header sections larger than this are truncated.
+const MAX_HEADER_SIZE: usize = 1024 * 1024;
+
+/// The raw header section of an email.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct RawHeaders {
+    /// The raw headers, decoded as UTF-8 with invalid bytes replaced.
+    pub headers: String,
+    /// The raw header bytes, base64 encoded.
+    pub headers_base64: String,
+    /// Set when the cached envelope of the email was only partially parsed, with the reason.
+    pub parse_error: Option<String>,
+    /// Whether the headers were read from a partially parsed envelope instead of being
+    /// fetched from the server. Envelopes only keep the header fields fetched during sync.
+    pub cached: bool,
+}
+
+impl RawHeaders {
+    fn new(header: &[u8], parse_error: Option<String>, cached: bool) -> Self {
+        Self {
+            headers: String::from_utf8_lossy(header).into_owned(),
+            headers_base64: general_purpose::STANDARD.encode(header),
+            parse_error,
+            cached,
+        }
+    }
+}
+
+/// Retrieves the raw headers of an email, without parsing them.
+///
+/// Works for emails whose envelope could only be partially parsed: for IMAP accounts, the
+/// header bytes kept with the envelope are returned, otherwise the full header section is
+/// fetched from the server.
+pub async fn retrieve_raw_headers(
+    account_id: u64,
+    mailbox: Option<&str>,
+    id: &str,
+) -> RustMailerResult<RawHeaders> {
+    let account = AccountModel::check_account_active(account_id, false).await?;
+    if !matches!(account.mailer_type, MailerType::ImapSmtp) {
+        let reader = retrieve_raw_email(account_id, mailbox, id).await?;
+        return Ok(RawHeaders::new(
+            &read_header_section(reader).await?,
+            None,
+            false,
+        ));
+    }
+
+    let mailbox = mailbox.ok_or_else(|| {
+        raise_error!(
+            "Missing required parameter: `mailbox` for IMAP/SMTP".into(),
+            ErrorCode::InvalidParameter
+        )
+    })?;
+    let uid = id.parse::<u32>().ok().ok_or_else(|| {
+        raise_error!(
+            "Invalid IMAP UID: `id` must be a numeric string".into(),
+            ErrorCode::InvalidParameter
+        )
+    })?;
+
+    let envelope = if account.minimal_sync() {
+        None
+    } else {
+        EmailEnvelopeV7::find(account_id, mailbox_id(account_id, mailbox), uid).await?
+    };
+    let parse_error = envelope.as_ref().and_then(|e| e.parse_error.clone());
+    if let Some(raw_headers) = envelope.and_then(|e| e.raw_headers) {
+        return Ok(RawHeaders::new(&raw_headers, parse_error, true));
+    }
+
+    let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
+    let fetches = executor
+        .uid_fetch_single_part(
+            &uid.to_string(),
+            encode_mailbox_name!(mailbox).as_str(),
+            "HEADER",
+        )
+        .await?;
+    let header = fetches
+        .iter()
+        .find(|f| f.uid == Some(uid))
+        .and_then(|f| f.header())
+        .ok_or_else(|| {
+            raise_error!(
+                format!("No headers found for UID {} in mailbox {}", uid, mailbox),
+                ErrorCode::ImapUnexpectedResult
+            )
+        })?;
+    Ok(RawHeaders::new(header, parse_error, false))
+}
+
+/// Reads the header section of a raw email, up to and including the blank line ending it.
+async fn read_header_section(mut reader: CacheReader) -> RustMailerResult<Vec<u8>> {
+    let mut header = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .await
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        if n == 0 {
+            break;
+        }
+        header.extend_from_slice(&buf[..n]);
+        if let Some(end) = header_end(&header) {
+            header.truncate(end);
+            break;
+        }
+        if header.len() >= MAX_HEADER_SIZE {
+            header.truncate(MAX_HEADER_SIZE);
+            break;
+        }
+    }
+    Ok(header)
+}
+
+/// The length of the header section, if the blank line ending it has been read.
+fn header_end(data: &[u8]) -> Option<usize> {
+    let crlf = data
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|p| p + 4);
+    let lf = data.windows(2).position(|w| w == b"\n\n").map(|p| p + 2);
+    match (crlf, lf) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_end_of_the_header_section() {
+        assert_eq!(
+            header_end(b"Subject: a\r\nTo: b\r\n\r\nbody\r\n\r\n"),
+            Some(21)
+        );
+        assert_eq!(header_end(b"Subject: a\n\nbody"), Some(12));
+        assert_eq!(header_end(b"Subject: a\r\nTo: b\r\n"), None);
+    }
+}
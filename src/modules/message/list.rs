@@ -7,17 +7,21 @@ use crate::{
     modules::{
         account::{entity::MailerType, migration::AccountModel},
         cache::{
-            imap::{mailbox::MailBox, migration::EmailEnvelopeV3, thread::EmailThread},
-            model::Envelope,
+            imap::{mailbox::MailBox, migration::EmailEnvelopeV7, thread::EmailThread},
+            model::{Envelope, EnvelopeSortField},
             vendor::{
                 gmail::sync::{client::GmailClient, envelope::GmailEnvelope, labels::GmailLabels},
+                jmap::sync::{client::JmapClient, envelope::JmapEnvelope, mailboxes::JmapMailbox},
                 outlook::sync::{
                     client::OutlookClient, envelope::OutlookEnvelope, folders::OutlookFolder,
                 },
             },
         },
-        common::{decode_page_token, parallel::run_with_limit},
-        context::executors::RUST_MAIL_CONTEXT,
+        common::{decode_page_token, importance::Importance, parallel::run_with_limit},
+        context::{
+            executors::RUST_MAIL_CONTEXT,
+            guard::{ImapRequestKind, IMAP_REQUEST_GUARD},
+        },
         envelope::extractor::extract_envelope,
         error::{code::ErrorCode, RustMailerResult},
         rest::response::{CursorDataPage, DataPage},
@@ -26,6 +30,7 @@ use crate::{
     raise_error,
 };
 use async_imap::types::Fetch;
+use futures::{stream, Stream};
 
 pub async fn list_messages_in_mailbox(
     account_id: u64,
@@ -34,6 +39,9 @@ pub async fn list_messages_in_mailbox(
     page_size: u64,
     remote: bool,
     desc: bool,
+    importance: Option<Importance>,
+    sort_by_importance: bool,
+    sort_by: Option<EnvelopeSortField>,
 ) -> RustMailerResult<CursorDataPage<Envelope>> {
     let account = AccountModel::check_account_active(account_id, false).await?;
     if page_size == 0 {
@@ -49,6 +57,55 @@ pub async fn list_messages_in_mailbox(
         ));
     }
     let remote = remote || account.minimal_sync();
+    let sort_by = sort_by.unwrap_or_default();
+    if sort_by != EnvelopeSortField::InternalDate {
+        if remote || !matches!(account.mailer_type, MailerType::ImapSmtp) {
+            return Err(raise_error!(
+                format!(
+                    "Sorting by {:?} is only supported for locally cached IMAP mailboxes; other listings are ordered by internal date.",
+                    sort_by
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if importance.is_some() || sort_by_importance {
+            return Err(raise_error!(
+                format!(
+                    "Sorting by {:?} cannot be combined with filtering or sorting by importance.",
+                    sort_by
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        return fetch_local_messages_sorted(
+            &account,
+            mailbox_name,
+            next_page_token,
+            page_size,
+            desc,
+            sort_by,
+        )
+        .await;
+    }
+    if importance.is_some() || sort_by_importance {
+        if remote || !matches!(account.mailer_type, MailerType::ImapSmtp) {
+            return Err(raise_error!(
+                "Filtering or sorting by importance is only supported for locally cached IMAP mailboxes."
+                    .into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        return fetch_local_messages_by_importance(
+            &account,
+            mailbox_name,
+            next_page_token,
+            page_size,
+            desc,
+            importance,
+            sort_by_importance,
+        )
+        .await;
+    }
     if remote {
         fetch_remote_messages(&account, mailbox_name, next_page_token, page_size, desc).await
     } else {
@@ -56,6 +113,63 @@ pub async fn list_messages_in_mailbox(
     }
 }
 
+/// Default number of envelopes per chunk when streaming a mailbox.
+pub const DEFAULT_STREAM_CHUNK_SIZE: u64 = 200;
+
+/// Streams the locally cached envelopes of a mailbox in chunks, newest first, so that clients
+/// can populate their own store with a single call.
+///
+/// With `since` (Unix epoch milliseconds), only envelopes received at or after that time are
+/// streamed, and reading stops at the first older envelope. Chunks may be empty.
+pub async fn stream_cached_envelopes(
+    account_id: u64,
+    mailbox_name: String,
+    since: Option<i64>,
+    chunk_size: u64,
+) -> RustMailerResult<impl Stream<Item = RustMailerResult<Vec<Envelope>>> + Send + 'static> {
+    let account = AccountModel::check_account_active(account_id, false).await?;
+    if chunk_size == 0 || chunk_size > 500 {
+        return Err(raise_error!(
+            "chunk_size must be between 1 and 500.".into(),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    if account.minimal_sync() {
+        return Err(raise_error!(
+            "Envelopes of this account are not cached locally, use the paginated listing with 'remote=true' instead."
+                .into(),
+            ErrorCode::MailBoxNotCached
+        ));
+    }
+    // Read the first chunk eagerly, so that an unknown mailbox fails the call itself.
+    let first = fetch_local_messages(&account, &mailbox_name, None, chunk_size, true).await?;
+    Ok(stream::try_unfold(Some(first), move |page| {
+        let account = account.clone();
+        let mailbox_name = mailbox_name.clone();
+        async move {
+            let Some(page) = page else {
+                return Ok(None);
+            };
+            let mut items = page.items;
+            let mut reached_since = false;
+            if let Some(since) = since {
+                reached_since = items
+                    .iter()
+                    .any(|e| e.internal_date.is_some_and(|date| date < since));
+                items.retain(|e| e.internal_date.is_none_or(|date| date >= since));
+            }
+            let next = match page.next_page_token {
+                Some(token) if !reached_since => Some(
+                    fetch_local_messages(&account, &mailbox_name, Some(&token), chunk_size, true)
+                        .await?,
+                ),
+                _ => None,
+            };
+            Ok(Some((items, next)))
+        }
+    }))
+}
+
 fn validate_pagination_params(page: u64, page_size: u64) -> RustMailerResult<()> {
     if page == 0 || page_size == 0 {
         return Err(raise_error!(
@@ -82,6 +196,9 @@ async fn fetch_remote_messages(
     match account.mailer_type {
         MailerType::ImapSmtp => {
             let page = decode_page_token(next_page_token)?;
+            let _permit = IMAP_REQUEST_GUARD
+                .acquire(account.id, ImapRequestKind::RemoteList)
+                .await?;
             let excutor = RUST_MAIL_CONTEXT.imap(account.id).await?;
             let (mut fetches, total_items) = excutor
                 .retrieve_metadata_paginated(
@@ -250,6 +367,57 @@ async fn fetch_remote_messages(
                 envelopes.into_iter().map(Into::into).collect(),
             ))
         }
+        MailerType::Jmap => {
+            let mailboxes = JmapClient::list_mailboxes(account).await?;
+            let mailbox = mailboxes
+                .into_iter()
+                .find(|m| m.name == mailbox_name)
+                .map(|m| JmapMailbox::new(account.id, m))
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!("Mailbox not found: {}", mailbox_name),
+                        ErrorCode::ResourceNotFound
+                    )
+                })?;
+            if mailbox.exists == 0 {
+                return Ok(CursorDataPage::new(
+                    None,
+                    Some(page_size),
+                    0,
+                    Some(0),
+                    vec![],
+                ));
+            }
+
+            let page = decode_page_token(next_page_token)?;
+            let (emails, total_items) = JmapClient::query_emails(
+                account,
+                &mailbox.mailbox_id,
+                (page - 1) * page_size,
+                page_size,
+                None,
+            )
+            .await?;
+            let envelopes = emails
+                .iter()
+                .map(|email| JmapEnvelope::new(account.id, &mailbox, email).map(Envelope::from))
+                .collect::<RustMailerResult<Vec<Envelope>>>()?;
+
+            let total_pages = (total_items as f64 / page_size as f64).ceil() as u64;
+            let next_page_token = if page >= total_pages {
+                None
+            } else {
+                Some(base64_encode_url_safe!((page + 1).to_string()))
+            };
+
+            Ok(CursorDataPage::new(
+                next_page_token,
+                Some(page_size),
+                total_items,
+                Some(total_pages),
+                envelopes,
+            ))
+        }
     }
 }
 
@@ -290,7 +458,7 @@ async fn fetch_local_messages(
                 total_items,
                 items,
                 total_pages,
-            } = EmailEnvelopeV3::list_messages_in_mailbox(mailbox.id, page, page_size, desc)
+            } = EmailEnvelopeV7::list_messages_in_mailbox(mailbox.id, page, page_size, desc)
                 .await?;
 
             if total_items == 0 {
@@ -367,6 +535,41 @@ async fn fetch_local_messages(
             } = OutlookEnvelope::list_messages_in_folder(target_label.id, page, page_size, desc)
                 .await?;
 
+            if total_items == 0 {
+                Ok(CursorDataPage::new(None, page_size, 0, None, vec![]))
+            } else {
+                let total_pages = total_pages.ok_or_else(|| {
+                    raise_error!(
+                        "Internal error: total_pages is None (this should never happen)".into(),
+                        ErrorCode::InternalError
+                    )
+                })?;
+
+                let next_page_token = if page == total_pages {
+                    None
+                } else {
+                    Some(base64_encode_url_safe!((page + 1).to_string()))
+                };
+
+                Ok(CursorDataPage::new(
+                    next_page_token,
+                    page_size,
+                    total_items,
+                    Some(total_pages),
+                    items.into_iter().map(|e| e.into()).collect(),
+                ))
+            }
+        }
+        MailerType::Jmap => {
+            let mailbox = JmapMailbox::get_by_name(account.id, mailbox_name).await?;
+            let DataPage {
+                current_page: _,
+                page_size,
+                total_items,
+                items,
+                total_pages,
+            } = JmapEnvelope::list_messages_in_folder(mailbox.id, page, page_size, desc).await?;
+
             if total_items == 0 {
                 Ok(CursorDataPage::new(None, page_size, 0, None, vec![]))
             } else {
@@ -395,6 +598,91 @@ async fn fetch_local_messages(
     }
 }
 
+async fn fetch_local_messages_by_importance(
+    account: &AccountModel,
+    mailbox_name: &str,
+    next_page_token: Option<&str>,
+    page_size: u64,
+    desc: bool,
+    importance: Option<Importance>,
+    sort_by_importance: bool,
+) -> RustMailerResult<CursorDataPage<Envelope>> {
+    let page = decode_page_token(next_page_token)?;
+    let mailbox = MailBox::get(account.id, mailbox_name).await.map_err(|_| {
+        raise_error!(
+            "This mailbox is not included in the synchronized mailbox list of the account.".into(),
+            ErrorCode::MailBoxNotCached
+        )
+    })?;
+    let DataPage {
+        current_page: _,
+        page_size,
+        total_items,
+        items,
+        total_pages,
+    } = EmailEnvelopeV7::list_messages_by_importance(
+        mailbox.id,
+        importance,
+        sort_by_importance,
+        page,
+        page_size,
+        desc,
+    )
+    .await?;
+
+    let next_page_token = match total_pages {
+        Some(total_pages) if page < total_pages => {
+            Some(base64_encode_url_safe!((page + 1).to_string()))
+        }
+        _ => None,
+    };
+    Ok(CursorDataPage::new(
+        next_page_token,
+        page_size,
+        total_items,
+        total_pages,
+        items.into_iter().map(Envelope::from).collect(),
+    ))
+}
+
+async fn fetch_local_messages_sorted(
+    account: &AccountModel,
+    mailbox_name: &str,
+    next_page_token: Option<&str>,
+    page_size: u64,
+    desc: bool,
+    sort_by: EnvelopeSortField,
+) -> RustMailerResult<CursorDataPage<Envelope>> {
+    let page = decode_page_token(next_page_token)?;
+    let mailbox = MailBox::get(account.id, mailbox_name).await.map_err(|_| {
+        raise_error!(
+            "This mailbox is not included in the synchronized mailbox list of the account.".into(),
+            ErrorCode::MailBoxNotCached
+        )
+    })?;
+    let DataPage {
+        current_page: _,
+        page_size,
+        total_items,
+        items,
+        total_pages,
+    } = EmailEnvelopeV7::list_messages_sorted(mailbox.id, sort_by, page, page_size, desc).await?;
+
+    let next_page_token = match total_pages {
+        Some(total_pages) if page < total_pages => {
+            Some(base64_encode_url_safe!((page + 1).to_string()))
+        }
+        _ => None,
+    };
+    Ok(CursorDataPage::new(
+        next_page_token,
+        page_size,
+        total_items,
+        total_pages,
+        items.into_iter().map(Envelope::from).collect(),
+    ))
+}
+
 pub async fn list_threads_in_mailbox(
     account_id: u64,
     mailbox_name: &str,
@@ -443,6 +731,10 @@ pub async fn list_threads_in_mailbox(
             let folder = OutlookFolder::get_by_name(account_id, mailbox_name).await?;
             EmailThread::list_threads_in_folder(folder.id, page, page_size, desc).await
         }
+        MailerType::Jmap => {
+            let mailbox = JmapMailbox::get_by_name(account_id, mailbox_name).await?;
+            EmailThread::list_threads_in_jmap_mailbox(mailbox.id, page, page_size, desc).await
+        }
     }
 }
 
@@ -464,7 +756,7 @@ pub async fn get_thread_messages(
     }
 
     match account.mailer_type {
-        MailerType::ImapSmtp => EmailEnvelopeV3::get_thread(account_id, thread_id).await,
+        MailerType::ImapSmtp => EmailEnvelopeV7::get_thread(account_id, thread_id).await,
         MailerType::GmailApi => {
             let envelopes = GmailEnvelope::get_thread(account_id, thread_id).await?;
             let map = GmailClient::label_map(account_id, account.use_proxy).await?;
@@ -477,5 +769,9 @@ pub async fn get_thread_messages(
             let envelopes = OutlookEnvelope::get_thread(account_id, thread_id).await?;
             Ok(envelopes.into_iter().map(|e| e.into()).collect())
         }
+        MailerType::Jmap => {
+            let envelopes = JmapEnvelope::get_thread(account_id, thread_id).await?;
+            Ok(envelopes.into_iter().map(|e| e.into()).collect())
+        }
     }
 }
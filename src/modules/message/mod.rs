@@ -11,10 +11,14 @@ use crate::modules::{envelope::MinimalEnvelopeMeta, error::RustMailerResult};
 
 pub mod append;
 pub mod attachment;
+pub mod attachment_policy;
+pub mod charset;
 pub mod content;
+pub mod conversation;
 pub mod delete;
 pub mod flag;
 pub mod full;
+pub mod headers;
 pub mod list;
 pub mod search;
 pub mod tags;
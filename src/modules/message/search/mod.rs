@@ -4,5 +4,7 @@
 
 pub mod cache;
 pub mod payload;
+pub mod query;
+pub mod saved;
 #[cfg(test)]
 mod tests;
\ No newline at end of file
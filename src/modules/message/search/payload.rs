@@ -5,17 +5,21 @@
 use crate::base64_encode_url_safe;
 use crate::modules::account::entity::MailerType;
 use crate::modules::cache::imap::address::AddressEntity;
-use crate::modules::cache::imap::migration::EmailEnvelopeV3;
+use crate::modules::cache::imap::migration::EmailEnvelopeV7;
 use crate::modules::cache::imap::sync::flow::generate_uid_sequence_hashset;
 use crate::modules::cache::model::Envelope;
 use crate::modules::cache::vendor::gmail::sync::client::GmailClient;
 use crate::modules::cache::vendor::gmail::sync::envelope::GmailEnvelope;
+use crate::modules::cache::vendor::jmap::sync::envelope::JmapEnvelope;
+use crate::modules::common::auth::ClientContext;
 use crate::modules::common::decode_page_token;
 use crate::modules::common::paginated::paginate_vec;
 use crate::modules::common::parallel::run_with_limit;
 use crate::modules::database::Paginated;
+use crate::modules::envelope::authentication::{AuthVerdict, AUTHENTICATION_RESULTS};
 use crate::modules::error::code::ErrorCode;
 use crate::modules::message::search::cache::IMAP_SEARCH_CACHE;
+use crate::modules::message::search::query::parse_message_search;
 use crate::modules::rest::response::CursorDataPage;
 use crate::{
     encode_mailbox_name,
@@ -136,6 +140,13 @@ pub enum Conditions {
     Unkeyword,
     /// Messages that have not been seen (unread)  
     Unseen,
+    /// Messages whose `Authentication-Results` header reports the specified SPF result
+    /// (e.g., "fail", "softfail"). The match is done by the server on the header text.
+    Spf,
+    /// Messages whose `Authentication-Results` header reports the specified DKIM result
+    Dkim,
+    /// Messages whose `Authentication-Results` header reports the specified DMARC result
+    Dmarc,
 }
 
 /// Represents search criteria for finding email messages  
@@ -253,6 +264,9 @@ impl MessageSearch {
                     Conditions::Unflagged => "UNFLAGGED".into(),
                     Conditions::Unkeyword => format!("UNKEYWORD {}", Self::quote_value(value)?),
                     Conditions::Unseen => "UNSEEN".into(),
+                    Conditions::Spf => Self::authentication_result("spf", value)?,
+                    Conditions::Dkim => Self::authentication_result("dkim", value)?,
+                    Conditions::Dmarc => Self::authentication_result("dmarc", value)?,
                     Conditions::GmailSeacrch => {
                         return Err(raise_error!(
                             "This condition is only supported for Gmail API accounts".into(),
@@ -351,6 +365,23 @@ impl MessageSearch {
         }
     }
 
+    fn authentication_result(method: &str, value: Option<&str>) -> RustMailerResult<String> {
+        let verdict = value.and_then(AuthVerdict::parse).ok_or_else(|| {
+            raise_error!(
+                format!(
+                    "Invalid {} result (expected pass, fail, softfail, neutral, none, temperror, permerror or policy)",
+                    method.to_uppercase()
+                ),
+                ErrorCode::InvalidParameter
+            )
+        })?;
+        Ok(format!(
+            "HEADER {} {}",
+            Self::quote_value(Some(AUTHENTICATION_RESULTS))?,
+            Self::quote_value(Some(&format!("{}={}", method, verdict.as_str())))?
+        ))
+    }
+
     fn format_date(date: Option<&str>) -> RustMailerResult<String> {
         let date = date.ok_or_else(|| {
             raise_error!("Date value is required".into(), ErrorCode::InvalidParameter)
@@ -466,6 +497,26 @@ pub struct MessageSearchRequest {
 }
 
 impl MessageSearchRequest {
+    /// Builds a search from a query string (see [`parse_message_search`]).
+    ///
+    /// Gmail API accounts receive the query unchanged as a Gmail search expression, which
+    /// uses the same `field:value` syntax.
+    pub async fn from_query(
+        account_id: u64,
+        query: &str,
+        mailbox: Option<String>,
+    ) -> RustMailerResult<Self> {
+        let account = AccountModel::get(account_id).await?;
+        let search = match account.mailer_type {
+            MailerType::GmailApi => MessageSearch::Condition(Condition {
+                condition: Conditions::GmailSeacrch,
+                value: Some(query.trim().to_string()),
+            }),
+            _ => parse_message_search(query)?,
+        };
+        Ok(Self { search, mailbox })
+    }
+
     fn imap_search_cache_key(
         &self,
         account_id: u64,
@@ -498,6 +549,10 @@ impl MessageSearchRequest {
                     .await
             }
             MailerType::GraphApi => todo!(),
+            MailerType::Jmap => Err(raise_error!(
+                "Remote search is not supported for JMAP accounts.".into(),
+                ErrorCode::Incompatible
+            )),
         }
     }
 
@@ -742,7 +797,7 @@ impl MessageSearchRequest {
 }
 
 /// Query parameters for unified customer email search.
-#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Object)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Object)]
 pub struct UnifiedSearchRequest {
     /// Optional list of account IDs to search within. If omitted, search all accessible accounts.
     pub accounts: Option<Vec<u64>>,
@@ -754,7 +809,52 @@ pub struct UnifiedSearchRequest {
     pub before: Option<i64>,
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Object)]
+pub struct SavedSearchCreateRequest {
+    /// Name of the saved search, as shown in the client. Maximum length is 256 characters.
+    #[oai(validator(max_length = "256"))]
+    pub name: String,
+    /// Optional description of the saved search.
+    pub description: Option<String>,
+    /// The search criteria. The search covers the account of the saved search, so
+    /// `accounts` may only list that account.
+    pub search: UnifiedSearchRequest,
+    /// Limits results to the last `within_days` days when the search runs (optional), for
+    /// searches such as "last 7 days".
+    pub within_days: Option<u32>,
+}
+
+/// Changes to a saved search. Fields that are not set are left unchanged.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize, Object)]
+pub struct SavedSearchUpdateRequest {
+    #[oai(validator(max_length = "256"))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub search: Option<UnifiedSearchRequest>,
+    pub within_days: Option<u32>,
+}
+
 impl UnifiedSearchRequest {
+    /// Checks that the caller can access the requested accounts, or limits a search of all
+    /// accounts to the accounts the caller can access.
+    pub fn restrict_to(&mut self, context: &ClientContext) -> RustMailerResult<()> {
+        match &self.accounts {
+            Some(accounts) => {
+                for &account_id in accounts.iter() {
+                    context.require_account_access(account_id)?;
+                }
+            }
+            None => {
+                if !context.is_root {
+                    if let Some(accessible) = context.accessible_accounts()? {
+                        self.accounts = Some(accessible.iter().map(|a| a.id).collect());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub async fn search(
         &self,
         page: u64,
@@ -809,7 +909,7 @@ impl UnifiedSearchRequest {
         for (id, account_id, _) in result.items {
             let account = AccountModel::get(account_id).await?;
             let envelope = match account.mailer_type {
-                MailerType::ImapSmtp => EmailEnvelopeV3::get(id)
+                MailerType::ImapSmtp => EmailEnvelopeV7::get(id)
                     .await?
                     .ok_or_else(|| {
                         raise_error!(
@@ -833,6 +933,15 @@ impl UnifiedSearchRequest {
                     envelope.into_envelope(&label_map)
                 }
                 MailerType::GraphApi => todo!(),
+                MailerType::Jmap => JmapEnvelope::get(id)
+                    .await?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!("Failed to get JmapEnvelope for hash {id} in search operation"),
+                            ErrorCode::InternalError
+                        )
+                    })?
+                    .into(),
             };
             items.push(envelope);
         }
@@ -0,0 +1,399 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! A compact query string syntax for message searches, e.g.
+//! `from:alice subject:"invoice" has:attachment after:2024-01-01 flag:unseen`.
+//!
+//! Terms are combined with AND. `OR` combines the terms on each side of it, `-term` or
+//! `NOT term` negates a term, and parentheses group terms. Values containing spaces are
+//! quoted. A term without a field searches the headers and body of the message.
+
+use chrono::NaiveDate;
+
+use crate::modules::error::{code::ErrorCode, RustMailerResult};
+use crate::modules::message::search::payload::{
+    Condition, Conditions, Logic, MessageSearch, Operator, UnifiedSearchRequest,
+};
+use crate::raise_error;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Not,
+    Or,
+    Term {
+        field: Option<String>,
+        value: String,
+    },
+}
+
+fn tokenize(query: &str) -> RustMailerResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            _ => {
+                let mut field = None;
+                let mut value = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    match c {
+                        '"' => {
+                            chars.next();
+                            quoted = true;
+                            loop {
+                                match chars.next() {
+                                    Some('\\') => {
+                                        if let Some(escaped) = chars.next() {
+                                            value.push(escaped);
+                                        }
+                                    }
+                                    Some('"') => break,
+                                    Some(c) => value.push(c),
+                                    None => {
+                                        return Err(raise_error!(
+                                            "Unterminated quote in search query".into(),
+                                            ErrorCode::InvalidParameter
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                        ':' if field.is_none() && !quoted && !value.is_empty() => {
+                            chars.next();
+                            field = Some(std::mem::take(&mut value).to_lowercase());
+                        }
+                        c if c.is_whitespace() || c == '(' || c == ')' => break,
+                        c => {
+                            chars.next();
+                            value.push(c);
+                        }
+                    }
+                }
+                let token = match (&field, value.as_str(), quoted) {
+                    (None, "OR", false) => Token::Or,
+                    (None, "NOT", false) => Token::Not,
+                    _ => Token::Term { field, value },
+                };
+                // `AND` is implied between terms.
+                if !matches!(&token, Token::Term { field: None, value } if value == "AND" && !quoted)
+                {
+                    tokens.push(token);
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens: OR binds looser than the implied AND, which binds
+/// looser than negation.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn or_expression(&mut self) -> RustMailerResult<MessageSearch> {
+        let mut children = vec![self.and_expression()?];
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            children.push(self.and_expression()?);
+        }
+        Ok(combine(Operator::Or, children))
+    }
+
+    fn and_expression(&mut self) -> RustMailerResult<MessageSearch> {
+        let mut children = Vec::new();
+        while matches!(
+            self.peek(),
+            Some(Token::Open | Token::Not | Token::Term { .. })
+        ) {
+            children.push(self.unary()?);
+        }
+        if children.is_empty() {
+            return Err(raise_error!(
+                "Expected a search term in the query".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        Ok(combine(Operator::And, children))
+    }
+
+    fn unary(&mut self) -> RustMailerResult<MessageSearch> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.position += 1;
+                let inner = self.unary()?;
+                Ok(MessageSearch::Logic(Logic {
+                    operator: Operator::Not,
+                    children: vec![inner],
+                }))
+            }
+            Some(Token::Open) => {
+                self.position += 1;
+                let inner = self.or_expression()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(raise_error!(
+                        "Missing closing parenthesis in search query".into(),
+                        ErrorCode::InvalidParameter
+                    ));
+                }
+                self.position += 1;
+                Ok(inner)
+            }
+            Some(Token::Term { field, value }) => {
+                let term = message_term(field.as_deref(), value);
+                self.position += 1;
+                term
+            }
+            _ => Err(raise_error!(
+                "Expected a search term in the query".into(),
+                ErrorCode::InvalidParameter
+            )),
+        }
+    }
+}
+
+fn combine(operator: Operator, mut children: Vec<MessageSearch>) -> MessageSearch {
+    if children.len() == 1 {
+        children.remove(0)
+    } else {
+        MessageSearch::Logic(Logic { operator, children })
+    }
+}
+
+/// Parses a search query string into a message search.
+///
+/// Supported terms are `from:`, `to:`, `cc:`, `bcc:`, `subject:`, `body:`, `text:`,
+/// `header:"Name value"`, `keyword:`, `after:`, `before:`, `on:`, `sent-after:`,
+/// `sent-before:`, `sent-on:` (dates as `YYYY-MM-DD`), `larger:`, `smaller:` (bytes, or
+/// with a K, M or G suffix), `uid:`, `flag:` (or `is:`) with `seen`, `unseen`, `flagged`,
+/// `unflagged`, `answered`, `unanswered`, `draft`, `deleted`, `new`, `old` or `recent`,
+/// `has:attachment`, and `spf:`, `dkim:` and `dmarc:` with an authentication result.
+pub fn parse_message_search(query: &str) -> RustMailerResult<MessageSearch> {
+    let tokens = tokenize(query)?;
+    if tokens.is_empty() {
+        return Err(raise_error!(
+            "The search query is empty".into(),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+    };
+    let search = parser.or_expression()?;
+    if parser.position < tokens.len() {
+        return Err(raise_error!(
+            "Unexpected closing parenthesis in search query".into(),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    Ok(search)
+}
+
+fn condition(condition: Conditions, value: Option<&str>) -> MessageSearch {
+    MessageSearch::Condition(Condition {
+        condition,
+        value: value.map(String::from),
+    })
+}
+
+fn flag_condition(flag: &str) -> RustMailerResult<Conditions> {
+    let condition = match flag.to_lowercase().as_str() {
+        "seen" | "read" => Conditions::Seen,
+        "unseen" | "unread" => Conditions::Unseen,
+        "flagged" | "starred" => Conditions::Flagged,
+        "unflagged" | "unstarred" => Conditions::Unflagged,
+        "answered" | "replied" => Conditions::Answered,
+        "unanswered" | "unreplied" => Conditions::Unanswered,
+        "draft" => Conditions::Draft,
+        "undraft" => Conditions::Undraft,
+        "deleted" => Conditions::Deleted,
+        "undeleted" => Conditions::Undeleted,
+        "new" => Conditions::New,
+        "old" => Conditions::Old,
+        "recent" => Conditions::Recent,
+        _ => {
+            return Err(raise_error!(
+                format!("Unknown flag '{}' in search query", flag),
+                ErrorCode::InvalidParameter
+            ))
+        }
+    };
+    Ok(condition)
+}
+
+/// Parses a size such as `1048576`, `500K` or `10M` into bytes.
+fn parse_size(value: &str) -> RustMailerResult<u64> {
+    let invalid = || {
+        raise_error!(
+            format!(
+                "Invalid size '{}' in search query (expected bytes, or a number with a K, M or G suffix)",
+                value
+            ),
+            ErrorCode::InvalidParameter
+        )
+    };
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1024),
+        Some((i, 'm' | 'M')) => (&value[..i], 1024 * 1024),
+        Some((i, 'g' | 'G')) => (&value[..i], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    number.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+fn message_term(field: Option<&str>, value: &str) -> RustMailerResult<MessageSearch> {
+    let Some(field) = field else {
+        return Ok(condition(Conditions::Text, Some(value)));
+    };
+    if value.is_empty() {
+        return Err(raise_error!(
+            format!("Missing value for search term '{}:'", field),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    let search = match field {
+        "from" => condition(Conditions::From, Some(value)),
+        "to" => condition(Conditions::To, Some(value)),
+        "cc" => condition(Conditions::Cc, Some(value)),
+        "bcc" => condition(Conditions::Bcc, Some(value)),
+        "subject" => condition(Conditions::Subject, Some(value)),
+        "body" => condition(Conditions::Body, Some(value)),
+        "text" => condition(Conditions::Text, Some(value)),
+        "header" => condition(Conditions::Header, Some(value)),
+        "keyword" | "label" => condition(Conditions::Keyword, Some(value)),
+        "after" | "since" => condition(Conditions::Since, Some(value)),
+        "before" => condition(Conditions::Before, Some(value)),
+        "on" => condition(Conditions::On, Some(value)),
+        "sent-after" => condition(Conditions::SentSince, Some(value)),
+        "sent-before" => condition(Conditions::SentBefore, Some(value)),
+        "sent-on" => condition(Conditions::SentOn, Some(value)),
+        "larger" => condition(Conditions::Larger, Some(&parse_size(value)?.to_string())),
+        "smaller" => condition(Conditions::Smaller, Some(&parse_size(value)?.to_string())),
+        "uid" => condition(Conditions::Uid, Some(value)),
+        "flag" | "is" => condition(flag_condition(value)?, None),
+        // IMAP has no attachment search; attachments make a message multipart/mixed.
+        "has" if value.eq_ignore_ascii_case("attachment") => {
+            condition(Conditions::Header, Some("Content-Type multipart/mixed"))
+        }
+        "spf" => condition(Conditions::Spf, Some(value)),
+        "dkim" => condition(Conditions::Dkim, Some(value)),
+        "dmarc" => condition(Conditions::Dmarc, Some(value)),
+        _ => {
+            return Err(raise_error!(
+                format!(
+                    "Unsupported search term '{}:{}'. Quote the term to search for it as text.",
+                    field, value
+                ),
+                ErrorCode::InvalidParameter
+            ))
+        }
+    };
+    Ok(search)
+}
+
+/// Reads a `YYYY-MM-DD` date as the UTC timestamp in milliseconds of its start.
+fn date_millis(value: &str) -> RustMailerResult<i64> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        raise_error!(
+            format!(
+                "Invalid date '{}' in search query (expected YYYY-MM-DD)",
+                value
+            ),
+            ErrorCode::InvalidParameter
+        )
+    })?;
+    Ok(date
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc()
+        .timestamp_millis())
+}
+
+/// Parses a search query string into a unified search.
+///
+/// The unified search matches one email address in any of the from, to and cc fields of the
+/// cached messages, so the query must hold exactly one address, given bare or as `email:`.
+/// It may also use `after:` and `before:` dates (`YYYY-MM-DD`, UTC) and `account:` with an
+/// account ID, repeated to search several accounts. `OR`, negation and parentheses are not
+/// supported.
+pub fn parse_unified_search(query: &str) -> RustMailerResult<UnifiedSearchRequest> {
+    let tokens = tokenize(query)?;
+    let mut request = UnifiedSearchRequest {
+        accounts: None,
+        email: String::new(),
+        after: None,
+        before: None,
+    };
+    for token in tokens {
+        let Token::Term { field, value } = token else {
+            return Err(raise_error!(
+                "The unified search query does not support OR, negation or parentheses".into(),
+                ErrorCode::InvalidParameter
+            ));
+        };
+        match field.as_deref() {
+            None | Some("email") => {
+                if !request.email.is_empty() {
+                    return Err(raise_error!(
+                        "The unified search query must contain exactly one email address".into(),
+                        ErrorCode::InvalidParameter
+                    ));
+                }
+                request.email = value;
+            }
+            Some("after") | Some("since") => request.after = Some(date_millis(&value)?),
+            Some("before") => request.before = Some(date_millis(&value)?),
+            Some("account") => {
+                let account_id = value.parse::<u64>().map_err(|_| {
+                    raise_error!(
+                        format!("Invalid account ID '{}' in search query", value),
+                        ErrorCode::InvalidParameter
+                    )
+                })?;
+                request.accounts.get_or_insert_with(Vec::new).push(account_id);
+            }
+            Some(field) => {
+                return Err(raise_error!(
+                    format!(
+                        "Unsupported unified search term '{}:{}' (expected email, after, before or account)",
+                        field, value
+                    ),
+                    ErrorCode::InvalidParameter
+                ))
+            }
+        }
+    }
+    if request.email.is_empty() {
+        return Err(raise_error!(
+            "The unified search query must contain an email address".into(),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    Ok(request)
+}
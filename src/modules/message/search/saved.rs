@@ -0,0 +1,291 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::cache::model::Envelope;
+use crate::modules::database::batch::WriteBatch;
+use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::{
+    delete_impl, insert_impl, paginate_secondary_scan_impl, secondary_find_impl, update_impl,
+};
+use crate::modules::error::code::ErrorCode;
+use crate::modules::error::RustMailerResult;
+use crate::modules::message::search::payload::{
+    SavedSearchCreateRequest, SavedSearchUpdateRequest, UnifiedSearchRequest,
+};
+use crate::modules::overview::memory::check_metadata_capacity;
+use crate::modules::rest::response::DataPage;
+use crate::{id, raise_error, utc_now};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// A named unified search of an account, stored so client UIs can offer it as a smart
+/// folder, such as "Mail from Alice in the last 7 days", and run it by ID.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 27, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct SavedSearch {
+    /// Unique identifier of the saved search.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// The account the saved search belongs to, and searches.
+    #[secondary_key]
+    pub account_id: u64,
+    /// Name of the saved search, as shown in the client.
+    pub name: String,
+    /// Optional description of the saved search.
+    pub description: Option<String>,
+    /// The search criteria.
+    pub search: UnifiedSearchRequest,
+    /// Limits results to the last `within_days` days when the search runs, on top of
+    /// `after` and `before`, which are fixed times.
+    pub within_days: Option<u32>,
+    /// Timestamp of when the saved search was created (in Unix epoch milliseconds).
+    pub created_at: i64,
+    /// Timestamp of when the saved search was last updated (in Unix epoch milliseconds).
+    pub updated_at: i64,
+}
+
+impl SavedSearch {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    pub fn new(account_id: u64, request: SavedSearchCreateRequest) -> RustMailerResult<Self> {
+        let now = utc_now!();
+        let saved = Self {
+            id: id!(64),
+            account_id,
+            name: request.name,
+            description: request.description,
+            search: request.search,
+            within_days: request.within_days,
+            created_at: now,
+            updated_at: now,
+        };
+        saved.validate()?;
+        Ok(saved)
+    }
+
+    fn validate(&self) -> RustMailerResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(raise_error!(
+                "field: name is empty.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if self.search.email.trim().is_empty() {
+            return Err(raise_error!(
+                "field: search.email is empty.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if self
+            .search
+            .accounts
+            .as_ref()
+            .is_some_and(|accounts| accounts.iter().any(|&id| id != self.account_id))
+        {
+            return Err(raise_error!(
+                "field: search.accounts may only list the account of the saved search.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if self.within_days == Some(0) {
+            return Err(raise_error!(
+                "field: within_days must be greater than 0.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn save(self) -> RustMailerResult<()> {
+        check_metadata_capacity()?;
+        insert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    pub async fn find(id: u64) -> RustMailerResult<Option<SavedSearch>> {
+        secondary_find_impl(DB_MANAGER.meta_db(), SavedSearchKey::id, id).await
+    }
+
+    pub async fn get(account_id: u64, id: u64) -> RustMailerResult<SavedSearch> {
+        Self::find(id)
+            .await?
+            .filter(|saved| saved.account_id == account_id)
+            .ok_or_else(|| {
+                raise_error!(
+                    format!("Saved search id='{id}' not found for account {account_id}."),
+                    ErrorCode::ResourceNotFound
+                )
+            })
+    }
+
+    /// The saved searches of an account, oldest first unless `desc` is set.
+    pub async fn paginate_list(
+        account_id: u64,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<SavedSearch>> {
+        paginate_secondary_scan_impl(
+            DB_MANAGER.meta_db(),
+            page,
+            page_size,
+            desc,
+            SavedSearchKey::account_id,
+            account_id,
+        )
+        .await
+        .map(DataPage::from)
+    }
+
+    pub async fn update(
+        account_id: u64,
+        id: u64,
+        request: SavedSearchUpdateRequest,
+    ) -> RustMailerResult<SavedSearch> {
+        let updated = Self::get(account_id, id).await?.apply(request);
+        updated.validate()?;
+        let saved = updated.clone();
+        update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .secondary::<SavedSearch>(SavedSearchKey::id, id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!("The saved search with id={id} that you want to modify was not found."),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            move |_| Ok(saved),
+        )
+        .await?;
+        Ok(updated)
+    }
+
+    fn apply(mut self, request: SavedSearchUpdateRequest) -> Self {
+        if let Some(name) = request.name {
+            self.name = name;
+        }
+        if request.description.is_some() {
+            self.description = request.description;
+        }
+        if let Some(search) = request.search {
+            self.search = search;
+        }
+        if request.within_days.is_some() {
+            self.within_days = request.within_days;
+        }
+        self.updated_at = utc_now!();
+        self
+    }
+
+    pub async fn remove(id: u64) -> RustMailerResult<()> {
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<SavedSearch>(SavedSearchKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!(
+                            "The saved search with id={id} that you want to delete was not found."
+                        ),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await
+    }
+
+    /// Adds the removal of all saved searches of an account to `batch`.
+    pub fn stage_remove_account_searches(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let searches: Vec<SavedSearch> = rw
+                .scan()
+                .secondary::<SavedSearch>(SavedSearchKey::account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(searches)
+        })
+    }
+
+    /// The criteria the search runs with at `now`: the account of the saved search, with
+    /// `within_days` turned into a start time.
+    pub fn resolve(&self, now: i64) -> UnifiedSearchRequest {
+        let mut search = self.search.clone();
+        search.accounts = Some(vec![self.account_id]);
+        if let Some(days) = self.within_days {
+            let since = now - days as i64 * DAY_MS;
+            search.after = Some(search.after.map_or(since, |after| after.max(since)));
+        }
+        search
+    }
+
+    pub async fn run(
+        &self,
+        page: u64,
+        page_size: u64,
+        desc: bool,
+    ) -> RustMailerResult<DataPage<Envelope>> {
+        self.resolve(utc_now!()).search(page, page_size, desc).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_the_relative_window_at_run_time() {
+        let mut saved = SavedSearch {
+            account_id: 1,
+            search: UnifiedSearchRequest {
+                accounts: Some(vec![1]),
+                email: "vip@example.com".into(),
+                after: None,
+                before: None,
+            },
+            within_days: Some(7),
+            ..Default::default()
+        };
+        let now = 100 * DAY_MS;
+        assert_eq!(saved.resolve(now).after, Some(93 * DAY_MS));
+
+        // A later fixed start time is kept.
+        saved.search.after = Some(95 * DAY_MS);
+        assert_eq!(saved.resolve(now).after, Some(95 * DAY_MS));
+
+        saved.within_days = None;
+        assert_eq!(saved.resolve(now), saved.search);
+    }
+
+    #[test]
+    fn searches_only_the_account_of_the_saved_search() {
+        let request = |accounts| SavedSearchCreateRequest {
+            name: "VIP".into(),
+            search: UnifiedSearchRequest {
+                accounts,
+                email: "vip@example.com".into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let saved = SavedSearch::new(1, request(None)).unwrap();
+        assert_eq!(saved.resolve(0).accounts, Some(vec![1]));
+        assert!(SavedSearch::new(1, request(Some(vec![1]))).is_ok());
+        assert!(SavedSearch::new(1, request(Some(vec![1, 2]))).is_err());
+    }
+}
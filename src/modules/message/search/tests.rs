@@ -7,6 +7,7 @@ mod tests {
     use crate::modules::message::search::payload::{
         Condition, Conditions, Logic, MessageSearch, Operator,
     };
+    use crate::modules::message::search::query::{parse_message_search, parse_unified_search};
     fn cond(condition: Conditions, value: &str) -> MessageSearch {
         MessageSearch::Condition(Condition {
             condition,
@@ -200,4 +201,110 @@ mod tests {
             .to_imap_command(false)
             .is_err());
     }
+
+    #[test]
+    fn test_authentication_conditions() {
+        assert_eq!(
+            cond(Conditions::Dmarc, "FAIL")
+                .to_imap_command(false)
+                .unwrap(),
+            "HEADER \"Authentication-Results\" \"dmarc=fail\""
+        );
+
+        assert_eq!(
+            cond(Conditions::Spf, "softfail")
+                .to_imap_command(false)
+                .unwrap(),
+            "HEADER \"Authentication-Results\" \"spf=softfail\""
+        );
+
+        assert!(cond(Conditions::Dkim, "maybe")
+            .to_imap_command(false)
+            .is_err());
+    }
+
+    #[test]
+    fn test_query_string() {
+        let search = parse_message_search(
+            r#"from:alice subject:"invoice" has:attachment after:2024-01-01 flag:unseen"#,
+        )
+        .unwrap();
+        assert_eq!(
+            search.to_imap_command(true).unwrap(),
+            "FROM \"alice\" SUBJECT \"invoice\" HEADER \"Content-Type\" \"multipart/mixed\" SINCE 01-Jan-2024 UNSEEN"
+        );
+
+        let search = parse_message_search("(from:alice OR from:bob) -is:read larger:10K").unwrap();
+        assert_eq!(
+            search,
+            logic(
+                Operator::And,
+                vec![
+                    logic(
+                        Operator::Or,
+                        vec![
+                            cond(Conditions::From, "alice"),
+                            cond(Conditions::From, "bob")
+                        ]
+                    ),
+                    logic(
+                        Operator::Not,
+                        vec![MessageSearch::Condition(Condition {
+                            condition: Conditions::Seen,
+                            value: None,
+                        })]
+                    ),
+                    cond(Conditions::Larger, "10240"),
+                ]
+            )
+        );
+        assert_eq!(
+            search.to_imap_command(true).unwrap(),
+            "(OR FROM \"alice\" FROM \"bob\") (NOT SEEN) LARGER 10240"
+        );
+
+        assert_eq!(
+            parse_message_search(r#""quarterly report" AND sent-after:2024-02-01"#).unwrap(),
+            logic(
+                Operator::And,
+                vec![
+                    cond(Conditions::Text, "quarterly report"),
+                    cond(Conditions::SentSince, "2024-02-01"),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_query_strings() {
+        for query in [
+            "",
+            "   ",
+            "from:",
+            "subject:\"unterminated",
+            "(from:alice",
+            "from:alice)",
+            "OR from:alice",
+            "flag:important",
+            "larger:big",
+            "https://example.com",
+        ] {
+            assert!(parse_message_search(query).is_err(), "{query}");
+        }
+    }
+
+    #[test]
+    fn test_unified_query_string() {
+        let request =
+            parse_unified_search("alice@example.com after:2024-01-01 account:1 account:2").unwrap();
+        assert_eq!(request.email, "alice@example.com");
+        assert_eq!(request.after, Some(1704067200000));
+        assert_eq!(request.before, None);
+        assert_eq!(request.accounts, Some(vec![1, 2]));
+
+        assert!(parse_unified_search("after:2024-01-01").is_err());
+        assert!(parse_unified_search("a@x.com b@x.com").is_err());
+        assert!(parse_unified_search("email:a@x.com OR email:b@x.com").is_err());
+        assert!(parse_unified_search("email:a@x.com subject:hi").is_err());
+    }
 }
@@ -310,6 +310,12 @@ pub async fn tag_messages_impl(account_id: u64, payload: BatchTagRequest) -> Rus
             )
             .await?;
         }
+        MailerType::Jmap => {
+            return Err(raise_error!(
+                "Modifying tags is not supported for JMAP accounts.".into(),
+                ErrorCode::Incompatible
+            ));
+        }
     }
 
     Ok(())
@@ -255,5 +255,9 @@ pub async fn transfer_messages(
             }
             Ok(())
         }
+        MailerType::Jmap => Err(raise_error!(
+            "Moving or copying messages is not supported for JMAP accounts.".into(),
+            ErrorCode::Incompatible
+        )),
     }
 }
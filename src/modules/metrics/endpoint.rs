@@ -7,9 +7,25 @@ use poem::{
     Endpoint, Request, Response, Result,
 };
 use prometheus::{default_registry, Encoder, TextEncoder};
+use serde::Deserialize;
 
-use crate::modules::{common::auth::authorize_access, token::AccessTokenScope};
+use crate::modules::{
+    common::{auth::authorize_access, create_api_error_response},
+    error::RustMailerError,
+    metrics::scope::{parse_account_ids, MetricsView},
+};
+
+#[derive(Deserialize)]
+struct MetricsQuery {
+    /// Comma separated account IDs to restrict the output to.
+    account_id: Option<String>,
+}
 
+/// Serves metrics in the Prometheus text format.
+///
+/// Tokens with the `AccountMetrics` scope only receive the per-account series of their
+/// own accounts. `?account_id=1,2` restricts the output to the per-account series of the
+/// given accounts, e.g. for tenant dashboards.
 pub struct PrometheusEndpoint;
 
 impl Endpoint for PrometheusEndpoint {
@@ -19,9 +35,15 @@ impl Endpoint for PrometheusEndpoint {
         if req.method() != Method::GET {
             return Ok(StatusCode::METHOD_NOT_ALLOWED.into());
         }
-        authorize_access(&req, Some(AccessTokenScope::Metrics)).await?;
+        let context = authorize_access(&req, None).await?;
+        let requested = match req.params::<MetricsQuery>().ok().and_then(|q| q.account_id) {
+            Some(value) => Some(parse_account_ids(&value).map_err(into_api_error)?),
+            None => None,
+        };
+        let view = MetricsView::resolve(&context, requested).map_err(into_api_error)?;
+
         let encoder = TextEncoder::new();
-        let metric_families = default_registry().gather();
+        let metric_families = view.filter(default_registry().gather());
         let mut result = Vec::new();
         match encoder.encode(&metric_families, &mut result) {
             Ok(()) => Ok(Response::builder()
@@ -31,3 +53,13 @@ impl Endpoint for PrometheusEndpoint {
         }
     }
 }
+
+fn into_api_error(error: RustMailerError) -> poem::Error {
+    match error {
+        RustMailerError::Generic {
+            message,
+            location: _,
+            code,
+        } => create_api_error_response(&message, code),
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeMap;
+
+use poem_openapi::Object;
+use prometheus::{core::Collector, proto::Histogram, HistogramVec};
+use serde::{Deserialize, Serialize};
+
+use crate::modules::metrics::{
+    RUSTMAILER_TASK_QUEUE_WAIT_SECONDS, RUSTMAILER_TASK_RUN_DURATION_SECONDS,
+};
+
+/// Summary of a latency histogram. Quantiles are estimated from the histogram buckets,
+/// like Prometheus' `histogram_quantile`, and capped at the largest bucket bound.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, Object)]
+pub struct LatencySummary {
+    /// Number of observations since startup.
+    pub count: u64,
+    /// Average latency, in seconds.
+    pub mean_seconds: f64,
+    /// Estimated median latency, in seconds.
+    pub p50_seconds: f64,
+    /// Estimated 95th percentile latency, in seconds.
+    pub p95_seconds: f64,
+    /// Estimated 99th percentile latency, in seconds.
+    pub p99_seconds: f64,
+}
+
+/// Latencies of one task type, such as `send_email` or `event_hook`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, Object)]
+pub struct TaskLatencyStats {
+    /// The task type.
+    pub task: String,
+    /// Time between a task becoming due (its scheduled time, or the time of its retry) and
+    /// starting to run.
+    pub queue_wait: LatencySummary,
+    /// Time between a task starting and finishing, including failed attempts.
+    pub run_duration: LatencySummary,
+}
+
+/// Summarizes the task latency histograms exported on `/metrics`, by task type.
+pub fn task_latency_stats() -> Vec<TaskLatencyStats> {
+    let mut stats: BTreeMap<String, TaskLatencyStats> = BTreeMap::new();
+    for (task, summary) in summarize_by_task(&RUSTMAILER_TASK_QUEUE_WAIT_SECONDS) {
+        stats.entry(task).or_default().queue_wait = summary;
+    }
+    for (task, summary) in summarize_by_task(&RUSTMAILER_TASK_RUN_DURATION_SECONDS) {
+        stats.entry(task).or_default().run_duration = summary;
+    }
+    stats
+        .into_iter()
+        .map(|(task, stats)| TaskLatencyStats { task, ..stats })
+        .collect()
+}
+
+fn summarize_by_task(histograms: &HistogramVec) -> Vec<(String, LatencySummary)> {
+    histograms
+        .collect()
+        .iter()
+        .flat_map(|family| family.metric.iter())
+        .filter_map(|metric| {
+            let task = metric
+                .label
+                .iter()
+                .find(|label| label.name() == "task")?
+                .value()
+                .to_string();
+            Some((task, summarize(&metric.histogram)))
+        })
+        .collect()
+}
+
+fn summarize(histogram: &Histogram) -> LatencySummary {
+    let count = histogram.sample_count();
+    if count == 0 {
+        return LatencySummary::default();
+    }
+    LatencySummary {
+        count,
+        mean_seconds: histogram.sample_sum() / count as f64,
+        p50_seconds: quantile(histogram, 0.5),
+        p95_seconds: quantile(histogram, 0.95),
+        p99_seconds: quantile(histogram, 0.99),
+    }
+}
+
+/// Estimates a quantile by linear interpolation within the bucket containing it.
+fn quantile(histogram: &Histogram, q: f64) -> f64 {
+    let rank = q * histogram.sample_count() as f64;
+    let mut lower_bound = 0.0;
+    let mut lower_count = 0;
+    for bucket in &histogram.bucket {
+        let count = bucket.cumulative_count();
+        if count as f64 >= rank {
+            let in_bucket = (count - lower_count) as f64;
+            if in_bucket == 0.0 {
+                return bucket.upper_bound();
+            }
+            let fraction = (rank - lower_count as f64) / in_bucket;
+            return lower_bound + (bucket.upper_bound() - lower_bound) * fraction;
+        }
+        lower_bound = bucket.upper_bound();
+        lower_count = count;
+    }
+    // The quantile lies beyond the largest finite bucket.
+    lower_bound
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus::HistogramOpts;
+
+    use super::*;
+
+    #[test]
+    fn summarizes_histograms() {
+        let histograms = HistogramVec::new(
+            HistogramOpts::new("test_latency_seconds", "test").buckets(vec![1.0, 2.0, 4.0]),
+            &["task"],
+        )
+        .unwrap();
+        let send = histograms.with_label_values(&["send_email"]);
+        for value in [0.5, 0.5, 1.5, 3.0] {
+            send.observe(value);
+        }
+        histograms.with_label_values(&["event_hook"]).observe(10.0);
+
+        let summaries: BTreeMap<_, _> = summarize_by_task(&histograms).into_iter().collect();
+        let send = &summaries["send_email"];
+        assert_eq!(send.count, 4);
+        assert_eq!(send.mean_seconds, 1.375);
+        assert_eq!(send.p50_seconds, 1.0);
+        assert!((send.p95_seconds - 3.6).abs() < 1e-9);
+        // Observations above the largest bucket are capped at its bound.
+        assert_eq!(summaries["event_hook"].p99_seconds, 4.0);
+    }
+}
@@ -6,16 +6,21 @@ use std::sync::LazyLock;
 
 use crate::rustmailer_version;
 use crate::{
-    modules::{context::Initialize, error::RustMailerResult},
+    modules::{
+        context::{guard::ImapRequestKind, Initialize},
+        error::RustMailerResult,
+    },
     utc_now,
 };
 use prometheus::{
     register_gauge, register_gauge_vec, register_histogram, register_histogram_vec,
-    register_int_counter, register_int_counter_vec, register_int_gauge_vec, Gauge, GaugeVec,
-    Histogram, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec,
+    register_int_counter, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    Gauge, GaugeVec, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 
 pub mod endpoint;
+pub mod latency;
+pub mod scope;
 
 pub const SENT: &str = "sent";
 pub const RECEIVED: &str = "received";
@@ -28,6 +33,8 @@ pub const HOOK: &str = "hook";
 
 pub const HTTP: &str = "http";
 pub const NATS: &str = "nats";
+pub const KAFKA: &str = "kafka";
+pub const REDIS: &str = "redis";
 
 // Metric name constants
 pub const METRIC_REQUEST_DURATION_BY_STATUS: &str = "rustmailer_request_duration_seconds_by_status";
@@ -43,6 +50,10 @@ pub const METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION: &str =
     "rustmailer_event_dispatch_total_by_type_status_and_destination";
 pub const METRIC_EVENT_DISPATCH_DURATION_SECONDS_BY_TYPE_STATUS_AND_DESTINATION: &str =
     "rustmailer_event_dispatch_duration_seconds_by_type_status_and_destination";
+pub const METRIC_EVENT_CHANNEL_DEPTH: &str = "rustmailer_event_channel_depth";
+pub const METRIC_EVENT_CHANNEL_SPILLED_DEPTH: &str = "rustmailer_event_channel_spilled_depth";
+pub const METRIC_EVENT_CHANNEL_DROPPED_TOTAL: &str = "rustmailer_event_channel_dropped_total";
+pub const METRIC_EVENT_CHANNEL_SPILLED_TOTAL: &str = "rustmailer_event_channel_spilled_total";
 pub const METRIC_NEW_EMAIL_ARRIVAL_TOTAL: &str = "rustmailer_new_email_arrival_total";
 pub const METRIC_MAIL_FLAG_CHANGE_TOTAL: &str = "rustmailer_mail_flag_change_total";
 pub const METRIC_EMAIL_OPENS_TOTAL: &str = "rustmailer_email_opens_total";
@@ -51,6 +62,40 @@ pub const METRIC_TASK_FETCH_DURATION: &str = "rustmailer_task_fetch_duration_sec
 pub const METRIC_BUILD_INFO: &str = "rustmailer_build_info";
 pub const METRIC_START_TIMESTAMP: &str = "rustmailer_start_timestamp";
 pub const METRIC_TASK_QUEUE_LENGTH: &str = "rustmailer_task_queue_length";
+pub const METRIC_TASK_QUEUE_WAIT_SECONDS: &str = "rustmailer_task_queue_wait_seconds";
+pub const METRIC_TASK_RUN_DURATION_SECONDS: &str = "rustmailer_task_run_duration_seconds";
+pub const METRIC_DEAD_LETTER_QUEUE_DEPTH: &str = "rustmailer_dead_letter_queue_depth";
+pub const METRIC_TASK_JOURNAL_REPLAY_DURATION: &str =
+    "rustmailer_task_journal_replay_duration_seconds";
+pub const METRIC_TASK_JOURNAL_REPLAYED_RECORDS: &str = "rustmailer_task_journal_replayed_records";
+pub const METRIC_TASK_JOURNAL_WRITTEN_RECORDS_TOTAL: &str =
+    "rustmailer_task_journal_written_records_total";
+pub const METRIC_FLAG_WRITE_TRANSACTIONS_TOTAL: &str = "rustmailer_flag_write_transactions_total";
+pub const METRIC_FLAG_WRITE_COALESCED_SUBMISSIONS_TOTAL: &str =
+    "rustmailer_flag_write_coalesced_submissions_total";
+pub const METRIC_IMAP_IDLE_CONNECTIONS: &str = "rustmailer_imap_idle_connections";
+pub const METRIC_OPEN_CONNECTIONS: &str = "rustmailer_open_connections";
+pub const METRIC_CONNECTION_ADMISSION_REJECTED_TOTAL: &str =
+    "rustmailer_connection_admission_rejected_total";
+pub const METRIC_OAUTH2_GC_REMOVED_TOTAL: &str = "rustmailer_oauth2_gc_removed_total";
+pub const METRIC_OPEN_FILE_DESCRIPTORS: &str = "rustmailer_open_file_descriptors";
+pub const METRIC_MAX_FILE_DESCRIPTORS: &str = "rustmailer_max_file_descriptors";
+pub const METRIC_ACCOUNT_EMAIL_SENT_TOTAL: &str = "rustmailer_account_email_sent_total";
+pub const METRIC_ACCOUNT_EMAIL_SENT_BYTES: &str = "rustmailer_account_email_sent_bytes";
+pub const METRIC_ACCOUNT_NEW_EMAIL_ARRIVAL_TOTAL: &str = "rustmailer_account_new_email_arrival_total";
+pub const METRIC_ACCOUNT_MAIL_FLAG_CHANGE_TOTAL: &str = "rustmailer_account_mail_flag_change_total";
+pub const METRIC_ACCOUNT_EMAIL_OPENS_TOTAL: &str = "rustmailer_account_email_opens_total";
+pub const METRIC_ACCOUNT_EMAIL_CLICKS_TOTAL: &str = "rustmailer_account_email_clicks_total";
+pub const METRIC_ACCOUNT_IMAP_REQUEST_CONTENDED_TOTAL: &str =
+    "rustmailer_account_imap_request_contended_total";
+pub const METRIC_ACCOUNT_IMAP_REQUEST_REJECTED_TOTAL: &str =
+    "rustmailer_account_imap_request_rejected_total";
+pub const METRIC_ACCOUNT_IMAP_REQUEST_WAIT_SECONDS: &str =
+    "rustmailer_account_imap_request_wait_seconds";
+
+/// Label carrying the account ID on per-account metrics. Metric views scoped to a set of
+/// accounts only include series with this label.
+pub const ACCOUNT_ID_LABEL: &str = "account_id";
 
 pub static RUSTMAILER_BUILD_INFO: LazyLock<GaugeVec> = LazyLock::new(|| {
     register_gauge_vec!(
@@ -139,7 +184,7 @@ pub static RUSTMAILER_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION: LazyL
     LazyLock::new(|| {
         register_int_counter_vec!(
             METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION,
-            "Total number of events dispatched, grouped by event type, status, and destination (http/nats)",
+            "Total number of events dispatched, grouped by event type, status, and destination (http/nats/kafka/redis)",
             &["status", "destination"]
         )
         .expect("Failed to register event_dispatch_total_by_type_status_and_destination")
@@ -150,13 +195,45 @@ pub static RUSTMAILER_EVENT_DISPATCH_DURATION_SECONDS_BY_TYPE_STATUS_AND_DESTINA
 > = LazyLock::new(|| {
     register_histogram_vec!(
         METRIC_EVENT_DISPATCH_DURATION_SECONDS_BY_TYPE_STATUS_AND_DESTINATION,
-        "Distribution of event dispatch durations (in seconds), grouped by event type, status, and destination (http/nats)",
+        "Distribution of event dispatch durations (in seconds), grouped by event type, status, and destination (http/nats/kafka/redis)",
         &["status", "destination"],
         vec![0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 7.5, 10.0, 30.0, 60.0]
     )
     .expect("Failed to register event_dispatch_duration_seconds_by_type_status_and_destination")
 });
 
+pub static RUSTMAILER_EVENT_CHANNEL_DEPTH: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        METRIC_EVENT_CHANNEL_DEPTH,
+        "Number of events waiting in memory to be dispatched to event hooks"
+    )
+    .expect("Failed to register rustmailer_event_channel_depth")
+});
+
+pub static RUSTMAILER_EVENT_CHANNEL_SPILLED_DEPTH: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        METRIC_EVENT_CHANNEL_SPILLED_DEPTH,
+        "Number of events waiting on disk to be dispatched to event hooks"
+    )
+    .expect("Failed to register rustmailer_event_channel_spilled_depth")
+});
+
+pub static RUSTMAILER_EVENT_CHANNEL_DROPPED_TOTAL: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        METRIC_EVENT_CHANNEL_DROPPED_TOTAL,
+        "Total number of events discarded because the event channel was full"
+    )
+    .expect("Failed to register rustmailer_event_channel_dropped_total")
+});
+
+pub static RUSTMAILER_EVENT_CHANNEL_SPILLED_TOTAL: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        METRIC_EVENT_CHANNEL_SPILLED_TOTAL,
+        "Total number of events spilled to disk because the event channel was full"
+    )
+    .expect("Failed to register rustmailer_event_channel_spilled_total")
+});
+
 pub static RUSTMAILER_NEW_EMAIL_ARRIVAL_TOTAL: LazyLock<IntCounter> = LazyLock::new(|| {
     register_int_counter!(
         METRIC_NEW_EMAIL_ARRIVAL_TOTAL,
@@ -207,6 +284,241 @@ pub static RUSTMAILER_TASK_QUEUE_LENGTH: LazyLock<IntGaugeVec> = LazyLock::new(|
     .expect("Failed to register rustmailer_task_queue_length")
 });
 
+/// Time tasks waited in the queue between becoming due and starting to run
+pub static RUSTMAILER_TASK_QUEUE_WAIT_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        METRIC_TASK_QUEUE_WAIT_SECONDS,
+        "Seconds tasks waited between becoming due and starting to run, by task type",
+        &["task"],
+        vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0]
+    )
+    .expect("Failed to register rustmailer_task_queue_wait_seconds")
+});
+
+pub static RUSTMAILER_TASK_RUN_DURATION_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        METRIC_TASK_RUN_DURATION_SECONDS,
+        "Seconds tasks ran from start to finish, by task type",
+        &["task"],
+        vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0]
+    )
+    .expect("Failed to register rustmailer_task_run_duration_seconds")
+});
+
+pub static RUSTMAILER_DEAD_LETTER_QUEUE_DEPTH: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        METRIC_DEAD_LETTER_QUEUE_DEPTH,
+        "Number of tasks in the dead-letter queue by type",
+        &["queue"]
+    )
+    .expect("Failed to register rustmailer_dead_letter_queue_depth")
+});
+
+/// Time spent replaying the task journal at startup (memory mode only)
+pub static RUSTMAILER_TASK_JOURNAL_REPLAY_DURATION: LazyLock<Gauge> = LazyLock::new(|| {
+    register_gauge!(
+        METRIC_TASK_JOURNAL_REPLAY_DURATION,
+        "Duration of the task journal replay at startup, measured in seconds"
+    )
+    .expect("Failed to register rustmailer_task_journal_replay_duration_seconds")
+});
+
+pub static RUSTMAILER_TASK_JOURNAL_REPLAYED_RECORDS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        METRIC_TASK_JOURNAL_REPLAYED_RECORDS,
+        "Number of task journal records replayed at startup"
+    )
+    .expect("Failed to register rustmailer_task_journal_replayed_records")
+});
+
+pub static RUSTMAILER_TASK_JOURNAL_WRITTEN_RECORDS_TOTAL: LazyLock<IntCounter> =
+    LazyLock::new(|| {
+        register_int_counter!(
+            METRIC_TASK_JOURNAL_WRITTEN_RECORDS_TOTAL,
+            "Total number of task journal records written since startup"
+        )
+        .expect("Failed to register rustmailer_task_journal_written_records_total")
+    });
+
+pub static RUSTMAILER_FLAG_WRITE_TRANSACTIONS_TOTAL: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        METRIC_FLAG_WRITE_TRANSACTIONS_TOTAL,
+        "Total number of write transactions committed for envelope flag updates"
+    )
+    .expect("Failed to register rustmailer_flag_write_transactions_total")
+});
+
+pub static RUSTMAILER_FLAG_WRITE_COALESCED_SUBMISSIONS_TOTAL: LazyLock<IntCounter> =
+    LazyLock::new(|| {
+        register_int_counter!(
+            METRIC_FLAG_WRITE_COALESCED_SUBMISSIONS_TOTAL,
+            "Total number of envelope flag update submissions coalesced into write transactions"
+        )
+        .expect("Failed to register rustmailer_flag_write_coalesced_submissions_total")
+    });
+
+pub static RUSTMAILER_IMAP_IDLE_CONNECTIONS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        METRIC_IMAP_IDLE_CONNECTIONS,
+        "Number of accounts currently waiting for changes over an IMAP IDLE connection"
+    )
+    .expect("Failed to register rustmailer_imap_idle_connections")
+});
+
+pub static RUSTMAILER_OPEN_CONNECTIONS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        METRIC_OPEN_CONNECTIONS,
+        "Number of open IMAP and SMTP connections across all accounts, by protocol",
+        &["protocol"]
+    )
+    .expect("Failed to register rustmailer_open_connections")
+});
+
+pub static RUSTMAILER_CONNECTION_ADMISSION_REJECTED_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        register_int_counter_vec!(
+            METRIC_CONNECTION_ADMISSION_REJECTED_TOTAL,
+            "Connections that failed because the global connection limit was reached, by protocol",
+            &["protocol"]
+        )
+        .expect("Failed to register rustmailer_connection_admission_rejected_total")
+    });
+
+pub static RUSTMAILER_OAUTH2_GC_REMOVED_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        METRIC_OAUTH2_GC_REMOVED_TOTAL,
+        "OAuth2 records removed by the cleanup task, by kind: pending_flow, orphaned_token or stale_token",
+        &["kind"]
+    )
+    .expect("Failed to register rustmailer_oauth2_gc_removed_total")
+});
+
+pub static RUSTMAILER_OPEN_FILE_DESCRIPTORS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        METRIC_OPEN_FILE_DESCRIPTORS,
+        "Number of file descriptors open by the process"
+    )
+    .expect("Failed to register rustmailer_open_file_descriptors")
+});
+
+pub static RUSTMAILER_MAX_FILE_DESCRIPTORS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        METRIC_MAX_FILE_DESCRIPTORS,
+        "Soft limit on the number of file descriptors the process can open"
+    )
+    .expect("Failed to register rustmailer_max_file_descriptors")
+});
+
+pub static RUSTMAILER_ACCOUNT_EMAIL_SENT_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        METRIC_ACCOUNT_EMAIL_SENT_TOTAL,
+        "Total number of sent emails, grouped by account and status",
+        &[ACCOUNT_ID_LABEL, "status"]
+    )
+    .expect("Failed to register rustmailer_account_email_sent_total")
+});
+
+pub static RUSTMAILER_ACCOUNT_EMAIL_SENT_BYTES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        METRIC_ACCOUNT_EMAIL_SENT_BYTES,
+        "Total bytes of successfully sent emails, grouped by account",
+        &[ACCOUNT_ID_LABEL]
+    )
+    .expect("Failed to register rustmailer_account_email_sent_bytes")
+});
+
+pub static RUSTMAILER_ACCOUNT_NEW_EMAIL_ARRIVAL_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        register_int_counter_vec!(
+            METRIC_ACCOUNT_NEW_EMAIL_ARRIVAL_TOTAL,
+            "Total number of new emails received, grouped by account",
+            &[ACCOUNT_ID_LABEL]
+        )
+        .expect("Failed to register rustmailer_account_new_email_arrival_total")
+    });
+
+pub static RUSTMAILER_ACCOUNT_MAIL_FLAG_CHANGE_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        register_int_counter_vec!(
+            METRIC_ACCOUNT_MAIL_FLAG_CHANGE_TOTAL,
+            "Total number of mail flag change events, grouped by account",
+            &[ACCOUNT_ID_LABEL]
+        )
+        .expect("Failed to register rustmailer_account_mail_flag_change_total")
+    });
+
+pub static RUSTMAILER_ACCOUNT_EMAIL_OPENS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        METRIC_ACCOUNT_EMAIL_OPENS_TOTAL,
+        "Total number of email opens, grouped by account",
+        &[ACCOUNT_ID_LABEL]
+    )
+    .expect("Failed to register rustmailer_account_email_opens_total")
+});
+
+pub static RUSTMAILER_ACCOUNT_EMAIL_CLICKS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        METRIC_ACCOUNT_EMAIL_CLICKS_TOTAL,
+        "Total number of email link clicks, grouped by account",
+        &[ACCOUNT_ID_LABEL]
+    )
+    .expect("Failed to register rustmailer_account_email_clicks_total")
+});
+
+pub static RUSTMAILER_ACCOUNT_IMAP_REQUEST_CONTENDED_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        register_int_counter_vec!(
+            METRIC_ACCOUNT_IMAP_REQUEST_CONTENDED_TOTAL,
+            "IMAP-heavy API requests that waited for a free slot, by account and operation",
+            &[ACCOUNT_ID_LABEL, "operation"]
+        )
+        .expect("Failed to register rustmailer_account_imap_request_contended_total")
+    });
+
+pub static RUSTMAILER_ACCOUNT_IMAP_REQUEST_REJECTED_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        register_int_counter_vec!(
+            METRIC_ACCOUNT_IMAP_REQUEST_REJECTED_TOTAL,
+            "IMAP-heavy API requests rejected as account busy, by account and operation",
+            &[ACCOUNT_ID_LABEL, "operation"]
+        )
+        .expect("Failed to register rustmailer_account_imap_request_rejected_total")
+    });
+
+pub static RUSTMAILER_ACCOUNT_IMAP_REQUEST_WAIT_SECONDS: LazyLock<HistogramVec> =
+    LazyLock::new(|| {
+        register_histogram_vec!(
+            METRIC_ACCOUNT_IMAP_REQUEST_WAIT_SECONDS,
+            "Seconds IMAP-heavy API requests waited for a free slot, by account and operation",
+            &[ACCOUNT_ID_LABEL, "operation"],
+            vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]
+        )
+        .expect("Failed to register rustmailer_account_imap_request_wait_seconds")
+    });
+
+/// Drops the per-account series of a deleted account.
+pub fn remove_account_metrics(account_id: u64) {
+    let id = account_id.to_string();
+    for status in [SUCCESS, FAILURE] {
+        let _ = RUSTMAILER_ACCOUNT_EMAIL_SENT_TOTAL.remove_label_values(&[&id, status]);
+    }
+    for counter in [
+        &*RUSTMAILER_ACCOUNT_EMAIL_SENT_BYTES,
+        &*RUSTMAILER_ACCOUNT_NEW_EMAIL_ARRIVAL_TOTAL,
+        &*RUSTMAILER_ACCOUNT_MAIL_FLAG_CHANGE_TOTAL,
+        &*RUSTMAILER_ACCOUNT_EMAIL_OPENS_TOTAL,
+        &*RUSTMAILER_ACCOUNT_EMAIL_CLICKS_TOTAL,
+    ] {
+        let _ = counter.remove_label_values(&[&id]);
+    }
+    for kind in ImapRequestKind::ALL {
+        let labels = [id.as_str(), kind.as_str()];
+        let _ = RUSTMAILER_ACCOUNT_IMAP_REQUEST_CONTENDED_TOTAL.remove_label_values(&labels);
+        let _ = RUSTMAILER_ACCOUNT_IMAP_REQUEST_REJECTED_TOTAL.remove_label_values(&labels);
+        let _ = RUSTMAILER_ACCOUNT_IMAP_REQUEST_WAIT_SECONDS.remove_label_values(&labels);
+    }
+}
+
 pub struct MetricsService;
 
 impl Initialize for MetricsService {
@@ -0,0 +1,150 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeSet;
+
+use prometheus::proto::MetricFamily;
+
+use crate::modules::{
+    common::auth::ClientContext,
+    error::{code::ErrorCode, RustMailerResult},
+    metrics::ACCOUNT_ID_LABEL,
+    token::AccessTokenScope,
+};
+use crate::raise_error;
+
+/// The metric series a `/metrics` request may see.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MetricsView {
+    /// Every metric, including global counters.
+    All,
+    /// Only per-account series whose `account_id` label is in the set.
+    Accounts(BTreeSet<u64>),
+}
+
+impl MetricsView {
+    /// Resolves the view for a request. Root and tokens with the `Metrics` scope see every
+    /// metric; tokens with the `AccountMetrics` scope only see the series of their own
+    /// accounts. `requested` narrows the view to the given accounts.
+    pub fn resolve(
+        context: &ClientContext,
+        requested: Option<BTreeSet<u64>>,
+    ) -> RustMailerResult<Self> {
+        let allowed = match &context.access_token {
+            Some(token) if !token.access_scopes.contains(&AccessTokenScope::Metrics) => {
+                if !token
+                    .access_scopes
+                    .contains(&AccessTokenScope::AccountMetrics)
+                {
+                    return Err(raise_error!(
+                        "Token lacks required 'Metrics' or 'AccountMetrics' scope".into(),
+                        ErrorCode::PermissionDenied
+                    ));
+                }
+                Some(
+                    token
+                        .accounts
+                        .iter()
+                        .map(|a| a.id)
+                        .collect::<BTreeSet<u64>>(),
+                )
+            }
+            _ => None,
+        };
+
+        Ok(match (allowed, requested) {
+            (None, None) => MetricsView::All,
+            (None, Some(requested)) => MetricsView::Accounts(requested),
+            (Some(allowed), None) => MetricsView::Accounts(allowed),
+            (Some(allowed), Some(requested)) => {
+                if let Some(id) = requested.iter().find(|id| !allowed.contains(id)) {
+                    return Err(raise_error!(
+                        format!("Token is not allowed to read metrics of account {}", id),
+                        ErrorCode::PermissionDenied
+                    ));
+                }
+                MetricsView::Accounts(requested)
+            }
+        })
+    }
+
+    /// Removes the series this view may not see. Families left without series are dropped.
+    pub fn filter(&self, families: Vec<MetricFamily>) -> Vec<MetricFamily> {
+        let MetricsView::Accounts(accounts) = self else {
+            return families;
+        };
+        families
+            .into_iter()
+            .filter_map(|mut family| {
+                family.metric.retain(|metric| {
+                    metric.label.iter().any(|label| {
+                        label.name() == ACCOUNT_ID_LABEL
+                            && label
+                                .value()
+                                .parse::<u64>()
+                                .is_ok_and(|id| accounts.contains(&id))
+                    })
+                });
+                (!family.metric.is_empty()).then_some(family)
+            })
+            .collect()
+    }
+}
+
+/// Parses the `account_id` query parameter, a comma separated list of account IDs.
+pub fn parse_account_ids(value: &str) -> RustMailerResult<BTreeSet<u64>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<u64>().map_err(|_| {
+                raise_error!(
+                    format!("Invalid account ID '{}' in account_id parameter", s),
+                    ErrorCode::InvalidParameter
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prometheus::{IntCounterVec, Opts, Registry};
+
+    #[test]
+    fn test_filter_by_account_label() {
+        let registry = Registry::new();
+        let per_account = IntCounterVec::new(
+            Opts::new("per_account_total", "per account"),
+            &[ACCOUNT_ID_LABEL],
+        )
+        .unwrap();
+        let global = IntCounterVec::new(Opts::new("global_total", "global"), &["status"]).unwrap();
+        registry.register(Box::new(per_account.clone())).unwrap();
+        registry.register(Box::new(global.clone())).unwrap();
+        per_account.with_label_values(&["1"]).inc();
+        per_account.with_label_values(&["2"]).inc_by(2);
+        global.with_label_values(&["success"]).inc();
+
+        let view = MetricsView::Accounts(BTreeSet::from([2]));
+        let families = view.filter(registry.gather());
+        assert_eq!(families.len(), 1);
+        assert_eq!(families[0].name(), "per_account_total");
+        assert_eq!(families[0].metric.len(), 1);
+        assert_eq!(families[0].metric[0].label[0].value(), "2");
+
+        assert_eq!(MetricsView::All.filter(registry.gather()).len(), 2);
+    }
+
+    #[test]
+    fn test_parse_account_ids() {
+        assert_eq!(
+            parse_account_ids("1, 2,3").unwrap(),
+            BTreeSet::from([1, 2, 3])
+        );
+        assert!(parse_account_ids("1,abc").is_err());
+    }
+}
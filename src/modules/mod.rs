@@ -3,6 +3,7 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 pub mod account;
+pub mod audit;
 pub mod autoconfig;
 pub mod bounce;
 pub mod cache;
@@ -11,7 +12,10 @@ pub mod context;
 pub mod database;
 pub mod envelope;
 pub mod error;
+pub mod fault;
 pub mod grpc;
+#[cfg(feature = "test-harness")]
+pub mod harness;
 pub mod hook;
 pub mod imap;
 pub mod license;
@@ -22,6 +26,7 @@ pub mod metrics;
 pub mod oauth2;
 pub mod overview;
 pub mod rest;
+pub mod retention;
 pub mod scheduler;
 pub mod settings;
 pub mod smtp;
@@ -29,3 +34,4 @@ pub mod tasks;
 pub mod token;
 pub mod utils;
 pub mod version;
+pub mod workspace;
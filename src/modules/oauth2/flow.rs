@@ -5,7 +5,10 @@
 use crate::modules::error::code::ErrorCode;
 use crate::modules::error::RustMailerResult;
 use crate::modules::oauth2::{
-    entity::OAuth2, pending::OAuth2PendingEntity, token::OAuth2AccessToken,
+    entity::OAuth2,
+    pending::OAuth2PendingEntity,
+    refresh::clear_refresh_failures,
+    token::{OAuth2AccessToken, EXTERNAL_OAUTH_APP_ID},
 };
 use crate::modules::settings::proxy::Proxy;
 use crate::{decrypt, encrypt, raise_error};
@@ -49,6 +52,28 @@ impl OAuth2Flow {
         Self { oauth2_id }
     }
 
+    /// Generates a new authorization URL for an account already authorized with OAuth2, using
+    /// the configuration it was authorized with, so the user can grant access again after the
+    /// refresh token was revoked or expired.
+    pub async fn reauthorize_url(account_id: u64) -> RustMailerResult<String> {
+        let token = OAuth2AccessToken::get(account_id).await?.ok_or_else(|| {
+            raise_error!(
+                format!("Account {} has no OAuth2 authorization", account_id),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+        if token.oauth2_id == EXTERNAL_OAUTH_APP_ID {
+            return Err(raise_error!(
+                format!(
+                    "The OAuth2 token of account {} is managed externally and must be renewed by its provider",
+                    account_id
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        Self::new(token.oauth2_id).authorize_url(account_id).await
+    }
+
     pub async fn authorize_url(&self, account_id: u64) -> RustMailerResult<String> {
         // Fetch OAuth2 entity or return a custom error if not found
         let entity = self.fetch_oauth2_entity().await?;
@@ -128,6 +153,7 @@ impl OAuth2Flow {
 
         self.save_oauth2_entity(account_id, access_token, refresh_token)
             .await?;
+        clear_refresh_failures(account_id);
 
         Ok(())
     }
@@ -5,18 +5,24 @@
 use crate::{
     modules::{
         database::{
-            batch_delete_impl, delete_impl, async_find_impl, insert_impl, manager::DB_MANAGER,
+            async_find_impl, batch_delete_impl, delete_impl, insert_impl, list_all_impl,
+            manager::DB_MANAGER,
         },
         error::{code::ErrorCode, RustMailerResult},
+        settings::cli::SETTINGS,
     },
     raise_error, utc_now,
 };
 use itertools::Itertools;
 use native_db::*;
 use native_model::{native_model, Model};
+use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
 
-const EXPIRATION_DURATION_MS: i64 = 24 * 60 * 60 * 1000;
+/// How long an authorization flow can be completed after it was started.
+fn expiration_duration_ms() -> i64 {
+    SETTINGS.rustmailer_oauth2_pending_ttl_minutes as i64 * 60 * 1000
+}
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 #[native_model(id = 9, version = 1)]
@@ -37,6 +43,20 @@ pub struct OAuth2PendingEntity {
     pub created_at: i64,
 }
 
+/// An authorization flow that was started and not completed yet, without its secrets.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
+pub struct OAuth2PendingFlow {
+    /// The OAuth2 configuration the flow was started with.
+    pub oauth2_id: u64,
+    /// The account being authorized.
+    pub account_id: u64,
+    /// Time (Unix epoch milliseconds) the authorization URL was created.
+    pub created_at: i64,
+    /// Time (Unix epoch milliseconds) after which the flow can no longer be completed and
+    /// is removed.
+    pub expires_at: i64,
+}
+
 impl OAuth2PendingEntity {
     pub fn new(
         oauth2_id: u64,
@@ -68,8 +88,29 @@ impl OAuth2PendingEntity {
         }).await
     }
 
-    pub async fn clean() -> RustMailerResult<()> {
-        batch_delete_impl(DB_MANAGER.meta_db(), |rw| {
+    /// Lists the pending flows, of `account_id` if given, oldest first. Expired flows are
+    /// included until the next cleanup.
+    pub async fn list(account_id: Option<u64>) -> RustMailerResult<Vec<OAuth2PendingFlow>> {
+        let ttl = expiration_duration_ms();
+        Ok(list_all_impl::<OAuth2PendingEntity>(DB_MANAGER.meta_db())
+            .await?
+            .into_iter()
+            .filter(|e| account_id.is_none_or(|id| e.account_id == id))
+            .sorted_by_key(|e| e.created_at)
+            .map(|e| OAuth2PendingFlow {
+                oauth2_id: e.oauth2_id,
+                account_id: e.account_id,
+                created_at: e.created_at,
+                expires_at: e.created_at + ttl,
+            })
+            .collect())
+    }
+
+    /// Removes the flows older than `rustmailer_oauth2_pending_ttl_minutes`, returning the
+    /// number of flows removed.
+    pub async fn clean() -> RustMailerResult<usize> {
+        let ttl = expiration_duration_ms();
+        batch_delete_impl(DB_MANAGER.meta_db(), move |rw| {
             let all: Vec<OAuth2PendingEntity> = rw
                 .scan()
                 .primary()
@@ -82,12 +123,11 @@ impl OAuth2PendingEntity {
             let now = utc_now!();
             let to_delete: Vec<OAuth2PendingEntity> = all
                 .into_iter()
-                .filter(|e| now - e.created_at > EXPIRATION_DURATION_MS)
+                .filter(|e| now - e.created_at > ttl)
                 .collect();
             Ok(to_delete)
         })
-        .await?;
-        Ok(())
+        .await
     }
 
     pub async fn get(state: &str) -> RustMailerResult<Option<OAuth2PendingEntity>> {
@@ -98,7 +138,7 @@ impl OAuth2PendingEntity {
         match entity {
             Some(entity) => {
                 let state = state.to_string();
-                if utc_now!() - entity.created_at > EXPIRATION_DURATION_MS {
+                if utc_now!() - entity.created_at > expiration_duration_ms() {
                     delete_impl(DB_MANAGER.meta_db(), move |rw| {
                         rw.get()
                             .primary::<OAuth2PendingEntity>(state)
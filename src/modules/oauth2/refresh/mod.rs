@@ -2,16 +2,118 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::account::maintenance::MaintenanceWindow;
+use crate::modules::account::migration::AccountModel;
 use crate::modules::context::RustMailTask;
+use crate::modules::hook::channel::{Event, EVENT_CHANNEL};
+use crate::modules::hook::events::payload::AccountAuthenticationFailed;
+use crate::modules::hook::events::{EventPayload, EventType, RustMailerEvent};
+use crate::modules::hook::task::EventHookTask;
 use crate::modules::oauth2::token::EXTERNAL_OAUTH_APP_ID;
 use crate::modules::oauth2::{flow::OAuth2Flow, token::OAuth2AccessToken};
 use crate::modules::scheduler::periodic::PeriodicTask;
 use crate::utc_now;
+use dashmap::DashMap;
+use std::sync::LazyLock;
 use std::time::Duration;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 const TASK_INTERVAL: Duration = Duration::from_secs(60); // Interval set to 1 minute
 const FIFTEEN_MINUTES: Duration = Duration::from_secs(45 * 60);
+/// Consecutive refresh failures after which the account is reported as needing re-authorization.
+const REFRESH_FAILURE_THRESHOLD: u32 = 3;
+
+/// Consecutive token refresh failures, by account ID.
+static REFRESH_FAILURES: LazyLock<DashMap<u64, RefreshFailures>> = LazyLock::new(DashMap::new);
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct RefreshFailures {
+    count: u32,
+    since: i64,
+    notified: bool,
+}
+
+impl RefreshFailures {
+    /// Records a failure, returning `true` the first time the threshold is reached.
+    fn record(&mut self, now: i64) -> bool {
+        if self.count == 0 {
+            self.since = now;
+        }
+        self.count += 1;
+        if self.count >= REFRESH_FAILURE_THRESHOLD && !self.notified {
+            self.notified = true;
+            return true;
+        }
+        false
+    }
+}
+
+/// Forgets the refresh failures of an account, after a successful refresh or a new authorization.
+pub fn clear_refresh_failures(account_id: u64) {
+    REFRESH_FAILURES.remove(&account_id);
+}
+
+/// Whether the last refresh of the account's token failed. Failures are kept in memory, so
+/// this is `false` until a refresh fails again after a restart.
+pub fn is_refresh_failing(account_id: u64) -> bool {
+    REFRESH_FAILURES
+        .get(&account_id)
+        .is_some_and(|failures| failures.count > 0)
+}
+
+/// Counts a refresh failure, reporting the account once failures reach the threshold.
+/// Failures during a maintenance window of the account are not counted.
+async fn record_refresh_failure(token: &OAuth2AccessToken, error: String) {
+    if MaintenanceWindow::is_under_maintenance(token.account_id).await {
+        return;
+    }
+    let (reached, failures) = {
+        let mut failures = REFRESH_FAILURES.entry(token.account_id).or_default();
+        (failures.record(utc_now!()), failures.clone())
+    };
+    if !reached {
+        return;
+    }
+    warn!(
+        "Access token of account {} failed to refresh {} times in a row; the account must be re-authorized",
+        token.account_id, failures.count
+    );
+    match EventHookTask::is_watching_account_authentication_failed(token.account_id).await {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            error!(
+                "Failed to check event_watched for AccountAuthenticationFailed of account {}: {:#?}",
+                token.account_id, e
+            );
+            return;
+        }
+    }
+    let account = match AccountModel::get(token.account_id).await {
+        Ok(account) => account,
+        Err(e) => {
+            error!("Failed to load account {}: {:#?}", token.account_id, e);
+            return;
+        }
+    };
+    EVENT_CHANNEL
+        .queue(Event::new(
+            account.id,
+            &account.email,
+            RustMailerEvent::new(
+                EventType::AccountAuthenticationFailed,
+                EventPayload::AccountAuthenticationFailed(AccountAuthenticationFailed {
+                    account_id: account.id,
+                    account_email: account.email.clone(),
+                    oauth2_id: token.oauth2_id,
+                    error,
+                    consecutive_failures: failures.count,
+                    failing_since: failures.since,
+                }),
+            ),
+        ))
+        .await;
+}
 ///This task cleans up expired OAuth2 pending authorizations that haven't been completed by users in a timely manner.
 pub struct OAuth2RefreshTask;
 
@@ -50,11 +152,13 @@ impl RustMailTask for OAuth2RefreshTask {
                                             "Failed to refresh access token for {}: {}",
                                             token.account_id, error
                                         );
+                                        record_refresh_failure(&token, error.to_string()).await;
                                     } else {
                                         info!(
                                             "Successfully refreshed access token for {}",
                                             token.account_id
                                         );
+                                        clear_refresh_failures(token.account_id);
                                     }
                                 });
                             }
@@ -74,3 +178,19 @@ impl RustMailTask for OAuth2RefreshTask {
         periodic_task.start(task, None, TASK_INTERVAL, false, true);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_repeated_failures_once() {
+        let mut failures = RefreshFailures::default();
+        assert!(!failures.record(100));
+        assert!(!failures.record(200));
+        assert!(failures.record(300));
+        assert!(!failures.record(400));
+        assert_eq!(failures.count, 4);
+        assert_eq!(failures.since, 100);
+    }
+}
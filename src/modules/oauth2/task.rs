@@ -2,14 +2,24 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use crate::modules::{
-    context::RustMailTask, oauth2::pending::OAuth2PendingEntity, scheduler::periodic::PeriodicTask,
+use crate::{
+    modules::{
+        context::RustMailTask,
+        metrics::RUSTMAILER_OAUTH2_GC_REMOVED_TOTAL,
+        oauth2::{pending::OAuth2PendingEntity, token::OAuth2AccessToken},
+        scheduler::periodic::PeriodicTask,
+        settings::cli::SETTINGS,
+    },
+    utc_now,
 };
 use std::time::Duration;
+use tracing::info;
 
-const TASK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const TASK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
 
-///This task cleans up expired OAuth2 pending authorizations that haven't been completed by users in a timely manner.
+///This task cleans up expired OAuth2 pending authorizations that haven't been completed by users in a timely manner,
+///and the tokens of deleted accounts or that could no longer be refreshed.
 pub struct OAuth2CleanTask;
 
 impl RustMailTask for OAuth2CleanTask {
@@ -18,7 +28,26 @@ impl RustMailTask for OAuth2CleanTask {
 
         let task = move |_: Option<u64>| {
             Box::pin(async move {
-                OAuth2PendingEntity::clean().await?;
+                let pending = OAuth2PendingEntity::clean().await?;
+                let stale_before = SETTINGS
+                    .rustmailer_oauth2_stale_token_days
+                    .map(|days| utc_now!() - days as i64 * DAY_MS);
+                let (orphaned, stale) = OAuth2AccessToken::clean(stale_before).await?;
+                for (kind, removed) in [
+                    ("pending_flow", pending),
+                    ("orphaned_token", orphaned),
+                    ("stale_token", stale),
+                ] {
+                    RUSTMAILER_OAUTH2_GC_REMOVED_TOTAL
+                        .with_label_values(&[kind])
+                        .inc_by(removed as u64);
+                }
+                if pending + orphaned + stale > 0 {
+                    info!(
+                        "Removed {} expired OAuth2 flows, {} tokens of deleted accounts and {} stale tokens",
+                        pending, orphaned, stale
+                    );
+                }
                 Ok(())
             })
         };
@@ -2,15 +2,18 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use std::collections::HashSet;
+
 use crate::{
     decrypt, encrypt,
     modules::{
+        account::migration::AccountModel,
         database::{
-            async_find_impl, delete_impl, insert_impl, list_all_impl, manager::DB_MANAGER,
-            update_impl, upsert_impl,
+            async_find_impl, batch::WriteBatch, batch_delete_impl, delete_impl, insert_impl,
+            list_all_impl, manager::DB_MANAGER, update_impl, upsert_impl,
         },
         error::{code::ErrorCode, RustMailerResult},
-        oauth2::entity::OAuth2,
+        oauth2::{entity::OAuth2, refresh::is_refresh_failing},
     },
     raise_error, utc_now,
 };
@@ -134,18 +137,66 @@ impl OAuth2AccessToken {
             .collect()
     }
 
-    pub async fn try_delete(account_id: u64) -> RustMailerResult<()> {
-        if Self::get(account_id).await?.is_none() {
-            return Ok(());
-        }
+    /// Removes the tokens of accounts that no longer exist and, if `stale_before` is set,
+    /// the tokens last refreshed before that time whose refresh is currently failing.
+    /// Externally supplied tokens are never refreshed, so they are never stale.
+    /// Returns the numbers of orphaned and stale tokens removed.
+    pub async fn clean(stale_before: Option<i64>) -> RustMailerResult<(usize, usize)> {
+        // Tokens created after the accounts were listed may belong to new accounts.
+        let started = utc_now!();
+        let accounts: HashSet<u64> = AccountModel::list_all()
+            .await?
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        let orphaned = Self::remove_where(move |token| {
+            token.created_at < started && !accounts.contains(&token.account_id)
+        })
+        .await?;
+        let stale = match stale_before {
+            Some(before) => {
+                Self::remove_where(move |token| {
+                    token.is_stale(before) && is_refresh_failing(token.account_id)
+                })
+                .await?
+            }
+            None => 0,
+        };
+        Ok((orphaned, stale))
+    }
 
-        delete_impl(DB_MANAGER.meta_db(), move |rw|{
-            rw.get().primary::<OAuth2AccessToken>(account_id)
-            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-            .ok_or_else(|| raise_error!(format!(
-                "The oauth2 access token entity with account_id={account_id} that you want to delete was not found."
-            ),ErrorCode::ResourceNotFound))
-        }).await
+    /// Whether the token was last refreshed before `before`. Externally supplied tokens are
+    /// not refreshed by RustMailer, so their age says nothing about their validity.
+    fn is_stale(&self, before: i64) -> bool {
+        self.oauth2_id != EXTERNAL_OAUTH_APP_ID && self.updated_at < before
+    }
+
+    async fn remove_where(
+        matches: impl Fn(&OAuth2AccessToken) -> bool + Send + 'static,
+    ) -> RustMailerResult<usize> {
+        batch_delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            let tokens: Vec<OAuth2AccessToken> = rw
+                .scan()
+                .primary()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .all()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .collect::<Result<_, _>>()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(tokens.into_iter().filter(|t| matches(t)).collect())
+        })
+        .await
+    }
+
+    /// Adds the removal of the access token of an account, if any, to `batch`.
+    pub fn stage_try_delete(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let token = rw
+                .get()
+                .primary::<OAuth2AccessToken>(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(token.into_iter().collect())
+        })
     }
 
     pub async fn delete_by_oauth2_id(oauth2_id: u64) -> RustMailerResult<()> {
@@ -230,7 +281,20 @@ impl ExternalOAuth2Request {
 
 #[cfg(test)]
 mod tests {
-    use crate::modules::oauth2::token::OAuth2AccessToken;
+    use crate::modules::oauth2::token::{OAuth2AccessToken, EXTERNAL_OAUTH_APP_ID};
+
+    #[test]
+    fn external_tokens_are_never_stale() {
+        let token = |oauth2_id| OAuth2AccessToken {
+            account_id: 1000,
+            oauth2_id,
+            updated_at: 100,
+            ..Default::default()
+        };
+        assert!(token(1020).is_stale(200));
+        assert!(!token(1020).is_stale(100));
+        assert!(!token(EXTERNAL_OAUTH_APP_ID).is_stale(200));
+    }
 
     #[tokio::test]
     async fn test1() {
@@ -4,7 +4,7 @@
 
 use crate::{
     modules::{
-        context::RustMailTask, overview::metrics::DailyMetrics, scheduler::periodic::PeriodicTask,
+        context::RustMailTask, overview::rollup::MetricRollup, scheduler::periodic::PeriodicTask,
     },
     utc_now,
 };
@@ -12,9 +12,10 @@ use crate::{
 use std::time::Duration;
 
 const TASK_INTERVAL: Duration = Duration::from_secs(5 * 60); // every 5 mins
-const METRIC_RETENTION_MS: i64 = 24 * 60 * 60 * 1000; // 1 day
+pub const METRIC_RETENTION_MS: i64 = 24 * 60 * 60 * 1000; // 1 day
 
-///This task cleans up expired weekly metrics entries older than 7 days.
+/// Rolls metric points older than one day up into daily and weekly points, and removes
+/// points past their retention.
 pub struct MetricsCleanTask;
 
 impl RustMailTask for MetricsCleanTask {
@@ -22,11 +23,7 @@ impl RustMailTask for MetricsCleanTask {
         let periodic_task = PeriodicTask::new("daily-metrics-cleaner");
 
         let task = move |_ctx: Option<u64>| {
-            Box::pin(async move {
-                let now = utc_now!();
-                let expire_before = now - METRIC_RETENTION_MS;
-                DailyMetrics::clean(expire_before).await
-            })
+            Box::pin(async move { MetricRollup::roll_up(utc_now!()).await })
         };
 
         periodic_task.start(task, None, TASK_INTERVAL, false, false);
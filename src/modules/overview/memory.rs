@@ -0,0 +1,188 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    modules::{
+        cache::{
+            disk::CacheItem,
+            imap::{journal::FlagChangeJournal, manager::EnvelopeFlagsManager},
+            vendor::gmail::cache::GMAIL_LABELS_CACHE,
+        },
+        error::{code::ErrorCode, RustMailerResult},
+        message::search::cache::IMAP_SEARCH_CACHE,
+        settings::cli::SETTINGS,
+    },
+    raise_error,
+};
+
+/// Size in bytes of the metadata database as of the last snapshot, 0 if unknown.
+/// Only tracked when metadata memory mode is enabled.
+static METADATA_SIZE: AtomicU64 = AtomicU64::new(0);
+
+/// Allocator statistics reported by mimalloc.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct AllocatorStats {
+    /// Resident set size of the process, in bytes.
+    pub current_rss: u64,
+    /// Peak resident set size of the process, in bytes.
+    pub peak_rss: u64,
+    /// Memory currently committed by the allocator, in bytes.
+    pub current_commit: u64,
+    /// Peak memory committed by the allocator, in bytes.
+    pub peak_commit: u64,
+    /// Number of hard page faults since process start.
+    pub page_faults: u64,
+}
+
+/// Number of entries held by the in-process caches.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SubsystemCacheSizes {
+    /// UIDs tracked in the in-memory envelope flags state, across all accounts.
+    pub envelope_flags_entries: usize,
+    /// UIDs currently protected by the flag change journal.
+    pub flag_journal_entries: usize,
+    /// Entries in the IMAP search result LRU cache.
+    pub imap_search_cache_entries: usize,
+    /// Entries in the Gmail label LRU cache.
+    pub gmail_labels_cache_entries: usize,
+    /// Files indexed by the disk cache.
+    pub disk_cache_items: usize,
+    /// Total size of the files indexed by the disk cache, in bytes.
+    pub disk_cache_bytes: u64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct MemoryReport {
+    /// The global allocator in use.
+    pub allocator: String,
+    pub allocator_stats: AllocatorStats,
+    /// Total physical memory of the host, in bytes.
+    pub system_total_memory: u64,
+    /// Memory available for new allocations on the host, in bytes.
+    pub system_available_memory: u64,
+    pub caches: SubsystemCacheSizes,
+    /// Whether metadata is kept in memory and persisted through snapshots.
+    pub metadata_memory_mode: bool,
+    /// Size of the in-memory metadata as of the last snapshot, in bytes.
+    pub metadata_size: Option<u64>,
+    /// Configured limit for the in-memory metadata, in bytes.
+    pub metadata_memory_limit: Option<u64>,
+}
+
+impl MemoryReport {
+    pub async fn get() -> RustMailerResult<Self> {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+
+        let disk_items = CacheItem::list().await?;
+        let caches = SubsystemCacheSizes {
+            envelope_flags_entries: EnvelopeFlagsManager::count_uid_total(),
+            flag_journal_entries: FlagChangeJournal::len(),
+            imap_search_cache_entries: IMAP_SEARCH_CACHE.len().await,
+            gmail_labels_cache_entries: GMAIL_LABELS_CACHE.len().await,
+            disk_cache_items: disk_items.len(),
+            disk_cache_bytes: disk_items.iter().map(|i| i.size).sum(),
+        };
+
+        let metadata_memory_mode = SETTINGS.rustmailer_metadata_memory_mode_enabled;
+        Ok(Self {
+            allocator: "mimalloc".into(),
+            allocator_stats: allocator_stats(),
+            system_total_memory: system.total_memory(),
+            system_available_memory: system.available_memory(),
+            caches,
+            metadata_memory_mode,
+            metadata_size: metadata_memory_mode
+                .then(|| METADATA_SIZE.load(Ordering::Relaxed))
+                .filter(|size| *size > 0),
+            metadata_memory_limit: SETTINGS.rustmailer_metadata_memory_limit,
+        })
+    }
+}
+
+fn allocator_stats() -> AllocatorStats {
+    let mut elapsed_msecs = 0usize;
+    let mut user_msecs = 0usize;
+    let mut system_msecs = 0usize;
+    let mut current_rss = 0usize;
+    let mut peak_rss = 0usize;
+    let mut current_commit = 0usize;
+    let mut peak_commit = 0usize;
+    let mut page_faults = 0usize;
+    // SAFETY: all pointers are valid for writes for the duration of the call.
+    unsafe {
+        libmimalloc_sys::mi_process_info(
+            &mut elapsed_msecs,
+            &mut user_msecs,
+            &mut system_msecs,
+            &mut current_rss,
+            &mut peak_rss,
+            &mut current_commit,
+            &mut peak_commit,
+            &mut page_faults,
+        );
+    }
+    AllocatorStats {
+        current_rss: current_rss as u64,
+        peak_rss: peak_rss as u64,
+        current_commit: current_commit as u64,
+        peak_commit: peak_commit as u64,
+        page_faults: page_faults as u64,
+    }
+}
+
+// Options of `mi_option_e` in mimalloc v2 that `libmimalloc_sys` does not export.
+const MI_OPTION_PURGE_DELAY: libmimalloc_sys::mi_option_t = 15;
+const MI_OPTION_ARENA_RESERVE: libmimalloc_sys::mi_option_t = 23;
+
+/// Applies the mimalloc tuning options from the settings. Should be called as early as
+/// possible during startup; arenas that were already reserved are not affected.
+pub fn configure_allocator() {
+    if let Some(reserve_mib) = SETTINGS.rustmailer_mimalloc_arena_reserve_mib {
+        // mimalloc expects the arena reserve size in KiB.
+        let reserve_kib = (reserve_mib as i64).saturating_mul(1024);
+        // SAFETY: setting an allocator option has no memory safety requirements.
+        unsafe { libmimalloc_sys::mi_option_set(MI_OPTION_ARENA_RESERVE, reserve_kib) };
+        info!("mimalloc arena reserve set to {} MiB", reserve_mib);
+    }
+    if let Some(delay_ms) = SETTINGS.rustmailer_mimalloc_purge_delay_ms {
+        // SAFETY: see above.
+        unsafe { libmimalloc_sys::mi_option_set(MI_OPTION_PURGE_DELAY, delay_ms) };
+        info!("mimalloc purge delay set to {} ms", delay_ms);
+    }
+}
+
+/// Records the current size of the in-memory metadata, measured from its latest snapshot.
+pub fn record_metadata_size(size: u64) {
+    METADATA_SIZE.store(size, Ordering::Relaxed);
+}
+
+/// Rejects operations that grow the metadata database once the in-memory metadata has
+/// reached `rustmailer_metadata_memory_limit`. Has no effect outside memory mode.
+pub fn check_metadata_capacity() -> RustMailerResult<()> {
+    if !SETTINGS.rustmailer_metadata_memory_mode_enabled {
+        return Ok(());
+    }
+    let Some(limit) = SETTINGS.rustmailer_metadata_memory_limit else {
+        return Ok(());
+    };
+    let size = METADATA_SIZE.load(Ordering::Relaxed);
+    if size >= limit {
+        return Err(raise_error!(
+            format!(
+                "In-memory metadata has reached its size limit ({} of {} bytes). \
+                Remove unused data or raise rustmailer_metadata_memory_limit.",
+                size, limit
+            ),
+            ErrorCode::ExceedsLimitation
+        ));
+    }
+    Ok(())
+}
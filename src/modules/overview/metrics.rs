@@ -11,15 +11,31 @@ use serde::{Deserialize, Serialize};
 use crate::{
     id,
     modules::{
-        database::{batch_delete_impl, insert_impl, list_all_impl, manager::DB_MANAGER},
+        database::{
+            batch_delete_impl, batch_insert_impl, insert_impl, manager::DB_MANAGER,
+            range_by_secondary_key_impl, replica::READ_REPLICA,
+        },
         error::{code::ErrorCode, RustMailerResult},
     },
     raise_error,
 };
 
-#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
 #[native_model(id = 14, version = 1)]
 #[native_db]
+pub struct DailyMetricsV1 {
+    #[primary_key]
+    pub id: u64,
+    pub metric: String,
+    #[secondary_key]
+    pub created_at: i64,
+    pub value: u64,
+    pub label: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 14, version = 2, from = DailyMetricsV1)]
+#[native_db]
 pub struct DailyMetrics {
     #[primary_key]
     pub id: u64,
@@ -28,27 +44,76 @@ pub struct DailyMetrics {
     pub created_at: i64,
     pub value: u64,
     pub label: String,
+    /// The account a per-account point belongs to; `None` for global points.
+    pub account_id: Option<u64>,
+}
+
+impl From<DailyMetricsV1> for DailyMetrics {
+    fn from(value: DailyMetricsV1) -> Self {
+        Self {
+            id: value.id,
+            metric: value.metric,
+            created_at: value.created_at,
+            value: value.value,
+            label: value.label,
+            account_id: None,
+        }
+    }
+}
+
+impl From<DailyMetrics> for DailyMetricsV1 {
+    fn from(value: DailyMetrics) -> Self {
+        Self {
+            id: value.id,
+            metric: value.metric,
+            created_at: value.created_at,
+            value: value.value,
+            label: value.label,
+        }
+    }
 }
 
 impl DailyMetrics {
-    pub async fn save(
+    pub fn new(
         metric: String,
         value: u64,
         label: String,
         created_at: i64,
-    ) -> RustMailerResult<()> {
-        let item = DailyMetrics {
+        account_id: Option<u64>,
+    ) -> Self {
+        DailyMetrics {
             id: id!(96),
             metric,
             created_at,
             value,
             label,
-        };
+            account_id,
+        }
+    }
+
+    pub async fn save(
+        metric: String,
+        value: u64,
+        label: String,
+        created_at: i64,
+    ) -> RustMailerResult<()> {
+        let item = DailyMetrics::new(metric, value, label, created_at, None);
         insert_impl(DB_MANAGER.meta_db(), item).await
     }
 
-    pub async fn list_all() -> RustMailerResult<Vec<DailyMetrics>> {
-        list_all_impl(&DB_MANAGER.meta_db()).await
+    pub async fn save_batch(items: Vec<DailyMetrics>) -> RustMailerResult<()> {
+        batch_insert_impl(DB_MANAGER.meta_db(), items).await
+    }
+
+    /// Returns the metrics recorded in `[from, to)`, ordered by `created_at`.
+    /// Served from the read replica when it is enabled.
+    pub async fn list_between(from: i64, to: i64) -> RustMailerResult<Vec<DailyMetrics>> {
+        range_by_secondary_key_impl(
+            &READ_REPLICA.meta_db(),
+            DailyMetricsKey::created_at,
+            from..to,
+        )
+        .await
     }
 
     pub async fn clean(cut: i64) -> RustMailerResult<()> {
@@ -85,13 +150,13 @@ mod test {
         }
 
         // Check initial insertion
-        let all = DailyMetrics::list_all().await.unwrap();
+        let all = DailyMetrics::list_between(0, i64::MAX).await.unwrap();
         assert_eq!(all.len(), 6, "Expected 6 metrics before cleanup");
 
         // Clean records older than latest N = 3
         DailyMetrics::clean(3).await.unwrap();
 
-        let all = DailyMetrics::list_all().await.unwrap();
+        let all = DailyMetrics::list_between(0, i64::MAX).await.unwrap();
         println!("{:#?}", all);
         println!("Remaining metrics after cleanup: {}", all.len());
         assert_eq!(all.len(), 4, "Expected 4 metrics after cleanup");
@@ -5,24 +5,29 @@
 use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
 
+use crate::{raise_error, utc_now};
 use crate::modules::{
-    account::migration::AccountModel,
+    account::migration::{AccountModel, AccountV12Key},
     context::executors::RUST_MAIL_CONTEXT,
-    error::RustMailerResult,
+    database::{count_by_unique_secondary_key_impl, replica::READ_REPLICA},
+    error::{code::ErrorCode, RustMailerResult},
+    hook::task::EventHookTask,
     metrics::{
-        EMAIL, FAILURE, HOOK, HTTP, METRIC_EMAIL_CLICKS_TOTAL, METRIC_EMAIL_OPENS_TOTAL,
+        EMAIL, FAILURE, HOOK, HTTP, KAFKA, METRIC_EMAIL_CLICKS_TOTAL, METRIC_EMAIL_OPENS_TOTAL,
         METRIC_EMAIL_SENT_BYTES, METRIC_EMAIL_SENT_TOTAL,
         METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION, METRIC_IMAP_TRAFFIC_TOTAL,
         METRIC_MAIL_FLAG_CHANGE_TOTAL, METRIC_NEW_EMAIL_ARRIVAL_TOTAL, METRIC_TASK_QUEUE_LENGTH,
-        NATS, RECEIVED, SENT, SUCCESS,
+        NATS, RECEIVED, REDIS, SENT, SUCCESS,
     },
-    overview::metrics::DailyMetrics,
-    scheduler::model::TaskStatus,
-    tasks::queue::RustMailerTaskQueue,
+    overview::{clean::METRIC_RETENTION_MS, metrics::DailyMetrics, rollup::MetricRollup},
+    scheduler::{model::TaskStatus, nativedb::meta::NativeDbTaskStore, task::Task},
+    smtp::request::task::SmtpTask,
 };
 
 pub mod clean;
+pub mod memory;
 pub mod metrics;
+pub mod rollup;
 pub mod saver;
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
@@ -39,20 +44,43 @@ pub struct Overview {
     pub uptime: i64,
     pub rustmailer_version: String,
     pub time_series: MetricsTimeSeries,
+    /// When the read replica is enabled, the time (UNIX epoch milliseconds) of the copy
+    /// these figures were computed from. `None` means the figures are live.
+    pub data_refreshed_at: Option<i64>,
+}
+
+/// Time range and account filter for the overview time series.
+#[derive(Clone, Debug, Default)]
+pub struct OverviewQuery {
+    /// Start of the range (UNIX epoch milliseconds, inclusive). Defaults to one day ago.
+    pub from: Option<i64>,
+    /// End of the range (UNIX epoch milliseconds, exclusive). Defaults to now.
+    pub to: Option<i64>,
+    /// Restricts the time series to a single account.
+    pub account_id: Option<u64>,
 }
 
 impl Overview {
-    pub async fn get() -> RustMailerResult<Self> {
+    pub async fn get(query: OverviewQuery) -> RustMailerResult<Self> {
         let uptime = RUST_MAIL_CONTEXT.uptime_ms();
-        let send_queue = RustMailerTaskQueue::get().unwrap();
-        let pending_email_tasks = send_queue
-            .list_email_tasks_by_status(TaskStatus::Scheduled)
-            .await?;
-        let pending_hook_tasks = send_queue
-            .list_hook_tasks_by_status(TaskStatus::Scheduled)
-            .await?;
-        let account_num = AccountModel::count().await?;
-        let mut time_series = MetricsTimeSeries::get().await?;
+        // The task and account figures come from one replica snapshot, so they match each
+        // other and `data_refreshed_at` even if the replica is refreshed meanwhile.
+        let (meta_db, tasks_db, data_refreshed_at) = READ_REPLICA.snapshot();
+        let pending_email_tasks = NativeDbTaskStore::get_all_tasks_by_status(
+            &tasks_db,
+            SmtpTask::TASK_KEY,
+            TaskStatus::Scheduled,
+        )
+        .await?;
+        let pending_hook_tasks = NativeDbTaskStore::get_all_tasks_by_status(
+            &tasks_db,
+            EventHookTask::TASK_KEY,
+            TaskStatus::Scheduled,
+        )
+        .await?;
+        let account_num =
+            count_by_unique_secondary_key_impl::<AccountModel>(&meta_db, AccountV12Key::id).await?;
+        let mut time_series = MetricsTimeSeries::get(query).await?;
         time_series.sort_by_timestamp();
 
         Ok(Self {
@@ -62,6 +90,7 @@ impl Overview {
             uptime,
             rustmailer_version: env!("CARGO_PKG_VERSION").into(),
             time_series,
+            data_refreshed_at,
         })
     }
 }
@@ -81,6 +110,10 @@ pub struct MetricsTimeSeries {
     event_dispatch_success_nats: Vec<TimeSeriesPoint>,
     event_dispatch_failure_http: Vec<TimeSeriesPoint>,
     event_dispatch_failure_nats: Vec<TimeSeriesPoint>,
+    event_dispatch_success_kafka: Vec<TimeSeriesPoint>,
+    event_dispatch_failure_kafka: Vec<TimeSeriesPoint>,
+    event_dispatch_success_redis: Vec<TimeSeriesPoint>,
+    event_dispatch_failure_redis: Vec<TimeSeriesPoint>,
     email_task_queue_length: Vec<TimeSeriesPoint>,
     hook_task_queue_length: Vec<TimeSeriesPoint>,
 }
@@ -101,64 +134,118 @@ impl MetricsTimeSeries {
             event_dispatch_success_nats: Vec::new(),
             event_dispatch_failure_http: Vec::new(),
             event_dispatch_failure_nats: Vec::new(),
+            event_dispatch_success_kafka: Vec::new(),
+            event_dispatch_failure_kafka: Vec::new(),
+            event_dispatch_success_redis: Vec::new(),
+            event_dispatch_failure_redis: Vec::new(),
             email_task_queue_length: Vec::new(),
             hook_task_queue_length: Vec::new(),
         }
     }
 
-    pub async fn get() -> RustMailerResult<Self> {
-        let mut result = Self::new();
-        let all = DailyMetrics::list_all().await?;
+    /// Loads the series in the query range. Points within the last day have a one minute
+    /// resolution; older points are daily or weekly rollups. A range starting before the
+    /// oldest weekly point kept is rejected rather than silently cut short. With an
+    /// account, only the series recorded per account are filled.
+    pub async fn get(query: OverviewQuery) -> RustMailerResult<Self> {
+        let now = utc_now!();
+        let from = query.from.unwrap_or(now - METRIC_RETENTION_MS);
+        let to = query.to.unwrap_or(now + 1);
+        if from >= to {
+            return Err(raise_error!(
+                "'from' must be earlier than 'to'".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let retained_since = MetricRollup::retained_since(now);
+        if query.from.is_some_and(|from| from < retained_since) {
+            return Err(raise_error!(
+                format!(
+                    "'from' is before {retained_since}, the start of the oldest metrics kept \
+                     (see 'rustmailer_metrics_weekly_retention_weeks')"
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
 
-        for record in all {
-            let point = TimeSeriesPoint {
-                timestamp: record.created_at,
-                value: record.value,
-            };
-            if record.metric == METRIC_IMAP_TRAFFIC_TOTAL && record.label == SENT {
-                result.imap_traffic_sent.push(point);
-            } else if record.metric == METRIC_IMAP_TRAFFIC_TOTAL && record.label == RECEIVED {
-                result.imap_traffic_received.push(point);
-            } else if record.metric == METRIC_EMAIL_SENT_TOTAL && record.label == SUCCESS {
-                result.email_sent_success.push(point);
-            } else if record.metric == METRIC_EMAIL_SENT_TOTAL && record.label == FAILURE {
-                result.email_sent_failure.push(point);
-            } else if record.metric == METRIC_EMAIL_SENT_BYTES {
-                result.email_sent_bytes.push(point);
-            } else if record.metric == METRIC_NEW_EMAIL_ARRIVAL_TOTAL {
-                result.new_email_arrival.push(point);
-            } else if record.metric == METRIC_MAIL_FLAG_CHANGE_TOTAL {
-                result.mail_flag_change.push(point);
-            } else if record.metric == METRIC_EMAIL_OPENS_TOTAL {
-                result.email_opens.push(point)
-            } else if record.metric == METRIC_EMAIL_CLICKS_TOTAL {
-                result.email_clicks.push(point);
-            } else if record.metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
-                && record.label == format!("{}_{}", SUCCESS, HTTP)
-            {
-                result.event_dispatch_success_http.push(point);
-            } else if record.metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
-                && record.label == format!("{}_{}", SUCCESS, NATS)
-            {
-                result.event_dispatch_success_nats.push(point);
-            } else if record.metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
-                && record.label == format!("{}_{}", FAILURE, HTTP)
-            {
-                result.event_dispatch_failure_http.push(point);
-            } else if record.metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
-                && record.label == format!("{}_{}", FAILURE, NATS)
-            {
-                result.event_dispatch_failure_nats.push(point);
-            } else if record.metric == METRIC_TASK_QUEUE_LENGTH && record.label == EMAIL {
-                result.email_task_queue_length.push(point);
-            } else if record.metric == METRIC_TASK_QUEUE_LENGTH && record.label == HOOK {
-                result.hook_task_queue_length.push(point);
+        let mut result = Self::new();
+        let raw = DailyMetrics::list_between(from, to).await?;
+        let rollups = MetricRollup::list_between(from, to).await?;
+        let records = raw
+            .into_iter()
+            .map(|r| (r.metric, r.label, r.account_id, r.created_at, r.value))
+            .chain(
+                rollups
+                    .into_iter()
+                    .map(|r| (r.metric, r.label, r.account_id, r.bucket_start, r.value)),
+            );
+        for (metric, label, account_id, timestamp, value) in records {
+            if account_id == query.account_id {
+                result.push(&metric, &label, TimeSeriesPoint { timestamp, value });
             }
         }
 
         Ok(result)
     }
 
+    fn push(&mut self, metric: &str, label: &str, point: TimeSeriesPoint) {
+        if metric == METRIC_IMAP_TRAFFIC_TOTAL && label == SENT {
+            self.imap_traffic_sent.push(point);
+        } else if metric == METRIC_IMAP_TRAFFIC_TOTAL && label == RECEIVED {
+            self.imap_traffic_received.push(point);
+        } else if metric == METRIC_EMAIL_SENT_TOTAL && label == SUCCESS {
+            self.email_sent_success.push(point);
+        } else if metric == METRIC_EMAIL_SENT_TOTAL && label == FAILURE {
+            self.email_sent_failure.push(point);
+        } else if metric == METRIC_EMAIL_SENT_BYTES {
+            self.email_sent_bytes.push(point);
+        } else if metric == METRIC_NEW_EMAIL_ARRIVAL_TOTAL {
+            self.new_email_arrival.push(point);
+        } else if metric == METRIC_MAIL_FLAG_CHANGE_TOTAL {
+            self.mail_flag_change.push(point);
+        } else if metric == METRIC_EMAIL_OPENS_TOTAL {
+            self.email_opens.push(point)
+        } else if metric == METRIC_EMAIL_CLICKS_TOTAL {
+            self.email_clicks.push(point);
+        } else if metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            && label == format!("{}_{}", SUCCESS, HTTP)
+        {
+            self.event_dispatch_success_http.push(point);
+        } else if metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            && label == format!("{}_{}", SUCCESS, NATS)
+        {
+            self.event_dispatch_success_nats.push(point);
+        } else if metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            && label == format!("{}_{}", FAILURE, HTTP)
+        {
+            self.event_dispatch_failure_http.push(point);
+        } else if metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            && label == format!("{}_{}", FAILURE, NATS)
+        {
+            self.event_dispatch_failure_nats.push(point);
+        } else if metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            && label == format!("{}_{}", SUCCESS, KAFKA)
+        {
+            self.event_dispatch_success_kafka.push(point);
+        } else if metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            && label == format!("{}_{}", FAILURE, KAFKA)
+        {
+            self.event_dispatch_failure_kafka.push(point);
+        } else if metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            && label == format!("{}_{}", SUCCESS, REDIS)
+        {
+            self.event_dispatch_success_redis.push(point);
+        } else if metric == METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            && label == format!("{}_{}", FAILURE, REDIS)
+        {
+            self.event_dispatch_failure_redis.push(point);
+        } else if metric == METRIC_TASK_QUEUE_LENGTH && label == EMAIL {
+            self.email_task_queue_length.push(point);
+        } else if metric == METRIC_TASK_QUEUE_LENGTH && label == HOOK {
+            self.hook_task_queue_length.push(point);
+        }
+    }
+
     pub fn sort_by_timestamp(&mut self) {
         self.imap_traffic_sent.sort_by_key(|point| point.timestamp);
         self.imap_traffic_received
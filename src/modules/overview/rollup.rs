@@ -0,0 +1,451 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use ahash::AHashMap;
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modules::{
+        database::{
+            manager::DB_MANAGER, range_by_secondary_key_impl, replica::READ_REPLICA,
+            with_transaction,
+        },
+        error::{code::ErrorCode, RustMailerResult},
+        metrics::METRIC_TASK_QUEUE_LENGTH,
+        overview::{
+            clean::METRIC_RETENTION_MS,
+            metrics::{DailyMetrics, DailyMetricsKey},
+        },
+        settings::cli::SETTINGS,
+    },
+    raise_error,
+};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const WEEK_MS: i64 = 7 * DAY_MS;
+/// 1970-01-05, the first Monday after the UNIX epoch. Weekly buckets start on Mondays (UTC).
+const FIRST_MONDAY_MS: i64 = 4 * DAY_MS;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Deserialize, Serialize, Enum)]
+pub enum MetricResolution {
+    /// Points recorded every minute by the metrics saver, kept for one day.
+    #[default]
+    Raw,
+    /// One point per UTC day.
+    Daily,
+    /// One point per week, starting on Monday (UTC).
+    Weekly,
+}
+
+impl MetricResolution {
+    /// Start (UNIX epoch milliseconds) of the bucket `timestamp` falls into.
+    pub fn bucket_start(&self, timestamp: i64) -> i64 {
+        match self {
+            MetricResolution::Raw => timestamp,
+            MetricResolution::Daily => timestamp - timestamp.rem_euclid(DAY_MS),
+            MetricResolution::Weekly => {
+                timestamp - (timestamp - FIRST_MONDAY_MS).rem_euclid(WEEK_MS)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[native_model(id = 17, version = 1)]
+#[native_db]
+pub struct MetricRollupV1 {
+    #[primary_key]
+    pub id: String,
+    pub resolution: MetricResolution,
+    #[secondary_key]
+    pub bucket_start: i64,
+    pub metric: String,
+    pub label: String,
+    pub value: u64,
+}
+
+/// A metric rolled up from older `DailyMetrics` points, so that history can be kept
+/// without storing a point per minute forever.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[native_model(id = 17, version = 2, from = MetricRollupV1)]
+#[native_db]
+pub struct MetricRollup {
+    #[primary_key]
+    pub id: String,
+    pub resolution: MetricResolution,
+    /// Start of the day or week (UNIX epoch milliseconds).
+    #[secondary_key]
+    pub bucket_start: i64,
+    pub metric: String,
+    pub label: String,
+    pub value: u64,
+    /// The account a per-account point belongs to; `None` for global points.
+    pub account_id: Option<u64>,
+}
+
+impl From<MetricRollupV1> for MetricRollup {
+    fn from(value: MetricRollupV1) -> Self {
+        Self {
+            id: value.id,
+            resolution: value.resolution,
+            bucket_start: value.bucket_start,
+            metric: value.metric,
+            label: value.label,
+            value: value.value,
+            account_id: None,
+        }
+    }
+}
+
+impl From<MetricRollup> for MetricRollupV1 {
+    fn from(value: MetricRollup) -> Self {
+        Self {
+            id: value.id,
+            resolution: value.resolution,
+            bucket_start: value.bucket_start,
+            metric: value.metric,
+            label: value.label,
+            value: value.value,
+        }
+    }
+}
+
+impl MetricRollup {
+    /// Global points keep the key format of version 1, so migrated rollups still merge
+    /// with new points of the same bucket.
+    fn id(
+        resolution: MetricResolution,
+        bucket_start: i64,
+        metric: &str,
+        label: &str,
+        account_id: Option<u64>,
+    ) -> String {
+        match account_id {
+            Some(account_id) => format!(
+                "{:?}:{}:{}:{}:{}",
+                resolution, bucket_start, metric, label, account_id
+            ),
+            None => format!("{:?}:{}:{}:{}", resolution, bucket_start, metric, label),
+        }
+    }
+
+    /// Queue lengths are gauges and keep the peak of a bucket; every other metric is a
+    /// counter delta and is summed.
+    fn merge(&mut self, value: u64) {
+        if self.metric == METRIC_TASK_QUEUE_LENGTH {
+            self.value = self.value.max(value);
+        } else {
+            self.value = self.value.saturating_add(value);
+        }
+    }
+
+    /// Returns the rollups in `[from, to)`, ordered by bucket start.
+    pub async fn list_between(from: i64, to: i64) -> RustMailerResult<Vec<MetricRollup>> {
+        range_by_secondary_key_impl(
+            &READ_REPLICA.meta_db(),
+            MetricRollupKey::bucket_start,
+            from..to,
+        )
+        .await
+    }
+
+    /// The start of the oldest weekly point kept at `now`. Metrics before it are removed.
+    pub fn retained_since(now: i64) -> i64 {
+        MetricResolution::Weekly.bucket_start(
+            now - SETTINGS.rustmailer_metrics_weekly_retention_weeks as i64 * WEEK_MS,
+        )
+    }
+
+    /// Rolls raw points older than one day into daily points, daily points older than
+    /// `rustmailer_metrics_daily_retention_days` into weekly points, and removes weekly
+    /// points older than `rustmailer_metrics_weekly_retention_weeks`.
+    pub async fn roll_up(now: i64) -> RustMailerResult<()> {
+        let raw_cutoff = now - METRIC_RETENTION_MS;
+        let daily_cutoff = MetricResolution::Daily
+            .bucket_start(now - SETTINGS.rustmailer_metrics_daily_retention_days as i64 * DAY_MS);
+        let weekly_cutoff = Self::retained_since(now);
+
+        with_transaction(DB_MANAGER.meta_db(), move |rw| {
+            let raw: Vec<DailyMetrics> = rw
+                .scan()
+                .secondary(DailyMetricsKey::created_at)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .range(..raw_cutoff)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            let mut daily = AHashMap::new();
+            for point in raw {
+                aggregate(
+                    &mut daily,
+                    MetricResolution::Daily,
+                    point.created_at,
+                    &point.metric,
+                    &point.label,
+                    point.value,
+                    point.account_id,
+                );
+                rw.remove(point)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            }
+            store(rw, daily)?;
+
+            let expired: Vec<MetricRollup> = rw
+                .scan()
+                .secondary(MetricRollupKey::bucket_start)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .range(..daily_cutoff)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            let mut weekly = AHashMap::new();
+            for rollup in expired {
+                match rollup.resolution {
+                    MetricResolution::Weekly if rollup.bucket_start >= weekly_cutoff => continue,
+                    MetricResolution::Weekly | MetricResolution::Raw => {}
+                    MetricResolution::Daily => aggregate(
+                        &mut weekly,
+                        MetricResolution::Weekly,
+                        rollup.bucket_start,
+                        &rollup.metric,
+                        &rollup.label,
+                        rollup.value,
+                        rollup.account_id,
+                    ),
+                }
+                rw.remove(rollup)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            }
+            store(rw, weekly)
+        })
+        .await
+    }
+}
+
+/// Folds a point into the bucket of `resolution` it belongs to.
+fn aggregate(
+    buckets: &mut AHashMap<String, MetricRollup>,
+    resolution: MetricResolution,
+    timestamp: i64,
+    metric: &str,
+    label: &str,
+    value: u64,
+    account_id: Option<u64>,
+) {
+    let bucket_start = resolution.bucket_start(timestamp);
+    let id = MetricRollup::id(resolution, bucket_start, metric, label, account_id);
+    buckets
+        .entry(id.clone())
+        .or_insert_with(|| MetricRollup {
+            id,
+            resolution,
+            bucket_start,
+            metric: metric.to_string(),
+            label: label.to_string(),
+            value: 0,
+            account_id,
+        })
+        .merge(value);
+}
+
+/// Writes the buckets, merging them with points already stored for the same bucket by
+/// an earlier run.
+fn store(
+    rw: &transaction::RwTransaction,
+    buckets: AHashMap<String, MetricRollup>,
+) -> RustMailerResult<()> {
+    for (id, mut rollup) in buckets {
+        let existing: Option<MetricRollup> = rw
+            .get()
+            .primary(id)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        if let Some(existing) = existing {
+            rollup.merge(existing.value);
+        }
+        rw.upsert(rollup)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum MetricsExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// A single metric point in an export, at any resolution.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct MetricPoint {
+    /// Time of the point, or start of its day or week (UNIX epoch milliseconds).
+    pub timestamp: i64,
+    pub resolution: MetricResolution,
+    pub metric: String,
+    pub label: String,
+    pub value: u64,
+    /// The account of a per-account point; `None` for global points.
+    pub account_id: Option<u64>,
+}
+
+/// Returns every stored point in `[from, to)`: raw points for the last day and daily or
+/// weekly rollups before that. Points never overlap, since raw points are removed when
+/// they are rolled up.
+pub async fn export_metrics(from: i64, to: i64) -> RustMailerResult<Vec<MetricPoint>> {
+    if from >= to {
+        return Err(raise_error!(
+            "'from' must be earlier than 'to'".into(),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    let raw = DailyMetrics::list_between(from, to).await?;
+    let rollups = MetricRollup::list_between(from, to).await?;
+    let mut points: Vec<MetricPoint> = raw
+        .into_iter()
+        .map(|p| MetricPoint {
+            timestamp: p.created_at,
+            resolution: MetricResolution::Raw,
+            metric: p.metric,
+            label: p.label,
+            value: p.value,
+            account_id: p.account_id,
+        })
+        .chain(rollups.into_iter().map(|r| MetricPoint {
+            timestamp: r.bucket_start,
+            resolution: r.resolution,
+            metric: r.metric,
+            label: r.label,
+            value: r.value,
+            account_id: r.account_id,
+        }))
+        .collect();
+    points.sort_by(|a, b| {
+        (a.timestamp, &a.metric, &a.label).cmp(&(b.timestamp, &b.metric, &b.label))
+    });
+    Ok(points)
+}
+
+pub fn to_csv(points: &[MetricPoint]) -> String {
+    let mut csv = String::from("timestamp,resolution,metric,label,account_id,value\n");
+    for point in points {
+        csv.push_str(&format!(
+            "{},{:?},{},{},{},{}\n",
+            point.timestamp,
+            point.resolution,
+            csv_field(&point.metric),
+            csv_field(&point.label),
+            point
+                .account_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            point.value
+        ));
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::metrics::METRIC_EMAIL_SENT_TOTAL;
+
+    #[test]
+    fn test_bucket_start() {
+        // 2025-01-08T13:45:00Z, a Wednesday.
+        let ts = 1_736_343_900_000;
+        assert_eq!(MetricResolution::Daily.bucket_start(ts), 1_736_294_400_000);
+        // 2025-01-06T00:00:00Z, the Monday before.
+        assert_eq!(MetricResolution::Weekly.bucket_start(ts), 1_736_121_600_000);
+        assert_eq!(MetricResolution::Raw.bucket_start(ts), ts);
+    }
+
+    #[test]
+    fn test_aggregate_sums_counters_and_keeps_gauge_peak() {
+        let mut buckets = AHashMap::new();
+        let day = 1_736_294_400_000;
+        for (offset, value) in [(0, 3), (60_000, 5), (DAY_MS, 7)] {
+            aggregate(
+                &mut buckets,
+                MetricResolution::Daily,
+                day + offset,
+                METRIC_EMAIL_SENT_TOTAL,
+                "success",
+                value,
+                None,
+            );
+            aggregate(
+                &mut buckets,
+                MetricResolution::Daily,
+                day + offset,
+                METRIC_EMAIL_SENT_TOTAL,
+                "success",
+                value,
+                Some(42),
+            );
+            aggregate(
+                &mut buckets,
+                MetricResolution::Daily,
+                day + offset,
+                METRIC_TASK_QUEUE_LENGTH,
+                "email",
+                value,
+                None,
+            );
+        }
+        assert_eq!(buckets.len(), 6);
+        let sent = &buckets[&MetricRollup::id(
+            MetricResolution::Daily,
+            day,
+            METRIC_EMAIL_SENT_TOTAL,
+            "success",
+            None,
+        )];
+        assert_eq!(sent.value, 8);
+        let account_sent = &buckets[&MetricRollup::id(
+            MetricResolution::Daily,
+            day,
+            METRIC_EMAIL_SENT_TOTAL,
+            "success",
+            Some(42),
+        )];
+        assert_eq!(account_sent.value, 8);
+        assert_eq!(account_sent.account_id, Some(42));
+        let queue = &buckets[&MetricRollup::id(
+            MetricResolution::Daily,
+            day,
+            METRIC_TASK_QUEUE_LENGTH,
+            "email",
+            None,
+        )];
+        assert_eq!(queue.value, 5);
+    }
+
+    #[test]
+    fn test_to_csv_escapes_fields() {
+        let csv = to_csv(&[MetricPoint {
+            timestamp: 1,
+            resolution: MetricResolution::Daily,
+            metric: "m".into(),
+            label: "a,\"b\"".into(),
+            value: 2,
+            account_id: Some(7),
+        }]);
+        assert_eq!(
+            csv,
+            "timestamp,resolution,metric,label,account_id,value\n1,Daily,m,\"a,\"\"b\"\"\",7,2\n"
+        );
+    }
+}
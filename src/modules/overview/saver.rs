@@ -3,6 +3,7 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use ahash::AHashMap;
+use prometheus::{core::Collector, IntCounterVec};
 use std::sync::{LazyLock, Mutex};
 
 use crate::{
@@ -10,11 +11,14 @@ use crate::{
         context::RustMailTask,
         error::RustMailerResult,
         metrics::{
-            EMAIL, FAILURE, HOOK, HTTP, METRIC_EMAIL_CLICKS_TOTAL, METRIC_EMAIL_OPENS_TOTAL,
-            METRIC_EMAIL_SENT_BYTES, METRIC_EMAIL_SENT_TOTAL,
+            ACCOUNT_ID_LABEL, EMAIL, FAILURE, HOOK, HTTP, KAFKA, METRIC_EMAIL_CLICKS_TOTAL,
+            METRIC_EMAIL_OPENS_TOTAL, METRIC_EMAIL_SENT_BYTES, METRIC_EMAIL_SENT_TOTAL,
             METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION, METRIC_IMAP_TRAFFIC_TOTAL,
             METRIC_MAIL_FLAG_CHANGE_TOTAL, METRIC_NEW_EMAIL_ARRIVAL_TOTAL,
-            METRIC_TASK_QUEUE_LENGTH, NATS, RECEIVED, RUSTMAILER_EMAIL_CLICKS_TOTAL,
+            METRIC_TASK_QUEUE_LENGTH, NATS, RECEIVED, REDIS, RUSTMAILER_ACCOUNT_EMAIL_CLICKS_TOTAL,
+            RUSTMAILER_ACCOUNT_EMAIL_OPENS_TOTAL, RUSTMAILER_ACCOUNT_EMAIL_SENT_BYTES,
+            RUSTMAILER_ACCOUNT_EMAIL_SENT_TOTAL, RUSTMAILER_ACCOUNT_MAIL_FLAG_CHANGE_TOTAL,
+            RUSTMAILER_ACCOUNT_NEW_EMAIL_ARRIVAL_TOTAL, RUSTMAILER_EMAIL_CLICKS_TOTAL,
             RUSTMAILER_EMAIL_OPENS_TOTAL, RUSTMAILER_EMAIL_SENT_BYTES, RUSTMAILER_EMAIL_SENT_TOTAL,
             RUSTMAILER_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION,
             RUSTMAILER_IMAP_TRAFFIC_TOTAL_BY_METRIC, RUSTMAILER_MAIL_FLAG_CHANGE_TOTAL,
@@ -285,7 +289,150 @@ async fn take_snapshot() -> RustMailerResult<()> {
     )
     .await?;
 
-    Ok(())
+    // Event dispatch success to Kafka
+    let current_event_dispatch_success_kafka =
+        RUSTMAILER_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            .with_label_values(&[SUCCESS, KAFKA])
+            .get();
+    let delta_event_dispatch_success_kafka = METRIC_CACHE.calculate_delta(
+        METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION,
+        &format!("{}_{}", SUCCESS, KAFKA),
+        current_event_dispatch_success_kafka,
+    );
+    DailyMetrics::save(
+        METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION.to_string(),
+        delta_event_dispatch_success_kafka,
+        format!("{}_{}", SUCCESS, KAFKA),
+        now,
+    )
+    .await?;
+
+    // Event dispatch failure to Kafka
+    let current_event_dispatch_failure_kafka =
+        RUSTMAILER_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            .with_label_values(&[FAILURE, KAFKA])
+            .get();
+    let delta_event_dispatch_failure_kafka = METRIC_CACHE.calculate_delta(
+        METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION,
+        &format!("{}_{}", FAILURE, KAFKA),
+        current_event_dispatch_failure_kafka,
+    );
+    DailyMetrics::save(
+        METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION.to_string(),
+        delta_event_dispatch_failure_kafka,
+        format!("{}_{}", FAILURE, KAFKA),
+        now,
+    )
+    .await?;
+
+    // Event dispatch success to Redis
+    let current_event_dispatch_success_redis =
+        RUSTMAILER_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            .with_label_values(&[SUCCESS, REDIS])
+            .get();
+    let delta_event_dispatch_success_redis = METRIC_CACHE.calculate_delta(
+        METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION,
+        &format!("{}_{}", SUCCESS, REDIS),
+        current_event_dispatch_success_redis,
+    );
+    DailyMetrics::save(
+        METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION.to_string(),
+        delta_event_dispatch_success_redis,
+        format!("{}_{}", SUCCESS, REDIS),
+        now,
+    )
+    .await?;
+
+    // Event dispatch failure to Redis
+    let current_event_dispatch_failure_redis =
+        RUSTMAILER_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION
+            .with_label_values(&[FAILURE, REDIS])
+            .get();
+    let delta_event_dispatch_failure_redis = METRIC_CACHE.calculate_delta(
+        METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION,
+        &format!("{}_{}", FAILURE, REDIS),
+        current_event_dispatch_failure_redis,
+    );
+    DailyMetrics::save(
+        METRIC_EVENT_DISPATCH_TOTAL_BY_TYPE_STATUS_AND_DESTINATION.to_string(),
+        delta_event_dispatch_failure_redis,
+        format!("{}_{}", FAILURE, REDIS),
+        now,
+    )
+    .await?;
+
+    take_account_snapshot(now).await
+}
+
+/// Saves the deltas of the per-account counters under the name of the matching global
+/// metric, so that per-account series are read the same way as the global ones. Only
+/// non-zero deltas are saved, since most accounts are idle most of the time.
+async fn take_account_snapshot(now: i64) -> RustMailerResult<()> {
+    let counters: [(&str, &IntCounterVec); 6] = [
+        (
+            METRIC_EMAIL_SENT_TOTAL,
+            &RUSTMAILER_ACCOUNT_EMAIL_SENT_TOTAL,
+        ),
+        (
+            METRIC_EMAIL_SENT_BYTES,
+            &RUSTMAILER_ACCOUNT_EMAIL_SENT_BYTES,
+        ),
+        (
+            METRIC_NEW_EMAIL_ARRIVAL_TOTAL,
+            &RUSTMAILER_ACCOUNT_NEW_EMAIL_ARRIVAL_TOTAL,
+        ),
+        (
+            METRIC_MAIL_FLAG_CHANGE_TOTAL,
+            &RUSTMAILER_ACCOUNT_MAIL_FLAG_CHANGE_TOTAL,
+        ),
+        (
+            METRIC_EMAIL_OPENS_TOTAL,
+            &RUSTMAILER_ACCOUNT_EMAIL_OPENS_TOTAL,
+        ),
+        (
+            METRIC_EMAIL_CLICKS_TOTAL,
+            &RUSTMAILER_ACCOUNT_EMAIL_CLICKS_TOTAL,
+        ),
+    ];
+
+    let mut points = Vec::new();
+    for (metric_name, counter) in counters {
+        for family in counter.collect() {
+            for metric in family.metric.iter() {
+                let mut account_id = None;
+                let mut label = String::new();
+                for pair in metric.label.iter() {
+                    if pair.name() == ACCOUNT_ID_LABEL {
+                        account_id = pair.value().parse::<u64>().ok();
+                    } else {
+                        label = pair.value().to_string();
+                    }
+                }
+                let Some(account_id) = account_id else {
+                    continue;
+                };
+                let delta = METRIC_CACHE.calculate_delta(
+                    metric_name,
+                    &format!("{}@{}", label, account_id),
+                    metric.counter.value() as u64,
+                );
+                if delta > 0 {
+                    points.push(DailyMetrics::new(
+                        metric_name.to_string(),
+                        delta,
+                        label,
+                        now,
+                        Some(account_id),
+                    ));
+                }
+            }
+        }
+    }
+
+    if points.is_empty() {
+        return Ok(());
+    }
+    DailyMetrics::save_batch(points).await
 }
 
 #[cfg(test)]
@@ -5,7 +5,8 @@
 use crate::modules::common::auth::ClientContext;
 use crate::modules::rest::api::ApiTags;
 use crate::modules::rest::ApiResult;
-use crate::modules::token::payload::AccessTokenUpdateRequest;
+use crate::modules::token::lifecycle::{token_created, token_rotated};
+use crate::modules::token::payload::{AccessTokenRotateRequest, AccessTokenUpdateRequest};
 use crate::modules::token::root::set_root_password;
 use crate::modules::{
     token::payload::AccessTokenCreateRequest,
@@ -71,6 +72,7 @@ impl AccessTokenApi {
 
     /// Creates a new access token.
     ///
+    /// The creation is recorded in the audit log and emits the `AccessTokenCreated` event.
     /// Requires root privileges.
     #[oai(
         path = "/access-token",
@@ -84,7 +86,32 @@ impl AccessTokenApi {
         payload: Json<AccessTokenCreateRequest>,
     ) -> ApiResult<PlainText<String>> {
         context.require_root()?;
-        Ok(PlainText(AccessToken::create(payload.0).await?))
+        let token = AccessToken::create(payload.0).await?;
+        token_created(&context, &token).await?;
+        Ok(PlainText(token.token))
+    }
+
+    /// Rotates an access token, returning a new token with the same accounts, scopes and
+    /// settings.
+    ///
+    /// The current token keeps working until the end of the grace period, so that clients
+    /// can switch to the new one. The rotation is recorded in the audit log and emits the
+    /// `AccessTokenRotated` event. Requires root privileges.
+    #[oai(
+        path = "/rotate-access-token",
+        method = "post",
+        operation_id = "rotate_access_token"
+    )]
+    async fn rotate_access_token(
+        &self,
+        context: ClientContext,
+        /// The request payload, including the access token to be rotated.
+        payload: Json<AccessTokenRotateRequest>,
+    ) -> ApiResult<PlainText<String>> {
+        context.require_root()?;
+        let (rotated, replacement) = AccessToken::rotate(payload.0).await?;
+        token_rotated(&context, &rotated, &replacement).await?;
+        Ok(PlainText(replacement.token))
     }
 
     /// Updates an existing access token.
@@ -4,21 +4,30 @@
 
 use std::collections::BTreeSet;
 
+use crate::modules::account::dashboard::AccountDashboard;
+use crate::modules::account::maintenance::{MaintenanceWindow, MaintenanceWindowCreateRequest};
+use crate::modules::account::migration::AccountModel;
 use crate::modules::account::payload::{
     filter_accessible_accounts, AccountCreateRequest, AccountUpdateRequest, MinimalAccount,
 };
+use crate::modules::account::profile::{
+    AccountProfile, AccountProfileApplyRequest, AccountProfileApplyResult,
+    AccountProfileCreateRequest, AccountProfileUpdateRequest, ProfileAccountCreateRequest,
+    ProfileAccountCreateResult,
+};
 use crate::modules::account::status::AccountRunningState;
-use crate::modules::account::migration::AccountModel;
+use crate::modules::account::tags::{
+    has_all_tags, parse_tag_selector, AccountBulkEnableRequest, AccountBulkEnableResult,
+};
 use crate::modules::common::auth::ClientContext;
 use crate::modules::common::paginated::paginate_vec;
 use crate::modules::error::code::ErrorCode;
-use crate::modules::rest::api::ApiTags;
+use crate::modules::rest::api::{parse_if_match, ApiTags};
 use crate::modules::rest::response::DataPage;
 use crate::modules::rest::ApiResult;
-use crate::modules::token::{AccessToken, AccountInfo};
 use crate::raise_error;
 use poem::web::Path;
-use poem_openapi::param::Query;
+use poem_openapi::param::{Header, Query};
 use poem_openapi::payload::Json;
 use poem_openapi::OpenApi;
 
@@ -68,14 +77,8 @@ impl AccountApi {
         payload: Json<AccountCreateRequest>,
         context: ClientContext,
     ) -> ApiResult<Json<AccountModel>> {
-        let account = AccountModel::create_account(payload.0).await?;
-        if let Some(access_token) = &context.access_token {
-            let account_info = AccountInfo {
-                id: account.id,
-                email: account.email.clone(),
-            };
-            AccessToken::grant_account_access(&access_token.token, account_info).await?;
-        }
+        let grant_to = context.access_token.as_ref();
+        let account = AccountModel::create_account(payload.0, grant_to).await?;
         Ok(Json(account))
     }
 
@@ -91,11 +94,16 @@ impl AccountApi {
         account_id: Path<u64>,
         /// Account update request payload
         payload: Json<AccountUpdateRequest>,
+        /// Optional. The `updated_at` value of the account as last read by the client.
+        /// If set and the account has been modified since, the update is rejected with a conflict error.
+        #[oai(name = "If-Match")]
+        if_match: Header<Option<String>>,
         context: ClientContext,
     ) -> ApiResult<()> {
         let account_id = account_id.0;
         context.require_account_access(account_id)?;
-        Ok(AccountModel::update(account_id, payload.0, true).await?)
+        let expected_version = parse_if_match(if_match.0)?;
+        Ok(AccountModel::update(account_id, payload.0, true, expected_version).await?)
     }
 
     /// List accounts with optional pagination parameters
@@ -112,23 +120,31 @@ impl AccountApi {
         page_size: Query<Option<u64>>,
         /// Optional. Whether to sort the list in descending order.
         desc: Query<Option<bool>>,
+        /// Optional. Comma-separated tags; only accounts carrying all of them are listed.
+        tags: Query<Option<String>>,
         context: ClientContext,
     ) -> ApiResult<Json<DataPage<AccountModel>>> {
         let accessible_accounts = context.accessible_accounts()?;
+        let selector = parse_tag_selector(tags.0.as_deref().unwrap_or_default());
 
-        if accessible_accounts.is_none() {
+        if accessible_accounts.is_none() && selector.is_empty() {
             return Ok(Json(
                 AccountModel::paginate_list(page.0, page_size.0, desc.0).await?,
             ));
         }
 
         let all_accounts = AccountModel::list_all().await?;
-        let allowed_ids: BTreeSet<u64> =
-            accessible_accounts.unwrap().iter().map(|a| a.id).collect();
+        let allowed_ids: Option<BTreeSet<u64>> =
+            accessible_accounts.map(|set| set.iter().map(|a| a.id).collect());
 
         let mut filtered_accounts: Vec<AccountModel> = all_accounts
             .into_iter()
-            .filter(|acct| allowed_ids.contains(&acct.id))
+            .filter(|acct| {
+                allowed_ids
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&acct.id))
+            })
+            .filter(|acct| has_all_tags(&acct.tags, &selector))
             .collect();
 
         let sort_desc = desc.0.unwrap_or(true);
@@ -167,6 +183,25 @@ impl AccountApi {
         Ok(Json(state))
     }
 
+    /// Get everything the account page needs in one call: the account, its sync state and
+    /// error streak, the last sync of each folder, its send queue and rate limit usage, and
+    /// its connection pools
+    #[oai(
+        path = "/account-dashboard/:account_id",
+        method = "get",
+        operation_id = "account_dashboard"
+    )]
+    async fn account_dashboard(
+        &self,
+        /// The account ID to get the dashboard for
+        account_id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<AccountDashboard>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(AccountDashboard::get(account_id).await?))
+    }
+
     /// Get a minimal list of active accounts for use in selectors when creating account-related resources
     ///
     /// This endpoint provides a lightweight list of accounts containing only essential information (id and name).
@@ -178,15 +213,238 @@ impl AccountApi {
     )]
     async fn minimal_accounts_list(
         &self,
+        /// Optional. Comma-separated tags; only accounts carrying all of them are listed.
+        tags: Query<Option<String>>,
         context: ClientContext,
     ) -> ApiResult<Json<Vec<MinimalAccount>>> {
         let accessible_accounts = context.accessible_accounts()?;
+        let selector = parse_tag_selector(tags.0.as_deref().unwrap_or_default());
 
-        let minimal_list = AccountModel::minimal_list().await?;
+        let mut minimal_list = AccountModel::minimal_list().await?;
+        minimal_list.retain(|acct| has_all_tags(&acct.tags, &selector));
         let result = match accessible_accounts {
             Some(set) => filter_accessible_accounts(&minimal_list, set),
             None => minimal_list,
         };
         Ok(Json(result))
     }
+
+    /// Pause or resume all accounts carrying the given tags
+    ///
+    /// Only accounts accessible to the caller are affected. Use `dry_run` to preview the
+    /// matching accounts first.
+    #[oai(
+        path = "/accounts/enabled",
+        method = "post",
+        operation_id = "set_accounts_enabled"
+    )]
+    async fn set_accounts_enabled(
+        &self,
+        /// Tag selector and target state
+        payload: Json<AccountBulkEnableRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<AccountBulkEnableResult>> {
+        Ok(Json(payload.0.execute(&context).await?))
+    }
+
+    /// List maintenance windows
+    ///
+    /// With `account_id`, lists the windows of the account together with the global
+    /// windows; without it, lists every window, which requires root permission. Windows
+    /// that already ended are included until deleted.
+    #[oai(
+        path = "/maintenance-windows",
+        method = "get",
+        operation_id = "list_maintenance_windows"
+    )]
+    async fn list_maintenance_windows(
+        &self,
+        /// Optional. The account whose windows are listed.
+        account_id: Query<Option<u64>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<Vec<MaintenanceWindow>>> {
+        MaintenanceWindow::check_access(&context, account_id.0)?;
+        Ok(Json(MaintenanceWindow::list(account_id.0).await?))
+    }
+
+    /// Plan a maintenance window for an account, or for all accounts
+    ///
+    /// While the window is active, sync failures of the covered accounts do not trigger
+    /// `AccountSyncError` or `AccountAuthenticationFailed` events, and their queued emails
+    /// are deferred until the window ends without counting against their retries. Global
+    /// windows require root permission.
+    #[oai(
+        path = "/maintenance-windows",
+        method = "post",
+        operation_id = "create_maintenance_window"
+    )]
+    async fn create_maintenance_window(
+        &self,
+        payload: Json<MaintenanceWindowCreateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<MaintenanceWindow>> {
+        MaintenanceWindow::check_access(&context, payload.0.account_id)?;
+        let window = MaintenanceWindow::new(payload.0).await?;
+        window.clone().save().await?;
+        Ok(Json(window))
+    }
+
+    /// Delete a maintenance window, ending it if it is active
+    ///
+    /// Emails already deferred by the window stay deferred until its planned end.
+    #[oai(
+        path = "/maintenance-windows/:id",
+        method = "delete",
+        operation_id = "remove_maintenance_window"
+    )]
+    async fn remove_maintenance_window(
+        &self,
+        /// The ID of the maintenance window
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        let window = MaintenanceWindow::get(id.0).await?;
+        MaintenanceWindow::check_access(&context, window.account_id)?;
+        Ok(MaintenanceWindow::remove(id.0).await?)
+    }
+
+    /// Create an account profile
+    ///
+    /// A profile captures settings shared by many accounts, such as synced folders, sync
+    /// schedules, cleanup rules and tags, to create accounts from and to re-apply to
+    /// existing accounts. Requires root permission.
+    #[oai(
+        path = "/account-profiles",
+        method = "post",
+        operation_id = "create_account_profile"
+    )]
+    async fn create_account_profile(
+        &self,
+        payload: Json<AccountProfileCreateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<AccountProfile>> {
+        context.require_root()?;
+        let profile = AccountProfile::new(payload.0)?;
+        profile.clone().save().await?;
+        Ok(Json(profile))
+    }
+
+    /// List account profiles with pagination
+    #[oai(
+        path = "/account-profiles",
+        method = "get",
+        operation_id = "list_account_profiles"
+    )]
+    async fn list_account_profiles(
+        &self,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order.
+        desc: Query<Option<bool>>,
+    ) -> ApiResult<Json<DataPage<AccountProfile>>> {
+        Ok(Json(
+            AccountProfile::paginate_list(page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Get an account profile by ID
+    #[oai(
+        path = "/account-profiles/:id",
+        method = "get",
+        operation_id = "get_account_profile"
+    )]
+    async fn get_account_profile(
+        &self,
+        /// The ID of the profile
+        id: Path<u64>,
+    ) -> ApiResult<Json<AccountProfile>> {
+        Ok(Json(AccountProfile::get(id.0).await?))
+    }
+
+    /// Update an account profile
+    ///
+    /// Accounts created from the profile keep their settings until it is re-applied.
+    /// Requires root permission.
+    #[oai(
+        path = "/account-profiles/:id",
+        method = "post",
+        operation_id = "update_account_profile"
+    )]
+    async fn update_account_profile(
+        &self,
+        /// The ID of the profile
+        id: Path<u64>,
+        payload: Json<AccountProfileUpdateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<AccountProfile>> {
+        context.require_root()?;
+        Ok(Json(AccountProfile::update(id.0, payload.0).await?))
+    }
+
+    /// Delete an account profile
+    ///
+    /// Accounts created from the profile are not changed. Requires root permission.
+    #[oai(
+        path = "/account-profiles/:id",
+        method = "delete",
+        operation_id = "remove_account_profile"
+    )]
+    async fn remove_account_profile(
+        &self,
+        /// The ID of the profile
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        context.require_root()?;
+        Ok(AccountProfile::remove(id.0).await?)
+    }
+
+    /// Create a new account from a profile
+    ///
+    /// Only the address, credentials and servers of the account are given; the other
+    /// settings, synced folders and cleanup rules come from the profile. Settings that
+    /// cannot be applied are returned as warnings.
+    #[oai(
+        path = "/account-profiles/:id/create-account",
+        method = "post",
+        operation_id = "create_account_from_profile"
+    )]
+    async fn create_account_from_profile(
+        &self,
+        /// The ID of the profile
+        id: Path<u64>,
+        payload: Json<ProfileAccountCreateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<ProfileAccountCreateResult>> {
+        let profile = AccountProfile::get(id.0).await?;
+        let grant_to = context.access_token.as_ref();
+        Ok(Json(profile.create_account(payload.0, grant_to).await?))
+    }
+
+    /// Re-apply a profile to existing accounts
+    ///
+    /// Overwrites the settings set by the profile, adds its tags and creates its missing
+    /// cleanup rules. Each account is updated on its own; accounts that are not accessible
+    /// or fail to update are reported in `failed`.
+    #[oai(
+        path = "/account-profiles/:id/apply",
+        method = "post",
+        operation_id = "apply_account_profile"
+    )]
+    async fn apply_account_profile(
+        &self,
+        /// The ID of the profile
+        id: Path<u64>,
+        payload: Json<AccountProfileApplyRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<AccountProfileApplyResult>> {
+        let profile = AccountProfile::get(id.0).await?;
+        Ok(Json(
+            profile
+                .apply_to_accounts(payload.0.account_ids, &context)
+                .await?,
+        ))
+    }
 }
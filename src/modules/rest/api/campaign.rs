@@ -0,0 +1,307 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::bounce::classify::BounceStats;
+use crate::modules::common::auth::ClientContext;
+use crate::modules::rest::api::ApiTags;
+use crate::modules::rest::response::DataPage;
+use crate::modules::rest::ApiResult;
+use crate::modules::scheduler::recurring::entity::RecurringSend;
+use crate::modules::scheduler::recurring::payload::{
+    RecurringSendCreateRequest, RecurringSendUpdateRequest,
+};
+use crate::modules::scheduler::recurring::run::run_recurring_send;
+use crate::modules::smtp::campaign::entity::Campaign;
+use crate::modules::smtp::campaign::payload::{CampaignCreateRequest, CampaignProgress};
+use crate::modules::smtp::campaign::send::{
+    campaign_bounce_stats, campaign_progress, cancel_campaign, launch_campaign,
+};
+use crate::modules::smtp::queue::bulk::BulkTaskResult;
+use poem::web::Path;
+use poem_openapi::param::Query;
+use poem_openapi::payload::Json;
+use poem_openapi::OpenApi;
+
+pub struct CampaignApi;
+
+#[OpenApi(prefix_path = "/api/v1", tag = "ApiTags::Campaign")]
+impl CampaignApi {
+    /// Creates a campaign, sending a template-based email to each of its recipients.
+    ///
+    /// Every recipient gets an individual email rendered with its own variables. With
+    /// `messages_per_minute`, the emails are spread over time, sharing the rate with the
+    /// other campaigns of the same account or MTA. Recipients whose email cannot be queued
+    /// are listed in the `failures` of the returned campaign.
+    #[oai(
+        path = "/campaigns/:account_id",
+        method = "post",
+        operation_id = "create_campaign"
+    )]
+    async fn create_campaign(
+        &self,
+        /// The ID of the account sending the campaign
+        account_id: Path<u64>,
+        /// A JSON payload describing the campaign
+        request: Json<CampaignCreateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<Campaign>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(launch_campaign(account_id, request.0).await?))
+    }
+
+    /// Lists the campaigns of an account with pagination.
+    #[oai(
+        path = "/campaigns/:account_id",
+        method = "get",
+        operation_id = "list_campaigns"
+    )]
+    async fn list_campaigns(
+        &self,
+        /// The ID of the account whose campaigns are to be listed
+        account_id: Path<u64>,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<Campaign>>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            Campaign::paginate_list_account(account_id, page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Retrieves a campaign by its ID.
+    #[oai(
+        path = "/campaigns/:account_id/:id",
+        method = "get",
+        operation_id = "get_campaign"
+    )]
+    async fn get_campaign(
+        &self,
+        /// The ID of the account that sent the campaign
+        account_id: Path<u64>,
+        /// The ID of the campaign
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<Campaign>> {
+        context.require_account_access(account_id.0)?;
+        Ok(Json(Campaign::get(account_id.0, id.0).await?))
+    }
+
+    /// Counts the emails of a campaign by task status.
+    #[oai(
+        path = "/campaigns/:account_id/:id/progress",
+        method = "get",
+        operation_id = "get_campaign_progress"
+    )]
+    async fn get_campaign_progress(
+        &self,
+        /// The ID of the account that sent the campaign
+        account_id: Path<u64>,
+        /// The ID of the campaign
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<CampaignProgress>> {
+        context.require_account_access(account_id.0)?;
+        let campaign = Campaign::get(account_id.0, id.0).await?;
+        Ok(Json(campaign_progress(&campaign).await?))
+    }
+
+    /// Counts the bounced recipients of a campaign by bounce category.
+    ///
+    /// Bounces are counted from the delivery status notifications received in the
+    /// sending account and linked to the campaign's emails, so the figures grow as
+    /// notifications arrive. Delayed deliveries count as soft bounces.
+    #[oai(
+        path = "/campaigns/:account_id/:id/bounces",
+        method = "get",
+        operation_id = "get_campaign_bounce_stats"
+    )]
+    async fn get_campaign_bounce_stats(
+        &self,
+        /// The ID of the account that sent the campaign
+        account_id: Path<u64>,
+        /// The ID of the campaign
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<BounceStats>> {
+        context.require_account_access(account_id.0)?;
+        let campaign = Campaign::get(account_id.0, id.0).await?;
+        Ok(Json(campaign_bounce_stats(&campaign).await?))
+    }
+
+    /// Cancels the emails of a campaign that have not been sent yet.
+    #[oai(
+        path = "/campaigns/:account_id/:id/cancel",
+        method = "post",
+        operation_id = "cancel_campaign"
+    )]
+    async fn cancel_campaign(
+        &self,
+        /// The ID of the account that sent the campaign
+        account_id: Path<u64>,
+        /// The ID of the campaign
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<BulkTaskResult>> {
+        context.require_account_access(account_id.0)?;
+        let campaign = Campaign::get(account_id.0, id.0).await?;
+        Ok(Json(cancel_campaign(&context, &campaign).await?))
+    }
+
+    /// Deletes a campaign record.
+    ///
+    /// Emails of the campaign that are already queued are not affected; cancel the
+    /// campaign first to stop them.
+    #[oai(
+        path = "/campaigns/:account_id/:id",
+        method = "delete",
+        operation_id = "remove_campaign"
+    )]
+    async fn remove_campaign(
+        &self,
+        /// The ID of the account that sent the campaign
+        account_id: Path<u64>,
+        /// The ID of the campaign
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        context.require_account_access(account_id.0)?;
+        let campaign = Campaign::get(account_id.0, id.0).await?;
+        Ok(Campaign::remove(campaign.id).await?)
+    }
+
+    /// Creates a recurring send, sending a template to a list of recipients on a cron
+    /// schedule, such as a weekly digest.
+    ///
+    /// Each run is sent as a new campaign of the account.
+    #[oai(
+        path = "/recurring-sends/:account_id",
+        method = "post",
+        operation_id = "create_recurring_send"
+    )]
+    async fn create_recurring_send(
+        &self,
+        /// The ID of the account sending the emails
+        account_id: Path<u64>,
+        /// A JSON payload describing the recurring send
+        request: Json<RecurringSendCreateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<RecurringSend>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let send = RecurringSend::new(account_id, request.0).await?;
+        send.clone().save().await?;
+        Ok(Json(send))
+    }
+
+    /// Lists the recurring sends of an account with pagination.
+    #[oai(
+        path = "/recurring-sends/:account_id",
+        method = "get",
+        operation_id = "list_recurring_sends"
+    )]
+    async fn list_recurring_sends(
+        &self,
+        /// The ID of the account whose recurring sends are to be listed
+        account_id: Path<u64>,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<RecurringSend>>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            RecurringSend::paginate_list_account(account_id, page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Retrieves a recurring send by its ID.
+    #[oai(
+        path = "/recurring-sends/:account_id/:id",
+        method = "get",
+        operation_id = "get_recurring_send"
+    )]
+    async fn get_recurring_send(
+        &self,
+        /// The ID of the account sending the emails
+        account_id: Path<u64>,
+        /// The ID of the recurring send
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<RecurringSend>> {
+        context.require_account_access(account_id.0)?;
+        Ok(Json(RecurringSend::get(account_id.0, id.0).await?))
+    }
+
+    /// Updates a recurring send. Its next run is computed again from the current time.
+    #[oai(
+        path = "/recurring-sends/:account_id/:id",
+        method = "post",
+        operation_id = "update_recurring_send"
+    )]
+    async fn update_recurring_send(
+        &self,
+        /// The ID of the account sending the emails
+        account_id: Path<u64>,
+        /// The ID of the recurring send
+        id: Path<u64>,
+        /// The fields to change
+        request: Json<RecurringSendUpdateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<RecurringSend>> {
+        context.require_account_access(account_id.0)?;
+        Ok(Json(
+            RecurringSend::update(account_id.0, id.0, request.0).await?,
+        ))
+    }
+
+    /// Runs a recurring send now, outside of its schedule, and returns the campaign of
+    /// the run. The schedule is not changed.
+    #[oai(
+        path = "/recurring-sends/:account_id/:id/run",
+        method = "post",
+        operation_id = "run_recurring_send"
+    )]
+    async fn run_recurring_send(
+        &self,
+        /// The ID of the account sending the emails
+        account_id: Path<u64>,
+        /// The ID of the recurring send
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<Campaign>> {
+        context.require_account_access(account_id.0)?;
+        let send = RecurringSend::get(account_id.0, id.0).await?;
+        Ok(Json(run_recurring_send(&send).await?))
+    }
+
+    /// Deletes a recurring send. Campaigns of its past runs are kept.
+    #[oai(
+        path = "/recurring-sends/:account_id/:id",
+        method = "delete",
+        operation_id = "remove_recurring_send"
+    )]
+    async fn remove_recurring_send(
+        &self,
+        /// The ID of the account sending the emails
+        account_id: Path<u64>,
+        /// The ID of the recurring send
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        context.require_account_access(account_id.0)?;
+        let send = RecurringSend::get(account_id.0, id.0).await?;
+        Ok(RecurringSend::remove(send.id).await?)
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::common::auth::ClientContext;
+use crate::modules::rest::api::ApiTags;
+use crate::modules::rest::response::DataPage;
+use crate::modules::rest::ApiResult;
+use crate::modules::tasks::dead_letter::{
+    DeadLetter, DeadLetterDetail, DeadLetterFilter, DeadLetterKind, DeadLetterPurgeResult,
+    DeadLetterRequeueResult,
+};
+use poem::web::Path;
+use poem_openapi::param::Query;
+use poem_openapi::payload::Json;
+use poem_openapi::OpenApi;
+
+pub struct DeadLetterApi;
+
+#[OpenApi(prefix_path = "/api/v1", tag = "ApiTags::DeadLetter")]
+impl DeadLetterApi {
+    /// Lists the email and event hook tasks that failed permanently after exhausting
+    /// their retries, most recent failures first.
+    ///
+    /// Access tokens only see the dead letters of the accounts they can access.
+    #[oai(
+        path = "/dead-letters",
+        method = "get",
+        operation_id = "list_dead_letters"
+    )]
+    async fn list_dead_letters(
+        &self,
+        /// Optional. Only lists dead letters of this kind.
+        kind: Query<Option<DeadLetterKind>>,
+        /// Optional. Only lists dead letters of this account.
+        account_id: Query<Option<u64>>,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order. Defaults to `true`.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<DeadLetter>>> {
+        let filter = DeadLetterFilter {
+            kind: kind.0,
+            account_ids: account_id.0.map(|id| vec![id]),
+            failed_before: None,
+        };
+        Ok(Json(
+            DeadLetter::paginate_list(&context, &filter, page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Retrieves a dead letter, including the details of its failed task.
+    #[oai(
+        path = "/dead-letters/:id",
+        method = "get",
+        operation_id = "get_dead_letter"
+    )]
+    async fn get_dead_letter(
+        &self,
+        /// The ID of the failed task
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DeadLetterDetail>> {
+        let letter = DeadLetter::get(id.0).await?;
+        context.require_account_access(letter.account_id)?;
+        Ok(Json(letter.detail()?))
+    }
+
+    /// Queues the task of a dead letter again and removes the dead letter.
+    ///
+    /// The task runs as a new task, with a new ID and a fresh retry count.
+    #[oai(
+        path = "/dead-letters/:id/requeue",
+        method = "post",
+        operation_id = "requeue_dead_letter"
+    )]
+    async fn requeue_dead_letter(
+        &self,
+        /// The ID of the failed task
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DeadLetterRequeueResult>> {
+        let letter = DeadLetter::get(id.0).await?;
+        context.require_account_access(letter.account_id)?;
+        Ok(Json(DeadLetter::requeue(letter.id).await?))
+    }
+
+    /// Deletes a dead letter without running its task again.
+    #[oai(
+        path = "/dead-letters/:id",
+        method = "delete",
+        operation_id = "remove_dead_letter"
+    )]
+    async fn remove_dead_letter(
+        &self,
+        /// The ID of the failed task
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        let letter = DeadLetter::get(id.0).await?;
+        context.require_account_access(letter.account_id)?;
+        Ok(DeadLetter::remove(letter.id).await?)
+    }
+
+    /// Deletes all dead letters matching the filter.
+    ///
+    /// An empty filter purges every dead letter the caller can access.
+    #[oai(
+        path = "/dead-letters/purge",
+        method = "post",
+        operation_id = "purge_dead_letters"
+    )]
+    async fn purge_dead_letters(
+        &self,
+        /// A JSON payload selecting the dead letters to delete
+        filter: Json<DeadLetterFilter>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DeadLetterPurgeResult>> {
+        Ok(Json(DeadLetter::purge(&context, &filter.0).await?))
+    }
+}
@@ -7,21 +7,29 @@ use std::collections::BTreeSet;
 use crate::modules::common::auth::ClientContext;
 use crate::modules::common::paginated::paginate_vec;
 use crate::modules::error::code::ErrorCode;
+use crate::modules::hook::delivery::{HookDelivery, ReplayedDelivery};
 use crate::modules::hook::entity::EventHooks;
 use crate::modules::hook::events::EVENT_EXAMPLES;
-use crate::modules::hook::payload::{EventhookCreateRequest, EventhookUpdateRequest};
+use crate::modules::hook::payload::{
+    EventhookCreateRequest, EventhookUpdateRequest, RotateHookSecretRequest, RotatedHookSecret,
+};
+use crate::modules::hook::simulate::{simulate_event, EventSimulationRequest, SimulatedEvent};
 use crate::modules::hook::task::SendEventHookTask;
-use crate::modules::hook::vrl::payload::{ResolveResult, VrlScriptTestRequest};
-use crate::modules::hook::vrl::resolve_vrl_input;
+use crate::modules::hook::vrl::payload::{
+    ResolveResult, VrlSandboxRequest, VrlSandboxResult, VrlScriptTestRequest,
+};
+use crate::modules::hook::vrl::{resolve_vrl_input, run_vrl_sandbox};
 use crate::modules::rest::api::ApiTags;
 use crate::modules::rest::response::DataPage;
 use crate::modules::rest::ApiResult;
 use crate::modules::scheduler::model::TaskStatus;
+use crate::modules::tasks::export::{TaskExport, TaskExportKind};
 use crate::modules::tasks::queue::RustMailerTaskQueue;
-use crate::raise_error;
+use crate::{raise_error, utc_now};
 use poem::web::Path;
+use poem::Body;
 use poem_openapi::param::Query;
-use poem_openapi::payload::Json;
+use poem_openapi::payload::{Attachment, AttachmentType, Json};
 use poem_openapi::OpenApi;
 pub struct EventHookApi;
 
@@ -50,7 +58,7 @@ impl EventHookApi {
                 context.require_account_access(account_id)?;
             }
             None => {
-                context.require_root()?;
+                context.require_workspace_admin(hook.workspace_id)?;
             }
         }
         Ok(Json(hook))
@@ -80,7 +88,7 @@ impl EventHookApi {
                 context.require_account_access(account_id)?;
             }
             None => {
-                context.require_root()?;
+                context.require_workspace_admin(hook.workspace_id)?;
             }
         }
 
@@ -99,13 +107,14 @@ impl EventHookApi {
         payload: Json<EventhookCreateRequest>,
         context: ClientContext,
     ) -> ApiResult<Json<EventHooks>> {
-        let payload = payload.0;
+        let mut payload = payload.0;
         match payload.account_id {
             Some(account_id) => {
                 context.require_account_access(account_id)?;
             }
             None => {
-                context.require_root()?;
+                payload.workspace_id = payload.workspace_id.or(context.workspace_id());
+                context.require_workspace_admin(payload.workspace_id)?;
             }
         }
 
@@ -140,12 +149,148 @@ impl EventHookApi {
                 context.require_account_access(account_id)?;
             }
             None => {
-                context.require_root()?;
+                context.require_workspace_admin(hook.workspace_id)?;
             }
         }
         Ok(EventHooks::update(id, payload.0).await?)
     }
 
+    /// Rotate the signing secret of an event hook
+    ///
+    /// Generates a new secret used to sign the delivered payloads (`X-RustMailer-Signature`).
+    /// The current secret keeps signing payloads alongside the new one for the requested
+    /// overlap, so receivers can switch keys without rejecting deliveries. The new secret is
+    /// only returned in this response.
+    #[oai(
+        path = "/event-hook/:id/rotate-secret",
+        method = "post",
+        operation_id = "rotate_event_hook_secret"
+    )]
+    async fn rotate_event_hook_secret(
+        &self,
+        ///Request Body
+        payload: Json<RotateHookSecretRequest>,
+        ///The event hook identifier
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<RotatedHookSecret>> {
+        let id = id.0;
+        let hook = EventHooks::get_by_id(id).await?.ok_or_else(|| {
+            raise_error!(
+                format!("Failed to retrieve webhook record. id: {id}."),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+        match hook.account_id {
+            Some(account_id) => {
+                context.require_account_access(account_id)?;
+            }
+            None => {
+                context.require_workspace_admin(hook.workspace_id)?;
+            }
+        }
+        Ok(Json(EventHooks::rotate_secret(id, payload.0).await?))
+    }
+
+    /// Send a simulated event to an event hook
+    ///
+    /// Synthesizes an event of the requested type from the event examples, with the optional
+    /// overrides applied to its payload, and delivers it through the same pipeline as real
+    /// events (VRL script, signing and retries), even if the hook is disabled or does not
+    /// watch that event type. Simulated events carry `"test": true` and their deliveries the
+    /// `X-RustMailer-Test` header. Follow the delivery with the returned hook task ID.
+    #[oai(
+        path = "/event-hook/:id/simulate",
+        method = "post",
+        operation_id = "simulate_event_hook_event"
+    )]
+    async fn simulate_event_hook_event(
+        &self,
+        ///Request Body
+        payload: Json<EventSimulationRequest>,
+        ///The event hook identifier
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<SimulatedEvent>> {
+        let id = id.0;
+        let hook = EventHooks::get_by_id(id).await?.ok_or_else(|| {
+            raise_error!(
+                format!("Failed to retrieve webhook record. id: {id}."),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+        match hook.account_id {
+            Some(account_id) => {
+                context.require_account_access(account_id)?;
+            }
+            None => {
+                context.require_workspace_admin(hook.workspace_id)?;
+            }
+        }
+        Ok(Json(simulate_event(&hook, payload.0).await?))
+    }
+
+    /// List the deliveries of an event hook
+    ///
+    /// Each dispatch of an event to the hook is recorded with its outcome, attempt number,
+    /// latency and, for HTTP hooks, the status code and the beginning of the response body.
+    /// Deliveries are kept for `rustmailer_hook_delivery_log_retention_days` days.
+    #[oai(
+        path = "/event-hook/:id/deliveries",
+        method = "get",
+        operation_id = "list_event_hook_deliveries"
+    )]
+    async fn list_event_hook_deliveries(
+        &self,
+        ///The event hook identifier
+        id: Path<u64>,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<HookDelivery>>> {
+        Ok(Json(
+            HookDelivery::list(&context, id.0, page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Get an event hook delivery
+    #[oai(
+        path = "/event-hook-delivery/:id",
+        method = "get",
+        operation_id = "get_event_hook_delivery"
+    )]
+    async fn get_event_hook_delivery(
+        &self,
+        ///The delivery identifier
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<HookDelivery>> {
+        Ok(Json(HookDelivery::get(&context, id.0).await?))
+    }
+
+    /// Replay an event hook delivery
+    ///
+    /// Queues the event of the delivery again for delivery to its hook, with the current
+    /// configuration of the hook. The replayed deliveries reference the original one in
+    /// `replay_of`. Follow the delivery with the returned hook task ID.
+    #[oai(
+        path = "/event-hook-delivery/:id/replay",
+        method = "post",
+        operation_id = "replay_event_hook_delivery"
+    )]
+    async fn replay_event_hook_delivery(
+        &self,
+        ///The delivery identifier
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<ReplayedDelivery>> {
+        Ok(Json(HookDelivery::replay(&context, id.0).await?))
+    }
+
     /// List event hooks (root)
     ///
     /// Requires root privileges.
@@ -194,6 +339,61 @@ impl EventHookApi {
         Ok(Json(resolve_vrl_input(request.0).await?))
     }
 
+    /// Try a VRL script in a sandbox
+    ///
+    /// Runs the script against the event examples, or those of the requested event types,
+    /// and against the optional sample event, the way hooks run it before delivering events.
+    /// Returns the transformed events and errors without saving anything, so scripts can be
+    /// iterated on before being set on a hook. Events turned into `null` are reported as
+    /// dropped: a hook does not deliver them.
+    #[oai(
+        path = "/vrl-script-sandbox",
+        method = "post",
+        operation_id = "vrl_script_sandbox"
+    )]
+    async fn vrl_script_sandbox(
+        &self,
+        /// JSON body containing the script and the optional sample event.
+        request: Json<VrlSandboxRequest>,
+    ) -> ApiResult<Json<VrlSandboxResult>> {
+        Ok(Json(run_vrl_sandbox(request.0).await?))
+    }
+
+    /// Export hook task history as newline-delimited JSON, oldest first.
+    ///
+    /// Supports the same filters and field selection as `/send-email-tasks/export`.
+    #[oai(
+        path = "/hook-tasks/export",
+        method = "get",
+        operation_id = "export_hook_tasks"
+    )]
+    async fn export_hook_tasks(
+        &self,
+        /// Optional. Only tasks created at or after this time (Unix epoch milliseconds).
+        created_after: Query<Option<i64>>,
+        /// Optional. Only tasks created before this time (Unix epoch milliseconds).
+        created_before: Query<Option<i64>>,
+        /// Optional. Only tasks with this status.
+        status: Query<Option<TaskStatus>>,
+        /// Optional. Comma-separated list of fields to include, e.g. `id,status,created_at`.
+        /// All fields are included if omitted.
+        fields: Query<Option<String>>,
+        context: ClientContext,
+    ) -> ApiResult<Attachment<Body>> {
+        let export = TaskExport::new(
+            &context,
+            TaskExportKind::Hook,
+            created_after.0,
+            created_before.0,
+            status.0,
+            fields.0.as_deref(),
+        )?;
+        let body = Body::from_bytes_stream(export.into_stream());
+        Ok(Attachment::new(body)
+            .attachment_type(AttachmentType::Attachment)
+            .filename(TaskExportKind::Hook.filename(utc_now!())))
+    }
+
     /// List hook tasks
     #[oai(path = "/hook-tasks", method = "get", operation_id = "list_hook_tasks")]
     async fn list_hook_tasks(
@@ -2,13 +2,20 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::audit::AuditEntry;
+use crate::modules::cache::imap::integrity::{check_envelope_integrity, IntegrityReport};
 use crate::modules::cache::imap::mailbox::MailBox;
 use crate::modules::common::auth::ClientContext;
+use crate::modules::imap::console::{
+    run_console_command, ImapConsoleRequest, ImapConsoleResponse,
+};
 use crate::modules::mailbox::create::{create_mailbox, CreateMailboxRequest};
 use crate::modules::mailbox::delete::delete_mailbox;
 use crate::modules::mailbox::list::{get_account_mailboxes, list_subscribed_mailboxes};
 use crate::modules::mailbox::rename::{update_mailbox, MailboxUpdateRequest};
+use crate::modules::mailbox::resync::resync_mailbox;
 use crate::modules::mailbox::subscribe::{subscribe_mailbox, unsubscribe_mailbox};
+use crate::modules::mailbox::usage::{storage_usage, StorageUsageReport};
 use crate::modules::rest::api::ApiTags;
 use crate::modules::rest::ApiResult;
 use poem::web::Path;
@@ -181,4 +188,132 @@ impl MailBoxApi {
         context.require_account_access(account_id)?;
         Ok(update_mailbox(account_id, payload.0).await?)
     }
+
+    /// Forces a full resynchronization of a single cached mailbox.
+    ///
+    /// The locally cached envelopes of the given mailbox (IMAP folder, Gmail label
+    /// or Graph API folder) are discarded and fetched again from the server. Other
+    /// mailboxes of the account are not touched, which makes this much cheaper than
+    /// rebuilding the whole account cache when only one folder is out of sync
+    /// (e.g. after local corruption or unexpected UIDVALIDITY behaviour).
+    ///
+    /// The request returns once the mailbox has been validated; the resync itself
+    /// runs in the background. Failures are recorded in the account running state.
+    #[oai(
+        path = "/resync-mailbox/:account_id",
+        method = "post",
+        operation_id = "resync_mailbox"
+    )]
+    async fn resync_mailbox(
+        &self,
+        /// The unique identifier of the account.
+        account_id: Path<u64>,
+        /// The name of the cached mailbox to resync.
+        mailbox_name: PlainText<String>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(resync_mailbox(account_id, &mailbox_name).await?)
+    }
+
+    /// Verifies the local envelope cache of an IMAP account against the server.
+    ///
+    /// For every synchronized mailbox, the locally cached UIDs are compared with the
+    /// UIDs returned by a server-side search (respecting the account's `date_since`
+    /// and `folder_limit` settings). The report lists missing and orphaned UIDs and
+    /// UIDVALIDITY mismatches.
+    ///
+    /// When `repair` is `true`, missing envelopes are fetched and orphaned envelopes
+    /// are removed. Mailboxes with a UIDVALIDITY mismatch are only reported; use
+    /// `resync-mailbox` to rebuild them.
+    ///
+    /// This operation is only applicable to IMAP/SMTP accounts.
+    #[oai(
+        path = "/verify-envelope-cache/:account_id",
+        method = "post",
+        operation_id = "verify_envelope_cache"
+    )]
+    async fn verify_envelope_cache(
+        &self,
+        /// The unique identifier of the account.
+        account_id: Path<u64>,
+        /// Whether detected discrepancies should be repaired. Defaults to `false`.
+        repair: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<IntegrityReport>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let repair = repair.0.unwrap_or(false);
+        Ok(Json(check_envelope_integrity(account_id, repair).await?))
+    }
+
+    /// Reports the storage used by an IMAP account, per mailbox and per sender.
+    ///
+    /// Usage is computed from the sizes of the cached envelopes, so only messages within
+    /// the account's sync window are counted. The report lists the senders using the most
+    /// storage and suggests cleanups, such as emptying a large trash mailbox.
+    ///
+    /// When `refresh` is `true`, the message count of every mailbox is fetched live from
+    /// the server with `STATUS`, along with its size on servers supporting `STATUS=SIZE`.
+    ///
+    /// This operation is only applicable to IMAP/SMTP accounts without minimal sync.
+    #[oai(
+        path = "/storage-usage/:account_id",
+        method = "get",
+        operation_id = "get_storage_usage"
+    )]
+    async fn get_storage_usage(
+        &self,
+        /// The unique identifier of the account.
+        account_id: Path<u64>,
+        /// Optional. Number of top senders to report (1-100). Defaults to 10.
+        top: Query<Option<usize>>,
+        /// Optional. Whether to refresh mailbox counts from the server. Defaults to `false`.
+        refresh: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<StorageUsageReport>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            storage_usage(account_id, top.0, refresh.0.unwrap_or(false)).await?,
+        ))
+    }
+
+    /// Executes a raw IMAP command on a pooled connection of the account. Requires root permission.
+    ///
+    /// Intended for diagnosing protocol quirks of a server. Only read-only commands are
+    /// accepted: `CAPABILITY`, `STATUS`, `EXAMINE` and `UID SEARCH`. The raw server
+    /// response is returned as-is. Every invocation is recorded in the audit log.
+    ///
+    /// This operation is only applicable to IMAP/SMTP accounts.
+    #[oai(
+        path = "/imap-console/:account_id",
+        method = "post",
+        operation_id = "run_imap_console_command"
+    )]
+    async fn run_imap_console_command(
+        &self,
+        /// The unique identifier of the account.
+        account_id: Path<u64>,
+        request: Json<ImapConsoleRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<ImapConsoleResponse>> {
+        context.require_root()?;
+        let account_id = account_id.0;
+        let result = run_console_command(account_id, &request.0).await;
+        AuditEntry::new(
+            &context,
+            "imap_console",
+            Some(account_id),
+            match &result {
+                Ok(response) => response.command.clone(),
+                Err(_) => format!("{:?}", request.0),
+            },
+            result.is_ok(),
+        )
+        .save()
+        .await?;
+        Ok(Json(result?))
+    }
 }
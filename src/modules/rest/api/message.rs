@@ -3,20 +3,27 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::current_datetime;
-use crate::modules::cache::model::Envelope;
+use crate::modules::cache::model::{Envelope, EnvelopeSortField};
 use crate::modules::common::auth::ClientContext;
+use crate::modules::common::importance::Importance;
 use crate::modules::message::append::{AppendReplyToDraftRequest, ReplyDraft};
-use crate::modules::message::attachment::{retrieve_email_attachment, AttachmentRequest};
+use crate::modules::message::attachment::{
+    read_range, retrieve_email_attachment, AttachmentRequest, ByteRange,
+};
 use crate::modules::message::content::{
     retrieve_email_content, FullMessageContent, MessageContentRequest,
 };
+use crate::modules::message::conversation::{get_conversation, ThreadScope};
 use crate::modules::message::delete::{move_to_trash, MessageDeleteRequest};
 use crate::modules::message::flag::{modify_flags, FlagMessageRequest};
 use crate::modules::message::full::retrieve_raw_email;
-use crate::modules::message::list::{
-    get_thread_messages, list_messages_in_mailbox, list_threads_in_mailbox,
+use crate::modules::message::headers::{retrieve_raw_headers, RawHeaders};
+use crate::modules::message::list::{list_messages_in_mailbox, list_threads_in_mailbox};
+use crate::modules::message::search::payload::{
+    MessageSearchRequest, SavedSearchCreateRequest, SavedSearchUpdateRequest, UnifiedSearchRequest,
 };
-use crate::modules::message::search::payload::{MessageSearchRequest, UnifiedSearchRequest};
+use crate::modules::message::search::query::parse_unified_search;
+use crate::modules::message::search::saved::SavedSearch;
 use crate::modules::message::tags::tag_messages_impl;
 use crate::modules::message::tags::BatchTagRequest;
 use crate::modules::message::transfer::{
@@ -27,12 +34,27 @@ use crate::modules::rest::response::{CursorDataPage, DataPage};
 use crate::modules::rest::ApiResult;
 use poem::web::Path;
 use poem::Body;
-use poem_openapi::param::Query;
+use poem_openapi::param::{Header, Query};
 use poem_openapi::payload::{Attachment, AttachmentType, Json};
-use poem_openapi::OpenApi;
+use poem_openapi::{ApiResponse, OpenApi};
 
 pub struct MessageApi;
 
+/// An attachment, or the requested byte range of it.
+#[derive(ApiResponse)]
+pub enum AttachmentResponse {
+    /// The whole attachment.
+    #[oai(status = 200)]
+    Full(Attachment<Body>, #[oai(header = "Accept-Ranges")] String),
+    /// The requested byte range of the attachment.
+    #[oai(status = 206)]
+    Partial(
+        Attachment<Body>,
+        #[oai(header = "Content-Range")] String,
+        #[oai(header = "Accept-Ranges")] String,
+    ),
+}
+
 #[OpenApi(prefix_path = "/api/v1", tag = "ApiTags::Message")]
 impl MessageApi {
     /// Moves messages from one mailbox to another for the specified account.
@@ -163,6 +185,17 @@ impl MessageApi {
         page_size: Query<u64>,
         /// lists messages in descending order; otherwise, ascending. internal date
         desc: Query<Option<bool>>,
+        /// Only lists messages of this importance. Messages without a priority header count as `Normal`.
+        /// Only supported for locally cached IMAP mailboxes.
+        importance: Query<Option<Importance>>,
+        /// Lists the most important messages first, then by internal date.
+        /// Only supported for locally cached IMAP mailboxes.
+        sort_by_importance: Query<Option<bool>>,
+        /// The field to order messages by. Defaults to `InternalDate`.
+        /// Other orders are only supported for locally cached IMAP mailboxes and cannot be
+        /// combined with `importance` or `sort_by_importance`. `InternalDate`, `Date` and
+        /// `Size` are index-backed; `From` and `Subject` sort the whole mailbox in memory.
+        sort_by: Query<Option<EnvelopeSortField>>,
         context: ClientContext,
     ) -> ApiResult<Json<CursorDataPage<Envelope>>> {
         let remote = remote.0.unwrap_or(false);
@@ -178,6 +211,9 @@ impl MessageApi {
                 page_size.0,
                 remote,
                 desc,
+                importance.0,
+                sort_by_importance.0.unwrap_or(false),
+                sort_by.0,
             )
             .await?,
         ))
@@ -216,6 +252,11 @@ impl MessageApi {
     }
 
     /// Get thread's envelopes in a specified mailbox for the given account.
+    ///
+    /// With `scope` set to `Account` or `AllAccounts`, messages of other mailboxes, or of
+    /// other accessible accounts, linked to the thread by `Message-ID`, `In-Reply-To` or
+    /// `References` are included, so a conversation spanning INBOX and Sent is returned as
+    /// one thread.
     #[oai(
         path = "/get-thread-messages/:account_id",
         method = "get",
@@ -227,12 +268,17 @@ impl MessageApi {
         account_id: Path<u64>,
         // Thread ID
         thread_id: Query<u64>,
+        /// Optional. Which messages make up the thread. Defaults to `Thread`.
+        scope: Query<Option<ThreadScope>>,
         context: ClientContext,
     ) -> ApiResult<Json<Vec<Envelope>>> {
         let account_id = account_id.0;
         context.require_account_access(account_id)?;
 
-        Ok(Json(get_thread_messages(account_id, thread_id.0).await?))
+        let scope = scope.0.unwrap_or_default();
+        Ok(Json(
+            get_conversation(&context, account_id, thread_id.0, scope).await?,
+        ))
     }
 
     /// Fetches the content of a specific email for the given account.
@@ -257,6 +303,10 @@ impl MessageApi {
     }
 
     /// Fetches an attachment from a specific email for the given account.
+    ///
+    /// A byte range can be requested with the `Range` header or the `offset` and `length`
+    /// fields of the payload, to resume an interrupted download or preview the start of a
+    /// large attachment. Ranged responses have status 206 and a `Content-Range` header.
     #[oai(
         path = "/message-attachment/:account_id",
         method = "post",
@@ -268,18 +318,30 @@ impl MessageApi {
         account_id: Path<u64>,
         /// specifying the mailbox, message, and attachment to fetch.
         payload: Json<AttachmentRequest>,
+        /// Optional. A single byte range with a start offset, e.g. `bytes=1024-` or
+        /// `bytes=0-65535`. Takes precedence over `offset` and `length` of the payload.
+        #[oai(name = "Range")]
+        range: Header<Option<String>>,
         context: ClientContext,
-    ) -> ApiResult<Attachment<Body>> {
-        let request = payload.0;
+    ) -> ApiResult<AttachmentResponse> {
+        let mut request = payload.0;
         let account_id = account_id.0;
         context.require_account_access(account_id)?;
-        let (reader, filename) = retrieve_email_attachment(account_id, request).await?;
+        if let Some(range) = range.0.as_deref() {
+            let range = ByteRange::parse_header(range)?;
+            request.offset = Some(range.offset);
+            request.length = range.length;
+        }
+        let (reader, filename, range) = retrieve_email_attachment(account_id, request).await?;
         let body = Body::from_async_read(reader);
         let mut attachment = Attachment::new(body).attachment_type(AttachmentType::Attachment);
         if let Some(filename) = filename {
             attachment = attachment.filename(filename);
         }
-        Ok(attachment)
+        Ok(match range {
+            Some(range) => AttachmentResponse::Partial(attachment, range.header(), "bytes".into()),
+            None => AttachmentResponse::Full(attachment, "bytes".into()),
+        })
     }
 
     /// Fetches the full content of a specific email for the given account.
@@ -303,20 +365,62 @@ impl MessageApi {
         id: Query<String>,
         /// An optional filename for the attachment (defaults to a timestamped `.elm` file).
         filename: Query<Option<String>>,
+        /// Optional. A single byte range with a start offset, e.g. `bytes=1024-`, to resume
+        /// an interrupted download. Ranged responses have status 206.
+        #[oai(name = "Range")]
+        range: Header<Option<String>>,
         context: ClientContext,
-    ) -> ApiResult<Attachment<Body>> {
+    ) -> ApiResult<AttachmentResponse> {
         let account_id = account_id.0;
         context.require_account_access(account_id)?;
         let filename = filename.0.unwrap_or(format!("{}.elm", current_datetime!()));
         let mailbox_opt = mailbox.0.as_ref().map(|m| m.trim().to_owned());
         let id = id.0.trim();
+        let range = range
+            .0
+            .as_deref()
+            .map(ByteRange::parse_header)
+            .transpose()?;
 
         let reader = retrieve_raw_email(account_id, mailbox_opt.as_deref(), id).await?;
+        let (reader, range) = read_range(reader, range).await?;
         let body = Body::from_async_read(reader);
         let attachment = Attachment::new(body)
             .attachment_type(AttachmentType::Attachment)
             .filename(filename);
-        Ok(attachment)
+        Ok(match range {
+            Some(range) => AttachmentResponse::Partial(attachment, range.header(), "bytes".into()),
+            None => AttachmentResponse::Full(attachment, "bytes".into()),
+        })
+    }
+
+    /// Fetches the raw headers of an email without parsing them.
+    ///
+    /// Use this for emails whose envelope carries a `parse_error`: the header bytes kept
+    /// with the partially parsed envelope are returned as is.
+    #[oai(
+        path = "/raw-headers/:account_id",
+        method = "get",
+        operation_id = "fetch_raw_headers"
+    )]
+    async fn fetch_raw_headers(
+        &self,
+        /// The ID of the account owning the mailbox.
+        account_id: Path<u64>,
+        /// The decoded, human-readable name of the mailbox containing the email (e.g., "INBOX").
+        /// Required for IMAP accounts.
+        mailbox: Query<Option<String>>,
+        /// The unique ID of the message: the IMAP UID as a string, or the message ID of
+        /// Gmail API, Graph API and JMAP accounts.
+        id: Query<String>,
+        context: ClientContext,
+    ) -> ApiResult<Json<RawHeaders>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let mailbox = mailbox.0.as_ref().map(|m| m.trim().to_owned());
+        Ok(Json(
+            retrieve_raw_headers(account_id, mailbox.as_deref(), id.0.trim()).await?,
+        ))
     }
 
     /// Searches for messages in mailboxes for the specified account. performs the search on the IMAP server;
@@ -376,27 +480,222 @@ impl MessageApi {
     ) -> ApiResult<Json<DataPage<Envelope>>> {
         let mut request = payload.0;
         let desc = desc.0.unwrap_or(false);
-
-        // Ensure account access control
-        match &mut request.accounts {
-            Some(accounts) => {
-                for &account_id in accounts.iter() {
-                    context.require_account_access(account_id)?;
-                }
-            }
-            None => {
-                if !context.is_root {
-                    // Inject accessible accounts if not specified
-                    if let Some(accessible) = context.accessible_accounts()? {
-                        let account_ids = accessible.iter().map(|a| a.id).collect::<Vec<u64>>();
-                        request.accounts = Some(account_ids);
-                    }
-                }
-            }
-        }
+        request.restrict_to(&context)?;
         Ok(Json(request.search(page.0, page_size.0, desc).await?))
     }
 
+    /// Saves a unified search of an account under a name, to run it later by ID, e.g. as a
+    /// smart folder.
+    #[oai(
+        path = "/saved-searches/:account_id",
+        method = "post",
+        operation_id = "create_saved_search"
+    )]
+    async fn create_saved_search(
+        &self,
+        /// The ID of the account the search belongs to.
+        account_id: Path<u64>,
+        request: Json<SavedSearchCreateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<SavedSearch>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let saved = SavedSearch::new(account_id, request.0)?;
+        saved.clone().save().await?;
+        Ok(Json(saved))
+    }
+
+    /// Lists the saved searches of an account.
+    #[oai(
+        path = "/saved-searches/:account_id",
+        method = "get",
+        operation_id = "list_saved_searches"
+    )]
+    async fn list_saved_searches(
+        &self,
+        /// The ID of the account whose saved searches are listed.
+        account_id: Path<u64>,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<SavedSearch>>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            SavedSearch::paginate_list(account_id, page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Retrieves a saved search.
+    #[oai(
+        path = "/saved-searches/:account_id/:id",
+        method = "get",
+        operation_id = "get_saved_search"
+    )]
+    async fn get_saved_search(
+        &self,
+        /// The ID of the account the search belongs to.
+        account_id: Path<u64>,
+        /// The ID of the saved search.
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<SavedSearch>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(SavedSearch::get(account_id, id.0).await?))
+    }
+
+    /// Updates a saved search.
+    #[oai(
+        path = "/saved-searches/:account_id/:id",
+        method = "post",
+        operation_id = "update_saved_search"
+    )]
+    async fn update_saved_search(
+        &self,
+        /// The ID of the account the search belongs to.
+        account_id: Path<u64>,
+        /// The ID of the saved search.
+        id: Path<u64>,
+        request: Json<SavedSearchUpdateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<SavedSearch>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            SavedSearch::update(account_id, id.0, request.0).await?,
+        ))
+    }
+
+    /// Deletes a saved search.
+    #[oai(
+        path = "/saved-searches/:account_id/:id",
+        method = "delete",
+        operation_id = "remove_saved_search"
+    )]
+    async fn remove_saved_search(
+        &self,
+        /// The ID of the account the search belongs to.
+        account_id: Path<u64>,
+        /// The ID of the saved search.
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let saved = SavedSearch::get(account_id, id.0).await?;
+        Ok(SavedSearch::remove(saved.id).await?)
+    }
+
+    /// Runs a saved search and returns a page of matching messages, as `unified-search`
+    /// would with the saved criteria.
+    #[oai(
+        path = "/saved-searches/:account_id/:id/run",
+        method = "get",
+        operation_id = "run_saved_search"
+    )]
+    async fn run_saved_search(
+        &self,
+        /// The ID of the account the search belongs to.
+        account_id: Path<u64>,
+        /// The ID of the saved search.
+        id: Path<u64>,
+        /// The page number for pagination (1-based).
+        page: Query<u64>,
+        /// The number of messages per page.
+        page_size: Query<u64>,
+        /// If `true`, lists results in descending order; otherwise, ascending.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<Envelope>>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let saved = SavedSearch::get(account_id, id.0).await?;
+        Ok(Json(
+            saved
+                .run(page.0, page_size.0, desc.0.unwrap_or(false))
+                .await?,
+        ))
+    }
+
+    /// Searches for messages in a mailbox of the specified account with a query string,
+    /// e.g. `from:alice subject:"invoice" has:attachment after:2024-01-01 flag:unseen`.
+    ///
+    /// Terms are combined with AND; `OR`, `-term` and parentheses are also supported. A term
+    /// without a field searches the headers and body. For Gmail API accounts the query is
+    /// passed to Gmail unchanged.
+    #[oai(
+        path = "/search-message/:account_id/query",
+        method = "get",
+        operation_id = "search_messages_by_query"
+    )]
+    async fn search_messages_by_query(
+        &self,
+        /// The ID of the account owning the mailboxes.
+        account_id: Path<u64>,
+        /// The search query.
+        q: Query<String>,
+        /// The name of the mailbox to search in. Required for IMAP accounts; for Gmail API
+        /// accounts it is treated as a label name.
+        mailbox: Query<Option<String>>,
+        /// The token for fetching the next page of results in pagination.
+        next_page_token: Query<Option<String>>,
+        /// The number of messages per page.
+        page_size: Query<u64>,
+        /// If `true`, lists results in descending order; otherwise, ascending. imap account only
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<CursorDataPage<Envelope>>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let request = MessageSearchRequest::from_query(account_id, &q.0, mailbox.0).await?;
+        Ok(Json(
+            request
+                .search_impl(
+                    account_id,
+                    next_page_token.0.as_deref(),
+                    page_size.0,
+                    desc.0.unwrap_or(false),
+                )
+                .await?,
+        ))
+    }
+
+    /// Searches the local cache with a query string, e.g.
+    /// `alice@example.com after:2024-01-01 before:2024-07-01 account:42`.
+    ///
+    /// The query holds one email address (bare or as `email:`), matched in the from, to and
+    /// cc fields, and optionally `after:`, `before:` and repeated `account:` terms.
+    #[oai(
+        path = "/unified-search/query",
+        method = "get",
+        operation_id = "unified_search_by_query"
+    )]
+    async fn unified_search_by_query(
+        &self,
+        /// The search query.
+        q: Query<String>,
+        /// The page number for pagination (1-based).
+        page: Query<u64>,
+        /// The number of messages per page.
+        page_size: Query<u64>,
+        /// If `true`, lists results in descending order; otherwise, ascending.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<Envelope>>> {
+        let mut request = parse_unified_search(&q.0)?;
+        request.restrict_to(&context)?;
+        Ok(Json(
+            request
+                .search(page.0, page_size.0, desc.0.unwrap_or(false))
+                .await?,
+        ))
+    }
+
     /// Creates a reply draft email for the specified account.
     /// The server internally constructs the reply email, automatically linking it to the
     /// original email thread by applying appropriate headers such as `References` and `In-Reply-To`.
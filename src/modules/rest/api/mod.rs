@@ -5,6 +5,8 @@
 use access_token::AccessTokenApi;
 use account::AccountApi;
 use auto_config::AutoConfigApi;
+use campaign::CampaignApi;
+use dead_letter::DeadLetterApi;
 use event_hook::EventHookApi;
 use license::LicenseApi;
 use mailbox::MailBoxApi;
@@ -12,24 +14,33 @@ use message::MessageApi;
 use mta::MTAApi;
 use oauth2::OAuth2Api;
 use poem_openapi::{OpenApiService, Tags};
+use retention::RetentionApi;
 use send::SendMailApi;
 use system::SystemApi;
 use templates::TempaltesApi;
+use workspace::WorkspaceApi;
 
-use crate::rustmailer_version;
+use crate::modules::error::{code::ErrorCode, RustMailerResult};
+use crate::{raise_error, rustmailer_version};
 
 pub mod access_token;
 pub mod account;
 pub mod auto_config;
+pub mod campaign;
+pub mod dead_letter;
 pub mod event_hook;
 pub mod license;
 pub mod mailbox;
 pub mod message;
 pub mod mta;
 pub mod oauth2;
+pub mod retention;
 pub mod send;
 pub mod system;
 pub mod templates;
+#[cfg(feature = "test-harness")]
+pub mod test_harness;
+pub mod workspace;
 
 #[derive(Tags)]
 pub enum ApiTags {
@@ -44,9 +55,21 @@ pub enum ApiTags {
     Hook,
     Message,
     SendMail,
+    Campaign,
+    DeadLetter,
+    Retention,
     System,
+    Workspace,
+    #[cfg(feature = "test-harness")]
+    TestHarness,
 }
 
+/// Endpoints of the in-memory mail servers, only built with the `test-harness` feature.
+#[cfg(feature = "test-harness")]
+type TestHarnessOpenApi = test_harness::TestHarnessApi;
+#[cfg(not(feature = "test-harness"))]
+type TestHarnessOpenApi = ();
+
 type RustMailOpenApi = (
     AccessTokenApi,
     LicenseApi,
@@ -60,6 +83,11 @@ type RustMailOpenApi = (
     OAuth2Api,
     MessageApi,
     SendMailApi,
+    CampaignApi,
+    DeadLetterApi,
+    RetentionApi,
+    // poem_openapi implements `OpenApi` for tuples of up to 16 elements.
+    (WorkspaceApi, TestHarnessOpenApi),
 );
 
 pub fn create_openapi_service() -> OpenApiService<RustMailOpenApi, ()> {
@@ -77,8 +105,36 @@ pub fn create_openapi_service() -> OpenApiService<RustMailOpenApi, ()> {
             OAuth2Api,
             MessageApi,
             SendMailApi,
+            CampaignApi,
+            DeadLetterApi,
+            RetentionApi,
+            (WorkspaceApi, TestHarnessOpenApi::default()),
         ),
         "RustMailerApi",
         rustmailer_version!(),
     )
 }
+
+/// Parses an `If-Match` header carrying the `updated_at` version of an entity.
+///
+/// Both the bare value and the quoted (optionally weak) ETag form are accepted,
+/// e.g. `1718000000000`, `"1718000000000"` or `W/"1718000000000"`.
+pub(crate) fn parse_if_match(value: Option<String>) -> RustMailerResult<Option<i64>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let trimmed = value.trim();
+    let trimmed = trimmed
+        .strip_prefix("W/")
+        .unwrap_or(trimmed)
+        .trim_matches('"');
+    trimmed.parse::<i64>().map(Some).map_err(|_| {
+        raise_error!(
+            format!(
+                "Invalid If-Match header '{}': expected the entity's updated_at value.",
+                value
+            ),
+            ErrorCode::InvalidParameter
+        )
+    })
+}
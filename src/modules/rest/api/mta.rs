@@ -12,21 +12,27 @@ use crate::modules::smtp::mta::payload::{
     MTACreateRequest, MTAUpdateRequest, SendTestEmailRequest,
 };
 use crate::modules::smtp::mta::send::send_test_email;
+use crate::modules::smtp::probe::{start_probe, SmtpProbeEvent, SmtpProbeRequest};
 use crate::raise_error;
+use futures::stream::BoxStream;
 use poem::web::Path;
 use poem_openapi::param::Query;
-use poem_openapi::payload::Json;
+use poem_openapi::payload::{EventStream, Json};
 use poem_openapi::OpenApi;
+use std::time::Duration;
 pub struct MTAApi;
 
 #[OpenApi(prefix_path = "/api/v1", tag = "ApiTags::Mta")]
 impl MTAApi {
     /// Retrieves the MTA (Mail Transfer Agent) configuration by its unique name.
+    ///
+    /// MTAs of a workspace are only visible to root and to the tokens scoped to the workspace.
     #[oai(path = "/mta/:id", method = "get", operation_id = "get_mta")]
     async fn get_mta(
         &self,
         /// The unique name identifier of the MTA.
         id: Path<u64>,
+        context: ClientContext,
     ) -> ApiResult<Json<Mta>> {
         let id = id.0;
         let mta = Mta::get(id).await?.ok_or_else(|| {
@@ -35,12 +41,13 @@ impl MTAApi {
                 ErrorCode::ResourceNotFound
             )
         })?;
+        context.require_workspace_access(mta.workspace_id)?;
         Ok(Json(mta))
     }
 
     /// Deletes an existing MTA configuration identified by its name.
     ///
-    /// Requires root privileges.
+    /// Requires root privileges, or a token scoped to the workspace of the MTA.
     #[oai(path = "/mta/:id", method = "delete", operation_id = "remove_mta")]
     async fn remove_mta(
         &self,
@@ -48,13 +55,14 @@ impl MTAApi {
         id: Path<u64>,
         context: ClientContext,
     ) -> ApiResult<()> {
-        context.require_root()?;
+        context.require_workspace_admin(Mta::workspace_of(id.0).await?)?;
         Ok(Mta::delete(id.0).await?)
     }
 
     /// Creates a new MTA configuration.
     ///
-    /// Requires root privileges.
+    /// MTAs created with a token scoped to a workspace belong to that workspace. Shared MTAs
+    /// outside any workspace require root privileges.
     #[oai(path = "/mta", method = "post", operation_id = "create_mta")]
     async fn create_mta(
         &self,
@@ -62,14 +70,16 @@ impl MTAApi {
         request: Json<MTACreateRequest>,
         context: ClientContext,
     ) -> ApiResult<()> {
-        context.require_root()?;
-        let entity = Mta::new(request.0)?;
+        let mut request = request.0;
+        request.workspace_id = request.workspace_id.or(context.workspace_id());
+        context.require_workspace_admin(request.workspace_id)?;
+        let entity = Mta::new(request)?;
         Ok(entity.save().await?)
     }
 
     /// Updates an existing MTA configuration by its name.
     ///
-    /// Requires root privileges.
+    /// Requires root privileges, or a token scoped to the workspace of the MTA.
     #[oai(path = "/mta/:id", method = "post", operation_id = "update_mta")]
     async fn update_mta(
         &self,
@@ -79,11 +89,13 @@ impl MTAApi {
         request: Json<MTAUpdateRequest>,
         context: ClientContext,
     ) -> ApiResult<()> {
-        context.require_root()?;
+        context.require_workspace_admin(Mta::workspace_of(id.0).await?)?;
         Ok(Mta::update(id.0, request.0).await?)
     }
 
     /// Retrieves a list of all MTA
+    ///
+    /// Only the shared MTAs and those of the workspace of the token are listed.
     #[oai(path = "/list-mta", method = "get", operation_id = "list_mta")]
     async fn list_mta(
         &self,
@@ -93,8 +105,11 @@ impl MTAApi {
         page_size: Query<Option<u64>>,
         /// Optional. Whether to sort the list in descending order.
         desc: Query<Option<bool>>,
+        context: ClientContext,
     ) -> ApiResult<Json<DataPage<Mta>>> {
-        Ok(Json(Mta::paginate_list(page.0, page_size.0, desc.0).await?))
+        Ok(Json(
+            Mta::paginate_list_visible(&context, page.0, page_size.0, desc.0).await?,
+        ))
     }
 
     /// Sends a test email using the specified Mail Transfer Agent (MTA).
@@ -109,8 +124,30 @@ impl MTAApi {
         id: Path<u64>,
         /// request payload.
         request: Json<SendTestEmailRequest>,
+        context: ClientContext,
     ) -> ApiResult<()> {
+        context.require_workspace_access(Mta::workspace_of(id.0).await?)?;
         send_test_email(id.0, request.0).await?;
         Ok(())
     }
+
+    /// Tests SMTP connectivity of an account or an MTA, streaming the protocol transcript.
+    ///
+    /// Performs a real SMTP transaction (or, with `rcpt_only`, one that is reset after
+    /// `RCPT TO`) and streams one server-sent event per stage (connect, TLS, greeting, EHLO,
+    /// STARTTLS, authentication, MAIL FROM, RCPT TO, DATA or RSET, QUIT) with its
+    /// transcript and whether it passed, followed by a final `Finished` event.
+    #[oai(path = "/smtp-probe", method = "post", operation_id = "probe_smtp")]
+    async fn probe_smtp(
+        &self,
+        /// The account or MTA to test and the recipient to use.
+        request: Json<SmtpProbeRequest>,
+        context: ClientContext,
+    ) -> ApiResult<EventStream<BoxStream<'static, SmtpProbeEvent>>> {
+        if let Some(account_id) = request.0.account_id {
+            context.require_account_access(account_id)?;
+        }
+        let stream = start_probe(request.0).await?;
+        Ok(EventStream::new(stream).keep_alive(Duration::from_secs(15)))
+    }
 }
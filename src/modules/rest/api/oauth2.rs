@@ -6,6 +6,7 @@ use crate::modules::common::auth::ClientContext;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::oauth2::entity::{OAuth2, OAuth2CreateRequest, OAuth2UpdateRequest};
 use crate::modules::oauth2::flow::{AuthorizeUrlRequest, OAuth2Flow};
+use crate::modules::oauth2::pending::{OAuth2PendingEntity, OAuth2PendingFlow};
 use crate::modules::oauth2::token::{ExternalOAuth2Request, OAuth2AccessToken};
 use crate::modules::rest::api::ApiTags;
 use crate::modules::rest::response::DataPage;
@@ -151,6 +152,46 @@ impl OAuth2Api {
         Ok(PlainText(flow.authorize_url(request.account_id).await?))
     }
 
+    /// Generates a new OAuth2 authorization URL for an account that was already authorized.
+    ///
+    /// Uses the OAuth2 configuration the account was authorized with. Intended for prompting the
+    /// user to grant access again, e.g. after an `AccountAuthenticationFailed` event reported that
+    /// the refresh token was revoked. Not available for externally managed tokens.
+    #[oai(
+        path = "/oauth2-reauthorize-url/:account_id",
+        method = "post",
+        operation_id = "create_oauth2_reauthorize_url"
+    )]
+    async fn create_oauth2_reauthorize_url(
+        &self,
+        /// The ID of the account to authorize again
+        account_id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<PlainText<String>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(PlainText(OAuth2Flow::reauthorize_url(account_id).await?))
+    }
+
+    /// Lists the OAuth2 authorization flows that were started and not completed yet.
+    ///
+    /// Requires root privileges.
+    /// Flows are removed once they are older than `rustmailer_oauth2_pending_ttl_minutes`.
+    #[oai(
+        path = "/oauth2-pending",
+        method = "get",
+        operation_id = "list_oauth2_pending_flows"
+    )]
+    async fn list_oauth2_pending_flows(
+        &self,
+        /// Optional. Only the flows of this account are listed.
+        account_id: Query<Option<u64>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<Vec<OAuth2PendingFlow>>> {
+        context.require_root()?;
+        Ok(Json(OAuth2PendingEntity::list(account_id.0).await?))
+    }
+
     /// Retrieves OAuth2 access tokens for a specified account.
     ///
     /// This endpoint fetches the OAuth2 access tokens associated with the given account ID.
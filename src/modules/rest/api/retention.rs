@@ -0,0 +1,158 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::common::auth::ClientContext;
+use crate::modules::rest::api::ApiTags;
+use crate::modules::rest::response::DataPage;
+use crate::modules::rest::ApiResult;
+use crate::modules::retention::entity::CleanupRule;
+use crate::modules::retention::payload::{
+    CleanupDryRunReport, CleanupRuleCreateRequest, CleanupRuleUpdateRequest,
+};
+use poem::web::Path;
+use poem_openapi::param::Query;
+use poem_openapi::payload::Json;
+use poem_openapi::OpenApi;
+
+pub struct RetentionApi;
+
+#[OpenApi(prefix_path = "/api/v1", tag = "ApiTags::Retention")]
+impl RetentionApi {
+    /// Creates a cleanup rule, deleting the messages of an account that match its criteria
+    /// on a schedule.
+    ///
+    /// Rules are created disabled: run `dry-run` to review what the rule would delete, then
+    /// enable it. Each run deletes at most `max_deletions_per_run` messages, oldest first.
+    /// Only IMAP and Gmail API accounts are supported.
+    #[oai(
+        path = "/cleanup-rules/:account_id",
+        method = "post",
+        operation_id = "create_cleanup_rule"
+    )]
+    async fn create_cleanup_rule(
+        &self,
+        /// The ID of the account to clean up
+        account_id: Path<u64>,
+        /// A JSON payload describing the rule
+        request: Json<CleanupRuleCreateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<CleanupRule>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let rule = CleanupRule::new(account_id, request.0).await?;
+        rule.clone().save().await?;
+        Ok(Json(rule))
+    }
+
+    /// Lists the cleanup rules of an account with pagination.
+    #[oai(
+        path = "/cleanup-rules/:account_id",
+        method = "get",
+        operation_id = "list_cleanup_rules"
+    )]
+    async fn list_cleanup_rules(
+        &self,
+        /// The ID of the account whose rules are to be listed
+        account_id: Path<u64>,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<CleanupRule>>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            CleanupRule::paginate_list_account(account_id, page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Retrieves a cleanup rule, including the results of its last dry run and last run.
+    #[oai(
+        path = "/cleanup-rules/:account_id/:id",
+        method = "get",
+        operation_id = "get_cleanup_rule"
+    )]
+    async fn get_cleanup_rule(
+        &self,
+        /// The ID of the account the rule belongs to
+        account_id: Path<u64>,
+        /// The ID of the rule
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<CleanupRule>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(CleanupRule::get(account_id, id.0).await?))
+    }
+
+    /// Updates a cleanup rule, or enables or disables it.
+    ///
+    /// Changing the mailbox, query or age of a rule disables it until it is dry run again.
+    #[oai(
+        path = "/cleanup-rules/:account_id/:id",
+        method = "post",
+        operation_id = "update_cleanup_rule"
+    )]
+    async fn update_cleanup_rule(
+        &self,
+        /// The ID of the account the rule belongs to
+        account_id: Path<u64>,
+        /// The ID of the rule
+        id: Path<u64>,
+        /// A JSON payload with the fields to change
+        request: Json<CleanupRuleUpdateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<CleanupRule>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            CleanupRule::update(account_id, id.0, request.0).await?,
+        ))
+    }
+
+    /// Deletes a cleanup rule. Messages it already deleted are not restored.
+    #[oai(
+        path = "/cleanup-rules/:account_id/:id",
+        method = "delete",
+        operation_id = "remove_cleanup_rule"
+    )]
+    async fn remove_cleanup_rule(
+        &self,
+        /// The ID of the account the rule belongs to
+        account_id: Path<u64>,
+        /// The ID of the rule
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let rule = CleanupRule::get(account_id, id.0).await?;
+        Ok(CleanupRule::remove(rule.id).await?)
+    }
+
+    /// Reports what the next run of a cleanup rule would delete, without deleting anything.
+    ///
+    /// The report includes the number of matching messages and a sample of them. A rule can
+    /// only be enabled after a dry run of its current criteria.
+    #[oai(
+        path = "/cleanup-rules/:account_id/:id/dry-run",
+        method = "post",
+        operation_id = "dry_run_cleanup_rule"
+    )]
+    async fn dry_run_cleanup_rule(
+        &self,
+        /// The ID of the account the rule belongs to
+        account_id: Path<u64>,
+        /// The ID of the rule
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<CleanupDryRunReport>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(CleanupRule::dry_run(account_id, id.0).await?))
+    }
+}
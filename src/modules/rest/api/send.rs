@@ -2,6 +2,7 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::account::migration::AccountModel;
 use crate::modules::common::auth::ClientContext;
 use crate::modules::common::paginated::paginate_vec;
 use crate::modules::error::code::ErrorCode;
@@ -9,18 +10,28 @@ use crate::modules::rest::api::ApiTags;
 use crate::modules::rest::response::DataPage;
 use crate::modules::rest::ApiResult;
 use crate::modules::scheduler::model::TaskStatus;
+use crate::modules::smtp::lint::{lint_request, ContentLintReport};
+use crate::modules::smtp::queue::bulk::{
+    run_bulk_task_action, run_task_action, BulkTaskAction, BulkTaskRequest, BulkTaskResult,
+    RescheduleEmailTaskRequest,
+};
 use crate::modules::smtp::queue::message::SendEmailTask;
 use crate::modules::smtp::request::builder::EmailBuilder;
 use crate::modules::smtp::request::forward::ForwardEmailRequest;
 use crate::modules::smtp::request::new::SendEmailRequest;
 use crate::modules::smtp::request::reply::ReplyEmailRequest;
+use crate::modules::smtp::request::SendEmailResponse;
+use crate::modules::smtp::suppression::{SuppressedAddress, SuppressionRequest};
+use crate::modules::smtp::timeline::{DeliveryEvent, MessageTimeline};
+use crate::modules::tasks::export::{TaskExport, TaskExportKind};
 use crate::modules::tasks::queue::RustMailerTaskQueue;
-use crate::raise_error;
+use crate::{raise_error, utc_now};
 use poem::web::Path;
+use poem::Body;
 use std::collections::BTreeSet;
 
 use poem_openapi::param::Query;
-use poem_openapi::payload::Json;
+use poem_openapi::payload::{Attachment, AttachmentType, Json};
 use poem_openapi::OpenApi;
 pub struct SendMailApi;
 
@@ -29,6 +40,8 @@ impl SendMailApi {
     /// Sends a new email for a specified account.
     ///
     /// This endpoint constructs and sends a new email based on the provided request data.
+    /// The response contains the generated `Message-ID`, task id and estimated send time
+    /// of each queued email.
     #[oai(
         path = "/send-mail/:account_id",
         method = "post",
@@ -41,11 +54,67 @@ impl SendMailApi {
         /// A JSON payload containing the details of the email to be sent
         request: Json<SendEmailRequest>,
         context: ClientContext,
-    ) -> ApiResult<()> {
+    ) -> ApiResult<Json<SendEmailResponse>> {
         let account_id = account_id.0;
         context.require_account_access(account_id)?;
         let request = request.0;
-        Ok(request.build(account_id).await?)
+        Ok(Json(request.build(account_id).await?))
+    }
+
+    /// Checks a new email for common deliverability problems without sending it.
+    ///
+    /// The request is linted as it would be sent: missing plain-text alternative, image-only
+    /// body, excessive link-to-text ratio, blocked phrases, missing unsubscribe option for
+    /// campaign emails and broken template variables. Template-based emails are rendered
+    /// and checked for every recipient. Set `send_control.enforce_content_policy` to reject
+    /// sends with error-level findings.
+    #[oai(
+        path = "/lint-mail/:account_id",
+        method = "post",
+        operation_id = "lint_mail"
+    )]
+    async fn lint_mail(
+        &self,
+        /// The ID of the account that would send the email
+        account_id: Path<u64>,
+        /// A JSON payload containing the details of the email to be checked
+        request: Json<SendEmailRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<ContentLintReport>> {
+        context.require_account_access(account_id.0)?;
+        let request = request.0;
+        request.validate().await?;
+        let account = AccountModel::get(account_id.0).await?;
+        Ok(Json(lint_request(&account, &request).await?))
+    }
+
+    /// Composes a new email without sending it.
+    ///
+    /// The request goes through the same pipeline as a send (templates, markdown, inline
+    /// attachments, tracking and headers), but instead of being queued, the raw RFC 822
+    /// message of each recipient group is returned base64 encoded in `raw`. Nothing is sent
+    /// or stored. Replies and forwards can be composed by setting `send_control.compose_only`.
+    #[oai(
+        path = "/compose-mail/:account_id",
+        method = "post",
+        operation_id = "compose_mail"
+    )]
+    async fn compose_mail(
+        &self,
+        /// The ID of the account the email is composed for
+        account_id: Path<u64>,
+        /// A JSON payload containing the details of the email to be composed
+        request: Json<SendEmailRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<SendEmailResponse>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        let mut request = request.0;
+        request
+            .send_control
+            .get_or_insert_with(Default::default)
+            .compose_only = Some(true);
+        Ok(Json(request.build(account_id).await?))
     }
 
     /// Sends a reply to an existing email for a specified account.
@@ -63,11 +132,11 @@ impl SendMailApi {
         /// A JSON payload containing the details of the email reply
         request: Json<ReplyEmailRequest>,
         context: ClientContext,
-    ) -> ApiResult<()> {
+    ) -> ApiResult<Json<SendEmailResponse>> {
         let account_id = account_id.0;
         context.require_account_access(account_id)?;
         let request = request.0;
-        Ok(request.build(account_id).await?)
+        Ok(Json(request.build(account_id).await?))
     }
 
     /// Forwards an existing email for a specified account.
@@ -85,11 +154,49 @@ impl SendMailApi {
         /// A JSON payload containing the details of the email to be forwarded.
         request: Json<ForwardEmailRequest>,
         context: ClientContext,
-    ) -> ApiResult<()> {
+    ) -> ApiResult<Json<SendEmailResponse>> {
         let account_id = account_id.0;
         context.require_account_access(account_id)?;
         let request = request.0;
-        Ok(request.build(account_id).await?)
+        Ok(Json(request.build(account_id).await?))
+    }
+
+    /// Exports email task history as newline-delimited JSON.
+    ///
+    /// Tasks are streamed one JSON object per line, oldest first, limited to the accounts the
+    /// access token can access. Intended for periodic ETL into data warehouses; for
+    /// incremental exports, pass the `created_at` of the last exported task plus one as
+    /// `created_after`.
+    #[oai(
+        path = "/send-email-tasks/export",
+        method = "get",
+        operation_id = "export_email_tasks"
+    )]
+    async fn export_email_tasks(
+        &self,
+        /// Optional. Only tasks created at or after this time (Unix epoch milliseconds).
+        created_after: Query<Option<i64>>,
+        /// Optional. Only tasks created before this time (Unix epoch milliseconds).
+        created_before: Query<Option<i64>>,
+        /// Optional. Only tasks with this status.
+        status: Query<Option<TaskStatus>>,
+        /// Optional. Comma-separated list of fields to include, e.g. `id,status,created_at`.
+        /// All fields are included if omitted.
+        fields: Query<Option<String>>,
+        context: ClientContext,
+    ) -> ApiResult<Attachment<Body>> {
+        let export = TaskExport::new(
+            &context,
+            TaskExportKind::Email,
+            created_after.0,
+            created_before.0,
+            status.0,
+            fields.0.as_deref(),
+        )?;
+        let body = Body::from_bytes_stream(export.into_stream());
+        Ok(Attachment::new(body)
+            .attachment_type(AttachmentType::Attachment)
+            .filename(TaskExportKind::Email.filename(utc_now!())))
     }
 
     /// Lists email tasks with pagination, sorting, and optional status filtering.
@@ -166,6 +273,106 @@ impl SendMailApi {
         ))
     }
 
+    /// Cancels all scheduled email tasks matching a filter.
+    ///
+    /// Matching tasks are stopped and will not be sent. Use `dry_run` to get the number
+    /// of matching tasks without cancelling them.
+    #[oai(
+        path = "/send-email-tasks/cancel",
+        method = "post",
+        operation_id = "cancel_email_tasks"
+    )]
+    async fn cancel_email_tasks(
+        &self,
+        /// A JSON payload containing the task filter
+        request: Json<BulkTaskRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<BulkTaskResult>> {
+        Ok(Json(
+            run_bulk_task_action(&context, BulkTaskAction::Cancel, &request.0).await?,
+        ))
+    }
+
+    /// Retries all failed or stopped email tasks matching a filter.
+    ///
+    /// Matching tasks are rescheduled to run immediately with their retry count reset.
+    /// Use `dry_run` to get the number of matching tasks without retrying them.
+    #[oai(
+        path = "/send-email-tasks/retry",
+        method = "post",
+        operation_id = "retry_email_tasks"
+    )]
+    async fn retry_email_tasks(
+        &self,
+        /// A JSON payload containing the task filter
+        request: Json<BulkTaskRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<BulkTaskResult>> {
+        Ok(Json(
+            run_bulk_task_action(&context, BulkTaskAction::Retry, &request.0).await?,
+        ))
+    }
+
+    /// Pauses all scheduled email tasks matching a filter.
+    ///
+    /// Paused tasks keep their schedule and are not sent until they are resumed.
+    /// Use `dry_run` to get the number of matching tasks without pausing them.
+    #[oai(
+        path = "/send-email-tasks/pause",
+        method = "post",
+        operation_id = "pause_email_tasks"
+    )]
+    async fn pause_email_tasks(
+        &self,
+        /// A JSON payload containing the task filter
+        request: Json<BulkTaskRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<BulkTaskResult>> {
+        Ok(Json(
+            run_bulk_task_action(&context, BulkTaskAction::Pause, &request.0).await?,
+        ))
+    }
+
+    /// Resumes all paused email tasks matching a filter.
+    ///
+    /// Tasks are sent at their scheduled time, or immediately if it has passed.
+    /// Use `dry_run` to get the number of matching tasks without resuming them.
+    #[oai(
+        path = "/send-email-tasks/resume",
+        method = "post",
+        operation_id = "resume_email_tasks"
+    )]
+    async fn resume_email_tasks(
+        &self,
+        /// A JSON payload containing the task filter
+        request: Json<BulkTaskRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<BulkTaskResult>> {
+        Ok(Json(
+            run_bulk_task_action(&context, BulkTaskAction::Resume, &request.0).await?,
+        ))
+    }
+
+    /// Reschedules all scheduled or paused email tasks matching a filter to `send_at`.
+    ///
+    /// Paused tasks stay paused. Use `dry_run` to get the number of matching tasks
+    /// without rescheduling them.
+    #[oai(
+        path = "/send-email-tasks/reschedule",
+        method = "post",
+        operation_id = "reschedule_email_tasks"
+    )]
+    async fn reschedule_email_tasks(
+        &self,
+        /// A JSON payload containing the task filter and the new send time
+        request: Json<BulkTaskRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<BulkTaskResult>> {
+        Ok(Json(
+            run_bulk_task_action(&context, BulkTaskAction::Reschedule, &request.0).await?,
+        ))
+    }
+
     /// Retrieves a specific email task by its ID.
     ///
     /// This endpoint fetches the details of an email task identified by the provided ID.
@@ -189,6 +396,71 @@ impl SendMailApi {
         Ok(Json(task))
     }
 
+    /// Pauses a scheduled email task.
+    ///
+    /// The task keeps its schedule and is not sent until it is resumed.
+    #[oai(
+        path = "/send-email-task/:id/pause",
+        method = "post",
+        operation_id = "pause_email_task"
+    )]
+    async fn pause_email_task(
+        &self,
+        /// The ID of the email task to pause
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<SendEmailTask>> {
+        Ok(Json(
+            run_task_action(&context, id.0, BulkTaskAction::Pause, None).await?,
+        ))
+    }
+
+    /// Resumes a paused email task.
+    ///
+    /// The task is sent at its scheduled time, or immediately if it has passed.
+    #[oai(
+        path = "/send-email-task/:id/resume",
+        method = "post",
+        operation_id = "resume_email_task"
+    )]
+    async fn resume_email_task(
+        &self,
+        /// The ID of the email task to resume
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<SendEmailTask>> {
+        Ok(Json(
+            run_task_action(&context, id.0, BulkTaskAction::Resume, None).await?,
+        ))
+    }
+
+    /// Reschedules a scheduled or paused email task to `send_at`.
+    ///
+    /// A paused task stays paused.
+    #[oai(
+        path = "/send-email-task/:id/reschedule",
+        method = "post",
+        operation_id = "reschedule_email_task"
+    )]
+    async fn reschedule_email_task(
+        &self,
+        /// The ID of the email task to reschedule
+        id: Path<u64>,
+        /// A JSON payload containing the new send time
+        request: Json<RescheduleEmailTaskRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<SendEmailTask>> {
+        Ok(Json(
+            run_task_action(
+                &context,
+                id.0,
+                BulkTaskAction::Reschedule,
+                Some(request.0.send_at),
+            )
+            .await?,
+        ))
+    }
+
     /// Mark a email task for deletion from queue
     ///
     /// Initiates asynchronous removal of an email task by marking it for deletion.
@@ -217,4 +489,92 @@ impl SendMailApi {
         context.require_account_access(task.account_id)?;
         Ok(send_queue.remove_task(id).await?)
     }
+
+    /// Retrieves the delivery timeline of a sent email, per recipient.
+    ///
+    /// Lists the recorded events of the email identified by its `Message-ID`, with or
+    /// without angle brackets: queued, sent, retried or failed attempts, the outcomes
+    /// reported by delivery status notifications, opens, clicks and complaints.
+    /// Only events of accounts accessible to the caller are returned.
+    #[oai(
+        path = "/messages/:message_id/timeline",
+        method = "get",
+        operation_id = "get_message_timeline"
+    )]
+    async fn get_message_timeline(
+        &self,
+        /// The Message-ID of the email
+        message_id: Path<String>,
+        context: ClientContext,
+    ) -> ApiResult<Json<MessageTimeline>> {
+        Ok(Json(
+            DeliveryEvent::timeline(&context, &message_id.0).await?,
+        ))
+    }
+
+    /// Lists the suppression list of an account, most recently suppressed addresses first.
+    ///
+    /// Emails of the account are not sent to suppressed addresses. Hard bounces and
+    /// complaints are added automatically.
+    #[oai(
+        path = "/suppressions/:account_id",
+        method = "get",
+        operation_id = "list_suppressions"
+    )]
+    async fn list_suppressions(
+        &self,
+        /// The ID of the account
+        account_id: Path<u64>,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<SuppressedAddress>>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            SuppressedAddress::paginate_list(account_id, page.0, page_size.0).await?,
+        ))
+    }
+
+    /// Adds an address to the suppression list of an account.
+    ///
+    /// An existing entry of the address is replaced.
+    #[oai(
+        path = "/suppressions/:account_id",
+        method = "post",
+        operation_id = "create_suppression"
+    )]
+    async fn create_suppression(
+        &self,
+        /// The ID of the account
+        account_id: Path<u64>,
+        /// A JSON payload containing the address to suppress
+        request: Json<SuppressionRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<SuppressedAddress>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            SuppressedAddress::create(account_id, request.0).await?,
+        ))
+    }
+
+    /// Removes an address from the suppression list, so that emails are sent to it again.
+    #[oai(
+        path = "/suppression/:id",
+        method = "delete",
+        operation_id = "remove_suppression"
+    )]
+    async fn remove_suppression(
+        &self,
+        /// The ID of the suppression list entry
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        let entry = SuppressedAddress::get(id.0).await?;
+        context.require_account_access(entry.account_id)?;
+        Ok(SuppressedAddress::remove(id.0).await?)
+    }
 }
@@ -2,17 +2,27 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::audit::AuditEntry;
+use crate::modules::cache::disk::reconcile::ReconcileReport;
 use crate::modules::cache::disk::DISK_CACHE;
 use crate::modules::common::auth::ClientContext;
-use crate::modules::error::code::ErrorCode;
-use crate::modules::overview::Overview;
+use crate::modules::context::health::StatusPage;
+use crate::modules::context::resources::ResourceUsage;
+use crate::modules::error::code::{ErrorCode, ErrorCodeInfo};
+use crate::modules::fault::{FaultInjector, FaultRule, FaultRuleRequest};
+use crate::modules::metrics::latency::{task_latency_stats, TaskLatencyStats};
+use crate::modules::overview::memory::MemoryReport;
+use crate::modules::overview::rollup::{export_metrics, to_csv, MetricsExportFormat};
+use crate::modules::overview::{Overview, OverviewQuery};
 use crate::modules::rest::api::ApiTags;
+use crate::modules::rest::response::DataPage;
+use crate::modules::rest::spec::ApiChangeReport;
 use crate::modules::rest::ApiResult;
 use crate::modules::settings::proxy::Proxy;
 use crate::modules::version::{fetch_notifications, Notifications};
-use crate::raise_error;
-use poem_openapi::param::Path;
-use poem_openapi::payload::{Json, PlainText};
+use crate::{raise_error, utc_now};
+use poem_openapi::param::{Path, Query};
+use poem_openapi::payload::{Attachment, AttachmentType, Json, PlainText};
 use poem_openapi::OpenApi;
 
 pub struct SystemApi;
@@ -47,14 +57,147 @@ impl SystemApi {
     /// - Email opens
     /// - Email clicks
     /// - Event dispatch counts (success and failure for HTTP and NATS)
+    ///
+    /// The time series cover the last day unless `from` and `to` are given; points older
+    /// than one day are daily or weekly rollups. A `from` before the oldest weekly point
+    /// kept (`rustmailer_metrics_weekly_retention_weeks`) is rejected. With `account_id`, the time series only
+    /// contain the figures recorded for that account (sent emails, sent bytes, new emails,
+    /// flag changes, opens and clicks); the task and account counts remain global.
     #[oai(method = "get", path = "/overview", operation_id = "get_overview")]
-    async fn get_overview(&self) -> ApiResult<Json<Overview>> {
-        let metrics = Overview::get()
-            .await
-            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+    async fn get_overview(
+        &self,
+        /// Start of the range (UNIX epoch milliseconds, inclusive). Defaults to one day ago.
+        from: Query<Option<i64>>,
+        /// End of the range (UNIX epoch milliseconds, exclusive). Defaults to now.
+        to: Query<Option<i64>>,
+        /// Restricts the time series to this account.
+        account_id: Query<Option<u64>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<Overview>> {
+        if let Some(account_id) = account_id.0 {
+            context.require_account_access(account_id)?;
+        }
+        let metrics = Overview::get(OverviewQuery {
+            from: from.0,
+            to: to.0,
+            account_id: account_id.0,
+        })
+        .await?;
         Ok(Json(metrics))
     }
 
+    /// Exports stored metric points for offline analysis, as JSON or CSV.
+    ///
+    /// Points from the last day are returned at the resolution they were recorded at
+    /// (one per minute); older points are returned as daily rollups, and points older than
+    /// `rustmailer_metrics_daily_retention_days` as weekly rollups.
+    #[oai(
+        method = "get",
+        path = "/overview/metrics/export",
+        operation_id = "export_overview_metrics"
+    )]
+    async fn export_overview_metrics(
+        &self,
+        /// Start of the range (UNIX epoch milliseconds, inclusive). Defaults to the oldest point.
+        from: Query<Option<i64>>,
+        /// End of the range (UNIX epoch milliseconds, exclusive). Defaults to now.
+        to: Query<Option<i64>>,
+        /// Export format, `Json` by default.
+        format: Query<Option<MetricsExportFormat>>,
+    ) -> ApiResult<Attachment<Vec<u8>>> {
+        let from = from.0.unwrap_or(0);
+        let to = to.0.unwrap_or_else(|| utc_now!() + 1);
+        let points = export_metrics(from, to).await?;
+        let (data, extension) = match format.0.unwrap_or_default() {
+            MetricsExportFormat::Json => (
+                serde_json::to_vec(&points)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?,
+                "json",
+            ),
+            MetricsExportFormat::Csv => (to_csv(&points).into_bytes(), "csv"),
+        };
+        Ok(Attachment::new(data)
+            .attachment_type(AttachmentType::Attachment)
+            .filename(format!("rustmailer-metrics-{}-{}.{}", from, to, extension)))
+    }
+
+    /// Lists every error code the API can return.
+    ///
+    /// Each entry contains the numeric `code` found in error responses, its name and
+    /// category, the HTTP status it is returned with, and whether retrying the request
+    /// later may succeed.
+    #[oai(method = "get", path = "/errors", operation_id = "list_error_codes")]
+    async fn list_error_codes(&self) -> ApiResult<Json<Vec<ErrorCodeInfo>>> {
+        Ok(Json(ErrorCode::catalog()))
+    }
+
+    /// Reports REST operations that changed since the previous minor version.
+    ///
+    /// The operations of every version are recorded when it first starts; the current
+    /// operations are compared with those of the most recent earlier minor version that ran
+    /// on this instance. `violations` lists operations breaking the API conventions SDK
+    /// generators rely on: a unique snake_case `operation_id`, at least one tag, and
+    /// `page`/`page_size` pagination parameters.
+    #[oai(method = "get", path = "/system/api-changes", operation_id = "get_api_changes")]
+    async fn get_api_changes(&self) -> ApiResult<Json<ApiChangeReport>> {
+        Ok(Json(ApiChangeReport::get().await?))
+    }
+
+    /// Reports process memory usage. Requires root permission.
+    ///
+    /// Includes allocator statistics from mimalloc, host memory, the number of entries
+    /// held by in-process caches (envelope flags state, LRU caches, disk cache index) and,
+    /// in metadata memory mode, the metadata size against its configured limit.
+    #[oai(method = "get", path = "/system/memory", operation_id = "get_memory_report")]
+    async fn get_memory_report(&self, context: ClientContext) -> ApiResult<Json<MemoryReport>> {
+        context.require_root()?;
+        Ok(Json(MemoryReport::get().await?))
+    }
+
+    /// Reports the health of each component with an overall severity. Requires root
+    /// permission.
+    ///
+    /// Covers the metadata database, the email task queue, a sample of the IMAP accounts'
+    /// sync state, the event hook dispatch backlog and the disk cache volume. The checks
+    /// run at most every 15 seconds. A variant without measurements can be served without
+    /// authentication at `/api/status/components` with
+    /// `rustmailer_public_status_page_enabled`.
+    #[oai(method = "get", path = "/system/status", operation_id = "get_status_page")]
+    async fn get_status_page(&self, context: ClientContext) -> ApiResult<Json<StatusPage>> {
+        context.require_root()?;
+        Ok(Json(StatusPage::get().await))
+    }
+
+    /// Reports open file descriptors and IMAP/SMTP connections against their limits.
+    /// Requires root permission.
+    ///
+    /// Connection limits are set with `rustmailer_max_imap_connections` and
+    /// `rustmailer_max_smtp_connections`. The same figures are exported on `/metrics` as
+    /// `rustmailer_open_connections` and `rustmailer_open_file_descriptors`.
+    #[oai(
+        method = "get",
+        path = "/system/resources",
+        operation_id = "get_resource_usage"
+    )]
+    async fn get_resource_usage(&self, context: ClientContext) -> ApiResult<Json<ResourceUsage>> {
+        context.require_root()?;
+        Ok(Json(ResourceUsage::get()))
+    }
+
+    /// Summarizes task queue latencies since startup, by task type.
+    ///
+    /// `queue_wait` measures the time between a task becoming due and starting to run,
+    /// `run_duration` the time it ran. The full histograms are exported on `/metrics` as
+    /// `rustmailer_task_queue_wait_seconds` and `rustmailer_task_run_duration_seconds`.
+    #[oai(
+        method = "get",
+        path = "/system/task-latency",
+        operation_id = "get_task_latency"
+    )]
+    async fn get_task_latency(&self) -> ApiResult<Json<Vec<TaskLatencyStats>>> {
+        Ok(Json(task_latency_stats()))
+    }
+
     /// Get the full list of SOCKS5 proxy configurations.
     #[oai(method = "get", path = "/list-proxy", operation_id = "list_proxy")]
     async fn list_proxy(&self) -> ApiResult<Json<Vec<Proxy>>> {
@@ -122,4 +265,84 @@ impl SystemApi {
         context.require_root()?;
         Ok(DISK_CACHE.clear().await?)
     }
+
+    /// Reconcile the disk cache with its index. Requires root permission.
+    ///
+    /// Verifies the digest of every locally cached file, then removes index entries
+    /// whose file is missing or corrupted and files that no index entry refers to.
+    /// Returns the number of checked and repaired entries. The same check also runs
+    /// once a day in the background.
+    #[oai(
+        path = "/disk-cache/reconcile",
+        method = "post",
+        operation_id = "reconcile_disk_cache"
+    )]
+    async fn reconcile_disk_cache(
+        &self,
+        context: ClientContext,
+    ) -> ApiResult<Json<ReconcileReport>> {
+        context.require_root()?;
+        Ok(Json(DISK_CACHE.reconcile().await?))
+    }
+
+    /// Lists audit log entries of privileged operations. Requires root permission.
+    #[oai(method = "get", path = "/audit-log", operation_id = "list_audit_log")]
+    async fn list_audit_log(
+        &self,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order (newest first).
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<AuditEntry>>> {
+        context.require_root()?;
+        Ok(Json(
+            AuditEntry::paginate_list(page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Lists the active fault injection rules. Requires root permission.
+    ///
+    /// Fails unless `rustmailer_fault_injection_enabled` is set.
+    #[oai(method = "get", path = "/system/faults", operation_id = "list_fault_rules")]
+    async fn list_fault_rules(&self, context: ClientContext) -> ApiResult<Json<Vec<FaultRule>>> {
+        context.require_root()?;
+        Ok(Json(FaultInjector::list()?))
+    }
+
+    /// Adds a fault injection rule. Requires root permission.
+    ///
+    /// Matching IMAP, SMTP or event hook operations are delayed, or fail as if the
+    /// connection dropped or the server returned an error response, so retries, circuit
+    /// breakers and alerting can be validated. Rules are kept in memory until deleted, spent
+    /// or the service restarts. Fails unless `rustmailer_fault_injection_enabled` is set.
+    #[oai(method = "post", path = "/system/faults", operation_id = "create_fault_rule")]
+    async fn create_fault_rule(
+        &self,
+        request: Json<FaultRuleRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<FaultRule>> {
+        context.require_root()?;
+        Ok(Json(FaultInjector::add(request.0)?))
+    }
+
+    /// Deletes a fault injection rule. Requires root permission.
+    #[oai(
+        method = "delete",
+        path = "/system/faults/:id",
+        operation_id = "remove_fault_rule"
+    )]
+    async fn remove_fault_rule(&self, id: Path<u64>, context: ClientContext) -> ApiResult<()> {
+        context.require_root()?;
+        Ok(FaultInjector::remove(id.0)?)
+    }
+
+    /// Deletes every fault injection rule. Requires root permission.
+    #[oai(method = "delete", path = "/system/faults", operation_id = "clear_fault_rules")]
+    async fn clear_fault_rules(&self, context: ClientContext) -> ApiResult<()> {
+        context.require_root()?;
+        Ok(FaultInjector::clear()?)
+    }
 }
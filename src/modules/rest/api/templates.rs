@@ -3,16 +3,19 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::common::auth::ClientContext;
-use crate::modules::rest::api::ApiTags;
+use crate::modules::rest::api::{parse_if_match, ApiTags};
 use crate::modules::rest::response::DataPage;
 use crate::modules::rest::ApiResult;
 use crate::modules::smtp::template::entity::EmailTemplate;
+use crate::modules::smtp::template::partial::TemplatePartial;
 use crate::modules::smtp::template::payload::{
-    TemplateCreateRequest, TemplateSentTestRequest, TemplateUpdateRequest,
+    TemplateCreateRequest, TemplatePartialCreateRequest, TemplatePartialUpdateRequest,
+    TemplateSeedTestReport, TemplateSeedTestRequest, TemplateSentTestRequest,
+    TemplateUpdateRequest,
 };
-use crate::modules::smtp::template::send::send_template_test_email;
+use crate::modules::smtp::template::send::{send_template_seed_test, send_template_test_email};
 use poem::web::Path;
-use poem_openapi::param::Query;
+use poem_openapi::param::{Header, Query};
 use poem_openapi::payload::Json;
 use poem_openapi::OpenApi;
 pub struct TempaltesApi;
@@ -30,9 +33,7 @@ impl TempaltesApi {
         context: ClientContext,
     ) -> ApiResult<Json<EmailTemplate>> {
         let template = EmailTemplate::get(id.0).await?;
-        if let Some(account_info) = &template.account {
-            context.require_account_access(account_info.id)?;
-        }
+        template.check_access(&context)?;
         Ok(Json(template))
     }
 
@@ -50,24 +51,33 @@ impl TempaltesApi {
         id: Path<u64>,
         context: ClientContext,
     ) -> ApiResult<()> {
-        let template = EmailTemplate::get(id.0).await?;
-        if let Some(account_info) = &template.account {
-            context.require_account_access(account_info.id)?;
-        }
+        EmailTemplate::get(id.0).await?.check_access(&context)?;
 
         Ok(EmailTemplate::remove(id.0).await?)
     }
 
     /// Creates a new email template.
     ///
-    /// Saves a new email template based on the provided request data.
+    /// Saves a new email template based on the provided request data. Templates support
+    /// Handlebars conditionals (`{{#if}}`), loops (`{{#each}}`) and partials (`{{> name}}`);
+    /// included partials must exist. Public templates created with a token scoped to a
+    /// workspace belong to that workspace.
     #[oai(path = "/template", method = "post", operation_id = "create_template")]
     async fn create_template(
         &self,
         ///JSON payload containing the data needed to create a new email template
         request: Json<TemplateCreateRequest>,
+        context: ClientContext,
     ) -> ApiResult<()> {
-        let entity = EmailTemplate::new(request.0).await?;
+        let mut request = request.0;
+        match request.account_id {
+            Some(account_id) => context.require_account_access(account_id)?,
+            None => {
+                request.workspace_id = request.workspace_id.or(context.workspace_id());
+                context.require_workspace_access(request.workspace_id)?;
+            }
+        }
+        let entity = EmailTemplate::new(request).await?;
         Ok(entity.save().await?)
     }
 
@@ -85,13 +95,15 @@ impl TempaltesApi {
         id: Path<u64>,
         ///JSON payload containing the updated template data.
         payload: Json<TemplateUpdateRequest>,
+        /// Optional. The `updated_at` value of the template as last read by the client.
+        /// If set and the template has been modified since, the update is rejected with a conflict error.
+        #[oai(name = "If-Match")]
+        if_match: Header<Option<String>>,
         context: ClientContext,
     ) -> ApiResult<()> {
-        let template = EmailTemplate::get(id.0).await?;
-        if let Some(account_info) = &template.account {
-            context.require_account_access(account_info.id)?;
-        }
-        Ok(EmailTemplate::update(id.0, payload.0).await?)
+        EmailTemplate::get(id.0).await?.check_access(&context)?;
+        let expected_version = parse_if_match(if_match.0)?;
+        Ok(EmailTemplate::update(id.0, payload.0, expected_version).await?)
     }
 
     /// Lists all email templates with pagination.
@@ -168,6 +180,7 @@ impl TempaltesApi {
     /// Send a test email using a specific template
     ///
     /// This endpoint allows sending a test email to verify template rendering and delivery.
+    /// Rendering errors, such as MJML compile errors, are returned without sending the email.
     #[oai(
         path = "/template-send-test/:id",
         method = "post",
@@ -185,4 +198,154 @@ impl TempaltesApi {
         send_template_test_email(id.0, request.0).await?;
         Ok(())
     }
+
+    /// Send a template to a list of seed mailboxes
+    ///
+    /// Renders the template once and sends it to each seed, such as mailboxes at Gmail,
+    /// Outlook and Yahoo, to spot-check rendering and deliverability across providers.
+    /// Sends to the configured seed list when the request has no recipients. Returns the
+    /// SMTP result of every seed; a failed seed does not stop the others.
+    #[oai(
+        path = "/template-seed-test/:id",
+        method = "post",
+        operation_id = "template_seed_test"
+    )]
+    async fn template_seed_test(
+        &self,
+        /// The ID of the template to send.
+        id: Path<u64>,
+        /// request payload.
+        request: Json<TemplateSeedTestRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<TemplateSeedTestReport>> {
+        context.require_account_access(request.0.account_id)?;
+        Ok(Json(send_template_seed_test(id.0, request.0).await?))
+    }
+
+    /// Creates a template partial.
+    ///
+    /// Partials are reusable Handlebars snippets, such as a shared header or footer, that
+    /// templates include with `{{> name}}`. Public partials (without `account_id`) require
+    /// root permission. Partials included by the content must already exist.
+    #[oai(
+        path = "/template-partial",
+        method = "post",
+        operation_id = "create_template_partial"
+    )]
+    async fn create_template_partial(
+        &self,
+        /// JSON payload describing the partial to create.
+        request: Json<TemplatePartialCreateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<TemplatePartial>> {
+        TemplatePartial::check_access(&context, request.0.account_id)?;
+        let partial = TemplatePartial::new(request.0).await?;
+        partial.clone().save().await?;
+        Ok(Json(partial))
+    }
+
+    /// Retrieves a template partial by its ID.
+    #[oai(
+        path = "/template-partial/:id",
+        method = "get",
+        operation_id = "get_template_partial"
+    )]
+    async fn get_template_partial(
+        &self,
+        /// The ID of the partial to retrieve.
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<TemplatePartial>> {
+        let partial = TemplatePartial::get(id.0).await?;
+        TemplatePartial::check_access(&context, partial.account_id())?;
+        Ok(Json(partial))
+    }
+
+    /// Updates the description or content of a template partial.
+    ///
+    /// The name of a partial cannot change, as templates include it by name.
+    #[oai(
+        path = "/template-partial/:id",
+        method = "post",
+        operation_id = "update_template_partial"
+    )]
+    async fn update_template_partial(
+        &self,
+        /// The ID of the partial to update.
+        id: Path<u64>,
+        /// JSON payload containing the updated partial data.
+        payload: Json<TemplatePartialUpdateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        let partial = TemplatePartial::get(id.0).await?;
+        TemplatePartial::check_access(&context, partial.account_id())?;
+        Ok(TemplatePartial::update(id.0, payload.0).await?)
+    }
+
+    /// Deletes a template partial.
+    ///
+    /// Templates still including the partial fail to render until it is recreated.
+    #[oai(
+        path = "/template-partial/:id",
+        method = "delete",
+        operation_id = "remove_template_partial"
+    )]
+    async fn remove_template_partial(
+        &self,
+        /// The ID of the partial to delete.
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        let partial = TemplatePartial::get(id.0).await?;
+        TemplatePartial::check_access(&context, partial.account_id())?;
+        Ok(TemplatePartial::remove(id.0).await?)
+    }
+
+    /// Lists all template partials with pagination. Requires root privileges.
+    #[oai(
+        path = "/list-template-partial",
+        method = "get",
+        operation_id = "list_template_partials"
+    )]
+    async fn list_template_partials(
+        &self,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<TemplatePartial>>> {
+        context.require_root()?;
+        Ok(Json(
+            TemplatePartial::paginate_list(page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Lists the template partials of an account with pagination. Public partials are not
+    /// included. Requires access to the specified account.
+    #[oai(
+        path = "/account-template-partials/:account_id",
+        method = "get",
+        operation_id = "list_account_template_partials"
+    )]
+    async fn list_account_template_partials(
+        &self,
+        /// The ID of the account whose partials are to be listed.
+        account_id: Path<u64>,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<TemplatePartial>>> {
+        let account_id = account_id.0;
+        context.require_account_access(account_id)?;
+        Ok(Json(
+            TemplatePartial::paginate_list_account(account_id, page.0, page_size.0, desc.0).await?,
+        ))
+    }
 }
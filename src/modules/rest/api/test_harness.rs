@@ -0,0 +1,81 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::common::auth::ClientContext;
+use crate::modules::error::code::ErrorCode;
+use crate::modules::harness::payload::{CapturedMessage, InboundMessage, InboundMessageRequest};
+use crate::modules::harness::TestHarness;
+use crate::modules::rest::api::ApiTags;
+use crate::modules::rest::ApiResult;
+use crate::raise_error;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use poem_openapi::{payload::Json, OpenApi};
+
+#[derive(Default)]
+pub struct TestHarnessApi;
+
+#[OpenApi(prefix_path = "/api/v1", tag = "ApiTags::TestHarness")]
+impl TestHarnessApi {
+    /// Lists the messages accepted by the in-memory SMTP server, oldest first.
+    ///
+    /// Requires root privileges.
+    #[oai(
+        path = "/test-harness/outbox",
+        method = "get",
+        operation_id = "list_test_harness_outbox"
+    )]
+    async fn list_outbox(&self, context: ClientContext) -> ApiResult<Json<Vec<CapturedMessage>>> {
+        context.require_root()?;
+        Ok(Json(TestHarness::mailstore()?.outbox()))
+    }
+
+    /// Delivers a message to a mailbox of the in-memory IMAP server, waking IDLE sessions.
+    ///
+    /// Requires root privileges.
+    #[oai(
+        path = "/test-harness/inbound",
+        method = "post",
+        operation_id = "deliver_test_harness_message"
+    )]
+    async fn deliver_message(
+        &self,
+        /// The message and the mailbox to add it to
+        payload: Json<InboundMessageRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<InboundMessage>> {
+        context.require_root()?;
+        let request = payload.0;
+        let raw = STANDARD.decode(request.raw.trim()).map_err(|_| {
+            raise_error!(
+                "'raw' must be a base64 encoded message".into(),
+                ErrorCode::InvalidParameter
+            )
+        })?;
+        let uid = TestHarness::mailstore()?
+            .inject(
+                &request.login,
+                request.mailbox.as_deref().unwrap_or("INBOX"),
+                raw,
+                request.flags.unwrap_or_default(),
+            )
+            .map_err(|e| raise_error!(e, ErrorCode::ResourceNotFound))?;
+        Ok(Json(InboundMessage { uid }))
+    }
+
+    /// Restores the mailboxes and scripted behaviors of the fixture and empties the outbox.
+    ///
+    /// Requires root privileges.
+    #[oai(
+        path = "/test-harness/reset",
+        method = "post",
+        operation_id = "reset_test_harness"
+    )]
+    async fn reset(&self, context: ClientContext) -> ApiResult<()> {
+        context.require_root()?;
+        TestHarness::mailstore()?
+            .reset()
+            .map_err(|e| raise_error!(e, ErrorCode::MissingConfiguration))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::common::auth::ClientContext;
+use crate::modules::rest::api::ApiTags;
+use crate::modules::rest::response::DataPage;
+use crate::modules::rest::ApiResult;
+use crate::modules::workspace::payload::{
+    WorkspaceAccountsRequest, WorkspaceCreateRequest, WorkspaceUpdateRequest,
+};
+use crate::modules::workspace::Workspace;
+use poem::web::Path;
+use poem_openapi::param::Query;
+use poem_openapi::payload::Json;
+use poem_openapi::OpenApi;
+
+pub struct WorkspaceApi;
+
+#[OpenApi(prefix_path = "/api/v1", tag = "ApiTags::Workspace")]
+impl WorkspaceApi {
+    /// Creates a workspace, a tenant owning accounts, templates, MTAs and event hooks.
+    ///
+    /// Requires root privileges.
+    #[oai(
+        path = "/workspace",
+        method = "post",
+        operation_id = "create_workspace"
+    )]
+    async fn create_workspace(
+        &self,
+        request: Json<WorkspaceCreateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<Workspace>> {
+        context.require_root()?;
+        Ok(Json(Workspace::create(request.0).await?))
+    }
+
+    /// Retrieves a workspace by its ID.
+    ///
+    /// Available to root and to the access tokens scoped to the workspace.
+    #[oai(
+        path = "/workspace/:id",
+        method = "get",
+        operation_id = "get_workspace"
+    )]
+    async fn get_workspace(
+        &self,
+        /// The ID of the workspace.
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<Json<Workspace>> {
+        context.require_workspace_access(Some(id.0))?;
+        Ok(Json(Workspace::get(id.0).await?))
+    }
+
+    /// Lists the workspaces.
+    ///
+    /// Requires root privileges.
+    #[oai(path = "/workspaces", method = "get", operation_id = "list_workspaces")]
+    async fn list_workspaces(
+        &self,
+        /// Optional. The page number to retrieve (starting from 1).
+        page: Query<Option<u64>>,
+        /// Optional. The number of items per page.
+        page_size: Query<Option<u64>>,
+        /// Optional. Whether to sort the list in descending order.
+        desc: Query<Option<bool>>,
+        context: ClientContext,
+    ) -> ApiResult<Json<DataPage<Workspace>>> {
+        context.require_root()?;
+        Ok(Json(
+            Workspace::paginate_list(page.0, page_size.0, desc.0).await?,
+        ))
+    }
+
+    /// Updates the name or description of a workspace.
+    ///
+    /// Requires root privileges.
+    #[oai(
+        path = "/workspace/:id",
+        method = "post",
+        operation_id = "update_workspace"
+    )]
+    async fn update_workspace(
+        &self,
+        /// The ID of the workspace to update.
+        id: Path<u64>,
+        request: Json<WorkspaceUpdateRequest>,
+        context: ClientContext,
+    ) -> ApiResult<Json<Workspace>> {
+        context.require_root()?;
+        Ok(Json(Workspace::update(id.0, request.0).await?))
+    }
+
+    /// Deletes an empty workspace.
+    ///
+    /// Fails while accounts, templates, MTAs, event hooks or access tokens still belong to
+    /// the workspace. Requires root privileges.
+    #[oai(
+        path = "/workspace/:id",
+        method = "delete",
+        operation_id = "remove_workspace"
+    )]
+    async fn remove_workspace(
+        &self,
+        /// The ID of the workspace to delete.
+        id: Path<u64>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        context.require_root()?;
+        Ok(Workspace::delete(id.0).await?)
+    }
+
+    /// Moves existing accounts into a workspace, together with their templates and event
+    /// hooks.
+    ///
+    /// Requires root privileges.
+    #[oai(
+        path = "/workspace/:id/accounts",
+        method = "post",
+        operation_id = "add_workspace_accounts"
+    )]
+    async fn add_workspace_accounts(
+        &self,
+        /// The ID of the workspace.
+        id: Path<u64>,
+        request: Json<WorkspaceAccountsRequest>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        context.require_root()?;
+        let account_ids: Vec<u64> = request.0.account_ids.into_iter().collect();
+        Ok(Workspace::add_accounts(id.0, &account_ids).await?)
+    }
+
+    /// Moves accounts out of a workspace, making them shared accounts outside any workspace.
+    ///
+    /// Requires root privileges.
+    #[oai(
+        path = "/workspace/:id/accounts/remove",
+        method = "post",
+        operation_id = "remove_workspace_accounts"
+    )]
+    async fn remove_workspace_accounts(
+        &self,
+        /// The ID of the workspace.
+        id: Path<u64>,
+        request: Json<WorkspaceAccountsRequest>,
+        context: ClientContext,
+    ) -> ApiResult<()> {
+        context.require_root()?;
+        let account_ids: Vec<u64> = request.0.account_ids.into_iter().collect();
+        Ok(Workspace::remove_accounts(id.0, &account_ids).await?)
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{sync::Arc, time::Duration};
+
+use futures::{stream, SinkExt, StreamExt};
+use poem::{
+    handler,
+    http::HeaderMap,
+    web::{
+        sse::{Event, SSE},
+        websocket::{Message, WebSocket, WebSocketStream},
+        Data, Path, Query,
+    },
+    IntoResponse, Result,
+};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{
+    modules::{
+        account::migration::AccountModel,
+        common::auth::ClientContext,
+        error::{code::ErrorCode, RustMailerResult},
+        hook::{
+            events::EventType,
+            stream::{EventFilter, EventSubscription, EVENT_SUBSCRIBERS},
+        },
+    },
+    raise_error,
+};
+
+/// Interval of the pings keeping idle connections open through proxies.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// How long the events of an account keep being recorded after its SSE client disconnects,
+/// for the client to resume with `Last-Event-ID`.
+const SSE_RESUME_WINDOW: Duration = Duration::from_secs(5 * 60);
+/// Events streamed by the per-account SSE endpoint.
+const ACCOUNT_SSE_EVENTS: [EventType; 2] =
+    [EventType::EmailAddedToFolder, EventType::EmailFlagsChanged];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EventSocketParams {
+    /// Comma-separated event types to receive. All types if omitted.
+    event_types: Option<String>,
+    /// Comma-separated IDs of the accounts whose events to receive. All accessible accounts
+    /// if omitted.
+    account_ids: Option<String>,
+}
+
+impl EventSocketParams {
+    fn filter(&self, context: &ClientContext) -> RustMailerResult<EventFilter> {
+        let event_types = split(&self.event_types)
+            .map(parse_event_type)
+            .collect::<RustMailerResult<Vec<_>>>()?;
+        let account_ids = split(&self.account_ids)
+            .map(|id| {
+                id.parse::<u64>().map_err(|_| {
+                    raise_error!(
+                        format!("Invalid account ID '{}'", id),
+                        ErrorCode::InvalidParameter
+                    )
+                })
+            })
+            .collect::<RustMailerResult<Vec<_>>>()?;
+        EventFilter::for_context(context, event_types, account_ids)
+    }
+}
+
+fn parse_event_type(name: &str) -> RustMailerResult<EventType> {
+    serde_json::from_value(serde_json::Value::String(name.into())).map_err(|_| {
+        raise_error!(
+            format!("Unknown event type '{}'", name),
+            ErrorCode::InvalidParameter
+        )
+    })
+}
+
+fn split(list: &Option<String>) -> impl Iterator<Item = &str> {
+    list.iter()
+        .flat_map(|list| list.split(','))
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+/// Pushes the events of the accessible accounts to a WebSocket as they occur, one JSON text
+/// message per event, whether or not a hook watches them.
+///
+/// Events can be limited with the `event_types` and `account_ids` query parameters. Browsers
+/// cannot set the `Authorization` header of a WebSocket, so the access token may be passed
+/// in the `access_token` query parameter instead.
+#[handler]
+pub async fn events_ws(
+    ws: WebSocket,
+    Data(context): Data<&Arc<ClientContext>>,
+    Query(params): Query<EventSocketParams>,
+) -> Result<impl IntoResponse> {
+    let subscription = EVENT_SUBSCRIBERS.subscribe(params.filter(context)?);
+    Ok(ws.on_upgrade(move |socket| push_events(socket, subscription)))
+}
+
+async fn push_events(socket: WebSocketStream, mut subscription: EventSubscription) {
+    let (mut sink, mut stream) = socket.split();
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    loop {
+        tokio::select! {
+            event = subscription.next() => {
+                let Some(event) = event else { break };
+                let text = match serde_json::to_string(event.as_ref()) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        warn!("Failed to serialize event for WebSocket: {:#?}", e);
+                        continue;
+                    }
+                };
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            message = stream.next() => match message {
+                // Clients have nothing to send: anything but a close is ignored.
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+            _ = ping.tick() => {
+                if sink.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Streams the `EmailAddedToFolder` and `EmailFlagsChanged` events of an account as
+/// Server-Sent Events, one JSON message per event with the event ID as SSE `id`.
+///
+/// A client reconnecting within five minutes with the `Last-Event-ID` header, as
+/// `EventSource` does, first receives the events it missed, provided they are among the most
+/// recent ones. The access token may be passed in the `access_token` query parameter.
+#[handler]
+pub async fn account_events_sse(
+    Path(account_id): Path<u64>,
+    Data(context): Data<&Arc<ClientContext>>,
+    headers: &HeaderMap,
+) -> Result<SSE> {
+    AccountModel::get(account_id).await?;
+    let filter = EventFilter::for_context(context, ACCOUNT_SSE_EVENTS.to_vec(), vec![account_id])?;
+    let last_event_id = headers
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    let (missed, subscription) = EVENT_SUBSCRIBERS.subscribe_since(filter, last_event_id);
+    let events = stream::iter(missed)
+        .chain(subscription.linger(SSE_RESUME_WINDOW).into_stream())
+        .filter_map(|event| async move {
+            match serde_json::to_string(event.as_ref()) {
+                Ok(data) => Some(Event::message(data).id(event.event.event_id.to_string())),
+                Err(e) => {
+                    warn!("Failed to serialize event for SSE: {:#?}", e);
+                    None
+                }
+            }
+        });
+    Ok(SSE::new(events).keep_alive(PING_INTERVAL))
+}
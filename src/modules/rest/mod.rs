@@ -9,8 +9,9 @@ use crate::modules::error::code::ErrorCode;
 use crate::modules::error::handler::error_handler;
 use crate::modules::error::RustMailerResult;
 use crate::modules::metrics::endpoint::PrometheusEndpoint;
+use crate::modules::rest::events::{account_events_sse, events_ws};
 use crate::modules::rest::public::login::login;
-use crate::modules::rest::public::status::get_status;
+use crate::modules::rest::public::status::{get_public_status_page, get_status};
 use crate::modules::{settings::cli::SETTINGS, utils::shutdown::shutdown_signal};
 
 use super::error::ApiErrorResponse;
@@ -21,19 +22,24 @@ use api::create_openapi_service;
 use assets::FrontEndAssets;
 use http::HeaderValue;
 use poem::endpoint::EmbeddedFilesEndpoint;
-use poem::listener::{Listener, TcpListener};
+use poem::listener::{BoxListener, Listener, TcpListener};
 use poem::middleware::{CatchPanic, Compression, SetHeader};
 use poem::{endpoint::EmbeddedFileEndpoint, middleware::Cors, EndpointExt, Route, Server};
 use poem::{get, post};
 use poem_openapi::ContactObject;
+use public::autodiscover::{microsoft_autodiscover, mozilla_autoconfig};
 use public::oauth2::oauth2_callback;
 use public::tracking::get_tracking_code;
+use spec::ApiSpecSnapshot;
 use std::time::Duration;
+use tracing::{info, warn};
 
 pub mod api;
 pub mod assets;
+pub mod events;
 pub mod public;
 pub mod response;
+pub mod spec;
 
 pub type ApiResult<T, E = ApiErrorResponse> = std::result::Result<T, E>;
 
@@ -47,7 +53,7 @@ const DESCRIPTION: &str = r#"
     Whether you're building SaaS platforms, CRM systems, or customer support tools, RustMailer delivers high performance and full control over your email infrastructure.
 "#;
 
-pub async fn start_http_server() -> RustMailerResult<()> {
+fn http_listener() -> RustMailerResult<BoxListener> {
     let listener = TcpListener::bind((
         SETTINGS
             .rustmailer_bind_ip
@@ -56,11 +62,15 @@ pub async fn start_http_server() -> RustMailerResult<()> {
         SETTINGS.rustmailer_http_port as u16,
     ));
 
-    let listener = if SETTINGS.rustmailer_enable_rest_https {
+    Ok(if SETTINGS.rustmailer_enable_rest_https {
         listener.rustls(rustls_config()?).boxed()
     } else {
         listener.boxed()
-    };
+    })
+}
+
+pub async fn start_http_server() -> RustMailerResult<()> {
+    let listener = http_listener()?;
 
     let api_service = create_openapi_service()
         .description(DESCRIPTION)
@@ -69,6 +79,10 @@ pub async fn start_http_server() -> RustMailerResult<()> {
         .external_document("https://rustmailer.com/docs")
         .summary("A self-hosted IMAP/SMTP middleware designed for developers");
 
+    if let Err(e) = ApiSpecSnapshot::record_current().await {
+        warn!("Failed to record the REST API operations of this version: {:#?}", e);
+    }
+
     let swagger = api_service.swagger_ui();
     let redoc = api_service.redoc();
     let scalar = api_service.scalar();
@@ -113,8 +127,26 @@ pub async fn start_http_server() -> RustMailerResult<()> {
         .nest("/metrics", PrometheusEndpoint)
         .nest("/oauth2/callback", get(oauth2_callback))
         .at("/email-track/:id", get(get_tracking_code))
-        .nest("/api/status", get(get_status))
+        .at("/mail/config-v1.1.xml", get(mozilla_autoconfig))
+        .at(
+            "/.well-known/autoconfig/mail/config-v1.1.xml",
+            get(mozilla_autoconfig),
+        )
+        .at("/autodiscover/autodiscover.xml", post(microsoft_autodiscover))
+        .at("/Autodiscover/Autodiscover.xml", post(microsoft_autodiscover))
+        .nest(
+            "/api/status",
+            Route::new()
+                .at("/components", get(get_public_status_page))
+                .at("/*", get(get_status)),
+        )
         .nest("/api/login", post(login))
+        // Long-lived, so not subject to the request timeout.
+        .at("/api/v1/events/ws", get(events_ws).with(ApiGuard).with(Tracing))
+        .at(
+            "/api/v1/accounts/:account_id/events/sse",
+            get(account_events_sse).with(ApiGuard).with(Tracing),
+        )
         .nest_no_strip("/api/v1", open_api_route)
         .nest_no_strip(
             "/assets",
@@ -139,7 +171,7 @@ pub async fn start_http_server() -> RustMailerResult<()> {
             shutdown_signal(),
             Some(Duration::from_secs(5)),
         );
-    println!(
+    info!(
         "RustMailer API Service is now running on port {}.",
         SETTINGS.rustmailer_http_port
     );
@@ -147,3 +179,20 @@ pub async fn start_http_server() -> RustMailerResult<()> {
         .await
         .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
 }
+
+/// Serves only the status endpoint, which reports the failed integrity check. Used when
+/// the metadata failed the startup integrity check and was not restored.
+pub async fn start_safe_mode_http_server() -> RustMailerResult<()> {
+    let route = Route::new()
+        .nest("/api/status", get(get_status))
+        .with(CatchPanic::new());
+    warn!(
+        "RustMailer is running in safe mode on port {}, only /api/status is available.",
+        SETTINGS.rustmailer_http_port
+    );
+    Server::new(http_listener()?)
+        .name("RustMailer Safe Mode")
+        .run_with_graceful_shutdown(route, shutdown_signal(), Some(Duration::from_secs(5)))
+        .await
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+}
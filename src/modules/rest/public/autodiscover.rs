@@ -0,0 +1,53 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem::{handler, http::StatusCode, web::Query, IntoResponse, Response};
+use serde::Deserialize;
+use tracing::error;
+
+use crate::modules::{
+    autoconfig::serve::{autodiscover_request_email, ClientServerSettings},
+    settings::cli::SETTINGS,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct AutoconfigQuery {
+    emailaddress: Option<String>,
+}
+
+/// Mozilla autoconfig, requested by clients as
+/// `/mail/config-v1.1.xml?emailaddress=<address>`.
+#[handler]
+pub async fn mozilla_autoconfig(Query(query): Query<AutoconfigQuery>) -> Response {
+    match query.emailaddress {
+        Some(email) => settings_response(&email, ClientServerSettings::to_autoconfig_xml).await,
+        None => StatusCode::BAD_REQUEST.into_response(),
+    }
+}
+
+/// Microsoft Autodiscover, posted by clients to `/autodiscover/autodiscover.xml` with the
+/// address in the request body.
+#[handler]
+pub async fn microsoft_autodiscover(body: String) -> Response {
+    match autodiscover_request_email(&body) {
+        Some(email) => settings_response(&email, ClientServerSettings::to_autodiscover_xml).await,
+        None => StatusCode::BAD_REQUEST.into_response(),
+    }
+}
+
+async fn settings_response(email: &str, render: fn(&ClientServerSettings) -> String) -> Response {
+    if !SETTINGS.rustmailer_autodiscover_enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    match ClientServerSettings::find(email).await {
+        Ok(Some(settings)) => Response::builder()
+            .content_type("application/xml; charset=utf-8")
+            .body(render(&settings)),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            error!(error = %e, "Failed to look up the account of an autodiscover request");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
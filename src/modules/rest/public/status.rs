@@ -2,10 +2,35 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::context::health::{Severity, StatusPage, STATUS_PAGE_TTL_SECS};
 use crate::modules::context::status::RustMailerStatus;
-use poem::{handler, web::Json, IntoResponse};
+use crate::modules::settings::cli::SETTINGS;
+use poem::{handler, http::StatusCode, web::Json, IntoResponse, Response};
 
 #[handler]
 pub async fn get_status() -> impl IntoResponse {
     Json(RustMailerStatus::get())
 }
+
+/// The component status page without measurements, when
+/// `rustmailer_public_status_page_enabled` is set. Responds with `503` during an outage
+/// so uptime monitors can alert on the status code alone.
+#[handler]
+pub async fn get_public_status_page() -> Response {
+    if !SETTINGS.rustmailer_public_status_page_enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let page = StatusPage::get().await;
+    let status = if page.severity == Severity::Outage {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    Json(page.to_public())
+        .with_status(status)
+        .with_header(
+            http::header::CACHE_CONTROL,
+            format!("public, max-age={STATUS_PAGE_TTL_SECS}"),
+        )
+        .into_response()
+}
@@ -22,8 +22,14 @@ use crate::modules::{
         },
         task::EventHookTask,
     },
-    metrics::{RUSTMAILER_EMAIL_CLICKS_TOTAL, RUSTMAILER_EMAIL_OPENS_TOTAL},
-    smtp::track::{EmailTracker, TrackType},
+    metrics::{
+        RUSTMAILER_ACCOUNT_EMAIL_CLICKS_TOTAL, RUSTMAILER_ACCOUNT_EMAIL_OPENS_TOTAL,
+        RUSTMAILER_EMAIL_CLICKS_TOTAL, RUSTMAILER_EMAIL_OPENS_TOTAL,
+    },
+    smtp::{
+        timeline::DeliveryEvent,
+        track::{EmailTracker, TrackType},
+    },
 };
 
 // Static 1x1 transparent PNG
@@ -46,9 +52,13 @@ pub async fn get_tracking_code(
 ) -> Response {
     match EmailTracker::decrypt_payload(&id) {
         Ok(payload) => {
+            DeliveryEvent::record_tracking(&payload).await;
             match payload.track_type {
                 TrackType::Click => {
                     RUSTMAILER_EMAIL_CLICKS_TOTAL.inc();
+                    RUSTMAILER_ACCOUNT_EMAIL_CLICKS_TOTAL
+                        .with_label_values(&[&payload.account_id.to_string()])
+                        .inc();
                     let url = payload.url.clone().unwrap_or_default();
                     if url.is_empty() {
                         warn!(
@@ -101,6 +111,9 @@ pub async fn get_tracking_code(
                 }
                 TrackType::Open => {
                     RUSTMAILER_EMAIL_OPENS_TOTAL.inc();
+                    RUSTMAILER_ACCOUNT_EMAIL_OPENS_TOTAL
+                        .with_label_values(&[&payload.account_id.to_string()])
+                        .inc();
                     match EventHookTask::is_watching_email_opened(payload.account_id).await {
                         Ok(watched) => {
                             if watched {
@@ -0,0 +1,416 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::LazyLock,
+};
+
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use ring::digest::{digest, SHA256};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    modules::{
+        database::{list_all_impl, manager::DB_MANAGER, upsert_impl},
+        error::RustMailerResult,
+        rest::api::create_openapi_service,
+    },
+    rustmailer_version, utc_now,
+};
+
+const HTTP_METHODS: [&str; 7] = ["get", "post", "put", "patch", "delete", "head", "options"];
+
+/// Documentation-only keywords, ignored when fingerprinting operations.
+const DOC_KEYWORDS: [&str; 6] = [
+    "description",
+    "summary",
+    "title",
+    "example",
+    "examples",
+    "externalDocs",
+];
+
+/// Query parameter names that must not be used for pagination; paginated operations use
+/// `page` and `page_size`, or `next_page_token` and `page_size`.
+const NON_STANDARD_PAGINATION: [&str; 7] = [
+    "limit",
+    "offset",
+    "size",
+    "per_page",
+    "perPage",
+    "pageSize",
+    "page_number",
+];
+
+/// The operations of the running version's REST API, and the problems found in them.
+pub static CURRENT_API: LazyLock<ApiSpec> =
+    LazyLock::new(|| ApiSpec::parse(&create_openapi_service().spec()));
+
+/// The stable part of a REST operation, used to detect changes breaking generated SDKs.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct OperationSignature {
+    pub operation_id: String,
+    /// HTTP method, in uppercase.
+    pub method: String,
+    pub path: String,
+    pub tags: Vec<String>,
+    /// SHA-256 of the operation's parameters, request body and responses, including all
+    /// referenced schemas. Descriptions and examples are not taken into account.
+    pub fingerprint: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ApiSpec {
+    pub operations: Vec<OperationSignature>,
+    /// Operations missing an `operation_id` or tags, or using non-standard pagination
+    /// parameters.
+    pub violations: Vec<String>,
+}
+
+impl ApiSpec {
+    pub fn parse(spec: &str) -> Self {
+        let spec: Value = serde_json::from_str(spec).unwrap_or_default();
+        let schemas = spec
+            .pointer("/components/schemas")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut api = ApiSpec::default();
+        let mut seen_ids = BTreeSet::new();
+        let paths = spec.get("paths").and_then(Value::as_object);
+        for (path, item) in paths.into_iter().flatten() {
+            for method in HTTP_METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let method = method.to_uppercase();
+                let name = format!("{method} {path}");
+                let operation_id = operation
+                    .get("operationId")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+                if operation_id.is_empty() {
+                    api.violations.push(format!("{name}: missing operation_id"));
+                } else if !is_snake_case(&operation_id) {
+                    api.violations.push(format!(
+                        "{name}: operation_id '{operation_id}' is not snake_case"
+                    ));
+                } else if !seen_ids.insert(operation_id.clone()) {
+                    api.violations.push(format!(
+                        "{name}: operation_id '{operation_id}' is not unique"
+                    ));
+                }
+                let tags: Vec<String> = operation
+                    .get("tags")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|t| t.as_str().map(Into::into))
+                    .collect();
+                if tags.is_empty() {
+                    api.violations.push(format!("{name}: missing tags"));
+                }
+                api.violations
+                    .extend(pagination_violations(operation).map(|v| format!("{name}: {v}")));
+
+                api.operations.push(OperationSignature {
+                    operation_id,
+                    method,
+                    path: path.clone(),
+                    tags,
+                    fingerprint: fingerprint(operation, &schemas),
+                });
+            }
+        }
+        api
+    }
+}
+
+/// The operations exposed by a RustMailer version, recorded when it first starts.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[native_model(id = 19, version = 1)]
+#[native_db]
+pub struct ApiSpecSnapshot {
+    #[primary_key]
+    pub version: String,
+    pub operations: Vec<OperationSignature>,
+    pub created_at: i64,
+}
+
+impl ApiSpecSnapshot {
+    /// Records the operations of the running version.
+    pub async fn record_current() -> RustMailerResult<()> {
+        let snapshot = ApiSpecSnapshot {
+            version: rustmailer_version!().into(),
+            operations: CURRENT_API.operations.clone(),
+            created_at: utc_now!(),
+        };
+        upsert_impl(DB_MANAGER.meta_db(), snapshot).await
+    }
+}
+
+/// REST operations that changed since the previous minor version.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ApiChangeReport {
+    pub current_version: String,
+    /// The most recent recorded version with a lower major or minor version, which the
+    /// current operations are compared with. `None` if no such version ran on this instance.
+    pub baseline_version: Option<String>,
+    /// Operations whose `operation_id` did not exist in the baseline version.
+    pub added: Vec<OperationSignature>,
+    /// Operations of the baseline version whose `operation_id` no longer exists.
+    pub removed: Vec<OperationSignature>,
+    /// Operations whose method, path, tags or fingerprint changed, as they are now.
+    pub changed: Vec<OperationSignature>,
+    /// Operations missing an `operation_id` or tags, or using non-standard pagination
+    /// parameters.
+    pub violations: Vec<String>,
+}
+
+impl ApiChangeReport {
+    pub async fn get() -> RustMailerResult<Self> {
+        let snapshots: Vec<ApiSpecSnapshot> = list_all_impl(DB_MANAGER.meta_db()).await?;
+        let current_version = rustmailer_version!();
+        let baseline = select_baseline(current_version, snapshots);
+        Ok(compare(
+            current_version,
+            baseline,
+            &CURRENT_API.operations,
+            CURRENT_API.violations.clone(),
+        ))
+    }
+}
+
+/// Picks the most recent snapshot of an earlier minor version.
+fn select_baseline(current: &str, snapshots: Vec<ApiSpecSnapshot>) -> Option<ApiSpecSnapshot> {
+    let current = Version::parse(current).ok()?;
+    snapshots
+        .into_iter()
+        .filter_map(|s| Version::parse(&s.version).ok().map(|v| (v, s)))
+        .filter(|(v, _)| (v.major, v.minor) < (current.major, current.minor))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, s)| s)
+}
+
+fn compare(
+    current_version: &str,
+    baseline: Option<ApiSpecSnapshot>,
+    current: &[OperationSignature],
+    violations: Vec<String>,
+) -> ApiChangeReport {
+    let mut report = ApiChangeReport {
+        current_version: current_version.into(),
+        violations,
+        ..Default::default()
+    };
+    let Some(baseline) = baseline else {
+        return report;
+    };
+    report.baseline_version = Some(baseline.version);
+
+    let previous: BTreeMap<&str, &OperationSignature> = baseline
+        .operations
+        .iter()
+        .map(|op| (op.operation_id.as_str(), op))
+        .collect();
+    let current_ids: BTreeSet<&str> = current.iter().map(|op| op.operation_id.as_str()).collect();
+    for op in current {
+        match previous.get(op.operation_id.as_str()) {
+            None => report.added.push(op.clone()),
+            Some(previous) if *previous != op => report.changed.push(op.clone()),
+            Some(_) => {}
+        }
+    }
+    report.removed = baseline
+        .operations
+        .iter()
+        .filter(|op| !current_ids.contains(op.operation_id.as_str()))
+        .cloned()
+        .collect();
+    report
+}
+
+fn is_snake_case(value: &str) -> bool {
+    value.starts_with(|c: char| c.is_ascii_lowercase())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn pagination_violations(operation: &Value) -> impl Iterator<Item = String> {
+    let params: BTreeSet<String> = operation
+        .get("parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|p| p.get("in").and_then(Value::as_str) == Some("query"))
+        .filter_map(|p| p.get("name").and_then(Value::as_str).map(Into::into))
+        .collect();
+
+    let mut violations: Vec<String> = NON_STANDARD_PAGINATION
+        .iter()
+        .filter(|name| params.contains(**name))
+        .map(|name| format!("non-standard pagination parameter '{name}'"))
+        .collect();
+    if params.contains("page") && !params.contains("page_size") {
+        violations.push("'page' without 'page_size'".into());
+    }
+    if params.contains("page_size")
+        && !params.contains("page")
+        && !params.contains("next_page_token")
+    {
+        violations.push("'page_size' without 'page' or 'next_page_token'".into());
+    }
+    violations.into_iter()
+}
+
+fn fingerprint(operation: &Value, schemas: &Map<String, Value>) -> String {
+    let mut signature = Map::new();
+    for key in ["parameters", "requestBody", "responses"] {
+        if let Some(value) = operation.get(key) {
+            signature.insert(key.into(), strip_docs(value, false));
+        }
+    }
+
+    // Include every schema reachable from the operation, so changes to request or
+    // response models are detected too.
+    let mut referenced = BTreeMap::new();
+    let mut pending = Vec::new();
+    collect_refs(operation, &mut pending);
+    while let Some(name) = pending.pop() {
+        if referenced.contains_key(&name) {
+            continue;
+        }
+        let schema = schemas.get(&name).cloned().unwrap_or_default();
+        collect_refs(&schema, &mut pending);
+        referenced.insert(name, strip_docs(&schema, false));
+    }
+    signature.insert(
+        "schemas".into(),
+        Value::Object(referenced.into_iter().collect()),
+    );
+
+    let canonical = Value::Object(signature).to_string();
+    hex::encode(digest(&SHA256, canonical.as_bytes()))
+}
+
+fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match value.as_str() {
+                    Some(reference) if key == "$ref" => {
+                        if let Some(name) = reference.strip_prefix("#/components/schemas/") {
+                            refs.push(name.into());
+                        }
+                    }
+                    _ => collect_refs(value, refs),
+                }
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|v| collect_refs(v, refs)),
+        _ => {}
+    }
+}
+
+/// Removes documentation keywords. Keys of a `properties` object are field names, not
+/// keywords, so they are kept as is.
+fn strip_docs(value: &Value, is_properties: bool) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, _)| is_properties || !DOC_KEYWORDS.contains(&key.as_str()))
+                .map(|(key, value)| {
+                    let is_properties = !is_properties && key == "properties";
+                    (key.clone(), strip_docs(value, is_properties))
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(|v| strip_docs(v, false)).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(description: &str, extra_param: Option<&str>) -> String {
+        let mut parameters = vec![
+            json!({"name": "page", "in": "query", "description": description, "schema": {"type": "integer"}}),
+            json!({"name": "page_size", "in": "query", "schema": {"type": "integer"}}),
+        ];
+        if let Some(name) = extra_param {
+            parameters.push(json!({"name": name, "in": "query", "schema": {"type": "integer"}}));
+        }
+        json!({
+            "paths": {
+                "/api/v1/items": {
+                    "get": {
+                        "operationId": "list_items",
+                        "tags": ["Item"],
+                        "parameters": parameters,
+                        "responses": {"200": {"description": description, "content": {"application/json": {"schema": {"$ref": "#/components/schemas/Item"}}}}}
+                    },
+                    "post": {"responses": {}}
+                }
+            },
+            "components": {"schemas": {"Item": {"type": "object", "description": description, "properties": {"description": {"type": "string"}}}}}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn audits_operations() {
+        let api = ApiSpec::parse(&spec("Items", Some("limit")));
+        assert_eq!(api.operations.len(), 2);
+        assert_eq!(
+            api.violations,
+            vec![
+                "GET /api/v1/items: non-standard pagination parameter 'limit'",
+                "POST /api/v1/items: missing operation_id",
+                "POST /api/v1/items: missing tags",
+            ]
+        );
+    }
+
+    #[test]
+    fn fingerprint_ignores_descriptions() {
+        let a = ApiSpec::parse(&spec("Items", None));
+        let b = ApiSpec::parse(&spec("All items", None));
+        let c = ApiSpec::parse(&spec("Items", Some("desc")));
+        assert_eq!(a.operations[0].fingerprint, b.operations[0].fingerprint);
+        assert_ne!(a.operations[0].fingerprint, c.operations[0].fingerprint);
+
+        let baseline = ApiSpecSnapshot {
+            version: "1.4.2".into(),
+            operations: a.operations.clone(),
+            created_at: 0,
+        };
+        let newer = ApiSpecSnapshot {
+            version: "1.5.0".into(),
+            ..baseline.clone()
+        };
+        let selected = select_baseline("1.5.1", vec![baseline, newer]).unwrap();
+        assert_eq!(selected.version, "1.4.2");
+
+        let report = compare("1.5.1", Some(selected), &c.operations[..1], Vec::new());
+        assert_eq!(report.changed.len(), 1);
+        assert_eq!(report.removed.len(), 1);
+        assert!(report.added.is_empty());
+    }
+
+    #[test]
+    fn rest_operations_pass_audit() {
+        assert_eq!(CURRENT_API.violations, Vec::<String>::new());
+    }
+}
@@ -0,0 +1,471 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::account::entity::MailerType;
+use crate::modules::account::migration::AccountModel;
+use crate::modules::database::batch::WriteBatch;
+use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::{
+    delete_impl, insert_impl, list_all_impl, paginate_secondary_scan_impl, secondary_find_impl,
+    update_impl,
+};
+use crate::modules::error::code::ErrorCode;
+use crate::modules::error::RustMailerResult;
+use crate::modules::message::search::query::parse_message_search;
+use crate::modules::overview::memory::check_metadata_capacity;
+use crate::modules::rest::response::DataPage;
+use crate::modules::retention::payload::{CleanupRuleCreateRequest, CleanupRuleUpdateRequest};
+use crate::{id, raise_error, utc_now};
+
+/// Default number of messages a rule deletes per run.
+pub const DEFAULT_MAX_DELETIONS_PER_RUN: u32 = 500;
+/// Upper bound of `max_deletions_per_run`.
+pub const MAX_DELETIONS_PER_RUN: u32 = 10_000;
+/// Shortest interval between two runs of a rule.
+const MIN_INTERVAL_HOURS: u32 = 1;
+
+/// A cleanup rule deleting the messages of an account that match its criteria, on a
+/// schedule.
+///
+/// Rules are created disabled. A rule can only be enabled after a dry run of its current
+/// criteria, and each run deletes at most `max_deletions_per_run` messages.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 22, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct CleanupRule {
+    /// Unique identifier of the rule.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// The account the rule cleans up.
+    #[secondary_key]
+    pub account_id: u64,
+    /// A name describing the rule, e.g. "Old newsletters".
+    pub name: String,
+    /// The mailbox to clean up. Required for IMAP accounts; a label name for Gmail API
+    /// accounts, where it is optional.
+    pub mailbox: Option<String>,
+    /// Optional search query selecting the messages, in the query string syntax of message
+    /// search, e.g. `from:news@example.com OR subject:digest`. Passed to Gmail unchanged for
+    /// Gmail API accounts.
+    pub query: Option<String>,
+    /// Only messages received more than this many days ago are deleted.
+    pub older_than_days: Option<u32>,
+    /// How often the rule runs, in hours, e.g. 168 for weekly.
+    pub interval_hours: u32,
+    /// Maximum number of messages deleted per run. The oldest matching messages are deleted
+    /// first; the rest are left for the next runs.
+    pub max_deletions_per_run: u32,
+    /// Whether the rule runs on its schedule.
+    pub enabled: bool,
+    /// Time (Unix epoch milliseconds) of the last dry run of the current criteria. Reset when
+    /// the criteria change.
+    pub dry_run_at: Option<i64>,
+    /// Number of messages matched by the last dry run.
+    pub dry_run_matches: Option<u64>,
+    /// Time (Unix epoch milliseconds) of the last scheduled run.
+    pub last_run_at: Option<i64>,
+    /// Number of messages deleted by the last scheduled run.
+    pub last_run_deleted: Option<u64>,
+    /// Error of the last scheduled run, if it failed.
+    pub last_error: Option<String>,
+    /// Total number of messages deleted by the rule.
+    pub total_deleted: u64,
+    /// Timestamp of when the rule was created (in Unix epoch milliseconds).
+    pub created_at: i64,
+    /// Timestamp of when the rule was last updated (in Unix epoch milliseconds).
+    pub updated_at: i64,
+}
+
+impl CleanupRule {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    pub async fn new(account_id: u64, request: CleanupRuleCreateRequest) -> RustMailerResult<Self> {
+        let account = AccountModel::get(account_id).await?;
+        let now = utc_now!();
+        let rule = Self {
+            id: id!(64),
+            account_id,
+            name: request.name,
+            mailbox: request.mailbox,
+            query: request.query,
+            older_than_days: request.older_than_days,
+            interval_hours: request.interval_hours.unwrap_or(24),
+            max_deletions_per_run: request
+                .max_deletions_per_run
+                .unwrap_or(DEFAULT_MAX_DELETIONS_PER_RUN),
+            created_at: now,
+            updated_at: now,
+            ..Default::default()
+        };
+        rule.validate(&account.mailer_type)?;
+        Ok(rule)
+    }
+
+    /// Checks the rule can run on an account of type `mailer_type`.
+    pub fn validate(&self, mailer_type: &MailerType) -> RustMailerResult<()> {
+        let invalid = |message: String| raise_error!(message, ErrorCode::InvalidParameter);
+        if self.name.trim().is_empty() {
+            return Err(invalid("The rule name must not be empty".into()));
+        }
+        match mailer_type {
+            MailerType::ImapSmtp => {
+                if self.mailbox.as_deref().is_none_or(|m| m.trim().is_empty()) {
+                    return Err(invalid(
+                        "Cleanup rules of IMAP accounts must specify a mailbox".into(),
+                    ));
+                }
+                if let Some(query) = &self.query {
+                    parse_message_search(query)?;
+                }
+            }
+            MailerType::GmailApi => {
+                if self.mailbox.is_none() && self.query.is_none() && self.older_than_days.is_none()
+                {
+                    return Err(invalid(
+                        "Cleanup rules of Gmail API accounts must specify a label, a query or an age"
+                            .into(),
+                    ));
+                }
+            }
+            MailerType::GraphApi | MailerType::Jmap => {
+                return Err(raise_error!(
+                    "Cleanup rules are only supported for IMAP and Gmail API accounts".into(),
+                    ErrorCode::Incompatible
+                ));
+            }
+        }
+        if self.older_than_days == Some(0) {
+            return Err(invalid("older_than_days must be greater than 0".into()));
+        }
+        if self.interval_hours < MIN_INTERVAL_HOURS {
+            return Err(invalid(format!(
+                "interval_hours must be at least {MIN_INTERVAL_HOURS}"
+            )));
+        }
+        if self.max_deletions_per_run == 0 || self.max_deletions_per_run > MAX_DELETIONS_PER_RUN {
+            return Err(invalid(format!(
+                "max_deletions_per_run must be between 1 and {MAX_DELETIONS_PER_RUN}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether the rule is due to run at `now`.
+    pub fn is_due(&self, now: i64) -> bool {
+        self.enabled
+            && self
+                .last_run_at
+                .is_none_or(|at| now - at >= self.interval_hours as i64 * 60 * 60 * 1000)
+    }
+
+    pub async fn save(self) -> RustMailerResult<()> {
+        check_metadata_capacity()?;
+        insert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    pub async fn find(id: u64) -> RustMailerResult<Option<CleanupRule>> {
+        secondary_find_impl(DB_MANAGER.meta_db(), CleanupRuleKey::id, id).await
+    }
+
+    /// Returns the rule if it exists and belongs to `account_id`.
+    pub async fn get(account_id: u64, id: u64) -> RustMailerResult<CleanupRule> {
+        Self::find(id)
+            .await?
+            .filter(|rule| rule.account_id == account_id)
+            .ok_or_else(|| {
+                raise_error!(
+                    format!("Cleanup rule id='{id}' not found for account {account_id}."),
+                    ErrorCode::ResourceNotFound
+                )
+            })
+    }
+
+    pub async fn list_all() -> RustMailerResult<Vec<CleanupRule>> {
+        list_all_impl(DB_MANAGER.meta_db()).await
+    }
+
+    pub async fn paginate_list_account(
+        account_id: u64,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<CleanupRule>> {
+        paginate_secondary_scan_impl(
+            DB_MANAGER.meta_db(),
+            page,
+            page_size,
+            desc,
+            CleanupRuleKey::account_id,
+            account_id,
+        )
+        .await
+        .map(DataPage::from)
+    }
+
+    pub async fn update(
+        account_id: u64,
+        id: u64,
+        request: CleanupRuleUpdateRequest,
+    ) -> RustMailerResult<CleanupRule> {
+        Self::get(account_id, id).await?;
+        let account = AccountModel::get(account_id).await?;
+        Self::modify(id, move |rule| {
+            let updated = apply_update(rule, request)?;
+            updated.validate(&account.mailer_type)?;
+            Ok(updated)
+        })
+        .await
+    }
+
+    /// Records the result of a dry run of the rule's current criteria.
+    pub async fn record_dry_run(id: u64, updated_at: i64, matches: u64) -> RustMailerResult<()> {
+        Self::modify(id, move |rule| {
+            // The criteria changed while the dry run was running.
+            if rule.updated_at != updated_at {
+                return Err(raise_error!(
+                    format!(
+                        "Cleanup rule id='{id}' was modified during the dry run; run it again."
+                    ),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+            let mut rule = rule.clone();
+            rule.dry_run_at = Some(utc_now!());
+            rule.dry_run_matches = Some(matches);
+            Ok(rule)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Records the result of a scheduled run.
+    pub async fn record_run(
+        id: u64,
+        started_at: i64,
+        result: Result<u64, String>,
+    ) -> RustMailerResult<()> {
+        Self::modify(id, move |rule| {
+            let mut rule = rule.clone();
+            rule.last_run_at = Some(started_at);
+            match result {
+                Ok(deleted) => {
+                    rule.last_run_deleted = Some(deleted);
+                    rule.total_deleted += deleted;
+                    rule.last_error = None;
+                }
+                Err(error) => {
+                    rule.last_run_deleted = None;
+                    rule.last_error = Some(error);
+                }
+            }
+            Ok(rule)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn modify(
+        id: u64,
+        update: impl FnOnce(&CleanupRule) -> RustMailerResult<CleanupRule> + Send + 'static,
+    ) -> RustMailerResult<CleanupRule> {
+        update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .secondary::<CleanupRule>(CleanupRuleKey::id, id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!("Cleanup rule id='{id}' not found."),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            update,
+        )
+        .await
+    }
+
+    pub async fn remove(id: u64) -> RustMailerResult<()> {
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<CleanupRule>(CleanupRuleKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!(
+                            "The cleanup rule with id={id} that you want to delete was not found."
+                        ),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await
+    }
+
+    /// Adds the removal of all cleanup rules of an account to `batch`.
+    pub fn stage_remove_account_rules(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let rules: Vec<CleanupRule> = rw
+                .scan()
+                .secondary::<CleanupRule>(CleanupRuleKey::account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(rules)
+        })
+    }
+}
+
+fn apply_update(
+    old: &CleanupRule,
+    request: CleanupRuleUpdateRequest,
+) -> RustMailerResult<CleanupRule> {
+    let mut new = old.clone();
+    if let Some(name) = request.name {
+        new.name = name;
+    }
+    if let Some(mailbox) = request.mailbox {
+        new.mailbox = Some(mailbox).filter(|m| !m.is_empty());
+    }
+    if let Some(query) = request.query {
+        new.query = Some(query).filter(|q| !q.trim().is_empty());
+    }
+    if let Some(older_than_days) = request.older_than_days {
+        new.older_than_days = Some(older_than_days).filter(|days| *days != 0);
+    }
+    if let Some(interval_hours) = request.interval_hours {
+        new.interval_hours = interval_hours;
+    }
+    if let Some(max_deletions_per_run) = request.max_deletions_per_run {
+        new.max_deletions_per_run = max_deletions_per_run;
+    }
+
+    // A dry run only vouches for the criteria it was run with.
+    let criteria_changed = new.mailbox != old.mailbox
+        || new.query != old.query
+        || new.older_than_days != old.older_than_days;
+    if criteria_changed {
+        new.dry_run_at = None;
+        new.dry_run_matches = None;
+        new.enabled = false;
+    }
+    if let Some(enabled) = request.enabled {
+        if enabled && new.dry_run_at.is_none() {
+            return Err(raise_error!(
+                format!(
+                    "Cleanup rule id='{}' must be dry run before it is enabled{}",
+                    old.id,
+                    if criteria_changed {
+                        ", as its criteria changed"
+                    } else {
+                        ""
+                    }
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        new.enabled = enabled;
+    }
+    new.updated_at = utc_now!();
+    Ok(new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule() -> CleanupRule {
+        CleanupRule {
+            id: 1,
+            account_id: 2,
+            name: "Old newsletters".into(),
+            mailbox: Some("Promotions".into()),
+            query: Some("from:news@example.com".into()),
+            older_than_days: Some(365),
+            interval_hours: 24,
+            max_deletions_per_run: 500,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn requires_a_dry_run_before_enabling() {
+        let enable = CleanupRuleUpdateRequest {
+            enabled: Some(true),
+            ..Default::default()
+        };
+        assert!(apply_update(&rule(), enable.clone()).is_err());
+
+        let dry_run = CleanupRule {
+            dry_run_at: Some(100),
+            ..rule()
+        };
+        let enabled = apply_update(&dry_run, enable).unwrap();
+        assert!(enabled.enabled);
+
+        // Changing the criteria disables the rule until it is dry run again.
+        let changed = apply_update(
+            &enabled,
+            CleanupRuleUpdateRequest {
+                older_than_days: Some(30),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(!changed.enabled);
+        assert_eq!(changed.dry_run_at, None);
+
+        // Other changes keep it enabled.
+        let capped = apply_update(
+            &enabled,
+            CleanupRuleUpdateRequest {
+                max_deletions_per_run: Some(100),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(capped.enabled);
+        assert_eq!(capped.dry_run_at, Some(100));
+    }
+
+    #[test]
+    fn validates_rules() {
+        assert!(rule().validate(&MailerType::ImapSmtp).is_ok());
+        let no_mailbox = CleanupRule {
+            mailbox: None,
+            ..rule()
+        };
+        assert!(no_mailbox.validate(&MailerType::ImapSmtp).is_err());
+        assert!(no_mailbox.validate(&MailerType::GmailApi).is_ok());
+        let no_cap = CleanupRule {
+            max_deletions_per_run: MAX_DELETIONS_PER_RUN + 1,
+            ..rule()
+        };
+        assert!(no_cap.validate(&MailerType::ImapSmtp).is_err());
+        assert!(rule().validate(&MailerType::GraphApi).is_err());
+    }
+
+    #[test]
+    fn schedules_runs() {
+        let day = 24 * 60 * 60 * 1000;
+        let mut rule = rule();
+        assert!(!rule.is_due(0));
+        rule.enabled = true;
+        assert!(rule.is_due(0));
+        rule.last_run_at = Some(day);
+        assert!(!rule.is_due(day + day / 2));
+        assert!(rule.is_due(2 * day));
+    }
+}
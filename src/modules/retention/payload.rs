@@ -0,0 +1,61 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::cache::model::Envelope;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct CleanupRuleCreateRequest {
+    /// A name describing the rule, e.g. "Old newsletters".
+    pub name: String,
+    /// The mailbox to clean up. Required for IMAP accounts; a label name for Gmail API
+    /// accounts, where it is optional.
+    pub mailbox: Option<String>,
+    /// Optional search query selecting the messages, in the query string syntax of message
+    /// search, e.g. `from:news@example.com`.
+    pub query: Option<String>,
+    /// Optional. Only messages received more than this many days ago are deleted.
+    pub older_than_days: Option<u32>,
+    /// Optional. How often the rule runs, in hours. Defaults to 24.
+    pub interval_hours: Option<u32>,
+    /// Optional. Maximum number of messages deleted per run (1-10000). Defaults to 500.
+    pub max_deletions_per_run: Option<u32>,
+}
+
+/// Changes to a cleanup rule. Fields left unset are unchanged.
+///
+/// Changing `mailbox`, `query` or `older_than_days` disables the rule until it is dry run
+/// again. An empty `query`, or an `older_than_days` of 0, removes that criterion.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct CleanupRuleUpdateRequest {
+    pub name: Option<String>,
+    pub mailbox: Option<String>,
+    pub query: Option<String>,
+    pub older_than_days: Option<u32>,
+    pub interval_hours: Option<u32>,
+    pub max_deletions_per_run: Option<u32>,
+    /// Enables or disables the rule. A rule can only be enabled after a dry run of its
+    /// current criteria.
+    pub enabled: Option<bool>,
+}
+
+/// What a run of a cleanup rule would delete, without deleting anything.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct CleanupDryRunReport {
+    pub rule_id: u64,
+    /// The search sent to the server.
+    pub search: String,
+    /// Number of messages currently matching the rule. An estimate for Gmail API accounts.
+    pub matched: u64,
+    /// Number of messages the next run would delete, at most `max_deletions_per_run`.
+    pub would_delete: u64,
+    /// Whether more messages match than a single run deletes.
+    pub capped: bool,
+    /// Up to 20 of the messages the next run would delete, oldest first for IMAP accounts.
+    pub samples: Vec<Envelope>,
+    /// Time (Unix epoch milliseconds) of the dry run.
+    pub ran_at: i64,
+}
@@ -0,0 +1,233 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use chrono::{DateTime, Duration};
+use tracing::{error, info};
+
+use crate::{
+    modules::{
+        account::{entity::MailerType, migration::AccountModel},
+        cache::model::Envelope,
+        error::{code::ErrorCode, RustMailerResult},
+        message::{
+            delete::{move_to_trash, MessageDeleteRequest},
+            search::{
+                payload::{
+                    Condition, Conditions, Logic, MessageSearch, MessageSearchRequest, Operator,
+                },
+                query::parse_message_search,
+            },
+        },
+        retention::{entity::CleanupRule, payload::CleanupDryRunReport},
+    },
+    raise_error, utc_now,
+};
+
+/// Number of messages listed in a dry run report.
+const SAMPLE_SIZE: u64 = 20;
+/// Maximum page size of message search.
+const SEARCH_PAGE_SIZE: u64 = 500;
+/// Number of messages deleted per request.
+const DELETE_BATCH_SIZE: usize = 500;
+
+impl CleanupRule {
+    /// Builds the search selecting the messages of the rule at `now`.
+    pub fn search_request(
+        &self,
+        mailer_type: &MailerType,
+        now: i64,
+    ) -> RustMailerResult<MessageSearchRequest> {
+        let search = match mailer_type {
+            // Gmail receives a single search expression in its own syntax.
+            MailerType::GmailApi => {
+                let mut terms: Vec<String> = self.query.iter().cloned().collect();
+                if let Some(days) = self.older_than_days {
+                    terms.push(format!("older_than:{days}d"));
+                }
+                MessageSearch::Condition(Condition {
+                    condition: Conditions::GmailSeacrch,
+                    value: Some(terms.join(" ")),
+                })
+            }
+            _ => {
+                let mut children = Vec::new();
+                if let Some(query) = &self.query {
+                    children.push(parse_message_search(query)?);
+                }
+                if let Some(days) = self.older_than_days {
+                    let before = DateTime::from_timestamp_millis(now).ok_or_else(|| {
+                        raise_error!("Invalid timestamp".into(), ErrorCode::InternalError)
+                    })? - Duration::days(days as i64);
+                    children.push(MessageSearch::Condition(Condition {
+                        condition: Conditions::Before,
+                        value: Some(before.format("%Y-%m-%d").to_string()),
+                    }));
+                }
+                match children.len() {
+                    0 => MessageSearch::Condition(Condition {
+                        condition: Conditions::All,
+                        value: None,
+                    }),
+                    1 => children.remove(0),
+                    _ => MessageSearch::Logic(Logic {
+                        operator: Operator::And,
+                        children,
+                    }),
+                }
+            }
+        };
+        Ok(MessageSearchRequest {
+            search,
+            mailbox: self.mailbox.clone(),
+        })
+    }
+
+    /// Reports what the next run of the rule would delete, without deleting anything, and
+    /// records the dry run so the rule can be enabled.
+    pub async fn dry_run(account_id: u64, id: u64) -> RustMailerResult<CleanupDryRunReport> {
+        let rule = Self::get(account_id, id).await?;
+        let account = AccountModel::check_account_active(rule.account_id, false).await?;
+        let ran_at = utc_now!();
+        let request = rule.search_request(&account.mailer_type, ran_at)?;
+        let search = match account.mailer_type {
+            MailerType::GmailApi => request.search.to_gmail_api_search_command()?,
+            _ => request.search.to_imap_command(true)?,
+        };
+        let page = request
+            .search_impl(account.id, None, SAMPLE_SIZE, false)
+            .await?;
+        let matched = page.total_items;
+        let cap = rule.max_deletions_per_run as u64;
+        Self::record_dry_run(rule.id, rule.updated_at, matched).await?;
+        Ok(CleanupDryRunReport {
+            rule_id: rule.id,
+            search,
+            matched,
+            would_delete: matched.min(cap),
+            capped: matched > cap,
+            samples: page.items,
+            ran_at,
+        })
+    }
+
+    /// Deletes the messages matching the rule, at most `max_deletions_per_run`, returning
+    /// the number of messages deleted.
+    pub async fn execute(&self) -> RustMailerResult<u64> {
+        let account = AccountModel::check_account_active(self.account_id, false).await?;
+        let request = self.search_request(&account.mailer_type, utc_now!())?;
+        let limit = self.max_deletions_per_run as usize;
+
+        let mut ids = Vec::new();
+        let mut next_page_token: Option<String> = None;
+        loop {
+            let page = request
+                .search_impl(
+                    account.id,
+                    next_page_token.as_deref(),
+                    SEARCH_PAGE_SIZE.min(limit as u64),
+                    false,
+                )
+                .await?;
+            ids.extend(page.items.into_iter().map(|e: Envelope| e.id));
+            next_page_token = page.next_page_token;
+            if ids.len() >= limit || next_page_token.is_none() {
+                break;
+            }
+        }
+        ids.truncate(limit);
+
+        let mut deleted = 0;
+        for batch in ids.chunks(DELETE_BATCH_SIZE) {
+            let request = MessageDeleteRequest {
+                ids: batch.to_vec(),
+                mailbox: self.mailbox.clone(),
+            };
+            move_to_trash(account.id, &request).await?;
+            deleted += batch.len() as u64;
+        }
+        Ok(deleted)
+    }
+}
+
+/// Runs the enabled cleanup rules whose interval has elapsed.
+pub async fn run_due_rules() -> RustMailerResult<()> {
+    let now = utc_now!();
+    for rule in CleanupRule::list_all().await? {
+        if !rule.is_due(now) {
+            continue;
+        }
+        let result = rule.execute().await;
+        match &result {
+            Ok(deleted) => info!(
+                "Account {}: Cleanup rule '{}' ({}) deleted {} messages",
+                rule.account_id, rule.name, rule.id, deleted
+            ),
+            Err(e) => error!(
+                "Account {}: Cleanup rule '{}' ({}) failed: {:#?}",
+                rule.account_id, rule.name, rule.id, e
+            ),
+        }
+        if let Err(e) =
+            CleanupRule::record_run(rule.id, now, result.map_err(|e| e.to_string())).await
+        {
+            error!(
+                "Failed to record the run of cleanup rule {}: {:#?}",
+                rule.id, e
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule() -> CleanupRule {
+        CleanupRule {
+            name: "Old newsletters".into(),
+            mailbox: Some("INBOX".into()),
+            query: Some("from:news@example.com".into()),
+            older_than_days: Some(10),
+            ..Default::default()
+        }
+    }
+
+    // 2025-01-31T00:00:00Z
+    const NOW: i64 = 1_738_281_600_000;
+
+    #[test]
+    fn builds_imap_searches() {
+        let request = rule().search_request(&MailerType::ImapSmtp, NOW).unwrap();
+        assert_eq!(request.mailbox.as_deref(), Some("INBOX"));
+        assert_eq!(
+            request.search.to_imap_command(true).unwrap(),
+            "FROM \"news@example.com\" BEFORE 21-Jan-2025"
+        );
+
+        let everything = CleanupRule {
+            query: None,
+            older_than_days: None,
+            ..rule()
+        };
+        assert_eq!(
+            everything
+                .search_request(&MailerType::ImapSmtp, NOW)
+                .unwrap()
+                .search
+                .to_imap_command(true)
+                .unwrap(),
+            "ALL"
+        );
+    }
+
+    #[test]
+    fn builds_gmail_searches() {
+        let request = rule().search_request(&MailerType::GmailApi, NOW).unwrap();
+        assert_eq!(
+            request.search.to_gmail_api_search_command().unwrap(),
+            "from:news@example.com older_than:10d"
+        );
+    }
+}
@@ -0,0 +1,24 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Duration;
+
+use crate::modules::{
+    context::RustMailTask, retention::run::run_due_rules, scheduler::periodic::PeriodicTask,
+};
+
+const TASK_INTERVAL: Duration = Duration::from_secs(10 * 60); // every 10 mins
+
+/// Runs the enabled cleanup rules of all accounts once their interval has elapsed.
+pub struct CleanupRuleTask;
+
+impl RustMailTask for CleanupRuleTask {
+    fn start() {
+        let periodic_task = PeriodicTask::new("cleanup-rule-runner");
+
+        let task = move |_ctx: Option<u64>| Box::pin(async move { run_due_rules().await });
+
+        periodic_task.start(task, None, TASK_INTERVAL, false, false);
+    }
+}
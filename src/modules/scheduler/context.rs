@@ -5,6 +5,7 @@
 use crate::modules::scheduler::cleaner::TaskCleaner;
 use crate::modules::scheduler::flow::TaskFlow;
 use crate::modules::scheduler::handlers::TaskHandlers;
+use crate::modules::scheduler::model::TaskMeta;
 use crate::modules::scheduler::store::TaskStore;
 use crate::modules::scheduler::task::Task;
 use crate::modules::scheduler::updater::TaskStatusUpdater;
@@ -98,7 +99,8 @@ where
     }
 
     /// Adds a new task to the context for execution.
-    pub async fn add_task<T>(&self, task: T, delay_seconds: Option<u32>) -> Result<(), String>
+    /// Adds a task and returns the stored metadata, including its id and first run time.
+    pub async fn add_task<T>(&self, task: T, delay_seconds: Option<u32>) -> Result<TaskMeta, String>
     where
         T: Task + Send + Sync + 'static, // T must implement the Task trait and be thread-safe
     {
@@ -107,9 +109,10 @@ where
         let next_run = utc_now!() + delay_seconds as i64;
         task_meta.next_run = next_run;
         self.store
-            .store_task(task_meta) // Store the task metadata in the task store
+            .store_task(task_meta.clone()) // Store the task metadata in the task store
             .await
-            .map_err(|e| format!("{:#?}", e)) // Handle any errors during the store operation
+            .map_err(|e| format!("{:#?}", e))?; // Handle any errors during the store operation
+        Ok(task_meta)
     }
 
     pub async fn add_tasks<T>(&self, tasks: &[T], delay_seconds: Option<u32>) -> Result<(), String>
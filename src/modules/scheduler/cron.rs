@@ -0,0 +1,235 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time};
+use time_tz::{Offset, OffsetDateTimeExt, TimeZone, Tz};
+
+use crate::{
+    modules::error::{code::ErrorCode, RustMailerResult},
+    raise_error,
+};
+
+const MONTH_NAMES: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+/// How far ahead the next run is searched, long enough to reach the next February 29.
+const MAX_SEARCH_DAYS: i64 = 8 * 366;
+
+/// A standard 5-field cron expression: `minute hour day-of-month month day-of-week`.
+///
+/// Fields accept `*`, values, ranges (`1-5`), steps (`*/15`, `0-30/10`) and lists
+/// (`1,15`). Months and days of the week also accept three-letter English names; Sunday
+/// is `0` or `7`. When both the day of the month and the day of the week are restricted,
+/// a day matching either runs, as with cron. The macros `@hourly`, `@daily`
+/// (`@midnight`), `@weekly`, `@monthly` and `@yearly` (`@annually`) are supported.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> RustMailerResult<Self> {
+        Self::parse_fields(expression).map_err(|e| {
+            raise_error!(
+                format!("Invalid cron expression '{expression}': {e}"),
+                ErrorCode::InvalidParameter
+            )
+        })
+    }
+
+    fn parse_fields(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim().to_ascii_lowercase().as_str() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            _ => expression,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(format!("expected 5 fields, got {}", fields.len()));
+        };
+        let mut days_of_week = parse_field(day_of_week, 0, 7, WEEKDAY_NAMES)?;
+        // Sunday is both 0 and 7.
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days_of_month: parse_field(day_of_month, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, MONTH_NAMES)?,
+            days_of_week,
+            day_of_month_restricted: !day_of_month.starts_with('*'),
+            day_of_week_restricted: !day_of_week.starts_with('*'),
+        })
+    }
+
+    fn matches_day(&self, date: Date) -> bool {
+        if !has(self.months, date.month() as u8) {
+            return false;
+        }
+        let day_of_month = has(self.days_of_month, date.day());
+        let day_of_week = has(self.days_of_week, date.weekday().number_days_from_sunday());
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            (true, false) => day_of_month,
+            (false, true) => day_of_week,
+            (false, false) => true,
+        }
+    }
+
+    /// Returns the first time (Unix epoch milliseconds) strictly after `now` at which the
+    /// wall clock in `timezone` matches the schedule, or `None` if it never does, as with
+    /// `0 0 30 2 *`.
+    pub fn next_after(&self, now: i64, timezone: &Tz) -> Option<i64> {
+        let now = OffsetDateTime::from_unix_timestamp_nanos(now as i128 * 1_000_000)
+            .ok()?
+            .to_timezone(timezone);
+        for days in 0..=MAX_SEARCH_DAYS {
+            let date = now.date().checked_add(Duration::days(days))?;
+            if !self.matches_day(date) {
+                continue;
+            }
+            for hour in (0..24).filter(|h| has(self.hours, *h)) {
+                for minute in (0..60).filter(|m| has(self.minutes, *m)) {
+                    let local = PrimitiveDateTime::new(date, Time::from_hms(hour, minute, 0).ok()?);
+                    // Start from the current offset, then correct it in case a DST change
+                    // happens before the target time.
+                    let guess = local.assume_offset(now.offset());
+                    let offset = timezone.get_offset_utc(&guess).to_utc();
+                    let target = local.assume_offset(offset);
+                    if target > now {
+                        return Some((target.unix_timestamp_nanos() / 1_000_000) as i64);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+fn has(set: u64, value: u8) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parses a cron field into a bit set of the values it matches.
+fn parse_field(field: &str, min: u8, max: u8, names: &[&str]) -> Result<u64, String> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u8 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step in '{part}'"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, max, names)?,
+                parse_value(end, min, max, names)?,
+            )
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // `5/10` means every 10 from 5.
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start > end {
+            return Err(format!("invalid range '{range}'"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn parse_value(value: &str, min: u8, max: u8, names: &[&str]) -> Result<u8, String> {
+    let parsed = match names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+    {
+        // Month names start at 1, day names at 0.
+        Some(index) => index as u8 + min,
+        None => value
+            .parse()
+            .map_err(|_| format!("invalid value '{value}'"))?,
+    };
+    if parsed < min || parsed > max {
+        return Err(format!("'{value}' is out of range {min}-{max}"));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use time_tz::timezones;
+
+    use super::*;
+
+    // 2025-01-15 12:00:00 UTC, a Wednesday.
+    const NOW: i64 = 1_736_942_400_000;
+    const HOUR: i64 = 3_600_000;
+    const DAY: i64 = 24 * HOUR;
+
+    fn next(expression: &str, timezone: &str) -> Option<i64> {
+        CronSchedule::parse(expression)
+            .unwrap()
+            .next_after(NOW, timezones::get_by_name(timezone).unwrap())
+    }
+
+    #[test]
+    fn computes_next_runs() {
+        assert_eq!(next("*/15 * * * *", "UTC"), Some(NOW + 15 * 60_000));
+        assert_eq!(next("0 12 * * *", "UTC"), Some(NOW + DAY));
+        assert_eq!(
+            next("30 9 * * mon", "UTC"),
+            Some(NOW + 4 * DAY + 21 * HOUR + HOUR / 2)
+        );
+        assert_eq!(next("@weekly", "UTC"), next("0 0 * * 7", "UTC"));
+        assert_eq!(next("0 0 1 feb *", "UTC"), Some(NOW + 16 * DAY + 12 * HOUR));
+        // Either the day of the month or the day of the week.
+        assert_eq!(next("0 0 1 * fri", "UTC"), Some(NOW + DAY + 12 * HOUR));
+        // 09:00 in Berlin (UTC+1) is 08:00 UTC.
+        assert_eq!(next("0 9 * * *", "Europe/Berlin"), Some(NOW + 20 * HOUR));
+        assert_eq!(
+            next("0 0 29 2 *", "UTC").map(|t| t > NOW + 365 * DAY),
+            Some(true)
+        );
+        assert_eq!(next("0 0 30 2 *", "UTC"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in [
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "10-5 * * * *",
+            "* * * foo *",
+        ] {
+            assert!(CronSchedule::parse(expression).is_err(), "{expression}");
+        }
+        assert!(CronSchedule::parse("0,30 8-18/2 1-7 JAN-jun MON-FRI").is_ok());
+    }
+}
@@ -6,7 +6,7 @@ use crate::modules::error::code::ErrorCode;
 use crate::modules::error::RustMailerError;
 use crate::modules::scheduler::model::TaskMeta;
 use crate::modules::scheduler::result::TaskResult;
-use crate::modules::scheduler::task::Task;
+use crate::modules::scheduler::task::{take_deferral, Task};
 use crate::{raise_error, utc_now};
 use ahash::AHashMap;
 use std::time::Instant;
@@ -89,6 +89,12 @@ impl TaskHandlers {
             if result.is_success() {
                 return result;
             }
+            if let Some(run_at) = take_deferral(task_id) {
+                result.retry_count = attempts;
+                result.next_run = Some(run_at);
+                result.deferred = true;
+                return result;
+            }
             result.retry_count = attempts + 1;
 
             if let Some(max) = retry_policy.max_retries {
@@ -4,12 +4,14 @@
 
 mod cleaner;
 pub mod context;
+pub mod cron;
 mod flow;
 mod handlers;
 pub mod model;
 pub mod nativedb;
 pub mod periodic;
 mod processor;
+pub mod recurring;
 mod result;
 pub mod retry;
 pub mod store;
@@ -6,14 +6,16 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use itertools::Itertools;
-use native_db::Database;
+use native_db::{transaction::RwTransaction, Database};
 use tracing::warn;
 
 use crate::{
     modules::{
         database::{
             batch_delete_impl, batch_insert_impl, batch_update_impl, filter_by_secondary_key_impl,
-            insert_impl, paginate_secondary_scan_impl, secondary_find_impl, update_impl, Paginated,
+            insert_impl, key::timestamp_key_range, paginate_secondary_scan_impl,
+            range_by_primary_key_impl, scan_secondary_key_while_impl, secondary_find_impl,
+            snapshot::journal::TaskJournal, update_impl, Paginated,
         },
         error::{code::ErrorCode, RustMailerResult},
         hook::{
@@ -24,17 +26,23 @@ use crate::{
         metrics::{EMAIL, HOOK, RUSTMAILER_TASK_FETCH_DURATION, RUSTMAILER_TASK_QUEUE_LENGTH},
         scheduler::{
             model::{TaskMeta, TaskStatus},
-            nativedb::{TaskMetaEntity, TaskMetaEntityKey},
+            nativedb::{TaskMetaEntity, TaskMetaEntityKey, PAUSED_REASON},
             store::TaskStore,
             task::Task,
         },
         settings::cli::SETTINGS,
-        smtp::request::task::SmtpTask,
+        smtp::{
+            request::task::SmtpTask,
+            timeline::{DeliveryEvent, DeliveryEventKind},
+        },
+        tasks::dead_letter::capture_failed_task,
     },
     raise_error, utc_now,
 };
 
 const HOUR_TO_MS: u64 = 60 * 60 * 1000;
+/// Number of expired tasks removed per write transaction by the cleanup.
+const CLEANUP_BATCH_SIZE: usize = 100;
 
 #[derive(Clone)]
 pub struct NativeDbTaskStore {
@@ -102,6 +110,7 @@ impl NativeDbTaskStore {
         let elapsed = start.elapsed();
         RUSTMAILER_TASK_FETCH_DURATION.observe(elapsed.as_secs_f64());
         //debug!("Time taken to fetch task from native_db: {:#?}", elapsed);
+        TaskJournal::record(database, result.iter().map(|t| t.id).collect()).await;
 
         Ok(result.into_iter().map(Into::into).collect())
     }
@@ -156,6 +165,7 @@ impl NativeDbTaskStore {
             },
         )
         .await?;
+        TaskJournal::record(database, vec![task_id]).await;
         Ok(())
     }
 
@@ -169,33 +179,32 @@ impl NativeDbTaskStore {
 
         let cleanup_interval_ms =
             SETTINGS.rustmailer_cleanup_interval_hours as i64 * HOUR_TO_MS as i64;
-        let now = utc_now!();
+        let cutoff = utc_now!() - cleanup_interval_ms;
 
+        // Tasks of a status are indexed in the order of their primary key, which starts
+        // with `created_at`: each batch only reads expired tasks of the status.
         for status in statuses_to_clean {
-            // let status_str = status.to_string();
-            let task_ids: Vec<u64> = {
-                let rw = database
-                    .r_transaction()
-                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
-                rw.scan()
-                    .secondary::<TaskMetaEntity>(TaskMetaEntityKey::status)
-                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-                    .start_with(status.code())
-                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-                    .filter_map(|result| match result {
-                        Ok(t) if now - t.created_at > cleanup_interval_ms => Some(Ok(t.id)),
-                        Ok(_) => None,
-                        Err(e) => Some(Err(e)),
-                    })
-                    .try_collect()
-                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-            };
-
-            let chunks: Vec<Vec<u64>> = task_ids.chunks(100).map(|chunk| chunk.to_vec()).collect();
+            loop {
+                let task_ids: Vec<u64> = scan_secondary_key_while_impl::<TaskMetaEntity, _, _>(
+                    database,
+                    TaskMetaEntityKey::status,
+                    status.code(),
+                    move |t| t.created_at < cutoff,
+                    |t| !t.is_paused(),
+                    CLEANUP_BATCH_SIZE,
+                )
+                .await?
+                .into_iter()
+                .map(|t| t.id)
+                .collect();
+                if task_ids.is_empty() {
+                    break;
+                }
+                let last_batch = task_ids.len() < CLEANUP_BATCH_SIZE;
 
-            for chunk in chunks {
+                let deleted = task_ids.clone();
                 batch_delete_impl(database, move |rw| {
-                    let to_delete: Vec<TaskMetaEntity> = chunk
+                    let to_delete: Vec<TaskMetaEntity> = task_ids
                         .iter()
                         .filter_map(|task_id| {
                             rw.get()
@@ -210,12 +219,57 @@ impl NativeDbTaskStore {
                     Ok(to_delete)
                 })
                 .await?;
+                TaskJournal::record(database, deleted).await;
+                if last_batch {
+                    break;
+                }
             }
         }
 
         Ok(())
     }
 
+    async fn defer(
+        database: &Arc<Database<'static>>,
+        task_id: u64,
+        reason: Option<String>,
+        last_duration_ms: Option<usize>,
+        next_run: i64,
+    ) -> RustMailerResult<()> {
+        update_impl(
+            database,
+            move |rw| {
+                rw.get()
+                    .secondary::<TaskMetaEntity>(TaskMetaEntityKey::id, task_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!(
+                                "The task with id={} that you want to modify was not found.",
+                                &task_id
+                            ),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            move |current| {
+                let mut updated = current.clone();
+                updated.last_duration_ms = last_duration_ms;
+                updated.last_error = reason;
+                updated.updated_at = utc_now!();
+                // A task stopped or removed while running stays so.
+                if !matches!(updated.status, TaskStatus::Stopped | TaskStatus::Removed) {
+                    updated.status = TaskStatus::Scheduled;
+                    updated.next_run = next_run;
+                }
+                Ok(updated)
+            },
+        )
+        .await?;
+        TaskJournal::record(database, vec![task_id]).await;
+        Ok(())
+    }
+
     pub async fn set_status(
         database: &Arc<Database<'static>>,
         task_id: u64,
@@ -248,9 +302,200 @@ impl NativeDbTaskStore {
             },
         )
         .await?;
+        TaskJournal::record(database, vec![task_id]).await;
         Ok(())
     }
 
+    /// Replaces the parameters of a task, keeping its status and schedule.
+    pub async fn set_params(
+        database: &Arc<Database<'static>>,
+        task_id: u64,
+        task_params: String,
+    ) -> RustMailerResult<()> {
+        update_impl(
+            database,
+            move |rw| {
+                rw.get()
+                    .secondary::<TaskMetaEntity>(TaskMetaEntityKey::id, task_id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!(
+                                "The task with id={} that you want to modify was not found.",
+                                &task_id
+                            ),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            |current| {
+                let mut updated = current.clone();
+                updated.task_params = task_params;
+                updated.updated_at = utc_now!();
+                Ok(updated)
+            },
+        )
+        .await?;
+        TaskJournal::record(database, vec![task_id]).await;
+        Ok(())
+    }
+
+    /// Returns the tasks of the given kind created within `[from, to)`.
+    pub async fn list_created_between(
+        database: &Arc<Database<'static>>,
+        task_key: &str,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> RustMailerResult<Vec<TaskMetaEntity>> {
+        Ok(
+            range_by_primary_key_impl::<TaskMetaEntity, _>(database, timestamp_key_range(from, to))
+                .await?
+                .into_iter()
+                .filter(|t| t.task_key == task_key)
+                .collect(),
+        )
+    }
+
+    /// Stops the given tasks if they are still scheduled, returning how many were stopped.
+    pub async fn bulk_stop(
+        database: &Arc<Database<'static>>,
+        task_ids: Vec<u64>,
+        reason: String,
+    ) -> RustMailerResult<usize> {
+        Self::bulk_update(
+            database,
+            task_ids,
+            |task| task.status == TaskStatus::Scheduled,
+            move |task| {
+                task.status = TaskStatus::Stopped;
+                task.stopped_reason = Some(reason.clone());
+            },
+        )
+        .await
+    }
+
+    /// Reschedules the given tasks to run immediately with a fresh retry count, if their
+    /// status is still one of `statuses`. Returns how many were rescheduled.
+    pub async fn bulk_reschedule(
+        database: &Arc<Database<'static>>,
+        task_ids: Vec<u64>,
+        statuses: Vec<TaskStatus>,
+    ) -> RustMailerResult<usize> {
+        Self::bulk_update(
+            database,
+            task_ids,
+            move |task| statuses.contains(&task.status),
+            |task| {
+                task.status = TaskStatus::Scheduled;
+                task.stopped_reason = None;
+                task.retry_count = None;
+                task.next_run = utc_now!();
+            },
+        )
+        .await
+    }
+
+    /// Pauses the given tasks if they are still scheduled, keeping their schedule and
+    /// retry count. Returns how many were paused.
+    pub async fn bulk_pause(
+        database: &Arc<Database<'static>>,
+        task_ids: Vec<u64>,
+    ) -> RustMailerResult<usize> {
+        Self::bulk_update(
+            database,
+            task_ids,
+            |task| task.status == TaskStatus::Scheduled,
+            |task| {
+                task.status = TaskStatus::Stopped;
+                task.stopped_reason = Some(PAUSED_REASON.into());
+            },
+        )
+        .await
+    }
+
+    /// Schedules the given paused tasks again. They run at their scheduled time, or
+    /// immediately if it has passed. Returns how many were resumed.
+    pub async fn bulk_resume(
+        database: &Arc<Database<'static>>,
+        task_ids: Vec<u64>,
+    ) -> RustMailerResult<usize> {
+        Self::bulk_update(database, task_ids, TaskMetaEntity::is_paused, |task| {
+            task.status = TaskStatus::Scheduled;
+            task.stopped_reason = None;
+        })
+        .await
+    }
+
+    /// Moves the next run of the given tasks to `next_run` if they are still scheduled or
+    /// paused. Returns how many were moved.
+    pub async fn bulk_set_next_run(
+        database: &Arc<Database<'static>>,
+        task_ids: Vec<u64>,
+        next_run: i64,
+    ) -> RustMailerResult<usize> {
+        Self::bulk_update(
+            database,
+            task_ids,
+            |task| task.status == TaskStatus::Scheduled || task.is_paused(),
+            move |task| task.next_run = next_run,
+        )
+        .await
+    }
+
+    async fn bulk_update(
+        database: &Arc<Database<'static>>,
+        task_ids: Vec<u64>,
+        selects: impl Fn(&TaskMetaEntity) -> bool + Clone + Send + 'static,
+        update: impl Fn(&mut TaskMetaEntity) + Clone + Send + 'static,
+    ) -> RustMailerResult<usize> {
+        let mut updated = 0;
+        // Keep write transactions short, as the scheduler polls the same table.
+        for chunk in task_ids.chunks(100) {
+            let chunk = chunk.to_vec();
+            let selects = selects.clone();
+            let update = update.clone();
+            let batch = batch_update_impl(
+                database,
+                move |rw| Self::select_where(rw, &chunk, selects),
+                move |targets| {
+                    Ok(targets
+                        .iter()
+                        .map(|current| {
+                            let mut task = current.clone();
+                            update(&mut task);
+                            task.updated_at = utc_now!();
+                            (current.clone(), task)
+                        })
+                        .collect())
+                },
+            )
+            .await?;
+            updated += batch.len();
+            TaskJournal::record(database, batch.iter().map(|t| t.id).collect()).await;
+        }
+        Ok(updated)
+    }
+
+    /// Loads the given tasks, skipping any that `selects` no longer accepts, such as tasks
+    /// whose status changed in the meantime.
+    fn select_where(
+        rw: &RwTransaction,
+        task_ids: &[u64],
+        selects: impl Fn(&TaskMetaEntity) -> bool,
+    ) -> RustMailerResult<Vec<TaskMetaEntity>> {
+        let mut tasks = Vec::with_capacity(task_ids.len());
+        for task_id in task_ids {
+            let task: Option<TaskMetaEntity> = rw
+                .get()
+                .secondary(TaskMetaEntityKey::id, *task_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            if let Some(task) = task.filter(|t| selects(t)) {
+                tasks.push(task);
+            }
+        }
+        Ok(tasks)
+    }
+
     pub async fn heartbeat(
         database: &Arc<Database<'static>>,
         task_id: u64,
@@ -278,6 +523,8 @@ impl NativeDbTaskStore {
             },
         )
         .await?;
+        // Heartbeats are not journaled: they only matter to the running process, and
+        // `restore` reschedules the running tasks on startup anyway.
         Ok(())
     }
 
@@ -289,6 +536,7 @@ impl NativeDbTaskStore {
             TaskStatus::Running.code(),
         )
         .await?;
+        let task_ids: Vec<u64> = running_tasks.iter().map(|t| t.id).collect();
         let rw = database
             .rw_transaction()
             .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
@@ -320,6 +568,7 @@ impl NativeDbTaskStore {
         }
         rw.commit()
             .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        TaskJournal::record(database, task_ids).await;
         tracing::info!("finished task restore.");
         Ok(())
     }
@@ -346,7 +595,10 @@ impl NativeDbTaskStore {
         task: TaskMeta,
     ) -> RustMailerResult<()> {
         let entity: TaskMetaEntity = task.into();
-        insert_impl(database, entity).await
+        let task_id = entity.id;
+        insert_impl(database, entity).await?;
+        TaskJournal::record(database, vec![task_id]).await;
+        Ok(())
     }
 
     pub async fn store_many(
@@ -354,7 +606,10 @@ impl NativeDbTaskStore {
         tasks: Vec<TaskMeta>,
     ) -> RustMailerResult<()> {
         let batch: Vec<TaskMetaEntity> = tasks.into_iter().map(Into::into).collect();
-        batch_insert_impl(database, batch).await
+        let task_ids = batch.iter().map(|t| t.id).collect();
+        batch_insert_impl(database, batch).await?;
+        TaskJournal::record(database, task_ids).await;
+        Ok(())
     }
 
     pub async fn get_paginated_tasks_by_status(
@@ -448,10 +703,32 @@ impl TaskStore for NativeDbTaskStore {
         )
         .await?;
 
+        if !is_success && next_run.is_none() {
+            // Retries are exhausted: keep the task in the dead-letter queue.
+            let failed = Self::get(&db, task_id).await.ok().flatten().map(Into::into);
+            tokio::spawn(capture_failed_task(failed));
+        }
+
+        if is_success && task.task_key == SmtpTask::TASK_KEY {
+            let task_params = task.task_params.clone();
+            tokio::spawn(async move {
+                if let Ok(smtp_task) = serde_json::from_str::<SmtpTask>(&task_params) {
+                    DeliveryEvent::record_task(&smtp_task, task_id, DeliveryEventKind::Sent, None)
+                        .await;
+                }
+            });
+        }
+
         if !is_success && task.task_key == SmtpTask::TASK_KEY {
             let task_params = task.task_params.clone();
             tokio::spawn(async move {
                 if let Ok(smtp_task) = serde_json::from_str::<SmtpTask>(&task_params) {
+                    let kind = match next_run {
+                        Some(_) => DeliveryEventKind::Retrying,
+                        None => DeliveryEventKind::Failed,
+                    };
+                    DeliveryEvent::record_task(&smtp_task, task_id, kind, last_error.clone())
+                        .await;
                     if let Ok(true) =
                         EventHookTask::is_watching_email_sending_error(smtp_task.account_id).await
                     {
@@ -490,6 +767,17 @@ impl TaskStore for NativeDbTaskStore {
         Ok(())
     }
 
+    async fn defer_task(
+        &self,
+        task_id: u64,
+        reason: Option<String>,
+        last_duration_ms: Option<usize>,
+        next_run: i64,
+    ) -> RustMailerResult<()> {
+        let db = self.store.clone();
+        Self::defer(&db, task_id, reason, last_duration_ms, next_run).await
+    }
+
     async fn heartbeat(&self, task_id: u64) -> RustMailerResult<()> {
         let db = self.store.clone();
         Self::heartbeat(&db, task_id).await
@@ -4,7 +4,7 @@
 
 use std::sync::LazyLock;
 
-use crate::modules::database::ModelsAdapter;
+use crate::modules::database::{key::CompositeKey, migration::SchemaVersion, ModelsAdapter};
 use crate::modules::scheduler::model::{Retry, TaskMeta, TaskStatus};
 use native_db::*;
 use native_model::native_model;
@@ -13,9 +13,13 @@ use serde::{Deserialize, Serialize};
 
 pub mod meta;
 
+/// The `stopped_reason` of paused tasks.
+pub const PAUSED_REASON: &str = "Paused";
+
 pub static TASK_MODELS: LazyLock<Models> = LazyLock::new(|| {
     let mut adapter = ModelsAdapter::new();
     adapter.register_model::<TaskMetaEntity>();
+    adapter.register_model::<SchemaVersion>();
     adapter.models
 });
 
@@ -52,7 +56,16 @@ pub struct TaskMetaEntity {
 
 impl TaskMetaEntity {
     fn pk(&self) -> String {
-        format!("{}_{}", self.created_at, self.id)
+        CompositeKey::new()
+            .segment(self.created_at)
+            .segment(self.id)
+            .build()
+    }
+
+    /// Whether the task was paused, to be resumed later with its schedule. Paused tasks
+    /// are stopped tasks marked with `PAUSED_REASON`.
+    pub fn is_paused(&self) -> bool {
+        self.status == TaskStatus::Stopped && self.stopped_reason.as_deref() == Some(PAUSED_REASON)
     }
 
     pub fn status(&self) -> u32 {
@@ -60,11 +73,14 @@ impl TaskMetaEntity {
     }
 
     pub fn typed_status(&self) -> String {
-        format!("{}_{}", &self.task_key, self.status.code())
+        Self::status_filter_key(&self.task_key, self.status.clone())
     }
 
     pub fn status_filter_key(task_key: &str, status: TaskStatus) -> String {
-        format!("{}_{}", task_key, status.code())
+        CompositeKey::new()
+            .segment(task_key)
+            .segment(status.code())
+            .build()
     }
 }
 
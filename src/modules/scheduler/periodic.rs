@@ -3,8 +3,11 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::{common::signal::SIGNAL_MANAGER, error::RustMailerResult};
-use std::{future::Future, time::Duration};
-use tokio::{sync::oneshot, time::MissedTickBehavior};
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::{
+    sync::{oneshot, Notify},
+    time::MissedTickBehavior,
+};
 use tracing::{info, warn};
 
 pub struct PeriodicTask {
@@ -41,6 +44,47 @@ impl PeriodicTask {
         enable_cancel: bool,
         run_immediately: bool,
     ) -> TaskHandle
+    where
+        T: Fn(Option<u64>) -> F + Send + Sync + 'static,
+        F: Future<Output = RustMailerResult<()>> + Send + 'static,
+    {
+        self.spawn(task, param, interval, enable_cancel, run_immediately, None)
+    }
+
+    /// Like `start`, but the task also runs as soon as `trigger` is notified, without waiting
+    /// for the next tick. Runs never overlap, and the interval restarts after a triggered run.
+    pub fn start_with_trigger<F, T>(
+        self,
+        task: T,
+        param: Option<u64>,
+        interval: Duration,
+        enable_cancel: bool,
+        run_immediately: bool,
+        trigger: Arc<Notify>,
+    ) -> TaskHandle
+    where
+        T: Fn(Option<u64>) -> F + Send + Sync + 'static,
+        F: Future<Output = RustMailerResult<()>> + Send + 'static,
+    {
+        self.spawn(
+            task,
+            param,
+            interval,
+            enable_cancel,
+            run_immediately,
+            Some(trigger),
+        )
+    }
+
+    fn spawn<F, T>(
+        self,
+        task: T,
+        param: Option<u64>,
+        interval: Duration,
+        enable_cancel: bool,
+        run_immediately: bool,
+        trigger: Option<Arc<Notify>>,
+    ) -> TaskHandle
     where
         T: Fn(Option<u64>) -> F + Send + Sync + 'static,
         F: Future<Output = RustMailerResult<()>> + Send + 'static,
@@ -76,6 +120,18 @@ impl PeriodicTask {
                             },
                         }
                     }
+                    // only enabled if trigger is Some
+                    _ = async {
+                        match trigger {
+                            Some(ref trigger) => trigger.notified().await,
+                            None => futures::future::pending().await,
+                        }
+                    } => {
+                        if let Err(e) = task(param).await {
+                            warn!("Task '{}' failed: {:?}", name_clone, e);
+                        }
+                        interval.reset();
+                    }
                     // only enabled if cancel_receiver is Some
                     _ = async {
                         if let Some(ref mut rx) = cancel_receiver {
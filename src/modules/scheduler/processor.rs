@@ -2,11 +2,15 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::metrics::{
+    RUSTMAILER_TASK_QUEUE_WAIT_SECONDS, RUSTMAILER_TASK_RUN_DURATION_SECONDS,
+};
 use crate::modules::scheduler::handlers::TaskHandlers;
 use crate::modules::scheduler::{
     model::TaskMeta,
     updater::{self, TaskStatusUpdater},
 };
+use crate::utc_now;
 use std::{
     future::Future,
     sync::Arc,
@@ -112,6 +116,12 @@ impl TaskProcessor {
             let _permit = permit;
             let task_id = task.id;
             let task_key = task.task_key.clone();
+            // Tasks become due at `next_run`, which also covers retries and deferrals.
+            let waited_ms = (utc_now!() - task.next_run).max(0);
+            RUSTMAILER_TASK_QUEUE_WAIT_SECONDS
+                .with_label_values(&[&task_key])
+                .observe(waited_ms as f64 / 1000.0);
+            let started = Instant::now();
             let result = Self::monitor_task_execution(
                 handlers.execute(*task.clone()),
                 task_id,
@@ -120,6 +130,9 @@ impl TaskProcessor {
                 status_updater.clone(),
             )
             .await;
+            RUSTMAILER_TASK_RUN_DURATION_SECONDS
+                .with_label_values(&[&task_key])
+                .observe(started.elapsed().as_secs_f64());
 
             status_updater
                 .queue(updater::UpdateRequest::ExecutionResult(
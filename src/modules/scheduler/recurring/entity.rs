@@ -0,0 +1,462 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+use time_tz::{timezones, Tz};
+
+use crate::modules::account::migration::AccountModel;
+use crate::modules::database::batch::WriteBatch;
+use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::{
+    delete_impl, insert_impl, list_all_impl, paginate_secondary_scan_impl, secondary_find_impl,
+    update_impl,
+};
+use crate::modules::error::code::ErrorCode;
+use crate::modules::error::RustMailerResult;
+use crate::modules::overview::memory::check_metadata_capacity;
+use crate::modules::rest::response::DataPage;
+use crate::modules::scheduler::cron::CronSchedule;
+use crate::modules::scheduler::recurring::payload::{
+    RecipientSource, RecurringSendCreateRequest, RecurringSendUpdateRequest,
+};
+use crate::modules::smtp::campaign::payload::{CampaignCreateRequest, CampaignRecipient};
+use crate::modules::smtp::campaign::send::campaign_template;
+use crate::modules::smtp::request::{EmailAddress, SendControl};
+use crate::{id, raise_error, utc_now};
+
+const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// An email sent on a cron schedule, such as a weekly digest.
+///
+/// Every run sends the template to the current recipients as a new campaign, whose id is
+/// kept in `last_run`. Runs missed while the server was down are not caught up: the
+/// schedule resumes with a single run, then continues from the current time.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 26, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct RecurringSend {
+    /// Unique identifier of the recurring send.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// The account sending the emails.
+    #[secondary_key]
+    pub account_id: u64,
+    /// The name of the recurring send, and of the campaign of each run.
+    pub name: String,
+    /// A description of the recurring send.
+    pub description: Option<String>,
+    /// The cron expression of the schedule.
+    pub cron: String,
+    /// The timezone the cron expression is evaluated in.
+    pub timezone: String,
+    /// The template rendered for every recipient.
+    pub template_id: u64,
+    /// The sender's address, if not the account's.
+    pub from: Option<EmailAddress>,
+    /// The recipients of each run.
+    pub recipients: RecipientSource,
+    /// Maximum number of emails sent per minute by each run, if throttled.
+    pub messages_per_minute: Option<u32>,
+    /// Options applied to every email.
+    pub send_control: Option<SendControl>,
+    /// Whether the schedule is active.
+    pub enabled: bool,
+    /// Time (Unix epoch milliseconds) of the next run, if enabled.
+    pub next_run_at: Option<i64>,
+    /// The outcome of the most recent run.
+    pub last_run: Option<RecurringSendRun>,
+    /// Number of runs so far, including failed ones.
+    pub run_count: u64,
+    /// Timestamp of when the recurring send was created (in Unix epoch milliseconds).
+    pub created_at: i64,
+    /// Timestamp of the last update (in Unix epoch milliseconds).
+    pub updated_at: i64,
+}
+
+/// The outcome of a run of a recurring send.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct RecurringSendRun {
+    /// Time (Unix epoch milliseconds) the run started.
+    pub started_at: i64,
+    /// The campaign created by the run, unless it failed.
+    pub campaign_id: Option<u64>,
+    /// Number of emails queued.
+    pub queued: u64,
+    /// Number of recipients whose email could not be queued.
+    pub failed_recipients: u64,
+    /// Why the run failed, e.g. the recipient URL could not be fetched.
+    pub error: Option<String>,
+}
+
+impl RecurringSend {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    pub async fn new(
+        account_id: u64,
+        request: RecurringSendCreateRequest,
+    ) -> RustMailerResult<Self> {
+        AccountModel::get(account_id).await?;
+        let now = utc_now!();
+        let mut send = Self {
+            id: id!(64),
+            account_id,
+            name: request.name,
+            description: request.description,
+            cron: request.cron,
+            timezone: request
+                .timezone
+                .unwrap_or_else(|| DEFAULT_TIMEZONE.to_string()),
+            template_id: request.template_id,
+            from: request.from,
+            recipients: request.recipients,
+            messages_per_minute: request.messages_per_minute,
+            send_control: request.send_control,
+            enabled: request.enabled.unwrap_or(true),
+            next_run_at: None,
+            last_run: None,
+            run_count: 0,
+            created_at: now,
+            updated_at: now,
+        };
+        send.validate()?;
+        campaign_template(account_id, send.template_id).await?;
+        send.next_run_at = send.next_run(now)?;
+        Ok(send)
+    }
+
+    fn validate(&self) -> RustMailerResult<()> {
+        let mut errors = Vec::new();
+        if self.name.trim().is_empty() {
+            errors.push("'name' must not be empty".to_string());
+        }
+        if let Err(error) = self.schedule() {
+            errors.push(error.to_string());
+        }
+        let mut request = self.campaign_request(Vec::new());
+        match (&self.recipients.recipients, &self.recipients.url) {
+            (Some(recipients), None) => {
+                request.recipients = recipients.clone();
+                errors.extend(request.recipient_errors());
+            }
+            (None, Some(url)) => {
+                if !(url.starts_with("https://") || url.starts_with("http://")) {
+                    errors.push(format!("Recipient URL '{url}' must be an http(s) URL"));
+                }
+            }
+            _ => errors.push("Exactly one of 'recipients' and 'url' must be set".into()),
+        }
+        errors.extend(request.option_errors());
+        if request.dry_run() {
+            errors.push("'send_control.dry_run' is not supported for recurring sends".into());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(raise_error!(
+                format!("{:#?}", errors),
+                ErrorCode::InvalidParameter
+            ))
+        }
+    }
+
+    /// The parsed cron expression and timezone.
+    pub fn schedule(&self) -> RustMailerResult<(CronSchedule, &'static Tz)> {
+        let schedule = CronSchedule::parse(&self.cron)?;
+        let timezone = timezones::get_by_name(&self.timezone).ok_or_else(|| {
+            raise_error!(
+                format!("Invalid timezone: {}", self.timezone),
+                ErrorCode::InvalidParameter
+            )
+        })?;
+        Ok((schedule, timezone))
+    }
+
+    /// Time of the first run after `now`, or `None` if the recurring send is disabled.
+    pub fn next_run(&self, now: i64) -> RustMailerResult<Option<i64>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let (schedule, timezone) = self.schedule()?;
+        schedule.next_after(now, timezone).map(Some).ok_or_else(|| {
+            raise_error!(
+                format!("The cron expression '{}' never matches", self.cron),
+                ErrorCode::InvalidParameter
+            )
+        })
+    }
+
+    /// The campaign a run sends to `recipients`.
+    pub fn campaign_request(&self, recipients: Vec<CampaignRecipient>) -> CampaignCreateRequest {
+        CampaignCreateRequest {
+            name: Some(self.name.clone()),
+            template_id: self.template_id,
+            from: self.from.clone(),
+            recipients,
+            messages_per_minute: self.messages_per_minute,
+            start_at: None,
+            send_control: self.send_control.clone(),
+        }
+    }
+
+    pub async fn save(self) -> RustMailerResult<()> {
+        check_metadata_capacity()?;
+        insert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    pub async fn find(id: u64) -> RustMailerResult<Option<RecurringSend>> {
+        secondary_find_impl(DB_MANAGER.meta_db(), RecurringSendKey::id, id).await
+    }
+
+    /// Returns the recurring send if it exists and belongs to `account_id`.
+    pub async fn get(account_id: u64, id: u64) -> RustMailerResult<RecurringSend> {
+        Self::find(id)
+            .await?
+            .filter(|send| send.account_id == account_id)
+            .ok_or_else(|| {
+                raise_error!(
+                    format!("Recurring send id='{id}' not found for account {account_id}."),
+                    ErrorCode::ResourceNotFound
+                )
+            })
+    }
+
+    /// Enabled recurring sends whose next run is due at `now`.
+    pub async fn list_due(now: i64) -> RustMailerResult<Vec<RecurringSend>> {
+        let sends: Vec<RecurringSend> = list_all_impl(DB_MANAGER.meta_db()).await?;
+        Ok(sends
+            .into_iter()
+            .filter(|send| send.enabled && send.next_run_at.is_some_and(|at| at <= now))
+            .collect())
+    }
+
+    pub async fn update(
+        account_id: u64,
+        id: u64,
+        request: RecurringSendUpdateRequest,
+    ) -> RustMailerResult<RecurringSend> {
+        let current = Self::get(account_id, id).await?;
+        let mut updated = current.clone();
+        if let Some(name) = request.name {
+            updated.name = name;
+        }
+        if request.description.is_some() {
+            updated.description = request.description;
+        }
+        if let Some(cron) = request.cron {
+            updated.cron = cron;
+        }
+        if let Some(timezone) = request.timezone {
+            updated.timezone = timezone;
+        }
+        if let Some(template_id) = request.template_id {
+            campaign_template(account_id, template_id).await?;
+            updated.template_id = template_id;
+        }
+        if request.from.is_some() {
+            updated.from = request.from;
+        }
+        if let Some(recipients) = request.recipients {
+            updated.recipients = recipients;
+        }
+        if request.messages_per_minute.is_some() {
+            updated.messages_per_minute = request.messages_per_minute;
+        }
+        if request.send_control.is_some() {
+            updated.send_control = request.send_control;
+        }
+        if let Some(enabled) = request.enabled {
+            updated.enabled = enabled;
+        }
+        updated.validate()?;
+        let now = utc_now!();
+        updated.next_run_at = updated.next_run(now)?;
+        updated.updated_at = now;
+
+        Self::modify(id, move |current| {
+            Ok(RecurringSend {
+                last_run: current.last_run.clone(),
+                run_count: current.run_count,
+                ..updated
+            })
+        })
+        .await
+    }
+
+    /// Moves the schedule of the recurring send to its next run.
+    pub async fn set_next_run(id: u64, next_run_at: Option<i64>) -> RustMailerResult<()> {
+        Self::modify(id, move |current| {
+            Ok(RecurringSend {
+                next_run_at,
+                ..current.clone()
+            })
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Records the outcome of a run.
+    pub async fn record_run(id: u64, run: RecurringSendRun) -> RustMailerResult<()> {
+        Self::modify(id, move |current| {
+            Ok(RecurringSend {
+                last_run: Some(run),
+                run_count: current.run_count + 1,
+                ..current.clone()
+            })
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn modify(
+        id: u64,
+        f: impl FnOnce(&RecurringSend) -> RustMailerResult<RecurringSend> + Send + 'static,
+    ) -> RustMailerResult<RecurringSend> {
+        update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .secondary::<RecurringSend>(RecurringSendKey::id, id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!("The recurring send with id={id} that you want to modify was not found."),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            f,
+        )
+        .await
+    }
+
+    pub async fn remove(id: u64) -> RustMailerResult<()> {
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<RecurringSend>(RecurringSendKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!("The recurring send with id={id} that you want to delete was not found."),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await
+    }
+
+    pub async fn paginate_list_account(
+        account_id: u64,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<RecurringSend>> {
+        paginate_secondary_scan_impl(
+            DB_MANAGER.meta_db(),
+            page,
+            page_size,
+            desc,
+            RecurringSendKey::account_id,
+            account_id,
+        )
+        .await
+        .map(DataPage::from)
+    }
+
+    /// Adds the removal of all recurring sends of an account to `batch`.
+    pub fn stage_remove_account_recurring_sends(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let sends: Vec<RecurringSend> = rw
+                .scan()
+                .secondary::<RecurringSend>(RecurringSendKey::account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(sends)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weekly_digest() -> RecurringSend {
+        RecurringSend {
+            name: "Weekly digest".into(),
+            cron: "0 9 * * MON".into(),
+            timezone: DEFAULT_TIMEZONE.into(),
+            template_id: 1,
+            recipients: RecipientSource {
+                url: Some("https://example.com/subscribers".into()),
+                ..Default::default()
+            },
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validates_recurring_sends() {
+        assert!(weekly_digest().validate().is_ok());
+        // 2025-01-15 12:00:00 UTC, a Wednesday; the next Monday 09:00 is 4 days and 21
+        // hours later.
+        let now = 1_736_942_400_000;
+        assert_eq!(
+            weekly_digest().next_run(now).unwrap(),
+            Some(now + (4 * 24 + 21) * 3_600_000)
+        );
+        let disabled = RecurringSend {
+            enabled: false,
+            ..weekly_digest()
+        };
+        assert_eq!(disabled.next_run(now).unwrap(), None);
+
+        for invalid in [
+            RecurringSend {
+                cron: "0 9 * *".into(),
+                ..weekly_digest()
+            },
+            RecurringSend {
+                timezone: "Mars/Olympus".into(),
+                ..weekly_digest()
+            },
+            RecurringSend {
+                recipients: RecipientSource::default(),
+                ..weekly_digest()
+            },
+            RecurringSend {
+                recipients: RecipientSource {
+                    recipients: Some(Vec::new()),
+                    ..Default::default()
+                },
+                ..weekly_digest()
+            },
+            RecurringSend {
+                recipients: RecipientSource {
+                    url: Some("ftp://example.com".into()),
+                    ..Default::default()
+                },
+                ..weekly_digest()
+            },
+            RecurringSend {
+                send_control: Some(SendControl {
+                    dry_run: Some(true),
+                    ..Default::default()
+                }),
+                ..weekly_digest()
+            },
+        ] {
+            assert!(invalid.validate().is_err(), "{invalid:?}");
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::HashMap;
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::smtp::{
+    campaign::payload::CampaignRecipient,
+    request::{EmailAddress, SendControl},
+};
+
+/// Where the recipients of each run of a recurring send come from. Exactly one of
+/// `recipients` and `url` must be set.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct RecipientSource {
+    /// A fixed list of recipients, at most 10,000.
+    pub recipients: Option<Vec<CampaignRecipient>>,
+    /// A URL fetched with `GET` at every run, such as the subscriber list of a digest in
+    /// your application. It must return a JSON array of recipients, objects with a `to`
+    /// address and optional `variables`, in the format of `recipients`.
+    pub url: Option<String>,
+    /// Headers sent with the request to `url`, such as `Authorization`.
+    pub headers: Option<HashMap<String, String>>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct RecurringSendCreateRequest {
+    /// A name for the recurring send, used as the name of the campaign of each run.
+    /// Maximum length is 256 characters.
+    #[oai(validator(max_length = "256"))]
+    pub name: String,
+    /// A description of the recurring send (optional).
+    pub description: Option<String>,
+    /// When to send, as a standard 5-field cron expression (`minute hour day-of-month
+    /// month day-of-week`), e.g. `0 9 * * MON` every Monday at 09:00. The macros
+    /// `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are also accepted.
+    pub cron: String,
+    /// The timezone the cron expression is evaluated in, e.g. `Europe/Berlin`. Defaults
+    /// to `UTC`.
+    pub timezone: Option<String>,
+    /// The template rendered for every recipient. It must be public or belong to the
+    /// sending account.
+    pub template_id: u64,
+    /// The sender's address. If not set, the account's address is used.
+    pub from: Option<EmailAddress>,
+    /// The recipients of each run.
+    pub recipients: RecipientSource,
+    /// Maximum number of emails sent per minute by each run (optional), as for campaigns.
+    pub messages_per_minute: Option<u32>,
+    /// Options applied to every email (optional), as for campaigns. `dry_run` is not
+    /// supported.
+    pub send_control: Option<SendControl>,
+    /// Whether the schedule is active. Defaults to `true`.
+    pub enabled: Option<bool>,
+}
+
+/// Changes to a recurring send. Fields that are not set are left unchanged.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct RecurringSendUpdateRequest {
+    #[oai(validator(max_length = "256"))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub cron: Option<String>,
+    pub timezone: Option<String>,
+    pub template_id: Option<u64>,
+    pub from: Option<EmailAddress>,
+    pub recipients: Option<RecipientSource>,
+    pub messages_per_minute: Option<u32>,
+    pub send_control: Option<SendControl>,
+    pub enabled: Option<bool>,
+}
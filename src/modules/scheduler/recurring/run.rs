@@ -0,0 +1,105 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use tracing::{error, info};
+
+use crate::{
+    modules::{
+        common::http::HttpClient,
+        error::{code::ErrorCode, RustMailerResult},
+        scheduler::recurring::{
+            entity::{RecurringSend, RecurringSendRun},
+            payload::RecipientSource,
+        },
+        smtp::campaign::{entity::Campaign, payload::CampaignRecipient, send::launch_campaign},
+    },
+    raise_error, utc_now,
+};
+
+impl RecipientSource {
+    /// The current recipients: the fixed list, or the list returned by the URL.
+    pub async fn resolve(&self) -> RustMailerResult<Vec<CampaignRecipient>> {
+        if let Some(recipients) = &self.recipients {
+            return Ok(recipients.clone());
+        }
+        let Some(url) = &self.url else {
+            return Ok(Vec::new());
+        };
+        let json = HttpClient::new(None)
+            .await?
+            .get_json(url, self.headers.as_ref())
+            .await?;
+        serde_json::from_value(json).map_err(|e| {
+            raise_error!(
+                format!("The recipient URL {url} did not return a JSON array of recipients: {e}"),
+                ErrorCode::InvalidParameter
+            )
+        })
+    }
+}
+
+/// Sends a run of the recurring send as a new campaign and records its outcome. The
+/// schedule is not changed.
+pub async fn run_recurring_send(send: &RecurringSend) -> RustMailerResult<Campaign> {
+    let started_at = utc_now!();
+    let result = match send.recipients.resolve().await {
+        Ok(recipients) => launch_campaign(send.account_id, send.campaign_request(recipients)).await,
+        Err(e) => Err(e),
+    };
+    let run = match &result {
+        Ok(campaign) => RecurringSendRun {
+            started_at,
+            campaign_id: Some(campaign.id),
+            queued: campaign.queued,
+            failed_recipients: campaign.failures.len() as u64,
+            error: None,
+        },
+        Err(e) => RecurringSendRun {
+            started_at,
+            error: Some(e.to_string()),
+            ..Default::default()
+        },
+    };
+    RecurringSend::record_run(send.id, run).await?;
+    result
+}
+
+/// Runs the recurring sends that are due.
+///
+/// The schedule of a recurring send is moved to its next run before sending, so that a
+/// crash during a run does not send it twice.
+pub async fn run_due_recurring_sends() -> RustMailerResult<()> {
+    let now = utc_now!();
+    for send in RecurringSend::list_due(now).await? {
+        let next_run_at = match send.next_run(now) {
+            Ok(next_run_at) => next_run_at,
+            Err(e) => {
+                error!(
+                    "Account {}: Recurring send '{}' ({}) has an invalid schedule: {:#?}",
+                    send.account_id, send.name, send.id, e
+                );
+                None
+            }
+        };
+        if let Err(e) = RecurringSend::set_next_run(send.id, next_run_at).await {
+            // Without moving the schedule, the run would be repeated on every tick.
+            error!(
+                "Failed to schedule the next run of recurring send {}, skipping this run: {:#?}",
+                send.id, e
+            );
+            continue;
+        }
+        match run_recurring_send(&send).await {
+            Ok(campaign) => info!(
+                "Account {}: Recurring send '{}' ({}) queued {} emails in campaign {}",
+                send.account_id, send.name, send.id, campaign.queued, campaign.id
+            ),
+            Err(e) => error!(
+                "Account {}: Recurring send '{}' ({}) failed: {:#?}",
+                send.account_id, send.name, send.id, e
+            ),
+        }
+    }
+    Ok(())
+}
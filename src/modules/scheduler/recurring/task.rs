@@ -0,0 +1,26 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Duration;
+
+use crate::modules::{
+    context::RustMailTask,
+    scheduler::{periodic::PeriodicTask, recurring::run::run_due_recurring_sends},
+};
+
+const TASK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Runs the recurring sends of all accounts when their schedule is due.
+pub struct RecurringSendTask;
+
+impl RustMailTask for RecurringSendTask {
+    fn start() {
+        let periodic_task = PeriodicTask::new("recurring-send-runner");
+
+        let task =
+            move |_ctx: Option<u64>| Box::pin(async move { run_due_recurring_sends().await });
+
+        periodic_task.start(task, None, TASK_INTERVAL, false, false);
+    }
+}
@@ -10,6 +10,8 @@ pub struct TaskResult {
     pub last_duration_ms: usize,
     pub retry_count: usize,
     pub next_run: Option<i64>,
+    /// Whether the task deferred itself to `next_run` rather than failing.
+    pub deferred: bool,
     pub result: RustMailerResult<()>,
 }
 
@@ -22,6 +24,7 @@ impl TaskResult {
             last_duration_ms,
             retry_count: Default::default(),
             next_run: None,
+            deferred: false,
         }
     }
 
@@ -33,6 +36,7 @@ impl TaskResult {
             last_duration_ms,
             retry_count: Default::default(),
             next_run: None,
+            deferred: false,
         }
     }
 
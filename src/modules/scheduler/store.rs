@@ -25,6 +25,16 @@ pub trait TaskStore {
         next_run: Option<i64>,
     ) -> impl Future<Output = RustMailerResult<()>> + Send;
 
+    /// Puts a task that deferred itself back in the schedule at `next_run`, without
+    /// counting the attempt as a failure.
+    fn defer_task(
+        &self,
+        task_id: u64,
+        reason: Option<String>,
+        last_duration_ms: Option<usize>,
+        next_run: i64,
+    ) -> impl Future<Output = RustMailerResult<()>> + Send;
+
     fn heartbeat(&self, task_id: u64) -> impl Future<Output = RustMailerResult<()>> + Send;
 
     fn set_task_stopped(
@@ -3,16 +3,36 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::error::code::ErrorCode;
-use crate::modules::error::RustMailerResult;
+use crate::modules::error::{RustMailerError, RustMailerResult};
 use crate::modules::scheduler::model::TaskMeta;
 use crate::modules::scheduler::retry::{RetryPolicy, RetryStrategy};
 use crate::raise_error;
+use dashmap::DashMap;
 use serde::{de::DeserializeOwned, Serialize};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::LazyLock;
 
 pub type TaskFuture = Pin<Box<dyn Future<Output = RustMailerResult<()>> + Send>>;
 
+/// Run times requested by tasks that deferred themselves, keyed by task id.
+static DEFERRALS: LazyLock<DashMap<u64, i64>> = LazyLock::new(DashMap::new);
+
+/// Postpones the running task `task_id` until `run_at` (Unix epoch milliseconds).
+///
+/// The returned error must be returned from [`Task::run`]. The attempt is not counted
+/// against the task's retry policy and the task goes back to `Scheduled`, with `reason`
+/// recorded as its last error.
+pub fn defer_task(task_id: u64, run_at: i64, reason: String) -> RustMailerError {
+    DEFERRALS.insert(task_id, run_at);
+    raise_error!(reason, ErrorCode::TooManyRequest)
+}
+
+/// Takes the run time requested by [`defer_task`] for this task, if any.
+pub fn take_deferral(task_id: u64) -> Option<i64> {
+    DEFERRALS.remove(&task_id).map(|(_, run_at)| run_at)
+}
+
 pub trait Task: Serialize + DeserializeOwned + 'static {
     /// A unique identifier for this task.
     ///
@@ -13,7 +13,9 @@ use crate::{
         error::code::ErrorCode,
         scheduler::{
             context::TaskContext,
+            handlers::TaskHandlers,
             nativedb::{meta::NativeDbTaskStore, TASK_MODELS},
+            task::defer_task,
         },
     },
     raise_error,
@@ -44,6 +46,20 @@ async fn test_retry_policy() {
     tokio::time::sleep(std::time::Duration::from_secs(50)).await;
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DeferTestTask;
+
+#[tokio::test]
+async fn deferral_does_not_count_as_retry() {
+    let mut handlers = TaskHandlers::new();
+    handlers.register::<DeferTestTask>();
+
+    let result = handlers.execute(DeferTestTask.new_meta()).await;
+    assert!(result.deferred);
+    assert_eq!(result.retry_count, 0);
+    assert_eq!(result.next_run, Some(42_000));
+}
+
 #[test]
 fn test1() {
     println!("task-{}", generate_token!(48).to_lowercase())
@@ -77,3 +93,12 @@ impl Task for RetryTestTask {
         })
     }
 }
+
+impl Task for DeferTestTask {
+    const TASK_KEY: &'static str = "defer_test_task_key";
+    const TASK_QUEUE: &'static str = "defer_test_queue";
+
+    fn run(self, task_id: u64) -> super::task::TaskFuture {
+        Box::pin(async move { Err(defer_task(task_id, 42_000, "Rate limited".into())) })
+    }
+}
@@ -79,6 +79,14 @@ impl TaskStatusUpdater {
     where
         T: TaskStore + Send + Clone + 'static,
     {
+        if let (true, Some(next_run)) = (result.deferred, result.next_run) {
+            let reason = result.result.err().map(|e| e.to_string());
+            return task_store
+                .defer_task(task.id, reason, Some(result.last_duration_ms), next_run)
+                .await
+                .map_err(|e| format!("Failed to defer task {}: {:#?}", task.id, e));
+        }
+
         // Determine if the task execution was successful
         let is_success = result.is_success();
         let last_duration_ms = result.last_duration_ms;
@@ -132,6 +132,23 @@ pub struct Settings {
     )]
     pub rustmailer_event_hook_workers: usize,
 
+    #[clap(
+        long,
+        default_value = "1000",
+        env,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Maximum number of events kept in memory while they wait to be dispatched to event hooks"
+    )]
+    pub rustmailer_event_channel_capacity: u32,
+
+    #[clap(
+        long,
+        default_value = "block",
+        env,
+        help = "What to do with new events when the event channel is full (options: block, drop-oldest, spill)"
+    )]
+    pub rustmailer_event_channel_overflow_policy: EventOverflowPolicy,
+
     /// Enable ANSI logs (default: false)
     #[clap(long, default_value = "true", env, help = "Enable ANSI formatted logs")]
     pub rustmailer_ansi_logs: bool,
@@ -375,6 +392,29 @@ pub struct Settings {
     )]
     pub rustmailer_metadata_snapshot_interval_secs: u64,
 
+    #[clap(
+        long,
+        env,
+        default_value = "true",
+        help = "Journal task queue changes between snapshots in memory mode and replay them on startup"
+    )]
+    pub rustmailer_task_journal_enabled: bool,
+
+    #[clap(
+        long,
+        env,
+        default_value = "false",
+        help = "Flush each task journal write to disk before continuing (slower, survives power loss)"
+    )]
+    pub rustmailer_task_journal_fsync: bool,
+
+    #[clap(
+        long,
+        env,
+        help = "Maximum size in bytes of the in-memory metadata (memory mode only). Once reached, creating accounts and templates is rejected."
+    )]
+    pub rustmailer_metadata_memory_limit: Option<u64>,
+
     #[clap(
         long,
         env,
@@ -393,6 +433,314 @@ pub struct Settings {
         value_parser = clap::value_parser!(u16).range(1..)
     )]
     pub rustmailer_sync_concurrency: Option<u16>,
+
+    #[clap(
+        long,
+        env,
+        default_value = "false",
+        help = "Run pending database migrations without writing any changes, log what would be applied, then exit"
+    )]
+    pub rustmailer_migration_dry_run: bool,
+
+    #[clap(
+        long,
+        env,
+        default_value = "false",
+        help = "Restore the latest valid snapshot or backup if the metadata fails the startup integrity check, instead of starting in safe mode"
+    )]
+    pub rustmailer_integrity_auto_restore: bool,
+
+    #[clap(
+        long,
+        env,
+        help = "Serve overview and analytics queries from an in-memory copy of the metadata and task databases, refreshed at this interval in seconds (minimum: 10). Disabled by default.",
+        value_parser = clap::value_parser!(u64).range(10..)
+    )]
+    pub rustmailer_read_replica_refresh_secs: Option<u64>,
+
+    #[clap(
+        long,
+        env,
+        help = "Size in MiB of the memory arenas reserved by the mimalloc allocator at a time"
+    )]
+    pub rustmailer_mimalloc_arena_reserve_mib: Option<usize>,
+
+    #[clap(
+        long,
+        env,
+        help = "Delay in milliseconds before mimalloc returns freed memory to the OS (-1 disables purging)"
+    )]
+    pub rustmailer_mimalloc_purge_delay_ms: Option<i64>,
+
+    #[clap(
+        long,
+        env,
+        help = "Endpoint of an S3-compatible object storage used as second tier of the disk cache, e.g. https://s3.us-east-1.amazonaws.com"
+    )]
+    pub rustmailer_disk_cache_s3_endpoint: Option<String>,
+
+    #[clap(
+        long,
+        env,
+        help = "Bucket used as second tier of the disk cache. Tiering is enabled when both endpoint and bucket are set."
+    )]
+    pub rustmailer_disk_cache_s3_bucket: Option<String>,
+
+    #[clap(
+        long,
+        env,
+        default_value = "us-east-1",
+        help = "Region of the disk cache object storage"
+    )]
+    pub rustmailer_disk_cache_s3_region: String,
+
+    #[clap(long, env, help = "Access key of the disk cache object storage")]
+    pub rustmailer_disk_cache_s3_access_key: Option<String>,
+
+    #[clap(long, env, help = "Secret key of the disk cache object storage")]
+    pub rustmailer_disk_cache_s3_secret_key: Option<String>,
+
+    #[clap(
+        long,
+        env,
+        help = "Prefix prepended to the object names of the disk cache, e.g. rustmailer/cache/"
+    )]
+    pub rustmailer_disk_cache_s3_prefix: Option<String>,
+
+    #[clap(
+        long,
+        env,
+        help = "Maximum local size in MiB of cached outgoing messages; the oldest are evicted first"
+    )]
+    pub rustmailer_disk_cache_outgoing_quota_mib: Option<u64>,
+
+    #[clap(
+        long,
+        env,
+        help = "Maximum local size in MiB of cached attachments; the least recently used are evicted first"
+    )]
+    pub rustmailer_disk_cache_attachment_quota_mib: Option<u64>,
+
+    #[clap(
+        long,
+        env,
+        help = "Maximum local size in MiB of cached message content; the least recently used are evicted first"
+    )]
+    pub rustmailer_disk_cache_content_quota_mib: Option<u64>,
+
+    #[clap(
+        long,
+        env,
+        help = "Base64 encoded 256-bit key enabling AES-256-GCM encryption of cached message content and attachments at rest"
+    )]
+    pub rustmailer_disk_cache_encryption_key: Option<String>,
+
+    #[clap(
+        long,
+        env,
+        help = "Path of a file holding the base64 encoded disk cache encryption key, e.g. written by a KMS or secrets manager agent. Alternative to rustmailer_disk_cache_encryption_key"
+    )]
+    pub rustmailer_disk_cache_encryption_key_file: Option<String>,
+
+    #[clap(
+        long,
+        env,
+        default_value = "90",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Number of daily metric points kept for the overview; older days are rolled up into weekly points"
+    )]
+    pub rustmailer_metrics_daily_retention_days: u32,
+
+    #[clap(
+        long,
+        env,
+        default_value = "104",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Number of weekly metric points kept after daily points are rolled up"
+    )]
+    pub rustmailer_metrics_weekly_retention_weeks: u32,
+
+    #[clap(
+        long,
+        env,
+        default_value = "4",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Maximum number of concurrent IMAP-heavy API requests (remote listing, message and attachment fetch) per account"
+    )]
+    pub rustmailer_imap_request_concurrency: u32,
+
+    #[clap(
+        long,
+        env,
+        default_value = "10",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Seconds an IMAP-heavy API request waits for a free slot of its account before failing as busy"
+    )]
+    pub rustmailer_imap_request_queue_timeout_secs: u64,
+
+    #[clap(
+        long,
+        env,
+        default_value = "true",
+        help = "Keep an IMAP IDLE connection open for accounts whose server supports it, so new and changed emails are synced right away instead of on the next polling cycle"
+    )]
+    pub rustmailer_imap_idle_enabled: bool,
+
+    #[clap(
+        long,
+        env,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Maximum number of open IMAP connections across all accounts, including pooled and IDLE connections. New connections wait for a free slot once the limit is reached. Unlimited if unset"
+    )]
+    pub rustmailer_max_imap_connections: Option<u32>,
+
+    #[clap(
+        long,
+        env,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Maximum number of open SMTP connections across all accounts and MTAs. New connections wait for a free slot once the limit is reached. Unlimited if unset"
+    )]
+    pub rustmailer_max_smtp_connections: Option<u32>,
+
+    #[clap(
+        long,
+        env,
+        default_value = "10",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Seconds a new IMAP or SMTP connection waits for a free slot when the global connection limit is reached before failing"
+    )]
+    pub rustmailer_connection_admission_timeout_secs: u64,
+
+    #[clap(
+        long,
+        env,
+        default_value = "80",
+        value_parser = clap::value_parser!(u8).range(1..=100),
+        help = "Log a warning when the process uses this percentage of its open file descriptor limit (RLIMIT_NOFILE)"
+    )]
+    pub rustmailer_fd_warning_percent: u8,
+
+    #[clap(
+        long,
+        env,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Pause an account's sync once authentication has failed without a successful sync for this many days. Disabled if unset"
+    )]
+    pub rustmailer_auto_pause_auth_failure_days: Option<u32>,
+
+    #[clap(
+        long,
+        env,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Pause an account's sync once no access token scoped to it has been used for this many days. Disabled if unset"
+    )]
+    pub rustmailer_auto_pause_unused_days: Option<u32>,
+
+    #[clap(
+        long,
+        default_value = "",
+        env,
+        help = "Additional phrases flagged by the content linter, on top of the built-in list (comma-separated, case-insensitive)",
+        value_parser = ValueParser::new(|s: &str| -> Result<HashSet<String>, String> {
+            let set: HashSet<String> = s.split(',')
+                .map(|phrase| phrase.trim().to_lowercase())
+                .filter(|phrase| !phrase.is_empty())
+                .collect();
+            Ok(set)
+        })
+    )]
+    pub rustmailer_lint_blocked_phrases: HashSet<String>,
+
+    #[clap(
+        long,
+        default_value = "",
+        env,
+        help = "Seed mailbox addresses, e.g. at Gmail, Outlook and Yahoo, that template seed tests are sent to when the request does not list recipients (comma-separated)",
+        value_parser = ValueParser::new(|s: &str| -> Result<HashSet<String>, String> {
+            let set: HashSet<String> = s.split(',')
+                .map(|address| address.trim().to_lowercase())
+                .filter(|address| !address.is_empty())
+                .collect();
+            Ok(set)
+        })
+    )]
+    pub rustmailer_template_seed_addresses: HashSet<String>,
+
+    #[clap(
+        long,
+        default_value = "false",
+        env,
+        help = "Serves Mozilla autoconfig (/mail/config-v1.1.xml) and Microsoft Autodiscover (/autodiscover/autodiscover.xml) for the domains of enabled IMAP/SMTP accounts, so mail clients pointed at this host configure themselves with the servers of the domain. Every address of a served domain gets the same answer, taken from the oldest account of the domain, and anyone can read it"
+    )]
+    pub rustmailer_autodiscover_enabled: bool,
+
+    #[clap(
+        long,
+        default_value = "false",
+        env,
+        help = "Serves the component status page without authentication at /api/status/components, with the severity of each component but without measurements, for public status pages and uptime monitors"
+    )]
+    pub rustmailer_public_status_page_enabled: bool,
+
+    #[clap(
+        long,
+        default_value = "30",
+        env,
+        value_parser = clap::value_parser!(u32).range(1..=365),
+        help = "Days the delivery events of sent emails (queued, sent, bounced, opened, clicked...) are kept for their delivery timeline"
+    )]
+    pub rustmailer_delivery_timeline_retention_days: u32,
+
+    #[clap(
+        long,
+        default_value = "1440",
+        env,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Minutes an OAuth2 authorization flow can be completed after its authorization URL was created. Abandoned flows are removed afterwards"
+    )]
+    pub rustmailer_oauth2_pending_ttl_minutes: u32,
+
+    #[clap(
+        long,
+        env,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Remove OAuth2 tokens that could not be refreshed for this many days; their accounts must be authorized again. Tokens of deleted accounts are always removed. Disabled if unset"
+    )]
+    pub rustmailer_oauth2_stale_token_days: Option<u32>,
+
+    #[clap(
+        long,
+        default_value = "false",
+        env,
+        help = "Enables the fault injection API, which adds latency, dropped connections and error responses to IMAP, SMTP and event hook operations. Meant for staging environments"
+    )]
+    pub rustmailer_fault_injection_enabled: bool,
+
+    #[clap(
+        long,
+        default_value = "7",
+        env,
+        value_parser = clap::value_parser!(u32).range(1..=365),
+        help = "Days the outcome of every event hook delivery (status code, latency, response snippet and the delivered event) is kept in the hook delivery log, from which events can be replayed"
+    )]
+    pub rustmailer_hook_delivery_log_retention_days: u32,
+
+    #[clap(
+        long,
+        default_value = "7",
+        env,
+        value_parser = clap::value_parser!(u32).range(1..=365),
+        help = "Days before an access token expires at which the AccessTokenExpiring event is emitted and an audit entry recorded"
+    )]
+    pub rustmailer_access_token_expiry_warning_days: u32,
+
+    #[cfg(feature = "test-harness")]
+    #[clap(
+        long,
+        env,
+        help = "Path of a JSON fixture seeding the in-memory IMAP and SMTP servers of the test harness. When set, accounts and MTAs connect to these servers instead of the network. Never set it in production"
+    )]
+    pub rustmailer_test_harness_fixture: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
@@ -421,6 +769,32 @@ impl fmt::Display for CompressionAlgorithm {
     }
 }
 
+/// How the event channel handles new events once it holds `rustmailer_event_channel_capacity`
+/// events.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum EventOverflowPolicy {
+    /// Producers wait until the dispatcher makes room; no event is lost.
+    #[clap(name = "block")]
+    Block,
+    /// The oldest queued event is discarded to make room for the new one.
+    #[clap(name = "drop-oldest")]
+    DropOldest,
+    /// Further events are appended to a file in the data directory and dispatched once the
+    /// queue has drained, including after a restart.
+    #[clap(name = "spill")]
+    Spill,
+}
+
+impl fmt::Display for EventOverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventOverflowPolicy::Block => write!(f, "block"),
+            EventOverflowPolicy::DropOldest => write!(f, "drop-oldest"),
+            EventOverflowPolicy::Spill => write!(f, "spill"),
+        }
+    }
+}
+
 impl Settings {
     #[cfg(test)]
     fn new_for_test() -> Self {
@@ -443,7 +817,7 @@ impl Settings {
             rustmailer_metadata_cache_size: None,
             rustmailer_task_queue_cache_size: None,
             rustmailer_envelope_cache_size: None,
-            rustmailer_enable_access_token: false,
+            rustmailer_enable_access_token: true,
             rustmailer_email_tracking_enabled: false,
             rustmailer_bind_ip: Default::default(),
             rustmailer_cors_origins: Default::default(),
@@ -454,6 +828,8 @@ impl Settings {
             rustmailer_grpc_compression: CompressionAlgorithm::None,
             rustmailer_http_compression_enabled: true,
             rustmailer_event_hook_workers: 10,
+            rustmailer_event_channel_capacity: 1000,
+            rustmailer_event_channel_overflow_policy: EventOverflowPolicy::Block,
             rustmailer_max_email_content_length: 10000,
             rustmailer_cleanup_interval_hours: 72,
             rustmailer_backup_dir: None,
@@ -461,8 +837,50 @@ impl Settings {
             rustmailer_email_tracking_url: "http://localhost:15630/email-track".to_string(),
             rustmailer_metadata_memory_mode_enabled: false,
             rustmailer_metadata_snapshot_interval_secs: 900,
+            rustmailer_task_journal_enabled: true,
+            rustmailer_task_journal_fsync: false,
+            rustmailer_metadata_memory_limit: None,
             rustmailer_oauth2_success_redirect: None,
             rustmailer_sync_concurrency: Some(5),
+            rustmailer_migration_dry_run: false,
+            rustmailer_integrity_auto_restore: false,
+            rustmailer_read_replica_refresh_secs: None,
+            rustmailer_mimalloc_arena_reserve_mib: None,
+            rustmailer_mimalloc_purge_delay_ms: None,
+            rustmailer_disk_cache_s3_endpoint: None,
+            rustmailer_disk_cache_s3_bucket: None,
+            rustmailer_disk_cache_s3_region: "us-east-1".into(),
+            rustmailer_disk_cache_s3_access_key: None,
+            rustmailer_disk_cache_s3_secret_key: None,
+            rustmailer_disk_cache_s3_prefix: None,
+            rustmailer_disk_cache_outgoing_quota_mib: None,
+            rustmailer_disk_cache_attachment_quota_mib: None,
+            rustmailer_disk_cache_content_quota_mib: None,
+            rustmailer_disk_cache_encryption_key: None,
+            rustmailer_disk_cache_encryption_key_file: None,
+            rustmailer_metrics_daily_retention_days: 90,
+            rustmailer_metrics_weekly_retention_weeks: 104,
+            rustmailer_imap_request_concurrency: 4,
+            rustmailer_imap_request_queue_timeout_secs: 10,
+            rustmailer_imap_idle_enabled: true,
+            rustmailer_max_imap_connections: None,
+            rustmailer_max_smtp_connections: None,
+            rustmailer_connection_admission_timeout_secs: 10,
+            rustmailer_fd_warning_percent: 80,
+            rustmailer_auto_pause_auth_failure_days: None,
+            rustmailer_auto_pause_unused_days: None,
+            rustmailer_lint_blocked_phrases: Default::default(),
+            rustmailer_template_seed_addresses: Default::default(),
+            rustmailer_autodiscover_enabled: false,
+            rustmailer_public_status_page_enabled: false,
+            rustmailer_delivery_timeline_retention_days: 30,
+            rustmailer_oauth2_pending_ttl_minutes: 1440,
+            rustmailer_oauth2_stale_token_days: None,
+            rustmailer_fault_injection_enabled: false,
+            rustmailer_hook_delivery_log_retention_days: 7,
+            rustmailer_access_token_expiry_warning_days: 7,
+            #[cfg(feature = "test-harness")]
+            rustmailer_test_harness_fixture: None,
         }
     }
 }
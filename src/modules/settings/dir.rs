@@ -72,13 +72,20 @@ impl DataDirManager {
     }
 
     pub fn find_latest_snapshot_for(&self, db_prefix: &str) -> Option<PathBuf> {
+        self.list_snapshots_for(db_prefix).into_iter().next()
+    }
+
+    /// All snapshots of `db_prefix` in the data directory, newest first.
+    pub fn list_snapshots_for(&self, db_prefix: &str) -> Vec<PathBuf> {
         let pattern = format!("{}.*.snapshot", db_prefix);
         let pattern_path = self.root_dir.join(&pattern);
-        let pattern_str = pattern_path.to_str()?;
+        let Some(pattern_str) = pattern_path.to_str() else {
+            return Vec::new();
+        };
 
         let mut snapshot_files = Vec::new();
-        for entry in glob::glob(pattern_str).ok()? {
-            if let Ok(path) = entry {
+        if let Ok(entries) = glob::glob(pattern_str) {
+            for path in entries.flatten() {
                 snapshot_files.push(path);
             }
         }
@@ -97,7 +104,36 @@ impl DataDirManager {
             .collect();
 
         dated_files.sort_by(|a, b| b.0.cmp(&a.0));
-        dated_files.into_iter().next().map(|(_, path)| path)
+        dated_files.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// All journal files of `db_prefix` in the data directory with their generation,
+    /// oldest first.
+    pub fn list_journals_for(&self, db_prefix: &str) -> Vec<(u64, PathBuf)> {
+        let pattern = format!("{}.*.journal", db_prefix);
+        let pattern_path = self.root_dir.join(&pattern);
+        let Some(pattern_str) = pattern_path.to_str() else {
+            return Vec::new();
+        };
+
+        let mut journals: Vec<(u64, PathBuf)> = match glob::glob(pattern_str) {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|path| {
+                    let generation = path
+                        .file_name()?
+                        .to_str()?
+                        .strip_prefix(&format!("{}.", db_prefix))?
+                        .strip_suffix(".journal")?
+                        .parse()
+                        .ok()?;
+                    Some((generation, path))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        journals.sort_by_key(|(generation, _)| *generation);
+        journals
     }
 
     pub fn find_oldest_snapshot_for(&self, db_prefix: &str) -> Option<SnapshotScanResult> {
@@ -198,6 +234,10 @@ mod tests {
 
         let latest = manager.find_latest_snapshot_for("meta.db").unwrap();
         assert!(latest.ends_with("meta.db.2025-07-03-17-04.snapshot"));
+
+        let all = manager.list_snapshots_for("meta.db");
+        assert_eq!(all.len(), 3);
+        assert!(all[2].ends_with("meta.db.2025-07-03-16-44.snapshot"));
     }
 
     #[test]
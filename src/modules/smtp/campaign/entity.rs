@@ -0,0 +1,142 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::database::batch::WriteBatch;
+use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::{
+    delete_impl, insert_impl, paginate_secondary_scan_impl, secondary_find_impl,
+};
+use crate::modules::error::code::ErrorCode;
+use crate::modules::error::RustMailerResult;
+use crate::modules::overview::memory::check_metadata_capacity;
+use crate::modules::rest::response::DataPage;
+use crate::raise_error;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 20, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct Campaign {
+    /// Unique identifier of the campaign. Its emails are sent with this value, as a
+    /// string, as `campaign_id`.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// The account sending the campaign.
+    #[secondary_key]
+    pub account_id: u64,
+    /// The name of the campaign, if provided.
+    pub name: Option<String>,
+    /// The template rendered for every recipient.
+    pub template_id: u64,
+    /// The MTA the campaign is sent through, if any.
+    pub mta: Option<u64>,
+    /// Maximum number of campaign emails sent per minute, if throttled.
+    pub messages_per_minute: Option<u32>,
+    /// Number of recipients in the request.
+    pub total_recipients: u64,
+    /// Number of emails queued for sending.
+    pub queued: u64,
+    /// Recipients whose email could not be queued.
+    pub failures: Vec<CampaignFailure>,
+    /// Time (Unix epoch milliseconds) the first email is scheduled at.
+    pub first_send_at: Option<i64>,
+    /// Time (Unix epoch milliseconds) the last email is scheduled at.
+    pub last_send_at: Option<i64>,
+    /// Timestamp of when the campaign was created (in Unix epoch milliseconds).
+    pub created_at: i64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct CampaignFailure {
+    /// The recipient address.
+    pub recipient: String,
+    /// Why the email could not be queued.
+    pub error: String,
+}
+
+impl Campaign {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    /// The `campaign_id` the emails of the campaign are sent with.
+    pub fn campaign_id(&self) -> String {
+        self.id.to_string()
+    }
+
+    pub async fn save(self) -> RustMailerResult<()> {
+        check_metadata_capacity()?;
+        insert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    pub async fn find(id: u64) -> RustMailerResult<Option<Campaign>> {
+        secondary_find_impl(DB_MANAGER.meta_db(), CampaignKey::id, id).await
+    }
+
+    /// Returns the campaign if it exists and was sent by `account_id`.
+    pub async fn get(account_id: u64, id: u64) -> RustMailerResult<Campaign> {
+        Self::find(id)
+            .await?
+            .filter(|campaign| campaign.account_id == account_id)
+            .ok_or_else(|| {
+                raise_error!(
+                    format!("Campaign id='{id}' not found for account {account_id}."),
+                    ErrorCode::ResourceNotFound
+                )
+            })
+    }
+
+    pub async fn remove(id: u64) -> RustMailerResult<()> {
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<Campaign>(CampaignKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!("The campaign with id={id} that you want to delete was not found."),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await
+    }
+
+    pub async fn paginate_list_account(
+        account_id: u64,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<Campaign>> {
+        paginate_secondary_scan_impl(
+            DB_MANAGER.meta_db(),
+            page,
+            page_size,
+            desc,
+            CampaignKey::account_id,
+            account_id,
+        )
+        .await
+        .map(DataPage::from)
+    }
+
+    /// Adds the removal of all campaigns of an account to `batch`.
+    pub fn stage_remove_account_campaigns(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let campaigns: Vec<Campaign> = rw
+                .scan()
+                .secondary::<Campaign>(CampaignKey::account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(campaigns)
+        })
+    }
+}
@@ -0,0 +1,10 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+pub mod entity;
+pub mod payload;
+pub mod send;
+#[cfg(test)]
+mod tests;
+pub mod throttle;
@@ -0,0 +1,161 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modules::{
+        error::{code::ErrorCode, RustMailerResult},
+        smtp::request::{EmailAddress, EmailHandler, SendControl},
+    },
+    raise_error, utc_now, validate_email,
+};
+
+/// Maximum number of recipients of a single campaign.
+pub const MAX_CAMPAIGN_RECIPIENTS: usize = 10_000;
+/// Maximum throttling rate of a campaign, in messages per minute.
+pub const MAX_MESSAGES_PER_MINUTE: u32 = 10_000;
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct CampaignRecipient {
+    /// The address the campaign email is sent to.
+    pub to: EmailAddress,
+    /// Template variables for this recipient (optional), in JSON format.
+    pub variables: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct CampaignCreateRequest {
+    /// A name for the campaign (optional). Maximum length is 256 characters.
+    #[oai(validator(max_length = "256"))]
+    pub name: Option<String>,
+    /// The template rendered for every recipient. It must be public or belong to the
+    /// sending account.
+    pub template_id: u64,
+    /// The sender's address. If not set, the account's address is used.
+    pub from: Option<EmailAddress>,
+    /// The recipients of the campaign, each receiving an individual email. At most 10,000.
+    pub recipients: Vec<CampaignRecipient>,
+    /// Maximum number of campaign emails sent per minute (optional), at most 10,000.
+    ///
+    /// The rate is shared with the other campaigns sent through the same MTA, if
+    /// `send_control.mta` is set, or otherwise from the same account: emails of concurrent
+    /// campaigns are scheduled one after the other rather than in parallel. If not set,
+    /// all emails are queued to be sent as soon as possible.
+    pub messages_per_minute: Option<u32>,
+    /// Time (Unix epoch milliseconds) to start sending the campaign (optional), within
+    /// the next 2 weeks. Defaults to now.
+    pub start_at: Option<i64>,
+    /// Options applied to every email of the campaign (optional).
+    ///
+    /// `send_at` and `local_send_time` are not supported, use `start_at` and
+    /// `messages_per_minute` instead. `campaign_id` is set to the id of the campaign.
+    /// With `dry_run`, every email is rendered and validated but neither the emails nor the
+    /// campaign are stored.
+    pub send_control: Option<SendControl>,
+}
+
+impl CampaignCreateRequest {
+    pub fn validate(&self) -> RustMailerResult<()> {
+        let mut errors = self.recipient_errors();
+        errors.extend(self.option_errors());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(raise_error!(
+                format!("{:#?}", errors),
+                ErrorCode::InvalidParameter
+            ))
+        }
+    }
+
+    /// Problems with the recipients of the request.
+    pub fn recipient_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.recipients.is_empty() {
+            errors.push("At least one recipient is required".into());
+        }
+        if self.recipients.len() > MAX_CAMPAIGN_RECIPIENTS {
+            errors.push(format!(
+                "A campaign can have at most {} recipients, got {}",
+                MAX_CAMPAIGN_RECIPIENTS,
+                self.recipients.len()
+            ));
+        }
+        for recipient in &self.recipients {
+            if validate_email!(&recipient.to.address).is_err() {
+                errors.push(format!(
+                    "Invalid recipient email address: {}",
+                    &recipient.to.address
+                ));
+            }
+        }
+        errors
+    }
+
+    /// Problems with the request other than its recipients.
+    pub fn option_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(from) = &self.from {
+            if validate_email!(&from.address).is_err() {
+                errors.push("Invalid 'from' email address".to_string());
+            }
+        }
+        if let Some(rate) = self.messages_per_minute {
+            if rate == 0 || rate > MAX_MESSAGES_PER_MINUTE {
+                errors.push(format!(
+                    "'messages_per_minute' must be between 1 and {}",
+                    MAX_MESSAGES_PER_MINUTE
+                ));
+            }
+        }
+        if let Some(start_at) = self.start_at {
+            if let Err(error) = EmailHandler::validate_send_at(start_at, utc_now!()) {
+                errors.push(error.replace("send_at", "start_at"));
+            }
+        }
+        if let Some(send_control) = &self.send_control {
+            if send_control.send_at.is_some() {
+                errors.push("'send_control.send_at' is not supported, use 'start_at'".into());
+            }
+            if send_control.local_send_time.is_some() {
+                errors.push("'send_control.local_send_time' is not supported for campaigns".into());
+            }
+            if send_control.campaign_id.is_some() {
+                errors.push("'send_control.campaign_id' is assigned by the campaign".into());
+            }
+            if send_control.compose_only.is_some() {
+                errors.push("'send_control.compose_only' is not supported for campaigns".into());
+            }
+            if let Err(mut send_control_errors) = send_control.validate() {
+                errors.append(&mut send_control_errors);
+            }
+        }
+        errors
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.send_control
+            .as_ref()
+            .and_then(|c| c.dry_run)
+            .unwrap_or(false)
+    }
+}
+
+/// Number of emails of a campaign in each task status.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct CampaignProgress {
+    /// Emails waiting to be sent.
+    pub scheduled: u64,
+    /// Emails being sent.
+    pub running: u64,
+    /// Emails sent successfully.
+    pub success: u64,
+    /// Emails that could not be sent after all retries.
+    pub failed: u64,
+    /// Emails cancelled before being sent.
+    pub stopped: u64,
+}
@@ -0,0 +1,244 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::{
+    id,
+    modules::{
+        account::migration::AccountModel,
+        bounce::classify::{classify_bounce, BounceStats},
+        common::auth::ClientContext,
+        database::manager::DB_MANAGER,
+        error::{code::ErrorCode, RustMailerResult},
+        scheduler::{
+            model::TaskStatus,
+            nativedb::{meta::NativeDbTaskStore, TaskMetaEntity},
+            task::Task,
+        },
+        smtp::{
+            campaign::{
+                entity::{Campaign, CampaignFailure},
+                payload::{CampaignCreateRequest, CampaignProgress},
+                throttle::{reserve, spread, ThrottleKey},
+            },
+            queue::bulk::{
+                run_bulk_task_action, BulkTaskAction, BulkTaskRequest, BulkTaskResult,
+                EmailTaskFilter,
+            },
+            request::{
+                builder::EmailBuilder,
+                new::{Recipient, SendEmailRequest},
+                task::SmtpTask,
+                SendControl, TWO_WEEKS_IN_MS,
+            },
+            template::entity::EmailTemplate,
+        },
+    },
+    raise_error, utc_now,
+};
+
+/// Returns the template if it exists and can be used by the account: it must be public or
+/// belong to the account, and public templates of a workspace are limited to its accounts.
+pub async fn campaign_template(
+    account_id: u64,
+    template_id: u64,
+) -> RustMailerResult<EmailTemplate> {
+    let template = EmailTemplate::get(template_id).await?;
+    if template
+        .account
+        .as_ref()
+        .is_some_and(|a| a.id != account_id)
+    {
+        return Err(raise_error!(
+            format!(
+                "Template id='{}' belongs to another account and cannot be used by account {}.",
+                template_id, account_id
+            ),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    if template.account.is_none() && template.workspace_id.is_some() {
+        template.check_usable_by(&AccountModel::get(account_id).await?)?;
+    }
+    Ok(template)
+}
+
+/// Queues one email per recipient of the campaign, rendered from the campaign template.
+///
+/// With `messages_per_minute`, the emails are scheduled at evenly spaced times after the
+/// emails already scheduled by other campaigns of the same account or MTA. Recipients
+/// whose email cannot be queued are recorded in the campaign's `failures` and do not
+/// stop the others.
+pub async fn launch_campaign(
+    account_id: u64,
+    request: CampaignCreateRequest,
+) -> RustMailerResult<Campaign> {
+    request.validate()?;
+    AccountModel::check_account_active(account_id, false).await?;
+    campaign_template(account_id, request.template_id).await?;
+
+    let now = utc_now!();
+    let dry_run = request.dry_run();
+    let count = request.recipients.len();
+    let start = request.start_at.unwrap_or(now);
+    let mta = request.send_control.as_ref().and_then(|c| c.mta);
+    let slots = match request.messages_per_minute {
+        Some(rate) if dry_run => spread(start, rate, count),
+        Some(rate) => {
+            let key = mta.map_or(ThrottleKey::Account(account_id), ThrottleKey::Mta);
+            reserve(key, rate, count, start, now + TWO_WEEKS_IN_MS).ok_or_else(|| {
+                raise_error!(
+                    format!(
+                        "At {} messages per minute, the {} emails of the campaign cannot all be \
+                        scheduled within the next 2 weeks.",
+                        rate, count
+                    ),
+                    ErrorCode::ExceedsLimitation
+                )
+            })?
+        }
+        None => vec![start; count],
+    };
+
+    let mut campaign = Campaign {
+        id: id!(96),
+        account_id,
+        name: request.name.clone(),
+        template_id: request.template_id,
+        mta,
+        messages_per_minute: request.messages_per_minute,
+        total_recipients: count as u64,
+        queued: 0,
+        failures: Vec::new(),
+        first_send_at: None,
+        last_send_at: None,
+        created_at: now,
+    };
+    let send_control = SendControl {
+        campaign_id: Some(campaign.campaign_id()),
+        ..request.send_control.clone().unwrap_or_default()
+    };
+
+    for (recipient, slot) in request.recipients.iter().zip(slots) {
+        // Slots that are due, or about to be, are sent right away.
+        let send_at = (slot > utc_now!() + 1000).then_some(slot);
+        let send_request = SendEmailRequest {
+            from: request.from.clone(),
+            recipients: vec![Recipient {
+                to: vec![recipient.to.clone()],
+                template_params: recipient.variables.clone(),
+                send_at,
+                ..Default::default()
+            }],
+            subject: None,
+            text: None,
+            html: None,
+            markdown: None,
+            preview: None,
+            eml: None,
+            template_id: Some(request.template_id),
+            attachments: None,
+            headers: None,
+            importance: None,
+            send_control: Some(send_control.clone()),
+        };
+        match send_request.build(account_id).await {
+            Ok(_) => {
+                let send_at = send_at.unwrap_or(now);
+                campaign.queued += 1;
+                campaign.first_send_at.get_or_insert(send_at);
+                campaign.last_send_at = Some(send_at);
+            }
+            Err(error) => campaign.failures.push(CampaignFailure {
+                recipient: recipient.to.address.clone(),
+                error: error.to_string(),
+            }),
+        }
+    }
+
+    if !dry_run {
+        campaign.clone().save().await?;
+    }
+    Ok(campaign)
+}
+
+/// The email tasks of the campaign, with their metadata.
+async fn campaign_tasks(campaign: &Campaign) -> RustMailerResult<Vec<(TaskMetaEntity, SmtpTask)>> {
+    let campaign_id = campaign.campaign_id();
+    let tasks = NativeDbTaskStore::list_created_between(
+        DB_MANAGER.tasks_db(),
+        SmtpTask::TASK_KEY,
+        Some(campaign.created_at),
+        None,
+    )
+    .await?;
+
+    let mut result = Vec::new();
+    for meta in tasks {
+        let task: SmtpTask = serde_json::from_str(&meta.task_params)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        if task.account_id == campaign.account_id
+            && task.control.as_ref().and_then(|c| c.campaign_id.as_deref())
+                == Some(campaign_id.as_str())
+        {
+            result.push((meta, task));
+        }
+    }
+    Ok(result)
+}
+
+/// Counts the email tasks of the campaign by status.
+pub async fn campaign_progress(campaign: &Campaign) -> RustMailerResult<CampaignProgress> {
+    let mut progress = CampaignProgress::default();
+    for (meta, _) in campaign_tasks(campaign).await? {
+        match meta.status {
+            TaskStatus::Scheduled => progress.scheduled += 1,
+            TaskStatus::Running => progress.running += 1,
+            TaskStatus::Success => progress.success += 1,
+            TaskStatus::Failed => progress.failed += 1,
+            TaskStatus::Stopped => progress.stopped += 1,
+            TaskStatus::Removed => {}
+        }
+    }
+    Ok(progress)
+}
+
+/// Counts the bounced recipients of the campaign by bounce category, from the delivery
+/// status notifications linked to its emails.
+pub async fn campaign_bounce_stats(campaign: &Campaign) -> RustMailerResult<BounceStats> {
+    let mut stats = BounceStats::default();
+    for (_, task) in campaign_tasks(campaign).await? {
+        for status in &task.delivery_status {
+            // Notifications linked before bounces were classified carry no category.
+            let category = status.category.or_else(|| {
+                classify_bounce(
+                    status.action.as_deref(),
+                    status.status.as_deref(),
+                    status.diagnostic_code.as_deref(),
+                )
+            });
+            if let Some(category) = category {
+                stats.record(category);
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/// Stops the emails of the campaign that have not been sent yet.
+pub async fn cancel_campaign(
+    context: &ClientContext,
+    campaign: &Campaign,
+) -> RustMailerResult<BulkTaskResult> {
+    let request = BulkTaskRequest {
+        filter: EmailTaskFilter {
+            account_ids: Some(vec![campaign.account_id]),
+            campaign_id: Some(campaign.campaign_id()),
+            created_after: Some(campaign.created_at),
+            ..Default::default()
+        },
+        dry_run: None,
+        send_at: None,
+    };
+    run_bulk_task_action(context, BulkTaskAction::Cancel, &request).await
+}
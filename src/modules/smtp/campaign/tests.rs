@@ -0,0 +1,79 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use crate::modules::smtp::{
+    campaign::{
+        payload::{CampaignCreateRequest, CampaignRecipient},
+        throttle::{reserve, spread, ThrottleKey},
+    },
+    request::{EmailAddress, SendControl},
+};
+
+fn request(addresses: &[&str]) -> CampaignCreateRequest {
+    CampaignCreateRequest {
+        name: None,
+        template_id: 1,
+        from: None,
+        recipients: addresses
+            .iter()
+            .map(|address| CampaignRecipient {
+                to: EmailAddress {
+                    name: None,
+                    address: address.to_string(),
+                },
+                variables: None,
+            })
+            .collect(),
+        messages_per_minute: None,
+        start_at: None,
+        send_control: None,
+    }
+}
+
+#[test]
+fn spreads_send_times_evenly() {
+    assert_eq!(spread(1_000, 60, 3), vec![1_000, 2_000, 3_000]);
+    // Rounding does not accumulate over the campaign.
+    let slots = spread(0, 7, 8);
+    assert_eq!(slots[7], 60_000);
+}
+
+#[test]
+fn reserves_slots_after_earlier_campaigns() {
+    let key = ThrottleKey::Mta(u64::MAX);
+    let first = reserve(key, 60, 2, 10_000, i64::MAX).unwrap();
+    assert_eq!(first, vec![10_000, 11_000]);
+    // A second campaign of the same sender starts after the first one.
+    let second = reserve(key, 60, 2, 10_000, i64::MAX).unwrap();
+    assert_eq!(second, vec![12_000, 13_000]);
+    // Other senders are not affected.
+    let other = reserve(ThrottleKey::Account(u64::MAX), 60, 1, 10_000, i64::MAX).unwrap();
+    assert_eq!(other, vec![10_000]);
+}
+
+#[test]
+fn rejects_reservations_past_the_deadline() {
+    let key = ThrottleKey::Mta(u64::MAX - 1);
+    assert!(reserve(key, 1, 10, 0, 5 * 60_000).is_none());
+    // Nothing was reserved by the rejected attempt.
+    assert_eq!(reserve(key, 1, 1, 0, 5 * 60_000).unwrap(), vec![0]);
+}
+
+#[test]
+fn validates_campaign_request() {
+    assert!(request(&["jane@example.com"]).validate().is_ok());
+    assert!(request(&[]).validate().is_err());
+    assert!(request(&["not-an-address"]).validate().is_err());
+
+    let mut throttled = request(&["jane@example.com"]);
+    throttled.messages_per_minute = Some(0);
+    assert!(throttled.validate().is_err());
+
+    let mut with_campaign_id = request(&["jane@example.com"]);
+    with_campaign_id.send_control = Some(SendControl {
+        campaign_id: Some("spring".into()),
+        ..Default::default()
+    });
+    assert!(with_campaign_id.validate().is_err());
+}
@@ -0,0 +1,49 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::sync::LazyLock;
+
+use dashmap::DashMap;
+
+/// The sender whose rate a campaign is throttled against.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ThrottleKey {
+    Account(u64),
+    Mta(u64),
+}
+
+/// Time (Unix epoch milliseconds) from which the next campaign email of each sender may
+/// be scheduled.
+static NEXT_SLOTS: LazyLock<DashMap<ThrottleKey, i64>> = LazyLock::new(DashMap::new);
+
+/// Spreads `count` send times evenly at `messages_per_minute`, starting at `start`.
+pub fn spread(start: i64, messages_per_minute: u32, count: usize) -> Vec<i64> {
+    let rate = messages_per_minute.max(1) as i64;
+    (0..count as i64)
+        .map(|i| start + i * 60_000 / rate)
+        .collect()
+}
+
+/// Reserves `count` send times for `key`, no earlier than `start` and after the emails
+/// already scheduled for the same sender.
+///
+/// Returns `None`, without reserving anything, if the last send time would be after
+/// `deadline`.
+pub fn reserve(
+    key: ThrottleKey,
+    messages_per_minute: u32,
+    count: usize,
+    start: i64,
+    deadline: i64,
+) -> Option<Vec<i64>> {
+    let mut next = NEXT_SLOTS.entry(key).or_insert(start);
+    let first = (*next).max(start);
+    let slots = spread(first, messages_per_minute, count + 1);
+    let (last, slots) = slots.split_last()?;
+    if slots.last().is_some_and(|slot| *slot > deadline) {
+        return None;
+    }
+    *next = *last;
+    Some(slots.to_vec())
+}
@@ -14,6 +14,9 @@ use tokio_rustls::client::TlsStream;
 pub enum RustMailSmtpClient {
     Plain(SmtpClient<TcpStream>),
     Tls(SmtpClient<TlsStream<TcpStream>>),
+    /// A connection to the in-memory SMTP server of the test harness.
+    #[cfg(feature = "test-harness")]
+    InMemory(SmtpClient<tokio::io::DuplexStream>),
 }
 
 pub(crate) trait Sender {
@@ -39,6 +42,11 @@ impl Sender for RustMailSmtpClient {
                 .noop()
                 .await
                 .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpCommandFailed)),
+            #[cfg(feature = "test-harness")]
+            RustMailSmtpClient::InMemory(smtp_client) => smtp_client
+                .noop()
+                .await
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpCommandFailed)),
         }
     }
 
@@ -52,6 +60,11 @@ impl Sender for RustMailSmtpClient {
                 .rset()
                 .await
                 .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpCommandFailed)),
+            #[cfg(feature = "test-harness")]
+            RustMailSmtpClient::InMemory(smtp_client) => smtp_client
+                .rset()
+                .await
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpCommandFailed)),
         }
     }
 
@@ -65,6 +78,11 @@ impl Sender for RustMailSmtpClient {
                 .send(message)
                 .await
                 .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpCommandFailed)),
+            #[cfg(feature = "test-harness")]
+            RustMailSmtpClient::InMemory(smtp_client) => smtp_client
+                .send(message)
+                .await
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpCommandFailed)),
         }
     }
 
@@ -79,6 +97,11 @@ impl Sender for RustMailSmtpClient {
                     .capabilities(host, false)
                     .await
                     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpCommandFailed))?,
+                #[cfg(feature = "test-harness")]
+                RustMailSmtpClient::InMemory(smtp_client) => smtp_client
+                    .capabilities(host, false)
+                    .await
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpCommandFailed))?,
             };
         Ok(response.capabilities)
     }
@@ -2,7 +2,7 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use crate::modules::cache::imap::migration::EmailEnvelopeV3;
+use crate::modules::cache::imap::migration::EmailEnvelopeV7;
 use scraper::{Html, Selector};
 use time::{macros::format_description, OffsetDateTime};
 use time_tz::timezones;
@@ -50,7 +50,7 @@ impl BodyComposer {
     pub fn generate_html(
         original_html: &str,
         reply_content: &str,
-        envelope: &EmailEnvelopeV3,
+        envelope: &EmailEnvelopeV7,
         timezone_name: &str,
         reply: bool,
     ) -> String {
@@ -161,7 +161,7 @@ impl BodyComposer {
     pub fn generate_text(
         original_text: &str,
         reply_content: &str,
-        envelope: &EmailEnvelopeV3,
+        envelope: &EmailEnvelopeV7,
         timezone_name: &str,
         reply: bool,
     ) -> String {
@@ -230,7 +230,7 @@ mod tests {
         modules::{
             cache::imap::{
                 mailbox::{EmailFlag, EnvelopeFlag},
-                migration::EmailEnvelopeV3,
+                migration::EmailEnvelopeV7,
             },
             common::Addr,
         },
@@ -289,7 +289,7 @@ mod tests {
 
         let reply_content = "Thanks for your message!";
 
-        let envelope = EmailEnvelopeV3 {
+        let envelope = EmailEnvelopeV7 {
             account_id: 0,
             mailbox_id: 0,
             mailbox_name: "inbox_001".to_string(),
@@ -332,6 +332,11 @@ mod tests {
             received: None,
             mid: None,
             labels: vec![],
+            importance: None,
+            received_chain: None,
+            authentication_results: None,
+            parse_error: None,
+            raw_headers: None,
         };
 
         let result = BodyComposer::generate_html(
@@ -349,7 +354,7 @@ mod tests {
         let original_text = "Hello,\nThis is a test email.\nRegards,\nJohn";
         let reply_content = "Hi John,\nThanks for your email!";
 
-        let envelope = EmailEnvelopeV3 {
+        let envelope = EmailEnvelopeV7 {
             from: Some(Addr {
                 name: Some("John Doe".to_string()),
                 address: Some("john@example.com".to_string()),
@@ -381,6 +386,11 @@ mod tests {
             received: None,
             mid: None,
             labels: vec![],
+            importance: None,
+            received_chain: None,
+            authentication_results: None,
+            parse_error: None,
+            raw_headers: None,
         };
 
         let result = BodyComposer::generate_text(
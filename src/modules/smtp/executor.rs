@@ -2,6 +2,7 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::context::executors::PoolStats;
 use crate::modules::smtp::client::Sender;
 use crate::modules::{error::RustMailerResult, smtp::manager::SmtpClientManager};
 use bb8::Pool;
@@ -16,6 +17,10 @@ impl SmtpExecutor {
         Self { pool }
     }
 
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.state().into()
+    }
+
     pub async fn send_email<'x>(&self, message: impl IntoMessage<'x>) -> RustMailerResult<()> {
         let mut client = self.pool.get().await?;
         client.send_email(message).await
@@ -0,0 +1,451 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::{Enum, Object};
+use scraper::{Html, Node};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modules::{
+        account::migration::AccountModel,
+        error::{code::ErrorCode, RustMailerResult},
+        settings::cli::SETTINGS,
+        smtp::{
+            request::{new::SendEmailRequest, parser::EmlData},
+            template::{entity::EmailTemplate, render::Templates},
+        },
+    },
+    raise_error,
+};
+
+/// Phrases commonly scored by spam filters, checked in addition to the configured ones.
+const DEFAULT_BLOCKED_PHRASES: &[&str] = &[
+    "100% free",
+    "act now",
+    "cash bonus",
+    "click here",
+    "double your income",
+    "earn extra cash",
+    "no credit check",
+    "risk-free",
+    "winner",
+    "you have been selected",
+];
+/// An HTML body with images and fewer words than this is considered image-only.
+const IMAGE_ONLY_MAX_WORDS: usize = 20;
+/// Link density is only checked from this many links on.
+const MIN_LINKS_FOR_RATIO: usize = 3;
+/// Minimum number of words of text per link.
+const MIN_WORDS_PER_LINK: usize = 20;
+
+/// How serious a lint finding is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum LintSeverity {
+    /// Likely to hurt deliverability, but the email can be sent.
+    Warning,
+    /// The email is rejected when content policy enforcement is enabled.
+    Error,
+}
+
+/// The deliverability checks run by the content linter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum LintRule {
+    /// An HTML body is sent without a plain-text alternative.
+    MissingTextAlternative,
+    /// The HTML body consists of images with little or no text.
+    ImageOnlyBody,
+    /// The body contains many links compared to its amount of text.
+    ExcessiveLinks,
+    /// The subject or body contains a blocked phrase.
+    BlockedPhrase,
+    /// A campaign email has neither a `List-Unsubscribe` header nor an unsubscribe link.
+    MissingUnsubscribe,
+    /// A template variable is missing from the parameters, or a placeholder was left unrendered.
+    BrokenTemplateVariable,
+}
+
+impl LintRule {
+    pub fn severity(&self) -> LintSeverity {
+        match self {
+            LintRule::MissingTextAlternative
+            | LintRule::ExcessiveLinks
+            | LintRule::BlockedPhrase => LintSeverity::Warning,
+            LintRule::ImageOnlyBody
+            | LintRule::MissingUnsubscribe
+            | LintRule::BrokenTemplateVariable => LintSeverity::Error,
+        }
+    }
+}
+
+/// A problem found by the content linter.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct LintWarning {
+    /// The check that produced the finding.
+    pub rule: LintRule,
+    /// How serious the finding is.
+    pub severity: LintSeverity,
+    /// Human-readable description of the problem.
+    pub message: String,
+    /// Index of the recipient in `recipients` whose rendered email has the problem.
+    /// Only set for template-based emails, which are rendered per recipient.
+    pub recipient_index: Option<u32>,
+}
+
+impl LintWarning {
+    fn new(rule: LintRule, message: String) -> Self {
+        Self {
+            rule,
+            severity: rule.severity(),
+            message,
+            recipient_index: None,
+        }
+    }
+}
+
+/// The result of linting an email before sending.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct ContentLintReport {
+    /// `false` if any finding has `Error` severity.
+    pub passed: bool,
+    /// All findings, in the order they were found.
+    pub warnings: Vec<LintWarning>,
+}
+
+impl ContentLintReport {
+    pub fn new(warnings: Vec<LintWarning>) -> Self {
+        Self {
+            passed: !warnings.iter().any(|w| w.severity == LintSeverity::Error),
+            warnings,
+        }
+    }
+
+    /// Fails with [`ErrorCode::ContentPolicyViolation`] listing the error-level findings.
+    pub fn enforce(&self) -> RustMailerResult<()> {
+        if self.passed {
+            return Ok(());
+        }
+        let errors: Vec<&str> = self
+            .warnings
+            .iter()
+            .filter(|w| w.severity == LintSeverity::Error)
+            .map(|w| w.message.as_str())
+            .collect();
+        Err(raise_error!(
+            format!("Email violates the content policy: {}", errors.join("; ")),
+            ErrorCode::ContentPolicyViolation
+        ))
+    }
+}
+
+/// The rendered content of an email.
+#[derive(Clone, Debug, Default)]
+pub struct LintContent {
+    pub subject: Option<String>,
+    pub text: Option<String>,
+    pub html: Option<String>,
+}
+
+/// Checks rendered emails for common deliverability problems.
+#[derive(Clone, Debug, Default)]
+pub struct ContentLinter {
+    /// Lowercased phrases to flag in subjects and bodies.
+    blocked_phrases: Vec<String>,
+    /// Whether the email belongs to a campaign and needs an unsubscribe option.
+    campaign: bool,
+    /// Whether a `List-Unsubscribe` header is set.
+    list_unsubscribe: bool,
+}
+
+impl ContentLinter {
+    pub fn for_request(request: &SendEmailRequest) -> Self {
+        let mut blocked_phrases: Vec<String> = DEFAULT_BLOCKED_PHRASES
+            .iter()
+            .map(|p| p.to_string())
+            .chain(SETTINGS.rustmailer_lint_blocked_phrases.iter().cloned())
+            .collect();
+        blocked_phrases.sort();
+        blocked_phrases.dedup();
+        Self {
+            blocked_phrases,
+            campaign: request
+                .send_control
+                .as_ref()
+                .is_some_and(|c| c.campaign_id.is_some()),
+            list_unsubscribe: request.headers.as_ref().is_some_and(|headers| {
+                headers
+                    .keys()
+                    .any(|k| k.eq_ignore_ascii_case("List-Unsubscribe"))
+            }),
+        }
+    }
+
+    pub fn lint(&self, content: &LintContent) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        let text = content.text.as_deref().filter(|t| !t.trim().is_empty());
+        let html = content.html.as_deref().filter(|h| !h.trim().is_empty());
+        let stats = html.map(HtmlStats::parse);
+
+        if html.is_some() && text.is_none() {
+            warnings.push(LintWarning::new(
+                LintRule::MissingTextAlternative,
+                "The HTML body has no plain-text alternative".into(),
+            ));
+        }
+
+        if let Some(stats) = &stats {
+            let words = stats.text.split_whitespace().count();
+            if stats.images > 0 && words < IMAGE_ONLY_MAX_WORDS {
+                warnings.push(LintWarning::new(
+                    LintRule::ImageOnlyBody,
+                    format!(
+                        "The HTML body has {} image(s) but only {words} word(s) of text",
+                        stats.images
+                    ),
+                ));
+            }
+        }
+
+        // Link density is measured on the HTML body if there is one.
+        let (links, words) = match (&stats, text) {
+            (Some(stats), _) => (stats.links, stats.text.split_whitespace().count()),
+            (None, Some(text)) => (
+                text.matches("http://").count() + text.matches("https://").count(),
+                text.split_whitespace().count(),
+            ),
+            (None, None) => (0, 0),
+        };
+        if links >= MIN_LINKS_FOR_RATIO && words < links * MIN_WORDS_PER_LINK {
+            warnings.push(LintWarning::new(
+                LintRule::ExcessiveLinks,
+                format!(
+                    "The body has {links} links for {words} words of text, more than one link per {MIN_WORDS_PER_LINK} words"
+                ),
+            ));
+        }
+
+        let searchable = [
+            content.subject.as_deref(),
+            text,
+            stats.as_ref().map(|s| s.text.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+        for phrase in &self.blocked_phrases {
+            if searchable.contains(phrase.as_str()) {
+                warnings.push(LintWarning::new(
+                    LintRule::BlockedPhrase,
+                    format!("The email contains the blocked phrase '{phrase}'"),
+                ));
+            }
+        }
+
+        if self.campaign && !self.list_unsubscribe {
+            let has_link = [text, html]
+                .into_iter()
+                .flatten()
+                .any(|body| body.to_lowercase().contains("unsubscribe"));
+            if !has_link {
+                warnings.push(LintWarning::new(
+                    LintRule::MissingUnsubscribe,
+                    "The campaign email has no List-Unsubscribe header or unsubscribe link".into(),
+                ));
+            }
+        }
+
+        for (name, part) in [
+            ("subject", content.subject.as_deref()),
+            ("text", text),
+            ("html", html),
+        ] {
+            if part.is_some_and(|p| p.contains("{{") || p.contains("}}")) {
+                warnings.push(LintWarning::new(
+                    LintRule::BrokenTemplateVariable,
+                    format!("The {name} contains an unrendered '{{{{ }}}}' placeholder"),
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// Visible text, images and links of an HTML body.
+struct HtmlStats {
+    text: String,
+    images: usize,
+    links: usize,
+}
+
+impl HtmlStats {
+    fn parse(html: &str) -> Self {
+        let document = Html::parse_document(html);
+        let mut stats = HtmlStats {
+            text: String::new(),
+            images: 0,
+            links: 0,
+        };
+        for node in document.root_element().descendants() {
+            match node.value() {
+                Node::Element(e) if e.name() == "img" => stats.images += 1,
+                Node::Element(e) if e.name() == "a" && e.attr("href").is_some() => stats.links += 1,
+                Node::Text(text)
+                    if !node.ancestors().any(|a| {
+                        a.value()
+                            .as_element()
+                            .is_some_and(|e| matches!(e.name(), "head" | "script" | "style"))
+                    }) =>
+                {
+                    stats.text.push_str(text);
+                    stats.text.push(' ');
+                }
+                _ => {}
+            }
+        }
+        stats
+    }
+}
+
+/// Lints a send request as it would be sent by `account`. Template-based emails are
+/// rendered and linted for every recipient.
+pub async fn lint_request(
+    account: &AccountModel,
+    request: &SendEmailRequest,
+) -> RustMailerResult<ContentLintReport> {
+    let linter = ContentLinter::for_request(request);
+    let mut warnings = Vec::new();
+
+    if let Some(eml) = &request.eml {
+        let eml = EmlData::parse(eml)?;
+        warnings = linter.lint(&LintContent {
+            subject: eml.subject,
+            text: eml.text,
+            html: eml.html,
+        });
+    } else if let Some(template_id) = request.template_id {
+        let template = EmailTemplate::get(template_id).await?;
+        template.check_usable_by(account)?;
+        let partials = template.partials().await?;
+        for (index, recipient) in request.recipients.iter().enumerate() {
+            let params = &recipient.template_params;
+            let mut found: Vec<LintWarning> =
+                Templates::check_variables(&template, params, &partials)
+                    .into_iter()
+                    .map(|e| LintWarning::new(LintRule::BrokenTemplateVariable, e))
+                    .collect();
+            let strict_failed = !found.is_empty();
+
+            let (subject, text, html) = Templates::render(&template, params, &partials)?;
+            let content = LintContent {
+                subject: Some(subject),
+                text,
+                html,
+            };
+            // Placeholders left by missing variables are already reported above.
+            found.extend(
+                linter
+                    .lint(&content)
+                    .into_iter()
+                    .filter(|w| !(strict_failed && w.rule == LintRule::BrokenTemplateVariable)),
+            );
+            warnings.extend(found.into_iter().map(|mut w| {
+                w.recipient_index = Some(index as u32);
+                w
+            }));
+        }
+    } else {
+        let (text, html) = request.body();
+        warnings = linter.lint(&LintContent {
+            subject: request.subject.clone(),
+            text,
+            html,
+        });
+    }
+    Ok(ContentLintReport::new(warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linter(campaign: bool) -> ContentLinter {
+        ContentLinter {
+            blocked_phrases: vec!["click here".into(), "winner".into()],
+            campaign,
+            list_unsubscribe: false,
+        }
+    }
+
+    fn content(text: Option<&str>, html: Option<&str>) -> LintContent {
+        LintContent {
+            subject: Some("Monthly report".into()),
+            text: text.map(Into::into),
+            html: html.map(Into::into),
+        }
+    }
+
+    fn rules(warnings: &[LintWarning]) -> Vec<LintRule> {
+        warnings.iter().map(|w| w.rule).collect()
+    }
+
+    #[test]
+    fn accepts_clean_emails() {
+        let text = "Hello team, the monthly report is ready. Revenue grew by four percent and \
+                    churn stayed flat. See https://example.com/report for details.";
+        let warnings = linter(false).lint(&content(Some(text), None));
+        assert!(warnings.is_empty());
+        assert!(ContentLintReport::new(warnings).enforce().is_ok());
+    }
+
+    #[test]
+    fn flags_image_only_html_without_text() {
+        let html = "<html><head><style>p { color: red }</style></head>\
+                    <body><img src=\"https://example.com/banner.png\"><p>Sale!</p></body></html>";
+        let warnings = linter(false).lint(&content(None, Some(html)));
+        assert_eq!(
+            rules(&warnings),
+            vec![LintRule::MissingTextAlternative, LintRule::ImageOnlyBody]
+        );
+        let report = ContentLintReport::new(warnings);
+        assert!(!report.passed);
+        assert!(report.enforce().is_err());
+    }
+
+    #[test]
+    fn flags_links_phrases_and_placeholders() {
+        let html = "<p>Hi {{name}}, you are a WINNER. Click   here:\
+                    <a href=\"https://a.example.com\">a</a>\
+                    <a href=\"https://b.example.com\">b</a>\
+                    <a href=\"https://c.example.com\">c</a></p>";
+        let warnings = linter(false).lint(&content(Some("Hi"), Some(html)));
+        assert_eq!(
+            rules(&warnings),
+            vec![
+                LintRule::ExcessiveLinks,
+                LintRule::BlockedPhrase,
+                LintRule::BlockedPhrase,
+                LintRule::BrokenTemplateVariable,
+            ]
+        );
+    }
+
+    #[test]
+    fn requires_unsubscribe_for_campaigns() {
+        let text = Some("Our spring collection is here.");
+        assert_eq!(
+            rules(&linter(true).lint(&content(text, None))),
+            vec![LintRule::MissingUnsubscribe]
+        );
+        let with_link = Some("Our spring collection is here. Unsubscribe: https://example.com/u");
+        assert!(linter(true).lint(&content(with_link, None)).is_empty());
+
+        let mut with_header = linter(true);
+        with_header.list_unsubscribe = true;
+        assert!(with_header.lint(&content(text, None)).is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{LazyLock, Mutex},
+};
+
+use ahash::AHashSet;
+use lru::LruCache;
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::{
+    modules::{
+        account::migration::AccountModel,
+        error::{code::ErrorCode, RustMailerResult},
+        hook::{
+            channel::{Event, EVENT_CHANNEL},
+            events::{payload::EmailLoopDetected, EventPayload, EventType, RustMailerEvent},
+            task::EventHookTask,
+        },
+        smtp::subject::strip_prefixes,
+    },
+    raise_error, utc_now,
+};
+
+/// Number of (account, recipient, subject) combinations tracked for the identical subject check.
+const SUBJECT_HISTORY_CAPACITY: usize = 10_000;
+
+static SUBJECT_HISTORY: LazyLock<Mutex<SubjectHistory>> =
+    LazyLock::new(|| Mutex::new(SubjectHistory::new(SUBJECT_HISTORY_CAPACITY)));
+
+/// What happens to an outgoing email that looks like part of a mail loop.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum LoopAction {
+    /// The email is rejected with a `MailLoopDetected` error.
+    #[default]
+    Block,
+    /// The email is sent anyway; only an `EmailLoopDetected` event is emitted.
+    Flag,
+}
+
+/// Per-account mail loop and auto-responder protection.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct LoopProtection {
+    /// Action taken when a potential loop is detected.
+    pub action: LoopAction,
+    /// Treat recipients that are accounts managed by this instance (including the sending
+    /// account itself) as potential loops, since such aliases commonly auto-forward or
+    /// auto-reply back to the sender.
+    pub check_managed_recipients: bool,
+    /// Maximum number of emails with the same subject sent to the same recipient within
+    /// `window_minutes`. Reply and forward prefixes are ignored when comparing subjects.
+    /// `0` disables this check.
+    #[oai(validator(maximum(value = "10000")))]
+    pub max_identical_subjects: u32,
+    /// Length of the window used by `max_identical_subjects`, in minutes.
+    #[oai(validator(minimum(value = "1"), maximum(value = "10080")))]
+    pub window_minutes: u32,
+}
+
+impl LoopProtection {
+    fn window_ms(&self) -> i64 {
+        self.window_minutes.max(1) as i64 * 60_000
+    }
+}
+
+/// Timestamps of recently sent emails, keyed by account, recipient and normalized subject.
+struct SubjectHistory {
+    entries: LruCache<String, Vec<i64>>,
+}
+
+impl SubjectHistory {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: LruCache::new(NonZeroUsize::new(capacity).unwrap()),
+        }
+    }
+
+    /// Number of emails recorded for `key` within the window ending at `now`.
+    fn count(&mut self, key: &str, now: i64, window_ms: i64) -> usize {
+        match self.entries.get_mut(key) {
+            Some(sent) => {
+                sent.retain(|at| now - at < window_ms);
+                sent.len()
+            }
+            None => 0,
+        }
+    }
+
+    fn record(&mut self, key: String, now: i64) {
+        match self.entries.get_mut(&key) {
+            Some(sent) => sent.push(now),
+            None => {
+                self.entries.put(key, vec![now]);
+            }
+        }
+    }
+}
+
+/// Lowercases the subject and strips reply/forward prefixes, so that `Re: Re: Hello`
+/// and `hello` are considered identical.
+pub fn normalize_subject(subject: &str) -> String {
+    strip_prefixes(subject).to_lowercase()
+}
+
+fn subject_key(account_id: u64, recipient: &str, subject: &str) -> String {
+    format!("{account_id}|{}|{subject}", recipient.to_lowercase())
+}
+
+/// Checks an outgoing email against the account's loop protection settings.
+///
+/// Returns a `MailLoopDetected` error if a potential loop is detected and the account is
+/// configured to block it. In both block and flag mode, an `EmailLoopDetected` event is
+/// emitted to watching hooks.
+pub async fn check_mail_loop(
+    account: &AccountModel,
+    from: &str,
+    recipients: &[String],
+    subject: Option<&str>,
+    message_id: &str,
+) -> RustMailerResult<()> {
+    let Some(protection) = &account.loop_protection else {
+        return Ok(());
+    };
+
+    let mut reason = None;
+    if protection.check_managed_recipients {
+        let managed: AHashSet<String> = AccountModel::list_all()
+            .await?
+            .into_iter()
+            .map(|a| a.email.to_lowercase())
+            .collect();
+        reason = recipients
+            .iter()
+            .find(|r| managed.contains(&r.to_lowercase()))
+            .map(|r| format!("Recipient '{r}' is an account managed by this instance"));
+    }
+
+    let subject_keys: Vec<(String, &String)> = match subject {
+        Some(subject) if protection.max_identical_subjects > 0 => {
+            let subject = normalize_subject(subject);
+            recipients
+                .iter()
+                .map(|r| (subject_key(account.id, r, &subject), r))
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    let now = utc_now!();
+    {
+        let mut history = SUBJECT_HISTORY.lock().unwrap();
+        if reason.is_none() {
+            reason = subject_keys.iter().find_map(|(key, recipient)| {
+                let count = history.count(key, now, protection.window_ms());
+                (count >= protection.max_identical_subjects as usize).then(|| {
+                    format!(
+                        "{count} emails with the same subject were sent to '{recipient}' within the last {} minutes",
+                        protection.window_minutes
+                    )
+                })
+            });
+        }
+        let blocked = reason.is_some() && protection.action == LoopAction::Block;
+        if !blocked {
+            for (key, _) in subject_keys {
+                history.record(key, now);
+            }
+        }
+    }
+
+    let Some(reason) = reason else {
+        return Ok(());
+    };
+    let blocked = protection.action == LoopAction::Block;
+    warn!(
+        account_id = account.id,
+        message_id = message_id,
+        blocked = blocked,
+        "Potential mail loop detected: {}",
+        reason
+    );
+
+    match EventHookTask::is_watching_email_loop_detected(account.id).await {
+        Ok(true) => {
+            EVENT_CHANNEL
+                .queue(Event::new(
+                    account.id,
+                    &account.email,
+                    RustMailerEvent::new(
+                        EventType::EmailLoopDetected,
+                        EventPayload::EmailLoopDetected(EmailLoopDetected {
+                            account_id: account.id,
+                            account_email: account.email.clone(),
+                            from: from.to_string(),
+                            to: recipients.to_vec(),
+                            subject: subject.map(Into::into),
+                            message_id: message_id.to_string(),
+                            reason: reason.clone(),
+                            blocked,
+                        }),
+                    ),
+                ))
+                .await;
+        }
+        Ok(false) => {}
+        Err(e) => {
+            error!(
+                account_id = account.id,
+                error = %e,
+                "Failed to check event_watched for EmailLoopDetected"
+            );
+        }
+    }
+
+    if blocked {
+        return Err(raise_error!(
+            format!("Potential mail loop detected, email not sent: {reason}"),
+            ErrorCode::MailLoopDetected
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_reply_and_forward_prefixes() {
+        assert_eq!(
+            normalize_subject("  Re: RE:Fwd: Hello World "),
+            "hello world"
+        );
+        assert_eq!(normalize_subject("AW: Termin"), "termin");
+        assert_eq!(normalize_subject("Report: Q3"), "report: q3");
+    }
+
+    #[test]
+    fn counts_only_within_window() {
+        let mut history = SubjectHistory::new(2);
+        let key = subject_key(1, "User@Example.com", "hello");
+        assert_eq!(key, "1|user@example.com|hello");
+        history.record(key.clone(), 1_000);
+        history.record(key.clone(), 2_000);
+        assert_eq!(history.count(&key, 2_500, 10_000), 2);
+        assert_eq!(history.count(&key, 11_500, 10_000), 1);
+        assert_eq!(history.count("1|other@example.com|hello", 2_500, 10_000), 0);
+    }
+}
@@ -30,12 +30,24 @@ pub enum SmtpServerType {
     Account(u64),
 }
 
-impl SmtpClientManager {
-    pub fn new(server: SmtpServerType) -> Self {
-        Self { server }
+/// Connection settings of an SMTP server, resolved from an MTA or an account.
+pub struct SmtpEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub encryption: Encryption,
+    pub use_proxy: Option<u64>,
+    pub credentials: Credentials<String>,
+}
+
+impl SmtpEndpoint {
+    pub async fn resolve(server: &SmtpServerType) -> RustMailerResult<Self> {
+        match server {
+            SmtpServerType::Mta(mta_id) => Self::mta(*mta_id).await,
+            SmtpServerType::Account(account_id) => Self::account(*account_id).await,
+        }
     }
 
-    async fn build_mta(mta_id: u64) -> RustMailerResult<RustMailSmtpClient> {
+    async fn mta(mta_id: u64) -> RustMailerResult<Self> {
         let mta = Mta::get(mta_id).await?.ok_or_else(|| {
             raise_error!(
                 format!("MTA '{}' not found", mta_id),
@@ -53,57 +65,16 @@ impl SmtpClientManager {
         let credentials =
             Credentials::new(mta.credentials.username, decrypt!(&encrypted_password)?);
 
-        let timeout = Duration::from_secs(30);
-        if let Some(proxy_id) = &mta.use_proxy {
-            let proxy = Proxy::get(*proxy_id).await?;
-            let proxy = parse_proxy_addr(&proxy.url)?;
-
-            let socks_stream =
-                Socks5Stream::connect(proxy, format!("{}:{}", &mta.server.host, mta.server.port))
-                    .await
-                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
-
-            let tcp_stream = socks_stream.into_inner();
-            return Self::connect(
-                mta.server.encryption,
-                &mta.server.host,
-                timeout,
-                tcp_stream,
-                credentials,
-            )
-            .await
-            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpConnectionFailed));
-        }
-
-        let builder = SmtpClientBuilder::new(mta.server.host, mta.server.port)
-            .credentials(credentials)
-            .timeout(timeout);
-
-        let client = match mta.server.encryption {
-            Encryption::Ssl => {
-                let client = builder.implicit_tls(true).connect().await.map_err(|e| {
-                    raise_error!(format!("{:#?}", e), ErrorCode::SmtpConnectionFailed)
-                })?;
-                RustMailSmtpClient::Tls(client)
-            }
-            Encryption::StartTls => {
-                let client = builder.implicit_tls(false).connect().await.map_err(|e| {
-                    raise_error!(format!("{:#?}", e), ErrorCode::SmtpConnectionFailed)
-                })?;
-                RustMailSmtpClient::Tls(client)
-            }
-            Encryption::None => {
-                let client = builder.connect_plain().await.map_err(|e| {
-                    raise_error!(format!("{:#?}", e), ErrorCode::SmtpConnectionFailed)
-                })?;
-                RustMailSmtpClient::Plain(client)
-            }
-        };
-
-        Ok(client)
+        Ok(Self {
+            host: mta.server.host,
+            port: mta.server.port,
+            encryption: mta.server.encryption,
+            use_proxy: mta.use_proxy,
+            credentials,
+        })
     }
 
-    async fn build_client(account_id: u64) -> RustMailerResult<RustMailSmtpClient> {
+    async fn account(account_id: u64) -> RustMailerResult<Self> {
         let account = AccountModel::get(account_id).await?;
 
         let smtp = account
@@ -119,7 +90,7 @@ impl SmtpClientManager {
                         ErrorCode::MissingConfiguration
                     )
                 })?;
-                Credentials::new(account.email, decrypt!(&password)?)
+                Credentials::new(account.email.clone(), decrypt!(&password)?)
             }
             AuthType::OAuth2 => {
                 let record = OAuth2AccessToken::get(account_id).await?;
@@ -131,35 +102,81 @@ impl SmtpClientManager {
                     )
                 })?;
 
-                Credentials::new_xoauth2(account.email, access_token)
+                Credentials::new_xoauth2(account.email.clone(), access_token)
             }
         };
 
-        let timeout = Duration::from_secs(30);
-        if let Some(proxy_id) = smtp.use_proxy {
+        Ok(Self {
+            host: smtp.host.clone(),
+            port: smtp.port,
+            encryption: smtp.encryption.clone(),
+            use_proxy: smtp.use_proxy,
+            credentials,
+        })
+    }
+
+    /// Opens the TCP connection to the server, through the configured proxy if any.
+    pub async fn tcp_connect(&self, timeout: Duration) -> RustMailerResult<TcpStream> {
+        let addr = format!("{}:{}", self.host, self.port);
+        if let Some(proxy_id) = self.use_proxy {
             let proxy = Proxy::get(proxy_id).await?;
             let proxy = parse_proxy_addr(&proxy.url)?;
-            let socks_stream = Socks5Stream::connect(proxy, format!("{}:{}", smtp.host, smtp.port))
+            let socks_stream = tokio::time::timeout(timeout, Socks5Stream::connect(proxy, addr))
                 .await
+                .map_err(|_| {
+                    raise_error!(
+                        "Timed out connecting through proxy".into(),
+                        ErrorCode::SmtpConnectionFailed
+                    )
+                })?
                 .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            return Ok(socks_stream.into_inner());
+        }
+        tokio::time::timeout(timeout, TcpStream::connect(addr))
+            .await
+            .map_err(|_| {
+                raise_error!(
+                    "Timed out connecting to the SMTP server".into(),
+                    ErrorCode::SmtpConnectionFailed
+                )
+            })?
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpConnectionFailed))
+    }
+}
 
-            let tcp_stream = socks_stream.into_inner();
+impl SmtpClientManager {
+    pub fn new(server: SmtpServerType) -> Self {
+        Self { server }
+    }
+
+    pub async fn build(&self) -> RustMailerResult<RustMailSmtpClient> {
+        let timeout = Duration::from_secs(30);
+        #[cfg(feature = "test-harness")]
+        if let Some(mailstore) = crate::modules::harness::TestHarness::get()? {
+            let credentials = Credentials::new(self.harness_login().await?, String::new());
+            return Self::connect_in_memory(mailstore, timeout, credentials)
+                .await
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpConnectionFailed));
+        }
+        let endpoint = SmtpEndpoint::resolve(&self.server).await?;
+        if endpoint.use_proxy.is_some() {
+            let tcp_stream = endpoint.tcp_connect(timeout).await?;
             return Self::connect(
-                smtp.encryption.clone(),
-                &smtp.host,
+                endpoint.encryption,
+                &endpoint.host,
                 timeout,
                 tcp_stream,
-                credentials,
+                endpoint.credentials,
             )
             .await
             .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::SmtpConnectionFailed));
         }
 
-        let builder = SmtpClientBuilder::new(smtp.host.clone(), smtp.port)
-            .credentials(credentials)
+        let builder = SmtpClientBuilder::new(endpoint.host, endpoint.port)
+            .credentials(endpoint.credentials)
             .timeout(timeout);
 
-        let client = match smtp.encryption {
+        let client = match endpoint.encryption {
             Encryption::Ssl => {
                 let client = builder.implicit_tls(true).connect().await.map_err(|e| {
                     raise_error!(format!("{:#?}", e), ErrorCode::SmtpConnectionFailed)
@@ -182,13 +199,44 @@ impl SmtpClientManager {
 
         Ok(client)
     }
-    pub async fn build(&self) -> RustMailerResult<RustMailSmtpClient> {
+
+    /// Login for the SMTP server of the test harness, which only checks the user name, so no
+    /// secret is decrypted and no OAuth2 token is required.
+    #[cfg(feature = "test-harness")]
+    async fn harness_login(&self) -> RustMailerResult<String> {
         match self.server {
-            SmtpServerType::Mta(mta_id) => Self::build_mta(mta_id).await,
-            SmtpServerType::Account(account_id) => Self::build_client(account_id).await,
+            SmtpServerType::Mta(mta_id) => Mta::get(mta_id)
+                .await?
+                .map(|mta| mta.credentials.username)
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!("MTA '{}' not found", mta_id),
+                        ErrorCode::ResourceNotFound
+                    )
+                }),
+            SmtpServerType::Account(account_id) => Ok(AccountModel::get(account_id).await?.email),
         }
     }
 
+    /// Connects to the in-memory SMTP server of the test harness.
+    #[cfg(feature = "test-harness")]
+    async fn connect_in_memory(
+        mailstore: std::sync::Arc<crate::modules::harness::store::Mailstore>,
+        timeout: Duration,
+        credentials: Credentials<String>,
+    ) -> Result<RustMailSmtpClient, mail_send::Error> {
+        let mut client = SmtpClient {
+            stream: crate::modules::harness::smtp::connect(mailstore),
+            timeout,
+        };
+        // Read greeting
+        client.read().await?.assert_positive_completion()?;
+        let capabilities = client.capabilities("localhost", false).await?;
+        // Authenticate
+        client.authenticate(&credentials, &capabilities).await?;
+        Ok(RustMailSmtpClient::InMemory(client))
+    }
+
     async fn connect(
         encryption: Encryption,
         host: &str,
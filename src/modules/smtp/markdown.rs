@@ -0,0 +1,240 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+
+/// URL schemes allowed in links and images. Relative URLs and fragments are always allowed.
+const ALLOWED_URL_SCHEMES: &[&str] = &["http", "https", "mailto", "tel", "cid"];
+
+/// An email body rendered from markdown.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarkdownBody {
+    /// Sanitized HTML body.
+    pub html: String,
+    /// Plain-text alternative of the HTML body.
+    pub text: String,
+}
+
+impl MarkdownBody {
+    /// Renders markdown (CommonMark with tables, strikethrough and task lists) to an HTML body
+    /// and a plain-text alternative.
+    ///
+    /// The HTML is sanitized: raw HTML in the markdown is escaped and shown as text, and links
+    /// or images with a URL scheme other than http(s), mailto, tel or cid are neutralized.
+    pub fn render(markdown: &str) -> Self {
+        let events: Vec<Event> = Parser::new_ext(markdown, options()).map(sanitize).collect();
+
+        let mut html = String::with_capacity(markdown.len() * 3 / 2);
+        html::push_html(&mut html, events.iter().cloned());
+        Self {
+            html,
+            text: plain_text(&events),
+        }
+    }
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+fn sanitize(event: Event) -> Event {
+    match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        event => event,
+    }
+}
+
+fn safe_url(url: CowStr) -> CowStr {
+    if is_safe_url(&url) {
+        url
+    } else {
+        CowStr::Borrowed("#")
+    }
+}
+
+/// Returns `true` if the URL is relative or uses an allowed scheme.
+fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters inside the scheme.
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => ALLOWED_URL_SCHEMES
+            .iter()
+            .any(|allowed| scheme.eq_ignore_ascii_case(allowed)),
+        _ => true,
+    }
+}
+
+/// Builds the plain-text alternative: blocks separated by blank lines, list items prefixed
+/// with `-` or their number, and link targets appended in parentheses.
+fn plain_text(events: &[Event]) -> String {
+    let mut out = String::new();
+    // Next number of each open list, `None` for bullet lists.
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    // Target of each open link, with the output length at its start.
+    let mut links: Vec<(usize, String)> = Vec::new();
+
+    for event in events {
+        match event {
+            Event::Text(text) | Event::Code(text) => out.push_str(text),
+            Event::SoftBreak | Event::HardBreak => out.push('\n'),
+            Event::Rule => {
+                block_break(&mut out);
+                out.push_str("----");
+                block_break(&mut out);
+            }
+            Event::TaskListMarker(checked) => out.push_str(if *checked { "[x] " } else { "[ ] " }),
+            Event::Start(Tag::List(start)) => {
+                line_break(&mut out);
+                lists.push(*start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    block_break(&mut out);
+                }
+            }
+            Event::Start(Tag::Item) => {
+                line_break(&mut out);
+                out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        out.push_str(&format!("{number}. "));
+                        *number += 1;
+                    }
+                    _ => out.push_str("- "),
+                }
+            }
+            Event::Start(Tag::BlockQuote(_)) => {
+                block_break(&mut out);
+                out.push_str("> ");
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                links.push((out.len(), dest_url.to_string()));
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some((start, url)) = links.pop() {
+                    let label = &out[start..];
+                    if url != "#" && label != url && label != url.trim_start_matches("mailto:") {
+                        out.push_str(&format!(" ({url})"));
+                    }
+                }
+            }
+            Event::End(TagEnd::TableCell) => out.push_str(" | "),
+            Event::End(TagEnd::TableHead) | Event::End(TagEnd::TableRow) => {
+                let trimmed = out.trim_end_matches([' ', '|']).len();
+                out.truncate(trimmed);
+                out.push('\n');
+            }
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::CodeBlock
+                | TagEnd::BlockQuote(_)
+                | TagEnd::Table,
+            ) if lists.is_empty() => {
+                block_break(&mut out);
+            }
+            _ => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Starts a new line unless the output is empty or already on a new line.
+fn line_break(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Ends the current block with a blank line.
+fn block_break(out: &mut String) {
+    let trimmed = out.trim_end_matches([' ', '\n']).len();
+    out.truncate(trimmed);
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_html_and_text() {
+        let body = MarkdownBody::render(
+            "# Disk alert\n\nHost **db-1** is at *95%*.\n\n- Check [logs](https://logs.example.com)\n- Rotate files\n\n1. First\n2. Second\n",
+        );
+        assert!(body.html.contains("<h1>Disk alert</h1>"));
+        assert!(body.html.contains("<strong>db-1</strong>"));
+        assert!(body
+            .html
+            .contains("<a href=\"https://logs.example.com\">logs</a>"));
+        assert_eq!(
+            body.text,
+            "Disk alert\n\nHost db-1 is at 95%.\n\n- Check logs (https://logs.example.com)\n- Rotate files\n\n1. First\n2. Second"
+        );
+    }
+
+    #[test]
+    fn escapes_raw_html() {
+        let body =
+            MarkdownBody::render("Hello <script>alert(1)</script>\n\n<img src=x onerror=alert(1)>");
+        assert!(!body.html.contains("<script>"));
+        assert!(!body.html.contains("<img"));
+        assert!(body.html.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn neutralizes_unsafe_urls() {
+        let body =
+            MarkdownBody::render("[click](javascript:alert(1)) ![x](data:image/svg+xml,abc)");
+        assert!(!body.html.contains("javascript:"));
+        assert!(!body.html.contains("data:"));
+        assert_eq!(body.text, "click x");
+
+        assert!(is_safe_url("https://example.com/a:b"));
+        assert!(is_safe_url("mailto:ops@example.com"));
+        assert!(is_safe_url("/status?at=10:00"));
+        assert!(!is_safe_url("java\tscript:alert(1)"));
+        assert!(!is_safe_url("VBScript:msgbox"));
+    }
+
+    #[test]
+    fn renders_tables_and_code_as_text() {
+        let body = MarkdownBody::render(
+            "| Host | Load |\n|---|---|\n| db-1 | 0.9 |\n\n```\nuptime\n```\n\n<ops@example.com>",
+        );
+        assert!(body.html.contains("<table>"));
+        assert_eq!(
+            body.text,
+            "Host | Load\ndb-1 | 0.9\n\nuptime\n\nops@example.com"
+        );
+    }
+}
@@ -2,16 +2,25 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+pub mod campaign;
 pub mod client;
 pub mod composer;
 pub mod executor;
+pub mod lint;
+pub mod loop_guard;
 pub mod manager;
+pub mod markdown;
 pub mod mta;
 pub mod pool;
+pub mod probe;
 pub mod queue;
 pub mod request;
+pub mod sent;
+pub mod subject;
+pub mod suppression;
 pub mod template;
 #[cfg(test)]
 mod tests;
+pub mod timeline;
 pub mod track;
 pub mod util;
@@ -3,14 +3,17 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::account::entity::Encryption;
+use crate::modules::account::migration::AccountModel;
+use crate::modules::common::auth::ClientContext;
+use crate::modules::common::paginated::paginate_vec;
 use crate::modules::database::manager::DB_MANAGER;
-use crate::modules::database::{
-    delete_impl, paginate_query_primary_scan_all_impl, secondary_find_impl, update_impl,
-};
+use crate::modules::database::{delete_impl, list_all_impl, secondary_find_impl, update_impl};
 use crate::modules::error::code::ErrorCode;
 use crate::modules::rest::response::DataPage;
 use crate::modules::smtp::mta::payload::MTACreateRequest;
 use crate::modules::smtp::mta::payload::MTAUpdateRequest;
+use crate::modules::smtp::queue::rate::SendRateLimit;
+use crate::modules::workspace::Workspace;
 use crate::{encrypt, id, raise_error};
 use crate::{modules::database::insert_impl, modules::error::RustMailerResult, utc_now};
 use native_db::*;
@@ -21,6 +24,88 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
 #[native_model(id = 7, version = 1)]
 #[native_db(primary_key(pk -> String))]
+pub struct MtaV1 {
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Optional descriptive text about the MTA.
+    pub description: Option<String>,
+
+    /// Credentials used for authenticating with the MTA server.
+    pub credentials: MTACredentials,
+
+    /// SMTP server configuration details.
+    pub server: SmtpServerConfig,
+
+    /// Timestamp (Unix epoch milliseconds) when the MTA was created.
+    pub created_at: i64,
+
+    /// Indicates if the MTA supports DSN (Delivery Status Notification).
+    pub dsn_capable: bool,
+
+    /// Timestamp (Unix epoch milliseconds) when the MTA was last updated.
+    pub updated_at: i64,
+
+    /// Timestamp (Unix epoch milliseconds) when the MTA was last accessed.
+    pub last_access_at: i64,
+
+    /// Optional proxy ID for establishing the connection.
+    /// - If `None` or not provided, the client will connect directly to the MTA server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
+    pub use_proxy: Option<u64>,
+}
+
+impl MtaV1 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 7, version = 2, from = MtaV1)]
+#[native_db(primary_key(pk -> String))]
+pub struct MtaV2 {
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Optional descriptive text about the MTA.
+    pub description: Option<String>,
+
+    /// Credentials used for authenticating with the MTA server.
+    pub credentials: MTACredentials,
+
+    /// SMTP server configuration details.
+    pub server: SmtpServerConfig,
+
+    /// Timestamp (Unix epoch milliseconds) when the MTA was created.
+    pub created_at: i64,
+
+    /// Indicates if the MTA supports DSN (Delivery Status Notification).
+    pub dsn_capable: bool,
+
+    /// Timestamp (Unix epoch milliseconds) when the MTA was last updated.
+    pub updated_at: i64,
+
+    /// Timestamp (Unix epoch milliseconds) when the MTA was last accessed.
+    pub last_access_at: i64,
+
+    /// Optional proxy ID for establishing the connection.
+    /// - If `None` or not provided, the client will connect directly to the MTA server.
+    /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
+    pub use_proxy: Option<u64>,
+
+    /// Outbound send rate limit of the MTA, shared by all accounts sending through it.
+    /// If not set, emails are sent as fast as the queue allows.
+    pub send_rate_limit: Option<SendRateLimit>,
+}
+
+impl MtaV2 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 7, version = 3, from = MtaV2)]
+#[native_db(primary_key(pk -> String))]
 pub struct Mta {
     #[secondary_key(unique)]
     pub id: u64,
@@ -49,6 +134,14 @@ pub struct Mta {
     /// - If `None` or not provided, the client will connect directly to the MTA server.
     /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
     pub use_proxy: Option<u64>,
+
+    /// Outbound send rate limit of the MTA, shared by all accounts sending through it.
+    /// If not set, emails are sent as fast as the queue allows.
+    pub send_rate_limit: Option<SendRateLimit>,
+
+    /// The workspace the MTA belongs to. Only the accounts of the workspace can send through
+    /// it. If not set, the MTA is shared by the whole instance.
+    pub workspace_id: Option<u64>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Object)]
@@ -102,6 +195,9 @@ impl Mta {
     }
 
     pub fn new(value: MTACreateRequest) -> RustMailerResult<Self> {
+        if let Some(limit) = &value.send_rate_limit {
+            limit.validate()?;
+        }
         Ok(Self {
             id: id!(64),
             description: value.description,
@@ -112,23 +208,73 @@ impl Mta {
             updated_at: utc_now!(),
             last_access_at: Default::default(),
             use_proxy: value.use_proxy,
+            send_rate_limit: value.send_rate_limit,
+            workspace_id: value.workspace_id,
         })
     }
 
-    pub async fn paginate_list(
-        page: Option<u64>,
-        page_size: Option<u64>,
-        desc: Option<bool>,
-    ) -> RustMailerResult<DataPage<Mta>> {
-        paginate_query_primary_scan_all_impl(DB_MANAGER.meta_db(), page, page_size, desc)
-            .await
-            .map(DataPage::from)
+    pub async fn list_all() -> RustMailerResult<Vec<Mta>> {
+        list_all_impl(DB_MANAGER.meta_db()).await
     }
 
     pub async fn get(id: u64) -> RustMailerResult<Option<Mta>> {
         secondary_find_impl(DB_MANAGER.meta_db(), MtaKey::id, id).await
     }
 
+    /// The workspace of an MTA, to check the permission to manage it.
+    pub async fn workspace_of(id: u64) -> RustMailerResult<Option<u64>> {
+        Self::get(id)
+            .await?
+            .map(|mta| mta.workspace_id)
+            .ok_or_else(|| {
+                raise_error!(
+                    format!("MTA with id {id} not found"),
+                    ErrorCode::ResourceNotFound
+                )
+            })
+    }
+
+    /// Checks that the MTA exists and that the account may send through it: MTAs of a
+    /// workspace are limited to the accounts of the workspace.
+    pub async fn check_available_to(id: u64, account: &AccountModel) -> RustMailerResult<()> {
+        let mta = Self::get(id).await?.ok_or_else(|| {
+            raise_error!(
+                format!("MTA with id {id} not found"),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+        if mta.workspace_id.is_some() && mta.workspace_id != account.workspace_id {
+            return Err(raise_error!(
+                format!(
+                    "MTA id='{id}' belongs to another workspace and cannot be used by account {}.",
+                    account.id
+                ),
+                ErrorCode::PermissionDenied
+            ));
+        }
+        Ok(())
+    }
+
+    /// Lists the MTAs visible to the client: those of its workspace for a token scoped to a
+    /// workspace, the shared MTAs otherwise.
+    pub async fn paginate_list_visible(
+        context: &ClientContext,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<Mta>> {
+        let mut mtas: Vec<Mta> = Self::list_all()
+            .await?
+            .into_iter()
+            .filter(|mta| context.can_see_workspace(mta.workspace_id))
+            .collect();
+        mtas.sort_by_key(|mta| (mta.created_at, mta.id));
+        if desc.unwrap_or(false) {
+            mtas.reverse();
+        }
+        paginate_vec(&mtas, page, page_size).map(DataPage::from)
+    }
+
     pub async fn delete(id: u64) -> RustMailerResult<()> {
         delete_impl(DB_MANAGER.meta_db(), move |rw| {
             rw.get()
@@ -145,6 +291,7 @@ impl Mta {
     }
 
     pub async fn save(self) -> RustMailerResult<()> {
+        Workspace::check_exists(self.workspace_id).await?;
         insert_impl(DB_MANAGER.meta_db(), self).await
     }
 
@@ -157,9 +304,7 @@ impl Mta {
                     .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
                     .ok_or_else(|| {
                         raise_error!(
-                            format!(
-                                "The MTA with id={id} that you want to modify was not found."
-                            ),
+                            format!("The MTA with id={id} that you want to modify was not found."),
                             ErrorCode::ResourceNotFound
                         )
                     })
@@ -193,7 +338,79 @@ fn apply_update(old: &Mta, request: MTAUpdateRequest) -> RustMailerResult<Mta> {
     if let Some(use_proxy) = request.use_proxy {
         new.use_proxy = Some(use_proxy);
     }
+    if let Some(send_rate_limit) = request.send_rate_limit {
+        send_rate_limit.validate()?;
+        new.send_rate_limit = Some(send_rate_limit);
+    }
 
     new.updated_at = utc_now!();
     Ok(new)
 }
+
+impl From<MtaV1> for MtaV2 {
+    fn from(value: MtaV1) -> Self {
+        Self {
+            id: value.id,
+            description: value.description,
+            credentials: value.credentials,
+            server: value.server,
+            created_at: value.created_at,
+            dsn_capable: value.dsn_capable,
+            updated_at: value.updated_at,
+            last_access_at: value.last_access_at,
+            use_proxy: value.use_proxy,
+            send_rate_limit: None,
+        }
+    }
+}
+
+impl From<MtaV2> for MtaV1 {
+    fn from(value: MtaV2) -> Self {
+        Self {
+            id: value.id,
+            description: value.description,
+            credentials: value.credentials,
+            server: value.server,
+            created_at: value.created_at,
+            dsn_capable: value.dsn_capable,
+            updated_at: value.updated_at,
+            last_access_at: value.last_access_at,
+            use_proxy: value.use_proxy,
+        }
+    }
+}
+
+impl From<MtaV2> for Mta {
+    fn from(value: MtaV2) -> Self {
+        Self {
+            id: value.id,
+            description: value.description,
+            credentials: value.credentials,
+            server: value.server,
+            created_at: value.created_at,
+            dsn_capable: value.dsn_capable,
+            updated_at: value.updated_at,
+            last_access_at: value.last_access_at,
+            use_proxy: value.use_proxy,
+            send_rate_limit: value.send_rate_limit,
+            workspace_id: None,
+        }
+    }
+}
+
+impl From<Mta> for MtaV2 {
+    fn from(value: Mta) -> Self {
+        Self {
+            id: value.id,
+            description: value.description,
+            credentials: value.credentials,
+            server: value.server,
+            created_at: value.created_at,
+            dsn_capable: value.dsn_capable,
+            updated_at: value.updated_at,
+            last_access_at: value.last_access_at,
+            use_proxy: value.use_proxy,
+            send_rate_limit: value.send_rate_limit,
+        }
+    }
+}
@@ -5,7 +5,10 @@
 use crate::{
     modules::{
         error::{code::ErrorCode, RustMailerResult},
-        smtp::mta::entity::{MTACredentials, SmtpServerConfig},
+        smtp::{
+            mta::entity::{MTACredentials, SmtpServerConfig},
+            queue::rate::SendRateLimit,
+        },
     },
     raise_error, validate_email,
 };
@@ -26,6 +29,12 @@ pub struct MTACreateRequest {
     /// - If `None` or not provided, the client will connect directly to the MTA server.
     /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
     pub use_proxy: Option<u64>,
+    /// Outbound send rate limit of the MTA, shared by all accounts sending through it.
+    /// If not set, emails are sent as fast as the queue allows.
+    pub send_rate_limit: Option<SendRateLimit>,
+    /// The workspace the MTA belongs to. Defaults to the workspace of the access token; if
+    /// not set, the MTA is shared by the whole instance and can only be created by root.
+    pub workspace_id: Option<u64>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
@@ -42,6 +51,8 @@ pub struct MTAUpdateRequest {
     /// - If `None` or not provided, the client will connect directly to the MTA server.
     /// - If `Some(proxy_id)`, the client will use the pre-configured proxy with the given ID.
     pub use_proxy: Option<u64>,
+    /// Optional updated outbound send rate limit.
+    pub send_rate_limit: Option<SendRateLimit>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
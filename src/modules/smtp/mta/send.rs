@@ -15,7 +15,7 @@ use crate::{
         error::{code::ErrorCode, RustMailerResult},
         smtp::{
             mta::{entity::Mta, payload::SendTestEmailRequest},
-            util::generate_message_id,
+            util::{generate_message_id, resolve_message_id_domain},
         },
     },
     raise_error,
@@ -35,6 +35,7 @@ pub async fn send_test_email(id: u64, reqwest: SendTestEmailRequest) -> RustMail
         subject,
         message,
     } = reqwest;
+    let message_id = generate_message_id(resolve_message_id_domain(None, &from));
     let from = Address::new_address(None::<&str>, Cow::Owned(from));
     let to = Address::new_address(None::<&str>, Cow::Owned(to));
     let builder = MessageBuilder::new()
@@ -42,7 +43,7 @@ pub async fn send_test_email(id: u64, reqwest: SendTestEmailRequest) -> RustMail
         .to(to)
         .subject(subject)
         .text_body(message)
-        .message_id(generate_message_id());
+        .message_id(message_id);
     let message = builder.into_message().map_err(|e| {
         raise_error!(
             format!("Failed to build message: {}", e),
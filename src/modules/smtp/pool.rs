@@ -2,6 +2,7 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::context::resources::{Admitted, ConnectionKind, CONNECTION_LIMITER};
 use crate::modules::error::RustMailerError;
 use crate::modules::error::RustMailerResult;
 use crate::modules::smtp::client::RustMailSmtpClient;
@@ -12,11 +13,12 @@ use bb8::Pool;
 use std::time::Duration;
 
 impl bb8::ManageConnection for SmtpClientManager {
-    type Connection = RustMailSmtpClient;
+    type Connection = Admitted<RustMailSmtpClient>;
     type Error = RustMailerError;
 
     async fn connect(&self) -> RustMailerResult<Self::Connection> {
-        self.build().await
+        let permit = CONNECTION_LIMITER.acquire(ConnectionKind::Smtp).await?;
+        Ok(Admitted::new(self.build().await?, permit))
     }
 
     // call this function before using the connection
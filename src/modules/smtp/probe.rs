@@ -0,0 +1,592 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{borrow::Cow, time::Duration, time::Instant};
+
+use futures::{stream::BoxStream, StreamExt};
+use mail_send::{
+    mail_builder::{headers::address::Address, MessageBuilder},
+    smtp::tls::build_tls_connector,
+    Credentials, SmtpClient,
+};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use smtp_proto::{EhloResponse, Response};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::mpsc,
+};
+
+use crate::{
+    modules::{
+        account::{entity::Encryption, migration::AccountModel},
+        error::{code::ErrorCode, RustMailerResult},
+        smtp::{
+            manager::{SmtpEndpoint, SmtpServerType, EXT_START_TLS},
+            util::{generate_message_id, resolve_message_id_domain},
+        },
+    },
+    raise_error, validate_email,
+};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SmtpProbeRequest {
+    /// Tests the SMTP server of this account. Exactly one of `account_id` and `mta_id` must be set.
+    pub account_id: Option<u64>,
+    /// Tests this MTA. Exactly one of `account_id` and `mta_id` must be set.
+    pub mta_id: Option<u64>,
+    /// Envelope sender. Defaults to the account's email address; required for MTAs.
+    pub from: Option<String>,
+    /// The email address of a single recipient (e.g., "user@example.com").
+    pub recipient: String,
+    /// Stops after `RCPT TO` and resets the transaction instead of sending a message.
+    #[serde(default)]
+    pub rcpt_only: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum SmtpProbeStage {
+    /// TCP connection, directly or through the configured proxy.
+    #[default]
+    Connect,
+    /// Implicit TLS handshake (SSL encryption).
+    Tls,
+    Greeting,
+    Ehlo,
+    StartTls,
+    Authenticate,
+    MailFrom,
+    RcptTo,
+    Data,
+    /// `RSET`, sent instead of `DATA` in RCPT-only mode.
+    Reset,
+    Quit,
+    /// The last event of a probe; `passed` tells whether every stage passed.
+    Finished,
+}
+
+/// Outcome of one stage of an SMTP probe, streamed as soon as the stage completes.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SmtpProbeEvent {
+    pub stage: SmtpProbeStage,
+    pub passed: bool,
+    /// Protocol lines of the stage. Client lines start with `C:`, server lines with `S:`;
+    /// credentials are never included.
+    pub transcript: Vec<String>,
+    /// Why the stage failed.
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+impl SmtpProbeRequest {
+    fn validate(&self) -> RustMailerResult<()> {
+        if self.account_id.is_some() == self.mta_id.is_some() {
+            return Err(raise_error!(
+                "Exactly one of 'account_id' and 'mta_id' must be set".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if validate_email!(&self.recipient).is_err() {
+            return Err(raise_error!(
+                "Invalid 'recipient' email address".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if let Some(from) = &self.from {
+            if validate_email!(from).is_err() {
+                return Err(raise_error!(
+                    "Invalid 'from' email address".into(),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs a real SMTP transaction (or one that stops after `RCPT TO`) against an account's
+/// SMTP server or an MTA, streaming the transcript of each stage as it completes.
+///
+/// Errors resolving the account or MTA are returned before streaming starts; protocol
+/// failures are reported as failed stages.
+pub async fn start_probe(
+    request: SmtpProbeRequest,
+) -> RustMailerResult<BoxStream<'static, SmtpProbeEvent>> {
+    request.validate()?;
+    let (server, from) = match (request.account_id, request.mta_id) {
+        (Some(account_id), _) => {
+            let account = AccountModel::get(account_id).await?;
+            if account.smtp.is_none() {
+                return Err(raise_error!(
+                    format!("Account {} has no SMTP configuration", account_id),
+                    ErrorCode::MissingConfiguration
+                ));
+            }
+            let from = request.from.clone().unwrap_or(account.email);
+            (SmtpServerType::Account(account_id), from)
+        }
+        (None, Some(mta_id)) => {
+            let from = request.from.clone().ok_or_else(|| {
+                raise_error!(
+                    "'from' is required when testing an MTA".into(),
+                    ErrorCode::InvalidParameter
+                )
+            })?;
+            (SmtpServerType::Mta(mta_id), from)
+        }
+        (None, None) => unreachable!("validated above"),
+    };
+    let endpoint = SmtpEndpoint::resolve(&server).await?;
+    let message = if request.rcpt_only {
+        None
+    } else {
+        Some(build_test_message(&from, &request.recipient)?)
+    };
+
+    let (tx, rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        let probe = Probe { tx };
+        let started = Instant::now();
+        let passed = probe
+            .run(&endpoint, &from, &request.recipient, message)
+            .await;
+        probe
+            .emit(SmtpProbeStage::Finished, started, Vec::new(), passed, None)
+            .await;
+    });
+    Ok(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (event, rx))
+    })
+    .boxed())
+}
+
+fn build_test_message(from: &str, recipient: &str) -> RustMailerResult<Vec<u8>> {
+    MessageBuilder::new()
+        .from(Address::new_address(
+            None::<&str>,
+            Cow::Owned(from.to_string()),
+        ))
+        .to(Address::new_address(
+            None::<&str>,
+            Cow::Owned(recipient.to_string()),
+        ))
+        .subject("RustMailer SMTP connectivity test")
+        .text_body("This message was sent by the RustMailer SMTP connectivity test.")
+        .message_id(generate_message_id(resolve_message_id_domain(None, from)))
+        .write_to_vec()
+        .map_err(|e| {
+            raise_error!(
+                format!("Failed to build message: {}", e),
+                ErrorCode::InternalError
+            )
+        })
+}
+
+fn format_reply(reply: &Response<String>) -> String {
+    format!("S: {} {}", reply.code, reply.message)
+}
+
+struct Probe {
+    tx: mpsc::Sender<SmtpProbeEvent>,
+}
+
+impl Probe {
+    /// Sends the event of a stage and returns whether it passed. A closed channel means
+    /// the client went away, which is not an error for the probe itself.
+    async fn emit(
+        &self,
+        stage: SmtpProbeStage,
+        started: Instant,
+        transcript: Vec<String>,
+        passed: bool,
+        error: Option<String>,
+    ) -> bool {
+        let _ = self
+            .tx
+            .send(SmtpProbeEvent {
+                stage,
+                passed,
+                transcript,
+                error,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            })
+            .await;
+        passed
+    }
+
+    async fn run(
+        &self,
+        endpoint: &SmtpEndpoint,
+        from: &str,
+        recipient: &str,
+        message: Option<Vec<u8>>,
+    ) -> bool {
+        let started = Instant::now();
+        let target = match endpoint.use_proxy {
+            Some(proxy_id) => format!(
+                "{}:{} through proxy {}",
+                endpoint.host, endpoint.port, proxy_id
+            ),
+            None => format!("{}:{}", endpoint.host, endpoint.port),
+        };
+        let stream = match endpoint.tcp_connect(PROBE_TIMEOUT).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                return self
+                    .emit(
+                        SmtpProbeStage::Connect,
+                        started,
+                        vec![format!("Connecting to {}", target)],
+                        false,
+                        Some(e.to_string()),
+                    )
+                    .await;
+            }
+        };
+        self.emit(
+            SmtpProbeStage::Connect,
+            started,
+            vec![format!("Connected to {}", target)],
+            true,
+            None,
+        )
+        .await;
+
+        let mut client = SmtpClient {
+            stream,
+            timeout: PROBE_TIMEOUT,
+        };
+        let local_host = gethostname::gethostname()
+            .to_str()
+            .unwrap_or("[127.0.0.1]")
+            .to_string();
+        let tls_connector = build_tls_connector(false);
+
+        match endpoint.encryption {
+            Encryption::Ssl => {
+                let started = Instant::now();
+                let mut client = match client.into_tls(&tls_connector, &endpoint.host).await {
+                    Ok(client) => client,
+                    Err(e) => {
+                        return self
+                            .emit(
+                                SmtpProbeStage::Tls,
+                                started,
+                                Vec::new(),
+                                false,
+                                Some(e.to_string()),
+                            )
+                            .await;
+                    }
+                };
+                self.emit(
+                    SmtpProbeStage::Tls,
+                    started,
+                    vec![format!("TLS handshake with {} completed", endpoint.host)],
+                    true,
+                    None,
+                )
+                .await;
+                if !self.greeting(&mut client).await {
+                    return false;
+                }
+                let Some(capabilities) = self.ehlo(&mut client, &local_host).await else {
+                    return false;
+                };
+                self.transaction(
+                    &mut client,
+                    &capabilities,
+                    &endpoint.credentials,
+                    from,
+                    recipient,
+                    message,
+                )
+                .await
+            }
+            Encryption::StartTls => {
+                if !self.greeting(&mut client).await {
+                    return false;
+                }
+                let Some(capabilities) = self.ehlo(&mut client, &local_host).await else {
+                    return false;
+                };
+                let started = Instant::now();
+                if !capabilities.has_capability(EXT_START_TLS) {
+                    return self
+                        .emit(
+                            SmtpProbeStage::StartTls,
+                            started,
+                            Vec::new(),
+                            false,
+                            Some("The server does not advertise STARTTLS".into()),
+                        )
+                        .await;
+                }
+                let mut client = match client.start_tls(&tls_connector, &endpoint.host).await {
+                    Ok(client) => client,
+                    Err(e) => {
+                        return self
+                            .emit(
+                                SmtpProbeStage::StartTls,
+                                started,
+                                vec!["C: STARTTLS".into()],
+                                false,
+                                Some(e.to_string()),
+                            )
+                            .await;
+                    }
+                };
+                self.emit(
+                    SmtpProbeStage::StartTls,
+                    started,
+                    vec![
+                        "C: STARTTLS".into(),
+                        format!("TLS handshake with {} completed", endpoint.host),
+                    ],
+                    true,
+                    None,
+                )
+                .await;
+                // Capabilities must be requested again over the encrypted channel.
+                let Some(capabilities) = self.ehlo(&mut client, &local_host).await else {
+                    return false;
+                };
+                self.transaction(
+                    &mut client,
+                    &capabilities,
+                    &endpoint.credentials,
+                    from,
+                    recipient,
+                    message,
+                )
+                .await
+            }
+            Encryption::None => {
+                if !self.greeting(&mut client).await {
+                    return false;
+                }
+                let Some(capabilities) = self.ehlo(&mut client, &local_host).await else {
+                    return false;
+                };
+                self.transaction(
+                    &mut client,
+                    &capabilities,
+                    &endpoint.credentials,
+                    from,
+                    recipient,
+                    message,
+                )
+                .await
+            }
+        }
+    }
+
+    async fn greeting<T: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        client: &mut SmtpClient<T>,
+    ) -> bool {
+        let started = Instant::now();
+        match client.read().await {
+            Ok(reply) => {
+                let passed = (200..300).contains(&reply.code);
+                let error = (!passed).then(|| "The server rejected the connection".to_string());
+                self.emit(
+                    SmtpProbeStage::Greeting,
+                    started,
+                    vec![format_reply(&reply)],
+                    passed,
+                    error,
+                )
+                .await
+            }
+            Err(e) => {
+                self.emit(
+                    SmtpProbeStage::Greeting,
+                    started,
+                    Vec::new(),
+                    false,
+                    Some(e.to_string()),
+                )
+                .await
+            }
+        }
+    }
+
+    async fn ehlo<T: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        client: &mut SmtpClient<T>,
+        local_host: &str,
+    ) -> Option<EhloResponse<String>> {
+        let started = Instant::now();
+        let mut transcript = vec![format!("C: EHLO {}", local_host)];
+        match client.capabilities(local_host, false).await {
+            Ok(capabilities) => {
+                transcript.push(format!("S: 250 {}", capabilities.hostname));
+                transcript.push(format!(
+                    "STARTTLS {}advertised",
+                    if capabilities.has_capability(EXT_START_TLS) {
+                        ""
+                    } else {
+                        "not "
+                    }
+                ));
+                self.emit(SmtpProbeStage::Ehlo, started, transcript, true, None)
+                    .await;
+                Some(capabilities)
+            }
+            Err(e) => {
+                self.emit(
+                    SmtpProbeStage::Ehlo,
+                    started,
+                    transcript,
+                    false,
+                    Some(e.to_string()),
+                )
+                .await;
+                None
+            }
+        }
+    }
+
+    async fn transaction<T: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        client: &mut SmtpClient<T>,
+        capabilities: &EhloResponse<String>,
+        credentials: &Credentials<String>,
+        from: &str,
+        recipient: &str,
+        message: Option<Vec<u8>>,
+    ) -> bool {
+        let started = Instant::now();
+        let transcript = vec!["C: AUTH [credentials redacted]".to_string()];
+        let authenticated = match client.authenticate(credentials, capabilities).await {
+            Ok(_) => {
+                self.emit(
+                    SmtpProbeStage::Authenticate,
+                    started,
+                    transcript,
+                    true,
+                    None,
+                )
+                .await
+            }
+            Err(e) => {
+                self.emit(
+                    SmtpProbeStage::Authenticate,
+                    started,
+                    transcript,
+                    false,
+                    Some(e.to_string()),
+                )
+                .await
+            }
+        };
+        if !authenticated {
+            return false;
+        }
+
+        if !self
+            .command(
+                client,
+                SmtpProbeStage::MailFrom,
+                format!("MAIL FROM:<{}>", from),
+            )
+            .await
+        {
+            return false;
+        }
+        if !self
+            .command(
+                client,
+                SmtpProbeStage::RcptTo,
+                format!("RCPT TO:<{}>", recipient),
+            )
+            .await
+        {
+            return false;
+        }
+
+        let delivered = match message {
+            Some(message) => {
+                let started = Instant::now();
+                let transcript = vec![
+                    "C: DATA".to_string(),
+                    format!("C: <{} bytes>", message.len()),
+                    "C: .".to_string(),
+                ];
+                match client.data(message).await {
+                    Ok(()) => {
+                        self.emit(SmtpProbeStage::Data, started, transcript, true, None)
+                            .await
+                    }
+                    Err(e) => {
+                        self.emit(
+                            SmtpProbeStage::Data,
+                            started,
+                            transcript,
+                            false,
+                            Some(e.to_string()),
+                        )
+                        .await
+                    }
+                }
+            }
+            None => {
+                self.command(client, SmtpProbeStage::Reset, "RSET".to_string())
+                    .await
+            }
+        };
+
+        let quit = self
+            .command(client, SmtpProbeStage::Quit, "QUIT".to_string())
+            .await;
+        delivered && quit
+    }
+
+    /// Sends a single command and expects a positive completion reply (2xx).
+    async fn command<T: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        client: &mut SmtpClient<T>,
+        stage: SmtpProbeStage,
+        command: String,
+    ) -> bool {
+        let started = Instant::now();
+        let mut transcript = vec![format!("C: {}", command)];
+        match client.cmd(format!("{}\r\n", command).as_bytes()).await {
+            Ok(reply) => {
+                transcript.push(format_reply(&reply));
+                let passed = (200..300).contains(&reply.code);
+                let error = (!passed).then(|| format!("Unexpected reply code {}", reply.code));
+                self.emit(stage, started, transcript, passed, error).await
+            }
+            Err(e) => {
+                self.emit(stage, started, transcript, false, Some(e.to_string()))
+                    .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_requires_exactly_one_target() {
+        let mut request = SmtpProbeRequest {
+            recipient: "user@example.com".into(),
+            ..Default::default()
+        };
+        assert!(request.validate().is_err());
+        request.account_id = Some(1);
+        assert!(request.validate().is_ok());
+        request.mta_id = Some(2);
+        assert!(request.validate().is_err());
+        request.mta_id = None;
+        request.recipient = "not-an-address".into();
+        assert!(request.validate().is_err());
+    }
+}
@@ -0,0 +1,398 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeSet;
+
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modules::{
+        account::tags::{account_ids_with_tags, parse_tag_selector},
+        common::auth::ClientContext,
+        database::manager::DB_MANAGER,
+        error::{code::ErrorCode, RustMailerResult},
+        scheduler::{
+            model::TaskStatus,
+            nativedb::{meta::NativeDbTaskStore, TaskMetaEntity},
+            task::Task,
+        },
+        smtp::{
+            queue::message::SendEmailTask,
+            request::{task::SmtpTask, EmailHandler},
+        },
+        tasks::{dead_letter::DeadLetter, queue::RustMailerTaskQueue},
+    },
+    raise_error, utc_now,
+};
+
+/// Operation applied to all email tasks matching a filter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum BulkTaskAction {
+    /// Stops scheduled tasks so they are never sent.
+    Cancel,
+    /// Reschedules failed or stopped tasks to run immediately, resetting their retry count.
+    Retry,
+    /// Stops scheduled tasks until they are resumed, keeping their schedule.
+    Pause,
+    /// Schedules paused tasks again. Tasks whose scheduled time has passed run immediately.
+    Resume,
+    /// Moves scheduled or paused tasks to `send_at`. Paused tasks stay paused.
+    Reschedule,
+}
+
+impl BulkTaskAction {
+    /// Task statuses the action can be applied to.
+    pub fn eligible_statuses(&self) -> &'static [TaskStatus] {
+        match self {
+            BulkTaskAction::Cancel => &[TaskStatus::Scheduled],
+            BulkTaskAction::Retry => &[TaskStatus::Failed, TaskStatus::Stopped],
+            BulkTaskAction::Pause => &[TaskStatus::Scheduled],
+            BulkTaskAction::Resume => &[TaskStatus::Stopped],
+            BulkTaskAction::Reschedule => &[TaskStatus::Scheduled, TaskStatus::Stopped],
+        }
+    }
+
+    /// Whether the action can be applied to a task in one of the eligible statuses.
+    /// Only paused tasks can be resumed or rescheduled among the stopped ones.
+    fn accepts(&self, task: &TaskMetaEntity) -> bool {
+        match self {
+            BulkTaskAction::Resume => task.is_paused(),
+            BulkTaskAction::Reschedule => task.status != TaskStatus::Stopped || task.is_paused(),
+            _ => true,
+        }
+    }
+
+    fn past_tense(&self) -> &'static str {
+        match self {
+            BulkTaskAction::Cancel => "cancelled",
+            BulkTaskAction::Retry => "retried",
+            BulkTaskAction::Pause => "paused",
+            BulkTaskAction::Resume => "resumed",
+            BulkTaskAction::Reschedule => "rescheduled",
+        }
+    }
+
+    /// Checks `send_at`, which is required to reschedule tasks and not accepted otherwise.
+    fn validate_send_at(&self, send_at: Option<i64>) -> RustMailerResult<()> {
+        match (self, send_at) {
+            (BulkTaskAction::Reschedule, Some(send_at)) => {
+                EmailHandler::validate_send_at(send_at, utc_now!())
+                    .map_err(|e| raise_error!(e, ErrorCode::InvalidParameter))
+            }
+            (BulkTaskAction::Reschedule, None) => Err(raise_error!(
+                "'send_at' is required to reschedule tasks".into(),
+                ErrorCode::InvalidParameter
+            )),
+            (_, Some(_)) => Err(raise_error!(
+                "'send_at' is only accepted to reschedule tasks".into(),
+                ErrorCode::InvalidParameter
+            )),
+            (_, None) => Ok(()),
+        }
+    }
+
+    /// Applies the action to the given tasks, returning how many were modified.
+    async fn apply(&self, task_ids: Vec<u64>, send_at: Option<i64>) -> RustMailerResult<usize> {
+        let database = DB_MANAGER.tasks_db();
+        match self {
+            BulkTaskAction::Cancel => {
+                NativeDbTaskStore::bulk_stop(
+                    database,
+                    task_ids,
+                    "Cancelled by bulk operation".into(),
+                )
+                .await
+            }
+            BulkTaskAction::Retry => {
+                let updated = NativeDbTaskStore::bulk_reschedule(
+                    database,
+                    task_ids.clone(),
+                    self.eligible_statuses().to_vec(),
+                )
+                .await?;
+                // The retried tasks are back in the queue, so they are no longer dead letters.
+                DeadLetter::remove_tasks(task_ids).await?;
+                Ok(updated)
+            }
+            BulkTaskAction::Pause => NativeDbTaskStore::bulk_pause(database, task_ids).await,
+            BulkTaskAction::Resume => NativeDbTaskStore::bulk_resume(database, task_ids).await,
+            BulkTaskAction::Reschedule => {
+                let send_at = send_at.unwrap_or_else(|| utc_now!());
+                NativeDbTaskStore::bulk_set_next_run(database, task_ids, send_at).await
+            }
+        }
+    }
+}
+
+/// Selects email tasks for a bulk operation. All set criteria must match.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct EmailTaskFilter {
+    /// Only tasks of these accounts. Access tokens are always limited to the accounts
+    /// they can access.
+    pub account_ids: Option<Vec<u64>>,
+    /// Only tasks of accounts carrying all of these tags.
+    pub account_tags: Option<Vec<String>>,
+    /// Only tasks sent with this `campaign_id`.
+    pub campaign_id: Option<String>,
+    /// Only tasks in these statuses. Statuses the action cannot be applied to are ignored.
+    pub statuses: Option<Vec<TaskStatus>>,
+    /// Only tasks created at or after this time (Unix epoch milliseconds).
+    pub created_after: Option<i64>,
+    /// Only tasks created before this time (Unix epoch milliseconds).
+    pub created_before: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct BulkTaskRequest {
+    /// Criteria selecting the tasks to operate on.
+    pub filter: EmailTaskFilter,
+    /// If `true`, only counts the matching tasks without modifying them.
+    pub dry_run: Option<bool>,
+    /// The new send time (Unix epoch milliseconds) of rescheduled tasks, within the next
+    /// 2 weeks. Required to reschedule tasks, and not accepted by the other operations.
+    pub send_at: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct RescheduleEmailTaskRequest {
+    /// The new send time (Unix epoch milliseconds), within the next 2 weeks.
+    pub send_at: i64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct BulkTaskResult {
+    /// Number of tasks matching the filter that the action can be applied to.
+    pub matched: u64,
+    /// Number of tasks actually modified. Always `0` for dry runs. May be lower than
+    /// `matched` if tasks changed status while the operation was running.
+    pub updated: u64,
+    /// Whether this was a dry run.
+    pub dry_run: bool,
+}
+
+impl EmailTaskFilter {
+    fn validate(&self) -> RustMailerResult<()> {
+        if let (Some(after), Some(before)) = (self.created_after, self.created_before) {
+            if after >= before {
+                return Err(raise_error!(
+                    "'created_after' must be earlier than 'created_before'".into(),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn matches(
+        &self,
+        status: &TaskStatus,
+        task: &SmtpTask,
+        eligible: &[TaskStatus],
+        accounts: Option<&BTreeSet<u64>>,
+    ) -> bool {
+        eligible.contains(status)
+            && self.statuses.as_ref().is_none_or(|s| s.contains(status))
+            && accounts.is_none_or(|a| a.contains(&task.account_id))
+            && self.campaign_id.as_ref().is_none_or(|campaign_id| {
+                task.control.as_ref().and_then(|c| c.campaign_id.as_ref()) == Some(campaign_id)
+            })
+    }
+
+    /// Accounts the operation is limited to, or `None` for all accounts.
+    async fn scope(&self, context: &ClientContext) -> RustMailerResult<Option<BTreeSet<u64>>> {
+        let scope: Option<BTreeSet<u64>> = match &self.account_ids {
+            Some(account_ids) => {
+                for account_id in account_ids {
+                    context.require_account_access(*account_id)?;
+                }
+                Some(account_ids.iter().copied().collect())
+            }
+            None => context
+                .accessible_accounts()?
+                .map(|accounts| accounts.iter().map(|a| a.id).collect()),
+        };
+        let Some(tags) = &self.account_tags else {
+            return Ok(scope);
+        };
+        let tagged = account_ids_with_tags(&parse_tag_selector(&tags.join(","))).await?;
+        Ok(Some(match scope {
+            Some(scope) => scope.intersection(&tagged).copied().collect(),
+            None => tagged,
+        }))
+    }
+}
+
+/// Applies `action` to all email tasks matching the request's filter.
+pub async fn run_bulk_task_action(
+    context: &ClientContext,
+    action: BulkTaskAction,
+    request: &BulkTaskRequest,
+) -> RustMailerResult<BulkTaskResult> {
+    let filter = &request.filter;
+    filter.validate()?;
+    action.validate_send_at(request.send_at)?;
+    let accounts = filter.scope(context).await?;
+    let eligible = action.eligible_statuses();
+
+    let candidates = NativeDbTaskStore::list_created_between(
+        DB_MANAGER.tasks_db(),
+        SmtpTask::TASK_KEY,
+        filter.created_after,
+        filter.created_before,
+    )
+    .await?;
+
+    let mut task_ids = Vec::new();
+    for candidate in candidates {
+        let task: SmtpTask = serde_json::from_str(&candidate.task_params)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        if filter.matches(&candidate.status, &task, eligible, accounts.as_ref())
+            && action.accepts(&candidate)
+        {
+            task_ids.push(candidate.id);
+        }
+    }
+
+    let matched = task_ids.len() as u64;
+    let dry_run = request.dry_run.unwrap_or(false);
+    if dry_run || task_ids.is_empty() {
+        return Ok(BulkTaskResult {
+            matched,
+            updated: 0,
+            dry_run,
+        });
+    }
+
+    let updated = action.apply(task_ids, request.send_at).await?;
+    Ok(BulkTaskResult {
+        matched,
+        updated: updated as u64,
+        dry_run,
+    })
+}
+
+/// Applies `action` to the email task `task_id` and returns the updated task.
+pub async fn run_task_action(
+    context: &ClientContext,
+    task_id: u64,
+    action: BulkTaskAction,
+    send_at: Option<i64>,
+) -> RustMailerResult<SendEmailTask> {
+    action.validate_send_at(send_at)?;
+    let queue = RustMailerTaskQueue::get()?;
+    let task = queue
+        .get_email_task(task_id)
+        .await?
+        .ok_or_else(|| raise_error!("Task not found".into(), ErrorCode::ResourceNotFound))?;
+    context.require_account_access(task.account_id)?;
+
+    if action.apply(vec![task_id], send_at).await? == 0 {
+        return Err(raise_error!(
+            format!(
+                "The task with id={} is {:?} and cannot be {}.",
+                task_id,
+                task.status,
+                action.past_tense()
+            ),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    queue
+        .get_email_task(task_id)
+        .await?
+        .ok_or_else(|| raise_error!("Task not found".into(), ErrorCode::ResourceNotFound))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::scheduler::nativedb::PAUSED_REASON;
+    use crate::modules::smtp::request::SendControl;
+
+    fn task(account_id: u64, campaign_id: Option<&str>) -> SmtpTask {
+        SmtpTask {
+            account_id,
+            account_email: "sender@example.com".into(),
+            subject: None,
+            message_id: "<1.abc@example.com>".into(),
+            from: "sender@example.com".into(),
+            to: vec!["rcpt@example.com".into()],
+            cc: None,
+            bcc: None,
+            attachment_count: 0,
+            control: Some(SendControl {
+                campaign_id: campaign_id.map(Into::into),
+                ..Default::default()
+            }),
+            cache_key: "key".into(),
+            answer_email: None,
+            delivery_status: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn matches_filter_criteria() {
+        let cancel = BulkTaskAction::Cancel.eligible_statuses();
+        let retry = BulkTaskAction::Retry.eligible_statuses();
+        let all = EmailTaskFilter::default();
+        assert!(all.matches(&TaskStatus::Scheduled, &task(1, None), cancel, None));
+        assert!(!all.matches(&TaskStatus::Running, &task(1, None), cancel, None));
+        assert!(!all.matches(&TaskStatus::Scheduled, &task(1, None), retry, None));
+        assert!(all.matches(&TaskStatus::Failed, &task(1, None), retry, None));
+
+        let accounts: BTreeSet<u64> = [2].into();
+        assert!(!all.matches(&TaskStatus::Failed, &task(1, None), retry, Some(&accounts)));
+        assert!(all.matches(&TaskStatus::Failed, &task(2, None), retry, Some(&accounts)));
+
+        let campaign = EmailTaskFilter {
+            campaign_id: Some("spring".into()),
+            statuses: Some(vec![TaskStatus::Stopped]),
+            ..Default::default()
+        };
+        assert!(campaign.matches(&TaskStatus::Stopped, &task(1, Some("spring")), retry, None));
+        assert!(!campaign.matches(&TaskStatus::Failed, &task(1, Some("spring")), retry, None));
+        assert!(!campaign.matches(&TaskStatus::Stopped, &task(1, Some("fall")), retry, None));
+        assert!(!campaign.matches(&TaskStatus::Stopped, &task(1, None), retry, None));
+    }
+
+    #[test]
+    fn only_paused_tasks_are_resumed() {
+        let scheduled = TaskMetaEntity::default();
+        let cancelled = TaskMetaEntity {
+            status: TaskStatus::Stopped,
+            stopped_reason: Some("Cancelled by bulk operation".into()),
+            ..Default::default()
+        };
+        let paused = TaskMetaEntity {
+            status: TaskStatus::Stopped,
+            stopped_reason: Some(PAUSED_REASON.into()),
+            ..Default::default()
+        };
+        assert!(!BulkTaskAction::Resume.accepts(&cancelled));
+        assert!(BulkTaskAction::Resume.accepts(&paused));
+        assert!(BulkTaskAction::Reschedule.accepts(&scheduled));
+        assert!(BulkTaskAction::Reschedule.accepts(&paused));
+        assert!(!BulkTaskAction::Reschedule.accepts(&cancelled));
+
+        let send_at = utc_now!() + 60 * 60 * 1000;
+        assert!(BulkTaskAction::Reschedule.validate_send_at(None).is_err());
+        assert!(BulkTaskAction::Reschedule
+            .validate_send_at(Some(send_at))
+            .is_ok());
+        assert!(BulkTaskAction::Pause
+            .validate_send_at(Some(send_at))
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_empty_created_range() {
+        let filter = EmailTaskFilter {
+            created_after: Some(2_000),
+            created_before: Some(1_000),
+            ..Default::default()
+        };
+        assert!(filter.validate().is_err());
+    }
+}
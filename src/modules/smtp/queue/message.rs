@@ -4,6 +4,7 @@
 
 use crate::{
     modules::{
+        bounce::delivery::RecipientDeliveryStatus,
         error::{code::ErrorCode, RustMailerError, RustMailerResult},
         scheduler::{model::TaskStatus, nativedb::TaskMetaEntity},
         smtp::request::{task::SmtpTask, DSNConfig, MailEnvelope},
@@ -79,6 +80,9 @@ pub struct SendEmailTask {
     /// The optional unique ID (e.g., IMAP UID) of the original email in reply or forward scenarios.
     /// Used when `reply` is `Some(true)` (reply) or `Some(false)` (forward) to reference the original email.
     pub uid: Option<u32>,
+    /// The delivery outcome of each recipient, as reported by Delivery Status Notifications
+    /// received in the sending account. Empty until a notification has been linked to the task.
+    pub delivery_status: Vec<RecipientDeliveryStatus>,
 }
 
 impl TryFrom<&TaskMetaEntity> for SendEmailTask {
@@ -124,6 +128,7 @@ impl TryFrom<&TaskMetaEntity> for SendEmailTask {
             reply: smtp_task.answer_email.as_ref().map(|a| a.reply),
             mailbox: smtp_task.answer_email.as_ref().map(|a| a.mailbox.clone()),
             uid: smtp_task.answer_email.as_ref().map(|a| a.uid),
+            delivery_status: smtp_task.delivery_status,
         })
     }
 }
@@ -2,4 +2,6 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-pub mod message;
\ No newline at end of file
+pub mod bulk;
+pub mod message;
+pub mod rate;
\ No newline at end of file
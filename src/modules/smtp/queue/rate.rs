@@ -0,0 +1,201 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::sync::LazyLock;
+
+use dashmap::DashMap;
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modules::error::{code::ErrorCode, RustMailerResult},
+    raise_error,
+};
+
+/// Time (Unix epoch milliseconds) at which the next send of each sender is due.
+static NEXT_DUE: LazyLock<DashMap<RateLimitKey, i64>> = LazyLock::new(DashMap::new);
+
+/// Outbound send rate limit of an account or MTA, enforced when the email queue sends.
+///
+/// Emails over the limit stay queued and are sent as soon as the limit allows; waiting
+/// does not count against their retry policy.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SendRateLimit {
+    /// Maximum number of emails sent within `period_minutes`.
+    #[oai(validator(minimum(value = "1")))]
+    pub max_messages: u32,
+    /// Length of the period, in minutes, e.g. `60` for an hourly limit.
+    #[oai(validator(minimum(value = "1"), maximum(value = "10080")))]
+    pub period_minutes: u32,
+    /// Maximum number of emails sent back to back after an idle period, at most
+    /// `max_messages`. Defaults to 1, spacing the emails evenly over the period.
+    #[oai(validator(minimum(value = "1")))]
+    pub burst: Option<u32>,
+}
+
+impl SendRateLimit {
+    pub fn validate(&self) -> RustMailerResult<()> {
+        if self.max_messages == 0 {
+            return Err(raise_error!(
+                "'max_messages' of a send rate limit must be at least 1".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if self.period_minutes == 0 || self.period_minutes > 10080 {
+            return Err(raise_error!(
+                "'period_minutes' of a send rate limit must be between 1 and 10080".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if let Some(burst) = self.burst {
+            if burst == 0 || burst > self.max_messages {
+                return Err(raise_error!(
+                    "'burst' of a send rate limit must be between 1 and 'max_messages'".into(),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Time needed to earn one send, in milliseconds.
+    fn interval_ms(&self) -> i64 {
+        (self.period_minutes.max(1) as i64 * 60_000 / self.max_messages.max(1) as i64).max(1)
+    }
+
+    /// How far ahead of schedule sends may run, allowing `burst` sends back to back.
+    fn tolerance_ms(&self) -> i64 {
+        let burst = self.burst.unwrap_or(1).clamp(1, self.max_messages.max(1));
+        (burst as i64 - 1) * self.interval_ms()
+    }
+}
+
+/// The sender a rate limit applies to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RateLimitKey {
+    Account(u64),
+    Mta(u64),
+}
+
+/// Takes one send from every given limit, or none of them.
+///
+/// Each sender keeps the time at which its next send is due; a send is allowed while that
+/// time is at most the burst tolerance ahead of now. Returns the time (Unix epoch
+/// milliseconds) at which all limits allow a send if any of them is exhausted.
+pub fn acquire(limits: &[(RateLimitKey, &SendRateLimit)], now: i64) -> Result<(), i64> {
+    let mut available_at = now;
+    for (key, limit) in limits {
+        let due = next_due(*key, now);
+        if due - now > limit.tolerance_ms() {
+            available_at = available_at.max(due - limit.tolerance_ms());
+        }
+    }
+    if available_at > now {
+        return Err(available_at);
+    }
+    for (key, limit) in limits {
+        let due = next_due(*key, now);
+        NEXT_DUE.insert(*key, due + limit.interval_ms());
+    }
+    Ok(())
+}
+
+/// Current usage of the send rate limit of a sender.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SendRateUsage {
+    /// The configured limit.
+    pub limit: SendRateLimit,
+    /// Number of emails that can be sent back to back right now.
+    pub available_sends: u32,
+    /// When the limit is exhausted, the time (Unix epoch milliseconds) at which the next
+    /// send is allowed.
+    pub throttled_until: Option<i64>,
+}
+
+/// Reports how much of `limit` is left for `key` at `now`, without taking a send.
+pub fn usage(key: RateLimitKey, limit: &SendRateLimit, now: i64) -> SendRateUsage {
+    let ahead = next_due(key, now) - now;
+    let (available_sends, throttled_until) = if ahead > limit.tolerance_ms() {
+        (0, Some(now + ahead - limit.tolerance_ms()))
+    } else {
+        (
+            ((limit.tolerance_ms() - ahead) / limit.interval_ms()) as u32 + 1,
+            None,
+        )
+    };
+    SendRateUsage {
+        limit: limit.clone(),
+        available_sends,
+        throttled_until,
+    }
+}
+
+fn next_due(key: RateLimitKey, now: i64) -> i64 {
+    NEXT_DUE.get(&key).map_or(now, |due| (*due).max(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn per_minute(max_messages: u32, burst: Option<u32>) -> SendRateLimit {
+        SendRateLimit {
+            max_messages,
+            period_minutes: 1,
+            burst,
+        }
+    }
+
+    #[test]
+    fn spaces_sends_without_burst() {
+        let key = RateLimitKey::Account(u64::MAX);
+        let limit = per_minute(6, None);
+        assert_eq!(acquire(&[(key, &limit)], 0), Ok(()));
+        assert_eq!(acquire(&[(key, &limit)], 1_000), Err(10_000));
+        assert_eq!(acquire(&[(key, &limit)], 10_000), Ok(()));
+    }
+
+    #[test]
+    fn allows_bursts_after_idle_periods() {
+        let key = RateLimitKey::Account(u64::MAX - 1);
+        let limit = per_minute(60, Some(3));
+        for _ in 0..3 {
+            assert_eq!(acquire(&[(key, &limit)], 0), Ok(()));
+        }
+        assert_eq!(acquire(&[(key, &limit)], 0), Err(1_000));
+        assert_eq!(usage(key, &limit, 0).throttled_until, Some(1_000));
+        assert_eq!(usage(key, &limit, 1_500).available_sends, 1);
+        // After an idle period, a new burst is allowed, but no larger than `burst`.
+        assert_eq!(usage(key, &limit, 600_000).available_sends, 3);
+        for _ in 0..3 {
+            assert_eq!(acquire(&[(key, &limit)], 600_000), Ok(()));
+        }
+        assert!(acquire(&[(key, &limit)], 600_000).is_err());
+    }
+
+    #[test]
+    fn takes_nothing_when_one_limit_is_exhausted() {
+        let account = RateLimitKey::Account(u64::MAX - 2);
+        let mta = RateLimitKey::Mta(u64::MAX - 2);
+        let fast = per_minute(60, None);
+        let slow = per_minute(1, None);
+        assert_eq!(acquire(&[(account, &fast), (mta, &slow)], 0), Ok(()));
+        assert_eq!(
+            acquire(&[(account, &fast), (mta, &slow)], 1_000),
+            Err(60_000)
+        );
+        // The account's send was not consumed by the rejected attempt.
+        assert_eq!(acquire(&[(account, &fast)], 1_000), Ok(()));
+    }
+
+    #[test]
+    fn validates_limits() {
+        assert!(per_minute(10, Some(10)).validate().is_ok());
+        assert!(per_minute(0, None).validate().is_err());
+        assert!(per_minute(10, Some(11)).validate().is_err());
+        let mut limit = per_minute(10, None);
+        limit.period_minutes = 0;
+        assert!(limit.validate().is_err());
+    }
+}
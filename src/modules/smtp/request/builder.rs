@@ -3,8 +3,9 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::error::RustMailerResult;
+use crate::modules::smtp::request::SendEmailResponse;
 
 pub trait EmailBuilder {
     async fn validate(&self) -> RustMailerResult<()>;
-    async fn build(&self, account_id: u64) -> RustMailerResult<()>;
+    async fn build(&self, account_id: u64) -> RustMailerResult<SendEmailResponse>;
 }
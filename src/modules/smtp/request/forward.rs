@@ -3,14 +3,17 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::account::entity::MailerType;
-use crate::modules::cache::imap::migration::EmailEnvelopeV3;
+use crate::modules::cache::imap::migration::EmailEnvelopeV7;
+use crate::modules::common::importance::Importance;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::smtp::request::builder::EmailBuilder;
 use crate::modules::smtp::request::headers::HeaderValue;
 use crate::modules::smtp::request::task::AnswerEmail;
 use crate::modules::smtp::request::EmailHandler;
 use crate::modules::smtp::request::SendControl;
-use crate::modules::smtp::util::generate_message_id;
+use crate::modules::smtp::request::SendEmailResponse;
+use crate::modules::smtp::subject::forward_subject;
+use crate::modules::smtp::util::generate_account_message_id;
 use crate::validate_email;
 use crate::{
     modules::{
@@ -76,6 +79,11 @@ pub struct ForwardEmailRequest {
     ///
     /// This optional field allows specifying additional headers as key-value pairs.
     pub headers: Option<HashMap<String, HeaderValue>>,
+    /// The importance of the email, set through the `X-Priority` and `Importance` headers.
+    ///
+    /// If not set or `Normal`, no priority headers are added. Do not combine with custom
+    /// `X-Priority` or `Importance` headers.
+    pub importance: Option<Importance>,
 
     /// The sender's timezone (e.g., "Asia/Shanghai").
     ///
@@ -153,7 +161,7 @@ impl EmailBuilder for ForwardEmailRequest {
         }
     }
 
-    async fn build(&self, account_id: u64) -> RustMailerResult<()> {
+    async fn build(&self, account_id: u64) -> RustMailerResult<SendEmailResponse> {
         self.validate().await?;
         let account = &AccountModel::get(account_id).await?;
 
@@ -181,14 +189,25 @@ impl EmailBuilder for ForwardEmailRequest {
                 (envelope, None)
             }
             MailerType::GraphApi => todo!(),
+            MailerType::Jmap => {
+                return Err(raise_error!(
+                    "Forwarding emails is not supported for JMAP accounts.".into(),
+                    ErrorCode::Incompatible
+                ))
+            }
         };
         let from = Address::new_address(
             account.name.as_ref().map(|n| Cow::Owned(n.to_string())),
             Cow::Owned(account.email.clone()),
         );
-        let subject = format!("Fwd: {}", envelope.subject.as_deref().unwrap_or(""));
+        let subject = forward_subject(
+            envelope.subject.as_deref(),
+            self.send_control
+                .as_ref()
+                .and_then(|c| c.subject_prefixes.as_ref()),
+        );
         let mut builder = MessageBuilder::new().from(from).subject(subject.clone());
-        let message_id = generate_message_id();
+        let message_id = generate_account_message_id(account, &account.email);
         builder = self.apply_recipient_headers(builder, &message_id)?;
         builder = self.apply_custom_headers(builder)?;
         builder = self.apply_references(builder, &envelope)?;
@@ -202,7 +221,7 @@ impl EmailBuilder for ForwardEmailRequest {
             }
         }
 
-        EmailHandler::schedule_task(
+        let queued = EmailHandler::schedule_task(
             account,
             Some(subject.clone()),
             message_id,
@@ -215,7 +234,9 @@ impl EmailBuilder for ForwardEmailRequest {
             answer_email,
         )
         .await?;
-        Ok(())
+        Ok(SendEmailResponse {
+            messages: vec![queued],
+        })
     }
 }
 
@@ -242,18 +263,22 @@ impl ForwardEmailRequest {
         &self,
         builder: MessageBuilder<'static>,
     ) -> RustMailerResult<MessageBuilder<'static>> {
-        Ok(match &self.headers {
+        let builder = match &self.headers {
             Some(headers) => headers.iter().fold(builder, |b, (k, v)| {
                 b.header(k.clone(), v.clone().to_header_type())
             }),
             None => builder,
+        };
+        Ok(match &self.importance {
+            Some(importance) => importance.apply_headers(builder),
+            None => builder,
         })
     }
 
     fn apply_references(
         &self,
         builder: MessageBuilder<'static>,
-        envelope: &EmailEnvelopeV3,
+        envelope: &EmailEnvelopeV7,
     ) -> RustMailerResult<MessageBuilder<'static>> {
         let mut references = envelope.references.clone().unwrap_or_default();
         if let Some(message_id) = &envelope.message_id {
@@ -267,7 +292,7 @@ impl ForwardEmailRequest {
     async fn apply_content(
         &self,
         mut builder: MessageBuilder<'static>,
-        envelope: &EmailEnvelopeV3,
+        envelope: &EmailEnvelopeV7,
         account: &AccountModel,
     ) -> RustMailerResult<MessageBuilder<'static>> {
         let timezone = self.timezone.as_deref().unwrap_or("UTC");
@@ -362,9 +387,10 @@ impl ForwardEmailRequest {
                         content,
                     )
                 } else {
-                    builder.attachment(
+                    EmailHandler::attach_file(
+                        builder,
                         mime,
-                        attachment.file_name.clone().ok_or_else(|| {
+                        attachment.file_name.as_deref().ok_or_else(|| {
                             raise_error!(
                                 "Missing file_name for attachment".into(),
                                 ErrorCode::MissingConfiguration
@@ -3,24 +3,33 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::base64_decode_url_safe;
+use crate::base64_encode;
 use crate::encode_mailbox_name;
 use crate::generate_token;
-use crate::modules::cache::disk::DISK_CACHE;
+use crate::modules::cache::disk::{CacheNamespace, DISK_CACHE};
 use crate::modules::cache::imap::mailbox::EmailFlag;
 use crate::modules::cache::imap::mailbox::EnvelopeFlag;
 use crate::modules::cache::imap::mailbox::MailBox;
-use crate::modules::cache::imap::migration::EmailEnvelopeV3;
+use crate::modules::cache::imap::migration::EmailEnvelopeV7;
 use crate::modules::cache::vendor::gmail::sync::client::GmailClient;
 use crate::modules::cache::vendor::gmail::sync::envelope::GmailEnvelope;
 use crate::modules::cache::vendor::gmail::sync::labels::GmailLabels;
+use crate::modules::common::filename::{encode_param, encode_word, sanitize_filename};
 use crate::modules::common::Addr;
 use crate::modules::context::executors::RUST_MAIL_CONTEXT;
 use crate::modules::envelope::extractor::extract_envelope;
 use crate::modules::error::code::ErrorCode;
+use crate::modules::imap::append::{format_append_flags, format_internal_date, message_date};
+use crate::modules::message::attachment_policy::screen_outbound_attachments;
 use crate::modules::message::content::retrieve_email_content;
 use crate::modules::message::content::FullMessageContent;
 use crate::modules::message::content::MessageContentRequest;
+use crate::modules::smtp::loop_guard::check_mail_loop;
+use crate::modules::smtp::sent::{message_id_search_query, SentCopyAction, SENT_COPY_CHECK_DELAY};
+use crate::modules::smtp::subject::SubjectPrefixes;
+use crate::modules::smtp::suppression::apply_suppression_list;
 use crate::modules::smtp::template::preview::EmailPreview;
+use crate::modules::smtp::timeline::{DeliveryEvent, DeliveryEventKind};
 use crate::modules::tasks::queue::RustMailerTaskQueue;
 use crate::utc_now;
 use crate::validate_email;
@@ -35,6 +44,10 @@ use crate::{
 };
 use imap_proto::NameAttribute;
 use mail_send::mail_builder::headers::address::EmailAddress as SmtpEmailAddress;
+use mail_send::mail_builder::headers::content_type::ContentType;
+use mail_send::mail_builder::headers::raw::Raw;
+use mail_send::mail_builder::headers::HeaderType;
+use mail_send::mail_builder::mime::MimePart;
 use mail_send::mail_builder::{headers::address::Address, mime::BodyPart, MessageBuilder};
 use mail_send::smtp::message::IntoMessage;
 use mail_send::smtp::message::Parameters;
@@ -45,7 +58,9 @@ use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use task::AnswerEmail;
 use task::SmtpTask;
+use time_tz::timezones;
 use tokio::io::AsyncReadExt;
+use tracing::{info, warn};
 
 pub mod builder;
 pub mod forward;
@@ -54,6 +69,8 @@ pub mod new;
 pub mod parser;
 pub mod reply;
 pub mod task;
+pub mod thread;
+pub mod window;
 
 /// A structure representing the envelope of an email used for sending.
 ///
@@ -209,7 +226,7 @@ impl MailAttachment {
         mime_type: &str,
         account: &AccountModel,
     ) -> RustMailerResult<BodyPart<'static>> {
-        let (mut reader, _) = retrieve_email_attachment(
+        let (mut reader, _, _) = retrieve_email_attachment(
             account.id,
             AttachmentRequest {
                 id: attachment_ref.uid.to_string(),
@@ -217,6 +234,8 @@ impl MailAttachment {
                 attachment: Some(attachment_ref.attachment_data.clone()),
                 attachment_info: None,
                 filename: None,
+                offset: None,
+                length: None,
             },
         )
         .await?;
@@ -439,9 +458,20 @@ pub struct SendControl {
     /// The name of the folder where the email should be saved if `save_to_sent` is true.
     /// If `None` and `save_to_sent` is true, a default folder (e.g., "Sent") may be used.
     pub sent_folder: Option<String>,
+    /// Flags set on the copy saved to the sent folder. Defaults to `\Seen`; an empty list
+    /// saves the copy without flags.
+    pub sent_flags: Option<Vec<EnvelopeFlag>>,
+    /// Internal date (Unix epoch milliseconds) of the copy saved to the sent folder.
+    /// Defaults to the `Date` header of the email, so the sent folder stays in send order
+    /// even when the copy is saved later.
+    pub sent_date: Option<i64>,
     /// Whether to perform a dry run (simulate sending without actual delivery).
     /// Useful for testing email configurations without sending emails.
     pub dry_run: Option<bool>,
+    /// Whether to only compose the email: the full build pipeline runs (templates,
+    /// attachments, tracking and headers), and the raw RFC 822 message is returned in
+    /// `QueuedEmail.raw` instead of being queued. Nothing is sent or stored.
+    pub compose_only: Option<bool>,
     /// An optional Unix timestamp (milliseconds since epoch) specifying when to send the email.
     /// If `None`, the email is sent immediately.
     pub send_at: Option<i64>,
@@ -466,9 +496,41 @@ pub struct SendControl {
     /// If system tracking is disabled, this flag has no effect and no tracking will be inserted.
     /// - This field is **only used when sending new emails**
     pub enable_tracking: Option<bool>,
+
+    /// Delivers each recipient's email at this local time of day (`HH:MM`, 24-hour clock)
+    /// in the recipient's timezone, e.g. `09:00`.
+    ///
+    /// Recipients are split into per-timezone waves, each sent at the next occurrence of this
+    /// time in its timezone. A recipient's timezone is taken from `Recipient.timezone`, then
+    /// inferred from the domain of its address, and otherwise falls back to `default_timezone`.
+    /// Cannot be combined with `send_at`; a recipient's own `send_at` still takes precedence.
+    /// - This field is **only used when sending new emails**
+    pub local_send_time: Option<String>,
+
+    /// Timezone (e.g. "Europe/Berlin") for recipients whose timezone is unknown when
+    /// `local_send_time` is set. Defaults to UTC.
+    /// - This field is **only used when sending new emails**
+    pub default_timezone: Option<String>,
+    /// Subject prefixes used when generating the subject of replies and forwards,
+    /// including locale presets such as `SV:` or `AW:`. Existing prefixes of the original
+    /// subject are collapsed rather than repeated.
+    pub subject_prefixes: Option<SubjectPrefixes>,
+    /// Whether to lint the content before queueing and reject the request with a
+    /// `ContentPolicyViolation` error if any error-level problem is found, such as an
+    /// image-only body or a campaign email without an unsubscribe option.
+    /// - This field is **only used when sending new emails**
+    pub enforce_content_policy: Option<bool>,
+    /// Whether to send to recipients on the account's suppression list. By default,
+    /// suppressed recipients are dropped, and the email is rejected with a
+    /// `RecipientSuppressed` error if no recipient is left.
+    pub ignore_suppression_list: Option<bool>,
 }
 
 impl SendControl {
+    pub fn is_compose_only(&self) -> bool {
+        self.compose_only == Some(true)
+    }
+
     pub fn validate(&self) -> Result<(), Vec<String>> {
         let mut errors = Vec::new();
         if let Some(envelope) = &self.envelope {
@@ -486,6 +548,39 @@ impl SendControl {
                 errors.push(error);
             }
         }
+        if let Some(flags) = &self.sent_flags {
+            if let Err(error) = format_append_flags(flags) {
+                errors.push(format!("Invalid 'send_control.sent_flags': {}", error));
+            }
+        }
+        if let Some(sent_date) = self.sent_date {
+            if format_internal_date(sent_date).is_none() {
+                errors.push(format!("Invalid 'send_control.sent_date' {sent_date}"));
+            }
+        }
+        if let Some(local_send_time) = &self.local_send_time {
+            if self.send_at.is_some() {
+                errors.push(
+                    "'send_control.local_send_time' cannot be combined with 'send_control.send_at'"
+                        .into(),
+                );
+            }
+            if window::parse_local_time(local_send_time).is_none() {
+                errors.push(format!(
+                    "Invalid 'send_control.local_send_time' {local_send_time}, expected HH:MM"
+                ));
+            }
+        }
+        if let Some(timezone) = &self.default_timezone {
+            if timezones::get_by_name(timezone).is_none() {
+                errors.push(format!("Invalid timezone: {}", timezone));
+            }
+        }
+        if let Some(prefixes) = &self.subject_prefixes {
+            if let Err(error) = prefixes.validate() {
+                errors.push(error);
+            }
+        }
 
         if errors.is_empty() {
             Ok(())
@@ -526,18 +621,60 @@ impl SendControl {
         Ok((mail_params, rcpt_params))
     }
 
+    /// Appends the sent email to the Sent folder if `save_to_sent` is set, following the
+    /// account's sent copy policy to avoid duplicating copies saved by the provider.
     pub async fn save_to_sent_if_needed(
         &self,
         account_id: u64,
+        message_id: &str,
         body: &[u8],
     ) -> RustMailerResult<()> {
-        if let Some(true) = self.save_to_sent {
-            let encoded_sent_folder = self.resolve_sent_mailbox(account_id).await?;
-            let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
-            executor
-                .append(&encoded_sent_folder, None, None, body)
+        if self.save_to_sent != Some(true) {
+            return Ok(());
+        }
+        let account = AccountModel::get(account_id).await?;
+        let policy = account.sent_copy.unwrap_or_default();
+        let action = policy.action(account.smtp.as_ref().map(|smtp| smtp.host.as_str()));
+        if action == SentCopyAction::Skip {
+            info!(
+                account_id,
+                message_id, "Provider saves sent emails itself, skipping the Sent folder append"
+            );
+            return Ok(());
+        }
+
+        let encoded_sent_folder = self.resolve_sent_mailbox(account_id).await?;
+        let executor = RUST_MAIL_CONTEXT.imap(account_id).await?;
+        if action == SentCopyAction::CheckThenAppend {
+            // Providers store their copy asynchronously, give them a moment.
+            tokio::time::sleep(SENT_COPY_CHECK_DELAY).await;
+            let existing = executor
+                .uid_search(&encoded_sent_folder, &message_id_search_query(message_id))
                 .await?;
+            if !existing.is_empty() {
+                info!(
+                    account_id,
+                    message_id, "Sent folder already holds a copy, skipping the append"
+                );
+                return Ok(());
+            }
         }
+        let flags = match &self.sent_flags {
+            Some(flags) => format_append_flags(flags)?,
+            None => Some("\\Seen".into()),
+        };
+        let internal_date = self
+            .sent_date
+            .or_else(|| message_date(body))
+            .and_then(format_internal_date);
+        executor
+            .append(
+                &encoded_sent_folder,
+                flags.as_deref(),
+                internal_date.as_deref(),
+                body,
+            )
+            .await?;
         Ok(())
     }
 
@@ -589,9 +726,48 @@ impl SendControl {
     }
 }
 
+/// Result of a send, reply or forward request.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SendEmailResponse {
+    /// The emails created by the request, one per recipient group, in request order.
+    pub messages: Vec<QueuedEmail>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct QueuedEmail {
+    /// The `Message-ID` header of the email, including angle brackets, in the form
+    /// `<{timestamp_ms}.{32 hex chars}@{domain}>`.
+    ///
+    /// Delivery, bounce and reply events for this email carry the same value, so it
+    /// can be used to correlate them.
+    pub message_id: String,
+    /// Id of the send task, usable with the email task endpoints.
+    /// Not set for dry runs, since no task is created.
+    pub task_id: Option<u64>,
+    /// Time (Unix epoch milliseconds) the task is scheduled to run.
+    pub scheduled_at: Option<i64>,
+    /// Number of scheduled email tasks that run before this one.
+    pub queue_position: Option<u64>,
+    /// Estimated send time (Unix epoch milliseconds), based on the queue position,
+    /// the number of send workers and the average send duration.
+    pub estimated_send_at: Option<i64>,
+    /// The timezone wave the email was scheduled in, when sent with
+    /// `send_control.local_send_time`.
+    pub timezone: Option<String>,
+    /// Message-ID of the message this email replies to, when sent with
+    /// `Recipient.in_reply_to`.
+    pub in_reply_to: Option<String>,
+    /// The raw RFC 822 message, base64 encoded, when composed with
+    /// `send_control.compose_only`.
+    pub raw: Option<String>,
+    /// Recipients dropped from the email because they are on the account's suppression
+    /// list.
+    pub suppressed_recipients: Option<Vec<String>>,
+}
+
 pub struct EmailHandler;
 
-const TWO_WEEKS_IN_MS: i64 = 14 * 24 * 60 * 60 * 1000;
+pub const TWO_WEEKS_IN_MS: i64 = 14 * 24 * 60 * 60 * 1000;
 
 impl EmailHandler {
     pub fn validate_send_at(send_at: i64, now: i64) -> Result<(), String> {
@@ -606,7 +782,7 @@ impl EmailHandler {
 
     pub async fn retrieve_message_content(
         account: &AccountModel,
-        envelope: &EmailEnvelopeV3,
+        envelope: &EmailEnvelopeV7,
     ) -> RustMailerResult<Option<FullMessageContent>> {
         let body_meta = match &envelope.body_meta {
             Some(meta) => meta,
@@ -634,29 +810,29 @@ impl EmailHandler {
         account: &AccountModel,
         label_name: &str,
         mid: &str,
-    ) -> RustMailerResult<EmailEnvelopeV3> {
+    ) -> RustMailerResult<EmailEnvelopeV7> {
         let map = GmailClient::label_map(account.id, account.use_proxy).await?;
         if let Ok(label) = GmailLabels::get_by_name(account.id, label_name).await {
             if !account.minimal_sync() {
                 let envelope = GmailEnvelope::find(account.id, label.id, mid).await?;
                 if let Some(envelope) = envelope {
-                    return Ok(envelope.into_v3(&map));
+                    return Ok(envelope.into_v7(&map));
                 }
             }
         }
         let message = GmailClient::get_message(account.id, account.use_proxy, mid).await?;
         let envelope: GmailEnvelope = message.try_into()?;
-        Ok(envelope.into_v3(&map))
+        Ok(envelope.into_v7(&map))
     }
 
     pub async fn get_envelope(
         account: &AccountModel,
         mailbox_name: &str,
         uid: u32,
-    ) -> RustMailerResult<EmailEnvelopeV3> {
+    ) -> RustMailerResult<EmailEnvelopeV7> {
         if let Ok(mailbox) = MailBox::get(account.id, mailbox_name).await {
             if !account.minimal_sync() {
-                let envelope = EmailEnvelopeV3::find(account.id, mailbox.id, uid).await?;
+                let envelope = EmailEnvelopeV7::find(account.id, mailbox.id, uid).await?;
                 if let Some(envelope) = envelope {
                     return Ok(envelope);
                 }
@@ -727,7 +903,7 @@ impl EmailHandler {
     async fn add_attachment(
         builder: MessageBuilder<'static>,
         attachment: &ImapAttachment,
-        envelope: &EmailEnvelopeV3,
+        envelope: &EmailEnvelopeV7,
         inline: bool,
         account: &AccountModel,
     ) -> RustMailerResult<MessageBuilder<'static>> {
@@ -752,9 +928,10 @@ impl EmailHandler {
                 content,
             )
         } else {
-            builder.attachment(
+            Self::attach_file(
+                builder,
                 mime_type,
-                attachment.filename.clone().ok_or_else(|| {
+                attachment.filename.as_deref().ok_or_else(|| {
                     raise_error!("Missing filename".into(), ErrorCode::ImapUnexpectedResult)
                 })?,
                 content,
@@ -762,6 +939,29 @@ impl EmailHandler {
         })
     }
 
+    /// Adds a regular (non-inline) attachment to the message.
+    ///
+    /// The filename is sanitized first. Non-ASCII filenames are emitted RFC 2231 encoded in
+    /// the Content-Disposition `filename` parameter, and as an RFC 2047 encoded word in the
+    /// Content-Type `name` parameter for clients that only read the latter.
+    pub fn attach_file(
+        mut builder: MessageBuilder<'static>,
+        mime_type: String,
+        filename: &str,
+        content: impl Into<BodyPart<'static>>,
+    ) -> MessageBuilder<'static> {
+        let filename = sanitize_filename(filename).unwrap_or_else(|| "attachment".into());
+        let content_type =
+            ContentType::new(mime_type).attribute("name", encode_word(&filename).into_owned());
+        let disposition = format!("attachment;\r\n {}", encode_param("filename", &filename));
+        let part = MimePart::new(content_type, content).header(
+            "Content-Disposition",
+            HeaderType::Raw(Raw::new(disposition)),
+        );
+        builder.attachments.get_or_insert_with(Vec::new).push(part);
+        builder
+    }
+
     pub fn insert_preview(preview: &Option<String>, html: String) -> String {
         if let Some(preview) = preview {
             EmailPreview::insert_preview_into_html(&html, preview)
@@ -777,46 +977,76 @@ impl EmailHandler {
         cc: Option<Vec<EmailAddress>>,
         bcc: Option<Vec<EmailAddress>>,
         attachment_count: usize,
-        builder: MessageBuilder<'_>,
-        send_control: Option<SendControl>,
+        mut builder: MessageBuilder<'_>,
+        mut send_control: Option<SendControl>,
         send_at: Option<i64>,
         answer_email: Option<AnswerEmail>,
-    ) -> RustMailerResult<()> {
+    ) -> RustMailerResult<QueuedEmail> {
+        let dry_run = send_control
+            .as_ref()
+            .is_some_and(|c| c.dry_run == Some(true));
+        let compose_only = send_control
+            .as_ref()
+            .is_some_and(SendControl::is_compose_only);
+        let stripped = screen_outbound_attachments(
+            account,
+            &mut builder,
+            subject.as_deref(),
+            &message_id,
+            dry_run || compose_only,
+        )
+        .await?;
+        let attachment_count = attachment_count.saturating_sub(stripped);
         let message = builder.into_message().map_err(|e| {
             raise_error!(
                 format!("Failed to build message: {}", e),
                 ErrorCode::InternalError
             )
         })?;
+        // Return the composed message as is, without queuing it.
+        if compose_only {
+            return Ok(QueuedEmail {
+                message_id,
+                raw: Some(base64_encode!(&message.body)),
+                ..Default::default()
+            });
+        }
         // Skip sending if dry_run is enabled; used for testing or simulation.
-        if let Some(send_control) = &send_control {
-            if let Some(true) = send_control.dry_run {
-                return Ok(());
-            }
+        if dry_run {
+            return Ok(QueuedEmail {
+                message_id,
+                ..Default::default()
+            });
         }
 
+        let from = message.mail_from.email.to_string();
+        let mut to: Vec<String> = message
+            .rcpt_to
+            .into_iter()
+            .map(|t| t.email.to_string())
+            .collect();
+        let suppressed = apply_suppression_list(account.id, &mut to, &mut send_control).await?;
+        check_mail_loop(account, &from, &to, subject.as_deref(), &message_id).await?;
+
         let cache_key = generate_token!(128);
         DISK_CACHE
-            .put_cache(&cache_key, &message.body, true)
+            .put_cache(&cache_key, &message.body, CacheNamespace::Outgoing)
             .await?;
 
         let task = SmtpTask {
             account_id: account.id,
             account_email: account.email.clone(),
             subject,
-            message_id,
+            message_id: message_id.clone(),
             cc: Self::extract_address(cc),
             bcc: Self::extract_address(bcc),
             attachment_count,
             control: send_control,
-            from: message.mail_from.email.to_string(),
-            to: message
-                .rcpt_to
-                .into_iter()
-                .map(|t| t.email.to_string())
-                .collect(),
+            from,
+            to,
             cache_key,
             answer_email,
+            delivery_status: Vec::new(),
         };
 
         let delay_seconds = send_at
@@ -830,11 +1060,32 @@ impl EmailHandler {
             })
             .unwrap_or(None);
 
-        RustMailerTaskQueue::get()?
-            .submit_task(task, delay_seconds)
-            .await?;
+        let queue = RustMailerTaskQueue::get()?;
+        let meta = queue.submit_task(task.clone(), delay_seconds).await?;
+        DeliveryEvent::record_task(&task, meta.id, DeliveryEventKind::Queued, None).await;
+        // The task is already queued at this point, so a failed estimate must not fail the request.
+        let estimate = queue
+            .estimate_email_task_start(&meta)
+            .await
+            .inspect_err(|e| {
+                warn!(
+                    task_id = meta.id,
+                    "Failed to estimate queue position: {e:#?}"
+                )
+            })
+            .ok();
 
-        Ok(())
+        Ok(QueuedEmail {
+            message_id,
+            task_id: Some(meta.id),
+            scheduled_at: Some(meta.next_run),
+            queue_position: estimate.map(|(position, _)| position),
+            estimated_send_at: estimate.map(|(_, eta)| eta),
+            timezone: None,
+            in_reply_to: None,
+            raw: None,
+            suppressed_recipients: (!suppressed.is_empty()).then_some(suppressed),
+        })
     }
 
     pub fn extract_address(f: Option<Vec<EmailAddress>>) -> Option<Vec<String>> {
@@ -4,19 +4,25 @@
 
 use crate::{
     modules::{
-        account::migration::AccountModel,
+        account::{entity::MailerType, migration::AccountModel},
+        common::importance::Importance,
         error::{code::ErrorCode, RustMailerResult},
         settings::cli::SETTINGS,
         smtp::{
+            lint::lint_request,
+            markdown::MarkdownBody,
+            mta::entity::Mta,
             request::{
                 builder::EmailBuilder,
                 headers::HeaderValue,
                 parser::{AttachmentFromEml, EmlData},
-                EmailAddress, EmailHandler, MailAttachment, SendControl,
+                thread::{normalize_message_id, resolve_reply_targets},
+                window::DeliveryWaves,
+                EmailAddress, EmailHandler, MailAttachment, SendControl, SendEmailResponse,
             },
             template::{entity::EmailTemplate, render::Templates},
             track::EmailTracker,
-            util::generate_message_id,
+            util::generate_account_message_id,
         },
     },
     raise_error, utc_now, validate_email,
@@ -28,6 +34,7 @@ use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
 
 use std::{borrow::Cow, collections::HashMap};
+use time_tz::timezones;
 
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
 pub struct SendEmailRequest {
@@ -52,6 +59,13 @@ pub struct SendEmailRequest {
     ///
     /// This field is optional and can be used for emails that support HTML content.
     pub html: Option<String>,
+    /// The body of the email in markdown (CommonMark with tables, strikethrough and task lists).
+    ///
+    /// It is converted to a sanitized HTML body and a plain-text alternative: raw HTML is
+    /// escaped, and links with schemes other than http(s), mailto, tel or cid are neutralized.
+    /// If `text` is also set, it is used instead of the generated plain text. Cannot be
+    /// combined with `html`, `eml` or `template_id`.
+    pub markdown: Option<String>,
     /// A preview text for the email.
     ///
     /// This optional field provides a short summary or preview of the email content, often displayed in email clients.
@@ -72,6 +86,11 @@ pub struct SendEmailRequest {
     ///
     /// This optional field allows specifying additional headers (e.g., Reply-To, X-Custom-Header) as key-value pairs.
     pub headers: Option<HashMap<String, HeaderValue>>,
+    /// The importance of the email, set through the `X-Priority` and `Importance` headers.
+    ///
+    /// If not set or `Normal`, no priority headers are added. Do not combine with custom
+    /// `X-Priority` or `Importance` headers.
+    pub importance: Option<Importance>,
     /// Configuration options for controlling the email sending process.
     ///
     /// This required field specifies settings such as scheduling, or retry policies for sending the email.
@@ -107,6 +126,19 @@ pub struct Recipient {
     /// This optional field allows specifying a future time for sending the email. If not provided,
     /// the email is sent immediately.
     pub send_at: Option<i64>,
+    /// The recipient's timezone (e.g., "America/New_York"), used with
+    /// `send_control.local_send_time`.
+    ///
+    /// If not provided, it is inferred from the domain of the first `to` address.
+    pub timezone: Option<String>,
+    /// Message-ID of a message of the sending account this email replies to, e.g. to send
+    /// follow-ups to the earlier emails of a campaign.
+    ///
+    /// The email is threaded under that message with `In-Reply-To` and `References` headers,
+    /// and without a `subject` or template its subject is the original one prefixed with `Re:`.
+    /// The message must be in the account's local cache, otherwise the request is rejected.
+    /// Use `send_control.dry_run` to preview the resolved threads without sending.
+    pub in_reply_to: Option<String>,
 }
 
 impl Recipient {
@@ -152,6 +184,11 @@ impl Recipient {
                 errors.push(error);
             }
         }
+        if let Some(timezone) = &self.timezone {
+            if timezones::get_by_name(timezone).is_none() {
+                errors.push(format!("Invalid timezone: {}", timezone));
+            }
+        }
 
         if errors.is_empty() {
             Ok(())
@@ -181,6 +218,12 @@ impl EmailBuilder for SendEmailRequest {
             }
         }
 
+        if self.markdown.is_some()
+            && (self.html.is_some() || self.eml.is_some() || self.template_id.is_some())
+        {
+            errors.push("'markdown' cannot be combined with 'html', 'eml' or 'template_id'".into());
+        }
+
         if let Some(send_control) = &self.send_control {
             if let Err(mut send_control_error) = send_control.validate() {
                 errors.append(&mut send_control_error);
@@ -197,9 +240,32 @@ impl EmailBuilder for SendEmailRequest {
         }
     }
 
-    async fn build(&self, account_id: u64) -> RustMailerResult<()> {
+    async fn build(&self, account_id: u64) -> RustMailerResult<SendEmailResponse> {
         self.validate().await?;
         let account = &AccountModel::get(account_id).await?;
+        if self
+            .send_control
+            .as_ref()
+            .and_then(|c| c.enforce_content_policy)
+            .unwrap_or(false)
+        {
+            lint_request(account, self).await?.enforce()?;
+        }
+        if matches!(account.mailer_type, MailerType::Jmap)
+            && self.send_control.as_ref().and_then(|c| c.mta).is_none()
+            && !self
+                .send_control
+                .as_ref()
+                .is_some_and(SendControl::is_compose_only)
+        {
+            return Err(raise_error!(
+                "JMAP accounts cannot submit emails themselves; send them through an MTA by setting `mta` in the send control.".into(),
+                ErrorCode::Incompatible
+            ));
+        }
+        if let Some(mta) = self.send_control.as_ref().and_then(|c| c.mta) {
+            Mta::check_available_to(mta, account).await?;
+        }
         let from = self.from.clone().map(Into::into).unwrap_or_else(|| {
             Address::new_address(
                 account.name.as_ref().map(|n| Cow::Owned(n.to_string())),
@@ -207,15 +273,69 @@ impl EmailBuilder for SendEmailRequest {
             )
         });
 
+        let from_address = self
+            .from
+            .as_ref()
+            .map_or(account.email.as_str(), |f| f.address.as_str());
+
+        let mut waves = match &self.send_control {
+            Some(SendControl {
+                local_send_time: Some(local_send_time),
+                default_timezone,
+                ..
+            }) => Some(DeliveryWaves::new(
+                local_send_time,
+                default_timezone.as_deref(),
+                utc_now!(),
+            )?),
+            _ => None,
+        };
+
+        let reply_targets = resolve_reply_targets(
+            account,
+            self.recipients
+                .iter()
+                .filter_map(|r| r.in_reply_to.as_deref())
+                .map(|id| normalize_message_id(id).to_string())
+                .collect(),
+        )
+        .await?;
+
+        let mut messages = Vec::with_capacity(self.recipients.len());
         for recipient in &self.recipients {
+            let wave = match (&mut waves, recipient.send_at) {
+                (Some(waves), None) => Some(waves.schedule(recipient)?),
+                _ => None,
+            };
+            let send_at = recipient
+                .send_at
+                .or(wave.as_ref().map(|(_, send_at)| *send_at))
+                .or_else(|| self.send_control.as_ref().and_then(|c| c.send_at));
             let mut builder = MessageBuilder::new().from(from.clone());
-            let message_id = generate_message_id();
+            let message_id = generate_account_message_id(account, from_address);
             builder = Self::apply_recipient_headers(builder, recipient, &message_id)?;
+            let reply_target = recipient
+                .in_reply_to
+                .as_deref()
+                .and_then(|id| reply_targets.get(normalize_message_id(id)));
+            if let Some(target) = reply_target {
+                builder = target.apply(builder);
+                if self.subject.is_none() && self.template_id.is_none() && self.eml.is_none() {
+                    let prefixes = self
+                        .send_control
+                        .as_ref()
+                        .and_then(|c| c.subject_prefixes.as_ref());
+                    builder = builder.subject(target.reply_subject(prefixes));
+                }
+            }
             if let Some(headers) = &self.headers {
                 builder = headers.iter().fold(builder, |b, (k, v)| {
                     b.header(k.clone(), v.clone().to_header_type())
                 });
             }
+            if let Some(importance) = &self.importance {
+                builder = importance.apply_headers(builder);
+            }
             let mut tracker: Option<EmailTracker> = None;
 
             if let Some(send_control) = &self.send_control {
@@ -251,14 +371,11 @@ impl EmailBuilder for SendEmailRequest {
                 }
             };
 
-            if let Some(send_control) = &self.send_control {
-                let send_at = recipient.send_at.or(send_control.send_at);
-                if let Some(send_at) = send_at {
-                    builder = builder.date(send_at / 1000)
-                }
+            if let Some(send_at) = send_at {
+                builder = builder.date(send_at / 1000)
             }
 
-            EmailHandler::schedule_task(
+            let mut queued = EmailHandler::schedule_task(
                 account,
                 self.subject.clone(),
                 message_id,
@@ -267,19 +384,29 @@ impl EmailBuilder for SendEmailRequest {
                 self.attachments.as_ref().map_or(0, |v| v.len()),
                 builder,
                 self.send_control.clone(),
-                recipient
-                    .send_at
-                    .or_else(|| self.send_control.as_ref().and_then(|c| c.send_at)),
+                send_at,
                 None,
             )
             .await?;
+            queued.timezone = wave.map(|(timezone, _)| timezone);
+            queued.in_reply_to = reply_target.map(|target| target.message_id.clone());
+            messages.push(queued);
         }
 
-        Ok(())
+        Ok(SendEmailResponse { messages })
     }
 }
 
 impl SendEmailRequest {
+    /// The plain-text and HTML bodies given directly in the request, rendering `markdown`
+    /// if set.
+    pub fn body(&self) -> (Option<String>, Option<String>) {
+        match self.markdown.as_deref().map(MarkdownBody::render) {
+            Some(body) => (self.text.clone().or(Some(body.text)), Some(body.html)),
+            None => (self.text.clone(), self.html.clone()),
+        }
+    }
+
     fn apply_recipient_headers(
         mut builder: MessageBuilder<'static>,
         recipient: &Recipient,
@@ -348,8 +475,10 @@ impl SendEmailRequest {
         match self.template_id {
             Some(id) => {
                 let template = EmailTemplate::get(id).await?;
+                template.check_usable_by(account)?;
+                let partials = template.partials().await?;
                 let (subject, text, html) =
-                    Templates::render(&template, &recipient.template_params)?;
+                    Templates::render(&template, &recipient.template_params, &partials)?;
 
                 builder = builder.subject(subject);
                 if let Some(text) = text {
@@ -374,11 +503,12 @@ impl SendEmailRequest {
                 if let Some(subject) = &self.subject {
                     builder = builder.subject(subject.clone());
                 }
-                if let Some(text) = &self.text {
-                    builder = builder.text_body(text.clone());
+                let (text, html) = self.body();
+                if let Some(text) = text {
+                    builder = builder.text_body(text);
                 }
-                if let Some(html) = &self.html {
-                    let html = EmailHandler::insert_preview(&self.preview, html.clone());
+                if let Some(html) = html {
+                    let html = EmailHandler::insert_preview(&self.preview, html);
                     match tracker {
                         Some(mut tracker) => {
                             tracker.set_html(html);
@@ -422,9 +552,10 @@ impl SendEmailRequest {
                     attachment.content.clone(),
                 )
             } else {
-                builder.attachment(
+                EmailHandler::attach_file(
+                    builder,
                     mime.to_string(),
-                    attachment.file_name.clone().ok_or_else(|| {
+                    attachment.file_name.as_deref().ok_or_else(|| {
                         raise_error!(
                             "Missing file_name for attachment".into(),
                             ErrorCode::EmlFileParseError
@@ -463,9 +594,10 @@ impl SendEmailRequest {
                     content,
                 )
             } else {
-                builder.attachment(
+                EmailHandler::attach_file(
+                    builder,
                     mime.to_string(),
-                    attachment.file_name.clone().ok_or_else(|| {
+                    attachment.file_name.as_deref().ok_or_else(|| {
                         raise_error!(
                             "Missing file_name for attachment".into(),
                             ErrorCode::InvalidParameter
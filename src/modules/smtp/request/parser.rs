@@ -3,7 +3,10 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::{
-    modules::error::{code::ErrorCode, RustMailerResult},
+    modules::{
+        common::filename::sanitize_filename,
+        error::{code::ErrorCode, RustMailerResult},
+    },
     raise_error,
 };
 use mail_parser::{
@@ -122,7 +125,7 @@ impl EmlData {
             content,
             mime_type,
             inline: content_type.is_some_and(|c| c.is_inline()),
-            file_name: attachment.attachment_name().map(String::from),
+            file_name: attachment.attachment_name().and_then(sanitize_filename),
             content_id: attachment.content_id().map(String::from),
         })
     }
@@ -5,15 +5,17 @@
 use crate::{
     modules::{
         account::{entity::MailerType, migration::AccountModel},
-        cache::{imap::migration::EmailEnvelopeV3, vendor::gmail::sync::envelope::GmailEnvelope},
+        cache::{imap::migration::EmailEnvelopeV7, vendor::gmail::sync::envelope::GmailEnvelope},
+        common::importance::Importance,
         error::{code::ErrorCode, RustMailerResult},
         smtp::{
             composer::BodyComposer,
             request::{
                 builder::EmailBuilder, headers::HeaderValue, task::AnswerEmail, EmailAddress,
-                EmailHandler, MailAttachment, SendControl,
+                EmailHandler, MailAttachment, SendControl, SendEmailResponse,
             },
-            util::generate_message_id,
+            subject::reply_subject,
+            util::generate_account_message_id,
         },
     },
     raise_error, validate_email,
@@ -54,6 +56,11 @@ pub struct ReplyEmailRequest {
     ///
     /// This optional field allows specifying additional headers as key-value pairs.
     pub headers: Option<HashMap<String, HeaderValue>>,
+    /// The importance of the email, set through the `X-Priority` and `Importance` headers.
+    ///
+    /// If not set or `Normal`, no priority headers are added. Do not combine with custom
+    /// `X-Priority` or `Importance` headers.
+    pub importance: Option<Importance>,
     /// Whether to reply to all original recipients (Reply-All).
     ///
     /// If true, the reply will be sent to all original recipients, including Cc.
@@ -130,7 +137,7 @@ impl EmailBuilder for ReplyEmailRequest {
         }
     }
 
-    async fn build(&self, account_id: u64) -> RustMailerResult<()> {
+    async fn build(&self, account_id: u64) -> RustMailerResult<SendEmailResponse> {
         let account = &AccountModel::get(account_id).await?;
         self.validate().await?;
 
@@ -158,6 +165,12 @@ impl EmailBuilder for ReplyEmailRequest {
                 (envelope, None)
             }
             MailerType::GraphApi => todo!(),
+            MailerType::Jmap => {
+                return Err(raise_error!(
+                    "Replying to emails is not supported for JMAP accounts.".into(),
+                    ErrorCode::Incompatible
+                ))
+            }
         };
 
         let from = Address::new_address(
@@ -178,12 +191,17 @@ impl EmailBuilder for ReplyEmailRequest {
                     )
                 })?,
         };
-        let subject = format!("Re: {}", envelope.subject.as_deref().unwrap_or(""));
+        let subject = reply_subject(
+            envelope.subject.as_deref(),
+            self.send_control
+                .as_ref()
+                .and_then(|c| c.subject_prefixes.as_ref()),
+        );
         let mut builder = MessageBuilder::new()
             .from(from)
             .to(Address::from(to.clone()))
             .subject(subject.clone());
-        let message_id = generate_message_id();
+        let message_id = generate_account_message_id(account, &account.email);
         builder = self.apply_recipient_headers(builder, &envelope, &message_id)?;
         builder = self.apply_custom_headers(builder)?;
         builder = apply_references(builder, &envelope)?;
@@ -197,7 +215,7 @@ impl EmailBuilder for ReplyEmailRequest {
             }
         }
 
-        EmailHandler::schedule_task(
+        let queued = EmailHandler::schedule_task(
             account,
             Some(subject.clone()),
             message_id,
@@ -210,7 +228,9 @@ impl EmailBuilder for ReplyEmailRequest {
             answer_email,
         )
         .await?;
-        Ok(())
+        Ok(SendEmailResponse {
+            messages: vec![queued],
+        })
     }
 }
 
@@ -218,7 +238,7 @@ impl ReplyEmailRequest {
     fn apply_recipient_headers(
         &self,
         mut builder: MessageBuilder<'static>,
-        envelope: &EmailEnvelopeV3,
+        envelope: &EmailEnvelopeV7,
         message_id: &str,
     ) -> RustMailerResult<MessageBuilder<'static>> {
         if self.reply_all {
@@ -244,18 +264,22 @@ impl ReplyEmailRequest {
         &self,
         builder: MessageBuilder<'static>,
     ) -> RustMailerResult<MessageBuilder<'static>> {
-        Ok(match &self.headers {
+        let builder = match &self.headers {
             Some(headers) => headers.iter().fold(builder, |b, (k, v)| {
                 b.header(k.clone(), v.clone().to_header_type())
             }),
             None => builder,
+        };
+        Ok(match &self.importance {
+            Some(importance) => importance.apply_headers(builder),
+            None => builder,
         })
     }
 
     async fn apply_content(
         &self,
         mut builder: MessageBuilder<'static>,
-        envelope: &EmailEnvelopeV3,
+        envelope: &EmailEnvelopeV7,
         account: &AccountModel,
     ) -> RustMailerResult<MessageBuilder<'static>> {
         let timezone = self.timezone.as_deref().unwrap_or("UTC");
@@ -350,9 +374,10 @@ impl ReplyEmailRequest {
                         content,
                     )
                 } else {
-                    builder.attachment(
+                    EmailHandler::attach_file(
+                        builder,
                         mime,
-                        attachment.file_name.clone().ok_or_else(|| {
+                        attachment.file_name.as_deref().ok_or_else(|| {
                             raise_error!(
                                 "Missing file_name for attachment".into(),
                                 ErrorCode::InvalidParameter
@@ -369,7 +394,7 @@ impl ReplyEmailRequest {
 
 pub fn apply_references(
     builder: MessageBuilder<'static>,
-    envelope: &EmailEnvelopeV3,
+    envelope: &EmailEnvelopeV7,
 ) -> RustMailerResult<MessageBuilder<'static>> {
     let builder = if let Some(message_id) = &envelope.message_id {
         builder.in_reply_to(message_id.clone())
@@ -6,29 +6,34 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use crate::modules::account::entity::MailerType;
+use crate::modules::account::maintenance::MaintenanceWindow;
+use crate::modules::bounce::delivery::RecipientDeliveryStatus;
 use crate::modules::cache::disk::DISK_CACHE;
 use crate::modules::cache::vendor::gmail::sync::client::GmailClient;
 use crate::modules::error::code::ErrorCode;
 use crate::modules::error::RustMailerResult;
+use crate::modules::fault::{FaultInjector, FaultTarget};
 use crate::modules::hook::channel::{Event, EVENT_CHANNEL};
 use crate::modules::hook::events::{
     payload::EmailSentSuccess, EventPayload, EventType, RustMailerEvent,
 };
 use crate::modules::hook::task::EventHookTask;
 use crate::modules::metrics::{
-    FAILURE, RUSTMAILER_EMAIL_SEND_DURATION_SECONDS, RUSTMAILER_EMAIL_SENT_BYTES,
+    FAILURE, RUSTMAILER_ACCOUNT_EMAIL_SENT_BYTES, RUSTMAILER_ACCOUNT_EMAIL_SENT_TOTAL,
+    RUSTMAILER_EMAIL_SEND_DURATION_SECONDS, RUSTMAILER_EMAIL_SENT_BYTES,
     RUSTMAILER_EMAIL_SENT_TOTAL, SUCCESS,
 };
 use crate::modules::smtp::executor::SmtpExecutor;
-use crate::{base64_encode_url_safe, raise_error};
+use crate::{base64_encode_url_safe, raise_error, utc_now};
 
 use crate::modules::scheduler::{
     retry::{RetryPolicy, RetryStrategy},
-    task::{Task, TaskFuture},
+    task::{defer_task, Task, TaskFuture},
 };
 
 use crate::modules::smtp::{
     mta::entity::Mta,
+    queue::rate::{acquire, RateLimitKey},
     request::{EmailHandler, MailEnvelope, SendControl, Strategy},
 };
 
@@ -55,6 +60,9 @@ pub struct SmtpTask {
     pub control: Option<SendControl>,
     pub cache_key: String,
     pub answer_email: Option<AnswerEmail>,
+    /// Outcomes reported by delivery status notifications, one entry per recipient.
+    #[serde(default)]
+    pub delivery_status: Vec<RecipientDeliveryStatus>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
@@ -65,6 +73,21 @@ pub struct AnswerEmail {
 }
 
 impl SmtpTask {
+    /// Records the outcome reported for a recipient, replacing an earlier report for the
+    /// same recipient.
+    pub fn record_delivery_status(&mut self, status: RecipientDeliveryStatus) {
+        let same_recipient =
+            |existing: &RecipientDeliveryStatus| match (&existing.recipient, &status.recipient) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                (None, None) => true,
+                _ => false,
+            };
+        match self.delivery_status.iter_mut().find(|s| same_recipient(s)) {
+            Some(existing) => *existing = status,
+            None => self.delivery_status.push(status),
+        }
+    }
+
     async fn build_message<'a>(
         envelope: Option<&'a MailEnvelope>,
         body: &'a [u8],
@@ -118,7 +141,39 @@ impl SmtpTask {
         Ok(body)
     }
 
-    fn record_send_failure_metrics(start: Instant) {
+    /// Takes one send from the rate limits of the account and the MTA, deferring the
+    /// task until they allow it if any of them is exhausted.
+    fn acquire_send_rate(
+        &self,
+        task_id: u64,
+        account: &AccountModel,
+        mta: Option<&Mta>,
+    ) -> RustMailerResult<()> {
+        let mut limits = Vec::new();
+        if let Some(limit) = &account.send_rate_limit {
+            limits.push((RateLimitKey::Account(account.id), limit));
+        }
+        if let Some(mta) = mta {
+            if let Some(limit) = &mta.send_rate_limit {
+                limits.push((RateLimitKey::Mta(mta.id), limit));
+            }
+        }
+        if limits.is_empty() {
+            return Ok(());
+        }
+        acquire(&limits, utc_now!()).map_err(|run_at| {
+            defer_task(
+                task_id,
+                run_at,
+                format!(
+                    "Send rate limit reached for account {}, email deferred until {}.",
+                    account.id, run_at
+                ),
+            )
+        })
+    }
+
+    fn record_send_failure_metrics(&self, start: Instant) {
         let elapsed = start.elapsed();
         RUSTMAILER_EMAIL_SEND_DURATION_SECONDS
             .with_label_values(&[FAILURE])
@@ -126,6 +181,9 @@ impl SmtpTask {
         RUSTMAILER_EMAIL_SENT_TOTAL
             .with_label_values(&[FAILURE])
             .inc();
+        RUSTMAILER_ACCOUNT_EMAIL_SENT_TOTAL
+            .with_label_values(&[&self.account_id.to_string(), FAILURE])
+            .inc();
     }
 
     async fn handle_email_send_success(
@@ -141,6 +199,13 @@ impl SmtpTask {
             .with_label_values(&[SUCCESS])
             .inc();
         RUSTMAILER_EMAIL_SENT_BYTES.inc_by(body_len as u64);
+        let account_id = self.account_id.to_string();
+        RUSTMAILER_ACCOUNT_EMAIL_SENT_TOTAL
+            .with_label_values(&[&account_id, SUCCESS])
+            .inc();
+        RUSTMAILER_ACCOUNT_EMAIL_SENT_BYTES
+            .with_label_values(&[&account_id])
+            .inc_by(body_len as u64);
         if EventHookTask::is_watching_email_sent_success(self.account_id).await? {
             EVENT_CHANNEL
                 .queue(Event::new(
@@ -175,7 +240,7 @@ impl SmtpTask {
 
         if let Some(send_control) = &self.control {
             send_control
-                .save_to_sent_if_needed(self.account_id, body)
+                .save_to_sent_if_needed(self.account_id, &self.message_id, body)
                 .await?;
         }
         Ok(())
@@ -233,17 +298,31 @@ impl Task for SmtpTask {
         }
     }
 
-    fn run(self, _task_id: u64) -> TaskFuture {
+    fn run(self, task_id: u64) -> TaskFuture {
         Box::pin(async move {
             let account = AccountModel::get(self.account_id).await?;
+            if let Some(until) = MaintenanceWindow::active_until(account.id).await? {
+                return Err(defer_task(
+                    task_id,
+                    until,
+                    format!(
+                        "Account {} is under maintenance, email deferred until {}.",
+                        account.id, until
+                    ),
+                ));
+            }
+            let mta = match self.control.as_ref().and_then(|c| c.mta) {
+                Some(mta) => Some(Mta::get(mta).await?.ok_or_else(|| {
+                    raise_error!("MTA not found.".into(), ErrorCode::ResourceNotFound)
+                })?),
+                None => None,
+            };
+            self.acquire_send_rate(task_id, &account, mta.as_ref())?;
             let start = Instant::now();
             let body = self.load_email_body().await?;
 
             if let Some(control) = &self.control {
-                if let Some(mta) = control.mta {
-                    let mta = Mta::get(mta).await?.ok_or_else(|| {
-                        raise_error!("MTA not found.".into(), ErrorCode::ResourceNotFound)
-                    })?;
+                if let Some(mta) = mta {
                     let executor = RUST_MAIL_CONTEXT.mta(mta.id).await?;
                     let params = if mta.dsn_capable {
                         let params = control.build_dsn_params()?;
@@ -255,7 +334,7 @@ impl Task for SmtpTask {
                     let message = self
                         .build_message_with_optional_params(&body, &params)
                         .await;
-                    match send_email(executor, message).await {
+                    match send_email(self.account_id, executor, message).await {
                         Ok(()) => {
                             self.handle_email_send_success(start, body.len()).await?;
                             if matches!(account.mailer_type, MailerType::ImapSmtp) {
@@ -265,7 +344,7 @@ impl Task for SmtpTask {
                             return Ok(());
                         }
                         Err(e) => {
-                            Self::record_send_failure_metrics(start);
+                            self.record_send_failure_metrics(start);
                             return Err(e);
                         }
                     }
@@ -297,13 +376,13 @@ impl Task for SmtpTask {
                     let message = self
                         .build_message_with_optional_params(&body, &params)
                         .await;
-                    match send_email(executor, message).await {
+                    match send_email(self.account_id, executor, message).await {
                         Ok(()) => {
                             self.handle_email_send_success(start, body.len()).await?;
                             self.finalize_sent_email(&body).await
                         }
                         Err(e) => {
-                            Self::record_send_failure_metrics(start);
+                            self.record_send_failure_metrics(start);
                             Err(e)
                         }
                     }
@@ -317,18 +396,30 @@ impl Task for SmtpTask {
                     match gmail_send_email(self.account_id, account.use_proxy, raw_encoded).await {
                         Ok(()) => self.handle_email_send_success(start, body.len()).await,
                         Err(e) => {
-                            Self::record_send_failure_metrics(start);
+                            self.record_send_failure_metrics(start);
                             Err(e)
                         }
                     }
                 }
                 MailerType::GraphApi => todo!(),
+                MailerType::Jmap => {
+                    self.record_send_failure_metrics(start);
+                    Err(raise_error!(
+                        "JMAP accounts cannot submit emails themselves; send them through an MTA by setting `mta` in the send control.".into(),
+                        ErrorCode::Incompatible
+                    ))
+                }
             }
         })
     }
 }
 
-async fn send_email(executor: Arc<SmtpExecutor>, message: Message<'_>) -> RustMailerResult<()> {
+async fn send_email(
+    account_id: u64,
+    executor: Arc<SmtpExecutor>,
+    message: Message<'_>,
+) -> RustMailerResult<()> {
+    FaultInjector::inject(FaultTarget::Smtp, account_id).await?;
     executor.send_email(message).await
 }
 
@@ -0,0 +1,168 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::{BTreeSet, HashMap};
+
+use mail_send::mail_builder::MessageBuilder;
+
+use crate::{
+    modules::{
+        account::{entity::MailerType, migration::AccountModel},
+        cache::{
+            imap::migration::{EmailEnvelopeV7, EmailEnvelopeV7Key},
+            vendor::gmail::sync::envelope::{GmailEnvelope, GmailEnvelopeKey},
+        },
+        database::{find_by_secondary_key_impl, manager::DB_MANAGER},
+        error::{code::ErrorCode, RustMailerResult},
+        smtp::subject::{reply_subject, SubjectPrefixes},
+    },
+    raise_error,
+};
+
+/// A message of the sending account that a campaign email replies to.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReplyTarget {
+    /// Message-ID of the original message, without angle brackets.
+    pub message_id: String,
+    /// The `References` of the original message.
+    pub references: Vec<String>,
+    /// The subject of the original message.
+    pub subject: Option<String>,
+}
+
+impl ReplyTarget {
+    /// Threads the email under the original message with `In-Reply-To` and `References`.
+    pub fn apply(&self, builder: MessageBuilder<'static>) -> MessageBuilder<'static> {
+        let mut references = self.references.clone();
+        if !references.contains(&self.message_id) {
+            references.push(self.message_id.clone());
+        }
+        builder
+            .in_reply_to(self.message_id.clone())
+            .references(references)
+    }
+
+    /// The subject of the original message with a reply prefix, collapsing any existing ones.
+    pub fn reply_subject(&self, prefixes: Option<&SubjectPrefixes>) -> String {
+        reply_subject(self.subject.as_deref(), prefixes)
+    }
+}
+
+/// Strips the angle brackets and whitespace around a Message-ID.
+pub fn normalize_message_id(message_id: &str) -> &str {
+    message_id
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+}
+
+/// Looks up the messages with the given Message-IDs among the locally cached messages of the
+/// account, keyed by normalized Message-ID.
+///
+/// Fails if any of them is not found, so that an email can never be threaded under a message
+/// that does not belong to the sending account.
+pub async fn resolve_reply_targets(
+    account: &AccountModel,
+    message_ids: BTreeSet<String>,
+) -> RustMailerResult<HashMap<String, ReplyTarget>> {
+    if message_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    if account.minimal_sync() {
+        return Err(raise_error!(
+            "Replying to existing messages requires an account with a local message cache.".into(),
+            ErrorCode::Incompatible
+        ));
+    }
+    let wanted = message_ids.clone();
+    let targets: Vec<ReplyTarget> = match account.mailer_type {
+        MailerType::ImapSmtp => find_by_secondary_key_impl::<EmailEnvelopeV7, _>(
+            DB_MANAGER.envelope_db(),
+            EmailEnvelopeV7Key::account_id,
+            account.id,
+            move |e| e.message_id.as_ref().is_some_and(|id| wanted.contains(id)),
+        )
+        .await?
+        .into_iter()
+        .map(|e| ReplyTarget {
+            message_id: e.message_id.unwrap_or_default(),
+            references: e.references.unwrap_or_default(),
+            subject: e.subject,
+        })
+        .collect(),
+        MailerType::GmailApi => find_by_secondary_key_impl::<GmailEnvelope, _>(
+            DB_MANAGER.envelope_db(),
+            GmailEnvelopeKey::account_id,
+            account.id,
+            move |e| e.message_id.as_ref().is_some_and(|id| wanted.contains(id)),
+        )
+        .await?
+        .into_iter()
+        .map(|e| ReplyTarget {
+            message_id: e.message_id.unwrap_or_default(),
+            references: e.references.unwrap_or_default(),
+            subject: e.subject,
+        })
+        .collect(),
+        MailerType::GraphApi => {
+            return Err(raise_error!(
+                "Replying to existing messages is not supported for Graph API accounts.".into(),
+                ErrorCode::Incompatible
+            ))
+        }
+        MailerType::Jmap => {
+            return Err(raise_error!(
+                "Replying to existing messages is not supported for JMAP accounts.".into(),
+                ErrorCode::Incompatible
+            ))
+        }
+    };
+
+    let targets: HashMap<String, ReplyTarget> = targets
+        .into_iter()
+        .map(|t| (t.message_id.clone(), t))
+        .collect();
+    let missing: Vec<&String> = message_ids
+        .iter()
+        .filter(|id| !targets.contains_key(*id))
+        .collect();
+    if !missing.is_empty() {
+        return Err(raise_error!(
+            format!(
+                "Messages not found in the local cache of account {}: {:?}",
+                account.id, missing
+            ),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_reply_subjects() {
+        let target = |subject: Option<&str>| ReplyTarget {
+            subject: subject.map(Into::into),
+            ..Default::default()
+        };
+        assert_eq!(target(Some("Pricing")).reply_subject(None), "Re: Pricing");
+        assert_eq!(
+            target(Some("RE: Re: Pricing")).reply_subject(None),
+            "Re: Pricing"
+        );
+        assert_eq!(target(None).reply_subject(None), "Re: ");
+    }
+
+    #[test]
+    fn normalizes_message_ids() {
+        assert_eq!(
+            normalize_message_id(" <abc@example.com> "),
+            "abc@example.com"
+        );
+        assert_eq!(normalize_message_id("abc@example.com"), "abc@example.com");
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::HashMap;
+
+use time::{Duration, OffsetDateTime, PrimitiveDateTime, Time};
+use time_tz::{timezones, Offset, OffsetDateTimeExt, TimeZone, Tz};
+
+use crate::{
+    modules::{
+        error::{code::ErrorCode, RustMailerResult},
+        smtp::request::new::Recipient,
+    },
+    raise_error,
+};
+
+/// Country code top-level domains mapped to the timezone most of their users live in.
+/// Used when a recipient has no explicit timezone.
+const TLD_TIMEZONES: &[(&str, &str)] = &[
+    ("ae", "Asia/Dubai"),
+    ("ar", "America/Argentina/Buenos_Aires"),
+    ("at", "Europe/Vienna"),
+    ("au", "Australia/Sydney"),
+    ("be", "Europe/Brussels"),
+    ("br", "America/Sao_Paulo"),
+    ("ca", "America/Toronto"),
+    ("ch", "Europe/Zurich"),
+    ("cl", "America/Santiago"),
+    ("cn", "Asia/Shanghai"),
+    ("co", "America/Bogota"),
+    ("cz", "Europe/Prague"),
+    ("de", "Europe/Berlin"),
+    ("dk", "Europe/Copenhagen"),
+    ("eg", "Africa/Cairo"),
+    ("es", "Europe/Madrid"),
+    ("fi", "Europe/Helsinki"),
+    ("fr", "Europe/Paris"),
+    ("gr", "Europe/Athens"),
+    ("hk", "Asia/Hong_Kong"),
+    ("hu", "Europe/Budapest"),
+    ("id", "Asia/Jakarta"),
+    ("ie", "Europe/Dublin"),
+    ("il", "Asia/Jerusalem"),
+    ("in", "Asia/Kolkata"),
+    ("it", "Europe/Rome"),
+    ("jp", "Asia/Tokyo"),
+    ("kr", "Asia/Seoul"),
+    ("mx", "America/Mexico_City"),
+    ("my", "Asia/Kuala_Lumpur"),
+    ("ng", "Africa/Lagos"),
+    ("nl", "Europe/Amsterdam"),
+    ("no", "Europe/Oslo"),
+    ("nz", "Pacific/Auckland"),
+    ("ph", "Asia/Manila"),
+    ("pl", "Europe/Warsaw"),
+    ("pt", "Europe/Lisbon"),
+    ("ro", "Europe/Bucharest"),
+    ("ru", "Europe/Moscow"),
+    ("sa", "Asia/Riyadh"),
+    ("se", "Europe/Stockholm"),
+    ("sg", "Asia/Singapore"),
+    ("th", "Asia/Bangkok"),
+    ("tr", "Europe/Istanbul"),
+    ("tw", "Asia/Taipei"),
+    ("ua", "Europe/Kyiv"),
+    ("uk", "Europe/London"),
+    ("vn", "Asia/Ho_Chi_Minh"),
+    ("za", "Africa/Johannesburg"),
+];
+
+/// Mail providers whose users are concentrated in a single timezone.
+const DOMAIN_TIMEZONES: &[(&str, &str)] = &[
+    ("126.com", "Asia/Shanghai"),
+    ("163.com", "Asia/Shanghai"),
+    ("qq.com", "Asia/Shanghai"),
+    ("naver.com", "Asia/Seoul"),
+    ("yandex.com", "Europe/Moscow"),
+];
+
+/// Parses a local time of day in the `HH:MM` 24-hour format.
+pub fn parse_local_time(value: &str) -> Option<Time> {
+    let (hour, minute) = value.trim().split_once(':')?;
+    if hour.len() != 2 || minute.len() != 2 {
+        return None;
+    }
+    Time::from_hms(hour.parse().ok()?, minute.parse().ok()?, 0).ok()
+}
+
+/// Infers the timezone of an email address from its domain, if the domain is tied to a
+/// single country or to a provider whose users mostly live in one timezone.
+pub fn timezone_for_address(address: &str) -> Option<&'static str> {
+    let domain = address.rsplit_once('@')?.1.trim().to_ascii_lowercase();
+    if let Some((_, tz)) = DOMAIN_TIMEZONES.iter().find(|(d, _)| *d == domain) {
+        return Some(*tz);
+    }
+    let tld = domain.rsplit('.').next()?;
+    TLD_TIMEZONES
+        .iter()
+        .find(|(t, _)| *t == tld)
+        .map(|(_, tz)| *tz)
+}
+
+/// Returns the first instant after `now` (Unix epoch milliseconds) at which the wall
+/// clock in `timezone` shows `time`.
+pub fn next_local_time(now: i64, time: Time, timezone: &Tz) -> Option<i64> {
+    let now = OffsetDateTime::from_unix_timestamp_nanos(now as i128 * 1_000_000)
+        .ok()?
+        .to_timezone(timezone);
+    (0..=2).find_map(|days| {
+        let local = PrimitiveDateTime::new(now.date() + Duration::days(days), time);
+        // Start from the current offset, then correct it in case a DST change happens
+        // before the target time.
+        let guess = local.assume_offset(now.offset());
+        let offset = timezone.get_offset_utc(&guess).to_utc();
+        let target = local.assume_offset(offset);
+        (target > now).then(|| (target.unix_timestamp_nanos() / 1_000_000) as i64)
+    })
+}
+
+/// Splits the recipients of a campaign into per-timezone waves, so that each recipient
+/// receives the email at the same local time of day.
+///
+/// A recipient's timezone is taken from the request, then inferred from the domain of its
+/// first `to` address, and finally falls back to the default timezone. All recipients in
+/// the same timezone share one send time.
+pub struct DeliveryWaves {
+    time: Time,
+    default_timezone: &'static Tz,
+    now: i64,
+    waves: HashMap<&'static str, i64>,
+}
+
+impl DeliveryWaves {
+    pub fn new(
+        local_time: &str,
+        default_timezone: Option<&str>,
+        now: i64,
+    ) -> RustMailerResult<Self> {
+        let time = parse_local_time(local_time).ok_or_else(|| {
+            raise_error!(
+                format!("Invalid local send time '{local_time}', expected HH:MM"),
+                ErrorCode::InvalidParameter
+            )
+        })?;
+        let default_timezone = lookup(default_timezone.unwrap_or("UTC"))?;
+        Ok(Self {
+            time,
+            default_timezone,
+            now,
+            waves: HashMap::new(),
+        })
+    }
+
+    /// Returns the timezone of the recipient's wave and the time it is sent at.
+    pub fn schedule(&mut self, recipient: &Recipient) -> RustMailerResult<(String, i64)> {
+        let timezone = match recipient.timezone.as_deref() {
+            Some(name) => lookup(name)?,
+            None => recipient
+                .to
+                .first()
+                .and_then(|to| timezone_for_address(&to.address))
+                .and_then(timezones::get_by_name)
+                .unwrap_or(self.default_timezone),
+        };
+        let send_at = match self.waves.get(timezone.name()) {
+            Some(send_at) => *send_at,
+            None => {
+                let send_at = next_local_time(self.now, self.time, timezone).ok_or_else(|| {
+                    raise_error!(
+                        format!("Failed to compute the send time in {}", timezone.name()),
+                        ErrorCode::InternalError
+                    )
+                })?;
+                self.waves.insert(timezone.name(), send_at);
+                send_at
+            }
+        };
+        Ok((timezone.name().to_string(), send_at))
+    }
+}
+
+fn lookup(name: &str) -> RustMailerResult<&'static Tz> {
+    timezones::get_by_name(name).ok_or_else(|| {
+        raise_error!(
+            format!("Invalid timezone: {name}"),
+            ErrorCode::InvalidParameter
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::smtp::request::EmailAddress;
+
+    fn recipient(address: &str, timezone: Option<&str>) -> Recipient {
+        Recipient {
+            to: vec![EmailAddress {
+                name: None,
+                address: address.into(),
+            }],
+            timezone: timezone.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_local_times() {
+        assert_eq!(parse_local_time("09:30"), Time::from_hms(9, 30, 0).ok());
+        assert_eq!(parse_local_time("23:59"), Time::from_hms(23, 59, 0).ok());
+        assert_eq!(parse_local_time("24:00"), None);
+        assert_eq!(parse_local_time("9:30"), None);
+        assert_eq!(parse_local_time("0930"), None);
+    }
+
+    #[test]
+    fn infers_timezones_from_domains() {
+        assert_eq!(timezone_for_address("a@example.de"), Some("Europe/Berlin"));
+        assert_eq!(
+            timezone_for_address("a@mail.example.co.uk"),
+            Some("Europe/London")
+        );
+        assert_eq!(timezone_for_address("a@QQ.com"), Some("Asia/Shanghai"));
+        assert_eq!(timezone_for_address("a@example.com"), None);
+        assert_eq!(timezone_for_address("invalid"), None);
+    }
+
+    #[test]
+    fn schedules_recipients_in_timezone_waves() {
+        // 2025-01-15 12:00:00 UTC
+        let now = 1_736_942_400_000;
+        let mut waves = DeliveryWaves::new("09:00", None, now).unwrap();
+
+        // 09:00 in Berlin (UTC+1) is 08:00 UTC the next day.
+        let (tz, berlin) = waves.schedule(&recipient("a@example.de", None)).unwrap();
+        assert_eq!(tz, "Europe/Berlin");
+        assert_eq!(berlin, now + 20 * 3_600_000);
+        let (_, berlin_again) = waves.schedule(&recipient("b@example.de", None)).unwrap();
+        assert_eq!(berlin_again, berlin);
+
+        // 09:00 in Tokyo (UTC+9) is 00:00 UTC the next day.
+        let (tz, tokyo) = waves
+            .schedule(&recipient("c@example.com", Some("Asia/Tokyo")))
+            .unwrap();
+        assert_eq!(tz, "Asia/Tokyo");
+        assert_eq!(tokyo, now + 12 * 3_600_000);
+
+        // Unknown domains use the default timezone.
+        let (tz, utc) = waves.schedule(&recipient("d@example.com", None)).unwrap();
+        assert_eq!(tz, lookup("UTC").unwrap().name());
+        assert_eq!(utc, now + 21 * 3_600_000);
+
+        assert!(waves
+            .schedule(&recipient("e@example.com", Some("Mars/Olympus")))
+            .is_err());
+        assert!(DeliveryWaves::new("25:00", None, now).is_err());
+    }
+
+    #[test]
+    fn accounts_for_daylight_saving_changes() {
+        // 2025-03-29 12:00:00 UTC, the day before Europe switches to summer time.
+        let now = 1_743_249_600_000;
+        let berlin = timezones::get_by_name("Europe/Berlin").unwrap();
+        let time = Time::from_hms(9, 0, 0).unwrap();
+        // 09:00 CEST (UTC+2) on 2025-03-30 is 07:00 UTC.
+        assert_eq!(
+            next_local_time(now, time, berlin),
+            Some(now + 19 * 3_600_000)
+        );
+    }
+}
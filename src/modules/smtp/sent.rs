@@ -0,0 +1,113 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Duration;
+
+use poem_openapi::Enum;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::smtp::request::thread::normalize_message_id;
+
+/// SMTP servers of providers that store a copy of every message sent through them in the
+/// Sent folder of the account.
+const AUTO_SAVING_SMTP_HOSTS: &[&str] = &[
+    "smtp.gmail.com",
+    "smtp.googlemail.com",
+    "smtp.office365.com",
+    "smtp-mail.outlook.com",
+    "smtp.outlook.com",
+];
+
+/// Time to wait after sending before looking for the provider's copy in the Sent folder.
+pub const SENT_COPY_CHECK_DELAY: Duration = Duration::from_secs(2);
+
+/// How a copy of a sent email is stored in the Sent folder when `save_to_sent` is requested.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum SentCopyPolicy {
+    /// The email is always appended to the Sent folder.
+    #[default]
+    Always,
+    /// The append is skipped for providers known to save sent emails themselves (such as
+    /// Gmail and Outlook). For other providers, the Sent folder is checked first, as with
+    /// `CheckSent`.
+    Auto,
+    /// The Sent folder is searched for the email's Message-ID after sending, and the email
+    /// is only appended if no copy exists yet.
+    CheckSent,
+}
+
+/// What to do with the sent copy of an email.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SentCopyAction {
+    Append,
+    CheckThenAppend,
+    Skip,
+}
+
+impl SentCopyPolicy {
+    /// Decides how to store the sent copy of an email sent through `smtp_host`.
+    pub fn action(&self, smtp_host: Option<&str>) -> SentCopyAction {
+        match self {
+            SentCopyPolicy::Always => SentCopyAction::Append,
+            SentCopyPolicy::CheckSent => SentCopyAction::CheckThenAppend,
+            SentCopyPolicy::Auto if smtp_host.is_some_and(provider_saves_sent) => {
+                SentCopyAction::Skip
+            }
+            SentCopyPolicy::Auto => SentCopyAction::CheckThenAppend,
+        }
+    }
+}
+
+/// Returns `true` if the SMTP server is known to save sent emails to the Sent folder.
+pub fn provider_saves_sent(smtp_host: &str) -> bool {
+    let host = smtp_host.trim().trim_end_matches('.').to_ascii_lowercase();
+    AUTO_SAVING_SMTP_HOSTS.contains(&host.as_str())
+}
+
+/// IMAP `SEARCH` criteria matching an email by its Message-ID.
+pub fn message_id_search_query(message_id: &str) -> String {
+    let id = normalize_message_id(message_id).replace(['"', '\\'], "");
+    format!("HEADER Message-ID \"{id}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decides_sent_copy_actions() {
+        assert_eq!(
+            SentCopyPolicy::Always.action(Some("smtp.gmail.com")),
+            SentCopyAction::Append
+        );
+        assert_eq!(
+            SentCopyPolicy::Auto.action(Some("SMTP.Office365.com")),
+            SentCopyAction::Skip
+        );
+        assert_eq!(
+            SentCopyPolicy::Auto.action(Some("mail.example.com")),
+            SentCopyAction::CheckThenAppend
+        );
+        assert_eq!(
+            SentCopyPolicy::Auto.action(None),
+            SentCopyAction::CheckThenAppend
+        );
+        assert_eq!(
+            SentCopyPolicy::CheckSent.action(Some("smtp.gmail.com")),
+            SentCopyAction::CheckThenAppend
+        );
+    }
+
+    #[test]
+    fn builds_message_id_queries() {
+        assert_eq!(
+            message_id_search_query("<1700000000000.abc@example.com>"),
+            "HEADER Message-ID \"1700000000000.abc@example.com\""
+        );
+        assert_eq!(
+            message_id_search_query("a\"b@example.com"),
+            "HEADER Message-ID \"ab@example.com\""
+        );
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+
+/// Reply prefixes used by common mail clients across locales, lowercased and without colon.
+const REPLY_PREFIXES: &[&str] = &["re", "aw", "antw", "sv", "vs", "res", "r", "odp"];
+/// Forward prefixes used by common mail clients across locales, lowercased and without colon.
+const FORWARD_PREFIXES: &[&str] = &[
+    "fwd", "fw", "wg", "doorst", "vb", "vs", "vl", "tr", "rv", "i", "enc", "pd",
+];
+/// Maximum length of a custom prefix.
+pub const MAX_PREFIX_LEN: usize = 16;
+
+/// Locale presets for the subject prefixes of replies and forwards.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum SubjectLocale {
+    /// `Re:` / `Fwd:`
+    #[default]
+    English,
+    /// `AW:` / `WG:`
+    German,
+    /// `Antw:` / `Doorst:`
+    Dutch,
+    /// `SV:` / `VB:`
+    Swedish,
+    /// `SV:` / `VS:`
+    Danish,
+    /// `SV:` / `VS:`
+    Norwegian,
+    /// `VS:` / `VL:`
+    Finnish,
+    /// `RE:` / `TR:`
+    French,
+    /// `RE:` / `RV:`
+    Spanish,
+    /// `R:` / `I:`
+    Italian,
+    /// `RES:` / `ENC:`
+    Portuguese,
+    /// `Odp:` / `PD:`
+    Polish,
+}
+
+impl SubjectLocale {
+    /// The reply and forward prefixes of the locale.
+    pub fn prefixes(&self) -> (&'static str, &'static str) {
+        match self {
+            SubjectLocale::English => ("Re:", "Fwd:"),
+            SubjectLocale::German => ("AW:", "WG:"),
+            SubjectLocale::Dutch => ("Antw:", "Doorst:"),
+            SubjectLocale::Swedish => ("SV:", "VB:"),
+            SubjectLocale::Danish | SubjectLocale::Norwegian => ("SV:", "VS:"),
+            SubjectLocale::Finnish => ("VS:", "VL:"),
+            SubjectLocale::French => ("RE:", "TR:"),
+            SubjectLocale::Spanish => ("RE:", "RV:"),
+            SubjectLocale::Italian => ("R:", "I:"),
+            SubjectLocale::Portuguese => ("RES:", "ENC:"),
+            SubjectLocale::Polish => ("Odp:", "PD:"),
+        }
+    }
+}
+
+/// Subject prefixes used for replies and forwards.
+///
+/// Custom prefixes take precedence over the locale preset. If nothing is set, the English
+/// `Re:` and `Fwd:` prefixes are used.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SubjectPrefixes {
+    /// Locale preset providing both prefixes.
+    pub locale: Option<SubjectLocale>,
+    /// Custom reply prefix, such as `Antwort:`.
+    pub reply: Option<String>,
+    /// Custom forward prefix, such as `Weitergeleitet:`.
+    pub forward: Option<String>,
+}
+
+impl SubjectPrefixes {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, prefix) in [("reply", &self.reply), ("forward", &self.forward)] {
+            if let Some(prefix) = prefix {
+                let prefix = prefix.trim();
+                if prefix.is_empty()
+                    || prefix.chars().count() > MAX_PREFIX_LEN
+                    || prefix.chars().any(char::is_control)
+                {
+                    return Err(format!(
+                        "Invalid {name} subject prefix: must be 1 to {MAX_PREFIX_LEN} characters without control characters"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn reply_prefix(&self) -> &str {
+        self.reply
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or_else(|| self.locale.unwrap_or_default().prefixes().0)
+    }
+
+    fn forward_prefix(&self) -> &str {
+        self.forward
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or_else(|| self.locale.unwrap_or_default().prefixes().1)
+    }
+}
+
+/// Builds the subject of a reply, collapsing existing reply prefixes so that replying to
+/// `AW: Re: Offer` gives `Re: Offer` rather than `Re: AW: Re: Offer`.
+pub fn reply_subject(subject: Option<&str>, prefixes: Option<&SubjectPrefixes>) -> String {
+    let prefix = prefixes.map_or("Re:", SubjectPrefixes::reply_prefix);
+    prefixed_subject(subject.unwrap_or_default(), prefix, REPLY_PREFIXES)
+}
+
+/// Builds the subject of a forward, collapsing existing forward prefixes.
+pub fn forward_subject(subject: Option<&str>, prefixes: Option<&SubjectPrefixes>) -> String {
+    let prefix = prefixes.map_or("Fwd:", SubjectPrefixes::forward_prefix);
+    prefixed_subject(subject.unwrap_or_default(), prefix, FORWARD_PREFIXES)
+}
+
+/// Strips all leading reply and forward prefixes, in any known locale.
+pub fn strip_prefixes(subject: &str) -> &str {
+    let mut subject = subject.trim();
+    while let Some(rest) = strip_prefix(subject, |token| {
+        REPLY_PREFIXES.contains(&token) || FORWARD_PREFIXES.contains(&token)
+    }) {
+        subject = rest;
+    }
+    subject
+}
+
+fn prefixed_subject(subject: &str, prefix: &str, known: &[&str]) -> String {
+    let own = normalize_token(prefix);
+    let mut rest = subject.trim();
+    while let Some(stripped) = strip_prefix(rest, |token| known.contains(&token) || token == own) {
+        rest = stripped;
+    }
+    format!("{prefix} {rest}")
+}
+
+/// Removes one leading `prefix:` whose token is accepted by `is_known`, also accepting
+/// counters such as `Re[2]:` or `AW(3):`.
+fn strip_prefix(subject: &str, is_known: impl Fn(&str) -> bool) -> Option<&str> {
+    let (head, rest) = subject.split_once(':')?;
+    if head.chars().count() > MAX_PREFIX_LEN {
+        return None;
+    }
+    let token = normalize_token(head);
+    (!token.is_empty() && is_known(&token)).then(|| rest.trim_start())
+}
+
+fn normalize_token(prefix: &str) -> String {
+    let token = prefix.trim().trim_end_matches(':').trim_end();
+    let token = match token.strip_suffix([']', ')']) {
+        Some(counted) => counted
+            .trim_end_matches(|c: char| c.is_ascii_digit())
+            .trim_end_matches(['[', '(']),
+        None => token,
+    };
+    token.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_reply_prefixes() {
+        assert_eq!(reply_subject(Some("Offer"), None), "Re: Offer");
+        assert_eq!(reply_subject(Some("Re: Re: RE: Offer"), None), "Re: Offer");
+        assert_eq!(reply_subject(Some("AW: Re[2]: Offer"), None), "Re: Offer");
+        assert_eq!(reply_subject(Some("Fwd: Offer"), None), "Re: Fwd: Offer");
+        assert_eq!(reply_subject(None, None), "Re: ");
+        // Colons further in the subject are not prefixes.
+        assert_eq!(
+            reply_subject(Some("Agenda: Q3 planning"), None),
+            "Re: Agenda: Q3 planning"
+        );
+    }
+
+    #[test]
+    fn uses_locale_and_custom_prefixes() {
+        let swedish = SubjectPrefixes {
+            locale: Some(SubjectLocale::Swedish),
+            ..Default::default()
+        };
+        assert_eq!(
+            reply_subject(Some("Re: Offer"), Some(&swedish)),
+            "SV: Offer"
+        );
+        assert_eq!(
+            forward_subject(Some("VB: Offer"), Some(&swedish)),
+            "VB: Offer"
+        );
+
+        let custom = SubjectPrefixes {
+            locale: Some(SubjectLocale::Swedish),
+            reply: Some("Antwort:".into()),
+            forward: None,
+        };
+        assert_eq!(
+            reply_subject(Some("Antwort: AW: Offer"), Some(&custom)),
+            "Antwort: Offer"
+        );
+        assert_eq!(forward_subject(Some("Offer"), Some(&custom)), "VB: Offer");
+        assert_eq!(
+            forward_subject(Some("FW: Re: Offer"), None),
+            "Fwd: Re: Offer"
+        );
+    }
+
+    #[test]
+    fn validates_custom_prefixes() {
+        let prefixes = |reply: &str| SubjectPrefixes {
+            reply: Some(reply.into()),
+            ..Default::default()
+        };
+        assert!(prefixes("Antwort:").validate().is_ok());
+        assert!(prefixes(" ").validate().is_err());
+        assert!(prefixes("Re:\r\nBcc: x").validate().is_err());
+        assert!(prefixes(&"x".repeat(MAX_PREFIX_LEN + 1))
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn strips_all_prefixes() {
+        assert_eq!(strip_prefixes("  Re: WG:AW: Hello "), "Hello");
+        assert_eq!(strip_prefixes("Hello: World"), "Hello: World");
+    }
+}
@@ -0,0 +1,386 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::cmp::Reverse;
+
+use ahash::AHashSet;
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    id,
+    modules::{
+        account::migration::AccountModel,
+        bounce::{
+            classify::{classify, BounceCategory},
+            parser::BounceReport,
+        },
+        common::paginated::paginate_vec,
+        database::{
+            async_find_impl, batch::WriteBatch, delete_impl, filter_by_secondary_key_impl,
+            key::CompositeKey, manager::DB_MANAGER, secondary_find_impl, upsert_impl,
+        },
+        error::{code::ErrorCode, RustMailerResult},
+        overview::memory::check_metadata_capacity,
+        rest::response::DataPage,
+        smtp::request::SendControl,
+    },
+    raise_error, utc_now, validate_email,
+};
+
+/// Why an address was added to the suppression list.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum SuppressionSource {
+    /// A delivery status notification reported a hard bounce (`EmailBounce` event).
+    Bounce,
+    /// The recipient reported an email as spam or abuse (`EmailFeedBackReport` event).
+    Complaint,
+    /// Added through the API.
+    #[default]
+    Manual,
+}
+
+/// A recipient address that emails of an account are no longer sent to.
+///
+/// Hard bounces and complaints detected while syncing the account are added automatically.
+/// Suppressed recipients are dropped from outgoing emails, and an email whose recipients
+/// are all suppressed is rejected with a `RecipientSuppressed` error, unless
+/// `send_control.ignore_suppression_list` is set.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 24, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct SuppressedAddress {
+    /// Unique identifier of the entry.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// The account whose emails are no longer sent to the address.
+    #[secondary_key]
+    pub account_id: u64,
+    /// The suppressed address, in lowercase.
+    pub address: String,
+    /// Why the address was suppressed.
+    pub source: SuppressionSource,
+    /// Details of the suppression, such as the diagnostic code of the bounce or the
+    /// feedback type of the complaint.
+    pub reason: Option<String>,
+    /// The email task that sent the email that bounced or was complained about, if known.
+    pub task_id: Option<u64>,
+    /// Time (Unix epoch milliseconds) the address was suppressed.
+    pub created_at: i64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SuppressionRequest {
+    /// The address to suppress.
+    pub address: String,
+    /// Why the address is suppressed.
+    pub reason: Option<String>,
+}
+
+impl SuppressedAddress {
+    fn pk(&self) -> String {
+        Self::key(self.account_id, &self.address)
+    }
+
+    fn key(account_id: u64, address: &str) -> String {
+        CompositeKey::new()
+            .segment(account_id)
+            .segment(normalize(address))
+            .build()
+    }
+
+    fn new(
+        account_id: u64,
+        address: &str,
+        source: SuppressionSource,
+        reason: Option<String>,
+        task_id: Option<u64>,
+    ) -> Self {
+        Self {
+            id: id!(64),
+            account_id,
+            address: normalize(address),
+            source,
+            reason: reason.filter(|r| !r.trim().is_empty()),
+            task_id,
+            created_at: utc_now!(),
+        }
+    }
+
+    /// Adds an address to the suppression list of an account through the API. An existing
+    /// entry of the address is replaced.
+    pub async fn create(account_id: u64, request: SuppressionRequest) -> RustMailerResult<Self> {
+        AccountModel::get(account_id).await?;
+        if validate_email!(&request.address).is_err() {
+            return Err(raise_error!(
+                format!("Invalid email address '{}'", request.address),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let entry = Self::new(
+            account_id,
+            &request.address,
+            SuppressionSource::Manual,
+            request.reason,
+            None,
+        );
+        entry.clone().save().await?;
+        Ok(entry)
+    }
+
+    async fn save(self) -> RustMailerResult<()> {
+        check_metadata_capacity()?;
+        upsert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    pub async fn get(id: u64) -> RustMailerResult<SuppressedAddress> {
+        secondary_find_impl(DB_MANAGER.meta_db(), SuppressedAddressKey::id, id)
+            .await?
+            .ok_or_else(|| {
+                raise_error!(
+                    format!("Suppressed address id='{id}' not found."),
+                    ErrorCode::ResourceNotFound
+                )
+            })
+    }
+
+    pub async fn find(account_id: u64, address: &str) -> RustMailerResult<Option<Self>> {
+        async_find_impl(DB_MANAGER.meta_db(), Self::key(account_id, address)).await
+    }
+
+    /// Lists the suppressed addresses of an account, most recently suppressed first.
+    pub async fn paginate_list(
+        account_id: u64,
+        page: Option<u64>,
+        page_size: Option<u64>,
+    ) -> RustMailerResult<DataPage<SuppressedAddress>> {
+        let mut entries: Vec<SuppressedAddress> = filter_by_secondary_key_impl(
+            DB_MANAGER.meta_db(),
+            SuppressedAddressKey::account_id,
+            account_id,
+        )
+        .await?;
+        entries.sort_by_key(|e| Reverse(e.created_at));
+        paginate_vec(&entries, page, page_size).map(DataPage::from)
+    }
+
+    pub async fn remove(id: u64) -> RustMailerResult<()> {
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<SuppressedAddress>(SuppressedAddressKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!(
+                            "The suppressed address with id={id} that you want to delete was not found."
+                        ),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await
+    }
+
+    /// Adds the removal of the suppression list of an account to `batch`.
+    pub fn stage_remove_account_suppressions(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let entries: Vec<SuppressedAddress> = rw
+                .scan()
+                .secondary::<SuppressedAddress>(SuppressedAddressKey::account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(entries)
+        })
+    }
+
+    /// Suppresses the recipient of a hard bounce or a complaint reported to an account.
+    /// Failures are only logged, so that the report is still processed.
+    pub async fn record_report(account_id: u64, report: &BounceReport, task_id: Option<u64>) {
+        let Some(entry) = Self::from_report(account_id, report, task_id) else {
+            return;
+        };
+        let address = entry.address.clone();
+        match entry.save().await {
+            Ok(()) => info!(
+                "Account {}: Added '{}' to the suppression list.",
+                account_id, address
+            ),
+            Err(e) => warn!(
+                "Account {}: Failed to add '{}' to the suppression list: {:#?}",
+                account_id, address, e
+            ),
+        }
+    }
+
+    /// The entry suppressing the recipient of a report, or `None` if the report is neither
+    /// a hard bounce nor a complaint, or does not name its recipient.
+    fn from_report(account_id: u64, report: &BounceReport, task_id: Option<u64>) -> Option<Self> {
+        if let Some(status) = &report.delivery_status {
+            if classify(status) == Some(BounceCategory::HardBounce) {
+                let recipient = status.recipient.as_deref()?;
+                let reason = status
+                    .diagnostic_code
+                    .clone()
+                    .or_else(|| status.status.clone());
+                return Some(Self::new(
+                    account_id,
+                    recipient,
+                    SuppressionSource::Bounce,
+                    reason,
+                    task_id,
+                ));
+            }
+        }
+        let feedback = report.feedback_report.as_ref()?;
+        if feedback
+            .feedback_type
+            .as_deref()
+            .is_some_and(|t| t.eq_ignore_ascii_case("not-spam"))
+        {
+            return None;
+        }
+        // Fall back to the original recipient when the report does not name it itself.
+        let recipient = feedback.original_rcpt_to.clone().or_else(|| {
+            report
+                .original_headers
+                .as_ref()
+                .and_then(|headers| headers.to.as_ref())
+                .filter(|to| to.len() == 1)
+                .and_then(|to| to.first().cloned())
+        })?;
+        Some(Self::new(
+            account_id,
+            &recipient,
+            SuppressionSource::Complaint,
+            feedback.feedback_type.clone(),
+            task_id,
+        ))
+    }
+}
+
+fn normalize(address: &str) -> String {
+    address
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_lowercase()
+}
+
+/// Drops the suppressed recipients of an outgoing email from `to` and from the envelope of
+/// `control`, and returns them.
+///
+/// Returns a `RecipientSuppressed` error if no recipient is left, and leaves the recipients
+/// untouched if `send_control.ignore_suppression_list` is set.
+pub async fn apply_suppression_list(
+    account_id: u64,
+    to: &mut Vec<String>,
+    control: &mut Option<SendControl>,
+) -> RustMailerResult<Vec<String>> {
+    if control
+        .as_ref()
+        .is_some_and(|c| c.ignore_suppression_list == Some(true))
+    {
+        return Ok(Vec::new());
+    }
+    // A custom envelope replaces the recipients of the message in `RCPT TO`.
+    let envelope = control.as_mut().and_then(|c| c.envelope.as_mut());
+    let mut suppressed = AHashSet::new();
+    for recipient in envelope.as_ref().map_or(&*to, |e| &e.recipients) {
+        if SuppressedAddress::find(account_id, recipient)
+            .await?
+            .is_some()
+        {
+            suppressed.insert(normalize(recipient));
+        }
+    }
+    if suppressed.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let is_allowed = |recipient: &String| !suppressed.contains(&normalize(recipient));
+    to.retain(is_allowed);
+    let remaining = match envelope {
+        Some(envelope) => {
+            envelope.recipients.retain(is_allowed);
+            envelope.recipients.len()
+        }
+        None => to.len(),
+    };
+    let mut suppressed: Vec<String> = suppressed.into_iter().collect();
+    suppressed.sort();
+    if remaining == 0 {
+        return Err(raise_error!(
+            format!(
+                "All recipients of the email are on the suppression list of account {}: {}",
+                account_id,
+                suppressed.join(", ")
+            ),
+            ErrorCode::RecipientSuppressed
+        ));
+    }
+    info!(
+        "Account {}: Dropped suppressed recipients {} from an outgoing email.",
+        account_id,
+        suppressed.join(", ")
+    );
+    Ok(suppressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::bounce::parser::{DeliveryStatus, FeedbackReport};
+
+    #[test]
+    fn suppresses_hard_bounces_and_complaints() {
+        let bounce = BounceReport {
+            original_headers: None,
+            delivery_status: Some(DeliveryStatus {
+                recipient: Some("Gone@Example.com".into()),
+                action: Some("failed".into()),
+                status: Some("5.1.1".into()),
+                diagnostic_code: Some("smtp; 550 5.1.1 User unknown".into()),
+                ..Default::default()
+            }),
+            feedback_report: None,
+        };
+        let entry = SuppressedAddress::from_report(1, &bounce, Some(9)).unwrap();
+        assert_eq!(entry.address, "gone@example.com");
+        assert_eq!(entry.source, SuppressionSource::Bounce);
+        assert_eq!(entry.task_id, Some(9));
+        assert_eq!(entry.pk(), "1_gone@example.com");
+
+        let soft = BounceReport {
+            delivery_status: Some(DeliveryStatus {
+                recipient: Some("busy@example.com".into()),
+                action: Some("delayed".into()),
+                status: Some("4.4.1".into()),
+                ..Default::default()
+            }),
+            ..bounce
+        };
+        assert!(SuppressedAddress::from_report(1, &soft, None).is_none());
+
+        let complaint = BounceReport {
+            original_headers: None,
+            delivery_status: None,
+            feedback_report: Some(FeedbackReport {
+                feedback_type: Some("abuse".into()),
+                original_rcpt_to: Some("<angry@example.com>".into()),
+                ..Default::default()
+            }),
+        };
+        let entry = SuppressedAddress::from_report(1, &complaint, None).unwrap();
+        assert_eq!(entry.address, "angry@example.com");
+        assert_eq!(entry.source, SuppressionSource::Complaint);
+        assert_eq!(entry.reason.as_deref(), Some("abuse"));
+    }
+}
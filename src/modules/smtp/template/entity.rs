@@ -3,16 +3,21 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::account::migration::AccountModel;
+use crate::modules::common::auth::ClientContext;
+use crate::modules::database::batch::WriteBatch;
 use crate::modules::database::manager::DB_MANAGER;
 use crate::modules::database::{
-    batch_delete_impl, delete_impl, paginate_query_primary_scan_all_impl,
-    paginate_secondary_scan_impl, secondary_find_impl, update_impl,
+    batch_delete_impl, delete_impl, list_all_impl, paginate_query_primary_scan_all_impl,
+    paginate_secondary_scan_impl, secondary_find_impl, versioned_update_impl, Versioned,
 };
 
 use crate::modules::error::code::ErrorCode;
+use crate::modules::overview::memory::check_metadata_capacity;
 use crate::modules::rest::response::DataPage;
+use crate::modules::smtp::template::partial::TemplatePartial;
 use crate::modules::smtp::template::payload::{TemplateCreateRequest, TemplateUpdateRequest};
 use crate::modules::token::AccountInfo;
+use crate::modules::workspace::Workspace;
 use crate::{id, raise_error};
 use crate::{modules::database::insert_impl, modules::error::RustMailerResult, utc_now};
 use handlebars::Handlebars;
@@ -27,6 +32,48 @@ const NOT_ASSIGNED: u64 = 0;
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
 #[native_model(id = 6, version = 1)]
 #[native_db(primary_key(pk -> String), secondary_key(account_id_key -> u64))]
+pub struct EmailTemplateV1 {
+    /// Unique identifier for the template, used as a secondary key.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Optional description of the template for additional context.
+    pub description: Option<String>,
+    /// Associated account information, if any. `None` indicates the template is public.
+    pub account: Option<AccountInfo>,
+    /// Subject line of the email template.
+    pub subject: String,
+    /// Optional preview text for the email, used in email clients.
+    pub preview: Option<String>,
+    /// Format of the HTML email content, either Markdown or HTML. Defaults to HTML if not specified.
+    pub format: Option<MessageFormat>,
+    /// Plain text content of the email, if provided.
+    pub text: Option<String>,
+    /// HTML content of the email, if provided.
+    pub html: Option<String>,
+    /// Timestamp of when the template was created (in Unix epoch milliseconds).
+    pub created_at: i64,
+    /// Timestamp of when the template was last updated (in Unix epoch milliseconds).
+    pub updated_at: i64,
+    /// Timestamp of when the template was last accessed (in Unix epoch milliseconds).
+    pub last_access_at: i64,
+}
+
+impl EmailTemplateV1 {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    fn account_id_key(&self) -> u64 {
+        self.account
+            .clone()
+            .map(|info| info.id)
+            .unwrap_or(NOT_ASSIGNED)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 6, version = 2, from = EmailTemplateV1)]
+#[native_db(primary_key(pk -> String), secondary_key(account_id_key -> u64))]
 pub struct EmailTemplate {
     /// Unique identifier for the template, used as a secondary key.
     #[secondary_key(unique)]
@@ -51,6 +98,11 @@ pub struct EmailTemplate {
     pub updated_at: i64,
     /// Timestamp of when the template was last accessed (in Unix epoch milliseconds).
     pub last_access_at: i64,
+    /// The workspace of a public template. Templates of an account belong to the workspace
+    /// of the account.
+    ///
+    /// If not set, a public template is shared by the whole instance.
+    pub workspace_id: Option<u64>,
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
@@ -60,6 +112,19 @@ pub enum MessageFormat {
     /// Content is formatted in HTML (default).
     #[default]
     Html,
+    /// Content is written in MJML, compiled to responsive HTML after the template
+    /// variables are substituted.
+    Mjml,
+}
+
+impl Versioned for EmailTemplate {
+    fn version(&self) -> i64 {
+        self.updated_at
+    }
+
+    fn set_version(&mut self, version: i64) {
+        self.updated_at = version;
+    }
 }
 
 impl EmailTemplate {
@@ -67,6 +132,24 @@ impl EmailTemplate {
         format!("{}_{}", self.created_at, self.id)
     }
 
+    /// The Handlebars parts of the template, by field name.
+    pub fn parts(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("subject", Some(&self.subject)),
+            ("text", self.text.as_ref()),
+            ("html", self.html.as_ref()),
+            ("preview", self.preview.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(name, content)| Some((name, content?.as_str())))
+        .collect()
+    }
+
+    /// The partials available to the template when rendering it.
+    pub async fn partials(&self) -> RustMailerResult<Vec<TemplatePartial>> {
+        TemplatePartial::available(self.account.as_ref().map(|a| a.id)).await
+    }
+
     fn account_id_key(&self) -> u64 {
         self.account
             .clone()
@@ -75,6 +158,13 @@ impl EmailTemplate {
     }
 
     pub async fn new(value: TemplateCreateRequest) -> RustMailerResult<Self> {
+        if value.account_id.is_some() && value.workspace_id.is_some() {
+            return Err(raise_error!(
+                "A workspace can only be set on public templates: templates of an account belong to the workspace of the account.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        Workspace::check_exists(value.workspace_id).await?;
         let account_info = if let Some(account_id) = value.account_id {
             AccountModel::get(account_id).await.map(|account| {
                 Some(AccountInfo {
@@ -98,9 +188,49 @@ impl EmailTemplate {
             created_at: utc_now!(),
             updated_at: utc_now!(),
             last_access_at: Default::default(),
+            workspace_id: value.workspace_id,
         })
     }
 
+    /// Checks that the client may read or manage the template: through its account, or
+    /// through the workspace of a public template.
+    pub fn check_access(&self, context: &ClientContext) -> RustMailerResult<()> {
+        match &self.account {
+            Some(account) => context.require_account_access(account.id),
+            None => context.require_workspace_access(self.workspace_id),
+        }
+    }
+
+    /// Checks that `account` may send with the template: a template of an account is only
+    /// used by that account, and a public template of a workspace only by the accounts of
+    /// that workspace.
+    pub fn check_usable_by(&self, account: &AccountModel) -> RustMailerResult<()> {
+        if let Some(owner) = &self.account {
+            if owner.id != account.id {
+                return Err(raise_error!(
+                    format!(
+                        "Template id='{}' belongs to another account and cannot be used by account {}.",
+                        self.id, account.id
+                    ),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+        if self.account.is_none()
+            && self.workspace_id.is_some()
+            && self.workspace_id != account.workspace_id
+        {
+            return Err(raise_error!(
+                format!(
+                    "Template id='{}' belongs to another workspace and cannot be used by account {}.",
+                    self.id, account.id
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        Ok(())
+    }
+
     pub async fn paginate_list_account(
         account_id: u64,
         page: Option<u64>,
@@ -120,10 +250,14 @@ impl EmailTemplate {
     }
 
     pub async fn save(self) -> RustMailerResult<()> {
+        check_metadata_capacity()?;
         self.validate_templates()?;
         if let Some(account) = &self.account {
             Self::check_account_id(account.id).await?;
         }
+        let parts: Vec<&str> = self.parts().into_iter().map(|(_, c)| c).collect();
+        TemplatePartial::check_references(self.account.as_ref().map(|a| a.id), &parts, None)
+            .await?;
         //check name
         if Self::find(self.id).await?.is_some() {
             return Err(raise_error!(
@@ -134,6 +268,21 @@ impl EmailTemplate {
         insert_impl(DB_MANAGER.meta_db(), self).await
     }
 
+    /// Adds the removal of all templates of an account to `batch`.
+    pub fn stage_remove_account_templates(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let templates: Vec<EmailTemplate> = rw
+                .scan()
+                .secondary::<EmailTemplate>(EmailTemplateKey::account_id_key)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(templates)
+        })
+    }
+
     pub async fn remove_account_templates(account_id: u64) -> RustMailerResult<()> {
         batch_delete_impl(DB_MANAGER.meta_db(), move |rw| {
             let templates: Vec<EmailTemplate> = rw
@@ -171,6 +320,10 @@ impl EmailTemplate {
         }).await
     }
 
+    pub async fn list_all() -> RustMailerResult<Vec<EmailTemplate>> {
+        list_all_impl(DB_MANAGER.meta_db()).await
+    }
+
     pub async fn paginate_list(
         page: Option<u64>,
         page_size: Option<u64>,
@@ -181,7 +334,15 @@ impl EmailTemplate {
             .map(DataPage::from)
     }
 
-    pub async fn update(id: u64, request: TemplateUpdateRequest) -> RustMailerResult<()> {
+    /// Updates a template.
+    ///
+    /// If `expected_version` is set, the update is rejected with a `VersionConflict` error
+    /// when the template's `updated_at` no longer matches it.
+    pub async fn update(
+        id: u64,
+        request: TemplateUpdateRequest,
+        expected_version: Option<i64>,
+    ) -> RustMailerResult<()> {
         if let Some(text) = &request.text {
             Self::validate_template("text", text)?;
         }
@@ -198,7 +359,21 @@ impl EmailTemplate {
             Self::validate_template("preview", preview)?;
         }
 
-        update_impl(
+        let parts: Vec<&str> = [
+            &request.subject,
+            &request.preview,
+            &request.text,
+            &request.html,
+        ]
+        .into_iter()
+        .filter_map(|c| c.as_deref())
+        .collect();
+        if !parts.is_empty() {
+            let account_id = Self::get(id).await?.account.map(|a| a.id);
+            TemplatePartial::check_references(account_id, &parts, None).await?;
+        }
+
+        versioned_update_impl(
             DB_MANAGER.meta_db(),
             move |rw| {
                 rw.get()
@@ -209,6 +384,7 @@ impl EmailTemplate {
                     })
             },
             |current| Ok(Self::apply_update(current, request)),
+            expected_version,
         )
         .await?;
         Ok(())
@@ -228,7 +404,7 @@ impl EmailTemplate {
             Self::validate_template("html", html)?;
             if self.format.is_none() {
                 return Err(raise_error!(
-                    "Content format must be specified when 'html' is set. Expected 'Markdown' or 'Html'."
+                    "Content format must be specified when 'html' is set. Expected 'Markdown', 'Html' or 'Mjml'."
                         .into(), ErrorCode::InvalidParameter
                 ));
             }
@@ -291,3 +467,40 @@ impl EmailTemplate {
         new
     }
 }
+
+impl From<EmailTemplateV1> for EmailTemplate {
+    fn from(value: EmailTemplateV1) -> Self {
+        Self {
+            id: value.id,
+            description: value.description,
+            account: value.account,
+            subject: value.subject,
+            preview: value.preview,
+            format: value.format,
+            text: value.text,
+            html: value.html,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            last_access_at: value.last_access_at,
+            workspace_id: None,
+        }
+    }
+}
+
+impl From<EmailTemplate> for EmailTemplateV1 {
+    fn from(value: EmailTemplate) -> Self {
+        Self {
+            id: value.id,
+            description: value.description,
+            account: value.account,
+            subject: value.subject,
+            preview: value.preview,
+            format: value.format,
+            text: value.text,
+            html: value.html,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            last_access_at: value.last_access_at,
+        }
+    }
+}
@@ -3,6 +3,7 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 pub mod entity;
+pub mod partial;
 pub mod payload;
 pub mod preview;
 pub mod render;
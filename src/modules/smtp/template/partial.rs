@@ -0,0 +1,396 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::LazyLock;
+
+use handlebars::Handlebars;
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::account::migration::AccountModel;
+use crate::modules::common::auth::ClientContext;
+use crate::modules::database::batch::WriteBatch;
+use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::{
+    delete_impl, filter_by_secondary_key_impl, insert_impl, paginate_query_primary_scan_all_impl,
+    paginate_secondary_scan_impl, secondary_find_impl, update_impl,
+};
+use crate::modules::error::code::ErrorCode;
+use crate::modules::error::RustMailerResult;
+use crate::modules::overview::memory::check_metadata_capacity;
+use crate::modules::rest::response::DataPage;
+use crate::modules::smtp::template::payload::{
+    TemplatePartialCreateRequest, TemplatePartialUpdateRequest,
+};
+use crate::modules::token::AccountInfo;
+use crate::{id, raise_error, utc_now};
+
+const NOT_ASSIGNED: u64 = 0;
+
+/// Matches partial includes, `{{> name}}` and `{{~> name}}`. Partial blocks (`{{#> name}}`)
+/// render their own content when the partial is missing, so they are not matched.
+static PARTIAL_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{~?>\s*([A-Za-z_][A-Za-z0-9_-]*)").unwrap());
+
+/// A reusable Handlebars snippet, such as a shared header or footer, included by templates
+/// with `{{> name}}`.
+///
+/// Public partials are available to every template. Partials of an account are only
+/// available to the templates of that account, and take precedence over a public partial
+/// with the same name.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 25, version = 1)]
+#[native_db(primary_key(pk -> String), secondary_key(account_id_key -> u64))]
+pub struct TemplatePartial {
+    /// Unique identifier of the partial.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Name templates include the partial by, unique among the partials of an account and
+    /// among public partials.
+    pub name: String,
+    /// Optional description of the partial.
+    pub description: Option<String>,
+    /// Associated account information, if any. `None` indicates the partial is public.
+    pub account: Option<AccountInfo>,
+    /// Handlebars content of the partial.
+    pub content: String,
+    /// Timestamp of when the partial was created (in Unix epoch milliseconds).
+    pub created_at: i64,
+    /// Timestamp of when the partial was last updated (in Unix epoch milliseconds).
+    pub updated_at: i64,
+}
+
+impl TemplatePartial {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    fn account_id_key(&self) -> u64 {
+        self.account_id().unwrap_or(NOT_ASSIGNED)
+    }
+
+    pub fn account_id(&self) -> Option<u64> {
+        self.account.as_ref().map(|info| info.id)
+    }
+
+    pub async fn new(request: TemplatePartialCreateRequest) -> RustMailerResult<Self> {
+        let account = match request.account_id {
+            Some(account_id) => Some(AccountInfo {
+                id: account_id,
+                email: AccountModel::get(account_id).await?.email,
+            }),
+            None => None,
+        };
+        validate_name(&request.name)?;
+        validate_content(&request.content)?;
+        let now = utc_now!();
+        Ok(Self {
+            id: id!(64),
+            name: request.name,
+            description: request.description,
+            account,
+            content: request.content,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Public partials require root permission, account partials access to the account.
+    pub fn check_access(context: &ClientContext, account_id: Option<u64>) -> RustMailerResult<()> {
+        match account_id {
+            Some(account_id) => context.require_account_access(account_id),
+            None => context.require_root(),
+        }
+    }
+
+    pub async fn save(self) -> RustMailerResult<()> {
+        check_metadata_capacity()?;
+        let account_id = self.account_id();
+        let scope: Vec<TemplatePartial> = filter_by_secondary_key_impl(
+            DB_MANAGER.meta_db(),
+            TemplatePartialKey::account_id_key,
+            self.account_id_key(),
+        )
+        .await?;
+        if scope.iter().any(|p| p.name == self.name) {
+            return Err(raise_error!(
+                format!("A partial named '{}' already exists.", self.name),
+                ErrorCode::AlreadyExists
+            ));
+        }
+        Self::check_references(account_id, &[&self.content], Some(&self)).await?;
+        insert_impl(DB_MANAGER.meta_db(), self).await
+    }
+
+    pub async fn find(id: u64) -> RustMailerResult<Option<TemplatePartial>> {
+        secondary_find_impl(DB_MANAGER.meta_db(), TemplatePartialKey::id, id).await
+    }
+
+    pub async fn get(id: u64) -> RustMailerResult<TemplatePartial> {
+        Self::find(id).await?.ok_or_else(|| {
+            raise_error!(
+                format!("Template partial id='{id}' not found."),
+                ErrorCode::ResourceNotFound
+            )
+        })
+    }
+
+    /// Updates a partial. Its name cannot change, as templates include it by name.
+    pub async fn update(id: u64, request: TemplatePartialUpdateRequest) -> RustMailerResult<()> {
+        let current = Self::get(id).await?;
+        if let Some(content) = &request.content {
+            validate_content(content)?;
+            let updated = Self {
+                content: content.clone(),
+                ..current.clone()
+            };
+            Self::check_references(current.account_id(), &[content], Some(&updated)).await?;
+        }
+        update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .secondary::<TemplatePartial>(TemplatePartialKey::id, id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!("The template partial with id={id} that you want to modify was not found."),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            move |current| {
+                let mut updated = current.clone();
+                if request.description.is_some() {
+                    updated.description = request.description;
+                }
+                if let Some(content) = request.content {
+                    updated.content = content;
+                }
+                updated.updated_at = utc_now!();
+                Ok(updated)
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a partial. Templates still including it fail to render until it is
+    /// recreated.
+    pub async fn remove(id: u64) -> RustMailerResult<()> {
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<TemplatePartial>(TemplatePartialKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!(
+                            "The template partial with id={id} that you want to delete was not found."
+                        ),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await
+    }
+
+    pub async fn paginate_list(
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<TemplatePartial>> {
+        paginate_query_primary_scan_all_impl(DB_MANAGER.meta_db(), page, page_size, desc)
+            .await
+            .map(DataPage::from)
+    }
+
+    pub async fn paginate_list_account(
+        account_id: u64,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<TemplatePartial>> {
+        paginate_secondary_scan_impl(
+            DB_MANAGER.meta_db(),
+            page,
+            page_size,
+            desc,
+            TemplatePartialKey::account_id_key,
+            account_id,
+        )
+        .await
+        .map(DataPage::from)
+    }
+
+    /// The partials available to the templates of `account_id`, or to public templates if
+    /// `None`: public partials, shadowed by the account's partials of the same name.
+    pub async fn available(account_id: Option<u64>) -> RustMailerResult<Vec<TemplatePartial>> {
+        let mut partials: HashMap<String, TemplatePartial> = HashMap::new();
+        for key in [Some(NOT_ASSIGNED), account_id].into_iter().flatten() {
+            let scope: Vec<TemplatePartial> = filter_by_secondary_key_impl(
+                DB_MANAGER.meta_db(),
+                TemplatePartialKey::account_id_key,
+                key,
+            )
+            .await?;
+            partials.extend(scope.into_iter().map(|p| (p.name.clone(), p)));
+        }
+        Ok(partials.into_values().collect())
+    }
+
+    /// Checks that the partials included by `contents` exist for `account_id`, and that
+    /// including them does not loop. `replacing` is a partial being saved, checked in place
+    /// of its stored version.
+    pub async fn check_references(
+        account_id: Option<u64>,
+        contents: &[&str],
+        replacing: Option<&TemplatePartial>,
+    ) -> RustMailerResult<()> {
+        if contents.iter().all(|c| partial_references(c).is_empty()) {
+            return Ok(());
+        }
+        let mut available: HashMap<String, String> = Self::available(account_id)
+            .await?
+            .into_iter()
+            .map(|p| (p.name, p.content))
+            .collect();
+        if let Some(partial) = replacing {
+            available.insert(partial.name.clone(), partial.content.clone());
+        }
+        validate_references(contents, &available)
+            .map_err(|e| raise_error!(e, ErrorCode::InvalidParameter))
+    }
+
+    /// Adds the removal of all partials of an account to `batch`.
+    pub fn stage_remove_account_partials(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let partials: Vec<TemplatePartial> = rw
+                .scan()
+                .secondary::<TemplatePartial>(TemplatePartialKey::account_id_key)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(partials)
+        })
+    }
+}
+
+fn validate_name(name: &str) -> RustMailerResult<()> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(raise_error!(
+            format!("Invalid partial name '{name}': use letters, digits, '_' and '-', starting with a letter or '_'."),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    Ok(())
+}
+
+fn validate_content(content: &str) -> RustMailerResult<()> {
+    if content.is_empty() {
+        return Err(raise_error!(
+            "field: content is empty.".into(),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    Handlebars::new()
+        .register_template_string("partial", content)
+        .map_err(|e| {
+            raise_error!(
+                format!("field: content , template error: {:#?}", e),
+                ErrorCode::InvalidParameter
+            )
+        })
+}
+
+/// Names of the partials included by a Handlebars template.
+pub fn partial_references(content: &str) -> BTreeSet<String> {
+    PARTIAL_REFERENCE
+        .captures_iter(content)
+        .map(|c| c[1].to_string())
+        .collect()
+}
+
+/// Checks that every partial included by `contents`, directly or through other partials,
+/// is in `available` (name to content), and that no partial ends up including itself.
+fn validate_references(
+    contents: &[&str],
+    available: &HashMap<String, String>,
+) -> Result<(), String> {
+    fn visit(
+        name: &str,
+        available: &HashMap<String, String>,
+        path: &mut Vec<String>,
+        done: &mut HashSet<String>,
+    ) -> Result<(), String> {
+        if done.contains(name) {
+            return Ok(());
+        }
+        if path.iter().any(|n| n == name) {
+            return Err(format!(
+                "Partial '{name}' includes itself: {} > {name}",
+                path.join(" > ")
+            ));
+        }
+        let content = available
+            .get(name)
+            .ok_or_else(|| format!("Partial '{name}' does not exist."))?;
+        path.push(name.to_string());
+        for reference in partial_references(content) {
+            visit(&reference, available, path, done)?;
+        }
+        path.pop();
+        done.insert(name.to_string());
+        Ok(())
+    }
+
+    let mut done = HashSet::new();
+    for content in contents {
+        for reference in partial_references(content) {
+            visit(&reference, available, &mut Vec::new(), &mut done)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_partial_references() {
+        assert_eq!(
+            partial_references("{{> header}} {{name}} {{~> footer-v2 }} {{#> layout}}x{{/layout}}"),
+            BTreeSet::from(["header".to_string(), "footer-v2".to_string()])
+        );
+
+        let mut available = HashMap::from([
+            ("header".to_string(), "<h1>{{> logo}}</h1>".to_string()),
+            ("logo".to_string(), "<img src=\"{{logo_url}}\">".to_string()),
+        ]);
+        assert!(validate_references(&["{{> header}}"], &available).is_ok());
+        assert_eq!(
+            validate_references(&["{{> footer}}"], &available).unwrap_err(),
+            "Partial 'footer' does not exist."
+        );
+
+        available.insert("logo".into(), "{{> header}}".into());
+        assert!(validate_references(&["{{> header}}"], &available)
+            .unwrap_err()
+            .contains("includes itself"));
+    }
+}
@@ -15,6 +15,9 @@ pub struct TemplateCreateRequest {
     /// The associated account information that created or uses this template (optional). `None` indicates a public template.
     pub account_id: Option<u64>,
 
+    /// The workspace of a public template (optional). Templates of an account belong to the workspace of the account. Defaults to the workspace of the access token.
+    pub workspace_id: Option<u64>,
+
     /// The subject line of the email. Maximum length is 20,480 characters.
     #[oai(validator(max_length = "20480"))]
     pub subject: String,
@@ -77,3 +80,75 @@ pub struct TemplateSentTestRequest {
     /// Example: {"name": "John Doe", "order_id": 12345}
     pub template_params: Option<serde_json::Value>,
 }
+
+/// Request structure for sending a template to a list of seed mailboxes
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct TemplateSeedTestRequest {
+    /// Account ID associated with the template and sending request
+    pub account_id: u64,
+    /// Seed mailboxes to send to, at most 50 (optional). Defaults to the addresses
+    /// configured with `rustmailer_template_seed_addresses`.
+    pub recipients: Option<Vec<String>>,
+    /// Optional parameters to be used for template variable substitution
+    pub template_params: Option<serde_json::Value>,
+}
+
+/// Outcome of a seed test, with one result per seed mailbox.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct TemplateSeedTestReport {
+    /// The rendered subject sent to every seed.
+    pub subject: String,
+    /// Number of seeds the SMTP server accepted the email for.
+    pub sent: usize,
+    /// Number of seeds the email could not be sent to.
+    pub failed: usize,
+    pub results: Vec<SeedSendResult>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct SeedSendResult {
+    /// The seed mailbox address.
+    pub recipient: String,
+    /// The mailbox provider, inferred from the domain: `Gmail`, `Outlook`, `Yahoo`,
+    /// `iCloud`, `AOL`, `Proton`, `GMX`, `Yandex`, `Zoho` or `Other`.
+    pub provider: String,
+    /// Whether the SMTP server accepted the email.
+    pub success: bool,
+    /// The SMTP error, if the email was not accepted.
+    pub error: Option<String>,
+    /// Message-ID of the email, to find it in the seed mailbox.
+    pub message_id: String,
+    /// Time taken to send the email, in milliseconds.
+    pub duration_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct TemplatePartialCreateRequest {
+    /// The account the partial belongs to (optional). `None` creates a public partial,
+    /// available to every template and requiring root permission.
+    pub account_id: Option<u64>,
+
+    /// Name templates include the partial by, e.g. `footer` for `{{> footer}}`. Letters,
+    /// digits, `_` and `-`, starting with a letter or `_`. Maximum length is 64 characters.
+    #[oai(validator(max_length = "64"))]
+    pub name: String,
+
+    /// A brief description of the partial (optional). Maximum length is 1024 characters.
+    #[oai(validator(max_length = "1024"))]
+    pub description: Option<String>,
+
+    /// Handlebars content of the partial. Maximum length is 8,388,608 characters (approximately 8MB).
+    #[oai(validator(max_length = "8388608"))]
+    pub content: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct TemplatePartialUpdateRequest {
+    /// A brief description of the partial (optional). Maximum length is 1024 characters.
+    #[oai(validator(max_length = "1024"))]
+    pub description: Option<String>,
+
+    /// Handlebars content of the partial (optional). Maximum length is 8,388,608 characters (approximately 8MB).
+    #[oai(validator(max_length = "8388608"))]
+    pub content: Option<String>,
+}
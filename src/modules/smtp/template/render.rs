@@ -4,26 +4,43 @@
 
 use crate::modules::error::code::ErrorCode;
 use crate::modules::smtp::template::entity::{EmailTemplate, MessageFormat};
+use crate::modules::smtp::template::partial::{partial_references, TemplatePartial};
 use crate::modules::smtp::template::preview::EmailPreview;
 use crate::{modules::error::RustMailerResult, raise_error};
 use handlebars::Handlebars;
+use mrml::prelude::render::RenderOptions;
 use pulldown_cmark::{html, Parser};
 use serde_json::Value;
 pub struct Templates;
 
 impl Templates {
+    /// Renders the subject, text and HTML of a template with `data`, making `partials`
+    /// available to `{{> name}}` includes.
+    ///
+    /// Without data, the template is returned as is, unless it includes partials: it is
+    /// then rendered with empty data so that the partials are expanded.
     pub fn render(
         template: &EmailTemplate,
         data: &Option<Value>,
+        partials: &[TemplatePartial],
     ) -> RustMailerResult<(String, Option<String>, Option<String>)> {
+        let empty = Value::Object(Default::default());
+        let data = match data {
+            None if Self::includes_partials(template) => Some(&empty),
+            data => data.as_ref(),
+        };
         match data {
-            None => Ok((
-                template.subject.clone(),
-                template.text.clone(),
-                template.html.clone(),
-            )),
+            None => {
+                let mut html = template.html.clone();
+                // MJML cannot be sent as is, so it is compiled even without template data.
+                if let (Some(MessageFormat::Mjml), Some(content)) = (&template.format, &mut html) {
+                    *content = Self::compile_mjml(content)?;
+                }
+                Ok((template.subject.clone(), template.text.clone(), html))
+            }
             Some(data) => {
                 let mut handlebars = Handlebars::new();
+                Self::register_partials(&mut handlebars, partials)?;
 
                 let register_template = |hb: &mut Handlebars, name: &str, content: &str| {
                     hb.register_template_string(name, content).map_err(|e| {
@@ -68,10 +85,16 @@ impl Templates {
 
                 if let Some(format) = &template.format {
                     if let Some(html_content) = &mut html {
-                        if matches!(format, MessageFormat::Markdown) {
-                            let mut html_output = String::new();
-                            html::push_html(&mut html_output, Parser::new(html_content));
-                            *html_content = html_output;
+                        match format {
+                            MessageFormat::Markdown => {
+                                let mut html_output = String::new();
+                                html::push_html(&mut html_output, Parser::new(html_content));
+                                *html_content = html_output;
+                            }
+                            MessageFormat::Mjml => {
+                                *html_content = Self::compile_mjml(html_content)?
+                            }
+                            MessageFormat::Html => {}
                         }
 
                         if template.preview.is_some() {
@@ -88,4 +111,69 @@ impl Templates {
             }
         }
     }
+
+    fn includes_partials(template: &EmailTemplate) -> bool {
+        template
+            .parts()
+            .into_iter()
+            .any(|(_, content)| !partial_references(content).is_empty())
+    }
+
+    fn register_partials(
+        handlebars: &mut Handlebars,
+        partials: &[TemplatePartial],
+    ) -> RustMailerResult<()> {
+        for partial in partials {
+            handlebars
+                .register_partial(&partial.name, &partial.content)
+                .map_err(|e| {
+                    raise_error!(
+                        format!("Handlebars register partial '{}' error: {e}", partial.name),
+                        ErrorCode::InternalError
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Compiles MJML to responsive HTML. Errors are returned as `InvalidParameter` errors
+    /// pointing at the offending element, e.g. in the response of a template test send.
+    pub fn compile_mjml(source: &str) -> RustMailerResult<String> {
+        let compile_error = |e: String| {
+            raise_error!(
+                format!("MJML compile error: {e}"),
+                ErrorCode::InvalidParameter
+            )
+        };
+        let parsed = mrml::parse(source).map_err(|e| compile_error(e.to_string()))?;
+        parsed
+            .element
+            .render(&RenderOptions::default())
+            .map_err(|e| compile_error(e.to_string()))
+    }
+
+    /// Renders every part of the template in strict mode, returning one error per part that
+    /// references a variable missing from `data` or fails to parse.
+    pub fn check_variables(
+        template: &EmailTemplate,
+        data: &Option<Value>,
+        partials: &[TemplatePartial],
+    ) -> Vec<String> {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+        if let Err(e) = Self::register_partials(&mut handlebars, partials) {
+            return vec![e.to_string()];
+        }
+        let empty = Value::Object(Default::default());
+        let data = data.as_ref().unwrap_or(&empty);
+
+        template
+            .parts()
+            .into_iter()
+            .filter_map(|(name, content)| {
+                let error = handlebars.render_template(content, data).err()?;
+                Some(format!("Template '{name}': {error}"))
+            })
+            .collect()
+    }
 }
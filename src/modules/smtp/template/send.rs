@@ -2,11 +2,12 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use std::borrow::Cow;
+use std::{borrow::Cow, time::Instant};
 
+use futures::future::join_all;
 use mail_send::{
     mail_builder::{headers::address::Address, MessageBuilder},
-    smtp::message::IntoMessage,
+    smtp::message::{IntoMessage, Message},
 };
 
 use crate::{
@@ -14,16 +15,25 @@ use crate::{
         account::migration::AccountModel,
         context::executors::RUST_MAIL_CONTEXT,
         error::{code::ErrorCode, RustMailerResult},
+        settings::cli::SETTINGS,
         smtp::{
             template::{
-                entity::EmailTemplate, payload::TemplateSentTestRequest, render::Templates,
+                entity::EmailTemplate,
+                payload::{
+                    SeedSendResult, TemplateSeedTestReport, TemplateSeedTestRequest,
+                    TemplateSentTestRequest,
+                },
+                render::Templates,
             },
-            util::generate_message_id,
+            util::generate_account_message_id,
         },
     },
-    raise_error,
+    raise_error, validate_email,
 };
 
+/// Maximum number of seed mailboxes a seed test sends to.
+const MAX_SEED_RECIPIENTS: usize = 50;
+
 pub async fn send_template_test_email(
     template_id: u64,
     reqwest: TemplateSentTestRequest,
@@ -36,21 +46,112 @@ pub async fn send_template_test_email(
 
     let template = EmailTemplate::get(template_id).await?;
     let account = AccountModel::get(account_id).await?;
+    template.check_usable_by(&account)?;
+
+    let partials = template.partials().await?;
+    let rendered = Templates::render(&template, &template_params, &partials)?;
 
-    let (subject, text, html) = Templates::render(&template, &template_params)?;
+    let (_, message) = build_test_message(&account, &rendered, recipient)?;
+    let executor = RUST_MAIL_CONTEXT.smtp(account_id).await?;
+    executor.send_email(message).await
+}
+
+/// Renders the template once and sends it to each seed mailbox as a separate email. A
+/// failed send is reported in the result of its seed and does not stop the others.
+pub async fn send_template_seed_test(
+    template_id: u64,
+    request: TemplateSeedTestRequest,
+) -> RustMailerResult<TemplateSeedTestReport> {
+    let TemplateSeedTestRequest {
+        account_id,
+        recipients,
+        template_params,
+    } = request;
+
+    let recipients = match recipients {
+        Some(recipients) => recipients,
+        None => {
+            let mut seeds: Vec<String> = SETTINGS
+                .rustmailer_template_seed_addresses
+                .iter()
+                .cloned()
+                .collect();
+            seeds.sort_by(|a, b| seed_provider(a).cmp(seed_provider(b)).then(a.cmp(b)));
+            seeds
+        }
+    };
+    if recipients.is_empty() {
+        return Err(raise_error!(
+            "No seed recipients given and none configured with 'rustmailer_template_seed_addresses'".into(),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    if recipients.len() > MAX_SEED_RECIPIENTS {
+        return Err(raise_error!(
+            format!("At most {MAX_SEED_RECIPIENTS} seed recipients are allowed"),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    for recipient in &recipients {
+        validate_email!(recipient)?;
+    }
 
-    let from = Address::new_address(None::<&str>, Cow::Owned(account.email));
+    let template = EmailTemplate::get(template_id).await?;
+    let account = AccountModel::get(account_id).await?;
+    template.check_usable_by(&account)?;
+
+    let partials = template.partials().await?;
+    let rendered = Templates::render(&template, &template_params, &partials)?;
+    let executor = RUST_MAIL_CONTEXT.smtp(account_id).await?;
+
+    let sends = recipients.into_iter().map(|recipient| {
+        let executor = executor.clone();
+        let built = build_test_message(&account, &rendered, recipient.clone());
+        async move {
+            let started = Instant::now();
+            let (message_id, outcome) = match built {
+                Ok((message_id, message)) => (message_id, executor.send_email(message).await),
+                Err(e) => (String::new(), Err(e)),
+            };
+            SeedSendResult {
+                provider: seed_provider(&recipient).into(),
+                recipient,
+                success: outcome.is_ok(),
+                error: outcome.err().map(|e| e.to_string()),
+                message_id,
+                duration_ms: started.elapsed().as_millis() as u64,
+            }
+        }
+    });
+    let results = join_all(sends).await;
+    let sent = results.iter().filter(|r| r.success).count();
+    Ok(TemplateSeedTestReport {
+        subject: rendered.0,
+        failed: results.len() - sent,
+        sent,
+        results,
+    })
+}
+
+/// Builds the test email of a rendered template, returning its Message-ID with it.
+fn build_test_message(
+    account: &AccountModel,
+    (subject, text, html): &(String, Option<String>, Option<String>),
+    recipient: String,
+) -> RustMailerResult<(String, Message<'static>)> {
+    let message_id = generate_account_message_id(account, &account.email);
+    let from = Address::new_address(None::<&str>, Cow::Owned(account.email.clone()));
     let to = Address::new_address(None::<&str>, Cow::Owned(recipient));
     let mut builder = MessageBuilder::new()
         .from(from)
         .to(to)
-        .subject(subject)
-        .message_id(generate_message_id());
+        .subject(subject.clone())
+        .message_id(message_id.clone());
     if let Some(text) = text {
-        builder = builder.text_body(text);
+        builder = builder.text_body(text.clone());
     }
     if let Some(html) = html {
-        builder = builder.html_body(html);
+        builder = builder.html_body(html.clone());
     }
     let message = builder.into_message().map_err(|e| {
         raise_error!(
@@ -58,6 +159,29 @@ pub async fn send_template_test_email(
             ErrorCode::InternalError
         )
     })?;
-    let executor = RUST_MAIL_CONTEXT.smtp(account_id).await?;
-    executor.send_email(message).await
+    Ok((message_id, message))
+}
+
+/// Infers the mailbox provider of a seed address from its domain.
+pub fn seed_provider(address: &str) -> &'static str {
+    let domain = address
+        .rsplit_once('@')
+        .map(|(_, domain)| domain.to_ascii_lowercase())
+        .unwrap_or_default();
+    // The first label, for providers with many country domains such as `yahoo.co.uk`.
+    let name = domain.split('.').next().unwrap_or_default();
+    match (domain.as_str(), name) {
+        ("gmail.com" | "googlemail.com", _) => "Gmail",
+        ("outlook.com" | "live.com" | "msn.com", _) | (_, "hotmail" | "outlook" | "live") => {
+            "Outlook"
+        }
+        ("ymail.com" | "rocketmail.com", _) | (_, "yahoo") => "Yahoo",
+        ("icloud.com" | "me.com" | "mac.com", _) => "iCloud",
+        ("aol.com", _) | (_, "aol") => "AOL",
+        ("proton.me" | "protonmail.com" | "pm.me", _) => "Proton",
+        (_, "gmx") => "GMX",
+        (_, "yandex") => "Yandex",
+        (_, "zoho" | "zohomail") => "Zoho",
+        _ => "Other",
+    }
 }
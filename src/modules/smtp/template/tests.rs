@@ -5,6 +5,13 @@
 use handlebars::Handlebars;
 use serde_json::json;
 
+use crate::modules::account::migration::AccountModel;
+use crate::modules::smtp::template::entity::{EmailTemplate, MessageFormat};
+use crate::modules::smtp::template::partial::TemplatePartial;
+use crate::modules::smtp::template::render::Templates;
+use crate::modules::smtp::template::send::seed_provider;
+use crate::modules::token::AccountInfo;
+
 #[test]
 fn test1() {
     let mut handlebars = Handlebars::new();
@@ -29,3 +36,102 @@ fn test1() {
     let rendered = handlebars.render("user_list", &data).unwrap();
     println!("{}", rendered);
 }
+
+#[test]
+fn renders_mjml_templates() {
+    let template = EmailTemplate {
+        subject: "Welcome {{name}}".into(),
+        format: Some(MessageFormat::Mjml),
+        html: Some(
+            "<mjml><mj-body><mj-section><mj-column><mj-text>Hello {{name}}</mj-text></mj-column></mj-section></mj-body></mjml>"
+                .into(),
+        ),
+        ..Default::default()
+    };
+    let (subject, _, html) =
+        Templates::render(&template, &Some(json!({"name": "Ada"})), &[]).unwrap();
+    assert_eq!(subject, "Welcome Ada");
+    let html = html.unwrap();
+    assert!(html.contains("<html"));
+    assert!(html.contains("Hello Ada"));
+
+    let error = Templates::compile_mjml("<mjml><mj-body><mj-section>").unwrap_err();
+    assert!(error.to_string().contains("MJML compile error"));
+}
+
+#[test]
+fn renders_partials_and_conditionals() {
+    let partials = vec![TemplatePartial {
+        name: "footer".into(),
+        content: "<p>{{#if company}}{{company}}{{else}}RustMailer{{/if}}</p>".into(),
+        ..Default::default()
+    }];
+    let template = EmailTemplate {
+        subject: "Orders".into(),
+        format: Some(MessageFormat::Html),
+        html: Some("<ul>{{#each orders}}<li>{{this}}</li>{{/each}}</ul>{{> footer}}".into()),
+        ..Default::default()
+    };
+    let data = Some(json!({"orders": ["A1", "B2"], "company": "Acme"}));
+    let (_, _, html) = Templates::render(&template, &data, &partials).unwrap();
+    assert_eq!(html.unwrap(), "<ul><li>A1</li><li>B2</li></ul><p>Acme</p>");
+
+    // Partials are expanded even without template data.
+    let (_, _, html) = Templates::render(&template, &None, &partials).unwrap();
+    assert_eq!(html.unwrap(), "<ul></ul><p>RustMailer</p>");
+}
+
+#[test]
+fn infers_seed_providers() {
+    for (address, provider) in [
+        ("seed@gmail.com", "Gmail"),
+        ("seed@GoogleMail.com", "Gmail"),
+        ("seed@outlook.com", "Outlook"),
+        ("seed@hotmail.co.uk", "Outlook"),
+        ("seed@yahoo.co.jp", "Yahoo"),
+        ("seed@ymail.com", "Yahoo"),
+        ("seed@icloud.com", "iCloud"),
+        ("seed@aol.com", "AOL"),
+        ("seed@gmx.de", "GMX"),
+        ("seed@example.com", "Other"),
+        ("not-an-address", "Other"),
+    ] {
+        assert_eq!(seed_provider(address), provider, "{address}");
+    }
+}
+
+#[test]
+fn rejects_templates_of_other_accounts() {
+    let owner = AccountModel {
+        id: 1,
+        email: "owner@example.com".into(),
+        ..Default::default()
+    };
+    let other = AccountModel {
+        id: 2,
+        email: "other@example.com".into(),
+        ..Default::default()
+    };
+    let template = EmailTemplate {
+        id: 10,
+        account: Some(AccountInfo {
+            id: owner.id,
+            email: owner.email.clone(),
+        }),
+        ..Default::default()
+    };
+    assert!(template.check_usable_by(&owner).is_ok());
+    assert!(template.check_usable_by(&other).is_err());
+
+    let public = EmailTemplate {
+        id: 11,
+        workspace_id: Some(5),
+        ..Default::default()
+    };
+    assert!(public.check_usable_by(&owner).is_err());
+    let member = AccountModel {
+        workspace_id: Some(5),
+        ..other
+    };
+    assert!(public.check_usable_by(&member).is_ok());
+}
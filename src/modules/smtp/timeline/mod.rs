@@ -0,0 +1,410 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    id,
+    modules::{
+        bounce::{classify::classify, parser::BounceReport},
+        common::auth::ClientContext,
+        database::{
+            batch::WriteBatch, batch_delete_impl, batch_insert_impl, filter_by_secondary_key_impl,
+            key::timestamp_key_range, manager::DB_MANAGER,
+        },
+        error::{code::ErrorCode, RustMailerResult},
+        settings::cli::SETTINGS,
+        smtp::{
+            request::{task::SmtpTask, thread::normalize_message_id},
+            track::{TrackType, TrackingPayload},
+        },
+    },
+    raise_error, utc_now,
+};
+
+pub mod task;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// A step in the delivery of a sent email to one recipient.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Enum)]
+pub enum DeliveryEventKind {
+    /// The email was queued for sending.
+    #[default]
+    Queued,
+    /// The email was accepted by the SMTP server or the mail API.
+    Sent,
+    /// A sending attempt failed and will be retried.
+    Retrying,
+    /// Sending failed and will not be retried.
+    Failed,
+    /// A delivery status notification reported the email as delivered or relayed.
+    Delivered,
+    /// A delivery status notification reported the delivery as delayed.
+    Delayed,
+    /// A delivery status notification reported the delivery as failed.
+    Bounced,
+    /// The tracking pixel of the email was loaded.
+    Opened,
+    /// A tracked link of the email was clicked.
+    Clicked,
+    /// The recipient reported the email as spam or abuse.
+    Complained,
+}
+
+/// An event in the delivery of a sent email, recorded for its delivery timeline.
+///
+/// Events are kept for `rustmailer_delivery_timeline_retention_days` days.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 29, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct DeliveryEvent {
+    /// Unique identifier of the event.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// The Message-ID of the email, without angle brackets.
+    #[secondary_key]
+    pub message_id: String,
+    /// The account that sent the email.
+    #[secondary_key]
+    pub account_id: u64,
+    /// The recipient the event is about, in lowercase. `None` if the report does not name it.
+    pub recipient: Option<String>,
+    pub kind: DeliveryEventKind,
+    /// The email task that sent the email, if known.
+    pub task_id: Option<u64>,
+    /// The campaign the email was sent with, if any.
+    pub campaign_id: Option<String>,
+    /// Details of the event, such as the error of a failed attempt, the status and
+    /// diagnostic of a delivery status notification, the clicked URL or the feedback type
+    /// of a complaint.
+    pub detail: Option<String>,
+    /// Time (Unix epoch milliseconds) the event was recorded.
+    pub created_at: i64,
+}
+
+/// The delivery timeline of an email to one recipient.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct RecipientTimeline {
+    /// The recipient, or `None` for events whose recipient is unknown.
+    pub recipient: Option<String>,
+    /// The kind of the latest event.
+    pub status: DeliveryEventKind,
+    /// The events, oldest first.
+    pub events: Vec<DeliveryEvent>,
+}
+
+/// The delivery timeline of a sent email, per recipient.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct MessageTimeline {
+    /// The Message-ID of the email, without angle brackets.
+    pub message_id: String,
+    /// The timelines of the recipients, ordered by address.
+    pub recipients: Vec<RecipientTimeline>,
+}
+
+impl DeliveryEvent {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    fn new(
+        account_id: u64,
+        message_id: &str,
+        recipient: Option<&str>,
+        kind: DeliveryEventKind,
+        detail: Option<String>,
+    ) -> Self {
+        Self {
+            id: id!(64),
+            message_id: normalize_message_id(message_id).to_string(),
+            account_id,
+            recipient: recipient.map(|r| r.trim().to_lowercase()),
+            kind,
+            task_id: None,
+            campaign_id: None,
+            detail: detail.filter(|d| !d.trim().is_empty()),
+            created_at: utc_now!(),
+        }
+    }
+
+    /// Records an event for each recipient of an email task.
+    pub async fn record_task(
+        task: &SmtpTask,
+        task_id: u64,
+        kind: DeliveryEventKind,
+        detail: Option<String>,
+    ) {
+        let campaign_id = task.control.as_ref().and_then(|c| c.campaign_id.clone());
+        let events = task
+            .to
+            .iter()
+            .map(|recipient| Self {
+                task_id: Some(task_id),
+                campaign_id: campaign_id.clone(),
+                ..Self::new(
+                    task.account_id,
+                    &task.message_id,
+                    Some(recipient),
+                    kind,
+                    detail.clone(),
+                )
+            })
+            .collect();
+        Self::save_all(task.account_id, events).await;
+    }
+
+    /// Records the outcome of a delivery status notification or the complaint of a
+    /// feedback report received by an account.
+    pub async fn record_report(account_id: u64, report: &BounceReport, task_id: Option<u64>) {
+        if let Some(event) = Self::from_report(account_id, report, task_id) {
+            Self::save_all(account_id, vec![event]).await;
+        }
+    }
+
+    fn from_report(account_id: u64, report: &BounceReport, task_id: Option<u64>) -> Option<Self> {
+        let headers = report.original_headers.as_ref();
+        if let Some(status) = &report.delivery_status {
+            let message_id = headers
+                .and_then(|h| h.message_id.as_deref())
+                .or(status.original_message_id.as_deref())?;
+            let kind = match status.action.as_deref()?.to_ascii_lowercase().as_str() {
+                "failed" => DeliveryEventKind::Bounced,
+                "delayed" => DeliveryEventKind::Delayed,
+                "delivered" | "relayed" | "expanded" => DeliveryEventKind::Delivered,
+                _ => return None,
+            };
+            let detail = [
+                status.status.clone(),
+                status.diagnostic_code.clone(),
+                classify(status).map(|c| format!("{c:?}")),
+            ]
+            .into_iter()
+            .flatten()
+            .join(" ");
+            return Some(Self {
+                task_id,
+                ..Self::new(
+                    account_id,
+                    message_id,
+                    status.recipient.as_deref(),
+                    kind,
+                    Some(detail),
+                )
+            });
+        }
+        let feedback = report.feedback_report.as_ref()?;
+        let message_id = headers.and_then(|h| h.message_id.as_deref())?;
+        let recipient = feedback.original_rcpt_to.clone().or_else(|| {
+            headers
+                .and_then(|h| h.to.as_ref())
+                .filter(|to| to.len() == 1)
+                .map(|to| to[0].clone())
+        });
+        Some(Self::new(
+            account_id,
+            message_id,
+            recipient.as_deref(),
+            DeliveryEventKind::Complained,
+            feedback.feedback_type.clone(),
+        ))
+    }
+
+    /// Records the open or the click reported by a tracking request.
+    pub async fn record_tracking(payload: &TrackingPayload) {
+        let kind = match payload.track_type {
+            TrackType::Open => DeliveryEventKind::Opened,
+            TrackType::Click => DeliveryEventKind::Clicked,
+        };
+        let event = Self {
+            campaign_id: Some(payload.campaign_id.clone()).filter(|c| !c.is_empty()),
+            ..Self::new(
+                payload.account_id,
+                &payload.message_id,
+                Some(&payload.recipient)
+                    .filter(|r| !r.is_empty())
+                    .map(|r| r.as_str()),
+                kind,
+                payload.url.clone(),
+            )
+        };
+        Self::save_all(payload.account_id, vec![event]).await;
+    }
+
+    /// Saves recorded events. Failures are only logged, so that sending and syncing are
+    /// not affected.
+    async fn save_all(account_id: u64, events: Vec<DeliveryEvent>) {
+        if events.is_empty() {
+            return;
+        }
+        if let Err(e) = batch_insert_impl(DB_MANAGER.meta_db(), events).await {
+            warn!(
+                "Account {}: Failed to record delivery events: {:#?}",
+                account_id, e
+            );
+        }
+    }
+
+    /// The delivery timeline of an email, with the events of the accounts accessible to
+    /// `context`.
+    pub async fn timeline(
+        context: &ClientContext,
+        message_id: &str,
+    ) -> RustMailerResult<MessageTimeline> {
+        let message_id = normalize_message_id(message_id).to_string();
+        let events: Vec<DeliveryEvent> = filter_by_secondary_key_impl::<DeliveryEvent>(
+            DB_MANAGER.meta_db(),
+            DeliveryEventKey::message_id,
+            message_id.clone(),
+        )
+        .await?
+        .into_iter()
+        // The lookup matches by prefix.
+        .filter(|e| e.message_id == message_id)
+        .filter(|e| context.require_account_access(e.account_id).is_ok())
+        .collect();
+        if events.is_empty() {
+            return Err(raise_error!(
+                format!("No delivery events found for message '{message_id}'."),
+                ErrorCode::ResourceNotFound
+            ));
+        }
+        Ok(MessageTimeline::from_events(message_id, events))
+    }
+
+    /// Removes the events older than `rustmailer_delivery_timeline_retention_days`,
+    /// returning the number of events removed.
+    pub async fn clean() -> RustMailerResult<usize> {
+        let cutoff =
+            utc_now!() - SETTINGS.rustmailer_delivery_timeline_retention_days as i64 * DAY_MS;
+        batch_delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            let expired: Vec<DeliveryEvent> = rw
+                .scan()
+                .primary()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .range(timestamp_key_range(None, Some(cutoff)))
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(expired)
+        })
+        .await
+    }
+
+    pub fn stage_remove_account_events(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let events: Vec<DeliveryEvent> = rw
+                .scan()
+                .secondary::<DeliveryEvent>(DeliveryEventKey::account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(events)
+        })
+    }
+}
+
+impl MessageTimeline {
+    /// Groups the events of an email by recipient, oldest first.
+    pub fn from_events(message_id: String, events: Vec<DeliveryEvent>) -> Self {
+        let recipients = events
+            .into_iter()
+            .sorted_by_key(|e| (e.recipient.clone(), e.created_at, e.id))
+            .chunk_by(|e| e.recipient.clone())
+            .into_iter()
+            .map(|(recipient, events)| {
+                let events: Vec<DeliveryEvent> = events.collect();
+                RecipientTimeline {
+                    recipient,
+                    status: events.last().map(|e| e.kind).unwrap_or_default(),
+                    events,
+                }
+            })
+            .collect();
+        Self {
+            message_id,
+            recipients,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::bounce::parser::{DeliveryStatus, RawEmailHeaders};
+
+    fn event(recipient: Option<&str>, kind: DeliveryEventKind, created_at: i64) -> DeliveryEvent {
+        DeliveryEvent {
+            created_at,
+            ..DeliveryEvent::new(1, "<1.abc@example.com>", recipient, kind, None)
+        }
+    }
+
+    #[test]
+    fn groups_events_by_recipient() {
+        let timeline = MessageTimeline::from_events(
+            "1.abc@example.com".into(),
+            vec![
+                event(Some("b@example.com"), DeliveryEventKind::Queued, 100),
+                event(Some("A@example.com"), DeliveryEventKind::Opened, 300),
+                event(Some("a@example.com"), DeliveryEventKind::Sent, 200),
+                event(Some("a@example.com"), DeliveryEventKind::Queued, 100),
+                event(None, DeliveryEventKind::Delayed, 400),
+            ],
+        );
+        let summary: Vec<(Option<&str>, DeliveryEventKind, usize)> = timeline
+            .recipients
+            .iter()
+            .map(|r| (r.recipient.as_deref(), r.status, r.events.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (None, DeliveryEventKind::Delayed, 1),
+                (Some("a@example.com"), DeliveryEventKind::Opened, 3),
+                (Some("b@example.com"), DeliveryEventKind::Queued, 1),
+            ]
+        );
+        assert_eq!(
+            timeline.recipients[1].events[0].message_id,
+            "1.abc@example.com"
+        );
+    }
+
+    #[test]
+    fn records_delivery_status_notifications() {
+        let report = BounceReport {
+            original_headers: Some(RawEmailHeaders {
+                message_id: Some("<1.abc@example.com>".into()),
+                subject: None,
+                from: None,
+                to: None,
+                date: None,
+            }),
+            delivery_status: Some(DeliveryStatus {
+                recipient: Some("Rcpt@example.com".into()),
+                action: Some("failed".into()),
+                status: Some("5.1.1".into()),
+                ..Default::default()
+            }),
+            feedback_report: None,
+        };
+        let event = DeliveryEvent::from_report(1, &report, Some(7)).unwrap();
+        assert_eq!(event.kind, DeliveryEventKind::Bounced);
+        assert_eq!(event.recipient.as_deref(), Some("rcpt@example.com"));
+        assert_eq!(event.message_id, "1.abc@example.com");
+        assert_eq!(event.task_id, Some(7));
+
+        let mut unknown = report.clone();
+        unknown.delivery_status.as_mut().unwrap().action = None;
+        assert!(DeliveryEvent::from_report(1, &unknown, None).is_none());
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Duration;
+
+use tracing::info;
+
+use crate::modules::{
+    context::RustMailTask, scheduler::periodic::PeriodicTask, smtp::timeline::DeliveryEvent,
+};
+
+const TASK_INTERVAL: Duration = Duration::from_secs(60 * 60); // every hour
+
+/// Removes the delivery events past their retention.
+pub struct DeliveryTimelineCleanTask;
+
+impl RustMailTask for DeliveryTimelineCleanTask {
+    fn start() {
+        let periodic_task = PeriodicTask::new("delivery-timeline-cleaner");
+
+        let task = move |_ctx: Option<u64>| {
+            Box::pin(async move {
+                let removed = DeliveryEvent::clean().await?;
+                if removed > 0 {
+                    info!("Removed {} expired delivery events", removed);
+                }
+                Ok(())
+            })
+        };
+
+        periodic_task.start(task, None, TASK_INTERVAL, false, false);
+    }
+}
@@ -4,9 +4,28 @@
 
 use rand::Rng;
 
-use crate::utc_now;
+use crate::{
+    modules::{
+        account::migration::AccountModel,
+        error::{code::ErrorCode, RustMailerResult},
+    },
+    raise_error, utc_now,
+};
 
-pub fn generate_message_id() -> String {
+/// Domain used in generated Message-IDs when no other domain is available.
+pub const DEFAULT_MESSAGE_ID_DOMAIN: &str = "rustmailer";
+
+/// Generates a globally unique `Message-ID`.
+///
+/// The format is stable and can be relied on by clients:
+///
+/// ```text
+/// <{timestamp}.{random}@{domain}>
+/// ```
+///
+/// where `timestamp` is the Unix epoch time in milliseconds, `random` is 32 lowercase
+/// hex characters (16 random bytes) and `domain` is the configured Message-ID domain.
+pub fn generate_message_id(domain: &str) -> String {
     // Generate 16 random bytes
     let random_bytes: [u8; 16] = rand::rng().random();
     // Convert to hex
@@ -14,14 +33,106 @@ pub fn generate_message_id() -> String {
     // Get current timestamp in milliseconds
     let timestamp_millis = utc_now!();
     // Format the message ID
-    format!("<{}.{}@rustmailer>", timestamp_millis, random_id)
+    format!("<{}.{}@{}>", timestamp_millis, random_id, domain)
+}
+
+/// Picks the domain for a generated Message-ID: the explicitly configured domain if
+/// set, otherwise the domain of the sender address, falling back to
+/// [`DEFAULT_MESSAGE_ID_DOMAIN`].
+pub fn resolve_message_id_domain<'a>(configured: Option<&'a str>, from: &'a str) -> &'a str {
+    configured
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .or_else(|| {
+            from.rsplit_once('@')
+                .map(|(_, domain)| domain.trim().trim_end_matches('>'))
+                .filter(|d| !d.is_empty() && is_valid_domain(d))
+        })
+        .unwrap_or(DEFAULT_MESSAGE_ID_DOMAIN)
+}
+
+/// Generates a Message-ID for an email sent by `account` from the `from` address.
+pub fn generate_account_message_id(account: &AccountModel, from: &str) -> String {
+    generate_message_id(resolve_message_id_domain(
+        account.message_id_domain.as_deref(),
+        from,
+    ))
+}
+
+pub fn validate_message_id_domain(domain: &str) -> RustMailerResult<()> {
+    if is_valid_domain(domain) {
+        Ok(())
+    } else {
+        Err(raise_error!(
+            format!("Invalid 'message_id_domain': '{domain}' is not a valid domain name"),
+            ErrorCode::InvalidParameter
+        ))
+    }
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+    !domain.is_empty()
+        && domain.len() <= 253
+        && domain
+            .split('.')
+            .all(|label| !label.is_empty() && label.len() <= 63)
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
 }
 
 #[cfg(test)]
 mod test {
-    use crate::modules::smtp::util::generate_message_id;
+    use crate::modules::smtp::util::{
+        generate_message_id, resolve_message_id_domain, validate_message_id_domain,
+        DEFAULT_MESSAGE_ID_DOMAIN,
+    };
     #[test]
     fn test1() {
-        println!("{}", generate_message_id());
+        println!("{}", generate_message_id(DEFAULT_MESSAGE_ID_DOMAIN));
+    }
+
+    #[test]
+    fn message_id_format_is_stable() {
+        let message_id = generate_message_id("mail.example.com");
+        let inner = message_id
+            .strip_prefix('<')
+            .and_then(|m| m.strip_suffix('>'))
+            .unwrap();
+        let (local, domain) = inner.split_once('@').unwrap();
+        assert_eq!(domain, "mail.example.com");
+        let (timestamp, random) = local.split_once('.').unwrap();
+        assert!(timestamp.parse::<i64>().is_ok());
+        assert_eq!(random.len(), 32);
+        assert!(random
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)));
+    }
+
+    #[test]
+    fn resolves_message_id_domain() {
+        assert_eq!(
+            resolve_message_id_domain(Some("mx.example.org"), "user@example.com"),
+            "mx.example.org"
+        );
+        assert_eq!(
+            resolve_message_id_domain(Some(" "), "user@example.com"),
+            "example.com"
+        );
+        assert_eq!(
+            resolve_message_id_domain(None, "user@example.com"),
+            "example.com"
+        );
+        assert_eq!(
+            resolve_message_id_domain(None, "not-an-address"),
+            DEFAULT_MESSAGE_ID_DOMAIN
+        );
+        assert_eq!(
+            resolve_message_id_domain(None, "user@bad domain"),
+            DEFAULT_MESSAGE_ID_DOMAIN
+        );
+        assert!(validate_message_id_domain("mail.example.com").is_ok());
+        assert!(validate_message_id_domain("example..com").is_err());
+        assert!(validate_message_id_domain("example.com>").is_err());
     }
 }
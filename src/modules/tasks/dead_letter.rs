@@ -0,0 +1,507 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::{Enum, Object};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    modules::{
+        common::{auth::ClientContext, paginated::paginate_vec},
+        database::{
+            batch::WriteBatch, batch_delete_impl, delete_impl, filter_by_secondary_key_impl,
+            key::CompositeKey, list_all_impl, manager::DB_MANAGER, secondary_find_impl,
+            with_transaction,
+        },
+        error::{code::ErrorCode, RustMailerResult},
+        hook::task::{EventHookTask, SendEventHookTask},
+        metrics::{EMAIL, HOOK, RUSTMAILER_DEAD_LETTER_QUEUE_DEPTH},
+        overview::memory::check_metadata_capacity,
+        rest::response::DataPage,
+        scheduler::{model::TaskStatus, nativedb::TaskMetaEntity, task::Task},
+        smtp::{queue::message::SendEmailTask, request::task::SmtpTask},
+        tasks::queue::RustMailerTaskQueue,
+    },
+    raise_error, utc_now,
+};
+
+/// The kind of task kept in the dead-letter queue.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Enum)]
+pub enum DeadLetterKind {
+    /// An email sending task.
+    #[default]
+    Email,
+    /// An event hook delivery task.
+    Hook,
+}
+
+impl DeadLetterKind {
+    fn from_task_key(task_key: &str) -> Option<Self> {
+        match task_key {
+            SmtpTask::TASK_KEY => Some(DeadLetterKind::Email),
+            EventHookTask::TASK_KEY => Some(DeadLetterKind::Hook),
+            _ => None,
+        }
+    }
+
+    fn task_key(&self) -> &'static str {
+        match self {
+            DeadLetterKind::Email => SmtpTask::TASK_KEY,
+            DeadLetterKind::Hook => EventHookTask::TASK_KEY,
+        }
+    }
+
+    fn metric_label(&self) -> &'static str {
+        match self {
+            DeadLetterKind::Email => EMAIL,
+            DeadLetterKind::Hook => HOOK,
+        }
+    }
+}
+
+/// An email or event hook task that failed permanently after exhausting its retries.
+///
+/// Dead letters are kept until they are requeued or purged, independently of the task
+/// cleanup, so permanent failures can be inspected and replayed later.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 21, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct DeadLetter {
+    /// The ID of the failed task.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// The kind of the failed task.
+    pub kind: DeadLetterKind,
+    /// The account the task belongs to.
+    #[secondary_key]
+    pub account_id: u64,
+    /// The email address of the account.
+    pub account_email: String,
+    /// The error of the last attempt.
+    pub error: Option<String>,
+    /// Number of attempts made before the task was given up.
+    pub retry_count: Option<usize>,
+    /// Duration of the last attempt, in milliseconds.
+    pub last_duration_ms: Option<usize>,
+    /// Time (Unix epoch milliseconds) the task was created.
+    pub task_created_at: i64,
+    /// Time (Unix epoch milliseconds) the task was moved to the dead-letter queue.
+    pub failed_at: i64,
+    /// The parameters of the task, used to inspect and requeue it.
+    #[oai(skip)]
+    pub task_params: String,
+}
+
+/// A dead letter together with the details of its task.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct DeadLetterDetail {
+    pub dead_letter: DeadLetter,
+    /// The email task, for `Email` dead letters.
+    pub email_task: Option<SendEmailTask>,
+    /// The event hook task, for `Hook` dead letters.
+    pub hook_task: Option<SendEventHookTask>,
+}
+
+/// Selects dead letters. All set criteria must match.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct DeadLetterFilter {
+    /// Only dead letters of this kind.
+    pub kind: Option<DeadLetterKind>,
+    /// Only dead letters of these accounts. Access tokens are always limited to the
+    /// accounts they can access.
+    pub account_ids: Option<Vec<u64>>,
+    /// Only dead letters moved to the queue before this time (Unix epoch milliseconds).
+    pub failed_before: Option<i64>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct DeadLetterRequeueResult {
+    /// The ID of the new task running the requeued work.
+    pub task_id: u64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct DeadLetterPurgeResult {
+    /// Number of dead letters removed.
+    pub purged: u64,
+}
+
+impl DeadLetterFilter {
+    /// Accounts the filter is limited to, or `None` for all accounts.
+    fn scope(&self, context: &ClientContext) -> RustMailerResult<Option<BTreeSet<u64>>> {
+        match &self.account_ids {
+            Some(account_ids) => {
+                for account_id in account_ids {
+                    context.require_account_access(*account_id)?;
+                }
+                Ok(Some(account_ids.iter().copied().collect()))
+            }
+            None => Ok(context
+                .accessible_accounts()?
+                .map(|accounts| accounts.iter().map(|a| a.id).collect())),
+        }
+    }
+
+    fn matches(&self, letter: &DeadLetter, accounts: Option<&BTreeSet<u64>>) -> bool {
+        self.kind.is_none_or(|kind| kind == letter.kind)
+            && accounts.is_none_or(|a| a.contains(&letter.account_id))
+            && self
+                .failed_before
+                .is_none_or(|before| letter.failed_at < before)
+    }
+}
+
+impl DeadLetter {
+    fn pk(&self) -> String {
+        CompositeKey::new()
+            .segment(self.failed_at)
+            .segment(self.id)
+            .build()
+    }
+
+    /// Builds the dead letter of a task that failed permanently, or `None` if the task
+    /// is not an email or event hook task.
+    fn from_task(task: &TaskMetaEntity, failed_at: i64) -> RustMailerResult<Option<Self>> {
+        let Some(kind) = DeadLetterKind::from_task_key(&task.task_key) else {
+            return Ok(None);
+        };
+        let (account_id, account_email) = match kind {
+            DeadLetterKind::Email => {
+                let task: SmtpTask = serde_json::from_str(&task.task_params)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                (task.account_id, task.account_email)
+            }
+            DeadLetterKind::Hook => {
+                let task: EventHookTask = serde_json::from_str(&task.task_params)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                (task.account_id, task.account_email)
+            }
+        };
+        Ok(Some(Self {
+            id: task.id,
+            kind,
+            account_id,
+            account_email,
+            error: task.last_error.clone(),
+            retry_count: task.retry_count,
+            last_duration_ms: task.last_duration_ms,
+            task_created_at: task.created_at,
+            failed_at,
+            task_params: task.task_params.clone(),
+        }))
+    }
+
+    /// Moves a task that exhausted its retries to the dead-letter queue. A previous dead
+    /// letter of the same task, e.g. one retried in bulk, is replaced.
+    pub async fn capture(task: &TaskMetaEntity) -> RustMailerResult<()> {
+        if task.status != TaskStatus::Failed {
+            return Ok(());
+        }
+        let Some(letter) = Self::from_task(task, utc_now!())? else {
+            return Ok(());
+        };
+        check_metadata_capacity()?;
+        with_transaction(DB_MANAGER.meta_db(), move |rw| {
+            let previous: Option<DeadLetter> = rw
+                .get()
+                .secondary(DeadLetterKey::id, letter.id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            if let Some(previous) = previous {
+                rw.remove(previous)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            }
+            rw.insert(letter)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(())
+        })
+        .await?;
+        Self::refresh_depth().await
+    }
+
+    pub async fn find(id: u64) -> RustMailerResult<Option<DeadLetter>> {
+        secondary_find_impl(DB_MANAGER.meta_db(), DeadLetterKey::id, id).await
+    }
+
+    pub async fn get(id: u64) -> RustMailerResult<DeadLetter> {
+        Self::find(id).await?.ok_or_else(|| {
+            raise_error!(
+                format!("Dead letter id='{id}' not found."),
+                ErrorCode::ResourceNotFound
+            )
+        })
+    }
+
+    /// Lists the dead letters matching the filter, most recent failures first unless
+    /// `desc` is `false`.
+    pub async fn paginate_list(
+        context: &ClientContext,
+        filter: &DeadLetterFilter,
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<DeadLetter>> {
+        let mut letters = Self::select(context, filter).await?;
+        if desc.unwrap_or(true) {
+            letters.reverse();
+        }
+        paginate_vec(&letters, page, page_size).map(DataPage::from)
+    }
+
+    /// Loads the dead letters matching the filter, oldest failures first.
+    async fn select(
+        context: &ClientContext,
+        filter: &DeadLetterFilter,
+    ) -> RustMailerResult<Vec<DeadLetter>> {
+        let accounts = filter.scope(context)?;
+        Ok(list_all_impl::<DeadLetter>(DB_MANAGER.meta_db())
+            .await?
+            .into_iter()
+            .filter(|letter| filter.matches(letter, accounts.as_ref()))
+            .collect())
+    }
+
+    /// The dead letter together with the details of its task.
+    pub fn detail(self) -> RustMailerResult<DeadLetterDetail> {
+        let task = TaskMetaEntity {
+            id: self.id,
+            task_key: self.kind.task_key().into(),
+            task_params: self.task_params.clone(),
+            status: TaskStatus::Failed,
+            last_error: self.error.clone(),
+            last_duration_ms: self.last_duration_ms,
+            retry_count: self.retry_count,
+            next_run: self.failed_at,
+            updated_at: self.failed_at,
+            created_at: self.task_created_at,
+            ..Default::default()
+        };
+        let (email_task, hook_task) = match self.kind {
+            DeadLetterKind::Email => (Some(SendEmailTask::try_from(&task)?), None),
+            DeadLetterKind::Hook => (None, Some(SendEventHookTask::try_from(&task)?)),
+        };
+        Ok(DeadLetterDetail {
+            dead_letter: self,
+            email_task,
+            hook_task,
+        })
+    }
+
+    /// Queues the task of the dead letter again as a new task with a fresh retry count,
+    /// and removes the dead letter.
+    ///
+    /// A requeued email fails again if its content was evicted from the disk cache in the
+    /// meantime.
+    pub async fn requeue(id: u64) -> RustMailerResult<DeadLetterRequeueResult> {
+        let letter = Self::get(id).await?;
+        let queue = RustMailerTaskQueue::get()?;
+        let task = match letter.kind {
+            DeadLetterKind::Email => {
+                let task: SmtpTask = serde_json::from_str(&letter.task_params)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                queue.submit_task(task, None).await?
+            }
+            DeadLetterKind::Hook => {
+                let task: EventHookTask = serde_json::from_str(&letter.task_params)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                queue.submit_task(task, None).await?
+            }
+        };
+        Self::remove(id).await?;
+        Ok(DeadLetterRequeueResult { task_id: task.id })
+    }
+
+    pub async fn remove(id: u64) -> RustMailerResult<()> {
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<DeadLetter>(DeadLetterKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!(
+                            "The dead letter with id={id} that you want to delete was not found."
+                        ),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await?;
+        Self::refresh_depth().await
+    }
+
+    /// Removes the dead letters of tasks that were rescheduled by other means, such as a
+    /// bulk retry.
+    pub async fn remove_tasks(task_ids: Vec<u64>) -> RustMailerResult<()> {
+        let removed = batch_delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            let mut letters = Vec::new();
+            for id in task_ids {
+                let letter: Option<DeadLetter> = rw
+                    .get()
+                    .secondary(DeadLetterKey::id, id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                letters.extend(letter);
+            }
+            Ok(letters)
+        })
+        .await?;
+        if removed > 0 {
+            Self::refresh_depth().await?;
+        }
+        Ok(())
+    }
+
+    /// Removes all dead letters matching the filter.
+    pub async fn purge(
+        context: &ClientContext,
+        filter: &DeadLetterFilter,
+    ) -> RustMailerResult<DeadLetterPurgeResult> {
+        let letters = Self::select(context, filter).await?;
+        let mut purged = 0;
+        // Keep write transactions short.
+        for chunk in letters.chunks(100) {
+            let chunk = chunk.to_vec();
+            purged += batch_delete_impl(DB_MANAGER.meta_db(), move |_| Ok(chunk)).await?;
+        }
+        Self::refresh_depth().await?;
+        Ok(DeadLetterPurgeResult {
+            purged: purged as u64,
+        })
+    }
+
+    /// Adds the removal of all dead letters of an account to `batch`.
+    pub fn stage_remove_account_dead_letters(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.delete_all(move |rw| {
+            let letters: Vec<DeadLetter> = rw
+                .scan()
+                .secondary::<DeadLetter>(DeadLetterKey::account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .start_with(account_id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .try_collect()
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            Ok(letters)
+        })
+    }
+
+    /// Number of dead letters of an account.
+    pub async fn count_for_account(account_id: u64) -> RustMailerResult<usize> {
+        let letters: Vec<DeadLetter> = filter_by_secondary_key_impl(
+            DB_MANAGER.meta_db(),
+            DeadLetterKey::account_id,
+            account_id,
+        )
+        .await?;
+        Ok(letters.len())
+    }
+
+    /// Updates the dead-letter queue depth metric.
+    pub async fn refresh_depth() -> RustMailerResult<()> {
+        let letters = list_all_impl::<DeadLetter>(DB_MANAGER.meta_db()).await?;
+        for kind in [DeadLetterKind::Email, DeadLetterKind::Hook] {
+            let depth = letters.iter().filter(|l| l.kind == kind).count();
+            RUSTMAILER_DEAD_LETTER_QUEUE_DEPTH
+                .with_label_values(&[kind.metric_label()])
+                .set(depth as i64);
+        }
+        Ok(())
+    }
+}
+
+/// Moves a task to the dead-letter queue if it just failed permanently. Failures are only
+/// logged, as the task itself is already marked as failed.
+pub async fn capture_failed_task(task: Option<TaskMetaEntity>) {
+    let Some(task) = task else {
+        return;
+    };
+    if let Err(e) = DeadLetter::capture(&task).await {
+        warn!(
+            "Failed to move task {} to the dead-letter queue: {:#?}",
+            task.id, e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::hook::events::EventType;
+
+    fn hook_task(status: TaskStatus) -> TaskMetaEntity {
+        let params = EventHookTask {
+            event_hook_id: 1,
+            account_id: 7,
+            account_email: "user@example.com".into(),
+            event_type: EventType::EmailSendingError,
+            event: serde_json::json!({}),
+            test: false,
+            replay_of: None,
+        };
+        TaskMetaEntity {
+            id: 42,
+            task_key: EventHookTask::TASK_KEY.into(),
+            task_params: serde_json::to_string(&params).unwrap(),
+            status,
+            last_error: Some("connection refused".into()),
+            retry_count: Some(10),
+            created_at: 1_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn builds_dead_letters_from_failed_tasks() {
+        let letter = DeadLetter::from_task(&hook_task(TaskStatus::Failed), 2_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(letter.kind, DeadLetterKind::Hook);
+        assert_eq!(letter.account_id, 7);
+        assert_eq!(letter.error.as_deref(), Some("connection refused"));
+        assert_eq!(
+            letter.pk(),
+            DeadLetter {
+                failed_at: 2_000,
+                id: 42,
+                ..Default::default()
+            }
+            .pk()
+        );
+
+        let detail = letter.detail().unwrap();
+        assert!(detail.email_task.is_none());
+        let hook = detail.hook_task.unwrap();
+        assert_eq!((hook.id, hook.status), (42, TaskStatus::Failed));
+
+        let other = TaskMetaEntity {
+            task_key: "other".into(),
+            ..hook_task(TaskStatus::Failed)
+        };
+        assert_eq!(DeadLetter::from_task(&other, 2_000).unwrap(), None);
+    }
+
+    #[test]
+    fn filters_dead_letters() {
+        let letter = DeadLetter::from_task(&hook_task(TaskStatus::Failed), 2_000)
+            .unwrap()
+            .unwrap();
+        let all = DeadLetterFilter::default();
+        assert!(all.matches(&letter, None));
+        assert!(!all.matches(&letter, Some(&BTreeSet::from([8]))));
+
+        let emails = DeadLetterFilter {
+            kind: Some(DeadLetterKind::Email),
+            ..Default::default()
+        };
+        assert!(!emails.matches(&letter, None));
+
+        let old = DeadLetterFilter {
+            failed_before: Some(2_000),
+            ..Default::default()
+        };
+        assert!(!old.matches(&letter, None));
+    }
+}
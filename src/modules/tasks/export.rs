@@ -0,0 +1,262 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::{collections::BTreeSet, io, ops::Bound, sync::Arc};
+
+use bytes::Bytes;
+use futures::{stream, Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    modules::{
+        common::auth::ClientContext,
+        database::{
+            key::{timestamp_key_range, CompositeKey, KeyRange},
+            manager::DB_MANAGER,
+            range_by_primary_key_limited_impl,
+        },
+        error::{code::ErrorCode, RustMailerError, RustMailerResult},
+        hook::task::{EventHookTask, SendEventHookTask},
+        scheduler::{model::TaskStatus, nativedb::TaskMetaEntity, task::Task},
+        smtp::{queue::message::SendEmailTask, request::task::SmtpTask},
+    },
+    raise_error,
+};
+
+/// Number of task records read from the database per round trip while exporting.
+const EXPORT_BATCH_SIZE: usize = 500;
+
+/// The task history that is exported.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TaskExportKind {
+    Email,
+    Hook,
+}
+
+impl TaskExportKind {
+    fn task_key(&self) -> &'static str {
+        match self {
+            TaskExportKind::Email => SmtpTask::TASK_KEY,
+            TaskExportKind::Hook => EventHookTask::TASK_KEY,
+        }
+    }
+
+    /// File name suggested to clients downloading the export.
+    pub fn filename(&self, timestamp: i64) -> String {
+        match self {
+            TaskExportKind::Email => format!("email-tasks-{timestamp}.ndjson"),
+            TaskExportKind::Hook => format!("hook-tasks-{timestamp}.ndjson"),
+        }
+    }
+
+    /// Converts a stored task into its API representation.
+    fn to_record(self, entity: &TaskMetaEntity) -> RustMailerResult<Value> {
+        match self {
+            TaskExportKind::Email => to_value(&SendEmailTask::try_from(entity)?),
+            TaskExportKind::Hook => to_value(&SendEventHookTask::try_from(entity)?),
+        }
+    }
+}
+
+/// Reads task history ordered by creation time (oldest first), either page by page or as
+/// a stream of newline-delimited JSON, one task per line.
+///
+/// Records are read from the database in batches, so exports of any size are served with
+/// bounded memory. Clients doing periodic ETL can pass the `created_at` of the last
+/// exported record (plus one) as `created_after` on their next run.
+#[derive(Clone, Debug)]
+pub struct TaskExport {
+    kind: TaskExportKind,
+    range: KeyRange,
+    status: Option<TaskStatus>,
+    fields: Option<Vec<String>>,
+    accounts: Option<BTreeSet<u64>>,
+}
+
+impl TaskExport {
+    /// Creates an export limited to the accounts the client can access.
+    ///
+    /// `fields` is a comma-separated list of top-level fields to include in each record;
+    /// when omitted, all fields are exported. Unknown field names are ignored.
+    pub fn new(
+        context: &ClientContext,
+        kind: TaskExportKind,
+        created_after: Option<i64>,
+        created_before: Option<i64>,
+        status: Option<TaskStatus>,
+        fields: Option<&str>,
+    ) -> RustMailerResult<Self> {
+        if let (Some(after), Some(before)) = (created_after, created_before) {
+            if after >= before {
+                return Err(raise_error!(
+                    "'created_after' must be earlier than 'created_before'".into(),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+        let fields = fields.map(parse_fields);
+        if fields.as_ref().is_some_and(|f| f.is_empty()) {
+            return Err(raise_error!(
+                "'fields' must name at least one field".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let accounts = context
+            .accessible_accounts()?
+            .map(|accounts| accounts.iter().map(|a| a.id).collect());
+        Ok(Self {
+            kind,
+            range: timestamp_key_range(created_after, created_before),
+            status,
+            fields,
+            accounts,
+        })
+    }
+
+    /// Start of the exported range; pass it as the cursor of the first [`Self::next_page`].
+    pub fn start(&self) -> Bound<String> {
+        self.range.0.clone()
+    }
+
+    /// Reads up to `limit` matching tasks following `cursor`, oldest first.
+    ///
+    /// Returns the tasks with the cursor to continue from, which is `None` once the range
+    /// is exhausted.
+    pub async fn next_page(
+        &self,
+        mut cursor: Bound<String>,
+        limit: usize,
+    ) -> RustMailerResult<(Vec<TaskMetaEntity>, Option<Bound<String>>)> {
+        let mut tasks = Vec::new();
+        loop {
+            let batch: Vec<TaskMetaEntity> = range_by_primary_key_limited_impl(
+                DB_MANAGER.tasks_db(),
+                (cursor.clone(), self.range.1.clone()),
+                EXPORT_BATCH_SIZE,
+            )
+            .await?;
+            let exhausted = batch.len() < EXPORT_BATCH_SIZE;
+            for entity in batch {
+                cursor = Bound::Excluded(task_cursor(&entity));
+                if self.matches(&entity)? {
+                    tasks.push(entity);
+                    if tasks.len() == limit {
+                        return Ok((tasks, Some(cursor)));
+                    }
+                }
+            }
+            if exhausted {
+                return Ok((tasks, None));
+            }
+        }
+    }
+
+    /// Returns the NDJSON byte stream. Database errors end the stream with an I/O error.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Bytes, io::Error>> + Send + 'static {
+        let export = Arc::new(self);
+        stream::try_unfold(Some(export.start()), move |cursor| {
+            let export = export.clone();
+            async move {
+                let Some(cursor) = cursor else {
+                    return Ok(None);
+                };
+                let (tasks, cursor) = export.next_page(cursor, EXPORT_BATCH_SIZE).await?;
+                if tasks.is_empty() {
+                    return Ok(None);
+                }
+                Ok(Some((Bytes::from(export.render(&tasks)?), cursor)))
+            }
+        })
+        .map_err(|e: RustMailerError| io::Error::other(e.to_string()))
+    }
+
+    fn matches(&self, entity: &TaskMetaEntity) -> RustMailerResult<bool> {
+        if entity.task_key != self.kind.task_key()
+            || self.status.as_ref().is_some_and(|s| *s != entity.status)
+        {
+            return Ok(false);
+        }
+        let Some(accounts) = &self.accounts else {
+            return Ok(true);
+        };
+        let task: TaskAccount = serde_json::from_str(&entity.task_params)
+            .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+        Ok(accounts.contains(&task.account_id))
+    }
+
+    /// Renders tasks as NDJSON lines.
+    fn render(&self, tasks: &[TaskMetaEntity]) -> RustMailerResult<Vec<u8>> {
+        let mut out = Vec::new();
+        for entity in tasks {
+            let record = self.kind.to_record(entity)?;
+            let record = match &self.fields {
+                Some(fields) => project_fields(record, fields),
+                None => record,
+            };
+            serde_json::to_writer(&mut out, &record)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+            out.push(b'\n');
+        }
+        Ok(out)
+    }
+}
+
+/// The account a task belongs to; both email and hook task parameters carry it.
+#[derive(Deserialize)]
+struct TaskAccount {
+    account_id: u64,
+}
+
+/// The primary key of a task, used as pagination cursor.
+fn task_cursor(entity: &TaskMetaEntity) -> String {
+    CompositeKey::new()
+        .segment(entity.created_at)
+        .segment(entity.id)
+        .build()
+}
+
+fn to_value<T: Serialize>(value: &T) -> RustMailerResult<Value> {
+    serde_json::to_value(value)
+        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))
+}
+
+fn parse_fields(fields: &str) -> Vec<String> {
+    fields
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(Into::into)
+        .collect()
+}
+
+/// Keeps only the given top-level fields of a JSON object.
+fn project_fields(record: Value, fields: &[String]) -> Value {
+    match record {
+        Value::Object(mut map) => {
+            map.retain(|key, _| fields.iter().any(|f| f == key));
+            Value::Object(map)
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn projects_selected_fields() {
+        let fields = parse_fields(" id, status,,unknown ");
+        assert_eq!(fields, vec!["id", "status", "unknown"]);
+
+        let record = json!({"id": 1, "status": "Success", "subject": "Hello", "to": ["a@b.c"]});
+        assert_eq!(
+            project_fields(record, &fields),
+            json!({"id": 1, "status": "Success"})
+        );
+        assert_eq!(parse_fields(" , "), Vec::<String>::new());
+    }
+}
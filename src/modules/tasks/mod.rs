@@ -2,17 +2,27 @@
 // Licensed under RustMailer License Agreement v1.0
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use crate::modules::account::inactive::InactiveAccountTask;
+use crate::modules::context::resources::FileDescriptorMonitorTask;
 use crate::modules::context::RustMailTask;
+use crate::modules::database::replica::ReadReplicaRefreshTask;
 use crate::modules::database::snapshot::task::DatabaseSnapshotTask;
+use crate::modules::hook::delivery::task::HookDeliveryCleanTask;
 use crate::modules::overview::clean::MetricsCleanTask;
 use crate::modules::overview::saver::MetricsSaveTask;
+use crate::modules::retention::task::CleanupRuleTask;
+use crate::modules::scheduler::recurring::task::RecurringSendTask;
+use crate::modules::smtp::timeline::task::DeliveryTimelineCleanTask;
+use crate::modules::token::lifecycle::AccessTokenExpiryTask;
 use crate::{
-    modules::cache::disk::task::DiskCacheCleanTask,
+    modules::cache::disk::task::{DiskCacheCleanTask, DiskCacheReconcileTask},
     modules::oauth2::{refresh::OAuth2RefreshTask, task::OAuth2CleanTask},
 };
 
 use crate::modules::database::backup::task::MetaBackupTask;
 
+pub mod dead_letter;
+pub mod export;
 pub mod queue;
 
 pub struct PeriodicTasks;
@@ -20,11 +30,20 @@ pub struct PeriodicTasks;
 impl PeriodicTasks {
     pub fn start_background_tasks() {
         DiskCacheCleanTask::start();
+        DiskCacheReconcileTask::start();
         OAuth2CleanTask::start();
         OAuth2RefreshTask::start();
         MetaBackupTask::start();
         DatabaseSnapshotTask::start();
         MetricsSaveTask::start();
         MetricsCleanTask::start();
+        ReadReplicaRefreshTask::start();
+        InactiveAccountTask::start();
+        CleanupRuleTask::start();
+        FileDescriptorMonitorTask::start();
+        RecurringSendTask::start();
+        DeliveryTimelineCleanTask::start();
+        HookDeliveryCleanTask::start();
+        AccessTokenExpiryTask::start();
     }
 }
@@ -4,24 +4,30 @@
 
 use crate::modules::error::code::ErrorCode;
 use crate::modules::hook::task::{EventHookTask, SendEventHookTask, EVENTHOOK_QUEUE};
+use crate::modules::metrics::{RUSTMAILER_EMAIL_SEND_DURATION_SECONDS, SUCCESS};
 use crate::modules::rest::response::DataPage;
 use crate::modules::scheduler::context::TaskContext;
-use crate::modules::scheduler::model::TaskStatus;
+use crate::modules::scheduler::model::{TaskMeta, TaskStatus};
 use crate::modules::scheduler::nativedb::meta::NativeDbTaskStore;
 use crate::modules::scheduler::nativedb::TaskMetaEntity;
 use crate::modules::scheduler::task::Task;
 use crate::modules::settings::cli::SETTINGS;
 use crate::modules::smtp::queue::message::SendEmailTask;
 use crate::modules::smtp::request::task::{SmtpTask, OUTBOX_QUEUE};
+use crate::modules::tasks::dead_letter::DeadLetter;
 use crate::{
     modules::{context::Initialize, database::manager::DB_MANAGER, error::RustMailerResult},
-    raise_error,
+    raise_error, utc_now,
 };
 use std::sync::{Arc, OnceLock};
 use tokio::sync::RwLock;
+use tracing::warn;
 
 static TASK_QUEUE: OnceLock<RustMailerTaskQueue> = OnceLock::new();
 
+/// Send duration assumed for ETA estimates until an email has actually been sent.
+const DEFAULT_SEND_DURATION_MS: i64 = 1000;
+
 impl Initialize for RustMailerTaskQueue {
     async fn initialize() -> RustMailerResult<()> {
         let scheduler = RustMailerTaskQueue::new().await;
@@ -53,12 +59,19 @@ impl RustMailerTaskQueue {
             .set_concurrency(EVENTHOOK_QUEUE, SETTINGS.rustmailer_event_hook_workers)
             .start_with_cleaner()
             .await;
+        if let Err(e) = DeadLetter::refresh_depth().await {
+            warn!("Failed to load the dead-letter queue depth: {:#?}", e);
+        }
         RustMailerTaskQueue {
             task_context: Arc::new(RwLock::new(task_context)),
         }
     }
 
-    pub async fn submit_task<T>(&self, task: T, delay_seconds: Option<u32>) -> RustMailerResult<()>
+    pub async fn submit_task<T>(
+        &self,
+        task: T,
+        delay_seconds: Option<u32>,
+    ) -> RustMailerResult<TaskMeta>
     where
         T: Task + Send + Sync + 'static,
     {
@@ -156,6 +169,40 @@ impl RustMailerTaskQueue {
         ))
     }
 
+    /// Returns the number of scheduled email tasks that will run before `task`, and the
+    /// estimated time (Unix epoch milliseconds) at which `task` will be sent.
+    ///
+    /// The estimate assumes all outbox workers are busy with the tasks ahead, each taking
+    /// the average send duration observed since startup.
+    pub async fn estimate_email_task_start(&self, task: &TaskMeta) -> RustMailerResult<(u64, i64)> {
+        let scheduled = NativeDbTaskStore::get_all_tasks_by_status(
+            DB_MANAGER.tasks_db(),
+            SmtpTask::TASK_KEY,
+            TaskStatus::Scheduled,
+        )
+        .await?;
+        let position = scheduled
+            .iter()
+            .filter(|t| {
+                t.id != task.id && (t.next_run, t.created_at) <= (task.next_run, task.created_at)
+            })
+            .count() as u64;
+
+        let histogram = RUSTMAILER_EMAIL_SEND_DURATION_SECONDS.with_label_values(&[SUCCESS]);
+        let average_ms = match histogram.get_sample_count() {
+            0 => DEFAULT_SEND_DURATION_MS,
+            count => (histogram.get_sample_sum() / count as f64 * 1000.0) as i64,
+        };
+        let eta = estimate_start_time(
+            task.next_run,
+            utc_now!(),
+            position,
+            SETTINGS.rustmailer_send_mail_workers,
+            average_ms,
+        );
+        Ok((position, eta))
+    }
+
     pub async fn list_email_tasks_by_status(
         &self,
         status: TaskStatus,
@@ -292,3 +339,32 @@ impl RustMailerTaskQueue {
         NativeDbTaskStore::set_status(DB_MANAGER.tasks_db(), id, TaskStatus::Removed, None).await
     }
 }
+
+/// Estimates when a task runs, given the number of tasks ahead of it in the queue.
+fn estimate_start_time(
+    next_run: i64,
+    now: i64,
+    position: u64,
+    workers: usize,
+    average_ms: i64,
+) -> i64 {
+    let rounds = position / workers.max(1) as u64;
+    next_run.max(now + rounds as i64 * average_ms.max(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::estimate_start_time;
+
+    #[test]
+    fn estimates_start_time_from_queue_position() {
+        // Nothing ahead: runs at its scheduled time, or now if that has passed.
+        assert_eq!(estimate_start_time(5_000, 1_000, 0, 4, 500), 5_000);
+        assert_eq!(estimate_start_time(500, 1_000, 0, 4, 500), 1_000);
+        // 9 tasks ahead on 4 workers: two full rounds before this one starts.
+        assert_eq!(estimate_start_time(0, 1_000, 9, 4, 500), 2_000);
+        // A later schedule wins over the backlog estimate.
+        assert_eq!(estimate_start_time(10_000, 1_000, 9, 4, 500), 10_000);
+        assert_eq!(estimate_start_time(0, 1_000, 3, 0, 500), 2_500);
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::time::Duration;
+
+use tracing::{error, info};
+
+use crate::{
+    modules::{
+        audit::AuditEntry,
+        common::auth::ClientContext,
+        context::RustMailTask,
+        error::RustMailerResult,
+        hook::{
+            channel::{Event, EVENT_CHANNEL},
+            events::{
+                payload::{AccessTokenCreated, AccessTokenExpiring, AccessTokenRotated},
+                EventPayload, EventType, RustMailerEvent,
+            },
+            task::EventHookTask,
+        },
+        scheduler::periodic::PeriodicTask,
+        settings::cli::SETTINGS,
+        token::{token_prefix, AccessToken},
+    },
+    utc_now,
+};
+
+const TASK_INTERVAL: Duration = Duration::from_secs(60 * 60); // every hour
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Records the creation of a token in the audit log and emits `AccessTokenCreated`.
+pub async fn token_created(context: &ClientContext, token: &AccessToken) -> RustMailerResult<()> {
+    AuditEntry::new(
+        context,
+        "access_token_created",
+        None,
+        format!(
+            "token={}... scopes={:?} workspace={:?} expires_at={:?}",
+            token.prefix(),
+            token.access_scopes,
+            token.workspace_id,
+            token.expires_at
+        ),
+        true,
+    )
+    .save()
+    .await?;
+    emit(
+        EventType::AccessTokenCreated,
+        EventPayload::AccessTokenCreated(AccessTokenCreated {
+            token_prefix: token.prefix(),
+            description: token.description.clone(),
+            access_scopes: token
+                .access_scopes
+                .iter()
+                .map(|scope| format!("{scope:?}"))
+                .collect(),
+            workspace_id: token.workspace_id,
+            expires_at: token.expires_at,
+            actor: AuditEntry::actor_of(context),
+        }),
+    )
+    .await;
+    Ok(())
+}
+
+/// Records the rotation of a token in the audit log and emits `AccessTokenRotated`.
+pub async fn token_rotated(
+    context: &ClientContext,
+    rotated: &AccessToken,
+    replacement: &AccessToken,
+) -> RustMailerResult<()> {
+    let grace_period_ends_at = rotated.expires_at.unwrap_or_else(|| utc_now!());
+    AuditEntry::new(
+        context,
+        "access_token_rotated",
+        None,
+        format!(
+            "token={}... replaced_by={}... grace_period_ends_at={} expires_at={:?}",
+            rotated.prefix(),
+            replacement.prefix(),
+            grace_period_ends_at,
+            replacement.expires_at
+        ),
+        true,
+    )
+    .save()
+    .await?;
+    emit(
+        EventType::AccessTokenRotated,
+        EventPayload::AccessTokenRotated(AccessTokenRotated {
+            token_prefix: rotated.prefix(),
+            new_token_prefix: replacement.prefix(),
+            description: replacement.description.clone(),
+            workspace_id: replacement.workspace_id,
+            grace_period_ends_at,
+            expires_at: replacement.expires_at,
+            actor: AuditEntry::actor_of(context),
+        }),
+    )
+    .await;
+    Ok(())
+}
+
+/// Emits `AccessTokenExpiring` once for each token entering the warning window.
+async fn notify_expiring_tokens() -> RustMailerResult<()> {
+    let now = utc_now!();
+    let window_ms = SETTINGS.rustmailer_access_token_expiry_warning_days as i64 * DAY_MS;
+    let expiring: Vec<AccessToken> = AccessToken::list_all()
+        .await?
+        .into_iter()
+        .filter(|token| token.expiry_due(now, window_ms))
+        .collect();
+    for token in expiring {
+        let expires_at = token.expires_at.unwrap_or_default();
+        info!(
+            "Access token {}... ({}) expires at {}",
+            token.prefix(),
+            token.description.as_deref().unwrap_or("no description"),
+            expires_at
+        );
+        AuditEntry::system(
+            "access_token_expiring",
+            None,
+            format!("token={}... expires_at={}", token.prefix(), expires_at),
+        )
+        .save()
+        .await?;
+        emit(
+            EventType::AccessTokenExpiring,
+            EventPayload::AccessTokenExpiring(AccessTokenExpiring {
+                token_prefix: token.prefix(),
+                description: token.description.clone(),
+                workspace_id: token.workspace_id,
+                expires_at,
+                replaced_by_prefix: token.replaced_by.as_deref().map(token_prefix),
+            }),
+        )
+        .await;
+        AccessToken::mark_expiry_notified(&token.token).await?;
+    }
+    Ok(())
+}
+
+/// Deletes expired tokens, including rotated tokens past their grace period, so that they
+/// do not pile up in the metadata database.
+async fn purge_expired_tokens() -> RustMailerResult<()> {
+    for token in AccessToken::purge_expired(utc_now!()).await? {
+        info!(
+            "Purged expired access token {}... ({})",
+            token.prefix(),
+            token.description.as_deref().unwrap_or("no description")
+        );
+        AuditEntry::system(
+            "access_token_purged",
+            None,
+            format!(
+                "token={}... expires_at={:?} replaced_by={:?}",
+                token.prefix(),
+                token.expires_at,
+                token.replaced_by.as_deref().map(token_prefix)
+            ),
+        )
+        .save()
+        .await?;
+    }
+    Ok(())
+}
+
+/// Queues an instance-wide token event, if a hook or a subscriber watches it.
+async fn emit(event_type: EventType, payload: EventPayload) {
+    match EventHookTask::is_watching_instance_event(event_type.clone()).await {
+        Ok(true) => {
+            EVENT_CHANNEL
+                .queue(Event::new(0, "", RustMailerEvent::new(event_type, payload)))
+                .await;
+        }
+        Ok(false) => {}
+        Err(e) => {
+            error!("Failed to check event_watched for {}: {:#?}", event_type, e);
+        }
+    }
+}
+
+/// Periodically warns about access tokens about to expire and purges the expired ones.
+pub struct AccessTokenExpiryTask;
+
+impl RustMailTask for AccessTokenExpiryTask {
+    fn start() {
+        let periodic_task = PeriodicTask::new("access-token-expiry-notifier");
+
+        let task = move |_ctx: Option<u64>| {
+            Box::pin(async move {
+                notify_expiring_tokens().await?;
+                purge_expired_tokens().await
+            })
+        };
+
+        periodic_task.start(task, None, TASK_INTERVAL, false, false);
+    }
+}
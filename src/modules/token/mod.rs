@@ -3,15 +3,19 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::modules::account::migration::AccountModel;
-use crate::modules::database::delete_impl;
+use crate::modules::database::batch::WriteBatch;
 use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::{async_find_impl, delete_impl};
 use crate::modules::database::{insert_impl, list_all_impl, update_impl};
-use crate::modules::token::payload::AccessTokenUpdateRequest;
+use crate::modules::token::payload::{AccessTokenRotateRequest, AccessTokenUpdateRequest};
+use crate::modules::token::permission::ApiOperation;
+use crate::modules::workspace::Workspace;
 use crate::raise_error;
 use crate::{
     generate_token, modules::error::RustMailerResult,
     modules::token::payload::AccessTokenCreateRequest, utc_now,
 };
+use itertools::Itertools;
 use native_db::*;
 use native_model::{native_model, Model};
 use poem_openapi::{Enum, Object};
@@ -22,17 +26,76 @@ use std::str::FromStr;
 
 use super::error::code::ErrorCode;
 
+pub mod lifecycle;
 pub mod payload;
+pub mod permission;
 pub mod root;
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Object)]
 #[native_model(id = 1, version = 1)]
 #[native_db]
+pub struct AccessTokenV1 {
+    /// The unique token string used for authentication
+    #[primary_key]
+    pub token: String,
+    /// A set of account information associated with the token.
+    pub accounts: BTreeSet<AccountInfo>,
+    /// The timestamp (in milliseconds since epoch) when the token was created.
+    pub created_at: i64,
+    /// The timestamp (in milliseconds since epoch) when the token was last updated.
+    pub updated_at: i64,
+    /// An optional description of the token's purpose or usage.
+    pub description: Option<String>,
+    /// A set of scopes defining the token's access permissions.
+    pub access_scopes: BTreeSet<AccessTokenScope>,
+    /// The timestamp (in milliseconds since epoch) when the token was last used.
+    pub last_access_at: i64,
+    /// Optional access control settings
+    pub acl: Option<AccessControl>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Object)]
+#[native_model(id = 1, version = 2, from = AccessTokenV1)]
+#[native_db]
+pub struct AccessTokenV2 {
+    /// The unique token string used for authentication
+    #[primary_key]
+    pub token: String,
+    /// A set of account information associated with the token.
+    ///
+    /// For a token scoped to a workspace, an empty set grants access to every account of the
+    /// workspace, including accounts created later.
+    pub accounts: BTreeSet<AccountInfo>,
+    /// The timestamp (in milliseconds since epoch) when the token was created.
+    pub created_at: i64,
+    /// The timestamp (in milliseconds since epoch) when the token was last updated.
+    pub updated_at: i64,
+    /// An optional description of the token's purpose or usage.
+    pub description: Option<String>,
+    /// A set of scopes defining the token's access permissions.
+    pub access_scopes: BTreeSet<AccessTokenScope>,
+    /// The timestamp (in milliseconds since epoch) when the token was last used.
+    pub last_access_at: i64,
+    /// Optional access control settings
+    pub acl: Option<AccessControl>,
+    /// The workspace the token is scoped to. The token only reaches the accounts,
+    /// templates, MTAs and event hooks of that workspace.
+    ///
+    /// If not set, the token is not scoped to any workspace.
+    pub workspace_id: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Object)]
+#[native_model(id = 1, version = 3, from = AccessTokenV2)]
+#[native_db]
 pub struct AccessToken {
     /// The unique token string used for authentication
     #[primary_key]
     pub token: String,
     /// A set of account information associated with the token.
+    ///
+    /// For a token scoped to a workspace, an empty set grants access to every account of the
+    /// workspace, including accounts created later.
     pub accounts: BTreeSet<AccountInfo>,
     /// The timestamp (in milliseconds since epoch) when the token was created.
     pub created_at: i64,
@@ -46,6 +109,20 @@ pub struct AccessToken {
     pub last_access_at: i64,
     /// Optional access control settings
     pub acl: Option<AccessControl>,
+    /// The workspace the token is scoped to. The token only reaches the accounts,
+    /// templates, MTAs and event hooks of that workspace.
+    ///
+    /// If not set, the token is not scoped to any workspace.
+    pub workspace_id: Option<u64>,
+    /// The timestamp (in milliseconds since epoch) after which the token is rejected.
+    ///
+    /// If not set, the token never expires.
+    pub expires_at: Option<i64>,
+    /// The token that replaced this one when it was rotated. A rotated token keeps working
+    /// until `expires_at`, the end of its grace period.
+    pub replaced_by: Option<String>,
+    /// Whether the `AccessTokenExpiring` event was emitted for the current `expires_at`.
+    pub expiry_notified: bool,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Deserialize, Serialize, Object)]
@@ -124,6 +201,8 @@ impl AccessToken {
         description: Option<String>,
         access_scopes: BTreeSet<AccessTokenScope>,
         acl: Option<AccessControl>,
+        workspace_id: Option<u64>,
+        expires_at: Option<i64>,
     ) -> Self {
         Self {
             token,
@@ -134,9 +213,53 @@ impl AccessToken {
             access_scopes,
             last_access_at: Default::default(),
             acl,
+            workspace_id,
+            expires_at,
+            replaced_by: None,
+            expiry_notified: false,
         }
     }
 
+    pub async fn find(token: &str) -> RustMailerResult<Option<AccessToken>> {
+        async_find_impl(DB_MANAGER.meta_db(), token.to_string()).await
+    }
+
+    /// Limits the accounts of a token scoped to a workspace to the current accounts of the
+    /// workspace, all of them if the token lists none. Other tokens are limited to the
+    /// accounts outside any workspace.
+    pub async fn resolve_workspace_accounts(self) -> RustMailerResult<AccessToken> {
+        match self.workspace_id {
+            Some(workspace_id) => {
+                let workspace_accounts = Workspace::accounts(workspace_id).await?;
+                Ok(self.within_workspace(workspace_accounts))
+            }
+            None => {
+                let assigned_accounts = Workspace::assigned_accounts().await?;
+                Ok(self.outside_workspaces(&assigned_accounts))
+            }
+        }
+    }
+
+    fn within_workspace(mut self, workspace_accounts: BTreeSet<AccountInfo>) -> Self {
+        self.accounts = if self.accounts.is_empty() {
+            workspace_accounts
+        } else {
+            self.accounts
+                .intersection(&workspace_accounts)
+                .cloned()
+                .collect()
+        };
+        self
+    }
+
+    /// Drops the accounts listed in `assigned_accounts`, the accounts belonging to a workspace.
+    fn outside_workspaces(mut self, assigned_accounts: &BTreeSet<u64>) -> Self {
+        self.accounts
+            .retain(|account| !assigned_accounts.contains(&account.id));
+        self
+    }
+
+    /// Records an access with `token`. Expired tokens are rejected without being touched.
     pub async fn try_update_access_timestamp(token: &str) -> RustMailerResult<AccessToken> {
         let token = token.to_string();
         update_impl(
@@ -150,6 +273,12 @@ impl AccessToken {
                     })
             },
             |current| {
+                if current.is_expired() {
+                    return Err(raise_error!(
+                        "Access token has expired".into(),
+                        ErrorCode::PermissionDenied
+                    ));
+                }
                 let mut updated = current.clone();
                 updated.last_access_at = utc_now!();
                 Ok(updated)
@@ -158,10 +287,15 @@ impl AccessToken {
         .await
     }
 
-    pub async fn grant_account_access(token: &str, account: AccountInfo) -> RustMailerResult<()> {
+    /// Adds granting `token` access to `account` to `batch`. Tokens already reaching every
+    /// account of their workspace are left unchanged.
+    pub fn stage_grant_account_access(
+        batch: WriteBatch,
+        token: &str,
+        account: AccountInfo,
+    ) -> WriteBatch {
         let token = token.to_string();
-        update_impl(
-            DB_MANAGER.meta_db(),
+        batch.update(
             move |rw| {
                 rw.get()
                     .primary::<AccessToken>(token.clone())
@@ -169,34 +303,116 @@ impl AccessToken {
                     .ok_or_else(|| {
                         raise_error!(
                             format!(
-                            "The access token with token={} that you want to modify was not found.",
-                            token
-                        ),
+                                "The access token with token={} that you want to modify was not found.",
+                                token
+                            ),
                             ErrorCode::ResourceNotFound
                         )
                     })
             },
             |current| {
                 let mut updated = current.clone();
+                if current.workspace_id.is_some() && current.accounts.is_empty() {
+                    return Ok(updated);
+                }
                 updated.accounts.insert(account);
                 updated.updated_at = utc_now!();
                 Ok(updated)
             },
         )
-        .await?;
-        Ok(())
+    }
+
+    /// Adds revoking access to `account_id` from every access token to `batch`.
+    pub fn stage_cleanup_account(batch: WriteBatch, account_id: u64) -> WriteBatch {
+        batch.update_all(
+            move |rw| {
+                let tokens: Vec<AccessToken> = rw
+                    .scan()
+                    .primary::<AccessToken>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .all()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .try_collect()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                Ok(tokens
+                    .into_iter()
+                    .filter(|token| token.can_access_account(account_id))
+                    .collect())
+            },
+            move |tokens| {
+                Ok(tokens
+                    .iter()
+                    .map(|current| {
+                        let mut updated = current.clone();
+                        updated.updated_at = utc_now!();
+                        updated.accounts.retain(|account| account.id != account_id);
+                        (current.clone(), updated)
+                    })
+                    .collect())
+            },
+        )
+    }
+
+    /// Adds revoking access to `account_ids` from every token not scoped to a workspace to
+    /// `batch`. Used when the accounts are moved into a workspace.
+    pub fn stage_revoke_unscoped_access(batch: WriteBatch, account_ids: Vec<u64>) -> WriteBatch {
+        let revoked = account_ids.clone();
+        batch.update_all(
+            move |rw| {
+                let tokens: Vec<AccessToken> = rw
+                    .scan()
+                    .primary::<AccessToken>()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .all()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .try_collect()
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                Ok(tokens
+                    .into_iter()
+                    .filter(|token| {
+                        token.workspace_id.is_none()
+                            && revoked.iter().any(|id| token.can_access_account(*id))
+                    })
+                    .collect())
+            },
+            move |tokens| {
+                Ok(tokens
+                    .iter()
+                    .map(|current| {
+                        let mut updated = current.clone();
+                        updated.updated_at = utc_now!();
+                        updated
+                            .accounts
+                            .retain(|account| !account_ids.contains(&account.id));
+                        (current.clone(), updated)
+                    })
+                    .collect())
+            },
+        )
     }
 
     pub async fn update(token: &str, request: AccessTokenUpdateRequest) -> RustMailerResult<()> {
         if request.should_skip_update() {
             return Err(raise_error!(
-                "No changes detected in access scopes, description, or accounts. \
+                "No changes detected in access scopes, description, accounts, acl or expiry. \
                  Please modify at least one of these fields to perform an update."
                     .into(),
                 ErrorCode::InvalidParameter
             ));
         }
-        request.validate().await?;
+        let workspace_id = Self::find(token)
+            .await?
+            .ok_or_else(|| {
+                raise_error!(
+                    format!(
+                        "The access token with token={} that you want to modify was not found.",
+                        token
+                    ),
+                    ErrorCode::ResourceNotFound
+                )
+            })?
+            .workspace_id;
+        request.validate(workspace_id).await?;
 
         let account_infos = if let Some(accounts) = &request.accounts {
             let mut account_infos = BTreeSet::new();
@@ -247,6 +463,11 @@ impl AccessToken {
                     updated.acl = Some(acl);
                 }
 
+                if let Some(expires_at) = request.expires_at {
+                    updated.expires_at = Some(expires_at);
+                    updated.expiry_notified = false;
+                }
+
                 updated.updated_at = utc_now!();
                 Ok(updated)
             },
@@ -255,7 +476,7 @@ impl AccessToken {
         Ok(())
     }
 
-    pub async fn create(request: AccessTokenCreateRequest) -> RustMailerResult<String> {
+    pub async fn create(request: AccessTokenCreateRequest) -> RustMailerResult<AccessToken> {
         // Validate request parameters first
         request.validate().await?;
 
@@ -264,6 +485,8 @@ impl AccessToken {
             description,
             access_scopes,
             acl,
+            workspace_id,
+            expires_at,
         } = request;
 
         let mut account_infos = BTreeSet::new();
@@ -282,10 +505,133 @@ impl AccessToken {
             description,
             access_scopes,
             acl,
+            workspace_id,
+            expires_at,
         );
 
-        insert_impl(DB_MANAGER.meta_db(), access_token).await?;
-        Ok(token)
+        insert_impl(DB_MANAGER.meta_db(), access_token.clone()).await?;
+        Ok(access_token)
+    }
+
+    /// Replaces the token with a new one having the same accounts, scopes and settings.
+    ///
+    /// The current token keeps working for the grace period of the request, so that clients
+    /// can switch to the new one. Returns the rotated token and its replacement.
+    pub async fn rotate(
+        request: AccessTokenRotateRequest,
+    ) -> RustMailerResult<(AccessToken, AccessToken)> {
+        request.validate()?;
+        let current = Self::find(request.token.trim()).await?.ok_or_else(|| {
+            raise_error!(
+                format!(
+                    "The access token starting with '{}' that you want to rotate was not found.",
+                    token_prefix(request.token.trim())
+                ),
+                ErrorCode::ResourceNotFound
+            )
+        })?;
+        let now = utc_now!();
+        let grace_period_ends_at = now + request.grace_period_secs() as i64 * 1000;
+        let replacement_token = generate_token!(128);
+        // Checked again on the row read inside the transaction, which is the one written back.
+        let rotated = current.rotated_to(&replacement_token, now, grace_period_ends_at)?;
+
+        // Without an explicit expiry, the replacement gets the lifetime of the current token.
+        let expires_at = request.expires_at.or_else(|| {
+            current
+                .expires_at
+                .map(|expires_at| now + (expires_at - current.created_at))
+        });
+        let replacement = AccessToken {
+            token: replacement_token.clone(),
+            created_at: now,
+            updated_at: now,
+            last_access_at: Default::default(),
+            expires_at,
+            replaced_by: None,
+            expiry_notified: false,
+            ..current.clone()
+        };
+
+        let token = current.token.clone();
+        WriteBatch::new()
+            .insert(replacement.clone())
+            .update(
+                move |rw| {
+                    rw.get()
+                        .primary::<AccessToken>(token.clone())
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                        .ok_or_else(|| {
+                            raise_error!(
+                                format!(
+                                    "Token starting with '{}' not found during rotation.",
+                                    token_prefix(&token)
+                                ),
+                                ErrorCode::ResourceNotFound
+                            )
+                        })
+                },
+                // A concurrent rotation aborts the batch, so that its replacement is not kept.
+                move |current| current.rotated_to(&replacement_token, now, grace_period_ends_at),
+            )
+            .commit(DB_MANAGER.meta_db())
+            .await?;
+        Ok((rotated, replacement))
+    }
+
+    /// The token once replaced by `replacement`: it keeps working until `grace_period_ends_at`,
+    /// or until its own expiry if earlier. Tokens already rotated or expired are rejected.
+    fn rotated_to(
+        &self,
+        replacement: &str,
+        now: i64,
+        grace_period_ends_at: i64,
+    ) -> RustMailerResult<AccessToken> {
+        if self.replaced_by.is_some() {
+            return Err(raise_error!(
+                "The access token was already rotated. Rotate its replacement instead.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        if self.is_expired_at(now) {
+            return Err(raise_error!(
+                "An expired access token cannot be rotated.".into(),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        let mut rotated = self.clone();
+        rotated.replaced_by = Some(replacement.to_string());
+        rotated.expires_at = Some(
+            self.expires_at
+                .map_or(grace_period_ends_at, |e| e.min(grace_period_ends_at)),
+        );
+        // The rotation event already announces the end of the grace period.
+        rotated.expiry_notified = true;
+        rotated.updated_at = now;
+        Ok(rotated)
+    }
+
+    /// Records that the `AccessTokenExpiring` event was emitted for the token.
+    pub async fn mark_expiry_notified(token: &str) -> RustMailerResult<()> {
+        let token = token.to_string();
+        update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .primary::<AccessToken>(token)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!("Token not exist.".into(), ErrorCode::ResourceNotFound)
+                    })
+            },
+            |current| {
+                let mut updated = current.clone();
+                updated.expiry_notified = true;
+                Ok(updated)
+            },
+        )
+        .await?;
+        Ok(())
     }
 
     pub async fn delete(token: &str) -> RustMailerResult<()> {
@@ -304,6 +650,36 @@ impl AccessToken {
         .await
     }
 
+    /// Deletes the tokens expired at `now`, including rotated tokens past their grace period,
+    /// and returns them.
+    pub async fn purge_expired(now: i64) -> RustMailerResult<Vec<AccessToken>> {
+        let expired: Vec<AccessToken> = Self::list_all()
+            .await?
+            .into_iter()
+            .filter(|token| token.is_expired_at(now))
+            .collect();
+        if expired.is_empty() {
+            return Ok(expired);
+        }
+        let tokens: Vec<String> = expired.iter().map(|token| token.token.clone()).collect();
+        WriteBatch::new()
+            .delete_all(move |rw| {
+                let mut purged = Vec::new();
+                for token in tokens {
+                    // A token extended since it was listed is kept.
+                    let current: Option<AccessToken> = rw
+                        .get()
+                        .primary(token)
+                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?;
+                    purged.extend(current.filter(|token| token.is_expired_at(now)));
+                }
+                Ok(purged)
+            })
+            .commit(DB_MANAGER.meta_db())
+            .await?;
+        Ok(expired)
+    }
+
     pub async fn list_all() -> RustMailerResult<Vec<AccessToken>> {
         list_all_impl(DB_MANAGER.meta_db()).await
     }
@@ -321,43 +697,66 @@ impl AccessToken {
         Ok(result)
     }
 
-    pub async fn cleanup_account(account_id: u64) -> RustMailerResult<()> {
-        let tokens = Self::list_account_tokens(account_id).await?;
-        if tokens.is_empty() {
-            return Ok(());
-        }
+    pub fn can_access_account(&self, account_id: u64) -> bool {
+        self.accounts.iter().any(|account| account.id == account_id)
+    }
 
-        for token in tokens {
-            update_impl(
-                DB_MANAGER.meta_db(),
-                move |rw| {
-                    rw.get()
-                        .primary::<AccessToken>(token.token.clone())
-                        .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
-                        .ok_or_else(|| {
-                            raise_error!(
-                                format!("Cannot find access token, {}", token.token),
-                                ErrorCode::ResourceNotFound
-                            )
-                        })
-                },
-                move |current| {
-                    let mut updated = current.clone();
-                    updated.updated_at = utc_now!();
-                    updated.accounts.retain(|account| account.id != account_id);
-                    Ok(updated)
-                },
-            )
-            .await?;
-        }
-        Ok(())
+    /// Whether the token reaches the resources of the workspace `workspace_id`. Tokens scoped
+    /// to a workspace only reach the resources of their workspace, other tokens only the
+    /// shared resources outside any workspace.
+    pub fn reaches_workspace(&self, workspace_id: Option<u64>) -> bool {
+        self.workspace_id == workspace_id
     }
 
-    pub fn can_access_account(&self, account_id: u64) -> bool {
-        self.accounts.iter().any(|account| account.id == account_id)
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(utc_now!())
+    }
+
+    /// Whether the token expired at `now`. Rotated tokens expire at the end of their grace
+    /// period.
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Whether the token expires within `window_ms` and its `AccessTokenExpiring` event is
+    /// still due.
+    pub fn expiry_due(&self, now: i64, window_ms: i64) -> bool {
+        !self.expiry_notified
+            && self
+                .expires_at
+                .is_some_and(|expires_at| expires_at > now && expires_at - now <= window_ms)
+    }
+
+    /// The first characters of the token, identifying it in events and logs without
+    /// revealing it.
+    pub fn prefix(&self) -> String {
+        token_prefix(&self.token)
+    }
+
+    /// Whether the token is limited to the parts of the API granted by its permission
+    /// scopes. Tokens with the `Api` scope, or without any permission scope, reach the
+    /// whole API.
+    pub fn is_fine_grained(&self) -> bool {
+        !self.access_scopes.contains(&AccessTokenScope::Api)
+            && self
+                .access_scopes
+                .iter()
+                .any(AccessTokenScope::is_permission)
+    }
+
+    pub fn permits(&self, operation: &ApiOperation) -> bool {
+        !self.is_fine_grained()
+            || operation
+                .granting_scopes()
+                .iter()
+                .any(|scope| self.access_scopes.contains(scope))
     }
 }
 
+pub fn token_prefix(token: &str) -> String {
+    token.chars().take(8).collect()
+}
+
 /// Defines the scope of access for an access token.
 #[derive(Enum, Hash, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Ord, PartialOrd)]
 pub enum AccessTokenScope {
@@ -365,6 +764,32 @@ pub enum AccessTokenScope {
     Api,
     /// Grants access to Prometheus metrics endpoints.
     Metrics,
+    /// Grants access to the Prometheus metrics endpoint, limited to the per-account series
+    /// of the token's own accounts.
+    AccountMetrics,
+    /// Grants reading mailboxes, messages and message events, without changing them.
+    ReadMessages,
+    /// Grants sending emails, and managing send tasks, campaigns and suppressions.
+    SendMail,
+    /// Grants managing accounts, their mailboxes and messages, MTAs and OAuth2 settings.
+    ManageAccounts,
+    /// Grants managing event hooks and their deliveries.
+    ManageHooks,
+    /// Grants managing email templates and template partials.
+    ManageTemplates,
+    /// Grants read-only access to everything but messages and secrets, e.g. for reporting.
+    ReadOnly,
+}
+
+impl AccessTokenScope {
+    /// Whether the scope grants part of the API, restricting tokens holding it to the parts
+    /// granted by their scopes.
+    pub fn is_permission(&self) -> bool {
+        !matches!(
+            self,
+            AccessTokenScope::Api | AccessTokenScope::Metrics | AccessTokenScope::AccountMetrics
+        )
+    }
 }
 
 impl FromStr for AccessTokenScope {
@@ -373,7 +798,181 @@ impl FromStr for AccessTokenScope {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "api" => Ok(AccessTokenScope::Api),
+            "read_messages" => Ok(AccessTokenScope::ReadMessages),
+            "send_mail" => Ok(AccessTokenScope::SendMail),
+            "manage_accounts" => Ok(AccessTokenScope::ManageAccounts),
+            "manage_hooks" => Ok(AccessTokenScope::ManageHooks),
+            "manage_templates" => Ok(AccessTokenScope::ManageTemplates),
+            "read_only" => Ok(AccessTokenScope::ReadOnly),
             _ => Err(()),
         }
     }
 }
+
+impl From<AccessTokenV1> for AccessTokenV2 {
+    fn from(value: AccessTokenV1) -> Self {
+        Self {
+            token: value.token,
+            accounts: value.accounts,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            description: value.description,
+            access_scopes: value.access_scopes,
+            last_access_at: value.last_access_at,
+            acl: value.acl,
+            workspace_id: None,
+        }
+    }
+}
+
+impl From<AccessTokenV2> for AccessTokenV1 {
+    fn from(value: AccessTokenV2) -> Self {
+        Self {
+            token: value.token,
+            accounts: value.accounts,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            description: value.description,
+            access_scopes: value.access_scopes,
+            last_access_at: value.last_access_at,
+            acl: value.acl,
+        }
+    }
+}
+
+impl From<AccessTokenV2> for AccessToken {
+    fn from(value: AccessTokenV2) -> Self {
+        Self {
+            token: value.token,
+            accounts: value.accounts,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            description: value.description,
+            access_scopes: value.access_scopes,
+            last_access_at: value.last_access_at,
+            acl: value.acl,
+            workspace_id: value.workspace_id,
+            expires_at: None,
+            replaced_by: None,
+            expiry_notified: false,
+        }
+    }
+}
+
+impl From<AccessToken> for AccessTokenV2 {
+    fn from(value: AccessToken) -> Self {
+        Self {
+            token: value.token,
+            accounts: value.accounts,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            description: value.description,
+            access_scopes: value.access_scopes,
+            last_access_at: value.last_access_at,
+            acl: value.acl,
+            workspace_id: value.workspace_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::common::auth::ClientContext;
+    use crate::modules::error::RustMailerError;
+
+    fn token(expires_at: Option<i64>) -> AccessToken {
+        AccessToken::new(
+            "Xk3r9Lq2secret".into(),
+            BTreeSet::new(),
+            None,
+            BTreeSet::from([AccessTokenScope::Api]),
+            None,
+            None,
+            expires_at,
+        )
+    }
+
+    #[test]
+    fn expiry_is_due_once_within_the_warning_window() {
+        let now = utc_now!();
+        let day = 24 * 60 * 60 * 1000;
+        assert!(!token(None).expiry_due(now, 7 * day));
+        assert!(!token(Some(now + 8 * day)).expiry_due(now, 7 * day));
+        assert!(token(Some(now + 6 * day)).expiry_due(now, 7 * day));
+        assert!(!token(Some(now - day)).expiry_due(now, 7 * day));
+
+        let mut notified = token(Some(now + 6 * day));
+        notified.expiry_notified = true;
+        assert!(!notified.expiry_due(now, 7 * day));
+    }
+
+    #[test]
+    fn expired_tokens_are_detected() {
+        assert!(!token(None).is_expired());
+        assert!(token(Some(utc_now!() - 1)).is_expired());
+        assert!(!token(Some(utc_now!() + 60_000)).is_expired());
+        assert_eq!(token(None).prefix(), "Xk3r9Lq2");
+    }
+
+    #[test]
+    fn rotated_tokens_expire_with_their_grace_period() {
+        let now = utc_now!();
+        let mut rotated = token(None);
+        rotated.replaced_by = Some("Yq8w2Pz1replacement".into());
+        rotated.expires_at = Some(now + 60_000);
+        assert!(!rotated.is_expired_at(now));
+        assert!(rotated.is_expired_at(now + 60_000));
+        assert!(!token(None).is_expired_at(i64::MAX));
+    }
+
+    #[test]
+    fn tokens_are_rotated_only_once() {
+        let now = utc_now!();
+        let rotated = token(None)
+            .rotated_to("Yq8w2Pz1replacement", now, now + 60_000)
+            .unwrap();
+        assert_eq!(rotated.replaced_by.as_deref(), Some("Yq8w2Pz1replacement"));
+        assert_eq!(rotated.expires_at, Some(now + 60_000));
+        assert!(rotated
+            .rotated_to("Zr7v3Nb4replacement", now, now + 60_000)
+            .is_err());
+    }
+
+    #[test]
+    fn workspace_tokens_do_not_reach_shared_resources() {
+        let mut scoped = token(None);
+        scoped.workspace_id = Some(7);
+        assert!(scoped.reaches_workspace(Some(7)));
+        assert!(!scoped.reaches_workspace(Some(8)));
+        assert!(!scoped.reaches_workspace(None));
+
+        let unscoped = token(None);
+        assert!(unscoped.reaches_workspace(None));
+        assert!(!unscoped.reaches_workspace(Some(7)));
+    }
+
+    #[test]
+    fn unscoped_tokens_do_not_reach_workspace_accounts() {
+        let account = |id| AccountInfo {
+            id,
+            email: format!("user{id}@example.com"),
+        };
+        let mut unscoped = token(None);
+        unscoped.accounts = BTreeSet::from([account(1), account(2)]);
+        let context = ClientContext {
+            ip_addr: None,
+            access_token: Some(unscoped.outside_workspaces(&BTreeSet::from([2]))),
+            is_root: false,
+        };
+        assert!(context.require_account_access(1).is_ok());
+        let error = context.require_account_access(2).unwrap_err();
+        assert!(matches!(
+            error,
+            RustMailerError::Generic {
+                code: ErrorCode::PermissionDenied,
+                ..
+            }
+        ));
+    }
+}
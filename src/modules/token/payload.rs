@@ -9,8 +9,9 @@ use crate::{
         account::migration::AccountModel,
         error::{code::ErrorCode, RustMailerResult},
         token::{AccessControl, AccessTokenScope},
+        workspace::Workspace,
     },
-    raise_error,
+    raise_error, utc_now,
 };
 use poem_openapi::Object;
 use serde::{Deserialize, Serialize};
@@ -26,6 +27,12 @@ pub struct AccessTokenCreateRequest {
     pub access_scopes: BTreeSet<AccessTokenScope>,
     /// Optional access control settings
     pub acl: Option<AccessControl>,
+    /// Optional workspace to scope the token to. The accounts of the token must belong to
+    /// it; if none are given, the token reaches every account of the workspace.
+    pub workspace_id: Option<u64>,
+    /// Optional expiry of the token (Unix epoch milliseconds). If not set, the token never
+    /// expires.
+    pub expires_at: Option<i64>,
 }
 
 impl AccessTokenCreateRequest {
@@ -33,8 +40,10 @@ impl AccessTokenCreateRequest {
         if let Some(acl) = &self.acl {
             acl.validate()?;
         }
+        validate_expires_at(self.expires_at)?;
 
-        if self.accounts.is_empty() {
+        Workspace::check_exists(self.workspace_id).await?;
+        if self.accounts.is_empty() && self.workspace_id.is_none() {
             return Err(raise_error!(
                 "Account list cannot be empty. Please provide at least one valid account ID."
                     .into(),
@@ -42,26 +51,69 @@ impl AccessTokenCreateRequest {
             ));
         }
 
-        let mut not_found = Vec::new();
-        for account_id in &self.accounts {
-            if AccountModel::find(*account_id).await?.is_none() {
-                not_found.push(*account_id);
+        validate_accounts(&self.accounts, self.workspace_id).await
+    }
+}
+
+/// Checks that an expiry, if any, is in the future.
+fn validate_expires_at(expires_at: Option<i64>) -> RustMailerResult<()> {
+    match expires_at {
+        Some(expires_at) if expires_at <= utc_now!() => Err(raise_error!(
+            "field: expires_at must be in the future.".into(),
+            ErrorCode::InvalidParameter
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Checks that the accounts exist and belong to the workspace of the token, or to no
+/// workspace for tokens not scoped to one.
+async fn validate_accounts(
+    accounts: &BTreeSet<u64>,
+    workspace_id: Option<u64>,
+) -> RustMailerResult<()> {
+    let mut not_found = Vec::new();
+    for account_id in accounts {
+        match AccountModel::find(*account_id).await? {
+            None => not_found.push(*account_id),
+            Some(account) if account.workspace_id != workspace_id => {
+                return Err(raise_error!(
+                    match workspace_id {
+                        Some(_) => format!(
+                            "Account {} does not belong to the workspace of the token.",
+                            account_id
+                        ),
+                        None => format!(
+                            "Account {} belongs to a workspace. Only tokens scoped to its workspace can access it.",
+                            account_id
+                        ),
+                    },
+                    ErrorCode::InvalidParameter
+                ));
             }
+            Some(_) => {}
         }
-        if !not_found.is_empty() {
-            return Err(raise_error!(
-                format!("The following account IDs were not found: {}. Please provide valid account IDs.", not_found.iter().map(u64::to_string).collect::<Vec<_>>().join(", ")).into(),
-                ErrorCode::InvalidParameter
-            ));
-        }
-
-        Ok(())
     }
+    if !not_found.is_empty() {
+        return Err(raise_error!(
+            format!(
+                "The following account IDs were not found: {}. Please provide valid account IDs.",
+                not_found
+                    .iter()
+                    .map(u64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    Ok(())
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Object)]
 pub struct AccessTokenUpdateRequest {
-    /// A set of account information associated with the token.
+    /// A set of account information associated with the token. For a token scoped to a
+    /// workspace, an empty set grants access to every account of the workspace.
     pub accounts: Option<BTreeSet<u64>>,
     /// An optional description of the token's purpose or usage.
     #[oai(validator(max_length = "255"))]
@@ -70,34 +122,26 @@ pub struct AccessTokenUpdateRequest {
     pub access_scopes: Option<BTreeSet<AccessTokenScope>>,
     /// Optional access control settings
     pub acl: Option<AccessControl>,
+    /// A new expiry of the token (Unix epoch milliseconds).
+    pub expires_at: Option<i64>,
 }
 
 impl AccessTokenUpdateRequest {
-    pub async fn validate(&self) -> RustMailerResult<()> {
+    /// Validates the request for a token scoped to `workspace_id`.
+    pub async fn validate(&self, workspace_id: Option<u64>) -> RustMailerResult<()> {
         if let Some(acl) = &self.acl {
             acl.validate()?;
         }
+        validate_expires_at(self.expires_at)?;
         if let Some(accounts) = &self.accounts {
-            if accounts.is_empty() {
+            if accounts.is_empty() && workspace_id.is_none() {
                 return Err(raise_error!(
                     "Account list cannot be empty. Please provide at least one valid account ID."
                         .into(),
                     ErrorCode::InvalidParameter
                 ));
             }
-
-            let mut not_found = Vec::new();
-            for account_id in accounts {
-                if AccountModel::find(*account_id).await?.is_none() {
-                    not_found.push(*account_id);
-                }
-            }
-            if !not_found.is_empty() {
-                return Err(raise_error!(
-                format!("The following account IDs were not found: {}. Please provide valid account IDs.", not_found.iter().map(u64::to_string).collect::<Vec<_>>().join(", ")).into(),
-                ErrorCode::InvalidParameter
-            ));
-            }
+            validate_accounts(accounts, workspace_id).await?;
         }
 
         Ok(())
@@ -110,5 +154,43 @@ impl AccessTokenUpdateRequest {
             && self.description.is_none()
             && self.accounts.is_none()
             && self.acl.is_none()
+            && self.expires_at.is_none()
+    }
+}
+
+/// Default time the current token keeps working after a rotation: one day.
+const DEFAULT_GRACE_PERIOD_SECS: u64 = 24 * 60 * 60;
+/// Longest time the current token can keep working after a rotation: 30 days.
+const MAX_GRACE_PERIOD_SECS: u64 = 30 * 24 * 60 * 60;
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, Object)]
+pub struct AccessTokenRotateRequest {
+    /// The access token to rotate. It is sent in the body rather than in the URL, so that it
+    /// does not end up in access logs.
+    pub token: String,
+    /// How long the current token keeps working after the rotation, in seconds. Defaults to
+    /// one day, at most 30 days. `0` revokes it right away.
+    pub grace_period_secs: Option<u64>,
+    /// Optional expiry of the new token (Unix epoch milliseconds). If not set, the new token
+    /// gets the lifetime of the current one, or never expires if the current one does not.
+    pub expires_at: Option<i64>,
+}
+
+impl AccessTokenRotateRequest {
+    pub fn validate(&self) -> RustMailerResult<()> {
+        if self.grace_period_secs() > MAX_GRACE_PERIOD_SECS {
+            return Err(raise_error!(
+                format!(
+                    "field: grace_period_secs must be at most {} seconds (30 days).",
+                    MAX_GRACE_PERIOD_SECS
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        validate_expires_at(self.expires_at)
+    }
+
+    pub fn grace_period_secs(&self) -> u64 {
+        self.grace_period_secs.unwrap_or(DEFAULT_GRACE_PERIOD_SECS)
     }
 }
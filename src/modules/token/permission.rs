@@ -0,0 +1,420 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use http::Method;
+
+use crate::modules::token::AccessTokenScope;
+
+/// The part of the API an operation belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiArea {
+    /// Mailboxes, messages, searches and live message events.
+    Messages,
+    /// Sending emails, send tasks, campaigns, recurring sends and suppressions.
+    Sending,
+    /// Accounts and their settings, OAuth2, MTAs, cleanup rules and workspaces.
+    Accounts,
+    /// Event hooks, their deliveries and tasks, and VRL scripts.
+    Hooks,
+    /// Email templates and template partials.
+    Templates,
+    /// System settings, access tokens, the license, notifications and the audit log.
+    System,
+    /// Operations missing from the tables below. Only tokens with the `Api` scope may
+    /// call them.
+    Unknown,
+}
+
+/// A REST or gRPC operation, as seen by the permission scopes of access tokens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ApiOperation {
+    pub area: ApiArea,
+    /// Whether the operation only reads data.
+    pub read_only: bool,
+}
+
+/// The first path segment of the REST resources of each area, after `/api/v1/`.
+const REST_AREAS: &[(ApiArea, &[&str])] = &[
+    (
+        ApiArea::Messages,
+        &[
+            "list-mailboxes",
+            "list-subscribed-mailboxes",
+            "subscribe-mailbox",
+            "unsubscribe-mailbox",
+            "create-mailbox",
+            "delete-mailbox",
+            "update-mailbox",
+            "resync-mailbox",
+            "verify-envelope-cache",
+            "storage-usage",
+            "imap-console",
+            "move-messages",
+            "copy-messages",
+            "delete-messages",
+            "flag-messages",
+            "tag-messages",
+            "list-messages",
+            "list-threads",
+            "get-thread-messages",
+            "message-content",
+            "message-attachment",
+            "raw-message",
+            "raw-headers",
+            "messages",
+            "search-message",
+            "unified-search",
+            "saved-searches",
+            "append-reply-to-draft",
+            "events",
+        ],
+    ),
+    (
+        ApiArea::Sending,
+        &[
+            "send-mail",
+            "lint-mail",
+            "compose-mail",
+            "reply-mail",
+            "forward-mail",
+            "send-email-tasks",
+            "send-email-task",
+            "suppressions",
+            "suppression",
+            "campaigns",
+            "recurring-sends",
+            "dead-letters",
+            "template-send-test",
+            "template-seed-test",
+            "mta-send-test",
+            "smtp-probe",
+        ],
+    ),
+    (
+        ApiArea::Accounts,
+        &[
+            "account",
+            "accounts",
+            "list-accounts",
+            "account-state",
+            "account-dashboard",
+            "minimal-account-list",
+            "maintenance-windows",
+            "account-profiles",
+            "oauth2",
+            "oauth2-list",
+            "oauth2-authorize-url",
+            "oauth2-reauthorize-url",
+            "oauth2-pending",
+            "oauth2-tokens",
+            "store-external-oauth2-token",
+            "cleanup-rules",
+            "mta",
+            "list-mta",
+            "autoconfig",
+            "workspace",
+            "workspaces",
+        ],
+    ),
+    (
+        ApiArea::Hooks,
+        &[
+            "event-hook",
+            "event-hook-delivery",
+            "event-hook-list",
+            "event-examples",
+            "vrl-script-resolve",
+            "vrl-script-sandbox",
+            "hook-tasks",
+            "hook-task",
+        ],
+    ),
+    (
+        ApiArea::Templates,
+        &[
+            "template",
+            "list-template",
+            "account-templates",
+            "template-partial",
+            "list-template-partial",
+            "account-template-partials",
+        ],
+    ),
+    (
+        ApiArea::System,
+        &[
+            "system",
+            "overview",
+            "license",
+            "notifications",
+            "audit-log",
+            "errors",
+            "api",
+            "disk-cache",
+            "proxy",
+            "list-proxy",
+            "access-token",
+            "access-token-list",
+            "rotate-access-token",
+            "reset-root-token",
+            "reset-root-password",
+            "login",
+            "test-harness",
+        ],
+    ),
+];
+
+/// REST operations sent with `POST` that only read data.
+const REST_READ_ONLY_POSTS: &[&str] = &[
+    "message-content",
+    "message-attachment",
+    "search-message",
+    "unified-search",
+    "lint-mail",
+    "vrl-script-resolve",
+    "vrl-script-sandbox",
+];
+
+/// REST resources exposing secrets, which read-only scopes do not grant.
+const REST_SECRET_READS: &[&str] = &["oauth2-tokens", "access-token", "access-token-list"];
+
+/// gRPC methods that only read data, besides the `Get`, `List`, `Fetch` and `Stream` ones.
+const GRPC_READ_ONLY_METHODS: &[&str] = &[
+    "MessageSearch",
+    "UnifiedSearch",
+    "RunSavedSearch",
+    "LintMail",
+    "EventExamples",
+    "EventStream",
+    "VrlScriptResolve",
+    "VrlScriptSandbox",
+    "DryRunCleanupRule",
+];
+
+impl ApiOperation {
+    /// Classifies a request to the REST API (`/api/v1/...`) or to the gRPC API
+    /// (`/rustmailer.grpc.<Service>/<Method>`).
+    pub fn classify(method: &Method, path: &str) -> Self {
+        match path.strip_prefix("/rustmailer.grpc.") {
+            Some(grpc) => Self::classify_grpc(grpc),
+            None => Self::classify_rest(method, path),
+        }
+    }
+
+    fn classify_rest(method: &Method, path: &str) -> Self {
+        let path = path.trim_start_matches("/api/v1").trim_matches('/');
+        let resource = path.split('/').next().unwrap_or_default();
+        let area = if path.ends_with("/events/sse") {
+            ApiArea::Messages
+        } else {
+            REST_AREAS
+                .iter()
+                .find(|(_, resources)| resources.contains(&resource))
+                .map_or(ApiArea::Unknown, |(area, _)| *area)
+        };
+        let read_only = !REST_SECRET_READS.contains(&resource)
+            && (*method == Method::GET
+                || *method == Method::HEAD
+                || (*method == Method::POST
+                    && (REST_READ_ONLY_POSTS.contains(&resource) || path.ends_with("/dry-run"))));
+        Self { area, read_only }
+    }
+
+    fn classify_grpc(path: &str) -> Self {
+        let (service, method) = path.split_once('/').unwrap_or((path, ""));
+        let service = service.trim_start_matches("v2.");
+        let area = match (service, method) {
+            (_, "EventStream") => ApiArea::Messages,
+            ("MtaService" | "TemplatesService", "SendTestEmail" | "SendSeedTest") => {
+                ApiArea::Sending
+            }
+            ("MailboxService" | "MessageService", _) => ApiArea::Messages,
+            (
+                "SendMailService"
+                | "SuppressionService"
+                | "CampaignService"
+                | "RecurringSendService"
+                | "DeadLetterService",
+                _,
+            ) => ApiArea::Sending,
+            (
+                "AccountService" | "AutoConfigService" | "MtaService" | "OAuth2Service"
+                | "RetentionService",
+                _,
+            ) => ApiArea::Accounts,
+            ("EventHooksService", _) => ApiArea::Hooks,
+            ("TemplatesService", _) => ApiArea::Templates,
+            ("StatusService", _) => ApiArea::System,
+            _ => ApiArea::Unknown,
+        };
+        let read_only = method != "GetOAuth2Tokens"
+            && (["Get", "List", "Fetch", "Stream"]
+                .iter()
+                .any(|prefix| method.starts_with(prefix))
+                || GRPC_READ_ONLY_METHODS.contains(&method));
+        Self { area, read_only }
+    }
+
+    /// The permission scopes allowing the operation. Fine-grained tokens need one of them,
+    /// so an empty list leaves the operation to tokens with the `Api` scope.
+    pub fn granting_scopes(&self) -> Vec<AccessTokenScope> {
+        let manage = match self.area {
+            ApiArea::Messages | ApiArea::Accounts => Some(AccessTokenScope::ManageAccounts),
+            ApiArea::Sending => Some(AccessTokenScope::SendMail),
+            ApiArea::Hooks => Some(AccessTokenScope::ManageHooks),
+            ApiArea::Templates => Some(AccessTokenScope::ManageTemplates),
+            ApiArea::System | ApiArea::Unknown => None,
+        };
+        match (self.area, self.read_only) {
+            (ApiArea::Unknown, _) => Vec::new(),
+            (ApiArea::Messages, true) => std::iter::once(AccessTokenScope::ReadMessages)
+                .chain(manage)
+                .collect(),
+            (_, true) => std::iter::once(AccessTokenScope::ReadOnly)
+                .chain(manage)
+                .collect(),
+            (_, false) => manage.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::rest::spec::CURRENT_API;
+
+    fn rest(method: Method, path: &str) -> ApiOperation {
+        ApiOperation::classify(&method, path)
+    }
+
+    #[test]
+    fn classifies_rest_operations() {
+        assert_eq!(
+            rest(Method::POST, "/api/v1/send-mail/1"),
+            ApiOperation {
+                area: ApiArea::Sending,
+                read_only: false
+            }
+        );
+        assert_eq!(
+            rest(Method::POST, "/api/v1/search-message/1"),
+            ApiOperation {
+                area: ApiArea::Messages,
+                read_only: true
+            }
+        );
+        assert_eq!(
+            rest(Method::GET, "/api/v1/accounts/1/events/sse").area,
+            ApiArea::Messages
+        );
+        assert!(rest(Method::POST, "/api/v1/cleanup-rules/1/2/dry-run").read_only);
+        assert_eq!(
+            rest(Method::DELETE, "/api/v1/template/7").area,
+            ApiArea::Templates
+        );
+        assert_eq!(
+            rest(Method::POST, "/api/v1/access-token").area,
+            ApiArea::System
+        );
+        assert_eq!(
+            rest(Method::GET, "/api/v1/messages/<id@example.com>/timeline").area,
+            ApiArea::Messages
+        );
+    }
+
+    #[test]
+    fn classifies_grpc_operations() {
+        let grpc = |path: &str| ApiOperation::classify(&Method::POST, path);
+        assert_eq!(
+            grpc("/rustmailer.grpc.MessageService/FetchMessageContent"),
+            ApiOperation {
+                area: ApiArea::Messages,
+                read_only: true
+            }
+        );
+        assert_eq!(
+            grpc("/rustmailer.grpc.v2.SendMailService/SendNewMail"),
+            ApiOperation {
+                area: ApiArea::Sending,
+                read_only: false
+            }
+        );
+        assert_eq!(
+            grpc("/rustmailer.grpc.TemplatesService/SendTestEmail").area,
+            ApiArea::Sending
+        );
+        assert!(grpc("/rustmailer.grpc.EventHooksService/ListEventHook").read_only);
+    }
+
+    #[test]
+    fn read_only_scope_never_grants_messages_or_writes() {
+        let send = rest(Method::POST, "/api/v1/send-mail/1");
+        assert_eq!(send.granting_scopes(), vec![AccessTokenScope::SendMail]);
+        let content = rest(Method::POST, "/api/v1/message-content/1");
+        assert_eq!(
+            content.granting_scopes(),
+            vec![
+                AccessTokenScope::ReadMessages,
+                AccessTokenScope::ManageAccounts
+            ]
+        );
+        let tasks = rest(Method::GET, "/api/v1/send-email-tasks");
+        assert_eq!(
+            tasks.granting_scopes(),
+            vec![AccessTokenScope::ReadOnly, AccessTokenScope::SendMail]
+        );
+        let token = rest(Method::GET, "/api/v1/access-token-list");
+        assert!(token.granting_scopes().is_empty());
+        let oauth2 = rest(Method::GET, "/api/v1/oauth2-tokens/1");
+        assert_eq!(
+            oauth2.granting_scopes(),
+            vec![AccessTokenScope::ManageAccounts]
+        );
+    }
+
+    #[test]
+    fn unknown_operations_fail_closed() {
+        for operation in [
+            rest(Method::GET, "/api/v1/not-a-resource"),
+            ApiOperation::classify(&Method::POST, "/rustmailer.grpc.NewService/GetThing"),
+        ] {
+            assert_eq!(operation.area, ApiArea::Unknown);
+            assert!(operation.granting_scopes().is_empty());
+        }
+    }
+
+    #[test]
+    fn every_rest_operation_is_classified() {
+        let unclassified: Vec<String> = CURRENT_API
+            .operations
+            .iter()
+            .filter(|operation| {
+                let method = Method::from_bytes(operation.method.as_bytes()).unwrap();
+                let path = format!("/api/v1{}", operation.path);
+                ApiOperation::classify(&method, &path).area == ApiArea::Unknown
+            })
+            .map(|operation| format!("{} {}", operation.method, operation.path))
+            .collect();
+        assert_eq!(unclassified, Vec::<String>::new());
+    }
+
+    #[test]
+    fn every_grpc_service_is_classified() {
+        let protos = [
+            include_str!("../../../protos/rustmailer.proto"),
+            include_str!("../../../protos/rustmailer_v2.proto"),
+        ];
+        let unclassified: Vec<&str> = protos
+            .iter()
+            .flat_map(|proto| proto.lines())
+            .filter_map(|line| line.strip_prefix("service "))
+            .filter_map(|line| line.split_whitespace().next())
+            .filter(|service| {
+                let path = format!("/rustmailer.grpc.{service}/Call");
+                ApiOperation::classify(&Method::POST, &path).area == ApiArea::Unknown
+            })
+            .collect();
+        assert_eq!(unclassified, Vec::<&str>::new());
+    }
+}
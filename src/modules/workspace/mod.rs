@@ -0,0 +1,363 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, RwLock};
+
+use dashmap::DashMap;
+use native_db::*;
+use native_model::{native_model, Model};
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::account::migration::AccountModel;
+use crate::modules::common::paginated::paginate_vec;
+use crate::modules::database::batch::WriteBatch;
+use crate::modules::database::manager::DB_MANAGER;
+use crate::modules::database::{
+    delete_impl, insert_impl, list_all_impl, secondary_find_impl, update_impl,
+};
+use crate::modules::error::code::ErrorCode;
+use crate::modules::error::RustMailerResult;
+use crate::modules::hook::entity::EventHooks;
+use crate::modules::overview::memory::check_metadata_capacity;
+use crate::modules::rest::response::DataPage;
+use crate::modules::smtp::mta::entity::Mta;
+use crate::modules::smtp::template::entity::EmailTemplate;
+use crate::modules::token::{AccessToken, AccountInfo};
+use crate::modules::workspace::payload::{WorkspaceCreateRequest, WorkspaceUpdateRequest};
+use crate::{id, raise_error, utc_now};
+
+pub mod payload;
+
+/// The accounts of each workspace, loaded when a token scoped to the workspace is first used
+/// and dropped whenever an account is created, deleted or moved between workspaces.
+static WORKSPACE_ACCOUNTS: LazyLock<DashMap<u64, BTreeSet<AccountInfo>>> =
+    LazyLock::new(DashMap::new);
+/// The accounts belonging to any workspace, loaded when a token not scoped to a workspace is
+/// first used and dropped together with `WORKSPACE_ACCOUNTS`.
+static ASSIGNED_ACCOUNTS: RwLock<Option<BTreeSet<u64>>> = RwLock::new(None);
+/// Bumped on every invalidation, so that a load racing with it is not cached.
+static WORKSPACE_ACCOUNTS_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// A tenant of the instance, such as a customer, owning accounts, templates, MTAs and event
+/// hooks.
+///
+/// Access tokens scoped to a workspace only reach the resources of their workspace. Templates
+/// and event hooks of an account belong to the workspace of the account; MTAs, global
+/// templates and global hooks belong to the workspace they were created in, if any.
+/// Resources outside any workspace are shared by the whole instance and managed by root.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+#[native_model(id = 31, version = 1)]
+#[native_db(primary_key(pk -> String))]
+pub struct Workspace {
+    /// Unique identifier of the workspace.
+    #[secondary_key(unique)]
+    pub id: u64,
+    /// Name of the workspace, e.g. the name of the customer.
+    pub name: String,
+    /// Optional description of the workspace.
+    pub description: Option<String>,
+    /// Timestamp of when the workspace was created (in Unix epoch milliseconds).
+    pub created_at: i64,
+    /// Timestamp of when the workspace was last updated (in Unix epoch milliseconds).
+    pub updated_at: i64,
+}
+
+impl Workspace {
+    fn pk(&self) -> String {
+        format!("{}_{}", self.created_at, self.id)
+    }
+
+    pub async fn create(request: WorkspaceCreateRequest) -> RustMailerResult<Workspace> {
+        check_metadata_capacity()?;
+        let name = validate_name(&request.name)?;
+        Self::check_name_available(&name, None).await?;
+        let now = utc_now!();
+        let workspace = Self {
+            id: id!(64),
+            name,
+            description: request.description,
+            created_at: now,
+            updated_at: now,
+        };
+        insert_impl(DB_MANAGER.meta_db(), workspace.clone()).await?;
+        Ok(workspace)
+    }
+
+    pub async fn find(id: u64) -> RustMailerResult<Option<Workspace>> {
+        secondary_find_impl(DB_MANAGER.meta_db(), WorkspaceKey::id, id).await
+    }
+
+    pub async fn get(id: u64) -> RustMailerResult<Workspace> {
+        Self::find(id).await?.ok_or_else(|| {
+            raise_error!(
+                format!("Workspace id='{id}' not found."),
+                ErrorCode::ResourceNotFound
+            )
+        })
+    }
+
+    /// Checks that the workspace a resource is assigned to exists.
+    pub async fn check_exists(id: Option<u64>) -> RustMailerResult<()> {
+        if let Some(id) = id {
+            Self::get(id).await?;
+        }
+        Ok(())
+    }
+
+    /// The accounts of the workspace.
+    pub async fn accounts(id: u64) -> RustMailerResult<BTreeSet<AccountInfo>> {
+        if let Some(accounts) = WORKSPACE_ACCOUNTS.get(&id) {
+            return Ok(accounts.clone());
+        }
+        let generation = WORKSPACE_ACCOUNTS_GENERATION.load(Ordering::SeqCst);
+        let accounts: BTreeSet<AccountInfo> = AccountModel::list_all()
+            .await?
+            .into_iter()
+            .filter(|account| account.workspace_id == Some(id))
+            .map(|account| AccountInfo {
+                id: account.id,
+                email: account.email,
+            })
+            .collect();
+        WORKSPACE_ACCOUNTS.insert(id, accounts.clone());
+        if WORKSPACE_ACCOUNTS_GENERATION.load(Ordering::SeqCst) != generation {
+            WORKSPACE_ACCOUNTS.remove(&id);
+        }
+        Ok(accounts)
+    }
+
+    /// The ids of the accounts belonging to any workspace, which tokens not scoped to a
+    /// workspace cannot reach.
+    pub async fn assigned_accounts() -> RustMailerResult<BTreeSet<u64>> {
+        if let Some(accounts) = ASSIGNED_ACCOUNTS
+            .read()
+            .ok()
+            .and_then(|cached| cached.clone())
+        {
+            return Ok(accounts);
+        }
+        let generation = WORKSPACE_ACCOUNTS_GENERATION.load(Ordering::SeqCst);
+        let accounts: BTreeSet<u64> = AccountModel::list_all()
+            .await?
+            .into_iter()
+            .filter(|account| account.workspace_id.is_some())
+            .map(|account| account.id)
+            .collect();
+        if let Ok(mut cached) = ASSIGNED_ACCOUNTS.write() {
+            if WORKSPACE_ACCOUNTS_GENERATION.load(Ordering::SeqCst) == generation {
+                *cached = Some(accounts.clone());
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// Drops the cached accounts of every workspace. Called whenever an account is created,
+    /// deleted or moved between workspaces.
+    pub fn invalidate_accounts() {
+        WORKSPACE_ACCOUNTS_GENERATION.fetch_add(1, Ordering::SeqCst);
+        WORKSPACE_ACCOUNTS.clear();
+        if let Ok(mut cached) = ASSIGNED_ACCOUNTS.write() {
+            *cached = None;
+        }
+    }
+
+    pub async fn list_all() -> RustMailerResult<Vec<Workspace>> {
+        list_all_impl(DB_MANAGER.meta_db()).await
+    }
+
+    pub async fn paginate_list(
+        page: Option<u64>,
+        page_size: Option<u64>,
+        desc: Option<bool>,
+    ) -> RustMailerResult<DataPage<Workspace>> {
+        let mut workspaces = Self::list_all().await?;
+        workspaces.sort_by_key(|w| (w.created_at, w.id));
+        if desc.unwrap_or(false) {
+            workspaces.reverse();
+        }
+        paginate_vec(&workspaces, page, page_size).map(DataPage::from)
+    }
+
+    pub async fn update(id: u64, request: WorkspaceUpdateRequest) -> RustMailerResult<Workspace> {
+        let name = request.name.as_deref().map(validate_name).transpose()?;
+        if let Some(name) = &name {
+            Self::check_name_available(name, Some(id)).await?;
+        }
+        update_impl(
+            DB_MANAGER.meta_db(),
+            move |rw| {
+                rw.get()
+                    .secondary::<Workspace>(WorkspaceKey::id, id)
+                    .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                    .ok_or_else(|| {
+                        raise_error!(
+                            format!(
+                                "The workspace with id={id} that you want to modify was not found."
+                            ),
+                            ErrorCode::ResourceNotFound
+                        )
+                    })
+            },
+            move |current| {
+                let mut updated = current.clone();
+                if let Some(name) = name {
+                    updated.name = name;
+                }
+                if request.description.is_some() {
+                    updated.description = request.description;
+                }
+                updated.updated_at = utc_now!();
+                Ok(updated)
+            },
+        )
+        .await
+    }
+
+    /// Deletes a workspace. Only empty workspaces can be deleted: its accounts, templates,
+    /// MTAs, hooks and access tokens must be removed or moved out first.
+    pub async fn delete(id: u64) -> RustMailerResult<()> {
+        Self::get(id).await?;
+        let in_use = [
+            (
+                "accounts",
+                AccountModel::list_all()
+                    .await?
+                    .iter()
+                    .any(|a| a.workspace_id == Some(id)),
+            ),
+            (
+                "templates",
+                EmailTemplate::list_all()
+                    .await?
+                    .iter()
+                    .any(|t| t.workspace_id == Some(id)),
+            ),
+            (
+                "MTAs",
+                Mta::list_all()
+                    .await?
+                    .iter()
+                    .any(|m| m.workspace_id == Some(id)),
+            ),
+            (
+                "event hooks",
+                EventHooks::global_hooks()
+                    .await?
+                    .iter()
+                    .any(|h| h.workspace_id == Some(id)),
+            ),
+            (
+                "access tokens",
+                AccessToken::list_all()
+                    .await?
+                    .iter()
+                    .any(|t| t.workspace_id == Some(id)),
+            ),
+        ];
+        let in_use: Vec<&str> = in_use
+            .into_iter()
+            .filter_map(|(resource, used)| used.then_some(resource))
+            .collect();
+        if !in_use.is_empty() {
+            return Err(raise_error!(
+                format!(
+                    "Workspace id='{id}' still has {}. Remove them or move them out first.",
+                    in_use.join(", ")
+                ),
+                ErrorCode::InvalidParameter
+            ));
+        }
+        delete_impl(DB_MANAGER.meta_db(), move |rw| {
+            rw.get()
+                .secondary::<Workspace>(WorkspaceKey::id, id)
+                .map_err(|e| raise_error!(format!("{:#?}", e), ErrorCode::InternalError))?
+                .ok_or_else(|| {
+                    raise_error!(
+                        format!(
+                            "The workspace with id={id} that you want to delete was not found."
+                        ),
+                        ErrorCode::ResourceNotFound
+                    )
+                })
+        })
+        .await
+    }
+
+    /// Moves accounts into the workspace, together with their templates and event hooks.
+    ///
+    /// Tokens not scoped to a workspace lose access to the accounts in the same transaction.
+    pub async fn add_accounts(id: u64, account_ids: &[u64]) -> RustMailerResult<()> {
+        Self::get(id).await?;
+        for account_id in account_ids {
+            AccountModel::get(*account_id).await?;
+        }
+        let mut batch = WriteBatch::new();
+        for account_id in account_ids {
+            batch = AccountModel::stage_set_workspace(batch, *account_id, Some(id));
+        }
+        batch = AccessToken::stage_revoke_unscoped_access(batch, account_ids.to_vec());
+        batch.commit(DB_MANAGER.meta_db()).await?;
+        Self::invalidate_accounts();
+        Ok(())
+    }
+
+    /// Moves accounts of the workspace out of any workspace.
+    pub async fn remove_accounts(id: u64, account_ids: &[u64]) -> RustMailerResult<()> {
+        Self::get(id).await?;
+        for account_id in account_ids {
+            if AccountModel::get(*account_id).await?.workspace_id != Some(id) {
+                return Err(raise_error!(
+                    format!("Account id='{account_id}' does not belong to workspace id='{id}'."),
+                    ErrorCode::InvalidParameter
+                ));
+            }
+        }
+        let mut batch = WriteBatch::new();
+        for account_id in account_ids {
+            batch = AccountModel::stage_set_workspace(batch, *account_id, None);
+        }
+        batch.commit(DB_MANAGER.meta_db()).await?;
+        Self::invalidate_accounts();
+        Ok(())
+    }
+
+    async fn check_name_available(name: &str, own_id: Option<u64>) -> RustMailerResult<()> {
+        let taken = Self::list_all()
+            .await?
+            .iter()
+            .any(|w| Some(w.id) != own_id && w.name.eq_ignore_ascii_case(name));
+        if taken {
+            return Err(raise_error!(
+                format!("A workspace named '{name}' already exists."),
+                ErrorCode::AlreadyExists
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> RustMailerResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(raise_error!(
+            "field: name is empty.".into(),
+            ErrorCode::InvalidParameter
+        ));
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trims_workspace_names() {
+        assert_eq!(validate_name("  Acme Corp ").unwrap(), "Acme Corp");
+        assert!(validate_name(" ").is_err());
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright © 2025 rustmailer.com
+// Licensed under RustMailer License Agreement v1.0
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeSet;
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct WorkspaceCreateRequest {
+    /// Name of the workspace, e.g. the name of the customer. Must be unique.
+    #[oai(validator(min_length = 1, max_length = 128))]
+    pub name: String,
+    /// Optional description of the workspace.
+    #[oai(validator(max_length = "255"))]
+    pub description: Option<String>,
+}
+
+/// Changes to a workspace. Fields left unset are unchanged.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct WorkspaceUpdateRequest {
+    #[oai(validator(min_length = 1, max_length = 128))]
+    pub name: Option<String>,
+    #[oai(validator(max_length = "255"))]
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, Object)]
+pub struct WorkspaceAccountsRequest {
+    /// The accounts to move into the workspace.
+    pub account_ids: BTreeSet<u64>,
+}
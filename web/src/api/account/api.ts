@@ -23,6 +23,8 @@ export const minimal_account_list = async () => {
 export interface ErrorMessage {
     error: string;
     at: number; // milliseconds timestamp
+    kind?: string | null;
+    remediation?: string | null;
 }
 
 export interface AccountRunningState {
@@ -29,6 +29,23 @@ export const vrl_script_resolve = async (data: Record<string, any>) => {
     return response.data;
 };
 
+export interface VrlSandboxCase {
+    event_type?: EventType;
+    result?: any;
+    dropped: boolean;
+    error?: string;
+}
+
+export interface VrlSandboxResult {
+    compile_error?: string;
+    cases: VrlSandboxCase[];
+}
+
+export const vrl_script_sandbox = async (data: { program: string, event_types?: EventType[], sample?: string }) => {
+    const response = await axiosInstance.post<VrlSandboxResult>("/api/v1/vrl-script-sandbox", data);
+    return response.data;
+};
+
 
 export const create_event_hook = async (data: Record<string, any>) => {
     const response = await axiosInstance.post("/api/v1/event-hook", data);
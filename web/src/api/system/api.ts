@@ -57,6 +57,10 @@ export interface MetricsTimeSeries {
     event_dispatch_success_nats: TimeSeriesPoint[];
     event_dispatch_failure_http: TimeSeriesPoint[];
     event_dispatch_failure_nats: TimeSeriesPoint[];
+    event_dispatch_success_kafka: TimeSeriesPoint[];
+    event_dispatch_failure_kafka: TimeSeriesPoint[];
+    event_dispatch_success_redis: TimeSeriesPoint[];
+    event_dispatch_failure_redis: TimeSeriesPoint[];
     email_task_queue_length: TimeSeriesPoint[];
     hook_task_queue_length: TimeSeriesPoint[];
 }
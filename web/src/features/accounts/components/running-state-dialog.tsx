@@ -294,6 +294,11 @@ export function RunningStateDialog({ currentRow, open, onOpenChange }: Props) {
                               {formatDistanceToNow(new Date(item.at), { addSuffix: true })}
                             </div>
                           </div>
+                          {item.remediation && (
+                            <div className="text-xs font-semibold break-words" style={{ wordBreak: 'break-word' }}>
+                              {item.remediation}
+                            </div>
+                          )}
                           <div className="text-xs font-medium break-words" style={{ wordBreak: 'break-word' }}>
                             {item.error}
                           </div>
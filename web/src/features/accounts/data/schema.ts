@@ -30,6 +30,12 @@ export interface ImapConfig {
   use_proxy?: number;
 }
 
+export interface JmapConfig {
+  session_url: string;
+  username?: string;
+  auth: AuthConfig;
+}
+
 interface RelativeDate {
   unit: Unit;
   value: number; // integer, minimum 1
@@ -44,6 +50,7 @@ export interface AccountEntity {
   id: number;
   imap?: ImapConfig;
   smtp?: SmtpConfig;
+  jmap?: JmapConfig;
   enabled: boolean;
   mailer_type: MailerType,
   deleted: boolean;
@@ -70,4 +77,6 @@ export enum MailerType {
   GmailApi = "GmailApi",
   /** Use Graph API */
   GraphApi = "GraphApi",
+  /** Use JMAP (RFC 8620/8621) */
+  Jmap = "Jmap",
 }
\ No newline at end of file
@@ -228,6 +228,30 @@ export default function Dashboard() {
                   dataKey="event_dispatch_failure_nats"
                   color="var(--chart-1)"
                 />
+                <ChartCard
+                  title="Kafka Success"
+                  data={data?.time_series.event_dispatch_success_kafka}
+                  dataKey="event_dispatch_success_kafka"
+                  color="var(--chart-1)"
+                />
+                <ChartCard
+                  title="Kafka Failure"
+                  data={data?.time_series.event_dispatch_failure_kafka}
+                  dataKey="event_dispatch_failure_kafka"
+                  color="var(--chart-1)"
+                />
+                <ChartCard
+                  title="Redis Success"
+                  data={data?.time_series.event_dispatch_success_redis}
+                  dataKey="event_dispatch_success_redis"
+                  color="var(--chart-1)"
+                />
+                <ChartCard
+                  title="Redis Failure"
+                  data={data?.time_series.event_dispatch_failure_redis}
+                  dataKey="event_dispatch_failure_redis"
+                  color="var(--chart-1)"
+                />
               </CollapsibleContent>
             </Collapsible>
           </div>
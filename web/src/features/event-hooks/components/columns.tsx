@@ -75,6 +75,14 @@ export const columns: ColumnDef<EventHook>[] = [
             <Badge variant='outline' className='bg-green-100 text-green-800'>
               NATS
             </Badge>
+          ) : hook_type === "Kafka" ? (
+            <Badge variant='outline' className='bg-orange-100 text-orange-800'>
+              Kafka
+            </Badge>
+          ) : hook_type === "Redis" ? (
+            <Badge variant='outline' className='bg-red-100 text-red-800'>
+              Redis
+            </Badge>
           ) : (
             <Badge variant='outline' className='bg-gray-100 text-gray-800'>
               Unknown
@@ -65,7 +65,8 @@ const natsDefaultValues = {
     username: undefined,
     password: undefined,
     stream_name: '',
-    namespace: ''
+    namespace: '',
+    subject_template: undefined
   },
   vrl_script: '',
   watched_events: []
@@ -118,7 +119,8 @@ export function EventHooksMutateDrawer({ open, onOpenChange, currentRow }: Props
         auth_type: currentRow?.nats?.auth_type || "None",
         token: currentRow?.nats?.token || undefined,
         username: currentRow?.nats?.username || undefined,
-        password: currentRow?.nats?.password || undefined
+        password: currentRow?.nats?.password || undefined,
+        subject_template: currentRow?.nats?.subject_template || undefined
       },
       vrl_script: currentRow.vrl_script,
       watched_events: currentRow.watched_events
@@ -262,6 +264,7 @@ export function EventHooksMutateDrawer({ open, onOpenChange, currentRow }: Props
       hook_type: 'Nats',
       nats: {
         ...data.nats,
+        subject_template: data.nats.subject_template || undefined,
         ...(auth_type === 'None'
           ? { token: undefined, username: undefined, password: undefined }
           : auth_type === 'Token'
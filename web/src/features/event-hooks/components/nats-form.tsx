@@ -46,6 +46,7 @@ export const natsConfigSchema = z.object({
     namespace: z.string({
         required_error: 'Please provide a namespace.',
     }).min(1, { message: 'Please provide a namespace.' }).regex(/^[a-zA-Z][a-zA-Z0-9_]*$/, 'Invalid namespace format'),
+    subject_template: z.string().optional(),
 });
 
 export const natsFormSchema = z.object({
@@ -269,6 +270,27 @@ export function NatsForm({
                     )}
                 />
 
+                <FormField
+                    control={form.control}
+                    name='nats.subject_template'
+                    render={({ field }) => (
+                        <FormItem className='flex flex-col gap-y-1 space-y-0'>
+                            <FormLabel className='mb-1'>Subject Template:</FormLabel>
+                            <FormControl>
+                                <Input
+                                    placeholder='{namespace}.{event_type}'
+                                    {...field}
+                                    value={field.value ?? ''}
+                                />
+                            </FormControl>
+                            <FormDescription>
+                                Optional. The subject events are published under, using the placeholders {'{namespace}'}, {'{account_id}'} and {'{event_type}'}, e.g. {'{namespace}.{account_id}.{event_type}'}. It must start with the namespace so the stream captures it.
+                            </FormDescription>
+                            <FormMessage />
+                        </FormItem>
+                    )}
+                />
+
                 {/* NATS Authentication */}
                 <FormField
                     control={form.control}
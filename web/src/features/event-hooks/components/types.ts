@@ -18,7 +18,15 @@ export const eventTypeSchema = z.enum([
   "MailboxDeletion",
   "UIDValidityChange",
   "EmailOpened",
-  "EmailLinkClicked"
+  "EmailLinkClicked",
+  "EmailLoopDetected",
+  "AccountAutoPaused",
+  "AttachmentPolicyTriggered",
+  "AccountSyncError",
+  "AccountAuthenticationFailed",
+  "AccessTokenCreated",
+  "AccessTokenRotated",
+  "AccessTokenExpiring"
 ]);
 
 export const eventTypeDescriptions: Record<z.infer<typeof eventTypeSchema>, string> = {
@@ -33,7 +41,15 @@ export const eventTypeDescriptions: Record<z.infer<typeof eventTypeSchema>, stri
   MailboxDeletion: "Fired when a mailbox is permanently removed",
   UIDValidityChange: "Advanced: Occurs when a mailbox's UID validity changes",
   EmailOpened: "Represents an event triggered when an email is opened by a recipient.",
-  EmailLinkClicked: "Represents an event triggered when a link in an email is clicked by a recipient."
+  EmailLinkClicked: "Represents an event triggered when a link in an email is clicked by a recipient.",
+  EmailLoopDetected: "Occurs when an outgoing email is detected as a potential mail loop and is blocked or flagged.",
+  AccountAutoPaused: "Occurs when an account's sync is paused automatically after failing authentication or going unused for too long.",
+  AttachmentPolicyTriggered: "Occurs when an incoming or outgoing email carries an attachment type blocked by the account's attachment policy.",
+  AccountSyncError: "Occurs when an account's sync or connection fails, with a suggested fix for recognized provider errors.",
+  AccountAuthenticationFailed: "Occurs when an account's OAuth2 token keeps failing to refresh, e.g. after the user revoked access, and the account must be authorized again.",
+  AccessTokenCreated: "Occurs when an API access token is created. Only delivered to global hooks.",
+  AccessTokenRotated: "Occurs when an API access token is rotated; the old token keeps working for a grace period. Only delivered to global hooks.",
+  AccessTokenExpiring: "Occurs once when an API access token is about to expire. Only delivered to global hooks."
 };
 
 export const eventTypeOptions = eventTypeSchema.options.map((eventType) => ({
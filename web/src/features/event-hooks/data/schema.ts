@@ -16,12 +16,23 @@ export type EventType =
   | "MailboxDeletion"
   | "UIDValidityChange"
   | "EmailOpened"
-  | "EmailLinkClicked";
+  | "EmailLinkClicked"
+  | "EmailLoopDetected"
+  | "AccountAutoPaused"
+  | "AttachmentPolicyTriggered"
+  | "AccountSyncError"
+  | "AccountAuthenticationFailed"
+  | "AccessTokenCreated"
+  | "AccessTokenRotated"
+  | "AccessTokenExpiring";
 
 export type HttpMethod = "Post" | "Put";
 
 export type NatsAuthType = "None" | "Token" | "Password";
-export type HookType = "Http" | "Nats";
+export type HookType = "Http" | "Nats" | "Kafka" | "Redis";
+export type KafkaSecurityProtocol = "Plaintext" | "Ssl" | "SaslPlaintext" | "SaslSsl";
+export type KafkaSaslMechanism = "Plain" | "ScramSha256" | "ScramSha512";
+export type KafkaTopicMode = "Single" | "PerEventType";
 
 
 export interface HttpConfig {
@@ -39,6 +50,35 @@ export interface NatsConfig {
   password?: string;
   stream_name: string;
   namespace: string;
+  subject_template?: string;
+}
+
+export interface KafkaConfig {
+  brokers: string[];
+  security_protocol: KafkaSecurityProtocol;
+  sasl_mechanism: KafkaSaslMechanism;
+  username?: string;
+  password?: string;
+  ca_certificate?: string;
+  topic_mode: KafkaTopicMode;
+  topic: string;
+}
+
+export interface EventVrlScript {
+  event_type: EventType;
+  script: string;
+}
+
+export interface RedisConfig {
+  host: string;
+  port: number;
+  use_tls: boolean;
+  username?: string;
+  password?: string;
+  database: number;
+  stream_key: string;
+  max_len?: number;
+  approximate_trimming: boolean;
 }
 
 export interface EventHook {
@@ -53,7 +93,10 @@ export interface EventHook {
   hook_type: HookType;
   http?: HttpConfig;
   nats?: NatsConfig;
+  kafka?: KafkaConfig;
+  redis?: RedisConfig;
   vrl_script: string;
+  event_vrl_scripts: EventVrlScript[];
   call_count: number;
   success_count: number;
   failure_count: number;
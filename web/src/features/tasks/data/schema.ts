@@ -102,4 +102,12 @@ export type EventType =
   | 'EmailBounce'
   | 'EmailFeedBackReport'
   | 'EmailOpened'
-  | 'EmailLinkClicked';
\ No newline at end of file
+  | 'EmailLinkClicked'
+  | 'EmailLoopDetected'
+  | 'AccountAutoPaused'
+  | 'AttachmentPolicyTriggered'
+  | 'AccountSyncError'
+  | 'AccountAuthenticationFailed'
+  | 'AccessTokenCreated'
+  | 'AccessTokenRotated'
+  | 'AccessTokenExpiring';
\ No newline at end of file